@@ -3,8 +3,8 @@
 //! Main fuzzing harness and orchestration.
 
 use crate::{
-    Corpus, CoverageTracker, CrashInfo, CrashType, FuzzResult, FuzzStats, FuzzTarget, FuzzerConfig,
-    Mutator, StackFrame,
+    content_hash, Corpus, CoverageTracker, CrashInfo, CrashType, FuzzResult, FuzzStats, FuzzTarget,
+    FuzzerConfig, Mutator, StackFrame, DEDUP_FRAMES,
 };
 use alloc::boxed::Box;
 use alloc::format;
@@ -12,6 +12,9 @@ use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
+/// Maximum corpus entries a harness keeps
+const CORPUS_SIZE: usize = 10000;
+
 /// Main fuzzer harness
 pub struct FuzzHarness {
     /// Configuration
@@ -28,6 +31,8 @@ pub struct FuzzHarness {
     stats: FuzzStats,
     /// Found crashes
     crashes: Vec<CrashEntry>,
+    /// Crash buckets seen so far
+    deduper: CrashDeduper,
 }
 
 /// A crash entry
@@ -47,15 +52,42 @@ impl FuzzHarness {
     /// Create new harness
     pub fn new(config: FuzzerConfig) -> Self {
         let seed = 12345u64; // Would use random in real implementation
+        let corpus = Self::load_corpus(&config);
+        let deduper = CrashDeduper::with_frames(config.dedup_frames);
 
         Self {
             config,
             targets: Vec::new(),
             mutator: Mutator::new(seed),
-            corpus: Corpus::new(10000),
+            corpus,
             coverage: CoverageTracker::new(65536),
             stats: FuzzStats::default(),
             crashes: Vec::new(),
+            deduper,
+        }
+    }
+
+    /// The corpus saved in `corpus_dir`, if there is one
+    fn load_corpus(config: &FuzzerConfig) -> Corpus {
+        #[cfg(feature = "std")]
+        if let Some(dir) = &config.corpus_dir {
+            if let Ok(corpus) = Corpus::load_from_dir(CORPUS_SIZE, config.max_input_size, dir) {
+                return corpus;
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = config;
+
+        Corpus::new(CORPUS_SIZE)
+    }
+
+    /// Save the corpus to `corpus_dir` for later runs to start from.
+    /// Returns the number of entries saved.
+    #[cfg(feature = "std")]
+    pub fn save_corpus(&self) -> Result<usize, String> {
+        match &self.config.corpus_dir {
+            Some(dir) => self.corpus.save_to_dir(dir),
+            None => Ok(0),
         }
     }
 
@@ -66,7 +98,7 @@ impl FuzzHarness {
 
     /// Add corpus entry
     pub fn add_corpus(&mut self, data: Vec<u8>) {
-        self.corpus.add(data, 0);
+        self.corpus.add(data, &[]);
     }
 
     /// Add dictionary entries
@@ -105,6 +137,15 @@ impl FuzzHarness {
         // Mutate input
         self.mutator.mutate(&mut input);
 
+        // Now and then cross it over with another corpus entry
+        if self.mutator.mutate_seed().is_multiple_of(4) {
+            if let Some(other) = self.corpus.random_entry(self.mutator.mutate_seed()) {
+                let other = other.clone();
+                self.mutator
+                    .splice_with(&mut input, &other, self.config.max_input_size);
+            }
+        }
+
         // Collect results first to avoid borrow issues
         let mut crash_info: Option<(CrashInfo, String)> = None;
         let mut has_timeout = false;
@@ -140,7 +181,7 @@ impl FuzzHarness {
         if is_interesting {
             if self
                 .corpus
-                .add(input.clone(), self.coverage.total_edges() as u64)
+                .add(input.clone(), self.coverage.iteration_edges())
             {
                 self.stats.corpus_size = self.corpus.len();
             }
@@ -149,21 +190,17 @@ impl FuzzHarness {
         // Check for new coverage
         if self.coverage.has_new_coverage() {
             self.corpus
-                .add(input.clone(), self.coverage.total_edges() as u64);
+                .add(input.clone(), self.coverage.iteration_edges());
             self.stats.coverage_edges = self.coverage.total_edges();
         }
     }
 
     fn handle_crash(&mut self, input: &[u8], info: &CrashInfo, target_name: &str) {
-        // Calculate crash hash for deduplication
-        let hash = self.hash_crash(info);
-
-        // Check if unique
-        let is_unique = !self.crashes.iter().any(|c| c.hash == hash);
+        let hash = self.deduper.bucket(info);
 
         self.stats.crashes += 1;
 
-        if is_unique {
+        if self.deduper.add(info) {
             self.stats.unique_crashes += 1;
             self.crashes.push(CrashEntry {
                 input: input.to_vec(),
@@ -174,25 +211,6 @@ impl FuzzHarness {
         }
     }
 
-    fn hash_crash(&self, info: &CrashInfo) -> u64 {
-        // Simple hash based on crash type and top stack frames
-        let mut hash = match info.crash_type {
-            CrashType::Segfault => 1,
-            CrashType::StackOverflow => 2,
-            CrashType::Abort => 3,
-            CrashType::Panic => 4,
-            CrashType::IntegerOverflow => 5,
-            CrashType::OutOfMemory => 6,
-            CrashType::Unknown => 0,
-        };
-
-        for (i, frame) in info.stack_trace.iter().take(5).enumerate() {
-            hash ^= frame.address.wrapping_mul((i + 1) as u64);
-        }
-
-        hash
-    }
-
     /// Get current statistics
     pub fn stats(&self) -> &FuzzStats {
         &self.stats
@@ -209,6 +227,77 @@ impl FuzzHarness {
     }
 }
 
+/// Buckets crashes by crash type and top stack frames
+///
+/// Frames are compared by function name when known, otherwise by address
+/// relative to the lowest address among the compared frames, so the same
+/// crash in a relocated binary lands in the same bucket.
+#[derive(Debug, Clone)]
+pub struct CrashDeduper {
+    /// Frames from the top of the stack that are compared
+    frames: usize,
+    /// Buckets seen so far
+    buckets: hashbrown::HashSet<u64>,
+}
+
+impl CrashDeduper {
+    /// Create a deduper comparing the default number of frames
+    pub fn new() -> Self {
+        Self::with_frames(DEDUP_FRAMES)
+    }
+
+    /// Create a deduper comparing the top `frames` frames
+    pub fn with_frames(frames: usize) -> Self {
+        Self {
+            frames,
+            buckets: hashbrown::HashSet::new(),
+        }
+    }
+
+    /// Bucket a crash falls in
+    pub fn bucket(&self, info: &CrashInfo) -> u64 {
+        let frames = &info.stack_trace[..info.stack_trace.len().min(self.frames)];
+        let base = frames.iter().map(|f| f.address).min().unwrap_or(0);
+
+        let mut key = vec![info.crash_type as u8];
+        for frame in frames {
+            match &frame.function {
+                Some(name) => {
+                    key.push(b'f');
+                    key.extend_from_slice(name.as_bytes());
+                    key.push(0);
+                }
+                None => {
+                    key.push(b'a');
+                    key.extend_from_slice(&(frame.address - base).to_le_bytes());
+                }
+            }
+        }
+        content_hash(&key)
+    }
+
+    /// Record a crash; true if its bucket is new
+    pub fn add(&mut self, info: &CrashInfo) -> bool {
+        self.buckets.insert(self.bucket(info))
+    }
+
+    /// Number of distinct buckets seen
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Check if no crash has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+impl Default for CrashDeduper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Fuzzing report
 #[derive(Debug, Clone)]
 pub struct FuzzReport {
@@ -344,6 +433,57 @@ impl<'a> CrashMinimizer<'a> {
     }
 }
 
+/// Most times [`minimize`] runs its predicate
+const MINIMIZE_MAX_RUNS: usize = 10_000;
+
+/// Shrink a crashing input while it still crashes the same way
+///
+/// Chunks are removed at decreasing sizes, keeping each removal after
+/// which `predicate` still reports a crash in the original input's
+/// [`CrashDeduper`] bucket. Stops when no single byte can be removed or
+/// after `MINIMIZE_MAX_RUNS` runs. An input that does not crash is
+/// returned as is.
+pub fn minimize<F: FnMut(&[u8]) -> FuzzResult>(input: &[u8], mut predicate: F) -> Vec<u8> {
+    let deduper = CrashDeduper::new();
+    let bucket = match predicate(input) {
+        FuzzResult::Crash(info) => deduper.bucket(&info),
+        _ => return input.to_vec(),
+    };
+    let mut runs = 1;
+
+    let mut current = input.to_vec();
+    let mut chunk_len = (current.len() / 2).max(1);
+    loop {
+        let mut removed = false;
+        let mut start = 0;
+        while start < current.len() {
+            if runs == MINIMIZE_MAX_RUNS {
+                return current;
+            }
+            runs += 1;
+
+            let end = (start + chunk_len).min(current.len());
+            let mut candidate = current[..start].to_vec();
+            candidate.extend_from_slice(&current[end..]);
+            match predicate(&candidate) {
+                FuzzResult::Crash(info) if deduper.bucket(&info) == bucket => {
+                    // The next chunk has moved to `start`
+                    current = candidate;
+                    removed = true;
+                }
+                _ => start = end,
+            }
+        }
+
+        if !removed {
+            if chunk_len == 1 {
+                return current;
+            }
+            chunk_len /= 2;
+        }
+    }
+}
+
 /// Create a crash info from a panic
 pub fn crash_from_panic(message: &str) -> CrashInfo {
     CrashInfo {
@@ -395,7 +535,132 @@ pub fn quick_fuzz<T: FuzzTarget>(mut target: T, corpus: Vec<Vec<u8>>, iterations
 impl Mutator {
     /// Get random value (exposed for quick_fuzz)
     pub fn mutate_seed(&mut self) -> u64 {
-        self.seed = self.seed.wrapping_mul(1103515245).wrapping_add(12345);
-        self.seed
+        self.random()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(function: Option<&str>, address: u64) -> StackFrame {
+        StackFrame {
+            function: function.map(String::from),
+            address,
+            file: None,
+            line: None,
+        }
+    }
+
+    fn crash(stack_trace: Vec<StackFrame>) -> CrashInfo {
+        CrashInfo {
+            crash_type: CrashType::Segfault,
+            address: Some(0),
+            stack_trace,
+            registers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_crashes_differing_below_top_frames_share_a_bucket() {
+        let names = ["parse_attr", "parse_tag", "parse", "load", "navigate"];
+        let trace = |leaf: &str| {
+            let mut frames: Vec<StackFrame> =
+                names.iter().map(|name| frame(Some(name), 0)).collect();
+            frames.push(frame(Some(leaf), 0));
+            frames
+        };
+
+        let mut deduper = CrashDeduper::new();
+        assert!(deduper.add(&crash(trace("main"))));
+        assert!(!deduper.add(&crash(trace("worker_main"))));
+        assert_eq!(deduper.len(), 1);
+
+        let mut other_type = crash(trace("main"));
+        other_type.crash_type = CrashType::Abort;
+        assert!(deduper.add(&other_type));
+
+        // With one more frame compared the two differ
+        let mut deeper = CrashDeduper::with_frames(6);
+        assert!(deeper.add(&crash(trace("main"))));
+        assert!(deeper.add(&crash(trace("worker_main"))));
+    }
+
+    #[test]
+    fn test_address_only_traces_bucket_across_relocation() {
+        let trace = |base: u64, top: u64| {
+            vec![
+                frame(None, base + top),
+                frame(None, base + 0x2000),
+                frame(None, base + 0x1000),
+            ]
+        };
+
+        let mut deduper = CrashDeduper::new();
+        assert!(deduper.add(&crash(trace(0x5555_0000_0000, 0x3000))));
+        assert!(!deduper.add(&crash(trace(0x7f00_1234_0000, 0x3000))));
+        assert!(deduper.add(&crash(trace(0x5555_0000_0000, 0x3400))));
+    }
+
+    #[test]
+    fn test_minimize_keeps_the_crashing_byte() {
+        let mut input = vec![0u8; 1024];
+        input[3] = 0xFF;
+
+        let mut runs = 0;
+        let minimized = minimize(&input, |input| {
+            runs += 1;
+            if input.get(3) == Some(&0xFF) {
+                FuzzResult::Crash(crash_from_panic("bad byte"))
+            } else {
+                FuzzResult::Ok
+            }
+        });
+        assert_eq!(minimized, [0, 0, 0, 0xFF]);
+        assert!(runs < 200, "{} runs", runs);
+
+        // A crash in another bucket does not count
+        let minimized = minimize(&input, |input| {
+            if input.get(3) != Some(&0xFF) {
+                FuzzResult::Ok
+            } else if input.len() < 100 {
+                FuzzResult::Crash(crash_from_panic("other"))
+            } else {
+                FuzzResult::Crash(crash_from_panic("bad byte"))
+            }
+        });
+        assert!(minimized.len() >= 100 && minimized.len() < 200);
+
+        assert_eq!(minimize(&input, |_| FuzzResult::Ok), input);
+    }
+
+    #[test]
+    fn test_harness_counts_unique_crashes() {
+        struct Crasher(u64);
+
+        impl FuzzTarget for Crasher {
+            fn name(&self) -> &str {
+                "crasher"
+            }
+
+            fn fuzz(&mut self, _input: &[u8]) -> FuzzResult {
+                self.0 += 1;
+                let mut info = crash_from_panic("index out of bounds");
+                info.stack_trace.push(frame(None, self.0));
+                FuzzResult::Crash(info)
+            }
+
+            fn reset(&mut self) {}
+        }
+
+        let mut harness = FuzzHarness::new(FuzzerConfig {
+            dedup_frames: 1,
+            ..FuzzerConfig::default()
+        });
+        harness.add_target(Box::new(Crasher(0)));
+        let report = harness.run(10);
+        assert_eq!(report.stats.crashes, 10);
+        assert_eq!(report.stats.unique_crashes, 1);
+        assert_eq!(report.crashes.len(), 1);
     }
 }
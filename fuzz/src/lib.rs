@@ -4,6 +4,8 @@
 
 #![no_std]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 pub mod css;
 pub mod harness;
@@ -11,8 +13,11 @@ pub mod html;
 pub mod js;
 pub mod network;
 
+#[cfg(feature = "std")]
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
+use hashbrown::HashSet;
 
 /// Fuzzing target trait
 pub trait FuzzTarget {
@@ -124,6 +129,9 @@ pub enum MemoryErrorKind {
     HeapBufferOverflow,
 }
 
+/// Default stack frames compared when deduplicating crashes
+const DEDUP_FRAMES: usize = 5;
+
 /// Fuzzer configuration
 #[derive(Debug, Clone)]
 pub struct FuzzerConfig {
@@ -143,6 +151,8 @@ pub struct FuzzerConfig {
     pub coverage: bool,
     /// Enable sanitizers
     pub sanitizers: Sanitizers,
+    /// Stack frames that decide whether two crashes are the same
+    pub dedup_frames: usize,
 }
 
 impl Default for FuzzerConfig {
@@ -156,6 +166,7 @@ impl Default for FuzzerConfig {
             workers: 1,
             coverage: true,
             sanitizers: Sanitizers::default(),
+            dedup_frames: DEDUP_FRAMES,
         }
     }
 }
@@ -206,10 +217,18 @@ impl FuzzStats {
     }
 }
 
+/// PCG multiplier
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+/// PCG increment (must be odd)
+const PCG_INCREMENT: u64 = 1442695040888963407;
+
 /// Mutator for input generation
+///
+/// Randomness comes from a PCG-XSH-RR generator whose whole state is one
+/// `u64`, so a mutation sequence can be replayed from [`Mutator::state`].
 pub struct Mutator {
-    /// Current seed
-    seed: u64,
+    /// PCG state
+    state: u64,
     /// Dictionary of interesting values
     dictionary: Vec<Vec<u8>>,
 }
@@ -217,26 +236,48 @@ pub struct Mutator {
 impl Mutator {
     /// Create a new mutator
     pub fn new(seed: u64) -> Self {
+        let mut mutator = Self::with_state(0);
+        mutator.next_u32();
+        mutator.state = mutator.state.wrapping_add(seed);
+        mutator.next_u32();
+        mutator
+    }
+
+    /// Create a mutator that continues from a saved [`Mutator::state`]
+    pub fn with_state(state: u64) -> Self {
         Self {
-            seed,
+            state,
             dictionary: Vec::new(),
         }
     }
 
+    /// Generator state; a mutator made with [`Mutator::with_state`] from
+    /// it makes the same mutations from here on
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
     /// Add dictionary entry
     pub fn add_dictionary(&mut self, entry: Vec<u8>) {
         self.dictionary.push(entry);
     }
 
+    /// Advance the generator and return its output
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(PCG_INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
     /// Generate random bytes
     fn random(&mut self) -> u64 {
-        self.seed = self.seed.wrapping_mul(1103515245).wrapping_add(12345);
-        self.seed
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
     }
 
     /// Mutate input
     pub fn mutate(&mut self, input: &mut Vec<u8>) {
-        let strategy = self.random() % 10;
+        let strategy = self.random() % 9;
 
         match strategy {
             0 => self.bit_flip(input),
@@ -244,14 +285,27 @@ impl Mutator {
             2 => self.byte_insert(input),
             3 => self.byte_delete(input),
             4 => self.byte_replace(input),
-            5 => self.splice(input),
-            6 => self.interesting_value(input),
-            7 => self.dictionary_insert(input),
-            8 => self.havoc(input),
+            5 => self.interesting_value(input),
+            6 => self.dictionary_insert(input),
+            7 => self.havoc(input),
             _ => self.random_bytes(input),
         }
     }
 
+    /// Cross `input` over with `other`: a prefix of `input` followed by
+    /// a suffix of `other`, each at least one byte, cut to `max_len`.
+    /// Leaves `input` alone if either is empty.
+    pub fn splice_with(&mut self, input: &mut Vec<u8>, other: &[u8], max_len: usize) {
+        if input.is_empty() || other.is_empty() {
+            return;
+        }
+        let prefix_len = 1 + (self.random() as usize) % input.len();
+        let suffix_start = (self.random() as usize) % other.len();
+        input.truncate(prefix_len);
+        input.extend_from_slice(&other[suffix_start..]);
+        input.truncate(max_len);
+    }
+
     fn bit_flip(&mut self, input: &mut Vec<u8>) {
         if input.is_empty() {
             return;
@@ -295,11 +349,6 @@ impl Mutator {
         input[pos] = (self.random() & 0xFF) as u8;
     }
 
-    fn splice(&mut self, input: &mut Vec<u8>) {
-        // Would splice with corpus entries
-        let _ = input;
-    }
-
     fn interesting_value(&mut self, input: &mut Vec<u8>) {
         const INTERESTING: &[u8] = &[0, 1, 0x7F, 0x80, 0xFF];
         if input.is_empty() {
@@ -357,11 +406,16 @@ impl Mutator {
 }
 
 /// Corpus manager
+///
+/// Keeps inputs that reach edges no other entry reaches. Inputs with no
+/// recorded edges, such as seeds, are always kept.
 pub struct Corpus {
     /// Input entries
     entries: Vec<CorpusEntry>,
     /// Maximum size
     max_size: usize,
+    /// Edges reached by some entry
+    covered: HashSet<usize>,
 }
 
 /// A corpus entry
@@ -369,8 +423,8 @@ pub struct Corpus {
 pub struct CorpusEntry {
     /// Input data
     pub data: Vec<u8>,
-    /// Coverage this input provides
-    pub coverage: u64,
+    /// Edges this input reaches, sorted
+    pub edges: Vec<usize>,
     /// Execution count
     pub exec_count: u64,
     /// Found timestamp
@@ -383,32 +437,73 @@ impl Corpus {
         Self {
             entries: Vec::new(),
             max_size,
+            covered: HashSet::new(),
         }
     }
 
-    /// Add entry if interesting
-    pub fn add(&mut self, data: Vec<u8>, coverage: u64) -> bool {
-        // Check if this provides new coverage
-        let dominated = self.entries.iter().any(|e| e.coverage >= coverage);
+    /// Add an input that reached `edges`, if it reaches one no entry
+    /// does yet
+    pub fn add(&mut self, data: Vec<u8>, edges: &[usize]) -> bool {
+        let mut edges = edges.to_vec();
+        edges.sort_unstable();
+        edges.dedup();
+        self.insert(CorpusEntry {
+            data,
+            edges,
+            exec_count: 1,
+            timestamp: 0,
+        })
+    }
 
-        if !dominated {
-            self.entries.push(CorpusEntry {
-                data,
-                coverage,
-                exec_count: 1,
-                timestamp: 0,
-            });
+    /// Add an entry if it adds coverage (or has none recorded)
+    fn insert(&mut self, entry: CorpusEntry) -> bool {
+        let adds_coverage =
+            entry.edges.is_empty() || entry.edges.iter().any(|edge| !self.covered.contains(edge));
+        if !adds_coverage {
+            return false;
+        }
 
-            // Trim if too large
-            if self.entries.len() > self.max_size {
-                self.entries.sort_by_key(|e| core::cmp::Reverse(e.coverage));
-                self.entries.truncate(self.max_size);
-            }
+        self.covered.extend(entry.edges.iter().copied());
+        self.entries.push(entry);
 
-            true
-        } else {
-            false
+        // Trim if too large
+        if self.entries.len() > self.max_size {
+            self.minimize();
+        }
+
+        true
+    }
+
+    /// Drop entries whose edges smaller entries already reach. If that
+    /// is not enough, entries without recorded edges go first, then the
+    /// largest inputs.
+    fn minimize(&mut self) {
+        self.entries.sort_by_key(|e| e.data.len());
+
+        let mut covered = HashSet::new();
+        let mut kept = Vec::new();
+        let mut unscored = Vec::new();
+        for entry in self.entries.drain(..) {
+            if entry.edges.is_empty() {
+                unscored.push(entry);
+                continue;
+            }
+            let before = covered.len();
+            covered.extend(entry.edges.iter().copied());
+            if covered.len() > before {
+                kept.push(entry);
+            }
         }
+        kept.append(&mut unscored);
+        kept.truncate(self.max_size);
+
+        self.covered = kept.iter().flat_map(|e| e.edges.iter().copied()).collect();
+        self.entries = kept;
+    }
+
+    /// Number of distinct edges the corpus reaches
+    pub fn total_coverage(&self) -> usize {
+        self.covered.len()
     }
 
     /// Get random entry
@@ -432,14 +527,130 @@ impl Corpus {
     }
 }
 
+#[cfg(feature = "std")]
+impl Corpus {
+    /// Write every entry to `dir`: the input in a file named by its
+    /// content hash, next to a `.meta` sidecar holding its edges and
+    /// statistics. Entries saved by an earlier run are overwritten in
+    /// place. Returns the number of entries written.
+    pub fn save_to_dir(&self, dir: &str) -> Result<usize, String> {
+        let dir = dir.trim_end_matches('/');
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir, e))?;
+
+        for entry in &self.entries {
+            let path = format!("{}/{:016x}", dir, content_hash(&entry.data));
+            let edges: Vec<String> = entry.edges.iter().map(|e| format!("{}", e)).collect();
+            let meta = format!(
+                "edges={}\nexec_count={}\ntimestamp={}\n",
+                edges.join(","),
+                entry.exec_count,
+                entry.timestamp
+            );
+            std::fs::write(&path, &entry.data).map_err(|e| format!("{}: {}", path, e))?;
+            std::fs::write(format!("{}.meta", path), meta)
+                .map_err(|e| format!("{}: {}", path, e))?;
+        }
+
+        Ok(self.entries.len())
+    }
+
+    /// Load a corpus written by [`Corpus::save_to_dir`]
+    ///
+    /// Files without a sidecar, such as hand-written seeds, load with no
+    /// recorded edges. Inputs over `max_input_size` bytes are skipped, as
+    /// are files repeating an input already loaded. Entries are re-added
+    /// smallest first, so one whose edges smaller inputs reach is dropped.
+    ///
+    /// `max_input_size` is taken separately from `max_size` because the
+    /// corpus does not hold the fuzzer's configuration; callers pass
+    /// [`FuzzerConfig::max_input_size`].
+    pub fn load_from_dir(
+        max_size: usize,
+        max_input_size: usize,
+        dir: &str,
+    ) -> Result<Self, String> {
+        let read_dir = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir, e))?;
+
+        let mut loaded = Vec::new();
+        for dir_entry in read_dir {
+            let path = dir_entry.map_err(|e| format!("{}: {}", dir, e))?.path();
+            if !path.is_file() || path.extension().is_some_and(|ext| ext == "meta") {
+                continue;
+            }
+            let Ok(data) = std::fs::read(&path) else {
+                continue;
+            };
+            if data.len() > max_input_size {
+                continue;
+            }
+
+            let mut entry = CorpusEntry {
+                data,
+                edges: Vec::new(),
+                exec_count: 1,
+                timestamp: 0,
+            };
+            let mut meta_path = path.into_os_string();
+            meta_path.push(".meta");
+            if let Ok(meta) = std::fs::read_to_string(&meta_path) {
+                for (key, value) in meta.lines().filter_map(|line| line.split_once('=')) {
+                    let value = value.trim();
+                    match key.trim() {
+                        "edges" => {
+                            entry.edges = value
+                                .split(',')
+                                .filter_map(|edge| edge.parse().ok())
+                                .collect();
+                            entry.edges.sort_unstable();
+                            entry.edges.dedup();
+                        }
+                        "exec_count" => entry.exec_count = value.parse().unwrap_or(1),
+                        "timestamp" => entry.timestamp = value.parse().unwrap_or(0),
+                        _ => {}
+                    }
+                }
+            }
+            loaded.push(entry);
+        }
+
+        // Keep the best-covering copy of each duplicated input
+        loaded.sort_by_key(|entry| core::cmp::Reverse(entry.edges.len()));
+        let mut seen = HashSet::new();
+        loaded.retain(|entry| seen.insert(entry.data.clone()));
+
+        loaded.sort_by_key(|entry| (entry.data.len(), entry.timestamp));
+        let mut corpus = Self::new(max_size);
+        for entry in loaded {
+            corpus.insert(entry);
+        }
+        Ok(corpus)
+    }
+}
+
+/// FNV-1a hash naming saved corpus entries and crash buckets
+fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
 /// Coverage tracker
+///
+/// Hit counts are bucketed AFL-style (1, 2, 3, 4-7, 8-15, 16-31, 32-127,
+/// 128+), and an iteration is new coverage only if some edge lands in a
+/// bucket it has not reached before, so a loop running 9 rather than 10
+/// times is not interesting.
 pub struct CoverageTracker {
-    /// Edge coverage bitmap
+    /// Hit counts this iteration
     edges: Vec<u8>,
+    /// Buckets not yet reached by each edge, one bit per bucket
+    virgin: Vec<u8>,
     /// Total edges seen
     total_edges: usize,
-    /// New edges found this run
-    new_edges: usize,
+    /// Edges hit this iteration
+    iteration_edges: Vec<usize>,
+    /// Result of comparing this iteration against `virgin`, once done
+    new_coverage: Option<bool>,
 }
 
 impl CoverageTracker {
@@ -447,8 +658,25 @@ impl CoverageTracker {
     pub fn new(size: usize) -> Self {
         Self {
             edges: alloc::vec![0u8; size],
+            virgin: alloc::vec![0xFF; size],
             total_edges: 0,
-            new_edges: 0,
+            iteration_edges: Vec::new(),
+            new_coverage: None,
+        }
+    }
+
+    /// Bucket bit for a hit count, 0 if the edge was not hit
+    pub fn bucket(count: u8) -> u8 {
+        match count {
+            0 => 0,
+            1 => 1,
+            2 => 2,
+            3 => 4,
+            4..=7 => 8,
+            8..=15 => 16,
+            16..=31 => 32,
+            32..=127 => 64,
+            128..=255 => 128,
         }
     }
 
@@ -456,20 +684,53 @@ impl CoverageTracker {
     pub fn record_edge(&mut self, edge: usize) {
         let idx = edge % self.edges.len();
         if self.edges[idx] == 0 {
-            self.new_edges += 1;
-            self.total_edges += 1;
+            self.iteration_edges.push(idx);
         }
         self.edges[idx] = self.edges[idx].saturating_add(1);
     }
 
     /// Reset for new iteration
     pub fn reset_iteration(&mut self) {
-        self.new_edges = 0;
+        self.new_coverage = None;
+        for idx in self.iteration_edges.drain(..) {
+            self.edges[idx] = 0;
+        }
     }
 
-    /// Check if new coverage found
-    pub fn has_new_coverage(&self) -> bool {
-        self.new_edges > 0
+    /// Edges hit since the last reset
+    pub fn iteration_edges(&self) -> &[usize] {
+        &self.iteration_edges
+    }
+
+    /// Check if this iteration reached a new bucket on some edge. The
+    /// first call in an iteration records its buckets as reached.
+    pub fn has_new_coverage(&mut self) -> bool {
+        if let Some(new_coverage) = self.new_coverage {
+            return new_coverage;
+        }
+
+        let mut new_coverage = false;
+        for &idx in &self.iteration_edges {
+            let bucket = Self::bucket(self.edges[idx]);
+            if self.virgin[idx] & bucket != 0 {
+                if self.virgin[idx] == 0xFF {
+                    self.total_edges += 1;
+                }
+                self.virgin[idx] &= !bucket;
+                new_coverage = true;
+            }
+        }
+        self.new_coverage = Some(new_coverage);
+        new_coverage
+    }
+
+    /// Add the buckets another tracker of the same size has reached,
+    /// such as a parallel worker's
+    pub fn merge(&mut self, other: &CoverageTracker) {
+        for (virgin, other) in self.virgin.iter_mut().zip(&other.virgin) {
+            *virgin &= other;
+        }
+        self.total_edges = self.virgin.iter().filter(|&&v| v != 0xFF).count();
     }
 
     /// Get total edges
@@ -482,3 +743,183 @@ impl CoverageTracker {
         (self.total_edges as f64 / self.edges.len() as f64) * 100.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_same_state_same_mutations() {
+        let run = |mut mutator: Mutator| {
+            let mut input = b"<html><body>seed</body></html>".to_vec();
+            for _ in 0..200 {
+                mutator.mutate(&mut input);
+            }
+            input
+        };
+        assert_eq!(run(Mutator::new(42)), run(Mutator::new(42)));
+        assert_ne!(run(Mutator::new(42)), run(Mutator::new(43)));
+
+        // Replaying from a saved state reproduces what follows it
+        let mut mutator = Mutator::new(42);
+        let mut input = b"warm up".to_vec();
+        mutator.mutate(&mut input);
+        let state = mutator.state();
+        assert_eq!(run(mutator), run(Mutator::with_state(state)));
+    }
+
+    #[test]
+    fn test_positions_are_uniform() {
+        const LEN: usize = 10;
+        const SAMPLES: usize = 20_000;
+        let mut mutator = Mutator::new(1);
+        let mut counts = [0usize; LEN];
+        for _ in 0..SAMPLES {
+            let mut input = vec![0u8; LEN];
+            mutator.byte_flip(&mut input);
+            counts[input.iter().position(|&b| b == 0xFF).unwrap()] += 1;
+        }
+        let expected = SAMPLES / LEN;
+        for count in counts {
+            assert!(
+                count.abs_diff(expected) < expected / 10,
+                "position counts {:?}",
+                counts
+            );
+        }
+    }
+
+    #[test]
+    fn test_coverage_buckets() {
+        let mut tracker = CoverageTracker::new(64);
+        let run = |tracker: &mut CoverageTracker, hits: &[(usize, usize)]| {
+            tracker.reset_iteration();
+            for &(edge, count) in hits {
+                for _ in 0..count {
+                    tracker.record_edge(edge);
+                }
+            }
+            tracker.has_new_coverage()
+        };
+
+        assert!(run(&mut tracker, &[(1, 9)]));
+        // 10 hits is in the same 8-15 bucket as 9
+        assert!(!run(&mut tracker, &[(1, 10)]));
+        assert!(run(&mut tracker, &[(1, 1)]));
+        assert!(!run(&mut tracker, &[(1, 1)]));
+        assert!(run(&mut tracker, &[(1, 200), (2, 1)]));
+        assert!(tracker.has_new_coverage());
+        assert_eq!(tracker.total_edges(), 2);
+
+        let mut worker = CoverageTracker::new(64);
+        assert!(run(&mut worker, &[(2, 3), (5, 1)]));
+        tracker.merge(&worker);
+        assert_eq!(tracker.total_edges(), 3);
+        assert!(!run(&mut tracker, &[(2, 3), (5, 1)]));
+        assert!(run(&mut tracker, &[(5, 2)]));
+    }
+
+    #[test]
+    fn test_splice_takes_bytes_from_both_parents() {
+        let mut mutator = Mutator::new(7);
+        let other = vec![0xBB; 32];
+
+        for _ in 0..100 {
+            let mut child = vec![0xAA; 32];
+            mutator.splice_with(&mut child, &other, 64);
+            assert_eq!(child[0], 0xAA);
+            assert_eq!(child[child.len() - 1], 0xBB);
+            assert!(child.len() <= 64);
+
+            let mut capped = vec![0xAA; 32];
+            mutator.splice_with(&mut capped, &other, 16);
+            assert!(capped.len() <= 16);
+        }
+
+        let mut empty = Vec::new();
+        mutator.splice_with(&mut empty, &other, 64);
+        assert!(empty.is_empty());
+        let mut unchanged = vec![0xAA; 4];
+        mutator.splice_with(&mut unchanged, &[], 64);
+        assert_eq!(unchanged, [0xAA; 4]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_corpus_dir_round_trip() {
+        let dir = std::env::temp_dir().join(format!("kpio-corpus-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir_str = dir.to_str().unwrap();
+
+        let mut corpus = Corpus::new(16);
+        assert!(corpus.add(b"abc".to_vec(), &[3, 1, 2]));
+        assert!(corpus.add(b"defgh".to_vec(), &[4]));
+        corpus.entries[1].exec_count = 7;
+        corpus.entries[1].timestamp = 99;
+        assert_eq!(corpus.save_to_dir(&format!("{}/", dir_str)), Ok(2));
+        // Saving again overwrites the same files
+        assert_eq!(corpus.save_to_dir(dir_str), Ok(2));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4);
+
+        // A copy of a saved input without its sidecar, an input over the
+        // size limit and a hand-written seed
+        std::fs::write(dir.join("copy"), b"abc").unwrap();
+        std::fs::write(dir.join("huge"), [0u8; 64]).unwrap();
+        std::fs::write(dir.join("seed"), b"<p>").unwrap();
+
+        let loaded = Corpus::load_from_dir(16, 32, dir_str).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.total_coverage(), 4);
+        let entry = |data: &[u8]| loaded.entries.iter().find(|e| e.data == data).unwrap();
+        assert_eq!(entry(b"abc").edges, [1, 2, 3]);
+        assert_eq!(entry(b"defgh").edges, [4]);
+        assert_eq!(entry(b"defgh").exec_count, 7);
+        assert_eq!(entry(b"defgh").timestamp, 99);
+        assert!(entry(b"<p>").edges.is_empty());
+        assert!(loaded.entries.iter().all(|e| e.data.len() <= 32));
+    }
+
+    #[test]
+    fn test_corpus_keeps_new_edges() {
+        let mut corpus = Corpus::new(16);
+        assert!(corpus.add(b"a".to_vec(), &[1, 2]));
+        // Same number of edges, but different ones
+        assert!(corpus.add(b"b".to_vec(), &[3, 4]));
+        assert!(!corpus.add(b"c".to_vec(), &[2, 3]));
+        assert!(!corpus.add(b"d".to_vec(), &[4, 4, 1]));
+        assert!(corpus.add(b"e".to_vec(), &[4, 5]));
+        assert_eq!(corpus.len(), 3);
+        assert_eq!(corpus.total_coverage(), 5);
+
+        // Seeds have no recorded edges and are always kept
+        assert!(corpus.add(b"seed".to_vec(), &[]));
+        assert_eq!(corpus.total_coverage(), 5);
+    }
+
+    #[test]
+    fn test_corpus_minimize_keeps_smallest_inputs() {
+        let mut corpus = Corpus::new(3);
+        assert!(corpus.add(b"aaaaaaaa".to_vec(), &[1, 2]));
+        assert!(corpus.add(b"bbbbbbbb".to_vec(), &[3]));
+        assert!(corpus.add(b"cccc".to_vec(), &[4]));
+        // Over the limit: the small input reaching 1-3 makes the two
+        // large ones redundant
+        assert!(corpus.add(b"dd".to_vec(), &[1, 2, 3, 5]));
+        assert_eq!(corpus.total_coverage(), 5);
+        let mut kept: Vec<&[u8]> = corpus.entries.iter().map(|e| &e.data[..]).collect();
+        kept.sort();
+        assert_eq!(kept, [&b"cccc"[..], b"dd"]);
+
+        // When every entry adds coverage, the largest go first
+        let mut corpus = Corpus::new(2);
+        assert!(corpus.add(b"aaaa".to_vec(), &[1]));
+        assert!(corpus.add(b"b".to_vec(), &[2]));
+        assert!(corpus.add(b"cc".to_vec(), &[3]));
+        assert_eq!(corpus.len(), 2);
+        assert_eq!(corpus.total_coverage(), 2);
+        assert!(corpus.entries.iter().all(|e| e.data != b"aaaa"));
+    }
+}
@@ -23,6 +23,7 @@ use kpio_layout::paint::{
     BorderRadii, BorderStyle, BorderWidths, Color, DisplayCommand, DisplayList, TextStyle,
 };
 
+use crate::command::{CommandBuffer, ScissorRect};
 use crate::surface::SurfaceId;
 use crate::GraphicsError;
 
@@ -44,16 +45,23 @@ pub struct BrowserRenderer {
     batches: Vec<RenderBatch>,
 
     /// Clip stack for nested clips
-    clip_stack: Vec<RenderRect>,
+    clip_stack: Vec<Clip>,
 
     /// Transform stack
     transform_stack: Vec<Transform2D>,
 
-    /// Opacity stack
-    opacity_stack: Vec<f32>,
+    /// Opacity stack: the opacity of each group that was given its own
+    /// layer, `None` for groups drawn directly
+    opacity_stack: Vec<Option<f32>>,
+}
 
-    /// Current effective opacity
-    current_opacity: f32,
+/// An entry of the clip stack.
+#[derive(Debug, Clone, Copy)]
+struct Clip {
+    /// Bounds of the clip, which primitives are clipped to on the CPU
+    rect: RenderRect,
+    /// Whether the corners are masked off as well
+    rounded: bool,
 }
 
 impl BrowserRenderer {
@@ -68,7 +76,6 @@ impl BrowserRenderer {
             clip_stack: Vec::new(),
             transform_stack: Vec::new(),
             opacity_stack: Vec::new(),
-            current_opacity: 1.0,
         }
     }
 
@@ -95,7 +102,6 @@ impl BrowserRenderer {
         self.clip_stack.clear();
         self.transform_stack.clear();
         self.opacity_stack.clear();
-        self.current_opacity = 1.0;
 
         // Add background clear
         self.batches.push(RenderBatch::Clear(self.background_color));
@@ -155,6 +161,7 @@ impl BrowserRenderer {
             DisplayCommand::BoxShadow {
                 color,
                 rect,
+                radii,
                 blur_radius,
                 spread_radius,
                 offset_x,
@@ -164,6 +171,7 @@ impl BrowserRenderer {
                 self.draw_box_shadow(
                     *color,
                     *rect,
+                    *radii,
                     *blur_radius,
                     *spread_radius,
                     *offset_x,
@@ -176,6 +184,10 @@ impl BrowserRenderer {
                 self.push_clip(*rect);
             }
 
+            DisplayCommand::PushRoundedClip { rect, radii } => {
+                self.push_rounded_clip(*rect, *radii);
+            }
+
             DisplayCommand::PopClip => {
                 self.pop_clip();
             }
@@ -206,7 +218,7 @@ impl BrowserRenderer {
 
         // Apply clipping
         if let Some(clipped) = self.clip_rect(render_rect) {
-            let render_color = self.apply_opacity(RenderColor::from_layout(color));
+            let render_color = RenderColor::from_layout(color);
             self.batches.push(RenderBatch::Rect {
                 rect: clipped,
                 color: render_color,
@@ -225,7 +237,7 @@ impl BrowserRenderer {
         _style: BorderStyle,
     ) -> Result<(), GraphicsError> {
         let render_rect = self.transform_rect(rect);
-        let render_color = self.apply_opacity(RenderColor::from_layout(color));
+        let render_color = RenderColor::from_layout(color);
 
         // Draw four border edges as separate rectangles
         // Top border
@@ -303,7 +315,7 @@ impl BrowserRenderer {
         style: &TextStyle,
     ) -> Result<(), GraphicsError> {
         let render_rect = self.transform_rect(rect);
-        let render_color = self.apply_opacity(RenderColor::from_layout(style.color));
+        let render_color = RenderColor::from_layout(style.color);
 
         if let Some(clipped) = self.clip_rect(render_rect) {
             self.batches.push(RenderBatch::Text {
@@ -331,7 +343,6 @@ impl BrowserRenderer {
             self.batches.push(RenderBatch::Image {
                 image_id,
                 rect: clipped,
-                opacity: self.current_opacity,
             });
         }
 
@@ -351,8 +362,8 @@ impl BrowserRenderer {
         if let Some(clipped) = self.clip_rect(render_rect) {
             self.batches.push(RenderBatch::Gradient {
                 rect: clipped,
-                start_color: self.apply_opacity(RenderColor::from_layout(start_color)),
-                end_color: self.apply_opacity(RenderColor::from_layout(end_color)),
+                start_color: RenderColor::from_layout(start_color),
+                end_color: RenderColor::from_layout(end_color),
                 angle,
             });
         }
@@ -368,18 +379,13 @@ impl BrowserRenderer {
         radii: BorderRadii,
     ) -> Result<(), GraphicsError> {
         let render_rect = self.transform_rect(rect);
-        let render_color = self.apply_opacity(RenderColor::from_layout(color));
+        let render_color = RenderColor::from_layout(color);
 
         if let Some(clipped) = self.clip_rect(render_rect) {
             self.batches.push(RenderBatch::RoundedRect {
                 rect: clipped,
                 color: render_color,
-                radii: [
-                    radii.top_left,
-                    radii.top_right,
-                    radii.bottom_right,
-                    radii.bottom_left,
-                ],
+                radii: self.transform_radii(radii),
             });
         }
 
@@ -387,10 +393,15 @@ impl BrowserRenderer {
     }
 
     /// Draw a box shadow.
+    ///
+    /// The batch keeps the whole shape, which the blur needs; it is only
+    /// dropped when nothing it covers is visible.
+    #[allow(clippy::too_many_arguments)]
     fn draw_box_shadow(
         &mut self,
         color: Color,
         rect: Rect,
+        radii: BorderRadii,
         blur_radius: f32,
         spread_radius: f32,
         offset_x: f32,
@@ -398,11 +409,24 @@ impl BrowserRenderer {
         inset: bool,
     ) -> Result<(), GraphicsError> {
         let render_rect = self.transform_rect(rect);
-        let render_color = self.apply_opacity(RenderColor::from_layout(color));
+
+        // An outer shadow reaches past the offset box by its spread plus
+        // its blur; an inset one stays inside the box
+        let extent = if inset {
+            render_rect
+        } else {
+            render_rect
+                .offset(offset_x, offset_y)
+                .inflate(spread_radius + blur_radius)
+        };
+        if self.clip_rect(extent).is_none() {
+            return Ok(());
+        }
 
         self.batches.push(RenderBatch::Shadow {
             rect: render_rect,
-            color: render_color,
+            color: RenderColor::from_layout(color),
+            radii: self.transform_radii(radii),
             blur_radius,
             spread_radius,
             offset_x,
@@ -414,19 +438,48 @@ impl BrowserRenderer {
     }
 
     /// Push a clip rectangle.
+    ///
+    /// Primitives are clipped on the CPU where possible; the scissor
+    /// catches what cannot be, such as glyphs and image contents.
     fn push_clip(&mut self, rect: Rect) {
         let render_rect = self.transform_rect(rect);
-        self.clip_stack.push(render_rect);
+        self.clip_stack.push(Clip {
+            rect: render_rect,
+            rounded: false,
+        });
+        self.batches.push(RenderBatch::PushScissor(render_rect));
+    }
+
+    /// Push a rounded clip rectangle: a scissor to its bounds plus a
+    /// mask for the corners.
+    fn push_rounded_clip(&mut self, rect: Rect, radii: BorderRadii) {
+        let render_rect = self.transform_rect(rect);
+        self.clip_stack.push(Clip {
+            rect: render_rect,
+            rounded: true,
+        });
+        self.batches.push(RenderBatch::PushScissor(render_rect));
+        self.batches.push(RenderBatch::PushClipMask {
+            rect: render_rect,
+            radii: self.transform_radii(radii),
+        });
     }
 
     /// Pop the clip rectangle.
     fn pop_clip(&mut self) {
-        self.clip_stack.pop();
+        if let Some(clip) = self.clip_stack.pop() {
+            if clip.rounded {
+                self.batches.push(RenderBatch::PopClipMask);
+            }
+            self.batches.push(RenderBatch::PopScissor);
+        }
     }
 
-    /// Push a transform.
+    /// Push a transform. It applies inside any transform already pushed.
     fn push_transform(&mut self, matrix: [f32; 6]) {
-        self.transform_stack.push(Transform2D::from_matrix(matrix));
+        let current = self.transform_stack.last().copied().unwrap_or_default();
+        self.transform_stack
+            .push(current.multiply(&Transform2D::from_matrix(matrix)));
     }
 
     /// Pop a transform.
@@ -435,32 +488,54 @@ impl BrowserRenderer {
     }
 
     /// Push opacity.
+    ///
+    /// A translucent group is drawn into a layer of its own and composited
+    /// once, so overlapping primitives inside it do not show through each
+    /// other.
     fn push_opacity(&mut self, opacity: f32) {
-        self.opacity_stack.push(self.current_opacity);
-        self.current_opacity *= opacity;
+        if opacity < 1.0 {
+            self.opacity_stack.push(Some(opacity.max(0.0)));
+            self.batches.push(RenderBatch::PushLayer);
+        } else {
+            self.opacity_stack.push(None);
+        }
     }
 
-    /// Pop opacity.
+    /// Pop opacity, compositing the group's layer if it has one.
     fn pop_opacity(&mut self) {
-        if let Some(prev) = self.opacity_stack.pop() {
-            self.current_opacity = prev;
+        if let Some(Some(opacity)) = self.opacity_stack.pop() {
+            self.batches.push(RenderBatch::PopLayer { opacity });
         }
     }
 
     /// Transform a rect using the current transform stack.
     fn transform_rect(&self, rect: Rect) -> RenderRect {
-        let mut result = RenderRect {
+        let result = RenderRect {
             x: rect.x,
             y: rect.y,
             width: rect.width,
             height: rect.height,
         };
 
-        for transform in &self.transform_stack {
-            result = transform.apply_to_rect(result);
+        match self.transform_stack.last() {
+            Some(transform) => transform.apply_to_rect(result),
+            None => result,
         }
+    }
 
-        result
+    /// Scale corner radii by the current transform.
+    fn transform_radii(&self, radii: BorderRadii) -> [f32; 4] {
+        let scale = self
+            .transform_stack
+            .last()
+            .map_or(1.0, Transform2D::scale_factor);
+        [
+            radii.top_left,
+            radii.top_right,
+            radii.bottom_right,
+            radii.bottom_left,
+        ]
+        .map(|radius| radius * scale)
     }
 
     /// Clip a rect against the current clip stack.
@@ -468,7 +543,7 @@ impl BrowserRenderer {
         let mut result = rect;
 
         for clip in &self.clip_stack {
-            result = result.intersect(clip)?;
+            result = result.intersect(&clip.rect)?;
         }
 
         // Clip against viewport
@@ -482,12 +557,6 @@ impl BrowserRenderer {
         result.intersect(&viewport)
     }
 
-    /// Apply current opacity to a color.
-    fn apply_opacity(&self, mut color: RenderColor) -> RenderColor {
-        color.a = (color.a as f32 * self.current_opacity) as u8;
-        color
-    }
-
     /// Execute all render batches.
     fn execute_batches(&self) -> Result<(), GraphicsError> {
         // In a real implementation, this would:
@@ -499,6 +568,10 @@ impl BrowserRenderer {
         // For now, we just count the batches (placeholder)
         log::debug!("Executing {} render batches", self.batches.len());
 
+        let mut cmd = CommandBuffer::new()?;
+        self.record_scissors(&mut cmd)?;
+        cmd.finish()?;
+
         for batch in &self.batches {
             match batch {
                 RenderBatch::Clear(color) => {
@@ -519,8 +592,28 @@ impl BrowserRenderer {
                 RenderBatch::RoundedRect { rect, .. } => {
                     log::trace!("RoundedRect at {:?}", rect);
                 }
-                RenderBatch::Shadow { rect, .. } => {
-                    log::trace!("Shadow at {:?}", rect);
+                RenderBatch::Shadow {
+                    rect, blur_radius, ..
+                } => {
+                    log::trace!("Shadow at {:?} blur={}", rect, blur_radius);
+                }
+                RenderBatch::PushScissor(rect) => {
+                    log::trace!("PushScissor: {:?}", rect);
+                }
+                RenderBatch::PopScissor => {
+                    log::trace!("PopScissor");
+                }
+                RenderBatch::PushClipMask { rect, radii } => {
+                    log::trace!("PushClipMask: {:?} radii={:?}", rect, radii);
+                }
+                RenderBatch::PopClipMask => {
+                    log::trace!("PopClipMask");
+                }
+                RenderBatch::PushLayer => {
+                    log::trace!("PushLayer");
+                }
+                RenderBatch::PopLayer { opacity } => {
+                    log::trace!("PopLayer: opacity={}", opacity);
                 }
             }
         }
@@ -528,6 +621,25 @@ impl BrowserRenderer {
         Ok(())
     }
 
+    /// Record the scissor changes of the current batches into `cmd`.
+    ///
+    /// Nested clips intersect in the command buffer's scissor stack.
+    fn record_scissors(&self, cmd: &mut CommandBuffer) -> Result<(), GraphicsError> {
+        for batch in &self.batches {
+            match batch {
+                RenderBatch::PushScissor(rect) => cmd.push_scissor(ScissorRect::covering(
+                    rect.x,
+                    rect.y,
+                    rect.width,
+                    rect.height,
+                ))?,
+                RenderBatch::PopScissor => cmd.pop_scissor()?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Get statistics about the last render.
     pub fn get_stats(&self) -> RenderStats {
         let mut stats = RenderStats::default();
@@ -541,6 +653,10 @@ impl BrowserRenderer {
                 RenderBatch::Gradient { .. } => stats.gradient_count += 1,
                 RenderBatch::RoundedRect { .. } => stats.rounded_rect_count += 1,
                 RenderBatch::Shadow { .. } => stats.shadow_count += 1,
+                RenderBatch::PushScissor(_) | RenderBatch::PopScissor => stats.scissor_count += 1,
+                RenderBatch::PushClipMask { .. } => stats.clip_mask_count += 1,
+                RenderBatch::PushLayer => stats.layer_count += 1,
+                RenderBatch::PopClipMask | RenderBatch::PopLayer { .. } => {}
             }
         }
 
@@ -608,6 +724,25 @@ impl RenderRect {
         self.y + self.height
     }
 
+    /// Move by the given offsets.
+    pub fn offset(&self, dx: f32, dy: f32) -> RenderRect {
+        RenderRect {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..*self
+        }
+    }
+
+    /// Grow by `amount` on every side (shrink if negative).
+    pub fn inflate(&self, amount: f32) -> RenderRect {
+        RenderRect {
+            x: self.x - amount,
+            y: self.y - amount,
+            width: (self.width + 2.0 * amount).max(0.0),
+            height: (self.height + 2.0 * amount).max(0.0),
+        }
+    }
+
     /// Intersect with another rect.
     pub fn intersect(&self, other: &RenderRect) -> Option<RenderRect> {
         let x = self.x.max(other.x);
@@ -660,23 +795,55 @@ impl Transform2D {
         }
     }
 
-    /// Apply transform to a rect.
-    pub fn apply_to_rect(&self, rect: RenderRect) -> RenderRect {
-        let [a, b, c, d, e, f] = self.matrix;
+    /// The transform that applies `other` first, then `self`.
+    pub fn multiply(&self, other: &Transform2D) -> Self {
+        let [a1, b1, c1, d1, e1, f1] = self.matrix;
+        let [a2, b2, c2, d2, e2, f2] = other.matrix;
+        Self {
+            matrix: [
+                a1 * a2 + c1 * b2,
+                b1 * a2 + d1 * b2,
+                a1 * c2 + c1 * d2,
+                b1 * c2 + d1 * d2,
+                a1 * e2 + c1 * f2 + e1,
+                b1 * e2 + d1 * f2 + f1,
+            ],
+        }
+    }
 
-        // Transform top-left corner
-        let x = a * rect.x + c * rect.y + e;
-        let y = b * rect.x + d * rect.y + f;
+    /// The factor lengths are scaled by, on average over directions.
+    pub fn scale_factor(&self) -> f32 {
+        let [a, b, c, d, _, _] = self.matrix;
+        libm::sqrtf((a * d - b * c).abs())
+    }
 
-        // Scale dimensions (simplified, assumes no rotation)
-        let width = rect.width * a.abs();
-        let height = rect.height * d.abs();
+    /// Apply transform to a rect. Rotated and skewed rects become their
+    /// axis-aligned bounding box.
+    pub fn apply_to_rect(&self, rect: RenderRect) -> RenderRect {
+        let [a, b, c, d, e, f] = self.matrix;
+        let corners = [
+            (rect.x, rect.y),
+            (rect.x + rect.width, rect.y),
+            (rect.x, rect.y + rect.height),
+            (rect.x + rect.width, rect.y + rect.height),
+        ];
+
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for (x, y) in corners {
+            let tx = a * x + c * y + e;
+            let ty = b * x + d * y + f;
+            min_x = min_x.min(tx);
+            min_y = min_y.min(ty);
+            max_x = max_x.max(tx);
+            max_y = max_y.max(ty);
+        }
 
         RenderRect {
-            x,
-            y,
-            width,
-            height,
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
         }
     }
 }
@@ -709,11 +876,7 @@ pub enum RenderBatch {
     },
 
     /// Draw an image.
-    Image {
-        image_id: u64,
-        rect: RenderRect,
-        opacity: f32,
-    },
+    Image { image_id: u64, rect: RenderRect },
 
     /// Draw a gradient.
     Gradient {
@@ -730,16 +893,39 @@ pub enum RenderBatch {
         radii: [f32; 4], // top-left, top-right, bottom-right, bottom-left
     },
 
-    /// Draw a box shadow.
+    /// Draw a box shadow: the shape of `rect` with `radii`, blurred.
     Shadow {
         rect: RenderRect,
         color: RenderColor,
+        radii: [f32; 4],
         blur_radius: f32,
         spread_radius: f32,
         offset_x: f32,
         offset_y: f32,
         inset: bool,
     },
+
+    /// Constrain subsequent batches to a clip rect (intersected with any
+    /// enclosing one).
+    PushScissor(RenderRect),
+
+    /// Restore the scissor in effect before the matching `PushScissor`.
+    PopScissor,
+
+    /// Also mask subsequent batches to the rounded corners of a rect.
+    PushClipMask {
+        rect: RenderRect,
+        radii: [f32; 4], // top-left, top-right, bottom-right, bottom-left
+    },
+
+    /// Remove the mask of the matching `PushClipMask`.
+    PopClipMask,
+
+    /// Draw subsequent batches into a new offscreen layer.
+    PushLayer,
+
+    /// Composite the layer of the matching `PushLayer` onto the one below.
+    PopLayer { opacity: f32 },
 }
 
 /// Render statistics.
@@ -753,6 +939,9 @@ pub struct RenderStats {
     pub gradient_count: usize,
     pub rounded_rect_count: usize,
     pub shadow_count: usize,
+    pub scissor_count: usize,
+    pub clip_mask_count: usize,
+    pub layer_count: usize,
 }
 
 impl RenderStats {
@@ -763,6 +952,7 @@ impl RenderStats {
             + self.gradient_count
             + self.rounded_rect_count
             + self.shadow_count
+            + self.layer_count
     }
 }
 
@@ -802,6 +992,144 @@ mod tests {
         assert_eq!(arr[3], 1.0);
     }
 
+    #[test]
+    fn test_clip_commands_push_scissors() {
+        let mut renderer = BrowserRenderer::new(800, 600);
+        let mut list = DisplayList::new();
+        list.push(DisplayCommand::PushClip {
+            rect: Rect::new(10.0, 10.0, 200.0, 100.0),
+        });
+        list.push(DisplayCommand::PushClip {
+            rect: Rect::new(100.0, 50.0, 300.0, 300.0),
+        });
+        list.push(DisplayCommand::PopClip);
+        list.push(DisplayCommand::PopClip);
+        renderer.render(&list).unwrap();
+        assert_eq!(renderer.get_stats().scissor_count, 4);
+
+        let mut cmd = CommandBuffer::new().unwrap();
+        renderer.record_scissors(&mut cmd).unwrap();
+        assert_eq!(cmd.scissor(), None);
+
+        // Stop before the pops: the inner clip intersects the outer one.
+        renderer.batches.truncate(3);
+        let mut cmd = CommandBuffer::new().unwrap();
+        renderer.record_scissors(&mut cmd).unwrap();
+        assert_eq!(cmd.scissor(), Some(ScissorRect::new(100, 50, 110, 60)));
+    }
+
+    #[test]
+    fn test_nested_transforms_compose() {
+        let mut renderer = BrowserRenderer::new(800, 600);
+        let mut list = DisplayList::new();
+        list.push(DisplayCommand::PushTransform {
+            matrix: [1.0, 0.0, 0.0, 1.0, 100.0, 0.0],
+        });
+        // Scale about (20, 20), inside the translation
+        list.push(DisplayCommand::PushTransform {
+            matrix: [2.0, 0.0, 0.0, 2.0, -20.0, -20.0],
+        });
+        list.push(DisplayCommand::SolidRect {
+            color: Color::black(),
+            rect: Rect::new(10.0, 10.0, 20.0, 20.0),
+        });
+        list.push(DisplayCommand::PopTransform);
+        list.push(DisplayCommand::PopTransform);
+        renderer.render(&list).unwrap();
+
+        match &renderer.batches[1] {
+            RenderBatch::Rect { rect, .. } => {
+                assert_eq!((rect.x, rect.y), (100.0, 0.0));
+                assert_eq!((rect.width, rect.height), (40.0, 40.0));
+            }
+            other => panic!("unexpected batch {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_opacity_groups_use_layers() {
+        let mut renderer = BrowserRenderer::new(800, 600);
+        let mut list = DisplayList::new();
+        list.push(DisplayCommand::PushOpacity { opacity: 0.5 });
+        list.push(DisplayCommand::SolidRect {
+            color: Color::black(),
+            rect: Rect::new(0.0, 0.0, 20.0, 20.0),
+        });
+        list.push(DisplayCommand::SolidRect {
+            color: Color::white(),
+            rect: Rect::new(10.0, 10.0, 20.0, 20.0),
+        });
+        list.push(DisplayCommand::PopOpacity);
+        // An opaque group needs no layer
+        list.push(DisplayCommand::PushOpacity { opacity: 1.0 });
+        list.push(DisplayCommand::PopOpacity);
+        renderer.render(&list).unwrap();
+
+        assert!(matches!(renderer.batches[1], RenderBatch::PushLayer));
+        // Primitives inside the layer keep their own alpha
+        assert!(matches!(renderer.batches[2], RenderBatch::Rect { color, .. } if color.a == 255));
+        assert!(matches!(
+            renderer.batches[4],
+            RenderBatch::PopLayer { opacity } if opacity == 0.5
+        ));
+        assert_eq!(renderer.batches.len(), 5);
+        assert_eq!(renderer.get_stats().layer_count, 1);
+    }
+
+    #[test]
+    fn test_rounded_clip_masks_and_culls_shadows() {
+        let mut renderer = BrowserRenderer::new(800, 600);
+        let mut list = DisplayList::new();
+        list.push(DisplayCommand::PushRoundedClip {
+            rect: Rect::new(0.0, 0.0, 100.0, 100.0),
+            radii: BorderRadii::uniform(10.0),
+        });
+        let shadow = |x: f32| DisplayCommand::BoxShadow {
+            color: Color::black(),
+            rect: Rect::new(x, 0.0, 50.0, 50.0),
+            radii: BorderRadii::uniform(4.0),
+            blur_radius: 8.0,
+            spread_radius: 2.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            inset: false,
+        };
+        // Only its blur reaches into the clip
+        list.push(shadow(105.0));
+        // Entirely outside the clip
+        list.push(shadow(200.0));
+        list.push(DisplayCommand::PopClip);
+        renderer.render(&list).unwrap();
+
+        let stats = renderer.get_stats();
+        assert_eq!(stats.clip_mask_count, 1);
+        assert_eq!(stats.scissor_count, 2);
+        assert_eq!(stats.shadow_count, 1);
+        match &renderer.batches[3] {
+            RenderBatch::Shadow { rect, radii, .. } => {
+                assert_eq!((rect.x, rect.width), (105.0, 50.0));
+                assert_eq!(*radii, [4.0; 4]);
+            }
+            other => panic!("unexpected batch {:?}", other),
+        }
+        assert!(matches!(renderer.batches[4], RenderBatch::PopClipMask));
+        assert!(matches!(renderer.batches[5], RenderBatch::PopScissor));
+    }
+
+    #[test]
+    fn test_rotated_rect_bounds() {
+        // A quarter turn about the origin
+        let rotate = Transform2D::from_matrix([0.0, 1.0, -1.0, 0.0, 0.0, 0.0]);
+        let rect = rotate.apply_to_rect(RenderRect {
+            x: 0.0,
+            y: 0.0,
+            width: 30.0,
+            height: 10.0,
+        });
+        assert_eq!((rect.x, rect.y), (-10.0, 0.0));
+        assert_eq!((rect.width, rect.height), (10.0, 30.0));
+    }
+
     #[test]
     fn test_browser_renderer_creation() {
         let renderer = BrowserRenderer::new(800, 600);
@@ -19,6 +19,10 @@ pub struct CommandBuffer {
     commands: Vec<Command>,
     /// Whether recording is complete.
     finished: bool,
+    /// Scissor constraining subsequent draws (`None` = whole target).
+    scissor: Option<ScissorRect>,
+    /// Scissors saved by `push_scissor`.
+    scissor_stack: Vec<Option<ScissorRect>>,
 }
 
 impl CommandBuffer {
@@ -35,6 +39,8 @@ impl CommandBuffer {
             handle,
             commands: Vec::new(),
             finished: false,
+            scissor: None,
+            scissor_stack: Vec::new(),
         })
     }
 
@@ -76,23 +82,52 @@ impl CommandBuffer {
     }
 
     /// Set the scissor rectangle.
-    pub fn set_scissor(
-        &mut self,
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    ) -> Result<(), GraphicsError> {
+    ///
+    /// Subsequent draws only touch pixels inside `rect`.
+    pub fn set_scissor(&mut self, rect: ScissorRect) -> Result<(), GraphicsError> {
         self.check_recording()?;
-        self.commands.push(Command::SetScissor {
-            x,
-            y,
-            width,
-            height,
-        });
+        self.scissor = Some(rect);
+        self.commands.push(Command::SetScissor(rect));
         Ok(())
     }
 
+    /// Remove the scissor so that draws cover the whole render target.
+    pub fn clear_scissor(&mut self) -> Result<(), GraphicsError> {
+        self.check_recording()?;
+        self.scissor = None;
+        self.commands.push(Command::ClearScissor);
+        Ok(())
+    }
+
+    /// Narrow the scissor to its intersection with `rect`, saving the
+    /// current one for `pop_scissor`.
+    pub fn push_scissor(&mut self, rect: ScissorRect) -> Result<(), GraphicsError> {
+        self.check_recording()?;
+        let clipped = match self.scissor {
+            Some(current) => current.intersect(&rect),
+            None => rect,
+        };
+        self.scissor_stack.push(self.scissor);
+        self.set_scissor(clipped)
+    }
+
+    /// Restore the scissor saved by the matching `push_scissor`.
+    pub fn pop_scissor(&mut self) -> Result<(), GraphicsError> {
+        self.check_recording()?;
+        match self.scissor_stack.pop() {
+            Some(Some(previous)) => self.set_scissor(previous),
+            Some(None) => self.clear_scissor(),
+            None => Err(GraphicsError::InvalidOperation(
+                "Scissor stack underflow".into(),
+            )),
+        }
+    }
+
+    /// Get the scissor currently constraining draws.
+    pub fn scissor(&self) -> Option<ScissorRect> {
+        self.scissor
+    }
+
     /// Bind a vertex buffer.
     pub fn bind_vertex_buffer(
         &mut self,
@@ -227,12 +262,9 @@ pub enum Command {
         height: f32,
     },
     /// Set scissor.
-    SetScissor {
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-    },
+    SetScissor(ScissorRect),
+    /// Reset the scissor to the whole render target.
+    ClearScissor,
     /// Bind vertex buffer.
     BindVertexBuffer {
         slot: u32,
@@ -270,6 +302,72 @@ pub enum Command {
     },
 }
 
+/// Scissor rectangle in framebuffer pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScissorRect {
+    /// Left edge.
+    pub x: u32,
+    /// Top edge.
+    pub y: u32,
+    /// Width.
+    pub width: u32,
+    /// Height.
+    pub height: u32,
+}
+
+impl ScissorRect {
+    /// Create a scissor rectangle.
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        ScissorRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Smallest pixel rectangle covering a floating-point rect; the
+    /// parts left of or above the origin are dropped.
+    pub fn covering(x: f32, y: f32, width: f32, height: f32) -> Self {
+        let left = libm::floorf(x).max(0.0);
+        let top = libm::floorf(y).max(0.0);
+        let right = libm::ceilf(x + width).max(left);
+        let bottom = libm::ceilf(y + height).max(top);
+        ScissorRect::new(
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        )
+    }
+
+    /// Right edge (exclusive).
+    pub fn right(&self) -> u32 {
+        self.x.saturating_add(self.width)
+    }
+
+    /// Bottom edge (exclusive).
+    pub fn bottom(&self) -> u32 {
+        self.y.saturating_add(self.height)
+    }
+
+    /// Check if the rectangle covers no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Intersect with another rectangle.
+    ///
+    /// Disjoint rectangles give an empty scissor, which discards all draws.
+    pub fn intersect(&self, other: &ScissorRect) -> ScissorRect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        ScissorRect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+}
+
 /// Render pass descriptor.
 #[derive(Debug, Clone)]
 pub struct RenderPassDescriptor {
@@ -353,3 +451,35 @@ pub fn submit(command_buffers: &[&CommandBuffer]) -> Result<(), GraphicsError> {
     // Actual submission would go here
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_scissors_intersect() {
+        let mut cmd = CommandBuffer::new().unwrap();
+        cmd.push_scissor(ScissorRect::new(0, 0, 100, 100)).unwrap();
+        cmd.push_scissor(ScissorRect::new(50, 20, 100, 100))
+            .unwrap();
+        assert_eq!(cmd.scissor(), Some(ScissorRect::new(50, 20, 50, 80)));
+
+        cmd.push_scissor(ScissorRect::new(200, 200, 10, 10))
+            .unwrap();
+        assert!(cmd.scissor().unwrap().is_empty());
+
+        cmd.pop_scissor().unwrap();
+        cmd.pop_scissor().unwrap();
+        assert_eq!(cmd.scissor(), Some(ScissorRect::new(0, 0, 100, 100)));
+        cmd.pop_scissor().unwrap();
+        assert_eq!(cmd.scissor(), None);
+        assert!(matches!(cmd.commands().last(), Some(Command::ClearScissor)));
+        assert!(cmd.pop_scissor().is_err());
+    }
+
+    #[test]
+    fn test_scissor_covering() {
+        let rect = ScissorRect::covering(10.5, -4.0, 20.0, 10.2);
+        assert_eq!(rect, ScissorRect::new(10, 0, 21, 7));
+    }
+}
@@ -3,13 +3,19 @@
 //! This module implements the window compositor that manages
 //! surfaces from multiple applications and composites them
 //! into the final display output.
+//!
+//! The compositor drives one or more outputs (e.g. virtio-gpu scanouts).
+//! Outputs are arranged on a single desktop coordinate space; each has
+//! its own `DisplayMode` and framebuffer, and a surface is composited
+//! onto every output it overlaps, so windows may span several heads.
 
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
 use spin::Mutex;
 
 use crate::surface::{Surface, SurfaceId};
-use crate::{DisplayMode, GraphicsError, PixelFormat};
+use crate::{DisplayId, DisplayInfo, DisplayMode, GraphicsError, PixelFormat};
 
 /// Global compositor instance.
 static COMPOSITOR: Mutex<Option<Compositor>> = Mutex::new(None);
@@ -21,12 +27,12 @@ pub fn init() -> Result<(), GraphicsError> {
     Ok(())
 }
 
-/// Get the current display mode.
+/// Get the current display mode of the primary output.
 pub fn get_display_mode() -> Option<DisplayMode> {
-    COMPOSITOR.lock().as_ref().map(|c| c.display_mode)
+    COMPOSITOR.lock().as_ref().map(|c| c.display_mode())
 }
 
-/// Set the display mode.
+/// Set the display mode of the primary output.
 pub fn set_display_mode(mode: DisplayMode) -> Result<(), GraphicsError> {
     if let Some(ref mut comp) = *COMPOSITOR.lock() {
         comp.set_display_mode(mode)?;
@@ -34,10 +40,70 @@ pub fn set_display_mode(mode: DisplayMode) -> Result<(), GraphicsError> {
     Ok(())
 }
 
+/// Get information about all outputs.
+pub fn enumerate_displays() -> Vec<DisplayInfo> {
+    COMPOSITOR
+        .lock()
+        .as_ref()
+        .map(|c| c.enumerate_displays())
+        .unwrap_or_default()
+}
+
+/// Set the display mode of a specific output.
+pub fn set_display_mode_for(display: DisplayId, mode: DisplayMode) -> Result<(), GraphicsError> {
+    match *COMPOSITOR.lock() {
+        Some(ref mut comp) => comp.set_output_mode(display, mode),
+        None => Err(GraphicsError::InvalidOperation(
+            "compositor not initialized".into(),
+        )),
+    }
+}
+
+/// Register an additional output (e.g. a second virtio-gpu scanout).
+pub fn add_display(name: &str, mode: DisplayMode) -> Result<DisplayId, GraphicsError> {
+    match *COMPOSITOR.lock() {
+        Some(ref mut comp) => Ok(comp.add_output(name, mode)),
+        None => Err(GraphicsError::InvalidOperation(
+            "compositor not initialized".into(),
+        )),
+    }
+}
+
+/// A display output driven by the compositor.
+struct Output {
+    /// Output identifier.
+    id: DisplayId,
+    /// Connector/scanout name.
+    name: String,
+    /// Current display mode.
+    mode: DisplayMode,
+    /// X position on the desktop.
+    x: i32,
+    /// Y position on the desktop.
+    y: i32,
+    /// Framebuffer handle scanned out by this output.
+    framebuffer: Option<u64>,
+}
+
+impl Output {
+    /// The area this output covers in desktop coordinates.
+    fn rect(&self) -> DamageRect {
+        DamageRect {
+            x: self.x,
+            y: self.y,
+            width: self.mode.width,
+            height: self.mode.height,
+        }
+    }
+}
+
 /// The window compositor.
 pub struct Compositor {
-    /// Current display mode.
-    display_mode: DisplayMode,
+    /// Outputs in enumeration order; the first one is primary.
+    outputs: Vec<Output>,
+
+    /// Next output ID.
+    next_output_id: u32,
 
     /// All surfaces managed by the compositor.
     surfaces: BTreeMap<SurfaceId, SurfaceState>,
@@ -61,13 +127,23 @@ pub struct Compositor {
 impl Compositor {
     /// Create a new compositor.
     pub fn new() -> Result<Self, GraphicsError> {
-        Ok(Compositor {
-            display_mode: DisplayMode {
+        let primary = Output {
+            id: DisplayId(0),
+            name: "virtio-gpu-0".into(),
+            mode: DisplayMode {
                 width: 1920,
                 height: 1080,
                 refresh_rate: 60,
                 format: PixelFormat::Bgra8Srgb,
             },
+            x: 0,
+            y: 0,
+            framebuffer: None,
+        };
+
+        Ok(Compositor {
+            outputs: alloc::vec![primary],
+            next_output_id: 1,
             surfaces: BTreeMap::new(),
             stacking_order: Vec::new(),
             focused_surface: None,
@@ -77,14 +153,213 @@ impl Compositor {
         })
     }
 
-    /// Set the display mode.
+    /// Get the display mode of the primary output.
+    pub fn display_mode(&self) -> DisplayMode {
+        self.outputs[0].mode
+    }
+
+    /// Set the display mode of the primary output.
     pub fn set_display_mode(&mut self, mode: DisplayMode) -> Result<(), GraphicsError> {
-        self.display_mode = mode;
-        // Resize framebuffer
+        let primary = self.outputs[0].id;
+        self.set_output_mode(primary, mode)
+    }
+
+    /// Describe all outputs.
+    pub fn enumerate_displays(&self) -> Vec<DisplayInfo> {
+        self.outputs
+            .iter()
+            .enumerate()
+            .map(|(i, o)| DisplayInfo {
+                id: o.id,
+                name: o.name.clone(),
+                mode: o.mode,
+                x: o.x,
+                y: o.y,
+                primary: i == 0,
+            })
+            .collect()
+    }
+
+    /// Add an output, placed to the right of the existing desktop.
+    pub fn add_output(&mut self, name: &str, mode: DisplayMode) -> DisplayId {
+        let id = DisplayId(self.next_output_id);
+        self.next_output_id += 1;
+
+        let x = self
+            .outputs
+            .iter()
+            .map(|o| o.x + o.mode.width as i32)
+            .max()
+            .unwrap_or(0);
+
+        self.outputs.push(Output {
+            id,
+            name: name.into(),
+            mode,
+            x,
+            y: 0,
+            framebuffer: None,
+        });
+        self.damage_output(id);
+        id
+    }
+
+    /// Remove an output.
+    ///
+    /// Surfaces assigned to it fall back to the primary output. The last
+    /// remaining output cannot be removed.
+    pub fn remove_output(&mut self, id: DisplayId) -> Result<(), GraphicsError> {
+        let index = self.output_index(id)?;
+        if self.outputs.len() == 1 {
+            return Err(GraphicsError::InvalidOperation(
+                "cannot remove the last display".into(),
+            ));
+        }
+        self.outputs.remove(index);
+
+        let orphaned: Vec<SurfaceId> = self
+            .surfaces
+            .iter()
+            .filter(|(_, s)| s.output == Some(id))
+            .map(|(&sid, _)| sid)
+            .collect();
+        let primary = self.outputs[0].id;
+        for sid in orphaned {
+            self.assign_surface(sid, Some(primary))?;
+        }
+
+        self.damage_full_screen();
+        Ok(())
+    }
+
+    /// Set the display mode of one output.
+    pub fn set_output_mode(
+        &mut self,
+        id: DisplayId,
+        mode: DisplayMode,
+    ) -> Result<(), GraphicsError> {
+        let index = self.output_index(id)?;
+        self.outputs[index].mode = mode;
+        // Framebuffer must be reallocated for the new size.
+        self.outputs[index].framebuffer = None;
+        self.damage_output(id);
+        Ok(())
+    }
+
+    /// Move an output to a new position on the desktop.
+    pub fn set_output_position(
+        &mut self,
+        id: DisplayId,
+        x: i32,
+        y: i32,
+    ) -> Result<(), GraphicsError> {
+        let index = self.output_index(id)?;
+        self.outputs[index].x = x;
+        self.outputs[index].y = y;
         self.damage_full_screen();
         Ok(())
     }
 
+    /// Attach the framebuffer that an output scans out from.
+    pub fn set_output_framebuffer(
+        &mut self,
+        id: DisplayId,
+        framebuffer: u64,
+    ) -> Result<(), GraphicsError> {
+        let index = self.output_index(id)?;
+        self.outputs[index].framebuffer = Some(framebuffer);
+        self.damage_output(id);
+        Ok(())
+    }
+
+    /// Bounding box of all outputs in desktop coordinates, as
+    /// `(x, y, width, height)`.
+    pub fn desktop_bounds(&self) -> (i32, i32, u32, u32) {
+        let min_x = self.outputs.iter().map(|o| o.x).min().unwrap_or(0);
+        let min_y = self.outputs.iter().map(|o| o.y).min().unwrap_or(0);
+        let max_x = self
+            .outputs
+            .iter()
+            .map(|o| o.x + o.mode.width as i32)
+            .max()
+            .unwrap_or(0);
+        let max_y = self
+            .outputs
+            .iter()
+            .map(|o| o.y + o.mode.height as i32)
+            .max()
+            .unwrap_or(0);
+        (min_x, min_y, (max_x - min_x) as u32, (max_y - min_y) as u32)
+    }
+
+    /// Find the output containing a desktop point.
+    pub fn output_at(&self, x: i32, y: i32) -> Option<DisplayId> {
+        self.outputs
+            .iter()
+            .find(|o| o.rect().contains(x, y))
+            .map(|o| o.id)
+    }
+
+    /// Assign a surface to an output, or unpin it with `None`.
+    ///
+    /// A surface that lies entirely outside its new output is moved to
+    /// the output's top-left corner, keeping its offset otherwise.
+    pub fn assign_surface(
+        &mut self,
+        id: SurfaceId,
+        output: Option<DisplayId>,
+    ) -> Result<(), GraphicsError> {
+        let target = match output {
+            Some(display) => Some(self.outputs[self.output_index(display)?].rect()),
+            None => None,
+        };
+
+        let (x, y, rect) = match self.surfaces.get_mut(&id) {
+            Some(state) => {
+                state.output = output;
+                (state.x, state.y, state.rect())
+            }
+            None => return Ok(()),
+        };
+
+        if let Some(target) = target {
+            if !target.intersects(&rect) {
+                self.move_surface(id, target.x, target.y)?;
+            } else {
+                self.move_surface(id, x, y)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the output a surface is pinned to, if any.
+    pub fn surface_output(&self, id: SurfaceId) -> Option<DisplayId> {
+        self.surfaces.get(&id).and_then(|s| s.output)
+    }
+
+    /// Get every output a surface currently overlaps.
+    pub fn surface_outputs(&self, id: SurfaceId) -> Vec<DisplayId> {
+        match self.surfaces.get(&id) {
+            Some(state) => {
+                let rect = state.rect();
+                self.outputs
+                    .iter()
+                    .filter(|o| o.rect().intersects(&rect))
+                    .map(|o| o.id)
+                    .collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn output_index(&self, id: DisplayId) -> Result<usize, GraphicsError> {
+        self.outputs
+            .iter()
+            .position(|o| o.id == id)
+            .ok_or_else(|| GraphicsError::InvalidOperation("unknown display".into()))
+    }
+
     /// Create a new surface.
     pub fn create_surface(&mut self, width: u32, height: u32) -> Result<SurfaceId, GraphicsError> {
         let id = SurfaceId(self.next_surface_id);
@@ -98,6 +373,7 @@ impl Compositor {
             visible: true,
             opacity: 1.0,
             buffer: None,
+            output: None,
         };
 
         self.surfaces.insert(id, state);
@@ -265,12 +541,26 @@ impl Compositor {
             return Ok(());
         }
 
-        // Composite all visible surfaces
-        for &id in &self.stacking_order {
-            if let Some(state) = self.surfaces.get(&id) {
-                if state.visible && state.buffer.is_some() {
-                    // Render surface to framebuffer
-                    // (actual implementation would use Vello)
+        // Composite every output from the surfaces that overlap it
+        for output in &self.outputs {
+            let output_rect = output.rect();
+            if !self
+                .damage_regions
+                .iter()
+                .any(|d| d.intersects(&output_rect))
+            {
+                continue;
+            }
+            for &id in &self.stacking_order {
+                if let Some(state) = self.surfaces.get(&id) {
+                    if state.visible
+                        && state.buffer.is_some()
+                        && state.rect().intersects(&output_rect)
+                    {
+                        // Render the overlapping part of the surface into
+                        // this output's framebuffer, offset by the output
+                        // origin (actual implementation would use Vello)
+                    }
                 }
             }
         }
@@ -288,17 +578,20 @@ impl Compositor {
         self.frame_pending = true;
     }
 
-    /// Damage the full screen.
+    /// Damage every output.
     fn damage_full_screen(&mut self) {
         self.damage_regions.clear();
-        self.damage_regions.push(DamageRect {
-            x: 0,
-            y: 0,
-            width: self.display_mode.width,
-            height: self.display_mode.height,
-        });
+        let rects: Vec<DamageRect> = self.outputs.iter().map(|o| o.rect()).collect();
+        self.damage_regions.extend(rects);
         self.frame_pending = true;
     }
+
+    /// Damage a single output.
+    fn damage_output(&mut self, id: DisplayId) {
+        if let Some(rect) = self.outputs.iter().find(|o| o.id == id).map(|o| o.rect()) {
+            self.add_damage(rect);
+        }
+    }
 }
 
 /// Surface state tracked by the compositor.
@@ -317,6 +610,20 @@ struct SurfaceState {
     opacity: f32,
     /// Current buffer handle.
     buffer: Option<u64>,
+    /// Output the surface is pinned to (`None` = free to span outputs).
+    output: Option<DisplayId>,
+}
+
+impl SurfaceState {
+    /// The area this surface covers in desktop coordinates.
+    fn rect(&self) -> DamageRect {
+        DamageRect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
 }
 
 /// Damage rectangle.
@@ -327,3 +634,82 @@ struct DamageRect {
     width: u32,
     height: u32,
 }
+
+impl DamageRect {
+    /// Check whether two rectangles overlap.
+    fn intersects(&self, other: &DamageRect) -> bool {
+        self.x < other.x + other.width as i32
+            && other.x < self.x + self.width as i32
+            && self.y < other.y + other.height as i32
+            && other.y < self.y + self.height as i32
+    }
+
+    /// Check whether a point lies inside the rectangle.
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && x < self.x + self.width as i32
+            && y >= self.y
+            && y < self.y + self.height as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(width: u32, height: u32) -> DisplayMode {
+        DisplayMode {
+            width,
+            height,
+            refresh_rate: 60,
+            format: PixelFormat::Bgra8Srgb,
+        }
+    }
+
+    #[test]
+    fn test_outputs_are_laid_out_side_by_side() {
+        let mut comp = Compositor::new().unwrap();
+        let second = comp.add_output("virtio-gpu-1", mode(1280, 1024));
+
+        let displays = comp.enumerate_displays();
+        assert_eq!(displays.len(), 2);
+        assert!(displays[0].primary);
+        assert_eq!(displays[1].id, second);
+        assert_eq!(displays[1].x, 1920);
+        assert_eq!(comp.desktop_bounds(), (0, 0, 3200, 1080));
+        assert_eq!(comp.output_at(2000, 10), Some(second));
+    }
+
+    #[test]
+    fn test_per_display_mode() {
+        let mut comp = Compositor::new().unwrap();
+        let second = comp.add_output("virtio-gpu-1", mode(1280, 1024));
+        comp.set_output_mode(second, mode(800, 600)).unwrap();
+
+        assert_eq!(comp.display_mode().width, 1920);
+        let displays = comp.enumerate_displays();
+        assert_eq!(displays[1].mode.width, 800);
+        assert!(comp.set_output_mode(DisplayId(99), mode(640, 480)).is_err());
+    }
+
+    #[test]
+    fn test_surface_spans_and_assignment() {
+        let mut comp = Compositor::new().unwrap();
+        let second = comp.add_output("virtio-gpu-1", mode(1280, 1024));
+        let surface = comp.create_surface(400, 300).unwrap();
+
+        comp.move_surface(surface, 1800, 100).unwrap();
+        assert_eq!(
+            comp.surface_outputs(surface),
+            alloc::vec![DisplayId(0), second]
+        );
+
+        comp.move_surface(surface, 0, 0).unwrap();
+        comp.assign_surface(surface, Some(second)).unwrap();
+        assert_eq!(comp.surface_output(surface), Some(second));
+        assert_eq!(comp.surface_outputs(surface), alloc::vec![second]);
+
+        comp.remove_output(second).unwrap();
+        assert_eq!(comp.surface_output(surface), Some(DisplayId(0)));
+    }
+}
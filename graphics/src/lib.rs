@@ -73,6 +73,30 @@ pub struct DisplayMode {
     pub format: PixelFormat,
 }
 
+/// Display (output) identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DisplayId(pub u32);
+
+/// Information about a connected display.
+///
+/// Displays are laid out side by side on one desktop; `x`/`y` give the
+/// position of the display's top-left corner in desktop coordinates.
+#[derive(Debug, Clone)]
+pub struct DisplayInfo {
+    /// Display identifier.
+    pub id: DisplayId,
+    /// Connector/scanout name (e.g. "virtio-gpu-0").
+    pub name: String,
+    /// Current display mode.
+    pub mode: DisplayMode,
+    /// X position on the desktop.
+    pub x: i32,
+    /// Y position on the desktop.
+    pub y: i32,
+    /// Whether this is the primary display.
+    pub primary: bool,
+}
+
 /// Pixel formats.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PixelFormat {
@@ -155,7 +179,17 @@ pub fn get_display_mode() -> Option<DisplayMode> {
     compositor::get_display_mode()
 }
 
-/// Set the display mode.
+/// Set the primary display mode.
 pub fn set_display_mode(mode: DisplayMode) -> Result<(), GraphicsError> {
     compositor::set_display_mode(mode)
 }
+
+/// Get information about all connected displays.
+pub fn enumerate_displays() -> Vec<DisplayInfo> {
+    compositor::enumerate_displays()
+}
+
+/// Set the mode of a specific display.
+pub fn set_display_mode_for(display: DisplayId, mode: DisplayMode) -> Result<(), GraphicsError> {
+    compositor::set_display_mode_for(display, mode)
+}
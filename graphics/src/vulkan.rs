@@ -8,6 +8,7 @@ use alloc::vec;
 use alloc::vec::Vec;
 use spin::Mutex;
 
+use crate::command::{Command, ScissorRect};
 use crate::{GpuInfo, GpuType, GraphicsError};
 
 /// Global Vulkan context.
@@ -204,3 +205,45 @@ bitflags::bitflags! {
         const SPARSE_BINDING = 0b1000;
     }
 }
+
+/// Rectangle in `VkRect2D` layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect2D {
+    /// Offset of the top-left corner.
+    pub offset: [i32; 2],
+    /// Width and height.
+    pub extent: [u32; 2],
+}
+
+impl Rect2D {
+    /// Rectangle covering a whole framebuffer of `extent`.
+    pub fn full(extent: [u32; 2]) -> Self {
+        Rect2D {
+            offset: [0, 0],
+            extent,
+        }
+    }
+
+    /// Convert a scissor, clamped to a framebuffer of `extent` as
+    /// `vkCmdSetScissor` requires.
+    pub fn from_scissor(rect: ScissorRect, extent: [u32; 2]) -> Self {
+        let clamped = rect.intersect(&ScissorRect::new(0, 0, extent[0], extent[1]));
+        Rect2D {
+            offset: [clamped.x as i32, clamped.y as i32],
+            extent: [clamped.width, clamped.height],
+        }
+    }
+}
+
+/// Dynamic scissor state a recorded command sets, if any.
+///
+/// Pipelines are created with `VK_DYNAMIC_STATE_SCISSOR`, so every
+/// `SetScissor`/`ClearScissor` becomes a `vkCmdSetScissor` call; clearing
+/// resets the scissor to the full framebuffer.
+pub fn dynamic_scissor(command: &Command, extent: [u32; 2]) -> Option<Rect2D> {
+    match command {
+        Command::SetScissor(rect) => Some(Rect2D::from_scissor(*rect, extent)),
+        Command::ClearScissor => Some(Rect2D::full(extent)),
+        _ => None,
+    }
+}
@@ -218,6 +218,8 @@ pub enum DumpError {
     NoSpace,
     /// Write failed
     WriteFailed,
+    /// No dump file has been reserved yet
+    NotArmed,
 }
 
 /// Dump configuration
@@ -4,18 +4,25 @@
 
 mod dump;
 mod handler;
+mod persist;
 mod reporter;
 mod symbols;
 
 pub use dump::*;
 pub use handler::*;
+pub use persist::*;
 pub use reporter::*;
 pub use symbols::*;
 
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+use core::sync::atomic::{AtomicU64, Ordering};
 use spin::Mutex;
 
+use crate::percpu::MAX_CPUS;
+
 /// Crash type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CrashType {
@@ -127,9 +134,70 @@ pub struct CpuState {
 
 impl CpuState {
     /// Capture current CPU state
+    ///
+    /// General-purpose registers reflect the state inside this function,
+    /// so `rbp` is the caller's frame and can seed a backtrace.
+    #[inline(always)]
     pub fn capture() -> Self {
-        // Would use inline assembly
-        Self::default()
+        let mut state = Self::default();
+
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: only copies register values into locals; no memory is
+        // accessed and no flags or registers are modified.
+        unsafe {
+            core::arch::asm!(
+                "mov {rax}, rax",
+                "mov {rbx}, rbx",
+                "mov {rcx}, rcx",
+                "mov {rdx}, rdx",
+                "mov {rsi}, rsi",
+                "mov {rdi}, rdi",
+                "mov {rbp}, rbp",
+                "mov {rsp}, rsp",
+                rax = out(reg) state.rax,
+                rbx = out(reg) state.rbx,
+                rcx = out(reg) state.rcx,
+                rdx = out(reg) state.rdx,
+                rsi = out(reg) state.rsi,
+                rdi = out(reg) state.rdi,
+                rbp = out(reg) state.rbp,
+                rsp = out(reg) state.rsp,
+                options(nomem, nostack, preserves_flags),
+            );
+            core::arch::asm!(
+                "mov {r8}, r8",
+                "mov {r9}, r9",
+                "mov {r10}, r10",
+                "mov {r11}, r11",
+                "mov {r12}, r12",
+                "mov {r13}, r13",
+                "mov {r14}, r14",
+                "mov {r15}, r15",
+                "lea {rip}, [rip]",
+                r8 = out(reg) state.r8,
+                r9 = out(reg) state.r9,
+                r10 = out(reg) state.r10,
+                r11 = out(reg) state.r11,
+                r12 = out(reg) state.r12,
+                r13 = out(reg) state.r13,
+                r14 = out(reg) state.r14,
+                r15 = out(reg) state.r15,
+                rip = out(reg) state.rip,
+                options(nomem, nostack, preserves_flags),
+            );
+            core::arch::asm!(
+                "pushfq",
+                "pop {rflags}",
+                "mov {cr2}, cr2",
+                "mov {cr3}, cr3",
+                rflags = out(reg) state.rflags,
+                cr2 = out(reg) state.cr2,
+                cr3 = out(reg) state.cr3,
+                options(nomem, preserves_flags),
+            );
+        }
+
+        state
     }
 }
 
@@ -205,6 +273,10 @@ pub struct CrashInfo {
     pub thread_id: Option<u64>,
     /// CPU number
     pub cpu_number: u32,
+    /// Source location (`file:line:column`), for panics
+    pub location: Option<String>,
+    /// Console output leading up to the crash
+    pub recent_log: Vec<String>,
 }
 
 impl CrashInfo {
@@ -220,6 +292,8 @@ impl CrashInfo {
             process_id: None,
             thread_id: None,
             cpu_number: 0,
+            location: None,
+            recent_log: Vec::new(),
         }
     }
 
@@ -235,6 +309,9 @@ impl CrashInfo {
         report.push_str("=== KPIO CRASH REPORT ===\n\n");
         report.push_str(&alloc::format!("Type: {}\n", self.crash_type.name()));
         report.push_str(&alloc::format!("Message: {}\n", self.message));
+        if let Some(ref location) = self.location {
+            report.push_str(&alloc::format!("Location: {}\n", location));
+        }
         report.push_str(&alloc::format!(
             "Severity: {:?}\n",
             self.crash_type.severity()
@@ -250,52 +327,211 @@ impl CrashInfo {
         }
 
         report.push_str("\n--- CPU State ---\n");
-        report.push_str(&alloc::format!("RIP: {:#018x}\n", self.cpu_state.rip));
-        report.push_str(&alloc::format!("RSP: {:#018x}\n", self.cpu_state.rsp));
-        report.push_str(&alloc::format!("RBP: {:#018x}\n", self.cpu_state.rbp));
-        report.push_str(&alloc::format!("RFLAGS: {:#018x}\n", self.cpu_state.rflags));
-
-        if self.crash_type == CrashType::PageFault {
-            report.push_str(&alloc::format!("CR2: {:#018x}\n", self.cpu_state.cr2));
-        }
+        let _ = write_cpu_state(&mut report, &self.cpu_state, self.crash_type);
 
         report.push_str("\n--- Backtrace ---\n");
         for (i, frame) in self.backtrace.iter().enumerate() {
             report.push_str(&alloc::format!("#{}: {}\n", i, frame.to_string()));
         }
 
+        if !self.recent_log.is_empty() {
+            report.push_str("\n--- Recent Log ---\n");
+            for line in &self.recent_log {
+                report.push_str(line);
+                report.push('\n');
+            }
+        }
+
         report.push_str("\n=== END CRASH REPORT ===\n");
 
         report
     }
 }
 
-/// Unwind stack to get backtrace
-fn unwind_stack(mut rbp: u64, max_frames: usize) -> Vec<StackFrame> {
-    let mut frames = Vec::new();
+/// Write the register dump section of a crash report.
+fn write_cpu_state(
+    out: &mut impl fmt::Write,
+    state: &CpuState,
+    crash_type: CrashType,
+) -> fmt::Result {
+    writeln!(out, "RIP: {:#018x}", state.rip)?;
+    writeln!(out, "RSP: {:#018x}", state.rsp)?;
+    writeln!(out, "RBP: {:#018x}", state.rbp)?;
+    writeln!(out, "RFLAGS: {:#018x}", state.rflags)?;
+    for (name, value) in [
+        ("RAX", state.rax),
+        ("RBX", state.rbx),
+        ("RCX", state.rcx),
+        ("RDX", state.rdx),
+        ("RSI", state.rsi),
+        ("RDI", state.rdi),
+        ("R8", state.r8),
+        ("R9", state.r9),
+        ("R10", state.r10),
+        ("R11", state.r11),
+        ("R12", state.r12),
+        ("R13", state.r13),
+        ("R14", state.r14),
+        ("R15", state.r15),
+        ("CR3", state.cr3),
+    ] {
+        writeln!(out, "{}: {:#018x}", name, value)?;
+    }
+
+    if crash_type == CrashType::PageFault {
+        writeln!(out, "CR2: {:#018x}", state.cr2)?;
+    }
+    Ok(())
+}
+
+/// Bounds of a kernel stack, updated without locks so that a crash can
+/// read them whatever it interrupted.
+struct StackBounds {
+    bottom: AtomicU64,
+    top: AtomicU64,
+}
 
-    for _ in 0..max_frames {
-        if rbp == 0 {
-            break;
+impl StackBounds {
+    const fn new() -> Self {
+        Self {
+            bottom: AtomicU64::new(0),
+            top: AtomicU64::new(0),
         }
+    }
 
-        // Would read from memory safely
-        // let return_addr = unsafe { *(rbp as *const u64).offset(1) };
-        // let next_rbp = unsafe { *(rbp as *const u64) };
+    fn set(&self, stack: Range<u64>) {
+        // An empty range while the two halves disagree
+        self.top.store(0, Ordering::Release);
+        self.bottom.store(stack.start, Ordering::Release);
+        self.top.store(stack.end, Ordering::Release);
+    }
 
-        // Placeholder
-        let return_addr = 0u64;
-        let next_rbp = 0u64;
+    fn get(&self) -> Range<u64> {
+        self.bottom.load(Ordering::Acquire)..self.top.load(Ordering::Acquire)
+    }
+}
+
+/// Stack the bootloader started the kernel on.
+static BOOT_STACK: StackBounds = StackBounds::new();
 
-        if return_addr == 0 {
-            break;
+/// Kernel stack of the task running on each CPU.
+static TASK_STACKS: [StackBounds; MAX_CPUS] = [const { StackBounds::new() }; MAX_CPUS];
+
+/// Record the boot stack: `top` is the stack pointer on entry and `size`
+/// the stack size the bootloader was configured with.
+pub fn set_boot_stack(top: u64, size: u64) {
+    BOOT_STACK.set(top.saturating_sub(size)..top);
+}
+
+/// Record the kernel stack of the task now running on `cpu`.
+pub fn set_task_stack(cpu: usize, stack: Range<u64>) {
+    if let Some(bounds) = TASK_STACKS.get(cpu) {
+        bounds.set(stack);
+    }
+}
+
+/// The known kernel stack holding `addr`: the running task's, the boot
+/// stack, or the double fault stack.
+fn stack_containing(addr: u64) -> Option<Range<u64>> {
+    let task = TASK_STACKS
+        .get(crate::percpu::current_cpu())
+        .map(StackBounds::get);
+    task.into_iter()
+        .chain([BOOT_STACK.get(), crate::gdt::double_fault_stack()])
+        .find(|stack| stack.contains(&addr))
+}
+
+/// Return addresses along a frame-pointer chain, innermost first.
+///
+/// Every frame record must lie within `stack`, be 8-byte aligned, and
+/// sit above the previous one; the walk stops at the first that does not.
+struct FrameWalker {
+    rbp: u64,
+    stack: Range<u64>,
+}
+
+impl FrameWalker {
+    /// Walk from `rbp` within whichever known stack holds it; an address
+    /// on no known stack yields no frames.
+    fn new(rbp: u64) -> Self {
+        Self {
+            rbp,
+            stack: stack_containing(rbp).unwrap_or(0..0),
         }
+    }
+}
 
-        frames.push(StackFrame::new(return_addr));
-        rbp = next_rbp;
+impl Iterator for FrameWalker {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let rbp = self.rbp;
+        if rbp < self.stack.start
+            || rbp.checked_add(16).is_none_or(|end| end > self.stack.end)
+            || !rbp.is_multiple_of(8)
+        {
+            return None;
+        }
+
+        // SAFETY: both slots of the frame record lie within a live kernel
+        // stack, which is mapped.
+        let (next_rbp, return_addr) = unsafe {
+            let record = rbp as *const u64;
+            (
+                core::ptr::read_volatile(record),
+                core::ptr::read_volatile(record.add(1)),
+            )
+        };
+        if return_addr == 0 || !is_canonical(return_addr) {
+            return None;
+        }
+
+        // Frames grow towards higher addresses as we unwind; a record out
+        // of order ends the walk after this frame
+        self.rbp = if next_rbp > rbp { next_rbp } else { 0 };
+        Some(return_addr)
     }
+}
 
-    frames
+/// Unwind stack to get backtrace
+fn unwind_stack(rbp: u64, max_frames: usize) -> Vec<StackFrame> {
+    FrameWalker::new(rbp)
+        .take(max_frames)
+        .map(StackFrame::new)
+        .collect()
+}
+
+/// Frame pointer of the calling function.
+#[inline(always)]
+fn frame_pointer() -> u64 {
+    let rbp: u64;
+
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: reads the frame pointer register without side effects.
+    unsafe {
+        core::arch::asm!("mov {}, rbp", out(reg) rbp, options(nomem, nostack, preserves_flags));
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        rbp = 0;
+    }
+
+    rbp
+}
+
+/// Capture a backtrace of the calling code
+///
+/// Walks from this function's own frame, which stays live for the
+/// duration of the walk.
+#[inline(never)]
+pub fn backtrace_here(max_frames: usize) -> Vec<StackFrame> {
+    unwind_stack(frame_pointer(), max_frames)
+}
+
+/// Check that an address is canonical (bits 63..47 all equal).
+fn is_canonical(addr: u64) -> bool {
+    let upper = addr >> 47;
+    upper == 0 || upper == 0x1_ffff
 }
 
 /// Global crash handler state
@@ -320,3 +556,47 @@ pub fn handle_crash(crash_type: CrashType, message: &str) -> ! {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_walker_stays_on_stack() {
+        let mut stack = [0u64; 16];
+        let base = stack.as_ptr() as u64;
+        let slot = |i: usize| base + 8 * i as u64;
+        // Three frames chained upwards, the last pointing off the stack
+        stack[2] = slot(6);
+        stack[3] = 0x1000;
+        stack[6] = slot(10);
+        stack[7] = 0x2000;
+        stack[10] = slot(16) + 0x1000;
+        stack[11] = 0x3000;
+        let stack_range = base..slot(16);
+
+        let walker = FrameWalker {
+            rbp: slot(2),
+            stack: stack_range.clone(),
+        };
+        assert_eq!(walker.collect::<Vec<_>>(), [0x1000, 0x2000, 0x3000]);
+
+        // A link pointing back down the stack ends the walk
+        stack[6] = slot(2);
+        let walker = FrameWalker {
+            rbp: slot(2),
+            stack: stack_range.clone(),
+        };
+        assert_eq!(walker.collect::<Vec<_>>(), [0x1000, 0x2000]);
+
+        // Misaligned, non-canonical, and off-stack records yield nothing
+        stack[3] = 0xdead_0000_0000_0000;
+        for rbp in [slot(2) + 4, slot(2), slot(15), base - 16] {
+            let walker = FrameWalker {
+                rbp,
+                stack: stack_range.clone(),
+            };
+            assert_eq!(walker.count(), 0);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Crash Dump Persistence
+//!
+//! Writes the panic context to persistent storage so it survives a reboot,
+//! and surfaces the previous boot's crash on the next start.
+//!
+//! A panic may strike while any lock is held, the heap's and the
+//! filesystem's included, so the panic path neither allocates nor goes
+//! through the VFS. At boot, [`arm_persistent_dump`] reserves the dump file
+//! at a fixed size and resolves the disk sectors backing it; on panic the
+//! report is formatted into a preallocated buffer and written straight to
+//! those sectors.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use spin::Mutex;
+
+use super::{frame_pointer, write_cpu_state, CpuState, CrashType, DumpError, FrameWalker};
+use crate::driver::virtio::block::{self, BLOCK_SIZE};
+
+/// Default crash dump file on the persistent disk (FAT 8.3 name).
+pub const DEFAULT_DUMP_PATH: &str = "/mnt/test/CRASH.TXT";
+
+/// In-memory copy of the previous boot's crash, readable from the shell
+/// and DevTools.
+pub const LAST_CRASH_PATH: &str = "/var/log/last_crash";
+
+/// First line of every crash report.
+const REPORT_MAGIC: &str = "=== KPIO CRASH REPORT ===";
+
+/// Last line of every crash report.
+const REPORT_END: &str = "\n=== END CRASH REPORT ===\n";
+
+/// Maximum backtrace depth recorded for a panic.
+pub const MAX_PANIC_FRAMES: usize = 32;
+
+/// Size of the reserved dump file in sectors.
+const DUMP_SECTORS: usize = 32;
+
+/// Size of the reserved dump file in bytes.
+const DUMP_SIZE: usize = DUMP_SECTORS * BLOCK_SIZE;
+
+/// Set once a panic is being recorded, to avoid recursing on a nested panic.
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Crash dump file, if changed with [`set_dump_path`].
+static DUMP_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+/// Whether the dump file is reserved and its sectors below are valid.
+static ARMED: AtomicBool = AtomicBool::new(false);
+
+/// VirtIO block device holding the dump file.
+static DUMP_DEVICE: AtomicUsize = AtomicUsize::new(0);
+
+/// Device sectors backing the dump file, in file order.
+static DUMP_LBAS: [AtomicU64; DUMP_SECTORS] = [const { AtomicU64::new(0) }; DUMP_SECTORS];
+
+/// Buffer the panic report is formatted into.
+struct ReportBuffer(UnsafeCell<[u8; DUMP_SIZE]>);
+
+// SAFETY: only the panic that set `RECORDING` touches the buffer.
+unsafe impl Sync for ReportBuffer {}
+
+static REPORT: ReportBuffer = ReportBuffer(UnsafeCell::new([0; DUMP_SIZE]));
+
+/// Crash report left behind by the previous boot.
+static PREVIOUS_CRASH: Mutex<Option<String>> = Mutex::new(None);
+
+/// Use `path` for the crash dump file instead of [`DEFAULT_DUMP_PATH`].
+///
+/// Takes effect at the next [`arm_persistent_dump`].
+pub fn set_dump_path(path: &str) {
+    *DUMP_PATH.lock() = Some(path.into());
+}
+
+/// Path of the crash dump file.
+pub fn dump_path() -> String {
+    DUMP_PATH
+        .lock()
+        .clone()
+        .unwrap_or_else(|| DEFAULT_DUMP_PATH.into())
+}
+
+/// What [`record_panic`] captured.
+pub struct PanicRecord {
+    frames: [u64; MAX_PANIC_FRAMES],
+    frame_count: usize,
+    /// Result of writing the report to the dump file.
+    pub result: Result<(), DumpError>,
+}
+
+impl PanicRecord {
+    /// Return addresses of the panicking code, innermost first.
+    pub fn backtrace(&self) -> &[u64] {
+        &self.frames[..self.frame_count]
+    }
+}
+
+/// Capture the panic context and write it to the reserved dump file.
+///
+/// Neither allocates nor waits on a lock, so it works whatever the
+/// panicking code held. Returns `None` if a panic is already being
+/// recorded (nested panic). Persisting is best-effort: the backtrace is
+/// returned even if the write failed, together with the write result.
+#[inline(never)]
+pub fn record_panic(info: &PanicInfo) -> Option<PanicRecord> {
+    if RECORDING.swap(true, Ordering::SeqCst) {
+        return None;
+    }
+
+    let mut record = PanicRecord {
+        frames: [0; MAX_PANIC_FRAMES],
+        frame_count: 0,
+        result: Ok(()),
+    };
+    for (slot, address) in record
+        .frames
+        .iter_mut()
+        .zip(FrameWalker::new(frame_pointer()))
+    {
+        *slot = address;
+        record.frame_count += 1;
+    }
+
+    // SAFETY: `RECORDING` gives this call sole access to the buffer.
+    let report = unsafe { &mut *REPORT.0.get() };
+    report.fill(0);
+    write_panic_report(report, info, record.backtrace());
+    record.result = write_dump(report);
+    Some(record)
+}
+
+/// Format the panic report into `buf`, truncating the recent log to fit.
+fn write_panic_report(buf: &mut [u8], info: &PanicInfo, frames: &[u64]) -> usize {
+    let end = buf.len() - REPORT_END.len();
+    let mut out = SliceWriter {
+        buf: &mut buf[..end],
+        len: 0,
+    };
+
+    let crash_type = CrashType::Panic;
+    let _ = write!(
+        out,
+        "{}\n\nType: {}\nMessage: {}\n",
+        REPORT_MAGIC,
+        crash_type.name(),
+        info.message()
+    );
+    if let Some(location) = info.location() {
+        let _ = writeln!(
+            out,
+            "Location: {}:{}:{}",
+            location.file(),
+            location.line(),
+            location.column()
+        );
+    }
+    let _ = writeln!(out, "Severity: {:?}", crash_type.severity());
+    let _ = writeln!(out, "CPU: {}", crate::percpu::current_cpu());
+    let _ = writeln!(out, "Timer Ticks: {}", crate::interrupts::timer_ticks());
+
+    let _ = out.write_str("\n--- CPU State ---\n");
+    let _ = write_cpu_state(&mut out, &CpuState::capture(), crash_type);
+
+    let _ = out.write_str("\n--- Backtrace ---\n");
+    for (i, address) in frames.iter().enumerate() {
+        let _ = writeln!(out, "#{}: {:#018x}", i, address);
+    }
+
+    let _ = out.write_str("\n--- Recent Log ---\n");
+    let mut len = out.len;
+    len += crate::serial::copy_recent(&mut buf[len..end]);
+
+    buf[len..len + REPORT_END.len()].copy_from_slice(REPORT_END.as_bytes());
+    len + REPORT_END.len()
+}
+
+/// [`fmt::Write`] into a fixed buffer, dropping whatever does not fit.
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let n = s.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+/// Write the whole dump buffer to the sectors reserved by
+/// [`arm_persistent_dump`].
+fn write_dump(report: &[u8; DUMP_SIZE]) -> Result<(), DumpError> {
+    if !ARMED.load(Ordering::Acquire) {
+        return Err(DumpError::NotArmed);
+    }
+
+    let device = DUMP_DEVICE.load(Ordering::Relaxed);
+    let mut sector = [0u8; BLOCK_SIZE];
+    for (chunk, lba) in report.chunks_exact(BLOCK_SIZE).zip(&DUMP_LBAS) {
+        sector.copy_from_slice(chunk);
+        if !block::try_write_sector(device, lba.load(Ordering::Relaxed), &sector) {
+            return Err(DumpError::WriteFailed);
+        }
+    }
+    Ok(())
+}
+
+/// Reserve the crash dump file and resolve the sectors backing it, so a
+/// panic can write it without the filesystem.
+///
+/// The file at [`dump_path`] is recreated at a fixed size, replacing any
+/// previous report, so call [`load_previous_crash`] first. It must be on a
+/// FAT32 volume starting at sector `volume_start` of VirtIO block device
+/// `device_index`, and must not be changed afterwards: a panic writes to
+/// its sectors whatever the filesystem has since done with them.
+pub fn arm_persistent_dump(device_index: usize, volume_start: u64) -> Result<(), DumpError> {
+    use storage::OpenFlags;
+
+    ARMED.store(false, Ordering::Release);
+
+    let path = dump_path();
+    let storage_error = |e| DumpError::StorageError(alloc::format!("{}: {:?}", path, e));
+
+    let flags = OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE;
+    let fd = storage::vfs::open(&path, flags).map_err(storage_error)?;
+    let result = storage::vfs::write(fd, &alloc::vec![0u8; DUMP_SIZE]);
+    let _ = storage::vfs::close(fd);
+    match result {
+        Ok(n) if n == DUMP_SIZE => {}
+        Ok(_) => return Err(DumpError::NoSpace),
+        Err(_) => return Err(DumpError::WriteFailed),
+    }
+    storage::vfs::sync_all().map_err(storage_error)?;
+
+    // A FAT32 file's inode number is its first cluster
+    let first_cluster = storage::vfs::stat(&path).map_err(storage_error)?.inode as u32;
+    let sectors = fat32_file_sectors(first_cluster, DUMP_SECTORS, |sector, buf| {
+        block::read_sector(device_index, volume_start + sector, buf)
+    })
+    .ok_or_else(|| DumpError::StorageError(alloc::format!("{}: not on a FAT32 volume", path)))?;
+
+    DUMP_DEVICE.store(device_index, Ordering::Relaxed);
+    for (slot, sector) in DUMP_LBAS.iter().zip(sectors) {
+        slot.store(volume_start + sector, Ordering::Relaxed);
+    }
+    ARMED.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Volume-relative sectors holding the first `count` sectors of the FAT32
+/// file starting at `first_cluster`, in file order.
+fn fat32_file_sectors(
+    first_cluster: u32,
+    count: usize,
+    mut read_sector: impl FnMut(u64, &mut [u8; BLOCK_SIZE]) -> bool,
+) -> Option<Vec<u64>> {
+    let mut boot = [0u8; BLOCK_SIZE];
+    if !read_sector(0, &mut boot) || boot[510..] != [0x55, 0xAA] {
+        return None;
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([boot[offset], boot[offset + 1]]) as u64;
+    let sectors_per_cluster = boot[13] as u64;
+    let reserved = u16_at(14);
+    let fat_size = u32::from_le_bytes([boot[36], boot[37], boot[38], boot[39]]) as u64;
+    if u16_at(11) != BLOCK_SIZE as u64 || sectors_per_cluster == 0 || fat_size == 0 {
+        return None;
+    }
+    let data_start = reserved + boot[16] as u64 * fat_size;
+
+    let mut sectors = Vec::with_capacity(count);
+    let mut cluster = first_cluster;
+    let mut fat = [0u8; BLOCK_SIZE];
+    loop {
+        if !(2..0x0FFF_FFF7).contains(&cluster) {
+            return None;
+        }
+        let first = data_start + (cluster as u64 - 2) * sectors_per_cluster;
+        let needed = count - sectors.len();
+        sectors.extend((first..first + sectors_per_cluster).take(needed));
+        if sectors.len() == count {
+            return Some(sectors);
+        }
+
+        let offset = cluster as u64 * 4;
+        if !read_sector(reserved + offset / BLOCK_SIZE as u64, &mut fat) {
+            return None;
+        }
+        let at = (offset % BLOCK_SIZE as u64) as usize;
+        cluster =
+            u32::from_le_bytes([fat[at], fat[at + 1], fat[at + 2], fat[at + 3]]) & 0x0FFF_FFFF;
+    }
+}
+
+/// Check the persistent disk for a crash report from the previous boot.
+///
+/// Must be called after the persistent disk is mounted, and before
+/// [`arm_persistent_dump`] clears the report. The report is copied to
+/// [`LAST_CRASH_PATH`] and kept for [`previous_crash`].
+pub fn load_previous_crash() -> Option<String> {
+    use storage::OpenFlags;
+
+    let fd = storage::vfs::open(&dump_path(), OpenFlags::READ).ok()?;
+    let mut data = Vec::new();
+    let mut buf = [0u8; 512];
+    loop {
+        match storage::vfs::read(fd, &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    }
+    let _ = storage::vfs::close(fd);
+
+    // The reserved file is zero-padded past the report
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    let report = String::from_utf8_lossy(&data[..end]).into_owned();
+    if !report.starts_with(REPORT_MAGIC) {
+        return None;
+    }
+
+    let _ = crate::vfs::write_all(LAST_CRASH_PATH, report.as_bytes());
+    *PREVIOUS_CRASH.lock() = Some(report.clone());
+    Some(report)
+}
+
+/// Crash report from the previous boot, if there was one.
+pub fn previous_crash() -> Option<String> {
+    PREVIOUS_CRASH.lock().clone()
+}
+
+/// One-line summary of a crash report (type, message, and location).
+pub fn crash_summary(report: &str) -> String {
+    let field = |name: &str| {
+        report
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(str::trim)
+    };
+
+    let mut summary = String::from(field("Type:").unwrap_or("Unknown Crash"));
+    if let Some(message) = field("Message:") {
+        summary.push_str(": ");
+        summary.push_str(message);
+    }
+    if let Some(location) = field("Location:") {
+        summary.push_str(" at ");
+        summary.push_str(location);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fat32_file_sectors_follow_cluster_chain() {
+        // 4 reserved sectors, 2 FATs of 8 sectors, 8 sectors per cluster
+        let mut boot = [0u8; BLOCK_SIZE];
+        boot[11..13].copy_from_slice(&512u16.to_le_bytes());
+        boot[13] = 8;
+        boot[14..16].copy_from_slice(&4u16.to_le_bytes());
+        boot[16] = 2;
+        boot[36..40].copy_from_slice(&8u32.to_le_bytes());
+        boot[510] = 0x55;
+        boot[511] = 0xAA;
+        // Chain 5 -> 9 -> 6 -> end of chain
+        let mut fat = [0u8; BLOCK_SIZE];
+        for (cluster, next) in [(5usize, 9u32), (9, 6), (6, 0x0FFF_FFFF)] {
+            fat[cluster * 4..cluster * 4 + 4].copy_from_slice(&next.to_le_bytes());
+        }
+        let read = |sector: u64, buf: &mut [u8; BLOCK_SIZE]| {
+            match sector {
+                0 => *buf = boot,
+                4 => *buf = fat,
+                _ => return false,
+            }
+            true
+        };
+
+        // Data starts at sector 4 + 2 * 8 = 20
+        let cluster = |n: u64| 20 + (n - 2) * 8;
+        let expected: Vec<u64> = (cluster(5)..cluster(5) + 8)
+            .chain(cluster(9)..cluster(9) + 8)
+            .chain(cluster(6)..cluster(6) + 4)
+            .collect();
+        assert_eq!(fat32_file_sectors(5, 20, read), Some(expected));
+
+        // The chain ends before enough sectors
+        assert_eq!(fat32_file_sectors(5, 25, read), None);
+        // Not FAT32
+        assert_eq!(fat32_file_sectors(5, 4, |_, _| false), None);
+    }
+}
@@ -0,0 +1,628 @@
+//! Encrypted block devices.
+//!
+//! An encrypted volume starts with a LUKS-style header that describes the
+//! cipher and holds key slots. Each slot stores a copy of the volume's
+//! master key, encrypted with a key derived from a passphrase by
+//! PBKDF2-HMAC-SHA-256. Unlocking a volume tries the passphrase against
+//! every active slot and checks the result against the master key digest.
+//!
+//! [`CryptDevice`] sits between the VFS and the underlying device: it
+//! presents the payload area as a plain block device and encrypts each
+//! 512-byte sector with XTS-AES-256, using the sector number within the
+//! payload as the tweak.
+//!
+//! On-disk layout (512-byte sectors, integers big-endian):
+//!
+//! | Sector | Contents                              |
+//! |--------|---------------------------------------|
+//! | 0      | header                                |
+//! | 1..=4  | key material of slots 0-3             |
+//! | 8..    | payload                               |
+
+use alloc::boxed::Box;
+
+use storage::driver::BlockDevice;
+use storage::{BlockDeviceInfo, MountFlags, StorageError};
+
+use crate::net::crypto::aes_xts::Aes256Xts;
+use crate::net::crypto::pbkdf2::pbkdf2_hmac_sha256;
+use crate::net::crypto::random::csprng_fill;
+
+/// Sector size of encrypted volumes.
+pub const SECTOR_SIZE: usize = 512;
+
+/// Number of key slots in the header.
+pub const KEY_SLOTS: usize = 4;
+
+/// Recommended PBKDF2 iterations for new key slots.
+pub const DEFAULT_ITERATIONS: u32 = 100_000;
+
+/// First payload sector.
+const PAYLOAD_OFFSET: u32 = 8;
+
+/// Master key size (XTS-AES-256 uses two 256-bit keys).
+const KEY_BYTES: usize = 64;
+
+const MAGIC: [u8; 6] = *b"KCRYPT";
+const VERSION: u16 = 1;
+const CIPHER_NAME: &str = "aes";
+const CIPHER_MODE: &str = "xts-plain64";
+const HASH_SPEC: &str = "sha256";
+
+const SLOT_ACTIVE: u32 = 0x00AC_71F3;
+const SLOT_INACTIVE: u32 = 0x0000_DEAD;
+
+/// Header field offsets.
+const OFF_VERSION: usize = 6;
+const OFF_CIPHER_NAME: usize = 8;
+const OFF_CIPHER_MODE: usize = 40;
+const OFF_HASH_SPEC: usize = 72;
+const OFF_PAYLOAD: usize = 104;
+const OFF_KEY_BYTES: usize = 108;
+const OFF_DIGEST: usize = 112;
+const OFF_DIGEST_SALT: usize = 144;
+const OFF_DIGEST_ITER: usize = 176;
+const OFF_SLOTS: usize = 180;
+const SLOT_SIZE: usize = 44;
+
+/// Encrypted volume errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptError {
+    /// The underlying device failed.
+    Storage(StorageError),
+    /// No encrypted volume header on the device.
+    NoHeader,
+    /// The header uses a version, cipher or hash that is not supported.
+    Unsupported,
+    /// The device does not use 512-byte blocks.
+    UnsupportedBlockSize,
+    /// The device is too small to hold a volume.
+    DeviceTooSmall,
+    /// No key slot could be unlocked with the passphrase.
+    WrongPassphrase,
+    /// All key slots are in use.
+    NoFreeSlot,
+}
+
+impl From<StorageError> for CryptError {
+    fn from(e: StorageError) -> Self {
+        CryptError::Storage(e)
+    }
+}
+
+/// A key slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySlot {
+    /// Slot holds a key.
+    pub active: bool,
+    /// PBKDF2 iterations for the passphrase.
+    pub iterations: u32,
+    /// PBKDF2 salt for the passphrase.
+    pub salt: [u8; 32],
+    /// Sector of the encrypted master key.
+    pub key_material_offset: u32,
+}
+
+/// Encrypted volume header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CryptHeader {
+    /// First payload sector.
+    pub payload_offset: u32,
+    /// PBKDF2 of the master key, to recognise it when unlocking.
+    pub mk_digest: [u8; 32],
+    /// Salt of the master key digest.
+    pub mk_digest_salt: [u8; 32],
+    /// Iterations of the master key digest.
+    pub mk_digest_iterations: u32,
+    /// Key slots.
+    pub slots: [KeySlot; KEY_SLOTS],
+}
+
+impl CryptHeader {
+    /// Parse a header sector.
+    pub fn parse(sector: &[u8]) -> Result<Self, CryptError> {
+        if sector.len() < SECTOR_SIZE || sector[..MAGIC.len()] != MAGIC {
+            return Err(CryptError::NoHeader);
+        }
+        let supported = be16(sector, OFF_VERSION) == VERSION
+            && field_str(sector, OFF_CIPHER_NAME) == CIPHER_NAME
+            && field_str(sector, OFF_CIPHER_MODE) == CIPHER_MODE
+            && field_str(sector, OFF_HASH_SPEC) == HASH_SPEC
+            && be32(sector, OFF_KEY_BYTES) as usize == KEY_BYTES;
+        if !supported {
+            return Err(CryptError::Unsupported);
+        }
+
+        let mut slots = [KeySlot {
+            active: false,
+            iterations: 0,
+            salt: [0; 32],
+            key_material_offset: 0,
+        }; KEY_SLOTS];
+        for (i, slot) in slots.iter_mut().enumerate() {
+            let off = OFF_SLOTS + i * SLOT_SIZE;
+            slot.active = be32(sector, off) == SLOT_ACTIVE;
+            slot.iterations = be32(sector, off + 4);
+            slot.salt.copy_from_slice(&sector[off + 8..off + 40]);
+            slot.key_material_offset = be32(sector, off + 40);
+        }
+
+        let mut header = CryptHeader {
+            payload_offset: be32(sector, OFF_PAYLOAD),
+            mk_digest: [0; 32],
+            mk_digest_salt: [0; 32],
+            mk_digest_iterations: be32(sector, OFF_DIGEST_ITER),
+            slots,
+        };
+        header
+            .mk_digest
+            .copy_from_slice(&sector[OFF_DIGEST..OFF_DIGEST + 32]);
+        header
+            .mk_digest_salt
+            .copy_from_slice(&sector[OFF_DIGEST_SALT..OFF_DIGEST_SALT + 32]);
+        Ok(header)
+    }
+
+    /// Serialize the header into a sector.
+    pub fn to_bytes(&self) -> [u8; SECTOR_SIZE] {
+        let mut sector = [0u8; SECTOR_SIZE];
+        sector[..MAGIC.len()].copy_from_slice(&MAGIC);
+        sector[OFF_VERSION..OFF_VERSION + 2].copy_from_slice(&VERSION.to_be_bytes());
+        put_str(&mut sector, OFF_CIPHER_NAME, CIPHER_NAME);
+        put_str(&mut sector, OFF_CIPHER_MODE, CIPHER_MODE);
+        put_str(&mut sector, OFF_HASH_SPEC, HASH_SPEC);
+        put32(&mut sector, OFF_PAYLOAD, self.payload_offset);
+        put32(&mut sector, OFF_KEY_BYTES, KEY_BYTES as u32);
+        sector[OFF_DIGEST..OFF_DIGEST + 32].copy_from_slice(&self.mk_digest);
+        sector[OFF_DIGEST_SALT..OFF_DIGEST_SALT + 32].copy_from_slice(&self.mk_digest_salt);
+        put32(&mut sector, OFF_DIGEST_ITER, self.mk_digest_iterations);
+
+        for (i, slot) in self.slots.iter().enumerate() {
+            let off = OFF_SLOTS + i * SLOT_SIZE;
+            let state = if slot.active {
+                SLOT_ACTIVE
+            } else {
+                SLOT_INACTIVE
+            };
+            put32(&mut sector, off, state);
+            put32(&mut sector, off + 4, slot.iterations);
+            sector[off + 8..off + 40].copy_from_slice(&slot.salt);
+            put32(&mut sector, off + 40, slot.key_material_offset);
+        }
+        sector
+    }
+
+    /// Read the header of a device.
+    pub fn read(device: &dyn BlockDevice) -> Result<Self, CryptError> {
+        check_block_size(device)?;
+        let mut sector = [0u8; SECTOR_SIZE];
+        device.read_blocks(0, &mut sector)?;
+        Self::parse(&sector)
+    }
+
+    /// Try to recover the master key with a passphrase.
+    fn unlock(
+        &self,
+        device: &dyn BlockDevice,
+        passphrase: &[u8],
+    ) -> Result<[u8; KEY_BYTES], CryptError> {
+        for slot in self.slots.iter().filter(|s| s.active) {
+            let mut material = [0u8; SECTOR_SIZE];
+            device.read_blocks(slot.key_material_offset as u64, &mut material)?;
+            slot_cipher(slot, passphrase)
+                .decrypt_unit(slot.key_material_offset as u64, &mut material);
+
+            let mut key = [0u8; KEY_BYTES];
+            key.copy_from_slice(&material[..KEY_BYTES]);
+            if self.is_master_key(&key) {
+                return Ok(key);
+            }
+        }
+        Err(CryptError::WrongPassphrase)
+    }
+
+    fn is_master_key(&self, key: &[u8; KEY_BYTES]) -> bool {
+        let mut digest = [0u8; 32];
+        pbkdf2_hmac_sha256(
+            key,
+            &self.mk_digest_salt,
+            self.mk_digest_iterations,
+            &mut digest,
+        );
+        // Constant-time comparison
+        digest
+            .iter()
+            .zip(&self.mk_digest)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+    }
+}
+
+/// Create an encrypted volume on a device, unlocked by `passphrase`.
+///
+/// Destroys the contents of the device.
+pub fn format(
+    device: &dyn BlockDevice,
+    passphrase: &[u8],
+    iterations: u32,
+) -> Result<(), CryptError> {
+    check_block_size(device)?;
+    if device.info().total_blocks <= PAYLOAD_OFFSET as u64 {
+        return Err(CryptError::DeviceTooSmall);
+    }
+
+    let mut master_key = [0u8; KEY_BYTES];
+    csprng_fill(&mut master_key);
+
+    let mut header = CryptHeader {
+        payload_offset: PAYLOAD_OFFSET,
+        mk_digest: [0; 32],
+        mk_digest_salt: [0; 32],
+        mk_digest_iterations: iterations,
+        slots: core::array::from_fn(|i| KeySlot {
+            active: false,
+            iterations: 0,
+            salt: [0; 32],
+            key_material_offset: 1 + i as u32,
+        }),
+    };
+    csprng_fill(&mut header.mk_digest_salt);
+    pbkdf2_hmac_sha256(
+        &master_key,
+        &header.mk_digest_salt,
+        iterations,
+        &mut header.mk_digest,
+    );
+
+    write_slot(device, &mut header, 0, &master_key, passphrase, iterations)?;
+    device.write_blocks(0, &header.to_bytes())?;
+    device.flush()?;
+    Ok(())
+}
+
+/// Add a passphrase to a volume, unlocking it with an existing one.
+/// Returns the key slot used.
+pub fn add_passphrase(
+    device: &dyn BlockDevice,
+    passphrase: &[u8],
+    new_passphrase: &[u8],
+    iterations: u32,
+) -> Result<usize, CryptError> {
+    let mut header = CryptHeader::read(device)?;
+    let master_key = header.unlock(device, passphrase)?;
+    let slot = header
+        .slots
+        .iter()
+        .position(|s| !s.active)
+        .ok_or(CryptError::NoFreeSlot)?;
+
+    write_slot(
+        device,
+        &mut header,
+        slot,
+        &master_key,
+        new_passphrase,
+        iterations,
+    )?;
+    device.write_blocks(0, &header.to_bytes())?;
+    device.flush()?;
+    Ok(slot)
+}
+
+/// Encrypt the master key into a key slot.
+fn write_slot(
+    device: &dyn BlockDevice,
+    header: &mut CryptHeader,
+    index: usize,
+    master_key: &[u8; KEY_BYTES],
+    passphrase: &[u8],
+    iterations: u32,
+) -> Result<(), CryptError> {
+    let slot = &mut header.slots[index];
+    slot.iterations = iterations;
+    csprng_fill(&mut slot.salt);
+
+    let mut material = [0u8; SECTOR_SIZE];
+    material[..KEY_BYTES].copy_from_slice(master_key);
+    slot_cipher(slot, passphrase).encrypt_unit(slot.key_material_offset as u64, &mut material);
+    device.write_blocks(slot.key_material_offset as u64, &material)?;
+
+    slot.active = true;
+    Ok(())
+}
+
+/// Cipher protecting a slot's key material.
+fn slot_cipher(slot: &KeySlot, passphrase: &[u8]) -> Aes256Xts {
+    let mut key = [0u8; KEY_BYTES];
+    pbkdf2_hmac_sha256(passphrase, &slot.salt, slot.iterations, &mut key);
+    Aes256Xts::new(&key)
+}
+
+/// An unlocked encrypted volume.
+pub struct CryptDevice {
+    inner: &'static dyn BlockDevice,
+    name: [u8; 32],
+    name_len: usize,
+    payload_offset: u64,
+    cipher: Aes256Xts,
+}
+
+impl CryptDevice {
+    /// Unlock the encrypted volume on `inner` with a passphrase.
+    pub fn open(inner: &'static dyn BlockDevice, passphrase: &[u8]) -> Result<Self, CryptError> {
+        let header = CryptHeader::read(inner)?;
+        let master_key = header.unlock(inner, passphrase)?;
+
+        // Named after the underlying device, like a device-mapper target
+        let info = inner.info();
+        let mut name = [0u8; 32];
+        let mut name_len = 0;
+        for part in [info.name_str().as_bytes(), b"-crypt"] {
+            let take = part.len().min(name.len() - name_len);
+            name[name_len..name_len + take].copy_from_slice(&part[..take]);
+            name_len += take;
+        }
+
+        Ok(Self {
+            inner,
+            name,
+            name_len,
+            payload_offset: header.payload_offset as u64,
+            cipher: Aes256Xts::new(&master_key),
+        })
+    }
+
+    fn check_range(&self, start_block: u64, len: usize) -> Result<(), StorageError> {
+        if !len.is_multiple_of(SECTOR_SIZE) {
+            return Err(StorageError::InvalidArgument);
+        }
+        let end = start_block + (len / SECTOR_SIZE) as u64;
+        if end > self.info().total_blocks {
+            return Err(StorageError::InvalidBlock);
+        }
+        Ok(())
+    }
+}
+
+impl BlockDevice for CryptDevice {
+    fn info(&self) -> BlockDeviceInfo {
+        let mut info = self.inner.info();
+        info.name = self.name;
+        info.name_len = self.name_len;
+        info.total_blocks = info.total_blocks.saturating_sub(self.payload_offset);
+        // Discards would reveal which sectors are in use
+        info.supports_trim = false;
+        info
+    }
+
+    fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+        self.check_range(start_block, buffer.len())?;
+        let read = self
+            .inner
+            .read_blocks(self.payload_offset + start_block, buffer)?;
+
+        for (i, sector) in buffer.chunks_exact_mut(SECTOR_SIZE).enumerate() {
+            self.cipher.decrypt_unit(start_block + i as u64, sector);
+        }
+        Ok(read)
+    }
+
+    fn write_blocks(&self, start_block: u64, data: &[u8]) -> Result<usize, StorageError> {
+        self.check_range(start_block, data.len())?;
+        let mut encrypted = alloc::vec::Vec::from(data);
+        for (i, sector) in encrypted.chunks_exact_mut(SECTOR_SIZE).enumerate() {
+            self.cipher.encrypt_unit(start_block + i as u64, sector);
+        }
+        self.inner
+            .write_blocks(self.payload_offset + start_block, &encrypted)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        self.inner.flush()
+    }
+
+    fn discard(&self, _start_block: u64, _num_blocks: u64) -> Result<(), StorageError> {
+        Err(StorageError::Unsupported)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+}
+
+/// Unlock the encrypted volume on a registered block device and register
+/// the decrypted view as `<device>-crypt`. Returns the new device's name.
+pub fn open(device: &str, passphrase: &[u8]) -> Result<&'static str, CryptError> {
+    let inner = storage::driver::find_device(device)
+        .and_then(storage::driver::get_device)
+        .ok_or(CryptError::Storage(StorageError::DeviceNotFound))?;
+
+    let crypt: &'static CryptDevice = Box::leak(Box::new(CryptDevice::open(inner, passphrase)?));
+    let name = core::str::from_utf8(&crypt.name[..crypt.name_len])
+        .map_err(|_| CryptError::Storage(StorageError::InvalidName))?;
+    storage::register_block_device(name, crypt)?;
+    Ok(name)
+}
+
+/// Unlock an encrypted volume and mount the filesystem inside it.
+pub fn mount(
+    device: &str,
+    passphrase: &[u8],
+    mount_point: &str,
+    fs_type: &str,
+    flags: MountFlags,
+) -> Result<(), CryptError> {
+    let name = open(device, passphrase)?;
+    storage::mount(name, mount_point, fs_type, flags)?;
+    Ok(())
+}
+
+fn check_block_size(device: &dyn BlockDevice) -> Result<(), CryptError> {
+    if device.info().block_size as usize != SECTOR_SIZE {
+        return Err(CryptError::UnsupportedBlockSize);
+    }
+    Ok(())
+}
+
+fn be16(buf: &[u8], off: usize) -> u16 {
+    u16::from_be_bytes([buf[off], buf[off + 1]])
+}
+
+fn be32(buf: &[u8], off: usize) -> u32 {
+    u32::from_be_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn put32(buf: &mut [u8], off: usize, value: u32) {
+    buf[off..off + 4].copy_from_slice(&value.to_be_bytes());
+}
+
+/// Read a NUL-padded 32-byte string field.
+fn field_str(buf: &[u8], off: usize) -> &str {
+    let field = &buf[off..off + 32];
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    core::str::from_utf8(&field[..len]).unwrap_or("")
+}
+
+fn put_str(buf: &mut [u8], off: usize, value: &str) {
+    buf[off..off + value.len()].copy_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use spin::Mutex;
+
+    /// In-memory block device.
+    struct RamDisk {
+        data: Mutex<Vec<u8>>,
+    }
+
+    impl RamDisk {
+        fn new(sectors: usize) -> &'static Self {
+            Box::leak(Box::new(Self {
+                data: Mutex::new(vec![0; sectors * SECTOR_SIZE]),
+            }))
+        }
+    }
+
+    impl BlockDevice for RamDisk {
+        fn info(&self) -> BlockDeviceInfo {
+            let mut name = [0u8; 32];
+            name[..3].copy_from_slice(b"ram");
+            BlockDeviceInfo {
+                name,
+                name_len: 3,
+                block_size: SECTOR_SIZE as u32,
+                total_blocks: (self.data.lock().len() / SECTOR_SIZE) as u64,
+                read_only: false,
+                supports_trim: true,
+                optimal_io_size: 1,
+                physical_block_size: SECTOR_SIZE as u32,
+            }
+        }
+
+        fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+            let start = start_block as usize * SECTOR_SIZE;
+            buffer.copy_from_slice(&self.data.lock()[start..start + buffer.len()]);
+            Ok(buffer.len())
+        }
+
+        fn write_blocks(&self, start_block: u64, data: &[u8]) -> Result<usize, StorageError> {
+            let start = start_block as usize * SECTOR_SIZE;
+            self.data.lock()[start..start + data.len()].copy_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&self) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn discard(&self, _start_block: u64, _num_blocks: u64) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_known_answers() {
+        // RFC 7914 §11
+        let mut dk = [0u8; 64];
+        pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut dk);
+        assert_eq!(
+            dk.as_slice(),
+            hex(
+                "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+                 49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+            )
+        );
+
+        // IEEE 1619 XTS-AES-256, vector 10 (first 32 bytes)
+        let mut key = [0u8; 64];
+        key.copy_from_slice(&hex(
+            "2718281828459045235360287471352662497757247093699959574966967627\
+             3141592653589793238462643383279502884197169399375105820974944592",
+        ));
+        let mut data: Vec<u8> = (0..512).map(|i| i as u8).collect();
+        let xts = Aes256Xts::new(&key);
+        xts.encrypt_unit(0xff, &mut data);
+        assert_eq!(
+            &data[..32],
+            hex("1c3b3a102f770386e4836c99e370cf9bea00803f5e482357a4ae12d414a3e63b")
+        );
+        xts.decrypt_unit(0xff, &mut data);
+        assert!(data.iter().enumerate().all(|(i, &b)| b == i as u8));
+    }
+
+    #[test]
+    fn test_encrypted_volume() {
+        let disk = RamDisk::new(32);
+        format(disk, b"correct horse", 2).unwrap();
+        assert_eq!(
+            CryptDevice::open(disk, b"wrong").err(),
+            Some(CryptError::WrongPassphrase)
+        );
+
+        let crypt = CryptDevice::open(disk, b"correct horse").unwrap();
+        let info = crypt.info();
+        assert_eq!(info.name_str(), "ram-crypt");
+        assert_eq!(info.total_blocks, 32 - PAYLOAD_OFFSET as u64);
+
+        let plain = [0x5au8; 2 * SECTOR_SIZE];
+        crypt.write_blocks(3, &plain).unwrap();
+
+        // Ciphertext on disk differs per sector
+        let mut raw = [0u8; 2 * SECTOR_SIZE];
+        disk.read_blocks(PAYLOAD_OFFSET as u64 + 3, &mut raw)
+            .unwrap();
+        assert_ne!(raw[..SECTOR_SIZE], plain[..SECTOR_SIZE]);
+        assert_ne!(raw[..SECTOR_SIZE], raw[SECTOR_SIZE..]);
+
+        let mut read = [0u8; 2 * SECTOR_SIZE];
+        crypt.read_blocks(3, &mut read).unwrap();
+        assert_eq!(read, plain);
+        assert_eq!(
+            crypt.read_blocks(info.total_blocks, &mut read),
+            Err(StorageError::InvalidBlock)
+        );
+
+        // A second passphrase unlocks the same data
+        assert_eq!(add_passphrase(disk, b"correct horse", b"battery", 2), Ok(1));
+        let header = CryptHeader::read(disk).unwrap();
+        assert_eq!(CryptHeader::parse(&header.to_bytes()), Ok(header));
+        let crypt = CryptDevice::open(disk, b"battery").unwrap();
+        crypt.read_blocks(3, &mut read).unwrap();
+        assert_eq!(read, plain);
+    }
+}
@@ -1,7 +1,9 @@
 //! Device driver subsystem.
 //!
-//! This module contains hardware device drivers for the kernel.
+//! This module contains hardware device drivers for the kernel, and the
+//! encrypted block device layer that sits on top of them.
 
+pub mod crypt;
 pub mod pci;
 pub mod ps2_mouse;
 pub mod virtio;
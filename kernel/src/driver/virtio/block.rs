@@ -17,7 +17,7 @@
 //! 3. Status byte
 
 use crate::driver::pci::{self, PciAddress, PciDevice};
-use alloc::boxed::Box;
+use crate::memory::{dma_alloc, DmaBuffer};
 use alloc::vec::Vec;
 use core::ptr;
 use spin::Mutex;
@@ -74,6 +74,14 @@ pub struct BlockRequestHeader {
     pub sector: u64,
 }
 
+/// Offsets within the per-device request page.
+///
+/// The header, data and status of a request share one DMA page; the data
+/// buffer is sector-aligned.
+const REQUEST_HEADER_OFFSET: usize = 0;
+const REQUEST_STATUS_OFFSET: usize = 16;
+const REQUEST_DATA_OFFSET: usize = 512;
+
 /// VirtIO PCI capability offsets (legacy mode).
 mod legacy_regs {
     /// Device features (32-bit).
@@ -150,12 +158,10 @@ pub struct VirtioBlock {
     used_phys: u64,
     /// Used ring — virtual address.
     used_virt: u64,
-    /// Request header buffer.
-    header_buf: Box<BlockRequestHeader>,
-    /// Status buffer.
-    status_buf: Box<u8>,
-    /// Data buffer for single-sector operations.
-    data_buf: Box<[u8; BLOCK_SIZE]>,
+    /// Backing memory for the descriptor table and rings.
+    _queue_mem: DmaBuffer,
+    /// Request header, status byte and single-sector data buffer.
+    request_buf: DmaBuffer,
 }
 
 impl VirtioBlock {
@@ -237,19 +243,18 @@ impl VirtioBlock {
         let avail_size = 6 + 2 * queue_size as usize;
         let _used_size = 6 + 8 * queue_size as usize;
 
-        // Allocate page-aligned memory so the PFN calculation is correct.
-        let layout = alloc::alloc::Layout::from_size_align(4096 * 4, 4096)
-            .expect("[VirtIO-Blk] Layout error");
-        let queue_virt = unsafe { alloc::alloc::alloc_zeroed(layout) } as u64;
-        if queue_virt == 0 {
-            crate::serial_println!("[VirtIO-Blk] Queue allocation failed");
-            Self::write_status_raw(io_base, device_status::FAILED);
-            return None;
-        }
-
-        // Translate virtual → physical for DMA.
-        let queue_phys = crate::memory::virt_to_phys(queue_virt)
-            .expect("[VirtIO-Blk] virt_to_phys failed for queue memory");
+        // The device walks the rings by physical address, so the queue must
+        // be physically contiguous and page-aligned for the PFN.
+        let (queue_mem, request_buf) = match (dma_alloc(4096 * 4, 4096), dma_alloc(4096, 4096)) {
+            (Ok(queue_mem), Ok(request_buf)) => (queue_mem, request_buf),
+            _ => {
+                crate::serial_println!("[VirtIO-Blk] Queue allocation failed");
+                Self::write_status_raw(io_base, device_status::FAILED);
+                return None;
+            }
+        };
+        let queue_virt = queue_mem.virt_addr();
+        let queue_phys = queue_mem.phys_addr();
 
         let desc_virt = queue_virt;
         let desc_phys = queue_phys;
@@ -301,16 +306,43 @@ impl VirtioBlock {
             avail_virt,
             used_phys,
             used_virt,
-            header_buf: Box::new(BlockRequestHeader {
-                request_type: 0,
-                reserved: 0,
-                sector: 0,
-            }),
-            status_buf: Box::new(RequestStatus::Pending as u8),
-            data_buf: Box::new([0u8; BLOCK_SIZE]),
+            _queue_mem: queue_mem,
+            request_buf,
         })
     }
 
+    /// Fill in the request header and reset the status byte.
+    fn prepare_request(&mut self, request_type: RequestType, sector: u64) {
+        let header = BlockRequestHeader {
+            request_type: request_type as u32,
+            reserved: 0,
+            sector,
+        };
+        let base = self.request_buf.as_mut_ptr();
+        unsafe {
+            ptr::write_volatile(
+                base.add(REQUEST_HEADER_OFFSET) as *mut BlockRequestHeader,
+                header,
+            );
+            ptr::write_volatile(
+                base.add(REQUEST_STATUS_OFFSET),
+                RequestStatus::Pending as u8,
+            );
+        }
+    }
+
+    /// Status byte written back by the device.
+    fn request_status(&self) -> RequestStatus {
+        let status =
+            unsafe { ptr::read_volatile(self.request_buf.as_ptr().add(REQUEST_STATUS_OFFSET)) };
+        RequestStatus::from(status)
+    }
+
+    /// Single-sector data buffer within the request page.
+    fn data_buf(&mut self) -> &mut [u8] {
+        &mut self.request_buf.as_mut_slice()[REQUEST_DATA_OFFSET..REQUEST_DATA_OFFSET + BLOCK_SIZE]
+    }
+
     /// Read device status register.
     fn read_status_raw(io_base: u16) -> u8 {
         let mut port: Port<u8> = Port::new(io_base + legacy_regs::DEVICE_STATUS);
@@ -354,24 +386,16 @@ impl VirtioBlock {
             return Err(RequestStatus::IoErr);
         }
 
-        // Set up request header
-        self.header_buf.request_type = RequestType::In as u32;
-        self.header_buf.reserved = 0;
-        self.header_buf.sector = sector;
-
-        // Reset status
-        *self.status_buf = RequestStatus::Pending as u8;
+        // Set up request header and reset status
+        self.prepare_request(RequestType::In, sector);
 
         // Clear data buffer
-        self.data_buf.fill(0);
+        self.data_buf().fill(0);
 
-        // Translate DMA buffer addresses: virtual → physical.
-        let hdr_virt = &*self.header_buf as *const BlockRequestHeader as u64;
-        let hdr_phys = crate::memory::virt_to_phys(hdr_virt).unwrap_or(hdr_virt);
-        let data_virt = self.data_buf.as_ptr() as u64;
-        let data_phys = crate::memory::virt_to_phys(data_virt).unwrap_or(data_virt);
-        let status_virt = &*self.status_buf as *const u8 as u64;
-        let status_phys = crate::memory::virt_to_phys(status_virt).unwrap_or(status_virt);
+        // DMA addresses of the request parts.
+        let hdr_phys = self.request_buf.phys_at(REQUEST_HEADER_OFFSET);
+        let data_phys = self.request_buf.phys_at(REQUEST_DATA_OFFSET);
+        let status_phys = self.request_buf.phys_at(REQUEST_STATUS_OFFSET);
 
         // Build 3-descriptor chain — CPU writes via VIRTUAL desc base,
         // but addr fields inside descriptors must be PHYSICAL (DMA).
@@ -439,9 +463,9 @@ impl VirtioBlock {
         }
 
         // Check status
-        let status = RequestStatus::from(*self.status_buf);
+        let status = self.request_status();
         if status == RequestStatus::Ok {
-            buffer.copy_from_slice(&*self.data_buf);
+            buffer.copy_from_slice(self.data_buf());
             Ok(())
         } else {
             Err(status)
@@ -458,24 +482,16 @@ impl VirtioBlock {
             return Err(RequestStatus::IoErr);
         }
 
-        // Set up request header
-        self.header_buf.request_type = RequestType::Out as u32;
-        self.header_buf.reserved = 0;
-        self.header_buf.sector = sector;
-
-        // Reset status
-        *self.status_buf = RequestStatus::Pending as u8;
+        // Set up request header and reset status
+        self.prepare_request(RequestType::Out, sector);
 
         // Copy data to our buffer
-        self.data_buf.copy_from_slice(buffer);
+        self.data_buf().copy_from_slice(buffer);
 
-        // Translate DMA buffer addresses: virtual → physical.
-        let hdr_virt = &*self.header_buf as *const BlockRequestHeader as u64;
-        let hdr_phys = crate::memory::virt_to_phys(hdr_virt).unwrap_or(hdr_virt);
-        let data_virt = self.data_buf.as_ptr() as u64;
-        let data_phys = crate::memory::virt_to_phys(data_virt).unwrap_or(data_virt);
-        let status_virt = &*self.status_buf as *const u8 as u64;
-        let status_phys = crate::memory::virt_to_phys(status_virt).unwrap_or(status_virt);
+        // DMA addresses of the request parts.
+        let hdr_phys = self.request_buf.phys_at(REQUEST_HEADER_OFFSET);
+        let data_phys = self.request_buf.phys_at(REQUEST_DATA_OFFSET);
+        let status_phys = self.request_buf.phys_at(REQUEST_STATUS_OFFSET);
 
         // Build 3-descriptor chain — PHYSICAL addresses in descriptors.
         let desc_base = self.desc_virt as *mut VirtqDescRaw;
@@ -534,7 +550,7 @@ impl VirtioBlock {
             core::hint::spin_loop();
         }
 
-        let status = RequestStatus::from(*self.status_buf);
+        let status = self.request_status();
         if status == RequestStatus::Ok {
             Ok(())
         } else {
@@ -607,3 +623,19 @@ pub fn write_sector(device_index: usize, sector: u64, buffer: &[u8; BLOCK_SIZE])
         None => false,
     }
 }
+
+/// Write one 512-byte sector without blocking on the device lock, for
+/// paths such as a panic where the lock may be held by the interrupted
+/// code. Gives up if the lock stays busy for a bounded number of tries.
+pub fn try_write_sector(device_index: usize, sector: u64, buffer: &[u8; BLOCK_SIZE]) -> bool {
+    for _ in 0..1_000_000 {
+        if let Some(mut devices) = VIRTIO_BLOCK_DEVICES.try_lock() {
+            return match devices.get_mut(device_index) {
+                Some(dev) => dev.write_sector(sector, buffer).is_ok(),
+                None => false,
+            };
+        }
+        core::hint::spin_loop();
+    }
+    false
+}
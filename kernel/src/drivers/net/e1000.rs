@@ -196,6 +196,8 @@ pub struct E1000Device {
     link_status: LinkStatus,
     /// Is up
     is_up: bool,
+    /// Multicast addresses in the receive filter (may repeat)
+    multicast: Vec<MacAddress>,
 }
 
 impl E1000Device {
@@ -231,6 +233,7 @@ impl E1000Device {
             stats: NetworkStats::default(),
             link_status: LinkStatus::default(),
             is_up: false,
+            multicast: Vec::new(),
         }
     }
 
@@ -368,6 +371,20 @@ impl E1000Device {
         self.tx_cur = 0;
     }
 
+    /// Rebuild the multicast table array from the joined addresses
+    fn update_mta(&mut self) {
+        let mut table = [0u32; 128];
+        for mac in &self.multicast {
+            // RCTL.MO = 00: hash on address bits [47:36]
+            let bytes = mac.as_bytes();
+            let hash = ((bytes[4] as u32 >> 4) | ((bytes[5] as u32) << 4)) & 0xFFF;
+            table[(hash >> 5) as usize] |= 1 << (hash & 0x1F);
+        }
+        for (i, bits) in table.iter().enumerate() {
+            self.write_reg(regs::MTA + i as u32 * 4, *bits);
+        }
+    }
+
     /// Update link status
     fn update_link_status(&mut self) {
         let status = self.read_reg(regs::STATUS);
@@ -569,13 +586,17 @@ impl NetworkDevice for E1000Device {
         Ok(())
     }
 
-    fn add_multicast(&mut self, _addr: MacAddress) -> Result<(), NetworkError> {
-        // Would update MTA table
+    fn add_multicast(&mut self, addr: MacAddress) -> Result<(), NetworkError> {
+        self.multicast.push(addr);
+        self.update_mta();
         Ok(())
     }
 
-    fn remove_multicast(&mut self, _addr: MacAddress) -> Result<(), NetworkError> {
-        // Would update MTA table
+    fn remove_multicast(&mut self, addr: MacAddress) -> Result<(), NetworkError> {
+        if let Some(pos) = self.multicast.iter().position(|m| *m == addr) {
+            self.multicast.remove(pos);
+            self.update_mta();
+        }
         Ok(())
     }
 
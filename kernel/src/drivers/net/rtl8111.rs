@@ -174,6 +174,8 @@ pub struct Rtl8111Device {
     link_status: LinkStatus,
     /// Is up
     is_up: bool,
+    /// Multicast addresses joined (may repeat)
+    multicast: Vec<MacAddress>,
 }
 
 impl Rtl8111Device {
@@ -204,6 +206,7 @@ impl Rtl8111Device {
             stats: NetworkStats::default(),
             link_status: LinkStatus::default(),
             is_up: false,
+            multicast: Vec::new(),
         }
     }
 
@@ -324,6 +327,20 @@ impl Rtl8111Device {
         self.tx_cur = 0;
     }
 
+    /// Program the multicast hash filter.
+    ///
+    /// Opens the whole filter while any group is joined; the stack drops
+    /// frames for groups nobody joined.
+    fn update_mar(&mut self) {
+        let bits = if self.multicast.is_empty() {
+            0
+        } else {
+            0xFFFF_FFFF
+        };
+        self.write32(regs::MAR0, bits);
+        self.write32(regs::MAR4, bits);
+    }
+
     /// Update link status from PHY
     fn update_link_status(&mut self) {
         let status = self.read8(regs::PHY_STATUS);
@@ -539,12 +556,17 @@ impl NetworkDevice for Rtl8111Device {
         Ok(())
     }
 
-    fn add_multicast(&mut self, _addr: MacAddress) -> Result<(), NetworkError> {
-        // Would update MAR registers
+    fn add_multicast(&mut self, addr: MacAddress) -> Result<(), NetworkError> {
+        self.multicast.push(addr);
+        self.update_mar();
         Ok(())
     }
 
-    fn remove_multicast(&mut self, _addr: MacAddress) -> Result<(), NetworkError> {
+    fn remove_multicast(&mut self, addr: MacAddress) -> Result<(), NetworkError> {
+        if let Some(pos) = self.multicast.iter().position(|m| *m == addr) {
+            self.multicast.remove(pos);
+            self.update_mar();
+        }
         Ok(())
     }
 
@@ -13,6 +13,8 @@ use alloc::vec::Vec;
 use core::ptr;
 use x86_64::instructions::port::Port;
 
+use crate::memory::DmaBuffer;
+
 use super::{
     LinkDuplex, LinkSpeed, LinkStatus, MacAddress, NetworkCapabilities, NetworkDevice,
     NetworkError, NetworkStats, NETWORK_MANAGER,
@@ -160,6 +162,8 @@ struct VirtqRings {
     used_virt: u64,
     /// Queue size reported by device
     queue_size: u16,
+    /// Backing memory, kept alive while the device owns the rings
+    _mem: DmaBuffer,
 }
 
 /// MMIO register offsets (VirtIO MMIO transport v1/v2)
@@ -672,21 +676,16 @@ impl VirtioNetDevice {
         let avail_size = 6 + 2 * qsz as usize;
         let _used_size = 6 + 8 * qsz as usize;
 
-        // Allocate heap memory (4 pages, 16 KiB — plenty for 128-entry queues)
-        // and translate the virtual address to physical for DMA.
+        // Allocate DMA memory (4 pages, 16 KiB — plenty for 128-entry queues).
         //
         // CRITICAL: the legacy VirtIO QUEUE_ADDRESS register takes a physical
-        // page frame number (PFN), so the queue memory must start at a
-        // page-aligned address.  Use Layout with 4096-byte alignment.
-        let layout = alloc::alloc::Layout::from_size_align(4096 * 4, 4096)
-            .expect("[VirtIO Net] Failed to create page-aligned layout");
-        let queue_virt = unsafe { alloc::alloc::alloc_zeroed(layout) } as u64;
-        if queue_virt == 0 {
-            return Err(NetworkError::HardwareError(0xDEAD));
-        }
-
-        let queue_phys = crate::memory::virt_to_phys(queue_virt)
-            .expect("[VirtIO Net] Failed to translate queue memory to physical address");
+        // page frame number (PFN) and the device walks all three rings from
+        // there, so the queue memory must be page-aligned and physically
+        // contiguous.
+        let queue_mem = crate::memory::dma_alloc(4096 * 4, 4096)
+            .map_err(|_| NetworkError::HardwareError(0xDEAD))?;
+        let queue_virt = queue_mem.virt_addr();
+        let queue_phys = queue_mem.phys_addr();
 
         let desc_virt = queue_virt;
         let desc_phys = queue_phys;
@@ -709,6 +708,7 @@ impl VirtioNetDevice {
             used_phys,
             used_virt,
             queue_size: qsz,
+            _mem: queue_mem,
         };
 
         if queue_idx == 0 {
@@ -1212,13 +1212,3 @@ pub fn init_pio(io_base: u16) -> Result<(), NetworkError> {
 pub fn is_initialized() -> bool {
     NETWORK_MANAGER.lock().device_count() > 0
 }
-
-/// Returns the cumulative TX packet count across all registered NICs.
-pub fn tx_packet_count() -> u64 {
-    let mgr = NETWORK_MANAGER.lock();
-    let mut total = 0u64;
-    for dev in mgr.enumerate() {
-        total += dev.stats().tx_packets;
-    }
-    total
-}
@@ -159,6 +159,12 @@ pub fn set_kernel_stack(stack_top: VirtAddr) {
     }
 }
 
+/// Address range of the double fault stack.
+pub fn double_fault_stack() -> core::ops::Range<u64> {
+    let start = core::ptr::addr_of!(DOUBLE_FAULT_STACK) as u64;
+    start..start + DOUBLE_FAULT_STACK_SIZE as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,6 +53,9 @@ pub mod vectors {
     pub const SPURIOUS: u8 = 0xFF;
 }
 
+/// End of the lower (user) half of the address space.
+const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
 /// Timer tick counter.
 static TIMER_TICKS: AtomicU64 = AtomicU64::new(0);
 
@@ -89,13 +92,73 @@ pub fn register_timer_callback(cb: TimerCallback) {
     workqueue::register_timer_callback(cb);
 }
 
+// ==================== Fault Fixups ====================
+//
+// Subsystems that run generated code in Ring 0 (the WASM JIT) register a
+// fixup to turn faults in that code into recoverable errors. Handlers
+// consult it for kernel-mode faults before treating them as fatal.
+
+/// CPU exception passed to a fault fixup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
+    /// Divide error (#DE).
+    DivideError,
+    /// Invalid opcode (#UD).
+    InvalidOpcode,
+    /// General protection fault (#GP).
+    GeneralProtection,
+    /// Page fault (#PF) at the given address.
+    PageFault(u64),
+}
+
+/// Fault fixup type: (fault, faulting RIP) -> address to resume at, or
+/// `None` if the fault is not handled.
+pub type FaultFixup = fn(FaultKind, u64) -> Option<u64>;
+
+static FAULT_FIXUP: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Register the kernel-mode fault fixup, replacing any previous one.
+pub fn register_fault_fixup(fixup: FaultFixup) {
+    FAULT_FIXUP.store(fixup as *mut (), Ordering::Release);
+}
+
+/// Run the registered fixup for a kernel-mode fault.
+///
+/// Returns `true` if the fixup handled the fault; the interrupted
+/// context then resumes at the address it chose.
+fn apply_fault_fixup(kind: FaultKind, stack_frame: &mut InterruptStackFrame) -> bool {
+    let fixup = FAULT_FIXUP.load(Ordering::Acquire);
+    if fixup.is_null() {
+        return false;
+    }
+    // SAFETY: only `register_fault_fixup` stores to FAULT_FIXUP.
+    let fixup: FaultFixup = unsafe { core::mem::transmute(fixup) };
+
+    match fixup(kind, stack_frame.instruction_pointer.as_u64()) {
+        Some(resume) => {
+            // SAFETY: the saved RIP is the first word of the hardware
+            // frame; `iretq` continues at the fixup's resume address.
+            unsafe {
+                core::ptr::write_volatile(
+                    stack_frame as *mut InterruptStackFrame as *mut u64,
+                    resume,
+                );
+            }
+            true
+        }
+        None => false,
+    }
+}
+
 lazy_static! {
     /// The interrupt descriptor table.
     static ref IDT: InterruptDescriptorTable = {
         let mut idt = InterruptDescriptorTable::new();
 
         // CPU Exceptions
+        idt.divide_error.set_handler_fn(divide_error_handler);
         idt.breakpoint.set_handler_fn(breakpoint_handler);
+        idt.invalid_opcode.set_handler_fn(invalid_opcode_handler);
 
         unsafe {
             idt.double_fault
@@ -224,10 +287,24 @@ pub fn hlt_loop() -> ! {
 
 // Exception Handlers
 
+extern "x86-interrupt" fn divide_error_handler(mut stack_frame: InterruptStackFrame) {
+    if apply_fault_fixup(FaultKind::DivideError, &mut stack_frame) {
+        return;
+    }
+    panic!("EXCEPTION: DIVIDE ERROR\n{:#?}", stack_frame);
+}
+
 extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
     crate::serial_println!("EXCEPTION: BREAKPOINT\n{:#?}", stack_frame);
 }
 
+extern "x86-interrupt" fn invalid_opcode_handler(mut stack_frame: InterruptStackFrame) {
+    if apply_fault_fixup(FaultKind::InvalidOpcode, &mut stack_frame) {
+        return;
+    }
+    panic!("EXCEPTION: INVALID OPCODE\n{:#?}", stack_frame);
+}
+
 extern "x86-interrupt" fn double_fault_handler(
     stack_frame: InterruptStackFrame,
     _error_code: u64,
@@ -236,7 +313,7 @@ extern "x86-interrupt" fn double_fault_handler(
 }
 
 extern "x86-interrupt" fn general_protection_fault_handler(
-    stack_frame: InterruptStackFrame,
+    mut stack_frame: InterruptStackFrame,
     error_code: u64,
 ) {
     // Check if the fault originated in Ring 3 (user mode).
@@ -260,6 +337,10 @@ extern "x86-interrupt" fn general_protection_fault_handler(
         }
     }
 
+    if apply_fault_fixup(FaultKind::GeneralProtection, &mut stack_frame) {
+        return;
+    }
+
     panic!(
         "EXCEPTION: GENERAL PROTECTION FAULT (error code: {})\n{:#?}",
         error_code, stack_frame
@@ -267,7 +348,7 @@ extern "x86-interrupt" fn general_protection_fault_handler(
 }
 
 extern "x86-interrupt" fn page_fault_handler(
-    stack_frame: InterruptStackFrame,
+    mut stack_frame: InterruptStackFrame,
     error_code: PageFaultErrorCode,
 ) {
     use x86_64::registers::control::Cr2;
@@ -290,41 +371,34 @@ extern "x86-interrupt" fn page_fault_handler(
         unsafe { core::arch::asm!("swapgs", options(nomem, nostack)); }
     }
 
-    if from_usermode {
-        // Check for Copy-on-Write fault:
-        // A CoW fault is a write (CAUSED_BY_WRITE) to a present page
-        // (PROTECTION_VIOLATION) in user mode, where the PTE has COW_BIT set.
-        let is_cow_candidate = error_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION)
-            && error_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE);
-
-        if is_cow_candidate {
-            // Get the current process's CR3 to walk its page table.
-            let cr3 = x86_64::registers::control::Cr3::read()
-                .0
-                .start_address()
-                .as_u64();
-            let fault_addr = fault_addr_u64;
-
-            // Check if the faulting page has the COW_BIT marker.
-            if let Some((_phys, flags)) = crate::memory::user_page_table::read_pte(cr3, fault_addr)
-            {
-                if flags.contains(crate::memory::user_page_table::COW_BIT) {
-                    // Handle the CoW fault — allocate private copy.
-                    if crate::memory::user_page_table::handle_cow_fault(cr3, fault_addr) {
-                        // CoW handled successfully — restore GS and resume.
-                        // SAFETY: Reverses the entry swapgs for Ring 3 return.
-                        unsafe { core::arch::asm!("swapgs", options(nomem, nostack)); }
-                        return;
-                    }
-                    // CoW handler failed (OOM) — fall through to kill.
-                    crate::serial_println!(
-                        "[FAULT] CoW handler failed at {:?} — killing process",
-                        faulting_address
-                    );
-                }
-            }
+    // Faults on user addresses may be demand paging or copy-on-write:
+    // from user mode, or from the kernel touching user memory during a
+    // syscall.
+    if fault_addr_u64 < USER_SPACE_END {
+        use crate::memory::fault::Access;
+
+        let access = if error_code.contains(PageFaultErrorCode::INSTRUCTION_FETCH) {
+            Access::Execute
+        } else if error_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE) {
+            Access::Write
+        } else {
+            Access::Read
+        };
+        let cr3 = x86_64::registers::control::Cr3::read()
+            .0
+            .start_address()
+            .as_u64();
+
+        if crate::memory::fault::handle_user_fault(cr3, fault_addr_u64, access, from_usermode) {
+            if from_usermode {
+                // SAFETY: Reverses the entry swapgs for Ring 3 return.
+                unsafe { core::arch::asm!("swapgs", options(nomem, nostack)); }
+            }
+            return;
         }
+    }
 
+    if from_usermode {
         crate::serial_println!(
             "[FAULT] User-mode page fault at {:?} (error={:?}, RIP={:#x}) — killing process",
             faulting_address,
@@ -348,6 +422,10 @@ extern "x86-interrupt" fn page_fault_handler(
         );
     }
 
+    if apply_fault_fixup(FaultKind::PageFault(fault_addr_u64), &mut stack_frame) {
+        return;
+    }
+
     crate::serial_println!(
         "EXCEPTION: PAGE FAULT\nAccessed Address: {:?}\nError Code: {:?}\n{:#?}",
         faulting_address,
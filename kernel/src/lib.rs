@@ -31,8 +31,10 @@ pub mod interrupts;
 pub mod io;
 pub mod ipc;
 pub mod loader;
+pub mod log;
 pub mod memory;
 pub mod net;
+pub mod percpu;
 pub mod process;
 pub mod scheduler;
 pub mod security;
@@ -41,6 +43,7 @@ pub mod sync;
 pub mod syscall;
 pub mod terminal;
 pub mod test;
+pub mod time;
 pub mod update;
 pub mod vfs;
 
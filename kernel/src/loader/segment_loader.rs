@@ -8,22 +8,33 @@
 //!
 //! 1. For each PT_LOAD segment in the ELF:
 //!    a. Calculate page-aligned range covering the segment
-//!    b. Allocate physical frames for each page
-//!    c. Copy segment data from the ELF binary to the frames
-//!    d. Zero-fill BSS (memsz > filesz region)
-//!    e. Map frames into the process page table with correct permissions
-//! 2. Set up user stack (8MB below USER_STACK_TOP)
+//!    b. Record a file-backed VMA for it with the segment's permissions;
+//!       the BSS (memsz > filesz region) reads as zeros
+//! 2. Record an anonymous VMA for the user stack (8MB below
+//!    USER_STACK_TOP) and populate its top pages
 //! 3. Initialize heap break pointer
+//!
+//! Segment pages are not mapped here: the page fault handler
+//! ([`crate::memory::fault`]) reads each one from the file on first access.
 
 use super::elf::LoadedProgram;
 use super::program::layout;
+use crate::memory::fault::{self, PROT_EXEC, PROT_READ, PROT_WRITE};
 use crate::memory::user_page_table;
-use x86_64::structures::paging::PageTableFlags;
+use crate::process::table::{FileSource, Vma, VmaBacking};
 
 extern crate alloc;
 use alloc::string::String;
 use alloc::vec::Vec;
 
+/// Linux `MAP_PRIVATE`.
+const MAP_PRIVATE: u32 = 0x02;
+/// Linux `MAP_ANONYMOUS`.
+const MAP_ANONYMOUS: u32 = 0x20;
+
+/// Stack pages populated up front, for the initial stack contents.
+const INITIAL_STACK_PAGES: u64 = 16; // 64KB
+
 /// Errors that can occur during segment loading.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SegmentLoadError {
@@ -60,8 +71,11 @@ pub struct LoadResult {
     pub initial_sp: u64,
     /// Initial heap break (page after last segment)
     pub brk_start: u64,
-    /// Number of pages mapped
+    /// Number of pages mapped up front
     pub pages_mapped: usize,
+    /// VMAs for the segments and the stack, to record in the process's
+    /// memory map so that faults can populate them
+    pub vmas: Vec<Vma>,
 }
 
 /// Load ELF segments into a process's page table.
@@ -72,20 +86,24 @@ pub struct LoadResult {
 ///
 /// * `cr3_phys` - Physical address of the process's P4 page table
 /// * `loaded` - Parsed ELF program information
-/// * `elf_binary` - Raw ELF binary data (to copy segment contents)
+/// * `elf_binary` - Raw ELF binary data (to validate segment bounds)
+/// * `source` - The file segment pages are read from on first access
 /// * `pie_base` - Base address for PIE binaries (0 for non-PIE)
 ///
 /// # Returns
 ///
-/// `LoadResult` containing entry point, stack pointer, and heap break.
+/// `LoadResult` containing entry point, stack pointer, heap break and
+/// the VMAs the caller must record for the process.
 pub fn load_elf_segments(
     cr3_phys: u64,
     loaded: &LoadedProgram,
     elf_binary: &[u8],
+    source: FileSource,
     pie_base: u64,
 ) -> Result<LoadResult, SegmentLoadError> {
     let mut pages_mapped: usize = 0;
     let mut max_vaddr: u64 = 0;
+    let mut vmas: Vec<Vma> = Vec::new();
 
     // Load each PT_LOAD segment
     for segment in &loaded.segments {
@@ -106,74 +124,72 @@ pub fn load_elf_segments(
         let page_start = seg_start & !0xFFF;
         let page_end = (seg_end + 0xFFF) & !0xFFF;
 
-        // Determine page flags from segment permissions
-        let flags = segment_to_page_flags(segment);
+        // Determine protection from segment permissions
+        let prot = segment_to_prot(segment);
 
         // Track maximum address for heap break
         if seg_end > max_vaddr {
             max_vaddr = seg_end;
         }
 
-        // Map each page in the segment's range
-        for page_vaddr in (page_start..page_end).step_by(4096) {
-            // Allocate and map a zeroed page
-            let frame_phys = user_page_table::map_user_page(cr3_phys, page_vaddr, flags)
-                .map_err(|_| SegmentLoadError::OutOfMemory)?;
-
-            // Calculate what part of the file data falls on this page
-            let page_data_start = page_vaddr;
-            let page_data_end = page_vaddr + 4096;
-
-            // File data range for this segment
-            let file_data_start = seg_start;
-            let file_data_end = seg_start + segment.file_size;
-
-            // Calculate overlap between this page and file data
-            let copy_start = core::cmp::max(page_data_start, file_data_start);
-            let copy_end = core::cmp::min(page_data_end, file_data_end);
-
-            if copy_start < copy_end {
-                // There's file data to copy to this page
-                let offset_in_page = (copy_start - page_vaddr) as usize;
-                let offset_in_segment = (copy_start - seg_start) as usize;
-                let file_offset = segment.file_offset as usize + offset_in_segment;
-                let copy_len = (copy_end - copy_start) as usize;
-
-                // Validate source bounds
-                if file_offset + copy_len > elf_binary.len() {
-                    return Err(SegmentLoadError::SegmentOutOfBounds);
-                }
-
-                let src_data = &elf_binary[file_offset..file_offset + copy_len];
-
-                // Write data directly to the physical frame via offset mapping
-                unsafe {
-                    user_page_table::write_to_phys(frame_phys, offset_in_page, src_data);
-                }
-            }
-            // Pages beyond file_size but within mem_size are BSS (already zeroed)
+        // Validate source bounds
+        let file_end = segment.file_offset.checked_add(segment.file_size);
+        if file_end.is_none_or(|end| end > elf_binary.len() as u64) {
+            return Err(SegmentLoadError::SegmentOutOfBounds);
+        }
 
-            pages_mapped += 1;
+        // Segments sharing a page would need one frame filled from both
+        if vmas
+            .iter()
+            .any(|vma| vma.start < page_end && vma.end > page_start)
+        {
+            return Err(SegmentLoadError::InvalidAddress);
         }
+
+        // The first page starts `lead` bytes before the segment; like the
+        // segment's address, its file offset is congruent modulo the page
+        // size, so the page maps the file from `file_offset - lead`
+        let lead = seg_start - page_start;
+        let backing = if segment.file_size == 0 {
+            VmaBacking::Anonymous
+        } else {
+            VmaBacking::File {
+                source: source.clone(),
+                offset: segment
+                    .file_offset
+                    .checked_sub(lead)
+                    .ok_or(SegmentLoadError::SegmentOutOfBounds)?,
+                file_size: lead + segment.file_size,
+            }
+        };
+
+        vmas.push(Vma {
+            start: page_start,
+            end: page_end,
+            prot,
+            flags: MAP_PRIVATE,
+            backing,
+        });
     }
 
-    // Set up user stack
+    // Set up user stack: the whole region is reserved, and faults grow
+    // it into pages below the initially populated ones
     let stack_top = layout::USER_STACK_TOP;
     let stack_bottom = stack_top - layout::USER_STACK_SIZE;
-    let stack_flags = PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
-
-    // Map stack pages (guard page at bottom could be added later)
-    // For now, map the entire stack region
-    // Note: 8MB = 2048 pages. For initial implementation, map a smaller
-    // pre-committed region and let page faults expand it.
-    let initial_stack_pages = 16; // 64KB initial committed stack
-    let initial_stack_bottom = stack_top - (initial_stack_pages * 4096);
-
-    for page_addr in (initial_stack_bottom..stack_top).step_by(4096) {
-        user_page_table::map_user_page(cr3_phys, page_addr, stack_flags)
-            .map_err(|_| SegmentLoadError::OutOfMemory)?;
-        pages_mapped += 1;
-    }
+    let stack = Vma {
+        start: stack_bottom,
+        end: stack_top,
+        prot: PROT_READ | PROT_WRITE,
+        flags: MAP_PRIVATE | MAP_ANONYMOUS,
+        backing: VmaBacking::Anonymous,
+    };
+
+    // The initial stack contents are written through the page table, so
+    // those pages must exist now
+    let initial_stack_bottom = stack_top - INITIAL_STACK_PAGES * 4096;
+    pages_mapped += fault::populate(cr3_phys, &stack, initial_stack_bottom, stack_top)
+        .map_err(|_| SegmentLoadError::OutOfMemory)?;
+    vmas.push(stack);
 
     // Calculate entry point (adjusted for PIE)
     let entry_point = if loaded.is_pie {
@@ -190,37 +206,37 @@ pub fn load_elf_segments(
         initial_sp: stack_top,
         brk_start,
         pages_mapped,
+        vmas,
     })
 }
 
-/// Convert ELF segment flags to x86_64 page table flags.
+/// Convert ELF segment flags to Linux `PROT_*` flags.
 ///
 /// Enforces W^X: a page cannot be both writable and executable.
-fn segment_to_page_flags(
-    segment: &super::elf::LoadSegment,
-) -> PageTableFlags {
-    let mut flags = PageTableFlags::empty();
+fn segment_to_prot(segment: &super::elf::LoadSegment) -> u32 {
+    let mut prot = 0;
 
+    if segment.is_readable() {
+        prot |= PROT_READ;
+    }
     if segment.is_writable() {
-        flags |= PageTableFlags::WRITABLE;
+        prot |= PROT_WRITE;
     }
-
-    // NX (No Execute) bit: set if segment is NOT executable
-    if !segment.is_executable() {
-        flags |= PageTableFlags::NO_EXECUTE;
+    if segment.is_executable() {
+        prot |= PROT_EXEC;
     }
 
     // W^X enforcement: if both W and X, prefer X (remove W)
     if segment.is_writable() && segment.is_executable() {
-        // Safety: remove WRITABLE to enforce W^X
-        flags.remove(PageTableFlags::WRITABLE);
+        // Safety: remove PROT_WRITE to enforce W^X
+        prot &= !PROT_WRITE;
         crate::serial_println!(
             "[KPIO] Warning: W^X enforcement - segment at {:#x} has W+X, removing W",
             segment.vaddr
         );
     }
 
-    flags
+    prot
 }
 
 /// Push the initial stack contents for a Linux process.
@@ -437,6 +453,7 @@ fn translate_user_vaddr(cr3_phys: u64, virt_addr: u64) -> Option<u64> {
 mod tests {
     use super::*;
     use crate::loader::elf::LoadSegment;
+    use x86_64::structures::paging::PageTableFlags;
 
     #[test]
     fn test_segment_flags_readable_only() {
@@ -448,7 +465,7 @@ mod tests {
             flags: 4, // PF_R only
             align: 0x1000,
         };
-        let flags = segment_to_page_flags(&seg);
+        let flags = fault::prot_to_page_flags(segment_to_prot(&seg));
         assert!(!flags.contains(PageTableFlags::WRITABLE));
         assert!(flags.contains(PageTableFlags::NO_EXECUTE));
     }
@@ -463,7 +480,7 @@ mod tests {
             flags: 5, // PF_R | PF_X
             align: 0x1000,
         };
-        let flags = segment_to_page_flags(&seg);
+        let flags = fault::prot_to_page_flags(segment_to_prot(&seg));
         assert!(!flags.contains(PageTableFlags::WRITABLE));
         assert!(!flags.contains(PageTableFlags::NO_EXECUTE));
     }
@@ -478,7 +495,7 @@ mod tests {
             flags: 6, // PF_R | PF_W
             align: 0x1000,
         };
-        let flags = segment_to_page_flags(&seg);
+        let flags = fault::prot_to_page_flags(segment_to_prot(&seg));
         assert!(flags.contains(PageTableFlags::WRITABLE));
         assert!(flags.contains(PageTableFlags::NO_EXECUTE));
     }
@@ -494,7 +511,7 @@ mod tests {
             flags: 7, // PF_R | PF_W | PF_X
             align: 0x1000,
         };
-        let flags = segment_to_page_flags(&seg);
+        let flags = fault::prot_to_page_flags(segment_to_prot(&seg));
         // W^X: should NOT be writable if executable
         assert!(!flags.contains(PageTableFlags::WRITABLE));
         assert!(!flags.contains(PageTableFlags::NO_EXECUTE));
@@ -0,0 +1,409 @@
+//! Kernel log ring buffer.
+//!
+//! Leveled, tagged messages are kept in a fixed-size in-memory ring so
+//! they can be read back after the fact (`dmesg`, `/proc/kmsg`), and
+//! mirrored to the serial console as `[TAG] message`.
+//!
+//! Recording never allocates: the ring is a static array and messages
+//! are formatted into a fixed buffer (longer ones are truncated). This
+//! makes the log usable before the heap is initialised.
+//!
+//! Timestamps come from [`crate::time::monotonic`], so records written
+//! before `time::init` count from TSC reset rather than from boot.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use spin::Mutex;
+
+use crate::time::Timespec;
+
+/// Number of records kept; older ones are overwritten.
+pub const LOG_CAPACITY: usize = 256;
+
+/// Maximum message length in bytes.
+pub const MAX_MESSAGE_LEN: usize = 160;
+
+/// Tag used by the untagged logging macros.
+pub const DEFAULT_TAG: &str = "KPIO";
+
+/// Log levels, from most to least verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Trace level (most verbose).
+    Trace = 0,
+    /// Debug level.
+    Debug = 1,
+    /// Info level.
+    Info = 2,
+    /// Warning level.
+    Warn = 3,
+    /// Error level.
+    Error = 4,
+}
+
+impl Level {
+    /// Lowercase name, as shown by `dmesg`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+
+    /// Parse a level name (case-insensitive, `warning` and `err` accepted).
+    pub fn parse(name: &str) -> Option<Level> {
+        match name.to_ascii_lowercase().as_str() {
+            "trace" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" | "warning" => Some(Level::Warn),
+            "error" | "err" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+/// A record read back from the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// Sequence number, increasing from 0 since boot.
+    pub seq: u64,
+    /// Time the record was written.
+    pub timestamp: Timespec,
+    pub level: Level,
+    /// Subsystem tag, e.g. `NET` or `VFS`.
+    pub tag: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    /// Format as a `dmesg` line: `[    1.234567] warn  NET: message`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:>5}.{:06}] {:<5} {}: {}",
+            self.timestamp.secs,
+            self.timestamp.subsec_micros(),
+            self.level.name(),
+            self.tag,
+            self.message
+        )
+    }
+}
+
+/// Selects which records [`read`] returns.
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    /// Only records at or above this level.
+    pub min_level: Level,
+    /// Only records with this tag (case-insensitive).
+    pub tag: Option<String>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter {
+            min_level: Level::Trace,
+            tag: None,
+        }
+    }
+}
+
+impl LogFilter {
+    /// Check if a record with the given level and tag passes the filter.
+    pub fn matches(&self, level: Level, tag: &str) -> bool {
+        level >= self.min_level
+            && self
+                .tag
+                .as_deref()
+                .is_none_or(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// Fixed-size message text, truncated on a character boundary.
+#[derive(Clone, Copy)]
+struct Message {
+    buf: [u8; MAX_MESSAGE_LEN],
+    len: usize,
+}
+
+impl Message {
+    const EMPTY: Message = Message {
+        buf: [0; MAX_MESSAGE_LEN],
+        len: 0,
+    };
+
+    fn as_str(&self) -> &str {
+        // Only whole characters are ever copied in
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl fmt::Write for Message {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut take = s.len().min(MAX_MESSAGE_LEN - self.len);
+        while !s.is_char_boundary(take) {
+            take -= 1;
+        }
+        self.buf[self.len..self.len + take].copy_from_slice(&s.as_bytes()[..take]);
+        self.len += take;
+        Ok(())
+    }
+}
+
+/// One stored record.
+#[derive(Clone, Copy)]
+struct Slot {
+    seq: u64,
+    timestamp: Timespec,
+    level: Level,
+    tag: &'static str,
+    message: Message,
+}
+
+impl Slot {
+    const EMPTY: Slot = Slot {
+        seq: 0,
+        timestamp: Timespec { secs: 0, nanos: 0 },
+        level: Level::Trace,
+        tag: "",
+        message: Message::EMPTY,
+    };
+
+    fn record(&self) -> LogRecord {
+        LogRecord {
+            seq: self.seq,
+            timestamp: self.timestamp,
+            level: self.level,
+            tag: self.tag,
+            message: String::from(self.message.as_str()),
+        }
+    }
+}
+
+/// Ring of the most recent records.
+struct LogRing {
+    slots: [Slot; LOG_CAPACITY],
+    /// Next write position.
+    head: usize,
+    /// Number of valid slots.
+    len: usize,
+    /// Sequence number of the next record.
+    next_seq: u64,
+}
+
+impl LogRing {
+    const fn new() -> Self {
+        LogRing {
+            slots: [Slot::EMPTY; LOG_CAPACITY],
+            head: 0,
+            len: 0,
+            next_seq: 0,
+        }
+    }
+
+    fn push(&mut self, timestamp: Timespec, level: Level, tag: &'static str, message: Message) {
+        self.slots[self.head] = Slot {
+            seq: self.next_seq,
+            timestamp,
+            level,
+            tag,
+            message,
+        };
+        self.next_seq += 1;
+        self.head = (self.head + 1) % LOG_CAPACITY;
+        self.len = (self.len + 1).min(LOG_CAPACITY);
+    }
+
+    /// Stored slots, oldest first.
+    fn iter(&self) -> impl Iterator<Item = &Slot> {
+        let start = (self.head + LOG_CAPACITY - self.len) % LOG_CAPACITY;
+        (0..self.len).map(move |i| &self.slots[(start + i) % LOG_CAPACITY])
+    }
+
+    fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+}
+
+/// The kernel log.
+static LOG: Mutex<LogRing> = Mutex::new(LogRing::new());
+
+/// Minimum level mirrored to the serial console.
+static CONSOLE_LEVEL: Mutex<Level> = Mutex::new(Level::Info);
+
+/// Set the minimum level mirrored to the serial console. All levels are
+/// recorded in the ring regardless.
+pub fn set_console_level(level: Level) {
+    *CONSOLE_LEVEL.lock() = level;
+}
+
+/// Get the minimum level mirrored to the serial console.
+pub fn console_level() -> Level {
+    *CONSOLE_LEVEL.lock()
+}
+
+/// Record a message and mirror it to the serial console.
+pub fn write(level: Level, tag: &'static str, args: fmt::Arguments) {
+    // Format before taking the lock: `args` may itself log.
+    let mut message = Message::EMPTY;
+    let _ = message.write_fmt(args);
+    let timestamp = crate::time::monotonic();
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        LOG.lock().push(timestamp, level, tag, message);
+    });
+
+    if level >= console_level() {
+        crate::serial_println!("[{}] {}", tag, args);
+    }
+}
+
+/// Read the records passing `filter`, oldest first.
+pub fn read(filter: &LogFilter) -> Vec<LogRecord> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        LOG.lock()
+            .iter()
+            .filter(|slot| filter.matches(slot.level, slot.tag))
+            .map(Slot::record)
+            .collect()
+    })
+}
+
+/// Discard all records. Sequence numbers keep counting.
+pub fn clear() {
+    x86_64::instructions::interrupts::without_interrupts(|| LOG.lock().clear());
+}
+
+/// Log macros. An optional `target:` sets the subsystem tag, which
+/// otherwise defaults to [`DEFAULT_TAG`]:
+///
+/// ```ignore
+/// info!(target: "NET", "link up on {}", name);
+/// ```
+#[macro_export]
+macro_rules! trace {
+    (target: $tag:expr, $($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Trace, $tag, format_args!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Trace, $crate::log::DEFAULT_TAG, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    (target: $tag:expr, $($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Debug, $tag, format_args!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Debug, $crate::log::DEFAULT_TAG, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    (target: $tag:expr, $($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Info, $tag, format_args!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Info, $crate::log::DEFAULT_TAG, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    (target: $tag:expr, $($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Warn, $tag, format_args!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Warn, $crate::log::DEFAULT_TAG, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    (target: $tag:expr, $($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Error, $tag, format_args!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Error, $crate::log::DEFAULT_TAG, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(text: &str) -> Message {
+        let mut message = Message::EMPTY;
+        message.write_str(text).unwrap();
+        message
+    }
+
+    #[test]
+    fn test_ring_wraps_oldest_first() {
+        let mut ring = LogRing::new();
+        for i in 0..LOG_CAPACITY + 3 {
+            let text = alloc::format!("{}", i);
+            ring.push(Timespec::default(), Level::Info, "T", message(&text));
+        }
+        let seqs: Vec<u64> = ring.iter().map(|s| s.seq).collect();
+        assert_eq!(seqs.len(), LOG_CAPACITY);
+        assert_eq!(seqs[0], 3);
+        assert_eq!(*seqs.last().unwrap(), LOG_CAPACITY as u64 + 2);
+
+        ring.clear();
+        assert_eq!(ring.iter().count(), 0);
+        ring.push(Timespec::default(), Level::Info, "T", message("x"));
+        assert_eq!(ring.iter().next().unwrap().seq, LOG_CAPACITY as u64 + 3);
+    }
+
+    #[test]
+    fn test_message_truncates_on_char_boundary() {
+        let mut text = String::from("a");
+        for _ in 0..MAX_MESSAGE_LEN {
+            text.push('é');
+        }
+        let m = message(&text);
+        assert_eq!(m.len, MAX_MESSAGE_LEN - 1);
+        assert!(m.as_str().ends_with('é'));
+    }
+
+    #[test]
+    fn test_filter_by_level_and_tag() {
+        let filter = LogFilter {
+            min_level: Level::Warn,
+            tag: Some(String::from("net")),
+        };
+        assert!(filter.matches(Level::Error, "NET"));
+        assert!(!filter.matches(Level::Info, "NET"));
+        assert!(!filter.matches(Level::Error, "VFS"));
+        assert!(LogFilter::default().matches(Level::Trace, "VFS"));
+        assert_eq!(Level::parse("WARNING"), Some(Level::Warn));
+        assert_eq!(Level::parse("loud"), None);
+    }
+
+    #[test]
+    fn test_record_display() {
+        let record = LogRecord {
+            seq: 0,
+            timestamp: Timespec {
+                secs: 12,
+                nanos: 345_678_000,
+            },
+            level: Level::Warn,
+            tag: "NET",
+            message: String::from("link down"),
+        };
+        assert_eq!(
+            alloc::format!("{}", record),
+            "[   12.345678] warn  NET: link down"
+        );
+    }
+}
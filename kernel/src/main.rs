@@ -31,6 +31,7 @@ extern crate alloc;
 
 mod allocator;
 mod app;
+mod crash;
 mod driver;
 mod drivers;
 mod gdt;
@@ -39,14 +40,17 @@ mod gui;
 mod hw;
 mod interrupts;
 mod loader;
+mod log;
 mod memory;
 mod net;
 mod panic;
+mod percpu;
 mod process;
 mod scheduler;
 mod serial;
 mod sync;
 mod terminal;
+mod time;
 mod vfs;
 mod wasm;
 
@@ -74,6 +78,14 @@ entry_point!(kernel_main, config = &BOOTLOADER_CONFIG);
 /// - Physical memory mapping at configurable offset
 /// - Page tables in higher-half kernel space
 fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
+    // Record the boot stack so crash backtraces stay within it
+    let boot_rsp: u64;
+    // SAFETY: reads the stack pointer register without side effects.
+    unsafe {
+        core::arch::asm!("mov {}, rsp", out(reg) boot_rsp, options(nomem, nostack, preserves_flags));
+    }
+    crash::set_boot_stack(boot_rsp, BOOTLOADER_CONFIG.kernel_stack_size);
+
     // Phase 1: Serial console initialization
     serial::init();
     serial_println!("Hello, Kernel");
@@ -106,17 +118,20 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
     }
 
     // Phase 2: GDT initialization (required before IDT for TSS)
-    serial_println!("[KPIO] Initializing GDT...");
+    info!(target: "KPIO", "Initializing GDT...");
     gdt::init();
-    serial_println!("[KPIO] GDT initialized");
+    info!(target: "KPIO", "GDT initialized");
 
     // Phase 3: IDT initialization
-    serial_println!("[KPIO] Initializing IDT...");
+    info!(target: "KPIO", "Initializing IDT...");
     interrupts::init();
-    serial_println!("[KPIO] IDT initialized");
+    info!(target: "KPIO", "IDT initialized");
+
+    // Phase 3.5: Wall clock (RTC anchor + TSC calibration)
+    time::init();
 
     // Phase 4: Memory management initialization
-    serial_println!("[KPIO] Initializing memory management...");
+    info!(target: "KPIO", "Initializing memory management...");
 
     let phys_mem_offset = boot_info.physical_memory_offset.into_option().expect(
         "Physical memory offset not provided by bootloader. \
@@ -125,17 +140,17 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
 
     // Validate physical memory offset before use
     memory::validate_physical_memory_offset(phys_mem_offset);
-    serial_println!("[KPIO] Physical memory offset: {:#x}", phys_mem_offset);
+    info!(target: "KPIO", "Physical memory offset: {:#x}", phys_mem_offset);
 
     let mut mapper = unsafe { memory::init(phys_mem_offset) };
     let mut frame_allocator =
         unsafe { memory::BootInfoFrameAllocator::new(boot_info.memory_regions.into_iter()) };
-    serial_println!("[KPIO] Page mapper and frame allocator initialized");
+    info!(target: "KPIO", "Page mapper and frame allocator initialized");
 
     // Phase 5: Heap initialization
-    serial_println!("[KPIO] Initializing heap...");
+    info!(target: "KPIO", "Initializing heap...");
     allocator::init_heap(&mut mapper, &mut frame_allocator).expect("heap initialization failed");
-    serial_println!("[KPIO] Heap initialized");
+    info!(target: "KPIO", "Heap initialized");
 
     // Phase 5.1: Global frame allocator initialization
     // Reserve a pool of physical frames for slab/buddy/user-page-table allocators.
@@ -160,8 +175,9 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
         let pool_start = ((best_start + best_size / 2) + 4095) & !4095;
         let pool_end = best_start + best_size;
         memory::init_frame_allocator(pool_start, pool_end);
-        serial_println!(
-            "[MEM] Global frame pool: {:#x}..{:#x} ({} KiB)",
+        info!(
+            target: "MEM",
+            "Global frame pool: {:#x}..{:#x} ({} KiB)",
             pool_start,
             pool_end,
             (pool_end - pool_start) / 1024
@@ -184,44 +200,51 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
             recycled == frame,
             "free_frame self-test failed: recycled frame mismatch"
         );
-        serial_println!("[MEM] Frame recycling self-test passed (free+realloc OK)");
+        info!(target: "MEM", "Frame recycling self-test passed (free+realloc OK)");
     }
 
     // Phase 5.3: User-space page table allocator
-    serial_println!("[KPIO] Initializing user page table allocator...");
+    info!(target: "KPIO", "Initializing user page table allocator...");
     memory::user_page_table::init(phys_mem_offset);
-    serial_println!("[KPIO] User page table allocator initialized");
+    info!(target: "KPIO", "User page table allocator initialized");
 
     // Phase 6: Scheduler initialization
-    serial_println!("[KPIO] Initializing scheduler...");
+    info!(target: "KPIO", "Initializing scheduler...");
     scheduler::init();
-    serial_println!("[KPIO] Scheduler initialized");
+    info!(target: "KPIO", "Scheduler initialized");
 
     // Phase 6.1: User-space SYSCALL/SYSRET MSR setup
-    serial_println!("[KPIO] Initializing Ring 3 userspace support...");
+    info!(target: "KPIO", "Initializing Ring 3 userspace support...");
     scheduler::userspace::init();
-    serial_println!("[KPIO] Ring 3 support initialized (STAR/LSTAR/SFMASK + PerCPU)");
+    info!(target: "KPIO", "Ring 3 support initialized (STAR/LSTAR/SFMASK + PerCPU)");
+
+    // Phase 6.1a: Per-CPU variables and kernel TLS self-test
+    if let Err(e) = percpu::self_test() {
+        panic!("per-CPU self-test failed: {}", e);
+    }
+    info!(target: "PERCPU", "Self-test passed (per-CPU counters and task TLS isolated)");
 
     // Phase 6.2: Process table initialization
-    serial_println!("[KPIO] Initializing process table...");
+    info!(target: "KPIO", "Initializing process table...");
     // Process table init is called from lib; scheduler already handles tasks.
-    serial_println!("[KPIO] Process table initialized");
+    info!(target: "KPIO", "Process table initialized");
 
     // Phase 6.5: Terminal filesystem & shell
-    serial_println!("[KPIO] Initializing terminal subsystem...");
+    info!(target: "KPIO", "Initializing terminal subsystem...");
     terminal::fs::init();
     terminal::shell::init();
-    serial_println!("[KPIO] Terminal subsystem ready (50+ commands)");
+    info!(target: "KPIO", "Terminal subsystem ready (50+ commands)");
 
     // Phase 6.6: VFS & file descriptor table
-    serial_println!("[KPIO] Initializing VFS...");
+    info!(target: "KPIO", "Initializing VFS...");
     vfs::fd::init();
-    serial_println!("[KPIO] VFS initialized (fd table ready)");
+    let _ = vfs::procfs::mount(vfs::procfs::DEFAULT_MOUNT_POINT);
+    info!(target: "KPIO", "VFS initialized (fd table ready, procfs at /proc)");
 
     // Phase 7: APIC initialization (Phase 1 feature)
-    serial_println!("[KPIO] Initializing APIC...");
+    info!(target: "KPIO", "Initializing APIC...");
     unsafe { interrupts::init_apic(phys_mem_offset) };
-    serial_println!("[KPIO] APIC initialized");
+    info!(target: "KPIO", "APIC initialized");
 
     // Phase 8: PCI enumeration (moved before ACPI — basic PCI config-space
     // access via CF8/CFC works without ACPI tables, and ACPI parsing has a
@@ -264,6 +287,23 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
                             device_name
                         );
 
+                        // Surface a crash dump left by the previous boot
+                        if let Some(report) = crash::load_previous_crash() {
+                            serial_println!(
+                                "[CRASH] Previous boot crashed: {}",
+                                crash::crash_summary(&report)
+                            );
+                            serial_println!(
+                                "[CRASH] Full report saved to {}",
+                                crash::LAST_CRASH_PATH
+                            );
+                        }
+
+                        // Reserve the dump file a panic writes to
+                        if let Err(e) = crash::arm_persistent_dump(storage_dev_idx, 0) {
+                            serial_println!("[CRASH] Crash dumps disabled: {:?}", e);
+                        }
+
                         // Self-test 1: read a file
                         match storage::vfs::open("/mnt/test/HELLO.TXT", storage::OpenFlags::READ) {
                             Ok(fd) => {
@@ -520,7 +560,7 @@ fn kernel_main(boot_info: &'static mut BootInfo) -> ! {
         }
 
         // Check 5: Network TX counter (packets were transmitted during DHCP)
-        let tx_count = drivers::net::virtio_net::tx_packet_count();
+        let tx_count = net::stats().tx_packets();
         if tx_count > 0 {
             serial_println!("[E2E] Network TX: OK ({} packets)", tx_count);
         } else {
@@ -0,0 +1,287 @@
+//! DMA buffer allocation for device drivers.
+//!
+//! Devices address memory physically, so descriptor rings and data
+//! buffers handed to them must be physically contiguous and their
+//! physical address must be known. Heap memory is only virtually
+//! contiguous: a multi-page `Box` or `alloc_zeroed` block may straddle
+//! unrelated frames, and translating its address page by page with
+//! [`virt_to_phys`](super::virt_to_phys) is easy to get wrong.
+//!
+//! [`dma_alloc`] hands out zeroed, page-granular runs of contiguous
+//! frames. The CPU accesses them through the kernel's direct physical
+//! memory mapping, which is write-back cacheable. On x86_64 that is the
+//! correct attribute for DMA memory: PCI devices snoop the CPU caches, so
+//! write-back buffers are coherent without explicit flushes. Drivers
+//! still need volatile accesses and fences to order their writes against
+//! device notifications.
+//!
+//! Freed runs go to a DMA pool and are reused by later allocations before
+//! fresh frames are taken from the global frame allocator.
+
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use super::PAGE_SIZE;
+
+/// Largest supported allocation (4 MiB).
+pub const MAX_DMA_SIZE: usize = 4 * 1024 * 1024;
+
+/// DMA allocation errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmaError {
+    /// Size was zero or larger than [`MAX_DMA_SIZE`].
+    InvalidSize,
+    /// Alignment was not a power of two.
+    InvalidAlignment,
+    /// No contiguous run of frames was available.
+    OutOfMemory,
+}
+
+impl core::fmt::Display for DmaError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DmaError::InvalidSize => write!(f, "invalid DMA buffer size"),
+            DmaError::InvalidAlignment => write!(f, "DMA alignment is not a power of two"),
+            DmaError::OutOfMemory => write!(f, "out of contiguous physical memory"),
+        }
+    }
+}
+
+/// A physically contiguous, zero-initialized buffer for device DMA.
+///
+/// The buffer is returned to the DMA pool when dropped or passed to
+/// [`dma_free`]; the device must no longer be using it by then.
+#[derive(Debug)]
+pub struct DmaBuffer {
+    /// Kernel virtual address (direct physical mapping).
+    virt: u64,
+    /// Physical address, as programmed into the device.
+    phys: u64,
+    /// Number of 4 KiB frames backing the buffer.
+    pages: usize,
+    /// Requested size in bytes.
+    size: usize,
+}
+
+impl DmaBuffer {
+    /// Physical address of the start of the buffer.
+    pub fn phys_addr(&self) -> u64 {
+        self.phys
+    }
+
+    /// Physical address of the byte at `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is past the end of the buffer.
+    pub fn phys_at(&self, offset: usize) -> u64 {
+        assert!(offset <= self.size, "DMA offset {:#x} out of range", offset);
+        self.phys + offset as u64
+    }
+
+    /// Kernel virtual address of the start of the buffer.
+    pub fn virt_addr(&self) -> u64 {
+        self.virt
+    }
+
+    /// CPU pointer to the start of the buffer.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.virt as *const u8
+    }
+
+    /// Mutable CPU pointer to the start of the buffer.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.virt as *mut u8
+    }
+
+    /// Requested size in bytes.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Check if the buffer is empty (never true for allocated buffers).
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Size in bytes rounded up to whole pages.
+    pub fn capacity(&self) -> usize {
+        self.pages * PAGE_SIZE
+    }
+
+    /// View the buffer as a byte slice.
+    ///
+    /// Contents the device is writing concurrently should be read with
+    /// volatile accesses instead.
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: the buffer owns `size` mapped bytes at `virt`.
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.size) }
+    }
+
+    /// View the buffer as a mutable byte slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: the buffer owns `size` mapped bytes at `virt`.
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr(), self.size) }
+    }
+}
+
+impl Drop for DmaBuffer {
+    fn drop(&mut self) {
+        DMA_POOL.lock().release(self.phys, self.pages);
+    }
+}
+
+/// Free runs of contiguous frames previously used for DMA.
+struct DmaPool {
+    /// `(physical address, frame count)`, sorted by address, never adjacent.
+    free: Vec<(u64, usize)>,
+}
+
+impl DmaPool {
+    const fn new() -> Self {
+        Self { free: Vec::new() }
+    }
+
+    /// Take `pages` contiguous frames aligned to `align` from a free run.
+    fn take(&mut self, pages: usize, align: u64) -> Option<u64> {
+        let len = (pages * PAGE_SIZE) as u64;
+        for i in 0..self.free.len() {
+            let (start, count) = self.free[i];
+            let end = start + (count * PAGE_SIZE) as u64;
+            let aligned = (start + align - 1) & !(align - 1);
+            if aligned + len > end {
+                continue;
+            }
+
+            // Split the run around the allocation
+            self.free.remove(i);
+            if aligned + len < end {
+                let rest = ((end - aligned - len) as usize) / PAGE_SIZE;
+                self.free.insert(i, (aligned + len, rest));
+            }
+            if aligned > start {
+                let head = ((aligned - start) as usize) / PAGE_SIZE;
+                self.free.insert(i, (start, head));
+            }
+            return Some(aligned);
+        }
+        None
+    }
+
+    /// Return a run of frames, merging it with adjacent free runs.
+    fn release(&mut self, phys: u64, pages: usize) {
+        let i = self.free.partition_point(|&(start, _)| start < phys);
+        self.free.insert(i, (phys, pages));
+
+        // Merge with the following run
+        if i + 1 < self.free.len() {
+            let (next, next_pages) = self.free[i + 1];
+            if phys + (pages * PAGE_SIZE) as u64 == next {
+                self.free[i].1 += next_pages;
+                self.free.remove(i + 1);
+            }
+        }
+        // Merge with the preceding run
+        if i > 0 {
+            let (prev, prev_pages) = self.free[i - 1];
+            if prev + (prev_pages * PAGE_SIZE) as u64 == phys {
+                self.free[i - 1].1 += self.free[i].1;
+                self.free.remove(i);
+            }
+        }
+    }
+
+    /// Total frames held by the pool.
+    fn free_pages(&self) -> usize {
+        self.free.iter().map(|&(_, count)| count).sum()
+    }
+}
+
+static DMA_POOL: Mutex<DmaPool> = Mutex::new(DmaPool::new());
+
+/// Allocate a zeroed, physically contiguous DMA buffer.
+///
+/// The buffer spans whole pages and its physical address is aligned to
+/// `align` (at least 4 KiB).
+pub fn dma_alloc(size: usize, align: usize) -> Result<DmaBuffer, DmaError> {
+    if size == 0 || size > MAX_DMA_SIZE {
+        return Err(DmaError::InvalidSize);
+    }
+    if !align.is_power_of_two() {
+        return Err(DmaError::InvalidAlignment);
+    }
+    let align = align.max(PAGE_SIZE);
+    let pages = size.div_ceil(PAGE_SIZE);
+
+    let pooled = DMA_POOL.lock().take(pages, align as u64);
+    let phys = match pooled {
+        Some(phys) => phys,
+        None => {
+            super::allocate_contiguous_frames(pages, align).ok_or(DmaError::OutOfMemory)? as u64
+        }
+    };
+
+    let virt = super::user_page_table::get_phys_offset() + phys;
+    // SAFETY: the frames were just taken from a free pool and are mapped
+    // through the direct physical memory mapping.
+    unsafe { core::ptr::write_bytes(virt as *mut u8, 0, pages * PAGE_SIZE) };
+
+    Ok(DmaBuffer {
+        virt,
+        phys,
+        pages,
+        size,
+    })
+}
+
+/// Free a DMA buffer.
+///
+/// Equivalent to dropping it; provided so driver teardown reads
+/// symmetrically with [`dma_alloc`].
+pub fn dma_free(buffer: DmaBuffer) {
+    drop(buffer);
+}
+
+/// Number of frames waiting in the DMA pool for reuse.
+pub fn pooled_pages() -> usize {
+    DMA_POOL.lock().free_pages()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P: u64 = PAGE_SIZE as u64;
+
+    #[test]
+    fn test_pool_release_merges_neighbours() {
+        let mut pool = DmaPool::new();
+        pool.release(0x10_0000, 1);
+        pool.release(0x10_0000 + 2 * P, 1);
+        assert_eq!(pool.free.len(), 2);
+
+        // Fills the hole between both runs
+        pool.release(0x10_0000 + P, 1);
+        assert_eq!(pool.free, alloc::vec![(0x10_0000, 3)]);
+        assert_eq!(pool.free_pages(), 3);
+    }
+
+    #[test]
+    fn test_pool_take_aligned() {
+        let mut pool = DmaPool::new();
+        pool.release(0x10_1000, 8);
+
+        // 16 KiB alignment skips the first three frames
+        let phys = pool.take(2, 4 * P).unwrap();
+        assert_eq!(phys, 0x10_4000);
+        assert_eq!(pool.free, alloc::vec![(0x10_1000, 3), (0x10_6000, 3)]);
+
+        assert!(pool.take(4, P).is_none());
+        assert_eq!(pool.take(3, P), Some(0x10_1000));
+        assert_eq!(pool.free, alloc::vec![(0x10_6000, 3)]);
+
+        pool.release(0x10_4000, 2);
+        pool.release(0x10_1000, 3);
+        assert_eq!(pool.free, alloc::vec![(0x10_1000, 8)]);
+    }
+}
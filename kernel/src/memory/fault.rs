@@ -0,0 +1,379 @@
+//! Page Fault Resolution
+//!
+//! User address spaces are populated lazily. The ELF loader, `mmap` and
+//! the stack record [`Vma`]s instead of mapping every page up front; the
+//! first access to a page faults and is resolved here according to what
+//! backs the VMA containing it:
+//!
+//! - anonymous memory gets a freshly zeroed frame (demand-zero);
+//! - file-backed memory gets a frame filled from the file, with anything
+//!   past the VMA's file extent zeroed;
+//! - a write to a present page that `fork` shared copy-on-write gets a
+//!   private copy of the frame.
+//!
+//! Any other fault is an access violation, and the process gets SIGSEGV.
+
+use crate::memory::user_page_table::{self, PageTableFlags, COW_BIT};
+use crate::process::table::{FileSource, ProcessId, Vma, VmaBacking, PROCESS_TABLE};
+use crate::vfs::VfsError;
+
+const PAGE_SIZE: u64 = 4096;
+
+/// Linux `PROT_READ`, as stored in [`Vma::prot`].
+pub const PROT_READ: u32 = 0x1;
+/// Linux `PROT_WRITE`.
+pub const PROT_WRITE: u32 = 0x2;
+/// Linux `PROT_EXEC`.
+pub const PROT_EXEC: u32 = 0x4;
+
+/// Kind of access that caused a fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+/// How a page fault is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    /// Map a zeroed frame.
+    DemandZero,
+    /// Map a frame filled from the VMA's file.
+    FileBacked,
+    /// Give the writer a private copy of a shared frame.
+    CopyOnWrite,
+    /// The access is not allowed.
+    Violation,
+}
+
+/// Decide how to resolve a fault.
+///
+/// `vma` is the VMA containing the faulting address, if any, and `pte`
+/// the flags of the page's entry if the page is present.
+pub fn classify(vma: Option<&Vma>, pte: Option<PageTableFlags>, access: Access) -> FaultAction {
+    if let Some(flags) = pte {
+        // A present page only faults on a permission mismatch. The one
+        // we fix up is a write to a frame shared copy-on-write; pages
+        // outside any VMA (the brk heap) keep the protection they had.
+        let writable = vma.is_none_or(|vma| vma.prot & PROT_WRITE != 0);
+        return if access == Access::Write && flags.contains(COW_BIT) && writable {
+            FaultAction::CopyOnWrite
+        } else {
+            FaultAction::Violation
+        };
+    }
+
+    let vma = match vma {
+        Some(vma) => vma,
+        None => return FaultAction::Violation,
+    };
+
+    let allowed = match access {
+        // x86_64 has no write-only or execute-only pages
+        Access::Read => vma.prot != 0,
+        Access::Write => vma.prot & PROT_WRITE != 0,
+        Access::Execute => vma.prot & PROT_EXEC != 0,
+    };
+    if !allowed {
+        return FaultAction::Violation;
+    }
+
+    match vma.backing {
+        VmaBacking::Anonymous => FaultAction::DemandZero,
+        VmaBacking::File { .. } => FaultAction::FileBacked,
+    }
+}
+
+/// Convert Linux `PROT_*` flags to the page table flags of a user page.
+pub fn prot_to_page_flags(prot: u32) -> PageTableFlags {
+    let mut flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+
+    if prot & PROT_WRITE != 0 {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if prot & PROT_EXEC == 0 {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    // PROT_READ is implicit when PRESENT is set on x86_64
+
+    flags
+}
+
+/// Fill `buf` with the initial contents of the page at `page` in `vma`:
+/// file data up to the VMA's file extent, zeros everywhere else.
+pub fn fill_page(vma: &Vma, page: u64, buf: &mut [u8]) -> Result<(), VfsError> {
+    buf.fill(0);
+
+    let (source, offset, file_size) = match &vma.backing {
+        VmaBacking::Anonymous => return Ok(()),
+        VmaBacking::File {
+            source,
+            offset,
+            file_size,
+        } => (source, *offset, *file_size),
+    };
+
+    let skipped = page - vma.start;
+    if skipped >= file_size {
+        return Ok(());
+    }
+    let len = (file_size - skipped).min(buf.len() as u64) as usize;
+    // A short read leaves the rest zeroed, like a file that shrank
+    read_source(source, offset + skipped, &mut buf[..len]).map(|_| ())
+}
+
+fn read_source(source: &FileSource, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError> {
+    match source {
+        FileSource::Inode(ino) => crate::vfs::read_at(*ino, offset, buf),
+        FileSource::Image(image) => {
+            let start = (offset as usize).min(image.len());
+            let len = buf.len().min(image.len() - start);
+            buf[..len].copy_from_slice(&image[start..start + len]);
+            Ok(len)
+        }
+    }
+}
+
+/// Resolve a fault at a user address in the address space `cr3_phys`,
+/// which belongs to the current process.
+///
+/// `from_usermode` is false for faults the kernel takes while accessing
+/// user memory for a syscall; the VMA lookup then does not wait for the
+/// process table, whose lock the interrupted code may hold.
+///
+/// Returns `true` if the page is now mapped and the access can be retried.
+pub fn handle_user_fault(
+    cr3_phys: u64,
+    fault_addr: u64,
+    access: Access,
+    from_usermode: bool,
+) -> bool {
+    let page = fault_addr & !(PAGE_SIZE - 1);
+    let pte = user_page_table::read_pte(cr3_phys, page).map(|(_, flags)| flags);
+    let vma = match crate::percpu::current_pid() {
+        0 => None,
+        pid => PROCESS_TABLE.find_vma(ProcessId(pid), fault_addr, from_usermode),
+    };
+
+    match classify(vma.as_ref(), pte, access) {
+        FaultAction::CopyOnWrite => user_page_table::handle_cow_fault(cr3_phys, fault_addr),
+        FaultAction::DemandZero | FaultAction::FileBacked => {
+            // classify only asks for population when there is a VMA
+            let vma = vma.expect("populating fault without a VMA");
+            match populate_page(cr3_phys, &vma, page) {
+                Ok(()) => true,
+                Err(e) => {
+                    crate::serial_println!("[FAULT] Cannot populate {:#x}: {}", page, e);
+                    false
+                }
+            }
+        }
+        FaultAction::Violation => false,
+    }
+}
+
+/// Populate the not-yet-present pages of `vma` in `[start, end)` now
+/// rather than on first access. Returns the number of pages mapped.
+pub fn populate(cr3_phys: u64, vma: &Vma, start: u64, end: u64) -> Result<usize, &'static str> {
+    let mut mapped = 0;
+    for page in (start.max(vma.start)..end.min(vma.end)).step_by(PAGE_SIZE as usize) {
+        if user_page_table::read_pte(cr3_phys, page).is_none() {
+            populate_page(cr3_phys, vma, page)?;
+            mapped += 1;
+        }
+    }
+    Ok(mapped)
+}
+
+/// Map a frame holding the initial contents of `page` in `vma`.
+fn populate_page(cr3_phys: u64, vma: &Vma, page: u64) -> Result<(), &'static str> {
+    let frame = crate::memory::allocate_frame()
+        .ok_or("Out of memory: cannot allocate user page frame")? as u64;
+
+    // SAFETY: the frame was just allocated, so nothing else references
+    // it, and all physical memory is mapped at the offset.
+    let buf = unsafe {
+        core::slice::from_raw_parts_mut(
+            (user_page_table::get_phys_offset() + frame) as *mut u8,
+            PAGE_SIZE as usize,
+        )
+    };
+
+    let result = fill_page(vma, page, buf)
+        .map_err(|_| "Failed to read file-backed page")
+        .and_then(|()| {
+            user_page_table::map_user_page_at(cr3_phys, page, frame, prot_to_page_flags(vma.prot))
+        });
+
+    if let Err(e) = result {
+        crate::memory::free_frame(frame as usize);
+        // Another thread of the process may have mapped the page first
+        if user_page_table::read_pte(cr3_phys, page).is_some() {
+            return Ok(());
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    const MAP_PRIVATE: u32 = 0x02;
+    const MAP_ANONYMOUS: u32 = 0x20;
+
+    fn anonymous(prot: u32) -> Vma {
+        Vma {
+            start: 0x7f00_0000_0000,
+            end: 0x7f00_0000_4000,
+            prot,
+            flags: MAP_PRIVATE | MAP_ANONYMOUS,
+            backing: VmaBacking::Anonymous,
+        }
+    }
+
+    fn file_backed(image: &[u8], offset: u64, file_size: u64) -> Vma {
+        Vma {
+            start: 0x40_0000,
+            end: 0x40_3000,
+            prot: PROT_READ | PROT_WRITE,
+            flags: MAP_PRIVATE,
+            backing: VmaBacking::File {
+                source: FileSource::Image(Arc::from(image)),
+                offset,
+                file_size,
+            },
+        }
+    }
+
+    fn present(extra: PageTableFlags) -> Option<PageTableFlags> {
+        Some(PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE | extra)
+    }
+
+    #[test]
+    fn test_demand_zero_fault() {
+        let vma = anonymous(PROT_READ | PROT_WRITE);
+        assert_eq!(
+            classify(Some(&vma), None, Access::Read),
+            FaultAction::DemandZero
+        );
+        assert_eq!(
+            classify(Some(&vma), None, Access::Write),
+            FaultAction::DemandZero
+        );
+        assert_eq!(
+            classify(Some(&vma), None, Access::Execute),
+            FaultAction::Violation
+        );
+
+        let mut page = [0xAAu8; 4096];
+        fill_page(&vma, vma.start + 0x1000, &mut page).unwrap();
+        assert!(page.iter().all(|&b| b == 0));
+
+        let flags = prot_to_page_flags(vma.prot);
+        assert!(flags.contains(PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE));
+    }
+
+    #[test]
+    fn test_file_backed_fault() {
+        let image: Vec<u8> = (0..0x3000u32).map(|i| (i / 16) as u8).collect();
+        // Two and a half pages of file data starting one page in, then BSS
+        let vma = file_backed(&image, 0x1000, 0x1800);
+        assert_eq!(
+            classify(Some(&vma), None, Access::Read),
+            FaultAction::FileBacked
+        );
+
+        let mut page = [0xAAu8; 4096];
+        fill_page(&vma, 0x40_0000, &mut page).unwrap();
+        assert_eq!(&page[..], &image[0x1000..0x2000]);
+
+        fill_page(&vma, 0x40_1000, &mut page).unwrap();
+        assert_eq!(&page[..0x800], &image[0x2000..0x2800]);
+        assert!(page[0x800..].iter().all(|&b| b == 0));
+
+        fill_page(&vma, 0x40_2000, &mut page).unwrap();
+        assert!(page.iter().all(|&b| b == 0));
+
+        // A file shorter than the mapping reads as zeros past its end
+        let short = file_backed(&image[..0x1400], 0x1000, 0x1800);
+        fill_page(&short, 0x40_0000, &mut page).unwrap();
+        assert_eq!(&page[..0x400], &image[0x1000..0x1400]);
+        assert!(page[0x400..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_copy_on_write_fault() {
+        let vma = anonymous(PROT_READ | PROT_WRITE);
+        let shared = present(COW_BIT);
+        assert_eq!(
+            classify(Some(&vma), shared, Access::Write),
+            FaultAction::CopyOnWrite
+        );
+        // brk pages have no VMA but are still shared by fork
+        assert_eq!(
+            classify(None, shared, Access::Write),
+            FaultAction::CopyOnWrite
+        );
+        assert_eq!(
+            classify(Some(&vma), shared, Access::Execute),
+            FaultAction::Violation
+        );
+
+        // Read-only after mprotect, or never shared
+        let read_only = anonymous(PROT_READ);
+        assert_eq!(
+            classify(Some(&read_only), shared, Access::Write),
+            FaultAction::Violation
+        );
+        let private = present(PageTableFlags::empty());
+        assert_eq!(
+            classify(Some(&vma), private, Access::Write),
+            FaultAction::Violation
+        );
+    }
+
+    #[test]
+    fn test_violations() {
+        assert_eq!(classify(None, None, Access::Read), FaultAction::Violation);
+
+        let guard = anonymous(0);
+        assert_eq!(
+            classify(Some(&guard), None, Access::Read),
+            FaultAction::Violation
+        );
+
+        let text = Vma {
+            prot: PROT_READ | PROT_EXEC,
+            ..file_backed(&[], 0, 0)
+        };
+        assert_eq!(
+            classify(Some(&text), None, Access::Execute),
+            FaultAction::FileBacked
+        );
+        assert_eq!(
+            classify(Some(&text), None, Access::Write),
+            FaultAction::Violation
+        );
+    }
+
+    #[test]
+    fn test_slice_keeps_file_offset() {
+        let image: Vec<u8> = (0..0x4000u32).map(|i| (i / 16) as u8).collect();
+        let vma = file_backed(&image, 0x1000, 0x1800);
+        let tail = vma.slice(0x40_1000, 0x40_3000);
+
+        let mut page = [0u8; 4096];
+        fill_page(&tail, 0x40_1000, &mut page).unwrap();
+        assert_eq!(&page[..0x800], &image[0x2000..0x2800]);
+        assert!(page[0x800..].iter().all(|&b| b == 0));
+
+        let past_file = vma.slice(0x40_2000, 0x40_3000);
+        fill_page(&past_file, 0x40_2000, &mut page).unwrap();
+        assert!(page.iter().all(|&b| b == 0));
+    }
+}
@@ -9,9 +9,13 @@
 //! - **Heap**: Dynamic memory allocation (in allocator module)
 //! - **Slab**: Fixed-size object caching
 //! - **Buddy**: Power-of-two block allocator
+//! - **DMA**: Physically contiguous buffers for device drivers
+//! - **Fault**: Demand paging and copy-on-write for user address spaces
 //! - **Optimization**: Memory compression and reclamation
 
 pub mod buddy;
+pub mod dma;
+pub mod fault;
 pub mod optimization;
 pub mod refcount;
 pub mod slab;
@@ -25,6 +29,8 @@ use x86_64::{
     PhysAddr, VirtAddr,
 };
 
+pub use dma::{dma_alloc, dma_free, DmaBuffer, DmaError};
+
 /// Page size constant (4 KiB).
 const PAGE_SIZE: usize = 4096;
 
@@ -63,6 +69,7 @@ impl FreeFrameList {
 
 /// Simple global frame allocator.
 struct GlobalFrameAllocator {
+    start_frame: u64,
     next_frame: u64,
     end_frame: u64,
 }
@@ -76,11 +83,35 @@ impl GlobalFrameAllocator {
         self.next_frame += PAGE_SIZE as u64;
         Some(frame)
     }
+
+    /// Allocate `count` physically contiguous frames starting at an
+    /// `align`-aligned address. Frames skipped to satisfy the alignment are
+    /// pushed to `skipped`.
+    fn allocate_contiguous(
+        &mut self,
+        count: usize,
+        align: u64,
+        skipped: &mut FreeFrameList,
+    ) -> Option<u64> {
+        let start = (self.next_frame + align - 1) & !(align - 1);
+        let end = start.checked_add((count * PAGE_SIZE) as u64)?;
+        if end > self.end_frame {
+            return None;
+        }
+        let mut gap = self.next_frame;
+        while gap < start {
+            skipped.push(gap as usize);
+            gap += PAGE_SIZE as u64;
+        }
+        self.next_frame = end;
+        Some(start)
+    }
 }
 
 /// Initialize the global frame allocator for slab/buddy.
 pub fn init_frame_allocator(start: u64, end: u64) {
     *GLOBAL_FRAME_ALLOCATOR.lock() = Some(GlobalFrameAllocator {
+        start_frame: start,
         next_frame: start,
         end_frame: end,
     });
@@ -104,6 +135,31 @@ pub fn allocate_frame() -> Option<usize> {
         .map(|f| f as usize)
 }
 
+/// Allocate `count` physically contiguous frames aligned to `align` bytes.
+///
+/// Used for DMA buffers (see [`dma`]). A single page can come from the
+/// free list; larger runs are carved from the bump allocator, since
+/// recycled frames are not contiguous.
+pub fn allocate_contiguous_frames(count: usize, align: usize) -> Option<usize> {
+    if count == 0 || !align.is_power_of_two() {
+        return None;
+    }
+    let align = align.max(PAGE_SIZE);
+
+    let mut free = GLOBAL_FREE_FRAMES.lock();
+    if count == 1 && align == PAGE_SIZE {
+        if let Some(addr) = free.pop() {
+            return Some(addr);
+        }
+    }
+
+    GLOBAL_FRAME_ALLOCATOR
+        .lock()
+        .as_mut()?
+        .allocate_contiguous(count, align as u64, &mut free)
+        .map(|f| f as usize)
+}
+
 /// Free a physical frame, returning it to the free list for reuse.
 ///
 /// # Panics
@@ -125,6 +181,30 @@ pub fn free_frame_count() -> usize {
     GLOBAL_FREE_FRAMES.lock().len()
 }
 
+/// Physical frame usage of the global frame allocator.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    /// Frames managed by the allocator.
+    pub total: usize,
+    /// Frames available (never handed out, or returned to the free list).
+    pub free: usize,
+}
+
+/// Return total/free frame counts for the global frame allocator.
+pub fn frame_stats() -> FrameStats {
+    let recycled = GLOBAL_FREE_FRAMES.lock().len();
+    match GLOBAL_FRAME_ALLOCATOR.lock().as_ref() {
+        Some(a) => FrameStats {
+            total: ((a.end_frame - a.start_frame) / PAGE_SIZE as u64) as usize,
+            free: ((a.end_frame - a.next_frame) / PAGE_SIZE as u64) as usize + recycled,
+        },
+        None => FrameStats {
+            total: 0,
+            free: recycled,
+        },
+    }
+}
+
 /// Validate the physical memory offset.
 ///
 /// Verifies that the physical memory offset provided by the bootloader is valid.
@@ -0,0 +1,632 @@
+//! Packet Capture
+//!
+//! When enabled, every Ethernet frame handed to a NIC or received from
+//! one is copied with a timestamp into a ring buffer. Once the ring is
+//! full the oldest frames are overwritten. The ring is exported in libpcap
+//! format at `/proc/net/capture.pcap`, which Wireshark and tcpdump open
+//! directly.
+//!
+//! A filter limits what gets recorded. Its syntax is a subset of the
+//! tcpdump (BPF) language:
+//!
+//! - protocols: `arp`, `ip`, `icmp`, `igmp`, `tcp`, `udp`
+//! - `[src|dst] host A.B.C.D`, `[src|dst] port N`
+//! - `inbound`, `outbound`
+//! - `not`/`!`, `and`/`&&`, `or`/`||` and parentheses
+//!
+//! For example `udp and (port 67 or port 53)` records DHCP and DNS.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+
+use super::ethernet::{ETHERTYPE_ARP, ETHERTYPE_IPV4, HEADER_SIZE as ETH_HEADER_SIZE};
+use super::ipv4::{PROTO_ICMP, PROTO_IGMP, PROTO_TCP, PROTO_UDP};
+use super::Ipv4Addr;
+use crate::time::Timespec;
+
+/// Frames kept by default before the oldest are overwritten.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Bytes kept of each frame by default (a full frame without FCS).
+pub const DEFAULT_SNAPLEN: usize = 1514;
+
+/// Largest accepted ring capacity.
+pub const MAX_CAPACITY: usize = 16384;
+
+/// pcap magic for microsecond timestamps, written in native (LE) order.
+const PCAP_MAGIC: u32 = 0xA1B2_C3D4;
+
+/// pcap link type for Ethernet.
+const LINKTYPE_ETHERNET: u32 = 1;
+
+/// Fast-path flag checked before taking the capture lock.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The capture ring, kept after `stop()` so it can still be exported.
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+
+// ── Configuration ───────────────────────────────────────────
+
+/// Which way a frame crossed the NIC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received from the wire.
+    Inbound,
+    /// Handed to a NIC for transmission.
+    Outbound,
+}
+
+/// Capture settings.
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    /// Maximum number of frames in the ring.
+    pub capacity: usize,
+    /// Bytes kept of each frame; longer frames are truncated.
+    pub snaplen: usize,
+    /// Only frames matching this filter are recorded.
+    pub filter: Option<Filter>,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            snaplen: DEFAULT_SNAPLEN,
+            filter: None,
+        }
+    }
+}
+
+/// Capture counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CaptureStats {
+    /// Frames recorded into the ring.
+    pub captured: u64,
+    /// Frames rejected by the filter.
+    pub filtered: u64,
+    /// Recorded frames overwritten because the ring was full.
+    pub overwritten: u64,
+    /// Frames currently held in the ring.
+    pub buffered: usize,
+}
+
+/// A recorded frame.
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    /// Wall-clock time the frame was seen.
+    pub timestamp: Timespec,
+    /// Which way the frame went.
+    pub direction: Direction,
+    /// Length of the frame on the wire.
+    pub orig_len: usize,
+    /// Frame bytes, truncated to the snap length.
+    pub data: Vec<u8>,
+}
+
+// ── Ring buffer ─────────────────────────────────────────────
+
+/// Ring of captured frames plus its settings and counters.
+struct Capture {
+    config: CaptureConfig,
+    frames: VecDeque<CapturedFrame>,
+    stats: CaptureStats,
+}
+
+impl Capture {
+    fn new(config: CaptureConfig) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(config.capacity.min(DEFAULT_CAPACITY)),
+            config,
+            stats: CaptureStats::default(),
+        }
+    }
+
+    fn record(&mut self, direction: Direction, frame: &[u8], timestamp: Timespec) {
+        if let Some(filter) = &self.config.filter {
+            if !filter.matches(direction, frame) {
+                self.stats.filtered += 1;
+                return;
+            }
+        }
+
+        if self.frames.len() >= self.config.capacity {
+            self.frames.pop_front();
+            self.stats.overwritten += 1;
+        }
+        let kept = frame.len().min(self.config.snaplen);
+        self.frames.push_back(CapturedFrame {
+            timestamp,
+            direction,
+            orig_len: frame.len(),
+            data: frame[..kept].to_vec(),
+        });
+        self.stats.captured += 1;
+    }
+
+    fn stats(&self) -> CaptureStats {
+        CaptureStats {
+            buffered: self.frames.len(),
+            ..self.stats
+        }
+    }
+}
+
+// ── Public API ──────────────────────────────────────────────
+
+/// Start capturing with `config`, discarding any previous capture.
+pub fn start(mut config: CaptureConfig) {
+    config.capacity = config.capacity.clamp(1, MAX_CAPACITY);
+    config.snaplen = config.snaplen.max(ETH_HEADER_SIZE);
+    *CAPTURE.lock() = Some(Capture::new(config));
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Stop capturing. Frames already recorded stay available for export.
+pub fn stop() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+/// Stop capturing and drop the recorded frames.
+pub fn clear() {
+    stop();
+    *CAPTURE.lock() = None;
+}
+
+/// Whether frames are currently being recorded.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Counters of the current (or last) capture, if any.
+pub fn stats() -> Option<CaptureStats> {
+    CAPTURE.lock().as_ref().map(Capture::stats)
+}
+
+/// Settings of the current (or last) capture, if any.
+pub fn config() -> Option<CaptureConfig> {
+    CAPTURE.lock().as_ref().map(|c| c.config.clone())
+}
+
+/// Record a frame crossing the NIC boundary.
+///
+/// Costs a single atomic load while capture is disabled.
+pub fn record(direction: Direction, frame: &[u8]) {
+    if !is_enabled() {
+        return;
+    }
+    let timestamp = crate::time::now();
+    if let Some(capture) = CAPTURE.lock().as_mut() {
+        capture.record(direction, frame, timestamp);
+    }
+}
+
+/// Export the recorded frames as a pcap file (empty if never started).
+pub fn export_pcap() -> Vec<u8> {
+    match CAPTURE.lock().as_ref() {
+        Some(capture) => write_pcap(capture.frames.iter(), capture.config.snaplen),
+        None => write_pcap(core::iter::empty(), DEFAULT_SNAPLEN),
+    }
+}
+
+// ── pcap format ─────────────────────────────────────────────
+
+/// Serialize frames as a classic libpcap file with an Ethernet link type.
+fn write_pcap<'a>(frames: impl Iterator<Item = &'a CapturedFrame>, snaplen: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    // Global header: magic, version 2.4, GMT offset, sigfigs, snaplen, link type
+    out.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&0i32.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&(snaplen as u32).to_le_bytes());
+    out.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+
+    for frame in frames {
+        out.extend_from_slice(&(frame.timestamp.secs as u32).to_le_bytes());
+        out.extend_from_slice(&frame.timestamp.subsec_micros().to_le_bytes());
+        out.extend_from_slice(&(frame.data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(frame.orig_len as u32).to_le_bytes());
+        out.extend_from_slice(&frame.data);
+    }
+
+    out
+}
+
+// ── Filter ──────────────────────────────────────────────────
+
+/// Protocol primitive of a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Arp,
+    Ip,
+    Icmp,
+    Igmp,
+    Tcp,
+    Udp,
+}
+
+/// Which address or port a `host`/`port` primitive looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    Src,
+    Dst,
+    Either,
+}
+
+/// A compiled capture filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    Protocol(Protocol),
+    Host(Endpoint, Ipv4Addr),
+    Port(Endpoint, u16),
+    Direction(Direction),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+/// Why a filter expression failed to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError(String);
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid capture filter: {}", self.0)
+    }
+}
+
+impl Filter {
+    /// Compile a filter expression.
+    pub fn parse(expr: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(expr);
+        let mut parser = FilterParser { tokens, pos: 0 };
+        let filter = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(tok) => Err(FilterError(format!("unexpected '{}'", tok))),
+        }
+    }
+
+    /// Check whether a frame going in `direction` matches.
+    pub fn matches(&self, direction: Direction, frame: &[u8]) -> bool {
+        self.eval(direction, &FrameSummary::parse(frame))
+    }
+
+    fn eval(&self, direction: Direction, frame: &FrameSummary) -> bool {
+        match self {
+            Filter::Protocol(proto) => frame.is(*proto),
+            Filter::Host(which, addr) => frame
+                .addresses
+                .is_some_and(|(src, dst)| endpoint_matches(*which, src, dst, *addr)),
+            Filter::Port(which, port) => frame
+                .ports
+                .is_some_and(|(src, dst)| endpoint_matches(*which, src, dst, *port)),
+            Filter::Direction(d) => *d == direction,
+            Filter::Not(inner) => !inner.eval(direction, frame),
+            Filter::And(a, b) => a.eval(direction, frame) && b.eval(direction, frame),
+            Filter::Or(a, b) => a.eval(direction, frame) || b.eval(direction, frame),
+        }
+    }
+}
+
+fn endpoint_matches<T: PartialEq>(which: Endpoint, src: T, dst: T, wanted: T) -> bool {
+    match which {
+        Endpoint::Src => src == wanted,
+        Endpoint::Dst => dst == wanted,
+        Endpoint::Either => src == wanted || dst == wanted,
+    }
+}
+
+/// The header fields a filter can test, extracted once per frame.
+///
+/// Parsing is deliberately lenient (no checksum or length validation) so
+/// that malformed frames can still be captured and inspected.
+struct FrameSummary {
+    ethertype: u16,
+    /// IPv4 protocol number.
+    ip_protocol: Option<u8>,
+    /// IPv4 source/destination, or ARP sender/target protocol addresses.
+    addresses: Option<(Ipv4Addr, Ipv4Addr)>,
+    /// TCP/UDP source/destination ports.
+    ports: Option<(u16, u16)>,
+}
+
+impl FrameSummary {
+    fn parse(frame: &[u8]) -> Self {
+        let mut summary = FrameSummary {
+            ethertype: 0,
+            ip_protocol: None,
+            addresses: None,
+            ports: None,
+        };
+        if frame.len() < ETH_HEADER_SIZE {
+            return summary;
+        }
+        summary.ethertype = u16::from_be_bytes([frame[12], frame[13]]);
+        let payload = &frame[ETH_HEADER_SIZE..];
+
+        match summary.ethertype {
+            ETHERTYPE_IPV4 if payload.len() >= 20 && payload[0] >> 4 == 4 => {
+                let protocol = payload[9];
+                summary.ip_protocol = Some(protocol);
+                summary.addresses = Some((ipv4_at(payload, 12), ipv4_at(payload, 16)));
+
+                // Ports are only present in the first fragment
+                let header_len = (payload[0] & 0x0F) as usize * 4;
+                let fragment_offset = u16::from_be_bytes([payload[6], payload[7]]) & 0x1FFF;
+                let l4 = payload.get(header_len..).unwrap_or(&[]);
+                if matches!(protocol, PROTO_TCP | PROTO_UDP)
+                    && fragment_offset == 0
+                    && l4.len() >= 4
+                {
+                    summary.ports = Some((
+                        u16::from_be_bytes([l4[0], l4[1]]),
+                        u16::from_be_bytes([l4[2], l4[3]]),
+                    ));
+                }
+            }
+            // Ethernet/IPv4 ARP: sender IP at 14, target IP at 24
+            ETHERTYPE_ARP if payload.len() >= 28 => {
+                summary.addresses = Some((ipv4_at(payload, 14), ipv4_at(payload, 24)));
+            }
+            _ => {}
+        }
+
+        summary
+    }
+
+    fn is(&self, proto: Protocol) -> bool {
+        match proto {
+            Protocol::Arp => self.ethertype == ETHERTYPE_ARP,
+            Protocol::Ip => self.ip_protocol.is_some(),
+            Protocol::Icmp => self.ip_protocol == Some(PROTO_ICMP),
+            Protocol::Igmp => self.ip_protocol == Some(PROTO_IGMP),
+            Protocol::Tcp => self.ip_protocol == Some(PROTO_TCP),
+            Protocol::Udp => self.ip_protocol == Some(PROTO_UDP),
+        }
+    }
+}
+
+fn ipv4_at(bytes: &[u8], offset: usize) -> Ipv4Addr {
+    Ipv4Addr([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+/// Split a filter expression into words, parentheses and `!`.
+fn tokenize(expr: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for ch in expr.chars() {
+        if ch.is_whitespace() || ch == '(' || ch == ')' || ch == '!' {
+            if !word.is_empty() {
+                tokens.push(core::mem::take(&mut word));
+            }
+            if !ch.is_whitespace() {
+                tokens.push(String::from(ch));
+            }
+        } else {
+            word.push(ch);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// Recursive-descent parser: `or` binds loosest, then `and`, then `not`.
+struct FilterParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<&str, FilterError> {
+        let tok = self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| FilterError(String::from("unexpected end of expression")))?;
+        self.pos += 1;
+        Ok(tok.as_str())
+    }
+
+    fn eat(&mut self, words: &[&str]) -> bool {
+        if self.peek().is_some_and(|tok| words.contains(&tok)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.parse_and()?;
+        while self.eat(&["or", "||"]) {
+            filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.parse_not()?;
+        while self.eat(&["and", "&&"]) {
+            filter = Filter::And(Box::new(filter), Box::new(self.parse_not()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_not(&mut self) -> Result<Filter, FilterError> {
+        if self.eat(&["not", "!"]) {
+            return Ok(Filter::Not(Box::new(self.parse_not()?)));
+        }
+        if self.eat(&["("]) {
+            let filter = self.parse_or()?;
+            if !self.eat(&[")"]) {
+                return Err(FilterError(String::from("missing ')'")));
+            }
+            return Ok(filter);
+        }
+        self.parse_primitive()
+    }
+
+    fn parse_primitive(&mut self) -> Result<Filter, FilterError> {
+        let endpoint = if self.eat(&["src"]) {
+            Endpoint::Src
+        } else if self.eat(&["dst"]) {
+            Endpoint::Dst
+        } else {
+            Endpoint::Either
+        };
+
+        let word = String::from(self.next()?);
+        let filter = match word.as_str() {
+            "host" => Filter::Host(endpoint, parse_ipv4(self.next()?)?),
+            "port" => {
+                let value = self.next()?;
+                let port = value
+                    .parse()
+                    .map_err(|_| FilterError(format!("invalid port '{}'", value)))?;
+                Filter::Port(endpoint, port)
+            }
+            _ if endpoint != Endpoint::Either => {
+                return Err(FilterError(format!(
+                    "expected 'host' or 'port', found '{}'",
+                    word
+                )));
+            }
+            "arp" => Filter::Protocol(Protocol::Arp),
+            "ip" => Filter::Protocol(Protocol::Ip),
+            "icmp" => Filter::Protocol(Protocol::Icmp),
+            "igmp" => Filter::Protocol(Protocol::Igmp),
+            "tcp" => Filter::Protocol(Protocol::Tcp),
+            "udp" => Filter::Protocol(Protocol::Udp),
+            "inbound" => Filter::Direction(Direction::Inbound),
+            "outbound" => Filter::Direction(Direction::Outbound),
+            _ => return Err(FilterError(format!("unknown primitive '{}'", word))),
+        };
+        Ok(filter)
+    }
+}
+
+fn parse_ipv4(text: &str) -> Result<Ipv4Addr, FilterError> {
+    let invalid = || FilterError(format!("invalid IPv4 address '{}'", text));
+    let mut octets = [0u8; 4];
+    let mut parts = text.split('.');
+    for octet in &mut octets {
+        *octet = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+    }
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(Ipv4Addr(octets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ethernet + IPv4 + UDP header for `src:sport -> dst:dport`.
+    fn udp_frame(src: [u8; 4], sport: u16, dst: [u8; 4], dport: u16) -> Vec<u8> {
+        let mut frame = alloc::vec![0u8; ETH_HEADER_SIZE];
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        let mut ip = [0u8; 20];
+        ip[0] = 0x45;
+        ip[9] = PROTO_UDP;
+        ip[12..16].copy_from_slice(&src);
+        ip[16..20].copy_from_slice(&dst);
+        frame.extend_from_slice(&ip);
+        frame.extend_from_slice(&sport.to_be_bytes());
+        frame.extend_from_slice(&dport.to_be_bytes());
+        frame.extend_from_slice(&[0, 8, 0, 0]);
+        frame
+    }
+
+    #[test]
+    fn test_filter_parse_precedence() {
+        let filter = Filter::parse("udp and (port 67 or port 53)").unwrap();
+        let dns = udp_frame([10, 0, 2, 15], 40000, [10, 0, 2, 3], 53);
+        let other = udp_frame([10, 0, 2, 15], 40000, [10, 0, 2, 3], 123);
+        assert!(filter.matches(Direction::Outbound, &dns));
+        assert!(!filter.matches(Direction::Outbound, &other));
+
+        let filter = Filter::parse("not src host 10.0.2.15 && inbound").unwrap();
+        assert!(!filter.matches(Direction::Inbound, &dns));
+        let reply = udp_frame([10, 0, 2, 3], 53, [10, 0, 2, 15], 40000);
+        assert!(filter.matches(Direction::Inbound, &reply));
+        assert!(!filter.matches(Direction::Outbound, &reply));
+    }
+
+    #[test]
+    fn test_filter_parse_errors() {
+        assert!(Filter::parse("").is_err());
+        assert!(Filter::parse("tcp and").is_err());
+        assert!(Filter::parse("(udp").is_err());
+        assert!(Filter::parse("src tcp").is_err());
+        assert!(Filter::parse("port http").is_err());
+        assert!(Filter::parse("host 10.0.2").is_err());
+        assert!(Filter::parse("udp tcp").is_err());
+    }
+
+    #[test]
+    fn test_ring_overwrites_oldest_and_truncates() {
+        let mut capture = Capture::new(CaptureConfig {
+            capacity: 2,
+            snaplen: 20,
+            filter: Some(Filter::parse("udp").unwrap()),
+        });
+        let frame = udp_frame([10, 0, 2, 15], 1, [10, 0, 2, 3], 2);
+        for secs in 0..3 {
+            capture.record(Direction::Outbound, &frame, Timespec { secs, nanos: 0 });
+        }
+        capture.record(Direction::Inbound, &[0u8; 60], Timespec::default());
+
+        let stats = capture.stats();
+        assert_eq!(stats.captured, 3);
+        assert_eq!(stats.overwritten, 1);
+        assert_eq!(stats.filtered, 1);
+        assert_eq!(stats.buffered, 2);
+        assert_eq!(capture.frames[0].timestamp.secs, 1);
+        assert_eq!(capture.frames[0].data.len(), 20);
+        assert_eq!(capture.frames[0].orig_len, frame.len());
+    }
+
+    #[test]
+    fn test_write_pcap_layout() {
+        let frame = CapturedFrame {
+            timestamp: Timespec {
+                secs: 1_700_000_000,
+                nanos: 123_456_789,
+            },
+            direction: Direction::Inbound,
+            orig_len: 60,
+            data: alloc::vec![0xAB; 20],
+        };
+        let pcap = write_pcap(core::iter::once(&frame), 20);
+        assert_eq!(pcap.len(), 24 + 16 + 20);
+        assert_eq!(&pcap[..4], &[0xD4, 0xC3, 0xB2, 0xA1]);
+        assert_eq!(&pcap[20..24], &1u32.to_le_bytes());
+        assert_eq!(&pcap[24..28], &1_700_000_000u32.to_le_bytes());
+        assert_eq!(&pcap[28..32], &123_456u32.to_le_bytes());
+        assert_eq!(&pcap[32..36], &20u32.to_le_bytes());
+        assert_eq!(&pcap[36..40], &60u32.to_le_bytes());
+    }
+}
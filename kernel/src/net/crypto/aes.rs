@@ -129,10 +129,10 @@ pub fn aes256_key_schedule(key: &[u8; 32]) -> [[u8; 16]; 15] {
         } else {
             // Odd: SubWord only
             let t = [
-                SBOX[prev[0] as usize],
-                SBOX[prev[1] as usize],
-                SBOX[prev[2] as usize],
-                SBOX[prev[3] as usize],
+                SBOX[prev[12] as usize],
+                SBOX[prev[13] as usize],
+                SBOX[prev[14] as usize],
+                SBOX[prev[15] as usize],
             ];
             for j in 0..4 {
                 rk[i][j] = pprev[j] ^ t[j];
@@ -0,0 +1,29 @@
+//! PBKDF2 (Password-Based Key Derivation Function 2) — RFC 8018 §5.2
+//!
+//! Provides PBKDF2-HMAC-SHA-256 for deriving keys from passphrases.
+
+use super::hmac::hmac_sha256;
+use alloc::vec::Vec;
+
+/// PBKDF2-HMAC-SHA-256(password, salt, c) → `out.len()` bytes.
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    let mut input = Vec::with_capacity(salt.len() + 4);
+
+    for (i, block) in out.chunks_mut(32).enumerate() {
+        // U1 = PRF(P, S ‖ INT(i))
+        input.clear();
+        input.extend_from_slice(salt);
+        input.extend_from_slice(&(i as u32 + 1).to_be_bytes());
+        let mut u = hmac_sha256(password, &input);
+        let mut t = u;
+
+        // T = U1 ⊕ U2 ⊕ … ⊕ Uc
+        for _ in 1..iterations {
+            u = hmac_sha256(password, &u);
+            for j in 0..32 {
+                t[j] ^= u[j];
+            }
+        }
+        block.copy_from_slice(&t[..block.len()]);
+    }
+}
@@ -11,6 +11,7 @@ use alloc::vec::Vec;
 
 use super::ethernet;
 use super::ipv4;
+use super::stats;
 use super::Ipv4Addr;
 use crate::drivers::net::{MacAddress, NETWORK_MANAGER};
 
@@ -90,6 +91,7 @@ pub fn discover_and_apply() -> Result<DhcpLease, String> {
         crate::serial_println!("[DHCP] Sending DISCOVER (attempt {}/{})...", attempt + 1, MAX_ATTEMPTS);
         let discover = build_dhcp_packet(DHCP_DISCOVER, xid, mac, None, None);
         let frame = build_dhcp_frame(mac, &discover);
+        stats::update(|s| s.dhcp.discovers += 1);
         transmit_raw(&frame);
 
         // Step 2: Wait for OFFER
@@ -99,7 +101,10 @@ pub fn discover_and_apply() -> Result<DhcpLease, String> {
                 crate::serial_println!("[DHCP] No OFFER received, retrying...");
                 continue;
             }
-            Err(e) => return Err(e),
+            Err(e) => {
+                stats::update(|s| s.dhcp.failures += 1);
+                return Err(e);
+            }
         };
         crate::serial_println!(
             "[DHCP] Got OFFER: ip={}, gw={}, dns={}, mask={}",
@@ -119,6 +124,7 @@ pub fn discover_and_apply() -> Result<DhcpLease, String> {
             Some(offer.server_id),
         );
         let frame = build_dhcp_frame(mac, &request);
+        stats::update(|s| s.dhcp.requests += 1);
         transmit_raw(&frame);
 
         // Step 4: Wait for ACK
@@ -128,7 +134,10 @@ pub fn discover_and_apply() -> Result<DhcpLease, String> {
                 crate::serial_println!("[DHCP] No ACK received, retrying full cycle...");
                 continue;
             }
-            Err(e) => return Err(e),
+            Err(e) => {
+                stats::update(|s| s.dhcp.failures += 1);
+                return Err(e);
+            }
         };
         crate::serial_println!(
             "[DHCP] Got ACK: ip={}, lease={}s",
@@ -146,6 +155,7 @@ pub fn discover_and_apply() -> Result<DhcpLease, String> {
         });
 
         crate::serial_println!("[DHCP] IP configuration applied");
+        stats::update(|s| s.dhcp.leases += 1);
         return Ok(lease);
     }
 
@@ -13,6 +13,7 @@ use core::sync::atomic::{AtomicU16, Ordering};
 use spin::Mutex;
 
 use super::ipv4;
+use super::stats;
 use super::udp;
 use super::Ipv4Addr;
 
@@ -93,6 +94,7 @@ pub fn resolve(hostname: &str) -> Result<DnsEntry, super::NetError> {
     // 1. Built-in hosts
     let host_result = with_resolver(|r| r.hosts.get(hostname).cloned());
     if let Some(addrs) = host_result {
+        stats::update(|s| s.dns.cache_hits += 1);
         return Ok(DnsEntry {
             hostname: String::from(hostname),
             addresses: addrs,
@@ -103,6 +105,7 @@ pub fn resolve(hostname: &str) -> Result<DnsEntry, super::NetError> {
     // 2. Cache
     let cache_result = with_resolver(|r| r.cache.get(hostname).cloned());
     if let Some(entry) = cache_result {
+        stats::update(|s| s.dns.cache_hits += 1);
         return Ok(entry);
     }
 
@@ -122,6 +125,8 @@ fn wire_resolve(hostname: &str) -> Result<DnsEntry, super::NetError> {
 
     // Send the query as a UDP datagram
     // We need to send the raw frame out via the NIC
+    let timer = stats::Timer::start();
+    stats::update(|s| s.dns.queries += 1);
     if let Some(frame) = udp::send(local_port, dns_server, DNS_PORT, &query) {
         // Transmit the frame
         super::transmit_frame(&frame);
@@ -134,6 +139,8 @@ fn wire_resolve(hostname: &str) -> Result<DnsEntry, super::NetError> {
 
         if let Some(dgram) = udp::recv(local_port) {
             if let Some(entry) = parse_response(&dgram.data, hostname) {
+                let us = timer.elapsed_us();
+                stats::update(|s| s.dns.query_time.record(us));
                 // Cache the result
                 with_resolver(|r| {
                     r.cache.insert(String::from(hostname), entry.clone());
@@ -148,6 +155,7 @@ fn wire_resolve(hostname: &str) -> Result<DnsEntry, super::NetError> {
         }
     }
 
+    stats::update(|s| s.dns.failures += 1);
     Err(super::NetError::DnsNotFound)
 }
 
@@ -0,0 +1,256 @@
+//! IGMP Layer
+//!
+//! Host side of IGMPv2 (RFC 2236). Tracks which multicast groups local
+//! sockets have joined, announces membership to routers, answers
+//! membership queries, and programs the NICs' multicast filters so the
+//! group's frames are received at all.
+//!
+//! Groups are reference counted: the membership report and NIC filter
+//! update happen on the first join, the leave message on the last leave.
+
+#![allow(dead_code)]
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use super::ipv4::{self, Ipv4Packet};
+use super::{ethernet, Ipv4Addr, NetError};
+use crate::drivers::net::{MacAddress, NETWORK_MANAGER};
+
+// ── IGMP message types ──────────────────────────────────────
+
+/// Membership query (general or group-specific)
+pub const MEMBERSHIP_QUERY: u8 = 0x11;
+/// IGMPv1 membership report
+pub const V1_MEMBERSHIP_REPORT: u8 = 0x12;
+/// IGMPv2 membership report
+pub const V2_MEMBERSHIP_REPORT: u8 = 0x16;
+/// Leave group
+pub const LEAVE_GROUP: u8 = 0x17;
+
+/// IGMP message size
+pub const MESSAGE_SIZE: usize = 8;
+
+/// All systems on this subnet (every host is implicitly a member)
+pub const ALL_HOSTS: Ipv4Addr = Ipv4Addr([224, 0, 0, 1]);
+/// All routers on this subnet (destination of leave messages)
+pub const ALL_ROUTERS: Ipv4Addr = Ipv4Addr([224, 0, 0, 2]);
+
+/// IP Router Alert option (RFC 2113), required on IGMPv2 messages
+const ROUTER_ALERT: [u8; 4] = [0x94, 0x04, 0x00, 0x00];
+
+// ── Group table ─────────────────────────────────────────────
+
+/// A joined multicast group.
+#[derive(Debug, Clone, Copy)]
+struct Membership {
+    /// Local interface address the group was joined on.
+    interface: Ipv4Addr,
+    /// Number of sockets that joined the group.
+    users: usize,
+}
+
+static GROUPS: Mutex<BTreeMap<Ipv4Addr, Membership>> = Mutex::new(BTreeMap::new());
+
+// ── Public API ──────────────────────────────────────────────
+
+/// Ethernet address a multicast group maps to (RFC 1112 §6.4):
+/// `01:00:5e` followed by the low 23 bits of the group address.
+pub fn multicast_mac(group: Ipv4Addr) -> MacAddress {
+    let o = group.0;
+    MacAddress::new([0x01, 0x00, 0x5E, o[1] & 0x7F, o[2], o[3]])
+}
+
+/// Build an IGMPv2 message.
+pub fn build_message(kind: u8, group: Ipv4Addr) -> [u8; MESSAGE_SIZE] {
+    let mut msg = [0u8; MESSAGE_SIZE];
+    msg[0] = kind;
+    // Max response time is only meaningful in queries
+    msg[1] = 0;
+    msg[4..8].copy_from_slice(&group.0);
+    let cksum = ipv4::checksum(&msg);
+    msg[2] = (cksum >> 8) as u8;
+    msg[3] = cksum as u8;
+    msg
+}
+
+/// Join a multicast group on `interface` (`Ipv4Addr::ANY` for the default).
+///
+/// The first join sends a membership report and adds the group's MAC
+/// address to every NIC's receive filter.
+pub fn join(group: Ipv4Addr, interface: Ipv4Addr) -> Result<(), NetError> {
+    if !group.is_multicast() {
+        return Err(NetError::InvalidArgument);
+    }
+    let cfg = ipv4::config();
+    if !interface.is_unspecified() && interface != cfg.ip {
+        return Err(NetError::InvalidArgument);
+    }
+
+    let (first, first_group) = {
+        let mut groups = GROUPS.lock();
+        let first_group = groups.is_empty();
+        let membership = groups.entry(group).or_insert(Membership {
+            interface,
+            users: 0,
+        });
+        membership.users += 1;
+        (membership.users == 1, first_group)
+    };
+
+    if first {
+        if first_group {
+            // Receive queries addressed to all hosts while any group is joined
+            program_filters(multicast_mac(ALL_HOSTS), true);
+        }
+        program_filters(multicast_mac(group), true);
+        if group != ALL_HOSTS {
+            send_message(V2_MEMBERSHIP_REPORT, group, group);
+        }
+    }
+    Ok(())
+}
+
+/// Drop one membership of a multicast group.
+///
+/// The last leave sends a leave message and removes the group's MAC
+/// address from the NICs' receive filters.
+pub fn leave(group: Ipv4Addr) -> Result<(), NetError> {
+    let (last, no_groups) = {
+        let mut groups = GROUPS.lock();
+        let membership = groups.get_mut(&group).ok_or(NetError::InvalidArgument)?;
+        membership.users -= 1;
+        let last = membership.users == 0;
+        if last {
+            groups.remove(&group);
+        }
+        (last, groups.is_empty())
+    };
+
+    if last {
+        if group != ALL_HOSTS {
+            send_message(LEAVE_GROUP, group, ALL_ROUTERS);
+        }
+        program_filters(multicast_mac(group), false);
+        if no_groups {
+            program_filters(multicast_mac(ALL_HOSTS), false);
+        }
+    }
+    Ok(())
+}
+
+/// Check whether any socket has joined `group`.
+pub fn is_member(group: Ipv4Addr) -> bool {
+    GROUPS.lock().contains_key(&group)
+}
+
+/// Groups currently joined.
+pub fn groups() -> Vec<Ipv4Addr> {
+    GROUPS.lock().keys().copied().collect()
+}
+
+/// Check whether a frame sent to `mac` belongs to a joined group.
+pub fn accepts_mac(mac: &MacAddress) -> bool {
+    if !mac.is_multicast() || mac.is_broadcast() {
+        return false;
+    }
+    let groups = GROUPS.lock();
+    if groups.is_empty() {
+        return false;
+    }
+    *mac == multicast_mac(ALL_HOSTS) || groups.keys().any(|&g| multicast_mac(g) == *mac)
+}
+
+/// Process an incoming IGMP message (called from IPv4 dispatch).
+///
+/// Answers general queries with a report for every joined group and
+/// group-specific queries with a report for that group.
+pub fn process_incoming(pkt: &Ipv4Packet) {
+    let msg = pkt.payload;
+    if msg.len() < MESSAGE_SIZE || ipv4::checksum(msg) != 0 {
+        return;
+    }
+    if msg[0] != MEMBERSHIP_QUERY {
+        // Reports from other members need no action without report suppression
+        return;
+    }
+
+    let queried = Ipv4Addr([msg[4], msg[5], msg[6], msg[7]]);
+    let targets: Vec<Ipv4Addr> = {
+        let groups = GROUPS.lock();
+        if queried.is_unspecified() {
+            groups.keys().copied().filter(|&g| g != ALL_HOSTS).collect()
+        } else if groups.contains_key(&queried) && queried != ALL_HOSTS {
+            alloc::vec![queried]
+        } else {
+            Vec::new()
+        }
+    };
+
+    for group in targets {
+        send_message(V2_MEMBERSHIP_REPORT, group, group);
+    }
+}
+
+// ── Internal helpers ────────────────────────────────────────
+
+/// Send an IGMP message to `dst` with TTL 1 and the Router Alert option.
+fn send_message(kind: u8, group: Ipv4Addr, dst: Ipv4Addr) {
+    let cfg = ipv4::config();
+    let msg = build_message(kind, group);
+    let ip_pkt = ipv4::build_packet_with_options(
+        dst,
+        ipv4::PROTO_IGMP,
+        ipv4::MULTICAST_TTL,
+        &ROUTER_ALERT,
+        &msg,
+    );
+    let frame = ethernet::build_frame(
+        multicast_mac(dst),
+        cfg.mac,
+        ethernet::ETHERTYPE_IPV4,
+        &ip_pkt,
+    );
+    super::transmit_frame(&frame);
+}
+
+/// Add or remove a multicast MAC address on every registered NIC.
+fn program_filters(mac: MacAddress, add: bool) {
+    let mut mgr = NETWORK_MANAGER.lock();
+    for name in mgr.device_names() {
+        if let Some(dev) = mgr.device_mut(&name) {
+            let result = if add {
+                dev.add_multicast(mac)
+            } else {
+                dev.remove_multicast(mac)
+            };
+            if let Err(e) = result {
+                crate::serial_println!("[IGMP] {}: multicast filter update failed: {}", name, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multicast_mac_mapping() {
+        let mac = multicast_mac(Ipv4Addr::new(224, 0, 0, 251));
+        assert_eq!(mac.as_bytes(), &[0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB]);
+        // Only the low 23 bits survive
+        let mac = multicast_mac(Ipv4Addr::new(239, 255, 255, 250));
+        assert_eq!(mac.as_bytes(), &[0x01, 0x00, 0x5E, 0x7F, 0xFF, 0xFA]);
+        assert_eq!(mac, multicast_mac(Ipv4Addr::new(224, 127, 255, 250)));
+    }
+
+    #[test]
+    fn test_build_report() {
+        let msg = build_message(V2_MEMBERSHIP_REPORT, Ipv4Addr::new(224, 0, 0, 251));
+        assert_eq!(msg[0], 0x16);
+        assert_eq!(&msg[4..], &[224, 0, 0, 251]);
+        assert_eq!(ipv4::checksum(&msg), 0);
+    }
+}
@@ -19,12 +19,16 @@ pub const HEADER_SIZE: usize = 20;
 
 /// Protocol numbers
 pub const PROTO_ICMP: u8 = 1;
+pub const PROTO_IGMP: u8 = 2;
 pub const PROTO_TCP: u8 = 6;
 pub const PROTO_UDP: u8 = 17;
 
 /// Default TTL
 pub const DEFAULT_TTL: u8 = 64;
 
+/// TTL for multicast datagrams (link-local scope unless raised)
+pub const MULTICAST_TTL: u8 = 1;
+
 // ── Network configuration ───────────────────────────────────
 
 /// IP configuration for the interface.
@@ -148,8 +152,30 @@ impl<'a> Ipv4Packet<'a> {
 // ── Packet construction ─────────────────────────────────────
 
 /// Build a raw IPv4 packet (header + payload).
+///
+/// Multicast destinations get a TTL of 1 so they stay on the local link.
 pub fn build_packet(dst: Ipv4Addr, protocol: u8, payload: &[u8]) -> Vec<u8> {
-    let total_len = (HEADER_SIZE + payload.len()) as u16;
+    let ttl = if dst.is_multicast() {
+        MULTICAST_TTL
+    } else {
+        DEFAULT_TTL
+    };
+    build_packet_with_options(dst, protocol, ttl, &[], payload)
+}
+
+/// Build a raw IPv4 packet with an explicit TTL and header options.
+///
+/// `options` must be padded to a multiple of 4 bytes (at most 40).
+pub fn build_packet_with_options(
+    dst: Ipv4Addr,
+    protocol: u8,
+    ttl: u8,
+    options: &[u8],
+    payload: &[u8],
+) -> Vec<u8> {
+    debug_assert!(options.len() & 3 == 0 && options.len() <= 40);
+    let header_len = HEADER_SIZE + options.len();
+    let total_len = (header_len + payload.len()) as u16;
     let id = {
         let mut g = NEXT_ID.lock();
         let id = *g;
@@ -160,8 +186,8 @@ pub fn build_packet(dst: Ipv4Addr, protocol: u8, payload: &[u8]) -> Vec<u8> {
     let cfg = config();
     let mut pkt = Vec::with_capacity(total_len as usize);
 
-    // Version (4) + IHL (5 = 20 bytes)
-    pkt.push(0x45);
+    // Version (4) + IHL (in 32-bit words)
+    pkt.push(0x40 | (header_len / 4) as u8);
     // DSCP / ECN
     pkt.push(0x00);
     // Total length
@@ -174,7 +200,7 @@ pub fn build_packet(dst: Ipv4Addr, protocol: u8, payload: &[u8]) -> Vec<u8> {
     pkt.push(0x40); // DF set
     pkt.push(0x00);
     // TTL
-    pkt.push(DEFAULT_TTL);
+    pkt.push(ttl);
     // Protocol
     pkt.push(protocol);
     // Header checksum (placeholder, filled below)
@@ -184,9 +210,11 @@ pub fn build_packet(dst: Ipv4Addr, protocol: u8, payload: &[u8]) -> Vec<u8> {
     pkt.extend_from_slice(&cfg.ip.0);
     // Destination IP
     pkt.extend_from_slice(&dst.0);
+    // Options
+    pkt.extend_from_slice(options);
 
     // Compute and fill header checksum
-    let cksum = checksum(&pkt[..HEADER_SIZE]);
+    let cksum = checksum(&pkt[..header_len]);
     pkt[10] = (cksum >> 8) as u8;
     pkt[11] = cksum as u8;
 
@@ -199,12 +227,22 @@ pub fn build_packet(dst: Ipv4Addr, protocol: u8, payload: &[u8]) -> Vec<u8> {
 /// Wrap an IPv4 packet into a full Ethernet frame, resolving the
 /// next-hop MAC via ARP.
 ///
+/// Multicast destinations map straight to their group MAC address.
 /// Returns `None` if the MAC is not yet known (caller should send
 /// an ARP request and retry).
 pub fn send_packet(dst: Ipv4Addr, protocol: u8, payload: &[u8]) -> Option<Vec<u8>> {
     let cfg = config();
     let ip_pkt = build_packet(dst, protocol, payload);
 
+    if dst.is_multicast() {
+        return Some(ethernet::build_frame(
+            super::igmp::multicast_mac(dst),
+            cfg.mac,
+            ethernet::ETHERTYPE_IPV4,
+            &ip_pkt,
+        ));
+    }
+
     // Determine next-hop IP (gateway if not on local subnet)
     let next_hop = if is_local(dst, &cfg) {
         dst
@@ -3,17 +3,20 @@
 //! Full TCP/IP stack for online browsing over VirtIO-net.
 //!
 //! Layer overview (bottom → top):
-//!   VirtIO-net → Ethernet → ARP → IPv4 → UDP/TCP → DNS → HTTP/TLS
+//!   VirtIO-net → Ethernet → ARP → IPv4 (+ IGMP) → UDP/TCP → DNS → HTTP/TLS
 
 #![allow(dead_code)]
 
 pub mod arp;
+pub mod capture;
 pub mod crypto;
 pub mod dhcp;
 pub mod dns;
 pub mod ethernet;
 pub mod http;
+pub mod igmp;
 pub mod ipv4;
+pub mod stats;
 pub mod tcp;
 pub mod tls;
 pub mod tls13;
@@ -28,6 +31,8 @@ use core::fmt;
 
 use crate::drivers::net::NETWORK_MANAGER;
 
+pub use stats::NetworkStats;
+
 // ── IPv4 address ────────────────────────────────────────────
 
 /// IPv4 address.
@@ -51,6 +56,11 @@ impl Ipv4Addr {
         self.0 == [0, 0, 0, 0]
     }
 
+    /// Class D multicast address (224.0.0.0/4).
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0xF0 == 0xE0
+    }
+
     pub fn octets(&self) -> [u8; 4] {
         self.0
     }
@@ -213,6 +223,24 @@ pub fn interfaces() -> Vec<InterfaceInfo> {
     ifaces
 }
 
+/// Snapshot interface, protocol and socket statistics.
+pub fn stats() -> NetworkStats {
+    NetworkStats {
+        interfaces: interfaces(),
+        protocol: stats::protocol(),
+        tcp_sockets: tcp::connections()
+            .into_iter()
+            .filter(|&(_, state, _, _)| state != tcp::TcpState::Closed)
+            .map(|(_, state, local, remote)| stats::TcpSocketInfo {
+                local,
+                remote,
+                state,
+            })
+            .collect(),
+        udp_sockets: udp::socket_count(),
+    }
+}
+
 /// Initialise the network stack.
 pub fn init() {
     arp::init();
@@ -301,6 +329,7 @@ pub fn transmit_frame(frame: &[u8]) {
     for name in mgr.device_names() {
         if let Some(dev) = mgr.device_mut(&name) {
             if dev.transmit(frame).is_ok() {
+                capture::record(capture::Direction::Outbound, frame);
                 return;
             }
         }
@@ -319,7 +348,10 @@ pub fn receive_frame() -> Result<Vec<u8>, crate::drivers::net::NetworkError> {
             if dev.rx_available() {
                 let mut buf = [0u8; 2048];
                 match dev.receive(&mut buf) {
-                    Ok(n) if n > 0 => return Ok(buf[..n].to_vec()),
+                    Ok(n) if n > 0 => {
+                        capture::record(capture::Direction::Inbound, &buf[..n]);
+                        return Ok(buf[..n].to_vec());
+                    }
                     _ => {}
                 }
             }
@@ -342,6 +374,7 @@ pub fn poll_rx() {
                         // because the handler may want to transmit.
                         let pkt = buf[..n].to_vec();
                         drop(mgr);
+                        capture::record(capture::Direction::Inbound, &pkt);
                         process_rx(&pkt);
                         mgr = NETWORK_MANAGER.lock();
                         // Re-lookup the device because we dropped & re-acquired.
@@ -354,6 +387,20 @@ pub fn poll_rx() {
     }
 }
 
+/// Wait on a socket [`Poller`](network::socket::Poller), pumping the
+/// NICs with [`poll_rx`] until a registered socket is ready or
+/// `timeout_ms` runs out (`None` waits forever).
+pub fn wait_sockets(
+    poller: &network::socket::Poller,
+    timeout_ms: Option<u64>,
+) -> Vec<network::socket::PollEvent> {
+    let now_ms = || {
+        let t = crate::time::monotonic();
+        t.secs * 1000 + u64::from(t.nanos) / 1_000_000
+    };
+    poller.poll(timeout_ms, poll_rx, now_ms)
+}
+
 /// Dispatch a received Ethernet frame through the protocol stack.
 pub fn process_rx(frame: &[u8]) {
     let eth = match ethernet::EthernetFrame::parse(frame) {
@@ -363,8 +410,8 @@ pub fn process_rx(frame: &[u8]) {
 
     let cfg = ipv4::config();
 
-    // Only accept frames addressed to us or broadcast
-    if !eth.is_for_us(&cfg.mac) {
+    // Only accept frames addressed to us, broadcast, or a joined group
+    if !eth.is_for_us(&cfg.mac) && !igmp::accepts_mac(&eth.dst) {
         return;
     }
 
@@ -382,8 +429,11 @@ pub fn process_rx(frame: &[u8]) {
                             transmit_frame(&reply);
                         }
                     }
+                    ipv4::PROTO_IGMP => {
+                        igmp::process_incoming(&ip_pkt);
+                    }
                     ipv4::PROTO_UDP => {
-                        udp::process_incoming(ip_pkt.src, ip_pkt.payload);
+                        udp::process_incoming(ip_pkt.src, ip_pkt.dst, ip_pkt.payload);
                     }
                     ipv4::PROTO_TCP => {
                         tcp::process_incoming(ip_pkt.src, ip_pkt.payload);
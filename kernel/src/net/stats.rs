@@ -0,0 +1,269 @@
+//! Network Statistics
+//!
+//! Stack-wide counters for diagnostics (`netstat -s`, the DevTools
+//! timing panel). The protocol layers record events here as they happen;
+//! [`super::stats`] combines them with the per-interface packet counters
+//! kept by the NIC drivers and the current socket tables.
+
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use super::tcp::TcpState;
+use super::{InterfaceInfo, SocketAddr};
+use crate::time::{self, Timespec, NANOS_PER_SEC};
+
+// ── Latency ─────────────────────────────────────────────────
+
+/// Running summary of a latency measurement, in microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Latency {
+    /// Number of samples recorded.
+    pub count: u64,
+    /// Sum of all samples.
+    pub total_us: u64,
+    /// Fastest sample (0 until the first one).
+    pub min_us: u64,
+    /// Slowest sample.
+    pub max_us: u64,
+    /// Most recent sample.
+    pub last_us: u64,
+}
+
+impl Latency {
+    const fn new() -> Self {
+        Latency {
+            count: 0,
+            total_us: 0,
+            min_us: 0,
+            max_us: 0,
+            last_us: 0,
+        }
+    }
+
+    /// Add one sample.
+    pub fn record(&mut self, us: u64) {
+        self.min_us = if self.count == 0 {
+            us
+        } else {
+            self.min_us.min(us)
+        };
+        self.max_us = self.max_us.max(us);
+        self.last_us = us;
+        self.total_us = self.total_us.saturating_add(us);
+        self.count += 1;
+    }
+
+    /// Mean of all samples, or `None` before the first one.
+    pub fn average_us(&self) -> Option<u64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.total_us / self.count)
+        }
+    }
+}
+
+/// A started measurement; hand it back to [`Timer::elapsed_us`] when done.
+#[derive(Debug, Clone, Copy)]
+pub struct Timer(Timespec);
+
+impl Timer {
+    /// Start timing now.
+    pub fn start() -> Self {
+        Timer(time::monotonic())
+    }
+
+    /// Microseconds since the timer was started.
+    pub fn elapsed_us(&self) -> u64 {
+        let now = time::monotonic();
+        let to_ns = |t: Timespec| t.secs * NANOS_PER_SEC + t.nanos as u64;
+        to_ns(now).saturating_sub(to_ns(self.0)) / 1000
+    }
+}
+
+// ── Protocol counters ───────────────────────────────────────
+
+/// TCP counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpStats {
+    /// Connections we initiated (`connect`).
+    pub active_opens: u64,
+    /// Connections accepted on a listening port.
+    pub passive_opens: u64,
+    /// Connects that were refused or timed out.
+    pub failed_opens: u64,
+    /// Segments retransmitted after the retransmission timeout.
+    pub retransmits: u64,
+    /// Segments handed to IPv4.
+    pub segments_sent: u64,
+    /// Segments received and parsed.
+    pub segments_received: u64,
+    /// Time from SYN to ESTABLISHED for successful `connect`s.
+    pub connect_time: Latency,
+}
+
+/// UDP counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UdpStats {
+    /// Datagrams sent.
+    pub datagrams_sent: u64,
+    /// Datagrams queued on a bound socket.
+    pub datagrams_received: u64,
+    /// Datagrams dropped because no socket was bound to the port.
+    pub no_port: u64,
+}
+
+/// DNS resolver counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DnsStats {
+    /// Lookups answered from the host table or the cache.
+    pub cache_hits: u64,
+    /// Queries sent to the server.
+    pub queries: u64,
+    /// Queries that got no usable answer.
+    pub failures: u64,
+    /// Round-trip time of answered queries.
+    pub query_time: Latency,
+}
+
+/// DHCP client counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DhcpStats {
+    /// DISCOVER messages sent.
+    pub discovers: u64,
+    /// REQUEST messages sent.
+    pub requests: u64,
+    /// Leases acquired.
+    pub leases: u64,
+    /// Exchanges that ended without a lease.
+    pub failures: u64,
+}
+
+/// Counters kept by the protocol layers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtocolStats {
+    pub tcp: TcpStats,
+    pub udp: UdpStats,
+    pub dns: DnsStats,
+    pub dhcp: DhcpStats,
+}
+
+static COUNTERS: Mutex<ProtocolStats> = Mutex::new(ProtocolStats {
+    tcp: TcpStats {
+        active_opens: 0,
+        passive_opens: 0,
+        failed_opens: 0,
+        retransmits: 0,
+        segments_sent: 0,
+        segments_received: 0,
+        connect_time: Latency::new(),
+    },
+    udp: UdpStats {
+        datagrams_sent: 0,
+        datagrams_received: 0,
+        no_port: 0,
+    },
+    dns: DnsStats {
+        cache_hits: 0,
+        queries: 0,
+        failures: 0,
+        query_time: Latency::new(),
+    },
+    dhcp: DhcpStats {
+        discovers: 0,
+        requests: 0,
+        leases: 0,
+        failures: 0,
+    },
+});
+
+/// Update the protocol counters.
+///
+/// The counter lock is a leaf: it may be taken while holding a socket
+/// table lock, but nothing else is locked inside `f`.
+pub fn update<F: FnOnce(&mut ProtocolStats)>(f: F) {
+    f(&mut COUNTERS.lock());
+}
+
+/// Copy of the current protocol counters.
+pub fn protocol() -> ProtocolStats {
+    *COUNTERS.lock()
+}
+
+/// Zero the protocol counters (interface counters belong to the drivers).
+pub fn reset() {
+    *COUNTERS.lock() = ProtocolStats::default();
+}
+
+// ── Snapshot ────────────────────────────────────────────────
+
+/// An open TCP connection.
+#[derive(Debug, Clone)]
+pub struct TcpSocketInfo {
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: TcpState,
+}
+
+/// Point-in-time view of the whole stack, returned by [`super::stats`].
+#[derive(Debug, Clone)]
+pub struct NetworkStats {
+    /// Per-interface packet and byte counters.
+    pub interfaces: Vec<InterfaceInfo>,
+    /// Protocol counters.
+    pub protocol: ProtocolStats,
+    /// TCP connections that are not closed.
+    pub tcp_sockets: Vec<TcpSocketInfo>,
+    /// Bound UDP ports.
+    pub udp_sockets: usize,
+}
+
+impl NetworkStats {
+    /// Packets sent on all interfaces except loopback.
+    pub fn tx_packets(&self) -> u64 {
+        self.physical().map(|i| i.tx_packets).sum()
+    }
+
+    /// Packets received on all interfaces except loopback.
+    pub fn rx_packets(&self) -> u64 {
+        self.physical().map(|i| i.rx_packets).sum()
+    }
+
+    /// Bytes sent on all interfaces except loopback.
+    pub fn tx_bytes(&self) -> u64 {
+        self.physical().map(|i| i.tx_bytes).sum()
+    }
+
+    /// Bytes received on all interfaces except loopback.
+    pub fn rx_bytes(&self) -> u64 {
+        self.physical().map(|i| i.rx_bytes).sum()
+    }
+
+    /// Open sockets of either protocol.
+    pub fn active_sockets(&self) -> usize {
+        self.tcp_sockets.len() + self.udp_sockets
+    }
+
+    fn physical(&self) -> impl Iterator<Item = &InterfaceInfo> {
+        self.interfaces.iter().filter(|i| !i.ip.is_loopback())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_summary() {
+        let mut l = Latency::default();
+        assert_eq!(l.average_us(), None);
+        l.record(300);
+        l.record(100);
+        l.record(200);
+        assert_eq!(l.count, 3);
+        assert_eq!(l.min_us, 100);
+        assert_eq!(l.max_us, 300);
+        assert_eq!(l.last_us, 200);
+        assert_eq!(l.average_us(), Some(200));
+    }
+}
@@ -12,7 +12,7 @@ use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU16, AtomicU64, Ordering};
 use spin::Mutex;
 
-use super::ipv4;
+use super::{ipv4, stats};
 use super::{Ipv4Addr, NetError, SocketAddr};
 
 // ── TCP header constants ────────────────────────────────────
@@ -181,6 +181,8 @@ struct TcpTable {
     key_map: BTreeMap<ConnKey, ConnId>,
     /// Listening sockets: local_port -> ConnId
     listeners: BTreeMap<u16, ConnId>,
+    /// Passively opened connections per listener, oldest first
+    backlogs: BTreeMap<ConnId, VecDeque<ConnId>>,
 }
 
 static TABLE: Mutex<Option<TcpTable>> = Mutex::new(None);
@@ -215,6 +217,7 @@ pub fn init() {
         connections: BTreeMap::new(),
         key_map: BTreeMap::new(),
         listeners: BTreeMap::new(),
+        backlogs: BTreeMap::new(),
     });
 }
 
@@ -270,6 +273,7 @@ pub fn listen(id: ConnId, port: u16) -> Result<(), NetError> {
 
 /// Connect to a remote address (real TCP 3-way handshake).
 pub fn connect(id: ConnId, remote: SocketAddr) -> Result<(), NetError> {
+    let timer = stats::Timer::start();
     let syn_frame = with_table(|t| {
         let conn = t
             .connections
@@ -300,29 +304,56 @@ pub fn connect(id: ConnId, remote: SocketAddr) -> Result<(), NetError> {
     })?;
 
     // Transmit SYN
+    stats::update(|s| s.tcp.active_opens += 1);
     if let Some(frame) = syn_frame {
         super::transmit_frame(&frame);
     }
 
     // Wait for SYN-ACK (poll-based)
-    for _ in 0..300 {
+    let mut retries = 0;
+    for tick in 1..=300u32 {
         super::poll_rx();
 
         let state = with_table(|t| t.connections.get(&id).map(|c| c.state));
 
         match state {
-            Some(TcpState::Established) => return Ok(()),
-            Some(TcpState::Closed) => return Err(NetError::ConnectionRefused),
+            Some(TcpState::Established) => {
+                let us = timer.elapsed_us();
+                stats::update(|s| s.tcp.connect_time.record(us));
+                return Ok(());
+            }
+            Some(TcpState::Closed) => {
+                stats::update(|s| s.tcp.failed_opens += 1);
+                return Err(NetError::ConnectionRefused);
+            }
             None => return Err(NetError::ConnectionNotFound),
             _ => {}
         }
 
+        // No SYN-ACK within the RTO: send the SYN again. Rebuilding it
+        // also covers a first SYN that was held back by ARP.
+        if tick % RTO_TICKS == 0 && retries < MAX_RETRIES {
+            retries += 1;
+            let frame = with_table(|t| {
+                let conn = t.connections.get_mut(&id)?;
+                let snd_nxt = conn.snd_nxt;
+                let frame = build_segment(conn, SYN, &[]);
+                conn.snd_nxt = snd_nxt;
+                frame
+            });
+            if let Some(frame) = frame {
+                stats::update(|s| s.tcp.retransmits += 1);
+                super::transmit_frame(&frame);
+            }
+        }
+
         // ~10ms spin
         for _ in 0..100_000 {
             core::hint::spin_loop();
         }
     }
 
+    stats::update(|s| s.tcp.failed_opens += 1);
     Err(NetError::TimedOut)
 }
 
@@ -414,6 +445,47 @@ pub fn recv_blocking(id: ConnId, buf: &mut [u8], timeout_iters: u32) -> Result<u
     Err(NetError::TimedOut)
 }
 
+/// Take the oldest connection a listener has completed the handshake
+/// for. `WouldBlock` if there is none yet.
+pub fn accept(id: ConnId) -> Result<ConnId, NetError> {
+    with_table(|t| {
+        let listener = t.connections.get(&id).ok_or(NetError::ConnectionNotFound)?;
+        if listener.state != TcpState::Listen {
+            return Err(NetError::InvalidArgument);
+        }
+
+        let backlog = t.backlogs.entry(id).or_default();
+        // Forget connections destroyed before they were accepted
+        backlog.retain(|conn| t.connections.contains_key(conn));
+        let ready = backlog
+            .iter()
+            .position(|conn| {
+                t.connections
+                    .get(conn)
+                    .is_some_and(|c| c.state != TcpState::SynReceived)
+            })
+            .ok_or(NetError::WouldBlock)?;
+        Ok(backlog.remove(ready).unwrap())
+    })
+}
+
+/// Blocking accept: polls until a connection is established.
+pub fn accept_blocking(id: ConnId, timeout_iters: u32) -> Result<ConnId, NetError> {
+    for _ in 0..timeout_iters {
+        super::poll_rx();
+
+        match accept(id) {
+            Err(NetError::WouldBlock) => {
+                for _ in 0..50_000 {
+                    core::hint::spin_loop();
+                }
+            }
+            result => return result,
+        }
+    }
+    Err(NetError::TimedOut)
+}
+
 /// Check how many bytes are available in the receive buffer.
 pub fn recv_available(id: ConnId) -> usize {
     with_table(|t| t.connections.get(&id).map_or(0, |c| c.recv_buf.len()))
@@ -462,6 +534,7 @@ pub fn destroy(id: ConnId) {
             t.key_map.remove(&key);
             if conn.state == TcpState::Listen {
                 t.listeners.remove(&conn.local.port);
+                t.backlogs.remove(&id);
             }
         }
     });
@@ -495,6 +568,7 @@ pub fn process_incoming(src_ip: Ipv4Addr, data: &[u8]) {
         Some(s) => s,
         None => return,
     };
+    stats::update(|s| s.tcp.segments_received += 1);
 
     with_table(|t| {
         // Look up connection by 4-tuple
@@ -515,7 +589,7 @@ pub fn process_incoming(src_ip: Ipv4Addr, data: &[u8]) {
             }
         } else if (seg.flags & SYN) != 0 {
             // Check for listening socket
-            if let Some(&_listener_id) = t.listeners.get(&seg.dst_port) {
+            if let Some(&listener_id) = t.listeners.get(&seg.dst_port) {
                 // Accept incoming connection (simplified: auto-accept)
                 let id = ConnId(NEXT_CONN.fetch_add(1, Ordering::Relaxed));
                 let iss = generate_isn();
@@ -543,6 +617,8 @@ pub fn process_incoming(src_ip: Ipv4Addr, data: &[u8]) {
 
                 t.key_map.insert(key, id);
                 t.connections.insert(id, conn);
+                t.backlogs.entry(listener_id).or_default().push_back(id);
+                stats::update(|s| s.tcp.passive_opens += 1);
             }
         } else if (seg.flags & RST) == 0 {
             // Send RST for unexpected segments
@@ -759,7 +835,11 @@ fn build_segment(conn: &mut TcpConnection, flags: u8, payload: &[u8]) -> Option<
     conn.snd_nxt = conn.snd_nxt.wrapping_add(seq_advance);
 
     // Wrap in IPv4 + Ethernet
-    ipv4::send_packet(conn.remote.ip, ipv4::PROTO_TCP, &seg)
+    let frame = ipv4::send_packet(conn.remote.ip, ipv4::PROTO_TCP, &seg);
+    if frame.is_some() {
+        stats::update(|s| s.tcp.segments_sent += 1);
+    }
+    frame
 }
 
 /// TCP checksum.
@@ -1,6 +1,10 @@
 //! UDP Layer
 //!
 //! Simple datagram protocol over IPv4. Used primarily for DNS queries.
+//!
+//! Sockets are identified by their bound port. A socket can join
+//! multicast groups; datagrams sent to a group are delivered to every
+//! socket on the destination port that joined it.
 
 #![allow(dead_code)]
 
@@ -10,8 +14,8 @@ use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU16, Ordering};
 use spin::Mutex;
 
-use super::ipv4;
-use super::Ipv4Addr;
+use super::{igmp, ipv4, stats};
+use super::{Ipv4Addr, NetError};
 
 // ── UDP header ──────────────────────────────────────────────
 
@@ -68,6 +72,8 @@ pub struct ReceivedDatagram {
 struct UdpSocketTable {
     /// port -> receive queue
     sockets: BTreeMap<u16, VecDeque<ReceivedDatagram>>,
+    /// multicast group -> ports that joined it
+    groups: BTreeMap<Ipv4Addr, Vec<u16>>,
 }
 
 static SOCKETS: Mutex<Option<UdpSocketTable>> = Mutex::new(None);
@@ -88,6 +94,7 @@ where
 pub fn init() {
     *SOCKETS.lock() = Some(UdpSocketTable {
         sockets: BTreeMap::new(),
+        groups: BTreeMap::new(),
     });
 }
 
@@ -106,11 +113,70 @@ pub fn bind(port: u16) -> u16 {
     port
 }
 
-/// Unbind a socket.
+/// Unbind a socket, leaving any multicast groups it joined.
 pub fn unbind(port: u16) {
-    with_sockets(|t| {
+    let joined = with_sockets(|t| {
         t.sockets.remove(&port);
+        let mut joined = Vec::new();
+        t.groups.retain(|&group, ports| {
+            if let Some(pos) = ports.iter().position(|&p| p == port) {
+                ports.remove(pos);
+                joined.push(group);
+            }
+            !ports.is_empty()
+        });
+        joined
     });
+    for group in joined {
+        let _ = igmp::leave(group);
+    }
+}
+
+/// Join the socket bound to `port` to a multicast group.
+///
+/// `interface` selects the local interface by address; `Ipv4Addr::ANY`
+/// picks the default one. Several sockets may join the same group.
+pub fn join_multicast(port: u16, group: Ipv4Addr, interface: Ipv4Addr) -> Result<(), NetError> {
+    if !group.is_multicast() {
+        return Err(NetError::InvalidArgument);
+    }
+    with_sockets(|t| {
+        if !t.sockets.contains_key(&port) {
+            return Err(NetError::NotConnected);
+        }
+        let ports = t.groups.entry(group).or_default();
+        if ports.contains(&port) {
+            return Err(NetError::AddressInUse);
+        }
+        ports.push(port);
+        Ok(())
+    })?;
+
+    // IGMP transmits and touches the NICs, so run it outside the table lock
+    if let Err(e) = igmp::join(group, interface) {
+        with_sockets(|t| remove_member(t, group, port));
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Leave a multicast group previously joined by the socket on `port`.
+pub fn leave_multicast(port: u16, group: Ipv4Addr) -> Result<(), NetError> {
+    if !with_sockets(|t| remove_member(t, group, port)) {
+        return Err(NetError::InvalidArgument);
+    }
+    igmp::leave(group)
+}
+
+/// Multicast groups joined by the socket on `port`.
+pub fn multicast_groups(port: u16) -> Vec<Ipv4Addr> {
+    with_sockets(|t| {
+        t.groups
+            .iter()
+            .filter(|(_, ports)| ports.contains(&port))
+            .map(|(&group, _)| group)
+            .collect()
+    })
 }
 
 /// Build a UDP datagram wrapped in an IPv4 packet (no Ethernet yet).
@@ -146,30 +212,46 @@ pub fn build_datagram(src_port: u16, dst_ip: Ipv4Addr, dst_port: u16, payload: &
 
 /// Send a UDP datagram out as a full Ethernet frame.
 ///
-/// Returns `None` if ARP resolution is pending.
+/// Datagrams to a group joined locally are also looped back to the
+/// local members. Returns `None` if ARP resolution is pending.
 pub fn send(src_port: u16, dst_ip: Ipv4Addr, dst_port: u16, payload: &[u8]) -> Option<Vec<u8>> {
-    ipv4::send_packet(
-        dst_ip,
-        ipv4::PROTO_UDP,
-        &build_udp_segment(src_port, dst_ip, dst_port, payload),
-    )
+    let segment = build_udp_segment(src_port, dst_ip, dst_port, payload);
+    if dst_ip.is_multicast() && igmp::is_member(dst_ip) {
+        process_incoming(ipv4::config().ip, dst_ip, &segment);
+    }
+    stats::update(|s| s.udp.datagrams_sent += 1);
+    ipv4::send_packet(dst_ip, ipv4::PROTO_UDP, &segment)
 }
 
 /// Process an incoming UDP datagram (called from IPv4 dispatch).
-pub fn process_incoming(src_ip: Ipv4Addr, data: &[u8]) {
+///
+/// Multicast datagrams only reach sockets that joined the group.
+pub fn process_incoming(src_ip: Ipv4Addr, dst_ip: Ipv4Addr, data: &[u8]) {
     if let Some(pkt) = UdpPacket::parse(data) {
         with_sockets(|t| {
-            if let Some(queue) = t.sockets.get_mut(&pkt.dst_port) {
-                queue.push_back(ReceivedDatagram {
-                    src_ip,
-                    src_port: pkt.src_port,
-                    data: Vec::from(pkt.payload),
-                });
-                // Keep queue bounded
-                while queue.len() > 64 {
-                    queue.pop_front();
+            if dst_ip.is_multicast() {
+                let joined = t
+                    .groups
+                    .get(&dst_ip)
+                    .is_some_and(|ports| ports.contains(&pkt.dst_port));
+                if !joined {
+                    return;
                 }
             }
+            let Some(queue) = t.sockets.get_mut(&pkt.dst_port) else {
+                stats::update(|s| s.udp.no_port += 1);
+                return;
+            };
+            queue.push_back(ReceivedDatagram {
+                src_ip,
+                src_port: pkt.src_port,
+                data: Vec::from(pkt.payload),
+            });
+            // Keep queue bounded
+            while queue.len() > 64 {
+                queue.pop_front();
+            }
+            stats::update(|s| s.udp.datagrams_received += 1);
         });
     }
 }
@@ -179,6 +261,11 @@ pub fn recv(port: u16) -> Option<ReceivedDatagram> {
     with_sockets(|t| t.sockets.get_mut(&port).and_then(|q| q.pop_front()))
 }
 
+/// Number of bound ports.
+pub fn socket_count() -> usize {
+    with_sockets(|t| t.sockets.len())
+}
+
 /// Check if there's data available on a port.
 pub fn has_data(port: u16) -> bool {
     with_sockets(|t| t.sockets.get(&port).map_or(false, |q| !q.is_empty()))
@@ -186,6 +273,21 @@ pub fn has_data(port: u16) -> bool {
 
 // ── Internal helpers ────────────────────────────────────────
 
+/// Remove `port` from a group's member list. Returns whether it was a member.
+fn remove_member(t: &mut UdpSocketTable, group: Ipv4Addr, port: u16) -> bool {
+    let Some(ports) = t.groups.get_mut(&group) else {
+        return false;
+    };
+    let Some(pos) = ports.iter().position(|&p| p == port) else {
+        return false;
+    };
+    ports.remove(pos);
+    if ports.is_empty() {
+        t.groups.remove(&group);
+    }
+    true
+}
+
 /// Build a raw UDP segment (header + payload) without IPv4 wrapping.
 fn build_udp_segment(src_port: u16, dst_ip: Ipv4Addr, dst_port: u16, payload: &[u8]) -> Vec<u8> {
     let udp_len = (HEADER_SIZE + payload.len()) as u16;
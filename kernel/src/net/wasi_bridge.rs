@@ -92,6 +92,29 @@ pub fn tcp_connect(ip: [u8; 4], port: u16) -> Result<TcpHandle, NetError> {
     Ok(TcpHandle { conn_id: conn })
 }
 
+/// Listen for TCP connections on a local port.
+pub fn tcp_listen(port: u16) -> Result<TcpHandle, NetError> {
+    let conn = tcp::create();
+    if let Err(e) = tcp::listen(conn, port) {
+        tcp::destroy(conn);
+        return Err(e);
+    }
+    Ok(TcpHandle { conn_id: conn })
+}
+
+/// Accept a connection on a listening handle (blocking, ≈3 s timeout).
+pub fn tcp_accept(handle: &TcpHandle) -> Result<TcpHandle, NetError> {
+    let conn = tcp::accept_blocking(handle.conn_id, 300)?;
+    Ok(TcpHandle { conn_id: conn })
+}
+
+/// Non-blocking accept — returns `WouldBlock` when no connection is ready.
+pub fn tcp_accept_nonblocking(handle: &TcpHandle) -> Result<TcpHandle, NetError> {
+    super::poll_rx();
+    let conn = tcp::accept(handle.conn_id)?;
+    Ok(TcpHandle { conn_id: conn })
+}
+
 /// Send data on an established TCP connection.
 pub fn tcp_send(handle: &TcpHandle, data: &[u8]) -> Result<usize, NetError> {
     tcp::send(handle.conn_id, data)
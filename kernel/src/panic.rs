@@ -29,6 +29,22 @@ fn panic(info: &PanicInfo) -> ! {
 
     serial_println!("Message: {}", info.message());
 
+    // Persist the panic context so it can be inspected after reboot
+    match crate::crash::record_panic(info) {
+        Some(record) => {
+            serial_println!();
+            serial_println!("Backtrace:");
+            for (i, address) in record.backtrace().iter().enumerate() {
+                serial_println!("  #{}: {:#018x}", i, address);
+            }
+            match record.result {
+                Ok(()) => serial_println!("Crash dump saved"),
+                Err(e) => serial_println!("Crash dump not saved: {:?}", e),
+            }
+        }
+        None => serial_println!("(nested panic while recording crash dump)"),
+    }
+
     serial_println!();
     serial_println!("System halted.");
     serial_println!("========================================");
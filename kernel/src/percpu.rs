@@ -0,0 +1,549 @@
+//! Per-CPU Data and Kernel Thread-Local Storage
+//!
+//! Every logical CPU owns a 64-byte [`PerCpuData`] area. Both
+//! `IA32_GS_BASE` and `IA32_KERNEL_GS_BASE` point at it, so `gs:`-relative
+//! loads reach the current CPU's area in Ring 0 whether or not a `swapgs`
+//! is outstanding. User space never sets its own GS base
+//! (`arch_prctl(ARCH_SET_GS)` is not supported), so the `swapgs` pair at
+//! syscall entry/exit exchanges two equal values.
+//!
+//! On top of the area this module provides:
+//!
+//! - [`PerCpu<T>`] (declared with [`per_cpu!`]) — a variable with one
+//!   cache-line-aligned instance per CPU, for state such as run queues or
+//!   allocator caches that each CPU updates without locking.
+//! - [`KernelTls`] / [`TlsKey`] — a small block of words owned by each
+//!   task. The scheduler installs the incoming task's block in the area on
+//!   every switch, so a [`TlsKey`] always addresses the running task.
+//!
+//! # Memory Layout (at GS base)
+//!
+//! | Offset | Field            | Description                       |
+//! |--------|------------------|-----------------------------------|
+//! |   0    | kernel_rsp       | Kernel stack top for this CPU     |
+//! |   8    | user_rsp_scratch | Saved user RSP during syscall     |
+//! |  16    | current_pid      | Current process ID on this CPU    |
+//! |  24    | cpu_id           | CPU core number                   |
+//! |  32    | in_syscall       | Non-zero while processing syscall |
+//! |  40    | self_ptr         | Address of this area              |
+//! |  48    | tls_base         | Running task's [`KernelTls`]      |
+//!
+//! Offsets 0–32 are hard-coded in the syscall entry stubs
+//! (`ring3_syscall_entry`, `linux_syscall_entry`).
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use x86_64::registers::model_specific::Msr;
+
+/// Maximum number of CPUs supported.
+pub const MAX_CPUS: usize = 64;
+
+/// Number of word-sized slots in a task's kernel TLS block.
+pub const TLS_SLOTS: usize = 16;
+
+/// MSR holding the active GS base.
+const IA32_GS_BASE: u32 = 0xC000_0101;
+
+/// MSR swapped with the GS base on `swapgs`.
+const IA32_KERNEL_GS_BASE: u32 = 0xC000_0102;
+
+/// Per-CPU data structure.
+///
+/// Accessed via the GS segment; field offsets must match [`offsets`] and
+/// the syscall entry assembly.
+#[repr(C, align(64))]
+pub struct PerCpuData {
+    /// Kernel stack pointer for the current process on this CPU.
+    /// Loaded by `swapgs; mov rsp, [gs:0]` during syscall entry.
+    pub kernel_rsp: u64,
+
+    /// Scratch space for saving user RSP.
+    /// Written by `mov [gs:8], rsp` during syscall entry.
+    pub user_rsp_scratch: u64,
+
+    /// Process ID of the currently running process on this CPU.
+    pub current_pid: u64,
+
+    /// CPU core ID.
+    pub cpu_id: u64,
+
+    /// Non-zero while a syscall is being processed.
+    pub in_syscall: u64,
+
+    /// Address of this structure.
+    pub self_ptr: u64,
+
+    /// Address of the running task's [`KernelTls`] (0 if none).
+    pub tls_base: u64,
+
+    /// Padding to cache-line boundary.
+    _pad: u64,
+}
+
+impl PerCpuData {
+    const fn new() -> Self {
+        Self {
+            kernel_rsp: 0,
+            user_rsp_scratch: 0,
+            current_pid: 0,
+            cpu_id: 0,
+            in_syscall: 0,
+            self_ptr: 0,
+            tls_base: 0,
+            _pad: 0,
+        }
+    }
+}
+
+// Verify layout at compile time.
+const _: () = {
+    assert!(core::mem::size_of::<PerCpuData>() == 64);
+    assert!(core::mem::align_of::<PerCpuData>() == 64);
+    assert!(core::mem::offset_of!(PerCpuData, kernel_rsp) == offsets::KERNEL_RSP);
+    assert!(core::mem::offset_of!(PerCpuData, user_rsp_scratch) == offsets::USER_RSP_SCRATCH);
+    assert!(core::mem::offset_of!(PerCpuData, current_pid) == offsets::CURRENT_PID);
+    assert!(core::mem::offset_of!(PerCpuData, cpu_id) == offsets::CPU_ID);
+    assert!(core::mem::offset_of!(PerCpuData, in_syscall) == offsets::IN_SYSCALL);
+    assert!(core::mem::offset_of!(PerCpuData, self_ptr) == offsets::SELF_PTR);
+    assert!(core::mem::offset_of!(PerCpuData, tls_base) == offsets::TLS_BASE);
+};
+
+/// Static array of per-CPU data, one entry per logical CPU.
+static mut PER_CPU_ARRAY: [PerCpuData; MAX_CPUS] = {
+    const INIT: PerCpuData = PerCpuData::new();
+    [INIT; MAX_CPUS]
+};
+
+/// Bitmask of CPUs whose area has been installed in their GS base.
+static ONLINE: AtomicU64 = AtomicU64::new(0);
+
+/// Assembly-visible field offsets.
+/// These MUST match the struct layout above.
+pub mod offsets {
+    /// Offset of `kernel_rsp` within [`PerCpuData`](super::PerCpuData).
+    pub const KERNEL_RSP: usize = 0;
+    /// Offset of `user_rsp_scratch` within [`PerCpuData`](super::PerCpuData).
+    pub const USER_RSP_SCRATCH: usize = 8;
+    /// Offset of `current_pid` within [`PerCpuData`](super::PerCpuData).
+    pub const CURRENT_PID: usize = 16;
+    /// Offset of `cpu_id` within [`PerCpuData`](super::PerCpuData).
+    pub const CPU_ID: usize = 24;
+    /// Offset of `in_syscall` within [`PerCpuData`](super::PerCpuData).
+    pub const IN_SYSCALL: usize = 32;
+    /// Offset of `self_ptr` within [`PerCpuData`](super::PerCpuData).
+    pub const SELF_PTR: usize = 40;
+    /// Offset of `tls_base` within [`PerCpuData`](super::PerCpuData).
+    pub const TLS_BASE: usize = 48;
+}
+
+/// Raw pointer to the area of `cpu`.
+fn area(cpu: usize) -> *mut PerCpuData {
+    assert!(cpu < MAX_CPUS, "CPU ID out of range");
+    // SAFETY: in bounds; only a raw pointer is formed, no reference.
+    unsafe { core::ptr::addr_of_mut!(PER_CPU_ARRAY[cpu]) }
+}
+
+// ─── Initialisation ──────────────────────────────────────────────────
+
+/// Initialise per-CPU data for the bootstrap processor (CPU 0).
+///
+/// Idempotent; both the scheduler's Ring 3 setup and the syscall layer
+/// call it.
+pub fn init() {
+    init_cpu(0);
+}
+
+/// Initialise the area of `cpu` and load it into both GS base MSRs.
+///
+/// Must run on `cpu` itself: the BSP through [`init`], each AP from its
+/// startup path before it schedules tasks.
+pub fn init_cpu(cpu: usize) {
+    let area = area(cpu);
+
+    // SAFETY: each CPU only initialises its own area, before using it.
+    // Writing the GS base MSRs is valid in Ring 0.
+    unsafe {
+        (*area).cpu_id = cpu as u64;
+        (*area).self_ptr = area as u64;
+
+        Msr::new(IA32_GS_BASE).write(area as u64);
+        Msr::new(IA32_KERNEL_GS_BASE).write(area as u64);
+    }
+
+    ONLINE.fetch_or(1 << cpu, Ordering::Release);
+}
+
+/// Whether the bootstrap processor's area is installed.
+pub fn is_initialized() -> bool {
+    ONLINE.load(Ordering::Acquire) & 1 != 0
+}
+
+/// Number of CPUs whose area is installed.
+pub fn online_cpus() -> usize {
+    ONLINE.load(Ordering::Acquire).count_ones() as usize
+}
+
+/// ID of the CPU executing this code.
+///
+/// A single `gs:`-relative load once [`init`] has run; CPU 0 before that.
+#[inline]
+pub fn current_cpu() -> usize {
+    if !is_initialized() {
+        return 0;
+    }
+
+    let id: u64;
+    // SAFETY: GS base points at this CPU's area (see module docs);
+    // offset 24 is `cpu_id`.
+    unsafe {
+        core::arch::asm!(
+            "mov {}, qword ptr gs:[24]",
+            out(reg) id,
+            options(nostack, readonly, preserves_flags),
+        );
+    }
+    id as usize
+}
+
+/// Raw pointer to the area of the CPU executing this code.
+pub fn current() -> *mut PerCpuData {
+    area(current_cpu())
+}
+
+// ─── Field accessors ─────────────────────────────────────────────────
+
+/// Update the kernel stack pointer for a CPU.
+///
+/// Called during process switch to set the kernel RSP that will be
+/// loaded on the next `syscall` instruction from userspace.
+pub fn set_kernel_rsp(cpu: usize, rsp: u64) {
+    // SAFETY: aligned pointer into the static array.
+    unsafe {
+        (*area(cpu)).kernel_rsp = rsp;
+    }
+}
+
+/// Update the current PID on a CPU.
+pub fn set_current_pid(cpu: usize, pid: u64) {
+    // SAFETY: aligned pointer into the static array.
+    unsafe {
+        (*area(cpu)).current_pid = pid;
+    }
+}
+
+/// Get the current PID on a CPU.
+pub fn get_current_pid(cpu: usize) -> u64 {
+    // SAFETY: aligned pointer into the static array.
+    unsafe { (*area(cpu)).current_pid }
+}
+
+/// Get the current PID on the CPU executing this code.
+pub fn current_pid() -> u64 {
+    get_current_pid(current_cpu())
+}
+
+/// Get the saved user RSP (set by syscall entry assembly).
+pub fn get_user_rsp(cpu: usize) -> u64 {
+    // SAFETY: aligned pointer into the static array.
+    unsafe { (*area(cpu)).user_rsp_scratch }
+}
+
+/// Get a raw pointer to the per-CPU data for a given CPU.
+pub fn get_per_cpu_ptr(cpu: usize) -> *const PerCpuData {
+    area(cpu)
+}
+
+// ─── Per-CPU variables ───────────────────────────────────────────────
+
+/// One CPU's instance of a [`PerCpu`] variable, alone in its cache line.
+#[doc(hidden)]
+#[repr(align(64))]
+pub struct CpuSlot<T>(T);
+
+impl<T> CpuSlot<T> {
+    #[doc(hidden)]
+    pub const fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+/// A variable with one instance per CPU.
+///
+/// Declare with [`per_cpu!`]. Each CPU normally touches only its own
+/// instance, so `T` only needs cheap interior mutability (atomics with
+/// `Relaxed` ordering, or a lock that is never contended). Other CPUs'
+/// instances stay reachable through [`get_for`](Self::get_for) and
+/// [`iter`](Self::iter) for aggregation.
+pub struct PerCpu<T> {
+    slots: [CpuSlot<T>; MAX_CPUS],
+}
+
+impl<T> PerCpu<T> {
+    #[doc(hidden)]
+    pub const fn from_slots(slots: [CpuSlot<T>; MAX_CPUS]) -> Self {
+        Self { slots }
+    }
+
+    /// Instance of the CPU executing this code.
+    ///
+    /// Without interrupts disabled the task may be preempted and, once
+    /// tasks migrate, resumed on another CPU; use [`with`](Self::with)
+    /// when that matters.
+    pub fn get(&self) -> &T {
+        &self.slots[current_cpu()].0
+    }
+
+    /// Instance of `cpu`.
+    pub fn get_for(&self, cpu: usize) -> &T {
+        &self.slots[cpu].0
+    }
+
+    /// Run `f` on this CPU's instance with interrupts disabled.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        x86_64::instructions::interrupts::without_interrupts(|| f(self.get()))
+    }
+
+    /// Instances of all CPUs, in CPU order. Offline CPUs keep their
+    /// initial value.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().map(|slot| &slot.0)
+    }
+}
+
+/// Declare a [`PerCpu`] static; `$init` is evaluated once per CPU and
+/// must be a constant expression.
+///
+/// ```ignore
+/// per_cpu! {
+///     /// Timer interrupts handled by each CPU.
+///     static TICKS: AtomicU64 = AtomicU64::new(0);
+/// }
+///
+/// TICKS.get().fetch_add(1, Ordering::Relaxed);
+/// let total: u64 = TICKS.iter().map(|t| t.load(Ordering::Relaxed)).sum();
+/// ```
+#[macro_export]
+macro_rules! per_cpu {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;) => {
+        $(#[$attr])*
+        $vis static $name: $crate::percpu::PerCpu<$ty> = $crate::percpu::PerCpu::from_slots(
+            [const { $crate::percpu::CpuSlot::new($init) }; $crate::percpu::MAX_CPUS],
+        );
+    };
+}
+
+// ─── Kernel thread-local storage ─────────────────────────────────────
+
+/// Kernel thread-local storage block of one task.
+///
+/// Each task owns one (boxed, so its address is stable); the scheduler
+/// installs it with [`set_tls`] when switching to the task.
+#[repr(C, align(64))]
+pub struct KernelTls {
+    slots: [AtomicU64; TLS_SLOTS],
+}
+
+impl KernelTls {
+    /// Create a zeroed block.
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { AtomicU64::new(0) }; TLS_SLOTS],
+        }
+    }
+}
+
+impl Default for KernelTls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Install `tls` as the running task's block on this CPU (`None` clears).
+///
+/// The block must stay alive until another block is installed.
+pub fn set_tls(tls: Option<&KernelTls>) {
+    let base = tls.map_or(0, |tls| tls as *const KernelTls as u64);
+    // SAFETY: aligned pointer into the static array.
+    unsafe {
+        (*current()).tls_base = base;
+    }
+}
+
+/// Address of the running task's block on this CPU (0 if none).
+pub fn tls_base() -> u64 {
+    // SAFETY: aligned pointer into the static array.
+    unsafe { (*current()).tls_base }
+}
+
+/// Next unreserved TLS slot.
+static NEXT_TLS_KEY: AtomicUsize = AtomicUsize::new(0);
+
+/// A slot reserved in every task's kernel TLS block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlsKey(usize);
+
+impl TlsKey {
+    /// Reserve a slot; `None` once all [`TLS_SLOTS`] are taken.
+    pub fn alloc() -> Option<Self> {
+        NEXT_TLS_KEY
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                (next < TLS_SLOTS).then_some(next + 1)
+            })
+            .ok()
+            .map(TlsKey)
+    }
+
+    /// Value of the slot for the running task (0 without a block).
+    pub fn get(self) -> u64 {
+        Self::with_current(|tls| tls.slots[self.0].load(Ordering::Relaxed)).unwrap_or(0)
+    }
+
+    /// Set the slot for the running task.
+    ///
+    /// Returns `false` if no block is installed (early boot).
+    pub fn set(self, value: u64) -> bool {
+        Self::with_current(|tls| tls.slots[self.0].store(value, Ordering::Relaxed)).is_some()
+    }
+
+    /// Run `f` on the running task's block, if installed.
+    fn with_current<R>(f: impl FnOnce(&KernelTls) -> R) -> Option<R> {
+        let base = tls_base();
+        // SAFETY: a non-zero base was installed by `set_tls` from a live
+        // block, which the running task owns until it is switched out.
+        unsafe { (base as *const KernelTls).as_ref() }.map(f)
+    }
+}
+
+// ─── Self-test ───────────────────────────────────────────────────────
+
+/// Check that per-CPU instances and task TLS blocks are isolated.
+///
+/// Runs at boot after [`init`]. Counters are bumped through the current
+/// CPU and through explicit CPU indices; each instance must see only its
+/// own updates. Two TLS blocks are then swapped in and out under one key.
+/// Returns a description of the first failed check.
+pub fn self_test() -> Result<(), &'static str> {
+    per_cpu! {
+        static COUNTERS: AtomicU64 = AtomicU64::new(0);
+    }
+
+    if !is_initialized() {
+        return Err("per-CPU area not installed");
+    }
+    let cpu = current_cpu();
+    if cpu != 0 {
+        return Err("boot CPU is not CPU 0");
+    }
+    let self_ptr: u64;
+    // SAFETY: GS base points at this CPU's area; offset 40 is `self_ptr`.
+    unsafe {
+        core::arch::asm!(
+            "mov {}, qword ptr gs:[40]",
+            out(reg) self_ptr,
+            options(nostack, readonly, preserves_flags),
+        );
+    }
+    if self_ptr != get_per_cpu_ptr(cpu) as u64 {
+        return Err("GS base does not point at this CPU's area");
+    }
+
+    let other = MAX_CPUS - 1;
+    for _ in 0..3 {
+        COUNTERS.get().fetch_add(1, Ordering::Relaxed);
+    }
+    COUNTERS.get_for(other).fetch_add(5, Ordering::Relaxed);
+    COUNTERS.with(|count| count.fetch_add(1, Ordering::Relaxed));
+
+    if COUNTERS.get_for(cpu).load(Ordering::Relaxed) != 4 {
+        return Err("current CPU counter saw foreign updates");
+    }
+    if COUNTERS.get_for(other).load(Ordering::Relaxed) != 5 {
+        return Err("remote CPU counter saw foreign updates");
+    }
+    let total: u64 = COUNTERS.iter().map(|c| c.load(Ordering::Relaxed)).sum();
+    if total != 9 {
+        return Err("untouched CPU counters changed");
+    }
+    if get_per_cpu_ptr(cpu) as usize + 64 > get_per_cpu_ptr(cpu + 1) as usize {
+        return Err("per-CPU areas overlap");
+    }
+
+    let key = TlsKey::alloc().ok_or("no free kernel TLS slot")?;
+    let first = KernelTls::new();
+    let second = KernelTls::new();
+    let saved = tls_base();
+
+    set_tls(Some(&first));
+    key.set(1);
+    set_tls(Some(&second));
+    key.set(2);
+    set_tls(Some(&first));
+    let isolated = key.get() == 1;
+
+    // SAFETY: restores the block that was installed on entry.
+    unsafe {
+        (*current()).tls_base = saved;
+    }
+    if !isolated {
+        return Err("kernel TLS blocks are not isolated");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percpu_data_size() {
+        assert_eq!(core::mem::size_of::<PerCpuData>(), 64);
+    }
+
+    #[test]
+    fn test_field_offsets() {
+        assert_eq!(offsets::KERNEL_RSP, 0);
+        assert_eq!(offsets::USER_RSP_SCRATCH, 8);
+        assert_eq!(offsets::CURRENT_PID, 16);
+        assert_eq!(offsets::CPU_ID, 24);
+        assert_eq!(offsets::IN_SYSCALL, 32);
+        assert_eq!(offsets::SELF_PTR, 40);
+        assert_eq!(offsets::TLS_BASE, 48);
+    }
+
+    #[test]
+    fn test_set_kernel_rsp() {
+        set_kernel_rsp(0, 0);
+        set_kernel_rsp(0, 0xDEAD_BEEF_0000);
+        assert_eq!(unsafe { (*area(0)).kernel_rsp }, 0xDEAD_BEEF_0000);
+    }
+
+    #[test]
+    fn test_set_current_pid() {
+        set_current_pid(0, 0);
+        set_current_pid(0, 42);
+        assert_eq!(get_current_pid(0), 42);
+    }
+
+    #[test]
+    fn test_per_cpu_slots_isolated() {
+        per_cpu! {
+            static COUNTER: AtomicU64 = AtomicU64::new(0);
+        }
+
+        COUNTER.get_for(1).fetch_add(7, Ordering::Relaxed);
+        assert_eq!(COUNTER.get_for(0).load(Ordering::Relaxed), 0);
+        assert_eq!(COUNTER.get_for(1).load(Ordering::Relaxed), 7);
+        assert_eq!(core::mem::size_of::<CpuSlot<AtomicU64>>(), 64);
+    }
+
+    #[test]
+    fn test_tls_key_without_block() {
+        let saved = tls_base();
+        set_tls(None);
+        let key = TlsKey::alloc().unwrap();
+        assert!(!key.set(5));
+        assert_eq!(key.get(), 0);
+        unsafe { (*current()).tls_base = saved };
+    }
+}
@@ -15,6 +15,7 @@
 //! 8. Enter userspace via `iretq`
 
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 use crate::gdt;
@@ -24,7 +25,7 @@ use crate::loader::segment_loader::{self, SegmentLoadError};
 use crate::memory::user_page_table;
 use crate::process::context::ProcessContext;
 use crate::process::table::{
-    LinuxMemoryInfo, Process, ProcessId, Thread, ThreadId, MMAP_BASE, PROCESS_TABLE,
+    FileSource, LinuxMemoryInfo, Process, ProcessId, Thread, ThreadId, MMAP_BASE, PROCESS_TABLE,
 };
 
 /// Kernel stack size for each user process (16 KiB).
@@ -154,7 +155,9 @@ pub fn launch_linux_process(
 
     // Step 3: Load ELF segments
     let pie_base = if loaded.is_pie { layout::PIE_BASE } else { 0 };
-    let load_result = segment_loader::load_elf_segments(cr3, &loaded, elf_binary, pie_base)?;
+    let source = FileSource::Image(Arc::from(elf_binary));
+    let load_result =
+        segment_loader::load_elf_segments(cr3, &loaded, elf_binary, source, pie_base)?;
 
     crate::serial_println!(
         "[KPIO/Linux] Loaded {} pages, entry={:#x}, brk={:#x}",
@@ -200,7 +203,7 @@ pub fn launch_linux_process(
         cr3,
         brk_start,
         brk_current: brk_start,
-        vma_list: alloc::vec::Vec::new(),
+        vma_list: load_result.vmas,
         mmap_next_addr: MMAP_BASE,
     });
 
@@ -6,13 +6,14 @@
 //! process for scheduling.
 
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
 
 use super::context::ProcessContext;
 use super::table::{
-    LinuxMemoryInfo, Process, ProcessId, ProcessState, Thread, ThreadId, Vma, MMAP_BASE,
-    PROCESS_TABLE,
+    FileSource, LinuxMemoryInfo, Process, ProcessId, ProcessState, Thread, ThreadId, Vma,
+    MMAP_BASE, PROCESS_TABLE,
 };
 use crate::loader::elf::{Elf64Loader, ElfError};
 use crate::loader::program::UserProgram;
@@ -137,7 +138,12 @@ impl ProcessManager {
         } else {
             0
         };
-        let load_result = segment_loader::load_elf_segments(cr3, &loaded, &elf_bytes, pie_base)?;
+        let source = match crate::vfs::stat(path) {
+            Ok(stat) => FileSource::Inode(stat.ino),
+            Err(_) => FileSource::Image(Arc::from(elf_bytes.as_slice())),
+        };
+        let load_result =
+            segment_loader::load_elf_segments(cr3, &loaded, &elf_bytes, source, pie_base)?;
 
         crate::serial_println!(
             "[SPAWN] loaded '{}' pid=pending cr3={:#x} entry={:#x} sp={:#x} brk={:#x} ({} pages)",
@@ -162,7 +168,7 @@ impl ProcessManager {
             cr3,
             brk_start: load_result.brk_start,
             brk_current: load_result.brk_start,
-            vma_list: Vec::new(),
+            vma_list: load_result.vmas,
             mmap_next_addr: MMAP_BASE,
         });
 
@@ -4,6 +4,7 @@
 
 use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU64, Ordering};
 use spin::RwLock;
@@ -17,6 +18,9 @@ use crate::process::signal::SignalState;
 // ═══════════════════════════════════════════════════════════════════════
 
 /// Virtual Memory Area — tracks a mapped region in the process address space.
+///
+/// Pages of a VMA are populated on first access by the page fault
+/// handler (see [`crate::memory::fault`]), according to its `backing`.
 #[derive(Debug, Clone)]
 pub struct Vma {
     /// Start virtual address (page-aligned)
@@ -27,6 +31,76 @@ pub struct Vma {
     pub prot: u32,
     /// Map flags (Linux MAP_PRIVATE=0x02, MAP_ANONYMOUS=0x20, etc.)
     pub flags: u32,
+    /// Where the contents of the region come from
+    pub backing: VmaBacking,
+}
+
+impl Vma {
+    /// Check whether `addr` lies inside this VMA.
+    pub fn contains(&self, addr: u64) -> bool {
+        self.start <= addr && addr < self.end
+    }
+
+    /// The part of this VMA between `start` and `end` (page-aligned and
+    /// within the VMA), keeping file offsets in step with the new start.
+    pub fn slice(&self, start: u64, end: u64) -> Vma {
+        let skipped = start - self.start;
+        let backing = match &self.backing {
+            VmaBacking::Anonymous => VmaBacking::Anonymous,
+            VmaBacking::File {
+                source,
+                offset,
+                file_size,
+            } => VmaBacking::File {
+                source: source.clone(),
+                offset: offset + skipped,
+                file_size: file_size.saturating_sub(skipped),
+            },
+        };
+        Vma {
+            start,
+            end,
+            prot: self.prot,
+            flags: self.flags,
+            backing,
+        }
+    }
+}
+
+/// What backs the pages of a [`Vma`].
+#[derive(Debug, Clone)]
+pub enum VmaBacking {
+    /// Zero-filled on first access (anonymous mmap, stack, BSS).
+    Anonymous,
+    /// Read from a file on first access.
+    ///
+    /// The page at `vma.start + n` holds the file bytes at `offset + n`.
+    /// Only the first `file_size` bytes of the VMA come from the file;
+    /// the rest is zero-filled, as for the BSS tail of an ELF segment.
+    File {
+        source: FileSource,
+        offset: u64,
+        file_size: u64,
+    },
+}
+
+/// The file behind a file-backed [`Vma`].
+#[derive(Clone)]
+pub enum FileSource {
+    /// A regular file in the VFS, by inode number.
+    Inode(u64),
+    /// A file image held in memory, for programs that were not loaded
+    /// from the VFS.
+    Image(Arc<[u8]>),
+}
+
+impl core::fmt::Debug for FileSource {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FileSource::Inode(ino) => write!(f, "Inode({})", ino),
+            FileSource::Image(image) => write!(f, "Image({} bytes)", image.len()),
+        }
+    }
 }
 
 /// Linux-specific memory management state per process.
@@ -447,6 +521,21 @@ impl ProcessTable {
         guard.get_mut(&pid).map(f)
     }
 
+    /// Find the VMA containing `addr` in a process's address space.
+    ///
+    /// With `wait == false` this gives up instead of spinning when the
+    /// table is locked, for fault handlers that may have interrupted the
+    /// lock holder on this CPU.
+    pub fn find_vma(&self, pid: ProcessId, addr: u64, wait: bool) -> Option<Vma> {
+        let guard = if wait {
+            self.processes.read()
+        } else {
+            self.processes.try_read()?
+        };
+        let mem = guard.get(&pid)?.linux_memory.as_ref()?;
+        mem.vma_list.iter().find(|vma| vma.contains(addr)).cloned()
+    }
+
     /// Set the current process for a CPU
     pub fn set_current(&self, cpu: usize, pid: ProcessId) {
         self.current.write().insert(cpu, pid);
@@ -32,8 +32,12 @@ pub struct SwitchInfo {
     pub next_cr3: u64,
     /// Next task's kernel stack top (for TSS RSP0 and PerCpu).
     pub next_kernel_stack_top: u64,
+    /// Next task's kernel stack (for crash backtraces).
+    pub next_stack: core::ops::Range<u64>,
     /// Next task's process PID (for PerCpu current_pid).
     pub next_pid: u64,
+    /// Next task's kernel TLS block (for PerCpu tls_base).
+    pub next_tls: *const crate::percpu::KernelTls,
 }
 
 /// Maximum number of priority levels.
@@ -79,6 +83,8 @@ pub fn init() {
     let boot_task = Task::new_boot_task();
     let boot_arc = Arc::new(Mutex::new(boot_task));
     sched.all_tasks.push(boot_arc.clone());
+    // The boot context gets its kernel TLS block like any other task.
+    crate::percpu::set_tls(Some(boot_arc.lock().kernel_tls()));
     sched.current_task = Some(boot_arc);
     CURRENT_TASK_ID.store(0, Ordering::Relaxed);
 
@@ -134,21 +140,20 @@ pub fn schedule() {
         // Update TSS RSP0 and PerCpu kernel stack pointer
         // so that Ring 3 → Ring 0 transitions land on the
         // correct kernel stack for the next task.
+        let cpu = crate::percpu::current_cpu();
         if info.next_kernel_stack_top != 0 {
             crate::gdt::set_kernel_stack(
                 x86_64::VirtAddr::new(info.next_kernel_stack_top),
             );
-            // Write kernel RSP to per-CPU data (offset 0) via KERNEL_GS_BASE
-            unsafe { percpu_set_kernel_rsp(info.next_kernel_stack_top); }
+            crate::percpu::set_kernel_rsp(cpu, info.next_kernel_stack_top);
         }
 
-        // Update PerCpu current PID (offset 16 in per-CPU data)
-        unsafe { percpu_set_current_pid(info.next_pid); }
-
-        // Sync PID to the syscall percpu array so linux_handlers::current_pid() works
-        // Note: Uses the GS-based percpu which is already set above.
-        // The lib crate's percpu::set_current_pid is called separately for
-        // linux_syscall_entry users.
+        // Update PerCpu current PID and kernel TLS block.
+        crate::percpu::set_current_pid(cpu, info.next_pid);
+        // SAFETY: the scheduler's Arc keeps the next task (and its boxed
+        // TLS block) alive while it runs.
+        crate::percpu::set_tls(unsafe { info.next_tls.as_ref() });
+        crate::crash::set_task_stack(cpu, info.next_stack);
 
         unsafe {
             context::switch_context(info.prev_ctx, info.next_ctx);
@@ -173,53 +178,6 @@ unsafe fn switch_address_space(new_cr3: u64) {
     }
 }
 
-/// Write kernel RSP to the per-CPU data structure (offset 0).
-///
-/// Accesses the static `PER_CPU_ARRAY` directly (via the address
-/// stored in `IA32_KERNEL_GS_BASE` OR `IA32_GS_BASE` — whichever
-/// is non-zero).  This handles the SWAPGS-inverted state: after
-/// a `swapgs` (e.g., inside `ring3_syscall_entry`), the per-CPU
-/// address moves from `KERNEL_GS_BASE` to `GS_BASE`.
-///
-/// # Safety
-///
-/// Must only be called when per-CPU data has been initialized
-/// (i.e. after `scheduler::userspace::init()`).
-unsafe fn percpu_set_kernel_rsp(rsp: u64) {
-    use x86_64::registers::model_specific::Msr;
-    const IA32_GS_BASE: u32 = 0xC000_0101;
-    const IA32_KERNEL_GS_BASE: u32 = 0xC000_0102;
-    // After swapgs: per-CPU is in GS_BASE; before swapgs: in KERNEL_GS_BASE
-    let mut gs_base = unsafe { Msr::new(IA32_KERNEL_GS_BASE).read() };
-    if gs_base == 0 {
-        gs_base = unsafe { Msr::new(IA32_GS_BASE).read() };
-    }
-    if gs_base != 0 {
-        unsafe { core::ptr::write_volatile(gs_base as *mut u64, rsp); }
-    }
-}
-
-/// Write current PID to the per-CPU data structure (offset 16).
-///
-/// Per-CPU layout: `[kernel_rsp, user_rsp_scratch, current_pid, ...]`
-/// Each field is u64, so `current_pid` is at byte offset 16.
-///
-/// # Safety
-///
-/// Must only be called when per-CPU data has been initialized.
-unsafe fn percpu_set_current_pid(pid: u64) {
-    use x86_64::registers::model_specific::Msr;
-    const IA32_GS_BASE: u32 = 0xC000_0101;
-    const IA32_KERNEL_GS_BASE: u32 = 0xC000_0102;
-    let mut gs_base = unsafe { Msr::new(IA32_KERNEL_GS_BASE).read() };
-    if gs_base == 0 {
-        gs_base = unsafe { Msr::new(IA32_GS_BASE).read() };
-    }
-    if gs_base != 0 {
-        unsafe { core::ptr::write_volatile((gs_base as *mut u64).add(2), pid); }
-    }
-}
-
 /// Get the current task ID.
 pub fn current_task_id() -> TaskId {
     TaskId(CURRENT_TASK_ID.load(Ordering::Relaxed))
@@ -568,13 +526,15 @@ impl Scheduler {
             let mut g = prev.lock();
             g.switch_ctx_mut() as *mut SwitchContext
         };
-        let (next_ptr, next_cr3, next_kstack, next_pid): (*const SwitchContext, u64, u64, u64) = {
+        let (next_ptr, next_cr3, next_kstack, next_stack, next_pid, next_tls) = {
             let g = next.lock();
             (
                 g.switch_ctx() as *const SwitchContext,
                 g.cr3(),
                 g.kernel_stack_top_addr(),
+                g.stack_top() - g.stack_size() as u64..g.stack_top(),
                 g.process_pid(),
+                g.kernel_tls() as *const crate::percpu::KernelTls,
             )
         };
 
@@ -583,7 +543,9 @@ impl Scheduler {
             next_ctx: next_ptr,
             next_cr3,
             next_kernel_stack_top: next_kstack,
+            next_stack,
             next_pid,
+            next_tls,
         })
     }
 
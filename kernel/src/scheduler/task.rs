@@ -9,6 +9,7 @@ use core::sync::atomic::{AtomicU64, Ordering};
 
 use super::context::{setup_initial_stack, SwitchContext};
 use super::priority::Priority;
+use crate::percpu::KernelTls;
 
 /// Base virtual address for kernel stacks with guard pages.
 ///
@@ -351,6 +352,9 @@ pub struct Task {
     /// Per-thread signal mask (blocked signals). Each thread has its own mask;
     /// signal actions (handlers) remain shared at the process level.
     signal_mask: u64,
+    /// Kernel thread-local storage, installed in the per-CPU area while
+    /// this task runs. Boxed so its address survives moves of the task.
+    kernel_tls: Box<KernelTls>,
 }
 
 impl Task {
@@ -409,6 +413,7 @@ impl Task {
             clear_child_tid: 0,
             thread_tid: 0,
             signal_mask: 0,
+            kernel_tls: Box::new(KernelTls::new()),
         }
     }
 
@@ -446,6 +451,7 @@ impl Task {
             clear_child_tid: 0,
             thread_tid: 0,
             signal_mask: 0,
+            kernel_tls: Box::new(KernelTls::new()),
         }
     }
 
@@ -491,6 +497,7 @@ impl Task {
             clear_child_tid: 0,
             thread_tid: 0,
             signal_mask: 0,
+            kernel_tls: Box::new(KernelTls::new()),
         }
     }
 
@@ -524,6 +531,7 @@ impl Task {
             clear_child_tid: 0,
             thread_tid: 0,
             signal_mask: 0,
+            kernel_tls: Box::new(KernelTls::new()),
         }
     }
 
@@ -638,6 +646,11 @@ impl Task {
         self.kernel_stack_top_addr
     }
 
+    /// Get the kernel thread-local storage block.
+    pub fn kernel_tls(&self) -> &KernelTls {
+        &self.kernel_tls
+    }
+
     /// Get the associated process PID.
     pub fn process_pid(&self) -> u64 {
         self.process_pid
@@ -747,6 +760,7 @@ impl Task {
             clear_child_tid: 0,
             thread_tid: pid, // Main thread: TID == PID
             signal_mask: 0,
+            kernel_tls: Box::new(KernelTls::new()),
         }
     }
 }
@@ -859,6 +873,7 @@ impl Task {
             clear_child_tid: 0,
             thread_tid: pid, // Forked child main thread: TID == PID
             signal_mask: 0,
+            kernel_tls: Box::new(KernelTls::new()),
         }
     }
 
@@ -945,6 +960,7 @@ impl Task {
             clear_child_tid: clear_child_tid_ptr,
             thread_tid: tid,
             signal_mask: 0,
+            kernel_tls: Box::new(KernelTls::new()),
         }
     }
 }
@@ -968,22 +984,7 @@ fn fork_child_trampoline() -> ! {
 
     crate::serial_println!(
         "[FORK] child {} running, fork returned 0 (RIP={:#x} RSP={:#x})",
-        // Read PID from per-CPU data (offset 16 = current_pid)
-        {
-            use x86_64::registers::model_specific::Msr;
-            // SAFETY: Reading KERNEL_GS_BASE / GS_BASE MSRs to locate
-            // per-CPU data.  These are always valid to read in Ring 0.
-            let mut gs = unsafe { Msr::new(0xC000_0102).read() };
-            if gs == 0 {
-                gs = unsafe { Msr::new(0xC000_0101).read() };
-            }
-            if gs != 0 {
-                // SAFETY: per-CPU data is valid; offset 16 = current_pid.
-                unsafe { *((gs as *const u64).add(2)) }
-            } else {
-                0
-            }
-        },
+        crate::percpu::current_pid(),
         ctx.rip,
         ctx.rsp,
     );
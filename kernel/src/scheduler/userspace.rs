@@ -3,7 +3,7 @@
 //! This module provides the self-contained setup required for Ring 3
 //! process execution.  It configures:
 //!
-//! - **Per-CPU data** (`KERNEL_GS_BASE` MSR) so that `swapgs` in
+//! - **Per-CPU data** (via [`crate::percpu`]) so that `swapgs` in
 //!   `linux_syscall_entry` can locate the kernel stack pointer.
 //! - **SYSCALL/SYSRET MSRs** (`EFER.SCE`, `STAR`, `LSTAR`, `SFMASK`)
 //!   so that the `syscall` instruction transitions cleanly to the
@@ -24,55 +24,7 @@
 
 use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
-// ─── Per-CPU Data ────────────────────────────────────────────────────
-
-/// Maximum CPUs supported.
-const MAX_CPUS: usize = 64;
-
-/// Per-CPU data structure (must match `syscall::percpu::PerCpuData` exactly).
-///
-/// | Offset | Field            | Description                       |
-/// |--------|------------------|-----------------------------------|
-/// |   0    | kernel_rsp       | Kernel stack top for this CPU     |
-/// |   8    | user_rsp_scratch | Saved user RSP during syscall     |
-/// |  16    | current_pid      | Current process ID on this CPU    |
-/// |  24    | cpu_id           | CPU core number                   |
-/// |  32    | in_syscall       | Non-zero while processing syscall |
-#[repr(C, align(64))]
-struct PerCpuData {
-    kernel_rsp: u64,
-    user_rsp_scratch: u64,
-    current_pid: u64,
-    cpu_id: u64,
-    in_syscall: u64,
-    _pad: [u64; 3],
-}
-
-impl PerCpuData {
-    const fn new() -> Self {
-        Self {
-            kernel_rsp: 0,
-            user_rsp_scratch: 0,
-            current_pid: 0,
-            cpu_id: 0,
-            in_syscall: 0,
-            _pad: [0; 3],
-        }
-    }
-}
-
-// Compile-time layout assertion.
-const _: () = {
-    assert!(core::mem::size_of::<PerCpuData>() == 64);
-    assert!(core::mem::align_of::<PerCpuData>() == 64);
-};
-
-/// Static per-CPU data array (one entry per logical CPU).
-static mut PER_CPU_ARRAY: [PerCpuData; MAX_CPUS] = {
-    const INIT: PerCpuData = PerCpuData::new();
-    [INIT; MAX_CPUS]
-};
-
+/// Whether [`init`] has run.
 static INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 // ─── Execve context (shared between dispatch and assembly) ───────────
@@ -907,6 +859,7 @@ fn dispatch_sys_sendto(fd: i32, buf_ptr: u64, len: usize, _flags: u32, dest_addr
     match network::socket::send(handle, data) {
         Ok(n) => n as i64,
         Err(network::NetworkError::WouldBlock) => -11, // -EAGAIN
+        Err(network::NetworkError::TimedOut) => -110, // -ETIMEDOUT
         Err(_) => -104, // -ECONNRESET
     }
 }
@@ -925,6 +878,7 @@ fn dispatch_sys_recvfrom(fd: i32, buf_ptr: u64, len: usize, _flags: u32, _src_ad
     match network::socket::recv(handle, buf) {
         Ok(n) => n as i64,
         Err(network::NetworkError::WouldBlock) => -11, // -EAGAIN
+        Err(network::NetworkError::TimedOut) => -110, // -ETIMEDOUT
         Err(_) => -104, // -ECONNRESET
     }
 }
@@ -996,6 +950,10 @@ fn dispatch_sys_getsockopt(fd: i32, level: u32, optname: u32, optval_ptr: u64, o
         Ok(h) => h,
         Err(e) => return e,
     };
+    // TCP_INFO (level=IPPROTO_TCP, optname=11) is a struct, not a u32.
+    if level == 6 && optname == 11 {
+        return dispatch_tcp_info(handle, optval_ptr, optlen_ptr);
+    }
     match network::socket::getsockopt(handle, level, optname) {
         Ok(val) => {
             if optval_ptr != 0 && optval_ptr < 0x0000_8000_0000_0000 {
@@ -1012,6 +970,29 @@ fn dispatch_sys_getsockopt(fd: i32, level: u32, optname: u32, optval_ptr: u64, o
     }
 }
 
+/// getsockopt(TCP_INFO): copy the connection's statistics, truncated to
+/// the caller's `optlen`.
+fn dispatch_tcp_info(handle: network::socket::SocketHandle, optval_ptr: u64, optlen_ptr: u64) -> i64 {
+    if optval_ptr == 0
+        || optval_ptr >= 0x0000_8000_0000_0000
+        || optlen_ptr == 0
+        || optlen_ptr >= 0x0000_8000_0000_0000
+    {
+        return -14; // -EFAULT
+    }
+    let info = match network::socket::tcp_stats(handle) {
+        Ok(stats) => stats.to_bytes(),
+        Err(_) => return -92, // -ENOPROTOOPT
+    };
+    // SAFETY: both pointers are in valid user-space range.
+    unsafe {
+        let len = (core::ptr::read(optlen_ptr as *const u32) as usize).min(info.len());
+        core::ptr::copy_nonoverlapping(info.as_ptr(), optval_ptr as *mut u8, len);
+        core::ptr::write(optlen_ptr as *mut u32, len as u32);
+    }
+    0
+}
+
 fn dispatch_sys_lseek(fd: i32, offset: i64, whence: u32) -> i64 {
     let mut table = RING3_FD_TABLE.lock();
     let entry = match table.get_mut(fd as usize).and_then(|e| e.as_mut()) {
@@ -1333,6 +1314,13 @@ fn dispatch_sys_epoll_wait(epfd: i32, events_ptr: u64, maxevents: i32, _timeout:
         }
     };
 
+    // Pull in frames that arrived since the last wait so sockets that
+    // just received data report EPOLLIN, and run the TCP timers so failed
+    // connections report EPOLLERR.
+    crate::net::poll_rx();
+    let t = crate::time::monotonic();
+    network::socket::poll_tcp(t.secs * 1000 + u64::from(t.nanos) / 1_000_000);
+
     // Build a snapshot of fd→kind so we can poll without holding the FD table lock.
     let fd_snapshot: alloc::vec::Vec<(i32, FdKind)> = {
         let table = RING3_FD_TABLE.lock();
@@ -1666,7 +1654,6 @@ const IA32_EFER: u32 = 0xC000_0080;
 const IA32_STAR: u32 = 0xC000_0081;
 const IA32_LSTAR: u32 = 0xC000_0082;
 const IA32_SFMASK: u32 = 0xC000_0084;
-const IA32_KERNEL_GS_BASE: u32 = 0xC000_0102;
 
 /// EFER.SCE (System Call Extensions) bit.
 const EFER_SCE: u64 = 1 << 0;
@@ -1678,7 +1665,7 @@ const EFER_SCE: u64 = 1 << 0;
 /// Must be called after GDT, IDT, and heap initialization.
 ///
 /// This sets up:
-/// 1. Per-CPU data for the BSP (CPU 0) + `KERNEL_GS_BASE` MSR
+/// 1. Per-CPU data for the BSP (CPU 0) + GS base MSRs
 /// 2. EFER.SCE to enable the SYSCALL instruction
 /// 3. STAR MSR for segment selectors
 /// 4. LSTAR MSR pointing to `linux_syscall_entry`
@@ -1689,15 +1676,7 @@ pub fn init() {
     }
 
     // 1. Per-CPU data
-    let cpu_id: usize = 0; // BSP
-    unsafe {
-        PER_CPU_ARRAY[cpu_id].cpu_id = cpu_id as u64;
-
-        let per_cpu_addr = &PER_CPU_ARRAY[cpu_id] as *const PerCpuData as u64;
-
-        use x86_64::registers::model_specific::Msr;
-        Msr::new(IA32_KERNEL_GS_BASE).write(per_cpu_addr);
-    }
+    crate::percpu::init();
 
     // 2. Enable SYSCALL/SYSRET
     unsafe {
@@ -1731,7 +1710,7 @@ pub fn init() {
     INITIALIZED.store(true, Ordering::Release);
 
     crate::serial_println!(
-        "[RING3] User-space init: KERNEL_GS_BASE + STAR/LSTAR/SFMASK configured"
+        "[RING3] User-space init: GS base + STAR/LSTAR/SFMASK configured"
     );
     crate::serial_println!(
         "[RING3]   LSTAR = {:#x} (ring3_syscall_entry)",
@@ -2,6 +2,8 @@
 //!
 //! This module provides serial console output using the 16550 UART.
 
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::fmt;
 use core::fmt::Write;
 use spin::Mutex;
@@ -51,6 +53,96 @@ pub fn write_str(s: &str) {
     for byte in s.bytes() {
         write_byte(byte);
     }
+    record_output(s.as_bytes());
+}
+
+/// Size of the recent-output ring kept for crash dumps.
+const OUTPUT_RING_SIZE: usize = 8192;
+
+/// Ring buffer holding the most recent console output.
+struct OutputRing {
+    buf: [u8; OUTPUT_RING_SIZE],
+    /// Next write position.
+    head: usize,
+    /// Number of valid bytes.
+    len: usize,
+}
+
+/// Recent COM1 output, captured for crash dumps.
+static OUTPUT_RING: Mutex<OutputRing> = Mutex::new(OutputRing {
+    buf: [0; OUTPUT_RING_SIZE],
+    head: 0,
+    len: 0,
+});
+
+/// Append bytes to the recent-output ring.
+fn record_output(bytes: &[u8]) {
+    // Never block: a panic may fire while the ring is held.
+    let Some(mut ring) = OUTPUT_RING.try_lock() else {
+        return;
+    };
+
+    for &byte in bytes {
+        let head = ring.head;
+        ring.buf[head] = byte;
+        ring.head = (head + 1) % OUTPUT_RING_SIZE;
+        ring.len = (ring.len + 1).min(OUTPUT_RING_SIZE);
+    }
+}
+
+/// Get up to `max_lines` of the most recent console output, oldest first.
+///
+/// Returns an empty list if the ring is currently locked.
+pub fn recent_lines(max_lines: usize) -> Vec<String> {
+    let Some(ring) = OUTPUT_RING.try_lock() else {
+        return Vec::new();
+    };
+
+    let start = (ring.head + OUTPUT_RING_SIZE - ring.len) % OUTPUT_RING_SIZE;
+    let bytes: Vec<u8> = (0..ring.len)
+        .map(|i| ring.buf[(start + i) % OUTPUT_RING_SIZE])
+        .collect();
+    drop(ring);
+
+    let text = String::from_utf8_lossy(&bytes);
+    let mut lines: Vec<String> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect();
+
+    // The oldest line is likely truncated by the wrap-around
+    if bytes.len() == OUTPUT_RING_SIZE && !lines.is_empty() {
+        lines.remove(0);
+    }
+
+    let skip = lines.len().saturating_sub(max_lines);
+    lines.split_off(skip)
+}
+
+/// Copy the most recent console output into `out` without allocating,
+/// starting at a line boundary if it does not all fit. Returns the number
+/// of bytes copied, 0 if the ring is currently locked.
+pub fn copy_recent(out: &mut [u8]) -> usize {
+    let Some(ring) = OUTPUT_RING.try_lock() else {
+        return 0;
+    };
+
+    let mut len = ring.len.min(out.len());
+    let mut start = (ring.head + OUTPUT_RING_SIZE - len) % OUTPUT_RING_SIZE;
+    // The oldest line is likely truncated by the wrap-around or the cut
+    if len < ring.len || ring.len == OUTPUT_RING_SIZE {
+        let skip = (0..len)
+            .position(|i| ring.buf[(start + i) % OUTPUT_RING_SIZE] == b'\n')
+            .map_or(len, |i| i + 1);
+        start = (start + skip) % OUTPUT_RING_SIZE;
+        len -= skip;
+    }
+
+    for (i, byte) in out[..len].iter_mut().enumerate() {
+        *byte = ring.buf[(start + i) % OUTPUT_RING_SIZE];
+    }
+    len
 }
 
 /// Read a byte from COM1 (blocking).
@@ -123,81 +215,3 @@ pub fn _print(args: fmt::Arguments) {
         SerialWriter.write_fmt(args).unwrap();
     });
 }
-
-/// Log levels.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum LogLevel {
-    /// Trace level (most verbose).
-    Trace = 0,
-    /// Debug level.
-    Debug = 1,
-    /// Info level.
-    Info = 2,
-    /// Warning level.
-    Warn = 3,
-    /// Error level.
-    Error = 4,
-}
-
-/// Current log level.
-static LOG_LEVEL: Mutex<LogLevel> = Mutex::new(LogLevel::Info);
-
-/// Set the log level.
-pub fn set_log_level(level: LogLevel) {
-    *LOG_LEVEL.lock() = level;
-}
-
-/// Get the current log level.
-pub fn log_level() -> LogLevel {
-    *LOG_LEVEL.lock()
-}
-
-/// Log a message at the given level.
-pub fn log(level: LogLevel, args: fmt::Arguments) {
-    if level >= log_level() {
-        let prefix = match level {
-            LogLevel::Trace => "[TRACE]",
-            LogLevel::Debug => "[DEBUG]",
-            LogLevel::Info => "[INFO ]",
-            LogLevel::Warn => "[WARN ]",
-            LogLevel::Error => "[ERROR]",
-        };
-        serial_println!("{} {}", prefix, args);
-    }
-}
-
-/// Log macros.
-#[macro_export]
-macro_rules! trace {
-    ($($arg:tt)*) => {
-        $crate::serial::log($crate::serial::LogLevel::Trace, format_args!($($arg)*));
-    };
-}
-
-#[macro_export]
-macro_rules! debug {
-    ($($arg:tt)*) => {
-        $crate::serial::log($crate::serial::LogLevel::Debug, format_args!($($arg)*));
-    };
-}
-
-#[macro_export]
-macro_rules! info {
-    ($($arg:tt)*) => {
-        $crate::serial::log($crate::serial::LogLevel::Info, format_args!($($arg)*));
-    };
-}
-
-#[macro_export]
-macro_rules! warn {
-    ($($arg:tt)*) => {
-        $crate::serial::log($crate::serial::LogLevel::Warn, format_args!($($arg)*));
-    };
-}
-
-#[macro_export]
-macro_rules! error {
-    ($($arg:tt)*) => {
-        $crate::serial::log($crate::serial::LogLevel::Error, format_args!($($arg)*));
-    };
-}
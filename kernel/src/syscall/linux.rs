@@ -19,7 +19,7 @@
 //! | R11      | Saved RFLAGS (by SYSCALL) |
 
 use super::linux_handlers;
-use super::percpu;
+use crate::percpu;
 use super::trace;
 
 // ─── Linux errno constants ────────────────────────────────────────────
@@ -37,6 +37,7 @@ pub const ENOMEM: i64 = 12;
 pub const EACCES: i64 = 13;
 pub const EFAULT: i64 = 14;
 pub const EEXIST: i64 = 17;
+pub const ENODEV: i64 = 19;
 pub const ENOTDIR: i64 = 20;
 pub const EISDIR: i64 = 21;
 pub const EINVAL: i64 = 22;
@@ -74,6 +75,8 @@ pub const SYS_BRK: u64 = 12;
 pub const SYS_RT_SIGACTION: u64 = 13;
 pub const SYS_RT_SIGPROCMASK: u64 = 14;
 pub const SYS_IOCTL: u64 = 16;
+pub const SYS_PREAD64: u64 = 17;
+pub const SYS_PWRITE64: u64 = 18;
 pub const SYS_WRITEV: u64 = 20;
 pub const SYS_ACCESS: u64 = 21;
 pub const SYS_DUP: u64 = 32;
@@ -86,6 +89,8 @@ pub const SYS_CHDIR: u64 = 80;
 pub const SYS_MKDIR: u64 = 83;
 pub const SYS_UNLINK: u64 = 87;
 pub const SYS_READLINK: u64 = 89;
+pub const SYS_STATFS: u64 = 137;
+pub const SYS_FSTATFS: u64 = 138;
 pub const SYS_GETUID: u64 = 102;
 pub const SYS_GETGID: u64 = 104;
 pub const SYS_GETEUID: u64 = 107;
@@ -375,6 +380,8 @@ pub fn linux_syscall_dispatch(
         SYS_DUP => linux_handlers::sys_dup(a1 as i32),
         SYS_DUP2 => linux_handlers::sys_dup2(a1 as i32, a2 as i32),
         SYS_IOCTL => linux_handlers::sys_ioctl(a1 as i32, a2, a3),
+        SYS_PREAD64 => linux_handlers::sys_pread64(a1 as i32, a2, a3, a4 as i64),
+        SYS_PWRITE64 => linux_handlers::sys_pwrite64(a1 as i32, a2, a3, a4 as i64),
         SYS_READV => linux_handlers::sys_readv(a1 as i32, a2, a3 as u32),
         SYS_WRITEV => linux_handlers::sys_writev(a1 as i32, a2, a3 as u32),
         SYS_PIPE => linux_handlers::sys_pipe(a1),
@@ -420,6 +427,8 @@ pub fn linux_syscall_dispatch(
         SYS_READLINK => linux_handlers::sys_readlink(a1, a2, a3),
         SYS_READLINKAT => linux_handlers::sys_readlinkat(a1 as i32, a2, a3, a4),
         SYS_GETDENTS64 => linux_handlers::sys_getdents64(a1 as i32, a2, a3 as u32),
+        SYS_STATFS => linux_handlers::sys_statfs(a1, a2),
+        SYS_FSTATFS => linux_handlers::sys_fstatfs(a1 as i32, a2),
 
         // Signals
         SYS_RT_SIGACTION => linux_handlers::sys_rt_sigaction(a1 as u32, a2, a3, a4 as usize),
@@ -554,7 +563,7 @@ pub fn copy_from_user(dst: &mut [u8], user_src: u64) -> Result<(), i64> {
 
 /// Initialise the Linux syscall compatibility layer.
 ///
-/// 1. Init per-CPU data + KERNEL_GS_BASE MSR
+/// 1. Init per-CPU data + GS base MSRs
 /// 2. Install `linux_syscall_entry` in LSTAR
 /// 3. Init trace module (Phase 7-4.6)
 ///
@@ -5,7 +5,7 @@
 //!
 //! Each process has its own `BTreeMap<u32, FileDescriptor>` in the process table
 //! (created in Phase 7-4.1). These handlers look up the current process from
-//! `percpu::get_current_pid()` and operate on that process's FD table.
+//! `percpu::current_pid()` and operate on that process's FD table.
 //!
 //! Memory syscalls (brk, mmap, munmap, mprotect) use `LinuxMemoryInfo` stored
 //! in the process table (Phase 7-4.3).
@@ -21,12 +21,12 @@ use core::sync::atomic::{AtomicU64, Ordering};
 use super::linux::{
     copy_from_user, copy_to_user, read_user_string, validate_user_ptr,
     AT_FDCWD, EACCES, EAFNOSUPPORT, EAGAIN, EBADF, EFAULT, EINVAL, EISDIR, EMFILE,
-    ENOENT, ENOSYS, ENOTCONN, ENOTDIR, EPIPE, ERANGE, ESRCH, ESPIPE, EEXIST,
+    ENODEV, ENOENT, ENOMEM, ENOSYS, ENOTCONN, ENOTDIR, EPIPE, ERANGE, ESRCH, ESPIPE, EEXIST,
 };
 use crate::memory::user_page_table;
 use crate::process::table::{
-    FileDescriptor, FileResource, LinuxMemoryInfo, ProcessId, StdioType, Vma,
-    MAX_HEAP_SIZE, PROCESS_TABLE,
+    FileDescriptor, FileResource, FileSource, LinuxMemoryInfo, ProcessId, StdioType, Vma,
+    VmaBacking, MAX_HEAP_SIZE, PROCESS_TABLE,
 };
 use crate::serial;
 
@@ -123,7 +123,7 @@ const FD_MAX: u32 = 256;
 ///
 /// Returns `None` if there is no Linux process running (kernel context).
 fn current_pid() -> Option<ProcessId> {
-    let pid = super::percpu::get_current_pid(0);
+    let pid = crate::percpu::current_pid();
     if pid == 0 {
         None
     } else {
@@ -622,6 +622,116 @@ fn sys_stat_path(path: &str, statbuf_ptr: u64) -> i64 {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// SYS_STATFS (137) / SYS_FSTATFS (138)
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Linux `struct statfs` layout for x86_64.
+/// Total size: 120 bytes.
+#[repr(C)]
+#[derive(Default)]
+struct LinuxStatfs {
+    f_type: i64,
+    f_bsize: i64,
+    f_blocks: u64,
+    f_bfree: u64,
+    f_bavail: u64,
+    f_files: u64,
+    f_ffree: u64,
+    f_fsid: [i32; 2],
+    f_namelen: i64,
+    f_frsize: i64,
+    f_flags: i64,
+    f_spare: [i64; 4],
+}
+
+/// `RAMFS_MAGIC`, reported for the in-memory root filesystem.
+const RAMFS_MAGIC: i64 = 0x858458F6;
+
+/// `ST_RDONLY` in `f_flags`.
+const ST_RDONLY: i64 = 1;
+
+/// Statistics for the storage mount holding `path`, if any.
+fn statfs_path(path: &str) -> Option<LinuxStatfs> {
+    let stats = storage::statfs(path).ok()?;
+    Some(LinuxStatfs {
+        f_type: stats.fs_type as i64,
+        f_bsize: stats.block_size as i64,
+        f_blocks: stats.total_blocks,
+        f_bfree: stats.free_blocks,
+        f_bavail: stats.available_blocks,
+        f_files: stats.total_inodes,
+        f_ffree: stats.free_inodes,
+        f_fsid: [stats.fs_id as i32, (stats.fs_id >> 32) as i32],
+        f_namelen: stats.max_name_len as i64,
+        f_frsize: stats.fragment_size as i64,
+        f_flags: if stats.flags.contains(storage::MountFlags::READ_ONLY) {
+            ST_RDONLY
+        } else {
+            0
+        },
+        f_spare: [0; 4],
+    })
+}
+
+/// Statistics for the in-memory root filesystem. It has no fixed
+/// capacity, so like Linux ramfs it reports zero blocks.
+fn ramfs_statfs() -> LinuxStatfs {
+    LinuxStatfs {
+        f_type: RAMFS_MAGIC,
+        f_bsize: 4096,
+        f_namelen: 255,
+        f_frsize: 4096,
+        ..LinuxStatfs::default()
+    }
+}
+
+fn copy_statfs_to_user(buf_ptr: u64, stats: &LinuxStatfs) -> i64 {
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            stats as *const LinuxStatfs as *const u8,
+            core::mem::size_of::<LinuxStatfs>(),
+        )
+    };
+    if copy_to_user(buf_ptr, bytes).is_err() {
+        return -EFAULT;
+    }
+    0
+}
+
+/// `statfs(path, buf)` → `0` or `-errno`
+pub fn sys_statfs(path_ptr: u64, buf_ptr: u64) -> i64 {
+    let path = match read_user_string(path_ptr, PATH_MAX) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    if validate_user_ptr(buf_ptr, core::mem::size_of::<LinuxStatfs>() as u64).is_err() {
+        return -EFAULT;
+    }
+
+    let stats = match statfs_path(&path) {
+        Some(stats) => stats,
+        None if vfs::stat(&path).is_ok() => ramfs_statfs(),
+        None => return -ENOENT,
+    };
+    copy_statfs_to_user(buf_ptr, &stats)
+}
+
+/// `fstatfs(fd, buf)` → `0` or `-errno`
+pub fn sys_fstatfs(fd: i32, buf_ptr: u64) -> i64 {
+    if validate_user_ptr(buf_ptr, core::mem::size_of::<LinuxStatfs>() as u64).is_err() {
+        return -EFAULT;
+    }
+
+    let stats = match fd_file_path(fd) {
+        Ok(path) => statfs_path(&path).unwrap_or_else(ramfs_statfs),
+        // Pipes, sockets and terminals live in kernel memory
+        Err(e) if e == -ESPIPE => ramfs_statfs(),
+        Err(e) => return e,
+    };
+    copy_statfs_to_user(buf_ptr, &stats)
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // SYS_LSEEK (8)
 // ═══════════════════════════════════════════════════════════════════════
@@ -678,6 +788,97 @@ fn lseek_in_process(pid: ProcessId, fd: u32, offset: i64, whence: u32) -> i64 {
     result
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+// SYS_PREAD64 (17) / SYS_PWRITE64 (18)
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `pread64(fd, buf, count, offset)` → `ssize_t`
+///
+/// Like `read`, but at `offset` and without moving the file position.
+pub fn sys_pread64(fd: i32, buf_ptr: u64, count: u64, offset: i64) -> i64 {
+    if offset < 0 || (offset as u64).checked_add(count).is_none() {
+        return -EINVAL;
+    }
+    let path = match fd_file_path(fd) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    if count == 0 {
+        return 0;
+    }
+    if validate_user_ptr(buf_ptr, count).is_err() {
+        return -EFAULT;
+    }
+
+    match vfs::read_all(&path) {
+        Ok(data) => {
+            let start = (offset as u64).min(data.len() as u64) as usize;
+            let end = start + (count as usize).min(data.len() - start);
+            if copy_to_user(buf_ptr, &data[start..end]).is_err() {
+                return -EFAULT;
+            }
+            (end - start) as i64
+        }
+        Err(_) => -EIO,
+    }
+}
+
+/// `pwrite64(fd, buf, count, offset)` → `ssize_t`
+///
+/// Like `write`, but at `offset` and without moving the file position.
+/// A gap between end of file and `offset` is zero-filled.
+pub fn sys_pwrite64(fd: i32, buf_ptr: u64, count: u64, offset: i64) -> i64 {
+    if offset < 0 || (offset as u64).checked_add(count).is_none() {
+        return -EINVAL;
+    }
+    let path = match fd_file_path(fd) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    if count == 0 {
+        return 0;
+    }
+    if validate_user_ptr(buf_ptr, count).is_err() {
+        return -EFAULT;
+    }
+
+    let data = unsafe { core::slice::from_raw_parts(buf_ptr as *const u8, count as usize) };
+
+    // The ramfs only stores whole files, so splice the write in
+    let mut contents = match vfs::read_all(&path) {
+        Ok(c) => c,
+        Err(vfs::VfsError::NotFound) => Vec::new(),
+        Err(_) => return -EIO,
+    };
+    let start = offset as usize;
+    let end = start + data.len();
+    if contents.len() < end {
+        if contents.try_reserve(end - contents.len()).is_err() {
+            return -ENOMEM;
+        }
+        contents.resize(end, 0);
+    }
+    contents[start..end].copy_from_slice(data);
+
+    match vfs::write_all(&path, &contents) {
+        Ok(()) => count as i64,
+        Err(_) => -EIO,
+    }
+}
+
+/// Path of the regular file behind `fd` in the current process, or
+/// `-ESPIPE` for pipes, sockets and terminals.
+fn fd_file_path(fd: i32) -> Result<String, i64> {
+    let pid = current_pid().ok_or(-EBADF)?;
+    let guard = PROCESS_TABLE.get(pid).ok_or(-EBADF)?;
+    let proc = guard.get(&pid).ok_or(-EBADF)?;
+    match proc.get_fd(fd as u32).map(|f| &f.resource) {
+        Some(FileResource::File { path }) => Ok(path.clone()),
+        Some(_) => Err(-ESPIPE),
+        None => Err(-EBADF),
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 // SYS_ACCESS (21)
 // ═══════════════════════════════════════════════════════════════════════
@@ -1324,16 +1525,22 @@ pub fn sys_execve(path_ptr: u64, argv_ptr: u64, envp_ptr: u64) -> i64 {
     } else {
         0
     };
-    let load_result =
-        match crate::loader::segment_loader::load_elf_segments(cr3, &loaded, &elf_data, pie_base) {
-            Ok(r) => r,
-            Err(e) => {
-                crate::serial_println!("[KPIO/execve] Segment load error: {}", e);
-                // Process is in an inconsistent state — must be killed
-                sys_exit(128 + 11); // SIGSEGV
-                return -(super::linux::ENOMEM);
-            }
-        };
+    // Segment pages are read from the file as they are touched
+    let source = match vfs::stat(&resolved_path) {
+        Ok(stat) => FileSource::Inode(stat.ino),
+        Err(_) => FileSource::Image(alloc::sync::Arc::from(elf_data.as_slice())),
+    };
+    let load_result = match crate::loader::segment_loader::load_elf_segments(
+        cr3, &loaded, &elf_data, source, pie_base,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            crate::serial_println!("[KPIO/execve] Segment load error: {}", e);
+            // Process is in an inconsistent state — must be killed
+            sys_exit(128 + 11); // SIGSEGV
+            return -(super::linux::ENOMEM);
+        }
+    };
 
     // Create UserProgram for auxv
     let user_program = crate::loader::program::UserProgram::new(
@@ -1360,22 +1567,40 @@ pub fn sys_execve(path_ptr: u64, argv_ptr: u64, envp_ptr: u64) -> i64 {
     };
 
     // Update process state
-    PROCESS_TABLE.with_process_mut(pid, |proc| {
+    let vmas_recorded = PROCESS_TABLE.with_process_mut(pid, |proc| {
         proc.name = alloc::string::String::from(&*path);
         proc.program = Some(user_program);
 
         // Update linux memory state
-        if let Some(ref mut mem) = proc.linux_memory {
-            mem.brk_start = load_result.brk_start;
-            mem.brk_current = load_result.brk_start;
-            mem.vma_list.clear();
-            mem.mmap_next_addr = crate::process::table::MMAP_BASE;
-        }
+        let recorded = match proc.linux_memory {
+            Some(ref mut mem) => {
+                mem.brk_start = load_result.brk_start;
+                mem.brk_current = load_result.brk_start;
+                mem.vma_list = load_result.vmas.clone();
+                mem.mmap_next_addr = crate::process::table::MMAP_BASE;
+                true
+            }
+            None => false,
+        };
 
         // Reset signal handlers to default (POSIX requirement)
         proc.signals.reset_handlers();
+        recorded
     });
 
+    // Without a memory map to fault from, populate the segments now (the
+    // stack's initial pages already are)
+    if vmas_recorded != Some(true) {
+        let stack_top = crate::loader::program::layout::USER_STACK_TOP;
+        for vma in load_result.vmas.iter().filter(|vma| vma.end != stack_top) {
+            if let Err(e) = crate::memory::fault::populate(cr3, vma, vma.start, vma.end) {
+                crate::serial_println!("[KPIO/execve] Populate error: {}", e);
+                sys_exit(128 + 11);
+                return -(super::linux::ENOMEM);
+            }
+        }
+    }
+
     crate::serial_println!(
         "[KPIO/execve] Loaded '{}': entry={:#x}, sp={:#x}",
         path,
@@ -1573,10 +1798,10 @@ const PROT_NONE: u32 = 0x0;
 #[allow(dead_code)]
 const PROT_READ: u32 = 0x1;
 const PROT_WRITE: u32 = 0x2;
+#[allow(dead_code)]
 const PROT_EXEC: u32 = 0x4;
 
 // Linux mmap flags
-#[allow(dead_code)]
 const MAP_SHARED: u32 = 0x01;
 #[allow(dead_code)]
 const MAP_PRIVATE: u32 = 0x02;
@@ -1585,41 +1810,25 @@ const MAP_ANONYMOUS: u32 = 0x20;
 
 /// Convert Linux PROT_* flags to x86_64 page table flags.
 fn linux_prot_to_page_flags(prot: u32) -> user_page_table::PageTableFlags {
-    let mut flags = user_page_table::PageTableFlags::PRESENT
-        | user_page_table::PageTableFlags::USER_ACCESSIBLE;
-
-    if prot & PROT_WRITE != 0 {
-        flags |= user_page_table::PageTableFlags::WRITABLE;
-    }
-    if prot & PROT_EXEC == 0 {
-        // No execute permission → set NX bit
-        flags |= user_page_table::PageTableFlags::NO_EXECUTE;
-    }
-    // PROT_READ is implicit when PRESENT is set on x86_64
-
-    flags
+    crate::memory::fault::prot_to_page_flags(prot)
 }
 
 /// `mmap(addr, length, prot, flags, fd, offset)` → `mapped_addr` or `-errno`
 ///
-/// Currently supports only `MAP_ANONYMOUS | MAP_PRIVATE` (anonymous mappings).
-/// File-backed mappings return -ENOSYS.
+/// Supports anonymous mappings and private file mappings. Shared file
+/// mappings must be read-only, since nothing writes pages back to the file.
+///
+/// No pages are mapped here: the VMA is recorded and each page is
+/// populated on first access by the page fault handler.
 ///
 /// If `addr == 0`, the kernel picks an address starting from 0x7F0000000000
 /// and working downward. If `MAP_FIXED` is set, the address is used as-is.
-pub fn sys_mmap(addr: u64, length: u64, prot: u32, flags: u32, fd: i32, _offset: u64) -> i64 {
+pub fn sys_mmap(addr: u64, length: u64, prot: u32, flags: u32, fd: i32, offset: u64) -> i64 {
     // Validate length
     if length == 0 {
         return -EINVAL;
     }
 
-    // We only support anonymous private mappings
-    if flags & MAP_ANONYMOUS == 0 {
-        // File-backed mmap not yet supported
-        crate::serial_println!("[KPIO/mmap] File-backed mmap not supported (fd={})", fd);
-        return -ENOSYS;
-    }
-
     // Page-align the length
     let aligned_len = (length + 0xFFF) & !0xFFF;
 
@@ -1628,6 +1837,30 @@ pub fn sys_mmap(addr: u64, length: u64, prot: u32, flags: u32, fd: i32, _offset:
         None => return -ENOMEM,
     };
 
+    let backing = if flags & MAP_ANONYMOUS != 0 {
+        VmaBacking::Anonymous
+    } else {
+        if offset % 4096 != 0 {
+            return -EINVAL;
+        }
+        if flags & MAP_SHARED != 0 && prot & PROT_WRITE != 0 {
+            crate::serial_println!(
+                "[KPIO/mmap] Writable shared file mapping not supported (fd={})",
+                fd
+            );
+            return -ENOSYS;
+        }
+        match file_mapping_source(pid, fd) {
+            // Pages past the end of the file read as zeros
+            Ok(source) => VmaBacking::File {
+                source,
+                offset,
+                file_size: aligned_len,
+            },
+            Err(e) => return e,
+        }
+    };
+
     PROCESS_TABLE
         .with_process_mut(pid, |proc| {
             let mem = match proc.linux_memory.as_mut() {
@@ -1649,8 +1882,8 @@ pub fn sys_mmap(addr: u64, length: u64, prot: u32, flags: u32, fd: i32, _offset:
                     let _ = user_page_table::unmap_user_page(cr3, page);
                     page += 4096;
                 }
-                // Remove overlapping VMAs
-                mem.vma_list.retain(|vma| vma.end <= addr || vma.start >= addr + aligned_len);
+                // Cut the range out of overlapping VMAs
+                update_vma_range(&mut mem.vma_list, addr, addr + aligned_len, |_| None);
                 addr
             } else if addr != 0 {
                 // Hint address provided — try it, fall back to auto
@@ -1676,28 +1909,13 @@ pub fn sys_mmap(addr: u64, length: u64, prot: u32, flags: u32, fd: i32, _offset:
                 return -ENOMEM;
             }
 
-            // Map the pages
-            let page_flags = linux_prot_to_page_flags(prot);
-            let mut page = map_addr;
-            while page < map_addr + aligned_len {
-                if let Err(_e) = user_page_table::map_user_page(cr3, page, page_flags) {
-                    // Rollback: unmap pages we already mapped
-                    let mut rollback = map_addr;
-                    while rollback < page {
-                        let _ = user_page_table::unmap_user_page(cr3, rollback);
-                        rollback += 4096;
-                    }
-                    return -ENOMEM;
-                }
-                page += 4096;
-            }
-
-            // Record the VMA
+            // Record the VMA; its pages are populated on first access
             mem.vma_list.push(Vma {
                 start: map_addr,
                 end: map_addr + aligned_len,
                 prot,
                 flags,
+                backing,
             });
 
             map_addr as i64
@@ -1740,50 +1958,7 @@ pub fn sys_munmap(addr: u64, length: u64) -> i64 {
             }
 
             // Update VMA list: remove fully-contained, split partially-overlapping
-            let unmap_start = addr;
-            let unmap_end = addr + aligned_len;
-            let mut new_vmas: Vec<Vma> = Vec::new();
-
-            for vma in mem.vma_list.drain(..) {
-                if vma.end <= unmap_start || vma.start >= unmap_end {
-                    // No overlap — keep
-                    new_vmas.push(vma);
-                } else if vma.start >= unmap_start && vma.end <= unmap_end {
-                    // Fully contained — remove
-                } else if vma.start < unmap_start && vma.end > unmap_end {
-                    // Unmap punches a hole — split into two
-                    new_vmas.push(Vma {
-                        start: vma.start,
-                        end: unmap_start,
-                        prot: vma.prot,
-                        flags: vma.flags,
-                    });
-                    new_vmas.push(Vma {
-                        start: unmap_end,
-                        end: vma.end,
-                        prot: vma.prot,
-                        flags: vma.flags,
-                    });
-                } else if vma.start < unmap_start {
-                    // Partial overlap at the end — trim
-                    new_vmas.push(Vma {
-                        start: vma.start,
-                        end: unmap_start,
-                        prot: vma.prot,
-                        flags: vma.flags,
-                    });
-                } else {
-                    // Partial overlap at the start — trim
-                    new_vmas.push(Vma {
-                        start: unmap_end,
-                        end: vma.end,
-                        prot: vma.prot,
-                        flags: vma.flags,
-                    });
-                }
-            }
-
-            mem.vma_list = new_vmas;
+            update_vma_range(&mut mem.vma_list, addr, addr + aligned_len, |_| None);
             0
         })
         .unwrap_or(0)
@@ -1834,20 +2009,67 @@ pub fn sys_mprotect(addr: u64, length: u64, prot: u32) -> i64 {
                 flush_page += 4096;
             }
 
-            // Update VMA protection flags
-            for vma in &mut mem.vma_list {
-                let prot_start = addr;
-                let prot_end = addr + aligned_len;
-                if vma.start < prot_end && vma.end > prot_start {
-                    vma.prot = prot;
-                }
-            }
+            // Update VMA protection flags, splitting VMAs at the edges of
+            // the range so pages populated later get the right protection
+            update_vma_range(&mut mem.vma_list, addr, addr + aligned_len, |mut vma| {
+                vma.prot = prot;
+                Some(vma)
+            });
 
             0
         })
         .unwrap_or(0)
 }
 
+/// Apply `inner` to the parts of VMAs inside `[start, end)`, splitting
+/// VMAs that straddle its edges. `inner` returns the replacement for each
+/// part, or `None` to remove it.
+fn update_vma_range(
+    vma_list: &mut Vec<Vma>,
+    start: u64,
+    end: u64,
+    mut inner: impl FnMut(Vma) -> Option<Vma>,
+) {
+    let mut updated = Vec::with_capacity(vma_list.len() + 1);
+    for vma in vma_list.drain(..) {
+        if vma.end <= start || vma.start >= end {
+            updated.push(vma);
+            continue;
+        }
+        if vma.start < start {
+            updated.push(vma.slice(vma.start, start));
+        }
+        updated.extend(inner(vma.slice(vma.start.max(start), vma.end.min(end))));
+        if vma.end > end {
+            updated.push(vma.slice(end, vma.end));
+        }
+    }
+    *vma_list = updated;
+}
+
+/// Resolve the fd of a file mapping to the file its pages are read from.
+fn file_mapping_source(pid: ProcessId, fd: i32) -> Result<FileSource, i64> {
+    let path = {
+        let guard = PROCESS_TABLE.get(pid).ok_or(-EBADF)?;
+        let proc = guard.get(&pid).ok_or(-EBADF)?;
+        match proc.get_fd(fd as u32).map(|fde| &fde.resource) {
+            Some(FileResource::File { path }) => path.clone(),
+            Some(_) => return Err(-ENODEV),
+            None => return Err(-EBADF),
+        }
+    };
+
+    // procfs content is generated on each read, so it cannot be paged in
+    if vfs::procfs::resolve(&path).is_some() {
+        return Err(-ENODEV);
+    }
+    match vfs::stat(&path) {
+        Ok(stat) if stat.is_file => Ok(FileSource::Inode(stat.ino)),
+        Ok(_) => Err(-ENODEV),
+        Err(_) => Err(-EBADF),
+    }
+}
+
 /// Check if a range overlaps any existing VMA.
 fn vma_overlaps(vma_list: &[Vma], start: u64, len: u64) -> bool {
     let end = start + len;
@@ -1971,21 +2193,34 @@ pub fn sys_set_tid_address(tidptr: u64) -> i64 {
 }
 
 /// `clock_gettime(clockid, tp)` → `0` or `-errno`
-pub fn sys_clock_gettime(_clockid: i32, tp_ptr: u64) -> i64 {
+///
+/// `CLOCK_REALTIME` reports wall-clock time from [`crate::time::now`];
+/// the monotonic and CPU-time clocks report time since boot.
+pub fn sys_clock_gettime(clockid: i32, tp_ptr: u64) -> i64 {
+    const CLOCK_REALTIME: i32 = 0;
+    const CLOCK_MONOTONIC: i32 = 1;
+    const CLOCK_PROCESS_CPUTIME_ID: i32 = 2;
+    const CLOCK_THREAD_CPUTIME_ID: i32 = 3;
+    const CLOCK_MONOTONIC_RAW: i32 = 4;
+    const CLOCK_REALTIME_COARSE: i32 = 5;
+    const CLOCK_MONOTONIC_COARSE: i32 = 6;
+    const CLOCK_BOOTTIME: i32 = 7;
+
+    let now = match clockid {
+        CLOCK_REALTIME | CLOCK_REALTIME_COARSE => crate::time::now(),
+        CLOCK_MONOTONIC
+        | CLOCK_PROCESS_CPUTIME_ID
+        | CLOCK_THREAD_CPUTIME_ID
+        | CLOCK_MONOTONIC_RAW
+        | CLOCK_MONOTONIC_COARSE
+        | CLOCK_BOOTTIME => crate::time::monotonic(),
+        _ => return -EINVAL,
+    };
+
     if validate_user_ptr(tp_ptr, 16).is_err() {
         return -EFAULT;
     }
 
-    // Return a monotonic clock value based on TSC
-    let tsc: u64;
-    unsafe {
-        core::arch::asm!("rdtsc", "shl rdx, 32", "or rax, rdx", out("rax") tsc, out("rdx") _);
-    }
-
-    // Approximate: assume ~2GHz TSC, convert to timespec
-    let seconds = tsc / 2_000_000_000;
-    let nanoseconds = ((tsc % 2_000_000_000) * 1_000_000_000) / 2_000_000_000;
-
     #[repr(C)]
     struct Timespec {
         tv_sec: i64,
@@ -1993,8 +2228,8 @@ pub fn sys_clock_gettime(_clockid: i32, tp_ptr: u64) -> i64 {
     }
 
     let ts = Timespec {
-        tv_sec: seconds as i64,
-        tv_nsec: nanoseconds as i64,
+        tv_sec: now.secs as i64,
+        tv_nsec: now.nanos as i64,
     };
 
     let bytes = unsafe {
@@ -2979,16 +3214,8 @@ pub fn sys_gettimeofday(tv_ptr: u64, _tz_ptr: u64) -> i64 {
         return -EFAULT;
     }
 
-    // Use TSC for time measurement
-    let tsc: u64;
-    unsafe {
-        core::arch::asm!("rdtsc", "shl rdx, 32", "or rax, rdx", out("rax") tsc, out("rdx") _);
-    }
-
-    // Approximate: ~2GHz TSC
-    let seconds = tsc / 2_000_000_000;
-    let remainder = tsc % 2_000_000_000;
-    let microseconds = (remainder * 1_000_000) / 2_000_000_000;
+    // RTC-anchored wall clock, interpolated with the TSC
+    let now = crate::time::now();
 
     #[repr(C)]
     struct Timeval {
@@ -2997,8 +3224,8 @@ pub fn sys_gettimeofday(tv_ptr: u64, _tz_ptr: u64) -> i64 {
     }
 
     let tv = Timeval {
-        tv_sec: seconds as i64,
-        tv_usec: microseconds as i64,
+        tv_sec: now.secs as i64,
+        tv_usec: now.subsec_micros() as i64,
     };
 
     let bytes = unsafe {
@@ -3512,8 +3739,20 @@ mod tests {
     #[test]
     fn test_vma_overlaps() {
         let vmas = alloc::vec![
-            Vma { start: 0x1000, end: 0x3000, prot: 0, flags: 0 },
-            Vma { start: 0x5000, end: 0x8000, prot: 0, flags: 0 },
+            Vma {
+                start: 0x1000,
+                end: 0x3000,
+                prot: 0,
+                flags: 0,
+                backing: VmaBacking::Anonymous,
+            },
+            Vma {
+                start: 0x5000,
+                end: 0x8000,
+                prot: 0,
+                flags: 0,
+                backing: VmaBacking::Anonymous,
+            },
         ];
 
         // No overlap
@@ -183,6 +183,8 @@ pub fn syscall_name(nr: u64) -> Option<&'static str> {
         110 => Some("getppid"),
         111 => Some("getpgrp"),
         112 => Some("setsid"),
+        137 => Some("statfs"),
+        138 => Some("fstatfs"),
         158 => Some("arch_prctl"),
         200 => Some("tkill"),
         201 => Some("time"),
@@ -314,7 +316,7 @@ pub fn reset_stats() {
 /// Called from `linux_syscall_dispatch` when tracing is enabled.
 pub fn trace_syscall_entry(nr: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64, a6: u64) {
     let name = syscall_name(nr).unwrap_or("unknown");
-    let pid = super::percpu::get_current_pid(0);
+    let pid = crate::percpu::current_pid();
 
     crate::serial_println!(
         "[TRACE] pid={} {}({}) args=({:#x}, {:#x}, {:#x}, {:#x}, {:#x}, {:#x})",
@@ -327,7 +329,7 @@ pub fn trace_syscall_entry(nr: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64,
 /// Called from `linux_syscall_dispatch` when tracing is enabled.
 pub fn trace_syscall_exit(nr: u64, result: i64) {
     let name = syscall_name(nr).unwrap_or("unknown");
-    let pid = super::percpu::get_current_pid(0);
+    let pid = crate::percpu::current_pid();
 
     if result < 0 {
         // Error return — show errno name
@@ -349,7 +351,7 @@ pub fn trace_syscall_exit(nr: u64, result: i64) {
 /// Prints a prominent warning with the human-readable name if available.
 pub fn trace_unknown_syscall(nr: u64, a1: u64, a2: u64) {
     let name = syscall_name(nr).unwrap_or("???");
-    let pid = super::percpu::get_current_pid(0);
+    let pid = crate::percpu::current_pid();
 
     crate::serial_println!(
         "[KPIO/Linux] WARNING: Unimplemented syscall #{} ({}) from pid={} (a1={:#x}, a2={:#x})",
@@ -69,6 +69,7 @@ pub static COMMAND_LIST: &[&str] = &[
     "dhcp",
     "diff",
     "dirname",
+    "dmesg",
     "du",
     "echo",
     "env",
@@ -78,6 +79,7 @@ pub static COMMAND_LIST: &[&str] = &[
     "file",
     "find",
     "free",
+    "fsck",
     "grep",
     "groups",
     "head",
@@ -97,6 +99,7 @@ pub static COMMAND_LIST: &[&str] = &[
     "mkdir",
     "mv",
     "neofetch",
+    "netcap",
     "netstat",
     "nl",
     "nslookup",
@@ -193,6 +196,7 @@ pub fn execute_command(name: &str, args: &[String], pipe_in: Option<&[String]>)
         "whoami" => cmd_whoami(args),
         "hostname" => cmd_hostname(args),
         "uptime" => cmd_uptime(args),
+        "dmesg" => cmd_dmesg(args),
         "free" => cmd_free(args),
         "top" => cmd_top(args),
         "ps" => cmd_ps(args),
@@ -230,6 +234,7 @@ pub fn execute_command(name: &str, args: &[String], pipe_in: Option<&[String]>)
         "ping" => cmd_ping(args),
         "ifconfig" => cmd_ifconfig(args),
         "netstat" => cmd_netstat(args),
+        "netcap" => cmd_netcap(args),
         "nslookup" => cmd_nslookup(args),
         "curl" => cmd_curl(args),
         "wget" => cmd_wget(args),
@@ -238,6 +243,7 @@ pub fn execute_command(name: &str, args: &[String], pipe_in: Option<&[String]>)
         // ── Hardware ──
         "lspci" => cmd_lspci(args),
         "lsblk" => cmd_lsblk(args),
+        "fsck" => cmd_fsck(args),
         "acpi" => cmd_acpi(args),
 
         // ── Misc ──
@@ -777,14 +783,23 @@ fn cmd_cat(args: &[String], pipe_in: Option<&[String]>) -> CmdResult {
 
     for path in &paths {
         let abs = shell::with_shell(|sh| sh.resolve_path(path));
-        let ino = match fs::with_fs(|fs| fs.resolve(&abs)) {
-            Some(i) => i,
-            None => {
-                output.push(format!("cat: {}: No such file or directory", path));
-                continue;
-            }
+        let data = if let Some(node) = crate::vfs::procfs::resolve(&abs) {
+            crate::vfs::procfs::read(node).map_err(|e| match e {
+                crate::vfs::VfsError::IsDirectory => fs::FsError::IsADirectory,
+                crate::vfs::VfsError::NotFound => fs::FsError::NotFound,
+                _ => fs::FsError::InvalidOperation,
+            })
+        } else {
+            let ino = match fs::with_fs(|fs| fs.resolve(&abs)) {
+                Some(i) => i,
+                None => {
+                    output.push(format!("cat: {}: No such file or directory", path));
+                    continue;
+                }
+            };
+            fs::with_fs(|fs| fs.read_file(ino))
         };
-        match fs::with_fs(|fs| fs.read_file(ino)) {
+        match data {
             Ok(data) => {
                 let text = String::from_utf8_lossy(&data);
                 for line in text.lines() {
@@ -2060,6 +2075,65 @@ fn cmd_uptime(_args: &[String]) -> CmdResult {
     ))
 }
 
+fn cmd_dmesg(args: &[String]) -> CmdResult {
+    use crate::log::{self, Level, LogFilter};
+
+    const USAGE: &str = "Usage: dmesg [-l LEVEL] [-t TAG] [-c | -C] [-n LEVEL]";
+
+    let mut filter = LogFilter::default();
+    let mut clear_after = false;
+    let mut i = 0;
+    while i < args.len() {
+        let opt = args[i].as_str();
+        match opt {
+            "-c" => clear_after = true,
+            "-C" => {
+                log::clear();
+                return CmdResult::ok_empty();
+            }
+            "-l" | "-n" => {
+                let level = match args.get(i + 1).and_then(|v| Level::parse(v)) {
+                    Some(level) => level,
+                    None => {
+                        return CmdResult::err(format!(
+                            "dmesg: option {} requires a level (trace, debug, info, warn, error)",
+                            opt
+                        ))
+                    }
+                };
+                if opt == "-n" {
+                    log::set_console_level(level);
+                    return CmdResult::ok_empty();
+                }
+                filter.min_level = level;
+                i += 1;
+            }
+            "-t" => match args.get(i + 1) {
+                Some(tag) => {
+                    filter.tag = Some(tag.clone());
+                    i += 1;
+                }
+                None => return CmdResult::err(String::from("dmesg: option -t requires a tag")),
+            },
+            _ => return CmdResult::err(String::from(USAGE)),
+        }
+        i += 1;
+    }
+
+    let output = log::read(&filter)
+        .iter()
+        .map(|record| match record.level {
+            Level::Error => format!("{}{}{}", ansi::red(), record, ansi::reset()),
+            Level::Warn => format!("{}{}{}", ansi::yellow(), record, ansi::reset()),
+            _ => format!("{}", record),
+        })
+        .collect();
+    if clear_after {
+        log::clear();
+    }
+    CmdResult::ok(output)
+}
+
 fn cmd_free(args: &[String]) -> CmdResult {
     let human = args.iter().any(|a| a == "-h");
     let stats = crate::allocator::heap_stats();
@@ -2941,6 +3015,101 @@ fn cmd_dhcp(_args: &[String]) -> CmdResult {
     CmdResult::ok(output)
 }
 
+fn cmd_netcap(args: &[String]) -> CmdResult {
+    use crate::net::capture::{self, CaptureConfig, Filter};
+
+    const USAGE: &str =
+        "Usage: netcap start [-c FRAMES] [-s SNAPLEN] [FILTER] | stop | status | clear";
+
+    match args.first().map(String::as_str) {
+        Some("start") => {
+            let mut config = CaptureConfig::default();
+            let mut filter_words: Vec<&str> = Vec::new();
+            let mut i = 1;
+            while i < args.len() {
+                match args[i].as_str() {
+                    opt @ ("-c" | "-s") => {
+                        let value = match args.get(i + 1).and_then(|v| v.parse::<usize>().ok()) {
+                            Some(v) if v > 0 => v,
+                            _ => {
+                                return CmdResult::err(format!(
+                                    "netcap: option {} requires a positive number",
+                                    opt
+                                ))
+                            }
+                        };
+                        if opt == "-c" {
+                            config.capacity = value;
+                        } else {
+                            config.snaplen = value;
+                        }
+                        i += 2;
+                    }
+                    word => {
+                        filter_words.push(word);
+                        i += 1;
+                    }
+                }
+            }
+
+            if !filter_words.is_empty() {
+                match Filter::parse(&filter_words.join(" ")) {
+                    Ok(filter) => config.filter = Some(filter),
+                    Err(e) => return CmdResult::err(format!("netcap: {}", e)),
+                }
+            }
+
+            let filter_desc = if filter_words.is_empty() {
+                String::from("all frames")
+            } else {
+                format!("'{}'", filter_words.join(" "))
+            };
+            capture::start(config);
+            let config = capture::config().unwrap_or_default();
+            CmdResult::ok_one(format!(
+                "Capturing {} ({} frames, snaplen {}) -> /proc/net/capture.pcap",
+                filter_desc, config.capacity, config.snaplen
+            ))
+        }
+        Some("stop") => {
+            capture::stop();
+            match capture::stats() {
+                Some(stats) => CmdResult::ok_one(format!(
+                    "Capture stopped, {} frames buffered",
+                    stats.buffered
+                )),
+                None => CmdResult::ok_one(String::from("No capture running")),
+            }
+        }
+        Some("clear") => {
+            capture::clear();
+            CmdResult::ok_one(String::from("Capture buffer cleared"))
+        }
+        Some("status") | None => {
+            let (Some(stats), Some(config)) = (capture::stats(), capture::config()) else {
+                return CmdResult::ok_one(String::from("No capture"));
+            };
+            let state = if capture::is_enabled() {
+                "running"
+            } else {
+                "stopped"
+            };
+            CmdResult::ok(vec![
+                format!("Capture:      {}", state),
+                format!(
+                    "Buffered:     {} / {} frames",
+                    stats.buffered, config.capacity
+                ),
+                format!("Snap length:  {} bytes", config.snaplen),
+                format!("Captured:     {}", stats.captured),
+                format!("Filtered out: {}", stats.filtered),
+                format!("Overwritten:  {}", stats.overwritten),
+            ])
+        }
+        Some(_) => CmdResult::err(String::from(USAGE)),
+    }
+}
+
 // ════════════════════════════════════════════════════════════
 //  Hardware commands
 // ════════════════════════════════════════════════════════════
@@ -2984,6 +3153,61 @@ fn cmd_lsblk(_args: &[String]) -> CmdResult {
     CmdResult::ok(output)
 }
 
+fn cmd_fsck(args: &[String]) -> CmdResult {
+    let mut repair = false;
+    let mut device_name = None;
+    for arg in args {
+        match arg.as_str() {
+            "-r" | "--repair" => repair = true,
+            _ => device_name = Some(arg.as_str()),
+        }
+    }
+
+    let Some(device_name) = device_name else {
+        return CmdResult::err(String::from("usage: fsck [-r] DEVICE"));
+    };
+    let Some(device) =
+        storage::driver::find_device(device_name).and_then(storage::driver::get_device)
+    else {
+        return CmdResult::err(format!("fsck: {}: no such block device", device_name));
+    };
+    if repair
+        && storage::list_mounts()
+            .iter()
+            .any(|m| m.device_str() == device_name)
+    {
+        return CmdResult::err(format!(
+            "fsck: {} is mounted; unmount it before repairing",
+            device_name
+        ));
+    }
+
+    let report = match storage::fs::fat32::check(device, repair) {
+        Ok(report) => report,
+        Err(e) => return CmdResult::err(format!("fsck: {}: {:?}", device_name, e)),
+    };
+
+    let mut output: Vec<String> = report.issues.iter().map(|i| format!("{}", i)).collect();
+    output.push(format!(
+        "{}: {} problem(s), {}/{} clusters in use",
+        device_name,
+        report.issues.len(),
+        report.used_clusters,
+        report.total_clusters
+    ));
+    if report.repaired && !report.is_clean() {
+        output.push(format!(
+            "{}: repaired, {} lost cluster(s) reclaimed",
+            device_name, report.reclaimed_clusters
+        ));
+    }
+
+    CmdResult {
+        output,
+        success: report.is_clean() || report.repaired,
+    }
+}
+
 fn cmd_acpi(_args: &[String]) -> CmdResult {
     let count = crate::hw::acpi::table_count();
     let mut output = Vec::new();
@@ -3024,6 +3248,7 @@ fn cmd_man(args: &[String]) -> CmdResult {
         "cat" => "cat - concatenate files and print on the standard output\n\nSYNOPSIS: cat [OPTION]... [FILE]...\n\nOPTIONS:\n  -n  number all output lines",
         "grep" => "grep - print lines that match patterns\n\nSYNOPSIS: grep [OPTION]... PATTERN [FILE]...\n\nOPTIONS:\n  -i  ignore case\n  -v  invert match\n  -c  count only\n  -n  line numbers",
         "find" => "find - search for files in a directory hierarchy\n\nSYNOPSIS: find [path] [expression]\n\nOPTIONS:\n  -name PATTERN  match filename\n  -type d/f      match directory/file",
+        "dmesg" => "dmesg - print the kernel log\n\nSYNOPSIS: dmesg [OPTION]...\n\nOPTIONS:\n  -l LEVEL  only records at or above LEVEL\n  -t TAG    only records with subsystem TAG\n  -c        clear the log after printing\n  -C        clear the log\n  -n LEVEL  set the console level",
         _ => "No manual entry found. Try: help",
     };
 
@@ -3065,7 +3290,7 @@ fn cmd_help(_args: &[String]) -> CmdResult {
             "  {}uname whoami hostname uptime free top ps kill id groups{}",
             g, r
         ),
-        format!("  {}env printenv export unset who{}", g, r),
+        format!("  {}env printenv export unset who dmesg{}", g, r),
         String::new(),
         format!("{}{}Utilities:{}", b, y, r),
         format!(
@@ -3075,10 +3300,10 @@ fn cmd_help(_args: &[String]) -> CmdResult {
         format!("  {}sleep time test exit{}", g, r),
         String::new(),
         format!("{}{}Network:{}", b, y, r),
-        format!("  {}ping ifconfig netstat{}", g, r),
+        format!("  {}ping ifconfig netstat netcap{}", g, r),
         String::new(),
         format!("{}{}Hardware:{}", b, y, r),
-        format!("  {}lspci lsblk acpi{}", g, r),
+        format!("  {}lspci lsblk fsck acpi{}", g, r),
         String::new(),
         format!("{}{}Misc:{}", b, y, r),
         format!(
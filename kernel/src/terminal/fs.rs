@@ -57,12 +57,21 @@ impl FileMode {
     }
 }
 
-/// Timestamp (seconds since boot — no RTC yet)
+/// Timestamp (seconds since the Unix epoch)
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Timestamp {
     pub secs: u64,
 }
 
+impl Timestamp {
+    /// Current wall-clock time.
+    pub fn now() -> Self {
+        Timestamp {
+            secs: crate::time::now().secs,
+        }
+    }
+}
+
 /// Inode — metadata + content
 #[derive(Debug, Clone)]
 pub struct Inode {
@@ -263,8 +272,8 @@ impl MemFs {
             uid: 0,
             gid: 0,
             size: 0,
-            created: Timestamp::default(),
-            modified: Timestamp::default(),
+            created: Timestamp::now(),
+            modified: Timestamp::now(),
             nlink: 2,
             content: InodeContent::Directory({
                 let mut m = BTreeMap::new();
@@ -301,6 +310,7 @@ impl MemFs {
                             if node.mode.is_file() {
                                 node.content = InodeContent::File(Vec::from(data));
                                 node.size = data.len() as u64;
+                                node.modified = Timestamp::now();
                                 return Ok(existing);
                             }
                         }
@@ -318,8 +328,8 @@ impl MemFs {
             uid: 0,
             gid: 0,
             size: data.len() as u64,
-            created: Timestamp::default(),
-            modified: Timestamp::default(),
+            created: Timestamp::now(),
+            modified: Timestamp::now(),
             nlink: 1,
             content: InodeContent::File(Vec::from(data)),
         };
@@ -344,6 +354,22 @@ impl MemFs {
         }
     }
 
+    /// Read regular file content starting at `offset` into `buf`.
+    /// Returns the number of bytes read, which is short at end of file.
+    pub fn read_at(&self, ino: Ino, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let node = self.inodes.get(&ino).ok_or(FsError::NotFound)?;
+        match &node.content {
+            InodeContent::File(data) => {
+                let start = (offset as usize).min(data.len());
+                let len = buf.len().min(data.len() - start);
+                buf[..len].copy_from_slice(&data[start..start + len]);
+                Ok(len)
+            }
+            InodeContent::Directory(_) => Err(FsError::IsADirectory),
+            _ => Err(FsError::InvalidOperation),
+        }
+    }
+
     /// Write (overwrite) file content.
     pub fn write_file(&mut self, ino: Ino, data: &[u8]) -> Result<(), FsError> {
         let node = self.inodes.get_mut(&ino).ok_or(FsError::NotFound)?;
@@ -351,6 +377,7 @@ impl MemFs {
             InodeContent::File(ref mut buf) => {
                 *buf = Vec::from(data);
                 node.size = data.len() as u64;
+                node.modified = Timestamp::now();
                 Ok(())
             }
             InodeContent::ProcFile(_) => Err(FsError::ReadOnly),
@@ -366,6 +393,7 @@ impl MemFs {
             InodeContent::File(ref mut buf) => {
                 buf.extend_from_slice(data);
                 node.size = buf.len() as u64;
+                node.modified = Timestamp::now();
                 Ok(())
             }
             _ => Err(FsError::InvalidOperation),
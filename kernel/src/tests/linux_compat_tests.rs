@@ -270,7 +270,8 @@ mod tests {
             SYS_GETDENTS64, SYS_KILL, SYS_GETTIMEOFDAY, SYS_CLOCK_GETTIME,
             SYS_ARCH_PRCTL, SYS_SET_TID_ADDRESS, SYS_SET_ROBUST_LIST,
             SYS_GETRANDOM, SYS_PRLIMIT64, SYS_READV, SYS_MADVISE,
-            SYS_FUTEX, SYS_PIPE2, SYS_OPENAT,
+            SYS_FUTEX, SYS_PIPE2, SYS_OPENAT, SYS_PREAD64, SYS_PWRITE64,
+            SYS_STATFS, SYS_FSTATFS,
         ];
 
         // Dispatch each syscall 100 times with various args
@@ -323,6 +324,7 @@ mod tests {
             SYS_ARCH_PRCTL, SYS_FUTEX, SYS_SET_TID_ADDRESS,
             SYS_CLOCK_GETTIME, SYS_EXIT_GROUP, SYS_OPENAT, SYS_READLINKAT,
             SYS_SET_ROBUST_LIST, SYS_PIPE2, SYS_PRLIMIT64, SYS_GETRANDOM,
+            SYS_PREAD64, SYS_PWRITE64, SYS_STATFS, SYS_FSTATFS,
         ];
 
         for &nr in &implemented {
@@ -0,0 +1,415 @@
+//! Wall-clock time.
+//!
+//! The CMOS real-time clock is read once at boot to anchor the wall clock
+//! to the Unix epoch. From then on time advances by interpolating the TSC,
+//! whose frequency is calibrated against PIT channel 2, so `now()` has
+//! sub-microsecond resolution without touching the slow CMOS ports again.
+//!
+//! Before [`init`] runs, `now()` degrades to time since the TSC was reset
+//! at an assumed 2 GHz, which matches the old syscall behaviour.
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use x86_64::instructions::port::Port;
+
+/// Nanoseconds per second.
+pub const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// ── CMOS / RTC registers ────────────────────────────────────────────
+
+const CMOS_ADDRESS: u16 = 0x70;
+const CMOS_DATA: u16 = 0x71;
+/// Setting bit 7 of the CMOS index keeps NMIs masked during the access.
+const CMOS_NMI_DISABLE: u8 = 0x80;
+
+const RTC_SECONDS: u8 = 0x00;
+const RTC_MINUTES: u8 = 0x02;
+const RTC_HOURS: u8 = 0x04;
+const RTC_DAY: u8 = 0x07;
+const RTC_MONTH: u8 = 0x08;
+const RTC_YEAR: u8 = 0x09;
+const RTC_STATUS_A: u8 = 0x0A;
+const RTC_STATUS_B: u8 = 0x0B;
+/// Century register. The ACPI FADT can name a different index, but every
+/// PC-compatible firmware (and QEMU) uses 0x32.
+const RTC_CENTURY: u8 = 0x32;
+
+/// Status A: an update cycle is in progress, registers are unstable.
+const STATUS_A_UPDATE_IN_PROGRESS: u8 = 0x80;
+/// Status B: hours are in 24-hour format.
+const STATUS_B_24_HOUR: u8 = 0x02;
+/// Status B: registers hold binary values instead of BCD.
+const STATUS_B_BINARY: u8 = 0x04;
+/// Hour register PM flag in 12-hour mode.
+const HOUR_PM: u8 = 0x80;
+
+/// Upper bound on polling loops so broken hardware cannot hang boot.
+const MAX_POLL: u32 = 1_000_000;
+/// How long [`init`] waits for the RTC seconds to tick over.
+const RTC_EDGE_WAIT_MS: u64 = 5;
+
+// ── PIT calibration ─────────────────────────────────────────────────
+
+const PIT_FREQUENCY_HZ: u64 = 1_193_182;
+const PIT_CHANNEL2: u16 = 0x42;
+const PIT_COMMAND: u16 = 0x43;
+/// Keyboard controller port B: bit 0 gates PIT channel 2, bit 1 drives
+/// the speaker, bit 5 reflects the channel 2 output.
+const PORT_B: u16 = 0x61;
+/// Channel 2, lobyte/hibyte access, mode 0 (interrupt on terminal count).
+const PIT_CH2_ONESHOT: u8 = 0b1011_0000;
+const CALIBRATION_MS: u64 = 10;
+/// Used when calibration fails; matches the old fixed approximation.
+const FALLBACK_TSC_HZ: u64 = 2_000_000_000;
+
+static TSC_HZ: AtomicU64 = AtomicU64::new(FALLBACK_TSC_HZ);
+static BOOT_TSC: AtomicU64 = AtomicU64::new(0);
+static BOOT_EPOCH_SECS: AtomicU64 = AtomicU64::new(0);
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// A point in time as seconds and nanoseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timespec {
+    pub secs: u64,
+    pub nanos: u32,
+}
+
+impl Timespec {
+    /// Build from a nanosecond count.
+    pub const fn from_nanos(nanos: u128) -> Self {
+        Self {
+            secs: (nanos / NANOS_PER_SEC as u128) as u64,
+            nanos: (nanos % NANOS_PER_SEC as u128) as u32,
+        }
+    }
+
+    /// Sub-second part in microseconds.
+    pub const fn subsec_micros(&self) -> u32 {
+        self.nanos / 1_000
+    }
+}
+
+/// Broken-down UTC calendar time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Seconds since the Unix epoch. Dates before 1970 clamp to 0.
+    pub fn to_unix(&self) -> u64 {
+        let days = days_from_civil(self.year as i64, self.month as u32, self.day as u32);
+        let secs =
+            days * 86_400 + self.hour as i64 * 3_600 + self.minute as i64 * 60 + self.second as i64;
+        secs.max(0) as u64
+    }
+
+    /// Convert seconds since the Unix epoch to calendar time.
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86_400) as i64;
+        let rem = secs % 86_400;
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (rem / 3_600) as u8,
+            minute: (rem % 3_600 / 60) as u8,
+            second: (rem % 60) as u8,
+        }
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian date for a count of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = (if z >= 0 { z } else { z - 146_096 }) / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Decode a packed BCD byte.
+fn bcd_to_binary(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0x0F)
+}
+
+/// Raw RTC register snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RtcRegisters {
+    second: u8,
+    minute: u8,
+    hour: u8,
+    day: u8,
+    month: u8,
+    year: u8,
+    century: u8,
+}
+
+impl RtcRegisters {
+    /// Decode according to status register B (BCD vs. binary, 12 vs. 24h).
+    fn decode(self, status_b: u8) -> DateTime {
+        let binary = status_b & STATUS_B_BINARY != 0;
+        let decode = |v: u8| if binary { v } else { bcd_to_binary(v) };
+
+        let pm = self.hour & HOUR_PM != 0;
+        let mut hour = decode(self.hour & !HOUR_PM);
+        if status_b & STATUS_B_24_HOUR == 0 {
+            // 12-hour clock: 12 AM is midnight, 12 PM is noon.
+            hour %= 12;
+            if pm {
+                hour += 12;
+            }
+        }
+
+        let year = decode(self.year) as u16;
+        let century = decode(self.century) as u16;
+        let year = if (19..=99).contains(&century) {
+            century * 100 + year
+        } else if year < 70 {
+            // No usable century register: assume 1970..=2069.
+            2000 + year
+        } else {
+            1900 + year
+        };
+
+        DateTime {
+            year,
+            month: decode(self.month),
+            day: decode(self.day),
+            hour,
+            minute: decode(self.minute),
+            second: decode(self.second),
+        }
+    }
+}
+
+fn rdtsc() -> u64 {
+    let tsc: u64;
+    // SAFETY: RDTSC is unprivileged and has no side effects.
+    unsafe {
+        core::arch::asm!("rdtsc", "shl rdx, 32", "or rax, rdx", out("rax") tsc, out("rdx") _);
+    }
+    tsc
+}
+
+fn cmos_read(register: u8) -> u8 {
+    let mut address: Port<u8> = Port::new(CMOS_ADDRESS);
+    let mut data: Port<u8> = Port::new(CMOS_DATA);
+    // SAFETY: 0x70/0x71 are the standard CMOS index/data ports; reading
+    // an RTC register has no side effects beyond selecting the index.
+    unsafe {
+        address.write(CMOS_NMI_DISABLE | register);
+        data.read()
+    }
+}
+
+fn rtc_update_in_progress() -> bool {
+    cmos_read(RTC_STATUS_A) & STATUS_A_UPDATE_IN_PROGRESS != 0
+}
+
+fn read_rtc_registers() -> RtcRegisters {
+    let mut spins = 0;
+    while rtc_update_in_progress() && spins < MAX_POLL {
+        spins += 1;
+        core::hint::spin_loop();
+    }
+    RtcRegisters {
+        second: cmos_read(RTC_SECONDS),
+        minute: cmos_read(RTC_MINUTES),
+        hour: cmos_read(RTC_HOURS),
+        day: cmos_read(RTC_DAY),
+        month: cmos_read(RTC_MONTH),
+        year: cmos_read(RTC_YEAR),
+        century: cmos_read(RTC_CENTURY),
+    }
+}
+
+/// Read the current date and time from the CMOS RTC.
+///
+/// Registers are read until two consecutive snapshots agree, so an update
+/// cycle that starts mid-read cannot produce a torn value.
+pub fn read_rtc() -> DateTime {
+    let mut last = read_rtc_registers();
+    for _ in 0..8 {
+        let next = read_rtc_registers();
+        if next == last {
+            break;
+        }
+        last = next;
+    }
+    last.decode(cmos_read(RTC_STATUS_B))
+}
+
+/// Measure the TSC frequency against a PIT channel 2 one-shot.
+fn calibrate_tsc() -> Option<u64> {
+    let mut port_b: Port<u8> = Port::new(PORT_B);
+    let mut command: Port<u8> = Port::new(PIT_COMMAND);
+    let mut channel2: Port<u8> = Port::new(PIT_CHANNEL2);
+    let count = PIT_FREQUENCY_HZ * CALIBRATION_MS / 1_000;
+
+    // SAFETY: PIT channel 2 and port B are only used for the speaker,
+    // which stays disabled; the original port B value is restored.
+    let elapsed = unsafe {
+        let saved = port_b.read();
+        port_b.write((saved & !0x02) | 0x01);
+
+        command.write(PIT_CH2_ONESHOT);
+        channel2.write(count as u8);
+        channel2.write((count >> 8) as u8);
+
+        // Re-trigger the gate so counting starts now.
+        let gate = port_b.read();
+        port_b.write(gate & !0x01);
+        port_b.write(gate | 0x01);
+
+        let start = rdtsc();
+        let mut spins = 0;
+        while port_b.read() & 0x20 == 0 && spins < MAX_POLL {
+            spins += 1;
+        }
+        let end = rdtsc();
+        port_b.write(saved);
+
+        if spins >= MAX_POLL {
+            return None;
+        }
+        end.wrapping_sub(start)
+    };
+
+    let hz = elapsed * 1_000 / CALIBRATION_MS;
+    // Reject nonsense (below 100 MHz or above 10 GHz).
+    (100_000_000..=10_000_000_000).contains(&hz).then_some(hz)
+}
+
+/// Read the RTC and calibrate the TSC. Call once during boot.
+pub fn init() {
+    let hz = calibrate_tsc().unwrap_or(FALLBACK_TSC_HZ);
+    TSC_HZ.store(hz, Ordering::Relaxed);
+
+    // Anchor on an RTC second boundary if one comes soon, so the
+    // sub-second part starts at 0. Otherwise boot goes on and the wall
+    // clock may lag by up to a second.
+    let first = read_rtc();
+    let deadline = rdtsc().saturating_add(hz * RTC_EDGE_WAIT_MS / 1_000);
+    let mut rtc = first;
+    while rtc == first && rdtsc() < deadline {
+        rtc = read_rtc();
+    }
+    BOOT_TSC.store(rdtsc(), Ordering::Relaxed);
+    BOOT_EPOCH_SECS.store(rtc.to_unix(), Ordering::Relaxed);
+    INITIALIZED.store(true, Ordering::Release);
+
+    crate::info!(
+        target: "TIME",
+        "RTC {:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC, TSC {} MHz",
+        rtc.year,
+        rtc.month,
+        rtc.day,
+        rtc.hour,
+        rtc.minute,
+        rtc.second,
+        hz / 1_000_000
+    );
+}
+
+/// Whether the wall clock has been anchored to the RTC.
+pub fn is_initialized() -> bool {
+    INITIALIZED.load(Ordering::Acquire)
+}
+
+/// Calibrated TSC frequency in Hz.
+pub fn tsc_frequency() -> u64 {
+    TSC_HZ.load(Ordering::Relaxed)
+}
+
+/// Time elapsed since [`init`] (or since TSC reset if not yet initialised).
+pub fn monotonic() -> Timespec {
+    let ticks = rdtsc().saturating_sub(BOOT_TSC.load(Ordering::Relaxed));
+    let hz = TSC_HZ.load(Ordering::Relaxed).max(1);
+    Timespec::from_nanos(ticks as u128 * NANOS_PER_SEC as u128 / hz as u128)
+}
+
+/// Current wall-clock time as seconds/nanoseconds since the Unix epoch.
+pub fn now() -> Timespec {
+    let elapsed = monotonic();
+    Timespec {
+        secs: BOOT_EPOCH_SECS.load(Ordering::Relaxed) + elapsed.secs,
+        nanos: elapsed.nanos,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix_round_trip() {
+        let dt = DateTime {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 13,
+            minute: 45,
+            second: 7,
+        };
+        assert_eq!(dt.to_unix(), 1_709_214_307);
+        assert_eq!(DateTime::from_unix(dt.to_unix()), dt);
+        assert_eq!(DateTime::from_unix(0).year, 1970);
+    }
+
+    #[test]
+    fn test_rtc_decode_bcd_12_hour() {
+        let regs = RtcRegisters {
+            second: 0x59,
+            minute: 0x30,
+            hour: HOUR_PM | 0x12,
+            day: 0x31,
+            month: 0x12,
+            year: 0x99,
+            century: 0x19,
+        };
+        let dt = regs.decode(0);
+        assert_eq!((dt.year, dt.month, dt.day), (1999, 12, 31));
+        assert_eq!((dt.hour, dt.minute, dt.second), (12, 30, 59));
+
+        let midnight = RtcRegisters { hour: 0x12, ..regs }.decode(0);
+        assert_eq!(midnight.hour, 0);
+    }
+
+    #[test]
+    fn test_rtc_decode_binary_24_hour() {
+        let regs = RtcRegisters {
+            second: 5,
+            minute: 4,
+            hour: 23,
+            day: 1,
+            month: 3,
+            year: 26,
+            century: 0,
+        };
+        let dt = regs.decode(STATUS_B_BINARY | STATUS_B_24_HOUR);
+        assert_eq!((dt.year, dt.month, dt.day), (2026, 3, 1));
+        assert_eq!((dt.hour, dt.minute, dt.second), (23, 4, 5));
+    }
+}
@@ -8,6 +8,7 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use spin::Mutex;
 
+use super::procfs::{self, ProcNode};
 use super::VfsError;
 use crate::terminal::fs;
 
@@ -30,6 +31,8 @@ pub struct FdEntry {
     pub flags: u32,
     /// Whether this fd is a special (stdio) fd.
     pub special: Option<SpecialFd>,
+    /// procfs node, if this fd refers to a synthetic file.
+    pub proc_node: Option<ProcNode>,
 }
 
 /// Special file descriptors.
@@ -64,6 +67,7 @@ pub fn init() {
             offset: 0,
             flags: 0,
             special: Some(SpecialFd::Stdin),
+            proc_node: None,
         },
     );
     // 1 = stdout
@@ -75,6 +79,7 @@ pub fn init() {
             offset: 0,
             flags: 1,
             special: Some(SpecialFd::Stdout),
+            proc_node: None,
         },
     );
     // 2 = stderr
@@ -86,6 +91,7 @@ pub fn init() {
             offset: 0,
             flags: 1,
             special: Some(SpecialFd::Stderr),
+            proc_node: None,
         },
     );
 
@@ -107,6 +113,10 @@ where
 
 /// Open a file and return an fd.
 pub fn open(path: &str, flags: u32) -> Result<i32, VfsError> {
+    if let Some(node) = procfs::resolve(path) {
+        return open_proc(path, node, flags);
+    }
+
     let ino = fs::with_fs(|f| f.resolve(path));
 
     match ino {
@@ -125,6 +135,7 @@ pub fn open(path: &str, flags: u32) -> Result<i32, VfsError> {
                         offset: 0,
                         flags,
                         special: None,
+                        proc_node: None,
                     },
                 );
                 Ok(fd)
@@ -150,6 +161,7 @@ pub fn open(path: &str, flags: u32) -> Result<i32, VfsError> {
                             offset: 0,
                             flags,
                             special: None,
+                            proc_node: None,
                         },
                     );
                     fd
@@ -162,6 +174,32 @@ pub fn open(path: &str, flags: u32) -> Result<i32, VfsError> {
     }
 }
 
+/// Open a procfs node. procfs is read-only.
+fn open_proc(path: &str, node: ProcNode, flags: u32) -> Result<i32, VfsError> {
+    if flags & 3 != 0 {
+        return Err(VfsError::PermissionDenied);
+    }
+    with_table(|t| {
+        if t.entries.len() >= MAX_FDS {
+            return Err(VfsError::NoSpace);
+        }
+        let fd = t.next_fd;
+        t.next_fd += 1;
+        t.entries.insert(
+            fd,
+            FdEntry {
+                path: String::from(path),
+                ino: node.ino(),
+                offset: 0,
+                flags,
+                special: None,
+                proc_node: Some(node),
+            },
+        );
+        Ok(fd)
+    })
+}
+
 /// Read up to `len` bytes from an fd. Returns data read.
 pub fn read(fd: i32, len: usize) -> Result<Vec<u8>, VfsError> {
     // Stdio: stdin returns empty (no interactive input via syscall)
@@ -174,7 +212,11 @@ pub fn read(fd: i32, len: usize) -> Result<Vec<u8>, VfsError> {
         return Err(VfsError::PermissionDenied);
     }
 
-    let data = fs::with_fs(|f| f.read_file(entry.ino)).map_err(|_| VfsError::IoError)?;
+    // procfs content is regenerated on every read.
+    let data = match entry.proc_node {
+        Some(node) => procfs::read(node)?,
+        None => fs::with_fs(|f| f.read_file(entry.ino)).map_err(|_| VfsError::IoError)?,
+    };
 
     let start = entry.offset.min(data.len());
     let end = (start + len).min(data.len());
@@ -203,7 +245,7 @@ pub fn write(fd: i32, data: &[u8]) -> Result<usize, VfsError> {
         return Ok(data.len());
     }
 
-    if entry.special.is_some() {
+    if entry.special.is_some() || entry.proc_node.is_some() {
         return Err(VfsError::PermissionDenied);
     }
 
@@ -244,7 +286,10 @@ pub fn lseek(fd: i32, offset: i64, whence: u32) -> Result<u64, VfsError> {
             1 => (entry.offset as i64 + offset) as usize, // SEEK_CUR
             2 => {
                 // SEEK_END — need file size
-                let size = fs::with_fs(|f| f.get(entry.ino).map(|i| i.size as usize).unwrap_or(0));
+                let size = match entry.proc_node {
+                    Some(node) => procfs::stat(node).size as usize,
+                    None => fs::with_fs(|f| f.get(entry.ino).map(|i| i.size as usize).unwrap_or(0)),
+                };
                 (size as i64 + offset) as usize
             }
             _ => return Err(VfsError::IoError),
@@ -4,10 +4,14 @@
 //! filesystem (terminal::fs).  A global file descriptor table maps
 //! integer fds to open inodes + cursor offsets so that syscall
 //! read / write / open / close operate on real data.
+//!
+//! Paths under the procfs mount point (`/proc`) are served by
+//! [`procfs`], whose files are generated from live kernel state.
 
 #![allow(dead_code)]
 
 pub mod fd;
+pub mod procfs;
 pub mod sandbox;
 
 use alloc::string::String;
@@ -78,6 +82,10 @@ pub enum VfsError {
 pub fn stat(path: &str) -> Result<FileStat, VfsError> {
     use crate::terminal::fs;
 
+    if let Some(node) = procfs::resolve(path) {
+        return Ok(procfs::stat(node));
+    }
+
     let ino = fs::with_fs(|f| f.resolve(path)).ok_or(VfsError::NotFound)?;
 
     fs::with_fs(|f| {
@@ -100,15 +108,31 @@ pub fn stat(path: &str) -> Result<FileStat, VfsError> {
 pub fn read_all(path: &str) -> Result<Vec<u8>, VfsError> {
     use crate::terminal::fs;
 
+    if let Some(node) = procfs::resolve(path) {
+        return procfs::read(node);
+    }
+
     let ino = fs::with_fs(|f| f.resolve(path)).ok_or(VfsError::NotFound)?;
 
     fs::with_fs(|f| f.read_file(ino)).map_err(|_| VfsError::IoError)
 }
 
+/// Read part of a regular file by inode number, as when populating a
+/// file-backed mapping. Returns the number of bytes read.
+pub fn read_at(ino: u64, offset: u64, buf: &mut [u8]) -> Result<usize, VfsError> {
+    use crate::terminal::fs;
+
+    fs::with_fs(|f| f.read_at(ino, offset, buf)).map_err(|_| VfsError::IoError)
+}
+
 /// Write bytes to a file (create if missing, truncate existing).
 pub fn write_all(path: &str, data: &[u8]) -> Result<(), VfsError> {
     use crate::terminal::fs;
 
+    if procfs::resolve(path).is_some() {
+        return Err(VfsError::PermissionDenied);
+    }
+
     // Try to resolve existing
     let existing = fs::with_fs(|f| f.resolve(path));
     if let Some(ino) = existing {
@@ -127,9 +151,24 @@ pub fn write_all(path: &str, data: &[u8]) -> Result<(), VfsError> {
 pub fn readdir(path: &str) -> Result<Vec<(String, u64)>, VfsError> {
     use crate::terminal::fs;
 
-    let ino = fs::with_fs(|f| f.resolve(path)).ok_or(VfsError::NotFound)?;
-
-    fs::with_fs(|f| f.readdir_all(ino)).ok_or(VfsError::NotDirectory)
+    let static_entries = fs::with_fs(|f| f.resolve(path).and_then(|ino| f.readdir_all(ino)));
+
+    match procfs::resolve(path) {
+        Some(node) => {
+            // Merge in static entries the procfs does not shadow.
+            let mut entries = procfs::readdir(node)?;
+            for (name, ino) in static_entries.unwrap_or_default() {
+                if !entries.iter().any(|(n, _)| *n == name) {
+                    entries.push((name, ino));
+                }
+            }
+            Ok(entries)
+        }
+        None => {
+            fs::with_fs(|f| f.resolve(path)).ok_or(VfsError::NotFound)?;
+            static_entries.ok_or(VfsError::NotDirectory)
+        }
+    }
 }
 
 /// Split "/a/b/c" into ("/a/b", "c").
@@ -0,0 +1,429 @@
+//! procfs — synthetic filesystem exposing live kernel state
+//!
+//! Nothing here is stored: every file is rendered from kernel data
+//! structures at read time. The filesystem is read-only and is mounted
+//! at `/proc` by default. Names it does not recognise fall through to
+//! the in-memory filesystem, so the static `/proc` entries created by
+//! `terminal::fs` (cpuinfo, version, ...) stay visible.
+//!
+//! Layout:
+//! - `/proc/meminfo`       physical frame and kernel heap usage
+//! - `/proc/uptime`        seconds since boot
+//! - `/proc/kmsg`          kernel log records, as printed by `dmesg`
+//! - `/proc/net/capture.pcap`  frames recorded by `net::capture`
+//! - `/proc/<pid>/status`  name, state, ids and thread count
+//! - `/proc/<pid>/maps`    mapped virtual memory areas
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use super::{FileStat, VfsError};
+use crate::log::{LogFilter, LogRecord};
+use crate::process::table::{Process, ProcessId, ProcessState, Vma, PROCESS_TABLE};
+
+/// Default mount point.
+pub const DEFAULT_MOUNT_POINT: &str = "/proc";
+
+/// Synthetic inode numbers live above this base so they never collide
+/// with `terminal::fs` inodes.
+const PROC_INO_BASE: u64 = 0xF000_0000_0000_0000;
+
+/// Current mount point, if mounted.
+static MOUNT_POINT: Mutex<Option<String>> = Mutex::new(None);
+
+/// A node in the procfs tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcNode {
+    /// The mount root.
+    Root,
+    /// `meminfo`
+    MemInfo,
+    /// `uptime`
+    Uptime,
+    /// `kmsg`
+    Kmsg,
+    /// `net/`
+    NetDir,
+    /// `net/capture.pcap`
+    NetCapture,
+    /// `<pid>/`
+    ProcessDir(u64),
+    /// `<pid>/status`
+    Status(u64),
+    /// `<pid>/maps`
+    Maps(u64),
+}
+
+impl ProcNode {
+    /// Whether this node is a directory.
+    pub fn is_dir(self) -> bool {
+        matches!(
+            self,
+            ProcNode::Root | ProcNode::NetDir | ProcNode::ProcessDir(_)
+        )
+    }
+
+    /// Synthetic inode number.
+    pub fn ino(self) -> u64 {
+        match self {
+            ProcNode::Root => PROC_INO_BASE,
+            ProcNode::MemInfo => PROC_INO_BASE + 1,
+            ProcNode::Uptime => PROC_INO_BASE + 2,
+            ProcNode::NetDir => PROC_INO_BASE + 3,
+            ProcNode::NetCapture => PROC_INO_BASE + 4,
+            ProcNode::Kmsg => PROC_INO_BASE + 5,
+            ProcNode::ProcessDir(pid) => PROC_INO_BASE + 0x100 + (pid << 4),
+            ProcNode::Status(pid) => PROC_INO_BASE + 0x100 + (pid << 4) + 1,
+            ProcNode::Maps(pid) => PROC_INO_BASE + 0x100 + (pid << 4) + 2,
+        }
+    }
+}
+
+// ── Mounting ────────────────────────────────────────────────
+
+/// Mount procfs at `path`.
+pub fn mount(path: &str) -> Result<(), VfsError> {
+    let mut mp = MOUNT_POINT.lock();
+    if mp.is_some() {
+        return Err(VfsError::AlreadyExists);
+    }
+    let path = path.trim_end_matches('/');
+    *mp = Some(if path.is_empty() {
+        String::from("/")
+    } else {
+        path.to_string()
+    });
+    Ok(())
+}
+
+/// Unmount procfs.
+pub fn unmount() -> Result<(), VfsError> {
+    MOUNT_POINT
+        .lock()
+        .take()
+        .map(|_| ())
+        .ok_or(VfsError::NotFound)
+}
+
+/// Current mount point, if mounted.
+pub fn mount_point() -> Option<String> {
+    MOUNT_POINT.lock().clone()
+}
+
+/// Resolve an absolute path to a procfs node.
+///
+/// Returns `None` if procfs is not mounted, the path lies outside the
+/// mount point, or procfs has no such entry.
+pub fn resolve(path: &str) -> Option<ProcNode> {
+    let mp = MOUNT_POINT.lock().clone()?;
+    let rel = strip_mount(&mp, path)?;
+    lookup(rel)
+}
+
+/// Return `path` relative to `mount_point`, or `None` if outside it.
+fn strip_mount<'a>(mount_point: &str, path: &'a str) -> Option<&'a str> {
+    let rest = if mount_point == "/" {
+        path
+    } else {
+        path.strip_prefix(mount_point)?
+    };
+    if !rest.is_empty() && !rest.starts_with('/') {
+        // e.g. "/procfoo" under "/proc"
+        return None;
+    }
+    Some(rest.trim_matches('/'))
+}
+
+/// Look up a node by path relative to the mount root.
+fn lookup(rel: &str) -> Option<ProcNode> {
+    let mut parts = rel.split('/').filter(|p| !p.is_empty());
+    let first = match parts.next() {
+        None => return Some(ProcNode::Root),
+        Some(p) => p,
+    };
+
+    let node = match first {
+        "meminfo" => ProcNode::MemInfo,
+        "uptime" => ProcNode::Uptime,
+        "kmsg" => ProcNode::Kmsg,
+        "net" => match parts.next() {
+            None => ProcNode::NetDir,
+            Some("capture.pcap") => ProcNode::NetCapture,
+            Some(_) => return None,
+        },
+        _ => {
+            let pid: u64 = first.parse().ok()?;
+            if !process_exists(pid) {
+                return None;
+            }
+            match parts.next() {
+                None => ProcNode::ProcessDir(pid),
+                Some("status") => ProcNode::Status(pid),
+                Some("maps") => ProcNode::Maps(pid),
+                Some(_) => return None,
+            }
+        }
+    };
+
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(node)
+}
+
+// ── File operations ─────────────────────────────────────────
+
+/// Render a file's content from live kernel state.
+pub fn read(node: ProcNode) -> Result<Vec<u8>, VfsError> {
+    let text = match node {
+        ProcNode::Root | ProcNode::NetDir | ProcNode::ProcessDir(_) => {
+            return Err(VfsError::IsDirectory)
+        }
+        // Binary, so returned as-is
+        ProcNode::NetCapture => return Ok(crate::net::capture::export_pcap()),
+        ProcNode::MemInfo => render_meminfo(),
+        ProcNode::Uptime => render_uptime(crate::scheduler::boot_ticks()),
+        ProcNode::Kmsg => render_kmsg(&crate::log::read(&LogFilter::default())),
+        ProcNode::Status(pid) => with_process(pid, render_status).ok_or(VfsError::NotFound)?,
+        ProcNode::Maps(pid) => with_process(pid, |p| match &p.linux_memory {
+            Some(mem) => render_maps(&mem.vma_list, mem.brk_start, mem.brk_current),
+            None => String::new(),
+        })
+        .ok_or(VfsError::NotFound)?,
+    };
+    Ok(text.into_bytes())
+}
+
+/// List a directory as `(name, ino)` pairs, including `.` and `..`.
+pub fn readdir(node: ProcNode) -> Result<Vec<(String, u64)>, VfsError> {
+    match node {
+        ProcNode::Root => {
+            let mut entries = Vec::new();
+            entries.push((String::from("."), node.ino()));
+            entries.push((String::from(".."), node.ino()));
+            entries.push((String::from("meminfo"), ProcNode::MemInfo.ino()));
+            entries.push((String::from("uptime"), ProcNode::Uptime.ino()));
+            entries.push((String::from("kmsg"), ProcNode::Kmsg.ino()));
+            entries.push((String::from("net"), ProcNode::NetDir.ino()));
+            PROCESS_TABLE.for_each(|pid, _| {
+                let dir = ProcNode::ProcessDir(pid.as_u64());
+                entries.push((format!("{}", pid.as_u64()), dir.ino()));
+            });
+            Ok(entries)
+        }
+        ProcNode::NetDir => Ok(alloc::vec![
+            (String::from("."), node.ino()),
+            (String::from(".."), ProcNode::Root.ino()),
+            (String::from("capture.pcap"), ProcNode::NetCapture.ino()),
+        ]),
+        ProcNode::ProcessDir(pid) => Ok(alloc::vec![
+            (String::from("."), node.ino()),
+            (String::from(".."), ProcNode::Root.ino()),
+            (String::from("status"), ProcNode::Status(pid).ino()),
+            (String::from("maps"), ProcNode::Maps(pid).ino()),
+        ]),
+        _ => Err(VfsError::NotDirectory),
+    }
+}
+
+/// Stat a node. File sizes are those of a fresh read.
+pub fn stat(node: ProcNode) -> FileStat {
+    let is_dir = node.is_dir();
+    FileStat {
+        ino: node.ino(),
+        size: if is_dir {
+            0
+        } else {
+            read(node).map(|d| d.len() as u64).unwrap_or(0)
+        },
+        mode: if is_dir { 0o40555 } else { 0o100444 },
+        nlink: if is_dir { 2 } else { 1 },
+        uid: 0,
+        gid: 0,
+        is_dir,
+        is_file: !is_dir,
+        is_symlink: false,
+    }
+}
+
+// ── Renderers ───────────────────────────────────────────────
+
+fn process_exists(pid: u64) -> bool {
+    PROCESS_TABLE.get(ProcessId(pid)).is_some()
+}
+
+fn with_process<R>(pid: u64, f: impl FnOnce(&Process) -> R) -> Option<R> {
+    let guard = PROCESS_TABLE.get(ProcessId(pid))?;
+    guard.get(&ProcessId(pid)).map(f)
+}
+
+fn render_meminfo() -> String {
+    let frames = crate::memory::frame_stats();
+    let heap = crate::allocator::heap_stats();
+    let frame_kb = 4; // 4 KiB frames
+    format!(
+        "MemTotal:       {:>8} kB\n\
+         MemFree:        {:>8} kB\n\
+         FramesTotal:    {:>8}\n\
+         FramesFree:     {:>8}\n\
+         HeapTotal:      {:>8} kB\n\
+         HeapUsed:       {:>8} kB\n\
+         HeapFree:       {:>8} kB\n",
+        frames.total * frame_kb,
+        frames.free * frame_kb,
+        frames.total,
+        frames.free,
+        heap.total / 1024,
+        heap.used / 1024,
+        heap.free / 1024,
+    )
+}
+
+/// Render `/proc/uptime` from timer ticks (100 Hz).
+fn render_uptime(ticks: u64) -> String {
+    format!("{}.{:02} 0.00\n", ticks / 100, ticks % 100)
+}
+
+/// Render `/proc/kmsg`, one record per line.
+fn render_kmsg(records: &[LogRecord]) -> String {
+    let mut s = String::new();
+    for record in records {
+        s.push_str(&format!("{}\n", record));
+    }
+    s
+}
+
+fn render_status(p: &Process) -> String {
+    let state = match p.state {
+        ProcessState::Creating | ProcessState::Ready => "R (ready)",
+        ProcessState::Running => "R (running)",
+        ProcessState::Blocked(_) => "S (sleeping)",
+        ProcessState::Zombie(_) => "Z (zombie)",
+        ProcessState::Dead => "X (dead)",
+    };
+    let mut s = format!(
+        "Name:\t{}\nState:\t{}\nTgid:\t{}\nPid:\t{}\nPPid:\t{}\nUid:\t{}\nGid:\t{}\nThreads:\t{}\nCwd:\t{}\n",
+        p.name,
+        state,
+        p.tgid,
+        p.pid,
+        p.parent,
+        p.uid,
+        p.gid,
+        p.threads.len(),
+        p.cwd,
+    );
+    if let Some(mem) = &p.linux_memory {
+        let mapped: u64 = mem.vma_list.iter().map(|v| v.end - v.start).sum();
+        let heap = mem.brk_current.saturating_sub(mem.brk_start);
+        s.push_str(&format!(
+            "VmSize:\t{:>8} kB\nVmData:\t{:>8} kB\n",
+            (mapped + heap) / 1024,
+            heap / 1024
+        ));
+    }
+    s
+}
+
+/// Render `/proc/<pid>/maps` lines, sorted by address. The brk heap is
+/// listed as `[heap]` when non-empty.
+fn render_maps(vmas: &[Vma], brk_start: u64, brk_current: u64) -> String {
+    let mut regions: Vec<(u64, u64, u32, u32, &str)> = vmas
+        .iter()
+        .map(|v| (v.start, v.end, v.prot, v.flags, ""))
+        .collect();
+    if brk_current > brk_start {
+        // PROT_READ | PROT_WRITE, MAP_PRIVATE
+        regions.push((brk_start, brk_current, 0x3, 0x02, "[heap]"));
+    }
+    regions.sort_by_key(|r| r.0);
+
+    let mut s = String::new();
+    for (start, end, prot, flags, label) in regions {
+        let perms = [
+            if prot & 0x1 != 0 { 'r' } else { '-' },
+            if prot & 0x2 != 0 { 'w' } else { '-' },
+            if prot & 0x4 != 0 { 'x' } else { '-' },
+            if flags & 0x01 != 0 { 's' } else { 'p' },
+        ];
+        let perms: String = perms.iter().collect();
+        if label.is_empty() {
+            s.push_str(&format!(
+                "{:012x}-{:012x} {} 00000000 00:00 0\n",
+                start, end, perms
+            ));
+        } else {
+            s.push_str(&format!(
+                "{:012x}-{:012x} {} 00000000 00:00 0 {}\n",
+                start, end, perms, label
+            ));
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::table::VmaBacking;
+
+    #[test]
+    fn test_strip_mount() {
+        assert_eq!(strip_mount("/proc", "/proc"), Some(""));
+        assert_eq!(strip_mount("/proc", "/proc/"), Some(""));
+        assert_eq!(strip_mount("/proc", "/proc/meminfo"), Some("meminfo"));
+        assert_eq!(strip_mount("/proc", "/proc/12/maps"), Some("12/maps"));
+        assert_eq!(strip_mount("/proc", "/procfoo"), None);
+        assert_eq!(strip_mount("/proc", "/etc/hosts"), None);
+    }
+
+    #[test]
+    fn test_lookup_static_entries() {
+        assert_eq!(lookup(""), Some(ProcNode::Root));
+        assert_eq!(lookup("meminfo"), Some(ProcNode::MemInfo));
+        assert_eq!(lookup("uptime"), Some(ProcNode::Uptime));
+        assert_eq!(lookup("kmsg"), Some(ProcNode::Kmsg));
+        assert_eq!(lookup("net"), Some(ProcNode::NetDir));
+        assert_eq!(lookup("net/capture.pcap"), Some(ProcNode::NetCapture));
+        assert_eq!(lookup("net/dev"), None);
+        assert_eq!(lookup("meminfo/x"), None);
+        assert_eq!(lookup("cpuinfo"), None);
+    }
+
+    #[test]
+    fn test_render_uptime() {
+        assert_eq!(render_uptime(12345), "123.45 0.00\n");
+    }
+
+    #[test]
+    fn test_render_maps() {
+        let vmas = [
+            Vma {
+                start: 0x7f00_0000_0000,
+                end: 0x7f00_0000_2000,
+                prot: 0x1 | 0x4,
+                flags: 0x02 | 0x20,
+                backing: VmaBacking::Anonymous,
+            },
+            Vma {
+                start: 0x7e00_0000_0000,
+                end: 0x7e00_0000_1000,
+                prot: 0x3,
+                flags: 0x01,
+                backing: VmaBacking::Anonymous,
+            },
+        ];
+        let maps = render_maps(&vmas, 0x40_0000, 0x40_2000);
+        let lines: Vec<&str> = maps.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "000000400000-000000402000 rw-p 00000000 00:00 0 [heap]",
+                "7e0000000000-7e0000001000 rw-s 00000000 00:00 0",
+                "7f0000000000-7f0000002000 r-xp 00000000 00:00 0",
+            ]
+        );
+    }
+}
@@ -14,7 +14,9 @@ pub use tracking_protection::*;
 
 use alloc::string::{String, ToString};
 
-use crate::navigation::Navigator;
+use kpio_extensions::api::tabs::TabsApi;
+
+use crate::navigation::{self, CertificateInterstitial, Navigator, Url};
 use crate::tabs::TabManager;
 use crate::BrowserConfig;
 
@@ -38,6 +40,10 @@ pub struct Browser {
     pub tracking_protection: TrackingProtection,
     /// Private browsing manager.
     pub private_browsing: PrivateBrowsingManager,
+    /// `chrome.tabs` API over this browser's tabs.
+    extension_tabs: TabsApi,
+    /// Certificate error shown in place of the current page.
+    certificate_error: Option<CertificateInterstitial>,
 }
 
 /// Browser state.
@@ -64,6 +70,10 @@ impl Browser {
         let mut tabs = TabManager::new();
         tabs.new_tab();
 
+        // Extensions only hear about tabs opened from here on.
+        let extension_tabs = TabsApi::new();
+        extension_tabs.sync(&tabs);
+
         Self {
             config,
             tabs,
@@ -74,6 +84,8 @@ impl Browser {
             tab_suspension: TabSuspensionManager::new(),
             tracking_protection: TrackingProtection::new(),
             private_browsing: PrivateBrowsingManager::new(),
+            extension_tabs,
+            certificate_error: None,
         }
     }
 
@@ -84,13 +96,10 @@ impl Browser {
         // Parse URL
         let parsed_url = self.navigator.parse_url(url)?;
 
-        // Get active tab
-        if let Some(tab) = self.tabs.get_active_mut() {
-            tab.load_url(&parsed_url)?;
-        }
+        self.load(&parsed_url)?;
 
         self.navigator.push_history(parsed_url);
-        self.state = BrowserState::Ready;
+        self.sync_extension_tabs();
 
         Ok(())
     }
@@ -98,30 +107,67 @@ impl Browser {
     /// Go back in history.
     pub fn back(&mut self) -> Result<(), BrowserError> {
         if let Some(url) = self.navigator.go_back() {
-            if let Some(tab) = self.tabs.get_active_mut() {
-                tab.load_url(&url)?;
-            }
+            self.load(&url)?;
         }
+        self.sync_extension_tabs();
         Ok(())
     }
 
     /// Go forward in history.
     pub fn forward(&mut self) -> Result<(), BrowserError> {
         if let Some(url) = self.navigator.go_forward() {
-            if let Some(tab) = self.tabs.get_active_mut() {
-                tab.load_url(&url)?;
-            }
+            self.load(&url)?;
         }
+        self.sync_extension_tabs();
         Ok(())
     }
 
     /// Reload the current page.
     pub fn reload(&mut self) -> Result<(), BrowserError> {
         if let Some(url) = self.navigator.current_url() {
-            if let Some(tab) = self.tabs.get_active_mut() {
-                tab.load_url(&url)?;
+            self.load(&url)?;
+        }
+        self.sync_extension_tabs();
+        Ok(())
+    }
+
+    /// Load a URL in the active tab, or the certificate error page if
+    /// its certificate fails verification.
+    fn load(&mut self, url: &Url) -> Result<(), BrowserError> {
+        self.certificate_error = navigation::check_certificate(url)?;
+
+        if let Some(tab) = self.tabs.get_active_mut() {
+            match &self.certificate_error {
+                Some(error) => tab.load_html(&error.html(), &url.href())?,
+                None => tab.load_url(url)?,
             }
         }
+
+        self.state = if self.certificate_error.is_some() {
+            BrowserState::Error
+        } else {
+            BrowserState::Ready
+        };
+        Ok(())
+    }
+
+    /// Get the certificate error shown in place of the current page.
+    pub fn certificate_error(&self) -> Option<&CertificateInterstitial> {
+        self.certificate_error.as_ref()
+    }
+
+    /// Proceed to the site behind the certificate error page.
+    ///
+    /// The rejected certificate is remembered for the site, so later
+    /// visits load without the error page while it presents the same
+    /// certificate.
+    pub fn proceed_past_certificate_error(&mut self) -> Result<(), BrowserError> {
+        let Some(error) = self.certificate_error.take() else {
+            return Ok(());
+        };
+        error.proceed();
+        self.load(&error.url)?;
+        self.sync_extension_tabs();
         Ok(())
     }
 
@@ -137,6 +183,7 @@ impl Browser {
     pub fn new_tab(&mut self) -> usize {
         let idx = self.tabs.new_tab();
         self.active_tab = idx;
+        self.sync_extension_tabs();
         idx
     }
 
@@ -145,6 +192,7 @@ impl Browser {
         let session_id = self.private_browsing.create_session(timestamp);
         let tab_idx = self.tabs.new_tab();
         self.active_tab = tab_idx;
+        self.sync_extension_tabs();
         (tab_idx, session_id)
     }
 
@@ -154,6 +202,7 @@ impl Browser {
         if self.active_tab >= self.tabs.count() && self.tabs.count() > 0 {
             self.active_tab = self.tabs.count() - 1;
         }
+        self.sync_extension_tabs();
         Ok(())
     }
 
@@ -162,12 +211,31 @@ impl Browser {
         if index < self.tabs.count() {
             self.active_tab = index;
             self.tabs.set_active(index);
+            self.sync_extension_tabs();
             Ok(())
         } else {
             Err(BrowserError::InvalidTabIndex(index))
         }
     }
 
+    /// Run a `chrome.tabs` call against this browser's tabs.
+    ///
+    /// Tab changes made by the call are reported to extension listeners
+    /// and reflected in [`Browser::active_tab`].
+    pub fn with_extension_tabs<R>(
+        &mut self,
+        call: impl FnOnce(&TabsApi, &mut TabManager) -> R,
+    ) -> R {
+        let result = call(&self.extension_tabs, &mut self.tabs);
+        self.active_tab = self.tabs.active_index();
+        result
+    }
+
+    /// Report tab changes made by the browser to extension listeners.
+    fn sync_extension_tabs(&self) {
+        self.extension_tabs.sync(&self.tabs);
+    }
+
     /// Get current URL.
     pub fn current_url(&self) -> Option<String> {
         self.navigator.current_url().map(|u| u.to_string())
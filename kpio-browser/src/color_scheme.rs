@@ -0,0 +1,145 @@
+//! Color scheme preference.
+//!
+//! The user chooses light, dark, or system (follow the OS theme). The
+//! resolved scheme is what pages see through `prefers-color-scheme` media
+//! queries and what form controls use when a page's `color-scheme`
+//! supports it. Tabs restyle their documents when it changes.
+
+use kpio_css::stylesheet::ColorScheme;
+use spin::RwLock;
+
+use crate::ui::Theme;
+
+/// Browser-wide color scheme state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorSchemeSettings {
+    /// User preference from the appearance settings.
+    preference: Theme,
+    /// Scheme reported by the system theme.
+    system: ColorScheme,
+}
+
+impl ColorSchemeSettings {
+    /// Create settings that follow a light system theme.
+    pub const fn new() -> Self {
+        Self {
+            preference: Theme::System,
+            system: ColorScheme::Light,
+        }
+    }
+
+    /// Get the user preference.
+    pub fn preference(&self) -> Theme {
+        self.preference
+    }
+
+    /// Set the user preference.
+    pub fn set_preference(&mut self, preference: Theme) {
+        self.preference = preference;
+    }
+
+    /// Get the system theme's scheme.
+    pub fn system(&self) -> ColorScheme {
+        self.system
+    }
+
+    /// Record a system theme change.
+    pub fn set_system(&mut self, scheme: ColorScheme) {
+        self.system = scheme;
+    }
+
+    /// The scheme pages should be styled for.
+    pub fn effective(&self) -> ColorScheme {
+        match self.preference {
+            Theme::Light => ColorScheme::Light,
+            Theme::Dark => ColorScheme::Dark,
+            Theme::System => self.system,
+        }
+    }
+}
+
+impl Default for ColorSchemeSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global color scheme state.
+pub static COLOR_SCHEME: RwLock<ColorSchemeSettings> = RwLock::new(ColorSchemeSettings::new());
+
+/// The scheme pages should currently be styled for.
+pub fn preferred_color_scheme() -> ColorScheme {
+    COLOR_SCHEME.read().effective()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+
+    #[test]
+    fn test_effective_scheme() {
+        let mut settings = ColorSchemeSettings::new();
+        assert_eq!(settings.effective(), ColorScheme::Light);
+
+        settings.set_system(ColorScheme::Dark);
+        assert_eq!(settings.effective(), ColorScheme::Dark);
+
+        settings.set_preference(Theme::Light);
+        assert_eq!(settings.effective(), ColorScheme::Light);
+
+        settings.set_preference(Theme::Dark);
+        settings.set_system(ColorScheme::Light);
+        assert_eq!(settings.effective(), ColorScheme::Dark);
+    }
+
+    #[test]
+    fn test_document_restyles_on_scheme_change() {
+        struct NoImports;
+        impl kpio_css::StylesheetLoader for NoImports {
+            fn load(&self, _url: &str) -> Option<alloc::string::String> {
+                None
+            }
+        }
+
+        let mut doc = Document::from_html(
+            "<html><body><p>Hi</p><input></body></html>",
+            "http://example.com/",
+        );
+        assert!(doc.load_stylesheet(
+            "body { background-color: white; }
+             @media (prefers-color-scheme: dark) {
+                 body { background-color: black; }
+             }",
+            &NoImports,
+        ));
+        doc.compute_styles();
+
+        let body = |doc: &Document| doc.elements_by_tag_name("body")[0].clone();
+        let input = |doc: &Document| doc.elements_by_tag_name("input")[0].clone();
+        assert_eq!(body(&doc).borrow().computed_styles.background_color.r, 255);
+
+        assert!(doc.set_color_scheme(ColorScheme::Dark));
+        assert!(!doc.set_color_scheme(ColorScheme::Dark));
+        assert_eq!(body(&doc).borrow().computed_styles.background_color.r, 0);
+        // Pages that don't opt in keep light form controls
+        assert_eq!(
+            input(&doc).borrow().computed_styles.color_scheme,
+            ColorScheme::Light
+        );
+
+        assert!(doc.load_stylesheet(":root { color-scheme: light dark; }", &NoImports));
+        doc.compute_styles();
+        assert_eq!(
+            input(&doc).borrow().computed_styles.color_scheme,
+            ColorScheme::Dark
+        );
+
+        assert!(doc.set_color_scheme(ColorScheme::Light));
+        assert_eq!(body(&doc).borrow().computed_styles.background_color.r, 255);
+        assert_eq!(
+            input(&doc).borrow().computed_styles.color_scheme,
+            ColorScheme::Light
+        );
+    }
+}
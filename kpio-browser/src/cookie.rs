@@ -0,0 +1,235 @@
+//! HTTP cookies.
+//!
+//! The browser keeps one cookie jar shared by every tab. Responses store
+//! cookies from their `Set-Cookie` headers and requests send the ones
+//! whose domain, path and `Secure` flag match the request URL. There is
+//! no clock, so `Expires` is ignored and `Max-Age` only matters for
+//! deleting a cookie.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use spin::RwLock;
+
+use crate::navigation::Url;
+
+/// A stored cookie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    /// Cookie name.
+    pub name: String,
+    /// Cookie value.
+    pub value: String,
+    /// Domain the cookie belongs to.
+    pub domain: String,
+    /// Only send to `domain` itself, not its subdomains.
+    pub host_only: bool,
+    /// Path prefix the cookie applies to.
+    pub path: String,
+    /// Only send over HTTPS.
+    pub secure: bool,
+    /// Hide from scripts.
+    pub http_only: bool,
+}
+
+impl Cookie {
+    /// Check if the cookie should be sent with a request to `url`.
+    pub fn matches(&self, url: &Url) -> bool {
+        let domain_ok = if self.host_only {
+            url.host == self.domain
+        } else {
+            domain_matches(&url.host, &self.domain)
+        };
+        domain_ok && path_matches(&url.path, &self.path) && (!self.secure || url.scheme == "https")
+    }
+}
+
+/// Cookie storage.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// Create an empty jar.
+    pub const fn new() -> Self {
+        Self {
+            cookies: Vec::new(),
+        }
+    }
+
+    /// Store a cookie from a `Set-Cookie` header received from `url`.
+    ///
+    /// Returns `false` if the header was malformed or not allowed for
+    /// `url`.
+    pub fn set_cookie(&mut self, url: &Url, header: &str) -> bool {
+        let mut parts = header.split(';');
+        let Some((name, value)) = parts.next().and_then(|pair| pair.split_once('=')) else {
+            return false;
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return false;
+        }
+
+        let mut cookie = Cookie {
+            name: name.into(),
+            value: value.trim().into(),
+            domain: url.host.clone(),
+            host_only: true,
+            path: default_path(&url.path),
+            secure: false,
+            http_only: false,
+        };
+        let mut expired = false;
+
+        for attribute in parts {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            if key.eq_ignore_ascii_case("domain") {
+                let domain = value.trim_start_matches('.').to_lowercase();
+                if !domain.is_empty() {
+                    if !domain_matches(&url.host, &domain) {
+                        return false;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+            } else if key.eq_ignore_ascii_case("path") {
+                if value.starts_with('/') {
+                    cookie.path = value.into();
+                }
+            } else if key.eq_ignore_ascii_case("max-age") {
+                if let Ok(seconds) = value.parse::<i64>() {
+                    expired = seconds <= 0;
+                }
+            } else if key.eq_ignore_ascii_case("secure") {
+                cookie.secure = true;
+            } else if key.eq_ignore_ascii_case("httponly") {
+                cookie.http_only = true;
+            }
+        }
+
+        if cookie.secure && url.scheme != "https" {
+            return false;
+        }
+
+        self.cookies.retain(|c| {
+            !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path)
+        });
+        if !expired {
+            self.cookies.push(cookie);
+        }
+        true
+    }
+
+    /// Build the `Cookie` header for a request to `url`.
+    ///
+    /// Cookies with longer paths come first.
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let mut matching: Vec<&Cookie> = self.cookies.iter().filter(|c| c.matches(url)).collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|c| core::cmp::Reverse(c.path.len()));
+
+        let pairs: Vec<String> = matching
+            .iter()
+            .map(|c| alloc::format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    /// Get all stored cookies.
+    pub fn cookies(&self) -> &[Cookie] {
+        &self.cookies
+    }
+
+    /// Remove all cookies.
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+}
+
+/// Check if `host` is `domain` or one of its subdomains.
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Check if a request path falls under a cookie path.
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    match request_path.strip_prefix(cookie_path) {
+        Some(rest) => cookie_path.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// The directory of a request path, used when `Path` is not given.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => String::from("/"),
+        Some(end) => path[..end].into(),
+    }
+}
+
+/// Global cookie jar.
+pub static COOKIE_JAR: RwLock<CookieJar> = RwLock::new(CookieJar::new());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::Navigator;
+
+    fn url(s: &str) -> Url {
+        Navigator::new().parse_url(s).unwrap()
+    }
+
+    #[test]
+    fn test_cookie_matching() {
+        let mut jar = CookieJar::new();
+        let page = url("https://www.example.com/app/page");
+
+        assert!(jar.set_cookie(&page, "a=1"));
+        assert!(jar.set_cookie(&page, "b=2; Path=/; Domain=example.com; Secure"));
+        assert!(!jar.set_cookie(&page, "c=3; Domain=other.com"));
+        assert!(!jar.set_cookie(&url("http://www.example.com/"), "d=4; Secure"));
+
+        assert_eq!(jar.cookie_header(&page).as_deref(), Some("a=1; b=2"));
+        assert_eq!(
+            jar.cookie_header(&url("https://api.example.com/"))
+                .as_deref(),
+            Some("b=2")
+        );
+        assert_eq!(
+            jar.cookie_header(&url("http://www.example.com/app/x"))
+                .as_deref(),
+            Some("a=1")
+        );
+        // "/app" does not cover "/apple"
+        assert_eq!(
+            jar.cookie_header(&url("https://www.example.com/apple"))
+                .as_deref(),
+            Some("b=2")
+        );
+    }
+
+    #[test]
+    fn test_cookie_replace_and_delete() {
+        let mut jar = CookieJar::new();
+        let page = url("http://example.com/");
+
+        jar.set_cookie(&page, "id=old");
+        jar.set_cookie(&page, "id=new; HttpOnly");
+        assert_eq!(jar.cookies().len(), 1);
+        assert!(jar.cookies()[0].http_only);
+        assert_eq!(jar.cookie_header(&page).as_deref(), Some("id=new"));
+
+        jar.set_cookie(&page, "id=; Max-Age=0");
+        assert_eq!(jar.cookie_header(&page), None);
+    }
+}
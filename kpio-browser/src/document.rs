@@ -7,7 +7,15 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
-use kpio_css::Stylesheet;
+use kpio_css::cascade::CascadedValues;
+use kpio_css::selector::{PseudoClass, Selector, SelectorComponent};
+use kpio_css::stylesheet::{ColorScheme, StyleRule, StylesheetOrigin};
+use kpio_css::values::ColorSchemes;
+use kpio_css::{
+    CssParser, CssValue, ImportResolver, MediaContext, PropertyId, Stylesheet, StylesheetLoader,
+};
+
+use crate::color_scheme::preferred_color_scheme;
 
 /// Document object.
 pub struct Document {
@@ -19,6 +27,8 @@ pub struct Document {
     root: Option<Rc<RefCell<DocumentNode>>>,
     /// All stylesheets.
     stylesheets: Vec<Stylesheet>,
+    /// Context `@media` rules are evaluated against.
+    media_context: MediaContext,
     /// Raw HTML content.
     html_content: String,
 }
@@ -81,6 +91,10 @@ pub struct ComputedStyles {
     pub font_size: f32,
     pub font_family: String,
     pub font_weight: u16,
+    /// Declared `color-scheme` (inherited).
+    pub color_schemes: ColorSchemes,
+    /// Scheme form controls are drawn in.
+    pub color_scheme: ColorScheme,
 }
 
 /// Display value.
@@ -126,6 +140,10 @@ impl Document {
             url: url.into(),
             root: None,
             stylesheets: Vec::new(),
+            media_context: MediaContext {
+                color_scheme: preferred_color_scheme(),
+                ..MediaContext::default()
+            },
             html_content: String::new(),
         }
     }
@@ -298,25 +316,187 @@ impl Document {
         String::new()
     }
 
+    /// Parse a stylesheet, inline its `@import` rules, and add it to the document.
+    ///
+    /// Imports are resolved relative to the document URL and fetched through
+    /// `loader`. Returns `false` if the stylesheet could not be parsed.
+    pub fn load_stylesheet<L: StylesheetLoader + ?Sized>(&mut self, css: &str, loader: &L) -> bool {
+        let Ok(sheet) = CssParser::new(css).parse_stylesheet() else {
+            return false;
+        };
+
+        // Imports that fail, cycle, or exceed the depth limit are skipped
+        let sheet = ImportResolver::new(loader).resolve(sheet, &self.url);
+
+        self.stylesheets.push(sheet);
+        true
+    }
+
+    /// Get all stylesheets, in cascade order.
+    pub fn stylesheets(&self) -> &[Stylesheet] {
+        &self.stylesheets
+    }
+
+    /// Get the context `@media` rules are evaluated against.
+    pub fn media_context(&self) -> &MediaContext {
+        &self.media_context
+    }
+
+    /// Set the scheme `prefers-color-scheme` queries match.
+    ///
+    /// Restyles the document and returns `true` if the scheme changed.
+    pub fn set_color_scheme(&mut self, scheme: ColorScheme) -> bool {
+        if self.media_context.color_scheme == scheme {
+            return false;
+        }
+        self.media_context.color_scheme = scheme;
+        self.compute_styles();
+        true
+    }
+
     /// Apply styles to nodes.
+    ///
+    /// Runs the cascade over every stylesheet, with `@media` rules
+    /// evaluated against the current media context. Call again after the
+    /// context changes to restyle.
     pub fn compute_styles(&mut self) {
+        let mut rules = Vec::new();
+        for sheet in &self.stylesheets {
+            for rule in sheet.active_style_rules(&self.media_context) {
+                rules.push((sheet.origin, rule));
+            }
+        }
+
         if let Some(root) = self.root.clone() {
-            self.compute_styles_recursive(&root);
+            self.compute_styles_recursive(&root, None, &rules);
         }
     }
 
-    fn compute_styles_recursive(&self, node: &Rc<RefCell<DocumentNode>>) {
-        {
+    fn compute_styles_recursive(
+        &self,
+        node: &Rc<RefCell<DocumentNode>>,
+        parent: Option<&ComputedStyles>,
+        rules: &[(StylesheetOrigin, StyleRule)],
+    ) {
+        let styles = {
             let mut node_ref = node.borrow_mut();
 
+            // Start over so a restyle drops values from the previous pass
+            node_ref.computed_styles = ComputedStyles::default();
+            if let Some(parent) = parent {
+                node_ref.computed_styles.color = parent.color;
+                node_ref.computed_styles.color_schemes = parent.color_schemes;
+            }
+
             // Apply default styles based on tag
             self.apply_default_styles(&mut node_ref);
-        }
+
+            let cascaded = if node_ref.kind == NodeKind::Element {
+                Self::cascade(&node_ref, rules)
+            } else {
+                CascadedValues::new()
+            };
+            if let Some(decl) = cascaded.get(PropertyId::ColorScheme) {
+                if let CssValue::Keyword(ref k) = decl.value {
+                    if let Some(schemes) = ColorSchemes::parse(k) {
+                        node_ref.computed_styles.color_schemes = schemes;
+                    }
+                }
+            }
+            node_ref.computed_styles.color_scheme = node_ref
+                .computed_styles
+                .color_schemes
+                .used(self.media_context.color_scheme);
+
+            Self::apply_form_control_styles(&mut node_ref);
+            Self::apply_cascaded(&mut node_ref.computed_styles, &cascaded);
+            node_ref.computed_styles.clone()
+        };
 
         // Recurse
         let children = node.borrow().children.clone();
         for child in children {
-            self.compute_styles_recursive(&child);
+            self.compute_styles_recursive(&child, Some(&styles), rules);
+        }
+    }
+
+    /// Collect the declarations of every rule matching `node`.
+    fn cascade(node: &DocumentNode, rules: &[(StylesheetOrigin, StyleRule)]) -> CascadedValues {
+        let mut cascaded = CascadedValues::new();
+        for (order, (origin, rule)) in rules.iter().enumerate() {
+            let specificity = rule
+                .selectors
+                .selectors
+                .iter()
+                .filter(|s| Self::selector_matches(node, s))
+                .map(|s| s.specificity())
+                .max();
+            if let Some(specificity) = specificity {
+                cascaded.apply(&rule.declarations, specificity, *origin, order as u32);
+            }
+        }
+        cascaded
+    }
+
+    /// Match a compound selector (no combinators) against `node`.
+    fn selector_matches(node: &DocumentNode, selector: &Selector) -> bool {
+        !selector.is_empty()
+            && selector.components.iter().all(|component| match component {
+                SelectorComponent::Universal => true,
+                SelectorComponent::Type(name) => node
+                    .tag_name
+                    .as_deref()
+                    .is_some_and(|t| t.eq_ignore_ascii_case(name.as_str())),
+                SelectorComponent::Class(class) => node.classes.iter().any(|c| c == class),
+                SelectorComponent::Id(id) => node.id.as_deref() == Some(id.as_str()),
+                SelectorComponent::PseudoClass(PseudoClass::Root) => node
+                    .parent
+                    .as_ref()
+                    .is_some_and(|p| p.borrow().kind == NodeKind::Document),
+                _ => false,
+            })
+    }
+
+    /// Give form controls colors for their used color scheme.
+    fn apply_form_control_styles(node: &mut DocumentNode) {
+        if !matches!(
+            node.tag_name.as_deref(),
+            Some("input") | Some("button") | Some("select") | Some("textarea")
+        ) {
+            return;
+        }
+        let styles = &mut node.computed_styles;
+        if styles.color_scheme.is_dark() {
+            styles.color = Color::WHITE;
+            styles.background_color = Color::rgb(59, 59, 59);
+        } else {
+            styles.color = Color::BLACK;
+            styles.background_color = Color::WHITE;
+        }
+    }
+
+    /// Apply the cascaded properties the document model tracks.
+    fn apply_cascaded(styles: &mut ComputedStyles, cascaded: &CascadedValues) {
+        for decl in cascaded.iter() {
+            match (decl.property, &decl.value) {
+                (PropertyId::Color, CssValue::Color(c)) => {
+                    styles.color = Color::rgba(c.r, c.g, c.b, c.a);
+                }
+                (PropertyId::BackgroundColor, CssValue::Color(c)) => {
+                    styles.background_color = Color::rgba(c.r, c.g, c.b, c.a);
+                }
+                (PropertyId::Display, CssValue::Keyword(k)) => {
+                    styles.display = match k.as_str() {
+                        "none" => DisplayValue::None,
+                        "inline" => DisplayValue::Inline,
+                        "inline-block" => DisplayValue::InlineBlock,
+                        "flex" => DisplayValue::Flex,
+                        "grid" => DisplayValue::Grid,
+                        _ => DisplayValue::Block,
+                    };
+                }
+                _ => {}
+            }
         }
     }
 
@@ -363,6 +543,11 @@ impl Document {
         &self.url
     }
 
+    /// Get the HTML source the document was loaded from.
+    pub fn html(&self) -> &str {
+        &self.html_content
+    }
+
     /// Get root node.
     pub fn root(&self) -> Option<&Rc<RefCell<DocumentNode>>> {
         self.root.as_ref()
@@ -375,6 +560,33 @@ impl Document {
             .and_then(|root| Self::find_by_id_recursive(&root.borrow(), id))
     }
 
+    /// Get all elements with the given tag name, in document order.
+    pub fn elements_by_tag_name(&self, tag: &str) -> Vec<Rc<RefCell<DocumentNode>>> {
+        let mut found = Vec::new();
+        if let Some(root) = &self.root {
+            Self::find_by_tag_recursive(&root.borrow(), tag, &mut found);
+        }
+        found
+    }
+
+    fn find_by_tag_recursive(
+        node: &DocumentNode,
+        tag: &str,
+        found: &mut Vec<Rc<RefCell<DocumentNode>>>,
+    ) {
+        for child in &node.children {
+            if child
+                .borrow()
+                .tag_name
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(tag))
+            {
+                found.push(child.clone());
+            }
+            Self::find_by_tag_recursive(&child.borrow(), tag, found);
+        }
+    }
+
     fn find_by_id_recursive(node: &DocumentNode, id: &str) -> Option<Rc<RefCell<DocumentNode>>> {
         for child in &node.children {
             if child.borrow().id.as_deref() == Some(id) {
@@ -0,0 +1,1287 @@
+//! Fetch API.
+//!
+//! [`FetchClient`] runs the fetch algorithm for one document: it resolves
+//! the request URL against the document, checks the `connect-src` CSP
+//! directive, applies the same-origin policy and CORS (including
+//! preflight requests), attaches and stores cookies, and follows
+//! redirects. [`install`] exposes a client to page scripts as the global
+//! `fetch()` function, which returns a Promise of a `Response` object.
+//!
+//! The network bridge is synchronous, so the promise returned by `fetch()`
+//! has already settled by the time the call returns; its reactions still
+//! run as microtasks like any other promise.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use kpio_js::interpreter::Interpreter;
+use kpio_js::object::{
+    BoundFunction, Callable, IntrinsicFunction, JsObject, PropertyDescriptor, PropertyKey,
+};
+use kpio_js::{Engine, JsError, JsResult, Value};
+use spin::Mutex;
+
+use crate::cookie::COOKIE_JAR;
+use crate::csp::{CspContext, CspDirectiveType};
+use crate::navigation::{resolve_url, Navigator, Url};
+use crate::network_bridge::{network_bridge, HttpRequest, HttpResponse, HttpTransport, NetError};
+
+/// Maximum number of redirects followed by one fetch.
+pub const MAX_REDIRECTS: usize = 20;
+
+/// Whether a request may leave the document's origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RequestMode {
+    /// Cross-origin requests must pass CORS checks.
+    #[default]
+    Cors,
+    /// Cross-origin requests are limited to simple methods and get an
+    /// opaque response.
+    NoCors,
+    /// Cross-origin requests fail.
+    SameOrigin,
+}
+
+impl RequestMode {
+    /// Parse the `mode` member of a request init.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "cors" => Some(Self::Cors),
+            "no-cors" => Some(Self::NoCors),
+            "same-origin" => Some(Self::SameOrigin),
+            _ => None,
+        }
+    }
+}
+
+/// When cookies are sent with a request and stored from its response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CredentialsMode {
+    /// Never.
+    Omit,
+    /// Only for same-origin requests.
+    #[default]
+    SameOrigin,
+    /// Always.
+    Include,
+}
+
+impl CredentialsMode {
+    /// Parse the `credentials` member of a request init.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "omit" => Some(Self::Omit),
+            "same-origin" => Some(Self::SameOrigin),
+            "include" => Some(Self::Include),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with a redirect response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedirectMode {
+    /// Follow it.
+    #[default]
+    Follow,
+    /// Fail the fetch.
+    Error,
+    /// Return it as an opaque redirect.
+    Manual,
+}
+
+impl RedirectMode {
+    /// Parse the `redirect` member of a request init.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "follow" => Some(Self::Follow),
+            "error" => Some(Self::Error),
+            "manual" => Some(Self::Manual),
+            _ => None,
+        }
+    }
+}
+
+/// A request made by a page.
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    /// Request method.
+    pub method: String,
+    /// URL, possibly relative to the document.
+    pub url: String,
+    /// Request headers.
+    pub headers: Vec<(String, String)>,
+    /// Request body.
+    pub body: Vec<u8>,
+    /// Request mode.
+    pub mode: RequestMode,
+    /// Credentials mode.
+    pub credentials: CredentialsMode,
+    /// Redirect mode.
+    pub redirect: RedirectMode,
+}
+
+impl FetchRequest {
+    /// Create a GET request with default modes.
+    pub fn new(url: &str) -> Self {
+        Self {
+            method: String::from("GET"),
+            url: url.into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            mode: RequestMode::default(),
+            credentials: CredentialsMode::default(),
+            redirect: RedirectMode::default(),
+        }
+    }
+}
+
+/// How much of a response the page may see.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseType {
+    /// Same-origin response; everything but `Set-Cookie` is visible.
+    Basic,
+    /// Cross-origin response that passed CORS; only safelisted and exposed
+    /// headers are visible.
+    Cors,
+    /// Cross-origin `no-cors` response; nothing is visible.
+    Opaque,
+    /// Redirect returned in `manual` redirect mode; nothing is visible.
+    OpaqueRedirect,
+}
+
+impl ResponseType {
+    /// Name as reported by `Response.type`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Basic => "basic",
+            Self::Cors => "cors",
+            Self::Opaque => "opaque",
+            Self::OpaqueRedirect => "opaqueredirect",
+        }
+    }
+}
+
+/// A response as seen by the page.
+#[derive(Debug, Clone)]
+pub struct FetchResponse {
+    /// Response type.
+    pub response_type: ResponseType,
+    /// Final URL after redirects (empty for opaque responses).
+    pub url: String,
+    /// Whether any redirect was followed.
+    pub redirected: bool,
+    /// HTTP status code (0 for opaque responses).
+    pub status: u16,
+    /// Status text.
+    pub status_text: String,
+    /// Visible headers.
+    pub headers: Vec<(String, String)>,
+    /// Response body.
+    pub body: Vec<u8>,
+}
+
+impl FetchResponse {
+    /// Create a response with nothing visible.
+    fn opaque(response_type: ResponseType, url: String) -> Self {
+        Self {
+            response_type,
+            url,
+            redirected: false,
+            status: 0,
+            status_text: String::new(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Check for a 2xx status.
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Get header value.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// Fetch errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchError {
+    /// The URL could not be parsed or resolved.
+    InvalidUrl(String),
+    /// The URL scheme cannot be fetched.
+    UnsupportedScheme(String),
+    /// The method cannot be used in this request mode, or with a body.
+    InvalidMethod(String),
+    /// Content Security Policy blocked the URL.
+    BlockedByCsp(String),
+    /// A `same-origin` request went to another origin.
+    CrossOrigin(String),
+    /// The server did not allow the cross-origin request.
+    CorsRejected(String),
+    /// A redirect was returned in `error` redirect mode.
+    RedirectRejected,
+    /// More than [`MAX_REDIRECTS`] redirects.
+    TooManyRedirects,
+    /// The request failed at the network level.
+    Network(NetError),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(url) => write!(f, "invalid URL '{}'", url),
+            Self::UnsupportedScheme(scheme) => write!(f, "unsupported scheme '{}'", scheme),
+            Self::InvalidMethod(method) => write!(f, "method '{}' is not allowed", method),
+            Self::BlockedByCsp(url) => {
+                write!(f, "'{}' violates the connect-src directive", url)
+            }
+            Self::CrossOrigin(url) => {
+                write!(f, "'{}' is cross-origin in same-origin mode", url)
+            }
+            Self::CorsRejected(reason) => write!(f, "CORS check failed: {}", reason),
+            Self::RedirectRejected => write!(f, "redirect in error redirect mode"),
+            Self::TooManyRedirects => write!(f, "too many redirects"),
+            Self::Network(err) => write!(f, "network error: {:?}", err),
+        }
+    }
+}
+
+/// Runs fetches on behalf of one document.
+pub struct FetchClient {
+    /// Document URL, if it can be a base for relative URLs.
+    base: Option<Url>,
+    /// Document origin, `"null"` for opaque origins.
+    origin: String,
+    /// Document CSP.
+    csp: CspContext,
+}
+
+impl FetchClient {
+    /// Create a client for the document at `document_url`.
+    ///
+    /// Documents not loaded over HTTP get an opaque origin, so all of
+    /// their requests are cross-origin.
+    pub fn new(document_url: &str) -> Self {
+        let base = Navigator::new()
+            .parse_url(document_url)
+            .ok()
+            .filter(Url::is_http);
+        let origin = base
+            .as_ref()
+            .map_or_else(|| String::from("null"), Url::origin);
+        Self {
+            base,
+            origin,
+            csp: CspContext::new(document_url.into()),
+        }
+    }
+
+    /// Get the document origin.
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// Get the document CSP.
+    pub fn csp(&self) -> &CspContext {
+        &self.csp
+    }
+
+    /// Get the document CSP mutably, e.g. to add policies.
+    pub fn csp_mut(&mut self) -> &mut CspContext {
+        &mut self.csp
+    }
+
+    /// Fetch `request` through `transport`.
+    pub fn fetch(
+        &mut self,
+        request: FetchRequest,
+        transport: &dyn HttpTransport,
+    ) -> Result<FetchResponse, FetchError> {
+        let mut url = self.resolve(&request.url)?;
+        let mut method = normalize_method(&request.method);
+        if is_forbidden_method(&method) {
+            return Err(FetchError::InvalidMethod(method));
+        }
+        if !request.body.is_empty() && (method == "GET" || method == "HEAD") {
+            return Err(FetchError::InvalidMethod(method));
+        }
+        let mut headers: Vec<(String, String)> = request
+            .headers
+            .into_iter()
+            .filter(|(name, _)| !is_forbidden_header(name))
+            .collect();
+        let mut body = request.body;
+        // Set once a cross-origin hop redirects to yet another origin;
+        // from then on the request's origin is opaque.
+        let mut tainted = false;
+        let mut redirected = false;
+        let mut redirects = 0;
+
+        loop {
+            if !url.is_http() {
+                return Err(FetchError::UnsupportedScheme(url.scheme.clone()));
+            }
+            let href = url.href();
+            if !self.csp.allows(&CspDirectiveType::ConnectSrc, &href, None) {
+                return Err(FetchError::BlockedByCsp(href));
+            }
+
+            let same_origin = !tainted && url.origin() == self.origin;
+            let origin = if tainted {
+                "null"
+            } else {
+                self.origin.as_str()
+            };
+            let credentials = match request.credentials {
+                CredentialsMode::Omit => false,
+                CredentialsMode::SameOrigin => same_origin,
+                CredentialsMode::Include => true,
+            };
+
+            if !same_origin {
+                match request.mode {
+                    RequestMode::SameOrigin => return Err(FetchError::CrossOrigin(href)),
+                    RequestMode::NoCors if !is_simple_method(&method) => {
+                        return Err(FetchError::InvalidMethod(method));
+                    }
+                    RequestMode::NoCors => {}
+                    RequestMode::Cors => {
+                        if needs_preflight(&method, &headers) {
+                            preflight(&href, origin, &method, &headers, credentials, transport)?;
+                        }
+                    }
+                }
+            }
+
+            let mut http = HttpRequest::new(&method, &href);
+            http.headers = headers.clone();
+            if !same_origin || (method != "GET" && method != "HEAD") {
+                http.headers.push((String::from("Origin"), origin.into()));
+            }
+            if credentials {
+                if let Some(cookie) = COOKIE_JAR.read().cookie_header(&url) {
+                    http.headers.push((String::from("Cookie"), cookie));
+                }
+            }
+            http.body = body.clone();
+
+            let response = transport.send(&http).map_err(FetchError::Network)?;
+            if credentials {
+                let mut jar = COOKIE_JAR.write();
+                for (name, value) in &response.headers {
+                    if name.eq_ignore_ascii_case("set-cookie") {
+                        jar.set_cookie(&url, value);
+                    }
+                }
+            }
+
+            let location = match response.status {
+                301 | 302 | 303 | 307 | 308 => response.header("Location"),
+                _ => None,
+            };
+            let Some(location) = location else {
+                let response_type = if same_origin {
+                    ResponseType::Basic
+                } else if request.mode == RequestMode::Cors {
+                    check_cors(&response, origin, credentials)?;
+                    ResponseType::Cors
+                } else {
+                    return Ok(FetchResponse::opaque(ResponseType::Opaque, String::new()));
+                };
+                return Ok(filter_response(
+                    response,
+                    response_type,
+                    href,
+                    redirected,
+                    credentials,
+                ));
+            };
+
+            match request.redirect {
+                RedirectMode::Follow => {}
+                RedirectMode::Error => return Err(FetchError::RedirectRejected),
+                RedirectMode::Manual => {
+                    return Ok(FetchResponse::opaque(ResponseType::OpaqueRedirect, href));
+                }
+            }
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Err(FetchError::TooManyRedirects);
+            }
+            if !same_origin && request.mode == RequestMode::Cors {
+                check_cors(&response, origin, credentials)?;
+            }
+
+            let next = resolve_url(&url, location);
+            if !same_origin && next.origin() != url.origin() {
+                tainted = true;
+            }
+            let switch_to_get = match response.status {
+                303 => method != "GET" && method != "HEAD",
+                301 | 302 => method == "POST",
+                _ => false,
+            };
+            if switch_to_get {
+                method = String::from("GET");
+                body.clear();
+                headers.retain(|(name, _)| !is_request_body_header(name));
+            }
+            url = next;
+            redirected = true;
+        }
+    }
+
+    /// Resolve a request URL against the document.
+    fn resolve(&self, url: &str) -> Result<Url, FetchError> {
+        let url = url.trim();
+        if url.contains("://") {
+            return Navigator::new()
+                .parse_url(url)
+                .map_err(|_| FetchError::InvalidUrl(url.into()));
+        }
+        match &self.base {
+            Some(base) if !url.is_empty() => Ok(resolve_url(base, url)),
+            _ => Err(FetchError::InvalidUrl(url.into())),
+        }
+    }
+}
+
+/// Uppercase the standard methods, leaving others as given.
+fn normalize_method(method: &str) -> String {
+    const STANDARD: [&str; 6] = ["DELETE", "GET", "HEAD", "OPTIONS", "POST", "PUT"];
+    match STANDARD.iter().find(|m| m.eq_ignore_ascii_case(method)) {
+        Some(m) => String::from(*m),
+        None => method.into(),
+    }
+}
+
+fn is_forbidden_method(method: &str) -> bool {
+    ["CONNECT", "TRACE", "TRACK"]
+        .iter()
+        .any(|m| m.eq_ignore_ascii_case(method))
+}
+
+fn is_simple_method(method: &str) -> bool {
+    matches!(method, "GET" | "HEAD" | "POST")
+}
+
+/// Headers that scripts are not allowed to set.
+fn is_forbidden_header(name: &str) -> bool {
+    const FORBIDDEN: [&str; 20] = [
+        "accept-charset",
+        "accept-encoding",
+        "access-control-request-headers",
+        "access-control-request-method",
+        "connection",
+        "content-length",
+        "cookie",
+        "cookie2",
+        "date",
+        "dnt",
+        "expect",
+        "host",
+        "keep-alive",
+        "origin",
+        "referer",
+        "te",
+        "trailer",
+        "transfer-encoding",
+        "upgrade",
+        "via",
+    ];
+    let name = name.to_ascii_lowercase();
+    FORBIDDEN.contains(&name.as_str()) || name.starts_with("proxy-") || name.starts_with("sec-")
+}
+
+/// Headers that describe the body, dropped when a redirect turns the
+/// request into a GET.
+fn is_request_body_header(name: &str) -> bool {
+    [
+        "content-encoding",
+        "content-language",
+        "content-location",
+        "content-type",
+    ]
+    .iter()
+    .any(|h| h.eq_ignore_ascii_case(name))
+}
+
+/// Check if a request header can be sent cross-origin without preflight.
+fn is_safelisted_request_header(name: &str, value: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    match name.as_str() {
+        "accept" | "accept-language" | "content-language" => true,
+        "content-type" => {
+            let essence = value.split(';').next().unwrap_or("").trim();
+            [
+                "application/x-www-form-urlencoded",
+                "multipart/form-data",
+                "text/plain",
+            ]
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(essence))
+        }
+        _ => false,
+    }
+}
+
+/// Sorted, lowercased names of the headers that need preflight.
+fn unsafe_header_names(headers: &[(String, String)]) -> Vec<String> {
+    let mut names: Vec<String> = headers
+        .iter()
+        .filter(|(name, value)| !is_safelisted_request_header(name, value))
+        .map(|(name, _)| name.to_ascii_lowercase())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn needs_preflight(method: &str, headers: &[(String, String)]) -> bool {
+    !is_simple_method(method) || !unsafe_header_names(headers).is_empty()
+}
+
+/// Ask the server whether a cross-origin request may be made.
+fn preflight(
+    url: &str,
+    origin: &str,
+    method: &str,
+    headers: &[(String, String)],
+    credentials: bool,
+    transport: &dyn HttpTransport,
+) -> Result<(), FetchError> {
+    let names = unsafe_header_names(headers);
+    let mut request = HttpRequest::new("OPTIONS", url);
+    request.headers = vec![
+        (String::from("Origin"), origin.into()),
+        (String::from("Access-Control-Request-Method"), method.into()),
+    ];
+    if !names.is_empty() {
+        request.headers.push((
+            String::from("Access-Control-Request-Headers"),
+            names.join(","),
+        ));
+    }
+
+    let response = transport.send(&request).map_err(FetchError::Network)?;
+    if !(200..300).contains(&response.status) {
+        return Err(FetchError::CorsRejected(alloc::format!(
+            "preflight returned status {}",
+            response.status
+        )));
+    }
+    check_cors(&response, origin, credentials)?;
+
+    let methods = header_list(&response, "Access-Control-Allow-Methods");
+    let any_method = !credentials && methods.iter().any(|m| m == "*");
+    if !is_simple_method(method) && !any_method && !methods.iter().any(|m| m == method) {
+        return Err(FetchError::CorsRejected(alloc::format!(
+            "method {} is not allowed",
+            method
+        )));
+    }
+
+    let allowed = header_list(&response, "Access-Control-Allow-Headers");
+    let any_header = !credentials && allowed.iter().any(|h| h == "*");
+    for name in names {
+        if !any_header && !allowed.iter().any(|h| h.eq_ignore_ascii_case(&name)) {
+            return Err(FetchError::CorsRejected(alloc::format!(
+                "header {} is not allowed",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a cross-origin response allows `origin` to read it.
+fn check_cors(response: &HttpResponse, origin: &str, credentials: bool) -> Result<(), FetchError> {
+    let allow_origin = response
+        .header("Access-Control-Allow-Origin")
+        .map(str::trim)
+        .ok_or_else(|| FetchError::CorsRejected("no Access-Control-Allow-Origin header".into()))?;
+    if allow_origin == "*" && !credentials {
+        return Ok(());
+    }
+    if allow_origin != origin {
+        return Err(FetchError::CorsRejected(alloc::format!(
+            "origin {} is not allowed",
+            origin
+        )));
+    }
+    if credentials && response.header("Access-Control-Allow-Credentials") != Some("true") {
+        return Err(FetchError::CorsRejected(
+            "credentials are not allowed".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Split a comma-separated header into its items.
+fn header_list(response: &HttpResponse, name: &str) -> Vec<String> {
+    response
+        .header(name)
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Hide the parts of a response the page may not see.
+fn filter_response(
+    response: HttpResponse,
+    response_type: ResponseType,
+    url: String,
+    redirected: bool,
+    credentials: bool,
+) -> FetchResponse {
+    const SAFELISTED: [&str; 7] = [
+        "cache-control",
+        "content-language",
+        "content-length",
+        "content-type",
+        "expires",
+        "last-modified",
+        "pragma",
+    ];
+
+    let exposed = header_list(&response, "Access-Control-Expose-Headers");
+    let expose_all = !credentials && exposed.iter().any(|h| h == "*");
+    let headers = response
+        .headers
+        .iter()
+        .filter(|(name, _)| {
+            let name = name.to_ascii_lowercase();
+            if name == "set-cookie" || name == "set-cookie2" {
+                return false;
+            }
+            response_type == ResponseType::Basic
+                || expose_all
+                || SAFELISTED.contains(&name.as_str())
+                || exposed.iter().any(|h| h.eq_ignore_ascii_case(&name))
+        })
+        .cloned()
+        .collect();
+
+    FetchResponse {
+        response_type,
+        url,
+        redirected,
+        status: response.status,
+        status_text: response.status_text,
+        headers,
+        body: response.body,
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+// JavaScript bindings
+
+/// Clients of installed `fetch` functions, by id.
+///
+/// Ids are never reused, so a `fetch` function a script kept from an
+/// earlier document cannot reach a later document's client.
+static CLIENTS: Mutex<BTreeMap<u64, FetchClient>> = Mutex::new(BTreeMap::new());
+
+/// Next client id.
+static NEXT_CLIENT_ID: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(1);
+
+/// Keeps an installed `fetch` function connected to its client.
+///
+/// Once the handle is dropped, calls to the function reject.
+pub struct FetchHandle {
+    id: u64,
+}
+
+impl FetchHandle {
+    /// Run `f` with the client, e.g. to add CSP policies.
+    pub fn with_client<R>(&self, f: impl FnOnce(&mut FetchClient) -> R) -> R {
+        let mut clients = CLIENTS.lock();
+        f(clients
+            .get_mut(&self.id)
+            .expect("fetch client removed while its handle is alive"))
+    }
+}
+
+impl Drop for FetchHandle {
+    fn drop(&mut self) {
+        CLIENTS.lock().remove(&self.id);
+    }
+}
+
+/// Define the global `fetch` function in `engine`, backed by `client`.
+pub fn install(engine: &mut Engine, client: FetchClient) -> FetchHandle {
+    let id = NEXT_CLIENT_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+    CLIENTS.lock().insert(id, client);
+
+    let fetch = Callable::Bound(BoundFunction {
+        target: Box::new(Callable::Intrinsic(IntrinsicFunction {
+            name: "fetch".into(),
+            length: 2,
+            func: js_fetch,
+        })),
+        bound_this: Value::undefined(),
+        bound_args: vec![Value::number(id as f64)],
+    });
+    engine.define_global("fetch", Value::object(JsObject::function(fetch)));
+    FetchHandle { id }
+}
+
+/// `fetch(input, init)`, with the client id bound as the first argument.
+fn js_fetch(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    let id = args.first().map_or(Ok(0.0), Value::to_number)? as u64;
+    let input = args.get(1).cloned().unwrap_or(Value::undefined());
+    let init = args.get(2).cloned().unwrap_or(Value::undefined());
+
+    let result = request_from_init(&input, &init).and_then(|request| {
+        let mut clients = CLIENTS.lock();
+        let client = clients
+            .get_mut(&id)
+            .ok_or_else(|| JsError::type_error("Failed to fetch: document was unloaded"))?;
+        client
+            .fetch(request, network_bridge())
+            .map_err(|e| JsError::type_error(alloc::format!("Failed to fetch: {}", e)))
+    });
+    let result = result.map(|response| response_object(interp, response));
+    Ok(settled_promise(interp, result))
+}
+
+/// Build a request from the arguments of `fetch()`.
+fn request_from_init(input: &Value, init: &Value) -> JsResult<FetchRequest> {
+    let mut request = FetchRequest::new(&input.to_string()?);
+    if init.is_nullish() {
+        return Ok(request);
+    }
+
+    let method = init.get(&PropertyKey::string("method"))?;
+    if !method.is_undefined() {
+        request.method = method.to_string()?;
+    }
+
+    let headers = init.get(&PropertyKey::string("headers"))?;
+    if let Value::Object(obj) = &headers {
+        let obj = obj.borrow();
+        if obj.is_array() {
+            // [[name, value], ...]
+            for i in 0..obj.array_length() {
+                let pair = obj.get(&PropertyKey::Index(i as u32))?;
+                let name = pair.get(&PropertyKey::Index(0))?.to_string()?;
+                let value = pair.get(&PropertyKey::Index(1))?.to_string()?;
+                request.headers.push((name, value));
+            }
+        } else {
+            for key in obj.own_enumerable_keys() {
+                if let PropertyKey::String(name) = &key {
+                    let value = obj.get(&key)?.to_string()?;
+                    request.headers.push((name.clone(), value));
+                }
+            }
+        }
+    }
+
+    let body = init.get(&PropertyKey::string("body"))?;
+    if let Value::Object(obj) = &body {
+        if let Some(bytes) = obj.borrow().array_buffer_bytes() {
+            request.body = bytes.to_vec();
+        }
+    }
+    if !body.is_nullish() && request.body.is_empty() {
+        request.body = body.to_string()?.into_bytes();
+        if find_header(&request.headers, "Content-Type").is_none() {
+            request.headers.push((
+                String::from("Content-Type"),
+                String::from("text/plain;charset=UTF-8"),
+            ));
+        }
+    }
+
+    let option = |name: &str| -> JsResult<Option<String>> {
+        let value = init.get(&PropertyKey::string(name))?;
+        if value.is_undefined() {
+            Ok(None)
+        } else {
+            value.to_string().map(Some)
+        }
+    };
+    if let Some(mode) = option("mode")? {
+        request.mode = RequestMode::parse(&mode)
+            .ok_or_else(|| JsError::type_error(alloc::format!("Invalid mode '{}'", mode)))?;
+    }
+    if let Some(credentials) = option("credentials")? {
+        request.credentials = CredentialsMode::parse(&credentials).ok_or_else(|| {
+            JsError::type_error(alloc::format!("Invalid credentials '{}'", credentials))
+        })?;
+    }
+    if let Some(redirect) = option("redirect")? {
+        request.redirect = RedirectMode::parse(&redirect).ok_or_else(|| {
+            JsError::type_error(alloc::format!("Invalid redirect '{}'", redirect))
+        })?;
+    }
+    Ok(request)
+}
+
+/// Build the `Response` object handed to scripts.
+fn response_object(interp: &Interpreter, response: FetchResponse) -> Value {
+    let mut obj = JsObject::new();
+    let mut field = |name: &str, value: Value| {
+        obj.define_property(
+            PropertyKey::string(name),
+            PropertyDescriptor::data(value, false, true, true),
+        );
+    };
+    field("type", Value::string(response.response_type.as_str()));
+    field("url", Value::string(response.url.clone()));
+    field("redirected", Value::boolean(response.redirected));
+    field("status", Value::number(f64::from(response.status)));
+    field("ok", Value::boolean(response.ok()));
+    field("statusText", Value::string(response.status_text.clone()));
+    field("headers", headers_object(&response.headers));
+
+    obj.define_property(
+        PropertyKey::string("bodyUsed"),
+        PropertyDescriptor::data(Value::boolean(false), true, true, true),
+    );
+    obj.define_property(
+        PropertyKey::string("_body"),
+        PropertyDescriptor::data(
+            Value::Object(interp.new_array_buffer(response.body)),
+            false,
+            false,
+            true,
+        ),
+    );
+    define_method(&mut obj, "text", response_text);
+    define_method(&mut obj, "json", response_json);
+    define_method(&mut obj, "arrayBuffer", response_array_buffer);
+    Value::object(obj)
+}
+
+/// Build a `Headers` object: one property per lowercased header name,
+/// with `get()` and `has()` on its prototype.
+fn headers_object(headers: &[(String, String)]) -> Value {
+    let mut proto = JsObject::new();
+    define_method(&mut proto, "get", headers_get);
+    define_method(&mut proto, "has", headers_has);
+
+    let mut obj = JsObject::new();
+    obj.set_prototype(Some(alloc::rc::Rc::new(core::cell::RefCell::new(proto))));
+    for (name, value) in headers {
+        let key = PropertyKey::string(name.to_ascii_lowercase());
+        // Repeated headers are combined, as in `Headers.get()`
+        let value = match obj.get(&key) {
+            Ok(Value::String(previous)) => alloc::format!("{}, {}", previous, value),
+            _ => value.clone(),
+        };
+        obj.define_property(
+            key,
+            PropertyDescriptor::data(Value::string(value), false, true, true),
+        );
+    }
+    Value::object(obj)
+}
+
+fn define_method(
+    obj: &mut JsObject,
+    name: &str,
+    func: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+) {
+    let method = JsObject::function(Callable::Intrinsic(IntrinsicFunction {
+        name: name.into(),
+        length: 0,
+        func,
+    }));
+    obj.define_property(
+        PropertyKey::string(name),
+        PropertyDescriptor::data(Value::object(method), true, false, true),
+    );
+}
+
+fn headers_get(_interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let name = args.first().unwrap_or(&Value::undefined()).to_string()?;
+    let key = PropertyKey::string(name.to_ascii_lowercase());
+    match this {
+        Value::Object(obj) if obj.borrow().has_own_property(&key) => obj.borrow().get(&key),
+        _ => Ok(Value::null()),
+    }
+}
+
+fn headers_has(_interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let name = args.first().unwrap_or(&Value::undefined()).to_string()?;
+    let key = PropertyKey::string(name.to_ascii_lowercase());
+    let has = matches!(this, Value::Object(obj) if obj.borrow().has_own_property(&key));
+    Ok(Value::boolean(has))
+}
+
+/// Take the body of the `Response` `this`, marking it used.
+fn take_body(this: &Value) -> JsResult<Vec<u8>> {
+    let Value::Object(obj) = this else {
+        return Err(JsError::type_error("Illegal invocation"));
+    };
+    let mut obj = obj.borrow_mut();
+    if obj.get(&PropertyKey::string("bodyUsed"))?.to_boolean() {
+        return Err(JsError::type_error("Body has already been consumed"));
+    }
+    let body_key = PropertyKey::string("_body");
+    let body = match obj.get(&body_key)? {
+        Value::Object(buffer) => buffer.borrow().array_buffer_bytes().map(<[u8]>::to_vec),
+        _ => None,
+    }
+    .ok_or_else(|| JsError::type_error("Illegal invocation"))?;
+    obj.set(PropertyKey::string("bodyUsed"), Value::boolean(true))?;
+    obj.delete(&body_key);
+    Ok(body)
+}
+
+fn response_text(interp: &mut Interpreter, this: &Value, _args: &[Value]) -> JsResult<Value> {
+    let result = take_body(this).map(|body| Value::string(String::from_utf8_lossy(&body)));
+    Ok(settled_promise(interp, result))
+}
+
+fn response_json(interp: &mut Interpreter, this: &Value, _args: &[Value]) -> JsResult<Value> {
+    let result = take_body(this).and_then(|body| {
+        let json = interp.get_global("JSON")?;
+        let parse = json.get(&PropertyKey::string("parse"))?;
+        let text = Value::string(String::from_utf8_lossy(&body));
+        interp.call_function(&parse, &json, &[text])
+    });
+    Ok(settled_promise(interp, result))
+}
+
+fn response_array_buffer(
+    interp: &mut Interpreter,
+    this: &Value,
+    _args: &[Value],
+) -> JsResult<Value> {
+    let result = take_body(this).map(|body| Value::Object(interp.new_array_buffer(body)));
+    Ok(settled_promise(interp, result))
+}
+
+/// Create a promise already fulfilled or rejected with `result`.
+fn settled_promise(interp: &mut Interpreter, result: JsResult<Value>) -> Value {
+    let promise = interp.new_promise();
+    match result {
+        Ok(value) => interp.resolve_promise(&promise, value),
+        Err(e) => {
+            let reason = Value::object(JsObject::error(e.name().into(), e.message().into()));
+            interp.reject_promise(&promise, reason);
+        }
+    }
+    Value::Object(promise)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+
+    use crate::csp::CspPolicy;
+
+    /// Transport answering from a fixed list of routes.
+    struct Server {
+        routes: Vec<(&'static str, &'static str, HttpResponse)>,
+        sent: RefCell<Vec<HttpRequest>>,
+    }
+
+    impl Server {
+        fn new() -> Self {
+            Self {
+                routes: Vec::new(),
+                sent: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn route(
+            mut self,
+            method: &'static str,
+            url: &'static str,
+            status: u16,
+            headers: &[(&str, &str)],
+            body: &str,
+        ) -> Self {
+            let response = HttpResponse {
+                status,
+                status_text: String::new(),
+                headers: headers
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                body: body.as_bytes().to_vec(),
+            };
+            self.routes.push((method, url, response));
+            self
+        }
+
+        fn sent(&self, index: usize) -> HttpRequest {
+            self.sent.borrow()[index].clone()
+        }
+    }
+
+    impl HttpTransport for Server {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, NetError> {
+            self.sent.borrow_mut().push(request.clone());
+            self.routes
+                .iter()
+                .find(|(method, url, _)| *method == request.method && *url == request.url)
+                .map(|(_, _, response)| response.clone())
+                .ok_or(NetError::ConnectionRefused)
+        }
+    }
+
+    #[test]
+    fn test_same_origin_cookies() {
+        let server = Server::new().route(
+            "GET",
+            "https://app.test/login",
+            200,
+            &[("Set-Cookie", "session=42"), ("Content-Type", "text/plain")],
+            "hi",
+        );
+        let mut client = FetchClient::new("https://app.test/index.html");
+
+        let response = client.fetch(FetchRequest::new("/login"), &server).unwrap();
+        assert_eq!(response.response_type, ResponseType::Basic);
+        assert_eq!(response.url, "https://app.test/login");
+        assert_eq!(response.header("Set-Cookie"), None);
+        assert_eq!(response.header("content-type"), Some("text/plain"));
+        assert_eq!(server.sent(0).header("Origin"), None);
+
+        client.fetch(FetchRequest::new("/login"), &server).unwrap();
+        assert_eq!(server.sent(1).header("Cookie"), Some("session=42"));
+    }
+
+    #[test]
+    fn test_cors() {
+        let server = Server::new()
+            .route("GET", "https://api.test/closed", 200, &[], "")
+            .route(
+                "GET",
+                "https://api.test/open",
+                200,
+                &[
+                    ("Access-Control-Allow-Origin", "https://app.test"),
+                    ("Access-Control-Expose-Headers", "X-Total"),
+                    ("X-Total", "3"),
+                    ("X-Secret", "1"),
+                ],
+                "[]",
+            );
+        let mut client = FetchClient::new("https://app.test/");
+
+        let err = client
+            .fetch(FetchRequest::new("https://api.test/closed"), &server)
+            .unwrap_err();
+        assert!(matches!(err, FetchError::CorsRejected(_)));
+
+        let response = client
+            .fetch(FetchRequest::new("https://api.test/open"), &server)
+            .unwrap();
+        assert_eq!(response.response_type, ResponseType::Cors);
+        assert_eq!(response.header("X-Total"), Some("3"));
+        assert_eq!(response.header("X-Secret"), None);
+        assert_eq!(server.sent(1).header("Origin"), Some("https://app.test"));
+
+        let request = FetchRequest {
+            mode: RequestMode::NoCors,
+            ..FetchRequest::new("https://api.test/closed")
+        };
+        let response = client.fetch(request, &server).unwrap();
+        assert_eq!(response.response_type, ResponseType::Opaque);
+        assert_eq!(response.status, 0);
+
+        let request = FetchRequest {
+            mode: RequestMode::SameOrigin,
+            ..FetchRequest::new("https://api.test/open")
+        };
+        assert!(matches!(
+            client.fetch(request, &server),
+            Err(FetchError::CrossOrigin(_))
+        ));
+    }
+
+    #[test]
+    fn test_preflight() {
+        let server = Server::new()
+            .route(
+                "OPTIONS",
+                "https://api.test/item",
+                204,
+                &[
+                    ("Access-Control-Allow-Origin", "https://app.test"),
+                    ("Access-Control-Allow-Methods", "PUT, DELETE"),
+                    ("Access-Control-Allow-Headers", "X-Token"),
+                ],
+                "",
+            )
+            .route(
+                "PUT",
+                "https://api.test/item",
+                200,
+                &[("Access-Control-Allow-Origin", "https://app.test")],
+                "",
+            );
+        let mut client = FetchClient::new("https://app.test/");
+
+        let request = FetchRequest {
+            method: String::from("put"),
+            headers: vec![
+                (String::from("X-Token"), String::from("t")),
+                (String::from("Accept"), String::from("*/*")),
+            ],
+            ..FetchRequest::new("https://api.test/item")
+        };
+        client.fetch(request.clone(), &server).unwrap();
+        let preflight = server.sent(0);
+        assert_eq!(preflight.method, "OPTIONS");
+        assert_eq!(
+            preflight.header("Access-Control-Request-Method"),
+            Some("PUT")
+        );
+        assert_eq!(
+            preflight.header("Access-Control-Request-Headers"),
+            Some("x-token")
+        );
+        assert_eq!(server.sent(1).method, "PUT");
+
+        let request = FetchRequest {
+            headers: vec![(String::from("X-Other"), String::from("1"))],
+            ..request
+        };
+        assert!(matches!(
+            client.fetch(request, &server),
+            Err(FetchError::CorsRejected(_))
+        ));
+        assert_eq!(server.sent.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_redirects() {
+        let server = Server::new()
+            .route(
+                "POST",
+                "https://app.test/form",
+                302,
+                &[("Location", "/done")],
+                "",
+            )
+            .route("GET", "https://app.test/done", 200, &[], "ok")
+            .route(
+                "GET",
+                "https://app.test/loop",
+                307,
+                &[("Location", "https://app.test/loop")],
+                "",
+            );
+        let mut client = FetchClient::new("https://app.test/");
+
+        let request = FetchRequest {
+            method: String::from("POST"),
+            headers: vec![(String::from("Content-Type"), String::from("text/plain"))],
+            body: b"x=1".to_vec(),
+            ..FetchRequest::new("/form")
+        };
+        let response = client.fetch(request.clone(), &server).unwrap();
+        assert!(response.redirected);
+        assert_eq!(response.url, "https://app.test/done");
+        assert_eq!(response.body, b"ok");
+        let followed = server.sent(1);
+        assert_eq!(followed.method, "GET");
+        assert!(followed.body.is_empty());
+        assert_eq!(followed.header("Content-Type"), None);
+
+        let manual = FetchRequest {
+            redirect: RedirectMode::Manual,
+            ..request.clone()
+        };
+        let response = client.fetch(manual, &server).unwrap();
+        assert_eq!(response.response_type, ResponseType::OpaqueRedirect);
+
+        let error = FetchRequest {
+            redirect: RedirectMode::Error,
+            ..request
+        };
+        assert_eq!(
+            client.fetch(error, &server).unwrap_err(),
+            FetchError::RedirectRejected
+        );
+
+        assert_eq!(
+            client
+                .fetch(FetchRequest::new("/loop"), &server)
+                .unwrap_err(),
+            FetchError::TooManyRedirects
+        );
+    }
+
+    #[test]
+    fn test_connect_src() {
+        let server = Server::new()
+            .route("GET", "https://app.test/a", 200, &[], "")
+            .route("GET", "https://cdn.test/a", 200, &[], "");
+        let mut client = FetchClient::new("https://app.test/");
+        client
+            .csp_mut()
+            .add_policy(CspPolicy::parse("connect-src https://app.test", false));
+
+        assert!(client
+            .fetch(FetchRequest::new("https://app.test/a"), &server)
+            .is_ok());
+        assert_eq!(
+            client
+                .fetch(FetchRequest::new("https://cdn.test/a"), &server)
+                .unwrap_err(),
+            FetchError::BlockedByCsp(String::from("https://cdn.test/a"))
+        );
+        assert_eq!(client.csp().violations().len(), 1);
+        assert_eq!(server.sent.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_fetch_from_script() {
+        let mut engine = Engine::new();
+        let handle = install(&mut engine, FetchClient::new("http://example.com/"));
+
+        engine
+            .eval(
+                "var status, type, text, again;
+                 fetch('/page').then(function (r) {
+                     status = r.status;
+                     type = r.headers.get('Content-Type');
+                     return r.text().then(function (t) {
+                         text = t;
+                         return r.json();
+                     });
+                 }).catch(function (e) { again = e.message; });",
+            )
+            .unwrap();
+        let global =
+            |engine: &Engine, name: &str| engine.get_global(name).unwrap().to_string().unwrap();
+        assert_eq!(global(&engine, "status"), "200");
+        assert_eq!(global(&engine, "type"), "text/html");
+        assert_eq!(
+            global(&engine, "text"),
+            "<!DOCTYPE html><html><body>Hello!</body></html>"
+        );
+        assert_eq!(global(&engine, "again"), "Body has already been consumed");
+
+        drop(handle);
+        engine
+            .eval("var failed; fetch('/page').catch(function (e) { failed = e.name; });")
+            .unwrap();
+        assert_eq!(global(&engine, "failed"), "TypeError");
+    }
+}
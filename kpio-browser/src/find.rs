@@ -0,0 +1,339 @@
+//! Find in page.
+//!
+//! [`FindInPage`] searches the rendered text of a document, maps each
+//! match onto the text commands of the page's display list, and keeps
+//! the active match that next/previous navigation moves through. The
+//! find bar reads the match count and active index from here, and the
+//! tab scrolls the active match into view.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use kpio_dom::{Document, FindOptions, NodeId, Range};
+use kpio_layout::paint::Color;
+use kpio_layout::{DisplayCommand, DisplayList, Rect};
+
+/// Highlight behind the active match.
+const ACTIVE_HIGHLIGHT: Color = Color::rgb(255, 150, 50);
+/// Highlight behind the other matches.
+const MATCH_HIGHLIGHT: Color = Color::rgb(255, 255, 0);
+
+/// Character advance and line height, relative to the font size, used
+/// by the render pipeline's text layout.
+const CHAR_ADVANCE: f32 = 0.6;
+const LINE_HEIGHT: f32 = 1.4;
+
+/// A match of the find query.
+#[derive(Debug, Clone)]
+pub struct FindMatch {
+    /// Matched text in the document.
+    pub range: Range,
+    /// Painted area of the match, as the index of a text command in the
+    /// display list and the rectangle covering the matched characters.
+    pub rects: Vec<(usize, Rect)>,
+}
+
+/// Find-in-page state of a tab.
+#[derive(Debug, Clone, Default)]
+pub struct FindInPage {
+    query: String,
+    options: FindOptions,
+    matches: Vec<FindMatch>,
+    active: Option<usize>,
+}
+
+impl FindInPage {
+    /// Create an empty find session.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Search `document` for `query` and make the first match active.
+    /// `display_list` is the painted page, used to locate the matches.
+    /// Returns the number of matches.
+    pub fn find(
+        &mut self,
+        document: &Document,
+        display_list: &DisplayList,
+        query: &str,
+        options: FindOptions,
+    ) -> usize {
+        self.query = String::from(query);
+        self.options = options;
+
+        let painted = pair_text_commands(document, display_list.commands());
+        self.matches = document
+            .find_text(query, options)
+            .into_iter()
+            .map(|range| FindMatch {
+                rects: match_rects(document, &range, &painted, display_list.commands()),
+                range,
+            })
+            .collect();
+        self.active = if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.matches.len()
+    }
+
+    /// Move to the next match, wrapping around to the first.
+    pub fn next_match(&mut self) -> Option<&FindMatch> {
+        let count = self.matches.len();
+        self.active = self.active.map(|i| (i + 1) % count);
+        self.active_match()
+    }
+
+    /// Move to the previous match, wrapping around to the last.
+    pub fn previous_match(&mut self) -> Option<&FindMatch> {
+        let count = self.matches.len();
+        self.active = self.active.map(|i| (i + count - 1) % count);
+        self.active_match()
+    }
+
+    /// End the find session.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Get the current query.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Get the options of the current query.
+    pub fn options(&self) -> FindOptions {
+        self.options
+    }
+
+    /// Get all matches, in document order.
+    pub fn matches(&self) -> &[FindMatch] {
+        &self.matches
+    }
+
+    /// Get the number of matches.
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Get the index of the active match.
+    pub fn active_index(&self) -> Option<usize> {
+        self.active
+    }
+
+    /// Get the active match.
+    pub fn active_match(&self) -> Option<&FindMatch> {
+        self.matches.get(self.active?)
+    }
+
+    /// Paint highlights behind the matched text of `display_list`.
+    pub fn highlight(&self, display_list: &DisplayList) -> DisplayList {
+        let mut highlights: Vec<Vec<(Color, Rect)>> =
+            (0..display_list.len()).map(|_| Vec::new()).collect();
+        for (i, m) in self.matches.iter().enumerate() {
+            let color = if Some(i) == self.active {
+                ACTIVE_HIGHLIGHT
+            } else {
+                MATCH_HIGHLIGHT
+            };
+            for &(command, rect) in &m.rects {
+                if let Some(slot) = highlights.get_mut(command) {
+                    slot.push((color, rect));
+                }
+            }
+        }
+
+        let mut list = DisplayList::new();
+        for (command, rects) in display_list.commands().iter().zip(highlights) {
+            for (color, rect) in rects {
+                list.push(DisplayCommand::SolidRect { color, rect });
+            }
+            list.push(command.clone());
+        }
+        list
+    }
+
+    /// Get the vertical scroll position that brings the active match into
+    /// view, centering it when it is off screen.
+    pub fn scroll_into_view(&self, scroll_y: i32, viewport_height: f32) -> i32 {
+        let Some(rect) = self.active_match().and_then(|m| m.rects.first()) else {
+            return scroll_y;
+        };
+        let (_, rect) = *rect;
+        let top = scroll_y as f32;
+        if rect.y >= top && rect.y + rect.height <= top + viewport_height {
+            return scroll_y;
+        }
+        let centered = rect.y + rect.height / 2.0 - viewport_height / 2.0;
+        centered.max(0.0) as i32
+    }
+}
+
+/// Pair each rendered text node with the display-list text command that
+/// paints it. The pipeline paints trimmed text, one command per node, in
+/// document order.
+fn pair_text_commands(document: &Document, commands: &[DisplayCommand]) -> Vec<(NodeId, usize)> {
+    let mut pairs = Vec::new();
+    let mut next = 0;
+    for node in document.rendered_text_nodes() {
+        let text = document
+            .get(node)
+            .and_then(|n| n.text_content())
+            .unwrap_or("")
+            .trim();
+        if text.is_empty() {
+            continue;
+        }
+        let found = commands[next..]
+            .iter()
+            .position(|c| matches!(c, DisplayCommand::Text { text: t, .. } if t == text));
+        if let Some(offset) = found {
+            pairs.push((node, next + offset));
+            next += offset + 1;
+        }
+    }
+    pairs
+}
+
+/// Get the painted rectangles of a match, one per text node it covers.
+fn match_rects(
+    document: &Document,
+    range: &Range,
+    painted: &[(NodeId, usize)],
+    commands: &[DisplayCommand],
+) -> Vec<(usize, Rect)> {
+    let start = painted
+        .iter()
+        .position(|&(node, _)| node == range.start_container());
+    let Some(start) = start else {
+        return Vec::new();
+    };
+
+    let mut rects = Vec::new();
+    for &(node, command) in &painted[start..] {
+        let Some(data) = document.get(node).and_then(|n| n.text_content()) else {
+            break;
+        };
+        let from = if node == range.start_container() {
+            range.start_offset()
+        } else {
+            0
+        };
+        let to = if node == range.end_container() {
+            range.end_offset()
+        } else {
+            data.len()
+        };
+
+        if let Some(DisplayCommand::Text { rect, style, .. }) = commands.get(command) {
+            // Offsets are into the untrimmed data; the command starts at
+            // its first non-whitespace character.
+            let lead = data.len() - data.trim_start().len();
+            let column = |offset: usize| data[lead..offset.max(lead)].chars().count() as f32;
+            let advance = style.font_size * CHAR_ADVANCE;
+            let (first, last) = (column(from), column(to.min(data.trim_end().len())));
+            if last > first {
+                rects.push((
+                    command,
+                    Rect::new(
+                        rect.x + first * advance,
+                        rect.y,
+                        (last - first) * advance,
+                        rect.height.min(style.font_size * LINE_HEIGHT),
+                    ),
+                ));
+            }
+        }
+
+        if node == range.end_container() {
+            break;
+        }
+    }
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::RenderPipeline;
+    use kpio_dom::document::parse_html;
+
+    const PAGE: &str =
+        "<html><body><p>One fish, two fish</p><p>red <b>fish</b></p><p>blue fish</p></body></html>";
+
+    fn search(query: &str, options: FindOptions) -> (FindInPage, DisplayList) {
+        let document = parse_html(PAGE);
+        let list = RenderPipeline::new(800.0, 600.0).render(PAGE).unwrap();
+        let mut find = FindInPage::new();
+        find.find(&document, &list, query, options);
+        (find, list)
+    }
+
+    #[test]
+    fn test_find_and_navigate() {
+        let (mut find, _) = search("FISH", FindOptions::default());
+        assert_eq!(find.match_count(), 4);
+        assert_eq!(find.active_index(), Some(0));
+
+        find.next_match();
+        find.next_match();
+        find.next_match();
+        assert_eq!(find.active_index(), Some(3));
+        find.next_match();
+        assert_eq!(find.active_index(), Some(0));
+        find.previous_match();
+        assert_eq!(find.active_index(), Some(3));
+
+        let exact = FindOptions {
+            case_sensitive: true,
+            ..FindOptions::default()
+        };
+        let (mut find, _) = search("FISH", exact);
+        assert_eq!(find.match_count(), 0);
+        assert!(find.next_match().is_none());
+        assert_eq!(find.active_index(), None);
+    }
+
+    #[test]
+    fn test_match_rects_and_highlight() {
+        let (find, list) = search("two", FindOptions::default());
+        let (command, rect) = find.active_match().unwrap().rects[0];
+        let DisplayCommand::Text {
+            rect: text_rect,
+            style,
+            ..
+        } = &list.commands()[command]
+        else {
+            panic!("match is not on a text command");
+        };
+
+        let advance = style.font_size * CHAR_ADVANCE;
+        assert_eq!(rect.x, text_rect.x + 10.0 * advance);
+        assert_eq!(rect.width, 3.0 * advance);
+
+        let highlighted = find.highlight(&list);
+        assert_eq!(highlighted.len(), list.len() + 1);
+        match &highlighted.commands()[command] {
+            DisplayCommand::SolidRect { color, rect: r } => {
+                assert_eq!(*color, ACTIVE_HIGHLIGHT);
+                assert_eq!(*r, rect);
+            }
+            other => panic!("expected a highlight, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_scroll_into_view() {
+        let (mut find, _) = search("fish", FindOptions::default());
+        assert_eq!(find.scroll_into_view(0, 600.0), 0);
+
+        // The last match, in a viewport too short to show it
+        find.previous_match();
+        let (_, rect) = find.active_match().unwrap().rects[0];
+        assert_eq!(
+            find.scroll_into_view(0, 10.0),
+            (rect.y + rect.height / 2.0 - 5.0) as i32
+        );
+    }
+}
@@ -253,6 +253,38 @@ impl I18nManager {
         &self.current_locale
     }
 
+    /// Build an `Accept-Language` header value from the current locale
+    /// and then the fallback locale.
+    ///
+    /// `ko-KR` with an `en-US` fallback gives
+    /// `ko-KR,ko;q=0.9,en-US;q=0.8,en;q=0.7`.
+    pub fn accept_language(&self) -> String {
+        let mut tags: Vec<String> = Vec::new();
+        for locale in [&self.current_locale, &self.fallback_locale] {
+            let Some(locale) = Locale::parse(locale) else {
+                continue;
+            };
+            for tag in [locale.to_tag(), locale.language] {
+                if !tag.is_empty() && !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        if tags.is_empty() {
+            return "en-US,en;q=0.9".to_string();
+        }
+
+        let ranges: Vec<String> = tags
+            .iter()
+            .enumerate()
+            .map(|(i, tag)| match i {
+                0 => tag.clone(),
+                _ => alloc::format!("{};q=0.{}", tag, 10 - i.min(9)),
+            })
+            .collect();
+        ranges.join(",")
+    }
+
     /// Get current direction
     pub fn direction(&self) -> Direction {
         self.languages
@@ -0,0 +1,904 @@
+//! Iframes and nested browsing contexts.
+//!
+//! Every `<iframe>` gets its own browsing context: a separate document,
+//! loader, resource cache and render pipeline, sized to the iframe's
+//! content box. Child surfaces are composited into the parent's surface
+//! after rendering.
+//!
+//! Script access across a frame boundary is only granted between
+//! same-origin contexts. `srcdoc` and `about:blank` frames inherit their
+//! parent's origin; sandboxed frames without `allow-same-origin` get a
+//! fresh opaque origin that matches nothing else.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use kpio_layout::Rect;
+use kpio_network::Url;
+use libm::ceilf;
+
+use crate::document::Document;
+use crate::loader::{DocumentLoader, LoaderError};
+use crate::pipeline::PipelineError;
+
+/// Maximum nesting depth of frames below the top-level context.
+pub const MAX_FRAME_DEPTH: usize = 8;
+
+/// Background of a frame surface before its document has rendered.
+const BLANK_PIXEL: u32 = 0xFFFFFFFF;
+
+/// Browsing context identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BrowsingContextId(u32);
+
+impl BrowsingContextId {
+    /// Get the raw ID value.
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Security origin of a browsing context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// Scheme/host/port tuple of an http(s) URL.
+    Tuple {
+        scheme: String,
+        host: String,
+        port: u16,
+    },
+    /// Opaque origin, only same-origin with itself.
+    Opaque(u64),
+}
+
+impl Origin {
+    /// Derive a tuple origin from an absolute http(s) URL.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let parsed = Url::parse(url).ok()?;
+        Some(Origin::Tuple {
+            scheme: parsed.scheme,
+            host: parsed.host.to_ascii_lowercase(),
+            port: parsed.port,
+        })
+    }
+
+    /// Check whether two origins are the same origin.
+    pub fn is_same_origin(&self, other: &Origin) -> bool {
+        self == other
+    }
+
+    /// Check whether this is an opaque origin.
+    pub fn is_opaque(&self) -> bool {
+        matches!(self, Origin::Opaque(_))
+    }
+}
+
+impl core::fmt::Display for Origin {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Origin::Tuple { scheme, host, port } => {
+                let default_port = match scheme.as_str() {
+                    "http" => 80,
+                    "https" => 443,
+                    _ => 0,
+                };
+                if *port == default_port {
+                    write!(f, "{}://{}", scheme, host)
+                } else {
+                    write!(f, "{}://{}:{}", scheme, host, port)
+                }
+            }
+            Origin::Opaque(_) => write!(f, "null"),
+        }
+    }
+}
+
+/// Capabilities re-enabled by the `sandbox` attribute.
+///
+/// A sandboxed frame starts with everything disabled; each `allow-*`
+/// token turns one capability back on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SandboxFlags {
+    /// `allow-scripts`.
+    pub allow_scripts: bool,
+    /// `allow-same-origin`.
+    pub allow_same_origin: bool,
+    /// `allow-forms`.
+    pub allow_forms: bool,
+    /// `allow-popups`.
+    pub allow_popups: bool,
+    /// `allow-top-navigation`.
+    pub allow_top_navigation: bool,
+}
+
+impl SandboxFlags {
+    /// Parse the value of a `sandbox` attribute.
+    pub fn parse(value: &str) -> Self {
+        let mut flags = Self::default();
+        for token in value.split_ascii_whitespace() {
+            match token.to_ascii_lowercase().as_str() {
+                "allow-scripts" => flags.allow_scripts = true,
+                "allow-same-origin" => flags.allow_same_origin = true,
+                "allow-forms" => flags.allow_forms = true,
+                "allow-popups" => flags.allow_popups = true,
+                "allow-top-navigation" => flags.allow_top_navigation = true,
+                _ => {}
+            }
+        }
+        flags
+    }
+
+    /// Combine with an enclosing sandbox; a nested frame can never
+    /// regain a capability its parent lacks.
+    pub fn intersect(&self, other: &SandboxFlags) -> Self {
+        Self {
+            allow_scripts: self.allow_scripts && other.allow_scripts,
+            allow_same_origin: self.allow_same_origin && other.allow_same_origin,
+            allow_forms: self.allow_forms && other.allow_forms,
+            allow_popups: self.allow_popups && other.allow_popups,
+            allow_top_navigation: self.allow_top_navigation && other.allow_top_navigation,
+        }
+    }
+}
+
+/// Attributes of an `<iframe>` element relevant to loading it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IframeElement {
+    /// `src` attribute.
+    pub src: Option<String>,
+    /// `srcdoc` attribute (takes precedence over `src`).
+    pub srcdoc: Option<String>,
+    /// `name` attribute (browsing context name).
+    pub name: Option<String>,
+    /// Parsed `sandbox` attribute, if present.
+    pub sandbox: Option<SandboxFlags>,
+}
+
+impl IframeElement {
+    /// Build from an element's attribute list.
+    pub fn from_attributes(attrs: &[(String, String)]) -> Self {
+        let mut element = Self::default();
+        for (name, value) in attrs {
+            match name.to_ascii_lowercase().as_str() {
+                "src" => {
+                    let src = value.trim();
+                    if !src.is_empty() {
+                        element.src = Some(src.to_string());
+                    }
+                }
+                "srcdoc" => element.srcdoc = Some(value.clone()),
+                "name" => element.name = Some(value.clone()),
+                "sandbox" => element.sandbox = Some(SandboxFlags::parse(value)),
+                _ => {}
+            }
+        }
+        element
+    }
+}
+
+/// Load state of a browsing context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadState {
+    /// Waiting for its document to be fetched.
+    Pending,
+    /// Document loaded and rendered.
+    Complete,
+    /// Fetching or rendering the document failed.
+    Failed,
+}
+
+/// A browsing context: a document with its own loader and surface.
+pub struct BrowsingContext {
+    id: BrowsingContextId,
+    parent: Option<BrowsingContextId>,
+    children: Vec<BrowsingContextId>,
+    name: Option<String>,
+    url: String,
+    origin: Origin,
+    sandbox: Option<SandboxFlags>,
+    depth: usize,
+    /// Position within the parent's surface.
+    rect: Rect,
+    width: u32,
+    height: u32,
+    loader: DocumentLoader,
+    document: Document,
+    surface: Vec<u32>,
+    state: LoadState,
+}
+
+impl BrowsingContext {
+    fn new(
+        id: BrowsingContextId,
+        parent: Option<BrowsingContextId>,
+        url: &str,
+        origin: Origin,
+        rect: Rect,
+    ) -> Self {
+        let width = ceilf(rect.width).max(0.0) as u32;
+        let height = ceilf(rect.height).max(0.0) as u32;
+        let mut loader = DocumentLoader::new(width, height);
+        // Non-http URLs (about:blank, about:srcdoc) simply have no base
+        let _ = loader.loader_mut().set_base_url(url);
+
+        Self {
+            id,
+            parent,
+            children: Vec::new(),
+            name: None,
+            url: url.to_string(),
+            origin,
+            sandbox: None,
+            depth: 0,
+            rect,
+            width,
+            height,
+            loader,
+            document: Document::new(url),
+            surface: vec![BLANK_PIXEL; (width * height) as usize],
+            state: LoadState::Pending,
+        }
+    }
+
+    /// Get the context ID.
+    pub fn id(&self) -> BrowsingContextId {
+        self.id
+    }
+
+    /// Get the parent context, `None` for the top-level context.
+    pub fn parent(&self) -> Option<BrowsingContextId> {
+        self.parent
+    }
+
+    /// Get child contexts in document order.
+    pub fn children(&self) -> &[BrowsingContextId] {
+        &self.children
+    }
+
+    /// Check if this is the top-level context.
+    pub fn is_top_level(&self) -> bool {
+        self.parent.is_none()
+    }
+
+    /// Get the context name (from the iframe's `name` attribute).
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Get the document URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the security origin.
+    pub fn origin(&self) -> &Origin {
+        &self.origin
+    }
+
+    /// Get the effective sandbox flags, `None` if unsandboxed.
+    pub fn sandbox(&self) -> Option<SandboxFlags> {
+        self.sandbox
+    }
+
+    /// Check whether scripts may run in this context.
+    pub fn scripts_enabled(&self) -> bool {
+        self.sandbox.is_none_or(|flags| flags.allow_scripts)
+    }
+
+    /// Get the nesting depth (0 for the top-level context).
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get the rectangle this context occupies in its parent.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Get the surface size in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Get the document.
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Get the rendered surface, without child frames.
+    pub fn surface(&self) -> &[u32] {
+        &self.surface
+    }
+
+    /// Get the load state.
+    pub fn state(&self) -> LoadState {
+        self.state
+    }
+
+    /// Render HTML into this context's surface and replace its document.
+    fn render(&mut self, html: &str) -> Result<(), FrameError> {
+        let width = self.width;
+        let height = self.height;
+        self.surface.resize((width * height) as usize, BLANK_PIXEL);
+
+        if let Err(e) =
+            self.loader
+                .pipeline()
+                .render_to_framebuffer(html, &mut self.surface, width, height)
+        {
+            self.state = LoadState::Failed;
+            return Err(e.into());
+        }
+
+        self.document = Document::from_html(html, &self.url);
+        self.state = LoadState::Complete;
+        Ok(())
+    }
+}
+
+/// Frame error.
+#[derive(Debug, Clone)]
+pub enum FrameError {
+    /// No context with this ID.
+    NotFound(BrowsingContextId),
+    /// Loading the frame's document failed.
+    Loader(LoaderError),
+    /// Rendering the frame's document failed.
+    Pipeline(PipelineError),
+    /// Cross-origin access was blocked.
+    SecurityError(String),
+}
+
+impl From<LoaderError> for FrameError {
+    fn from(err: LoaderError) -> Self {
+        FrameError::Loader(err)
+    }
+}
+
+impl From<PipelineError> for FrameError {
+    fn from(err: PipelineError) -> Self {
+        FrameError::Pipeline(err)
+    }
+}
+
+impl core::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FrameError::NotFound(id) => write!(f, "No browsing context {}", id.0),
+            FrameError::Loader(e) => write!(f, "Frame load error: {:?}", e),
+            FrameError::Pipeline(e) => write!(f, "Frame render error: {}", e),
+            FrameError::SecurityError(s) => write!(f, "Security error: {}", s),
+        }
+    }
+}
+
+/// Tree of browsing contexts rooted at a top-level page.
+pub struct FrameTree {
+    contexts: BTreeMap<BrowsingContextId, BrowsingContext>,
+    top: BrowsingContextId,
+    next_id: u32,
+    next_opaque: u64,
+    max_depth: usize,
+}
+
+impl FrameTree {
+    /// Create a tree with a top-level context for `url`.
+    pub fn new(url: &str, width: u32, height: u32) -> Self {
+        let mut tree = Self {
+            contexts: BTreeMap::new(),
+            top: BrowsingContextId(0),
+            next_id: 1,
+            next_opaque: 0,
+            max_depth: MAX_FRAME_DEPTH,
+        };
+
+        let origin = tree.origin_for(url);
+        let rect = Rect {
+            x: 0.0,
+            y: 0.0,
+            width: width as f32,
+            height: height as f32,
+        };
+        let top = BrowsingContext::new(tree.top, None, url, origin, rect);
+        tree.contexts.insert(tree.top, top);
+        tree
+    }
+
+    /// Set the maximum frame nesting depth.
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
+    /// Get the top-level context ID.
+    pub fn top(&self) -> BrowsingContextId {
+        self.top
+    }
+
+    /// Get a context.
+    pub fn get(&self, id: BrowsingContextId) -> Option<&BrowsingContext> {
+        self.contexts.get(&id)
+    }
+
+    /// Get the number of live contexts, including the top-level one.
+    pub fn len(&self) -> usize {
+        self.contexts.len()
+    }
+
+    /// Check if the tree has no contexts (never true; the top stays).
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+    }
+
+    /// Find a frame by name among the descendants of `from`.
+    pub fn find_by_name(&self, from: BrowsingContextId, name: &str) -> Option<BrowsingContextId> {
+        let ctx = self.contexts.get(&from)?;
+        for &child in &ctx.children {
+            if self.contexts.get(&child)?.name.as_deref() == Some(name) {
+                return Some(child);
+            }
+            if let Some(found) = self.find_by_name(child, name) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// Contexts still waiting for their document to be fetched.
+    pub fn pending(&self) -> Vec<BrowsingContextId> {
+        self.contexts
+            .values()
+            .filter(|ctx| ctx.state == LoadState::Pending && Url::parse(&ctx.url).is_ok())
+            .map(|ctx| ctx.id)
+            .collect()
+    }
+
+    /// Load HTML into a context and create browsing contexts for its iframes.
+    ///
+    /// Returns the new contexts that still need a network load.
+    pub fn load_html(
+        &mut self,
+        id: BrowsingContextId,
+        html: &str,
+    ) -> Result<Vec<BrowsingContextId>, FrameError> {
+        self.contexts
+            .get_mut(&id)
+            .ok_or(FrameError::NotFound(id))?
+            .render(html)?;
+        self.attach_frames(id, html)
+    }
+
+    /// Build the request for a pending frame, using that frame's own loader.
+    ///
+    /// Returns the resolved URL and the request bytes to send.
+    pub fn navigate(&mut self, id: BrowsingContextId) -> Result<(String, Vec<u8>), FrameError> {
+        let ctx = self.contexts.get_mut(&id).ok_or(FrameError::NotFound(id))?;
+        let url = ctx.url.clone();
+        ctx.state = LoadState::Pending;
+        Ok(ctx.loader.navigate(&url)?)
+    }
+
+    /// Feed the HTTP response for a frame's document and render it.
+    ///
+    /// Returns the nested contexts that still need a network load.
+    pub fn process_response(
+        &mut self,
+        id: BrowsingContextId,
+        response_data: &[u8],
+    ) -> Result<Vec<BrowsingContextId>, FrameError> {
+        let ctx = self.contexts.get_mut(&id).ok_or(FrameError::NotFound(id))?;
+        let url = ctx.url.clone();
+
+        let loader = ctx.loader.loader_mut();
+        loader.feed_response(response_data.to_vec());
+        let result = match loader.parse_response(&url) {
+            Ok(result) => result,
+            Err(e) => {
+                ctx.state = LoadState::Failed;
+                return Err(e.into());
+            }
+        };
+
+        let html = result.text().unwrap_or_default();
+        self.load_html(id, &html)
+    }
+
+    /// Remove a frame and all of its descendants.
+    pub fn detach(&mut self, id: BrowsingContextId) {
+        if id == self.top {
+            return;
+        }
+        if let Some(parent) = self.contexts.get(&id).and_then(|ctx| ctx.parent) {
+            if let Some(parent) = self.contexts.get_mut(&parent) {
+                parent.children.retain(|&child| child != id);
+            }
+        }
+        self.remove_subtree(id);
+    }
+
+    /// Composite a context's surface with all of its descendant frames.
+    pub fn composite(&self, id: BrowsingContextId) -> Option<Vec<u32>> {
+        let ctx = self.contexts.get(&id)?;
+        let mut output = ctx.surface.clone();
+
+        for &child_id in &ctx.children {
+            let Some(child) = self.contexts.get(&child_id) else {
+                continue;
+            };
+            if let Some(child_surface) = self.composite(child_id) {
+                blit(
+                    &mut output,
+                    ctx.width,
+                    ctx.height,
+                    &child_surface,
+                    child.width,
+                    child.height,
+                    child.rect.x as i32,
+                    child.rect.y as i32,
+                );
+            }
+        }
+
+        Some(output)
+    }
+
+    /// Check whether scripts in `accessor` may reach into `target`.
+    pub fn can_access(&self, accessor: BrowsingContextId, target: BrowsingContextId) -> bool {
+        match (self.contexts.get(&accessor), self.contexts.get(&target)) {
+            (Some(a), Some(t)) => a.scripts_enabled() && a.origin.is_same_origin(&t.origin),
+            _ => false,
+        }
+    }
+
+    /// Get another context's document on behalf of a script in `accessor`.
+    ///
+    /// Fails with a security error unless both contexts are same-origin.
+    pub fn access_document(
+        &self,
+        accessor: BrowsingContextId,
+        target: BrowsingContextId,
+    ) -> Result<&Document, FrameError> {
+        let a = self
+            .contexts
+            .get(&accessor)
+            .ok_or(FrameError::NotFound(accessor))?;
+        let t = self
+            .contexts
+            .get(&target)
+            .ok_or(FrameError::NotFound(target))?;
+
+        if !a.scripts_enabled() {
+            return Err(FrameError::SecurityError(
+                "Scripts are disabled in this sandboxed frame".to_string(),
+            ));
+        }
+        if !a.origin.is_same_origin(&t.origin) {
+            return Err(FrameError::SecurityError(format!(
+                "Blocked a frame with origin \"{}\" from accessing a cross-origin frame with origin \"{}\"",
+                a.origin, t.origin
+            )));
+        }
+
+        Ok(&t.document)
+    }
+
+    /// Replace a context's child frames with the iframes found in `html`.
+    fn attach_frames(
+        &mut self,
+        parent_id: BrowsingContextId,
+        html: &str,
+    ) -> Result<Vec<BrowsingContextId>, FrameError> {
+        let old_children = match self.contexts.get_mut(&parent_id) {
+            Some(parent) => core::mem::take(&mut parent.children),
+            None => return Err(FrameError::NotFound(parent_id)),
+        };
+        for child in old_children {
+            self.remove_subtree(child);
+        }
+
+        let parent = &self.contexts[&parent_id];
+        if parent.depth >= self.max_depth {
+            return Ok(Vec::new());
+        }
+        let boxes = parent.loader.pipeline().layout_frames(html)?;
+
+        let mut pending = Vec::new();
+        for frame in boxes {
+            let parent = &self.contexts[&parent_id];
+            let element = frame.element;
+
+            let sandbox = match (parent.sandbox, element.sandbox) {
+                (Some(outer), Some(inner)) => Some(outer.intersect(&inner)),
+                (outer, inner) => outer.or(inner),
+            };
+
+            // srcdoc and missing/blank src load a document that inherits
+            // the parent's origin; everything else is fetched.
+            let (url, srcdoc) = if let Some(srcdoc) = element.srcdoc {
+                (String::from("about:srcdoc"), Some(srcdoc))
+            } else {
+                match element.src.as_deref() {
+                    None | Some("about:blank") => (String::from("about:blank"), None),
+                    Some(src) => {
+                        let resolved = parent
+                            .loader
+                            .loader()
+                            .resolve_url(src)
+                            .unwrap_or_else(|_| src.to_string());
+                        if self.is_ancestor_url(parent_id, &resolved) {
+                            // A frame may not embed one of its ancestors
+                            (String::from("about:blank"), None)
+                        } else {
+                            (resolved, None)
+                        }
+                    }
+                }
+            };
+            let fetched = url.starts_with("http://") || url.starts_with("https://");
+
+            let parent_origin = parent.origin.clone();
+            let parent_url = parent.url.clone();
+            let depth = parent.depth + 1;
+
+            let origin = if sandbox.is_some_and(|flags| !flags.allow_same_origin) {
+                self.opaque_origin()
+            } else if fetched {
+                self.origin_for(&url)
+            } else {
+                parent_origin
+            };
+
+            let id = BrowsingContextId(self.next_id);
+            self.next_id += 1;
+
+            let mut ctx = BrowsingContext::new(id, Some(parent_id), &url, origin, frame.rect);
+            ctx.name = element.name;
+            ctx.sandbox = sandbox;
+            ctx.depth = depth;
+            if !fetched {
+                // Relative URLs in inherited documents resolve against the parent
+                let _ = ctx.loader.loader_mut().set_base_url(&parent_url);
+            }
+            self.contexts.insert(id, ctx);
+            if let Some(parent) = self.contexts.get_mut(&parent_id) {
+                parent.children.push(id);
+            }
+
+            if fetched {
+                pending.push(id);
+            } else {
+                let nested = self.load_html(id, srcdoc.as_deref().unwrap_or(""))?;
+                pending.extend(nested);
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// Check whether `url` is already loaded in `id` or one of its ancestors.
+    fn is_ancestor_url(&self, id: BrowsingContextId, url: &str) -> bool {
+        let mut current = Some(id);
+        while let Some(ctx) = current.and_then(|id| self.contexts.get(&id)) {
+            if ctx.url == url {
+                return true;
+            }
+            current = ctx.parent;
+        }
+        false
+    }
+
+    fn remove_subtree(&mut self, id: BrowsingContextId) {
+        if let Some(ctx) = self.contexts.remove(&id) {
+            for child in ctx.children {
+                self.remove_subtree(child);
+            }
+        }
+    }
+
+    fn origin_for(&mut self, url: &str) -> Origin {
+        Origin::from_url(url).unwrap_or_else(|| self.opaque_origin())
+    }
+
+    fn opaque_origin(&mut self) -> Origin {
+        let origin = Origin::Opaque(self.next_opaque);
+        self.next_opaque += 1;
+        origin
+    }
+}
+
+/// Copy `src` into `dst` at (`x`, `y`), clipped to the destination.
+#[allow(clippy::too_many_arguments)]
+fn blit(
+    dst: &mut [u32],
+    dst_width: u32,
+    dst_height: u32,
+    src: &[u32],
+    src_width: u32,
+    src_height: u32,
+    x: i32,
+    y: i32,
+) {
+    for row in 0..src_height as i32 {
+        let dy = y + row;
+        if dy < 0 || dy >= dst_height as i32 {
+            continue;
+        }
+        for col in 0..src_width as i32 {
+            let dx = x + col;
+            if dx < 0 || dx >= dst_width as i32 {
+                continue;
+            }
+            let s = (row as u32 * src_width + col as u32) as usize;
+            let d = (dy as u32 * dst_width + dx as u32) as usize;
+            if let (Some(&pixel), Some(out)) = (src.get(s), dst.get_mut(d)) {
+                *out = pixel;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_iframe_attributes() {
+        let attrs = vec![
+            (String::from("SRC"), String::from(" /widget.html ")),
+            (String::from("name"), String::from("ad")),
+            (
+                String::from("sandbox"),
+                String::from("allow-scripts ALLOW-FORMS"),
+            ),
+        ];
+        let element = IframeElement::from_attributes(&attrs);
+        assert_eq!(element.src.as_deref(), Some("/widget.html"));
+        assert_eq!(element.name.as_deref(), Some("ad"));
+        let sandbox = element.sandbox.unwrap();
+        assert!(sandbox.allow_scripts && sandbox.allow_forms);
+        assert!(!sandbox.allow_same_origin);
+    }
+
+    #[test]
+    fn test_iframe_sized_to_box() {
+        let mut tree = FrameTree::new("http://example.com/", 800, 600);
+        let top = tree.top();
+        let pending = tree
+            .load_html(
+                top,
+                "<body><iframe src=\"/a.html\" width=\"200\" height=\"100\"></iframe>\
+                 <iframe src=\"/b.html\">fallback</iframe></body>",
+            )
+            .unwrap();
+
+        assert_eq!(pending.len(), 2);
+        let first = tree.get(pending[0]).unwrap();
+        assert_eq!(first.size(), (200, 100));
+        assert_eq!(first.url(), "http://example.com/a.html");
+        assert_eq!(first.parent(), Some(top));
+        // Default object size
+        assert_eq!(tree.get(pending[1]).unwrap().size(), (300, 150));
+        assert!(tree.get(pending[1]).unwrap().rect().y > first.rect().y);
+    }
+
+    #[test]
+    fn test_iframe_load_and_composite() {
+        let mut tree = FrameTree::new("http://example.com/", 400, 300);
+        let top = tree.top();
+        let pending = tree
+            .load_html(
+                top,
+                "<body><iframe src=\"http://other.com/\" width=\"100\" height=\"50\"></iframe></body>",
+            )
+            .unwrap();
+        let child = pending[0];
+
+        let (url, request) = tree.navigate(child).unwrap();
+        assert_eq!(url, "http://other.com/");
+        assert!(request.starts_with(b"GET / HTTP/1.1"));
+
+        let nested = tree
+            .process_response(
+                child,
+                &response("<body style=\"background-color: red\"><p>Hi</p></body>"),
+            )
+            .unwrap();
+        assert!(nested.is_empty());
+        assert_eq!(tree.get(child).unwrap().state(), LoadState::Complete);
+
+        let rect = tree.get(child).unwrap().rect();
+        let output = tree.composite(top).unwrap();
+        let inside = (rect.y as usize + 10) * 400 + rect.x as usize + 10;
+        assert_eq!(output[inside], 0xFFFF0000);
+        assert_eq!(tree.get(top).unwrap().surface()[inside], 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn test_same_origin_script_access() {
+        let mut tree = FrameTree::new("http://example.com/", 800, 600);
+        let top = tree.top();
+        let pending = tree
+            .load_html(
+                top,
+                "<body><iframe src=\"/same.html\"></iframe>\
+                 <iframe src=\"http://evil.com/\"></iframe>\
+                 <iframe srcdoc=\"<p>inline</p>\"></iframe></body>",
+            )
+            .unwrap();
+        assert_eq!(pending.len(), 2);
+        let same = pending[0];
+        let cross = pending[1];
+        let srcdoc = tree.get(top).unwrap().children()[2];
+
+        assert!(tree.can_access(top, same));
+        assert!(tree.can_access(same, top));
+        assert!(tree.can_access(top, srcdoc));
+        assert_eq!(tree.get(srcdoc).unwrap().url(), "about:srcdoc");
+
+        assert!(!tree.can_access(top, cross));
+        assert!(!tree.can_access(cross, top));
+        assert!(matches!(
+            tree.access_document(cross, top),
+            Err(FrameError::SecurityError(_))
+        ));
+        assert!(tree.access_document(top, same).is_ok());
+    }
+
+    #[test]
+    fn test_sandbox_gets_opaque_origin() {
+        let mut tree = FrameTree::new("http://example.com/", 800, 600);
+        let top = tree.top();
+        tree.load_html(
+            top,
+            "<iframe sandbox=\"allow-scripts\" srcdoc=\"<iframe srcdoc=x></iframe>\"></iframe>\
+             <iframe sandbox=\"allow-same-origin\" srcdoc=\"y\"></iframe>",
+        )
+        .unwrap();
+        let children = tree.get(top).unwrap().children().to_vec();
+        let sandboxed = tree.get(children[0]).unwrap();
+        assert!(sandboxed.origin().is_opaque());
+        assert!(sandboxed.scripts_enabled());
+        assert!(!tree.can_access(children[0], top));
+
+        // Nested frames inherit the sandbox
+        let nested = tree.get(sandboxed.children()[0]).unwrap();
+        assert_eq!(nested.sandbox(), sandboxed.sandbox());
+
+        let same_origin = tree.get(children[1]).unwrap();
+        assert!(!same_origin.scripts_enabled());
+        assert!(tree.can_access(top, children[1]));
+        assert!(!tree.can_access(children[1], top));
+    }
+
+    #[test]
+    fn test_recursive_frames_are_bounded() {
+        let mut tree = FrameTree::new("http://example.com/", 800, 600);
+        let top = tree.top();
+        let pending = tree
+            .load_html(top, "<iframe src=\"http://example.com/\"></iframe>")
+            .unwrap();
+        assert!(pending.is_empty());
+        let child = tree.get(top).unwrap().children()[0];
+        assert_eq!(tree.get(child).unwrap().url(), "about:blank");
+
+        tree.set_max_depth(2);
+        let html = "<iframe srcdoc=\"<iframe srcdoc='<iframe></iframe>'></iframe>\"></iframe>";
+        tree.load_html(top, html).unwrap();
+        assert_eq!(tree.len(), 3);
+
+        tree.detach(tree.get(top).unwrap().children()[0]);
+        assert_eq!(tree.len(), 1);
+        assert!(tree.get(top).unwrap().children().is_empty());
+    }
+}
@@ -15,12 +15,17 @@ pub mod a11y;
 pub mod account;
 pub mod apps;
 pub mod browser;
+pub mod color_scheme;
+pub mod cookie;
 pub mod csp;
 pub mod design;
 pub mod document;
 pub mod events;
+pub mod fetch;
+pub mod find;
 pub mod fs_bridge;
 pub mod i18n;
+pub mod iframe;
 pub mod input;
 pub mod input_bridge;
 pub mod kernel_bridge;
@@ -30,6 +35,7 @@ pub mod network_bridge;
 pub mod pipeline;
 pub mod pwa;
 pub mod renderer;
+pub mod storage;
 pub mod tabs;
 pub mod ui;
 pub mod window;
@@ -40,6 +46,7 @@ mod tests;
 pub use browser::Browser;
 pub use csp::{CspCheck, CspContext, CspPolicy};
 pub use document::Document;
+pub use iframe::{BrowsingContext, BrowsingContextId, FrameError, FrameTree};
 pub use input::{DomInputEvent, HitTestResult, InputManager, RawInputEvent};
 pub use loader::{DocumentLoader, LoadResult, LoaderError, PageLoader};
 pub use navigation::Navigator;
@@ -8,9 +8,11 @@ use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use kpio_network::{HttpClient, HttpError, HttpRequest, HttpResponse, StatusCode, Url};
+use kpio_network::{HeaderMap, HttpClient, HttpError, HttpRequest, HttpResponse, StatusCode, Url};
 
+use crate::i18n::I18N;
 use crate::pipeline::{PipelineError, RenderPipeline};
+use crate::BrowserConfig;
 
 /// Resource type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -154,9 +156,19 @@ pub struct PageLoader {
 impl PageLoader {
     /// Create a new page loader.
     pub fn new() -> Self {
+        Self::with_config(&BrowserConfig::default())
+    }
+
+    /// Create a page loader that sends the configured user agent and the
+    /// current locale's `Accept-Language` with every request.
+    pub fn with_config(config: &BrowserConfig) -> Self {
+        let mut defaults = HeaderMap::new();
+        defaults.insert("Accept-Language".to_string(), I18N.read().accept_language());
+
         Self {
             client: HttpClient::new()
-                .user_agent("KPIO-Browser/0.1 (KPIO OS)")
+                .user_agent(&config.user_agent)
+                .with_defaults(defaults)
                 .max_redirects(10)
                 .timeout(30000),
             base_url: None,
@@ -240,12 +252,9 @@ impl PageLoader {
         let request = HttpRequest::get(&parsed.path_and_query())
             .host(&parsed.host_port())
             .header("Accept", resource_type.accept_header())
-            .header("Accept-Language", "en-US,en;q=0.9")
-            .header("Accept-Encoding", "identity")
-            .header("Connection", "close")
-            .user_agent("KPIO-Browser/0.1 (KPIO OS)");
+            .header("Connection", "close");
 
-        Ok((parsed, request))
+        Ok((parsed, self.client.apply_defaults(request)))
     }
 
     /// Simulate receiving response data.
@@ -477,8 +486,13 @@ pub struct DocumentLoader {
 impl DocumentLoader {
     /// Create a new document loader.
     pub fn new(width: u32, height: u32) -> Self {
+        Self::with_config(width, height, &BrowserConfig::default())
+    }
+
+    /// Create a document loader whose requests follow `config`.
+    pub fn with_config(width: u32, height: u32, config: &BrowserConfig) -> Self {
         Self {
-            loader: PageLoader::new(),
+            loader: PageLoader::with_config(config),
             cache: ResourceCache::default(),
             pipeline: RenderPipeline::new(width as f32, height as f32),
         }
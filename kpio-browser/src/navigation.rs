@@ -1,11 +1,24 @@
 //! Navigation and URL handling.
 //!
-//! Manages browser navigation history.
+//! Manages browser navigation history, and stops navigations to sites
+//! whose TLS certificate fails verification.
+//!
+//! Such a site is replaced by a [`CertificateInterstitial`]. The user can
+//! go back, or proceed, which pins the rejected certificate for the host
+//! in the storage VFS. Only that exact certificate is trusted: if the
+//! site later presents a different one, the error page is shown again.
 
+use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::fmt::Write;
+
+use kpio_network::tls::TlsError;
+use spin::Mutex;
 
 use crate::browser::BrowserError;
+use crate::fs_bridge::fs_bridge;
+use crate::network_bridge::{network_bridge, CertificateFailure, Fingerprint, NetError};
 
 /// Navigator - handles URL parsing and history.
 pub struct Navigator {
@@ -294,3 +307,269 @@ pub fn resolve_url(base: &Url, relative: &str) -> Url {
         url
     }
 }
+
+/// A navigation stopped because the site's certificate failed verification.
+#[derive(Debug, Clone)]
+pub struct CertificateInterstitial {
+    /// URL the user tried to open.
+    pub url: Url,
+    /// Why the certificate was rejected.
+    pub error: TlsError,
+    /// Fingerprint of the rejected certificate.
+    pub fingerprint: Fingerprint,
+}
+
+impl CertificateInterstitial {
+    /// Get the interstitial for a network error, if it is a certificate
+    /// error the user may override.
+    pub fn intercept(url: &Url, error: NetError) -> Option<Self> {
+        match error {
+            NetError::Certificate(CertificateFailure { fault, fingerprint }) => Some(Self {
+                url: url.clone(),
+                error: fault.tls_error(),
+                fingerprint,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Explain the error to the user.
+    pub fn message(&self) -> String {
+        let host = &self.url.host;
+        match self.error {
+            TlsError::HostnameMismatch => {
+                alloc::format!(
+                    "The certificate presented by {} is for a different site.",
+                    host
+                )
+            }
+            TlsError::CertificateExpired => {
+                alloc::format!("The certificate presented by {} has expired.", host)
+            }
+            TlsError::UnknownCa => alloc::format!(
+                "The certificate presented by {} was not issued by a trusted authority.",
+                host
+            ),
+            _ => alloc::format!("The certificate presented by {} is not valid.", host),
+        }
+    }
+
+    /// Build the error page shown in place of the site.
+    pub fn html(&self) -> String {
+        alloc::format!(
+            r#"<html>
+<head><title>Privacy error</title></head>
+<body style="font-family: sans-serif; margin: 48px;">
+    <h1 style="color: #b3261e;">Your connection is not private</h1>
+    <p>{}</p>
+    <p>Someone may be trying to read or change what you send to this site.</p>
+    <p style="color: #666;">{}<br>SHA-256: {}</p>
+</body>
+</html>"#,
+            escape_html(&self.message()),
+            escape_html(&self.error.to_string()),
+            format_fingerprint(&self.fingerprint)
+        )
+    }
+
+    /// Trust the rejected certificate for this host from now on.
+    pub fn proceed(&self) {
+        pin_certificate(&self.url.host, &self.fingerprint);
+    }
+}
+
+/// Check the certificate of a URL before navigating to it.
+///
+/// Returns the interstitial to show if the certificate has an error the
+/// user may override; other TLS failures fail the navigation.
+pub fn check_certificate(url: &Url) -> Result<Option<CertificateInterstitial>, BrowserError> {
+    if url.scheme != "https" {
+        return Ok(None);
+    }
+    match network_bridge().tls_handshake(&url.host) {
+        Ok(_) => Ok(None),
+        Err(error) => CertificateInterstitial::intercept(url, error)
+            .map(Some)
+            .ok_or_else(|| BrowserError::NetworkError(alloc::format!("{:?}", error))),
+    }
+}
+
+/// Certificates the user chose to trust despite errors, by host.
+///
+/// `None` until loaded from the VFS.
+static CERTIFICATE_PINS: Mutex<Option<BTreeMap<String, Fingerprint>>> = Mutex::new(None);
+
+/// VFS path of the pinned certificates, one `host fingerprint` per line.
+const CERTIFICATE_PINS_PATH: &str = "/apps/storage/certificate_pins";
+
+/// Run `f` with the pinned certificates, loading them the first time.
+fn with_certificate_pins<R>(f: impl FnOnce(&mut BTreeMap<String, Fingerprint>) -> R) -> R {
+    let mut pins = CERTIFICATE_PINS.lock();
+    let pins = pins.get_or_insert_with(|| {
+        let text = fs_bridge()
+            .read_file(CERTIFICATE_PINS_PATH)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default();
+        text.lines()
+            .filter_map(|line| {
+                let (host, fingerprint) = line.split_once(' ')?;
+                Some((String::from(host), parse_fingerprint(fingerprint)?))
+            })
+            .collect()
+    });
+    f(pins)
+}
+
+/// Write the pinned certificates back to the VFS.
+fn persist_certificate_pins(pins: &BTreeMap<String, Fingerprint>) {
+    let mut text = String::new();
+    for (host, fingerprint) in pins {
+        let _ = writeln!(text, "{} {}", host, format_fingerprint(fingerprint));
+    }
+    let fs = fs_bridge();
+    let _ = fs.create_dir_all("/apps/storage");
+    let _ = fs.write_file(CERTIFICATE_PINS_PATH, text.as_bytes());
+}
+
+/// Check whether the user trusted exactly this certificate for `host`.
+pub fn is_certificate_pinned(host: &str, fingerprint: &Fingerprint) -> bool {
+    with_certificate_pins(|pins| pins.get(host) == Some(fingerprint))
+}
+
+/// Trust a certificate for `host`, replacing any certificate trusted
+/// for it before.
+pub fn pin_certificate(host: &str, fingerprint: &Fingerprint) {
+    with_certificate_pins(|pins| {
+        pins.insert(String::from(host), *fingerprint);
+        persist_certificate_pins(pins);
+    });
+}
+
+/// Stop trusting the certificate pinned for `host`. Returns whether one
+/// was pinned.
+pub fn forget_certificate(host: &str) -> bool {
+    with_certificate_pins(|pins| {
+        let removed = pins.remove(host).is_some();
+        if removed {
+            persist_certificate_pins(pins);
+        }
+        removed
+    })
+}
+
+/// Format a fingerprint as colon-separated hex bytes.
+fn format_fingerprint(fingerprint: &Fingerprint) -> String {
+    let mut text = String::with_capacity(fingerprint.len() * 3);
+    for (i, byte) in fingerprint.iter().enumerate() {
+        if i > 0 {
+            text.push(':');
+        }
+        let _ = write!(text, "{:02X}", byte);
+    }
+    text
+}
+
+/// Parse a fingerprint written by [`format_fingerprint`].
+fn parse_fingerprint(text: &str) -> Option<Fingerprint> {
+    let mut fingerprint = [0u8; 32];
+    let mut bytes = text.split(':');
+    for byte in fingerprint.iter_mut() {
+        *byte = u8::from_str_radix(bytes.next()?, 16).ok()?;
+    }
+    bytes.next().is_none().then_some(fingerprint)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(href: &str) -> Url {
+        Navigator::new().parse_url(href).unwrap()
+    }
+
+    #[test]
+    fn test_certificate_error_interstitial() {
+        let url = parse("https://expired.badssl.com/");
+        let interstitial = check_certificate(&url).unwrap().unwrap();
+        assert!(matches!(interstitial.error, TlsError::CertificateExpired));
+        assert!(interstitial.message().contains("has expired"));
+        assert!(interstitial
+            .html()
+            .contains(&format_fingerprint(&interstitial.fingerprint)));
+
+        // Valid certificates and plain HTTP go straight through
+        assert!(check_certificate(&parse("https://example.com"))
+            .unwrap()
+            .is_none());
+        assert!(check_certificate(&parse("http://expired.badssl.com"))
+            .unwrap()
+            .is_none());
+
+        // Only certificate errors are shown as an interstitial
+        assert!(CertificateInterstitial::intercept(&url, NetError::TlsError).is_none());
+    }
+
+    #[test]
+    fn test_proceed_pins_exact_certificate() {
+        let url = parse("https://untrusted-root.badssl.com/login");
+        let interstitial = check_certificate(&url).unwrap().unwrap();
+        assert!(matches!(interstitial.error, TlsError::UnknownCa));
+
+        interstitial.proceed();
+        assert!(check_certificate(&url).unwrap().is_none());
+        assert!(network_bridge()
+            .http_get("https://untrusted-root.badssl.com/")
+            .is_ok());
+
+        // A different certificate for the same host is not trusted
+        let mut other = interstitial.fingerprint;
+        other[0] ^= 1;
+        assert!(!is_certificate_pinned("untrusted-root.badssl.com", &other));
+        assert!(!is_certificate_pinned(
+            "badssl.com",
+            &interstitial.fingerprint
+        ));
+
+        assert!(forget_certificate("untrusted-root.badssl.com"));
+        assert!(check_certificate(&url).unwrap().is_some());
+        assert!(!forget_certificate("untrusted-root.badssl.com"));
+    }
+
+    #[test]
+    fn test_browser_shows_certificate_error() {
+        let mut browser = crate::Browser::new();
+        browser.navigate("https://wrong.host.badssl.com/").unwrap();
+        assert_eq!(browser.state(), crate::browser::BrowserState::Error);
+        assert_eq!(browser.title().as_deref(), Some("Privacy error"));
+        let error = browser.certificate_error().unwrap();
+        assert!(matches!(error.error, TlsError::HostnameMismatch));
+
+        browser.proceed_past_certificate_error().unwrap();
+        assert!(browser.certificate_error().is_none());
+        assert_eq!(browser.state(), crate::browser::BrowserState::Ready);
+        assert_eq!(
+            browser.current_url().as_deref(),
+            Some("https://wrong.host.badssl.com/")
+        );
+
+        browser.reload().unwrap();
+        assert!(browser.certificate_error().is_none());
+        forget_certificate("wrong.host.badssl.com");
+    }
+
+    #[test]
+    fn test_fingerprint_round_trip() {
+        let fingerprint: Fingerprint = core::array::from_fn(|i| (i * 37) as u8);
+        let text = format_fingerprint(&fingerprint);
+        assert!(text.starts_with("00:25:4A:"));
+        assert_eq!(parse_fingerprint(&text), Some(fingerprint));
+        assert_eq!(parse_fingerprint("Hello"), None);
+        assert_eq!(parse_fingerprint(&alloc::format!("{}:00", text)), None);
+    }
+}
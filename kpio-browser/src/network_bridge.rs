@@ -8,6 +8,10 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
+use kpio_network::tls::{Certificate, CertificateError, RootCertStore, TlsError};
+
+use crate::navigation::is_certificate_pinned;
+
 /// Socket descriptor type
 pub type SocketFd = i32;
 
@@ -200,6 +204,44 @@ pub enum Shutdown {
     Both,
 }
 
+/// HTTP request
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    /// Request method
+    pub method: String,
+    /// Absolute URL
+    pub url: String,
+    /// Request headers
+    pub headers: Vec<(String, String)>,
+    /// Request body
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Create a request with no headers or body
+    pub fn new(method: &str, url: &str) -> Self {
+        Self {
+            method: String::from(method),
+            url: String::from(url),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Create a GET request
+    pub fn get(url: &str) -> Self {
+        Self::new("GET", url)
+    }
+
+    /// Get header value
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
 /// HTTP response
 #[derive(Debug, Clone)]
 pub struct HttpResponse {
@@ -291,14 +333,23 @@ impl NetworkBridge {
             "localhost" => Ok(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]),
             "example.com" => Ok(vec![IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34))]),
             "google.com" => Ok(vec![IpAddr::V4(Ipv4Addr::new(142, 250, 80, 46))]),
+            #[cfg(test)]
+            "expired.badssl.com" | "wrong.host.badssl.com" | "untrusted-root.badssl.com" => {
+                Ok(vec![IpAddr::V4(Ipv4Addr::new(104, 154, 89, 105))])
+            }
             _ => Err(NetError::DnsError),
         }
     }
 
     /// Simple HTTP GET request
     pub fn http_get(&self, url: &str) -> Result<HttpResponse, NetError> {
+        self.http_request(&HttpRequest::get(url))
+    }
+
+    /// Send an HTTP request
+    pub fn http_request(&self, request: &HttpRequest) -> Result<HttpResponse, NetError> {
         // Parse URL
-        let (host, port, path) = self.parse_url(url)?;
+        let (host, port, path) = self.parse_url(&request.url)?;
 
         // Resolve DNS
         let ips = self.resolve_dns(&host)?;
@@ -306,14 +357,24 @@ impl NetworkBridge {
 
         // Connect
         let mut socket = self.tcp_connect(&ip.to_string(), port)?;
+        if request.url.trim_start().starts_with("https://") {
+            self.tls_handshake(&host)?;
+        }
 
         // Send HTTP request
-        let request = alloc::format!(
-            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
-            path,
-            host
-        );
-        socket.send(request.as_bytes())?;
+        let mut head = alloc::format!("{} {} HTTP/1.1\r\nHost: {}\r\n", request.method, path, host);
+        for (name, value) in &request.headers {
+            head.push_str(&alloc::format!("{}: {}\r\n", name, value));
+        }
+        if !request.body.is_empty() {
+            head.push_str(&alloc::format!(
+                "Content-Length: {}\r\n",
+                request.body.len()
+            ));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        socket.send(head.as_bytes())?;
+        socket.send(&request.body)?;
 
         // Read response (mock)
         // TODO: Actually read from socket
@@ -329,6 +390,40 @@ impl NetworkBridge {
         })
     }
 
+    /// Verify the certificate `host` presents over TLS
+    ///
+    /// Returns the fingerprint of its leaf certificate. A certificate that
+    /// fails verification is still accepted if the user pinned exactly that
+    /// certificate for `host` from the certificate error page.
+    pub fn tls_handshake(&self, host: &str) -> Result<Fingerprint, NetError> {
+        let presented = server_certificates(host);
+        let leaf = presented.chain.first().ok_or(NetError::TlsError)?;
+        let fingerprint = leaf.fingerprint_sha256();
+
+        let verified = if leaf.verify_hostname(host) {
+            presented
+                .roots
+                .verify(&presented.chain, presented.now)
+                .map_err(|error| match error {
+                    CertificateError::UnknownIssuer => TlsError::UnknownCa,
+                    CertificateError::Expired => TlsError::CertificateExpired,
+                    error => TlsError::CertificateError(error),
+                })
+        } else {
+            Err(TlsError::HostnameMismatch)
+        };
+
+        match verified.map_err(|error| CertificateFault::from_tls(&error)) {
+            Ok(()) => Ok(fingerprint),
+            Err(Some(_)) if is_certificate_pinned(host, &fingerprint) => Ok(fingerprint),
+            Err(Some(fault)) => Err(NetError::Certificate(CertificateFailure {
+                fault,
+                fingerprint,
+            })),
+            Err(None) => Err(NetError::TlsError),
+        }
+    }
+
     /// Parse IP address string
     fn parse_ip(&self, ip: &str) -> Result<IpAddr, NetError> {
         // Try IPv4
@@ -410,6 +505,102 @@ impl Default for NetworkBridge {
     }
 }
 
+/// Something that can send HTTP requests
+///
+/// `fetch` goes through this so that it can run over the network bridge
+/// or a scripted server in tests.
+pub trait HttpTransport {
+    /// Send a request and wait for the whole response
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, NetError>;
+}
+
+impl HttpTransport for NetworkBridge {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, NetError> {
+        self.http_request(request)
+    }
+}
+
+impl kpio_css::StylesheetLoader for NetworkBridge {
+    fn load(&self, url: &str) -> Option<String> {
+        let response = self.http_get(url).ok()?;
+        if response.status != 200 {
+            return None;
+        }
+        response.text().ok()
+    }
+}
+
+/// Certificates a server presents in the TLS handshake, with what to
+/// verify them against
+struct ServerCertificates {
+    /// Chain as presented, leaf first
+    chain: Vec<Certificate>,
+    /// Trusted root certificates
+    roots: RootCertStore,
+    /// Unix time to check validity periods at, if known
+    now: Option<i64>,
+}
+
+/// Get the certificates `host` presents
+///
+/// The socket syscalls do not carry TLS records yet, so no server presents
+/// a certificate and every handshake fails with `NetError::TlsError`.
+#[cfg(not(test))]
+fn server_certificates(_host: &str) -> ServerCertificates {
+    ServerCertificates {
+        chain: Vec::new(),
+        roots: RootCertStore::empty(),
+        now: None,
+    }
+}
+
+#[cfg(test)]
+use tests::server_certificates;
+
+/// SHA-256 fingerprint of a certificate
+pub type Fingerprint = [u8; 32];
+
+/// Certificate verification failures the user may choose to override
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateFault {
+    /// The certificate is not valid for the host name
+    HostnameMismatch,
+    /// The certificate has expired
+    Expired,
+    /// The certificate was not issued by a trusted authority
+    UnknownCa,
+}
+
+impl CertificateFault {
+    /// Get the fault behind a TLS error, if the user may override it
+    pub fn from_tls(error: &TlsError) -> Option<Self> {
+        match error {
+            TlsError::HostnameMismatch => Some(CertificateFault::HostnameMismatch),
+            TlsError::CertificateExpired => Some(CertificateFault::Expired),
+            TlsError::UnknownCa => Some(CertificateFault::UnknownCa),
+            _ => None,
+        }
+    }
+
+    /// Get the TLS error reported for this fault
+    pub fn tls_error(self) -> TlsError {
+        match self {
+            CertificateFault::HostnameMismatch => TlsError::HostnameMismatch,
+            CertificateFault::Expired => TlsError::CertificateExpired,
+            CertificateFault::UnknownCa => TlsError::UnknownCa,
+        }
+    }
+}
+
+/// A server certificate rejected during the TLS handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CertificateFailure {
+    /// Why verification failed
+    pub fault: CertificateFault,
+    /// Fingerprint of the rejected leaf certificate
+    pub fingerprint: Fingerprint,
+}
+
 /// TCP listener
 pub struct TcpListener {
     /// Socket FD
@@ -494,6 +685,8 @@ pub enum NetError {
     InvalidUrl,
     /// TLS error
     TlsError,
+    /// Server certificate failed verification
+    Certificate(CertificateFailure),
 }
 
 /// Global network bridge instance
@@ -508,6 +701,141 @@ pub fn network_bridge() -> &'static NetworkBridge {
 mod tests {
     use super::*;
 
+    /// 2024-01-01T00:00:00Z
+    const NOW: i64 = 1_704_067_200;
+    const VALID: (&str, &str) = ("230101000000Z", "300101000000Z");
+    const EXPIRED: (&str, &str) = ("200101000000Z", "230101000000Z");
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        match content.len() {
+            len @ 0..=0x7F => out.push(len as u8),
+            len @ 0x80..=0xFF => out.extend_from_slice(&[0x81, len as u8]),
+            len => {
+                out.push(0x82);
+                out.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn name(common_name: &str) -> Vec<u8> {
+        let attribute = [
+            tlv(0x06, &[0x55, 0x04, 0x03]),
+            tlv(0x0C, common_name.as_bytes()),
+        ]
+        .concat();
+        tlv(0x30, &tlv(0x31, &tlv(0x30, &attribute)))
+    }
+
+    /// Build a certificate with a distinct public key per `key`; CA
+    /// certificates carry Basic Constraints.
+    fn issue(
+        subject: &str,
+        issuer: &str,
+        key: u8,
+        ca: bool,
+        validity: (&str, &str),
+    ) -> Certificate {
+        let algorithm = tlv(
+            0x30,
+            &[
+                tlv(
+                    0x06,
+                    &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B],
+                ),
+                tlv(0x05, &[]),
+            ]
+            .concat(),
+        );
+        let public_key = tlv(
+            0x30,
+            &[
+                tlv(
+                    0x30,
+                    &tlv(0x06, &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01]),
+                ),
+                tlv(0x03, &[0, 4, key, key]),
+            ]
+            .concat(),
+        );
+        let validity = [
+            tlv(0x17, validity.0.as_bytes()),
+            tlv(0x17, validity.1.as_bytes()),
+        ]
+        .concat();
+
+        let mut tbs = [
+            tlv(0xA0, &tlv(0x02, &[2])),
+            tlv(0x02, &[key]),
+            algorithm.clone(),
+            name(issuer),
+            tlv(0x30, &validity),
+            name(subject),
+            public_key,
+        ]
+        .concat();
+        if ca {
+            let extension = [
+                tlv(0x06, &[0x55, 0x1D, 0x13]),
+                tlv(0x01, &[0xFF]),
+                tlv(0x04, &tlv(0x30, &tlv(0x01, &[0xFF]))),
+            ]
+            .concat();
+            tbs.extend(tlv(0xA3, &tlv(0x30, &tlv(0x30, &extension))));
+        }
+
+        let der = tlv(
+            0x30,
+            &[tlv(0x30, &tbs), algorithm, tlv(0x03, &[0, 1, 2, 3])].concat(),
+        );
+        Certificate::from_der(&der).unwrap()
+    }
+
+    /// Test servers: a valid certificate for the mock hosts, and the
+    /// badssl.com failures for the certificate error page.
+    pub(super) fn server_certificates(host: &str) -> ServerCertificates {
+        let root = issue("Test Root", "Test Root", 1, true, VALID);
+        let leaf = match host {
+            "expired.badssl.com" => issue(host, "Test Root", 2, false, EXPIRED),
+            "wrong.host.badssl.com" => issue("*.badssl.com", "Test Root", 3, false, VALID),
+            "untrusted-root.badssl.com" => issue(host, "Untrusted Root", 4, false, VALID),
+            _ => issue(host, "Test Root", 5, false, VALID),
+        };
+
+        let mut roots = RootCertStore::empty();
+        roots.add(root.clone()).unwrap();
+        ServerCertificates {
+            chain: vec![leaf, root],
+            roots,
+            now: Some(NOW),
+        }
+    }
+
+    #[test]
+    fn test_tls_handshake_verifies_chain() {
+        let net = NetworkBridge::new();
+        let fingerprint = net.tls_handshake("example.com").unwrap();
+        let presented = server_certificates("example.com");
+        assert_eq!(fingerprint, presented.chain[0].fingerprint_sha256());
+
+        for (host, fault) in [
+            ("expired.badssl.com", CertificateFault::Expired),
+            ("wrong.host.badssl.com", CertificateFault::HostnameMismatch),
+            ("untrusted-root.badssl.com", CertificateFault::UnknownCa),
+        ] {
+            let expected = server_certificates(host).chain[0].fingerprint_sha256();
+            match net.tls_handshake(host) {
+                Err(NetError::Certificate(failure)) => {
+                    assert_eq!(failure.fault, fault, "{}", host);
+                    assert_eq!(failure.fingerprint, expected, "{}", host);
+                }
+                other => panic!("{}: {:?}", host, other),
+            }
+        }
+    }
+
     #[test]
     fn test_tcp_connect() {
         let net = NetworkBridge::new();
@@ -25,6 +25,8 @@ use kpio_layout::paint::{BorderStyle, BorderWidths, TextStyle};
 use kpio_layout::{BoxDimensions, DisplayCommand, DisplayList, EdgeSizes, Rect};
 use libm::ceilf;
 
+use crate::iframe::IframeElement;
+
 /// A color in RGBA format (pipeline-local type).
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct PipelineColor {
@@ -152,6 +154,27 @@ impl RenderPipeline {
         Ok(())
     }
 
+    /// Lay out HTML and return the boxes reserved for its `<iframe>` elements.
+    ///
+    /// Frames are returned in document order; the index of each box matches
+    /// the `image_id` of the `DisplayCommand::Image` painted in its place.
+    pub fn layout_frames(&self, html: &str) -> Result<Vec<FrameBox>, PipelineError> {
+        let tree = self.parse_and_layout(html)?;
+
+        let mut frames = Vec::new();
+        for node in &tree.nodes {
+            if let Some(index) = node.frame {
+                frames.push(FrameBox {
+                    index,
+                    element: tree.frames[index].clone(),
+                    rect: node.dimensions.content,
+                });
+            }
+        }
+
+        Ok(frames)
+    }
+
     /// Parse HTML and build layout tree.
     fn parse_and_layout(&self, html: &str) -> Result<LayoutTree, PipelineError> {
         let mut tree = LayoutTree::new();
@@ -192,12 +215,33 @@ impl RenderPipeline {
                         continue;
                     }
 
+                    // Iframes are replaced elements: their fallback content
+                    // is never rendered, the child browsing context is.
+                    if matches!(box_type, LayoutBoxType::Replaced) {
+                        let frame = tree.add_frame(IframeElement::from_attributes(&attrs));
+                        let node = LayoutNode {
+                            box_type,
+                            style,
+                            dimensions: BoxDimensions::default(),
+                            text: None,
+                            children: Vec::new(),
+                            frame: Some(frame),
+                        };
+                        tree.add(node, parent_id);
+
+                        if !self_closing {
+                            parser.skip_until_close(&name);
+                        }
+                        continue;
+                    }
+
                     let node = LayoutNode {
                         box_type,
                         style,
                         dimensions: BoxDimensions::default(),
                         text: None,
                         children: Vec::new(),
+                        frame: None,
                     };
 
                     let node_id = tree.add(node, parent_id);
@@ -220,6 +264,7 @@ impl RenderPipeline {
                             dimensions: BoxDimensions::default(),
                             text: Some(trimmed.to_string()),
                             children: Vec::new(),
+                            frame: None,
                         };
                         tree.add(node, parent_id);
                     }
@@ -248,6 +293,8 @@ impl RenderPipeline {
             "head" | "script" | "style" | "meta" | "link" | "title" | "noscript" => {
                 LayoutBoxType::None
             }
+            // Replaced elements
+            "iframe" => LayoutBoxType::Replaced,
             // Default to inline
             _ => LayoutBoxType::Inline,
         };
@@ -293,6 +340,32 @@ impl RenderPipeline {
                 style.background_color = Some(PipelineColor::rgb(128, 128, 128));
                 style.margin = EdgeSizes::new(8.0, 0.0, 8.0, 0.0);
             }
+            "iframe" => {
+                // Default object size of a replaced element is 300x150
+                style.width = Some(300.0);
+                style.height = Some(150.0);
+                style.border = EdgeSizes::uniform(2.0);
+                style.border_color = Some(PipelineColor::rgb(118, 118, 118));
+
+                for (name, value) in attrs {
+                    match name.to_ascii_lowercase().as_str() {
+                        "width" => {
+                            if let Some(w) = parse_length(value) {
+                                style.width = Some(w);
+                            }
+                        }
+                        "height" => {
+                            if let Some(h) = parse_length(value) {
+                                style.height = Some(h);
+                            }
+                        }
+                        "frameborder" if value.trim() == "0" => {
+                            style.border = EdgeSizes::zero();
+                        }
+                        _ => {}
+                    }
+                }
+            }
             _ => {}
         }
 
@@ -407,6 +480,9 @@ impl RenderPipeline {
             LayoutBoxType::Inline => {
                 self.layout_inline(tree, node_id, containing_block, y_offset, &style, &text)
             }
+            LayoutBoxType::Replaced => {
+                self.layout_replaced(tree, node_id, containing_block, y_offset, &style)
+            }
             LayoutBoxType::None => y_offset,
         }
     }
@@ -508,6 +584,31 @@ impl RenderPipeline {
         y_offset + height
     }
 
+    /// Layout a replaced element (iframe) at its intrinsic or specified size.
+    fn layout_replaced(
+        &self,
+        tree: &mut LayoutTree,
+        node_id: usize,
+        containing_block: &Rect,
+        y_offset: f32,
+        style: &NodeStyle,
+    ) -> f32 {
+        let node = tree.get_mut(node_id).unwrap();
+
+        node.dimensions.content.x =
+            containing_block.x + style.margin.left + style.border.left + style.padding.left;
+        node.dimensions.content.y =
+            y_offset + style.margin.top + style.border.top + style.padding.top;
+        node.dimensions.content.width = style.width.unwrap_or(300.0).max(0.0);
+        node.dimensions.content.height = style.height.unwrap_or(150.0).max(0.0);
+
+        node.dimensions.margin = style.margin;
+        node.dimensions.padding = style.padding;
+        node.dimensions.border = style.border;
+
+        node.dimensions.margin_box().y + node.dimensions.margin_box().height
+    }
+
     /// Generate display list from layout tree.
     fn paint(&self, tree: &LayoutTree) -> DisplayList {
         let mut list = DisplayList::new();
@@ -568,6 +669,15 @@ impl RenderPipeline {
             });
         }
 
+        // Reserve the frame's content box for the child browsing context
+        if let Some(frame) = node.frame {
+            list.push(DisplayCommand::Image {
+                image_id: frame as u64,
+                source_rect: None,
+                dest_rect: node.dimensions.content,
+            });
+        }
+
         // Paint text
         if let Some(ref text) = node.text {
             list.push(DisplayCommand::Text {
@@ -901,6 +1011,8 @@ impl core::fmt::Display for PipelineError {
 pub enum LayoutBoxType {
     Block,
     Inline,
+    /// Replaced element whose content comes from elsewhere (iframe).
+    Replaced,
     None,
 }
 
@@ -948,12 +1060,26 @@ pub struct LayoutNode {
     pub dimensions: BoxDimensions,
     pub text: Option<String>,
     pub children: Vec<usize>,
+    /// Index into the tree's frames if this node is an iframe.
+    pub frame: Option<usize>,
+}
+
+/// Box laid out for an `<iframe>` element.
+#[derive(Debug, Clone)]
+pub struct FrameBox {
+    /// Frame index in document order.
+    pub index: usize,
+    /// Parsed iframe attributes.
+    pub element: IframeElement,
+    /// Content box the child browsing context is composited into.
+    pub rect: Rect,
 }
 
 /// Layout tree.
 pub struct LayoutTree {
     nodes: Vec<LayoutNode>,
     root: Option<usize>,
+    frames: Vec<IframeElement>,
 }
 
 impl LayoutTree {
@@ -961,9 +1087,21 @@ impl LayoutTree {
         Self {
             nodes: Vec::new(),
             root: None,
+            frames: Vec::new(),
         }
     }
 
+    /// Register an iframe element and return its frame index.
+    pub fn add_frame(&mut self, element: IframeElement) -> usize {
+        self.frames.push(element);
+        self.frames.len() - 1
+    }
+
+    /// Iframe elements in document order.
+    pub fn frames(&self) -> &[IframeElement] {
+        &self.frames
+    }
+
     pub fn add(&mut self, mut node: LayoutNode, parent: Option<usize>) -> usize {
         let id = self.nodes.len();
         self.nodes.push(node);
@@ -177,7 +177,7 @@ impl WebStorage {
             // Find key start
             if let Some(ks) = inner[pos..].find('"') {
                 let key_start = pos + ks + 1;
-                if let Some(ke) = inner[key_start..].find('"') {
+                if let Some(ke) = find_closing_quote(&inner[key_start..]) {
                     let key = unescape_json(&inner[key_start..key_start + ke]);
 
                     // Find value start (after ":")
@@ -186,7 +186,7 @@ impl WebStorage {
                         let after_colon = after_key + colon + 1;
                         if let Some(vs) = inner[after_colon..].find('"') {
                             let val_start = after_colon + vs + 1;
-                            if let Some(ve) = inner[val_start..].find('"') {
+                            if let Some(ve) = find_closing_quote(&inner[val_start..]) {
                                 let value = unescape_json(&inner[val_start..val_start + ve]);
                                 self.current_size += key.len() + value.len();
                                 self.data.insert(key, value);
@@ -224,6 +224,20 @@ fn escape_json(s: &str) -> String {
     out
 }
 
+/// Byte offset of the first `"` in `s` that is not escaped.
+fn find_closing_quote(s: &str) -> Option<usize> {
+    let mut escape = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escape => escape = false,
+            '\\' => escape = true,
+            '"' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
 fn unescape_json(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     let mut escape = false;
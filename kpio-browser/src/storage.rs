@@ -0,0 +1,433 @@
+//! Web Storage bindings.
+//!
+//! [`install`] gives page scripts the `localStorage` and `sessionStorage`
+//! globals of a document. Both are keyed by the document origin, so a
+//! page can only reach the items of its own origin:
+//!
+//! - `localStorage` areas are shared by every tab and written through to
+//!   the storage VFS under `/apps/storage/origins/`.
+//! - `sessionStorage` areas belong to one tab's [`Session`] and are
+//!   dropped with it.
+//!
+//! Each area holds at most 5 MB of keys and values; a `setItem` past that
+//! throws `QuotaExceededError`. Documents with an opaque origin
+//! (`about:`, `data:`) get no storage and every method throws
+//! `SecurityError`.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use kpio_js::interpreter::Interpreter;
+use kpio_js::object::{
+    BoundFunction, Callable, IntrinsicFunction, JsObject, PropertyDescriptor, PropertyKey,
+};
+use kpio_js::{Engine, JsError, JsResult, Value};
+use spin::Mutex;
+
+use crate::fs_bridge::fs_bridge;
+use crate::navigation::{Navigator, Url};
+use crate::pwa::web_storage::{StorageType, WebStorage};
+
+/// Storage areas of all origins.
+struct Areas {
+    /// `localStorage`, by origin. Loaded from the VFS on first use.
+    local: BTreeMap<String, WebStorage>,
+    /// `sessionStorage`, by session id and origin.
+    session: BTreeMap<(u64, String), WebStorage>,
+}
+
+static AREAS: Mutex<Areas> = Mutex::new(Areas {
+    local: BTreeMap::new(),
+    session: BTreeMap::new(),
+});
+
+/// Documents behind installed storage objects, by binding id.
+///
+/// As with `fetch`, ids are never reused, so a storage object a script
+/// kept from an earlier document stops working once the tab navigates.
+static BINDINGS: Mutex<BTreeMap<u64, Binding>> = Mutex::new(BTreeMap::new());
+
+/// Next binding or session id.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+struct Binding {
+    /// Document origin, `None` if it is opaque.
+    origin: Option<String>,
+    /// Session of the tab showing the document.
+    session: u64,
+}
+
+/// The `sessionStorage` of one tab.
+///
+/// Dropping the session discards its items.
+pub struct Session {
+    id: u64,
+}
+
+impl Session {
+    /// Start an empty session.
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        AREAS.lock().session.retain(|(id, _), _| *id != self.id);
+    }
+}
+
+/// Keeps installed storage objects connected to their document.
+///
+/// Once the handle is dropped, calls to their methods throw.
+pub struct StorageHandle {
+    id: u64,
+}
+
+impl Drop for StorageHandle {
+    fn drop(&mut self) {
+        BINDINGS.lock().remove(&self.id);
+    }
+}
+
+/// Define `localStorage` and `sessionStorage` in `engine` for the
+/// document at `document_url`, shown in the tab owning `session`.
+pub fn install(engine: &mut Engine, document_url: &str, session: &Session) -> StorageHandle {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let origin = Navigator::new()
+        .parse_url(document_url)
+        .ok()
+        .filter(Url::is_http)
+        .map(|url| url.origin());
+    BINDINGS.lock().insert(
+        id,
+        Binding {
+            origin,
+            session: session.id,
+        },
+    );
+
+    engine.define_global("localStorage", storage_object(id, StorageType::Local));
+    engine.define_global("sessionStorage", storage_object(id, StorageType::Session));
+    StorageHandle { id }
+}
+
+/// Run `f` with the `localStorage` area of `origin`, e.g. to inspect or
+/// clear site data.
+pub fn with_local_storage<R>(origin: &str, f: impl FnOnce(&mut WebStorage) -> R) -> R {
+    let mut areas = AREAS.lock();
+    let result = f(local_area(&mut areas, origin));
+    persist(&areas.local[origin]);
+    result
+}
+
+/// Get the `localStorage` area of `origin`, loading it from the VFS the
+/// first time.
+fn local_area<'a>(areas: &'a mut Areas, origin: &str) -> &'a mut WebStorage {
+    areas.local.entry(String::from(origin)).or_insert_with(|| {
+        let mut area = WebStorage::new(origin, 0, StorageType::Local);
+        if let Ok(bytes) = fs_bridge().read_file(&vfs_path(origin)) {
+            area.from_json(&String::from_utf8_lossy(&bytes));
+        }
+        area
+    })
+}
+
+/// Write a `localStorage` area back to the VFS.
+///
+/// The in-memory area stays authoritative, so a failed write does not
+/// fail the script's call.
+fn persist(area: &WebStorage) {
+    let fs = fs_bridge();
+    let path = vfs_path(area.origin());
+    if let Some((dir, _)) = path.rsplit_once('/') {
+        let _ = fs.create_dir_all(dir);
+    }
+    let _ = fs.write_file(&path, area.to_json().as_bytes());
+}
+
+/// VFS path of the `localStorage` of `origin`.
+fn vfs_path(origin: &str) -> String {
+    // "https://example.com:8080" -> "https_example.com_8080"
+    let dir: String = origin
+        .replace("://", "_")
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    alloc::format!("/apps/storage/origins/{}/local_storage.json", dir)
+}
+
+// JavaScript bindings
+
+/// Build a `Storage` object whose methods are bound to binding `id`.
+fn storage_object(id: u64, storage_type: StorageType) -> Value {
+    let kind = match storage_type {
+        StorageType::Local => 0.0,
+        StorageType::Session => 1.0,
+    };
+    let method = |name: &str, length: usize, func: NativeFn| {
+        Value::object(JsObject::function(Callable::Bound(BoundFunction {
+            target: Box::new(Callable::Intrinsic(IntrinsicFunction {
+                name: name.into(),
+                length,
+                func,
+            })),
+            bound_this: Value::undefined(),
+            bound_args: vec![Value::number(id as f64), Value::number(kind)],
+        })))
+    };
+
+    let mut obj = JsObject::new();
+    for (name, length, func) in [
+        ("getItem", 1, storage_get_item as NativeFn),
+        ("setItem", 2, storage_set_item),
+        ("removeItem", 1, storage_remove_item),
+        ("clear", 0, storage_clear),
+        ("key", 1, storage_key),
+    ] {
+        obj.define_property(
+            PropertyKey::string(name),
+            PropertyDescriptor::data(method(name, length, func), true, false, true),
+        );
+    }
+    obj.define_property(
+        PropertyKey::string("length"),
+        PropertyDescriptor::accessor(Some(method("length", 0, storage_length)), None, false, true),
+    );
+    Value::object(obj)
+}
+
+type NativeFn = fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>;
+
+/// A `DOMException` called `name`.
+///
+/// Errors thrown by native code only keep their message, so the name
+/// leads it.
+fn dom_exception(name: &str, message: &str) -> JsError {
+    JsError::error(name, alloc::format!("{}: {}", name, message))
+}
+
+/// Run `f` with the area a storage method was called on.
+///
+/// `args` starts with the bound binding id and storage type; `f` gets
+/// the rest.
+fn with_area<R>(args: &[Value], f: impl FnOnce(&mut WebStorage, &[Value]) -> R) -> JsResult<R> {
+    let id = args.first().map_or(Ok(0.0), Value::to_number)? as u64;
+    let session = args.get(1).map_or(Ok(0.0), Value::to_number)? != 0.0;
+    let rest = args.get(2..).unwrap_or(&[]);
+
+    let bindings = BINDINGS.lock();
+    let binding = bindings
+        .get(&id)
+        .ok_or_else(|| dom_exception("InvalidStateError", "The document was unloaded"))?;
+    let origin = binding.origin.as_deref().ok_or_else(|| {
+        dom_exception(
+            "SecurityError",
+            "Storage is disabled for documents with an opaque origin",
+        )
+    })?;
+
+    let mut areas = AREAS.lock();
+    if session {
+        let area = areas
+            .session
+            .entry((binding.session, String::from(origin)))
+            .or_insert_with(|| WebStorage::new(origin, 0, StorageType::Session));
+        Ok(f(area, rest))
+    } else {
+        let area = local_area(&mut areas, origin);
+        let result = f(area, rest);
+        persist(area);
+        Ok(result)
+    }
+}
+
+/// The string value of argument `index`.
+fn string_arg(args: &[Value], index: usize) -> JsResult<String> {
+    args.get(index).unwrap_or(&Value::undefined()).to_string()
+}
+
+fn storage_get_item(_interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    with_area(args, |area, args| {
+        let key = string_arg(args, 0)?;
+        Ok(area.get_item(&key).map_or(Value::null(), Value::string))
+    })?
+}
+
+fn storage_set_item(_interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    with_area(args, |area, args| {
+        let key = string_arg(args, 0)?;
+        let value = string_arg(args, 1)?;
+        area.set_item(&key, &value).map_err(|_| {
+            let message = alloc::format!("Setting the value of '{}' exceeded the quota", key);
+            dom_exception("QuotaExceededError", &message)
+        })?;
+        Ok(Value::undefined())
+    })?
+}
+
+fn storage_remove_item(
+    _interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    with_area(args, |area, args| {
+        area.remove_item(&string_arg(args, 0)?);
+        Ok(Value::undefined())
+    })?
+}
+
+fn storage_clear(_interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    with_area(args, |area, _| area.clear())?;
+    Ok(Value::undefined())
+}
+
+fn storage_key(_interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    with_area(args, |area, args| {
+        let index = args.first().map_or(Ok(0.0), Value::to_number)?;
+        let key = if index >= 0.0 {
+            area.key(index as usize)
+        } else {
+            None
+        };
+        Ok(key.map_or(Value::null(), Value::string))
+    })?
+}
+
+fn storage_length(_interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    with_area(args, |area, _| Value::number(area.length() as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(url: &str, session: &Session) -> (Engine, StorageHandle) {
+        let mut engine = Engine::new();
+        let handle = install(&mut engine, url, session);
+        (engine, handle)
+    }
+
+    fn eval(engine: &mut Engine, script: &str) -> String {
+        engine
+            .eval_script(script, "test")
+            .unwrap()
+            .to_string()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_local_storage_is_per_origin() {
+        let session = Session::new();
+        let (mut a, _ha) = engine("https://storage-a.test/page", &session);
+        let (mut a2, _ha2) = engine("https://storage-a.test/other", &Session::new());
+        let (mut b, _hb) = engine("https://storage-b.test/", &session);
+
+        eval(
+            &mut a,
+            "localStorage.setItem('user', 'alice'); localStorage.setItem('n', 42);",
+        );
+        assert_eq!(eval(&mut a2, "localStorage.getItem('user')"), "alice");
+        assert_eq!(eval(&mut a2, "typeof localStorage.getItem('n')"), "string");
+        assert_eq!(eval(&mut a2, "localStorage.length"), "2");
+        assert_eq!(
+            eval(&mut a2, "localStorage.key(0) + ',' + localStorage.key(5)"),
+            "n,null"
+        );
+
+        assert_eq!(eval(&mut b, "localStorage.getItem('user')"), "null");
+        assert_eq!(eval(&mut b, "localStorage.length"), "0");
+        with_local_storage("https://storage-b.test", |area| {
+            assert_eq!(area.length(), 0);
+        });
+
+        eval(&mut a, "localStorage.removeItem('n')");
+        assert_eq!(eval(&mut a2, "localStorage.length"), "1");
+        eval(&mut a2, "localStorage.clear()");
+        with_local_storage("https://storage-a.test", |area| {
+            assert_eq!(area.length(), 0);
+        });
+    }
+
+    #[test]
+    fn test_session_storage_is_per_tab() {
+        let session = Session::new();
+        let (mut first, _h1) = engine("https://session.test/", &session);
+        let (mut same_tab, _h2) = engine("https://session.test/next", &session);
+        let (mut other_tab, _h3) = engine("https://session.test/", &Session::new());
+
+        eval(&mut first, "sessionStorage.setItem('step', '2')");
+        assert_eq!(eval(&mut same_tab, "sessionStorage.getItem('step')"), "2");
+        assert_eq!(
+            eval(&mut other_tab, "sessionStorage.getItem('step')"),
+            "null"
+        );
+        assert_eq!(eval(&mut first, "localStorage.getItem('step')"), "null");
+
+        let id = session.id;
+        drop(session);
+        assert!(!AREAS.lock().session.keys().any(|(s, _)| *s == id));
+    }
+
+    #[test]
+    fn test_quota_exceeded() {
+        let session = Session::new();
+        let (mut engine, _h) = engine("https://quota.test/", &session);
+        with_local_storage("https://quota.test", |area| {
+            let big = "x".repeat(area.max_size() - 8);
+            area.set_item("big", &big).unwrap();
+        });
+
+        let result = eval(
+            &mut engine,
+            "var msg = ''; try { localStorage.setItem('more', 'data'); } \
+             catch (e) { msg = String(e); } msg",
+        );
+        assert!(result.starts_with("QuotaExceededError"));
+        assert_eq!(eval(&mut engine, "localStorage.getItem('more')"), "null");
+        assert_eq!(eval(&mut engine, "localStorage.length"), "1");
+        with_local_storage("https://quota.test", WebStorage::clear);
+    }
+
+    #[test]
+    fn test_opaque_origin_and_unloaded_document() {
+        let session = Session::new();
+        let (mut blank, _h) = engine("about:blank", &session);
+        let result = blank.eval_script("localStorage.getItem('a')", "test");
+        assert!(result.unwrap_err().message().starts_with("SecurityError"));
+
+        let (mut page, handle) = engine("https://unload.test/", &session);
+        eval(
+            &mut page,
+            "var kept = localStorage; kept.setItem('a', '1');",
+        );
+        drop(handle);
+        let result = page.eval_script("kept.getItem('a')", "test");
+        assert!(result
+            .unwrap_err()
+            .message()
+            .starts_with("InvalidStateError"));
+    }
+
+    #[test]
+    fn test_vfs_path() {
+        assert_eq!(
+            vfs_path("https://example.com:8080"),
+            "/apps/storage/origins/https_example.com_8080/local_storage.json"
+        );
+    }
+}
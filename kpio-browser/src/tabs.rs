@@ -1,16 +1,27 @@
 //! Tab management.
 //!
-//! Browser tab system.
+//! Browser tab system. [`TabManager`] also backs the extension
+//! `chrome.tabs` API through [`TabHost`].
 
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use kpio_js::Engine;
+use kpio_dom::FindOptions;
+use kpio_extensions::api::tabs::{
+    self as ext, CreateProperties, TabHost, TabStatus, UpdateProperties,
+};
+use kpio_extensions::api::{ApiError, ApiResult};
+use kpio_js::{ConsoleMessage, Engine};
 
 use crate::browser::{BrowserError, Key, KeyState, Modifiers, MouseButton, MouseState};
+use crate::color_scheme::preferred_color_scheme;
 use crate::document::Document;
-use crate::navigation::Url;
+use crate::fetch::{self, FetchClient, FetchHandle};
+use crate::find::FindInPage;
+use crate::navigation::{Navigator, Url};
+use crate::pipeline::RenderPipeline;
 use crate::renderer::Renderer;
+use crate::storage::{self, Session, StorageHandle};
 use crate::window::Window;
 
 /// Browser tab.
@@ -27,6 +38,13 @@ pub struct Tab {
     document: Option<Document>,
     /// JavaScript engine.
     js_engine: Engine,
+    /// Client behind the engine's `fetch()`, for the current document.
+    fetch: Option<FetchHandle>,
+    /// The tab's `sessionStorage`.
+    session: Session,
+    /// Binding behind the engine's `localStorage` and `sessionStorage`,
+    /// for the current document.
+    storage: Option<StorageHandle>,
     /// Renderer.
     renderer: Renderer,
     /// Window.
@@ -34,6 +52,8 @@ pub struct Tab {
     /// Scroll position.
     scroll_x: i32,
     scroll_y: i32,
+    /// Find-in-page state.
+    find: FindInPage,
 }
 
 impl Tab {
@@ -46,10 +66,14 @@ impl Tab {
             loading: false,
             document: None,
             js_engine: Engine::new(),
+            fetch: None,
+            session: Session::new(),
+            storage: None,
             renderer: Renderer::new(),
             window: Window::default(),
             scroll_x: 0,
             scroll_y: 0,
+            find: FindInPage::new(),
         }
     }
 
@@ -76,6 +100,7 @@ impl Tab {
     /// Load a URL.
     pub fn load_url(&mut self, url: &Url) -> Result<(), BrowserError> {
         self.loading = true;
+        self.find.clear();
         self.url = Some(url.clone());
 
         // Handle special URLs
@@ -95,6 +120,8 @@ impl Tab {
         // For HTTP URLs, we would fetch here
         // For now, just create an empty document
         self.document = Some(Document::new(&url.href()));
+        self.attach_fetch(&url.href());
+        self.attach_storage(&url.href());
         self.title = self.document.as_ref().map(|d| d.title().to_string());
 
         self.loading = false;
@@ -104,12 +131,15 @@ impl Tab {
     /// Load HTML content directly.
     pub fn load_html(&mut self, html: &str, url: &str) -> Result<(), BrowserError> {
         self.loading = true;
+        self.find.clear();
 
         let mut document = Document::from_html(html, url);
         document.compute_styles();
 
         self.title = Some(document.title().to_string());
         self.document = Some(document);
+        self.attach_fetch(url);
+        self.attach_storage(url);
 
         // Execute inline scripts
         self.execute_inline_scripts()?;
@@ -118,6 +148,23 @@ impl Tab {
         Ok(())
     }
 
+    /// Give scripts a `fetch()` that makes requests as the document at
+    /// `url`.
+    fn attach_fetch(&mut self, url: &str) {
+        self.fetch = Some(fetch::install(&mut self.js_engine, FetchClient::new(url)));
+    }
+
+    /// Give scripts the `localStorage` and `sessionStorage` of the
+    /// document at `url`.
+    fn attach_storage(&mut self, url: &str) {
+        self.storage = Some(storage::install(&mut self.js_engine, url, &self.session));
+    }
+
+    /// Get the `fetch()` client of the current document.
+    pub fn fetch_client(&self) -> Option<&FetchHandle> {
+        self.fetch.as_ref()
+    }
+
     /// Load about: page.
     fn load_about_page(&mut self, page: &str) {
         let html = match page {
@@ -153,19 +200,53 @@ impl Tab {
 
     /// Execute JavaScript.
     pub fn execute_script(&mut self, script: &str) -> Result<String, BrowserError> {
+        let source = self.url.as_ref().map(Url::href).unwrap_or_default();
         self.js_engine
-            .eval(script)
+            .eval_script(script, &source)
             .map(|v| v.to_string().unwrap_or_default())
             .map_err(|e| BrowserError::JavaScriptError(alloc::format!("{:?}", e)))
     }
 
-    /// Execute inline scripts in document.
+    /// Execute inline scripts in document order.
+    ///
+    /// A script that throws does not stop the ones after it; the error is
+    /// reported on the console, as in other browsers.
     fn execute_inline_scripts(&mut self) -> Result<(), BrowserError> {
-        // Would extract and execute <script> tags
-        // Simplified for now
+        let Some(document) = &self.document else {
+            return Ok(());
+        };
+
+        let scripts: Vec<String> = document
+            .elements_by_tag_name("script")
+            .iter()
+            .map(|script| {
+                script
+                    .borrow()
+                    .children
+                    .iter()
+                    .filter_map(|child| child.borrow().text_content.clone())
+                    .collect()
+            })
+            .collect();
+        let source = document.url().to_string();
+
+        for script in scripts.iter().filter(|s| !s.trim().is_empty()) {
+            let _ = self.js_engine.eval_script(script, &source);
+        }
         Ok(())
     }
 
+    /// Get the console messages and uncaught exceptions of page scripts
+    /// that have not been taken yet.
+    pub fn console_messages(&self) -> impl Iterator<Item = &ConsoleMessage> {
+        self.js_engine.console_messages()
+    }
+
+    /// Take the pending console messages, oldest first.
+    pub fn take_console_messages(&mut self) -> Vec<ConsoleMessage> {
+        self.js_engine.take_console_messages()
+    }
+
     /// Render tab to framebuffer.
     pub fn render(
         &mut self,
@@ -199,6 +280,17 @@ impl Tab {
     /// Tick - process pending work.
     pub fn tick(&mut self) {
         // Process timers, animations, etc.
+        self.sync_color_scheme();
+    }
+
+    /// Restyle the document if the preferred color scheme changed.
+    ///
+    /// Returns `true` if the document was restyled.
+    pub fn sync_color_scheme(&mut self) -> bool {
+        match &mut self.document {
+            Some(doc) => doc.set_color_scheme(preferred_color_scheme()),
+            None => false,
+        }
     }
 
     /// Scroll page.
@@ -215,8 +307,74 @@ impl Tab {
     pub fn scroll_position(&self) -> (i32, i32) {
         (self.scroll_x, self.scroll_y)
     }
+
+    /// Search the page for `query`, scroll to the first match, and return
+    /// the number of matches.
+    pub fn find_in_page(&mut self, query: &str, options: FindOptions) -> usize {
+        let Some(document) = &self.document else {
+            self.find.clear();
+            return 0;
+        };
+
+        let html = document.html();
+        let dom = kpio_dom::document::parse_html(html);
+        let pipeline = RenderPipeline::new(
+            self.window.inner_width() as f32,
+            self.window.inner_height() as f32,
+        );
+        let display_list = pipeline.render(html).unwrap_or_default();
+
+        let count = self.find.find(&dom, &display_list, query, options);
+        self.scroll_to_active_match();
+        count
+    }
+
+    /// Move to the next match and scroll it into view.
+    pub fn find_next(&mut self) {
+        self.find.next_match();
+        self.scroll_to_active_match();
+    }
+
+    /// Move to the previous match and scroll it into view.
+    pub fn find_previous(&mut self) {
+        self.find.previous_match();
+        self.scroll_to_active_match();
+    }
+
+    /// Close the find session.
+    pub fn stop_finding(&mut self) {
+        self.find.clear();
+    }
+
+    /// Get the find-in-page state, for the find bar and match highlights.
+    pub fn find_results(&self) -> &FindInPage {
+        &self.find
+    }
+
+    fn scroll_to_active_match(&mut self) {
+        let height = self.window.inner_height() as f32;
+        self.scroll_y = self.find.scroll_into_view(self.scroll_y, height);
+    }
+
+    /// Describe the tab for the extension tabs API.
+    fn extension_info(&self, index: usize, active: bool) -> ext::Tab {
+        let mut info = ext::Tab::new(self.id as ext::TabId, WINDOW_ID, index as i32);
+        info.active = active;
+        info.highlighted = active;
+        info.url = self.url.as_ref().map(Url::href);
+        info.title = self.title.clone();
+        info.status = Some(if self.loading {
+            TabStatus::Loading
+        } else {
+            TabStatus::Complete
+        });
+        info
+    }
 }
 
+/// Window ID reported to extensions; the browser has a single window.
+const WINDOW_ID: ext::WindowId = 1;
+
 /// Tab manager.
 pub struct TabManager {
     /// All tabs.
@@ -279,6 +437,16 @@ impl TabManager {
         }
     }
 
+    /// Get active tab index.
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    /// Get the index of the tab with `id`.
+    pub fn index_of(&self, id: usize) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.id == id)
+    }
+
     /// Get active tab.
     pub fn get_active(&self) -> Option<&Tab> {
         self.tabs.get(self.active)
@@ -308,6 +476,20 @@ impl TabManager {
     pub fn all(&self) -> &[Tab] {
         &self.tabs
     }
+
+    /// Restyle every tab for the current color scheme preference.
+    ///
+    /// Call after the user or the system switches theme. Returns the
+    /// number of tabs restyled.
+    pub fn sync_color_scheme(&mut self) -> usize {
+        let mut restyled = 0;
+        for tab in &mut self.tabs {
+            if tab.sync_color_scheme() {
+                restyled += 1;
+            }
+        }
+        restyled
+    }
 }
 
 impl Default for TabManager {
@@ -315,3 +497,215 @@ impl Default for TabManager {
         Self::new()
     }
 }
+
+impl TabManager {
+    /// Index of the tab an extension refers to.
+    fn extension_index(&self, tab_id: ext::TabId) -> ApiResult<usize> {
+        self.index_of(tab_id as usize)
+            .ok_or_else(|| ApiError::not_found("Tab"))
+    }
+}
+
+impl TabHost for TabManager {
+    fn tabs(&self) -> Vec<ext::Tab> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| tab.extension_info(index, index == self.active))
+            .collect()
+    }
+
+    fn create(&mut self, props: &CreateProperties) -> ApiResult<ext::TabId> {
+        let url = props.url.as_deref().map(parse_url).transpose()?;
+        let previous = self.active;
+
+        let mut index = self.new_tab();
+        if let Some(position) = props.index {
+            let position = (position.max(0) as usize).min(index);
+            let tab = self.tabs.remove(index);
+            self.tabs.insert(position, tab);
+            index = position;
+        }
+
+        self.active = match props.active {
+            Some(false) if previous >= index && self.tabs.len() > 1 => previous + 1,
+            Some(false) => previous,
+            _ => index,
+        };
+
+        let tab = &mut self.tabs[index];
+        if let Some(url) = url {
+            tab.load_url(&url).map_err(api_error)?;
+        }
+        Ok(tab.id as ext::TabId)
+    }
+
+    fn update(&mut self, tab_id: ext::TabId, props: &UpdateProperties) -> ApiResult<()> {
+        let index = self.extension_index(tab_id)?;
+        if let Some(url) = &props.url {
+            let url = parse_url(url)?;
+            self.tabs[index].load_url(&url).map_err(api_error)?;
+        }
+        if props.active == Some(true) || props.highlighted == Some(true) {
+            self.active = index;
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, tab_id: ext::TabId) -> ApiResult<()> {
+        let index = self.extension_index(tab_id)?;
+        self.close_tab(index).map_err(api_error)
+    }
+
+    fn reload(&mut self, tab_id: ext::TabId, _bypass_cache: bool) -> ApiResult<()> {
+        let index = self.extension_index(tab_id)?;
+        let tab = &mut self.tabs[index];
+        if let Some(url) = tab.url.clone() {
+            tab.load_url(&url).map_err(api_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a URL passed in by an extension.
+fn parse_url(url: &str) -> ApiResult<Url> {
+    Navigator::new().parse_url(url).map_err(api_error)
+}
+
+/// Report a browser error to an extension.
+fn api_error(err: BrowserError) -> ApiError {
+    ApiError::new(&alloc::format!("{:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use kpio_extensions::api::tabs::{QueryInfo, TabsApi};
+    use kpio_extensions::api::ApiContext;
+    use kpio_extensions::extension_manager;
+    use kpio_extensions::manifest::Manifest;
+    use kpio_js::ConsoleLevel;
+
+    #[test]
+    fn test_extension_tab_host() {
+        let mut manifest = Manifest::new("Browser Tab Host", "1.0");
+        manifest.add_permission("tabs");
+        let id = extension_manager()
+            .load_extension(manifest, "/ext")
+            .unwrap();
+        let ctx = ApiContext::new(id);
+
+        let api = TabsApi::new();
+        let mut tabs = TabManager::new();
+        tabs.new_tab();
+        let first = tabs.get(0).unwrap().id() as ext::TabId;
+
+        // Opened in the background, in front of the existing tab.
+        let props = CreateProperties {
+            url: Some("about:blank".to_string()),
+            index: Some(0),
+            active: Some(false),
+            ..Default::default()
+        };
+        let created = api.create(&ctx, &mut tabs, props).unwrap();
+        assert_eq!(created.index, 0);
+        assert!(!created.active);
+        assert_eq!(created.title.as_deref(), Some("about:blank"));
+        assert_eq!(tabs.active_index(), 1);
+
+        let props = UpdateProperties {
+            active: Some(true),
+            ..Default::default()
+        };
+        api.update(&ctx, &mut tabs, created.id, props).unwrap();
+        assert_eq!(tabs.active_index(), 0);
+
+        api.remove(&ctx, &mut tabs, vec![first]).unwrap();
+        let open = api.query(&ctx, &tabs, QueryInfo::default()).unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].id, created.id);
+        assert!(open[0].active);
+    }
+
+    #[test]
+    fn test_find_in_page_scrolls_to_match() {
+        let mut html = String::from("<html><body><p>Needle at the top</p>");
+        for _ in 0..80 {
+            html.push_str("<p>filler</p>");
+        }
+        html.push_str("<p>a needle at the bottom</p></body></html>");
+
+        let mut tab = Tab::new(1);
+        tab.load_html(&html, "https://example.com/").unwrap();
+        assert_eq!(tab.find_in_page("needle", FindOptions::default()), 2);
+        assert_eq!(tab.find_results().active_index(), Some(0));
+        assert_eq!(tab.scroll_position(), (0, 0));
+
+        tab.find_next();
+        assert_eq!(tab.find_results().active_index(), Some(1));
+        assert!(tab.scroll_position().1 > 0);
+
+        tab.find_next();
+        assert_eq!(tab.find_results().active_index(), Some(0));
+        assert_eq!(tab.scroll_position(), (0, 0));
+
+        tab.stop_finding();
+        assert_eq!(tab.find_results().match_count(), 0);
+    }
+    #[test]
+    fn test_page_scripts_report_to_console() {
+        let html = "<html><body>\
+                    <script>console.log('hello', 1 + 1);\n  missing();</script>\
+                    <script>console.warn('still runs');</script>\
+                    </body></html>";
+
+        let mut tab = Tab::new(1);
+        tab.load_html(html, "https://example.com/app").unwrap();
+
+        let messages = tab.take_console_messages();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].level, ConsoleLevel::Log);
+        assert_eq!(messages[0].text, "hello 2");
+        assert_eq!(messages[0].source, "https://example.com/app");
+        assert!(!messages[0].uncaught);
+
+        assert_eq!(messages[1].level, ConsoleLevel::Error);
+        assert!(messages[1].uncaught);
+        assert!(messages[1].text.starts_with("Uncaught"));
+        assert_eq!((messages[1].line, messages[1].column), (2, 3));
+
+        assert_eq!(messages[2].level, ConsoleLevel::Warn);
+        assert_eq!(tab.console_messages().count(), 0);
+    }
+
+    #[test]
+    fn test_page_scripts_get_web_storage() {
+        let html = "<html><body><script>\
+                    sessionStorage.setItem('draft', 'hi');\
+                    localStorage.setItem('theme', 'dark');\
+                    </script></body></html>";
+
+        let mut tab = Tab::new(1);
+        tab.load_html(html, "https://tab-storage.test/a").unwrap();
+        tab.load_html("<html></html>", "https://tab-storage.test/b")
+            .unwrap();
+        assert_eq!(
+            tab.execute_script("sessionStorage.getItem('draft')")
+                .unwrap(),
+            "hi"
+        );
+
+        let mut other = Tab::new(2);
+        other
+            .load_html("<html></html>", "https://tab-storage.test/")
+            .unwrap();
+        assert_eq!(
+            other
+                .execute_script("localStorage.getItem('theme')")
+                .unwrap(),
+            "dark"
+        );
+        assert_eq!(other.execute_script("sessionStorage.length").unwrap(), "0");
+    }
+}
@@ -362,3 +362,46 @@ mod bridge_tests {
         assert!(!events.is_empty());
     }
 }
+
+#[cfg(test)]
+mod loader_tests {
+    //! Page loader request tests
+
+    use alloc::string::String;
+
+    use crate::i18n::I18nManager;
+    use crate::loader::{PageLoader, ResourceType};
+    use crate::BrowserConfig;
+
+    #[test]
+    fn test_request_default_headers() {
+        let config = BrowserConfig {
+            user_agent: String::from("Custom/1.0"),
+            ..BrowserConfig::default()
+        };
+        let loader = PageLoader::with_config(&config);
+
+        let (_, request) = loader
+            .build_request("http://example.com/style.css", ResourceType::Stylesheet)
+            .unwrap();
+        assert_eq!(request.headers.get("User-Agent").unwrap(), "Custom/1.0");
+        assert!(request.headers.contains_key("Accept-Language"));
+        // The resource type's Accept overrides the client default
+        assert_eq!(
+            request.headers.get("Accept").map(|v| v.as_str()),
+            Some(ResourceType::Stylesheet.accept_header())
+        );
+    }
+
+    #[test]
+    fn test_accept_language_from_locale() {
+        let mut i18n = I18nManager::new();
+        assert_eq!(i18n.accept_language(), "en-US,en;q=0.9");
+
+        i18n.set_locale("ko-KR").unwrap();
+        assert_eq!(
+            i18n.accept_language(),
+            "ko-KR,ko;q=0.9,en-US;q=0.8,en;q=0.7"
+        );
+    }
+}
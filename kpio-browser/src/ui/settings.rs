@@ -460,6 +460,9 @@ impl SettingsManager {
 
     /// Update appearance settings.
     pub fn set_appearance(&self, appearance: AppearanceSettings) {
+        crate::color_scheme::COLOR_SCHEME
+            .write()
+            .set_preference(appearance.theme);
         self.settings.write().appearance = appearance;
     }
 
@@ -559,6 +562,9 @@ impl SettingsManager {
                 "dark" => Theme::Dark,
                 _ => Theme::System,
             };
+            crate::color_scheme::COLOR_SCHEME
+                .write()
+                .set_preference(settings.appearance.theme);
         }
         if let Some(SettingValue::Int(zoom)) = data.get("appearance.page_zoom") {
             settings.appearance.page_zoom = *zoom as u32;
@@ -1,11 +1,16 @@
 //! Computed Style - Final computed style values
 
+use alloc::vec::Vec;
+
 use crate::cascade::CascadedValues;
+use crate::effects::{BorderRadius, BoxShadow};
 use crate::properties::PropertyId;
+use crate::stylesheet::ColorScheme;
+use crate::transform::{TransformFunction, TransformOrigin};
 use crate::values::{
-    AlignContent, AlignItems, AlignSelf, BoxSizing, Color, Display, FlexDirection, FlexWrap,
-    FontStyle, FontWeight, JustifyContent, Length, LengthContext, Overflow, Position, TextAlign,
-    Visibility, WhiteSpace,
+    AlignContent, AlignItems, AlignSelf, BoxSizing, Color, ColorSchemes, Display, FlexDirection,
+    FlexWrap, FontStyle, FontWeight, JustifyContent, Length, LengthContext, Overflow, Position,
+    TextAlign, VerticalAlign, Visibility, WhiteSpace,
 };
 
 /// Computed style for an element.
@@ -80,6 +85,7 @@ pub struct ComputedStyle {
     pub font_style: FontStyle,
     pub line_height: f32,
     pub text_align: TextAlign,
+    pub vertical_align: VerticalAlign,
     pub white_space: WhiteSpace,
     pub letter_spacing: Length,
     pub word_spacing: Length,
@@ -89,6 +95,13 @@ pub struct ComputedStyle {
 
     // Effects
     pub opacity: f32,
+    pub border_radius: BorderRadius,
+    pub box_shadow: Vec<BoxShadow>,
+    pub transform: Vec<TransformFunction>,
+    pub transform_origin: TransformOrigin,
+
+    // Color adjustment
+    pub color_scheme: ColorSchemes,
 }
 
 impl Default for ComputedStyle {
@@ -160,6 +173,7 @@ impl Default for ComputedStyle {
             font_style: FontStyle::Normal,
             line_height: 1.2,
             text_align: TextAlign::Start,
+            vertical_align: VerticalAlign::Baseline,
             white_space: WhiteSpace::Normal,
             letter_spacing: Length::zero(),
             word_spacing: Length::zero(),
@@ -169,6 +183,13 @@ impl Default for ComputedStyle {
 
             // Effects
             opacity: 1.0,
+            border_radius: BorderRadius::default(),
+            box_shadow: Vec::new(),
+            transform: Vec::new(),
+            transform_origin: TransformOrigin::default(),
+
+            // Color adjustment
+            color_scheme: ColorSchemes::NORMAL,
         }
     }
 }
@@ -211,6 +232,13 @@ impl ComputedStyle {
         self.letter_spacing = parent.letter_spacing;
         self.word_spacing = parent.word_spacing;
         self.visibility = parent.visibility;
+        self.color_scheme = parent.color_scheme;
+    }
+
+    /// The color scheme form controls and scrollbars are drawn in, given
+    /// the user's preferred scheme.
+    pub fn used_color_scheme(&self, preferred: ColorScheme) -> ColorScheme {
+        self.color_scheme.used(preferred)
     }
 
     /// Apply cascaded values to the computed style.
@@ -327,9 +355,70 @@ impl ComputedStyle {
                         self.line_height = n;
                     }
                 }
+                PropertyId::VerticalAlign => {
+                    if let CssValue::Length(l) = decl.value {
+                        self.vertical_align = VerticalAlign::Length(l);
+                    } else if let CssValue::Keyword(ref k) = decl.value {
+                        self.vertical_align = match k.as_str() {
+                            "middle" => VerticalAlign::Middle,
+                            "top" => VerticalAlign::Top,
+                            "bottom" => VerticalAlign::Bottom,
+                            "sub" => VerticalAlign::Sub,
+                            "super" => VerticalAlign::Super,
+                            "text-top" => VerticalAlign::TextTop,
+                            "text-bottom" => VerticalAlign::TextBottom,
+                            _ => VerticalAlign::Baseline,
+                        };
+                    }
+                }
                 PropertyId::Opacity => {
                     if let CssValue::Number(n) = decl.value {
                         self.opacity = n.clamp(0.0, 1.0);
+                    } else if let CssValue::Integer(i) = decl.value {
+                        self.opacity = (i as f32).clamp(0.0, 1.0);
+                    }
+                }
+                PropertyId::BorderRadius => {
+                    if let Some(radius) = BorderRadius::from_css(&decl.value) {
+                        self.border_radius = radius;
+                    }
+                }
+                PropertyId::BoxShadow => {
+                    self.box_shadow = BoxShadow::list_from_css(&decl.value);
+                }
+                PropertyId::Overflow | PropertyId::OverflowX | PropertyId::OverflowY => {
+                    if let CssValue::Keyword(ref k) = decl.value {
+                        let mut keywords = k.split_whitespace().map(|k| match k {
+                            "hidden" => Overflow::Hidden,
+                            "scroll" => Overflow::Scroll,
+                            "auto" => Overflow::Auto,
+                            "clip" => Overflow::Clip,
+                            _ => Overflow::Visible,
+                        });
+                        let x = keywords.next().unwrap_or_default();
+                        let y = keywords.next().unwrap_or(x);
+                        match decl.property {
+                            PropertyId::OverflowX => self.overflow_x = x,
+                            PropertyId::OverflowY => self.overflow_y = x,
+                            _ => (self.overflow_x, self.overflow_y) = (x, y),
+                        }
+                    }
+                }
+                PropertyId::Transform => {
+                    self.transform = TransformFunction::list_from_css(&decl.value);
+                }
+                PropertyId::TransformOrigin => {
+                    if let CssValue::List(ref values) = decl.value {
+                        if let [CssValue::Length(x), CssValue::Length(y)] = values[..] {
+                            self.transform_origin = TransformOrigin { x, y };
+                        }
+                    }
+                }
+                PropertyId::ColorScheme => {
+                    if let CssValue::Keyword(ref k) = decl.value {
+                        if let Some(schemes) = ColorSchemes::parse(k) {
+                            self.color_scheme = schemes;
+                        }
                     }
                 }
                 PropertyId::FlexDirection => {
@@ -447,7 +536,10 @@ impl ComputedStyle {
 
     /// Check if this element creates a stacking context.
     pub fn creates_stacking_context(&self) -> bool {
-        self.position.creates_stacking_context() || self.opacity < 1.0 || self.z_index.is_some()
+        self.position.creates_stacking_context()
+            || self.opacity < 1.0
+            || self.z_index.is_some()
+            || !self.transform.is_empty()
     }
 
     /// Check if this element is positioned.
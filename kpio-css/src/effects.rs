@@ -0,0 +1,228 @@
+//! Effects - The `border-radius` and `box-shadow` properties
+//!
+//! Both keep their lengths unresolved until paint time: percentages in
+//! `border-radius` refer to the size of the element's border box, and a
+//! shadow without a color takes the element's `color`.
+
+use alloc::vec::Vec;
+
+use crate::values::{Color, CssValue, Length, LengthContext};
+
+/// The `border-radius` property, one radius per corner.
+///
+/// Elliptical radii (`10px / 20px`) are accepted and drawn circular,
+/// using the horizontal radius.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BorderRadius {
+    pub top_left: Length,
+    pub top_right: Length,
+    pub bottom_right: Length,
+    pub bottom_left: Length,
+}
+
+impl BorderRadius {
+    /// Build from a parsed `border-radius` value: a list of four lengths
+    /// in top-left, top-right, bottom-right, bottom-left order.
+    pub fn from_css(value: &CssValue) -> Option<Self> {
+        let CssValue::List(values) = value else {
+            return None;
+        };
+        match values[..] {
+            [CssValue::Length(top_left), CssValue::Length(top_right), CssValue::Length(bottom_right), CssValue::Length(bottom_left)] => {
+                Some(BorderRadius {
+                    top_left,
+                    top_right,
+                    bottom_right,
+                    bottom_left,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.top_left.is_zero()
+            && self.top_right.is_zero()
+            && self.bottom_right.is_zero()
+            && self.bottom_left.is_zero()
+    }
+
+    /// Resolve to pixel radii `[top_left, top_right, bottom_right,
+    /// bottom_left]` for a border box of the given size.
+    ///
+    /// Percentages refer to the box's width. Radii are scaled down
+    /// together until the two on each side fit along it.
+    pub fn resolve(&self, width: f32, height: f32, context: &LengthContext) -> [f32; 4] {
+        let context = LengthContext {
+            containing_block: width,
+            ..*context
+        };
+        let radii = [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+        .map(|radius| radius.to_px(&context).max(0.0));
+        let [top_left, top_right, bottom_right, bottom_left] = radii;
+
+        let scale = [
+            (width, top_left + top_right),
+            (height, top_right + bottom_right),
+            (width, bottom_right + bottom_left),
+            (height, bottom_left + top_left),
+        ]
+        .iter()
+        .filter(|&&(_, sum)| sum > 0.0)
+        .map(|&(side, sum)| side.max(0.0) / sum)
+        .fold(1.0f32, f32::min);
+        radii.map(|radius| radius * scale)
+    }
+}
+
+/// One shadow of a `box-shadow` list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxShadow {
+    pub offset_x: Length,
+    pub offset_y: Length,
+    pub blur: Length,
+    pub spread: Length,
+    /// `None` for `currentcolor`.
+    pub color: Option<Color>,
+    pub inset: bool,
+}
+
+impl BoxShadow {
+    /// Build a shadow from its parsed components (two to four lengths,
+    /// and optionally a color and `inset`, in any order around them), or
+    /// `None` if they are invalid.
+    pub fn from_css(value: &CssValue) -> Option<Self> {
+        let CssValue::List(components) = value else {
+            return None;
+        };
+
+        let mut lengths = Vec::new();
+        let mut lengths_done = false;
+        let mut color = None;
+        let mut inset = false;
+        for component in components {
+            match component {
+                CssValue::Length(length) if !lengths_done && lengths.len() < 4 => {
+                    lengths.push(*length)
+                }
+                CssValue::Color(c) if color.is_none() => color = Some(*c),
+                CssValue::Keyword(keyword) if keyword.eq_ignore_ascii_case("inset") && !inset => {
+                    inset = true
+                }
+                _ => return None,
+            }
+            // The lengths must be contiguous
+            lengths_done |= !lengths.is_empty() && !matches!(component, CssValue::Length(_));
+        }
+
+        let (offset_x, offset_y, blur, spread) = match lengths[..] {
+            [x, y] => (x, y, Length::zero(), Length::zero()),
+            [x, y, blur] => (x, y, blur, Length::zero()),
+            [x, y, blur, spread] => (x, y, blur, spread),
+            _ => return None,
+        };
+        if blur.value < 0.0 {
+            return None;
+        }
+        Some(BoxShadow {
+            offset_x,
+            offset_y,
+            blur,
+            spread,
+            color,
+            inset,
+        })
+    }
+
+    /// Build the shadows of a parsed `box-shadow` value. `none` and
+    /// invalid values give an empty list.
+    pub fn list_from_css(value: &CssValue) -> Vec<BoxShadow> {
+        match value {
+            CssValue::List(shadows) => shadows
+                .iter()
+                .map(BoxShadow::from_css)
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cascade::CascadedValues;
+    use crate::computed::ComputedStyle;
+    use crate::parser::CssParser;
+    use crate::selector::Specificity;
+    use crate::stylesheet::StylesheetOrigin;
+
+    fn computed(declarations: &str) -> ComputedStyle {
+        let mut cascaded = CascadedValues::new();
+        let block = CssParser::new(declarations)
+            .parse_declaration_block()
+            .unwrap();
+        cascaded.apply(&block, Specificity::default(), StylesheetOrigin::Author, 0);
+        ComputedStyle::compute(&cascaded, None, &LengthContext::default())
+    }
+
+    #[test]
+    fn test_border_radius_expands_and_scales() {
+        let style = computed("border-radius: 10px 20%");
+        let context = LengthContext::default();
+        assert_eq!(
+            style.border_radius.resolve(200.0, 100.0, &context),
+            [10.0, 40.0, 10.0, 40.0]
+        );
+
+        // Radii that overlap along a side shrink together
+        let style = computed("border-radius: 80px / 10px");
+        assert_eq!(
+            style.border_radius.resolve(100.0, 100.0, &context),
+            [50.0; 4]
+        );
+
+        assert!(computed("border-radius: -1px").border_radius.is_zero());
+        assert!(computed("border-radius: 1px 2px 3px 4px 5px")
+            .border_radius
+            .is_zero());
+    }
+
+    #[test]
+    fn test_box_shadow_list() {
+        let style = computed(
+            "box-shadow: 2px 4px 6px rgba(0, 0, 0, 0.5), inset 0 0 0 1px red; opacity: 50%",
+        );
+        assert_eq!(style.box_shadow.len(), 2);
+
+        let outer = style.box_shadow[0];
+        assert!(!outer.inset);
+        assert_eq!(outer.offset_x, Length::px(2.0));
+        assert_eq!(outer.blur, Length::px(6.0));
+        assert!(outer.spread.is_zero());
+        assert!(matches!(outer.color, Some(color) if (127..=128).contains(&color.a)));
+
+        let inner = style.box_shadow[1];
+        assert!(inner.inset);
+        assert_eq!(inner.spread, Length::px(1.0));
+        assert_eq!(inner.color, Some(Color::rgb(255, 0, 0)));
+        assert_eq!(style.opacity, 0.5);
+
+        // Without a color the shadow takes `color`
+        assert_eq!(computed("box-shadow: 1px 1px").box_shadow[0].color, None);
+    }
+
+    #[test]
+    fn test_invalid_box_shadow_is_dropped() {
+        assert!(computed("box-shadow: none").box_shadow.is_empty());
+        assert!(computed("box-shadow: 1px").box_shadow.is_empty());
+        assert!(computed("box-shadow: 1px 1px -2px").box_shadow.is_empty());
+        assert!(computed("box-shadow: 1px red 1px").box_shadow.is_empty());
+        assert!(computed("box-shadow: 1px 1px,").box_shadow.is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+//! Import - `@import` rule parsing and resolution
+//!
+//! Imported stylesheets are fetched through a [`StylesheetLoader`] and their
+//! rules are spliced in at the position of the `@import`, so they keep their
+//! place in the cascade order.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::parser::CssParser;
+use crate::stylesheet::{MediaContext, MediaQueryList, Rule, Stylesheet};
+
+/// Maximum nesting depth of `@import` chains.
+pub const MAX_IMPORT_DEPTH: usize = 8;
+
+/// A parsed `@import` rule.
+#[derive(Debug, Clone)]
+pub struct ImportRule {
+    /// URL as written in the stylesheet (unresolved).
+    pub url: String,
+    /// Media condition; empty means unconditional.
+    pub media: MediaQueryList,
+}
+
+impl ImportRule {
+    /// Parse an `@import` prelude such as `url("a.css") screen`.
+    pub fn parse(prelude: &str) -> Option<ImportRule> {
+        let prelude = prelude.trim();
+
+        let (url, rest) = if let Some(inner) = prelude.strip_prefix("url(") {
+            let close = inner.find(')')?;
+            (unquote(&inner[..close])?, &inner[close + 1..])
+        } else {
+            let quote = prelude.chars().next()?;
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+            let close = prelude[1..].find(quote)? + 1;
+            (&prelude[1..close], &prelude[close + 1..])
+        };
+
+        if url.is_empty() {
+            return None;
+        }
+
+        Some(ImportRule {
+            url: url.to_string(),
+            media: MediaQueryList::parse(rest),
+        })
+    }
+}
+
+/// Strip optional matching quotes from a `url()` argument.
+fn unquote(s: &str) -> Option<&str> {
+    let s = s.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(quote) {
+            return inner.strip_suffix(quote);
+        }
+    }
+    Some(s)
+}
+
+/// Source of imported stylesheet text.
+pub trait StylesheetLoader {
+    /// Fetch the stylesheet at an absolute URL.
+    fn load(&self, url: &str) -> Option<String>;
+}
+
+/// Reasons an `@import` was skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// The URL is already being imported higher up the chain.
+    Cycle(String),
+    /// The import chain is deeper than the configured limit.
+    DepthExceeded(String),
+    /// The stylesheet could not be fetched or parsed.
+    LoadFailed(String),
+}
+
+/// Resolves `@import` rules by inlining the imported stylesheets.
+pub struct ImportResolver<'a, L: StylesheetLoader + ?Sized> {
+    loader: &'a L,
+    context: MediaContext,
+    max_depth: usize,
+    stack: Vec<String>,
+    errors: Vec<ImportError>,
+}
+
+impl<'a, L: StylesheetLoader + ?Sized> ImportResolver<'a, L> {
+    /// Create a resolver using the default media context.
+    pub fn new(loader: &'a L) -> Self {
+        ImportResolver {
+            loader,
+            context: MediaContext::default(),
+            max_depth: MAX_IMPORT_DEPTH,
+            stack: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Set the media context used to evaluate import conditions.
+    pub fn with_context(mut self, context: MediaContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Set the maximum import depth.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Imports skipped during resolution.
+    pub fn errors(&self) -> &[ImportError] {
+        &self.errors
+    }
+
+    /// Resolve all imports in `sheet`, which was loaded from `base_url`.
+    pub fn resolve(&mut self, sheet: Stylesheet, base_url: &str) -> Stylesheet {
+        self.stack.clear();
+        self.stack.push(base_url.to_string());
+        let resolved = self.resolve_rules(sheet, base_url);
+        self.stack.clear();
+        resolved
+    }
+
+    fn resolve_rules(&mut self, sheet: Stylesheet, base_url: &str) -> Stylesheet {
+        let mut out = Stylesheet {
+            rules: Vec::with_capacity(sheet.rules.len()),
+            origin: sheet.origin,
+        };
+        // @import is only valid before any other rule (besides @charset)
+        let mut imports_allowed = true;
+
+        for rule in sheet.rules {
+            let import = match &rule {
+                Rule::AtRule(at) if at.is_import() => at.import_rule(),
+                Rule::AtRule(at) if at.name == "charset" => {
+                    out.rules.push(rule);
+                    continue;
+                }
+                _ => None,
+            };
+
+            let Some(import) = import else {
+                if !matches!(&rule, Rule::AtRule(at) if at.is_import()) {
+                    imports_allowed = false;
+                    out.rules.push(rule);
+                }
+                continue;
+            };
+
+            if !imports_allowed || !import.media.matches(&self.context) {
+                continue;
+            }
+
+            let url = resolve_url(base_url, &import.url);
+            if self.stack.contains(&url) {
+                self.errors.push(ImportError::Cycle(url));
+                continue;
+            }
+            if self.stack.len() > self.max_depth {
+                self.errors.push(ImportError::DepthExceeded(url));
+                continue;
+            }
+            let Some(mut imported) = self
+                .loader
+                .load(&url)
+                .and_then(|css| CssParser::new(&css).parse_stylesheet().ok())
+            else {
+                self.errors.push(ImportError::LoadFailed(url));
+                continue;
+            };
+            imported.origin = out.origin;
+
+            self.stack.push(url.clone());
+            let imported = self.resolve_rules(imported, &url);
+            self.stack.pop();
+
+            out.rules.extend(
+                imported
+                    .rules
+                    .into_iter()
+                    .filter(|r| !matches!(r, Rule::AtRule(at) if at.name == "charset")),
+            );
+        }
+
+        out
+    }
+}
+
+/// Resolve a possibly relative URL against a base URL.
+pub fn resolve_url(base: &str, relative: &str) -> String {
+    if relative.contains("://") || relative.starts_with("data:") {
+        return relative.to_string();
+    }
+
+    let (origin, base_path) = match base.find("://") {
+        Some(scheme_end) => {
+            let after = scheme_end + 3;
+            match base[after..].find('/') {
+                Some(p) => base.split_at(after + p),
+                None => (base, "/"),
+            }
+        }
+        None => ("", base),
+    };
+
+    if let Some(rest) = relative.strip_prefix("//") {
+        let scheme = base.find("://").map(|i| &base[..i]).unwrap_or("http");
+        let mut url = String::from(scheme);
+        url.push_str("://");
+        url.push_str(rest);
+        return url;
+    }
+
+    // Drop query/fragment and the last path segment of the base
+    let base_path = base_path.split(['?', '#']).next().unwrap_or("");
+    let dir = match base_path.rfind('/') {
+        Some(i) => &base_path[..i],
+        None => "",
+    };
+
+    let joined = if relative.starts_with('/') {
+        String::from(relative)
+    } else {
+        let mut s = String::from(dir);
+        s.push('/');
+        s.push_str(relative);
+        s
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+
+    let mut url = String::from(origin);
+    for segment in &segments {
+        url.push('/');
+        url.push_str(segment);
+    }
+    if segments.is_empty() || joined.ends_with('/') {
+        url.push('/');
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+
+    struct MapLoader(BTreeMap<&'static str, &'static str>);
+
+    impl StylesheetLoader for MapLoader {
+        fn load(&self, url: &str) -> Option<String> {
+            self.0.get(url).map(|s| s.to_string())
+        }
+    }
+
+    fn loader(entries: &[(&'static str, &'static str)]) -> MapLoader {
+        MapLoader(entries.iter().copied().collect())
+    }
+
+    fn parse(css: &str) -> Stylesheet {
+        CssParser::new(css).parse_stylesheet().unwrap()
+    }
+
+    fn style_count(sheet: &Stylesheet) -> usize {
+        sheet.style_rules().count()
+    }
+
+    #[test]
+    fn test_parse_import_rule() {
+        let rule = ImportRule::parse("url('a.css')").unwrap();
+        assert_eq!(rule.url, "a.css");
+        assert!(rule.media.is_empty());
+
+        let rule = ImportRule::parse("\"b.css\" screen and (min-width: 600px)").unwrap();
+        assert_eq!(rule.url, "b.css");
+        assert_eq!(rule.media.queries.len(), 1);
+
+        assert!(ImportRule::parse("a.css").is_none());
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        assert_eq!(
+            resolve_url("http://x.org/css/main.css", "a.css"),
+            "http://x.org/css/a.css"
+        );
+        assert_eq!(
+            resolve_url("http://x.org/css/main.css", "../b.css"),
+            "http://x.org/b.css"
+        );
+        assert_eq!(
+            resolve_url("http://x.org/css/main.css", "/c.css"),
+            "http://x.org/c.css"
+        );
+        assert_eq!(
+            resolve_url("http://x.org/", "https://y.org/d.css"),
+            "https://y.org/d.css"
+        );
+    }
+
+    #[test]
+    fn test_import_inlined_in_order() {
+        let l = loader(&[(
+            "http://x.org/a.css",
+            "p { color: red; } div { color: blue; }",
+        )]);
+        let sheet = parse("@import url(a.css); span { color: green; }");
+
+        let mut resolver = ImportResolver::new(&l);
+        let out = resolver.resolve(sheet, "http://x.org/index.css");
+
+        assert_eq!(style_count(&out), 3);
+        assert!(resolver.errors().is_empty());
+    }
+
+    #[test]
+    fn test_import_cycle_is_broken() {
+        let l = loader(&[
+            ("http://x.org/a.css", "@import 'b.css'; a { color: red; }"),
+            ("http://x.org/b.css", "@import 'a.css'; b { color: red; }"),
+        ]);
+        let sheet = parse("@import 'a.css';");
+
+        let mut resolver = ImportResolver::new(&l);
+        let out = resolver.resolve(sheet, "http://x.org/index.css");
+
+        assert_eq!(style_count(&out), 2);
+        assert_eq!(
+            resolver.errors(),
+            &[ImportError::Cycle("http://x.org/a.css".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_import_depth_limit() {
+        let l = loader(&[
+            ("http://x.org/a.css", "@import 'b.css'; a { color: red; }"),
+            ("http://x.org/b.css", "b { color: red; }"),
+        ]);
+        let sheet = parse("@import 'a.css';");
+
+        let mut resolver = ImportResolver::new(&l).with_max_depth(1);
+        let out = resolver.resolve(sheet, "http://x.org/index.css");
+
+        assert_eq!(style_count(&out), 1);
+        assert!(matches!(resolver.errors(), [ImportError::DepthExceeded(_)]));
+    }
+
+    #[test]
+    fn test_media_conditional_import() {
+        let l = loader(&[("http://x.org/print.css", "p { color: black; }")]);
+        let sheet = parse("@import 'print.css' print;");
+
+        let mut resolver = ImportResolver::new(&l);
+        let out = resolver.resolve(sheet, "http://x.org/index.css");
+        assert_eq!(style_count(&out), 0);
+
+        let sheet = parse("@import 'print.css' print;");
+        let mut resolver = ImportResolver::new(&l).with_context(MediaContext {
+            media_type: crate::stylesheet::MediaType::Print,
+            ..MediaContext::default()
+        });
+        let out = resolver.resolve(sheet, "http://x.org/index.css");
+        assert_eq!(style_count(&out), 1);
+    }
+
+    #[test]
+    fn test_late_import_ignored() {
+        let l = loader(&[("http://x.org/a.css", "p { color: red; }")]);
+        let sheet = parse("div { color: blue; } @import 'a.css';");
+
+        let mut resolver = ImportResolver::new(&l);
+        let out = resolver.resolve(sheet, "http://x.org/index.css");
+        assert_eq!(style_count(&out), 1);
+        assert!(resolver.errors().is_empty());
+    }
+}
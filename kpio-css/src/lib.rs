@@ -9,10 +9,13 @@ extern crate alloc;
 
 pub mod cascade;
 pub mod computed;
+pub mod effects;
+pub mod import;
 pub mod parser;
 pub mod properties;
 pub mod selector;
 pub mod stylesheet;
+pub mod transform;
 pub mod values;
 
 #[cfg(test)]
@@ -20,10 +23,13 @@ mod tests;
 
 pub use cascade::CascadedValues;
 pub use computed::ComputedStyle;
+pub use effects::{BorderRadius, BoxShadow};
+pub use import::{ImportResolver, StylesheetLoader};
 pub use parser::{CssParser, ParseError};
 pub use properties::{PropertyDeclaration, PropertyId};
 pub use selector::{Selector, SelectorList, Specificity};
-pub use stylesheet::{Rule, StyleRule, Stylesheet};
+pub use stylesheet::{ColorScheme, MediaContext, MediaQueryList, Rule, StyleRule, Stylesheet};
+pub use transform::{TransformFunction, TransformMatrix, TransformOrigin};
 pub use values::{Color, CssValue, Display, Length};
 
 /// Prelude for common imports
@@ -1,17 +1,20 @@
 //! CSS Parser - Tokenization and parsing of CSS
 
+use alloc::boxed::Box;
 use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
 use core::iter::Peekable;
 use core::str::Chars;
 
+use crate::effects::BoxShadow;
 use crate::properties::{DeclarationBlock, PropertyDeclaration, PropertyId};
 use crate::selector::{
     AttributeOperator, CaseSensitivity, Combinator, NthExpr, PseudoClass, PseudoElement, Selector,
     SelectorComponent, SelectorList,
 };
 use crate::stylesheet::{AtRule, Rule, StyleRule, Stylesheet};
+use crate::transform::TransformFunction;
 use crate::values::{Color, CssValue, Length, LengthUnit};
 
 use servo_types::LocalName;
@@ -139,8 +142,24 @@ impl<'a> CssParser<'a> {
         let mut selector = Selector::new();
 
         loop {
+            let start = self.pos;
             self.skip_whitespace();
 
+            // Whitespace between two compound selectors is the descendant
+            // combinator
+            let starts_compound = self
+                .peek_char()
+                .is_some_and(|c| matches!(c, '*' | '.' | '#' | '[' | ':') || is_ident_start(c));
+            let after_compound = selector
+                .components
+                .last()
+                .is_some_and(|c| !matches!(c, SelectorComponent::Combinator(_)));
+            if self.pos > start && starts_compound && after_compound {
+                selector
+                    .components
+                    .push(SelectorComponent::Combinator(Combinator::Descendant));
+            }
+
             match self.peek_char() {
                 Some('{') | Some(',') | None => break,
                 Some('>') => {
@@ -230,12 +249,17 @@ impl<'a> CssParser<'a> {
 
         self.skip_whitespace();
 
-        // Check for case sensitivity flag
-        let case_sensitivity = if self.peek_char() == Some('i') || self.peek_char() == Some('I') {
-            self.consume_char();
-            CaseSensitivity::AsciiCaseInsensitive
-        } else {
-            CaseSensitivity::CaseSensitive
+        // Check for case sensitivity flag (`i` or the explicit default `s`)
+        let case_sensitivity = match self.peek_char() {
+            Some('i') | Some('I') if value.is_some() => {
+                self.consume_char();
+                CaseSensitivity::AsciiCaseInsensitive
+            }
+            Some('s') | Some('S') if value.is_some() => {
+                self.consume_char();
+                CaseSensitivity::CaseSensitive
+            }
+            _ => CaseSensitivity::CaseSensitive,
         };
 
         self.skip_whitespace();
@@ -331,6 +355,18 @@ impl<'a> CssParser<'a> {
                     _ => unreachable!(),
                 }
             }
+            "not" | "is" | "where" => {
+                self.expect_char('(')?;
+                let list = Box::new(self.parse_selector_list()?);
+                self.skip_whitespace();
+                self.expect_char(')')?;
+                match name.as_str() {
+                    "not" => Ok(PseudoClass::Not(list)),
+                    "is" => Ok(PseudoClass::Is(list)),
+                    "where" => Ok(PseudoClass::Where(list)),
+                    _ => unreachable!(),
+                }
+            }
             _ => Err(ParseError::InvalidSelector(alloc::format!(
                 "unknown pseudo-class: {}",
                 name
@@ -340,26 +376,10 @@ impl<'a> CssParser<'a> {
 
     /// Parse an An+B expression.
     fn parse_nth_expr(&mut self) -> Result<NthExpr, ParseError> {
-        self.skip_whitespace();
-
         let s = self.consume_until(')');
-        let s = s.trim();
-
-        match s {
-            "odd" => Ok(NthExpr::ODD),
-            "even" => Ok(NthExpr::EVEN),
-            _ => {
-                // Simplified parsing: just handle basic cases
-                if let Ok(n) = s.parse::<i32>() {
-                    Ok(NthExpr::new(0, n))
-                } else if s == "n" {
-                    Ok(NthExpr::new(1, 0))
-                } else {
-                    // Try to parse An+B
-                    Ok(NthExpr::new(1, 0)) // Fallback
-                }
-            }
-        }
+        NthExpr::parse(&s).ok_or_else(|| {
+            ParseError::InvalidSelector(alloc::format!("invalid An+B expression: {}", s.trim()))
+        })
     }
 
     /// Parse a pseudo-element.
@@ -477,11 +497,16 @@ impl<'a> CssParser<'a> {
             | PropertyId::ColumnGap => self.parse_length_value(value_str),
             PropertyId::Display => Ok(CssValue::Keyword(value_str.to_string())),
             PropertyId::Position => Ok(CssValue::Keyword(value_str.to_string())),
+            PropertyId::ColorScheme => Ok(CssValue::Keyword(value_str.to_string())),
             PropertyId::FlexGrow
             | PropertyId::FlexShrink
             | PropertyId::Order
             | PropertyId::ZIndex => self.parse_number_value(value_str),
-            PropertyId::Opacity => self.parse_number_value(value_str),
+            PropertyId::Opacity => self.parse_opacity_value(value_str),
+            PropertyId::BorderRadius => self.parse_border_radius_value(value_str),
+            PropertyId::BoxShadow => self.parse_box_shadow_value(value_str),
+            PropertyId::Transform => self.parse_transform_value(value_str),
+            PropertyId::TransformOrigin => self.parse_transform_origin_value(value_str),
             _ => {
                 // Default: try to parse as length, number, or keyword
                 if let Ok(length) = self.parse_length_value(value_str) {
@@ -672,6 +697,200 @@ impl<'a> CssParser<'a> {
         }
     }
 
+    /// Parse an `opacity` value: a number or a percentage.
+    fn parse_opacity_value(&self, s: &str) -> Result<CssValue, ParseError> {
+        match s.strip_suffix('%') {
+            Some(percent) => percent
+                .parse::<f32>()
+                .map(|percent| CssValue::Number(percent / 100.0))
+                .map_err(|_| ParseError::InvalidNumber(s.to_string())),
+            None => self.parse_number_value(s),
+        }
+    }
+
+    /// Parse a `border-radius` value into a list of four lengths, one per
+    /// corner clockwise from the top left. Radii after a `/` (the vertical
+    /// radii of elliptical corners) are checked and dropped.
+    fn parse_border_radius_value(&self, s: &str) -> Result<CssValue, ParseError> {
+        let invalid = || ParseError::InvalidValue(s.to_string());
+        let mut halves = s.split('/');
+        let radii = halves
+            .next()
+            .and_then(|horizontal| self.parse_corner_radii(horizontal))
+            .ok_or_else(invalid)?;
+        if let Some(vertical) = halves.next() {
+            self.parse_corner_radii(vertical).ok_or_else(invalid)?;
+        }
+        if halves.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(CssValue::List(
+            radii.into_iter().map(CssValue::Length).collect(),
+        ))
+    }
+
+    /// Expand one to four non-negative radii to one per corner, the way
+    /// `margin` expands to its sides.
+    fn parse_corner_radii(&self, s: &str) -> Option<[Length; 4]> {
+        let radii = s
+            .split_whitespace()
+            .map(|token| match self.parse_length_value(token) {
+                Ok(CssValue::Length(length)) if length.value >= 0.0 => Some(length),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        match radii[..] {
+            [all] => Some([all; 4]),
+            [a, b] => Some([a, b, a, b]),
+            [a, b, c] => Some([a, b, c, b]),
+            [a, b, c, d] => Some([a, b, c, d]),
+            _ => None,
+        }
+    }
+
+    /// Parse a `box-shadow` value: `none` or a comma-separated list of
+    /// shadows, each kept as the list of its lengths, color and `inset`.
+    fn parse_box_shadow_value(&self, s: &str) -> Result<CssValue, ParseError> {
+        if s.eq_ignore_ascii_case("none") {
+            return Ok(CssValue::Keyword("none".to_string()));
+        }
+
+        let mut shadows = Vec::new();
+        for shadow in split_top_level(s, |c| c == ',') {
+            let components = split_top_level(shadow, char::is_whitespace)
+                .into_iter()
+                .filter(|token| !token.is_empty())
+                .map(|token| {
+                    if token.eq_ignore_ascii_case("inset") {
+                        Ok(CssValue::Keyword("inset".to_string()))
+                    } else if let Ok(length) = self.parse_length_value(token) {
+                        Ok(length)
+                    } else {
+                        self.parse_color_value(token)
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let shadow = CssValue::List(components);
+            if BoxShadow::from_css(&shadow).is_none() {
+                return Err(ParseError::InvalidValue(s.to_string()));
+            }
+            shadows.push(shadow);
+        }
+        Ok(CssValue::List(shadows))
+    }
+
+    /// Parse a `transform` value: `none` or a list of transform functions.
+    fn parse_transform_value(&self, s: &str) -> Result<CssValue, ParseError> {
+        if s.eq_ignore_ascii_case("none") {
+            return Ok(CssValue::Keyword("none".to_string()));
+        }
+
+        let mut functions = Vec::new();
+        let mut rest = s.trim_start();
+        while !rest.is_empty() {
+            let open = rest
+                .find('(')
+                .ok_or_else(|| ParseError::InvalidValue(s.to_string()))?;
+            let close = rest
+                .find(')')
+                .ok_or_else(|| ParseError::InvalidValue(s.to_string()))?;
+            let name = rest[..open].trim();
+            if close < open || name.is_empty() || !name.chars().all(is_ident_char) {
+                return Err(ParseError::InvalidValue(s.to_string()));
+            }
+
+            let args = rest[open + 1..close]
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| self.parse_transform_argument(arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            if TransformFunction::from_css(name, &args).is_none() {
+                return Err(ParseError::InvalidValue(s.to_string()));
+            }
+
+            functions.push(CssValue::Function(name.to_ascii_lowercase(), args));
+            rest = rest[close + 1..].trim_start();
+        }
+
+        if functions.is_empty() {
+            return Err(ParseError::InvalidValue(s.to_string()));
+        }
+        Ok(CssValue::List(functions))
+    }
+
+    /// Parse a transform function argument: a number, an angle (converted
+    /// to degrees), or a length.
+    fn parse_transform_argument(&self, s: &str) -> Result<CssValue, ParseError> {
+        if let Ok(number) = self.parse_number_value(s) {
+            return Ok(number);
+        }
+
+        let num_end = s
+            .find(|c: char| c.is_ascii_alphabetic() || c == '%')
+            .unwrap_or(s.len());
+        let degrees_per_unit = match s[num_end..].to_ascii_lowercase().as_str() {
+            "deg" => Some(1.0),
+            "rad" => Some(180.0 / core::f32::consts::PI),
+            "grad" => Some(0.9),
+            "turn" => Some(360.0),
+            _ => None,
+        };
+        match degrees_per_unit {
+            Some(factor) => s[..num_end]
+                .parse::<f32>()
+                .map(|value| CssValue::Angle(value * factor))
+                .map_err(|_| ParseError::InvalidNumber(s.to_string())),
+            None => self.parse_length_value(s),
+        }
+    }
+
+    /// Parse a `transform-origin` value into a list of two lengths (x, y).
+    /// A z offset is accepted and ignored.
+    fn parse_transform_origin_value(&self, s: &str) -> Result<CssValue, ParseError> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Axis {
+            X,
+            Y,
+            Either,
+        }
+
+        let mut parts = Vec::new();
+        for token in s.split_whitespace() {
+            let part = match token.to_ascii_lowercase().as_str() {
+                "left" => (Axis::X, Length::percent(0.0)),
+                "right" => (Axis::X, Length::percent(100.0)),
+                "top" => (Axis::Y, Length::percent(0.0)),
+                "bottom" => (Axis::Y, Length::percent(100.0)),
+                "center" => (Axis::Either, Length::percent(50.0)),
+                _ => match self.parse_length_value(token)? {
+                    CssValue::Length(length) => (Axis::Either, length),
+                    _ => return Err(ParseError::InvalidValue(s.to_string())),
+                },
+            };
+            parts.push(part);
+        }
+
+        let (x, y) = match parts[..] {
+            [(Axis::Y, y)] => (Length::percent(50.0), y),
+            [(_, x)] => (x, Length::percent(50.0)),
+            [first, second] | [first, second, _] => {
+                if first.0 == Axis::Y || second.0 == Axis::X {
+                    if first.0 == Axis::X || second.0 == Axis::Y {
+                        return Err(ParseError::InvalidValue(s.to_string()));
+                    }
+                    (second.1, first.1)
+                } else {
+                    (first.1, second.1)
+                }
+            }
+            _ => return Err(ParseError::InvalidValue(s.to_string())),
+        };
+        Ok(CssValue::List(vec![
+            CssValue::Length(x),
+            CssValue::Length(y),
+        ]))
+    }
+
     /// Parse an at-rule.
     fn parse_at_rule(&mut self) -> Result<AtRule, ParseError> {
         self.expect_char('@')?;
@@ -875,6 +1094,27 @@ impl<'a> CssParser<'a> {
 }
 
 /// Check if a character can start an identifier.
+/// Split `s` at separator characters outside parentheses, trimming each
+/// part.
+fn split_top_level(s: &str, is_separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && is_separator(c) => {
+                parts.push(s[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
 fn is_ident_start(c: char) -> bool {
     c.is_alphabetic() || c == '_' || c == '-'
 }
@@ -895,10 +1135,131 @@ mod tests {
         assert_eq!(selector.components.len(), 3);
     }
 
+    #[test]
+    fn test_parse_descendant_and_logical_selectors() {
+        let selector = CssParser::new("nav  ul > li").parse_selector().unwrap();
+        assert_eq!(
+            selector.components[1],
+            SelectorComponent::Combinator(Combinator::Descendant)
+        );
+        assert_eq!(selector.components.len(), 5);
+
+        let selector = CssParser::new("li:not(.a, .b) p").parse_selector().unwrap();
+        match &selector.components[1] {
+            SelectorComponent::PseudoClass(PseudoClass::Not(list)) => {
+                assert_eq!(list.selectors.len(), 2)
+            }
+            other => panic!("unexpected component {:?}", other),
+        }
+        assert_eq!(selector.components.len(), 4);
+
+        assert!(CssParser::new(":nth-child(2 n)").parse_selector().is_err());
+        assert!(CssParser::new(":is(p").parse_selector().is_err());
+    }
+
+    #[test]
+    fn test_parse_attribute_operators() {
+        let cases = [
+            ("[href^=\"https:\"]", AttributeOperator::Prefix),
+            ("[href$='.pdf']", AttributeOperator::Suffix),
+            ("[class*=btn]", AttributeOperator::Substring),
+            ("[rel~=\"nofollow\"]", AttributeOperator::Includes),
+            ("[lang|=en]", AttributeOperator::DashMatch),
+            ("[type=text]", AttributeOperator::Equals),
+            ("[disabled]", AttributeOperator::Exists),
+        ];
+        for (input, expected) in cases {
+            let selector = CssParser::new(input).parse_selector().unwrap();
+            match &selector.components[0] {
+                SelectorComponent::Attribute { operator, .. } => assert_eq!(*operator, expected),
+                other => panic!("unexpected component {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_attribute_case_flag() {
+        let flag = |input: &str| {
+            let selector = CssParser::new(input).parse_selector().unwrap();
+            match &selector.components[0] {
+                SelectorComponent::Attribute {
+                    case_sensitivity, ..
+                } => *case_sensitivity,
+                other => panic!("unexpected component {:?}", other),
+            }
+        };
+        assert_eq!(
+            flag("[type=\"TEXT\" i]"),
+            CaseSensitivity::AsciiCaseInsensitive
+        );
+        assert_eq!(flag("[href$=PDF I]"), CaseSensitivity::AsciiCaseInsensitive);
+        assert_eq!(flag("[type=\"a\" s]"), CaseSensitivity::CaseSensitive);
+        assert_eq!(flag("[type=text]"), CaseSensitivity::CaseSensitive);
+    }
+
     #[test]
     fn test_parse_declaration() {
         let mut parser = CssParser::new("color: red");
         let decl = parser.parse_declaration().unwrap();
         assert_eq!(decl.property, PropertyId::Color);
     }
+
+    #[test]
+    fn test_parse_transform() {
+        let decl = CssParser::new("transform: translate(10px, 50%) rotate(0.25turn) scaleX(2)")
+            .parse_declaration()
+            .unwrap();
+        assert_eq!(decl.property, PropertyId::Transform);
+        match decl.value {
+            CssValue::List(ref functions) => {
+                assert_eq!(functions.len(), 3);
+                assert_eq!(
+                    functions[0],
+                    CssValue::Function(
+                        "translate".to_string(),
+                        vec![
+                            CssValue::Length(Length::px(10.0)),
+                            CssValue::Length(Length::percent(50.0))
+                        ]
+                    )
+                );
+                assert_eq!(
+                    functions[1],
+                    CssValue::Function("rotate".to_string(), vec![CssValue::Angle(90.0)])
+                );
+            }
+            ref other => panic!("unexpected value {:?}", other),
+        }
+
+        let parse = |value: &str| CssParser::new(value).parse_declaration();
+        assert!(parse("transform: none").is_ok());
+        assert!(parse("transform: rotate(10px)").is_err());
+        assert!(parse("transform: translate(1px, 2px, 3px)").is_err());
+        assert!(parse("transform: spin(10deg)").is_err());
+    }
+
+    #[test]
+    fn test_parse_transform_origin() {
+        let origin = |value: &str| {
+            CssParser::new(value)
+                .parse_declaration()
+                .map(|decl| decl.value)
+        };
+        let point =
+            |x: Length, y: Length| CssValue::List(vec![CssValue::Length(x), CssValue::Length(y)]);
+
+        assert_eq!(
+            origin("transform-origin: top left"),
+            Ok(point(Length::percent(0.0), Length::percent(0.0)))
+        );
+        assert_eq!(
+            origin("transform-origin: bottom"),
+            Ok(point(Length::percent(50.0), Length::percent(100.0)))
+        );
+        assert_eq!(
+            origin("transform-origin: 10px center 4px"),
+            Ok(point(Length::px(10.0), Length::percent(50.0)))
+        );
+        assert!(origin("transform-origin: left right").is_err());
+    }
 }
@@ -90,6 +90,7 @@ pub enum PropertyId {
     FontStyle,
     LineHeight,
     TextAlign,
+    VerticalAlign,
     TextDecoration,
     TextDecorationLine,
     TextDecorationColor,
@@ -159,6 +160,9 @@ pub enum PropertyId {
     PageBreakAfter,
     PageBreakInside,
 
+    // Color adjustment
+    ColorScheme,
+
     // Custom property
     Custom,
 }
@@ -234,6 +238,7 @@ impl PropertyId {
             PropertyId::FontStyle => "font-style",
             PropertyId::LineHeight => "line-height",
             PropertyId::TextAlign => "text-align",
+            PropertyId::VerticalAlign => "vertical-align",
             PropertyId::TextDecoration => "text-decoration",
             PropertyId::TextDecorationLine => "text-decoration-line",
             PropertyId::TextDecorationColor => "text-decoration-color",
@@ -286,6 +291,7 @@ impl PropertyId {
             PropertyId::PageBreakBefore => "page-break-before",
             PropertyId::PageBreakAfter => "page-break-after",
             PropertyId::PageBreakInside => "page-break-inside",
+            PropertyId::ColorScheme => "color-scheme",
             PropertyId::Custom => "custom",
         }
     }
@@ -318,6 +324,7 @@ impl PropertyId {
                 | PropertyId::BorderSpacing
                 | PropertyId::CaptionSide
                 | PropertyId::EmptyCells
+                | PropertyId::ColorScheme
         )
     }
 
@@ -385,6 +392,7 @@ impl PropertyId {
             "font-style" => Some(PropertyId::FontStyle),
             "line-height" => Some(PropertyId::LineHeight),
             "text-align" => Some(PropertyId::TextAlign),
+            "vertical-align" => Some(PropertyId::VerticalAlign),
             "text-decoration" => Some(PropertyId::TextDecoration),
             "text-decoration-line" => Some(PropertyId::TextDecorationLine),
             "white-space" => Some(PropertyId::WhiteSpace),
@@ -392,11 +400,14 @@ impl PropertyId {
             "background-color" => Some(PropertyId::BackgroundColor),
             "background-image" => Some(PropertyId::BackgroundImage),
             "opacity" => Some(PropertyId::Opacity),
+            "box-shadow" => Some(PropertyId::BoxShadow),
             "transform" => Some(PropertyId::Transform),
+            "transform-origin" => Some(PropertyId::TransformOrigin),
             "transition" => Some(PropertyId::Transition),
             "animation" => Some(PropertyId::Animation),
             "cursor" => Some(PropertyId::Cursor),
             "pointer-events" => Some(PropertyId::PointerEvents),
+            "color-scheme" => Some(PropertyId::ColorScheme),
             _ => None,
         }
     }
@@ -47,6 +47,11 @@ impl SelectorList {
             .max()
             .unwrap_or_default()
     }
+
+    /// Check if any selector in the list matches an element.
+    pub fn matches<E: Element>(&self, element: &E) -> bool {
+        self.selectors.iter().any(|s| s.matches(element))
+    }
 }
 
 /// A CSS selector.
@@ -104,9 +109,10 @@ impl Selector {
         for component in &self.components {
             match component {
                 SelectorComponent::Id(_) => spec.id += 1,
-                SelectorComponent::Class(_)
-                | SelectorComponent::Attribute { .. }
-                | SelectorComponent::PseudoClass(_) => spec.class += 1,
+                SelectorComponent::Class(_) | SelectorComponent::Attribute { .. } => {
+                    spec.class += 1
+                }
+                SelectorComponent::PseudoClass(pseudo) => spec += pseudo.specificity(),
                 SelectorComponent::Type(_) | SelectorComponent::PseudoElement(_) => {
                     spec.element += 1
                 }
@@ -121,6 +127,11 @@ impl Selector {
     pub fn is_empty(&self) -> bool {
         self.components.is_empty()
     }
+
+    /// Check if this selector matches an element.
+    pub fn matches<E: Element>(&self, element: &E) -> bool {
+        matches_complex(&self.components, element)
+    }
 }
 
 impl Default for Selector {
@@ -183,6 +194,56 @@ pub enum CaseSensitivity {
     AsciiCaseInsensitive,
 }
 
+impl AttributeOperator {
+    /// Check whether an attribute value satisfies this operator.
+    ///
+    /// `expected` is ignored for [`AttributeOperator::Exists`]. Following
+    /// Selectors Level 4, `~=` never matches an empty or whitespace-containing
+    /// value, and `^=`, `$=` and `*=` never match an empty value.
+    pub fn matches(self, actual: &str, expected: &str, case: CaseSensitivity) -> bool {
+        let actual = actual.as_bytes();
+        let expected = expected.as_bytes();
+        let eq = |a: &[u8], b: &[u8]| match case {
+            CaseSensitivity::CaseSensitive => a == b,
+            CaseSensitivity::AsciiCaseInsensitive => a.eq_ignore_ascii_case(b),
+        };
+
+        match self {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Equals => eq(actual, expected),
+            AttributeOperator::Includes => {
+                !expected.is_empty()
+                    && !expected.iter().any(u8::is_ascii_whitespace)
+                    && actual
+                        .split(u8::is_ascii_whitespace)
+                        .any(|word| eq(word, expected))
+            }
+            AttributeOperator::DashMatch => {
+                eq(actual, expected)
+                    || (actual.len() > expected.len()
+                        && actual[expected.len()] == b'-'
+                        && eq(&actual[..expected.len()], expected))
+            }
+            AttributeOperator::Prefix => {
+                !expected.is_empty()
+                    && actual.len() >= expected.len()
+                    && eq(&actual[..expected.len()], expected)
+            }
+            AttributeOperator::Suffix => {
+                !expected.is_empty()
+                    && actual.len() >= expected.len()
+                    && eq(&actual[actual.len() - expected.len()..], expected)
+            }
+            AttributeOperator::Substring => {
+                !expected.is_empty()
+                    && actual
+                        .windows(expected.len())
+                        .any(|window| eq(window, expected))
+            }
+        }
+    }
+}
+
 /// Selector combinators.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Combinator {
@@ -263,6 +324,22 @@ pub enum PseudoClass {
     Scope,
 }
 
+impl PseudoClass {
+    /// Specificity contributed by this pseudo-class.
+    ///
+    /// `:not()`, `:is()` and `:has()` take the specificity of their most
+    /// specific argument and `:where()` contributes nothing.
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            PseudoClass::Not(list) | PseudoClass::Is(list) | PseudoClass::Has(list) => {
+                list.max_specificity()
+            }
+            PseudoClass::Where(_) => Specificity::default(),
+            _ => Specificity::new(0, 1, 0),
+        }
+    }
+}
+
 /// The :dir() pseudo-class direction.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
@@ -289,6 +366,43 @@ impl NthExpr {
     /// `:nth-child(even)` = 2n
     pub const EVEN: NthExpr = NthExpr { a: 2, b: 0 };
 
+    /// Parse the `An+B` micro-syntax, including `odd` and `even`.
+    ///
+    /// Whitespace is allowed around the sign between `An` and `B`, but not
+    /// inside either part.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "odd" => return Some(Self::ODD),
+            "even" => return Some(Self::EVEN),
+            _ => {}
+        }
+
+        let Some(n) = s.find('n') else {
+            return s.parse().ok().map(|b| NthExpr::new(0, b));
+        };
+        let a = match &s[..n] {
+            "" | "+" => 1,
+            "-" => -1,
+            a if a.ends_with(|c: char| c.is_ascii_digit()) => a.parse().ok()?,
+            _ => return None,
+        };
+
+        let rest = s[n + 1..].trim_start();
+        if rest.is_empty() {
+            return Some(NthExpr::new(a, 0));
+        }
+        let (sign, digits) = match rest.as_bytes()[0] {
+            b'+' => (1, rest[1..].trim_start()),
+            b'-' => (-1, rest[1..].trim_start()),
+            _ => return None,
+        };
+        if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some(NthExpr::new(a, sign * digits.parse::<i32>().ok()?))
+    }
+
     /// Check if an index matches this expression.
     /// Index is 1-based.
     pub fn matches(&self, index: i32) -> bool {
@@ -361,6 +475,24 @@ impl Specificity {
     };
 }
 
+impl core::ops::Add for Specificity {
+    type Output = Specificity;
+
+    fn add(self, other: Specificity) -> Specificity {
+        Specificity {
+            id: self.id.saturating_add(other.id),
+            class: self.class.saturating_add(other.class),
+            element: self.element.saturating_add(other.element),
+        }
+    }
+}
+
+impl core::ops::AddAssign for Specificity {
+    fn add_assign(&mut self, other: Specificity) {
+        *self = *self + other;
+    }
+}
+
 impl PartialOrd for Specificity {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -382,6 +514,168 @@ impl fmt::Display for Specificity {
     }
 }
 
+/// An element that selectors can be matched against.
+///
+/// Each DOM implements this over its own node type. Positions among
+/// siblings, needed by the structural pseudo-classes, are computed by
+/// walking the sibling links.
+pub trait Element: Sized {
+    /// Get the parent element.
+    fn parent_element(&self) -> Option<Self>;
+
+    /// Get the previous sibling element, skipping other nodes.
+    fn prev_sibling_element(&self) -> Option<Self>;
+
+    /// Get the next sibling element, skipping other nodes.
+    fn next_sibling_element(&self) -> Option<Self>;
+
+    /// Check the element's local name (ASCII case-insensitive for HTML).
+    fn has_local_name(&self, name: &str) -> bool;
+
+    /// Check if two elements have the same local name.
+    fn is_same_type(&self, other: &Self) -> bool;
+
+    /// Check the element's ID.
+    fn has_id(&self, id: &str) -> bool;
+
+    /// Check if the element has a class.
+    fn has_class(&self, class: &str) -> bool;
+
+    /// Get an attribute value and test it with `f`.
+    ///
+    /// Returns `false` if the attribute is not set.
+    fn attribute_matches(&self, name: &str, f: &dyn Fn(&str) -> bool) -> bool;
+
+    /// Check if the element is the document element.
+    fn is_root(&self) -> bool;
+
+    /// Check if the element has no element or text children.
+    fn is_empty(&self) -> bool;
+
+    /// Match a pseudo-class that depends on state rather than the tree,
+    /// such as `:hover` or `:checked`.
+    fn matches_state(&self, _pseudo: &PseudoClass) -> bool {
+        false
+    }
+}
+
+/// Match a complex selector right to left, backtracking over
+/// descendant and subsequent-sibling combinators.
+fn matches_complex<E: Element>(components: &[SelectorComponent], element: &E) -> bool {
+    let split = components
+        .iter()
+        .rposition(|c| matches!(c, SelectorComponent::Combinator(_)));
+    let (rest, compound) = match split {
+        Some(i) => components.split_at(i + 1),
+        None => (&[][..], components),
+    };
+    if compound.is_empty() || !compound.iter().all(|c| matches_simple(c, element)) {
+        return false;
+    }
+
+    let Some((SelectorComponent::Combinator(combinator), left)) = rest.split_last() else {
+        return true;
+    };
+    match combinator {
+        Combinator::Child => element
+            .parent_element()
+            .is_some_and(|parent| matches_complex(left, &parent)),
+        Combinator::Descendant => {
+            let mut ancestor = element.parent_element();
+            while let Some(current) = ancestor {
+                if matches_complex(left, &current) {
+                    return true;
+                }
+                ancestor = current.parent_element();
+            }
+            false
+        }
+        Combinator::NextSibling => element
+            .prev_sibling_element()
+            .is_some_and(|sibling| matches_complex(left, &sibling)),
+        Combinator::SubsequentSibling => {
+            let mut sibling = element.prev_sibling_element();
+            while let Some(current) = sibling {
+                if matches_complex(left, &current) {
+                    return true;
+                }
+                sibling = current.prev_sibling_element();
+            }
+            false
+        }
+    }
+}
+
+/// Match one simple selector.
+fn matches_simple<E: Element>(component: &SelectorComponent, element: &E) -> bool {
+    match component {
+        SelectorComponent::Universal => true,
+        SelectorComponent::Type(name) => element.has_local_name(name.as_str()),
+        SelectorComponent::Class(class) => element.has_class(class),
+        SelectorComponent::Id(id) => element.has_id(id),
+        SelectorComponent::Attribute {
+            name,
+            operator,
+            value,
+            case_sensitivity,
+            ..
+        } => element.attribute_matches(name.as_str(), &|actual| {
+            operator.matches(actual, value.as_deref().unwrap_or(""), *case_sensitivity)
+        }),
+        SelectorComponent::PseudoClass(pseudo) => matches_pseudo_class(pseudo, element),
+        // Pseudo-elements style generated boxes, never the element itself
+        SelectorComponent::PseudoElement(_) => false,
+        SelectorComponent::Combinator(_) => false,
+    }
+}
+
+fn matches_pseudo_class<E: Element>(pseudo: &PseudoClass, element: &E) -> bool {
+    match pseudo {
+        PseudoClass::Root => element.is_root(),
+        PseudoClass::Empty => element.is_empty(),
+        PseudoClass::FirstChild => sibling_index(element, false, false) == 1,
+        PseudoClass::LastChild => sibling_index(element, false, true) == 1,
+        PseudoClass::OnlyChild => {
+            sibling_index(element, false, false) == 1 && sibling_index(element, false, true) == 1
+        }
+        PseudoClass::FirstOfType => sibling_index(element, true, false) == 1,
+        PseudoClass::LastOfType => sibling_index(element, true, true) == 1,
+        PseudoClass::OnlyOfType => {
+            sibling_index(element, true, false) == 1 && sibling_index(element, true, true) == 1
+        }
+        PseudoClass::NthChild(expr) => expr.matches(sibling_index(element, false, false)),
+        PseudoClass::NthLastChild(expr) => expr.matches(sibling_index(element, false, true)),
+        PseudoClass::NthOfType(expr) => expr.matches(sibling_index(element, true, false)),
+        PseudoClass::NthLastOfType(expr) => expr.matches(sibling_index(element, true, true)),
+        PseudoClass::Not(list) => !list.matches(element),
+        PseudoClass::Is(list) | PseudoClass::Where(list) => list.matches(element),
+        _ => element.matches_state(pseudo),
+    }
+}
+
+/// 1-based position of `element` among its sibling elements, counting
+/// from the end if `from_end`, and only elements of its type if
+/// `of_type`.
+fn sibling_index<E: Element>(element: &E, of_type: bool, from_end: bool) -> i32 {
+    let step = |e: &E| {
+        if from_end {
+            e.next_sibling_element()
+        } else {
+            e.prev_sibling_element()
+        }
+    };
+
+    let mut index = 1;
+    let mut sibling = step(element);
+    while let Some(current) = sibling {
+        if !of_type || current.is_same_type(element) {
+            index += 1;
+        }
+        sibling = step(&current);
+    }
+    index
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -406,4 +700,175 @@ mod tests {
         assert!(NthExpr::EVEN.matches(4));
         assert!(!NthExpr::EVEN.matches(1));
     }
+
+    #[test]
+    fn test_nth_expr_parse() {
+        let parse = |s| NthExpr::parse(s).map(|e| (e.a, e.b));
+        assert_eq!(parse("odd"), Some((2, 1)));
+        assert_eq!(parse(" EVEN "), Some((2, 0)));
+        assert_eq!(parse("3"), Some((0, 3)));
+        assert_eq!(parse("-2"), Some((0, -2)));
+        assert_eq!(parse("n"), Some((1, 0)));
+        assert_eq!(parse("-n+3"), Some((-1, 3)));
+        assert_eq!(parse("+n"), Some((1, 0)));
+        assert_eq!(parse("2n+1"), Some((2, 1)));
+        assert_eq!(parse("3n - 2"), Some((3, -2)));
+        assert_eq!(parse("-2n"), Some((-2, 0)));
+        assert_eq!(parse("2 n"), None);
+        assert_eq!(parse("2n+"), None);
+        assert_eq!(parse("2n 1"), None);
+        assert_eq!(parse("x"), None);
+
+        let first_three = NthExpr::parse("-n+3").unwrap();
+        assert!(first_three.matches(1) && first_three.matches(3));
+        assert!(!first_three.matches(4));
+    }
+
+    fn parse(s: &str) -> SelectorList {
+        crate::parser::CssParser::new(s)
+            .parse_selector_list()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_pseudo_class_specificity() {
+        let spec = |s: &str| parse(s).max_specificity();
+        assert_eq!(spec("li:nth-child(2n+1)"), Specificity::new(0, 1, 1));
+        assert_eq!(spec(":not(#a, .b)"), Specificity::new(1, 0, 0));
+        assert_eq!(spec("p:is(.a, div)"), Specificity::new(0, 1, 1));
+        assert_eq!(spec("p:where(#a .b)"), Specificity::new(0, 0, 1));
+        assert_eq!(spec(":where(:not(#a))"), Specificity::default());
+    }
+
+    /// A flat tree of elements for matching tests.
+    struct Tree {
+        /// (tag, class, parent)
+        nodes: Vec<(&'static str, &'static str, Option<usize>)>,
+    }
+
+    #[derive(Clone, Copy)]
+    struct Node<'a>(&'a Tree, usize);
+
+    impl Node<'_> {
+        fn siblings(&self) -> Vec<usize> {
+            let parent = self.0.nodes[self.1].2;
+            (0..self.0.nodes.len())
+                .filter(|&i| parent.is_some() && self.0.nodes[i].2 == parent)
+                .collect()
+        }
+
+        fn sibling(&self, offset: isize) -> Option<Self> {
+            let siblings = self.siblings();
+            let pos = siblings.iter().position(|&i| i == self.1)?;
+            let pos = pos.checked_add_signed(offset)?;
+            siblings.get(pos).map(|&i| Node(self.0, i))
+        }
+    }
+
+    impl Element for Node<'_> {
+        fn parent_element(&self) -> Option<Self> {
+            self.0.nodes[self.1].2.map(|i| Node(self.0, i))
+        }
+        fn prev_sibling_element(&self) -> Option<Self> {
+            self.sibling(-1)
+        }
+        fn next_sibling_element(&self) -> Option<Self> {
+            self.sibling(1)
+        }
+        fn has_local_name(&self, name: &str) -> bool {
+            self.0.nodes[self.1].0 == name
+        }
+        fn is_same_type(&self, other: &Self) -> bool {
+            self.0.nodes[self.1].0 == other.0.nodes[other.1].0
+        }
+        fn has_id(&self, _id: &str) -> bool {
+            false
+        }
+        fn has_class(&self, class: &str) -> bool {
+            self.0.nodes[self.1].1 == class
+        }
+        fn attribute_matches(&self, _name: &str, _f: &dyn Fn(&str) -> bool) -> bool {
+            false
+        }
+        fn is_root(&self) -> bool {
+            self.1 == 0
+        }
+        fn is_empty(&self) -> bool {
+            !self.0.nodes.iter().any(|n| n.2 == Some(self.1))
+        }
+    }
+
+    #[test]
+    fn test_structural_matching() {
+        // <ul><li/><p class=x/><li class=x/><li/></ul>
+        let tree = Tree {
+            nodes: alloc::vec![
+                ("ul", "", None),
+                ("li", "", Some(0)),
+                ("p", "x", Some(0)),
+                ("li", "x", Some(0)),
+                ("li", "", Some(0)),
+            ],
+        };
+        let matching = |s: &str| -> Vec<usize> {
+            let list = parse(s);
+            (0..tree.nodes.len())
+                .filter(|&i| list.matches(&Node(&tree, i)))
+                .collect()
+        };
+
+        assert_eq!(matching(":root"), [0]);
+        assert_eq!(matching("li:first-child"), [1]);
+        assert_eq!(matching("li:last-child"), [4]);
+        assert_eq!(matching(":only-child"), [0]);
+        assert_eq!(matching("ul > :nth-child(odd)"), [1, 3]);
+        assert_eq!(matching("li:nth-last-child(-n+2)"), [3, 4]);
+        assert_eq!(matching("li:nth-of-type(2)"), [3]);
+        assert_eq!(matching(":nth-last-of-type(1)"), [0, 2, 4]);
+        assert_eq!(matching(":only-of-type"), [0, 2]);
+        assert_eq!(matching("li:not(.x)"), [1, 4]);
+        assert_eq!(matching(":is(p, li).x"), [2, 3]);
+        assert_eq!(matching("ul :where(.x)"), [2, 3]);
+        assert_eq!(matching(".x ~ li"), [3, 4]);
+        assert_eq!(matching("p + li"), [3]);
+        assert_eq!(matching("ul li:empty:last-child"), [4]);
+    }
+
+    #[test]
+    fn test_attribute_operator_matches() {
+        let cs = CaseSensitivity::CaseSensitive;
+        let ci = CaseSensitivity::AsciiCaseInsensitive;
+
+        assert!(AttributeOperator::Equals.matches("text", "text", cs));
+        assert!(!AttributeOperator::Equals.matches("Text", "text", cs));
+        assert!(AttributeOperator::Equals.matches("Text", "text", ci));
+
+        assert!(AttributeOperator::Prefix.matches("https://a.b", "https:", cs));
+        assert!(!AttributeOperator::Prefix.matches("http://a.b", "https:", cs));
+        assert!(AttributeOperator::Prefix.matches("HTTPS://a.b", "https:", ci));
+        assert!(!AttributeOperator::Prefix.matches("anything", "", cs));
+
+        assert!(AttributeOperator::Suffix.matches("report.pdf", ".pdf", cs));
+        assert!(!AttributeOperator::Suffix.matches("report.PDF", ".pdf", cs));
+        assert!(AttributeOperator::Suffix.matches("report.PDF", ".pdf", ci));
+        assert!(!AttributeOperator::Suffix.matches("pdf", ".pdf", cs));
+
+        assert!(AttributeOperator::Substring.matches("btn-primary-lg", "primary", cs));
+        assert!(!AttributeOperator::Substring.matches("btn-secondary", "primary", cs));
+        assert!(AttributeOperator::Substring.matches("btn-PRIMARY", "primary", ci));
+        assert!(!AttributeOperator::Substring.matches("abc", "", cs));
+
+        assert!(AttributeOperator::Includes.matches("a  nav\tb", "nav", cs));
+        assert!(!AttributeOperator::Includes.matches("navbar", "nav", cs));
+        assert!(!AttributeOperator::Includes.matches("a b", "a b", cs));
+        assert!(!AttributeOperator::Includes.matches("", "", cs));
+        assert!(AttributeOperator::Includes.matches("a NAV", "nav", ci));
+
+        assert!(AttributeOperator::DashMatch.matches("en", "en", cs));
+        assert!(AttributeOperator::DashMatch.matches("en-US", "en", cs));
+        assert!(!AttributeOperator::DashMatch.matches("english", "en", cs));
+        assert!(AttributeOperator::DashMatch.matches("EN-us", "en", ci));
+
+        assert!(AttributeOperator::Exists.matches("", "", cs));
+    }
 }
@@ -3,6 +3,8 @@
 use alloc::string::String;
 use alloc::vec::Vec;
 
+use crate::import::ImportRule;
+use crate::parser::CssParser;
 use crate::properties::DeclarationBlock;
 use crate::selector::SelectorList;
 
@@ -46,6 +48,17 @@ impl Stylesheet {
         })
     }
 
+    /// Get the style rules that apply in `context`, in cascade order.
+    ///
+    /// Rules inside `@media` blocks are included in place when the block's
+    /// query list matches `context`; nested `@media` blocks are evaluated
+    /// the same way.
+    pub fn active_style_rules(&self, context: &MediaContext) -> Vec<StyleRule> {
+        let mut active = Vec::new();
+        collect_active_rules(&self.rules, context, &mut active);
+        active
+    }
+
     /// Check if empty.
     pub fn is_empty(&self) -> bool {
         self.rules.is_empty()
@@ -57,6 +70,26 @@ impl Stylesheet {
     }
 }
 
+/// Append the style rules of `rules` that apply in `context` to `out`.
+fn collect_active_rules(rules: &[Rule], context: &MediaContext, out: &mut Vec<StyleRule>) {
+    for rule in rules {
+        match rule {
+            Rule::Style(style_rule) => out.push(style_rule.clone()),
+            Rule::AtRule(at) => {
+                let Some(queries) = at.media_queries() else {
+                    continue;
+                };
+                if !queries.matches(context) {
+                    continue;
+                }
+                if let Some(nested) = at.block_rules() {
+                    collect_active_rules(&nested.rules, context, out);
+                }
+            }
+        }
+    }
+}
+
 /// The origin of a stylesheet.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum StylesheetOrigin {
@@ -134,11 +167,35 @@ impl AtRule {
         self.name == "media"
     }
 
+    /// Parse the query list of a `@media` rule.
+    pub fn media_queries(&self) -> Option<MediaQueryList> {
+        if self.is_media() {
+            Some(MediaQueryList::parse(&self.prelude))
+        } else {
+            None
+        }
+    }
+
+    /// Parse the rules inside this at-rule's block.
+    pub fn block_rules(&self) -> Option<Stylesheet> {
+        let block = self.block.as_ref()?;
+        CssParser::new(block).parse_stylesheet().ok()
+    }
+
     /// Check if this is an @import rule.
     pub fn is_import(&self) -> bool {
         self.name == "import"
     }
 
+    /// Parse this rule as an `@import`, if it is one.
+    pub fn import_rule(&self) -> Option<ImportRule> {
+        if self.is_import() {
+            ImportRule::parse(&self.prelude)
+        } else {
+            None
+        }
+    }
+
     /// Check if this is a @font-face rule.
     pub fn is_font_face(&self) -> bool {
         self.name == "font-face"
@@ -155,9 +212,86 @@ impl AtRule {
 pub struct MediaQuery {
     pub media_type: MediaType,
     pub conditions: Vec<MediaCondition>,
+    /// Query was prefixed with `not`.
+    pub negated: bool,
 }
 
 impl MediaQuery {
+    /// The `not all` query, which never matches.
+    pub fn not_all() -> Self {
+        MediaQuery {
+            media_type: MediaType::All,
+            conditions: Vec::new(),
+            negated: true,
+        }
+    }
+
+    /// Parse a single media query such as `screen and (min-width: 768px)`.
+    ///
+    /// Returns `None` for unknown media types, unknown features, or
+    /// malformed syntax.
+    pub fn parse(input: &str) -> Option<MediaQuery> {
+        let input = input.trim().to_ascii_lowercase();
+        let mut rest = input.as_str();
+
+        let mut query = MediaQuery {
+            media_type: MediaType::All,
+            conditions: Vec::new(),
+            negated: false,
+        };
+
+        // Optional `not`/`only` followed by a media type
+        if !rest.starts_with('(') {
+            let (word, tail) = split_word(rest);
+            rest = tail;
+            let type_word = match word {
+                "not" | "only" => {
+                    query.negated = word == "not";
+                    let (word, tail) = split_word(rest);
+                    rest = tail;
+                    word
+                }
+                _ => word,
+            };
+            query.media_type = match type_word {
+                "all" => MediaType::All,
+                "screen" => MediaType::Screen,
+                "print" => MediaType::Print,
+                _ => return None,
+            };
+
+            if rest.is_empty() {
+                return Some(query);
+            }
+            let (word, tail) = split_word(rest);
+            if word != "and" {
+                return None;
+            }
+            rest = tail;
+        }
+
+        // Feature list joined by `and`
+        loop {
+            let close = rest.find(')')?;
+            if !rest.starts_with('(') {
+                return None;
+            }
+            query
+                .conditions
+                .push(MediaCondition::parse(&rest[1..close])?);
+            rest = rest[close + 1..].trim_start();
+
+            if rest.is_empty() {
+                return Some(query);
+            }
+            let (word, tail) = split_word(rest);
+            if word != "and" {
+                return None;
+            }
+            rest = tail;
+        }
+    }
+
     /// Check if this query matches.
     pub fn matches(&self, context: &MediaContext) -> bool {
         // Check media type
@@ -167,15 +301,57 @@ impl MediaQuery {
             MediaType::Print => context.media_type == MediaType::Print,
         };
 
-        if !type_matches {
-            return false;
-        }
-
         // Check all conditions
-        self.conditions.iter().all(|c| c.matches(context))
+        let matches = type_matches && self.conditions.iter().all(|c| c.matches(context));
+
+        matches != self.negated
+    }
+}
+
+/// A comma-separated list of media queries.
+///
+/// An empty list matches every context.
+#[derive(Debug, Clone, Default)]
+pub struct MediaQueryList {
+    pub queries: Vec<MediaQuery>,
+}
+
+impl MediaQueryList {
+    /// Parse a media query list. Queries that fail to parse become
+    /// `not all`, so they never match but do not invalidate the list.
+    pub fn parse(input: &str) -> Self {
+        let queries = if input.trim().is_empty() {
+            Vec::new()
+        } else {
+            input
+                .split(',')
+                .map(|q| MediaQuery::parse(q).unwrap_or_else(MediaQuery::not_all))
+                .collect()
+        };
+
+        MediaQueryList { queries }
+    }
+
+    /// Check if any query in the list matches.
+    pub fn matches(&self, context: &MediaContext) -> bool {
+        self.queries.is_empty() || self.queries.iter().any(|q| q.matches(context))
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
     }
 }
 
+/// Split the leading whitespace-delimited word off `input`.
+fn split_word(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    let end = input
+        .find(|c: char| c.is_whitespace() || c == '(')
+        .unwrap_or(input.len());
+    (&input[..end], input[end..].trim_start())
+}
+
 /// Media type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MediaType {
@@ -198,6 +374,35 @@ pub enum MediaCondition {
 }
 
 impl MediaCondition {
+    /// Parse the inside of a media feature, e.g. `min-width: 768px`.
+    pub fn parse(feature: &str) -> Option<MediaCondition> {
+        let (name, value) = feature.split_once(':')?;
+        let value = value.trim();
+
+        match name.trim() {
+            "min-width" => parse_media_length(value).map(MediaCondition::MinWidth),
+            "max-width" => parse_media_length(value).map(MediaCondition::MaxWidth),
+            "min-height" => parse_media_length(value).map(MediaCondition::MinHeight),
+            "max-height" => parse_media_length(value).map(MediaCondition::MaxHeight),
+            "orientation" => match value {
+                "landscape" => Some(MediaCondition::Orientation(Orientation::Landscape)),
+                "portrait" => Some(MediaCondition::Orientation(Orientation::Portrait)),
+                _ => None,
+            },
+            "prefers-color-scheme" => match value {
+                "light" => Some(MediaCondition::PrefersColorScheme(ColorScheme::Light)),
+                "dark" => Some(MediaCondition::PrefersColorScheme(ColorScheme::Dark)),
+                _ => None,
+            },
+            "prefers-reduced-motion" => match value {
+                "reduce" => Some(MediaCondition::PrefersReducedMotion(true)),
+                "no-preference" => Some(MediaCondition::PrefersReducedMotion(false)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     /// Check if this condition matches.
     pub fn matches(&self, context: &MediaContext) -> bool {
         match self {
@@ -212,6 +417,25 @@ impl MediaCondition {
     }
 }
 
+/// Parse a media feature length in CSS pixels (`px`, `em`/`rem` at 16px, or `0`).
+fn parse_media_length(value: &str) -> Option<f32> {
+    if value == "0" {
+        return Some(0.0);
+    }
+
+    let (number, scale) = if let Some(n) = value.strip_suffix("px") {
+        (n, 1.0)
+    } else if let Some(n) = value.strip_suffix("rem") {
+        (n, 16.0)
+    } else if let Some(n) = value.strip_suffix("em") {
+        (n, 16.0)
+    } else {
+        return None;
+    };
+
+    number.trim().parse::<f32>().ok().map(|n| n * scale)
+}
+
 /// Screen orientation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Orientation {
@@ -228,6 +452,13 @@ pub enum ColorScheme {
     Dark,
 }
 
+impl ColorScheme {
+    /// Check if this is the dark scheme.
+    pub fn is_dark(&self) -> bool {
+        *self == ColorScheme::Dark
+    }
+}
+
 /// Context for evaluating media queries.
 #[derive(Debug, Clone)]
 pub struct MediaContext {
@@ -251,3 +482,98 @@ impl Default for MediaContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cascade::CascadedValues;
+    use crate::computed::ComputedStyle;
+    use crate::properties::PropertyId;
+    use crate::values::{Color, ColorSchemes, CssValue, LengthContext};
+
+    fn parse(css: &str) -> Stylesheet {
+        CssParser::new(css).parse_stylesheet().unwrap()
+    }
+
+    fn context(color_scheme: ColorScheme) -> MediaContext {
+        MediaContext {
+            color_scheme,
+            ..MediaContext::default()
+        }
+    }
+
+    fn background(rules: &[StyleRule]) -> Option<Color> {
+        let mut cascaded = CascadedValues::new();
+        for (order, rule) in rules.iter().enumerate() {
+            cascaded.apply(
+                &rule.declarations,
+                rule.selectors.max_specificity(),
+                StylesheetOrigin::Author,
+                order as u32,
+            );
+        }
+        match cascaded.get(PropertyId::BackgroundColor)?.value {
+            CssValue::Color(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_prefers_color_scheme_rules() {
+        let sheet = parse(
+            "body { background-color: white; }
+             @media (prefers-color-scheme: dark) {
+                 body { background-color: black; }
+                 @media (min-width: 4000px) { p { color: red; } }
+             }
+             @media print { body { background-color: red; } }",
+        );
+
+        let light = sheet.active_style_rules(&context(ColorScheme::Light));
+        assert_eq!(light.len(), 1);
+        assert_eq!(background(&light), Some(Color::WHITE));
+
+        let dark = sheet.active_style_rules(&context(ColorScheme::Dark));
+        assert_eq!(dark.len(), 2);
+        assert_eq!(background(&dark), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn test_color_scheme_property() {
+        assert_eq!(ColorSchemes::parse("normal"), Some(ColorSchemes::NORMAL));
+        assert_eq!(ColorSchemes::parse("only"), None);
+        assert_eq!(ColorSchemes::parse("normal dark"), None);
+
+        let both = ColorSchemes::parse("light dark").unwrap();
+        assert_eq!(both.used(ColorScheme::Dark), ColorScheme::Dark);
+        assert_eq!(both.used(ColorScheme::Light), ColorScheme::Light);
+
+        let dark_only = ColorSchemes::parse("only dark").unwrap();
+        assert_eq!(dark_only.used(ColorScheme::Light), ColorScheme::Dark);
+        assert_eq!(
+            ColorSchemes::NORMAL.used(ColorScheme::Dark),
+            ColorScheme::Light
+        );
+
+        // Inherited by descendants
+        let sheet = parse(":root { color-scheme: light dark; }");
+        let mut cascaded = CascadedValues::new();
+        cascaded.apply(
+            &sheet.style_rules().next().unwrap().declarations,
+            Default::default(),
+            StylesheetOrigin::Author,
+            0,
+        );
+        let ctx = LengthContext::default();
+        let root = ComputedStyle::compute(&cascaded, None, &ctx);
+        let child = ComputedStyle::compute(&CascadedValues::new(), Some(&root), &ctx);
+        assert_eq!(
+            child.used_color_scheme(ColorScheme::Dark),
+            ColorScheme::Dark
+        );
+        assert_eq!(
+            ComputedStyle::default().used_color_scheme(ColorScheme::Dark),
+            ColorScheme::Light
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Transforms - The `transform` and `transform-origin` properties
+//!
+//! A transform list is kept as its functions until layout is done, since
+//! percentages in `translate()` and `transform-origin` refer to the size
+//! of the element's border box. [`TransformMatrix::from_functions`] then
+//! composes the list into one 2D affine matrix.
+//!
+//! 3D functions are accepted and flattened: their z components are
+//! dropped, which is what a 2D compositor would draw without `perspective`.
+
+use alloc::vec::Vec;
+
+use crate::values::{CssValue, Length, LengthContext};
+
+/// A 2D affine transform `[a, b, c, d, e, f]`, mapping a point (x, y) to
+/// (a·x + c·y + e, b·x + d·y + f).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransformMatrix(pub [f32; 6]);
+
+impl TransformMatrix {
+    pub const IDENTITY: TransformMatrix = TransformMatrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    pub fn translate(x: f32, y: f32) -> Self {
+        TransformMatrix([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        TransformMatrix([x, 0.0, 0.0, y, 0.0, 0.0])
+    }
+
+    /// Clockwise rotation (with y pointing down) by `degrees`.
+    pub fn rotate(degrees: f32) -> Self {
+        let radians = degrees.to_radians();
+        let (sin, cos) = (libm::sinf(radians), libm::cosf(radians));
+        TransformMatrix([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    pub fn skew(x_degrees: f32, y_degrees: f32) -> Self {
+        let tan_x = libm::tanf(x_degrees.to_radians());
+        let tan_y = libm::tanf(y_degrees.to_radians());
+        TransformMatrix([1.0, tan_y, tan_x, 1.0, 0.0, 0.0])
+    }
+
+    /// The transform that applies `other` first, then `self`.
+    pub fn multiply(&self, other: &TransformMatrix) -> TransformMatrix {
+        let [a1, b1, c1, d1, e1, f1] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+        TransformMatrix([
+            a1 * a2 + c1 * b2,
+            b1 * a2 + d1 * b2,
+            a1 * c2 + c1 * d2,
+            b1 * c2 + d1 * d2,
+            a1 * e2 + c1 * f2 + e1,
+            b1 * e2 + d1 * f2 + f1,
+        ])
+    }
+
+    /// The same transform applied about (x, y) instead of the origin.
+    pub fn about(&self, x: f32, y: f32) -> TransformMatrix {
+        TransformMatrix::translate(x, y)
+            .multiply(self)
+            .multiply(&TransformMatrix::translate(-x, -y))
+    }
+
+    /// Map a point through the transform.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == TransformMatrix::IDENTITY
+    }
+
+    /// Compose a transform list for a border box of the given size.
+    /// Functions apply right to left, as in CSS.
+    pub fn from_functions(
+        functions: &[TransformFunction],
+        width: f32,
+        height: f32,
+        context: &LengthContext,
+    ) -> TransformMatrix {
+        functions
+            .iter()
+            .fold(TransformMatrix::IDENTITY, |matrix, function| {
+                matrix.multiply(&function.to_matrix(width, height, context))
+            })
+    }
+}
+
+impl Default for TransformMatrix {
+    fn default() -> Self {
+        TransformMatrix::IDENTITY
+    }
+}
+
+/// A single function of a `transform` list. Angles are in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransformFunction {
+    Matrix([f32; 6]),
+    Translate(Length, Length),
+    Scale(f32, f32),
+    Rotate(f32),
+    Skew(f32, f32),
+}
+
+impl TransformFunction {
+    /// Build a transform function from a parsed CSS function, or `None`
+    /// if the name or arguments are invalid.
+    pub fn from_css(name: &str, args: &[CssValue]) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        let function = match (name.as_str(), args) {
+            ("matrix", [_, _, _, _, _, _]) => {
+                let mut matrix = [0.0; 6];
+                for (slot, arg) in matrix.iter_mut().zip(args) {
+                    *slot = number(arg)?;
+                }
+                TransformFunction::Matrix(matrix)
+            }
+            ("translate", [x]) => TransformFunction::Translate(length(x)?, Length::zero()),
+            ("translate", [x, y]) | ("translate3d", [x, y, _]) => {
+                TransformFunction::Translate(length(x)?, length(y)?)
+            }
+            ("translatex", [x]) => TransformFunction::Translate(length(x)?, Length::zero()),
+            ("translatey", [y]) => TransformFunction::Translate(Length::zero(), length(y)?),
+            ("translatez", [_]) => TransformFunction::Translate(Length::zero(), Length::zero()),
+            ("scale", [s]) => TransformFunction::Scale(number(s)?, number(s)?),
+            ("scale", [x, y]) | ("scale3d", [x, y, _]) => {
+                TransformFunction::Scale(number(x)?, number(y)?)
+            }
+            ("scalex", [x]) => TransformFunction::Scale(number(x)?, 1.0),
+            ("scaley", [y]) => TransformFunction::Scale(1.0, number(y)?),
+            ("scalez", [_]) => TransformFunction::Scale(1.0, 1.0),
+            ("rotate" | "rotatez", [a]) => TransformFunction::Rotate(angle(a)?),
+            ("skew", [x]) => TransformFunction::Skew(angle(x)?, 0.0),
+            ("skew", [x, y]) => TransformFunction::Skew(angle(x)?, angle(y)?),
+            ("skewx", [x]) => TransformFunction::Skew(angle(x)?, 0.0),
+            ("skewy", [y]) => TransformFunction::Skew(0.0, angle(y)?),
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    /// Build the transform functions of a parsed `transform` value.
+    /// `none` and invalid values give an empty list.
+    pub fn list_from_css(value: &CssValue) -> Vec<TransformFunction> {
+        let functions = match value {
+            CssValue::List(items) => items.as_slice(),
+            CssValue::Function(..) => core::slice::from_ref(value),
+            _ => return Vec::new(),
+        };
+        functions
+            .iter()
+            .map(|function| match function {
+                CssValue::Function(name, args) => TransformFunction::from_css(name, args),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default()
+    }
+
+    /// The matrix of this function for a border box of the given size.
+    pub fn to_matrix(&self, width: f32, height: f32, context: &LengthContext) -> TransformMatrix {
+        match *self {
+            TransformFunction::Matrix(matrix) => TransformMatrix(matrix),
+            TransformFunction::Translate(x, y) => TransformMatrix::translate(
+                x.to_px(&LengthContext {
+                    containing_block: width,
+                    ..*context
+                }),
+                y.to_px(&LengthContext {
+                    containing_block: height,
+                    ..*context
+                }),
+            ),
+            TransformFunction::Scale(x, y) => TransformMatrix::scale(x, y),
+            TransformFunction::Rotate(degrees) => TransformMatrix::rotate(degrees),
+            TransformFunction::Skew(x, y) => TransformMatrix::skew(x, y),
+        }
+    }
+}
+
+/// The `transform-origin` property value, relative to the border box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransformOrigin {
+    pub x: Length,
+    pub y: Length,
+}
+
+impl TransformOrigin {
+    /// Resolve to a point relative to the top-left corner of a border
+    /// box of the given size.
+    pub fn resolve(&self, width: f32, height: f32, context: &LengthContext) -> (f32, f32) {
+        (
+            self.x.to_px(&LengthContext {
+                containing_block: width,
+                ..*context
+            }),
+            self.y.to_px(&LengthContext {
+                containing_block: height,
+                ..*context
+            }),
+        )
+    }
+}
+
+impl Default for TransformOrigin {
+    fn default() -> Self {
+        TransformOrigin {
+            x: Length::percent(50.0),
+            y: Length::percent(50.0),
+        }
+    }
+}
+
+fn number(value: &CssValue) -> Option<f32> {
+    match *value {
+        CssValue::Number(n) => Some(n),
+        CssValue::Integer(i) => Some(i as f32),
+        _ => None,
+    }
+}
+
+fn length(value: &CssValue) -> Option<Length> {
+    match *value {
+        CssValue::Length(l) => Some(l),
+        CssValue::Percentage(p) => Some(Length::percent(p)),
+        CssValue::Integer(0) => Some(Length::zero()),
+        _ => None,
+    }
+}
+
+fn angle(value: &CssValue) -> Option<f32> {
+    match *value {
+        CssValue::Angle(degrees) => Some(degrees),
+        CssValue::Integer(0) => Some(0.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cascade::CascadedValues;
+    use crate::computed::ComputedStyle;
+    use crate::parser::CssParser;
+    use crate::properties::PropertyId;
+    use crate::selector::Specificity;
+    use crate::stylesheet::StylesheetOrigin;
+
+    fn assert_point(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-3 && (actual.1 - expected.1).abs() < 1e-3,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn computed(declarations: &str) -> ComputedStyle {
+        let mut cascaded = CascadedValues::new();
+        let block = CssParser::new(declarations)
+            .parse_declaration_block()
+            .unwrap();
+        cascaded.apply(&block, Specificity::default(), StylesheetOrigin::Author, 0);
+        ComputedStyle::compute(&cascaded, None, &LengthContext::default())
+    }
+
+    #[test]
+    fn test_compose_applies_right_to_left() {
+        let functions = [
+            TransformFunction::Translate(Length::px(10.0), Length::zero()),
+            TransformFunction::Rotate(90.0),
+        ];
+        let matrix =
+            TransformMatrix::from_functions(&functions, 0.0, 0.0, &LengthContext::default());
+        // Rotate (1, 0) to (0, 1), then translate
+        assert_point(matrix.apply(1.0, 0.0), (10.0, 1.0));
+
+        let skew = TransformMatrix::skew(45.0, 0.0);
+        assert_point(skew.apply(0.0, 2.0), (2.0, 2.0));
+    }
+
+    #[test]
+    fn test_percentages_and_origin_use_border_box() {
+        let style =
+            computed("transform: translate(50%, -10%) scale(2); transform-origin: left top");
+        assert_eq!(style.transform.len(), 2);
+        assert!(style.creates_stacking_context());
+
+        let context = LengthContext::default();
+        let (ox, oy) = style.transform_origin.resolve(200.0, 100.0, &context);
+        assert_point((ox, oy), (0.0, 0.0));
+
+        let matrix = TransformMatrix::from_functions(&style.transform, 200.0, 100.0, &context)
+            .about(30.0 + ox, 40.0 + oy);
+        // The box's top-left corner stays put under the scale and moves
+        // by half its width and a tenth of its height
+        assert_point(matrix.apply(30.0, 40.0), (130.0, 30.0));
+        assert_point(matrix.apply(31.0, 40.0), (132.0, 30.0));
+
+        // The default origin is the center of the box
+        let centered = TransformMatrix::scale(2.0, 2.0).about(100.0, 50.0);
+        let (cx, cy) = TransformOrigin::default().resolve(200.0, 100.0, &context);
+        assert_point((cx, cy), (100.0, 50.0));
+        assert_point(centered.apply(100.0, 50.0), (100.0, 50.0));
+    }
+
+    #[test]
+    fn test_none_clears_transform() {
+        let style = computed("transform: none");
+        assert!(style.transform.is_empty());
+        assert!(!style.creates_stacking_context());
+        assert!(TransformFunction::list_from_css(&CssValue::Keyword("none".into())).is_empty());
+        assert!(PropertyId::from_name("transform-origin").is_some());
+    }
+}
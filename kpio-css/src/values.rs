@@ -5,6 +5,8 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt;
 
+use crate::stylesheet::ColorScheme;
+
 /// A CSS value that can be applied to a property.
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssValue {
@@ -469,6 +471,68 @@ pub enum Visibility {
     Collapse,
 }
 
+/// The `color-scheme` property value: the color schemes an element can
+/// be rendered in. Neither flag set is `normal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ColorSchemes {
+    pub light: bool,
+    pub dark: bool,
+}
+
+impl ColorSchemes {
+    /// The `normal` value: the page supports no particular scheme.
+    pub const NORMAL: Self = ColorSchemes {
+        light: false,
+        dark: false,
+    };
+
+    /// Parse `normal` or a list of scheme keywords such as `light dark`.
+    ///
+    /// `only` and unknown scheme names are accepted and ignored.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("normal") {
+            return Some(Self::NORMAL);
+        }
+
+        let mut schemes = Self::NORMAL;
+        let mut any = false;
+        for word in value.split_ascii_whitespace() {
+            if word.eq_ignore_ascii_case("only") {
+                continue;
+            }
+            any = true;
+            if word.eq_ignore_ascii_case("light") {
+                schemes.light = true;
+            } else if word.eq_ignore_ascii_case("dark") {
+                schemes.dark = true;
+            } else if word.eq_ignore_ascii_case("normal") {
+                return None;
+            }
+        }
+        any.then_some(schemes)
+    }
+
+    /// Check if this is `normal`.
+    pub fn is_normal(&self) -> bool {
+        !self.light && !self.dark
+    }
+
+    /// The scheme to render with, given the user's preference.
+    ///
+    /// The preferred scheme is used when supported; otherwise the first
+    /// supported one, falling back to light for `normal`.
+    pub fn used(&self, preferred: ColorScheme) -> ColorScheme {
+        match preferred {
+            ColorScheme::Dark if self.dark => ColorScheme::Dark,
+            ColorScheme::Light if self.light => ColorScheme::Light,
+            _ if self.light => ColorScheme::Light,
+            _ if self.dark => ColorScheme::Dark,
+            _ => ColorScheme::Light,
+        }
+    }
+}
+
 // ============================================================================
 // Flexbox values
 // ============================================================================
@@ -560,6 +624,22 @@ pub enum TextAlign {
     Justify,
 }
 
+/// The `vertical-align` property value.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Baseline,
+    Middle,
+    Top,
+    Bottom,
+    Sub,
+    Super,
+    TextTop,
+    TextBottom,
+    /// Raise the baseline by a length, or a percentage of `line-height`
+    Length(Length),
+}
+
 /// The `text-decoration-line` property value.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum TextDecorationLine {
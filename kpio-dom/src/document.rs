@@ -7,7 +7,9 @@ use hashbrown::HashMap;
 
 use servo_types::{LocalName, QualName};
 
+use crate::element::{parse_class_tokens, ClassList, Dataset};
 use crate::node::{Attribute, Node, NodeData, NodeId, NodeType};
+use crate::selection::Selection;
 use kpio_html::tokenizer::Attribute as HtmlAttribute;
 use kpio_html::tree_builder::{QuirksMode, TreeSink};
 
@@ -20,6 +22,10 @@ pub struct Document {
     quirks_mode: QuirksMode,
     /// ID to node mapping.
     id_map: HashMap<String, NodeId>,
+    /// Elements whose style must be recomputed.
+    style_dirty: Vec<NodeId>,
+    /// The user's current selection.
+    pub(crate) selection: Selection,
 }
 
 impl Document {
@@ -29,6 +35,8 @@ impl Document {
             nodes: Vec::new(),
             quirks_mode: QuirksMode::NoQuirks,
             id_map: HashMap::new(),
+            style_dirty: Vec::new(),
+            selection: Selection::default(),
         };
 
         // Create document node
@@ -111,6 +119,189 @@ impl Document {
         id
     }
 
+    /// Get an attribute value of an element.
+    pub fn get_attribute(&self, node_id: NodeId, name: &str) -> Option<&str> {
+        self.get(node_id)?.get_attribute(name)
+    }
+
+    /// Set an attribute on an element, updating cached id/classes and
+    /// marking the element for restyle.
+    pub fn set_attribute(&mut self, node_id: NodeId, name: &str, value: &str) {
+        let Some(Node {
+            data: NodeData::Element {
+                attrs, id, classes, ..
+            },
+            ..
+        }) = self.nodes.get_mut(node_id)
+        else {
+            return;
+        };
+
+        if name == "id" {
+            if let Some(old) = id.take() {
+                self.id_map.remove(&old);
+            }
+            *id = Some(value.into());
+            self.id_map.insert(value.into(), node_id);
+        } else if name == "class" {
+            *classes = parse_class_tokens(value);
+        }
+
+        if let Some(attr) = attrs.iter_mut().find(|a| a.name.local.as_str() == name) {
+            attr.value = value.into();
+        } else {
+            attrs.push(Attribute::new(name, value));
+        }
+        self.mark_style_dirty(node_id);
+    }
+
+    /// Remove an attribute from an element, updating cached id/classes and
+    /// marking the element for restyle. Returns whether it was present.
+    pub fn remove_attribute(&mut self, node_id: NodeId, name: &str) -> bool {
+        let Some(Node {
+            data: NodeData::Element {
+                attrs, id, classes, ..
+            },
+            ..
+        }) = self.nodes.get_mut(node_id)
+        else {
+            return false;
+        };
+
+        let Some(index) = attrs.iter().position(|a| a.name.local.as_str() == name) else {
+            return false;
+        };
+        attrs.remove(index);
+
+        if name == "id" {
+            if let Some(old) = id.take() {
+                if self.id_map.get(&old) == Some(&node_id) {
+                    self.id_map.remove(&old);
+                }
+            }
+        } else if name == "class" {
+            classes.clear();
+        }
+        self.mark_style_dirty(node_id);
+        true
+    }
+
+    /// Get the `classList` of an element. Returns `None` for non-elements.
+    pub fn class_list(&mut self, node_id: NodeId) -> Option<ClassList<'_>> {
+        ClassList::new(self, node_id)
+    }
+
+    /// Get the `dataset` of an element. Returns `None` for non-elements.
+    pub fn dataset(&mut self, node_id: NodeId) -> Option<Dataset<'_>> {
+        Dataset::new(self, node_id)
+    }
+
+    // Reflected IDL attributes. Getters return the empty string for a
+    // missing attribute and setters go through `set_attribute`, so every
+    // change is picked up by the next restyle.
+
+    /// `element.id`.
+    pub fn id(&self, node_id: NodeId) -> &str {
+        self.get_attribute(node_id, "id").unwrap_or("")
+    }
+
+    /// Set `element.id`.
+    pub fn set_id(&mut self, node_id: NodeId, value: &str) {
+        self.set_attribute(node_id, "id", value);
+    }
+
+    /// `element.className`, the raw `class` attribute.
+    pub fn class_name(&self, node_id: NodeId) -> &str {
+        self.get_attribute(node_id, "class").unwrap_or("")
+    }
+
+    /// Set `element.className`.
+    pub fn set_class_name(&mut self, node_id: NodeId, value: &str) {
+        self.set_attribute(node_id, "class", value);
+    }
+
+    /// `element.src`, unresolved.
+    pub fn src(&self, node_id: NodeId) -> &str {
+        self.get_attribute(node_id, "src").unwrap_or("")
+    }
+
+    /// Set `element.src`.
+    pub fn set_src(&mut self, node_id: NodeId, value: &str) {
+        self.set_attribute(node_id, "src", value);
+    }
+
+    /// `element.href`, unresolved.
+    pub fn href(&self, node_id: NodeId) -> &str {
+        self.get_attribute(node_id, "href").unwrap_or("")
+    }
+
+    /// Set `element.href`.
+    pub fn set_href(&mut self, node_id: NodeId, value: &str) {
+        self.set_attribute(node_id, "href", value);
+    }
+
+    /// `element.value`, the `value` attribute.
+    pub fn value(&self, node_id: NodeId) -> &str {
+        self.get_attribute(node_id, "value").unwrap_or("")
+    }
+
+    /// Set `element.value`.
+    pub fn set_value(&mut self, node_id: NodeId, value: &str) {
+        self.set_attribute(node_id, "value", value);
+    }
+
+    /// `element.checked`, true if the `checked` attribute is present.
+    pub fn checked(&self, node_id: NodeId) -> bool {
+        self.get_attribute(node_id, "checked").is_some()
+    }
+
+    /// Set `element.checked` by adding or removing the `checked` attribute.
+    pub fn set_checked(&mut self, node_id: NodeId, checked: bool) {
+        if checked {
+            self.set_attribute(node_id, "checked", "");
+        } else {
+            self.remove_attribute(node_id, "checked");
+        }
+    }
+
+    /// Store a new class token set and serialize it into the `class`
+    /// attribute. No attribute is created for an empty set.
+    pub(crate) fn set_class_tokens(&mut self, node_id: NodeId, tokens: Vec<String>) {
+        let Some(Node {
+            data: NodeData::Element { attrs, classes, .. },
+            ..
+        }) = self.nodes.get_mut(node_id)
+        else {
+            return;
+        };
+
+        let value = tokens.join(" ");
+        *classes = tokens;
+        if let Some(attr) = attrs.iter_mut().find(|a| a.name.local.as_str() == "class") {
+            attr.value = value;
+        } else if !value.is_empty() {
+            attrs.push(Attribute::new("class", &value));
+        }
+        self.mark_style_dirty(node_id);
+    }
+
+    /// Mark an element as needing its style recomputed.
+    pub fn mark_style_dirty(&mut self, node_id: NodeId) {
+        if !self.style_dirty.contains(&node_id) {
+            self.style_dirty.push(node_id);
+        }
+    }
+
+    /// Check if any element needs restyle.
+    pub fn needs_restyle(&self) -> bool {
+        !self.style_dirty.is_empty()
+    }
+
+    /// Take the set of elements needing restyle, clearing it.
+    pub fn take_style_dirty(&mut self) -> Vec<NodeId> {
+        core::mem::take(&mut self.style_dirty)
+    }
+
     /// Create a new text node.
     pub fn create_text(&mut self, content: String) -> NodeId {
         let id = self.nodes.len();
@@ -125,6 +316,38 @@ impl Document {
         id
     }
 
+    /// Create a new, empty document fragment.
+    pub fn create_document_fragment(&mut self) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node::new_document_fragment(id));
+        id
+    }
+
+    /// Clone a node, including its descendants if `deep` is set.
+    ///
+    /// The clone is detached. Cloned elements are not registered in the
+    /// ID map until their attributes are set again.
+    pub fn clone_node(&mut self, node_id: NodeId, deep: bool) -> Option<NodeId> {
+        let mut node = self.nodes.get(node_id)?.clone();
+        let id = self.nodes.len();
+        node.id = id;
+        node.parent = None;
+        node.first_child = None;
+        node.last_child = None;
+        node.prev_sibling = None;
+        node.next_sibling = None;
+        self.nodes.push(node);
+
+        if deep {
+            for child_id in self.children(node_id) {
+                if let Some(clone_id) = self.clone_node(child_id, true) {
+                    self.append_child(id, clone_id);
+                }
+            }
+        }
+        Some(id)
+    }
+
     /// Append a child to a parent.
     pub fn append_child(&mut self, parent_id: NodeId, child_id: NodeId) {
         // Set child's parent
@@ -408,6 +631,7 @@ pub fn parse_html(html: &str) -> Document {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::element::{DatasetError, TokenError};
 
     #[test]
     fn test_create_document() {
@@ -436,4 +660,98 @@ mod tests {
         let text = doc.text_content(p_elements[0]);
         assert_eq!(text, "Hello");
     }
+
+    #[test]
+    fn test_class_list_add_remove() {
+        let doc_html = r#"<div class="a b a">x</div>"#;
+        let mut doc = parse_html(doc_html);
+        let div = doc.get_elements_by_tag_name("div")[0];
+        doc.take_style_dirty();
+
+        let mut list = doc.class_list(div).unwrap();
+        assert_eq!(list.value(), "a b");
+        list.add(&["c", "a"]).unwrap();
+        list.remove(&["b"]).unwrap();
+        assert!(list.contains("c"));
+        assert!(!list.contains("b"));
+
+        assert_eq!(doc.get_attribute(div, "class"), Some("a c"));
+        assert_eq!(doc.take_style_dirty(), vec![div]);
+        assert_eq!(doc.get_elements_by_class_name("c"), vec![div]);
+    }
+
+    #[test]
+    fn test_class_list_toggle_replace() {
+        let mut doc = Document::new();
+        let div = doc.create_element("div");
+        let mut list = doc.class_list(div).unwrap();
+
+        assert_eq!(list.toggle("on", None), Ok(true));
+        assert_eq!(list.toggle("on", Some(true)), Ok(true));
+        assert_eq!(list.toggle("off", Some(false)), Ok(false));
+        assert_eq!(list.toggle("on", None), Ok(false));
+        assert!(list.is_empty());
+
+        list.add(&["x", "y", "z"]).unwrap();
+        assert_eq!(list.replace("y", "w"), Ok(true));
+        assert_eq!(list.replace("missing", "q"), Ok(false));
+        assert_eq!(list.replace("z", "x"), Ok(true));
+        assert_eq!(list.value(), "x w");
+
+        assert_eq!(list.add(&[""]), Err(TokenError::Syntax));
+        assert_eq!(list.toggle("a b", None), Err(TokenError::InvalidCharacter));
+
+        let text = doc.create_text("t".into());
+        assert!(doc.class_list(text).is_none());
+    }
+
+    #[test]
+    fn test_dataset_maps_camel_case_keys() {
+        let mut doc = parse_html(r#"<div data-foo-bar="1" data-x="2" data-Up="3">x</div>"#);
+        let div = doc.get_elements_by_tag_name("div")[0];
+        doc.take_style_dirty();
+
+        let mut dataset = doc.dataset(div).unwrap();
+        assert_eq!(dataset.get("fooBar"), Some("1"));
+        assert_eq!(dataset.keys(), vec!["fooBar", "x", "up"]);
+
+        dataset.set("userId", "42").unwrap();
+        assert_eq!(dataset.set("foo-bar", "y"), Err(DatasetError::Syntax));
+        assert_eq!(dataset.set("a b", "y"), Err(DatasetError::InvalidCharacter));
+        assert!(dataset.remove("x"));
+        assert!(!dataset.remove("x"));
+
+        assert_eq!(doc.get_attribute(div, "data-user-id"), Some("42"));
+        assert_eq!(doc.get_attribute(div, "data-x"), None);
+        assert_eq!(doc.take_style_dirty(), vec![div]);
+
+        let text = doc.create_text("t".into());
+        assert!(doc.dataset(text).is_none());
+    }
+
+    #[test]
+    fn test_reflected_attributes() {
+        let mut doc = parse_html(r#"<a id="old" href="/a">x</a><input type="checkbox">"#);
+        let a = doc.get_elements_by_tag_name("a")[0];
+        let input = doc.get_elements_by_tag_name("input")[0];
+        doc.take_style_dirty();
+
+        assert_eq!(doc.href(a), "/a");
+        assert_eq!(doc.src(a), "");
+        doc.set_id(a, "new");
+        doc.set_class_name(a, "link active");
+        assert_eq!(doc.get_element_by_id("new"), Some(a));
+        assert_eq!(doc.get_element_by_id("old"), None);
+        assert_eq!(doc.get_elements_by_class_name("active"), vec![a]);
+
+        assert!(!doc.checked(input));
+        doc.set_checked(input, true);
+        doc.set_value(input, "on");
+        assert!(doc.checked(input));
+        assert_eq!(doc.value(input), "on");
+        doc.set_checked(input, false);
+        assert_eq!(doc.get_attribute(input, "checked"), None);
+
+        assert_eq!(doc.take_style_dirty(), vec![a, input]);
+    }
 }
@@ -2,6 +2,7 @@
 
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::fmt;
 
 use servo_types::namespace::HTML_NAMESPACE;
 use servo_types::{LocalName, Namespace, QualName};
@@ -29,7 +30,7 @@ impl ElementData {
         let classes: Vec<String> = attributes
             .iter()
             .find(|a| a.name.local.as_str() == "class")
-            .map(|a| a.value.split_whitespace().map(|s| s.into()).collect())
+            .map(|a| parse_class_tokens(&a.value))
             .unwrap_or_default();
 
         ElementData {
@@ -59,7 +60,7 @@ impl ElementData {
         if name == "id" {
             self.id = Some(value.clone());
         } else if name == "class" {
-            self.classes = value.split_whitespace().map(|s| s.into()).collect();
+            self.classes = parse_class_tokens(&value);
         }
 
         // Update or add attribute
@@ -149,6 +150,322 @@ impl ElementData {
     }
 }
 
+/// Parse a `class` attribute value into an ordered, de-duplicated token set.
+pub(crate) fn parse_class_tokens(value: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    for token in value.split_ascii_whitespace() {
+        if !tokens.iter().any(|t| t == token) {
+            tokens.push(token.into());
+        }
+    }
+    tokens
+}
+
+/// Error raised by [`ClassList`] for malformed tokens.
+///
+/// The variants mirror the `DOMException` names scripts observe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    /// The token is empty (`SyntaxError`).
+    Syntax,
+    /// The token contains ASCII whitespace (`InvalidCharacterError`).
+    InvalidCharacter,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::Syntax => write!(f, "SyntaxError: token is empty"),
+            TokenError::InvalidCharacter => {
+                write!(f, "InvalidCharacterError: token contains whitespace")
+            }
+        }
+    }
+}
+
+fn validate_token(token: &str) -> Result<(), TokenError> {
+    if token.is_empty() {
+        Err(TokenError::Syntax)
+    } else if token.bytes().any(|b| b.is_ascii_whitespace()) {
+        Err(TokenError::InvalidCharacter)
+    } else {
+        Ok(())
+    }
+}
+
+/// Live view of an element's `class` attribute (`element.classList`).
+///
+/// Every mutation writes the serialized token set back to the `class`
+/// attribute and marks the element for restyle.
+pub struct ClassList<'a> {
+    document: &'a mut Document,
+    element: NodeId,
+}
+
+impl<'a> ClassList<'a> {
+    /// Create a class list for an element. Returns `None` for non-elements.
+    pub(crate) fn new(document: &'a mut Document, element: NodeId) -> Option<Self> {
+        if !document.get(element)?.is_element() {
+            return None;
+        }
+        Some(ClassList { document, element })
+    }
+
+    fn tokens(&self) -> &[String] {
+        self.document
+            .get(self.element)
+            .map(|n| n.element_classes())
+            .unwrap_or(&[])
+    }
+
+    /// Number of tokens.
+    pub fn len(&self) -> usize {
+        self.tokens().len()
+    }
+
+    /// Check if there are no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.tokens().is_empty()
+    }
+
+    /// Get the token at `index`.
+    pub fn item(&self, index: usize) -> Option<&str> {
+        self.tokens().get(index).map(|t| t.as_str())
+    }
+
+    /// Iterate over the tokens in order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.tokens().iter().map(|t| t.as_str())
+    }
+
+    /// Serialized token set (`classList.value`).
+    pub fn value(&self) -> String {
+        self.tokens().join(" ")
+    }
+
+    /// Check if the token is present.
+    pub fn contains(&self, token: &str) -> bool {
+        self.tokens().iter().any(|t| t == token)
+    }
+
+    /// Add tokens, skipping those already present.
+    pub fn add(&mut self, tokens: &[&str]) -> Result<(), TokenError> {
+        for token in tokens {
+            validate_token(token)?;
+        }
+        self.update(|classes| {
+            for token in tokens {
+                if !classes.iter().any(|c| c == token) {
+                    classes.push((*token).into());
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Remove tokens.
+    pub fn remove(&mut self, tokens: &[&str]) -> Result<(), TokenError> {
+        for token in tokens {
+            validate_token(token)?;
+        }
+        self.update(|classes| classes.retain(|c| !tokens.contains(&c.as_str())));
+        Ok(())
+    }
+
+    /// Toggle a token. With `force`, only add (`Some(true)`) or only remove
+    /// (`Some(false)`). Returns whether the token is present afterwards.
+    pub fn toggle(&mut self, token: &str, force: Option<bool>) -> Result<bool, TokenError> {
+        validate_token(token)?;
+        let present = self.contains(token);
+        match (present, force) {
+            (true, None) | (true, Some(false)) => {
+                self.update(|classes| classes.retain(|c| c != token));
+                Ok(false)
+            }
+            (false, None) | (false, Some(true)) => {
+                self.update(|classes| classes.push(token.into()));
+                Ok(true)
+            }
+            (true, Some(true)) => Ok(true),
+            (false, Some(false)) => Ok(false),
+        }
+    }
+
+    /// Replace `old` with `new` in place. Returns `false` if `old` was absent.
+    pub fn replace(&mut self, old: &str, new: &str) -> Result<bool, TokenError> {
+        validate_token(old)?;
+        validate_token(new)?;
+        if !self.contains(old) {
+            return Ok(false);
+        }
+        // Ordered-set replace: the first of `old`/`new` takes the new
+        // token's place and any other occurrence is dropped.
+        self.update(|classes| {
+            if classes.iter().any(|c| c == new) {
+                let first = classes.iter().position(|c| c == old || c == new);
+                let mut index = 0;
+                classes.retain(|c| {
+                    let keep = Some(index) == first || (c != old && c != new);
+                    index += 1;
+                    keep
+                });
+                if let Some(i) = first {
+                    classes[i] = new.into();
+                }
+            } else if let Some(c) = classes.iter_mut().find(|c| *c == old) {
+                *c = new.into();
+            }
+        });
+        Ok(true)
+    }
+
+    /// Apply `f` to the token set and write it back to the `class` attribute.
+    fn update(&mut self, f: impl FnOnce(&mut Vec<String>)) {
+        let mut classes = self.tokens().to_vec();
+        f(&mut classes);
+        self.document.set_class_tokens(self.element, classes);
+    }
+}
+
+/// Error raised by [`Dataset`] for names that can't be stored.
+///
+/// The variants mirror the `DOMException` names scripts observe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetError {
+    /// The name has a `-` followed by a lowercase letter (`SyntaxError`).
+    Syntax,
+    /// The resulting attribute name is invalid (`InvalidCharacterError`).
+    InvalidCharacter,
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Syntax => {
+                write!(f, "SyntaxError: '-' followed by a lowercase letter")
+            }
+            DatasetError::InvalidCharacter => {
+                write!(f, "InvalidCharacterError: invalid attribute name")
+            }
+        }
+    }
+}
+
+/// Map a `data-*` attribute name to its `dataset` key (`data-foo-bar` to
+/// `fooBar`). Returns `None` for other attributes and for names with
+/// uppercase letters, which the dataset doesn't expose.
+fn dataset_key(attribute: &str) -> Option<String> {
+    let name = attribute.strip_prefix("data-")?;
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    let mut key = String::with_capacity(name.len());
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '-' && next.is_ascii_lowercase() => {
+                key.push(next.to_ascii_uppercase());
+                chars.next();
+            }
+            _ => key.push(c),
+        }
+    }
+    Some(key)
+}
+
+/// Map a `dataset` key to its `data-*` attribute name (`fooBar` to
+/// `data-foo-bar`).
+fn dataset_attribute(key: &str) -> Result<String, DatasetError> {
+    let bytes = key.as_bytes();
+    if bytes
+        .windows(2)
+        .any(|pair| pair[0] == b'-' && pair[1].is_ascii_lowercase())
+    {
+        return Err(DatasetError::Syntax);
+    }
+    if key
+        .chars()
+        .any(|c| c.is_whitespace() || c.is_control() || "\"'/=>".contains(c))
+    {
+        return Err(DatasetError::InvalidCharacter);
+    }
+
+    let mut attribute = String::from("data-");
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            attribute.push('-');
+            attribute.push(c.to_ascii_lowercase());
+        } else {
+            attribute.push(c);
+        }
+    }
+    Ok(attribute)
+}
+
+/// Live view of an element's `data-*` attributes (`element.dataset`).
+///
+/// Keys are the camelCase form of the attribute names. Every mutation
+/// writes through to the attribute and marks the element for restyle.
+pub struct Dataset<'a> {
+    document: &'a mut Document,
+    element: NodeId,
+}
+
+impl<'a> Dataset<'a> {
+    /// Create a dataset for an element. Returns `None` for non-elements.
+    pub(crate) fn new(document: &'a mut Document, element: NodeId) -> Option<Self> {
+        if !document.get(element)?.is_element() {
+            return None;
+        }
+        Some(Dataset { document, element })
+    }
+
+    fn attributes(&self) -> &[Attribute] {
+        match self.document.get(self.element).map(|n| &n.data) {
+            Some(NodeData::Element { attrs, .. }) => attrs,
+            _ => &[],
+        }
+    }
+
+    /// Get the value stored under `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let attribute = dataset_attribute(key).ok()?;
+        self.document.get_attribute(self.element, &attribute)
+    }
+
+    /// Check if a value is stored under `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Store `value` under `key`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), DatasetError> {
+        let attribute = dataset_attribute(key)?;
+        self.document.set_attribute(self.element, &attribute, value);
+        Ok(())
+    }
+
+    /// Remove the value stored under `key`. Returns whether it was present.
+    pub fn remove(&mut self, key: &str) -> bool {
+        match dataset_attribute(key) {
+            Ok(attribute) => self.document.remove_attribute(self.element, &attribute),
+            Err(_) => false,
+        }
+    }
+
+    /// Keys in attribute order.
+    pub fn keys(&self) -> Vec<String> {
+        self.iter().map(|(key, _)| key).collect()
+    }
+
+    /// Iterate over `(key, value)` pairs in attribute order.
+    pub fn iter(&self) -> impl Iterator<Item = (String, &str)> {
+        self.attributes()
+            .iter()
+            .filter_map(|a| dataset_key(a.name.local.as_str()).map(|key| (key, a.value.as_str())))
+    }
+}
+
 /// Element trait for accessing element functionality on nodes.
 pub trait Element {
     /// Get the tag name.
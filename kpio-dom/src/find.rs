@@ -0,0 +1,243 @@
+//! Find in page - Text search over the rendered text of a document
+//!
+//! Text is searched in runs: adjacent text nodes inside the same block,
+//! joined across inline elements such as `<b>` or `<a>`, so that a match
+//! may span several nodes. Block-level elements and `<br>` end a run.
+//! Text that is never rendered (`<script>`, `<style>`, `<head>`, elements
+//! with a `hidden` attribute, ...) is not searched. Whitespace is matched
+//! as it appears in the DOM.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::node::{NodeData, NodeId};
+use crate::range::Range;
+use crate::Document;
+
+/// Elements whose text is never rendered.
+const UNRENDERED_ELEMENTS: &[&str] = &["head", "noscript", "script", "style", "template", "title"];
+
+/// Elements that do not end a text run.
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "font", "i", "kbd",
+    "label", "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// Options for a find-in-page search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FindOptions {
+    /// Match letter case exactly.
+    pub case_sensitive: bool,
+    /// Only match whole words.
+    pub whole_word: bool,
+}
+
+/// A run of adjacent text nodes searched as one string.
+#[derive(Default)]
+struct TextRun {
+    text: String,
+    /// Each node with the byte offset of its data within `text`.
+    nodes: Vec<(NodeId, usize)>,
+}
+
+impl TextRun {
+    /// Map a byte offset in the run to a boundary point. `end` picks the
+    /// earlier node when the offset falls between two nodes.
+    fn point(&self, offset: usize, end: bool) -> (NodeId, usize) {
+        let index = self
+            .nodes
+            .iter()
+            .rposition(|&(_, start)| if end { start < offset } else { start <= offset })
+            .unwrap_or(0);
+        let (node, start) = self.nodes[index];
+        (node, offset - start)
+    }
+}
+
+/// Find methods for Document.
+impl Document {
+    /// Find all non-overlapping occurrences of `query` in the rendered
+    /// text, in document order.
+    pub fn find_text(&self, query: &str, options: FindOptions) -> Vec<Range> {
+        let needle: Vec<char> = query
+            .chars()
+            .map(|c| fold(c, options.case_sensitive))
+            .collect();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+
+        let mut runs = Vec::new();
+        let mut run = TextRun::default();
+        self.collect_runs(self.document_node().id, &mut runs, &mut run);
+        runs.push(run);
+
+        let mut ranges = Vec::new();
+        for run in runs.iter().filter(|run| !run.text.is_empty()) {
+            let chars: Vec<(usize, char)> = run
+                .text
+                .char_indices()
+                .map(|(i, c)| (i, fold(c, options.case_sensitive)))
+                .collect();
+
+            let mut i = 0;
+            while i + needle.len() <= chars.len() {
+                let end = i + needle.len();
+                let starts_word = i == 0 || !is_word_char(chars[i - 1].1);
+                let ends_word = !chars.get(end).is_some_and(|&(_, c)| is_word_char(c));
+                let found = chars[i..end]
+                    .iter()
+                    .map(|&(_, c)| c)
+                    .eq(needle.iter().copied())
+                    && (!options.whole_word || (starts_word && ends_word));
+                if !found {
+                    i += 1;
+                    continue;
+                }
+
+                let start_byte = chars[i].0;
+                let end_byte = chars.get(end).map_or(run.text.len(), |&(b, _)| b);
+                let (start_node, start_offset) = run.point(start_byte, false);
+                let (end_node, end_offset) = run.point(end_byte, true);
+
+                let mut range = Range::new();
+                if range.set_start(self, start_node, start_offset).is_ok()
+                    && range.set_end(self, end_node, end_offset).is_ok()
+                {
+                    ranges.push(range);
+                }
+                i = end;
+            }
+        }
+        ranges
+    }
+
+    /// Get the text nodes that are rendered, in document order.
+    pub fn rendered_text_nodes(&self) -> Vec<NodeId> {
+        let mut runs = Vec::new();
+        let mut run = TextRun::default();
+        self.collect_runs(self.document_node().id, &mut runs, &mut run);
+        runs.push(run);
+
+        runs.into_iter()
+            .flat_map(|run| run.nodes.into_iter().map(|(node, _)| node))
+            .collect()
+    }
+
+    fn collect_runs(&self, node_id: NodeId, runs: &mut Vec<TextRun>, run: &mut TextRun) {
+        let Some(node) = self.get(node_id) else {
+            return;
+        };
+
+        match &node.data {
+            NodeData::Text { content } => {
+                run.nodes.push((node_id, run.text.len()));
+                run.text.push_str(content);
+            }
+            NodeData::Element { .. } => {
+                let tag = node.tag_name().unwrap_or("");
+                if UNRENDERED_ELEMENTS.contains(&tag) || node.get_attribute("hidden").is_some() {
+                    return;
+                }
+
+                let inline = INLINE_ELEMENTS.contains(&tag);
+                if !inline {
+                    runs.push(core::mem::take(run));
+                }
+                for child in self.children(node_id) {
+                    self.collect_runs(child, runs, run);
+                }
+                if !inline {
+                    runs.push(core::mem::take(run));
+                }
+            }
+            NodeData::Document | NodeData::DocumentFragment => {
+                for child in self.children(node_id) {
+                    self.collect_runs(child, runs, run);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Fold a character for comparison, keeping one-to-one mappings only so
+/// that match offsets line up with the original text.
+fn fold(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        return c;
+    }
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::parse_html;
+
+    #[test]
+    fn test_find_text_options() {
+        let doc = parse_html("<p>Cat scatter CAT cat_nap</p>");
+
+        let all = doc.find_text("cat", FindOptions::default());
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[1].text(&doc), "cat");
+        assert_eq!((all[1].start_offset(), all[1].end_offset()), (5, 8));
+
+        let exact = FindOptions {
+            case_sensitive: true,
+            ..FindOptions::default()
+        };
+        assert_eq!(doc.find_text("CAT", exact).len(), 1);
+
+        let words = FindOptions {
+            whole_word: true,
+            ..FindOptions::default()
+        };
+        let found = doc.find_text("cat", words);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].text(&doc), "CAT");
+
+        assert!(doc.find_text("", FindOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_find_text_across_inline_elements() {
+        let doc = parse_html("<p>hel<b>lo</b> wor</p><p>ld</p>");
+        let p = doc.get_elements_by_tag_name("p");
+
+        let found = doc.find_text("hello", FindOptions::default());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text(&doc), "hello");
+        assert_eq!(
+            found[0].start_container(),
+            doc.get(p[0]).unwrap().first_child.unwrap()
+        );
+
+        // Paragraphs are separate runs
+        assert!(doc.find_text("world", FindOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_find_text_skips_unrendered_text() {
+        let doc = parse_html(
+            "<html><head><title>needle</title></head><body>\
+             <script>needle</script><div hidden>needle</div><p>needle</p></body></html>",
+        );
+        let found = doc.find_text("needle", FindOptions::default());
+        assert_eq!(found.len(), 1);
+
+        let p = doc.get_elements_by_tag_name("p")[0];
+        let text = doc.get(p).unwrap().first_child.unwrap();
+        assert_eq!(found[0].start_container(), text);
+        assert_eq!(doc.rendered_text_nodes(), [text]);
+    }
+}
@@ -10,15 +10,21 @@ extern crate alloc;
 pub mod document;
 pub mod element;
 pub mod events;
+pub mod find;
 pub mod node;
+pub mod range;
+pub mod selection;
 pub mod style;
 pub mod text;
 pub mod traversal;
 
 pub use document::Document;
-pub use element::{Element, ElementData};
+pub use element::{ClassList, Dataset, DatasetError, Element, ElementData, TokenError};
 pub use events::{Event, EventDispatcher, EventPhase, EventTarget, EventType};
+pub use find::FindOptions;
 pub use node::{Node, NodeId, NodeType};
+pub use range::{Range, RangeError};
+pub use selection::{Selection, SelectionDirection};
 pub use style::StyledNode;
 pub use text::Text;
 pub use traversal::{NodeIterator, TreeWalker};
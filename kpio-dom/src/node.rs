@@ -51,6 +51,8 @@ pub struct Node {
 pub enum NodeData {
     /// Document node
     Document,
+    /// Document fragment
+    DocumentFragment,
     /// Document type
     DocumentType {
         name: String,
@@ -111,6 +113,20 @@ impl Node {
         }
     }
 
+    /// Create a new document fragment node.
+    pub fn new_document_fragment(id: NodeId) -> Self {
+        Node {
+            id,
+            node_type: NodeType::DocumentFragment,
+            data: NodeData::DocumentFragment,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        }
+    }
+
     /// Create a new element node.
     pub fn new_element(id: NodeId, name: QualName, attrs: Vec<Attribute>) -> Self {
         let id_attr = attrs
@@ -121,7 +137,7 @@ impl Node {
         let classes: Vec<String> = attrs
             .iter()
             .find(|a| a.name.local.as_str() == "class")
-            .map(|a| a.value.split_whitespace().map(|s| s.into()).collect())
+            .map(|a| crate::element::parse_class_tokens(&a.value))
             .unwrap_or_default();
 
         Node {
@@ -293,6 +309,7 @@ impl fmt::Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.data {
             NodeData::Document => write!(f, "#document"),
+            NodeData::DocumentFragment => write!(f, "#document-fragment"),
             NodeData::DocumentType { name, .. } => write!(f, "<!DOCTYPE {}>", name),
             NodeData::Element { name, .. } => write!(f, "<{}>", name.local.as_str()),
             NodeData::Text { content } => {
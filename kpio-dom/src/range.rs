@@ -0,0 +1,1008 @@
+//! DOM Range - Boundary points and content manipulation
+//!
+//! A range spans two boundary points, each a `(node, offset)` pair. For
+//! text, comment and processing-instruction nodes the offset is a byte
+//! offset into the node's data (it must fall on a UTF-8 character
+//! boundary); for other nodes it counts children.
+//!
+//! Ranges are plain values and are not updated by unrelated tree
+//! mutations. Operations that mutate the tree through a range keep that
+//! range consistent.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::node::{NodeData, NodeId, NodeType};
+use crate::Document;
+
+/// Errors raised by range operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    /// The offset is past the end of the node (`IndexSizeError`).
+    IndexSize,
+    /// The node cannot hold a boundary point (`InvalidNodeTypeError`).
+    InvalidNodeType,
+    /// The operation would produce an invalid tree (`HierarchyRequestError`).
+    HierarchyRequest,
+    /// The selection has no range (`InvalidStateError`).
+    InvalidState,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::IndexSize => write!(f, "IndexSizeError: offset out of range"),
+            RangeError::InvalidNodeType => {
+                write!(
+                    f,
+                    "InvalidNodeTypeError: node cannot contain a boundary point"
+                )
+            }
+            RangeError::HierarchyRequest => {
+                write!(f, "HierarchyRequestError: invalid node insertion")
+            }
+            RangeError::InvalidState => write!(f, "InvalidStateError: selection is empty"),
+        }
+    }
+}
+
+/// A range between two boundary points in a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    start_container: NodeId,
+    start_offset: usize,
+    end_container: NodeId,
+    end_offset: usize,
+}
+
+impl Range {
+    /// Create a range collapsed at the start of the document.
+    pub fn new() -> Self {
+        Range {
+            start_container: 0,
+            start_offset: 0,
+            end_container: 0,
+            end_offset: 0,
+        }
+    }
+
+    /// Get the node containing the start point.
+    pub fn start_container(&self) -> NodeId {
+        self.start_container
+    }
+
+    /// Get the start offset.
+    pub fn start_offset(&self) -> usize {
+        self.start_offset
+    }
+
+    /// Get the node containing the end point.
+    pub fn end_container(&self) -> NodeId {
+        self.end_container
+    }
+
+    /// Get the end offset.
+    pub fn end_offset(&self) -> usize {
+        self.end_offset
+    }
+
+    /// Check if the start and end points are the same.
+    pub fn collapsed(&self) -> bool {
+        self.start_container == self.end_container && self.start_offset == self.end_offset
+    }
+
+    /// Get the deepest node containing both boundary points.
+    pub fn common_ancestor_container(&self, document: &Document) -> NodeId {
+        let mut container = self.start_container;
+        while !is_inclusive_ancestor(document, container, self.end_container) {
+            match parent(document, container) {
+                Some(parent) => container = parent,
+                None => break,
+            }
+        }
+        container
+    }
+
+    /// Set the start point. Moves the end point too if it would come
+    /// before the start or lies in another tree.
+    pub fn set_start(
+        &mut self,
+        document: &Document,
+        node: NodeId,
+        offset: usize,
+    ) -> Result<(), RangeError> {
+        check_boundary_point(document, node, offset)?;
+        if root(document, node) != root(document, self.end_container)
+            || compare_points(
+                document,
+                (node, offset),
+                (self.end_container, self.end_offset),
+            ) == Ordering::Greater
+        {
+            self.end_container = node;
+            self.end_offset = offset;
+        }
+        self.start_container = node;
+        self.start_offset = offset;
+        Ok(())
+    }
+
+    /// Set the end point. Moves the start point too if it would come
+    /// after the end or lies in another tree.
+    pub fn set_end(
+        &mut self,
+        document: &Document,
+        node: NodeId,
+        offset: usize,
+    ) -> Result<(), RangeError> {
+        check_boundary_point(document, node, offset)?;
+        if root(document, node) != root(document, self.start_container)
+            || compare_points(
+                document,
+                (node, offset),
+                (self.start_container, self.start_offset),
+            ) == Ordering::Less
+        {
+            self.start_container = node;
+            self.start_offset = offset;
+        }
+        self.end_container = node;
+        self.end_offset = offset;
+        Ok(())
+    }
+
+    /// Set the start point just before a node.
+    pub fn set_start_before(
+        &mut self,
+        document: &Document,
+        node: NodeId,
+    ) -> Result<(), RangeError> {
+        let parent = parent(document, node).ok_or(RangeError::InvalidNodeType)?;
+        self.set_start(document, parent, index(document, node))
+    }
+
+    /// Set the start point just after a node.
+    pub fn set_start_after(&mut self, document: &Document, node: NodeId) -> Result<(), RangeError> {
+        let parent = parent(document, node).ok_or(RangeError::InvalidNodeType)?;
+        self.set_start(document, parent, index(document, node) + 1)
+    }
+
+    /// Set the end point just before a node.
+    pub fn set_end_before(&mut self, document: &Document, node: NodeId) -> Result<(), RangeError> {
+        let parent = parent(document, node).ok_or(RangeError::InvalidNodeType)?;
+        self.set_end(document, parent, index(document, node))
+    }
+
+    /// Set the end point just after a node.
+    pub fn set_end_after(&mut self, document: &Document, node: NodeId) -> Result<(), RangeError> {
+        let parent = parent(document, node).ok_or(RangeError::InvalidNodeType)?;
+        self.set_end(document, parent, index(document, node) + 1)
+    }
+
+    /// Collapse the range to its start (or end) point.
+    pub fn collapse(&mut self, to_start: bool) {
+        if to_start {
+            self.end_container = self.start_container;
+            self.end_offset = self.start_offset;
+        } else {
+            self.start_container = self.end_container;
+            self.start_offset = self.end_offset;
+        }
+    }
+
+    /// Select a node and its contents.
+    pub fn select_node(&mut self, document: &Document, node: NodeId) -> Result<(), RangeError> {
+        let parent = parent(document, node).ok_or(RangeError::InvalidNodeType)?;
+        let index = index(document, node);
+        self.start_container = parent;
+        self.start_offset = index;
+        self.end_container = parent;
+        self.end_offset = index + 1;
+        Ok(())
+    }
+
+    /// Select the contents of a node.
+    pub fn select_node_contents(
+        &mut self,
+        document: &Document,
+        node: NodeId,
+    ) -> Result<(), RangeError> {
+        match document.get(node).map(|n| n.node_type) {
+            None | Some(NodeType::DocumentType) => return Err(RangeError::InvalidNodeType),
+            Some(_) => {}
+        }
+        self.start_container = node;
+        self.start_offset = 0;
+        self.end_container = node;
+        self.end_offset = node_length(document, node);
+        Ok(())
+    }
+
+    /// Compare a boundary point with the range.
+    ///
+    /// Returns `Less` if the point is before the range, `Equal` if it is
+    /// inside it and `Greater` if it is after it.
+    pub fn compare_point(
+        &self,
+        document: &Document,
+        node: NodeId,
+        offset: usize,
+    ) -> Result<Ordering, RangeError> {
+        check_boundary_point(document, node, offset)?;
+        if root(document, node) != root(document, self.start_container) {
+            return Err(RangeError::InvalidNodeType);
+        }
+        let point = (node, offset);
+        if compare_points(document, point, (self.start_container, self.start_offset))
+            == Ordering::Less
+        {
+            Ok(Ordering::Less)
+        } else if compare_points(document, point, (self.end_container, self.end_offset))
+            == Ordering::Greater
+        {
+            Ok(Ordering::Greater)
+        } else {
+            Ok(Ordering::Equal)
+        }
+    }
+
+    /// Check if a boundary point lies within the range.
+    pub fn is_point_in_range(&self, document: &Document, node: NodeId, offset: usize) -> bool {
+        self.compare_point(document, node, offset) == Ok(Ordering::Equal)
+    }
+
+    /// Remove the contents of the range from the document.
+    ///
+    /// Partially selected text nodes are truncated, fully selected nodes
+    /// are removed, and the range collapses to where the contents were.
+    pub fn delete_contents(&mut self, document: &mut Document) {
+        if self.collapsed() {
+            return;
+        }
+
+        let (start, start_offset) = (self.start_container, self.start_offset);
+        let (end, end_offset) = (self.end_container, self.end_offset);
+        if start == end && is_character_data(document, start) {
+            take_data(document, start, start_offset, end_offset);
+            self.collapse(true);
+            return;
+        }
+
+        // Contained nodes whose parent is not contained
+        let common = self.common_ancestor_container(document);
+        let to_remove: Vec<NodeId> = document
+            .descendants(common)
+            .into_iter()
+            .filter(|&id| {
+                self.contains_node(document, id)
+                    && !parent(document, id).is_some_and(|p| self.contains_node(document, p))
+            })
+            .collect();
+
+        let (new_node, new_offset) = self.collapse_point(document);
+
+        if is_character_data(document, start) {
+            let len = node_length(document, start);
+            take_data(document, start, start_offset, len);
+        }
+        for id in to_remove {
+            document.remove_child(id);
+        }
+        if is_character_data(document, end) {
+            take_data(document, end, 0, end_offset);
+        }
+
+        self.set_collapsed(new_node, new_offset);
+    }
+
+    /// Move the contents of the range into a new document fragment.
+    ///
+    /// Partially selected nodes stay in the document; shallow clones of
+    /// them hold the extracted parts in the fragment.
+    pub fn extract_contents(&mut self, document: &mut Document) -> Result<NodeId, RangeError> {
+        let fragment = document.create_document_fragment();
+        if self.collapsed() {
+            return Ok(fragment);
+        }
+
+        let (start, start_offset) = (self.start_container, self.start_offset);
+        let (end, end_offset) = (self.end_container, self.end_offset);
+        if start == end && is_character_data(document, start) {
+            let data = take_data(document, start, start_offset, end_offset);
+            append_data_clone(document, fragment, start, data);
+            self.collapse(true);
+            return Ok(fragment);
+        }
+
+        let common = self.common_ancestor_container(document);
+        let (first_partial, last_partial) = self.partially_contained_children(document, common);
+        let contained = self.contained_children(document, common)?;
+        let (new_node, new_offset) = self.collapse_point(document);
+
+        if let Some(child) = first_partial {
+            if is_character_data(document, child) {
+                let len = node_length(document, start);
+                let data = take_data(document, start, start_offset, len);
+                append_data_clone(document, fragment, start, data);
+            } else {
+                let clone = shallow_clone(document, fragment, child);
+                let mut subrange = Range {
+                    start_container: start,
+                    start_offset,
+                    end_container: child,
+                    end_offset: node_length(document, child),
+                };
+                let subfragment = subrange.extract_contents(document)?;
+                move_children(document, subfragment, clone);
+            }
+        }
+
+        for child in contained {
+            document.remove_child(child);
+            document.append_child(fragment, child);
+        }
+
+        if let Some(child) = last_partial {
+            if is_character_data(document, child) {
+                let data = take_data(document, end, 0, end_offset);
+                append_data_clone(document, fragment, end, data);
+            } else {
+                let clone = shallow_clone(document, fragment, child);
+                let mut subrange = Range {
+                    start_container: child,
+                    start_offset: 0,
+                    end_container: end,
+                    end_offset,
+                };
+                let subfragment = subrange.extract_contents(document)?;
+                move_children(document, subfragment, clone);
+            }
+        }
+
+        self.set_collapsed(new_node, new_offset);
+        Ok(fragment)
+    }
+
+    /// Copy the contents of the range into a new document fragment,
+    /// leaving the document unchanged.
+    pub fn clone_contents(&self, document: &mut Document) -> Result<NodeId, RangeError> {
+        let fragment = document.create_document_fragment();
+        if self.collapsed() {
+            return Ok(fragment);
+        }
+
+        let (start, start_offset) = (self.start_container, self.start_offset);
+        let (end, end_offset) = (self.end_container, self.end_offset);
+        if start == end && is_character_data(document, start) {
+            let data = substring(document, start, start_offset, end_offset);
+            append_data_clone(document, fragment, start, data);
+            return Ok(fragment);
+        }
+
+        let common = self.common_ancestor_container(document);
+        let (first_partial, last_partial) = self.partially_contained_children(document, common);
+        let contained = self.contained_children(document, common)?;
+
+        if let Some(child) = first_partial {
+            if is_character_data(document, child) {
+                let len = node_length(document, start);
+                let data = substring(document, start, start_offset, len);
+                append_data_clone(document, fragment, start, data);
+            } else {
+                let clone = shallow_clone(document, fragment, child);
+                let subrange = Range {
+                    start_container: start,
+                    start_offset,
+                    end_container: child,
+                    end_offset: node_length(document, child),
+                };
+                let subfragment = subrange.clone_contents(document)?;
+                move_children(document, subfragment, clone);
+            }
+        }
+
+        for child in contained {
+            if let Some(clone) = document.clone_node(child, true) {
+                document.append_child(fragment, clone);
+            }
+        }
+
+        if let Some(child) = last_partial {
+            if is_character_data(document, child) {
+                let data = substring(document, end, 0, end_offset);
+                append_data_clone(document, fragment, end, data);
+            } else {
+                let clone = shallow_clone(document, fragment, child);
+                let subrange = Range {
+                    start_container: child,
+                    start_offset: 0,
+                    end_container: end,
+                    end_offset,
+                };
+                let subfragment = subrange.clone_contents(document)?;
+                move_children(document, subfragment, clone);
+            }
+        }
+
+        Ok(fragment)
+    }
+
+    /// Insert a node at the start of the range.
+    ///
+    /// A text node holding the start point is split around the insertion.
+    /// Inserting a document fragment inserts its children. A collapsed
+    /// range grows to include the inserted nodes.
+    pub fn insert_node(&mut self, document: &mut Document, node: NodeId) -> Result<(), RangeError> {
+        let start = self.start_container;
+        let start_offset = self.start_offset;
+        let start_type = document
+            .get(start)
+            .map(|n| n.node_type)
+            .ok_or(RangeError::HierarchyRequest)?;
+        let start_is_text = start_type == NodeType::Text;
+        if matches!(
+            start_type,
+            NodeType::Comment | NodeType::ProcessingInstruction
+        ) || (start_is_text && parent(document, start).is_none())
+            || start == node
+        {
+            return Err(RangeError::HierarchyRequest);
+        }
+
+        let parent_id = if start_is_text {
+            parent(document, start).ok_or(RangeError::HierarchyRequest)?
+        } else {
+            start
+        };
+        let node_type = document
+            .get(node)
+            .map(|n| n.node_type)
+            .ok_or(RangeError::HierarchyRequest)?;
+        if matches!(node_type, NodeType::Document | NodeType::DocumentType)
+            || is_inclusive_ancestor(document, node, parent_id)
+        {
+            return Err(RangeError::HierarchyRequest);
+        }
+
+        let was_collapsed = self.collapsed();
+        // Child the end point sits before, to re-derive its offset afterwards
+        let end_anchor = if is_character_data(document, self.end_container) {
+            None
+        } else {
+            Some(child_at(document, self.end_container, self.end_offset))
+        };
+
+        // Find the node to insert before, splitting text if needed
+        let mut split = None;
+        let reference = if start_is_text {
+            if start_offset == 0 {
+                Some(start)
+            } else if start_offset >= node_length(document, start) {
+                document.get(start).and_then(|n| n.next_sibling)
+            } else {
+                split = document.split_text(start, start_offset);
+                split
+            }
+        } else {
+            child_at(document, start, start_offset)
+        };
+
+        let reference = if reference == Some(node) {
+            document.get(node).and_then(|n| n.next_sibling)
+        } else {
+            reference
+        };
+        if parent(document, node).is_some() {
+            document.remove_child(node);
+        }
+        let inserted = if node_type == NodeType::DocumentFragment {
+            document.children(node)
+        } else {
+            alloc::vec![node]
+        };
+        for &child in &inserted {
+            document.remove_child(child);
+            document.insert_before(parent_id, child, reference);
+        }
+
+        if let (Some(new_text), true) = (split, self.end_container == start) {
+            self.end_container = new_text;
+            self.end_offset -= start_offset;
+        } else if let Some(anchor) = end_anchor {
+            self.end_offset = match anchor {
+                Some(child) => index(document, child),
+                None => node_length(document, self.end_container),
+            };
+        }
+
+        if let (Some(&first), Some(&last)) = (inserted.first(), inserted.last()) {
+            if start_is_text && start_offset == 0 {
+                self.start_container = parent_id;
+                self.start_offset = index(document, first);
+            }
+            if was_collapsed {
+                self.end_container = parent_id;
+                self.end_offset = index(document, last) + 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the text of all text nodes in the range.
+    pub fn text(&self, document: &Document) -> String {
+        let (start, start_offset) = (self.start_container, self.start_offset);
+        let (end, end_offset) = (self.end_container, self.end_offset);
+        let is_text = |id| document.get(id).is_some_and(|n| n.is_text());
+
+        if start == end && is_text(start) {
+            return substring(document, start, start_offset, end_offset);
+        }
+
+        let mut text = String::new();
+        if is_text(start) {
+            let len = node_length(document, start);
+            text.push_str(&substring(document, start, start_offset, len));
+        }
+        let common = self.common_ancestor_container(document);
+        for id in document.descendants(common) {
+            if is_text(id) && self.contains_node(document, id) {
+                text.push_str(&substring(document, id, 0, node_length(document, id)));
+            }
+        }
+        if is_text(end) {
+            text.push_str(&substring(document, end, 0, end_offset));
+        }
+        text
+    }
+
+    /// Check if a node lies entirely within the range.
+    fn contains_node(&self, document: &Document, node: NodeId) -> bool {
+        root(document, node) == root(document, self.start_container)
+            && compare_points(
+                document,
+                (node, 0),
+                (self.start_container, self.start_offset),
+            ) == Ordering::Greater
+            && compare_points(
+                document,
+                (node, node_length(document, node)),
+                (self.end_container, self.end_offset),
+            ) == Ordering::Less
+    }
+
+    /// Children of `common` holding the start and end points, unless one
+    /// boundary container is an inclusive ancestor of the other.
+    fn partially_contained_children(
+        &self,
+        document: &Document,
+        common: NodeId,
+    ) -> (Option<NodeId>, Option<NodeId>) {
+        let (start, end) = (self.start_container, self.end_container);
+        let first = (!is_inclusive_ancestor(document, start, end))
+            .then(|| child_toward(document, common, start));
+        let last = (!is_inclusive_ancestor(document, end, start))
+            .then(|| child_toward(document, common, end));
+        (first, last)
+    }
+
+    /// Children of `common` entirely within the range.
+    fn contained_children(
+        &self,
+        document: &Document,
+        common: NodeId,
+    ) -> Result<Vec<NodeId>, RangeError> {
+        let contained: Vec<NodeId> = document
+            .children(common)
+            .into_iter()
+            .filter(|&id| self.contains_node(document, id))
+            .collect();
+        if contained
+            .iter()
+            .any(|&id| document.get(id).map(|n| n.node_type) == Some(NodeType::DocumentType))
+        {
+            return Err(RangeError::HierarchyRequest);
+        }
+        Ok(contained)
+    }
+
+    /// Boundary point the range collapses to once its contents are gone.
+    fn collapse_point(&self, document: &Document) -> (NodeId, usize) {
+        let (start, end) = (self.start_container, self.end_container);
+        if is_inclusive_ancestor(document, start, end) {
+            return (start, self.start_offset);
+        }
+        let mut reference = start;
+        while let Some(parent) = parent(document, reference) {
+            if is_inclusive_ancestor(document, parent, end) {
+                return (parent, index(document, reference) + 1);
+            }
+            reference = parent;
+        }
+        (start, self.start_offset)
+    }
+
+    fn set_collapsed(&mut self, node: NodeId, offset: usize) {
+        self.start_container = node;
+        self.start_offset = offset;
+        self.end_container = node;
+        self.end_offset = offset;
+    }
+}
+
+impl Default for Range {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Range methods for Document.
+impl Document {
+    /// Create a range collapsed at the start of the document.
+    pub fn create_range(&self) -> Range {
+        Range::new()
+    }
+}
+
+/// Compare two boundary points in the same tree.
+pub(crate) fn compare_points(
+    document: &Document,
+    a: (NodeId, usize),
+    b: (NodeId, usize),
+) -> Ordering {
+    if a.0 == b.0 {
+        return a.1.cmp(&b.1);
+    }
+    if tree_order(document, a.0, b.0) == Ordering::Greater {
+        return compare_points(document, b, a).reverse();
+    }
+    if is_inclusive_ancestor(document, a.0, b.0) {
+        let child = child_toward(document, a.0, b.0);
+        if index(document, child) < a.1 {
+            return Ordering::Greater;
+        }
+    }
+    Ordering::Less
+}
+
+/// Validate that `(node, offset)` is a usable boundary point.
+pub(crate) fn check_boundary_point(
+    document: &Document,
+    node: NodeId,
+    offset: usize,
+) -> Result<(), RangeError> {
+    let n = document.get(node).ok_or(RangeError::InvalidNodeType)?;
+    if n.node_type == NodeType::DocumentType {
+        return Err(RangeError::InvalidNodeType);
+    }
+    if offset > node_length(document, node) {
+        return Err(RangeError::IndexSize);
+    }
+    if let Some(data) = character_data(document, node) {
+        if !data.is_char_boundary(offset) {
+            return Err(RangeError::IndexSize);
+        }
+    }
+    Ok(())
+}
+
+/// Get the root of the tree containing a node.
+pub(crate) fn root(document: &Document, node: NodeId) -> NodeId {
+    let mut current = node;
+    while let Some(parent) = parent(document, current) {
+        current = parent;
+    }
+    current
+}
+
+/// Order two nodes by their position in a pre-order traversal.
+fn tree_order(document: &Document, a: NodeId, b: NodeId) -> Ordering {
+    let path = |id| {
+        let mut path = document.ancestors(id);
+        path.reverse();
+        path.push(id);
+        path
+    };
+    let (path_a, path_b) = (path(a), path(b));
+
+    let shared = path_a
+        .iter()
+        .zip(&path_b)
+        .take_while(|(x, y)| x == y)
+        .count();
+    match (path_a.get(shared), path_b.get(shared)) {
+        (None, None) => Ordering::Equal,
+        // An ancestor precedes its descendants
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(&x), Some(&y)) if shared > 0 => index(document, x).cmp(&index(document, y)),
+        // Disconnected trees have no defined order
+        (Some(&x), Some(&y)) => x.cmp(&y),
+    }
+}
+
+fn parent(document: &Document, node: NodeId) -> Option<NodeId> {
+    document.get(node).and_then(|n| n.parent)
+}
+
+fn is_inclusive_ancestor(document: &Document, ancestor: NodeId, node: NodeId) -> bool {
+    ancestor == node || document.is_descendant_of(node, ancestor)
+}
+
+/// Get the child of `ancestor` that is an inclusive ancestor of `node`.
+fn child_toward(document: &Document, ancestor: NodeId, node: NodeId) -> NodeId {
+    let mut child = node;
+    while let Some(parent) = parent(document, child) {
+        if parent == ancestor {
+            break;
+        }
+        child = parent;
+    }
+    child
+}
+
+/// Get the position of a node among its siblings.
+fn index(document: &Document, node: NodeId) -> usize {
+    let mut index = 0;
+    let mut sibling = document.get(node).and_then(|n| n.prev_sibling);
+    while let Some(id) = sibling {
+        index += 1;
+        sibling = document.get(id).and_then(|n| n.prev_sibling);
+    }
+    index
+}
+
+/// Get the child at `offset`, or `None` past the last child.
+fn child_at(document: &Document, parent: NodeId, offset: usize) -> Option<NodeId> {
+    let mut child = document.get(parent).and_then(|n| n.first_child);
+    for _ in 0..offset {
+        child = document.get(child?).and_then(|n| n.next_sibling);
+    }
+    child
+}
+
+/// Length of a node: data bytes for character data, children otherwise.
+fn node_length(document: &Document, node: NodeId) -> usize {
+    match document.get(node).map(|n| &n.data) {
+        None | Some(NodeData::DocumentType { .. }) => 0,
+        Some(_) => match character_data(document, node) {
+            Some(data) => data.len(),
+            None => document.children(node).len(),
+        },
+    }
+}
+
+fn character_data(document: &Document, node: NodeId) -> Option<&String> {
+    match &document.get(node)?.data {
+        NodeData::Text { content } | NodeData::Comment { content } => Some(content),
+        NodeData::ProcessingInstruction { data, .. } => Some(data),
+        _ => None,
+    }
+}
+
+fn character_data_mut(document: &mut Document, node: NodeId) -> Option<&mut String> {
+    match &mut document.get_mut(node)?.data {
+        NodeData::Text { content } | NodeData::Comment { content } => Some(content),
+        NodeData::ProcessingInstruction { data, .. } => Some(data),
+        _ => None,
+    }
+}
+
+fn is_character_data(document: &Document, node: NodeId) -> bool {
+    character_data(document, node).is_some()
+}
+
+/// Copy `start..end` of a node's data.
+fn substring(document: &Document, node: NodeId, start: usize, end: usize) -> String {
+    character_data(document, node)
+        .and_then(|data| data.get(start..end))
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+/// Remove and return `start..end` of a node's data.
+fn take_data(document: &mut Document, node: NodeId, start: usize, end: usize) -> String {
+    match character_data_mut(document, node) {
+        Some(data) if start <= end && data.get(start..end).is_some() => {
+            data.drain(start..end).collect()
+        }
+        _ => String::new(),
+    }
+}
+
+/// Append a clone of a character data node holding `data` to `parent`.
+fn append_data_clone(document: &mut Document, parent: NodeId, node: NodeId, data: String) {
+    if let Some(clone) = document.clone_node(node, false) {
+        if let Some(content) = character_data_mut(document, clone) {
+            *content = data;
+        }
+        document.append_child(parent, clone);
+    }
+}
+
+/// Append a shallow clone of `node` to `parent`, returning the clone.
+fn shallow_clone(document: &mut Document, parent: NodeId, node: NodeId) -> NodeId {
+    let clone = document
+        .clone_node(node, false)
+        .unwrap_or_else(|| document.create_document_fragment());
+    document.append_child(parent, clone);
+    clone
+}
+
+fn move_children(document: &mut Document, from: NodeId, to: NodeId) {
+    for child in document.children(from) {
+        document.remove_child(child);
+        document.append_child(to, child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::parse_html;
+
+    fn text_of(doc: &Document, tag: &str) -> NodeId {
+        let element = doc.get_elements_by_tag_name(tag)[0];
+        doc.get(element).unwrap().first_child.unwrap()
+    }
+
+    #[test]
+    fn test_boundary_point_comparison() {
+        let doc = parse_html("<div><p>one</p><p>two</p></div>");
+        let div = doc.get_elements_by_tag_name("div")[0];
+        let p = doc.get_elements_by_tag_name("p");
+        let one = text_of(&doc, "p");
+
+        assert_eq!(compare_points(&doc, (div, 0), (one, 1)), Ordering::Less);
+        assert_eq!(compare_points(&doc, (div, 1), (one, 1)), Ordering::Greater);
+        assert_eq!(compare_points(&doc, (one, 3), (p[1], 0)), Ordering::Less);
+        assert_eq!(compare_points(&doc, (p[1], 0), (div, 1)), Ordering::Greater);
+        assert_eq!(compare_points(&doc, (div, 1), (p[0], 1)), Ordering::Greater);
+
+        let mut range = doc.create_range();
+        range.set_start(&doc, one, 1).unwrap();
+        range.set_end(&doc, div, 2).unwrap();
+        assert_eq!(range.common_ancestor_container(&doc), div);
+        assert_eq!(range.compare_point(&doc, one, 0), Ok(Ordering::Less));
+        assert_eq!(range.compare_point(&doc, p[1], 0), Ok(Ordering::Equal));
+        assert_eq!(
+            range.compare_point(&doc, one, 9),
+            Err(RangeError::IndexSize)
+        );
+
+        // Setting the start past the end collapses the range
+        range.set_start(&doc, div, 2).unwrap();
+        assert!(range.collapsed());
+        range.set_end(&doc, one, 0).unwrap();
+        assert_eq!(range.start_container(), one);
+        assert!(range.collapsed());
+    }
+
+    #[test]
+    fn test_select_node_contents_and_text() {
+        let doc = parse_html("<p>Hello <b>big</b> world</p>");
+        let p = doc.get_elements_by_tag_name("p")[0];
+
+        let mut range = doc.create_range();
+        range.select_node_contents(&doc, p).unwrap();
+        assert_eq!((range.start_offset(), range.end_offset()), (0, 3));
+        assert_eq!(range.text(&doc), "Hello big world");
+
+        let hello = doc.get(p).unwrap().first_child.unwrap();
+        let world = doc.get(p).unwrap().last_child.unwrap();
+        range.set_start(&doc, hello, 2).unwrap();
+        range.set_end(&doc, world, 3).unwrap();
+        assert_eq!(range.text(&doc), "llo big wo");
+    }
+
+    #[test]
+    fn test_delete_contents_across_elements() {
+        let mut doc = parse_html("<div><p>abc</p><hr><p>def</p></div>");
+        let div = doc.get_elements_by_tag_name("div")[0];
+        let p = doc.get_elements_by_tag_name("p");
+        let (abc, def) = (
+            doc.get(p[0]).unwrap().first_child.unwrap(),
+            doc.get(p[1]).unwrap().first_child.unwrap(),
+        );
+
+        let mut range = doc.create_range();
+        range.set_start(&doc, abc, 1).unwrap();
+        range.set_end(&doc, def, 2).unwrap();
+        range.delete_contents(&mut doc);
+
+        assert_eq!(doc.text_content(div), "af");
+        assert_eq!(doc.children(div), alloc::vec![p[0], p[1]]);
+        assert!(range.collapsed());
+        assert_eq!((range.start_container(), range.start_offset()), (div, 1));
+    }
+
+    #[test]
+    fn test_extract_and_clone_contents() {
+        let mut doc = parse_html("<div><p>abc</p><p>def</p></div>");
+        let div = doc.get_elements_by_tag_name("div")[0];
+        let (abc, def) = (text_of(&doc, "p"), {
+            let p = doc.get_elements_by_tag_name("p")[1];
+            doc.get(p).unwrap().first_child.unwrap()
+        });
+
+        let mut range = doc.create_range();
+        range.set_start(&doc, abc, 1).unwrap();
+        range.set_end(&doc, def, 2).unwrap();
+
+        let copy = range.clone_contents(&mut doc).unwrap();
+        assert_eq!(doc.text_content(div), "abcdef");
+        let cloned = doc.children(copy);
+        assert_eq!(cloned.len(), 2);
+        assert_eq!(doc.get(cloned[0]).unwrap().tag_name(), Some("p"));
+        assert_eq!(doc.text_content(cloned[0]), "bc");
+        assert_eq!(doc.text_content(cloned[1]), "de");
+
+        let fragment = range.extract_contents(&mut doc).unwrap();
+        assert_eq!(doc.text_content(div), "af");
+        assert_eq!(
+            doc.get(fragment).unwrap().node_type,
+            NodeType::DocumentFragment
+        );
+        let extracted = doc.children(fragment);
+        assert_eq!(doc.text_content(extracted[0]), "bc");
+        assert_eq!(doc.text_content(extracted[1]), "de");
+        assert!(range.collapsed());
+        assert_eq!((range.start_container(), range.start_offset()), (div, 1));
+    }
+
+    #[test]
+    fn test_extract_within_text_node() {
+        let mut doc = parse_html("<p>Hello</p>");
+        let text = text_of(&doc, "p");
+        let mut range = doc.create_range();
+        range.set_start(&doc, text, 1).unwrap();
+        range.set_end(&doc, text, 4).unwrap();
+
+        let fragment = range.extract_contents(&mut doc).unwrap();
+        let child = doc.get(fragment).unwrap().first_child.unwrap();
+        assert_eq!(doc.get(child).unwrap().text_content(), Some("ell"));
+        assert_eq!(doc.get(text).unwrap().text_content(), Some("Ho"));
+        assert_eq!((range.start_container(), range.start_offset()), (text, 1));
+    }
+
+    #[test]
+    fn test_insert_node_splits_text() {
+        let mut doc = parse_html("<p>Hello</p>");
+        let p = doc.get_elements_by_tag_name("p")[0];
+        let text = text_of(&doc, "p");
+        let b = doc.create_element("b");
+
+        let mut range = doc.create_range();
+        range.set_start(&doc, text, 2).unwrap();
+        range.set_end(&doc, text, 2).unwrap();
+        range.insert_node(&mut doc, b).unwrap();
+
+        let children = doc.children(p);
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[1], b);
+        assert_eq!(doc.get(children[0]).unwrap().text_content(), Some("He"));
+        assert_eq!(doc.get(children[2]).unwrap().text_content(), Some("llo"));
+        assert_eq!((range.start_container(), range.start_offset()), (text, 2));
+        assert_eq!((range.end_container(), range.end_offset()), (p, 2));
+
+        assert_eq!(
+            range.insert_node(&mut doc, p),
+            Err(RangeError::HierarchyRequest)
+        );
+    }
+
+    #[test]
+    fn test_invalid_boundary_points() {
+        let doc = parse_html("<p>h\u{e9}</p>");
+        let text = text_of(&doc, "p");
+        let mut range = doc.create_range();
+        assert_eq!(range.set_start(&doc, text, 2), Err(RangeError::IndexSize));
+        assert_eq!(range.set_start(&doc, text, 4), Err(RangeError::IndexSize));
+        assert_eq!(
+            range.set_start_before(&doc, 0),
+            Err(RangeError::InvalidNodeType)
+        );
+        assert!(range.set_start(&doc, text, 3).is_ok());
+    }
+}
@@ -0,0 +1,247 @@
+//! DOM Selection - The user's current text selection
+//!
+//! Each document tracks one selection. The input layer drives it: a
+//! mouse press collapses the selection at the hit point, dragging extends
+//! it to the pointer, and double-click or select-all select whole nodes.
+//! The selection keeps its anchor (where the drag started) and focus
+//! (where it is now) so that dragging backwards works.
+
+use alloc::string::String;
+use core::cmp::Ordering;
+
+use crate::node::NodeId;
+use crate::range::{compare_points, root, Range, RangeError};
+use crate::Document;
+
+/// Direction of a selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionDirection {
+    /// Set programmatically; the anchor is the start.
+    #[default]
+    None,
+    /// The focus is at or after the anchor.
+    Forwards,
+    /// The focus is before the anchor.
+    Backwards,
+}
+
+/// A document's selection, holding at most one range.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    range: Option<Range>,
+    direction: SelectionDirection,
+}
+
+impl Selection {
+    /// Get the selected range.
+    pub fn range(&self) -> Option<&Range> {
+        self.range.as_ref()
+    }
+
+    /// Get the number of ranges (0 or 1).
+    pub fn range_count(&self) -> usize {
+        usize::from(self.range.is_some())
+    }
+
+    /// Get the selection direction.
+    pub fn direction(&self) -> SelectionDirection {
+        self.direction
+    }
+
+    /// Check if the selection is empty or a caret.
+    pub fn is_collapsed(&self) -> bool {
+        self.range.is_none_or(|r| r.collapsed())
+    }
+
+    /// Get the node where the selection started.
+    pub fn anchor_node(&self) -> Option<NodeId> {
+        self.anchor().map(|(node, _)| node)
+    }
+
+    /// Get the offset where the selection started.
+    pub fn anchor_offset(&self) -> usize {
+        self.anchor().map_or(0, |(_, offset)| offset)
+    }
+
+    /// Get the node where the selection ends.
+    pub fn focus_node(&self) -> Option<NodeId> {
+        self.focus().map(|(node, _)| node)
+    }
+
+    /// Get the offset where the selection ends.
+    pub fn focus_offset(&self) -> usize {
+        self.focus().map_or(0, |(_, offset)| offset)
+    }
+
+    fn anchor(&self) -> Option<(NodeId, usize)> {
+        let range = self.range.as_ref()?;
+        Some(match self.direction {
+            SelectionDirection::Backwards => (range.end_container(), range.end_offset()),
+            _ => (range.start_container(), range.start_offset()),
+        })
+    }
+
+    fn focus(&self) -> Option<(NodeId, usize)> {
+        let range = self.range.as_ref()?;
+        Some(match self.direction {
+            SelectionDirection::Backwards => (range.start_container(), range.start_offset()),
+            _ => (range.end_container(), range.end_offset()),
+        })
+    }
+}
+
+/// Selection methods for Document.
+impl Document {
+    /// Get the current selection.
+    pub fn selection(&self) -> &Selection {
+        &self.selection
+    }
+
+    /// Replace the selection with a range.
+    pub fn set_selection_range(&mut self, range: Range) {
+        self.selection = Selection {
+            range: Some(range),
+            direction: SelectionDirection::None,
+        };
+    }
+
+    /// Clear the selection.
+    pub fn clear_selection(&mut self) {
+        self.selection = Selection::default();
+    }
+
+    /// Collapse the selection to a caret, e.g. on mouse press.
+    pub fn collapse_selection(&mut self, node: NodeId, offset: usize) -> Result<(), RangeError> {
+        let mut range = Range::new();
+        range.set_start(self, node, offset)?;
+        range.collapse(true);
+        self.set_selection_range(range);
+        Ok(())
+    }
+
+    /// Move the focus of the selection, keeping its anchor, e.g. while
+    /// dragging.
+    pub fn extend_selection(&mut self, node: NodeId, offset: usize) -> Result<(), RangeError> {
+        let anchor = self.selection.anchor().ok_or(RangeError::InvalidState)?;
+        self.set_selection_base_and_extent(anchor.0, anchor.1, node, offset)
+    }
+
+    /// Select from an anchor point to a focus point, in either order.
+    pub fn set_selection_base_and_extent(
+        &mut self,
+        anchor_node: NodeId,
+        anchor_offset: usize,
+        focus_node: NodeId,
+        focus_offset: usize,
+    ) -> Result<(), RangeError> {
+        let mut range = Range::new();
+        range.set_start(self, anchor_node, anchor_offset)?;
+        range.collapse(true);
+
+        let backwards = root(self, anchor_node) == root(self, focus_node)
+            && compare_points(
+                self,
+                (focus_node, focus_offset),
+                (anchor_node, anchor_offset),
+            ) == Ordering::Less;
+        if backwards {
+            range.set_start(self, focus_node, focus_offset)?;
+        } else {
+            range.set_end(self, focus_node, focus_offset)?;
+        }
+
+        self.selection = Selection {
+            range: Some(range),
+            direction: if backwards {
+                SelectionDirection::Backwards
+            } else {
+                SelectionDirection::Forwards
+            },
+        };
+        Ok(())
+    }
+
+    /// Select all children of a node.
+    pub fn select_all_children(&mut self, node: NodeId) -> Result<(), RangeError> {
+        let mut range = Range::new();
+        range.select_node_contents(self, node)?;
+        self.selection = Selection {
+            range: Some(range),
+            direction: SelectionDirection::Forwards,
+        };
+        Ok(())
+    }
+
+    /// Get the selected text.
+    pub fn selected_text(&self) -> String {
+        self.selection
+            .range
+            .map(|range| range.text(self))
+            .unwrap_or_default()
+    }
+
+    /// Delete the selected contents, leaving a caret where they were.
+    pub fn delete_selection(&mut self) {
+        if let Some(mut range) = self.selection.range {
+            range.delete_contents(self);
+            self.set_selection_range(range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::parse_html;
+
+    #[test]
+    fn test_drag_selection() {
+        let mut doc = parse_html("<p>one</p><p>two</p>");
+        let p = doc.get_elements_by_tag_name("p");
+        let one = doc.get(p[0]).unwrap().first_child.unwrap();
+        let two = doc.get(p[1]).unwrap().first_child.unwrap();
+
+        assert!(doc.selection().is_collapsed());
+        assert_eq!(doc.extend_selection(one, 1), Err(RangeError::InvalidState));
+
+        // Press in "two", drag back into "one"
+        doc.collapse_selection(two, 2).unwrap();
+        doc.extend_selection(two, 3).unwrap();
+        assert_eq!(doc.selected_text(), "o");
+        assert_eq!(doc.selection().direction(), SelectionDirection::Forwards);
+
+        doc.extend_selection(one, 1).unwrap();
+        let selection = doc.selection();
+        assert_eq!(selection.direction(), SelectionDirection::Backwards);
+        assert_eq!(
+            (selection.anchor_node(), selection.anchor_offset()),
+            (Some(two), 2)
+        );
+        assert_eq!(
+            (selection.focus_node(), selection.focus_offset()),
+            (Some(one), 1)
+        );
+        let range = selection.range().unwrap();
+        assert_eq!((range.start_container(), range.start_offset()), (one, 1));
+        assert_eq!(doc.selected_text(), "netw");
+
+        doc.delete_selection();
+        assert_eq!(doc.text_content(p[0]), "o");
+        assert_eq!(doc.text_content(p[1]), "o");
+        assert!(doc.selection().is_collapsed());
+        assert_eq!(doc.selection().range_count(), 1);
+
+        doc.clear_selection();
+        assert_eq!(doc.selection().anchor_node(), None);
+    }
+
+    #[test]
+    fn test_select_all_children() {
+        let mut doc = parse_html("<div><b>bold</b> text</div>");
+        let div = doc.get_elements_by_tag_name("div")[0];
+
+        doc.select_all_children(div).unwrap();
+        assert_eq!(doc.selected_text(), "bold text");
+        assert_eq!(doc.selection().focus_offset(), 2);
+    }
+}
@@ -8,7 +8,7 @@ use crate::Document;
 use kpio_css::cascade::CascadedValues;
 use kpio_css::computed::ComputedStyle;
 use kpio_css::prelude::*;
-use kpio_css::selector::{Selector, SelectorComponent, SelectorList};
+use kpio_css::selector::{Element, SelectorList};
 use kpio_css::stylesheet::{Rule, Stylesheet};
 use kpio_css::values::LengthContext;
 
@@ -119,208 +119,99 @@ impl<'a> StyleResolver<'a> {
 
     /// Check if a selector matches a node.
     fn selector_matches(&self, node: &Node, selectors: &SelectorList) -> bool {
-        selectors
-            .selectors
-            .iter()
-            .any(|selector| self.selector_matches_single(node, selector))
+        selectors.matches(&DomElement {
+            document: self.document,
+            node,
+        })
     }
+}
 
-    /// Check if a single selector matches.
-    fn selector_matches_single(&self, node: &Node, selector: &Selector) -> bool {
-        // Match from right to left (most specific first)
-        let components = &selector.components;
-        if components.is_empty() {
-            return false;
-        }
-
-        // Match the last component against the current node
-        let last = &components[components.len() - 1];
-        if !self.component_matches(node, last) {
-            return false;
-        }
-
-        // If there's only one component, we're done
-        if components.len() == 1 {
-            return true;
-        }
+/// A document element seen by the selector matcher.
+#[derive(Clone, Copy)]
+struct DomElement<'a> {
+    document: &'a Document,
+    node: &'a Node,
+}
 
-        // Handle combinators
-        // Simplified: only handle descendant combinator
-        let mut current_node = node;
-        let mut comp_idx = components.len() - 2;
-
-        while comp_idx > 0 {
-            match &components[comp_idx] {
-                SelectorComponent::Combinator(combinator) => {
-                    match combinator {
-                        kpio_css::selector::Combinator::Descendant => {
-                            // Find ancestor that matches
-                            if comp_idx == 0 {
-                                break;
-                            }
-
-                            let target = &components[comp_idx - 1];
-                            let mut found = false;
-
-                            let mut parent_id = current_node.parent;
-                            while let Some(pid) = parent_id {
-                                if let Some(parent) = self.document.get(pid) {
-                                    if self.component_matches(parent, target) {
-                                        current_node = parent;
-                                        found = true;
-                                        break;
-                                    }
-                                    parent_id = parent.parent;
-                                } else {
-                                    break;
-                                }
-                            }
-
-                            if !found {
-                                return false;
-                            }
-
-                            if comp_idx >= 2 {
-                                comp_idx -= 2;
-                            } else {
-                                break;
-                            }
-                        }
-                        kpio_css::selector::Combinator::Child => {
-                            // Parent must match
-                            if comp_idx == 0 {
-                                break;
-                            }
-
-                            let target = &components[comp_idx - 1];
-
-                            if let Some(parent_id) = current_node.parent {
-                                if let Some(parent) = self.document.get(parent_id) {
-                                    if self.component_matches(parent, target) {
-                                        current_node = parent;
-                                    } else {
-                                        return false;
-                                    }
-                                } else {
-                                    return false;
-                                }
-                            } else {
-                                return false;
-                            }
-
-                            if comp_idx >= 2 {
-                                comp_idx -= 2;
-                            } else {
-                                break;
-                            }
-                        }
-                        _ => {
-                            // Other combinators not fully implemented
-                            comp_idx = comp_idx.saturating_sub(1);
-                        }
-                    }
-                }
-                _ => {
-                    comp_idx = comp_idx.saturating_sub(1);
-                }
+impl<'a> DomElement<'a> {
+    /// Walk sibling links from `start` to the first element.
+    fn element_from(
+        &self,
+        start: Option<NodeId>,
+        next: fn(&Node) -> Option<NodeId>,
+    ) -> Option<Self> {
+        let mut current = start;
+        while let Some(id) = current {
+            let node = self.document.get(id)?;
+            if node.is_element() {
+                return Some(DomElement {
+                    document: self.document,
+                    node,
+                });
             }
+            current = next(node);
         }
+        None
+    }
+}
 
-        true
+impl Element for DomElement<'_> {
+    fn parent_element(&self) -> Option<Self> {
+        let parent = self.document.get(self.node.parent?)?;
+        parent.is_element().then_some(DomElement {
+            document: self.document,
+            node: parent,
+        })
     }
 
-    /// Check if a component matches a node.
-    fn component_matches(&self, node: &Node, component: &SelectorComponent) -> bool {
-        match component {
-            SelectorComponent::Universal => true,
-
-            SelectorComponent::Type(name) => node
-                .tag_name()
-                .map(|t| t.eq_ignore_ascii_case(name.as_str()))
-                .unwrap_or(false),
-
-            SelectorComponent::Class(class) => node.has_class(class),
-
-            SelectorComponent::Id(id) => node.element_id() == Some(id.as_str()),
-
-            SelectorComponent::Attribute {
-                name,
-                operator,
-                value,
-                ..
-            } => {
-                if let Some(attr_value) = node.get_attribute(name.as_str()) {
-                    if let Some(expected) = value {
-                        match operator {
-                            kpio_css::selector::AttributeOperator::Exists => true,
-                            kpio_css::selector::AttributeOperator::Equals => {
-                                attr_value == expected.as_str()
-                            }
-                            kpio_css::selector::AttributeOperator::Includes => attr_value
-                                .split_whitespace()
-                                .any(|w| w == expected.as_str()),
-                            kpio_css::selector::AttributeOperator::DashMatch => {
-                                attr_value == expected.as_str()
-                                    || (attr_value.starts_with(expected.as_str())
-                                        && attr_value.chars().nth(expected.len()) == Some('-'))
-                            }
-                            kpio_css::selector::AttributeOperator::Prefix => {
-                                attr_value.starts_with(expected.as_str())
-                            }
-                            kpio_css::selector::AttributeOperator::Suffix => {
-                                attr_value.ends_with(expected.as_str())
-                            }
-                            kpio_css::selector::AttributeOperator::Substring => {
-                                attr_value.contains(expected.as_str())
-                            }
-                        }
-                    } else {
-                        // Just checking for presence
-                        matches!(operator, kpio_css::selector::AttributeOperator::Exists)
-                    }
-                } else {
-                    false
-                }
-            }
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.element_from(self.node.prev_sibling, |n| n.prev_sibling)
+    }
 
-            SelectorComponent::PseudoClass(pseudo) => {
-                match pseudo {
-                    kpio_css::selector::PseudoClass::FirstChild => {
-                        if let Some(parent_id) = node.parent {
-                            let children = self.document.child_elements(parent_id);
-                            children.first() == Some(&node.id)
-                        } else {
-                            false
-                        }
-                    }
-                    kpio_css::selector::PseudoClass::LastChild => {
-                        if let Some(parent_id) = node.parent {
-                            let children = self.document.child_elements(parent_id);
-                            children.last() == Some(&node.id)
-                        } else {
-                            false
-                        }
-                    }
-                    kpio_css::selector::PseudoClass::OnlyChild => {
-                        if let Some(parent_id) = node.parent {
-                            let children = self.document.child_elements(parent_id);
-                            children.len() == 1 && children.first() == Some(&node.id)
-                        } else {
-                            false
-                        }
-                    }
-                    kpio_css::selector::PseudoClass::Root => {
-                        node.parent.map(|p| p == 0).unwrap_or(false)
-                    }
-                    kpio_css::selector::PseudoClass::Empty => !node.has_children(),
-                    // Other pseudo-classes need more context
-                    _ => false,
-                }
-            }
+    fn next_sibling_element(&self) -> Option<Self> {
+        self.element_from(self.node.next_sibling, |n| n.next_sibling)
+    }
+
+    fn has_local_name(&self, name: &str) -> bool {
+        self.node
+            .tag_name()
+            .is_some_and(|t| t.eq_ignore_ascii_case(name))
+    }
 
+    fn is_same_type(&self, other: &Self) -> bool {
+        match (self.node.tag_name(), other.node.tag_name()) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
             _ => false,
         }
     }
+
+    fn has_id(&self, id: &str) -> bool {
+        self.node.element_id() == Some(id)
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.node.has_class(class)
+    }
+
+    fn attribute_matches(&self, name: &str, f: &dyn Fn(&str) -> bool) -> bool {
+        self.node.get_attribute(name).is_some_and(f)
+    }
+
+    fn is_root(&self) -> bool {
+        self.node
+            .parent
+            .and_then(|p| self.document.get(p))
+            .is_some_and(|p| p.is_document())
+    }
+
+    fn is_empty(&self) -> bool {
+        // Comments do not count as content
+        !self.document.children(self.node.id).into_iter().any(|id| {
+            self.document
+                .get(id)
+                .is_some_and(|n| n.is_element() || n.is_text())
+        })
+    }
 }
 
 /// Extension methods for Document.
@@ -357,4 +248,55 @@ mod tests {
         let styled = resolver.resolve();
         assert!(styled.is_some());
     }
+
+    #[test]
+    fn test_attribute_selector_operators() {
+        let doc = parse_html(
+            "<html><body><a id='link' href='https://example.com/report.PDF' lang='en-US' \
+             class='btn btn-primary'>Report</a></body></html>",
+        );
+        let resolver = doc.create_style_resolver();
+        let node = doc.get(doc.get_element_by_id("link").unwrap()).unwrap();
+        let matches = |css: &str| {
+            let selectors = CssParser::new(css).parse_selector_list().unwrap();
+            resolver.selector_matches(node, &selectors)
+        };
+
+        assert!(matches("[href^=\"https:\"]"));
+        assert!(!matches("[href^=\"http:\"]"));
+        assert!(!matches("[href$=\".pdf\"]"));
+        assert!(matches("[href$=\".pdf\" i]"));
+        assert!(matches("[href*=example]"));
+        assert!(matches("[class~=btn-primary]"));
+        assert!(!matches("[class~=primary]"));
+        assert!(matches("[lang|=en]"));
+        assert!(!matches("[lang|=US]"));
+        assert!(matches("a[href]"));
+    }
+
+    #[test]
+    fn test_structural_selectors() {
+        let doc = parse_html(
+            "<html><body><ul><li id='a'>1</li><!-- note --><li id='b' class='x'></li>\
+             <p id='c'>3</p><li id='d'>4</li></ul></body></html>",
+        );
+        let resolver = doc.create_style_resolver();
+        let matches = |id: &str, css: &str| {
+            let node = doc.get(doc.get_element_by_id(id).unwrap()).unwrap();
+            let selectors = CssParser::new(css).parse_selector_list().unwrap();
+            resolver.selector_matches(node, &selectors)
+        };
+
+        assert!(matches("a", "body li:first-child"));
+        assert!(matches("b", "li:nth-child(2)"));
+        assert!(matches("b", "li:empty"));
+        assert!(matches("d", "li:nth-of-type(3)"));
+        assert!(matches("d", "ul > :last-child:nth-child(2n)"));
+        assert!(matches("c", "li + p:only-of-type"));
+        assert!(matches("d", ".x ~ li:not(.x)"));
+        assert!(!matches("b", "li:not(.x)"));
+        assert!(matches("c", ":is(p, span)"));
+        assert!(!matches("a", "ul:root li"));
+        assert!(matches("a", ":root li"));
+    }
 }
@@ -1,17 +1,20 @@
 //! chrome.tabs API
 //!
-//! Provides tab management for extensions.
+//! Provides tab management for extensions. Tabs are owned by the browser,
+//! which exposes them through [`TabHost`]; URLs and titles are redacted
+//! for extensions without the `tabs` permission or matching host access.
 
 #![allow(dead_code)]
 
 extern crate alloc;
 
-use alloc::collections::BTreeMap;
-use alloc::string::{String, ToString};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
 use alloc::vec::Vec;
 use spin::RwLock;
 
-use super::{ApiContext, ApiError, ApiResult, EventEmitter};
+use super::{ApiContext, ApiError, ApiResult, EventEmitter, EventListener};
+use crate::{content, extension_manager, Extension, ExtensionId};
 
 /// Tab ID.
 pub type TabId = u32;
@@ -219,7 +222,7 @@ pub struct MoveProperties {
 }
 
 /// Tab change info.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TabChangeInfo {
     /// Status change.
     pub status: Option<TabStatus>,
@@ -243,6 +246,22 @@ pub struct TabChangeInfo {
     pub title: Option<String>,
 }
 
+impl TabChangeInfo {
+    /// Whether nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.url.is_none()
+            && self.group_id.is_none()
+            && self.pinned.is_none()
+            && self.audible.is_none()
+            && self.discarded.is_none()
+            && self.auto_discardable.is_none()
+            && self.muted_info.is_none()
+            && self.fav_icon_url.is_none()
+            && self.title.is_none()
+    }
+}
+
 /// Tab active info.
 #[derive(Debug, Clone)]
 pub struct TabActiveInfo {
@@ -313,216 +332,423 @@ pub enum ZoomSettingsScope {
     PerTab,
 }
 
+/// Browser side of the tabs API.
+///
+/// The browser implements this for its tab manager. Snapshots carry full
+/// URLs and titles; [`TabsApi`] redacts them per extension.
+pub trait TabHost {
+    /// All open tabs, in window and index order.
+    fn tabs(&self) -> Vec<Tab>;
+
+    /// Open a tab and return its ID.
+    fn create(&mut self, props: &CreateProperties) -> ApiResult<TabId>;
+
+    /// Navigate, activate or otherwise modify a tab.
+    fn update(&mut self, tab_id: TabId, props: &UpdateProperties) -> ApiResult<()>;
+
+    /// Close a tab.
+    fn remove(&mut self, tab_id: TabId) -> ApiResult<()>;
+
+    /// Reload a tab.
+    fn reload(&mut self, tab_id: TabId, bypass_cache: bool) -> ApiResult<()>;
+
+    /// Go back in a tab's history.
+    fn go_back(&mut self, _tab_id: TabId) -> ApiResult<()> {
+        Err(ApiError::new("Tab history is not supported"))
+    }
+
+    /// Go forward in a tab's history.
+    fn go_forward(&mut self, _tab_id: TabId) -> ApiResult<()> {
+        Err(ApiError::new("Tab history is not supported"))
+    }
+}
+
 /// Tabs API.
+///
+/// Tabs live in the browser and are reached through a [`TabHost`]. The API
+/// only keeps the last snapshot it reported (to derive events),
+/// `activeTab` grants and each extension's listeners.
 pub struct TabsApi {
-    /// Tab storage.
-    tabs: RwLock<BTreeMap<TabId, Tab>>,
-    /// Next tab ID.
-    next_tab_id: RwLock<TabId>,
-    /// Current window ID.
-    current_window_id: RwLock<WindowId>,
-    /// On created event.
-    pub on_created: RwLock<EventEmitter<Tab>>,
-    /// On updated event.
-    pub on_updated: RwLock<EventEmitter<(TabId, TabChangeInfo, Tab)>>,
-    /// On removed event.
-    pub on_removed: RwLock<EventEmitter<(TabId, TabRemoveInfo)>>,
-    /// On activated event.
-    pub on_activated: RwLock<EventEmitter<TabActiveInfo>>,
-    /// On moved event.
-    pub on_moved: RwLock<EventEmitter<(TabId, TabMoveInfo)>>,
+    /// Tabs as of the last sync.
+    known: RwLock<BTreeMap<TabId, Tab>>,
+    /// Tabs each extension was granted through `activeTab`.
+    active_tab_grants: RwLock<BTreeMap<ExtensionId, BTreeSet<TabId>>>,
+    /// Event listeners by extension.
+    listeners: RwLock<BTreeMap<ExtensionId, TabListeners>>,
+}
+
+/// Tab event listeners of one extension.
+#[derive(Default)]
+struct TabListeners {
+    on_created: EventEmitter<Tab>,
+    on_updated: EventEmitter<(TabId, TabChangeInfo, Tab)>,
+    on_removed: EventEmitter<(TabId, TabRemoveInfo)>,
+    on_activated: EventEmitter<TabActiveInfo>,
+}
+
+impl TabListeners {
+    /// Deliver an event as `access` allows.
+    fn dispatch(&self, access: &TabAccess, event: &TabEvent) {
+        match event {
+            TabEvent::Created(tab) => self.on_created.emit(&access.redact(tab.clone())),
+            TabEvent::Updated(tab_id, change, tab) => {
+                // A change that only touched hidden fields is not reported.
+                let change = access.redact_change(tab, change.clone());
+                if !change.is_empty() {
+                    self.on_updated
+                        .emit(&(*tab_id, change, access.redact(tab.clone())));
+                }
+            }
+            TabEvent::Removed(tab_id, info) => self.on_removed.emit(&(*tab_id, info.clone())),
+            TabEvent::Activated(info) => self.on_activated.emit(info),
+        }
+    }
+}
+
+/// Tab lifecycle event found by [`TabsApi::sync`].
+enum TabEvent {
+    Created(Tab),
+    Updated(TabId, TabChangeInfo, Tab),
+    Removed(TabId, TabRemoveInfo),
+    Activated(TabActiveInfo),
+}
+
+/// What one extension may see of tabs.
+///
+/// Following Chrome, `url`, `pendingUrl`, `title` and `favIconUrl` are
+/// only exposed with the `tabs` permission, a host permission matching the
+/// tab's URL, or an `activeTab` grant for the tab.
+struct TabAccess {
+    /// The extension, if installed.
+    extension: Option<Extension>,
+    /// Tabs granted through `activeTab`.
+    granted: BTreeSet<TabId>,
+}
+
+impl TabAccess {
+    /// Whether the sensitive fields of `tab` are visible.
+    fn can_see(&self, tab: &Tab) -> bool {
+        let Some(extension) = &self.extension else {
+            return false;
+        };
+        extension.has_permission("tabs")
+            || self.granted.contains(&tab.id)
+            || tab
+                .url
+                .as_deref()
+                .is_some_and(|url| extension.has_host_permission(url))
+    }
+
+    /// Strip the sensitive fields of `tab` unless visible.
+    fn redact(&self, mut tab: Tab) -> Tab {
+        if !self.can_see(&tab) {
+            tab.url = None;
+            tab.pending_url = None;
+            tab.title = None;
+            tab.fav_icon_url = None;
+        }
+        tab
+    }
+
+    /// Strip the sensitive fields of a change to `tab` unless visible.
+    fn redact_change(&self, tab: &Tab, mut change: TabChangeInfo) -> TabChangeInfo {
+        if !self.can_see(tab) {
+            change.url = None;
+            change.title = None;
+            change.fav_icon_url = None;
+        }
+        change
+    }
 }
 
 impl TabsApi {
     /// Create a new Tabs API.
     pub fn new() -> Self {
         Self {
-            tabs: RwLock::new(BTreeMap::new()),
-            next_tab_id: RwLock::new(1),
-            current_window_id: RwLock::new(1),
-            on_created: RwLock::new(EventEmitter::new()),
-            on_updated: RwLock::new(EventEmitter::new()),
-            on_removed: RwLock::new(EventEmitter::new()),
-            on_activated: RwLock::new(EventEmitter::new()),
-            on_moved: RwLock::new(EventEmitter::new()),
+            known: RwLock::new(BTreeMap::new()),
+            active_tab_grants: RwLock::new(BTreeMap::new()),
+            listeners: RwLock::new(BTreeMap::new()),
         }
     }
 
     /// Query tabs.
-    pub fn query(&self, _ctx: &ApiContext, query: QueryInfo) -> ApiResult<Vec<Tab>> {
-        let tabs = self.tabs.read();
-        let mut results = Vec::new();
-
-        for tab in tabs.values() {
-            // Apply filters
-            if let Some(active) = query.active {
-                if tab.active != active {
-                    continue;
-                }
-            }
-            if let Some(pinned) = query.pinned {
-                if tab.pinned != pinned {
-                    continue;
-                }
-            }
-            if let Some(window_id) = query.window_id {
-                if tab.window_id != window_id {
-                    continue;
-                }
-            }
-            if let Some(ref url_patterns) = query.url {
-                let matches = tab
-                    .url
-                    .as_ref()
-                    .map(|url| {
-                        url_patterns
-                            .iter()
-                            .any(|pattern| matches_url_pattern(url, pattern))
-                    })
-                    .unwrap_or(false);
-                if !matches {
-                    continue;
-                }
-            }
-
-            results.push(tab.clone());
-        }
+    ///
+    /// `title` and `url` filters only match tabs whose fields the caller
+    /// may see.
+    pub fn query(
+        &self,
+        ctx: &ApiContext,
+        host: &dyn TabHost,
+        query: QueryInfo,
+    ) -> ApiResult<Vec<Tab>> {
+        let access = self.access(&ctx.extension_id);
+        let tabs = host.tabs();
+        let current_window = current_window(ctx, &tabs);
 
-        Ok(results)
+        Ok(tabs
+            .into_iter()
+            .map(|tab| access.redact(tab))
+            .filter(|tab| matches_query(tab, &query, current_window))
+            .collect())
     }
 
     /// Get a tab by ID.
-    pub fn get(&self, _ctx: &ApiContext, tab_id: TabId) -> ApiResult<Tab> {
-        self.tabs
-            .read()
-            .get(&tab_id)
-            .cloned()
-            .ok_or_else(|| ApiError::not_found("Tab"))
-    }
-
-    /// Get current tab.
-    pub fn get_current(&self, ctx: &ApiContext) -> ApiResult<Option<Tab>> {
-        if let Some(tab_id) = ctx.tab_id {
-            Ok(self.tabs.read().get(&tab_id).cloned())
-        } else {
-            Ok(None)
+    pub fn get(&self, ctx: &ApiContext, host: &dyn TabHost, tab_id: TabId) -> ApiResult<Tab> {
+        let tab = find_tab(host.tabs(), tab_id)?;
+        Ok(self.access(&ctx.extension_id).redact(tab))
+    }
+
+    /// Get the tab the call was made from, if any.
+    pub fn get_current(&self, ctx: &ApiContext, host: &dyn TabHost) -> ApiResult<Option<Tab>> {
+        match ctx.tab_id {
+            Some(tab_id) => self.get(ctx, host, tab_id).map(Some),
+            None => Ok(None),
         }
     }
 
     /// Create a new tab.
-    pub fn create(&self, _ctx: &ApiContext, props: CreateProperties) -> ApiResult<Tab> {
-        let mut next_id = self.next_tab_id.write();
-        let tab_id = *next_id;
-        *next_id += 1;
-
-        let window_id = props.window_id.unwrap_or(*self.current_window_id.read());
-        let index = props.index.unwrap_or(0);
-
-        let mut tab = Tab::new(tab_id, window_id, index);
-        tab.url = props.url;
-        tab.active = props.active.unwrap_or(true);
-        tab.pinned = props.pinned.unwrap_or(false);
-        tab.opener_tab_id = props.opener_tab_id;
-
-        self.tabs.write().insert(tab_id, tab.clone());
-        self.on_created.read().emit(&tab);
-
-        Ok(tab)
+    pub fn create(
+        &self,
+        ctx: &ApiContext,
+        host: &mut dyn TabHost,
+        props: CreateProperties,
+    ) -> ApiResult<Tab> {
+        let tab_id = host.create(&props)?;
+        self.sync(&*host);
+        self.get(ctx, &*host, tab_id)
     }
 
     /// Update a tab.
     pub fn update(
         &self,
-        _ctx: &ApiContext,
+        ctx: &ApiContext,
+        host: &mut dyn TabHost,
         tab_id: TabId,
         props: UpdateProperties,
     ) -> ApiResult<Tab> {
-        let mut tabs = self.tabs.write();
-        let tab = tabs
-            .get_mut(&tab_id)
-            .ok_or_else(|| ApiError::not_found("Tab"))?;
-
-        let mut change_info = TabChangeInfo {
-            status: None,
-            url: None,
-            group_id: None,
-            pinned: None,
-            audible: None,
-            discarded: None,
-            auto_discardable: None,
-            muted_info: None,
-            fav_icon_url: None,
-            title: None,
-        };
-
-        if let Some(url) = props.url {
-            tab.url = Some(url.clone());
-            change_info.url = Some(url);
-        }
-        if let Some(active) = props.active {
-            tab.active = active;
-        }
-        if let Some(pinned) = props.pinned {
-            if tab.pinned != pinned {
-                tab.pinned = pinned;
-                change_info.pinned = Some(pinned);
-            }
-        }
-        if let Some(auto_discardable) = props.auto_discardable {
-            if tab.auto_discardable != auto_discardable {
-                tab.auto_discardable = auto_discardable;
-                change_info.auto_discardable = Some(auto_discardable);
-            }
-        }
-
-        let tab_clone = tab.clone();
-        drop(tabs);
-
-        self.on_updated
-            .read()
-            .emit(&(tab_id, change_info, tab_clone.clone()));
-
-        Ok(tab_clone)
+        find_tab(host.tabs(), tab_id)?;
+        host.update(tab_id, &props)?;
+        self.sync(&*host);
+        self.get(ctx, &*host, tab_id)
     }
 
     /// Remove tabs.
-    pub fn remove(&self, _ctx: &ApiContext, tab_ids: Vec<TabId>) -> ApiResult<()> {
-        let mut tabs = self.tabs.write();
-
-        for tab_id in tab_ids {
-            if let Some(tab) = tabs.remove(&tab_id) {
-                let remove_info = TabRemoveInfo {
-                    window_id: tab.window_id,
-                    is_window_closing: false,
-                };
-                self.on_removed.read().emit(&(tab_id, remove_info));
+    ///
+    /// Nothing is closed if any of the tabs does not exist.
+    pub fn remove(
+        &self,
+        _ctx: &ApiContext,
+        host: &mut dyn TabHost,
+        tab_ids: Vec<TabId>,
+    ) -> ApiResult<()> {
+        let tabs = host.tabs();
+        for tab_id in &tab_ids {
+            if !tabs.iter().any(|tab| tab.id == *tab_id) {
+                return Err(ApiError::not_found("Tab"));
             }
         }
 
-        Ok(())
+        let result = tab_ids.iter().try_for_each(|tab_id| host.remove(*tab_id));
+        self.sync(&*host);
+        result
     }
 
     /// Reload a tab.
     pub fn reload(
         &self,
-        _ctx: &ApiContext,
-        _tab_id: Option<TabId>,
-        _bypass_cache: bool,
+        ctx: &ApiContext,
+        host: &mut dyn TabHost,
+        tab_id: Option<TabId>,
+        bypass_cache: bool,
     ) -> ApiResult<()> {
-        // Would trigger page reload
-        Ok(())
+        let tab_id = target_tab(ctx, &host.tabs(), tab_id)?;
+        let result = host.reload(tab_id, bypass_cache);
+        self.sync(&*host);
+        result
     }
 
     /// Go back in history.
-    pub fn go_back(&self, _ctx: &ApiContext, _tab_id: Option<TabId>) -> ApiResult<()> {
-        // Would navigate back
-        Ok(())
+    pub fn go_back(
+        &self,
+        ctx: &ApiContext,
+        host: &mut dyn TabHost,
+        tab_id: Option<TabId>,
+    ) -> ApiResult<()> {
+        let tab_id = target_tab(ctx, &host.tabs(), tab_id)?;
+        let result = host.go_back(tab_id);
+        self.sync(&*host);
+        result
     }
 
     /// Go forward in history.
-    pub fn go_forward(&self, _ctx: &ApiContext, _tab_id: Option<TabId>) -> ApiResult<()> {
-        // Would navigate forward
-        Ok(())
+    pub fn go_forward(
+        &self,
+        ctx: &ApiContext,
+        host: &mut dyn TabHost,
+        tab_id: Option<TabId>,
+    ) -> ApiResult<()> {
+        let tab_id = target_tab(ctx, &host.tabs(), tab_id)?;
+        let result = host.go_forward(tab_id);
+        self.sync(&*host);
+        result
+    }
+
+    /// Listen for `tabs.onCreated`.
+    pub fn on_created(&self, ctx: &ApiContext, listener: EventListener<Tab>) {
+        self.listeners
+            .write()
+            .entry(ctx.extension_id.clone())
+            .or_default()
+            .on_created
+            .add_listener(listener);
+    }
+
+    /// Listen for `tabs.onUpdated`.
+    pub fn on_updated(
+        &self,
+        ctx: &ApiContext,
+        listener: EventListener<(TabId, TabChangeInfo, Tab)>,
+    ) {
+        self.listeners
+            .write()
+            .entry(ctx.extension_id.clone())
+            .or_default()
+            .on_updated
+            .add_listener(listener);
+    }
+
+    /// Listen for `tabs.onRemoved`.
+    pub fn on_removed(&self, ctx: &ApiContext, listener: EventListener<(TabId, TabRemoveInfo)>) {
+        self.listeners
+            .write()
+            .entry(ctx.extension_id.clone())
+            .or_default()
+            .on_removed
+            .add_listener(listener);
+    }
+
+    /// Listen for `tabs.onActivated`.
+    pub fn on_activated(&self, ctx: &ApiContext, listener: EventListener<TabActiveInfo>) {
+        self.listeners
+            .write()
+            .entry(ctx.extension_id.clone())
+            .or_default()
+            .on_activated
+            .add_listener(listener);
+    }
+
+    /// Drop the listeners and grants of an unloaded extension.
+    pub fn remove_extension(&self, extension_id: &ExtensionId) {
+        self.listeners.write().remove(extension_id);
+        self.active_tab_grants.write().remove(extension_id);
+    }
+
+    /// Grant `activeTab` access to a tab the user invoked the extension on
+    /// (toolbar action, context menu, keyboard shortcut).
+    ///
+    /// Only extensions declaring `activeTab` are granted. The grant lasts
+    /// until the tab navigates or closes. Returns whether it was granted.
+    pub fn grant_active_tab(&self, extension_id: &ExtensionId, tab_id: TabId) -> bool {
+        let declared = extension_manager()
+            .get_extension(extension_id)
+            .is_some_and(|ext| ext.has_permission("activeTab"));
+        if declared {
+            self.active_tab_grants
+                .write()
+                .entry(extension_id.clone())
+                .or_default()
+                .insert(tab_id);
+        }
+        declared
+    }
+
+    /// Report tab changes to listeners.
+    ///
+    /// Compares the host's tabs with the previous sync and dispatches
+    /// `onRemoved`, `onCreated`, `onUpdated` and `onActivated` for the
+    /// difference. API calls that change tabs sync by themselves; the
+    /// browser calls this after the user changes tabs.
+    pub fn sync(&self, host: &dyn TabHost) {
+        let current: BTreeMap<TabId, Tab> =
+            host.tabs().into_iter().map(|tab| (tab.id, tab)).collect();
+        let mut events = Vec::new();
+
+        {
+            let mut known = self.known.write();
+
+            for (tab_id, tab) in known.iter() {
+                if !current.contains_key(tab_id) {
+                    let info = TabRemoveInfo {
+                        window_id: tab.window_id,
+                        is_window_closing: false,
+                    };
+                    events.push(TabEvent::Removed(*tab_id, info));
+                }
+            }
+
+            for (tab_id, tab) in &current {
+                match known.get(tab_id) {
+                    None => events.push(TabEvent::Created(tab.clone())),
+                    Some(old) => {
+                        let change = change_info(old, tab);
+                        if !change.is_empty() {
+                            events.push(TabEvent::Updated(*tab_id, change, tab.clone()));
+                        }
+                    }
+                }
+            }
+
+            let previous = active_tabs(known.values());
+            for (window_id, tab_id) in active_tabs(current.values()) {
+                let previous_tab_id = previous.get(&window_id).copied();
+                if previous_tab_id != Some(tab_id) {
+                    events.push(TabEvent::Activated(TabActiveInfo {
+                        previous_tab_id: previous_tab_id.filter(|id| current.contains_key(id)),
+                        tab_id,
+                        window_id,
+                    }));
+                }
+            }
+
+            *known = current;
+        }
+
+        // Navigating or closing a tab ends its `activeTab` grants, before
+        // the change is reported.
+        {
+            let mut grants = self.active_tab_grants.write();
+            for event in &events {
+                let revoked = match event {
+                    TabEvent::Removed(tab_id, _) => *tab_id,
+                    TabEvent::Updated(tab_id, change, _) if change.url.is_some() => *tab_id,
+                    _ => continue,
+                };
+                for granted in grants.values_mut() {
+                    granted.remove(&revoked);
+                }
+            }
+        }
+
+        let listeners = self.listeners.read();
+        for (extension_id, listeners) in listeners.iter() {
+            let access = self.access(extension_id);
+            for event in &events {
+                listeners.dispatch(&access, event);
+            }
+        }
+    }
+
+    /// Visibility of tab fields for an extension.
+    fn access(&self, extension_id: &ExtensionId) -> TabAccess {
+        TabAccess {
+            extension: extension_manager().get_extension(extension_id),
+            granted: self
+                .active_tab_grants
+                .read()
+                .get(extension_id)
+                .cloned()
+                .unwrap_or_default(),
+        }
     }
 }
 
@@ -532,6 +758,118 @@ impl Default for TabsApi {
     }
 }
 
+/// Find a tab in a snapshot.
+fn find_tab(tabs: Vec<Tab>, tab_id: TabId) -> ApiResult<Tab> {
+    tabs.into_iter()
+        .find(|tab| tab.id == tab_id)
+        .ok_or_else(|| ApiError::not_found("Tab"))
+}
+
+/// Window of the calling tab, or else of the first active tab.
+fn current_window(ctx: &ApiContext, tabs: &[Tab]) -> Option<WindowId> {
+    ctx.tab_id
+        .and_then(|tab_id| tabs.iter().find(|tab| tab.id == tab_id))
+        .or_else(|| tabs.iter().find(|tab| tab.active))
+        .map(|tab| tab.window_id)
+}
+
+/// Tab an optional `tabId` argument refers to: the given tab, else the
+/// calling tab, else the active tab of the current window.
+fn target_tab(ctx: &ApiContext, tabs: &[Tab], tab_id: Option<TabId>) -> ApiResult<TabId> {
+    let window_id = current_window(ctx, tabs);
+    let tab_id = tab_id.or(ctx.tab_id).or_else(|| {
+        tabs.iter()
+            .find(|tab| tab.active && Some(tab.window_id) == window_id)
+            .map(|tab| tab.id)
+    });
+
+    match tab_id {
+        Some(tab_id) if tabs.iter().any(|tab| tab.id == tab_id) => Ok(tab_id),
+        _ => Err(ApiError::not_found("Tab")),
+    }
+}
+
+/// Active tab of each window.
+fn active_tabs<'a>(tabs: impl Iterator<Item = &'a Tab>) -> BTreeMap<WindowId, TabId> {
+    tabs.filter(|tab| tab.active)
+        .map(|tab| (tab.window_id, tab.id))
+        .collect()
+}
+
+/// Fields that differ between two snapshots of a tab.
+fn change_info(old: &Tab, new: &Tab) -> TabChangeInfo {
+    fn changed<T: PartialEq + Clone>(old: &T, new: &T) -> Option<T> {
+        (old != new).then(|| new.clone())
+    }
+
+    let muted = |tab: &Tab| tab.muted_info.as_ref().is_some_and(|info| info.muted);
+
+    TabChangeInfo {
+        status: changed(&old.status, &new.status).flatten(),
+        url: changed(&old.url, &new.url).flatten(),
+        group_id: changed(&old.group_id, &new.group_id),
+        pinned: changed(&old.pinned, &new.pinned),
+        audible: changed(&old.audible, &new.audible).flatten(),
+        discarded: changed(&old.discarded, &new.discarded),
+        auto_discardable: changed(&old.auto_discardable, &new.auto_discardable),
+        muted_info: if muted(old) != muted(new) {
+            new.muted_info.clone()
+        } else {
+            None
+        },
+        fav_icon_url: changed(&old.fav_icon_url, &new.fav_icon_url).flatten(),
+        title: changed(&old.title, &new.title).flatten(),
+    }
+}
+
+/// Check a (redacted) tab against query filters.
+fn matches_query(tab: &Tab, query: &QueryInfo, current_window: Option<WindowId>) -> bool {
+    let in_current = Some(tab.window_id) == current_window;
+    let muted = tab.muted_info.as_ref().is_some_and(|info| info.muted);
+
+    query.active.is_none_or(|active| tab.active == active)
+        && query.pinned.is_none_or(|pinned| tab.pinned == pinned)
+        && query
+            .audible
+            .is_none_or(|audible| tab.audible.unwrap_or(false) == audible)
+        && query.muted.is_none_or(|want| muted == want)
+        && query
+            .highlighted
+            .is_none_or(|highlighted| tab.highlighted == highlighted)
+        && query
+            .discarded
+            .is_none_or(|discarded| tab.discarded == discarded)
+        && query
+            .auto_discardable
+            .is_none_or(|auto_discardable| tab.auto_discardable == auto_discardable)
+        && query
+            .current_window
+            .is_none_or(|current| in_current == current)
+        && query
+            .last_focused_window
+            .is_none_or(|focused| in_current == focused)
+        && query.status.is_none_or(|status| tab.status == Some(status))
+        && query
+            .window_id
+            .is_none_or(|window_id| tab.window_id == window_id)
+        && query.index.is_none_or(|index| tab.index == index)
+        && query
+            .group_id
+            .is_none_or(|group_id| tab.group_id == group_id)
+        && query.title.as_deref().is_none_or(|pattern| {
+            tab.title
+                .as_deref()
+                .is_some_and(|title| content::matches_glob(title, pattern))
+        })
+        && query.url.as_ref().is_none_or(|patterns| {
+            tab.url.as_deref().is_some_and(|url| {
+                patterns
+                    .iter()
+                    .any(|pattern| matches_url_pattern(url, pattern))
+            })
+        })
+}
+
 /// Match URL against pattern.
 fn matches_url_pattern(url: &str, pattern: &str) -> bool {
     if pattern == "<all_urls>" {
@@ -565,28 +903,286 @@ fn matches_url_pattern(url: &str, pattern: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ExtensionId;
+    use crate::manifest::Manifest;
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use alloc::vec;
+
+    /// Single-window host keeping tabs in creation order.
+    struct MemoryHost {
+        tabs: Vec<Tab>,
+        next_id: TabId,
+    }
+
+    impl MemoryHost {
+        fn new() -> Self {
+            Self {
+                tabs: Vec::new(),
+                next_id: 1,
+            }
+        }
+
+        fn navigate(&mut self, tab_id: TabId, url: &str) {
+            let tab = self.tabs.iter_mut().find(|t| t.id == tab_id).unwrap();
+            tab.url = Some(url.to_string());
+            tab.title = Some(url.to_string());
+        }
+
+        fn activate(&mut self, tab_id: TabId) {
+            for tab in &mut self.tabs {
+                tab.active = tab.id == tab_id;
+            }
+        }
+    }
+
+    impl TabHost for MemoryHost {
+        fn tabs(&self) -> Vec<Tab> {
+            self.tabs.clone()
+        }
+
+        fn create(&mut self, props: &CreateProperties) -> ApiResult<TabId> {
+            let id = self.next_id;
+            self.next_id += 1;
+            let mut tab = Tab::new(id, 1, self.tabs.len() as i32);
+            tab.status = Some(TabStatus::Complete);
+            tab.opener_tab_id = props.opener_tab_id;
+            self.tabs.push(tab);
+            if let Some(url) = &props.url {
+                self.navigate(id, url);
+            }
+            if props.active.unwrap_or(true) {
+                self.activate(id);
+            }
+            Ok(id)
+        }
+
+        fn update(&mut self, tab_id: TabId, props: &UpdateProperties) -> ApiResult<()> {
+            if let Some(url) = &props.url {
+                self.navigate(tab_id, url);
+            }
+            if props.active == Some(true) {
+                self.activate(tab_id);
+            }
+            if let Some(pinned) = props.pinned {
+                self.tabs
+                    .iter_mut()
+                    .find(|t| t.id == tab_id)
+                    .unwrap()
+                    .pinned = pinned;
+            }
+            Ok(())
+        }
+
+        fn remove(&mut self, tab_id: TabId) -> ApiResult<()> {
+            self.tabs.retain(|t| t.id != tab_id);
+            for (index, tab) in self.tabs.iter_mut().enumerate() {
+                tab.index = index as i32;
+            }
+            Ok(())
+        }
+
+        fn reload(&mut self, _tab_id: TabId, _bypass_cache: bool) -> ApiResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Install an extension with the given permissions and return a call
+    /// context for it. Names must be unique across tests.
+    fn extension(name: &str, permissions: &[&str], hosts: &[&str]) -> ApiContext {
+        let mut manifest = Manifest::new(name, "1.0");
+        for permission in permissions {
+            manifest.add_permission(permission);
+        }
+        for host in hosts {
+            manifest.add_host_permission(host);
+        }
+        let id = extension_manager()
+            .load_extension(manifest, "/ext")
+            .unwrap();
+        ApiContext::new(id)
+    }
+
+    fn open(api: &TabsApi, ctx: &ApiContext, host: &mut MemoryHost, url: &str) -> Tab {
+        let props = CreateProperties {
+            url: Some(url.to_string()),
+            ..Default::default()
+        };
+        api.create(ctx, host, props).unwrap()
+    }
 
     #[test]
     fn test_tabs_api() {
         let api = TabsApi::new();
-        let ctx = ApiContext::new(ExtensionId::new("test"));
+        let mut host = MemoryHost::new();
+        let ctx = extension("Tabs API", &["tabs"], &[]);
 
         // Create a tab
-        let props = CreateProperties {
-            url: Some("https://example.com".to_string()),
-            ..Default::default()
-        };
-        let tab = api.create(&ctx, props).unwrap();
+        let tab = open(&api, &ctx, &mut host, "https://example.com");
         assert_eq!(tab.url, Some("https://example.com".to_string()));
+        assert!(tab.active);
 
         // Query tabs
-        let tabs = api.query(&ctx, QueryInfo::default()).unwrap();
+        open(&api, &ctx, &mut host, "https://other.org/page");
+        let tabs = api.query(&ctx, &host, QueryInfo::default()).unwrap();
+        assert_eq!(tabs.len(), 2);
+
+        let query = QueryInfo {
+            url: Some(vec!["https://example.com*".to_string()]),
+            ..Default::default()
+        };
+        let tabs = api.query(&ctx, &host, query).unwrap();
+        assert_eq!(tabs.len(), 1);
+        assert_eq!(tabs[0].id, tab.id);
+
+        let query = QueryInfo {
+            active: Some(true),
+            current_window: Some(true),
+            ..Default::default()
+        };
+        let tabs = api.query(&ctx, &host, query).unwrap();
         assert_eq!(tabs.len(), 1);
+        assert_ne!(tabs[0].id, tab.id);
 
-        // Get tab
-        let tab = api.get(&ctx, tab.id).unwrap();
+        // Get, update and remove a tab
+        let tab = api.get(&ctx, &host, tab.id).unwrap();
         assert_eq!(tab.url, Some("https://example.com".to_string()));
+
+        let props = UpdateProperties {
+            url: Some("https://example.com/next".to_string()),
+            active: Some(true),
+            ..Default::default()
+        };
+        let tab = api.update(&ctx, &mut host, tab.id, props).unwrap();
+        assert_eq!(tab.url, Some("https://example.com/next".to_string()));
+        assert!(tab.active);
+
+        api.remove(&ctx, &mut host, vec![tab.id]).unwrap();
+        assert!(api.get(&ctx, &host, tab.id).is_err());
+        assert!(api.remove(&ctx, &mut host, vec![tab.id]).is_err());
+    }
+
+    #[test]
+    fn test_redaction_without_tabs_permission() {
+        let api = TabsApi::new();
+        let mut host = MemoryHost::new();
+        let ctx = extension("Tabs Redaction", &[], &["https://example.com/*"]);
+
+        let other = open(&api, &ctx, &mut host, "https://other.org/");
+        assert_eq!(other.url, None);
+        assert_eq!(other.title, None);
+
+        // Host permission reveals matching tabs only.
+        let allowed = open(&api, &ctx, &mut host, "https://example.com/a");
+        assert_eq!(allowed.url, Some("https://example.com/a".to_string()));
+
+        // URL and title filters do not match redacted tabs.
+        let query = QueryInfo {
+            url: Some(vec!["<all_urls>".to_string()]),
+            ..Default::default()
+        };
+        let tabs = api.query(&ctx, &host, query).unwrap();
+        assert_eq!(tabs.len(), 1);
+        assert_eq!(tabs[0].id, allowed.id);
+
+        let query = QueryInfo {
+            title: Some("*other*".to_string()),
+            ..Default::default()
+        };
+        assert!(api.query(&ctx, &host, query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_active_tab_grant() {
+        let api = TabsApi::new();
+        let mut host = MemoryHost::new();
+        let ctx = extension("Tabs ActiveTab", &["activeTab"], &[]);
+        let plain = extension("Tabs No ActiveTab", &[], &[]);
+
+        let tab = open(&api, &ctx, &mut host, "https://example.com/");
+        assert_eq!(tab.url, None);
+
+        assert!(!api.grant_active_tab(&plain.extension_id, tab.id));
+        assert!(api.grant_active_tab(&ctx.extension_id, tab.id));
+        let tab = api.get(&ctx, &host, tab.id).unwrap();
+        assert_eq!(tab.url, Some("https://example.com/".to_string()));
+        assert_eq!(api.get(&plain, &host, tab.id).unwrap().url, None);
+
+        // Navigating ends the grant.
+        host.navigate(tab.id, "https://example.com/next");
+        api.sync(&host);
+        assert_eq!(api.get(&ctx, &host, tab.id).unwrap().url, None);
+    }
+
+    #[test]
+    fn test_tab_events() {
+        let api = TabsApi::new();
+        let mut host = MemoryHost::new();
+        let ctx = extension("Tabs Events", &[], &[]);
+
+        let log = Arc::new(RwLock::new(Vec::new()));
+        let sink = log.clone();
+        api.on_created(
+            &ctx,
+            Box::new(move |tab: &Tab| sink.write().push(("created", tab.id, tab.url.clone()))),
+        );
+        let sink = log.clone();
+        api.on_updated(
+            &ctx,
+            Box::new(move |(id, change, _): &(TabId, TabChangeInfo, Tab)| {
+                sink.write().push(("updated", *id, change.url.clone()))
+            }),
+        );
+        let sink = log.clone();
+        api.on_activated(
+            &ctx,
+            Box::new(move |info: &TabActiveInfo| {
+                sink.write().push(("activated", info.tab_id, None))
+            }),
+        );
+        let sink = log.clone();
+        api.on_removed(
+            &ctx,
+            Box::new(move |(id, _): &(TabId, TabRemoveInfo)| {
+                sink.write().push(("removed", *id, None))
+            }),
+        );
+
+        let first = open(&api, &ctx, &mut host, "https://example.com/");
+        let second = open(&api, &ctx, &mut host, "https://example.com/");
+        assert_eq!(
+            *log.read(),
+            vec![
+                ("created", first.id, None),
+                ("activated", first.id, None),
+                ("created", second.id, None),
+                ("activated", second.id, None),
+            ]
+        );
+        log.write().clear();
+
+        // A navigation is hidden from an extension that cannot see the tab.
+        host.navigate(first.id, "https://example.com/next");
+        api.sync(&host);
+        assert!(log.read().is_empty());
+
+        // Changes outside the browser are picked up on sync.
+        host.activate(first.id);
+        host.tabs[0].pinned = true;
+        api.sync(&host);
+        api.remove(&ctx, &mut host, vec![second.id]).unwrap();
+        assert_eq!(
+            *log.read(),
+            vec![
+                ("updated", first.id, None),
+                ("activated", first.id, None),
+                ("removed", second.id, None),
+            ]
+        );
+
+        api.remove_extension(&ctx.extension_id);
+        open(&api, &ctx, &mut host, "https://example.com/");
+        assert_eq!(log.read().len(), 3);
     }
 
     #[test]
@@ -493,11 +493,20 @@ pub enum ObjectProperty {
         computed: bool,
         shorthand: bool,
         method: bool,
+        kind: PropertyKind,
         span: Span,
     },
     Spread(SpreadElement),
 }
 
+/// Object literal property kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyKind {
+    Init,
+    Get,
+    Set,
+}
+
 /// Function expression.
 #[derive(Debug, Clone)]
 pub struct FunctionExpr {
@@ -556,6 +565,9 @@ pub struct TemplateElement {
 pub struct TaggedTemplateExpr {
     pub tag: Box<Expression>,
     pub quasi: TemplateLiteral,
+    /// Identifies the call site, which keeps one strings array for every
+    /// evaluation.  Unique across parses.
+    pub site: u64,
     pub span: Span,
 }
 
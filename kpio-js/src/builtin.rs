@@ -2,15 +2,24 @@
 //!
 //! Implements standard JavaScript built-in objects.
 
+use alloc::boxed::Box;
+use alloc::rc::Rc;
 use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
+use core::cell::RefCell;
 use libm::trunc;
 
+use crate::console::ConsoleLevel;
+use crate::date;
 use crate::error::{JsError, JsResult};
-use crate::interpreter::Interpreter;
-use crate::object::{Callable, JsObject, NativeFunction, PropertyDescriptor, PropertyKey};
-use crate::value::Value;
+use crate::interpreter::{AsyncFrame, Interpreter};
+use crate::object::{
+    BoundFunction, Callable, CollectionData, DataViewData, IntrinsicFunction, IterationKind,
+    IteratorState, JsObject, NativeFunction, ObjectKind, PromiseReaction, PromiseState,
+    PropertyDescriptor, PropertyKey, TypedArrayData, TypedArrayKind,
+};
+use crate::value::{Symbol, Value, WellKnownSymbols};
 
 /// Initialize built-in objects.
 pub fn init(interp: &mut Interpreter) {
@@ -47,11 +56,29 @@ pub fn init(interp: &mut Interpreter) {
     // Math object
     init_math(interp);
 
+    // Date constructor
+    init_date(interp);
+
     // JSON object
     init_json(interp);
 
     // Error constructors
     init_error(interp);
+
+    // Symbol
+    init_symbol(interp);
+
+    // Keyed collections
+    init_map(interp);
+    init_set(interp);
+
+    // Binary data
+    init_array_buffer(interp);
+    init_typed_arrays(interp);
+    init_data_view(interp);
+
+    // Promise
+    init_promise(interp);
 }
 
 // Global functions
@@ -219,76 +246,64 @@ fn eval(_this: &Value, _args: &[Value]) -> JsResult<Value> {
 fn init_console(interp: &mut Interpreter) {
     let mut console = JsObject::new();
 
-    console.define_property(
-        PropertyKey::string("log"),
-        PropertyDescriptor::data(
-            Value::object(JsObject::function(Callable::Native(NativeFunction {
-                name: "log".into(),
-                length: 0,
-                func: console_log,
-            }))),
-            true,
-            false,
-            true,
-        ),
-    );
-
-    console.define_property(
-        PropertyKey::string("error"),
-        PropertyDescriptor::data(
-            Value::object(JsObject::function(Callable::Native(NativeFunction {
-                name: "error".into(),
-                length: 0,
-                func: console_log,
-            }))),
-            true,
-            false,
-            true,
-        ),
-    );
+    define_console_method(&mut console, "debug", console_debug);
+    define_console_method(&mut console, "log", console_log);
+    define_console_method(&mut console, "info", console_info);
+    define_console_method(&mut console, "warn", console_warn);
+    define_console_method(&mut console, "error", console_error);
 
-    console.define_property(
-        PropertyKey::string("warn"),
-        PropertyDescriptor::data(
-            Value::object(JsObject::function(Callable::Native(NativeFunction {
-                name: "warn".into(),
-                length: 0,
-                func: console_log,
-            }))),
-            true,
-            false,
-            true,
-        ),
-    );
+    interp.define_global("console", Value::object(console));
+}
 
+fn define_console_method(
+    console: &mut JsObject,
+    name: &str,
+    func: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+) {
     console.define_property(
-        PropertyKey::string("info"),
+        PropertyKey::string(name),
         PropertyDescriptor::data(
-            Value::object(JsObject::function(Callable::Native(NativeFunction {
-                name: "info".into(),
+            Value::object(JsObject::function(Callable::Intrinsic(IntrinsicFunction {
+                name: name.into(),
                 length: 0,
-                func: console_log,
+                func,
             }))),
             true,
             false,
             true,
         ),
     );
-
-    interp.define_global("console", Value::object(console));
 }
 
-fn console_log(_this: &Value, args: &[Value]) -> JsResult<Value> {
-    // In a real implementation, this would output to the console
+/// Join the arguments with spaces and log them at `level`.
+fn console_output(interp: &mut Interpreter, level: ConsoleLevel, args: &[Value]) -> Value {
     let parts: Vec<String> = args
         .iter()
         .map(|v| v.to_string().unwrap_or_else(|_| "[error]".into()))
         .collect();
 
-    let _message = parts.join(" ");
-    // TODO: Actually log the message somewhere
+    interp.console_message(level, parts.join(" "));
+    Value::undefined()
+}
+
+fn console_debug(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    Ok(console_output(interp, ConsoleLevel::Debug, args))
+}
 
-    Ok(Value::undefined())
+fn console_log(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    Ok(console_output(interp, ConsoleLevel::Log, args))
+}
+
+fn console_info(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    Ok(console_output(interp, ConsoleLevel::Info, args))
+}
+
+fn console_warn(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    Ok(console_output(interp, ConsoleLevel::Warn, args))
+}
+
+fn console_error(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    Ok(console_output(interp, ConsoleLevel::Error, args))
 }
 
 // Object constructor
@@ -315,49 +330,19 @@ fn init_object(interp: &mut Interpreter) {
         ),
     );
 
-    // Object.values
-    obj.define_property(
-        PropertyKey::string("values"),
-        PropertyDescriptor::data(
-            Value::object(JsObject::function(Callable::Native(NativeFunction {
-                name: "values".into(),
-                length: 1,
-                func: object_values,
-            }))),
-            true,
-            false,
-            true,
-        ),
-    );
-
-    // Object.entries
-    obj.define_property(
-        PropertyKey::string("entries"),
-        PropertyDescriptor::data(
-            Value::object(JsObject::function(Callable::Native(NativeFunction {
-                name: "entries".into(),
-                length: 1,
-                func: object_entries,
-            }))),
-            true,
-            false,
-            true,
-        ),
-    );
-
-    // Object.assign
-    obj.define_property(
-        PropertyKey::string("assign"),
-        PropertyDescriptor::data(
-            Value::object(JsObject::function(Callable::Native(NativeFunction {
-                name: "assign".into(),
-                length: 2,
-                func: object_assign,
-            }))),
-            true,
-            false,
-            true,
-        ),
+    // Object.values, Object.entries, Object.assign
+    define_intrinsic(&mut obj, "values", 1, object_values);
+    define_intrinsic(&mut obj, "entries", 1, object_entries);
+    define_intrinsic(&mut obj, "assign", 2, object_assign);
+
+    // Property descriptors
+    define_intrinsic(&mut obj, "defineProperty", 3, object_define_property);
+    define_intrinsic(&mut obj, "defineProperties", 2, object_define_properties);
+    define_intrinsic(
+        &mut obj,
+        "getOwnPropertyDescriptor",
+        2,
+        object_get_own_property_descriptor,
     );
 
     interp.define_global("Object", Value::object(obj));
@@ -383,38 +368,36 @@ fn object_keys(_this: &Value, args: &[Value]) -> JsResult<Value> {
     Ok(Value::object(JsObject::array(keys)))
 }
 
-fn object_values(_this: &Value, args: &[Value]) -> JsResult<Value> {
+fn object_values(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
     let obj = args.first().unwrap_or(&Value::undefined()).to_object()?;
-    let values: Vec<Option<Value>> = obj
-        .borrow()
-        .own_enumerable_keys()
-        .into_iter()
-        .map(|k| obj.borrow().get(&k).ok())
-        .collect();
+    let keys = obj.borrow().own_enumerable_keys();
+    let obj = Value::Object(obj);
+    let mut values = Vec::with_capacity(keys.len());
+    for key in keys {
+        values.push(Some(interp.get_property(&obj, &key)?));
+    }
 
     Ok(Value::object(JsObject::array(values)))
 }
 
-fn object_entries(_this: &Value, args: &[Value]) -> JsResult<Value> {
+fn object_entries(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
     let obj = args.first().unwrap_or(&Value::undefined()).to_object()?;
-    let entries: Vec<Option<Value>> = obj
-        .borrow()
-        .own_enumerable_keys()
-        .into_iter()
-        .map(|k| {
-            let value = obj.borrow().get(&k).unwrap_or(Value::undefined());
-            Some(Value::object(JsObject::array(vec![
-                Some(Value::string(k.to_string())),
-                Some(value),
-            ])))
-        })
-        .collect();
+    let keys = obj.borrow().own_enumerable_keys();
+    let obj = Value::Object(obj);
+    let mut entries = Vec::with_capacity(keys.len());
+    for key in keys {
+        let value = interp.get_property(&obj, &key)?;
+        entries.push(Some(Value::object(JsObject::array(vec![
+            Some(Value::string(key.to_string())),
+            Some(value),
+        ]))));
+    }
 
     Ok(Value::object(JsObject::array(entries)))
 }
 
-fn object_assign(_this: &Value, args: &[Value]) -> JsResult<Value> {
-    let target = args.first().unwrap_or(&Value::undefined()).to_object()?;
+fn object_assign(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    let target = Value::Object(args.first().unwrap_or(&Value::undefined()).to_object()?);
 
     for source in args.iter().skip(1) {
         if source.is_nullish() {
@@ -422,13 +405,155 @@ fn object_assign(_this: &Value, args: &[Value]) -> JsResult<Value> {
         }
 
         let src = source.to_object()?;
-        for key in src.borrow().own_enumerable_keys() {
-            let value = src.borrow().get(&key)?;
-            target.borrow_mut().set(key, value)?;
+        let keys = src.borrow().own_enumerable_keys();
+        let src = Value::Object(src);
+        for key in keys {
+            let value = interp.get_property(&src, &key)?;
+            interp.set_property(&target, key, value)?;
+        }
+    }
+
+    Ok(target)
+}
+
+fn object_define_property(
+    interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    let target = args.first().cloned().unwrap_or(Value::undefined());
+    let Value::Object(obj) = &target else {
+        return Err(JsError::type_error(
+            "Object.defineProperty called on non-object",
+        ));
+    };
+    let key = interp.value_to_property_key(args.get(1).unwrap_or(&Value::undefined()))?;
+    let descriptor = to_property_descriptor(interp, args.get(2).unwrap_or(&Value::undefined()))?;
+
+    obj.borrow_mut().define_own_property(key, descriptor)?;
+    Ok(target)
+}
+
+fn object_define_properties(
+    interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    let target = args.first().cloned().unwrap_or(Value::undefined());
+    let Value::Object(obj) = &target else {
+        return Err(JsError::type_error(
+            "Object.defineProperties called on non-object",
+        ));
+    };
+    let props = args.get(1).unwrap_or(&Value::undefined()).to_object()?;
+    let keys = props.borrow().own_enumerable_keys();
+    let props = Value::Object(props);
+
+    // Every descriptor is read and validated before any is applied
+    let mut descriptors = Vec::with_capacity(keys.len());
+    for key in keys {
+        let desc = interp.get_property(&props, &key)?;
+        descriptors.push((key, to_property_descriptor(interp, &desc)?));
+    }
+
+    for (key, descriptor) in descriptors {
+        obj.borrow_mut().define_own_property(key, descriptor)?;
+    }
+    Ok(target)
+}
+
+fn object_get_own_property_descriptor(
+    interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    let obj = args.first().unwrap_or(&Value::undefined()).to_object()?;
+    let key = interp.value_to_property_key(args.get(1).unwrap_or(&Value::undefined()))?;
+
+    let descriptor = obj.borrow().get_own_property(&key);
+    Ok(descriptor
+        .map(from_property_descriptor)
+        .unwrap_or(Value::undefined()))
+}
+
+/// Read a descriptor object (ToPropertyDescriptor).
+///
+/// Only the fields present on the object are set, so that
+/// `define_own_property` can tell a missing field from a `false` one.
+fn to_property_descriptor(interp: &mut Interpreter, desc: &Value) -> JsResult<PropertyDescriptor> {
+    let Value::Object(obj) = desc else {
+        return Err(JsError::type_error(
+            "Property description must be an object",
+        ));
+    };
+
+    let mut field = |name: &str| -> JsResult<Option<Value>> {
+        let key = PropertyKey::string(name);
+        if obj.borrow().has(&key) {
+            interp.get_property(desc, &key).map(Some)
+        } else {
+            Ok(None)
         }
+    };
+
+    let descriptor = PropertyDescriptor {
+        enumerable: field("enumerable")?.map(|v| v.to_boolean()),
+        configurable: field("configurable")?.map(|v| v.to_boolean()),
+        value: field("value")?,
+        writable: field("writable")?.map(|v| v.to_boolean()),
+        get: field("get")?,
+        set: field("set")?,
+    };
+
+    let not_callable = |f: &Option<Value>| {
+        f.as_ref()
+            .is_some_and(|f| !f.is_undefined() && !f.is_function())
+    };
+    if not_callable(&descriptor.get) {
+        return Err(JsError::type_error("Getter must be a function"));
+    }
+    if not_callable(&descriptor.set) {
+        return Err(JsError::type_error("Setter must be a function"));
+    }
+    if descriptor.is_data() && descriptor.is_accessor() {
+        return Err(JsError::type_error(
+            "Invalid property descriptor. Cannot both specify accessors and a value or writable attribute",
+        ));
+    }
+
+    Ok(descriptor)
+}
+
+/// Build a descriptor object (FromPropertyDescriptor).
+fn from_property_descriptor(descriptor: PropertyDescriptor) -> Value {
+    let mut obj = JsObject::new();
+    let mut field = |name: &str, value: Value| {
+        obj.define_property(
+            PropertyKey::string(name),
+            PropertyDescriptor::data(value, true, true, true),
+        );
+    };
+
+    if descriptor.is_accessor() {
+        field("get", descriptor.get.unwrap_or(Value::undefined()));
+        field("set", descriptor.set.unwrap_or(Value::undefined()));
+    } else {
+        field("value", descriptor.value.unwrap_or(Value::undefined()));
+        field(
+            "writable",
+            Value::boolean(descriptor.writable == Some(true)),
+        );
     }
+    field(
+        "enumerable",
+        Value::boolean(descriptor.enumerable == Some(true)),
+    );
+    field(
+        "configurable",
+        Value::boolean(descriptor.configurable == Some(true)),
+    );
 
-    Ok(Value::Object(target))
+    Value::object(obj)
 }
 
 // Array constructor
@@ -543,6 +668,19 @@ fn init_array(interp: &mut Interpreter) {
         ),
     );
 
+    define_method(&mut proto, "keys", 0, array_keys);
+    define_method(&mut proto, "values", 0, array_values);
+    define_method(&mut proto, "entries", 0, array_entries);
+    proto.define_property(
+        PropertyKey::Symbol(Symbol::iterator()),
+        PropertyDescriptor::data(
+            native_function("values", 0, array_values),
+            true,
+            false,
+            true,
+        ),
+    );
+
     arr.define_property(
         PropertyKey::string("prototype"),
         PropertyDescriptor::data(Value::object(proto), false, false, false),
@@ -680,6 +818,29 @@ fn array_includes(this: &Value, args: &[Value]) -> JsResult<Value> {
     }
 }
 
+fn array_iterator(this: &Value, kind: IterationKind, method: &str) -> JsResult<Value> {
+    if this.is_array() {
+        Ok(create_iterator(this.clone(), kind))
+    } else {
+        Err(JsError::type_error(alloc::format!(
+            "Array.prototype.{} called on non-array",
+            method
+        )))
+    }
+}
+
+fn array_keys(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    array_iterator(this, IterationKind::Keys, "keys")
+}
+
+fn array_values(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    array_iterator(this, IterationKind::Values, "values")
+}
+
+fn array_entries(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    array_iterator(this, IterationKind::Entries, "entries")
+}
+
 // String constructor
 
 fn init_string(interp: &mut Interpreter) {
@@ -1270,6 +1431,9 @@ fn json_stringify(_this: &Value, args: &[Value]) -> JsResult<Value> {
             }
         }
         Value::String(s) => Ok(Value::string(alloc::format!("\"{}\"", s))),
+        Value::Object(obj) if matches!(obj.borrow().kind(), ObjectKind::Date(_)) => {
+            json_stringify(&Value::undefined(), &[date_to_json(value, &[])?])
+        }
         Value::Object(_) => {
             // Simplified - just return [object Object] for now
             Ok(Value::string("{}"))
@@ -1278,68 +1442,1945 @@ fn json_stringify(_this: &Value, args: &[Value]) -> JsResult<Value> {
     }
 }
 
-// Error constructors
+// Date
 
-fn init_error(interp: &mut Interpreter) {
-    interp.define_native_function("Error", 1, error_constructor);
-    interp.define_native_function("TypeError", 1, type_error_constructor);
-    interp.define_native_function("RangeError", 1, range_error_constructor);
-    interp.define_native_function("ReferenceError", 1, reference_error_constructor);
-    interp.define_native_function("SyntaxError", 1, syntax_error_constructor);
+fn init_date(interp: &mut Interpreter) {
+    let mut date = JsObject::function(Callable::Intrinsic(IntrinsicFunction {
+        name: "Date".into(),
+        length: 7,
+        func: date_constructor,
+    }));
+    define_intrinsic(&mut date, "now", 0, date_now);
+    define_method(&mut date, "parse", 1, date_parse);
+    define_method(&mut date, "UTC", 7, date_utc);
+
+    let mut proto = JsObject::new();
+    define_method(&mut proto, "getTime", 0, date_get_time);
+    define_method(&mut proto, "valueOf", 0, date_get_time);
+    define_method(&mut proto, "getTimezoneOffset", 0, date_get_timezone_offset);
+    define_method(&mut proto, "getFullYear", 0, date_get_full_year);
+    define_method(&mut proto, "getMonth", 0, date_get_month);
+    define_method(&mut proto, "getDate", 0, date_get_date);
+    define_method(&mut proto, "getDay", 0, date_get_day);
+    define_method(&mut proto, "getHours", 0, date_get_hours);
+    define_method(&mut proto, "getMinutes", 0, date_get_minutes);
+    define_method(&mut proto, "getSeconds", 0, date_get_seconds);
+    define_method(&mut proto, "getMilliseconds", 0, date_get_milliseconds);
+    define_method(&mut proto, "getUTCFullYear", 0, date_get_utc_full_year);
+    define_method(&mut proto, "getUTCMonth", 0, date_get_utc_month);
+    define_method(&mut proto, "getUTCDate", 0, date_get_utc_date);
+    define_method(&mut proto, "getUTCDay", 0, date_get_utc_day);
+    define_method(&mut proto, "getUTCHours", 0, date_get_utc_hours);
+    define_method(&mut proto, "getUTCMinutes", 0, date_get_utc_minutes);
+    define_method(&mut proto, "getUTCSeconds", 0, date_get_utc_seconds);
+    define_method(
+        &mut proto,
+        "getUTCMilliseconds",
+        0,
+        date_get_utc_milliseconds,
+    );
+    define_method(&mut proto, "setTime", 1, date_set_time);
+    define_method(&mut proto, "setFullYear", 3, date_set_full_year);
+    define_method(&mut proto, "setMonth", 2, date_set_month);
+    define_method(&mut proto, "setDate", 1, date_set_date);
+    define_method(&mut proto, "setHours", 4, date_set_hours);
+    define_method(&mut proto, "setMinutes", 3, date_set_minutes);
+    define_method(&mut proto, "setSeconds", 2, date_set_seconds);
+    define_method(&mut proto, "setMilliseconds", 1, date_set_milliseconds);
+    define_method(&mut proto, "setUTCFullYear", 3, date_set_utc_full_year);
+    define_method(&mut proto, "setUTCMonth", 2, date_set_utc_month);
+    define_method(&mut proto, "setUTCDate", 1, date_set_utc_date);
+    define_method(&mut proto, "setUTCHours", 4, date_set_utc_hours);
+    define_method(&mut proto, "setUTCMinutes", 3, date_set_utc_minutes);
+    define_method(&mut proto, "setUTCSeconds", 2, date_set_utc_seconds);
+    define_method(
+        &mut proto,
+        "setUTCMilliseconds",
+        1,
+        date_set_utc_milliseconds,
+    );
+    define_method(&mut proto, "toISOString", 0, date_to_iso_string);
+    define_method(&mut proto, "toJSON", 1, date_to_json);
+    define_method(&mut proto, "toString", 0, date_to_string);
+    define_method(&mut proto, "toDateString", 0, date_to_date_string);
+    define_method(&mut proto, "toTimeString", 0, date_to_time_string);
+    define_method(&mut proto, "toUTCString", 0, date_to_utc_string);
+    define_method(&mut proto, "toGMTString", 0, date_to_utc_string);
+    define_method(&mut proto, "toLocaleString", 0, date_to_string);
+    define_method(&mut proto, "toLocaleDateString", 0, date_to_date_string);
+    define_method(&mut proto, "toLocaleTimeString", 0, date_to_time_string);
+
+    date.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::object(proto), false, false, false),
+    );
+
+    interp.define_global("Date", Value::object(date));
 }
 
-fn error_constructor(_this: &Value, args: &[Value]) -> JsResult<Value> {
-    let message = args
-        .first()
-        .map(|v| v.to_string())
-        .transpose()?
-        .unwrap_or_default();
+/// `new Date()`, `new Date(value)`, `new Date(year, month[, ...])`, or
+/// `Date()`, which returns the current time as a string.
+fn date_constructor(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => return Ok(Value::string(date::to_string(interp.now()))),
+    };
 
-    Ok(Value::object(JsObject::error("Error".into(), message)))
+    let t = match args {
+        [] => interp.now(),
+        [value] => match value {
+            Value::Object(other) => match other.borrow().kind() {
+                ObjectKind::Date(t) => *t,
+                _ => date::time_clip(value.to_number()?),
+            },
+            Value::String(s) => date::parse(s),
+            _ => date::time_clip(value.to_number()?),
+        },
+        _ => date::time_clip(date::utc(date_from_components(args)?)),
+    };
+
+    obj.borrow_mut().set_kind(ObjectKind::Date(t));
+    Ok(this.clone())
 }
 
-fn type_error_constructor(_this: &Value, args: &[Value]) -> JsResult<Value> {
-    let message = args
-        .first()
-        .map(|v| v.to_string())
-        .transpose()?
-        .unwrap_or_default();
+fn date_now(interp: &mut Interpreter, _this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(Value::number(interp.now()))
+}
 
-    Ok(Value::object(JsObject::error("TypeError".into(), message)))
+fn date_parse(_this: &Value, args: &[Value]) -> JsResult<Value> {
+    let s = args.first().unwrap_or(&Value::undefined()).to_string()?;
+    Ok(Value::number(date::parse(&s)))
 }
 
-fn range_error_constructor(_this: &Value, args: &[Value]) -> JsResult<Value> {
-    let message = args
-        .first()
-        .map(|v| v.to_string())
-        .transpose()?
-        .unwrap_or_default();
+fn date_utc(_this: &Value, args: &[Value]) -> JsResult<Value> {
+    Ok(Value::number(date::time_clip(date_from_components(args)?)))
+}
 
-    Ok(Value::object(JsObject::error("RangeError".into(), message)))
+/// Build a time value, without a time zone, from the `year, month[, day,
+/// hours, minutes, seconds, ms]` arguments of the constructor and
+/// `Date.UTC`. Years 0 to 99 mean 1900 to 1999.
+fn date_from_components(args: &[Value]) -> JsResult<f64> {
+    let mut fields = [f64::NAN, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    for (field, arg) in fields.iter_mut().zip(args) {
+        *field = arg.to_number()?;
+    }
+    let [mut year, month, day, hours, minutes, seconds, ms] = fields;
+    if year.is_finite() && (0.0..=99.0).contains(&trunc(year)) {
+        year = 1900.0 + trunc(year);
+    }
+    Ok(date::make_date(
+        date::make_day(year, month, day),
+        date::make_time(hours, minutes, seconds, ms),
+    ))
 }
 
-fn reference_error_constructor(_this: &Value, args: &[Value]) -> JsResult<Value> {
-    let message = args
-        .first()
-        .map(|v| v.to_string())
-        .transpose()?
-        .unwrap_or_default();
+/// The time value of the `Date` behind `this`.
+fn this_date(this: &Value) -> JsResult<f64> {
+    if let Value::Object(obj) = this {
+        if let ObjectKind::Date(t) = obj.borrow().kind() {
+            return Ok(*t);
+        }
+    }
+    Err(JsError::type_error("this is not a Date object."))
+}
 
-    Ok(Value::object(JsObject::error(
-        "ReferenceError".into(),
-        message,
-    )))
+/// Read one calendar field of `this`, in local time or UTC.
+fn date_get(this: &Value, local: bool, field: fn(&date::DateFields) -> f64) -> JsResult<Value> {
+    let t = this_date(this)?;
+    let t = if local { date::local_time(t) } else { t };
+    Ok(Value::number(
+        date::DateFields::from_time(t).map_or(f64::NAN, |fields| field(&fields)),
+    ))
 }
 
-fn syntax_error_constructor(_this: &Value, args: &[Value]) -> JsResult<Value> {
-    let message = args
-        .first()
-        .map(|v| v.to_string())
-        .transpose()?
-        .unwrap_or_default();
+fn date_get_time(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(Value::number(this_date(this)?))
+}
 
-    Ok(Value::object(JsObject::error(
-        "SyntaxError".into(),
-        message,
-    )))
+fn date_get_timezone_offset(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    let t = this_date(this)?;
+    Ok(Value::number(if t.is_nan() {
+        f64::NAN
+    } else {
+        (t - date::local_time(t)) / date::MS_PER_MINUTE
+    }))
+}
+
+fn date_get_full_year(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f.year as f64)
+}
+
+fn date_get_month(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f64::from(f.month))
+}
+
+fn date_get_date(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f64::from(f.date))
+}
+
+fn date_get_day(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f64::from(f.weekday))
+}
+
+fn date_get_hours(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f64::from(f.hours))
+}
+
+fn date_get_minutes(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f64::from(f.minutes))
+}
+
+fn date_get_seconds(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f64::from(f.seconds))
+}
+
+fn date_get_milliseconds(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, true, |f| f64::from(f.milliseconds))
+}
+
+fn date_get_utc_full_year(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f.year as f64)
+}
+
+fn date_get_utc_month(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f64::from(f.month))
+}
+
+fn date_get_utc_date(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f64::from(f.date))
+}
+
+fn date_get_utc_day(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f64::from(f.weekday))
+}
+
+fn date_get_utc_hours(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f64::from(f.hours))
+}
+
+fn date_get_utc_minutes(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f64::from(f.minutes))
+}
+
+fn date_get_utc_seconds(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f64::from(f.seconds))
+}
+
+fn date_get_utc_milliseconds(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date_get(this, false, |f| f64::from(f.milliseconds))
+}
+
+/// Store a new time value in the `Date` behind `this`.
+fn set_date_value(this: &Value, t: f64) -> JsResult<Value> {
+    this_date(this)?;
+    if let Value::Object(obj) = this {
+        obj.borrow_mut().set_kind(ObjectKind::Date(t));
+    }
+    Ok(Value::number(t))
+}
+
+fn date_set_time(this: &Value, args: &[Value]) -> JsResult<Value> {
+    this_date(this)?;
+    let t = args.first().unwrap_or(&Value::undefined()).to_number()?;
+    set_date_value(this, date::time_clip(t))
+}
+
+/// Replace calendar fields of `this`, in local time or UTC.
+///
+/// Fields are numbered year, month, date, hours, minutes, seconds and
+/// milliseconds. The arguments replace consecutive fields from `first`,
+/// up to the day for the date setters and up to the milliseconds for the
+/// time setters, so `setHours(h, m)` also sets the minutes.
+fn date_set(this: &Value, args: &[Value], local: bool, first: usize) -> JsResult<Value> {
+    let mut t = this_date(this)?;
+    if t.is_nan() {
+        // Only setFullYear can revive an invalid date
+        if first != 0 {
+            return Ok(Value::number(f64::NAN));
+        }
+        t = 0.0;
+    }
+    let t = if local { date::local_time(t) } else { t };
+    let Some(f) = date::DateFields::from_time(t) else {
+        return Ok(Value::number(f64::NAN));
+    };
+    let mut fields = [
+        f.year as f64,
+        f64::from(f.month),
+        f64::from(f.date),
+        f64::from(f.hours),
+        f64::from(f.minutes),
+        f64::from(f.seconds),
+        f64::from(f.milliseconds),
+    ];
+    let last = if first <= 2 { 2 } else { 6 };
+    let undefined = [Value::undefined()];
+    let args = if args.is_empty() {
+        &undefined[..]
+    } else {
+        args
+    };
+    for (field, arg) in fields[first..=last].iter_mut().zip(args) {
+        *field = arg.to_number()?;
+    }
+    let [year, month, day, hours, minutes, seconds, ms] = fields;
+    let t = date::make_date(
+        date::make_day(year, month, day),
+        date::make_time(hours, minutes, seconds, ms),
+    );
+    let t = if local { date::utc(t) } else { t };
+    set_date_value(this, date::time_clip(t))
+}
+
+fn date_set_full_year(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, true, 0)
+}
+
+fn date_set_month(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, true, 1)
+}
+
+fn date_set_date(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, true, 2)
+}
+
+fn date_set_hours(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, true, 3)
+}
+
+fn date_set_minutes(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, true, 4)
+}
+
+fn date_set_seconds(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, true, 5)
+}
+
+fn date_set_milliseconds(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, true, 6)
+}
+
+fn date_set_utc_full_year(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, false, 0)
+}
+
+fn date_set_utc_month(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, false, 1)
+}
+
+fn date_set_utc_date(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, false, 2)
+}
+
+fn date_set_utc_hours(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, false, 3)
+}
+
+fn date_set_utc_minutes(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, false, 4)
+}
+
+fn date_set_utc_seconds(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, false, 5)
+}
+
+fn date_set_utc_milliseconds(this: &Value, args: &[Value]) -> JsResult<Value> {
+    date_set(this, args, false, 6)
+}
+
+fn date_to_iso_string(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    date::to_iso_string(this_date(this)?)
+        .map(Value::string)
+        .ok_or_else(|| JsError::range("Invalid time value"))
+}
+
+fn date_to_json(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(date::to_iso_string(this_date(this)?).map_or(Value::null(), Value::string))
+}
+
+fn date_to_string(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(Value::string(date::to_string(this_date(this)?)))
+}
+
+fn date_to_date_string(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(Value::string(date::to_date_string(this_date(this)?)))
+}
+
+fn date_to_time_string(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(Value::string(date::to_time_string(this_date(this)?)))
+}
+
+fn date_to_utc_string(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(Value::string(date::to_utc_string(this_date(this)?)))
+}
+
+// Error constructors
+
+fn init_error(interp: &mut Interpreter) {
+    let base = define_error(interp, "Error", None, error_constructor);
+    define_error(interp, "TypeError", Some(&base), type_error_constructor);
+    define_error(interp, "RangeError", Some(&base), range_error_constructor);
+    define_error(
+        interp,
+        "ReferenceError",
+        Some(&base),
+        reference_error_constructor,
+    );
+    define_error(interp, "SyntaxError", Some(&base), syntax_error_constructor);
+}
+
+/// Define the global error constructor `name` and its prototype, chained
+/// to `parent`.
+fn define_error(
+    interp: &mut Interpreter,
+    name: &'static str,
+    parent: Option<&Rc<RefCell<JsObject>>>,
+    func: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+) -> Rc<RefCell<JsObject>> {
+    let mut constructor = JsObject::function(Callable::Intrinsic(IntrinsicFunction {
+        name: name.into(),
+        length: 1,
+        func,
+    }));
+
+    let mut proto = JsObject::new();
+    proto.set_prototype(parent.cloned());
+    proto.define_property(
+        PropertyKey::string("name"),
+        PropertyDescriptor::data(Value::string(name), true, false, true),
+    );
+    proto.define_property(
+        PropertyKey::string("message"),
+        PropertyDescriptor::data(Value::string(""), true, false, true),
+    );
+    let proto = Rc::new(RefCell::new(proto));
+
+    constructor.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::Object(proto.clone()), false, false, false),
+    );
+
+    interp.set_error_prototype(name, proto.clone());
+    interp.define_global(name, Value::object(constructor));
+    proto
+}
+
+/// Create an error named `name` with the message in `args`.
+fn construct_error(interp: &mut Interpreter, name: &str, args: &[Value]) -> JsResult<Value> {
+    let message = args
+        .first()
+        .map(|v| v.to_string())
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(interp.new_error(name, message))
+}
+
+fn error_constructor(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    construct_error(interp, "Error", args)
+}
+
+fn type_error_constructor(
+    interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    construct_error(interp, "TypeError", args)
+}
+
+fn range_error_constructor(
+    interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    construct_error(interp, "RangeError", args)
+}
+
+fn reference_error_constructor(
+    interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    construct_error(interp, "ReferenceError", args)
+}
+
+fn syntax_error_constructor(
+    interp: &mut Interpreter,
+    _this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    construct_error(interp, "SyntaxError", args)
+}
+
+// Helpers
+
+/// Wrap a native function in a function object value.
+fn native_function(
+    name: &str,
+    length: usize,
+    func: fn(&Value, &[Value]) -> JsResult<Value>,
+) -> Value {
+    Value::object(JsObject::function(Callable::Native(NativeFunction {
+        name: name.into(),
+        length,
+        func,
+    })))
+}
+
+/// Define a writable, non-enumerable method on `obj`.
+fn define_method(
+    obj: &mut JsObject,
+    name: &str,
+    length: usize,
+    func: fn(&Value, &[Value]) -> JsResult<Value>,
+) {
+    obj.define_property(
+        PropertyKey::string(name),
+        PropertyDescriptor::data(native_function(name, length, func), true, false, true),
+    );
+}
+
+/// Wrap an interpreter-aware native function in a function object value.
+fn intrinsic_function(
+    name: &str,
+    length: usize,
+    func: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+) -> Value {
+    Value::object(JsObject::function(Callable::Intrinsic(IntrinsicFunction {
+        name: name.into(),
+        length,
+        func,
+    })))
+}
+
+/// Define a writable, non-enumerable interpreter-aware method on `obj`.
+fn define_intrinsic(
+    obj: &mut JsObject,
+    name: &str,
+    length: usize,
+    func: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+) {
+    obj.define_property(
+        PropertyKey::string(name),
+        PropertyDescriptor::data(intrinsic_function(name, length, func), true, false, true),
+    );
+}
+
+/// Create a function that calls `func` with `this` bound and `args`
+/// prepended to its arguments.
+fn bind_intrinsic(
+    name: &str,
+    length: usize,
+    func: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+    this: Value,
+    args: Vec<Value>,
+) -> Value {
+    Value::object(JsObject::function(Callable::Bound(BoundFunction {
+        target: Box::new(Callable::Intrinsic(IntrinsicFunction {
+            name: name.into(),
+            length: length + args.len(),
+            func,
+        })),
+        bound_this: this,
+        bound_args: args,
+    })))
+}
+
+// Symbol
+
+fn init_symbol(interp: &mut Interpreter) {
+    let mut symbol = JsObject::function(Callable::Native(NativeFunction {
+        name: "Symbol".into(),
+        length: 0,
+        func: symbol_constructor,
+    }));
+    // `new Symbol()` is a TypeError.
+    symbol.set_constructable(false);
+
+    let well_known = WellKnownSymbols::new();
+    let statics = [
+        ("iterator", well_known.iterator),
+        ("asyncIterator", well_known.async_iterator),
+        ("hasInstance", well_known.has_instance),
+        ("isConcatSpreadable", well_known.is_concat_spreadable),
+        ("species", well_known.species),
+        ("toPrimitive", well_known.to_primitive),
+        ("toStringTag", well_known.to_string_tag),
+        ("unscopables", well_known.unscopables),
+    ];
+    for (name, sym) in statics {
+        symbol.define_property(
+            PropertyKey::string(name),
+            PropertyDescriptor::data(Value::Symbol(sym), false, false, false),
+        );
+    }
+
+    interp.define_global("Symbol", Value::object(symbol));
+}
+
+fn symbol_constructor(_this: &Value, args: &[Value]) -> JsResult<Value> {
+    let description = match args.first() {
+        None | Some(Value::Undefined) => None,
+        Some(v) => Some(v.to_string()?),
+    };
+    Ok(Value::Symbol(Symbol::new(description)))
+}
+
+// Iterators
+
+/// Create a built-in iterator over an array, typed array, string, `Map`
+/// or `Set`.
+///
+/// The iterator reads its source lazily, so entries added to a
+/// collection while it is being iterated are visited as well.
+pub fn create_iterator(source: Value, kind: IterationKind) -> Value {
+    let mut iter = JsObject::new();
+    iter.set_kind(ObjectKind::Iterator(IteratorState {
+        source: Some(source),
+        kind,
+        index: 0,
+    }));
+    define_method(&mut iter, "next", 0, iterator_next);
+    iter.define_property(
+        PropertyKey::Symbol(Symbol::iterator()),
+        PropertyDescriptor::data(
+            native_function("[Symbol.iterator]", 0, iterator_self),
+            true,
+            false,
+            true,
+        ),
+    );
+    Value::object(iter)
+}
+
+/// Build an iterator result object `{ value, done }`.
+fn iterator_result(value: Value, done: bool) -> JsResult<Value> {
+    let mut result = JsObject::new();
+    result.set(PropertyKey::string("value"), value)?;
+    result.set(PropertyKey::string("done"), Value::boolean(done))?;
+    Ok(Value::object(result))
+}
+
+/// Read the entry at `index` from an iterator source.
+///
+/// Returns the produced value and the index to resume from, or `None`
+/// when the source is exhausted.
+fn iterator_step(
+    source: &Value,
+    kind: IterationKind,
+    index: usize,
+) -> JsResult<Option<(Value, usize)>> {
+    let select = |key: Value, value: Value| match kind {
+        IterationKind::Keys => key,
+        IterationKind::Values => value,
+        IterationKind::Entries => Value::object(JsObject::array(vec![Some(key), Some(value)])),
+    };
+
+    match source {
+        Value::String(s) => Ok(s
+            .chars()
+            .nth(index)
+            .map(|c| (Value::string(c.to_string()), index + 1))),
+        Value::Object(obj) => {
+            let obj = obj.borrow();
+            match obj.kind() {
+                ObjectKind::Map(data) | ObjectKind::Set(data) => Ok(data
+                    .next_entry(index)
+                    .map(|(slot, k, v)| (select(k, v), slot + 1))),
+                ObjectKind::TypedArray(view) => Ok(view.get(index).map(|v| {
+                    (
+                        select(Value::number(index as f64), Value::number(v)),
+                        index + 1,
+                    )
+                })),
+                _ => {
+                    if index >= obj.array_length() {
+                        return Ok(None);
+                    }
+                    let value = obj.get(&PropertyKey::Index(index as u32))?;
+                    Ok(Some((
+                        select(Value::number(index as f64), value),
+                        index + 1,
+                    )))
+                }
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+fn iterator_next(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => return Err(JsError::type_error("next called on non-iterator")),
+    };
+    let state = match obj.borrow().kind() {
+        ObjectKind::Iterator(state) => state.clone(),
+        _ => return Err(JsError::type_error("next called on non-iterator")),
+    };
+
+    let step = match &state.source {
+        Some(source) => iterator_step(source, state.kind, state.index)?,
+        None => None,
+    };
+
+    if let ObjectKind::Iterator(live) = obj.borrow_mut().kind_mut() {
+        match &step {
+            Some((_, next)) => live.index = *next,
+            None => live.source = None,
+        }
+    }
+
+    match step {
+        Some((value, _)) => iterator_result(value, false),
+        None => iterator_result(Value::undefined(), true),
+    }
+}
+
+fn iterator_self(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    Ok(this.clone())
+}
+
+/// Collect the values of a built-in iterable.
+///
+/// Native functions cannot call back into the interpreter, so only
+/// arrays, strings, `Map`, `Set` and built-in iterators are accepted.
+fn collect_builtin_iterable(iterable: &Value) -> JsResult<Vec<Value>> {
+    let (source, kind, start) = match iterable {
+        Value::String(_) => (iterable.clone(), IterationKind::Values, 0),
+        Value::Object(obj) => match obj.borrow().kind() {
+            ObjectKind::Map(_) => (iterable.clone(), IterationKind::Entries, 0),
+            ObjectKind::Set(_) | ObjectKind::Array | ObjectKind::TypedArray(_) => {
+                (iterable.clone(), IterationKind::Values, 0)
+            }
+            ObjectKind::Iterator(state) => match &state.source {
+                Some(source) => (source.clone(), state.kind, state.index),
+                None => return Ok(Vec::new()),
+            },
+            _ => return Err(JsError::type_error("Value is not iterable")),
+        },
+        _ => return Err(JsError::type_error("Value is not iterable")),
+    };
+
+    let mut values = Vec::new();
+    let mut index = start;
+    while let Some((value, next)) = iterator_step(&source, kind, index)? {
+        values.push(value);
+        index = next;
+    }
+    Ok(values)
+}
+
+// Map and Set
+
+/// Run `f` on the collection backing `this`, then refresh its `size`.
+fn with_collection<R>(
+    this: &Value,
+    is_set: bool,
+    method: &str,
+    f: impl FnOnce(&mut CollectionData) -> R,
+) -> JsResult<R> {
+    if let Value::Object(obj) = this {
+        let mut obj = obj.borrow_mut();
+        let outcome = match obj.kind_mut() {
+            ObjectKind::Map(data) if !is_set => Some((f(data), data.len())),
+            ObjectKind::Set(data) if is_set => Some((f(data), data.len())),
+            _ => None,
+        };
+        if let Some((result, size)) = outcome {
+            define_size(&mut obj, size);
+            return Ok(result);
+        }
+    }
+    let class = if is_set { "Set" } else { "Map" };
+    Err(JsError::type_error(alloc::format!(
+        "{}.prototype.{} called on incompatible receiver",
+        class,
+        method
+    )))
+}
+
+/// Publish the collection size as a read-only `size` property.
+///
+/// Getters are not supported yet, so the property is refreshed after
+/// every mutation instead of being computed on access.
+fn define_size(obj: &mut JsObject, size: usize) {
+    obj.define_property(
+        PropertyKey::string("size"),
+        PropertyDescriptor::data(Value::number(size as f64), false, false, true),
+    );
+}
+
+/// Create an iterator over a `Map` or `Set` after checking the receiver.
+fn collection_iterator(
+    this: &Value,
+    is_set: bool,
+    kind: IterationKind,
+    method: &str,
+) -> JsResult<Value> {
+    with_collection(this, is_set, method, |_| ())?;
+    Ok(create_iterator(this.clone(), kind))
+}
+
+/// Call `callback(value, key, collection)` for each entry of a `Map` or
+/// `Set`, in insertion order.
+///
+/// Like the iterators, this sees entries added during the walk and skips
+/// ones deleted before they are reached.
+fn collection_for_each(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+    is_set: bool,
+) -> JsResult<Value> {
+    with_collection(this, is_set, "forEach", |_| ())?;
+    let callback = args.first().cloned().unwrap_or(Value::undefined());
+    if !callback.is_function() {
+        return Err(JsError::type_error("forEach callback is not a function"));
+    }
+    let this_arg = args.get(1).cloned().unwrap_or(Value::undefined());
+
+    let mut index = 0;
+    // The collection is only borrowed between calls, since the callback
+    // may change it
+    while let Some((slot, key, value)) =
+        with_collection(this, is_set, "forEach", |data| data.next_entry(index))?
+    {
+        index = slot + 1;
+        interp.call_function(&callback, &this_arg, &[value, key, this.clone()])?;
+    }
+    Ok(Value::undefined())
+}
+
+fn init_map(interp: &mut Interpreter) {
+    let mut map = JsObject::function(Callable::Native(NativeFunction {
+        name: "Map".into(),
+        length: 0,
+        func: map_constructor,
+    }));
+
+    let mut proto = JsObject::new();
+    define_method(&mut proto, "get", 1, map_get);
+    define_method(&mut proto, "set", 2, map_set);
+    define_method(&mut proto, "has", 1, map_has);
+    define_method(&mut proto, "delete", 1, map_delete);
+    define_method(&mut proto, "clear", 0, map_clear);
+    define_method(&mut proto, "keys", 0, map_keys);
+    define_method(&mut proto, "values", 0, map_values);
+    define_method(&mut proto, "entries", 0, map_entries);
+    define_intrinsic(&mut proto, "forEach", 1, map_for_each);
+    proto.define_property(
+        PropertyKey::Symbol(Symbol::iterator()),
+        PropertyDescriptor::data(
+            native_function("entries", 0, map_entries),
+            true,
+            false,
+            true,
+        ),
+    );
+
+    map.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::object(proto), false, false, false),
+    );
+
+    interp.define_global("Map", Value::object(map));
+}
+
+fn map_constructor(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => return Err(JsError::type_error("Constructor Map requires 'new'")),
+    };
+
+    let mut data = CollectionData::new();
+    if let Some(iterable) = args.first().filter(|v| !v.is_nullish()) {
+        for entry in collect_builtin_iterable(iterable)? {
+            if !entry.is_object() {
+                return Err(JsError::type_error("Iterator value is not an entry object"));
+            }
+            let key = entry.get(&PropertyKey::Index(0))?;
+            let value = entry.get(&PropertyKey::Index(1))?;
+            data.insert(key, value);
+        }
+    }
+
+    let mut obj = obj.borrow_mut();
+    let size = data.len();
+    obj.set_kind(ObjectKind::Map(data));
+    define_size(&mut obj, size);
+    Ok(this.clone())
+}
+
+fn map_get(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let key = args.first().cloned().unwrap_or(Value::undefined());
+    with_collection(this, false, "get", |data| {
+        data.get(&key).unwrap_or(Value::undefined())
+    })
+}
+
+fn map_set(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let key = args.first().cloned().unwrap_or(Value::undefined());
+    let value = args.get(1).cloned().unwrap_or(Value::undefined());
+    with_collection(this, false, "set", |data| data.insert(key, value))?;
+    Ok(this.clone())
+}
+
+fn map_has(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let key = args.first().cloned().unwrap_or(Value::undefined());
+    with_collection(this, false, "has", |data| Value::boolean(data.has(&key)))
+}
+
+fn map_delete(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let key = args.first().cloned().unwrap_or(Value::undefined());
+    with_collection(this, false, "delete", |data| {
+        Value::boolean(data.remove(&key))
+    })
+}
+
+fn map_clear(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    with_collection(this, false, "clear", |data| data.clear())?;
+    Ok(Value::undefined())
+}
+
+fn map_keys(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    collection_iterator(this, false, IterationKind::Keys, "keys")
+}
+
+fn map_values(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    collection_iterator(this, false, IterationKind::Values, "values")
+}
+
+fn map_entries(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    collection_iterator(this, false, IterationKind::Entries, "entries")
+}
+
+fn map_for_each(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    collection_for_each(interp, this, args, false)
+}
+
+fn init_set(interp: &mut Interpreter) {
+    let mut set = JsObject::function(Callable::Native(NativeFunction {
+        name: "Set".into(),
+        length: 0,
+        func: set_constructor,
+    }));
+
+    let mut proto = JsObject::new();
+    define_method(&mut proto, "add", 1, set_add);
+    define_method(&mut proto, "has", 1, set_has);
+    define_method(&mut proto, "delete", 1, set_delete);
+    define_method(&mut proto, "clear", 0, set_clear);
+    define_method(&mut proto, "values", 0, set_values);
+    // `keys` behaves exactly like `values` for sets.
+    define_method(&mut proto, "keys", 0, set_values);
+    define_method(&mut proto, "entries", 0, set_entries);
+    define_intrinsic(&mut proto, "forEach", 1, set_for_each);
+    proto.define_property(
+        PropertyKey::Symbol(Symbol::iterator()),
+        PropertyDescriptor::data(native_function("values", 0, set_values), true, false, true),
+    );
+
+    set.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::object(proto), false, false, false),
+    );
+
+    interp.define_global("Set", Value::object(set));
+}
+
+fn set_constructor(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => return Err(JsError::type_error("Constructor Set requires 'new'")),
+    };
+
+    let mut data = CollectionData::new();
+    if let Some(iterable) = args.first().filter(|v| !v.is_nullish()) {
+        for value in collect_builtin_iterable(iterable)? {
+            data.insert(value.clone(), value);
+        }
+    }
+
+    let mut obj = obj.borrow_mut();
+    let size = data.len();
+    obj.set_kind(ObjectKind::Set(data));
+    define_size(&mut obj, size);
+    Ok(this.clone())
+}
+
+fn set_add(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let value = args.first().cloned().unwrap_or(Value::undefined());
+    with_collection(this, true, "add", |data| data.insert(value.clone(), value))?;
+    Ok(this.clone())
+}
+
+fn set_has(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let value = args.first().cloned().unwrap_or(Value::undefined());
+    with_collection(this, true, "has", |data| Value::boolean(data.has(&value)))
+}
+
+fn set_delete(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let value = args.first().cloned().unwrap_or(Value::undefined());
+    with_collection(this, true, "delete", |data| {
+        Value::boolean(data.remove(&value))
+    })
+}
+
+fn set_clear(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    with_collection(this, true, "clear", |data| data.clear())?;
+    Ok(Value::undefined())
+}
+
+fn set_values(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    collection_iterator(this, true, IterationKind::Values, "values")
+}
+
+fn set_entries(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    collection_iterator(this, true, IterationKind::Entries, "entries")
+}
+
+fn set_for_each(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    collection_for_each(interp, this, args, true)
+}
+
+// ArrayBuffer, typed arrays and DataView
+
+/// Largest `ArrayBuffer` a script may allocate.  Allocation failure is
+/// fatal in `no_std`, so oversized requests must throw instead.
+const MAX_ARRAY_BUFFER_LENGTH: usize = 1 << 30;
+
+/// Convert a length or offset argument (ToIndex).  `undefined` is 0;
+/// negative or oversized values throw `RangeError`.
+fn to_index(value: Option<&Value>, message: &str) -> JsResult<usize> {
+    let value = match value {
+        Some(value) if !value.is_undefined() => value,
+        _ => return Ok(0),
+    };
+    let n = value.to_integer()?;
+    if n < 0 || n as u64 > MAX_ARRAY_BUFFER_LENGTH as u64 {
+        return Err(JsError::range(message));
+    }
+    Ok(n as usize)
+}
+
+/// Resolve a relative `begin`/`end` argument against `len`: negative
+/// values count from the end and the result is clamped to `0..=len`.
+fn relative_index(value: Option<&Value>, len: usize, default: usize) -> JsResult<usize> {
+    let value = match value {
+        Some(value) if !value.is_undefined() => value,
+        _ => return Ok(default),
+    };
+    let n = value.to_integer()?;
+    Ok(if n < 0 {
+        len.saturating_sub(n.unsigned_abs() as usize)
+    } else {
+        (n as u64).min(len as u64) as usize
+    })
+}
+
+/// Define a read-only, non-enumerable data property.
+fn define_read_only(obj: &mut JsObject, name: &str, value: Value) {
+    obj.define_property(
+        PropertyKey::string(name),
+        PropertyDescriptor::data(value, false, false, true),
+    );
+}
+
+/// The `ArrayBuffer` object behind `value`, if it is one.
+fn as_array_buffer(value: Option<&Value>) -> Option<Rc<RefCell<JsObject>>> {
+    match value {
+        Some(Value::Object(obj)) if obj.borrow().array_buffer_bytes().is_some() => {
+            Some(obj.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Allocate a zeroed `ArrayBuffer`, throwing `RangeError` when it would
+/// exceed the allocation limit.
+fn allocate_array_buffer(
+    interp: &Interpreter,
+    byte_length: usize,
+) -> JsResult<Rc<RefCell<JsObject>>> {
+    if byte_length > MAX_ARRAY_BUFFER_LENGTH {
+        return Err(JsError::range("Array buffer allocation failed"));
+    }
+    Ok(interp.new_array_buffer(vec![0; byte_length]))
+}
+
+fn init_array_buffer(interp: &mut Interpreter) {
+    let mut buffer = JsObject::function(Callable::Native(NativeFunction {
+        name: "ArrayBuffer".into(),
+        length: 1,
+        func: array_buffer_constructor,
+    }));
+    define_method(&mut buffer, "isView", 1, array_buffer_is_view);
+
+    let mut proto = JsObject::new();
+    define_intrinsic(&mut proto, "slice", 2, array_buffer_slice);
+    let proto = Rc::new(RefCell::new(proto));
+
+    buffer.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::Object(proto.clone()), false, false, false),
+    );
+
+    interp.set_array_buffer_prototype(proto);
+    interp.define_global("ArrayBuffer", Value::object(buffer));
+}
+
+fn array_buffer_constructor(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => {
+            return Err(JsError::type_error(
+                "Constructor ArrayBuffer requires 'new'",
+            ))
+        }
+    };
+
+    let length = to_index(args.first(), "Invalid array buffer length")?;
+
+    let mut obj = obj.borrow_mut();
+    obj.set_kind(ObjectKind::ArrayBuffer(vec![0; length]));
+    define_read_only(&mut obj, "byteLength", Value::number(length as f64));
+    Ok(this.clone())
+}
+
+fn array_buffer_is_view(_this: &Value, args: &[Value]) -> JsResult<Value> {
+    let is_view = match args.first() {
+        Some(Value::Object(obj)) => matches!(
+            obj.borrow().kind(),
+            ObjectKind::TypedArray(_) | ObjectKind::DataView(_)
+        ),
+        _ => false,
+    };
+    Ok(Value::boolean(is_view))
+}
+
+fn array_buffer_slice(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let bytes = match this {
+        Value::Object(obj) => obj.borrow().array_buffer_bytes().map(|b| b.to_vec()),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        JsError::type_error("ArrayBuffer.prototype.slice called on incompatible receiver")
+    })?;
+
+    let begin = relative_index(args.first(), bytes.len(), 0)?;
+    let end = relative_index(args.get(1), bytes.len(), bytes.len())?.max(begin);
+    let slice = interp.new_array_buffer(bytes[begin..end].to_vec());
+    Ok(Value::Object(slice))
+}
+
+fn init_typed_arrays(interp: &mut Interpreter) {
+    // Methods shared by every typed array type (%TypedArray%.prototype)
+    let mut shared = JsObject::new();
+    define_method(&mut shared, "subarray", 2, typed_array_subarray);
+    define_intrinsic(&mut shared, "slice", 2, typed_array_slice);
+    define_method(&mut shared, "set", 1, typed_array_set);
+    define_method(&mut shared, "keys", 0, typed_array_keys);
+    define_method(&mut shared, "values", 0, typed_array_values);
+    define_method(&mut shared, "entries", 0, typed_array_entries);
+    shared.define_property(
+        PropertyKey::Symbol(Symbol::iterator()),
+        PropertyDescriptor::data(
+            native_function("values", 0, typed_array_values),
+            true,
+            false,
+            true,
+        ),
+    );
+    let shared = Rc::new(RefCell::new(shared));
+
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Int8,
+        int8_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Uint8,
+        uint8_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Uint8Clamped,
+        uint8_clamped_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Int16,
+        int16_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Uint16,
+        uint16_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Int32,
+        int32_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Uint32,
+        uint32_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Float32,
+        float32_array_constructor,
+    );
+    define_typed_array(
+        interp,
+        &shared,
+        TypedArrayKind::Float64,
+        float64_array_constructor,
+    );
+}
+
+/// Register the constructor for one typed array type.
+fn define_typed_array(
+    interp: &mut Interpreter,
+    shared: &Rc<RefCell<JsObject>>,
+    kind: TypedArrayKind,
+    constructor: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+) {
+    let bytes_per_element = Value::number(kind.element_size() as f64);
+
+    let mut ctor = JsObject::function(Callable::Intrinsic(IntrinsicFunction {
+        name: kind.name().into(),
+        length: 3,
+        func: constructor,
+    }));
+    define_read_only(&mut ctor, "BYTES_PER_ELEMENT", bytes_per_element.clone());
+
+    let mut proto = JsObject::new();
+    proto.set_prototype(Some(shared.clone()));
+    define_read_only(&mut proto, "BYTES_PER_ELEMENT", bytes_per_element);
+
+    ctor.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::object(proto), false, false, false),
+    );
+
+    interp.define_global(kind.name(), Value::object(ctor));
+}
+
+fn int8_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Int8)
+}
+
+fn uint8_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Uint8)
+}
+
+fn uint8_clamped_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Uint8Clamped)
+}
+
+fn int16_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Int16)
+}
+
+fn uint16_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Uint16)
+}
+
+fn int32_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Int32)
+}
+
+fn uint32_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Uint32)
+}
+
+fn float32_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Float32)
+}
+
+fn float64_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    typed_array_constructor(interp, this, args, TypedArrayKind::Float64)
+}
+
+/// `new XxxArray(length)`, `new XxxArray(buffer, byteOffset, length)`
+/// or `new XxxArray(source)` for a typed array, array or iterable source.
+fn typed_array_constructor(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+    kind: TypedArrayKind,
+) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => {
+            return Err(JsError::type_error(alloc::format!(
+                "Constructor {} requires 'new'",
+                kind.name()
+            )))
+        }
+    };
+    let size = kind.element_size();
+
+    let view = if let Some(buffer) = as_array_buffer(args.first()) {
+        let buffer_len = buffer.borrow().array_buffer_bytes().map_or(0, |b| b.len());
+        let byte_offset = to_index(args.get(1), "Start offset is out of bounds")?;
+        if byte_offset % size != 0 {
+            return Err(JsError::range(alloc::format!(
+                "Start offset of {} should be a multiple of {}",
+                kind.name(),
+                size
+            )));
+        }
+        let length = match args.get(2).filter(|v| !v.is_undefined()) {
+            Some(length) => {
+                let length = to_index(Some(length), "Invalid typed array length")?;
+                if byte_offset + length * size > buffer_len {
+                    return Err(JsError::range(alloc::format!(
+                        "Invalid typed array length: {}",
+                        length
+                    )));
+                }
+                length
+            }
+            None => {
+                if buffer_len % size != 0 {
+                    return Err(JsError::range(alloc::format!(
+                        "Byte length of {} should be a multiple of {}",
+                        kind.name(),
+                        size
+                    )));
+                }
+                if byte_offset > buffer_len {
+                    return Err(JsError::range("Start offset is out of bounds"));
+                }
+                (buffer_len - byte_offset) / size
+            }
+        };
+        TypedArrayData {
+            kind,
+            buffer,
+            byte_offset,
+            length,
+        }
+    } else if let Some(source) = args.first().filter(|v| v.is_object()) {
+        let values = typed_array_source_values(source)?;
+        let view = TypedArrayData {
+            kind,
+            buffer: allocate_array_buffer(interp, values.len().saturating_mul(size))?,
+            byte_offset: 0,
+            length: values.len(),
+        };
+        for (i, value) in values.into_iter().enumerate() {
+            view.set(i, value);
+        }
+        view
+    } else {
+        let length = to_index(args.first(), "Invalid typed array length")?;
+        TypedArrayData {
+            kind,
+            buffer: allocate_array_buffer(interp, length * size)?,
+            byte_offset: 0,
+            length,
+        }
+    };
+
+    init_typed_array(&mut obj.borrow_mut(), view);
+    Ok(this.clone())
+}
+
+/// Turn `obj` into a typed array over `view`.
+///
+/// Getters are not supported yet, so the (fixed) view dimensions are
+/// published as read-only data properties.
+fn init_typed_array(obj: &mut JsObject, view: TypedArrayData) {
+    define_read_only(obj, "length", Value::number(view.length as f64));
+    define_read_only(obj, "byteLength", Value::number(view.byte_length() as f64));
+    define_read_only(obj, "byteOffset", Value::number(view.byte_offset as f64));
+    define_read_only(obj, "buffer", Value::Object(view.buffer.clone()));
+    obj.set_kind(ObjectKind::TypedArray(view));
+}
+
+/// Create a typed array sharing the prototype of `template`.
+fn new_typed_array(template: &Value, view: TypedArrayData) -> Value {
+    let mut obj = JsObject::new();
+    if let Value::Object(template) = template {
+        obj.set_prototype(template.borrow().prototype().cloned());
+    }
+    init_typed_array(&mut obj, view);
+    Value::object(obj)
+}
+
+/// Collect the numeric values of a typed array, built-in iterable or
+/// array-like object.
+fn typed_array_source_values(source: &Value) -> JsResult<Vec<f64>> {
+    let obj = match source {
+        Value::Object(obj) => obj,
+        _ => return Err(JsError::type_error("Source is not an object")),
+    };
+
+    if let ObjectKind::TypedArray(view) = obj.borrow().kind() {
+        return Ok(view.to_vec());
+    }
+    let iterable = matches!(
+        obj.borrow().kind(),
+        ObjectKind::Array | ObjectKind::Set(_) | ObjectKind::Map(_) | ObjectKind::Iterator(_)
+    );
+
+    if iterable {
+        collect_builtin_iterable(source)?
+            .iter()
+            .map(|v| v.to_number())
+            .collect()
+    } else {
+        let length = to_index(
+            Some(&source.get(&PropertyKey::string("length"))?),
+            "Invalid typed array length",
+        )?;
+        (0..length)
+            .map(|i| source.get(&PropertyKey::Index(i as u32))?.to_number())
+            .collect()
+    }
+}
+
+/// Copy out the view backing `this`, or throw for other receivers.
+fn this_typed_array(this: &Value, method: &str) -> JsResult<TypedArrayData> {
+    if let Value::Object(obj) = this {
+        if let ObjectKind::TypedArray(view) = obj.borrow().kind() {
+            return Ok(view.clone());
+        }
+    }
+    Err(JsError::type_error(alloc::format!(
+        "TypedArray.prototype.{} called on incompatible receiver",
+        method
+    )))
+}
+
+fn typed_array_subarray(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let view = this_typed_array(this, "subarray")?;
+    let begin = relative_index(args.first(), view.length, 0)?;
+    let end = relative_index(args.get(1), view.length, view.length)?.max(begin);
+    let sub = TypedArrayData {
+        byte_offset: view.byte_offset + begin * view.kind.element_size(),
+        length: end - begin,
+        ..view
+    };
+    Ok(new_typed_array(this, sub))
+}
+
+fn typed_array_slice(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let view = this_typed_array(this, "slice")?;
+    let begin = relative_index(args.first(), view.length, 0)?;
+    let end = relative_index(args.get(1), view.length, view.length)?.max(begin);
+
+    let size = view.kind.element_size();
+    let start = view.byte_offset + begin * size;
+    let bytes = view
+        .buffer
+        .borrow()
+        .array_buffer_bytes()
+        .map(|b| b[start..view.byte_offset + end * size].to_vec())
+        .unwrap_or_default();
+
+    let copy = TypedArrayData {
+        kind: view.kind,
+        buffer: interp.new_array_buffer(bytes),
+        byte_offset: 0,
+        length: end - begin,
+    };
+    Ok(new_typed_array(this, copy))
+}
+
+fn typed_array_set(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let view = this_typed_array(this, "set")?;
+    let source = args.first().cloned().unwrap_or(Value::undefined());
+    // Read the whole source first: it may overlap the target.
+    let values = typed_array_source_values(&source)?;
+    let offset = to_index(args.get(1), "Offset is out of bounds")?;
+    if offset + values.len() > view.length {
+        return Err(JsError::range("Offset is out of bounds"));
+    }
+    for (i, value) in values.into_iter().enumerate() {
+        view.set(offset + i, value);
+    }
+    Ok(Value::undefined())
+}
+
+fn typed_array_keys(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    this_typed_array(this, "keys")?;
+    Ok(create_iterator(this.clone(), IterationKind::Keys))
+}
+
+fn typed_array_values(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    this_typed_array(this, "values")?;
+    Ok(create_iterator(this.clone(), IterationKind::Values))
+}
+
+fn typed_array_entries(this: &Value, _args: &[Value]) -> JsResult<Value> {
+    this_typed_array(this, "entries")?;
+    Ok(create_iterator(this.clone(), IterationKind::Entries))
+}
+
+fn init_data_view(interp: &mut Interpreter) {
+    let mut view = JsObject::function(Callable::Native(NativeFunction {
+        name: "DataView".into(),
+        length: 1,
+        func: data_view_constructor,
+    }));
+
+    let mut proto = JsObject::new();
+    define_method(&mut proto, "getInt8", 1, data_view_get_int8);
+    define_method(&mut proto, "getUint8", 1, data_view_get_uint8);
+    define_method(&mut proto, "getInt16", 1, data_view_get_int16);
+    define_method(&mut proto, "getUint16", 1, data_view_get_uint16);
+    define_method(&mut proto, "getInt32", 1, data_view_get_int32);
+    define_method(&mut proto, "getUint32", 1, data_view_get_uint32);
+    define_method(&mut proto, "getFloat32", 1, data_view_get_float32);
+    define_method(&mut proto, "getFloat64", 1, data_view_get_float64);
+    define_method(&mut proto, "setInt8", 2, data_view_set_int8);
+    define_method(&mut proto, "setUint8", 2, data_view_set_uint8);
+    define_method(&mut proto, "setInt16", 2, data_view_set_int16);
+    define_method(&mut proto, "setUint16", 2, data_view_set_uint16);
+    define_method(&mut proto, "setInt32", 2, data_view_set_int32);
+    define_method(&mut proto, "setUint32", 2, data_view_set_uint32);
+    define_method(&mut proto, "setFloat32", 2, data_view_set_float32);
+    define_method(&mut proto, "setFloat64", 2, data_view_set_float64);
+
+    view.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::object(proto), false, false, false),
+    );
+
+    interp.define_global("DataView", Value::object(view));
+}
+
+fn data_view_constructor(this: &Value, args: &[Value]) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => return Err(JsError::type_error("Constructor DataView requires 'new'")),
+    };
+    let buffer = as_array_buffer(args.first()).ok_or_else(|| {
+        JsError::type_error("First argument to DataView constructor must be an ArrayBuffer")
+    })?;
+
+    let buffer_len = buffer.borrow().array_buffer_bytes().map_or(0, |b| b.len());
+    let byte_offset = to_index(
+        args.get(1),
+        "Start offset is outside the bounds of the buffer",
+    )?;
+    if byte_offset > buffer_len {
+        return Err(JsError::range(
+            "Start offset is outside the bounds of the buffer",
+        ));
+    }
+    let byte_length = match args.get(2).filter(|v| !v.is_undefined()) {
+        Some(length) => {
+            let length = to_index(Some(length), "Invalid DataView length")?;
+            if byte_offset + length > buffer_len {
+                return Err(JsError::range("Invalid DataView length"));
+            }
+            length
+        }
+        None => buffer_len - byte_offset,
+    };
+
+    let mut obj = obj.borrow_mut();
+    define_read_only(&mut obj, "byteLength", Value::number(byte_length as f64));
+    define_read_only(&mut obj, "byteOffset", Value::number(byte_offset as f64));
+    define_read_only(&mut obj, "buffer", Value::Object(buffer.clone()));
+    obj.set_kind(ObjectKind::DataView(DataViewData {
+        buffer,
+        byte_offset,
+        byte_length,
+    }));
+    Ok(this.clone())
+}
+
+/// Copy out the `DataView` state backing `this`.
+fn this_data_view(this: &Value) -> JsResult<DataViewData> {
+    if let Value::Object(obj) = this {
+        if let ObjectKind::DataView(view) = obj.borrow().kind() {
+            return Ok(view.clone());
+        }
+    }
+    Err(JsError::type_error(
+        "DataView method called on incompatible receiver",
+    ))
+}
+
+/// `getXxx(byteOffset, littleEndian = false)`
+fn data_view_get(this: &Value, args: &[Value], kind: TypedArrayKind) -> JsResult<Value> {
+    let view = this_data_view(this)?;
+    let offset = to_index(args.first(), "Offset is outside the bounds of the DataView")?;
+    let little_endian = args.get(1).is_some_and(|v| v.to_boolean());
+    view.get(kind, offset, little_endian)
+        .map(Value::number)
+        .ok_or_else(|| JsError::range("Offset is outside the bounds of the DataView"))
+}
+
+/// `setXxx(byteOffset, value, littleEndian = false)`
+fn data_view_set(this: &Value, args: &[Value], kind: TypedArrayKind) -> JsResult<Value> {
+    let view = this_data_view(this)?;
+    let offset = to_index(args.first(), "Offset is outside the bounds of the DataView")?;
+    let value = args
+        .get(1)
+        .cloned()
+        .unwrap_or(Value::undefined())
+        .to_number()?;
+    let little_endian = args.get(2).is_some_and(|v| v.to_boolean());
+    if !view.set(kind, offset, value, little_endian) {
+        return Err(JsError::range(
+            "Offset is outside the bounds of the DataView",
+        ));
+    }
+    Ok(Value::undefined())
+}
+
+fn data_view_get_int8(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Int8)
+}
+
+fn data_view_get_uint8(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Uint8)
+}
+
+fn data_view_get_int16(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Int16)
+}
+
+fn data_view_get_uint16(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Uint16)
+}
+
+fn data_view_get_int32(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Int32)
+}
+
+fn data_view_get_uint32(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Uint32)
+}
+
+fn data_view_get_float32(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Float32)
+}
+
+fn data_view_get_float64(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_get(this, args, TypedArrayKind::Float64)
+}
+
+fn data_view_set_int8(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Int8)
+}
+
+fn data_view_set_uint8(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Uint8)
+}
+
+fn data_view_set_int16(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Int16)
+}
+
+fn data_view_set_uint16(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Uint16)
+}
+
+fn data_view_set_int32(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Int32)
+}
+
+fn data_view_set_uint32(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Uint32)
+}
+
+fn data_view_set_float32(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Float32)
+}
+
+fn data_view_set_float64(this: &Value, args: &[Value]) -> JsResult<Value> {
+    data_view_set(this, args, TypedArrayKind::Float64)
+}
+
+// Promise
+
+fn init_promise(interp: &mut Interpreter) {
+    let mut promise = JsObject::function(Callable::Intrinsic(IntrinsicFunction {
+        name: "Promise".into(),
+        length: 1,
+        func: promise_constructor,
+    }));
+    define_intrinsic(&mut promise, "resolve", 1, promise_resolve);
+    define_intrinsic(&mut promise, "reject", 1, promise_reject);
+
+    let mut proto = JsObject::new();
+    define_intrinsic(&mut proto, "then", 2, promise_then);
+    define_intrinsic(&mut proto, "catch", 1, promise_catch);
+    define_intrinsic(&mut proto, "finally", 1, promise_finally);
+    let proto = Rc::new(RefCell::new(proto));
+
+    promise.define_property(
+        PropertyKey::string("prototype"),
+        PropertyDescriptor::data(Value::Object(proto.clone()), false, false, false),
+    );
+
+    interp.set_promise_prototype(proto);
+    interp.define_global("Promise", Value::object(promise));
+}
+
+/// Create the `resolve`/`reject` pair that settles `promise`.
+///
+/// Only the first call to either function has any effect.
+pub(crate) fn create_resolving_functions(promise: &Rc<RefCell<JsObject>>) -> (Value, Value) {
+    let this = Value::Object(promise.clone());
+    (
+        bind_intrinsic(
+            "resolve",
+            1,
+            promise_resolve_function,
+            this.clone(),
+            Vec::new(),
+        ),
+        bind_intrinsic("reject", 1, promise_reject_function, this, Vec::new()),
+    )
+}
+
+/// Claim the right to resolve the promise bound to a resolving function.
+fn take_unresolved(this: &Value) -> Option<Rc<RefCell<JsObject>>> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => return None,
+    };
+    match obj.borrow_mut().kind_mut() {
+        ObjectKind::Promise(state) if !state.already_resolved => {
+            state.already_resolved = true;
+        }
+        _ => return None,
+    }
+    Some(obj.clone())
+}
+
+fn promise_resolve_function(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    if let Some(promise) = take_unresolved(this) {
+        let value = args.first().cloned().unwrap_or(Value::undefined());
+        interp.resolve_promise(&promise, value);
+    }
+    Ok(Value::undefined())
+}
+
+fn promise_reject_function(
+    interp: &mut Interpreter,
+    this: &Value,
+    args: &[Value],
+) -> JsResult<Value> {
+    if let Some(promise) = take_unresolved(this) {
+        let reason = args.first().cloned().unwrap_or(Value::undefined());
+        interp.reject_promise(&promise, reason);
+    }
+    Ok(Value::undefined())
+}
+
+/// Create the pair of handlers that resume `frame` once the promise it
+/// awaits settles.
+///
+/// Only the first call to either function has any effect.
+pub(crate) fn create_await_functions(frame: Box<AsyncFrame>) -> (Value, Value) {
+    let mut suspended = JsObject::new();
+    suspended.set_kind(ObjectKind::Suspended(Some(frame)));
+    let this = Value::object(suspended);
+    (
+        bind_intrinsic("", 1, await_fulfilled, this.clone(), Vec::new()),
+        bind_intrinsic("", 1, await_rejected, this, Vec::new()),
+    )
+}
+
+/// Take the async function call bound to an await handler.
+fn take_suspended(this: &Value) -> Option<Box<AsyncFrame>> {
+    match this {
+        Value::Object(obj) => match obj.borrow_mut().kind_mut() {
+            ObjectKind::Suspended(frame) => frame.take(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn await_fulfilled(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    if let Some(frame) = take_suspended(this) {
+        let value = args.first().cloned().unwrap_or(Value::undefined());
+        interp.resume_async(frame, Ok(value));
+    }
+    Ok(Value::undefined())
+}
+
+fn await_rejected(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    if let Some(frame) = take_suspended(this) {
+        let reason = args.first().cloned().unwrap_or(Value::undefined());
+        interp.resume_async(frame, Err(reason));
+    }
+    Ok(Value::undefined())
+}
+
+fn promise_constructor(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let obj = match this {
+        Value::Object(obj) => obj,
+        _ => return Err(JsError::type_error("Constructor Promise requires 'new'")),
+    };
+
+    let executor = args.first().cloned().unwrap_or(Value::undefined());
+    if !executor.is_function() {
+        return Err(JsError::type_error("Promise resolver is not a function"));
+    }
+
+    obj.borrow_mut()
+        .set_kind(ObjectKind::Promise(PromiseState::new()));
+    let (resolve, reject) = create_resolving_functions(obj);
+    if let Err(e) = interp.call_function(&executor, &Value::undefined(), &[resolve, reject.clone()])
+    {
+        let reason = interp.error_to_value(&e);
+        interp.call_function(&reject, &Value::undefined(), &[reason])?;
+    }
+    Ok(this.clone())
+}
+
+fn promise_resolve(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    let value = args.first().cloned().unwrap_or(Value::undefined());
+    Ok(Value::Object(interp.promise_resolve(value)))
+}
+
+fn promise_reject(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    let reason = args.first().cloned().unwrap_or(Value::undefined());
+    let promise = interp.new_promise();
+    interp.reject_promise(&promise, reason);
+    Ok(Value::Object(promise))
+}
+
+/// Register handlers on the promise `this`, returning the derived promise.
+fn then_with(
+    interp: &mut Interpreter,
+    this: &Value,
+    method: &str,
+    on_fulfilled: Value,
+    on_rejected: Value,
+) -> JsResult<Value> {
+    let promise = match this {
+        Value::Object(obj) if matches!(obj.borrow().kind(), ObjectKind::Promise(_)) => obj,
+        _ => {
+            return Err(JsError::type_error(alloc::format!(
+                "Method Promise.prototype.{} called on incompatible receiver",
+                method
+            )))
+        }
+    };
+
+    let derived = interp.new_promise();
+    interp.promise_then(
+        promise,
+        PromiseReaction {
+            on_fulfilled,
+            on_rejected,
+            derived: Some(derived.clone()),
+        },
+    );
+    Ok(Value::Object(derived))
+}
+
+fn promise_then(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let on_fulfilled = args.first().cloned().unwrap_or(Value::undefined());
+    let on_rejected = args.get(1).cloned().unwrap_or(Value::undefined());
+    then_with(interp, this, "then", on_fulfilled, on_rejected)
+}
+
+fn promise_catch(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let on_rejected = args.first().cloned().unwrap_or(Value::undefined());
+    then_with(interp, this, "catch", Value::undefined(), on_rejected)
+}
+
+fn promise_finally(interp: &mut Interpreter, this: &Value, args: &[Value]) -> JsResult<Value> {
+    let on_finally = args.first().cloned().unwrap_or(Value::undefined());
+    if !on_finally.is_function() {
+        return then_with(interp, this, "finally", on_finally.clone(), on_finally);
+    }
+
+    let on_fulfilled = bind_intrinsic(
+        "",
+        1,
+        finally_fulfilled,
+        Value::undefined(),
+        vec![on_finally.clone()],
+    );
+    let on_rejected = bind_intrinsic(
+        "",
+        1,
+        finally_rejected,
+        Value::undefined(),
+        vec![on_finally],
+    );
+    then_with(interp, this, "finally", on_fulfilled, on_rejected)
+}
+
+/// `finally` handler for fulfillment: run the callback, keep the value.
+fn finally_fulfilled(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    let on_finally = args.first().cloned().unwrap_or(Value::undefined());
+    interp.call_function(&on_finally, &Value::undefined(), &[])?;
+    Ok(args.get(1).cloned().unwrap_or(Value::undefined()))
+}
+
+/// `finally` handler for rejection: run the callback, keep the reason.
+fn finally_rejected(interp: &mut Interpreter, _this: &Value, args: &[Value]) -> JsResult<Value> {
+    let on_finally = args.first().cloned().unwrap_or(Value::undefined());
+    interp.call_function(&on_finally, &Value::undefined(), &[])?;
+    let reason = args.get(1).cloned().unwrap_or(Value::undefined());
+    let promise = interp.new_promise();
+    interp.reject_promise(&promise, reason);
+    Ok(Value::Object(promise))
 }
@@ -0,0 +1,70 @@
+//! Console output.
+//!
+//! Messages logged through the `console` object, and exceptions that
+//! escape a script, are buffered on the engine until the host takes them
+//! with [`Interpreter::take_console_messages`](crate::interpreter::Interpreter::take_console_messages).
+
+use alloc::string::String;
+use core::fmt;
+
+/// Maximum number of buffered messages; the oldest are dropped first.
+pub const MAX_CONSOLE_MESSAGES: usize = 1000;
+
+/// Console message severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConsoleLevel {
+    /// `console.debug`
+    Debug,
+    /// `console.log`
+    Log,
+    /// `console.info`
+    Info,
+    /// `console.warn`
+    Warn,
+    /// `console.error` and uncaught exceptions.
+    Error,
+}
+
+impl ConsoleLevel {
+    /// Get level name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConsoleLevel::Debug => "debug",
+            ConsoleLevel::Log => "log",
+            ConsoleLevel::Info => "info",
+            ConsoleLevel::Warn => "warn",
+            ConsoleLevel::Error => "error",
+        }
+    }
+}
+
+/// A console message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsoleMessage {
+    /// Severity.
+    pub level: ConsoleLevel,
+    /// Message text.
+    pub text: String,
+    /// Name of the script that logged it, as given to `eval_script`.
+    pub source: String,
+    /// Line of the statement that logged it (1-based).
+    pub line: usize,
+    /// Column of the statement that logged it (1-based).
+    pub column: usize,
+    /// Whether this reports an exception no script caught.
+    pub uncaught: bool,
+}
+
+impl fmt::Display for ConsoleMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} ({}:{}:{})",
+            self.level.name(),
+            self.text,
+            self.source,
+            self.line,
+            self.column
+        )
+    }
+}
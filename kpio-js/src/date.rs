@@ -0,0 +1,519 @@
+//! Date and time arithmetic.
+//!
+//! A time value is a number of milliseconds since the Unix epoch in UTC,
+//! as in ECMAScript; NaN is an invalid date. Times are clipped to ±8.64e15
+//! ms (about ±273,790 years) around the epoch.
+//!
+//! The engine has no time source of its own: the host installs a [`Clock`]
+//! with [`Interpreter::set_clock`](crate::interpreter::Interpreter::set_clock),
+//! normally reading the kernel's RTC-anchored wall clock. Until then
+//! `Date.now()` is the epoch.
+//!
+//! Local time is UTC for now. Every local-time conversion goes through
+//! [`local_time`] and [`utc`], which apply [`LOCAL_TZA`].
+
+use alloc::format;
+use alloc::string::String;
+use libm::{floor, trunc};
+
+/// Source of the current time, in milliseconds since the Unix epoch.
+pub type Clock = fn() -> f64;
+
+/// Milliseconds per second.
+pub const MS_PER_SECOND: f64 = 1_000.0;
+/// Milliseconds per minute.
+pub const MS_PER_MINUTE: f64 = 60_000.0;
+/// Milliseconds per hour.
+pub const MS_PER_HOUR: f64 = 3_600_000.0;
+/// Milliseconds per day.
+pub const MS_PER_DAY: f64 = 86_400_000.0;
+
+/// Largest distance from the epoch a time value may have.
+pub const MAX_TIME: f64 = 8.64e15;
+
+/// Offset of local time from UTC in milliseconds.
+pub const LOCAL_TZA: f64 = 0.0;
+
+const WEEKDAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The default clock: always the epoch.
+pub fn epoch_clock() -> f64 {
+    0.0
+}
+
+/// Convert a UTC time value to local time.
+pub fn local_time(t: f64) -> f64 {
+    t + LOCAL_TZA
+}
+
+/// Convert a local time value to UTC.
+pub fn utc(t: f64) -> f64 {
+    t - LOCAL_TZA
+}
+
+/// Reduce a time value to a valid one (TimeClip): NaN when it is not
+/// finite or out of range, otherwise truncated to whole milliseconds.
+pub fn time_clip(t: f64) -> f64 {
+    if !t.is_finite() || t.abs() > MAX_TIME {
+        return f64::NAN;
+    }
+    trunc(t) + 0.0
+}
+
+/// Build a time of day from its components (MakeTime).
+pub fn make_time(hour: f64, min: f64, sec: f64, ms: f64) -> f64 {
+    if !(hour.is_finite() && min.is_finite() && sec.is_finite() && ms.is_finite()) {
+        return f64::NAN;
+    }
+    trunc(hour) * MS_PER_HOUR + trunc(min) * MS_PER_MINUTE + trunc(sec) * MS_PER_SECOND + trunc(ms)
+}
+
+/// Count the days from the epoch to the given date (MakeDay). `month` is
+/// 0-based and may be out of range; it carries into the year.
+pub fn make_day(year: f64, month: f64, date: f64) -> f64 {
+    if !(year.is_finite() && month.is_finite() && date.is_finite()) {
+        return f64::NAN;
+    }
+    let (year, month, date) = (trunc(year), trunc(month), trunc(date));
+    let ym = year + floor(month / 12.0);
+    // Anything this far out is clipped later; it must not overflow first.
+    if ym.abs() > 400_000.0 {
+        return f64::NAN;
+    }
+    let mn = month - floor(month / 12.0) * 12.0;
+    days_from_civil(ym as i64, mn as u32 + 1, 1) as f64 + date - 1.0
+}
+
+/// Combine a day number and a time of day (MakeDate).
+pub fn make_date(day: f64, time: f64) -> f64 {
+    if !(day.is_finite() && time.is_finite()) {
+        return f64::NAN;
+    }
+    day * MS_PER_DAY + time
+}
+
+/// Days from the epoch to a proleptic Gregorian date (1-based month/day).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian date (year, 1-based month, day) of a day number.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The calendar fields of a valid time value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateFields {
+    pub year: i64,
+    /// Month, 0-based.
+    pub month: u32,
+    /// Day of the month, 1-based.
+    pub date: u32,
+    /// Day of the week, 0 for Sunday.
+    pub weekday: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub milliseconds: u32,
+}
+
+impl DateFields {
+    /// Split a time value into calendar fields, or `None` if it is NaN.
+    pub fn from_time(t: f64) -> Option<Self> {
+        if !t.is_finite() {
+            return None;
+        }
+        let day = floor(t / MS_PER_DAY);
+        let ms_in_day = (t - day * MS_PER_DAY) as u64;
+        let day = day as i64;
+        let (year, month, date) = civil_from_days(day);
+        Some(DateFields {
+            year,
+            month: month - 1,
+            date,
+            weekday: (day + 4).rem_euclid(7) as u32,
+            hours: (ms_in_day / 3_600_000) as u32,
+            minutes: (ms_in_day / 60_000 % 60) as u32,
+            seconds: (ms_in_day / 1_000 % 60) as u32,
+            milliseconds: (ms_in_day % 1_000) as u32,
+        })
+    }
+}
+
+/// Format a year with at least four digits, as the string methods do.
+fn format_year(year: i64) -> String {
+    if year < 0 {
+        format!("-{:04}", -year)
+    } else {
+        format!("{:04}", year)
+    }
+}
+
+/// `Date.prototype.toISOString` of a valid time value.
+pub fn to_iso_string(t: f64) -> Option<String> {
+    let f = DateFields::from_time(t)?;
+    let year = if (0..=9999).contains(&f.year) {
+        format!("{:04}", f.year)
+    } else if f.year < 0 {
+        format!("-{:06}", -f.year)
+    } else {
+        format!("+{:06}", f.year)
+    };
+    Some(format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        f.month + 1,
+        f.date,
+        f.hours,
+        f.minutes,
+        f.seconds,
+        f.milliseconds
+    ))
+}
+
+/// The date part of `Date.prototype.toString`, e.g. `Fri Oct 16 2026`.
+pub fn to_date_string(t: f64) -> String {
+    match DateFields::from_time(local_time(t)) {
+        Some(f) => format!(
+            "{} {} {:02} {}",
+            WEEKDAY_NAMES[f.weekday as usize],
+            MONTH_NAMES[f.month as usize],
+            f.date,
+            format_year(f.year)
+        ),
+        None => String::from("Invalid Date"),
+    }
+}
+
+/// The time part of `Date.prototype.toString`, e.g.
+/// `12:30:00 GMT+0000 (Coordinated Universal Time)`.
+pub fn to_time_string(t: f64) -> String {
+    match DateFields::from_time(local_time(t)) {
+        Some(f) => {
+            let offset = (LOCAL_TZA / MS_PER_MINUTE) as i64;
+            let sign = if offset < 0 { '-' } else { '+' };
+            let zone = if offset == 0 {
+                " (Coordinated Universal Time)"
+            } else {
+                ""
+            };
+            format!(
+                "{:02}:{:02}:{:02} GMT{}{:02}{:02}{}",
+                f.hours,
+                f.minutes,
+                f.seconds,
+                sign,
+                offset.abs() / 60,
+                offset.abs() % 60,
+                zone
+            )
+        }
+        None => String::from("Invalid Date"),
+    }
+}
+
+/// `Date.prototype.toString`.
+pub fn to_string(t: f64) -> String {
+    if t.is_nan() {
+        return String::from("Invalid Date");
+    }
+    format!("{} {}", to_date_string(t), to_time_string(t))
+}
+
+/// `Date.prototype.toUTCString`, e.g. `Fri, 16 Oct 2026 12:30:00 GMT`.
+pub fn to_utc_string(t: f64) -> String {
+    match DateFields::from_time(t) {
+        Some(f) => format!(
+            "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+            WEEKDAY_NAMES[f.weekday as usize],
+            f.date,
+            MONTH_NAMES[f.month as usize],
+            format_year(f.year),
+            f.hours,
+            f.minutes,
+            f.seconds
+        ),
+        None => String::from("Invalid Date"),
+    }
+}
+
+/// Parse a date string (`Date.parse`).
+///
+/// Accepts the ISO 8601 subset of ECMA-262 (`2026-10-16T12:30:00.000Z`
+/// and its shorter forms) and the formats produced by `toString` and
+/// `toUTCString`. Date-only ISO forms are UTC, date-time forms without
+/// an offset are local time. Anything else is NaN.
+pub fn parse(s: &str) -> f64 {
+    let s = s.trim();
+    parse_iso(s)
+        .or_else(|| parse_fallback(s))
+        .unwrap_or(f64::NAN)
+}
+
+/// A cursor over the bytes of a date string.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(s: &'a str) -> Self {
+        Scanner {
+            bytes: s.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Exactly `count` decimal digits.
+    fn digits(&mut self, count: usize) -> Option<f64> {
+        let end = self.pos + count;
+        let digits = self.bytes.get(self.pos..end)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos = end;
+        Some(
+            digits
+                .iter()
+                .fold(0.0, |n, &d| n * 10.0 + f64::from(d - b'0')),
+        )
+    }
+
+    /// One or more decimal digits.
+    fn number(&mut self) -> Option<f64> {
+        let count = self.bytes[self.pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if count == 0 {
+            return None;
+        }
+        self.digits(count)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(|b| b == b' ' || b == b',') {
+            self.pos += 1;
+        }
+    }
+
+    /// A run of ASCII letters.
+    fn word(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        &self.bytes[start..self.pos]
+    }
+
+    /// `hh:mm[:ss[.sss]]`, as milliseconds into the day.
+    fn time(&mut self) -> Option<f64> {
+        let hour = self.digits(2)?;
+        if !self.eat(b':') {
+            return None;
+        }
+        let min = self.digits(2)?;
+        let (mut sec, mut ms) = (0.0, 0.0);
+        if self.eat(b':') {
+            sec = self.digits(2)?;
+            if self.eat(b'.') || self.eat(b',') {
+                let start = self.pos;
+                let frac = self.number()?;
+                let len = (self.pos - start) as i32;
+                ms = floor(frac * libm::pow(10.0, f64::from(3 - len)));
+            }
+        }
+        let valid = hour < 24.0 || (hour == 24.0 && min == 0.0 && sec == 0.0 && ms == 0.0);
+        if !valid || min > 59.0 || sec > 59.0 {
+            return None;
+        }
+        Some(make_time(hour, min, sec, ms))
+    }
+
+    /// `Z` or `±hh:mm` (`±hhmm` outside ISO strings), as milliseconds
+    /// to subtract from local time.
+    fn offset(&mut self, colon: bool) -> Option<f64> {
+        if self.eat(b'Z') {
+            return Some(0.0);
+        }
+        let sign = if self.eat(b'+') {
+            1.0
+        } else if self.eat(b'-') {
+            -1.0
+        } else {
+            return None;
+        };
+        let hours = self.digits(2)?;
+        if colon && !self.eat(b':') {
+            return None;
+        }
+        if !colon {
+            self.eat(b':');
+        }
+        let minutes = self.digits(2)?;
+        Some(sign * (hours * MS_PER_HOUR + minutes * MS_PER_MINUTE))
+    }
+}
+
+/// `YYYY[-MM[-DD]][THH:mm[:ss[.sss]][Z|±HH:mm]]`, with `±YYYYYY` years.
+fn parse_iso(s: &str) -> Option<f64> {
+    let mut scan = Scanner::new(s);
+    let year = match scan.peek()? {
+        b'+' | b'-' => {
+            let negative = scan.eat(b'-');
+            if !negative {
+                scan.eat(b'+');
+            }
+            let year = scan.digits(6)?;
+            if negative && year == 0.0 {
+                return None;
+            }
+            if negative {
+                -year
+            } else {
+                year
+            }
+        }
+        _ => scan.digits(4)?,
+    };
+    let mut month = 1.0;
+    let mut day = 1.0;
+    if scan.eat(b'-') {
+        month = scan.digits(2)?;
+        if scan.eat(b'-') {
+            day = scan.digits(2)?;
+        }
+    }
+    if !(1.0..=12.0).contains(&month) || day < 1.0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let date = make_day(year, month - 1.0, day);
+    if scan.at_end() {
+        return Some(time_clip(make_date(date, 0.0)));
+    }
+    if !scan.eat(b'T') && !scan.eat(b't') {
+        return None;
+    }
+    let time = scan.time()?;
+    let t = make_date(date, time);
+    let t = if scan.at_end() {
+        utc(t)
+    } else {
+        t - scan.offset(true)?
+    };
+    if !scan.at_end() {
+        return None;
+    }
+    Some(time_clip(t))
+}
+
+/// The formats of `toString` and `toUTCString`, and loose variations:
+/// an optional weekday, the month name before or after the day, the year,
+/// an optional time and an optional `GMT`/`UTC` zone with an offset.
+fn parse_fallback(s: &str) -> Option<f64> {
+    let mut scan = Scanner::new(s);
+    let (mut month, mut day, mut year) = (None, None, None);
+    let mut time = None;
+    let mut offset = None;
+
+    loop {
+        scan.skip_spaces();
+        let Some(byte) = scan.peek() else {
+            break;
+        };
+        if byte.is_ascii_alphabetic() {
+            let word = scan.word();
+            if word.eq_ignore_ascii_case(b"GMT")
+                || word.eq_ignore_ascii_case(b"UTC")
+                || word.eq_ignore_ascii_case(b"Z")
+            {
+                offset = Some(scan.offset(false).unwrap_or(0.0));
+            } else if let Some(m) = MONTH_NAMES
+                .iter()
+                .position(|name| word.len() >= 3 && word[..3].eq_ignore_ascii_case(name.as_bytes()))
+            {
+                month = Some(m as f64);
+            } else if !WEEKDAY_NAMES
+                .iter()
+                .any(|name| word.len() >= 3 && word[..3].eq_ignore_ascii_case(name.as_bytes()))
+            {
+                return None;
+            }
+        } else if byte == b'(' {
+            // Time zone name, e.g. "(Coordinated Universal Time)"
+            while !scan.at_end() && !scan.eat(b')') {
+                scan.pos += 1;
+            }
+        } else if byte == b'+' || byte == b'-' {
+            offset = Some(scan.offset(false)?);
+        } else if byte.is_ascii_digit() {
+            let start = scan.pos;
+            let n = scan.number()?;
+            if scan.peek() == Some(b':') {
+                scan.pos = start;
+                time = Some(scan.time()?);
+            } else if day.is_none() && scan.pos - start <= 2 {
+                day = Some(n);
+            } else if year.is_none() {
+                year = Some(n);
+            } else {
+                return None;
+            }
+        } else {
+            return None;
+        }
+    }
+
+    let (month, day, year) = (month?, day?, year?);
+    if day < 1.0 || day > days_in_month(year, month + 1.0) {
+        return None;
+    }
+    let t = make_date(make_day(year, month, day), time.unwrap_or(0.0));
+    let t = match offset {
+        Some(offset) => t - offset,
+        None => utc(t),
+    };
+    Some(time_clip(t))
+}
+
+/// Days in a 1-based month of a year.
+fn days_in_month(year: f64, month: f64) -> f64 {
+    make_day(year, month, 1.0) - make_day(year, month - 1.0, 1.0)
+}
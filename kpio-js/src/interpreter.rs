@@ -2,6 +2,8 @@
 //!
 //! Tree-walking interpreter for JavaScript AST.
 
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
@@ -12,11 +14,15 @@ use libm::trunc;
 
 use crate::ast::*;
 use crate::builtin;
+use crate::console::{ConsoleLevel, ConsoleMessage, MAX_CONSOLE_MESSAGES};
+use crate::date::{self, Clock};
 use crate::error::{JsError, JsResult};
 use crate::object::{
-    Callable, Environment, JsObject, NativeFunction, PropertyDescriptor, PropertyKey, UserFunction,
+    Callable, Environment, IterationKind, JsObject, NativeFunction, PromiseReaction, PromiseState,
+    PromiseStatus, PropertyDescriptor, PropertyKey, UserFunction,
 };
-use crate::value::{Completion, Value};
+use crate::token::Span;
+use crate::value::{Completion, Symbol, Value};
 
 /// JavaScript interpreter.
 pub struct Interpreter {
@@ -30,6 +36,154 @@ pub struct Interpreter {
     call_depth: usize,
     /// Maximum call stack depth.
     max_call_depth: usize,
+    /// Pending promise jobs, run in FIFO order.
+    microtasks: VecDeque<Microtask>,
+    /// Async function call being run, if any.
+    async_frame: Option<Box<AsyncFrame>>,
+    /// `Promise.prototype`, linked to promises created internally.
+    promise_prototype: Option<Rc<RefCell<JsObject>>>,
+    /// `ArrayBuffer.prototype`, linked to buffers created internally.
+    array_buffer_prototype: Option<Rc<RefCell<JsObject>>>,
+    /// `Error.prototype` and the native error prototypes, by error name.
+    error_prototypes: Vec<(&'static str, Rc<RefCell<JsObject>>)>,
+    /// Strings arrays of tagged templates, by call site.
+    template_objects: BTreeMap<u64, Rc<RefCell<JsObject>>>,
+    /// Buffered console messages, oldest first.
+    console: VecDeque<ConsoleMessage>,
+    /// Name of the script being run.
+    script_name: String,
+    /// Position of the statement being run, or of the one that threw.
+    location: Span,
+    /// Wall-clock time source for `Date`.
+    clock: Clock,
+}
+
+/// A job on the microtask queue.
+enum Microtask {
+    /// Run a `then` handler with the settled value of its promise.
+    Reaction {
+        reaction: PromiseReaction,
+        argument: Value,
+        rejected: bool,
+    },
+    /// Make `promise` follow a thenable passed to its resolve function.
+    ResolveThenable {
+        promise: Rc<RefCell<JsObject>>,
+        thenable: Value,
+        then: Value,
+    },
+}
+
+/// An async function call, kept while it is suspended at `await`.
+///
+/// Suspending unwinds the call: each statement being run saves where it
+/// stopped, and the statement's expressions keep the results of the
+/// subexpressions they finished.  Resuming runs the body again, going
+/// straight back to the `await` without repeating any of them.
+#[derive(Clone, Debug)]
+pub struct AsyncFrame {
+    /// Function body.
+    body: Rc<BlockStmt>,
+    /// Environment of the call, holding its parameters.
+    env: Rc<RefCell<Environment>>,
+    /// Promise returned to the caller.
+    promise: Rc<RefCell<JsObject>>,
+    /// State saved by the unwound statements, innermost first.
+    resume: Vec<Resume>,
+    /// Results of the subexpressions finished by the running statement,
+    /// keyed by outermost expression and evaluation order, with the
+    /// position to continue from.
+    results: BTreeMap<(usize, usize), (JsResult<Value>, usize)>,
+    /// Address of the outermost expression being evaluated.
+    root: usize,
+    /// Subexpressions of `root` evaluated so far.
+    evaluated: usize,
+    /// Nesting of the expressions being evaluated.
+    depth: usize,
+    /// Promise awaited, set while the call unwinds.
+    awaited: Option<Rc<RefCell<JsObject>>>,
+    /// Key of the pending `await`, and the position after it.
+    awaiting: Option<((usize, usize), usize)>,
+}
+
+impl AsyncFrame {
+    fn new(body: BlockStmt, env: Rc<RefCell<Environment>>, promise: Rc<RefCell<JsObject>>) -> Self {
+        AsyncFrame {
+            body: Rc::new(body),
+            env,
+            promise,
+            resume: Vec::new(),
+            results: BTreeMap::new(),
+            root: 0,
+            evaluated: 0,
+            depth: 0,
+            awaited: None,
+            awaiting: None,
+        }
+    }
+}
+
+/// Where a statement stopped when its async function suspended.
+#[derive(Clone, Debug)]
+enum Resume {
+    /// Block, at its `index`th statement.
+    Block {
+        env: Rc<RefCell<Environment>>,
+        index: usize,
+    },
+    /// Variable declaration, at its `n`th declarator.
+    Declaration(usize),
+    /// `if` statement, in its consequent (`true`) or alternate.
+    If(bool),
+    /// `for` loop.
+    For {
+        env: Rc<RefCell<Environment>>,
+        stage: LoopStage,
+    },
+    /// `for-in` loop, in the body for `keys[index]`.
+    ForIn {
+        env: Rc<RefCell<Environment>>,
+        keys: Vec<PropertyKey>,
+        index: usize,
+    },
+    /// `for-of` loop, in the body for the current value.
+    ForOf {
+        env: Rc<RefCell<Environment>>,
+        iterator: Value,
+    },
+    /// `while` or `do-while` loop.
+    Loop(LoopStage),
+    /// `switch` statement.
+    Switch(SwitchState),
+    /// `try` block.
+    Try,
+    /// `catch` block, with the environment holding its parameter.
+    Catch(Rc<RefCell<Environment>>),
+    /// `finally` block, with the outcome of the rest of the statement.
+    Finally(Box<JsResult<Completion>>),
+}
+
+/// Part of a loop being run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LoopStage {
+    Init,
+    Test,
+    Body,
+    Update,
+}
+
+/// Progress through a `switch` statement.
+#[derive(Clone, Debug)]
+struct SwitchState {
+    discriminant: Value,
+    /// Case being tested or run.
+    case: usize,
+    /// Statement of the case being run.
+    statement: usize,
+    /// Whether a case has matched, so later cases fall through.
+    matched: bool,
+    /// Whether no case matched and the default case is being run.
+    default: bool,
 }
 
 impl Interpreter {
@@ -44,6 +198,16 @@ impl Interpreter {
             global_object,
             call_depth: 0,
             max_call_depth: 1000,
+            microtasks: VecDeque::new(),
+            async_frame: None,
+            promise_prototype: None,
+            array_buffer_prototype: None,
+            error_prototypes: Vec::new(),
+            template_objects: BTreeMap::new(),
+            console: VecDeque::new(),
+            script_name: String::new(),
+            location: Span::default(),
+            clock: date::epoch_clock,
         };
 
         // Initialize built-in objects
@@ -93,8 +257,15 @@ impl Interpreter {
         self.define_global(name, value);
     }
 
-    /// Execute a program.
+    /// Execute a program, then run any microtasks it queued.
     pub fn execute(&mut self, program: &Program) -> JsResult<Value> {
+        let result = self.execute_program(program);
+        self.run_microtasks();
+        result
+    }
+
+    /// Execute the statements of a program.
+    fn execute_program(&mut self, program: &Program) -> JsResult<Value> {
         let mut last_value = Value::undefined();
 
         for statement in &program.body {
@@ -116,8 +287,32 @@ impl Interpreter {
         Ok(last_value)
     }
 
-    /// Execute a statement.
+    /// Execute a statement, tracking its position for console messages.
+    /// The position of a statement that throws is kept for the error.
     fn execute_statement(&mut self, stmt: &Statement) -> JsResult<Completion> {
+        let span = match stmt {
+            Statement::Expression(expr) => expr.span,
+            Statement::Variable(decl) => decl.span,
+            Statement::Return(ret) => ret.span,
+            Statement::Throw(throw) => throw.span,
+            _ => {
+                let result = self.run_statement(stmt);
+                self.finish_statement();
+                return result;
+            }
+        };
+
+        let outer = core::mem::replace(&mut self.location, span);
+        let result = self.run_statement(stmt);
+        if !matches!(result, Err(_) | Ok(Completion::Throw(_))) {
+            self.location = outer;
+        }
+        self.finish_statement();
+        result
+    }
+
+    /// Execute a statement.
+    fn run_statement(&mut self, stmt: &Statement) -> JsResult<Completion> {
         match stmt {
             Statement::Empty(_) => Ok(Completion::empty()),
             Statement::Expression(expr) => {
@@ -168,12 +363,21 @@ impl Interpreter {
     /// Execute a block.
     fn execute_block(&mut self, block: &BlockStmt) -> JsResult<Completion> {
         let outer = self.current_env.clone();
-        self.current_env = Rc::new(RefCell::new(Environment::child(outer.clone())));
+        let (env, start) = match self.take_resume() {
+            Some(Resume::Block { env, index }) => (env, index),
+            _ => (Rc::new(RefCell::new(Environment::child(outer.clone()))), 0),
+        };
+        self.current_env = env.clone();
 
         let mut result = Completion::empty();
 
-        for stmt in &block.body {
-            result = self.execute_statement(stmt)?;
+        for (index, stmt) in block.body.iter().enumerate().skip(start) {
+            let completion = self.execute_statement(stmt);
+            if self.suspending() {
+                self.save_resume(Resume::Block { env, index });
+                return completion;
+            }
+            result = completion?;
             if !result.is_normal() {
                 break;
             }
@@ -186,10 +390,18 @@ impl Interpreter {
     /// Execute variable declaration.
     fn execute_variable_declaration(&mut self, decl: &VariableDecl) -> JsResult<Completion> {
         let is_const = matches!(decl.kind, VariableKind::Const);
+        let start = match self.take_resume() {
+            Some(Resume::Declaration(index)) => index,
+            _ => 0,
+        };
 
-        for declarator in &decl.declarations {
+        for (index, declarator) in decl.declarations.iter().enumerate().skip(start) {
             let value = if let Some(init) = &declarator.init {
-                self.evaluate(init)?
+                let value = self.evaluate(init);
+                if self.suspending() {
+                    self.save_resume(Resume::Declaration(index));
+                }
+                value?
             } else {
                 if is_const {
                     return Err(JsError::syntax("Missing initializer in const declaration"));
@@ -213,11 +425,19 @@ impl Interpreter {
                 self.current_env.borrow_mut().initialize(&id.name, value)?;
             }
             Pattern::Array(arr) => {
-                let obj = value.to_object()?;
+                let items = self.iterate_to_vec(&value)?;
                 for (i, elem) in arr.elements.iter().enumerate() {
-                    if let Some(p) = elem {
-                        let v = obj.borrow().get(&PropertyKey::Index(i as u32))?;
-                        self.bind_pattern(p, v, mutable)?;
+                    match elem {
+                        Some(Pattern::Rest(rest)) => {
+                            let remaining = items.iter().skip(i).cloned().map(Some).collect();
+                            let rest_value = Value::object(JsObject::array(remaining));
+                            self.bind_pattern(&rest.argument, rest_value, mutable)?;
+                        }
+                        Some(p) => {
+                            let v = items.get(i).cloned().unwrap_or(Value::undefined());
+                            self.bind_pattern(p, v, mutable)?;
+                        }
+                        None => {}
                     }
                 }
             }
@@ -229,7 +449,7 @@ impl Interpreter {
                             key, value: pat, ..
                         } => {
                             let key = self.property_key_from_expr(key)?;
-                            let v = val_obj.borrow().get(&key)?;
+                            let v = self.get_property(&Value::Object(val_obj.clone()), &key)?;
                             self.bind_pattern(pat, v, mutable)?;
                         }
                         ObjectPatternProperty::Rest(rest) => {
@@ -252,101 +472,149 @@ impl Interpreter {
 
     /// Execute if statement.
     fn execute_if(&mut self, if_stmt: &IfStmt) -> JsResult<Completion> {
-        let test = self.evaluate(&if_stmt.test)?;
+        let consequent = match self.take_resume() {
+            Some(Resume::If(consequent)) => consequent,
+            _ => self.evaluate(&if_stmt.test)?.to_boolean(),
+        };
 
-        if test.to_boolean() {
+        let result = if consequent {
             self.execute_statement(&if_stmt.consequent)
         } else if let Some(alt) = &if_stmt.alternate {
             self.execute_statement(alt)
         } else {
             Ok(Completion::empty())
+        };
+        if self.suspending() {
+            self.save_resume(Resume::If(consequent));
         }
+        result
     }
 
     /// Execute for loop.
     fn execute_for(&mut self, for_stmt: &ForStmt) -> JsResult<Completion> {
         let outer = self.current_env.clone();
-        self.current_env = Rc::new(RefCell::new(Environment::child(outer.clone())));
+        let (env, mut stage) = match self.take_resume() {
+            Some(Resume::For { env, stage }) => (env, stage),
+            _ => (
+                Rc::new(RefCell::new(Environment::child(outer.clone()))),
+                LoopStage::Init,
+            ),
+        };
+        self.current_env = env.clone();
+
+        let result = self.run_for(for_stmt, &mut stage);
+        if self.suspending() {
+            self.save_resume(Resume::For { env, stage });
+        }
 
+        self.current_env = outer;
+        result
+    }
+
+    /// Run a for loop from `stage`, which is kept at the part running.
+    fn run_for(&mut self, for_stmt: &ForStmt, stage: &mut LoopStage) -> JsResult<Completion> {
         // Init
-        if let Some(init) = &for_stmt.init {
-            match init {
-                ForInit::Variable(decl) => {
-                    self.execute_variable_declaration(decl)?;
-                }
-                ForInit::Expression(expr) => {
-                    self.evaluate(expr)?;
+        if *stage == LoopStage::Init {
+            if let Some(init) = &for_stmt.init {
+                match init {
+                    ForInit::Variable(decl) => {
+                        self.execute_variable_declaration(decl)?;
+                    }
+                    ForInit::Expression(expr) => {
+                        self.evaluate(expr)?;
+                    }
                 }
             }
+            *stage = LoopStage::Test;
         }
 
         let mut result = Completion::empty();
 
         loop {
             // Test
-            if let Some(test) = &for_stmt.test {
-                let cond = self.evaluate(test)?;
-                if !cond.to_boolean() {
-                    break;
+            if *stage == LoopStage::Test {
+                if let Some(test) = &for_stmt.test {
+                    let cond = self.evaluate(test)?;
+                    if !cond.to_boolean() {
+                        break;
+                    }
                 }
+                *stage = LoopStage::Body;
             }
 
             // Body
-            result = self.execute_statement(&for_stmt.body)?;
-            match &result {
-                Completion::Break(_) => {
-                    result = Completion::empty();
-                    break;
-                }
-                Completion::Continue(_) => {
-                    // Continue to update
+            if *stage == LoopStage::Body {
+                result = self.execute_statement(&for_stmt.body)?;
+                match &result {
+                    Completion::Break(_) => {
+                        result = Completion::empty();
+                        break;
+                    }
+                    Completion::Continue(_) => {
+                        // Continue to update
+                    }
+                    Completion::Return(_) | Completion::Throw(_) => break,
+                    _ => {}
                 }
-                Completion::Return(_) | Completion::Throw(_) => break,
-                _ => {}
+                *stage = LoopStage::Update;
             }
 
             // Update
             if let Some(update) = &for_stmt.update {
                 self.evaluate(update)?;
             }
+            *stage = LoopStage::Test;
         }
 
-        self.current_env = outer;
         Ok(result)
     }
 
     /// Execute for-in loop.
     fn execute_for_in(&mut self, for_in: &ForInStmt) -> JsResult<Completion> {
-        let right = self.evaluate(&for_in.right)?;
-
-        if right.is_nullish() {
-            return Ok(Completion::empty());
-        }
+        let outer = self.current_env.clone();
+        let (env, keys, start) = match self.take_resume() {
+            Some(Resume::ForIn { env, keys, index }) => (env, keys, Some(index)),
+            _ => {
+                let right = self.evaluate(&for_in.right)?;
 
-        let obj = right.to_object()?;
-        let keys = obj.borrow().own_enumerable_keys();
+                if right.is_nullish() {
+                    return Ok(Completion::empty());
+                }
 
-        let outer = self.current_env.clone();
-        self.current_env = Rc::new(RefCell::new(Environment::child(outer.clone())));
+                let obj = right.to_object()?;
+                let keys = obj.borrow().own_enumerable_keys();
+                let env = Rc::new(RefCell::new(Environment::child(outer.clone())));
+                (env, keys, None)
+            }
+        };
+        self.current_env = env.clone();
 
         let mut result = Completion::empty();
 
-        for key in keys {
-            let key_value = Value::string(key.to_string());
+        for index in start.unwrap_or(0)..keys.len() {
+            // A resumed iteration already has its variable bound.
+            if start != Some(index) {
+                let key_value = Value::string(keys[index].to_string());
 
-            // Bind the variable
-            match &for_in.left {
-                ForInLeft::Variable(decl) => {
-                    if let Some(declarator) = decl.declarations.first() {
-                        self.bind_pattern(&declarator.id, key_value, true)?;
+                // Bind the variable
+                match &for_in.left {
+                    ForInLeft::Variable(decl) => {
+                        if let Some(declarator) = decl.declarations.first() {
+                            self.bind_pattern(&declarator.id, key_value, true)?;
+                        }
+                    }
+                    ForInLeft::Pattern(pat) => {
+                        self.bind_pattern(pat, key_value, true)?;
                     }
-                }
-                ForInLeft::Pattern(pat) => {
-                    self.bind_pattern(pat, key_value, true)?;
                 }
             }
 
-            result = self.execute_statement(&for_in.body)?;
+            let completion = self.execute_statement(&for_in.body);
+            if self.suspending() {
+                self.save_resume(Resume::ForIn { env, keys, index });
+                return completion;
+            }
+            result = completion?;
             match &result {
                 Completion::Break(_) => {
                     result = Completion::empty();
@@ -364,44 +632,63 @@ impl Interpreter {
 
     /// Execute for-of loop.
     fn execute_for_of(&mut self, for_of: &ForOfStmt) -> JsResult<Completion> {
-        let right = self.evaluate(&for_of.right)?;
-
-        // Simplified: only handle arrays
-        if !right.is_array() {
-            return Err(JsError::type_error("Value is not iterable"));
-        }
-
-        let obj = right.to_object()?;
-        let len = obj.borrow().array_length();
+        let (iterator, mut resumed) = match self.take_resume() {
+            Some(Resume::ForOf { env, iterator }) => (iterator, Some(env)),
+            _ => {
+                let right = self.evaluate(&for_of.right)?;
+                (self.get_iterator(&right)?, None)
+            }
+        };
 
         let outer = self.current_env.clone();
-        self.current_env = Rc::new(RefCell::new(Environment::child(outer.clone())));
 
         let mut result = Completion::empty();
 
-        for i in 0..len {
-            let value = obj.borrow().get(&PropertyKey::Index(i as u32))?;
+        loop {
+            let env = match resumed.take() {
+                Some(env) => env,
+                None => {
+                    let Some(value) = self.iterator_step(&iterator)? else {
+                        break;
+                    };
+
+                    // Each iteration gets a fresh binding for the loop variable.
+                    let env = Rc::new(RefCell::new(Environment::child(outer.clone())));
+                    self.current_env = env.clone();
 
-            // Bind the variable
-            match &for_of.left {
-                ForInLeft::Variable(decl) => {
-                    if let Some(declarator) = decl.declarations.first() {
-                        self.bind_pattern(&declarator.id, value, true)?;
+                    // Bind the variable
+                    match &for_of.left {
+                        ForInLeft::Variable(decl) => {
+                            if let Some(declarator) = decl.declarations.first() {
+                                self.bind_pattern(&declarator.id, value, true)?;
+                            }
+                        }
+                        ForInLeft::Pattern(pat) => {
+                            self.bind_pattern(pat, value, true)?;
+                        }
                     }
+                    env
                 }
-                ForInLeft::Pattern(pat) => {
-                    self.bind_pattern(pat, value, true)?;
-                }
-            }
+            };
+            self.current_env = env.clone();
 
-            result = self.execute_statement(&for_of.body)?;
+            let completion = self.execute_statement(&for_of.body);
+            if self.suspending() {
+                self.save_resume(Resume::ForOf { env, iterator });
+                return completion;
+            }
+            result = completion?;
             match &result {
                 Completion::Break(_) => {
                     result = Completion::empty();
+                    self.iterator_close(&iterator)?;
                     break;
                 }
                 Completion::Continue(_) => continue,
-                Completion::Return(_) | Completion::Throw(_) => break,
+                Completion::Return(_) | Completion::Throw(_) => {
+                    self.iterator_close(&iterator)?;
+                    break;
+                }
                 _ => {}
             }
         }
@@ -410,17 +697,98 @@ impl Interpreter {
         Ok(result)
     }
 
+    /// Get an iterator for `value` through its `Symbol.iterator` method.
+    ///
+    /// Array literals and string primitives are not linked to their
+    /// prototypes, so they fall back to the built-in iterators.
+    fn get_iterator(&mut self, value: &Value) -> JsResult<Value> {
+        let method = self.get_property(value, &PropertyKey::Symbol(Symbol::iterator()))?;
+        if method.is_function() {
+            let iterator = self.call_function(&method, value, &[])?;
+            if !iterator.is_object() {
+                return Err(JsError::type_error(
+                    "Result of the Symbol.iterator method is not an object",
+                ));
+            }
+            return Ok(iterator);
+        }
+
+        if value.is_array() || value.is_string() {
+            return Ok(builtin::create_iterator(
+                value.clone(),
+                IterationKind::Values,
+            ));
+        }
+
+        Err(JsError::type_error("Value is not iterable"))
+    }
+
+    /// Advance an iterator, returning `None` once it reports `done`.
+    fn iterator_step(&mut self, iterator: &Value) -> JsResult<Option<Value>> {
+        let next = self.get_property(iterator, &PropertyKey::string("next"))?;
+        let result = self.call_function(&next, iterator, &[])?;
+        if !result.is_object() {
+            return Err(JsError::type_error("Iterator result is not an object"));
+        }
+        if self
+            .get_property(&result, &PropertyKey::string("done"))?
+            .to_boolean()
+        {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.get_property(&result, &PropertyKey::string("value"))?,
+        ))
+    }
+
+    /// Call an iterator's `return` method when a loop exits early.
+    fn iterator_close(&mut self, iterator: &Value) -> JsResult<()> {
+        let return_fn = self.get_property(iterator, &PropertyKey::string("return"))?;
+        if return_fn.is_function() {
+            self.call_function(&return_fn, iterator, &[])?;
+        }
+        Ok(())
+    }
+
+    /// Drain an iterable into a vector of values.
+    fn iterate_to_vec(&mut self, value: &Value) -> JsResult<Vec<Value>> {
+        let iterator = self.get_iterator(value)?;
+        let mut values = Vec::new();
+        while let Some(v) = self.iterator_step(&iterator)? {
+            values.push(v);
+        }
+        Ok(values)
+    }
+
     /// Execute while loop.
     fn execute_while(&mut self, while_stmt: &WhileStmt) -> JsResult<Completion> {
+        let mut stage = match self.take_resume() {
+            Some(Resume::Loop(stage)) => stage,
+            _ => LoopStage::Test,
+        };
+
+        let result = self.run_while(while_stmt, &mut stage);
+        if self.suspending() {
+            self.save_resume(Resume::Loop(stage));
+        }
+        result
+    }
+
+    /// Run a while loop from `stage`, which is kept at the part running.
+    fn run_while(&mut self, while_stmt: &WhileStmt, stage: &mut LoopStage) -> JsResult<Completion> {
         let mut result = Completion::empty();
 
         loop {
-            let test = self.evaluate(&while_stmt.test)?;
-            if !test.to_boolean() {
-                break;
+            if *stage == LoopStage::Test {
+                let test = self.evaluate(&while_stmt.test)?;
+                if !test.to_boolean() {
+                    break;
+                }
+                *stage = LoopStage::Body;
             }
 
             result = self.execute_statement(&while_stmt.body)?;
+            *stage = LoopStage::Test;
             match &result {
                 Completion::Break(_) => {
                     result = Completion::empty();
@@ -437,24 +805,46 @@ impl Interpreter {
 
     /// Execute do-while loop.
     fn execute_do_while(&mut self, do_while: &DoWhileStmt) -> JsResult<Completion> {
-        let mut result;
+        let mut stage = match self.take_resume() {
+            Some(Resume::Loop(stage)) => stage,
+            _ => LoopStage::Body,
+        };
+
+        let result = self.run_do_while(do_while, &mut stage);
+        if self.suspending() {
+            self.save_resume(Resume::Loop(stage));
+        }
+        result
+    }
+
+    /// Run a do-while loop from `stage`, which is kept at the part running.
+    fn run_do_while(
+        &mut self,
+        do_while: &DoWhileStmt,
+        stage: &mut LoopStage,
+    ) -> JsResult<Completion> {
+        let mut result = Completion::empty();
 
         loop {
-            result = self.execute_statement(&do_while.body)?;
-            match &result {
-                Completion::Break(_) => {
-                    result = Completion::empty();
-                    break;
+            if *stage == LoopStage::Body {
+                result = self.execute_statement(&do_while.body)?;
+                match &result {
+                    Completion::Break(_) => {
+                        result = Completion::empty();
+                        break;
+                    }
+                    Completion::Continue(_) => {}
+                    Completion::Return(_) | Completion::Throw(_) => break,
+                    _ => {}
                 }
-                Completion::Continue(_) => {}
-                Completion::Return(_) | Completion::Throw(_) => break,
-                _ => {}
+                *stage = LoopStage::Test;
             }
 
             let test = self.evaluate(&do_while.test)?;
             if !test.to_boolean() {
                 break;
             }
+            *stage = LoopStage::Body;
         }
 
         Ok(result)
@@ -462,121 +852,176 @@ impl Interpreter {
 
     /// Execute switch statement.
     fn execute_switch(&mut self, switch_stmt: &SwitchStmt) -> JsResult<Completion> {
-        let discriminant = self.evaluate(&switch_stmt.discriminant)?;
+        let mut state = match self.take_resume() {
+            Some(Resume::Switch(state)) => state,
+            _ => SwitchState {
+                discriminant: self.evaluate(&switch_stmt.discriminant)?,
+                case: 0,
+                statement: 0,
+                matched: false,
+                default: false,
+            },
+        };
 
-        let mut matched = false;
-        let mut default_index = None;
+        let result = self.run_switch(switch_stmt, &mut state);
+        if self.suspending() {
+            self.save_resume(Resume::Switch(state));
+        }
+        result
+    }
+
+    /// Run a switch statement from `state`, which is kept at the part
+    /// running.
+    fn run_switch(
+        &mut self,
+        switch_stmt: &SwitchStmt,
+        state: &mut SwitchState,
+    ) -> JsResult<Completion> {
         let mut result = Completion::empty();
 
         // Find matching case
-        for (i, case) in switch_stmt.cases.iter().enumerate() {
-            if case.test.is_none() {
-                default_index = Some(i);
-                continue;
-            }
+        if !state.default {
+            while let Some(case) = switch_stmt.cases.get(state.case) {
+                if let Some(test) = &case.test {
+                    if !state.matched {
+                        let test = self.evaluate(test)?;
+                        state.matched = state.discriminant.strict_equals(&test);
+                    }
 
-            if !matched {
-                let test = self.evaluate(case.test.as_ref().unwrap())?;
-                if discriminant.strict_equals(&test) {
-                    matched = true;
+                    if state.matched {
+                        if let Some(completion) =
+                            self.run_case(&case.consequent, state, &mut result)?
+                        {
+                            return Ok(completion);
+                        }
+                    }
                 }
+                state.case += 1;
+                state.statement = 0;
             }
 
-            if matched {
-                for stmt in &case.consequent {
-                    result = self.execute_statement(stmt)?;
-                    if let Completion::Break(_) = result {
-                        return Ok(Completion::empty());
-                    }
-                    if !result.is_normal() {
-                        return Ok(result);
-                    }
+            if state.matched {
+                return Ok(result);
+            }
+            match switch_stmt
+                .cases
+                .iter()
+                .position(|case| case.test.is_none())
+            {
+                Some(index) => {
+                    state.case = index;
+                    state.default = true;
                 }
+                None => return Ok(result),
             }
         }
 
         // Default case
-        if !matched {
-            if let Some(idx) = default_index {
-                for stmt in &switch_stmt.cases[idx].consequent {
-                    result = self.execute_statement(stmt)?;
-                    if let Completion::Break(_) = result {
-                        return Ok(Completion::empty());
-                    }
-                    if !result.is_normal() {
-                        return Ok(result);
-                    }
-                }
+        let consequent = &switch_stmt.cases[state.case].consequent;
+        Ok(self
+            .run_case(consequent, state, &mut result)?
+            .unwrap_or(result))
+    }
+
+    /// Run the statements of a switch case from `state.statement`,
+    /// returning the completion of the switch if one ends it.
+    fn run_case(
+        &mut self,
+        consequent: &[Statement],
+        state: &mut SwitchState,
+        result: &mut Completion,
+    ) -> JsResult<Option<Completion>> {
+        while let Some(stmt) = consequent.get(state.statement) {
+            *result = self.execute_statement(stmt)?;
+            if let Completion::Break(_) = result {
+                return Ok(Some(Completion::empty()));
+            }
+            if !result.is_normal() {
+                return Ok(Some(result.clone()));
             }
+            state.statement += 1;
         }
-
-        Ok(result)
+        Ok(None)
     }
 
     /// Execute try statement.
     fn execute_try(&mut self, try_stmt: &TryStmt) -> JsResult<Completion> {
-        let result = self.execute_block(&try_stmt.block);
-
-        match &result {
-            Ok(Completion::Throw(ref value)) => {
-                // Execute catch
-                if let Some(handler) = &try_stmt.handler {
-                    let outer = self.current_env.clone();
-                    self.current_env = Rc::new(RefCell::new(Environment::child(outer.clone())));
-
-                    if let Some(param) = &handler.param {
-                        let error_value = value.clone();
-                        self.bind_pattern(param, error_value, true)?;
-                    }
-
-                    let catch_result = self.execute_block(&handler.body);
-                    self.current_env = outer;
+        let result = match self.take_resume() {
+            Some(Resume::Finally(result)) => return self.execute_finally(try_stmt, *result),
+            Some(Resume::Catch(env)) => self.execute_catch(try_stmt, env),
+            _ => {
+                let result = self.execute_block(&try_stmt.block);
+                if self.suspending() {
+                    self.save_resume(Resume::Try);
+                    return result;
+                }
 
-                    if let Some(finalizer) = &try_stmt.finalizer {
-                        self.execute_block(finalizer)?;
+                let thrown = match &result {
+                    Ok(Completion::Throw(value)) => Some(value.clone()),
+                    Err(e) => Some(self.error_to_value(e)),
+                    _ => None,
+                };
+                match (thrown, &try_stmt.handler) {
+                    (Some(value), Some(handler)) => {
+                        let env =
+                            Rc::new(RefCell::new(Environment::child(self.current_env.clone())));
+                        if let Some(param) = &handler.param {
+                            let outer = core::mem::replace(&mut self.current_env, env.clone());
+                            let bound = self.bind_pattern(param, value, true);
+                            self.current_env = outer;
+                            bound?;
+                        }
+                        self.execute_catch(try_stmt, env)
                     }
-
-                    catch_result
-                } else if let Some(finalizer) = &try_stmt.finalizer {
-                    self.execute_block(finalizer)?;
-                    Err(JsError::Error(format!("Uncaught exception")))
-                } else {
-                    Err(JsError::Error(format!("Uncaught exception")))
+                    (Some(_), None) if result.is_ok() => {
+                        Err(JsError::Error(format!("Uncaught exception")))
+                    }
+                    _ => result,
                 }
             }
-            Err(ref e) => {
-                // Handle Err case - execute catch with error message
-                if let Some(handler) = &try_stmt.handler {
-                    let outer = self.current_env.clone();
-                    self.current_env = Rc::new(RefCell::new(Environment::child(outer.clone())));
+        };
+        if self.suspending() {
+            return result;
+        }
 
-                    if let Some(param) = &handler.param {
-                        let error_value = Value::string(e.message());
-                        self.bind_pattern(param, error_value, true)?;
-                    }
+        self.execute_finally(try_stmt, result)
+    }
 
-                    let catch_result = self.execute_block(&handler.body);
-                    self.current_env = outer;
+    /// Execute the catch block of a try statement in `env`.
+    fn execute_catch(
+        &mut self,
+        try_stmt: &TryStmt,
+        env: Rc<RefCell<Environment>>,
+    ) -> JsResult<Completion> {
+        let Some(handler) = &try_stmt.handler else {
+            return Ok(Completion::empty());
+        };
 
-                    if let Some(finalizer) = &try_stmt.finalizer {
-                        self.execute_block(finalizer)?;
-                    }
+        let outer = core::mem::replace(&mut self.current_env, env.clone());
+        let result = self.execute_block(&handler.body);
+        self.current_env = outer;
+        if self.suspending() {
+            self.save_resume(Resume::Catch(env));
+        }
+        result
+    }
 
-                    catch_result
-                } else if let Some(finalizer) = &try_stmt.finalizer {
-                    self.execute_block(finalizer)?;
-                    result
-                } else {
-                    result
-                }
-            }
-            Ok(other) => {
-                if let Some(finalizer) = &try_stmt.finalizer {
-                    self.execute_block(finalizer)?;
-                }
-                Ok(other.clone())
-            }
+    /// Execute the finally block of a try statement, if it has one, then
+    /// complete with `result`.
+    fn execute_finally(
+        &mut self,
+        try_stmt: &TryStmt,
+        result: JsResult<Completion>,
+    ) -> JsResult<Completion> {
+        if let Some(finalizer) = &try_stmt.finalizer {
+            let completion = self.execute_block(finalizer);
+            if self.suspending() {
+                self.save_resume(Resume::Finally(Box::new(result)));
+                return completion;
+            }
+            completion?;
         }
+        result
     }
 
     /// Execute function declaration.
@@ -670,13 +1115,31 @@ impl Interpreter {
         // Add prototype
         let proto = Rc::new(RefCell::new(JsObject::new()));
 
-        // Add methods to prototype
+        // Add methods to the prototype, static ones to the constructor
         for elem in &class.body.body {
             if let ClassElement::Method(method) = elem {
-                if method.kind != MethodKind::Constructor {
-                    let method_func = self.create_function_from_expr(&method.value)?;
-                    let key = self.property_key_from_expr(&method.key)?;
-                    proto.borrow_mut().set(key, method_func)?;
+                let method_func = self.create_function_from_expr(&method.value)?;
+                let descriptor = match method.kind {
+                    MethodKind::Constructor => continue,
+                    MethodKind::Method => PropertyDescriptor::data(method_func, true, false, true),
+                    MethodKind::Get => PropertyDescriptor {
+                        get: Some(method_func),
+                        enumerable: Some(false),
+                        configurable: Some(true),
+                        ..Default::default()
+                    },
+                    MethodKind::Set => PropertyDescriptor {
+                        set: Some(method_func),
+                        enumerable: Some(false),
+                        configurable: Some(true),
+                        ..Default::default()
+                    },
+                };
+                let key = self.property_key_from_expr(&method.key)?;
+                if method.is_static {
+                    obj.define_own_property(key, descriptor)?;
+                } else {
+                    proto.borrow_mut().define_own_property(key, descriptor)?;
                 }
             }
         }
@@ -690,7 +1153,43 @@ impl Interpreter {
     }
 
     /// Evaluate an expression.
+    ///
+    /// In an async function the result of each subexpression is kept
+    /// until its statement finishes, so that an expression suspended at
+    /// `await` can be evaluated again on resumption without repeating the
+    /// parts it had finished.
     pub fn evaluate(&mut self, expr: &Expression) -> JsResult<Value> {
+        let Some(frame) = self.async_frame.as_deref_mut() else {
+            return self.evaluate_expression(expr);
+        };
+        if frame.depth == 0 {
+            frame.root = expr as *const Expression as usize;
+            frame.evaluated = 0;
+        }
+        let key = (frame.root, frame.evaluated);
+        frame.evaluated += 1;
+        if let Some((result, next)) = frame.results.get(&key) {
+            frame.evaluated = *next;
+            return result.clone();
+        }
+
+        frame.depth += 1;
+        let result = self.evaluate_expression(expr);
+        let Some(frame) = self.async_frame.as_deref_mut() else {
+            return result;
+        };
+        frame.depth -= 1;
+        if frame.awaited.is_none() {
+            frame.results.insert(key, (result.clone(), frame.evaluated));
+        } else if frame.awaiting.is_none() {
+            // The first expression to unwind is the `await` itself.
+            frame.awaiting = Some((key, frame.evaluated));
+        }
+        result
+    }
+
+    /// Evaluate an expression without keeping its result.
+    fn evaluate_expression(&mut self, expr: &Expression) -> JsResult<Value> {
         match expr {
             Expression::Identifier(id) => self.current_env.borrow().get(&id.name),
             Expression::Literal(lit) => self.evaluate_literal(lit),
@@ -712,10 +1211,13 @@ impl Interpreter {
             Expression::Sequence(seq) => self.evaluate_sequence(seq),
             Expression::Spread(spread) => self.evaluate(&spread.argument),
             Expression::Template(template) => self.evaluate_template(template),
-            Expression::Await(await_expr) => self.evaluate(&await_expr.argument),
+            Expression::Await(await_expr) => {
+                let value = self.evaluate(&await_expr.argument)?;
+                self.await_value(value)
+            }
             Expression::Yield(_) => Ok(Value::undefined()),
             Expression::OptionalChain(_) => Ok(Value::undefined()),
-            Expression::TaggedTemplate(_) => Ok(Value::undefined()),
+            Expression::TaggedTemplate(tagged) => self.evaluate_tagged_template(tagged),
         }
     }
 
@@ -746,13 +1248,8 @@ impl Interpreter {
             if let Some(e) = elem {
                 if let Expression::Spread(spread) = e {
                     let value = self.evaluate(&spread.argument)?;
-                    if let Value::Object(obj) = value {
-                        let len = obj.borrow().array_length();
-                        for i in 0..len {
-                            let v = obj.borrow().get(&PropertyKey::Index(i as u32))?;
-                            elements.push(Some(v));
-                        }
-                    }
+                    let values = self.iterate_to_vec(&value)?;
+                    elements.extend(values.into_iter().map(Some));
                 } else {
                     elements.push(Some(self.evaluate(e)?));
                 }
@@ -775,6 +1272,7 @@ impl Interpreter {
                     value,
                     shorthand,
                     method,
+                    kind,
                     ..
                 } => {
                     let key = if *shorthand || *method {
@@ -788,14 +1286,33 @@ impl Interpreter {
                     };
 
                     let val = self.evaluate(value)?;
-                    obj.set(key, val)?;
+                    let descriptor = match kind {
+                        PropertyKind::Init => PropertyDescriptor::data(val, true, true, true),
+                        PropertyKind::Get => PropertyDescriptor {
+                            get: Some(val),
+                            enumerable: Some(true),
+                            configurable: Some(true),
+                            ..Default::default()
+                        },
+                        PropertyKind::Set => PropertyDescriptor {
+                            set: Some(val),
+                            enumerable: Some(true),
+                            configurable: Some(true),
+                            ..Default::default()
+                        },
+                    };
+                    obj.define_own_property(key, descriptor)?;
                 }
                 ObjectProperty::Spread(spread) => {
                     let value = self.evaluate(&spread.argument)?;
-                    if let Value::Object(src) = value {
-                        for key in src.borrow().own_enumerable_keys() {
-                            let v = src.borrow().get(&key)?;
-                            obj.set(key, v)?;
+                    if let Value::Object(src) = &value {
+                        let keys = src.borrow().own_enumerable_keys();
+                        for key in keys {
+                            let v = self.get_property(&value, &key)?;
+                            obj.define_own_property(
+                                key,
+                                PropertyDescriptor::data(v, true, true, true),
+                            )?;
                         }
                     }
                 }
@@ -887,7 +1404,7 @@ impl Interpreter {
             return Err(JsError::syntax("Invalid member expression"));
         };
 
-        object.get(&key)
+        self.get_property(&object, &key)
     }
 
     /// Evaluate call expression.
@@ -897,8 +1414,23 @@ impl Interpreter {
             return Err(JsError::range("Maximum call stack size exceeded"));
         }
 
-        // Get the function and this value
-        let (func, this_value) = if let Expression::Member(member) = call.callee.as_ref() {
+        let (func, this_value) = self.evaluate_callee(&call.callee)?;
+
+        if call.optional && func.is_nullish() {
+            return Ok(Value::undefined());
+        }
+
+        // Evaluate arguments
+        let args = self.evaluate_arguments(&call.arguments)?;
+
+        self.call_function(&func, &this_value, &args)
+    }
+
+    /// Evaluate a callee, returning the function and its `this` value.
+    ///
+    /// Member callees use their object as `this`.
+    fn evaluate_callee(&mut self, callee: &Expression) -> JsResult<(Value, Value)> {
+        if let Expression::Member(member) = callee {
             let obj = self.evaluate(&member.object)?;
             let key = if member.computed {
                 let prop = self.evaluate(&member.property)?;
@@ -909,27 +1441,121 @@ impl Interpreter {
                 return Err(JsError::syntax("Invalid member expression"));
             };
 
-            let func = obj.get(&key)?;
-            (func, obj)
+            let func = self.get_property(&obj, &key)?;
+            Ok((func, obj))
         } else {
-            let func = self.evaluate(&call.callee)?;
-            (func, Value::undefined())
-        };
+            Ok((self.evaluate(callee)?, Value::undefined()))
+        }
+    }
 
-        if call.optional && func.is_nullish() {
-            return Ok(Value::undefined());
+    /// Evaluate a tagged template.
+    ///
+    /// The tag receives an array of the cooked strings, with the raw
+    /// strings in its `raw` property, followed by the substitution values.
+    fn evaluate_tagged_template(&mut self, tagged: &TaggedTemplateExpr) -> JsResult<Value> {
+        if self.call_depth >= self.max_call_depth {
+            return Err(JsError::range("Maximum call stack size exceeded"));
         }
 
-        // Evaluate arguments
-        let args: Vec<Value> = call
-            .arguments
-            .iter()
-            .map(|arg| self.evaluate(arg))
-            .collect::<JsResult<Vec<_>>>()?;
+        let (func, this_value) = self.evaluate_callee(&tagged.tag)?;
+
+        let strings = self
+            .template_objects
+            .entry(tagged.site)
+            .or_insert_with(|| Self::template_object(&tagged.quasi))
+            .clone();
+
+        let mut args = Vec::with_capacity(tagged.quasi.expressions.len() + 1);
+        args.push(Value::Object(strings));
+        for expr in &tagged.quasi.expressions {
+            args.push(self.evaluate(expr)?);
+        }
 
         self.call_function(&func, &this_value, &args)
     }
 
+    /// Build the frozen strings array, with its frozen `raw` array, passed
+    /// to the tag of a tagged template.
+    fn template_object(quasi: &TemplateLiteral) -> Rc<RefCell<JsObject>> {
+        let mut raw = JsObject::array(
+            quasi
+                .quasis
+                .iter()
+                .map(|quasi| Some(Value::string(quasi.raw.clone())))
+                .collect(),
+        );
+        raw.freeze();
+        let mut strings = JsObject::array(
+            quasi
+                .quasis
+                .iter()
+                .map(|quasi| {
+                    Some(match &quasi.cooked {
+                        Some(cooked) => Value::string(cooked.clone()),
+                        None => Value::undefined(),
+                    })
+                })
+                .collect(),
+        );
+        strings.define_property(
+            PropertyKey::string("raw"),
+            PropertyDescriptor::data(Value::object(raw), false, false, false),
+        );
+        strings.freeze();
+        Rc::new(RefCell::new(strings))
+    }
+
+    /// Evaluate call arguments, expanding spread elements.
+    fn evaluate_arguments(&mut self, arguments: &[Expression]) -> JsResult<Vec<Value>> {
+        let mut args = Vec::with_capacity(arguments.len());
+        for arg in arguments {
+            if let Expression::Spread(spread) = arg {
+                let value = self.evaluate(&spread.argument)?;
+                args.extend(self.iterate_to_vec(&value)?);
+            } else {
+                args.push(self.evaluate(arg)?);
+            }
+        }
+        Ok(args)
+    }
+
+    /// Read a property, calling its getter if it is an accessor.
+    pub fn get_property(&mut self, object: &Value, key: &PropertyKey) -> JsResult<Value> {
+        if let Value::Object(obj) = object {
+            let accessor = obj.borrow().lookup_accessor(key);
+            if let Some(descriptor) = accessor {
+                return match descriptor.get {
+                    Some(getter) if getter.is_function() => {
+                        self.call_function(&getter, object, &[])
+                    }
+                    _ => Ok(Value::undefined()),
+                };
+            }
+        }
+
+        object.get(key)
+    }
+
+    /// Write a property, calling its setter if it is an accessor.
+    pub fn set_property(&mut self, object: &Value, key: PropertyKey, value: Value) -> JsResult<()> {
+        if let Value::Object(obj) = object {
+            let accessor = obj.borrow().lookup_accessor(&key);
+            if let Some(descriptor) = accessor {
+                return match descriptor.set {
+                    Some(setter) if setter.is_function() => {
+                        self.call_function(&setter, object, &[value]).map(|_| ())
+                    }
+                    _ => Err(JsError::type_error(format!(
+                        "Cannot set property {} which has only a getter",
+                        key.to_string()
+                    ))),
+                };
+            }
+        }
+
+        object.set(key, value)
+    }
+
     /// Call a function.
     pub fn call_function(
         &mut self,
@@ -940,7 +1566,11 @@ impl Interpreter {
         if let Value::Object(obj) = func {
             if let Some(callable) = obj.borrow().callable() {
                 self.call_depth += 1;
+                // The callee's expressions are not part of the running
+                // async function call.
+                let frame = self.async_frame.take();
                 let result = self.call_callable(callable.clone(), this_value, args);
+                self.async_frame = frame;
                 self.call_depth -= 1;
                 return result;
             }
@@ -959,20 +1589,21 @@ impl Interpreter {
         match callable {
             Callable::Native(native) => (native.func)(this_value, args),
             Callable::UserDefined(user_func) => {
-                self.call_user_function(&user_func, this_value, args)
+                self.call_user_function(user_func, this_value, args)
             }
             Callable::Bound(bound) => {
                 let mut all_args = bound.bound_args.clone();
                 all_args.extend_from_slice(args);
                 self.call_callable(*bound.target, &bound.bound_this, &all_args)
             }
+            Callable::Intrinsic(intrinsic) => (intrinsic.func)(self, this_value, args),
         }
     }
 
     /// Call a user-defined function.
     fn call_user_function(
         &mut self,
-        func: &UserFunction,
+        func: UserFunction,
         this_value: &Value,
         args: &[Value],
     ) -> JsResult<Value> {
@@ -998,6 +1629,13 @@ impl Interpreter {
             .borrow_mut()
             .initialize("arguments", Value::object(args_array))?;
 
+        if func.is_async {
+            let env = core::mem::replace(&mut self.current_env, outer);
+            let promise = self.new_promise();
+            self.run_async(Box::new(AsyncFrame::new(func.body, env, promise.clone())));
+            return Ok(Value::Object(promise));
+        }
+
         // Execute body
         let result = self.execute_block(&func.body);
 
@@ -1034,11 +1672,7 @@ impl Interpreter {
         }
 
         // Call constructor
-        let args: Vec<Value> = new
-            .arguments
-            .iter()
-            .map(|arg| self.evaluate(arg))
-            .collect::<JsResult<Vec<_>>>()?;
+        let args = self.evaluate_arguments(&new.arguments)?;
 
         let this = Value::Object(new_obj.clone());
         let result = self.call_function(&constructor, &this, &args)?;
@@ -1212,11 +1846,35 @@ impl Interpreter {
                     ))
                 }
             }
-            BinaryOp::Instanceof => {
-                // Simplified
-                Ok(Value::boolean(false))
+            BinaryOp::Instanceof => self.instance_of(&left, &right).map(Value::boolean),
+        }
+    }
+
+    /// Check whether `constructor.prototype` is on the prototype chain of
+    /// `value`.
+    fn instance_of(&mut self, value: &Value, constructor: &Value) -> JsResult<bool> {
+        if !constructor.is_function() {
+            return Err(JsError::type_error(
+                "Right-hand side of 'instanceof' is not callable",
+            ));
+        }
+        // Plain functions have no `prototype` object, so nothing is an
+        // instance of them
+        let proto = match self.get_property(constructor, &PropertyKey::string("prototype"))? {
+            Value::Object(proto) => proto,
+            _ => return Ok(false),
+        };
+        let mut current = match value {
+            Value::Object(obj) => obj.borrow().prototype().cloned(),
+            _ => None,
+        };
+        while let Some(obj) = current {
+            if Rc::ptr_eq(&obj, &proto) {
+                return Ok(true);
             }
+            current = obj.borrow().prototype().cloned();
         }
+        Ok(false)
     }
 
     /// Evaluate logical expression.
@@ -1324,7 +1982,7 @@ impl Interpreter {
                     return Err(JsError::syntax("Invalid assignment target"));
                 };
 
-                object.set(key, value)?;
+                self.set_property(&object, key, value)?;
             }
             _ => return Err(JsError::syntax("Invalid assignment target")),
         }
@@ -1383,7 +2041,7 @@ impl Interpreter {
     }
 
     /// Convert a value to a property key.
-    fn value_to_property_key(&self, value: &Value) -> JsResult<PropertyKey> {
+    pub(crate) fn value_to_property_key(&self, value: &Value) -> JsResult<PropertyKey> {
         match value {
             Value::String(s) => Ok(PropertyKey::string(s.clone())),
             Value::Number(n) => {
@@ -1401,6 +2059,30 @@ impl Interpreter {
         }
     }
 
+    /// Convert an error into an `Error` object value.
+    pub(crate) fn error_to_value(&self, error: &JsError) -> Value {
+        self.new_error(error.name(), error.message().into())
+    }
+
+    /// Set the prototype given to errors named `name`.
+    pub(crate) fn set_error_prototype(&mut self, name: &'static str, proto: Rc<RefCell<JsObject>>) {
+        self.error_prototypes.push((name, proto));
+    }
+
+    /// Create an error object named `name`, linked to the matching error
+    /// prototype, or to `Error.prototype` for names without one.
+    pub(crate) fn new_error(&self, name: &str, message: String) -> Value {
+        let proto = self
+            .error_prototypes
+            .iter()
+            .find(|(n, _)| *n == name)
+            .or_else(|| self.error_prototypes.iter().find(|(n, _)| *n == "Error"))
+            .map(|(_, proto)| proto.clone());
+        let mut obj = JsObject::error(name.into(), message);
+        obj.set_prototype(proto);
+        Value::object(obj)
+    }
+
     /// Convert a value to an error.
     fn value_to_error(&self, value: Value) -> JsError {
         if let Value::Object(obj) = &value {
@@ -1414,6 +2096,371 @@ impl Interpreter {
     }
 }
 
+// Console
+
+impl Interpreter {
+    /// Log a message at the position of the running statement.
+    pub fn console_message(&mut self, level: ConsoleLevel, text: String) {
+        self.push_console_message(level, text, false);
+    }
+
+    fn push_console_message(&mut self, level: ConsoleLevel, text: String, uncaught: bool) {
+        if self.console.len() == MAX_CONSOLE_MESSAGES {
+            self.console.pop_front();
+        }
+        self.console.push_back(ConsoleMessage {
+            level,
+            text,
+            source: self.script_name.clone(),
+            line: self.location.line,
+            column: self.location.column,
+            uncaught,
+        });
+    }
+
+    /// Get the buffered console messages, oldest first.
+    pub fn console_messages(&self) -> impl Iterator<Item = &ConsoleMessage> {
+        self.console.iter()
+    }
+
+    /// Remove and return the buffered console messages, oldest first.
+    pub fn take_console_messages(&mut self) -> Vec<ConsoleMessage> {
+        self.console.drain(..).collect()
+    }
+}
+
+// Time
+
+impl Interpreter {
+    /// Install the wall clock behind `Date.now()` and `new Date()`.
+    ///
+    /// The host normally passes the kernel's wall clock, which returns
+    /// milliseconds since the Unix epoch.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// The current time value, in whole milliseconds since the epoch.
+    pub fn now(&self) -> f64 {
+        date::time_clip(libm::floor((self.clock)()))
+    }
+}
+
+// Binary data
+
+impl Interpreter {
+    /// Set the prototype given to `ArrayBuffer`s created by the interpreter.
+    pub(crate) fn set_array_buffer_prototype(&mut self, proto: Rc<RefCell<JsObject>>) {
+        self.array_buffer_prototype = Some(proto);
+    }
+
+    /// Create an `ArrayBuffer` holding `bytes`.
+    ///
+    /// Hosts use this to hand binary data (response bodies, socket
+    /// messages) to scripts.
+    pub fn new_array_buffer(&self, bytes: Vec<u8>) -> Rc<RefCell<JsObject>> {
+        let mut obj = JsObject::array_buffer(bytes);
+        obj.set_prototype(self.array_buffer_prototype.clone());
+        Rc::new(RefCell::new(obj))
+    }
+}
+
+// Promises and microtasks
+
+impl Interpreter {
+    /// Set the prototype given to promises created by the interpreter.
+    pub(crate) fn set_promise_prototype(&mut self, proto: Rc<RefCell<JsObject>>) {
+        self.promise_prototype = Some(proto);
+    }
+
+    /// Create a pending promise.
+    ///
+    /// Hosts use this, with [`Self::resolve_promise`] and
+    /// [`Self::reject_promise`], to hand results of host operations
+    /// (network requests, timers) to scripts.
+    pub fn new_promise(&self) -> Rc<RefCell<JsObject>> {
+        let mut obj = JsObject::new();
+        obj.set_kind(ObjectKind::Promise(PromiseState::new()));
+        obj.set_prototype(self.promise_prototype.clone());
+        Rc::new(RefCell::new(obj))
+    }
+
+    /// Return `value` if it is a promise, otherwise a promise resolved
+    /// with it.
+    pub(crate) fn promise_resolve(&mut self, value: Value) -> Rc<RefCell<JsObject>> {
+        if let Value::Object(obj) = &value {
+            if matches!(obj.borrow().kind(), ObjectKind::Promise(_)) {
+                return obj.clone();
+            }
+        }
+        let promise = self.new_promise();
+        self.resolve_promise(&promise, value);
+        promise
+    }
+
+    /// Resolve `promise` with `value`.
+    ///
+    /// Thenables are adopted through a microtask; anything else fulfills
+    /// the promise directly.
+    pub fn resolve_promise(&mut self, promise: &Rc<RefCell<JsObject>>, value: Value) {
+        if let Value::Object(obj) = &value {
+            if Rc::ptr_eq(obj, promise) {
+                let reason = self
+                    .error_to_value(&JsError::type_error("Chaining cycle detected for promise"));
+                self.reject_promise(promise, reason);
+                return;
+            }
+            match self.get_property(&value, &PropertyKey::string("then")) {
+                Ok(then) if then.is_function() => {
+                    self.microtasks.push_back(Microtask::ResolveThenable {
+                        promise: promise.clone(),
+                        thenable: value.clone(),
+                        then,
+                    });
+                    return;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    let reason = self.error_to_value(&e);
+                    self.reject_promise(promise, reason);
+                    return;
+                }
+            }
+        }
+        self.settle_promise(promise, PromiseStatus::Fulfilled(value));
+    }
+
+    /// Reject `promise` with `reason`.
+    pub fn reject_promise(&mut self, promise: &Rc<RefCell<JsObject>>, reason: Value) {
+        self.settle_promise(promise, PromiseStatus::Rejected(reason));
+    }
+
+    /// Settle a pending promise and queue its reactions.
+    fn settle_promise(&mut self, promise: &Rc<RefCell<JsObject>>, status: PromiseStatus) {
+        let reactions = match promise.borrow_mut().kind_mut() {
+            ObjectKind::Promise(state) if state.is_pending() => {
+                state.status = status.clone();
+                core::mem::take(&mut state.reactions)
+            }
+            _ => return,
+        };
+        for reaction in reactions {
+            self.enqueue_reaction(reaction, &status);
+        }
+    }
+
+    /// Register `reaction` on `promise`, queueing it at once if the
+    /// promise has already settled.
+    pub(crate) fn promise_then(
+        &mut self,
+        promise: &Rc<RefCell<JsObject>>,
+        reaction: PromiseReaction,
+    ) {
+        let status = match promise.borrow_mut().kind_mut() {
+            ObjectKind::Promise(state) if state.is_pending() => {
+                state.reactions.push(reaction);
+                return;
+            }
+            ObjectKind::Promise(state) => state.status.clone(),
+            _ => return,
+        };
+        self.enqueue_reaction(reaction, &status);
+    }
+
+    /// Queue a reaction job for a settled promise.
+    fn enqueue_reaction(&mut self, reaction: PromiseReaction, status: &PromiseStatus) {
+        let (argument, rejected) = match status {
+            PromiseStatus::Fulfilled(value) => (value.clone(), false),
+            PromiseStatus::Rejected(reason) => (reason.clone(), true),
+            PromiseStatus::Pending => return,
+        };
+        self.microtasks.push_back(Microtask::Reaction {
+            reaction,
+            argument,
+            rejected,
+        });
+    }
+
+    /// Check if any microtasks are queued.
+    pub fn has_pending_microtasks(&self) -> bool {
+        !self.microtasks.is_empty()
+    }
+
+    /// Run queued microtasks, including any they queue, until the queue
+    /// is empty.
+    pub fn run_microtasks(&mut self) {
+        while self.run_microtask() {}
+    }
+
+    /// Run the next microtask, returning `false` if the queue was empty.
+    fn run_microtask(&mut self) -> bool {
+        let Some(job) = self.microtasks.pop_front() else {
+            return false;
+        };
+
+        match job {
+            Microtask::Reaction {
+                reaction,
+                argument,
+                rejected,
+            } => {
+                let handler = if rejected {
+                    &reaction.on_rejected
+                } else {
+                    &reaction.on_fulfilled
+                };
+                let outcome = if handler.is_function() {
+                    self.call_function(handler, &Value::undefined(), &[argument])
+                        .map_err(|e| self.error_to_value(&e))
+                } else if rejected {
+                    Err(argument)
+                } else {
+                    Ok(argument)
+                };
+                if let Some(derived) = &reaction.derived {
+                    match outcome {
+                        Ok(value) => self.resolve_promise(derived, value),
+                        Err(reason) => self.reject_promise(derived, reason),
+                    }
+                }
+            }
+            Microtask::ResolveThenable {
+                promise,
+                thenable,
+                then,
+            } => {
+                // The thenable gets its own pair of resolving functions.
+                if let ObjectKind::Promise(state) = promise.borrow_mut().kind_mut() {
+                    state.already_resolved = false;
+                }
+                let (resolve, reject) = builtin::create_resolving_functions(&promise);
+                if let Err(e) = self.call_function(&then, &thenable, &[resolve, reject.clone()]) {
+                    let reason = self.error_to_value(&e);
+                    self.call_function(&reject, &Value::undefined(), &[reason])
+                        .ok();
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Wait for the result of `await value`.
+    ///
+    /// In an async function this unwinds the call, which is resumed from
+    /// the microtask queue once the promise settles.  Elsewhere there is
+    /// no call to suspend, so queued microtasks run until it settles.
+    fn await_value(&mut self, value: Value) -> JsResult<Value> {
+        let promise = self.promise_resolve(value);
+        if let Some(frame) = &mut self.async_frame {
+            frame.awaited = Some(promise);
+            return Err(JsError::internal("Async function suspended"));
+        }
+
+        loop {
+            let status = match promise.borrow().kind() {
+                ObjectKind::Promise(state) => state.status.clone(),
+                _ => return Err(JsError::internal("Awaited value is not a promise")),
+            };
+            match status {
+                PromiseStatus::Fulfilled(value) => return Ok(value),
+                PromiseStatus::Rejected(reason) => return Err(self.value_to_error(reason)),
+                PromiseStatus::Pending => {
+                    if !self.run_microtask() {
+                        return Err(JsError::internal("Awaited promise never settled"));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Async functions
+
+impl Interpreter {
+    /// Run an async function call until it returns or suspends at
+    /// `await`, settling its promise once it returns.
+    fn run_async(&mut self, frame: Box<AsyncFrame>) {
+        let body = frame.body.clone();
+        let outer_env = core::mem::replace(&mut self.current_env, frame.env.clone());
+        let outer_frame = self.async_frame.replace(frame);
+        let result = self.execute_block(&body);
+        let mut frame = match core::mem::replace(&mut self.async_frame, outer_frame) {
+            Some(frame) => frame,
+            None => return,
+        };
+        self.current_env = outer_env;
+
+        if let Some(awaited) = frame.awaited.take() {
+            let (on_fulfilled, on_rejected) = builtin::create_await_functions(frame);
+            self.promise_then(
+                &awaited,
+                PromiseReaction {
+                    on_fulfilled,
+                    on_rejected,
+                    derived: None,
+                },
+            );
+            return;
+        }
+
+        let promise = frame.promise;
+        match result {
+            Ok(Completion::Return(v)) => self.resolve_promise(&promise, v),
+            Ok(Completion::Throw(v)) => self.reject_promise(&promise, v),
+            Ok(_) => self.resolve_promise(&promise, Value::undefined()),
+            Err(e) => {
+                let reason = self.error_to_value(&e);
+                self.reject_promise(&promise, reason);
+            }
+        }
+    }
+
+    /// Resume an async function call suspended at `await` with the
+    /// outcome of the awaited promise.
+    pub(crate) fn resume_async(
+        &mut self,
+        mut frame: Box<AsyncFrame>,
+        outcome: Result<Value, Value>,
+    ) {
+        if let Some((key, next)) = frame.awaiting.take() {
+            let result = outcome.map_err(|reason| self.value_to_error(reason));
+            frame.results.insert(key, (result, next));
+        }
+        self.run_async(frame);
+    }
+
+    /// Check if the running async function call is unwinding at `await`.
+    fn suspending(&self) -> bool {
+        self.async_frame
+            .as_ref()
+            .is_some_and(|frame| frame.awaited.is_some())
+    }
+
+    /// Save where a statement stopped while its async function unwinds.
+    fn save_resume(&mut self, resume: Resume) {
+        if let Some(frame) = &mut self.async_frame {
+            frame.resume.push(resume);
+        }
+    }
+
+    /// Take the state saved by the statement being resumed, if any.
+    ///
+    /// Statements unwind innermost first, so they resume outermost first.
+    fn take_resume(&mut self) -> Option<Resume> {
+        self.async_frame.as_mut()?.resume.pop()
+    }
+
+    /// Drop the expression results kept for a statement that finished.
+    fn finish_statement(&mut self) {
+        if let Some(frame) = &mut self.async_frame {
+            if frame.awaited.is_none() {
+                frame.results.clear();
+            }
+        }
+    }
+}
+
 impl Default for Interpreter {
     fn default() -> Self {
         Self::new()
@@ -1428,8 +2475,19 @@ pub type Engine = Interpreter;
 impl Engine {
     /// Evaluate JavaScript source code.
     pub fn eval(&mut self, source: &str) -> JsResult<Value> {
-        let program = crate::parser::parse(source)?;
-        self.execute(&program)
+        self.eval_script(source, "")
+    }
+
+    /// Evaluate a script, naming it `name` in console messages. An error
+    /// that escapes the script is also logged as an uncaught exception.
+    pub fn eval_script(&mut self, source: &str, name: &str) -> JsResult<Value> {
+        self.script_name = name.into();
+        self.location = Span::default();
+        let result = crate::parser::parse(source).and_then(|program| self.execute(&program));
+        if let Err(ref e) = result {
+            self.push_console_message(ConsoleLevel::Error, format!("Uncaught {}", e), true);
+        }
+        result
     }
 
     /// Set a global variable.
@@ -1442,3 +2500,594 @@ impl Engine {
         self.global_env.borrow().get(name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2023-11-14T22:13:20.123Z, with a fraction to check that it is dropped.
+    fn fixed_clock() -> f64 {
+        1_700_000_000_123.75
+    }
+
+    fn run(engine: &mut Engine, source: &str) -> Vec<String> {
+        engine.eval(source).unwrap();
+        engine
+            .take_console_messages()
+            .into_iter()
+            .map(|message| message.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_await_returns_to_the_caller() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "async function f() { await 0; console.log(1); } f(); console.log(2);",
+        );
+        assert_eq!(logged, ["2", "1"]);
+    }
+
+    #[test]
+    fn test_pending_await_resumes_when_settled() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "let resolve; \
+             const p = new Promise(function (r) { resolve = r; }); \
+             async function f() { const v = await p; console.log('got ' + v); return v; } \
+             const result = f(); \
+             result.then(function (v) { console.log('result ' + v); }); \
+             console.log('waiting');",
+        );
+        assert_eq!(logged, ["waiting"]);
+
+        let logged = run(&mut engine, "resolve(5);");
+        assert_eq!(logged, ["got 5", "result 5"]);
+    }
+
+    #[test]
+    fn test_await_resumes_inside_loops_and_try() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "async function f() { \
+               let sum = 0; \
+               for (let i = 1; i <= 3; i++) { sum += await i; console.log('step ' + i); } \
+               try { await Promise.reject(new Error('boom')); } \
+               catch (e) { console.log('caught ' + e.message); } \
+               finally { await 0; console.log('finally'); } \
+               return sum; \
+             } \
+             f().then(function (v) { console.log('sum ' + v); }); \
+             console.log('started');",
+        );
+        assert_eq!(
+            logged,
+            [
+                "started",
+                "step 1",
+                "step 2",
+                "step 3",
+                "caught boom",
+                "finally",
+                "sum 6"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instanceof_walks_the_prototype_chain() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "class A {} \
+             class B {} \
+             const a = new A(); \
+             console.log((a instanceof A) + ' ' + (a instanceof B) + ' ' + (1 instanceof A)); \
+             const e = new TypeError('x'); \
+             console.log((e instanceof TypeError) + ' ' + (e instanceof Error) + ' ' + (e instanceof RangeError)); \
+             try { a instanceof a; } catch (e) { console.log(e.name); }",
+        );
+        assert_eq!(logged, ["true false false", "true true false", "TypeError"]);
+    }
+
+    #[test]
+    fn test_typed_array_elements_wrap() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const i8 = new Int8Array(2); i8[0] = 200; i8[1] = -129; \
+             const u8 = new Uint8Array(1); u8[0] = -1; \
+             const c = new Uint8ClampedArray(2); c[0] = 300; c[1] = -5; \
+             const i16 = new Int16Array(1); i16[0] = 40000; \
+             const u16 = new Uint16Array(1); u16[0] = -1; \
+             const i32 = new Int32Array(1); i32[0] = 4294967295; \
+             const u32 = new Uint32Array(1); u32[0] = -1; \
+             const f32 = new Float32Array(1); f32[0] = 0.1; \
+             const f64 = new Float64Array(1); f64[0] = 0.1; \
+             console.log(i8[0] + ' ' + i8[1]); \
+             console.log(u8[0]); \
+             console.log(c[0] + ' ' + c[1]); \
+             console.log(i16[0] + ' ' + u16[0]); \
+             console.log(i32[0] + ' ' + u32[0]); \
+             console.log((f32[0] === 0.1) + ' ' + (f64[0] === 0.1));",
+        );
+        assert_eq!(
+            logged,
+            [
+                "-56 127",
+                "255",
+                "255 0",
+                "-25536 65535",
+                "-1 4294967295",
+                "false true"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_data_view_byte_order() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const buf = new ArrayBuffer(8); \
+             const dv = new DataView(buf); \
+             const bytes = new Uint8Array(buf); \
+             dv.setUint16(0, 4660); \
+             console.log(bytes[0] + ' ' + bytes[1] + ' ' + dv.getUint16(0, true)); \
+             dv.setInt32(4, -2, true); \
+             console.log(dv.getInt32(4, true) + ' ' + dv.getUint32(4)); \
+             dv.setFloat64(0, 1.5); \
+             console.log(dv.getFloat64(0) + ' ' + bytes[0] + ' ' + bytes[1]);",
+        );
+        assert_eq!(logged, ["18 52 13330", "-2 4278190079", "1.5 63 248"]);
+    }
+
+    #[test]
+    fn test_subarray_shares_and_slice_copies() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const a = new Uint8Array([1, 2, 3, 4]); \
+             const sub = a.subarray(1, 3); \
+             const copy = a.slice(1, 3); \
+             sub[0] = 20; \
+             copy[1] = 30; \
+             console.log(a[1] + ' ' + a[2] + ' ' + sub.length + ' ' + copy[0]);",
+        );
+        assert_eq!(logged, ["20 3 2 2"]);
+    }
+
+    #[test]
+    fn test_typed_array_set_checks_offset() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const a = new Uint8Array(4); \
+             a.set([1, 2], 2); \
+             console.log(a[2] + ' ' + a[3]); \
+             try { a.set([5, 6], 3); } \
+             catch (e) { console.log((e instanceof RangeError) + ' ' + e.name); } \
+             console.log(a[3]);",
+        );
+        assert_eq!(logged, ["1 2", "true RangeError", "2"]);
+    }
+
+    #[test]
+    fn test_binary_data_out_of_bounds_throws_range_error() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "function check(f) { \
+               try { f(); console.log('ok'); } \
+               catch (e) { console.log((e instanceof RangeError) + ' ' + (e instanceof Error) + ' ' + e.name); } \
+             } \
+             const buf = new ArrayBuffer(8); \
+             check(function () { new Int32Array(buf, 2); }); \
+             check(function () { new Int16Array(buf, 0, 5); }); \
+             check(function () { new Uint8Array(buf, 9); }); \
+             const dv = new DataView(buf); \
+             check(function () { dv.getInt32(6); }); \
+             check(function () { dv.setFloat64(1, 0); }); \
+             check(function () { new DataView(buf, 4, 8); }); \
+             check(function () { dv.getUint8(7); });",
+        );
+        assert_eq!(
+            logged,
+            [
+                "true true RangeError",
+                "true true RangeError",
+                "true true RangeError",
+                "true true RangeError",
+                "true true RangeError",
+                "true true RangeError",
+                "ok"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typed_array_ignores_numeric_string_keys() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const a = new Uint8Array(2); \
+             a[-1] = 5; a['1.5'] = 6; a['-0'] = 7; a.foo = 8; \
+             console.log(a[-1] + ' ' + a['1.5'] + ' ' + a['-0'] + ' ' + a.foo); \
+             console.log(('-1' in a) + ' ' + ('1.5' in a) + ' ' + ('foo' in a)); \
+             const keys = Object.keys(a); \
+             console.log(keys.length + ' ' + keys[0] + ' ' + keys[1] + ' ' + keys[2]); \
+             try { Object.defineProperty(a, '-1', { value: 1 }); } \
+             catch (e) { console.log(e.name); }",
+        );
+        assert_eq!(
+            logged,
+            [
+                "undefined undefined undefined 8",
+                "false false true",
+                "3 0 1 foo",
+                "TypeError"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collection_keys_use_same_value_zero() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const m = new Map(); \
+             m.set(NaN, 'nan'); \
+             m.set(0, 'zero'); \
+             console.log(m.get(NaN) + ' ' + m.get(-0) + ' ' + m.has(NaN) + ' ' + m.size); \
+             m.set(-0, 'again'); \
+             console.log(m.get(0) + ' ' + m.size); \
+             const s = new Set([-0, NaN, NaN, 0]); \
+             console.log(s.size + ' ' + (1 / s.values().next().value));",
+        );
+        assert_eq!(logged, ["nan zero true 2", "again 2", "2 Infinity"]);
+    }
+
+    #[test]
+    fn test_collections_keep_insertion_order() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const m = new Map([['a', 1], ['b', 2], ['c', 3]]); \
+             m.delete('a'); \
+             m.set('a', 4); \
+             m.set('b', 5); \
+             let out = ''; \
+             for (const e of m) { out += e[0] + e[1]; } \
+             console.log(out); \
+             const s = new Set([1, 2, 3]); \
+             s.delete(1); \
+             s.add(1); \
+             s.add(2); \
+             out = ''; \
+             for (const v of s) { out += v; } \
+             console.log(out);",
+        );
+        assert_eq!(logged, ["b5c3a4", "231"]);
+    }
+
+    #[test]
+    fn test_for_of_and_spread_use_symbol_iterator() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const counter = {}; \
+             counter[Symbol.iterator] = function () { \
+               let i = 0; \
+               return { next: function () { i++; return { value: i, done: i > 3 }; } }; \
+             }; \
+             let out = ''; \
+             for (const v of counter) { out += v; } \
+             const spread = [...counter]; \
+             console.log(out + ' ' + spread.length + ' ' + spread[2]); \
+             const m = new Map([[1, 'a']]); \
+             const s = new Set(['x', 'y']); \
+             out = ''; \
+             for (const e of m) { out += e[0] + e[1]; } \
+             for (const v of s) { out += v; } \
+             const both = [...s, ...m.keys()]; \
+             console.log(out + ' ' + both.length + ' ' + both[2]); \
+             const arr = [7, 8]; \
+             arr[Symbol.iterator] = counter[Symbol.iterator]; \
+             out = ''; \
+             for (const v of arr) { out += v; } \
+             console.log(out + ' ' + [...arr].length);",
+        );
+        assert_eq!(logged, ["123 3 3", "1axy 3 1", "123 3"]);
+    }
+
+    #[test]
+    fn test_map_changed_during_iteration() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const m = new Map([['a', 1], ['b', 2], ['c', 3]]); \
+             let out = ''; \
+             for (const e of m) { \
+               out += e[0]; \
+               if (e[0] === 'a') { m.delete('b'); m.set('d', 4); } \
+             } \
+             console.log(out); \
+             out = ''; \
+             for (const e of m) { out += e[0]; m.clear(); } \
+             console.log(out + ' ' + m.size);",
+        );
+        assert_eq!(logged, ["acd", "a 0"]);
+    }
+
+    #[test]
+    fn test_collection_for_each() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const m = new Map([['a', 1], ['b', 2]]); \
+             let out = ''; \
+             m.forEach(function (v, k, map) { \
+               out += k + v + (map === m) + ' '; \
+               if (k === 'a') { map.delete('b'); map.set('c', 3); } \
+             }); \
+             console.log(out); \
+             out = ''; \
+             new Set([1, 2]).forEach(function (v, k) { out += v + '/' + k + this.tag + ' '; }, { tag: 't' }); \
+             console.log(out); \
+             try { new Set().forEach(1); } catch (e) { console.log(e.name); }",
+        );
+        assert_eq!(logged, ["a1true c3true ", "1/1t 2/2t ", "TypeError"]);
+    }
+
+    #[test]
+    fn test_accessors_in_object_literals_and_classes() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const o = { _v: 1, get v() { return this._v * 2; }, set v(x) { this._v = x; } }; \
+             o.v = 5; \
+             console.log(o.v + ' ' + o._v); \
+             class T { \
+               constructor() { this._n = 1; } \
+               get n() { return this._n; } \
+               set n(v) { this._n = v + 1; } \
+               static get kind() { return 'k'; } \
+             } \
+             const t = new T(); \
+             t.n = 4; \
+             console.log(t.n + ' ' + T.kind + ' ' + Object.getOwnPropertyDescriptor(t, 'n'));",
+        );
+        assert_eq!(logged, ["10 5", "5 k undefined"]);
+    }
+
+    #[test]
+    fn test_define_property_rejects_invalid_changes() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const o = {}; \
+             Object.defineProperty(o, 'x', { value: 1 }); \
+             try { Object.defineProperty(o, 'x', { value: 2 }); } catch (e) { console.log(e.name); } \
+             try { Object.defineProperty(o, 'x', { get: function () { return 3; } }); } \
+             catch (e) { console.log(e.name); } \
+             try { Object.defineProperty(o, 'x', { enumerable: true }); } catch (e) { console.log(e.name); } \
+             console.log(o.x); \
+             try { Object.defineProperty(o, 'y', { value: 1, get: function () {} }); } \
+             catch (e) { console.log(e.name + ' ' + ('y' in o)); }",
+        );
+        assert_eq!(
+            logged,
+            [
+                "TypeError",
+                "TypeError",
+                "TypeError",
+                "1",
+                "TypeError false"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_define_properties_validates_before_applying() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const t = {}; \
+             try { \
+               Object.defineProperties(t, { a: { value: 1 }, b: { value: 2, get: function () {} } }); \
+             } catch (e) { console.log(e.name + ' ' + ('a' in t)); } \
+             Object.defineProperties(t, { a: { value: 1, enumerable: true }, b: { value: 2 } }); \
+             console.log(t.a + ' ' + t.b + ' ' + Object.keys(t).length);",
+        );
+        assert_eq!(logged, ["TypeError false", "1 2 1"]);
+    }
+
+    #[test]
+    fn test_property_descriptor_round_trip() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const o = {}; \
+             Object.defineProperty(o, 'g', { get: function () { return 1; }, enumerable: true }); \
+             const g = Object.getOwnPropertyDescriptor(o, 'g'); \
+             console.log(typeof g.get + ' ' + g.set + ' ' + g.enumerable + ' ' + g.configurable + ' ' + ('value' in g)); \
+             Object.defineProperty(o, 'h', Object.getOwnPropertyDescriptor({ h: 4 }, 'h')); \
+             const h = Object.getOwnPropertyDescriptor(o, 'h'); \
+             console.log(h.value + ' ' + h.writable + ' ' + h.enumerable + ' ' + h.configurable + ' ' + ('get' in h)); \
+             Object.defineProperty(o, 'c', g); \
+             console.log(o.c + ' ' + Object.getOwnPropertyDescriptor(o, 'missing'));",
+        );
+        assert_eq!(
+            logged,
+            [
+                "function undefined true false false",
+                "4 true true true false",
+                "1 undefined"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_date_uses_the_host_clock() {
+        let mut engine = Engine::new();
+        engine.set_clock(fixed_clock);
+        let logged = run(
+            &mut engine,
+            "console.log(Date.now()); \
+             console.log(new Date().toISOString()); \
+             console.log(new Date().getTime() === Date.now());",
+        );
+        assert_eq!(
+            logged,
+            ["1700000000123", "2023-11-14T22:13:20.123Z", "true"]
+        );
+    }
+
+    #[test]
+    fn test_date_components_overflow() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "console.log(new Date(2020, 13, 1).toISOString()); \
+             console.log(new Date(2020, 1, 30).toISOString()); \
+             console.log(new Date(2020, -1, 1, 25).toISOString()); \
+             console.log(Date.UTC(2020, 13, 1) + ' ' + Date.UTC(2020, 0, 2, 3, 4, 5, 678)); \
+             console.log(Date.UTC(2020) + ' ' + Date.UTC(NaN, 0));",
+        );
+        assert_eq!(
+            logged,
+            [
+                "2021-02-01T00:00:00.000Z",
+                "2020-03-01T00:00:00.000Z",
+                "2019-12-02T01:00:00.000Z",
+                "1612137600000 1577934245678",
+                "1577836800000 NaN"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_date_parse() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "console.log(Date.parse('2020-01-02T03:04:05.678Z') + ' ' + Date.parse('2020-01-02')); \
+             console.log(Date.parse('nope') + ' ' + Date.parse('2020-13-01') + ' ' + new Date('nope').getTime()); \
+             const d = new Date('2021-06-15T12:30:45.250Z'); \
+             console.log(d.toISOString() + ' ' + (Date.parse(d.toISOString()) === d.getTime())); \
+             try { new Date(NaN).toISOString(); } catch (e) { console.log(e.name); }",
+        );
+        assert_eq!(
+            logged,
+            [
+                "1577934245678 1577923200000",
+                "NaN NaN NaN",
+                "2021-06-15T12:30:45.250Z true",
+                "RangeError"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_date_utc_getters_and_setters() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            "const d = new Date(Date.UTC(2020, 0, 31, 23, 59, 58, 999)); \
+             console.log(d.getUTCFullYear() + ' ' + d.getUTCMonth() + ' ' + d.getUTCDate() + ' ' + d.getUTCDay()); \
+             console.log(d.getUTCHours() + ' ' + d.getUTCMinutes() + ' ' + d.getUTCSeconds() + ' ' + d.getUTCMilliseconds()); \
+             d.setUTCMonth(1); \
+             console.log(d.toISOString()); \
+             d.setUTCMilliseconds(1001); \
+             d.setUTCHours(24); \
+             console.log(d.toISOString()); \
+             console.log(d.setUTCDate(0)); \
+             d.setUTCFullYear(2021); \
+             console.log(d.toISOString()); \
+             console.log(d.setUTCMinutes(NaN) + ' ' + d.getUTCHours());",
+        );
+        assert_eq!(
+            logged,
+            [
+                "2020 0 31 5",
+                "23 59 58 999",
+                "2020-03-02T23:59:58.999Z",
+                "2020-03-03T00:59:59.001Z",
+                "1582937999001",
+                "2021-03-01T00:59:59.001Z",
+                "NaN NaN"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_literals() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            r#"const a = 1, b = 'x';
+               console.log(`a${a + 1}b${`in${b}ner${`${a}`}`}c`);
+               console.log(`tab\tnl\\n A\x42 \`q\` \${no}`);"#,
+        );
+        assert_eq!(logged, ["a2binxner1c", "tab\tnl\\n AB `q` ${no}"]);
+    }
+
+    #[test]
+    fn test_tagged_template_arguments() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            r#"function tag(strings) {
+                 let out = strings.length + ' ' + (arguments.length - 1);
+                 for (let i = 1; i < arguments.length; i++) { out += ' ' + arguments[i]; }
+                 return out;
+               }
+               console.log(tag`a${1}b${2 + 3}c`);
+               console.log(tag``);
+               function raw(strings) {
+                 return strings[0] + '|' + strings.raw[0] + '|' + strings[1] + '|' + strings.raw[1];
+               }
+               console.log(raw`x\ny${0}\unicode`);"#,
+        );
+        assert_eq!(logged, ["3 2 1 5", "1 0", "x\ny|x\\ny|undefined|\\unicode"]);
+    }
+
+    #[test]
+    fn test_tagged_template_strings_are_cached_and_frozen() {
+        let mut engine = Engine::new();
+        let logged = run(
+            &mut engine,
+            r#"function id(strings) { return strings; }
+               function site() { return id`a${0}b`; }
+               const first = site();
+               console.log((first === site()) + ' ' + (first === id`a${0}b`));
+               let prev;
+               let same = true;
+               for (let i = 0; i < 3; i++) {
+                 const s = id`q`;
+                 if (prev !== undefined && prev !== s) { same = false; }
+                 prev = s;
+               }
+               console.log(same);
+               try { first[0] = 'z'; } catch (e) { console.log(e.name); }
+               try { first.extra = 1; } catch (e) { console.log(e.name); }
+               try { first.raw[1] = 'z'; } catch (e) { console.log(e.name); }
+               const d = Object.getOwnPropertyDescriptor(first, 0);
+               console.log(first[0] + ' ' + first.raw[1] + ' ' + first.length + ' ' + d.writable + ' ' + d.configurable);"#,
+        );
+        assert_eq!(
+            logged,
+            [
+                "true false",
+                "true",
+                "TypeError",
+                "TypeError",
+                "TypeError",
+                "a b 2 false false"
+            ]
+        );
+    }
+}
@@ -25,6 +25,11 @@ pub struct Lexer<'a> {
     token_line: usize,
     /// Start column of current token.
     token_column: usize,
+    /// Open brace count for each enclosing template substitution.
+    ///
+    /// A `}` seen when the innermost count is zero closes the `${` and
+    /// resumes the template.
+    template_braces: Vec<usize>,
 }
 
 impl<'a> Lexer<'a> {
@@ -39,6 +44,7 @@ impl<'a> Lexer<'a> {
             token_start: 0,
             token_line: 1,
             token_column: 1,
+            template_braces: Vec::new(),
         }
     }
 
@@ -84,7 +90,11 @@ impl<'a> Lexer<'a> {
 
         // Template literal
         if ch == '`' {
-            return self.scan_template();
+            return self.scan_template(true);
+        }
+        if ch == '}' && self.template_braces.last() == Some(&0) {
+            self.template_braces.pop();
+            return self.scan_template(false);
         }
 
         // Identifier or keyword
@@ -321,60 +331,133 @@ impl<'a> Lexer<'a> {
         Ok(self.make_token(TokenKind::String(value)))
     }
 
-    /// Scan a template literal.
-    fn scan_template(&mut self) -> JsResult<Token> {
-        self.advance(); // `
-        let mut value = String::new();
-
-        while !self.is_eof() && self.current() != '`' {
-            if self.current() == '$' && self.peek() == '{' {
-                // TODO: Handle template expressions
-                value.push(self.current());
-                self.advance();
-            } else if self.current() == '\\' {
-                self.advance();
-                match self.current() {
-                    'n' => {
-                        value.push('\n');
-                        self.advance();
-                    }
-                    't' => {
-                        value.push('\t');
-                        self.advance();
-                    }
-                    '`' => {
-                        value.push('`');
-                        self.advance();
-                    }
-                    '$' => {
-                        value.push('$');
-                        self.advance();
-                    }
-                    '\\' => {
-                        value.push('\\');
-                        self.advance();
+    /// Scan a template chunk, starting at the opening backtick or at the
+    /// `}` that closes a substitution.
+    ///
+    /// The chunk ends at the closing backtick or at `${`. Invalid escapes
+    /// are not an error here, since tagged templates still see the raw
+    /// text; they only leave the cooked value empty.
+    fn scan_template(&mut self, head: bool) -> JsResult<Token> {
+        self.advance(); // ` or }
+        let mut raw = String::new();
+        let mut cooked = Some(String::new());
+        let mut run_start = self.pos;
+
+        let tail = loop {
+            if self.is_eof() {
+                return Err(JsError::syntax("Unterminated template literal"));
+            }
+            match self.current() {
+                '`' => break true,
+                '$' if self.peek() == '{' => break false,
+                '\\' => {
+                    let run = normalize_line_terminators(&self.source[run_start..self.pos]);
+                    raw.push_str(&run);
+                    if let Some(cooked) = cooked.as_mut() {
+                        cooked.push_str(&run);
                     }
-                    ch => {
-                        value.push(ch);
-                        self.advance();
+
+                    let escape_start = self.pos;
+                    self.advance();
+                    let escaped = self.scan_template_escape();
+                    raw.push_str(&normalize_line_terminators(
+                        &self.source[escape_start..self.pos],
+                    ));
+                    match (cooked.as_mut(), escaped) {
+                        (Some(cooked), Ok(Some(ch))) => cooked.push(ch),
+                        (_, Ok(None)) | (None, _) => {}
+                        (Some(_), Err(())) => cooked = None,
                     }
+                    run_start = self.pos;
                 }
-            } else {
-                if self.current() == '\n' {
+                '\n' => {
                     self.line += 1;
                     self.column = 0;
+                    self.advance();
                 }
-                value.push(self.current());
-                self.advance();
+                _ => self.advance(),
             }
+        };
+
+        let run = normalize_line_terminators(&self.source[run_start..self.pos]);
+        raw.push_str(&run);
+        if let Some(cooked) = cooked.as_mut() {
+            cooked.push_str(&run);
         }
 
-        if self.is_eof() {
-            return Err(JsError::syntax("Unterminated template literal"));
+        if tail {
+            self.advance(); // `
+        } else {
+            self.advance(); // $
+            self.advance(); // {
+            self.template_braces.push(0);
         }
 
-        self.advance(); // `
-        Ok(self.make_token(TokenKind::Template(value)))
+        Ok(self.make_token(TokenKind::Template {
+            cooked,
+            raw,
+            head,
+            tail,
+        }))
+    }
+
+    /// Scan the escape sequence after a `\` in a template.
+    ///
+    /// Returns `Ok(None)` for a line continuation and `Err(())` for an
+    /// escape that is not valid in a template.
+    fn scan_template_escape(&mut self) -> Result<Option<char>, ()> {
+        let ch = match self.current() {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'v' => '\u{b}',
+            '0' if !self.peek().is_ascii_digit() => '\0',
+            '1'..='9' | '0' => return Err(()),
+            'x' => {
+                self.advance();
+                let hex = self.scan_hex_digits(2).map_err(|_| ())?;
+                return char::from_u32(hex).map(Some).ok_or(());
+            }
+            'u' => {
+                self.advance();
+                let code = if self.current() == '{' {
+                    self.advance();
+                    let start = self.pos;
+                    while self.current().is_ascii_hexdigit() {
+                        self.advance();
+                    }
+                    let digits = &self.source[start..self.pos];
+                    if self.current() != '}' || digits.is_empty() {
+                        return Err(());
+                    }
+                    self.advance(); // }
+                    u32::from_str_radix(digits, 16).map_err(|_| ())?
+                } else {
+                    self.scan_hex_digits(4).map_err(|_| ())?
+                };
+                return char::from_u32(code).map(Some).ok_or(());
+            }
+            '\r' | '\n' => {
+                if self.current() == '\r' && self.peek() == '\n' {
+                    self.advance();
+                }
+                self.advance();
+                self.line += 1;
+                self.column = 1;
+                return Ok(None);
+            }
+            _ => {
+                let ch = self.source[self.pos..].chars().next().unwrap_or('\0');
+                for _ in 0..ch.len_utf8() {
+                    self.advance();
+                }
+                return Ok(Some(ch));
+            }
+        };
+        self.advance();
+        Ok(Some(ch))
     }
 
     /// Scan an identifier or keyword.
@@ -411,8 +494,18 @@ impl<'a> Lexer<'a> {
         self.advance();
 
         let kind = match ch {
-            '{' => TokenKind::LeftBrace,
-            '}' => TokenKind::RightBrace,
+            '{' => {
+                if let Some(depth) = self.template_braces.last_mut() {
+                    *depth += 1;
+                }
+                TokenKind::LeftBrace
+            }
+            '}' => {
+                if let Some(depth) = self.template_braces.last_mut() {
+                    *depth -= 1;
+                }
+                TokenKind::RightBrace
+            }
             '(' => TokenKind::LeftParen,
             ')' => TokenKind::RightParen,
             '[' => TokenKind::LeftBracket,
@@ -685,6 +778,11 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Replace `\r\n` and lone `\r` with `\n`, as template text requires.
+fn normalize_line_terminators(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
 /// Check if character is whitespace.
 fn is_whitespace(ch: char) -> bool {
     matches!(ch, ' ' | '\t' | '\n' | '\r' | '\x0B' | '\x0C')
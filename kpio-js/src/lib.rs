@@ -14,6 +14,8 @@
 //! - `value`: JavaScript value representation
 //! - `object`: Object and property handling
 //! - `builtin`: Built-in objects and functions
+//! - `console`: Console messages buffered for the host
+//! - `date`: Date and time arithmetic for the `Date` built-in
 //! - `gc`: Simple mark-and-sweep garbage collector
 //! - `dom`: DOM binding interface for browser integration
 //!
@@ -34,6 +36,8 @@ extern crate alloc;
 
 pub mod ast;
 pub mod builtin;
+pub mod console;
+pub mod date;
 pub mod dom;
 pub mod error;
 pub mod gc;
@@ -46,6 +50,7 @@ pub mod value;
 
 use alloc::string::String;
 
+pub use console::{ConsoleLevel, ConsoleMessage};
 pub use error::{JsError, JsResult};
 pub use interpreter::Engine;
 pub use value::Value;
@@ -7,10 +7,12 @@ use alloc::rc::Rc;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cell::RefCell;
+use libm::{floor, trunc};
 
 use crate::ast::BlockStmt;
 use crate::error::{JsError, JsResult};
-use crate::value::{Symbol, Value};
+use crate::interpreter::{AsyncFrame, Interpreter};
+use crate::value::{is_canonical_numeric_string, Symbol, Value};
 
 /// Property key (string or symbol).
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -75,9 +77,9 @@ pub struct PropertyDescriptor {
     pub value: Option<Value>,
     /// Whether property is writable.
     pub writable: Option<bool>,
-    /// Getter function.
+    /// Getter function (`Some(undefined)` for an accessor without one).
     pub get: Option<Value>,
-    /// Setter function.
+    /// Setter function (`Some(undefined)` for an accessor without one).
     pub set: Option<Value>,
     /// Whether property is enumerable.
     pub enumerable: Option<bool>,
@@ -124,6 +126,87 @@ impl PropertyDescriptor {
     pub fn is_accessor(&self) -> bool {
         self.get.is_some() || self.set.is_some()
     }
+
+    /// Fill in the defaults of a newly defined property.
+    fn complete(mut self) -> Self {
+        if self.is_accessor() {
+            self.get.get_or_insert(Value::undefined());
+            self.set.get_or_insert(Value::undefined());
+        } else {
+            self.value.get_or_insert(Value::undefined());
+            self.writable.get_or_insert(false);
+        }
+        self.enumerable.get_or_insert(false);
+        self.configurable.get_or_insert(false);
+        self
+    }
+
+    /// Check whether this update may be applied to a non-configurable
+    /// property described by `current`.
+    ///
+    /// Only fields that leave the property unchanged are accepted, except
+    /// that a writable data property may change its value or become
+    /// read-only.
+    fn can_redefine(&self, current: &PropertyDescriptor) -> bool {
+        fn unchanged(update: &Option<Value>, current: &Option<Value>) -> bool {
+            update
+                .as_ref()
+                .is_none_or(|v| current.as_ref().is_some_and(|c| v.same_value(c)))
+        }
+
+        if self.configurable == Some(true) {
+            return false;
+        }
+        if self.enumerable.is_some() && self.enumerable != current.enumerable {
+            return false;
+        }
+        if current.is_accessor() {
+            !self.is_data()
+                && unchanged(&self.get, &current.get)
+                && unchanged(&self.set, &current.set)
+        } else if self.is_accessor() {
+            false
+        } else if current.writable == Some(false) {
+            self.writable != Some(true) && unchanged(&self.value, &current.value)
+        } else {
+            true
+        }
+    }
+
+    /// Merge the fields present in `update`, switching between a data and
+    /// an accessor property if the update is of the other kind.
+    fn apply(&mut self, update: PropertyDescriptor) {
+        if update.is_accessor() && !self.is_accessor() {
+            self.value = None;
+            self.writable = None;
+            self.get = Some(Value::undefined());
+            self.set = Some(Value::undefined());
+        } else if update.is_data() && self.is_accessor() {
+            self.get = None;
+            self.set = None;
+            self.value = Some(Value::undefined());
+            self.writable = Some(false);
+        }
+
+        if update.value.is_some() {
+            self.value = update.value;
+        }
+        if update.writable.is_some() {
+            self.writable = update.writable;
+        }
+        if update.get.is_some() {
+            self.get = update.get;
+        }
+        if update.set.is_some() {
+            self.set = update.set;
+        }
+        if update.enumerable.is_some() {
+            self.enumerable = update.enumerable;
+        }
+        if update.configurable.is_some() {
+            self.configurable = update.configurable;
+        }
+    }
 }
 
 impl Default for PropertyDescriptor {
@@ -139,6 +222,22 @@ impl Default for PropertyDescriptor {
     }
 }
 
+/// Error for a forbidden change to a non-configurable property.
+fn redefine_error(key: &PropertyKey) -> JsError {
+    JsError::type_error(alloc::format!(
+        "Cannot redefine property: {}",
+        key.to_string()
+    ))
+}
+
+/// Error for adding a property to a non-extensible object.
+fn not_extensible_error(key: &PropertyKey) -> JsError {
+    JsError::type_error(alloc::format!(
+        "Cannot define property {}, object is not extensible",
+        key.to_string()
+    ))
+}
+
 /// Property storage.
 #[derive(Clone, Debug)]
 pub struct Property {
@@ -148,8 +247,437 @@ pub struct Property {
     pub descriptor: PropertyDescriptor,
 }
 
+/// Backing store for `Map` and `Set` objects.
+///
+/// Entries are kept in insertion order and compared with SameValueZero.
+/// Deleting an entry leaves a tombstone so that live iterators keep
+/// their position.  `Set` stores each member as both key and value.
+#[derive(Clone, Debug, Default)]
+pub struct CollectionData {
+    /// Entries in insertion order (`None` = deleted).
+    entries: Vec<Option<(Value, Value)>>,
+    /// Number of live entries.
+    size: usize,
+}
+
+impl CollectionData {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of live entries.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Check if the collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn position(&self, key: &Value) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.as_ref().is_some_and(|(k, _)| k.same_value_zero(key)))
+    }
+
+    /// Look up the value stored under `key`.
+    pub fn get(&self, key: &Value) -> Option<Value> {
+        let index = self.position(key)?;
+        self.entries[index].as_ref().map(|(_, v)| v.clone())
+    }
+
+    /// Check if `key` is present.
+    pub fn has(&self, key: &Value) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Insert or update an entry.  A new key is appended at the end.
+    pub fn insert(&mut self, key: Value, value: Value) {
+        if let Some(index) = self.position(&key) {
+            if let Some(entry) = &mut self.entries[index] {
+                entry.1 = value;
+            }
+            return;
+        }
+        // -0 is normalised to +0 so that it is reported back as 0.
+        let key = match key {
+            Value::Number(n) if n == 0.0 => Value::Number(0.0),
+            other => other,
+        };
+        self.entries.push(Some((key, value)));
+        self.size += 1;
+    }
+
+    /// Remove an entry, returning whether it was present.
+    pub fn remove(&mut self, key: &Value) -> bool {
+        match self.position(key) {
+            Some(index) => {
+                self.entries[index] = None;
+                self.size -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all entries.
+    pub fn clear(&mut self) {
+        for entry in &mut self.entries {
+            *entry = None;
+        }
+        self.size = 0;
+    }
+
+    /// Find the first live entry at or after `index`.
+    ///
+    /// Returns the entry's slot together with its key and value.
+    pub fn next_entry(&self, index: usize) -> Option<(usize, Value, Value)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .skip(index)
+            .find_map(|(i, entry)| entry.as_ref().map(|(k, v)| (i, k.clone(), v.clone())))
+    }
+}
+
+/// What a built-in iterator yields for each entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IterationKind {
+    /// Keys (array indices for arrays).
+    Keys,
+    /// Values.
+    Values,
+    /// `[key, value]` pairs.
+    Entries,
+}
+
+/// Internal state of a built-in iterator object.
+#[derive(Clone, Debug)]
+pub struct IteratorState {
+    /// Array, string, `Map` or `Set` being iterated; `None` once exhausted.
+    pub source: Option<Value>,
+    /// What each step yields.
+    pub kind: IterationKind,
+    /// Next position in the source.
+    pub index: usize,
+}
+
+/// Settlement state of a promise.
+#[derive(Clone, Debug)]
+pub enum PromiseStatus {
+    /// Not yet settled.
+    Pending,
+    /// Fulfilled with a value.
+    Fulfilled(Value),
+    /// Rejected with a reason.
+    Rejected(Value),
+}
+
+/// A `then` registration waiting for its promise to settle.
+#[derive(Clone, Debug)]
+pub struct PromiseReaction {
+    /// Handler run on fulfillment (`undefined` passes the value through).
+    pub on_fulfilled: Value,
+    /// Handler run on rejection (`undefined` passes the reason through).
+    pub on_rejected: Value,
+    /// Promise returned by `then`, settled with the handler's outcome.
+    pub derived: Option<Rc<RefCell<JsObject>>>,
+}
+
+/// Internal state of a `Promise` object.
+#[derive(Clone, Debug)]
+pub struct PromiseState {
+    /// Current settlement state.
+    pub status: PromiseStatus,
+    /// Reactions registered while pending.
+    pub reactions: Vec<PromiseReaction>,
+    /// Set once a resolving function has been used, so that later calls
+    /// to `resolve`/`reject` are ignored.
+    pub already_resolved: bool,
+}
+
+impl PromiseState {
+    /// Create a pending promise state.
+    pub fn new() -> Self {
+        PromiseState {
+            status: PromiseStatus::Pending,
+            reactions: Vec::new(),
+            already_resolved: false,
+        }
+    }
+
+    /// Check if the promise is still pending.
+    pub fn is_pending(&self) -> bool {
+        matches!(self.status, PromiseStatus::Pending)
+    }
+}
+
+impl Default for PromiseState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Element type of a typed array (also the access width of `DataView`
+/// methods).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypedArrayKind {
+    /// `Int8Array`
+    Int8,
+    /// `Uint8Array`
+    Uint8,
+    /// `Uint8ClampedArray`
+    Uint8Clamped,
+    /// `Int16Array`
+    Int16,
+    /// `Uint16Array`
+    Uint16,
+    /// `Int32Array`
+    Int32,
+    /// `Uint32Array`
+    Uint32,
+    /// `Float32Array`
+    Float32,
+    /// `Float64Array`
+    Float64,
+}
+
+impl TypedArrayKind {
+    /// Name of the typed array constructor.
+    pub fn name(self) -> &'static str {
+        match self {
+            TypedArrayKind::Int8 => "Int8Array",
+            TypedArrayKind::Uint8 => "Uint8Array",
+            TypedArrayKind::Uint8Clamped => "Uint8ClampedArray",
+            TypedArrayKind::Int16 => "Int16Array",
+            TypedArrayKind::Uint16 => "Uint16Array",
+            TypedArrayKind::Int32 => "Int32Array",
+            TypedArrayKind::Uint32 => "Uint32Array",
+            TypedArrayKind::Float32 => "Float32Array",
+            TypedArrayKind::Float64 => "Float64Array",
+        }
+    }
+
+    /// Size of one element in bytes.
+    pub fn element_size(self) -> usize {
+        match self {
+            TypedArrayKind::Int8 | TypedArrayKind::Uint8 | TypedArrayKind::Uint8Clamped => 1,
+            TypedArrayKind::Int16 | TypedArrayKind::Uint16 => 2,
+            TypedArrayKind::Int32 | TypedArrayKind::Uint32 | TypedArrayKind::Float32 => 4,
+            TypedArrayKind::Float64 => 8,
+        }
+    }
+
+    /// Decode one element from the first `element_size` bytes of `bytes`.
+    pub fn read(self, bytes: &[u8], little_endian: bool) -> f64 {
+        let size = self.element_size();
+        let mut raw = [0u8; 8];
+        raw[..size].copy_from_slice(&bytes[..size]);
+        if !little_endian {
+            raw[..size].reverse();
+        }
+        let [b0, b1, b2, b3, ..] = raw;
+        match self {
+            TypedArrayKind::Int8 => b0 as i8 as f64,
+            TypedArrayKind::Uint8 | TypedArrayKind::Uint8Clamped => b0 as f64,
+            TypedArrayKind::Int16 => i16::from_le_bytes([b0, b1]) as f64,
+            TypedArrayKind::Uint16 => u16::from_le_bytes([b0, b1]) as f64,
+            TypedArrayKind::Int32 => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            TypedArrayKind::Uint32 => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            TypedArrayKind::Float32 => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            TypedArrayKind::Float64 => f64::from_le_bytes(raw),
+        }
+    }
+
+    /// Encode `value` into the first `element_size` bytes of `bytes`.
+    ///
+    /// Integer types wrap modulo 2^n (ToInt8, ToUint16, ...), except
+    /// `Uint8Clamped` which saturates and rounds half to even.
+    pub fn write(self, bytes: &mut [u8], value: f64, little_endian: bool) {
+        let size = self.element_size();
+        let mut raw = match self {
+            TypedArrayKind::Float64 => value.to_le_bytes(),
+            TypedArrayKind::Float32 => {
+                let [b0, b1, b2, b3] = (value as f32).to_le_bytes();
+                [b0, b1, b2, b3, 0, 0, 0, 0]
+            }
+            TypedArrayKind::Uint8Clamped => [clamp_u8(value), 0, 0, 0, 0, 0, 0, 0],
+            // The low bytes of the value modulo 2^32 are the value
+            // modulo 2^8 and 2^16 as well.
+            _ => (wrap_u32(value) as u64).to_le_bytes(),
+        };
+        if !little_endian {
+            raw[..size].reverse();
+        }
+        bytes[..size].copy_from_slice(&raw[..size]);
+    }
+}
+
+/// Reduce a number modulo 2^32 (the integer part of ToUint32).
+fn wrap_u32(value: f64) -> u32 {
+    if !value.is_finite() {
+        return 0;
+    }
+    const MODULUS: f64 = 4294967296.0;
+    let mut wrapped = trunc(value) % MODULUS;
+    if wrapped < 0.0 {
+        wrapped += MODULUS;
+    }
+    wrapped as u32
+}
+
+/// ToUint8Clamp: saturate to 0..=255 and round half to even.
+fn clamp_u8(value: f64) -> u8 {
+    if value.is_nan() || value <= 0.0 {
+        return 0;
+    }
+    if value >= 255.0 {
+        return 255;
+    }
+    let whole = floor(value);
+    let fraction = value - whole;
+    if fraction > 0.5 || (fraction == 0.5 && whole % 2.0 != 0.0) {
+        whole as u8 + 1
+    } else {
+        whole as u8
+    }
+}
+
+/// Typed arrays use the platform byte order.
+const NATIVE_LITTLE_ENDIAN: bool = cfg!(target_endian = "little");
+
+/// Read a `kind` element at byte `start` of an `ArrayBuffer` object.
+fn load_element(
+    buffer: &Rc<RefCell<JsObject>>,
+    start: usize,
+    kind: TypedArrayKind,
+    little_endian: bool,
+) -> Option<f64> {
+    let buffer = buffer.borrow();
+    let bytes = buffer.array_buffer_bytes()?;
+    let end = start.checked_add(kind.element_size())?;
+    bytes.get(start..end).map(|b| kind.read(b, little_endian))
+}
+
+/// Write a `kind` element at byte `start` of an `ArrayBuffer` object,
+/// returning `false` if it does not fit.
+fn store_element(
+    buffer: &Rc<RefCell<JsObject>>,
+    start: usize,
+    kind: TypedArrayKind,
+    value: f64,
+    little_endian: bool,
+) -> bool {
+    let mut buffer = buffer.borrow_mut();
+    let end = match start.checked_add(kind.element_size()) {
+        Some(end) => end,
+        None => return false,
+    };
+    match buffer
+        .array_buffer_bytes_mut()
+        .and_then(|bytes| bytes.get_mut(start..end))
+    {
+        Some(bytes) => {
+            kind.write(bytes, value, little_endian);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Internal state of a typed array: a window onto an `ArrayBuffer`.
+///
+/// Views share the buffer object, so writes through one view are seen
+/// by every other view of the same buffer.
+#[derive(Clone, Debug)]
+pub struct TypedArrayData {
+    /// Element type.
+    pub kind: TypedArrayKind,
+    /// The `ArrayBuffer` object holding the bytes.
+    pub buffer: Rc<RefCell<JsObject>>,
+    /// Offset of the first element in the buffer.
+    pub byte_offset: usize,
+    /// Length in elements.
+    pub length: usize,
+}
+
+impl TypedArrayData {
+    /// Length in bytes.
+    pub fn byte_length(&self) -> usize {
+        self.length * self.kind.element_size()
+    }
+
+    /// Read the element at `index`, or `None` past the end.
+    pub fn get(&self, index: usize) -> Option<f64> {
+        if index >= self.length {
+            return None;
+        }
+        let start = self.byte_offset + index * self.kind.element_size();
+        load_element(&self.buffer, start, self.kind, NATIVE_LITTLE_ENDIAN)
+    }
+
+    /// Write the element at `index`, returning `false` past the end.
+    pub fn set(&self, index: usize, value: f64) -> bool {
+        if index >= self.length {
+            return false;
+        }
+        let start = self.byte_offset + index * self.kind.element_size();
+        store_element(&self.buffer, start, self.kind, value, NATIVE_LITTLE_ENDIAN)
+    }
+
+    /// Read every element.
+    pub fn to_vec(&self) -> Vec<f64> {
+        (0..self.length).filter_map(|i| self.get(i)).collect()
+    }
+}
+
+/// Internal state of a `DataView`.
+#[derive(Clone, Debug)]
+pub struct DataViewData {
+    /// The `ArrayBuffer` object holding the bytes.
+    pub buffer: Rc<RefCell<JsObject>>,
+    /// Offset of the view in the buffer.
+    pub byte_offset: usize,
+    /// Length of the view in bytes.
+    pub byte_length: usize,
+}
+
+impl DataViewData {
+    /// Read a `kind` value at `offset` bytes into the view, or `None` if
+    /// it does not fit.
+    pub fn get(&self, kind: TypedArrayKind, offset: usize, little_endian: bool) -> Option<f64> {
+        if offset.checked_add(kind.element_size())? > self.byte_length {
+            return None;
+        }
+        load_element(&self.buffer, self.byte_offset + offset, kind, little_endian)
+    }
+
+    /// Write a `kind` value at `offset` bytes into the view, returning
+    /// `false` if it does not fit.
+    pub fn set(
+        &self,
+        kind: TypedArrayKind,
+        offset: usize,
+        value: f64,
+        little_endian: bool,
+    ) -> bool {
+        match offset.checked_add(kind.element_size()) {
+            Some(end) if end <= self.byte_length => store_element(
+                &self.buffer,
+                self.byte_offset + offset,
+                kind,
+                value,
+                little_endian,
+            ),
+            _ => false,
+        }
+    }
+}
+
 /// Object type classification.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum ObjectKind {
     /// Ordinary object.
     Ordinary,
@@ -174,17 +702,26 @@ pub enum ObjectKind {
     /// Error object.
     Error { name: String, message: String },
     /// Map object.
-    Map,
+    Map(CollectionData),
     /// Set object.
-    Set,
+    Set(CollectionData),
+    /// Built-in iterator (array, string, `Map` and `Set` iterators).
+    Iterator(IteratorState),
     /// WeakMap object.
     WeakMap,
     /// WeakSet object.
     WeakSet,
     /// ArrayBuffer object.
     ArrayBuffer(Vec<u8>),
+    /// Typed array view (`Uint8Array`, `Float64Array`, ...).
+    TypedArray(TypedArrayData),
+    /// DataView object.
+    DataView(DataViewData),
     /// Promise object.
-    Promise,
+    Promise(PromiseState),
+    /// Async function call suspended at `await`, taken by the handler
+    /// that resumes it.
+    Suspended(Option<Box<AsyncFrame>>),
     /// Proxy object.
     Proxy,
     /// Arguments object.
@@ -372,11 +909,39 @@ impl JsObject {
         obj
     }
 
+    /// Create an `ArrayBuffer` object holding `bytes`.
+    pub fn array_buffer(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let mut obj = JsObject::new();
+        obj.kind = ObjectKind::ArrayBuffer(bytes);
+        obj.define_property(
+            PropertyKey::string("byteLength"),
+            PropertyDescriptor::data(Value::number(len as f64), false, false, true),
+        );
+        obj
+    }
+
     /// Get the object kind.
     pub fn kind(&self) -> &ObjectKind {
         &self.kind
     }
 
+    /// Get the object kind mutably.
+    pub fn kind_mut(&mut self) -> &mut ObjectKind {
+        &mut self.kind
+    }
+
+    /// Replace the object kind (used by built-in constructors to
+    /// initialise the `this` object created by `new`).
+    pub fn set_kind(&mut self, kind: ObjectKind) {
+        self.kind = kind;
+    }
+
+    /// Mark whether the object can be used with `new`.
+    pub fn set_constructable(&mut self, constructable: bool) {
+        self.constructable = constructable;
+    }
+
     /// Check if object is callable.
     pub fn is_callable(&self) -> bool {
         self.callable.is_some()
@@ -392,6 +957,22 @@ impl JsObject {
         matches!(self.kind, ObjectKind::Array)
     }
 
+    /// Bytes of an `ArrayBuffer` object.
+    pub fn array_buffer_bytes(&self) -> Option<&[u8]> {
+        match &self.kind {
+            ObjectKind::ArrayBuffer(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Bytes of an `ArrayBuffer` object, mutably.
+    pub fn array_buffer_bytes_mut(&mut self) -> Option<&mut [u8]> {
+        match &mut self.kind {
+            ObjectKind::ArrayBuffer(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
     /// Get the callable.
     pub fn callable(&self) -> Option<&Callable> {
         self.callable.as_ref()
@@ -407,10 +988,28 @@ impl JsObject {
         self.prototype.as_ref()
     }
 
+    /// Whether `key` is a numeric string such as `"-1"` or `"1.5"` on a
+    /// typed array. These never name an element, and never reach ordinary
+    /// properties either.
+    fn is_typed_array_miss(&self, key: &PropertyKey) -> bool {
+        matches!(&self.kind, ObjectKind::TypedArray(_))
+            && matches!(key, PropertyKey::String(s) if is_canonical_numeric_string(s))
+    }
+
     /// Get a property.
     pub fn get(&self, key: &PropertyKey) -> JsResult<Value> {
+        if self.is_typed_array_miss(key) {
+            return Ok(Value::undefined());
+        }
+
         // Check array elements first
         if let PropertyKey::Index(i) = key {
+            if let ObjectKind::TypedArray(view) = &self.kind {
+                return Ok(view
+                    .get(*i as usize)
+                    .map(Value::number)
+                    .unwrap_or(Value::undefined()));
+            }
             if let Some(Some(v)) = self.elements.get(*i as usize) {
                 return Ok(v.clone());
             }
@@ -422,7 +1021,7 @@ impl JsObject {
                 if let Some(ref v) = prop.descriptor.value {
                     return Ok(v.clone());
                 }
-                // TODO: Call getter
+                // Getters need the interpreter; see `Interpreter::get_property`
                 return Ok(Value::undefined());
             }
         }
@@ -437,10 +1036,24 @@ impl JsObject {
 
     /// Set a property.
     pub fn set(&mut self, key: PropertyKey, value: Value) -> JsResult<()> {
+        if self.is_typed_array_miss(&key) {
+            return Ok(());
+        }
+
         // Handle array elements
         if let PropertyKey::Index(i) = key {
             let i = i as usize;
-            if self.is_array() {
+            if let ObjectKind::TypedArray(view) = &self.kind {
+                // Writes past the end are silently dropped
+                view.set(i, value.to_number()?);
+                return Ok(());
+            }
+            // Elements redefined with other attributes live in the property
+            // list, which checks them below
+            if self.is_array() && !self.properties.iter().any(|p| p.key == key) {
+                if !matches!(self.elements.get(i), Some(Some(_))) && !self.extensible {
+                    return Err(not_extensible_error(&key));
+                }
                 // Extend elements if needed
                 while self.elements.len() <= i {
                     self.elements.push(None);
@@ -460,6 +1073,10 @@ impl JsObject {
         // Check for existing property
         for prop in &mut self.properties {
             if prop.key == key {
+                if prop.descriptor.is_accessor() {
+                    // Setters need the interpreter; see `Interpreter::set_property`
+                    return Err(JsError::type_error("Cannot assign to accessor property"));
+                }
                 if prop.descriptor.writable == Some(false) {
                     return Err(JsError::type_error("Cannot assign to read-only property"));
                 }
@@ -515,10 +1132,190 @@ impl JsObject {
         self.properties.push(Property { key, descriptor });
     }
 
+    /// Define a property with the semantics of `Object.defineProperty`.
+    ///
+    /// Fields missing from `descriptor` keep their value on an existing
+    /// property and default to `false`/`undefined` on a new one. Array
+    /// elements that need an accessor or non-default attributes move from
+    /// the element storage to the property list.
+    pub fn define_own_property(
+        &mut self,
+        key: PropertyKey,
+        descriptor: PropertyDescriptor,
+    ) -> JsResult<()> {
+        if descriptor.is_data() && descriptor.is_accessor() {
+            return Err(JsError::type_error(
+                "Invalid property descriptor. Cannot both specify accessors and a value or writable attribute",
+            ));
+        }
+        if self.is_typed_array_miss(&key) {
+            return Err(redefine_error(&key));
+        }
+
+        if let PropertyKey::Index(i) = key {
+            let i = i as usize;
+            if let ObjectKind::TypedArray(view) = &self.kind {
+                // Typed array elements are always writable, enumerable and
+                // configurable data properties
+                let attributes = [
+                    descriptor.writable,
+                    descriptor.enumerable,
+                    descriptor.configurable,
+                ];
+                if i >= view.length || descriptor.is_accessor() || attributes.contains(&Some(false))
+                {
+                    return Err(redefine_error(&key));
+                }
+                if let Some(v) = descriptor.value {
+                    view.set(i, v.to_number()?);
+                }
+                return Ok(());
+            }
+
+            let element = self.elements.get(i).cloned().flatten();
+            if element.is_some() || (self.is_array() && !self.has_own_property(&key)) {
+                if element.is_none() && !self.extensible {
+                    return Err(not_extensible_error(&key));
+                }
+
+                let plain = !descriptor.is_accessor()
+                    && [
+                        descriptor.writable,
+                        descriptor.enumerable,
+                        descriptor.configurable,
+                    ]
+                    .iter()
+                    .all(|attr| attr.unwrap_or(element.is_some()));
+                if plain {
+                    let value = descriptor.value.or(element).unwrap_or(Value::undefined());
+                    self.store_element(i, value);
+                    return Ok(());
+                }
+
+                // Leave the slot empty so lookups fall through to the
+                // property list
+                self.reserve_element(i);
+                self.elements[i] = None;
+                if let Some(value) = element {
+                    self.properties.push(Property {
+                        key: key.clone(),
+                        descriptor: PropertyDescriptor::data(value, true, true, true),
+                    });
+                }
+            }
+        }
+
+        match self.properties.iter().position(|p| p.key == key) {
+            Some(pos) => {
+                let current = &mut self.properties[pos].descriptor;
+                if current.configurable == Some(false) && !descriptor.can_redefine(current) {
+                    return Err(redefine_error(&key));
+                }
+                current.apply(descriptor);
+            }
+            None => {
+                if !self.extensible {
+                    return Err(not_extensible_error(&key));
+                }
+                self.properties.push(Property {
+                    key,
+                    descriptor: descriptor.complete(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Store an array element.
+    fn store_element(&mut self, index: usize, value: Value) {
+        self.reserve_element(index);
+        self.elements[index] = Some(value);
+    }
+
+    /// Grow the element storage, and the `length` of arrays, to include
+    /// `index`.
+    fn reserve_element(&mut self, index: usize) {
+        if self.elements.len() <= index {
+            self.elements.resize(index + 1, None);
+            if self.is_array() {
+                self.define_property(
+                    PropertyKey::string("length"),
+                    PropertyDescriptor::data(
+                        Value::number(self.elements.len() as f64),
+                        true,
+                        false,
+                        false,
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Get the descriptor of an own property.
+    pub fn get_own_property(&self, key: &PropertyKey) -> Option<PropertyDescriptor> {
+        if self.is_typed_array_miss(key) {
+            return None;
+        }
+
+        if let PropertyKey::Index(i) = key {
+            if let ObjectKind::TypedArray(view) = &self.kind {
+                return view
+                    .get(*i as usize)
+                    .map(|v| PropertyDescriptor::data(Value::number(v), true, true, true));
+            }
+            if let Some(Some(v)) = self.elements.get(*i as usize) {
+                return Some(PropertyDescriptor::data(v.clone(), true, true, true));
+            }
+        }
+
+        self.properties
+            .iter()
+            .find(|p| &p.key == key)
+            .map(|p| p.descriptor.clone())
+    }
+
+    /// Find the accessor that `key` resolves to, if any.
+    ///
+    /// Walks the prototype chain like `get`, but returns `None` as soon as
+    /// the key resolves to a data property.
+    pub fn lookup_accessor(&self, key: &PropertyKey) -> Option<PropertyDescriptor> {
+        if self.is_typed_array_miss(key) {
+            return None;
+        }
+
+        if let PropertyKey::Index(i) = key {
+            if let ObjectKind::TypedArray(_) = &self.kind {
+                return None;
+            }
+            if let Some(Some(_)) = self.elements.get(*i as usize) {
+                return None;
+            }
+        }
+
+        if let Some(prop) = self.properties.iter().find(|p| &p.key == key) {
+            return prop
+                .descriptor
+                .is_accessor()
+                .then(|| prop.descriptor.clone());
+        }
+
+        self.prototype
+            .as_ref()
+            .and_then(|proto| proto.borrow().lookup_accessor(key))
+    }
+
     /// Check if object has own property.
     pub fn has_own_property(&self, key: &PropertyKey) -> bool {
+        if self.is_typed_array_miss(key) {
+            return false;
+        }
+
         // Check array elements
         if let PropertyKey::Index(i) = key {
+            if let ObjectKind::TypedArray(view) = &self.kind {
+                return (*i as usize) < view.length;
+            }
             if let Some(Some(_)) = self.elements.get(*i as usize) {
                 return true;
             }
@@ -532,6 +1329,9 @@ impl JsObject {
         if self.has_own_property(key) {
             return true;
         }
+        if self.is_typed_array_miss(key) {
+            return false;
+        }
 
         if let Some(proto) = &self.prototype {
             return proto.borrow().has(key);
@@ -544,8 +1344,8 @@ impl JsObject {
     pub fn delete(&mut self, key: &PropertyKey) -> bool {
         // Check array elements
         if let PropertyKey::Index(i) = key {
-            if (*i as usize) < self.elements.len() {
-                self.elements[*i as usize] = None;
+            if let Some(slot @ Some(_)) = self.elements.get_mut(*i as usize) {
+                *slot = None;
                 return true;
             }
         }
@@ -567,11 +1367,10 @@ impl JsObject {
         let mut keys = Vec::new();
 
         // Integer indices first
-        for i in 0..self.elements.len() {
-            if self.elements[i].is_some() {
-                keys.push(PropertyKey::Index(i as u32));
-            }
+        if let ObjectKind::TypedArray(view) = &self.kind {
+            keys.extend((0..view.length).map(|i| PropertyKey::Index(i as u32)));
         }
+        keys.extend(self.element_keys(false));
 
         // String keys
         for prop in &self.properties {
@@ -588,11 +1387,10 @@ impl JsObject {
         let mut keys = Vec::new();
 
         // Integer indices first
-        for i in 0..self.elements.len() {
-            if self.elements[i].is_some() {
-                keys.push(PropertyKey::Index(i as u32));
-            }
+        if let ObjectKind::TypedArray(view) = &self.kind {
+            keys.extend((0..view.length).map(|i| PropertyKey::Index(i as u32)));
         }
+        keys.extend(self.element_keys(true));
 
         // String keys
         for prop in &self.properties {
@@ -606,11 +1404,58 @@ impl JsObject {
         keys
     }
 
+    /// Index keys in ascending order, from both the element storage and
+    /// the property list (elements defined with non-default attributes).
+    fn element_keys(&self, enumerable_only: bool) -> Vec<PropertyKey> {
+        let mut indices: Vec<u32> = self
+            .elements
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.is_some())
+            .map(|(i, _)| i as u32)
+            .collect();
+        let sorted = indices.len();
+        indices.extend(self.properties.iter().filter_map(|prop| match prop.key {
+            PropertyKey::Index(i)
+                if !enumerable_only || prop.descriptor.enumerable == Some(true) =>
+            {
+                Some(i)
+            }
+            _ => None,
+        }));
+        if indices.len() > sorted {
+            indices.sort_unstable();
+        }
+
+        indices.into_iter().map(PropertyKey::Index).collect()
+    }
+
     /// Prevent extensions.
     pub fn prevent_extensions(&mut self) {
         self.extensible = false;
     }
 
+    /// Make every own property read-only and non-configurable, and prevent
+    /// extensions (`Object.freeze`).
+    pub fn freeze(&mut self) {
+        // Elements are always writable, so move them to the property list
+        for (i, slot) in self.elements.iter_mut().enumerate() {
+            if let Some(value) = slot.take() {
+                self.properties.push(Property {
+                    key: PropertyKey::Index(i as u32),
+                    descriptor: PropertyDescriptor::data(value, true, true, true),
+                });
+            }
+        }
+        for prop in &mut self.properties {
+            prop.descriptor.configurable = Some(false);
+            if !prop.descriptor.is_accessor() {
+                prop.descriptor.writable = Some(false);
+            }
+        }
+        self.extensible = false;
+    }
+
     /// Check if extensible.
     pub fn is_extensible(&self) -> bool {
         self.extensible
@@ -668,6 +1513,8 @@ pub enum Callable {
     UserDefined(UserFunction),
     /// Bound function.
     Bound(BoundFunction),
+    /// Native function with access to the interpreter.
+    Intrinsic(IntrinsicFunction),
 }
 
 impl Callable {
@@ -677,6 +1524,7 @@ impl Callable {
             Callable::Native(f) => f.name.clone(),
             Callable::UserDefined(f) => f.name.clone().unwrap_or_default(),
             Callable::Bound(f) => alloc::format!("bound {}", f.target.name()),
+            Callable::Intrinsic(f) => f.name.clone(),
         }
     }
 
@@ -689,6 +1537,7 @@ impl Callable {
                 let target_len = f.target.length();
                 target_len.saturating_sub(f.bound_args.len())
             }
+            Callable::Intrinsic(f) => f.length,
         }
     }
 }
@@ -713,6 +1562,26 @@ impl core::fmt::Debug for NativeFunction {
     }
 }
 
+/// Native function that calls back into script (e.g. `Promise` jobs).
+#[derive(Clone)]
+pub struct IntrinsicFunction {
+    /// Function name.
+    pub name: String,
+    /// Function length.
+    pub length: usize,
+    /// Function pointer.
+    pub func: fn(&mut Interpreter, &Value, &[Value]) -> JsResult<Value>,
+}
+
+impl core::fmt::Debug for IntrinsicFunction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IntrinsicFunction")
+            .field("name", &self.name)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
 /// User-defined function.
 #[derive(Clone, Debug)]
 pub struct UserFunction {
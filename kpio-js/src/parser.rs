@@ -696,11 +696,16 @@ impl<'a> Parser<'a> {
             }));
         }
 
+        let is_async = self.check_async_method();
+        if is_async {
+            self.advance();
+        }
+
         // Method kind
-        let method_kind = if self.check(&TokenKind::Get) && !self.peek_is(&TokenKind::LeftParen) {
+        let method_kind = if !is_async && self.check_modifier(&TokenKind::Get) {
             self.advance();
             MethodKind::Get
-        } else if self.check(&TokenKind::Set) && !self.peek_is(&TokenKind::LeftParen) {
+        } else if !is_async && self.check_modifier(&TokenKind::Set) {
             self.advance();
             MethodKind::Set
         } else {
@@ -735,6 +740,11 @@ impl<'a> Parser<'a> {
             self.expect(&TokenKind::LeftParen)?;
             let params = self.parse_function_params()?;
             self.expect(&TokenKind::RightParen)?;
+            match final_kind {
+                MethodKind::Get => check_accessor_params(true, &params)?,
+                MethodKind::Set => check_accessor_params(false, &params)?,
+                _ => {}
+            }
             let body = self.parse_block()?;
 
             Ok(ClassElement::Method(MethodDef {
@@ -743,7 +753,7 @@ impl<'a> Parser<'a> {
                     id: None,
                     params,
                     body,
-                    is_async: false,
+                    is_async,
                     is_generator: false,
                     span: start.merge(self.prev_span()),
                 },
@@ -1052,38 +1062,80 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// Parse left-hand side expression.
-    fn parse_left_hand_side_expression(&mut self) -> JsResult<Expression> {
+    /// Parse a `new` expression.
+    ///
+    /// The callee is a member expression without calls, so `new Map()`
+    /// constructs `Map` and `new a.B().c()` calls `c` on the new object.
+    fn parse_new_expression(&mut self) -> JsResult<Expression> {
         let start = self.current_span();
+        self.expect(&TokenKind::New)?;
 
-        // New expression
-        if self.check(&TokenKind::New) {
+        let mut callee = if self.check(&TokenKind::New) {
+            self.parse_new_expression()?
+        } else {
+            self.parse_primary_expression()?
+        };
+
+        loop {
+            match &self.current().kind {
+                TokenKind::Dot => {
+                    self.advance();
+                    let property = self.parse_identifier_name()?;
+                    callee = Expression::Member(MemberExpr {
+                        object: Box::new(callee),
+                        property: Box::new(Expression::Identifier(property)),
+                        computed: false,
+                        optional: false,
+                        span: start.merge(self.prev_span()),
+                    });
+                }
+                TokenKind::LeftBracket => {
+                    self.advance();
+                    let property = self.parse_expression()?;
+                    self.expect(&TokenKind::RightBracket)?;
+                    callee = Expression::Member(MemberExpr {
+                        object: Box::new(callee),
+                        property: Box::new(property),
+                        computed: true,
+                        optional: false,
+                        span: start.merge(self.prev_span()),
+                    });
+                }
+                _ => break,
+            }
+        }
+
+        let arguments = if self.check(&TokenKind::LeftParen) {
             self.advance();
-            let callee = self.parse_left_hand_side_expression()?;
+            let args = self.parse_arguments()?;
+            self.expect(&TokenKind::RightParen)?;
+            args
+        } else {
+            Vec::new()
+        };
 
-            let arguments = if self.check(&TokenKind::LeftParen) {
-                self.advance();
-                let args = self.parse_arguments()?;
-                self.expect(&TokenKind::RightParen)?;
-                args
-            } else {
-                Vec::new()
-            };
+        Ok(Expression::New(NewExpr {
+            callee: Box::new(callee),
+            arguments,
+            span: start.merge(self.prev_span()),
+        }))
+    }
 
-            return Ok(Expression::New(NewExpr {
-                callee: Box::new(callee),
-                arguments,
-                span: start.merge(self.prev_span()),
-            }));
-        }
+    /// Parse left-hand side expression.
+    fn parse_left_hand_side_expression(&mut self) -> JsResult<Expression> {
+        let start = self.current_span();
 
-        let mut expr = self.parse_primary_expression()?;
+        let mut expr = if self.check(&TokenKind::New) {
+            self.parse_new_expression()?
+        } else {
+            self.parse_primary_expression()?
+        };
 
         loop {
             match &self.current().kind {
                 TokenKind::Dot => {
                     self.advance();
-                    let property = self.parse_identifier()?;
+                    let property = self.parse_identifier_name()?;
                     expr = Expression::Member(MemberExpr {
                         object: Box::new(expr),
                         property: Box::new(Expression::Identifier(property)),
@@ -1139,7 +1191,7 @@ impl<'a> Parser<'a> {
                             span: start.merge(self.prev_span()),
                         });
                     } else {
-                        let property = self.parse_identifier()?;
+                        let property = self.parse_identifier_name()?;
                         expr = Expression::Member(MemberExpr {
                             object: Box::new(expr),
                             property: Box::new(Expression::Identifier(property)),
@@ -1149,6 +1201,17 @@ impl<'a> Parser<'a> {
                         });
                     }
                 }
+                TokenKind::Template { head: true, .. } => {
+                    let quasi = self.parse_template_literal(true)?;
+                    static NEXT_SITE: core::sync::atomic::AtomicU64 =
+                        core::sync::atomic::AtomicU64::new(0);
+                    expr = Expression::TaggedTemplate(TaggedTemplateExpr {
+                        tag: Box::new(expr),
+                        quasi,
+                        site: NEXT_SITE.fetch_add(1, core::sync::atomic::Ordering::SeqCst),
+                        span: start.merge(self.prev_span()),
+                    });
+                }
                 _ => break,
             }
         }
@@ -1156,6 +1219,57 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    /// Parse a template literal, starting at its head chunk.
+    ///
+    /// Invalid escapes are a syntax error unless the template is tagged,
+    /// in which case the tag sees `undefined` for that cooked string.
+    fn parse_template_literal(&mut self, tagged: bool) -> JsResult<TemplateLiteral> {
+        let start = self.current_span();
+        let mut quasis = Vec::new();
+        let mut expressions = Vec::new();
+
+        loop {
+            let span = self.current_span();
+            let (cooked, raw, tail) = match &self.current().kind {
+                TokenKind::Template {
+                    cooked,
+                    raw,
+                    head,
+                    tail,
+                } if *head == quasis.is_empty() => (cooked.clone(), raw.clone(), *tail),
+                kind => {
+                    return Err(JsError::syntax(alloc::format!(
+                        "Expected template continuation, got {:?}",
+                        kind
+                    )))
+                }
+            };
+            if cooked.is_none() && !tagged {
+                return Err(JsError::syntax(
+                    "Invalid escape sequence in template literal",
+                ));
+            }
+            self.advance();
+
+            quasis.push(TemplateElement {
+                raw,
+                cooked,
+                tail,
+                span,
+            });
+            if tail {
+                break;
+            }
+            expressions.push(self.parse_expression()?);
+        }
+
+        Ok(TemplateLiteral {
+            quasis,
+            expressions,
+            span: start.merge(self.prev_span()),
+        })
+    }
+
     /// Parse primary expression.
     fn parse_primary_expression(&mut self) -> JsResult<Expression> {
         let start = self.current_span();
@@ -1190,19 +1304,8 @@ impl<'a> Parser<'a> {
                     span: start,
                 })))
             }
-            TokenKind::Template(s) => {
-                let s = s.clone();
-                self.advance();
-                Ok(Expression::Template(TemplateLiteral {
-                    quasis: vec![TemplateElement {
-                        raw: s.clone(),
-                        cooked: Some(s),
-                        tail: true,
-                        span: start,
-                    }],
-                    expressions: Vec::new(),
-                    span: start,
-                }))
+            TokenKind::Template { head: true, .. } => {
+                Ok(Expression::Template(self.parse_template_literal(false)?))
             }
             TokenKind::Identifier(name) => {
                 let name = name.clone();
@@ -1288,6 +1391,21 @@ impl<'a> Parser<'a> {
                     span: prop_start.merge(self.prev_span()),
                 }));
             } else {
+                let is_async = self.check_async_method();
+                if is_async {
+                    self.advance();
+                }
+
+                let kind = if !is_async && self.check_modifier(&TokenKind::Get) {
+                    self.advance();
+                    PropertyKind::Get
+                } else if !is_async && self.check_modifier(&TokenKind::Set) {
+                    self.advance();
+                    PropertyKind::Set
+                } else {
+                    PropertyKind::Init
+                };
+
                 // Computed key
                 let computed = self.check(&TokenKind::LeftBracket);
 
@@ -1301,12 +1419,20 @@ impl<'a> Parser<'a> {
                     self.parse_property_name()?
                 };
 
+                // Accessors must be followed by their parameter list
+                if kind != PropertyKind::Init && !self.check(&TokenKind::LeftParen) {
+                    return Err(JsError::syntax("Expected '(' after accessor name"));
+                }
+
                 // Shorthand or method or normal
                 if self.check(&TokenKind::LeftParen) {
                     // Method
                     self.advance();
                     let params = self.parse_function_params()?;
                     self.expect(&TokenKind::RightParen)?;
+                    if kind != PropertyKind::Init {
+                        check_accessor_params(kind == PropertyKind::Get, &params)?;
+                    }
                     let body = self.parse_block()?;
 
                     properties.push(ObjectProperty::Property {
@@ -1315,13 +1441,14 @@ impl<'a> Parser<'a> {
                             id: None,
                             params,
                             body,
-                            is_async: false,
+                            is_async,
                             is_generator: false,
                             span: prop_start.merge(self.prev_span()),
                         }),
                         computed,
                         shorthand: false,
                         method: true,
+                        kind,
                         span: prop_start.merge(self.prev_span()),
                     });
                 } else if self.check(&TokenKind::Colon) {
@@ -1335,6 +1462,7 @@ impl<'a> Parser<'a> {
                         computed,
                         shorthand: false,
                         method: false,
+                        kind,
                         span: prop_start.merge(self.prev_span()),
                     });
                 } else {
@@ -1345,6 +1473,7 @@ impl<'a> Parser<'a> {
                         computed: false,
                         shorthand: true,
                         method: false,
+                        kind,
                         span: prop_start.merge(self.prev_span()),
                     });
                 }
@@ -1387,6 +1516,7 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(Expression::Literal(Literal::Number(n, span)))
             }
+            kind if kind.is_keyword() => Ok(Expression::Identifier(self.parse_identifier_name()?)),
             _ => Err(JsError::syntax("Expected property name")),
         }
     }
@@ -1617,6 +1747,19 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parse an IdentifierName, which unlike an identifier may be a
+    /// reserved word (e.g. `map.delete`, `obj.set`).
+    fn parse_identifier_name(&mut self) -> JsResult<Identifier> {
+        if self.current().kind.is_keyword() {
+            let span = self.current_span();
+            let name = self.source[span.start..span.end].into();
+            self.advance();
+            Ok(Identifier { name, span })
+        } else {
+            self.parse_identifier()
+        }
+    }
+
     // Helper methods
 
     fn current(&self) -> &Token {
@@ -1656,6 +1799,28 @@ impl<'a> Parser<'a> {
         core::mem::discriminant(&self.tokens[self.pos + 1].kind) == core::mem::discriminant(kind)
     }
 
+    /// Check for an `async` method modifier, as opposed to a property or
+    /// method that is itself named `async`.
+    fn check_async_method(&self) -> bool {
+        self.check_modifier(&TokenKind::Async)
+    }
+
+    /// Check for a modifier keyword (`async`, `get`, `set`) in front of a
+    /// property name, as opposed to a property named after the keyword.
+    fn check_modifier(&self, kind: &TokenKind) -> bool {
+        self.check(kind)
+            && ![
+                TokenKind::LeftParen,
+                TokenKind::Colon,
+                TokenKind::Comma,
+                TokenKind::RightBrace,
+                TokenKind::Assign,
+                TokenKind::Semicolon,
+            ]
+            .iter()
+            .any(|kind| self.peek_is(kind))
+    }
+
     fn expect(&mut self, kind: &TokenKind) -> JsResult<()> {
         if self.check(kind) {
             self.advance();
@@ -1678,6 +1843,21 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Check the parameter count of a getter or setter.
+fn check_accessor_params(getter: bool, params: &[Pattern]) -> JsResult<()> {
+    if getter && !params.is_empty() {
+        return Err(JsError::syntax(
+            "Getter must not have any formal parameters",
+        ));
+    }
+    if !getter && (params.len() != 1 || matches!(params[0], Pattern::Rest(_))) {
+        return Err(JsError::syntax(
+            "Setter must have exactly one formal parameter",
+        ));
+    }
+    Ok(())
+}
+
 /// Parse JavaScript source code into an AST.
 pub fn parse(source: &str) -> JsResult<Program> {
     let mut parser = Parser::new(source)?;
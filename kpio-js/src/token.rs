@@ -46,8 +46,21 @@ pub enum TokenKind {
     Number(f64),
     /// String literal ("hello", 'world')
     String(String),
-    /// Template literal (`hello ${name}`)
-    Template(String),
+    /// Template literal chunk.
+    ///
+    /// `` `a${x}b${y}c` `` lexes as three chunks: `a` (head), `b` and `c`
+    /// (tail). A template without substitutions is a single chunk that is
+    /// both head and tail.
+    Template {
+        /// Text with escapes applied, or `None` if an escape is invalid.
+        cooked: Option<String>,
+        /// Source text with line terminators normalized to `\n`.
+        raw: String,
+        /// Chunk starts with a backtick.
+        head: bool,
+        /// Chunk ends with a backtick.
+        tail: bool,
+    },
     /// BigInt literal (42n)
     BigInt(String),
     /// Regular expression literal (/pattern/flags)
@@ -9,8 +9,9 @@ use core::cell::RefCell;
 use core::fmt;
 use libm::{fabs, trunc};
 
+use crate::date;
 use crate::error::{JsError, JsResult};
-use crate::object::{JsObject, PropertyKey};
+use crate::object::{JsObject, ObjectKind, PropertyKey};
 
 /// A JavaScript value.
 #[derive(Clone)]
@@ -172,9 +173,13 @@ impl Value {
             }
             Value::Symbol(_) => Err(JsError::type_error("Cannot convert symbol to number")),
             Value::BigInt(_) => Err(JsError::type_error("Cannot convert BigInt to number")),
-            Value::Object(_) => {
-                // Should call ToPrimitive first
-                Ok(f64::NAN)
+            Value::Object(obj) => {
+                // Should call ToPrimitive first; a Date's primitive value
+                // is its time value
+                match obj.borrow().kind() {
+                    ObjectKind::Date(t) => Ok(*t),
+                    _ => Ok(f64::NAN),
+                }
             }
         }
     }
@@ -219,9 +224,12 @@ impl Value {
             Value::String(s) => Ok(s.clone()),
             Value::Symbol(_) => Err(JsError::type_error("Cannot convert symbol to string")),
             Value::BigInt(n) => Ok(format_number(*n)),
-            Value::Object(_) => {
+            Value::Object(obj) => {
                 // Should call ToPrimitive first
-                Ok("[object Object]".into())
+                match obj.borrow().kind() {
+                    ObjectKind::Date(t) => Ok(date::to_string(*t)),
+                    _ => Ok("[object Object]".into()),
+                }
             }
         }
     }
@@ -277,6 +285,17 @@ impl Value {
                 }
                 Ok(Value::undefined())
             }
+            Value::Symbol(sym) => {
+                if let PropertyKey::String(k) = key {
+                    if k == "description" {
+                        return Ok(sym
+                            .description()
+                            .map(Value::string)
+                            .unwrap_or(Value::undefined()));
+                    }
+                }
+                Ok(Value::undefined())
+            }
             _ => Ok(Value::undefined()),
         }
     }
@@ -310,6 +329,29 @@ impl Value {
         }
     }
 
+    /// SameValueZero comparison, used for `Map` keys and `Set` members.
+    ///
+    /// Like `===` except that `NaN` equals itself.
+    pub fn same_value_zero(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) if a.is_nan() && b.is_nan() => true,
+            _ => self.strict_equals(other),
+        }
+    }
+
+    /// SameValue comparison, used when redefining non-configurable
+    /// properties.
+    ///
+    /// Like SameValueZero except that `+0` and `-0` differ.
+    pub fn same_value(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) if *a == 0.0 && *b == 0.0 => {
+                a.is_sign_negative() == b.is_sign_negative()
+            }
+            _ => self.same_value_zero(other),
+        }
+    }
+
     /// Abstract equality (==).
     pub fn abstract_equals(&self, other: &Value) -> JsResult<bool> {
         // Same type
@@ -387,10 +429,14 @@ pub struct Symbol {
     id: u64,
 }
 
+/// Number of identifiers reserved for the well-known symbols.
+const WELL_KNOWN_SYMBOL_COUNT: u64 = 8;
+
 impl Symbol {
     /// Create a new symbol.
     pub fn new(description: Option<String>) -> Self {
-        static COUNTER: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+        static COUNTER: core::sync::atomic::AtomicU64 =
+            core::sync::atomic::AtomicU64::new(WELL_KNOWN_SYMBOL_COUNT);
         Symbol {
             description,
             id: COUNTER.fetch_add(1, core::sync::atomic::Ordering::SeqCst),
@@ -401,6 +447,22 @@ impl Symbol {
     pub fn description(&self) -> Option<&str> {
         self.description.as_deref()
     }
+
+    /// Create a well-known symbol with a fixed identifier.
+    ///
+    /// Well-known symbols are shared by every engine instance, so native
+    /// functions can look them up without access to the interpreter.
+    fn well_known(id: u64, name: &str) -> Self {
+        Symbol {
+            description: Some(name.into()),
+            id,
+        }
+    }
+
+    /// The `Symbol.iterator` well-known symbol.
+    pub fn iterator() -> Self {
+        Self::well_known(0, "Symbol.iterator")
+    }
 }
 
 /// Well-known symbols.
@@ -425,14 +487,14 @@ impl WellKnownSymbols {
     /// Create well-known symbols.
     pub fn new() -> Self {
         WellKnownSymbols {
-            iterator: Symbol::new(Some("Symbol.iterator".into())),
-            async_iterator: Symbol::new(Some("Symbol.asyncIterator".into())),
-            has_instance: Symbol::new(Some("Symbol.hasInstance".into())),
-            is_concat_spreadable: Symbol::new(Some("Symbol.isConcatSpreadable".into())),
-            species: Symbol::new(Some("Symbol.species".into())),
-            to_primitive: Symbol::new(Some("Symbol.toPrimitive".into())),
-            to_string_tag: Symbol::new(Some("Symbol.toStringTag".into())),
-            unscopables: Symbol::new(Some("Symbol.unscopables".into())),
+            iterator: Symbol::iterator(),
+            async_iterator: Symbol::well_known(1, "Symbol.asyncIterator"),
+            has_instance: Symbol::well_known(2, "Symbol.hasInstance"),
+            is_concat_spreadable: Symbol::well_known(3, "Symbol.isConcatSpreadable"),
+            species: Symbol::well_known(4, "Symbol.species"),
+            to_primitive: Symbol::well_known(5, "Symbol.toPrimitive"),
+            to_string_tag: Symbol::well_known(6, "Symbol.toStringTag"),
+            unscopables: Symbol::well_known(7, "Symbol.unscopables"),
         }
     }
 }
@@ -612,6 +674,15 @@ fn number_to_string(n: f64) -> String {
     s
 }
 
+/// Check whether `s` is the string form of some number, such as `"-1"`,
+/// `"1.5"` or `"-0"`.
+pub(crate) fn is_canonical_numeric_string(s: &str) -> bool {
+    if s == "-0" {
+        return true;
+    }
+    parse_number(s).is_some_and(|n| number_to_string(n) == s)
+}
+
 /// Format an integer.
 fn format_number(n: i64) -> String {
     if n == 0 {
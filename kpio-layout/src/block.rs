@@ -11,7 +11,9 @@
 
 use crate::box_model::{BoxDimensions, EdgeSizes, Rect, ResolvedLength};
 use crate::layout_box::{BoxType, ContainingBlock, LayoutBox, LayoutContext};
+use crate::table::layout_table;
 use alloc::vec::Vec;
+use kpio_css::values::Display;
 
 /// Perform block layout on a layout box
 pub fn layout_block(
@@ -75,7 +77,7 @@ fn calculate_block_width(layout_box: &mut LayoutBox, containing_block: Containin
 /// that are not 'auto') is larger than the width of the containing block, then any
 /// 'auto' values for 'margin-left' or 'margin-right' are, for the following rules,
 /// treated as zero.
-fn resolve_block_width(
+pub(crate) fn resolve_block_width(
     width: ResolvedLength,
     margin_left: ResolvedLength,
     margin_right: ResolvedLength,
@@ -161,7 +163,7 @@ fn calculate_block_position(layout_box: &mut LayoutBox, containing_block: Contai
 }
 
 /// Layout children of a block element
-fn layout_block_children(layout_box: &mut LayoutBox, context: &LayoutContext) {
+pub(crate) fn layout_block_children(layout_box: &mut LayoutBox, context: &LayoutContext) {
     let d = &layout_box.dimensions;
 
     // Create containing block for children
@@ -201,7 +203,11 @@ fn layout_block_children(layout_box: &mut LayoutBox, context: &LayoutContext) {
                 }
 
                 // Layout the child
-                layout_block(child, cb, context);
+                if child.style.display == Display::Table {
+                    layout_table(child, cb, context);
+                } else {
+                    layout_block(child, cb, context);
+                }
 
                 // Move Y down past this child
                 current_y = child.dimensions.margin_box().bottom();
@@ -8,12 +8,23 @@
 //! - **Line Box**: A horizontal box containing inline elements
 //! - **Inline Box**: An element that flows with text (span, a, etc.)
 //! - **Text Run**: A continuous run of text within an inline box
-//! - **Line Breaking**: Wrapping content to new lines
-
-use crate::box_model::{EdgeSizes, Rect};
-use crate::layout_box::{BoxType, ContainingBlock, LayoutBox, LayoutContext};
+//! - **Line Breaking**: Wrapping content to new lines at the break
+//!   opportunities found by [`crate::line_break`], as allowed by `white-space`
+//! - **Baseline**: Fragments are aligned on the line's baseline according to
+//!   their `vertical-align`, using the ascent/descent of their font metrics
+
+use crate::box_model::{EdgeSizes, Rect, ResolvedLength};
+use crate::layout_box::{BoxType, ContainingBlock, LayoutBox, LayoutContext, LayoutStyle};
+use crate::line_break::{self, Break, LineBreakClass};
 use alloc::string::String;
 use alloc::vec::Vec;
+use kpio_css::values::{LengthContext, VerticalAlign, WhiteSpace};
+
+/// Baseline shift for `vertical-align: sub`, in ems of the parent font
+const SUB_SHIFT: f32 = 0.2;
+
+/// Baseline shift for `vertical-align: super`, in ems of the parent font
+const SUPER_SHIFT: f32 = 0.34;
 
 /// A line box containing inline content
 #[derive(Debug)]
@@ -50,12 +61,54 @@ impl LineBox {
 
     /// Add a fragment to this line
     pub fn add_fragment(&mut self, fragment: LineFragment) {
-        // Update line height if needed
+        // Update line height if needed (refined by `align`)
         if fragment.height > self.height {
             self.height = fragment.height;
         }
         self.fragments.push(fragment);
     }
+
+    /// Compute the baseline and height of this line, and position each
+    /// fragment vertically according to its `vertical-align`.
+    ///
+    /// `strut` is the font of the block containing the line; its ascent and
+    /// descent set the minimum extent of the line around the baseline.
+    pub fn align(&mut self, strut: &FontMetrics) {
+        let mut above = strut.half_leading_ascent();
+        let mut below = strut.half_leading_descent();
+
+        // Baseline-relative fragments determine the baseline position
+        for fragment in &self.fragments {
+            if let Some(shift) = fragment.baseline_shift(strut) {
+                above = above.max(fragment.ascent + shift);
+                below = below.max(fragment.descent - shift);
+            }
+        }
+
+        // Line-relative fragments can only grow the line
+        for fragment in &self.fragments {
+            let excess = fragment.height - (above + below);
+            if excess > 0.0 {
+                match fragment.vertical_align {
+                    VerticalPosition::Top => below += excess,
+                    VerticalPosition::Bottom => above += excess,
+                    _ => {}
+                }
+            }
+        }
+
+        self.baseline = above;
+        self.height = above + below;
+
+        for fragment in &mut self.fragments {
+            let top = match fragment.vertical_align {
+                VerticalPosition::Top => 0.0,
+                VerticalPosition::Bottom => self.height - fragment.height,
+                _ => above - fragment.baseline_shift(strut).unwrap_or(0.0) - fragment.ascent,
+            };
+            fragment.y = self.y + top;
+        }
+    }
 }
 
 /// A fragment of inline content on a line
@@ -63,16 +116,94 @@ impl LineBox {
 pub struct LineFragment {
     /// X position within the line
     pub x: f32,
-    /// Y position (same as line Y for now)
+    /// Y position of the top of this fragment
     pub y: f32,
     /// Width of this fragment
     pub width: f32,
-    /// Height of this fragment
+    /// Height of this fragment (ascent + descent)
     pub height: f32,
+    /// Distance from the fragment's baseline to its top
+    pub ascent: f32,
+    /// Distance from the fragment's baseline to its bottom
+    pub descent: f32,
+    /// Vertical alignment within the line
+    pub vertical_align: VerticalPosition,
     /// The type of content
     pub content: FragmentContent,
 }
 
+impl LineFragment {
+    /// Height of the fragment's baseline above the line's baseline, or
+    /// `None` for fragments aligned to the line box (`top`/`bottom`).
+    pub fn baseline_shift(&self, strut: &FontMetrics) -> Option<f32> {
+        match self.vertical_align {
+            VerticalPosition::Shift(shift) => Some(shift),
+            // Midpoint of the box at the parent baseline plus half its x-height
+            VerticalPosition::Middle => {
+                Some(strut.x_height() / 2.0 - (self.ascent - self.descent) / 2.0)
+            }
+            VerticalPosition::TextTop => Some(strut.ascender - self.ascent),
+            VerticalPosition::TextBottom => Some(self.descent - strut.descender),
+            VerticalPosition::Top | VerticalPosition::Bottom => None,
+        }
+    }
+
+    /// Y position of this fragment's baseline
+    pub fn baseline(&self) -> f32 {
+        self.y + self.ascent
+    }
+}
+
+/// `vertical-align` resolved against the fragment's font metrics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerticalPosition {
+    /// Baseline raised by the given amount above the parent baseline
+    /// (negative values lower it)
+    Shift(f32),
+    /// Box centered on the parent baseline plus half the parent x-height
+    Middle,
+    /// Top aligned with the top of the parent's content area
+    TextTop,
+    /// Bottom aligned with the bottom of the parent's content area
+    TextBottom,
+    /// Top aligned with the top of the line box
+    Top,
+    /// Bottom aligned with the bottom of the line box
+    Bottom,
+}
+
+impl VerticalPosition {
+    /// Resolve a `vertical-align` value for a box using `metrics` inside a
+    /// parent using `parent`.
+    pub fn resolve(align: VerticalAlign, metrics: &FontMetrics, parent: &FontMetrics) -> Self {
+        match align {
+            VerticalAlign::Baseline => VerticalPosition::Shift(0.0),
+            VerticalAlign::Sub => VerticalPosition::Shift(-parent.size * SUB_SHIFT),
+            VerticalAlign::Super => VerticalPosition::Shift(parent.size * SUPER_SHIFT),
+            VerticalAlign::Middle => VerticalPosition::Middle,
+            VerticalAlign::TextTop => VerticalPosition::TextTop,
+            VerticalAlign::TextBottom => VerticalPosition::TextBottom,
+            VerticalAlign::Top => VerticalPosition::Top,
+            VerticalAlign::Bottom => VerticalPosition::Bottom,
+            VerticalAlign::Length(length) => {
+                // Percentages refer to the box's own line-height
+                let ctx = LengthContext {
+                    font_size: metrics.size,
+                    containing_block: metrics.line_height,
+                    ..LengthContext::default()
+                };
+                VerticalPosition::Shift(length.to_px(&ctx))
+            }
+        }
+    }
+}
+
+impl Default for VerticalPosition {
+    fn default() -> Self {
+        VerticalPosition::Shift(0.0)
+    }
+}
+
 /// Content of a line fragment
 #[derive(Debug)]
 pub enum FragmentContent {
@@ -106,6 +237,53 @@ pub struct FontMetrics {
     pub avg_char_width: f32,
 }
 
+impl FontMetrics {
+    /// Approximate metrics for a font size and line height in pixels
+    pub fn for_size(size: f32, line_height: f32) -> Self {
+        Self {
+            size,
+            line_height,
+            ascender: size * 0.875,
+            descender: size * 0.25,
+            avg_char_width: size * 0.5,
+        }
+    }
+
+    /// Metrics for a box's font, if its style specifies one
+    pub fn from_style(style: &LayoutStyle) -> Option<Self> {
+        if style.font_size <= 0.0 {
+            return None;
+        }
+        let line_height = if style.line_height > 0.0 {
+            style.line_height
+        } else {
+            style.font_size * 1.2
+        };
+        Some(Self::for_size(style.font_size, line_height))
+    }
+
+    /// Approximate x-height (height of lowercase letters)
+    pub fn x_height(&self) -> f32 {
+        self.size * 0.5
+    }
+
+    /// Half the leading (extra space from `line-height`), split above and
+    /// below the content area
+    pub fn half_leading(&self) -> f32 {
+        (self.line_height - (self.ascender + self.descender)) / 2.0
+    }
+
+    /// Ascent of an inline box using these metrics, including half-leading
+    pub fn half_leading_ascent(&self) -> f32 {
+        self.ascender + self.half_leading()
+    }
+
+    /// Descent of an inline box using these metrics, including half-leading
+    pub fn half_leading_descent(&self) -> f32 {
+        self.descender + self.half_leading()
+    }
+}
+
 impl Default for FontMetrics {
     fn default() -> Self {
         Self {
@@ -128,6 +306,9 @@ pub struct InlineFormattingContext {
     pub lines: Vec<LineBox>,
     /// Default font metrics
     pub font_metrics: FontMetrics,
+    /// A preserved newline ended the last line; the next content starts a
+    /// new one
+    break_pending: bool,
 }
 
 impl InlineFormattingContext {
@@ -137,13 +318,16 @@ impl InlineFormattingContext {
             current_y: containing_block.y,
             lines: Vec::new(),
             font_metrics: FontMetrics::default(),
+            break_pending: false,
         }
     }
 
     /// Start a new line
     pub fn new_line(&mut self) {
+        self.break_pending = false;
         // Finalize current line if it exists
-        if let Some(current_line) = self.lines.last() {
+        if let Some(current_line) = self.lines.last_mut() {
+            current_line.align(&self.font_metrics);
             self.current_y += current_line.height;
         }
 
@@ -152,26 +336,66 @@ impl InlineFormattingContext {
 
     /// Get current line, creating one if needed
     pub fn current_line(&mut self) -> &mut LineBox {
-        if self.lines.is_empty() {
+        if self.lines.is_empty() || self.break_pending {
             self.new_line();
         }
         self.lines.last_mut().unwrap()
     }
 
+    /// Position (line index, fragment index) the next fragment will take,
+    /// unless a line break intervenes
+    fn next_fragment_position(&self) -> (usize, usize) {
+        match self.lines.last() {
+            Some(line) => (self.lines.len() - 1, line.fragments.len()),
+            None => (0, 0),
+        }
+    }
+
+    /// Fragment at a position from `next_fragment_position`, following a
+    /// line break taken before the fragment was placed
+    fn fragment_at(&self, line: usize, fragment: usize) -> Option<&LineFragment> {
+        let current = self.lines.get(line)?;
+        match current.fragments.get(fragment) {
+            Some(f) => Some(f),
+            None => self.lines.get(line + 1)?.fragments.first(),
+        }
+    }
+
     /// Get remaining width on current line
     pub fn remaining_width(&self) -> f32 {
         let used = self.lines.last().map(|l| l.width()).unwrap_or(0.0);
         (self.containing_block.width - used).max(0.0)
     }
 
-    /// Layout text content
+    /// Layout text content in the context's default font
     pub fn layout_text(&mut self, text: &str, start_x: f32) {
+        let metrics = self.font_metrics;
+        self.layout_styled_text(
+            text,
+            start_x,
+            metrics,
+            VerticalPosition::default(),
+            WhiteSpace::Normal,
+        );
+    }
+
+    /// Layout text content using the given font, vertical alignment and
+    /// `white-space` mode
+    pub fn layout_styled_text(
+        &mut self,
+        text: &str,
+        start_x: f32,
+        metrics: FontMetrics,
+        vertical_align: VerticalPosition,
+        white_space: WhiteSpace,
+    ) {
         if text.is_empty() {
             return;
         }
 
-        let line_height = self.font_metrics.line_height;
-        let char_width = self.font_metrics.avg_char_width;
+        let ascent = metrics.half_leading_ascent();
+        let descent = metrics.half_leading_descent();
+        let char_width = metrics.avg_char_width;
 
         let mut remaining_text = text;
         let mut text_index = 0;
@@ -179,65 +403,100 @@ impl InlineFormattingContext {
         while !remaining_text.is_empty() {
             let line = self.current_line();
             let current_x = line.current_x(start_x);
+            let line_is_empty = line.fragments.is_empty();
             let available_width =
                 self.containing_block.width - (current_x - self.containing_block.x);
 
-            if available_width <= 0.0 {
-                // No space, start new line
-                self.new_line();
-                continue;
-            }
-
             // Calculate how many characters fit
-            let max_chars = (available_width / char_width) as usize;
-            if max_chars == 0 {
-                self.new_line();
-                continue;
-            }
+            let max_chars = (available_width.max(0.0) / char_width) as usize;
 
-            // Find break point (word boundary or forced)
-            let (break_index, forced_break) = find_break_point(remaining_text, max_chars);
+            // Find break point (break opportunity or preserved newline)
+            let (mut break_index, mandatory) =
+                find_break_point(remaining_text, max_chars, white_space);
 
             if break_index == 0 {
-                // Can't fit anything, force new line
-                self.new_line();
-                continue;
+                if !line_is_empty {
+                    // The next unbreakable run doesn't fit, move it down
+                    self.new_line();
+                    continue;
+                }
+                // Doesn't fit on an empty line either: break inside it
+                break_index = remaining_text
+                    .char_indices()
+                    .nth(max_chars.max(1))
+                    .map_or(remaining_text.len(), |(i, _)| i);
             }
 
-            // Create fragment for this portion
-            let fragment_text: String = remaining_text[..break_index].into();
-            let fragment_width = fragment_text.chars().count() as f32 * char_width;
-
-            let line = self.current_line();
-            let fragment = LineFragment {
-                x: line.current_x(start_x),
-                y: line.y,
-                width: fragment_width,
-                height: line_height,
-                content: FragmentContent::Text {
-                    text: fragment_text,
-                    start_index: text_index,
-                    end_index: text_index + break_index,
-                },
+            // The newline ending a line isn't part of its content
+            let fragment_text = if mandatory {
+                remaining_text[..break_index]
+                    .trim_end_matches(|c| LineBreakClass::of(c).is_hard_break())
+            } else {
+                &remaining_text[..break_index]
             };
-            line.add_fragment(fragment);
+
+            if !fragment_text.is_empty() {
+                let fragment_width = fragment_text.chars().count() as f32 * char_width;
+                let line = self.current_line();
+                let fragment = LineFragment {
+                    x: line.current_x(start_x),
+                    y: line.y,
+                    width: fragment_width,
+                    height: ascent + descent,
+                    ascent,
+                    descent,
+                    vertical_align,
+                    content: FragmentContent::Text {
+                        text: fragment_text.into(),
+                        start_index: text_index,
+                        end_index: text_index + fragment_text.len(),
+                    },
+                };
+                line.add_fragment(fragment);
+            }
 
             // Advance
             text_index += break_index;
             remaining_text = &remaining_text[break_index..];
 
-            // Skip leading whitespace on new line
-            if forced_break {
-                remaining_text = remaining_text.trim_start();
-            }
-
-            // If we filled the line, start a new one
-            if self.remaining_width() <= char_width && !remaining_text.is_empty() {
+            if mandatory {
+                self.break_pending = true;
+            } else if self.remaining_width() <= char_width && !remaining_text.is_empty() {
+                // If we filled the line, start a new one
                 self.new_line();
             }
         }
     }
 
+    /// Layout an atomic inline (image, inline-block) of the given margin
+    /// box size. Its baseline is the bottom margin edge.
+    pub fn layout_atomic(
+        &mut self,
+        width: f32,
+        height: f32,
+        box_index: usize,
+        start_x: f32,
+        vertical_align: VerticalPosition,
+    ) {
+        let line_is_empty = self.current_line().fragments.is_empty();
+        if !line_is_empty && width > self.remaining_width() {
+            self.new_line();
+        }
+
+        let line = self.current_line();
+        let fragment = LineFragment {
+            x: line.current_x(start_x),
+            y: line.y,
+            width,
+            height,
+            ascent: height,
+            descent: 0.0,
+            vertical_align,
+            content: FragmentContent::Atomic { box_index },
+        };
+        line.add_fragment(fragment);
+    }
+
     /// Get total height of all lines
     pub fn total_height(&self) -> f32 {
         self.lines.iter().map(|l| l.height).sum()
@@ -245,36 +504,45 @@ impl InlineFormattingContext {
 
     /// Finalize the context and return final Y position
     pub fn finalize(&mut self) -> f32 {
+        if let Some(last_line) = self.lines.last_mut() {
+            last_line.align(&self.font_metrics);
+        }
         let total = self.lines.iter().map(|l| l.height).sum::<f32>();
         self.containing_block.y + total
     }
 }
 
-/// Find a good break point in text
-fn find_break_point(text: &str, max_chars: usize) -> (usize, bool) {
-    let chars: Vec<char> = text.chars().collect();
-
-    if chars.len() <= max_chars {
-        return (text.len(), false);
-    }
-
-    // Look for whitespace break point
-    let mut last_space_char_index = None;
-    for (i, &c) in chars.iter().enumerate().take(max_chars + 1) {
-        if c.is_whitespace() {
-            last_space_char_index = Some(i);
+/// Find where the part of `text` that goes on the current line ends, with
+/// room for `max_chars` characters
+///
+/// Returns the byte index to break at and whether the break is a preserved
+/// newline. Trailing spaces may hang past the end of the line. An index of 0
+/// means even the first unbreakable run doesn't fit. Without wrapping
+/// (`nowrap`, `pre`) the text only ends at preserved newlines.
+fn find_break_point(text: &str, max_chars: usize, white_space: WhiteSpace) -> (usize, bool) {
+    let wrap = white_space.allows_wrap();
+    let mut fits = 0;
+
+    for (index, kind) in line_break::break_opportunities(text) {
+        // Collapsed newlines are ordinary break opportunities
+        let mandatory = kind == Break::Mandatory && white_space.preserves_newlines();
+        if !mandatory && !wrap {
+            continue;
+        }
+        if wrap && text[..index].trim_end().chars().count() > max_chars {
+            return (fits, false);
         }
+        if mandatory {
+            return (index, true);
+        }
+        fits = index;
     }
 
-    if let Some(space_idx) = last_space_char_index {
-        // Break at whitespace
-        let byte_index = chars[..=space_idx].iter().map(|c| c.len_utf8()).sum();
-        return (byte_index, true);
+    if !wrap || text.trim_end().chars().count() <= max_chars {
+        (text.len(), false)
+    } else {
+        (fits, false)
     }
-
-    // No whitespace found, force break at max_chars
-    let byte_index = chars[..max_chars].iter().map(|c| c.len_utf8()).sum();
-    (byte_index, false)
 }
 
 /// Layout inline children of a block box
@@ -284,23 +552,60 @@ pub fn layout_inline_children(
     _context: &LayoutContext,
 ) {
     let mut ifc = InlineFormattingContext::new(containing_block);
+    if let Some(metrics) = FontMetrics::from_style(&layout_box.style) {
+        ifc.font_metrics = metrics;
+    }
 
-    for child in &mut layout_box.children {
-        match child.box_type {
-            BoxType::Inline | BoxType::AnonymousInline => {
-                if let Some(ref text) = child.text {
-                    ifc.layout_text(text, containing_block.x);
-                }
+    // (child index, line index, fragment index) of each child's first fragment
+    let mut placements = Vec::new();
 
-                // Set child dimensions based on its content
-                let line = ifc.current_line();
-                child.dimensions.content.x = containing_block.x;
-                child.dimensions.content.y = line.y;
-                child.dimensions.content.height = ifc.font_metrics.line_height;
-            }
-            _ => {
-                // Skip block-level children in inline context
+    for (index, child) in layout_box.children.iter().enumerate() {
+        if !child.box_type.is_inline() {
+            // Skip block-level children in inline context
+            continue;
+        }
+
+        let metrics = FontMetrics::from_style(&child.style).unwrap_or(ifc.font_metrics);
+        let vertical_align =
+            VerticalPosition::resolve(child.style.vertical_align, &metrics, &ifc.font_metrics);
+        let position = ifc.next_fragment_position();
+
+        if let Some(ref text) = child.text {
+            ifc.layout_styled_text(
+                text,
+                containing_block.x,
+                metrics,
+                vertical_align,
+                child.style.white_space,
+            );
+        } else if let Some((width, height)) = atomic_size(&child.style) {
+            ifc.layout_atomic(width, height, index, containing_block.x, vertical_align);
+        } else {
+            continue;
+        }
+        placements.push((index, position));
+    }
+
+    ifc.finalize();
+
+    for (index, (line, fragment)) in placements {
+        let Some(fragment) = ifc.fragment_at(line, fragment) else {
+            continue;
+        };
+        let child = &mut layout_box.children[index];
+        child.dimensions.content.x = fragment.x;
+        child.dimensions.content.y = fragment.y;
+        match fragment.content {
+            FragmentContent::Atomic { .. } => {
+                let style = &child.style;
+                child.dimensions.content.x +=
+                    style.margin_left.to_px() + style.border_left_width + style.padding_left;
+                child.dimensions.content.y +=
+                    style.margin_top.to_px() + style.border_top_width + style.padding_top;
+                child.dimensions.content.width = style.width.to_px();
+                child.dimensions.content.height = style.height.to_px();
             }
+            _ => child.dimensions.content.height = fragment.height,
         }
     }
 
@@ -311,6 +616,29 @@ pub fn layout_inline_children(
     }
 }
 
+/// Margin box size of an atomic inline, if the box has a definite size
+fn atomic_size(style: &LayoutStyle) -> Option<(f32, f32)> {
+    let (ResolvedLength::Px(width), ResolvedLength::Px(height)) = (style.width, style.height)
+    else {
+        return None;
+    };
+    let width = width
+        + style.padding_left
+        + style.padding_right
+        + style.border_left_width
+        + style.border_right_width
+        + style.margin_left.to_px()
+        + style.margin_right.to_px();
+    let height = height
+        + style.padding_top
+        + style.padding_bottom
+        + style.border_top_width
+        + style.border_bottom_width
+        + style.margin_top.to_px()
+        + style.margin_bottom.to_px();
+    Some((width, height))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -318,9 +646,67 @@ mod tests {
     #[test]
     fn test_find_break_point() {
         let text = "Hello World";
-        let (idx, forced) = find_break_point(text, 7);
+        let (idx, mandatory) = find_break_point(text, 7, WhiteSpace::Normal);
         assert_eq!(&text[..idx], "Hello ");
-        assert!(forced);
+        assert!(!mandatory);
+    }
+
+    #[test]
+    fn test_find_break_point_cjk() {
+        // Breaks between ideographs, but 。 stays with the one before it
+        let text = "日本語の文章。次";
+        let (idx, _) = find_break_point(text, 5, WhiteSpace::Normal);
+        assert_eq!(&text[..idx], "日本語の文");
+        let (idx, _) = find_break_point(text, 6, WhiteSpace::Normal);
+        assert_eq!(&text[..idx], "日本語の文");
+        let (idx, _) = find_break_point(text, 7, WhiteSpace::Normal);
+        assert_eq!(&text[..idx], "日本語の文章。");
+    }
+
+    fn line_texts(ifc: &InlineFormattingContext) -> Vec<String> {
+        ifc.lines
+            .iter()
+            .map(|line| {
+                let mut text = String::new();
+                for fragment in &line.fragments {
+                    if let FragmentContent::Text { text: t, .. } = &fragment.content {
+                        text.push_str(t);
+                    }
+                }
+                text
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_white_space_modes() {
+        // Room for 10 characters per line
+        let cb = ContainingBlock::new(80.0, 100.0);
+        let text = "aaa bbb ccc\nddd";
+        let metrics = FontMetrics::default();
+        let layout = |white_space| {
+            let mut ifc = InlineFormattingContext::new(cb);
+            ifc.layout_styled_text(text, 0.0, metrics, VerticalPosition::default(), white_space);
+            ifc.finalize();
+            line_texts(&ifc)
+        };
+
+        assert_eq!(layout(WhiteSpace::Normal), ["aaa bbb ", "ccc\nddd"]);
+        assert_eq!(layout(WhiteSpace::Nowrap), ["aaa bbb ccc\nddd"]);
+        assert_eq!(layout(WhiteSpace::Pre), ["aaa bbb ccc", "ddd"]);
+        assert_eq!(layout(WhiteSpace::PreWrap), ["aaa bbb ", "ccc", "ddd"]);
+    }
+
+    #[test]
+    fn test_trailing_newline_breaks_before_next_content() {
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        let metrics = FontMetrics::default();
+        let pre = WhiteSpace::Pre;
+        ifc.layout_styled_text("a\n\n", 0.0, metrics, VerticalPosition::default(), pre);
+        ifc.layout_text("b", 0.0);
+        ifc.finalize();
+        assert_eq!(line_texts(&ifc), ["a", "", "b"]);
     }
 
     #[test]
@@ -332,4 +718,140 @@ mod tests {
         assert!(!ifc.lines.is_empty());
         assert!(ifc.total_height() > 0.0);
     }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn test_mixed_font_sizes_share_baseline() {
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        let large = FontMetrics::for_size(32.0, 40.0);
+        ifc.layout_text("small ", 0.0);
+        ifc.layout_styled_text(
+            "large",
+            0.0,
+            large,
+            VerticalPosition::default(),
+            WhiteSpace::Normal,
+        );
+        ifc.finalize();
+
+        let line = &ifc.lines[0];
+        assert_eq!(line.fragments.len(), 2);
+        let small_baseline = line.fragments[0].baseline();
+        assert!(close(small_baseline, line.fragments[1].baseline()));
+        assert!(close(small_baseline, line.y + line.baseline));
+        assert!(line.height >= 40.0);
+    }
+
+    #[test]
+    fn test_atomic_sits_on_baseline() {
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        ifc.layout_text("text ", 0.0);
+        ifc.layout_atomic(50.0, 40.0, 1, 0.0, VerticalPosition::default());
+        ifc.finalize();
+
+        let line = &ifc.lines[0];
+        let text = &line.fragments[0];
+        let image = &line.fragments[1];
+        assert!(close(image.y + image.height, text.baseline()));
+        // Image ascent plus the strut descent
+        let strut = FontMetrics::default();
+        assert!(close(line.height, 40.0 + strut.half_leading_descent()));
+    }
+
+    #[test]
+    fn test_vertical_align_middle() {
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        ifc.layout_text("text ", 0.0);
+        ifc.layout_atomic(50.0, 40.0, 1, 0.0, VerticalPosition::Middle);
+        ifc.finalize();
+
+        let line = &ifc.lines[0];
+        let image = &line.fragments[1];
+        let midpoint = image.y + image.height / 2.0;
+        let expected = line.y + line.baseline - FontMetrics::default().x_height() / 2.0;
+        assert!(close(midpoint, expected));
+    }
+
+    #[test]
+    fn test_vertical_align_top_and_bottom() {
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        ifc.layout_atomic(20.0, 60.0, 0, 0.0, VerticalPosition::default());
+        ifc.layout_atomic(20.0, 10.0, 1, 0.0, VerticalPosition::Top);
+        ifc.layout_atomic(20.0, 10.0, 2, 0.0, VerticalPosition::Bottom);
+        ifc.finalize();
+
+        let line = &ifc.lines[0];
+        assert!(close(line.fragments[1].y, line.y));
+        let bottom = &line.fragments[2];
+        assert!(close(bottom.y + bottom.height, line.y + line.height));
+    }
+
+    #[test]
+    fn test_tall_top_aligned_box_grows_line() {
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        ifc.layout_text("text ", 0.0);
+        ifc.layout_atomic(20.0, 100.0, 1, 0.0, VerticalPosition::Top);
+        ifc.finalize();
+
+        let line = &ifc.lines[0];
+        assert!(close(line.height, 100.0));
+        // Baseline stays where the text put it
+        assert!(close(
+            line.baseline,
+            FontMetrics::default().half_leading_ascent()
+        ));
+    }
+
+    #[test]
+    fn test_sub_super_and_length_shift() {
+        let parent = FontMetrics::default();
+        let sub = VerticalPosition::resolve(VerticalAlign::Sub, &parent, &parent);
+        let sup = VerticalPosition::resolve(VerticalAlign::Super, &parent, &parent);
+        assert!(matches!(sub, VerticalPosition::Shift(s) if s < 0.0));
+        assert!(matches!(sup, VerticalPosition::Shift(s) if s > 0.0));
+
+        let length = kpio_css::values::Length::px(5.0);
+        let shift = VerticalPosition::resolve(VerticalAlign::Length(length), &parent, &parent);
+        assert_eq!(shift, VerticalPosition::Shift(5.0));
+
+        let percent = kpio_css::values::Length::percent(50.0);
+        let shift = VerticalPosition::resolve(VerticalAlign::Length(percent), &parent, &parent);
+        assert_eq!(shift, VerticalPosition::Shift(parent.line_height / 2.0));
+
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        ifc.layout_text("x", 0.0);
+        ifc.layout_styled_text("2", 0.0, parent, sup, WhiteSpace::Normal);
+        ifc.finalize();
+
+        let line = &ifc.lines[0];
+        let VerticalPosition::Shift(raise) = sup else {
+            unreachable!()
+        };
+        assert!(close(
+            line.fragments[0].baseline() - line.fragments[1].baseline(),
+            raise
+        ));
+    }
+
+    #[test]
+    fn test_line_height_without_alignment_unchanged() {
+        let cb = ContainingBlock::new(400.0, 100.0);
+        let mut ifc = InlineFormattingContext::new(cb);
+        ifc.layout_text("Hello World", 0.0);
+        ifc.finalize();
+
+        assert!(close(
+            ifc.total_height(),
+            FontMetrics::default().line_height
+        ));
+    }
 }
@@ -11,10 +11,13 @@
 use alloc::string::String;
 use alloc::vec::Vec;
 use kpio_css::computed::ComputedStyle;
-use kpio_css::values::{Display, Position};
+use kpio_css::effects::{BorderRadius, BoxShadow};
+use kpio_css::transform::{TransformFunction, TransformOrigin};
+use kpio_css::values::{Color, Display, Overflow, Position, VerticalAlign, WhiteSpace};
 use kpio_dom::NodeId;
 
 use crate::box_model::{BoxDimensions, EdgeSizes, Rect, ResolvedLength};
+use crate::table::BorderCollapse;
 
 /// Type of formatting context for a box
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -100,7 +103,7 @@ impl Positioning {
 }
 
 /// Resolved style values needed for layout
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct LayoutStyle {
     /// Display type
     pub display: Display,
@@ -141,12 +144,98 @@ pub struct LayoutStyle {
     pub right: ResolvedLength,
     pub bottom: ResolvedLength,
     pub left: ResolvedLength,
+
+    /// Font size in pixels (0 if unresolved)
+    pub font_size: f32,
+    /// Line height in pixels
+    pub line_height: f32,
+    /// Vertical alignment within a line box
+    pub vertical_align: VerticalAlign,
+    /// Whether text wraps and keeps its newlines
+    pub white_space: WhiteSpace,
+
+    /// Table border model
+    pub border_collapse: BorderCollapse,
+    /// Space between cells of a separated-borders table
+    pub border_spacing_horizontal: f32,
+    pub border_spacing_vertical: f32,
+
+    /// Columns and rows covered by a table cell (0 is treated as 1)
+    pub col_span: u32,
+    pub row_span: u32,
+
+    /// Transform functions, composed at paint time once the border box
+    /// is known
+    pub transform: Vec<TransformFunction>,
+    pub transform_origin: TransformOrigin,
+
+    /// Background color of the border box
+    pub background_color: Color,
+    /// Foreground color, also used by shadows that give no color
+    pub color: Color,
+    /// Corner radii and shadows, resolved at paint time against the
+    /// border box
+    pub border_radius: BorderRadius,
+    pub box_shadow: Vec<BoxShadow>,
+    /// Opacity of the box and its descendants, composited as a group
+    pub opacity: f32,
+    /// Whether descendants are clipped to the padding box
+    pub clip_overflow: bool,
+}
+
+impl Default for LayoutStyle {
+    fn default() -> Self {
+        Self {
+            display: Display::default(),
+            position: Position::default(),
+            width: ResolvedLength::default(),
+            min_width: ResolvedLength::default(),
+            max_width: ResolvedLength::default(),
+            height: ResolvedLength::default(),
+            min_height: ResolvedLength::default(),
+            max_height: ResolvedLength::default(),
+            margin_top: ResolvedLength::default(),
+            margin_right: ResolvedLength::default(),
+            margin_bottom: ResolvedLength::default(),
+            margin_left: ResolvedLength::default(),
+            padding_top: 0.0,
+            padding_right: 0.0,
+            padding_bottom: 0.0,
+            padding_left: 0.0,
+            border_top_width: 0.0,
+            border_right_width: 0.0,
+            border_bottom_width: 0.0,
+            border_left_width: 0.0,
+            top: ResolvedLength::default(),
+            right: ResolvedLength::default(),
+            bottom: ResolvedLength::default(),
+            left: ResolvedLength::default(),
+            font_size: 0.0,
+            line_height: 0.0,
+            vertical_align: VerticalAlign::default(),
+            white_space: WhiteSpace::default(),
+            border_collapse: BorderCollapse::default(),
+            border_spacing_horizontal: 0.0,
+            border_spacing_vertical: 0.0,
+            col_span: 0,
+            row_span: 0,
+            transform: Vec::new(),
+            transform_origin: TransformOrigin::default(),
+            background_color: Color::TRANSPARENT,
+            color: Color::BLACK,
+            border_radius: BorderRadius::default(),
+            box_shadow: Vec::new(),
+            opacity: 1.0,
+            clip_overflow: false,
+        }
+    }
 }
 
 impl LayoutStyle {
     /// Create layout style from computed style
     pub fn from_computed(computed: &ComputedStyle) -> Self {
         let ctx = kpio_css::values::LengthContext::default();
+        let font_size = computed.font_size.to_px(&ctx);
         Self {
             display: computed.display,
             position: computed.position,
@@ -172,6 +261,24 @@ impl LayoutStyle {
             right: resolve_optional_length(&computed.right, &ctx),
             bottom: resolve_optional_length(&computed.bottom, &ctx),
             left: resolve_optional_length(&computed.left, &ctx),
+            font_size,
+            line_height: computed.line_height * font_size,
+            vertical_align: computed.vertical_align,
+            white_space: computed.white_space,
+            border_collapse: BorderCollapse::Separate,
+            border_spacing_horizontal: 0.0,
+            border_spacing_vertical: 0.0,
+            col_span: 1,
+            row_span: 1,
+            transform: computed.transform.clone(),
+            transform_origin: computed.transform_origin,
+            background_color: computed.background_color,
+            color: computed.color,
+            border_radius: computed.border_radius,
+            box_shadow: computed.box_shadow.clone(),
+            opacity: computed.opacity,
+            clip_overflow: computed.overflow_x != Overflow::Visible
+                || computed.overflow_y != Overflow::Visible,
         }
     }
 
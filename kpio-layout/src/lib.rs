@@ -11,7 +11,7 @@
 //!     ↓
 //! LayoutBox (with BoxType: Block, Inline, Anonymous)
 //!     ↓
-//! Layout Algorithm (block, inline, flex)
+//! Layout Algorithm (block, inline, flex, table)
 //!     ↓
 //! BoxDimensions (position, size, margins, etc.)
 //!     ↓
@@ -27,8 +27,10 @@ pub mod box_model;
 pub mod flex;
 pub mod inline;
 pub mod layout_box;
+pub mod line_break;
 pub mod paint;
 pub mod parallel;
+pub mod table;
 
 pub use box_model::{BoxDimensions, EdgeSizes, Rect};
 pub use layout_box::{BoxType, LayoutBox};
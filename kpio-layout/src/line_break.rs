@@ -0,0 +1,426 @@
+//! Line Breaking (UAX #14)
+//!
+//! Finds line break opportunities in text using the Unicode line breaking
+//! algorithm, so wrapping works for scripts that don't separate words with
+//! spaces: breaks are allowed between CJK ideographs and kana, but not
+//! before closing punctuation or small kana, not after opening brackets,
+//! and never across a no-break space or word joiner.
+//!
+//! The pair rules LB2–LB31 are implemented over a compact class table
+//! covering ASCII, Latin-1, general punctuation and the CJK blocks; other
+//! characters are treated as alphabetic (AL). Hangul syllables behave like
+//! ideographs. Regional indicator pairing and the emoji modifier rules are
+//! not implemented.
+
+use alloc::vec::Vec;
+
+/// Kind of break allowed before a position in the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Break {
+    /// The line must end here (after a newline or other hard break)
+    Mandatory,
+    /// The line may wrap here
+    Allowed,
+}
+
+/// Line breaking class of a character (UAX #14, Table 1)
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineBreakClass {
+    /// Mandatory break (form feed, line/paragraph separator)
+    BK,
+    /// Carriage return
+    CR,
+    /// Line feed
+    LF,
+    /// Next line
+    NL,
+    /// Space
+    SP,
+    /// Zero width space
+    ZW,
+    /// Zero width joiner
+    ZWJ,
+    /// Word joiner
+    WJ,
+    /// Non-breaking ("glue")
+    GL,
+    /// Combining mark
+    CM,
+    /// Break after (hyphen-like, tab, ideographic space)
+    BA,
+    /// Break before (acute accent and similar)
+    BB,
+    /// Hyphen-minus
+    HY,
+    /// Break on either side, but not between two (em dash)
+    B2,
+    /// Closing punctuation
+    CL,
+    /// Closing parenthesis
+    CP,
+    /// Opening punctuation
+    OP,
+    /// Ambiguous quotation
+    QU,
+    /// Exclamation / interrogation
+    EX,
+    /// Infix numeric separator
+    IS,
+    /// Symbols allowing break after (solidus)
+    SY,
+    /// Nonstarter (small kana, iteration marks, prolonged sound mark)
+    NS,
+    /// Inseparable (ellipsis)
+    IN,
+    /// Numeric
+    NU,
+    /// Prefix numeric (currency signs)
+    PR,
+    /// Postfix numeric (percent, degree)
+    PO,
+    /// Ideographic (CJK, kana, Hangul, emoji)
+    ID,
+    /// Alphabetic
+    AL,
+}
+
+use LineBreakClass::*;
+
+impl LineBreakClass {
+    /// Line breaking class of `c`
+    pub fn of(c: char) -> Self {
+        match c {
+            '\n' => LF,
+            '\r' => CR,
+            '\u{0B}' | '\u{0C}' | '\u{2028}' | '\u{2029}' => BK,
+            '\u{85}' => NL,
+            ' ' => SP,
+            '\u{200B}' => ZW,
+            '\u{200D}' => ZWJ,
+            '\u{2060}' | '\u{FEFF}' => WJ,
+            '\u{A0}' | '\u{202F}' | '\u{2007}' | '\u{2011}' | '\u{034F}' | '\u{0F0C}' => GL,
+            '\t' | '|' | '\u{AD}' | '\u{058A}' | '\u{1680}' | '\u{2010}' | '\u{2012}'
+            | '\u{2013}' | '\u{205F}' | '\u{3000}' => BA,
+            '\u{2000}'..='\u{2006}' | '\u{2008}'..='\u{200A}' => BA,
+            '\u{B4}' | '\u{2C8}' | '\u{2CC}' | '\u{2DF}' | '\u{1FFD}' => BB,
+            '-' => HY,
+            '\u{2014}' | '\u{2E3A}' | '\u{2E3B}' => B2,
+            ')' | ']' | '\u{FF09}' | '\u{FF3D}' => CP,
+            '}' | '\u{3001}' | '\u{3002}' | '\u{3009}' | '\u{300B}' | '\u{300D}' | '\u{300F}'
+            | '\u{3011}' | '\u{3015}' | '\u{3017}' | '\u{3019}' | '\u{301B}' | '\u{301E}'
+            | '\u{301F}' | '\u{FE50}' | '\u{FE52}' | '\u{FF0C}' | '\u{FF0E}' | '\u{FF5D}'
+            | '\u{FF60}' | '\u{FF61}' | '\u{FF63}' | '\u{FF64}' => CL,
+            '(' | '[' | '{' | '\u{A1}' | '\u{BF}' | '\u{3008}' | '\u{300A}' | '\u{300C}'
+            | '\u{300E}' | '\u{3010}' | '\u{3014}' | '\u{3016}' | '\u{3018}' | '\u{301A}'
+            | '\u{301D}' | '\u{FF08}' | '\u{FF3B}' | '\u{FF5B}' | '\u{FF5F}' | '\u{FF62}' => OP,
+            '"' | '\'' | '\u{AB}' | '\u{BB}' | '\u{2018}' | '\u{2019}' | '\u{201B}'
+            | '\u{201C}' | '\u{201D}' | '\u{201F}' | '\u{2039}' | '\u{203A}' => QU,
+            '!' | '?' | '\u{203C}' | '\u{2047}'..='\u{2049}' | '\u{FF01}' | '\u{FF1F}' => EX,
+            ',' | '.' | ':' | ';' | '\u{37E}' | '\u{589}' | '\u{60C}' | '\u{60D}' | '\u{2044}'
+            | '\u{FE10}' | '\u{FE13}' | '\u{FE14}' => IS,
+            '/' => SY,
+            '\u{2024}'..='\u{2026}' | '\u{FE19}' => IN,
+            '0'..='9' | '\u{660}'..='\u{669}' | '\u{6F0}'..='\u{6F9}' | '\u{966}'..='\u{96F}' => NU,
+            '$'
+            | '+'
+            | '\\'
+            | '\u{A3}'
+            | '\u{A5}'
+            | '\u{B1}'
+            | '\u{2116}'
+            | '\u{20A0}'..='\u{20CF}'
+            | '\u{FF04}'
+            | '\u{FFE1}'
+            | '\u{FFE5}'
+            | '\u{FFE6}' => PR,
+            '%'
+            | '\u{A2}'
+            | '\u{B0}'
+            | '\u{2030}'..='\u{2037}'
+            | '\u{2103}'
+            | '\u{2109}'
+            | '\u{FF05}'
+            | '\u{FFE0}' => PO,
+            // Small kana, iteration marks and other characters that may not
+            // start a line (CJ and NS, tailored to NS)
+            '\u{3005}'
+            | '\u{303B}'
+            | '\u{301C}'
+            | '\u{309B}'..='\u{309E}'
+            | '\u{30A0}'
+            | '\u{30FB}'..='\u{30FE}'
+            | '\u{31F0}'..='\u{31FF}'
+            | '\u{FF1A}'
+            | '\u{FF1B}'
+            | '\u{FF65}'
+            | '\u{FF67}'..='\u{FF70}'
+            | '\u{FF9E}'
+            | '\u{FF9F}' => NS,
+            '\u{3041}' | '\u{3043}' | '\u{3045}' | '\u{3047}' | '\u{3049}' | '\u{3063}'
+            | '\u{3083}' | '\u{3085}' | '\u{3087}' | '\u{308E}' | '\u{3095}' | '\u{3096}'
+            | '\u{30A1}' | '\u{30A3}' | '\u{30A5}' | '\u{30A7}' | '\u{30A9}' | '\u{30C3}'
+            | '\u{30E3}' | '\u{30E5}' | '\u{30E7}' | '\u{30EE}' | '\u{30F5}' | '\u{30F6}' => NS,
+            '\u{0}'..='\u{1F}' | '\u{7F}'..='\u{9F}' => CM,
+            '\u{300}'..='\u{36F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{3099}'
+            | '\u{309A}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{E0100}'..='\u{E01EF}' => CM,
+            '\u{1100}'..='\u{115F}'
+            | '\u{2E80}'..='\u{2FFF}'
+            | '\u{3003}'..='\u{303F}'
+            | '\u{3040}'..='\u{30FF}'
+            | '\u{3100}'..='\u{31EF}'
+            | '\u{3200}'..='\u{33FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{A000}'..='\u{A4CF}'
+            | '\u{AC00}'..='\u{D7A3}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FE30}'..='\u{FE4F}'
+            | '\u{FF00}'..='\u{FF60}'
+            | '\u{FFE0}'..='\u{FFE6}'
+            | '\u{1F000}'..='\u{1FAFF}'
+            | '\u{20000}'..='\u{3FFFD}' => ID,
+            _ => AL,
+        }
+    }
+
+    /// Whether the class forces a line break after it
+    pub fn is_hard_break(self) -> bool {
+        matches!(self, BK | CR | LF | NL)
+    }
+}
+
+/// Break opportunities in `text`, as byte offsets of the character a break
+/// would precede, in increasing order.
+///
+/// The start of the text is never a break opportunity. The end is only
+/// reported when the text ends with a hard break.
+pub fn break_opportunities(text: &str) -> Vec<(usize, Break)> {
+    let mut breaks = Vec::new();
+    let mut chars = text.char_indices();
+    let Some((_, first)) = chars.next() else {
+        return breaks;
+    };
+
+    // LB10: a leading combining mark is alphabetic
+    let mut prev = match LineBreakClass::of(first) {
+        CM | ZWJ => AL,
+        class => class,
+    };
+    // Class of the last character that wasn't a space
+    let mut before_spaces = prev;
+    // Whether the previous character was a ZWJ (LB8a)
+    let mut after_zwj = LineBreakClass::of(first) == ZWJ;
+
+    for (index, c) in chars {
+        let class = LineBreakClass::of(c);
+
+        // LB9: combining marks take the class of their base
+        if matches!(class, CM | ZWJ) && !matches!(prev, BK | CR | LF | NL | SP | ZW) {
+            after_zwj = class == ZWJ;
+            continue;
+        }
+        // LB10: marks without a base are alphabetic
+        let class = match class {
+            CM | ZWJ => AL,
+            class => class,
+        };
+
+        if let Some(kind) = pair_break(prev, before_spaces, class, after_zwj) {
+            breaks.push((index, kind));
+        }
+
+        after_zwj = LineBreakClass::of(c) == ZWJ;
+        prev = class;
+        if class != SP {
+            before_spaces = class;
+        }
+    }
+
+    if prev.is_hard_break() {
+        breaks.push((text.len(), Break::Mandatory));
+    }
+    breaks
+}
+
+/// Apply LB4–LB31 to the boundary between a character of class `prev` and
+/// one of class `next`. `before_spaces` is the class of the last non-space
+/// character before the boundary.
+fn pair_break(
+    prev: LineBreakClass,
+    before_spaces: LineBreakClass,
+    next: LineBreakClass,
+    after_zwj: bool,
+) -> Option<Break> {
+    // LB4, LB5: hard breaks, keeping CR LF together
+    if prev == CR && next == LF {
+        return None;
+    }
+    if prev.is_hard_break() {
+        return Some(Break::Mandatory);
+    }
+    // LB6, LB7: no break before hard breaks, spaces or ZW
+    if next.is_hard_break() || matches!(next, SP | ZW) {
+        return None;
+    }
+    // LB8: break after ZW, even across spaces
+    if before_spaces == ZW {
+        return Some(Break::Allowed);
+    }
+    // LB8a: keep ZWJ sequences together
+    if after_zwj {
+        return None;
+    }
+    // LB11, LB12, LB12a: word joiner and glue
+    if prev == WJ || next == WJ || prev == GL {
+        return None;
+    }
+    if next == GL && !matches!(prev, SP | BA | HY) {
+        return None;
+    }
+    // LB13: no break before closing punctuation and separators
+    if matches!(next, CL | CP | EX | IS | SY) {
+        return None;
+    }
+    // LB14: no break after opening punctuation, even across spaces
+    if before_spaces == OP {
+        return None;
+    }
+    // LB15: quote, spaces, opening punctuation
+    if before_spaces == QU && next == OP {
+        return None;
+    }
+    // LB16: closing punctuation, spaces, nonstarter
+    if matches!(before_spaces, CL | CP) && next == NS {
+        return None;
+    }
+    // LB17: em dash pairs
+    if before_spaces == B2 && next == B2 {
+        return None;
+    }
+    // LB18: break after spaces
+    if prev == SP {
+        return Some(Break::Allowed);
+    }
+    // LB19: quotation marks stick to both sides
+    if prev == QU || next == QU {
+        return None;
+    }
+    // LB21: no break before hyphens and nonstarters, or after BB
+    if matches!(next, BA | HY | NS) || prev == BB {
+        return None;
+    }
+    // LB22: no break before ellipsis
+    if next == IN {
+        return None;
+    }
+    // LB23, LB23a, LB24: letters, numbers and their prefixes/postfixes
+    if (prev == AL && next == NU) || (prev == NU && next == AL) {
+        return None;
+    }
+    if (prev == PR && next == ID) || (prev == ID && next == PO) {
+        return None;
+    }
+    if (matches!(prev, PR | PO) && next == AL) || (prev == AL && matches!(next, PR | PO)) {
+        return None;
+    }
+    // LB25: numeric expressions
+    if matches!(prev, CL | CP | NU) && matches!(next, PO | PR) {
+        return None;
+    }
+    if matches!(prev, PO | PR) && next == OP {
+        return None;
+    }
+    if matches!(prev, PO | PR | HY | IS | NU | SY) && next == NU {
+        return None;
+    }
+    // LB28, LB29: keep words together, including "e.g"
+    if matches!(prev, AL | IS) && next == AL {
+        return None;
+    }
+    // LB30: letters and numbers next to parentheses
+    if matches!(prev, AL | NU) && next == OP {
+        return None;
+    }
+    if prev == CP && matches!(next, AL | NU) {
+        return None;
+    }
+    // LB31: break everywhere else
+    Some(Break::Allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text of each segment between break opportunities
+    fn segments(text: &str) -> Vec<&str> {
+        let mut start = 0;
+        let mut parts = Vec::new();
+        for (index, _) in break_opportunities(text) {
+            parts.push(&text[start..index]);
+            start = index;
+        }
+        if start < text.len() {
+            parts.push(&text[start..]);
+        }
+        parts
+    }
+
+    #[test]
+    fn test_breaks_after_spaces() {
+        assert_eq!(segments("Hello  big world"), ["Hello  ", "big ", "world"]);
+    }
+
+    #[test]
+    fn test_cjk_breaks_between_ideographs() {
+        assert_eq!(segments("日本語"), ["日", "本", "語"]);
+        // Not before 。 or small kana, not after 「
+        assert_eq!(
+            segments("「東京」です。ちょっと"),
+            ["「東", "京」", "で", "す。", "ちょっ", "と"]
+        );
+    }
+
+    #[test]
+    fn test_hangul_syllables() {
+        assert_eq!(
+            segments("한국어 텍스트"),
+            ["한", "국", "어 ", "텍", "스", "트"]
+        );
+    }
+
+    #[test]
+    fn test_glue_and_hyphens() {
+        assert_eq!(segments("100\u{A0}km"), ["100\u{A0}km"]);
+        assert_eq!(segments("well-known"), ["well-", "known"]);
+        // A leading minus sign stays with its number
+        assert_eq!(segments("x -5"), ["x ", "-5"]);
+        assert_eq!(segments("a\u{2060}b"), ["a\u{2060}b"]);
+    }
+
+    #[test]
+    fn test_punctuation_and_numbers() {
+        assert_eq!(segments("1,000.50 (USD)!"), ["1,000.50 ", "(USD)!"]);
+        assert_eq!(segments("$5 50%"), ["$5 ", "50%"]);
+    }
+
+    #[test]
+    fn test_mandatory_breaks() {
+        let breaks = break_opportunities("a\r\nb\n");
+        assert_eq!(breaks, [(3, Break::Mandatory), (5, Break::Mandatory)]);
+    }
+
+    #[test]
+    fn test_combining_marks_stay_with_base() {
+        // e + combining acute, then an ideograph
+        assert_eq!(segments("e\u{301}字"), ["e\u{301}", "字"]);
+    }
+}
@@ -14,6 +14,8 @@ use crate::box_model::Rect;
 use crate::layout_box::LayoutBox;
 use alloc::string::String;
 use alloc::vec::Vec;
+use kpio_css::transform::TransformMatrix;
+use kpio_css::values::LengthContext;
 
 /// A color in RGBA format
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
@@ -149,10 +151,12 @@ pub enum DisplayCommand {
         radii: BorderRadii,
     },
 
-    /// Draw a box shadow
+    /// Draw a box shadow of a (rounded) rectangle: outside it, or inside
+    /// it when `inset`
     BoxShadow {
         color: Color,
         rect: Rect,
+        radii: BorderRadii,
         blur_radius: f32,
         spread_radius: f32,
         offset_x: f32,
@@ -163,7 +167,10 @@ pub enum DisplayCommand {
     /// Push a clip rectangle (subsequent commands clipped to this rect)
     PushClip { rect: Rect },
 
-    /// Pop the most recent clip rectangle
+    /// Push a clip to a rounded rectangle, popped by `PopClip`
+    PushRoundedClip { rect: Rect, radii: BorderRadii },
+
+    /// Pop the most recent clip
     PopClip,
 
     /// Push a transform
@@ -174,10 +181,10 @@ pub enum DisplayCommand {
     /// Pop a transform
     PopTransform,
 
-    /// Set opacity for subsequent commands
+    /// Composite subsequent commands as one group at this opacity
     PushOpacity { opacity: f32 },
 
-    /// Restore previous opacity
+    /// End the most recent opacity group
     PopOpacity,
 }
 
@@ -191,7 +198,7 @@ pub struct BorderWidths {
 }
 
 /// Border radii for rounded corners
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct BorderRadii {
     pub top_left: f32,
     pub top_right: f32,
@@ -208,6 +215,23 @@ impl BorderRadii {
             bottom_left: radius,
         }
     }
+
+    pub fn is_zero(&self) -> bool {
+        self.top_left <= 0.0
+            && self.top_right <= 0.0
+            && self.bottom_right <= 0.0
+            && self.bottom_left <= 0.0
+    }
+
+    /// The radii of the curve inside a border of the given widths
+    pub fn inner(&self, widths: &BorderWidths) -> Self {
+        Self {
+            top_left: (self.top_left - widths.left).max(0.0),
+            top_right: (self.top_right - widths.right).max(0.0),
+            bottom_right: (self.bottom_right - widths.right).max(0.0),
+            bottom_left: (self.bottom_left - widths.left).max(0.0),
+        }
+    }
 }
 
 /// A display list - ordered sequence of paint commands
@@ -267,8 +291,33 @@ pub fn build_display_list(layout_root: &LayoutBox) -> DisplayList {
 
 /// Paint a single layout box and its children
 fn paint_layout_box(display_list: &mut DisplayList, layout_box: &LayoutBox) {
-    // Paint background
-    paint_background(display_list, layout_box);
+    let style = &layout_box.style;
+    // Nothing inside a fully transparent box can show
+    if style.opacity <= 0.0 {
+        return;
+    }
+
+    // Transform the box and everything painted inside it
+    let transform = box_transform(layout_box);
+    if let Some(matrix) = transform {
+        display_list.push(DisplayCommand::PushTransform { matrix: matrix.0 });
+    }
+
+    // Fade the box and its descendants together, not one by one
+    let group_opacity = style.opacity < 1.0;
+    if group_opacity {
+        display_list.push(DisplayCommand::PushOpacity {
+            opacity: style.opacity,
+        });
+    }
+
+    let radii = border_radii(layout_box);
+    let inner_radii = radii.inner(&border_widths(layout_box));
+
+    // Outer shadows sit beneath the background, inset ones above it
+    paint_box_shadows(display_list, layout_box, radii, false);
+    paint_background(display_list, layout_box, radii);
+    paint_box_shadows(display_list, layout_box, inner_radii, true);
 
     // Paint borders
     paint_borders(display_list, layout_box);
@@ -278,23 +327,147 @@ fn paint_layout_box(display_list: &mut DisplayList, layout_box: &LayoutBox) {
         paint_text(display_list, layout_box, text);
     }
 
+    // Clip overflowing descendants to the padding box
+    if style.clip_overflow {
+        let rect = layout_box.dimensions.padding_box();
+        display_list.push(if inner_radii.is_zero() {
+            DisplayCommand::PushClip { rect }
+        } else {
+            DisplayCommand::PushRoundedClip {
+                rect,
+                radii: inner_radii,
+            }
+        });
+    }
+
     // Paint children (in document order for now)
     for child in &layout_box.children {
         paint_layout_box(display_list, child);
     }
+
+    if style.clip_overflow {
+        display_list.push(DisplayCommand::PopClip);
+    }
+    if group_opacity {
+        display_list.push(DisplayCommand::PopOpacity);
+    }
+    if transform.is_some() {
+        display_list.push(DisplayCommand::PopTransform);
+    }
+}
+
+/// The context a box's own lengths resolve against at paint time
+fn length_context(layout_box: &LayoutBox) -> LengthContext {
+    LengthContext {
+        font_size: layout_box.style.font_size,
+        ..LengthContext::default()
+    }
+}
+
+/// Resolve a box's `border-radius` against its border box
+fn border_radii(layout_box: &LayoutBox) -> BorderRadii {
+    let border_radius = &layout_box.style.border_radius;
+    if border_radius.is_zero() {
+        return BorderRadii::default();
+    }
+
+    let border_box = layout_box.dimensions.border_box();
+    let [top_left, top_right, bottom_right, bottom_left] = border_radius.resolve(
+        border_box.width,
+        border_box.height,
+        &length_context(layout_box),
+    );
+    BorderRadii {
+        top_left,
+        top_right,
+        bottom_right,
+        bottom_left,
+    }
+}
+
+fn border_widths(layout_box: &LayoutBox) -> BorderWidths {
+    let border = &layout_box.dimensions.border;
+    BorderWidths {
+        top: border.top,
+        right: border.right,
+        bottom: border.bottom,
+        left: border.left,
+    }
+}
+
+/// Compose a box's `transform` about its `transform-origin`, in the same
+/// coordinate space as its border box. `None` if it is not transformed.
+fn box_transform(layout_box: &LayoutBox) -> Option<TransformMatrix> {
+    let style = &layout_box.style;
+    if style.transform.is_empty() {
+        return None;
+    }
+
+    let border_box = layout_box.dimensions.border_box();
+    let context = length_context(layout_box);
+    let (origin_x, origin_y) =
+        style
+            .transform_origin
+            .resolve(border_box.width, border_box.height, &context);
+    let matrix = TransformMatrix::from_functions(
+        &style.transform,
+        border_box.width,
+        border_box.height,
+        &context,
+    )
+    .about(border_box.x + origin_x, border_box.y + origin_y);
+
+    (!matrix.is_identity()).then_some(matrix)
 }
 
 /// Paint the background of a box
-fn paint_background(display_list: &mut DisplayList, layout_box: &LayoutBox) {
-    // Get background color (default to transparent)
-    // In a full implementation, this would come from computed style
-    let background_color = Color::transparent();
+fn paint_background(display_list: &mut DisplayList, layout_box: &LayoutBox, radii: BorderRadii) {
+    let color = Color::from_css(&layout_box.style.background_color);
 
     // Only paint if not transparent
-    if background_color.a > 0 {
-        display_list.push(DisplayCommand::SolidRect {
-            color: background_color,
-            rect: layout_box.dimensions.border_box(),
+    if color.a == 0 {
+        return;
+    }
+
+    let rect = layout_box.dimensions.border_box();
+    display_list.push(if radii.is_zero() {
+        DisplayCommand::SolidRect { color, rect }
+    } else {
+        DisplayCommand::RoundedRect { color, rect, radii }
+    });
+}
+
+/// Paint either the outer shadows of a box, around its border box, or
+/// its inset shadows, inside its padding box. The first shadow listed
+/// ends up on top.
+fn paint_box_shadows(
+    display_list: &mut DisplayList,
+    layout_box: &LayoutBox,
+    radii: BorderRadii,
+    inset: bool,
+) {
+    let style = &layout_box.style;
+    let rect = if inset {
+        layout_box.dimensions.padding_box()
+    } else {
+        layout_box.dimensions.border_box()
+    };
+    let context = length_context(layout_box);
+
+    for shadow in style.box_shadow.iter().rev() {
+        let color = Color::from_css(&shadow.color.unwrap_or(style.color));
+        if shadow.inset != inset || color.a == 0 {
+            continue;
+        }
+        display_list.push(DisplayCommand::BoxShadow {
+            color,
+            rect,
+            radii,
+            blur_radius: shadow.blur.to_px(&context),
+            spread_radius: shadow.spread.to_px(&context),
+            offset_x: shadow.offset_x.to_px(&context),
+            offset_y: shadow.offset_y.to_px(&context),
+            inset,
         });
     }
 }
@@ -314,12 +487,7 @@ fn paint_borders(display_list: &mut DisplayList, layout_box: &LayoutBox) {
     display_list.push(DisplayCommand::Border {
         color: Color::black(), // Would come from style
         rect: border_box,
-        widths: BorderWidths {
-            top: border.top,
-            right: border.right,
-            bottom: border.bottom,
-            left: border.left,
-        },
+        widths: border_widths(layout_box),
         style: BorderStyle::Solid,
     });
 }
@@ -380,4 +548,142 @@ mod tests {
         // Empty box with no visible content produces minimal commands
         assert!(display_list.len() < 10);
     }
+
+    #[test]
+    fn test_transform_wraps_subtree() {
+        use alloc::vec;
+        use kpio_css::transform::{TransformFunction, TransformOrigin};
+        use kpio_css::values::Length;
+
+        let mut layout_box = LayoutBox::new(BoxType::Block);
+        layout_box.dimensions.content = Rect::new(100.0, 50.0, 40.0, 20.0);
+        layout_box.style.transform = vec![TransformFunction::Scale(2.0, 2.0)];
+        layout_box.style.transform_origin = TransformOrigin::default();
+        layout_box.add_child(LayoutBox::anonymous_inline("hi".into()));
+
+        let commands = build_display_list(&layout_box).into_commands();
+        assert!(matches!(
+            commands.last(),
+            Some(DisplayCommand::PopTransform)
+        ));
+        match commands[0] {
+            // Scaled about the center of the box, (120, 60)
+            DisplayCommand::PushTransform { matrix } => {
+                assert_eq!(matrix, [2.0, 0.0, 0.0, 2.0, -120.0, -60.0])
+            }
+            ref other => panic!("unexpected command {:?}", other),
+        }
+        assert!(commands
+            .iter()
+            .any(|command| matches!(command, DisplayCommand::Text { .. })));
+
+        // An identity transform paints nothing extra
+        layout_box.style.transform =
+            vec![TransformFunction::Translate(Length::zero(), Length::zero())];
+        let commands = build_display_list(&layout_box).into_commands();
+        assert!(!commands
+            .iter()
+            .any(|command| matches!(command, DisplayCommand::PushTransform { .. })));
+    }
+
+    #[test]
+    fn test_shadows_background_and_opacity_order() {
+        use alloc::vec;
+        use kpio_css::effects::{BorderRadius, BoxShadow};
+        use kpio_css::values::Length;
+
+        let shadow = |offset: f32, inset: bool| BoxShadow {
+            offset_x: Length::px(offset),
+            offset_y: Length::px(offset),
+            blur: Length::px(4.0),
+            spread: Length::zero(),
+            color: None,
+            inset,
+        };
+        let mut layout_box = LayoutBox::new(BoxType::Block);
+        layout_box.dimensions.content = Rect::new(10.0, 10.0, 100.0, 40.0);
+        layout_box.style.background_color = kpio_css::values::Color::rgb(0, 0, 255);
+        layout_box.style.border_radius = BorderRadius {
+            top_left: Length::px(8.0),
+            ..BorderRadius::default()
+        };
+        layout_box.style.box_shadow =
+            vec![shadow(1.0, false), shadow(2.0, false), shadow(0.0, true)];
+        layout_box.style.opacity = 0.5;
+
+        let commands = build_display_list(&layout_box).into_commands();
+        assert!(matches!(commands[0], DisplayCommand::PushOpacity { opacity } if opacity == 0.5));
+        // The first shadow is painted last, so on top
+        assert!(matches!(
+            commands[1],
+            DisplayCommand::BoxShadow { offset_x, inset: false, .. } if offset_x == 2.0
+        ));
+        assert!(matches!(
+            commands[2],
+            DisplayCommand::BoxShadow { offset_x, color, .. }
+                if offset_x == 1.0 && color == Color::black()
+        ));
+        match commands[3] {
+            DisplayCommand::RoundedRect { radii, .. } => {
+                assert_eq!(
+                    radii,
+                    BorderRadii {
+                        top_left: 8.0,
+                        ..BorderRadii::default()
+                    }
+                )
+            }
+            ref other => panic!("unexpected command {:?}", other),
+        }
+        assert!(matches!(
+            commands[4],
+            DisplayCommand::BoxShadow { inset: true, .. }
+        ));
+        assert!(matches!(commands[5], DisplayCommand::PopOpacity));
+        assert_eq!(commands.len(), 6);
+
+        // Nothing of a fully transparent box is painted
+        layout_box.style.opacity = 0.0;
+        assert!(build_display_list(&layout_box).is_empty());
+    }
+
+    #[test]
+    fn test_overflow_clips_children_to_padding_box() {
+        use kpio_css::effects::BorderRadius;
+        use kpio_css::values::Length;
+
+        let mut layout_box = LayoutBox::new(BoxType::Block);
+        layout_box.dimensions.content = Rect::new(10.0, 10.0, 100.0, 40.0);
+        layout_box.dimensions.border = crate::box_model::EdgeSizes::new(2.0, 2.0, 2.0, 2.0);
+        layout_box.style.clip_overflow = true;
+        layout_box.add_child(LayoutBox::anonymous_inline("hi".into()));
+
+        let commands = build_display_list(&layout_box).into_commands();
+        let clip = commands
+            .iter()
+            .position(|command| {
+                matches!(command, DisplayCommand::PushClip { rect }
+                    if *rect == layout_box.dimensions.padding_box())
+            })
+            .unwrap();
+        let text = commands
+            .iter()
+            .position(|command| matches!(command, DisplayCommand::Text { .. }))
+            .unwrap();
+        assert!(clip < text);
+        assert!(matches!(commands.last(), Some(DisplayCommand::PopClip)));
+
+        // Rounded corners clip along the inside of the border
+        layout_box.style.border_radius = BorderRadius {
+            top_left: Length::px(10.0),
+            top_right: Length::px(10.0),
+            bottom_right: Length::px(10.0),
+            bottom_left: Length::px(10.0),
+        };
+        let commands = build_display_list(&layout_box).into_commands();
+        assert!(commands.iter().any(|command| matches!(
+            command,
+            DisplayCommand::PushRoundedClip { radii, .. } if *radii == BorderRadii::uniform(8.0)
+        )));
+    }
 }
@@ -0,0 +1,855 @@
+//! Table Layout Algorithm
+//!
+//! This module implements the CSS 2.1 automatic table layout algorithm
+//! (section 17.5.2.2). Column widths come from the minimum and maximum
+//! content widths of the cells in each column, and any width the table
+//! has beyond the columns' maximum widths is shared in proportion to them.
+//!
+//! ## Table Structure
+//!
+//! - **Rows**: `display: table-row` children of the table or of its row
+//!   groups; header group rows come first and footer group rows last
+//! - **Cells**: Children of a row, placed left to right in the first free
+//!   grid slots; `col_span` and `row_span` make a cell cover several slots
+//! - **Captions**: `display: table-caption` children, stacked above the rows
+//!
+//! ## Border Models
+//!
+//! - **Separate**: Every cell keeps its own border and cells are spaced by
+//!   `border-spacing`
+//! - **Collapse**: Neighbouring cells share one border, the wider of the
+//!   two, and each side takes half of it. Table padding is ignored.
+
+use crate::block::{layout_block, layout_block_children, resolve_block_width};
+use crate::box_model::{BoxDimensions, EdgeSizes, Rect, ResolvedLength};
+use crate::inline::{layout_inline_children, FontMetrics};
+use crate::layout_box::{BoxType, ContainingBlock, LayoutBox, LayoutContext};
+use alloc::vec;
+use alloc::vec::Vec;
+use kpio_css::values::{Display, VerticalAlign};
+
+/// Table border model (`border-collapse`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BorderCollapse {
+    /// Cells have their own borders, separated by `border-spacing`
+    #[default]
+    Separate,
+    /// Neighbouring cells share their borders
+    Collapse,
+}
+
+/// Location of a row box in the table's box tree
+#[derive(Debug, Clone, Copy)]
+struct RowRef {
+    /// Index of the row, or of its row group, among the table's children
+    child: usize,
+    /// Index of the row within its row group
+    group_child: Option<usize>,
+}
+
+/// A cell placed in the table grid
+#[derive(Debug)]
+struct GridCell {
+    /// Row the cell starts in
+    row: usize,
+    /// Index of the cell among its row's children
+    child: usize,
+    /// Column the cell starts in
+    col: usize,
+    /// Number of columns covered
+    col_span: usize,
+    /// Number of rows covered
+    row_span: usize,
+    /// Border widths used for layout
+    border: EdgeSizes,
+    /// Minimum border-box width
+    min_width: f32,
+    /// Maximum border-box width
+    max_width: f32,
+    /// Border-box height of the laid out content
+    height: f32,
+}
+
+/// Perform table layout on a `display: table` box
+pub fn layout_table(
+    layout_box: &mut LayoutBox,
+    containing_block: ContainingBlock,
+    context: &LayoutContext,
+) {
+    let collapse = layout_box.style.border_collapse == BorderCollapse::Collapse;
+    let (h_spacing, v_spacing) = if collapse {
+        (0.0, 0.0)
+    } else {
+        (
+            layout_box.style.border_spacing_horizontal,
+            layout_box.style.border_spacing_vertical,
+        )
+    };
+
+    // Step 1: Place cells in the grid
+    let rows = collect_rows(layout_box);
+    let (mut cells, columns) = place_cells(layout_box, &rows);
+
+    // Step 2: Resolve borders
+    let table_border = if collapse {
+        collapse_borders(layout_box, &rows, &mut cells, columns)
+    } else {
+        for cell in &mut cells {
+            cell.border = cell_box(layout_box, &rows, cell).style.border();
+        }
+        layout_box.style.border()
+    };
+
+    // Step 3: Measure cells and compute column widths
+    let metrics = FontMetrics::from_style(&layout_box.style).unwrap_or_default();
+    for cell in &mut cells {
+        let (min, max) = cell_widths(cell_box(layout_box, &rows, cell), cell.border, &metrics);
+        cell.min_width = min;
+        cell.max_width = max;
+    }
+    let (min_widths, max_widths) = column_bounds(&cells, columns, h_spacing);
+
+    // Step 4: Resolve the table width
+    let style = &layout_box.style;
+    let d = &mut layout_box.dimensions;
+    d.padding = if collapse {
+        EdgeSizes::zero()
+    } else {
+        style.padding()
+    };
+    d.border = table_border;
+    let padding_border = d.padding.horizontal() + d.border.horizontal();
+    let spacing = if columns > 0 {
+        h_spacing * (columns + 1) as f32
+    } else {
+        0.0
+    };
+    let min_total = min_widths.iter().sum::<f32>() + spacing;
+    let max_total = max_widths.iter().sum::<f32>() + spacing;
+    let table_width = match style.width {
+        ResolvedLength::Px(width) => width.max(min_total),
+        ResolvedLength::Auto => {
+            let available = containing_block.width
+                - padding_border
+                - style.margin_left.to_px()
+                - style.margin_right.to_px();
+            max_total.min(available).max(min_total)
+        }
+    };
+    let (width, margin_left, margin_right) = resolve_block_width(
+        ResolvedLength::Px(table_width),
+        style.margin_left,
+        style.margin_right,
+        containing_block.width,
+        padding_border,
+    );
+    d.content.width = width;
+    d.margin.left = margin_left;
+    d.margin.right = margin_right;
+    d.margin.top = style.margin_top.to_px();
+    d.margin.bottom = style.margin_bottom.to_px();
+    d.content.x = containing_block.x + d.margin.left + d.border.left + d.padding.left;
+    d.content.y = containing_block.y + d.margin.top + d.border.top + d.padding.top;
+
+    let widths = distribute_width(&min_widths, &max_widths, width - spacing);
+    let content = layout_box.dimensions.content;
+
+    // Step 5: Captions go above the rows
+    let mut current_y = content.y;
+    for child in &mut layout_box.children {
+        if child.style.display == Display::TableCaption {
+            let cb = ContainingBlock {
+                width: content.width,
+                height: 0.0,
+                x: content.x,
+                y: current_y,
+            };
+            layout_block(child, cb, context);
+            current_y = child.dimensions.margin_box().bottom();
+        }
+    }
+
+    // Step 6: Lay out cells at their column widths
+    let mut col_x = Vec::with_capacity(columns);
+    let mut x = content.x + h_spacing;
+    for width in &widths {
+        col_x.push(x);
+        x += width + h_spacing;
+    }
+    for cell in &mut cells {
+        let last = cell.col + cell.col_span - 1;
+        let width = col_x[last] + widths[last] - col_x[cell.col];
+        let x = col_x[cell.col];
+        let cell_box = cell_box_mut(layout_box, &rows, cell);
+        layout_cell(cell_box, cell.border, x, width, context);
+        cell.height = cell_box.dimensions.border_box().height;
+    }
+
+    // Step 7: Row heights
+    let row_heights = row_heights(layout_box, &rows, &cells, v_spacing);
+    let mut row_y = Vec::with_capacity(rows.len());
+    let mut y = current_y + v_spacing;
+    for height in &row_heights {
+        row_y.push(y);
+        y += height + v_spacing;
+    }
+    let rows_bottom = if rows.is_empty() { current_y } else { y };
+
+    // Step 8: Move cells into their rows and stretch them over the rows
+    // they span
+    for cell in &cells {
+        let last = cell.row + cell.row_span - 1;
+        let span_height = row_y[last] + row_heights[last] - row_y[cell.row];
+        let cell_box = cell_box_mut(layout_box, &rows, cell);
+        let offset = match cell_box.style.vertical_align {
+            VerticalAlign::Middle => (span_height - cell.height) / 2.0,
+            VerticalAlign::Bottom => span_height - cell.height,
+            _ => 0.0,
+        };
+        translate(cell_box, 0.0, row_y[cell.row]);
+        for child in &mut cell_box.children {
+            translate(child, 0.0, offset);
+        }
+        let d = &mut cell_box.dimensions;
+        d.content.height = span_height - d.padding.vertical() - d.border.vertical();
+    }
+
+    // Step 9: Row and row group boxes cover the column area
+    let columns_x = content.x + h_spacing;
+    let columns_width = match (col_x.last(), widths.last()) {
+        (Some(x), Some(width)) => x + width - columns_x,
+        _ => 0.0,
+    };
+    for (index, row) in rows.iter().enumerate() {
+        row_box_mut(layout_box, *row).dimensions = BoxDimensions::from_content(Rect::new(
+            columns_x,
+            row_y[index],
+            columns_width,
+            row_heights[index],
+        ));
+    }
+    for group in &mut layout_box.children {
+        if is_row_group(group.style.display) {
+            let mut rect: Option<Rect> = None;
+            for row in &group.children {
+                if row.style.display != Display::TableRow {
+                    continue;
+                }
+                let row_rect = row.dimensions.content;
+                rect = Some(match rect {
+                    Some(r) => Rect::new(r.x, r.y, r.width, row_rect.bottom() - r.y),
+                    None => row_rect,
+                });
+            }
+            group.dimensions = BoxDimensions::from_content(rect.unwrap_or(Rect::new(
+                columns_x,
+                rows_bottom,
+                columns_width,
+                0.0,
+            )));
+        }
+    }
+
+    // Step 10: Table height
+    let height = rows_bottom - content.y;
+    layout_box.dimensions.content.height = match layout_box.style.height {
+        ResolvedLength::Px(h) => h.max(height),
+        ResolvedLength::Auto => height,
+    };
+}
+
+/// Check if a display value is a row group
+fn is_row_group(display: Display) -> bool {
+    matches!(
+        display,
+        Display::TableRowGroup | Display::TableHeaderGroup | Display::TableFooterGroup
+    )
+}
+
+/// Collect the table's rows in display order
+fn collect_rows(table: &LayoutBox) -> Vec<RowRef> {
+    let mut head = Vec::new();
+    let mut body = Vec::new();
+    let mut foot = Vec::new();
+
+    for (child, row) in table.children.iter().enumerate() {
+        let display = row.style.display;
+        if display == Display::TableRow {
+            body.push(RowRef {
+                child,
+                group_child: None,
+            });
+        } else if is_row_group(display) {
+            let rows = row
+                .children
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.style.display == Display::TableRow)
+                .map(|(i, _)| RowRef {
+                    child,
+                    group_child: Some(i),
+                });
+            match display {
+                Display::TableHeaderGroup => head.extend(rows),
+                Display::TableFooterGroup => foot.extend(rows),
+                _ => body.extend(rows),
+            }
+        }
+    }
+
+    head.extend(body);
+    head.extend(foot);
+    head
+}
+
+fn row_box(table: &LayoutBox, row: RowRef) -> &LayoutBox {
+    let child = &table.children[row.child];
+    match row.group_child {
+        Some(i) => &child.children[i],
+        None => child,
+    }
+}
+
+fn row_box_mut(table: &mut LayoutBox, row: RowRef) -> &mut LayoutBox {
+    let child = &mut table.children[row.child];
+    match row.group_child {
+        Some(i) => &mut child.children[i],
+        None => child,
+    }
+}
+
+fn cell_box<'a>(table: &'a LayoutBox, rows: &[RowRef], cell: &GridCell) -> &'a LayoutBox {
+    &row_box(table, rows[cell.row]).children[cell.child]
+}
+
+fn cell_box_mut<'a>(
+    table: &'a mut LayoutBox,
+    rows: &[RowRef],
+    cell: &GridCell,
+) -> &'a mut LayoutBox {
+    &mut row_box_mut(table, rows[cell.row]).children[cell.child]
+}
+
+/// Place each row's cells in the first free grid slots, skipping slots
+/// taken by cells spanning down from earlier rows
+///
+/// Returns the cells and the number of columns.
+fn place_cells(table: &LayoutBox, rows: &[RowRef]) -> (Vec<GridCell>, usize) {
+    let mut occupied: Vec<Vec<bool>> = vec![Vec::new(); rows.len()];
+    let mut cells = Vec::new();
+
+    for (row, row_ref) in rows.iter().enumerate() {
+        let mut col = 0;
+        for (child, cell) in row_box(table, *row_ref).children.iter().enumerate() {
+            if cell.box_type == BoxType::None {
+                continue;
+            }
+            while occupied[row].get(col) == Some(&true) {
+                col += 1;
+            }
+
+            let col_span = cell.style.col_span.max(1) as usize;
+            let row_span = (cell.style.row_span.max(1) as usize).min(rows.len() - row);
+            for slots in &mut occupied[row..row + row_span] {
+                if slots.len() < col + col_span {
+                    slots.resize(col + col_span, false);
+                }
+                slots[col..col + col_span].fill(true);
+            }
+
+            cells.push(GridCell {
+                row,
+                child,
+                col,
+                col_span,
+                row_span,
+                border: EdgeSizes::zero(),
+                min_width: 0.0,
+                max_width: 0.0,
+                height: 0.0,
+            });
+            col += col_span;
+        }
+    }
+
+    let columns = occupied.iter().map(Vec::len).max().unwrap_or(0);
+    (cells, columns)
+}
+
+/// Resolve collapsed borders
+///
+/// Each cell edge becomes the widest of the borders meeting there (the
+/// cell's own, its neighbours', or the table's at the outside), and the
+/// cell takes half of it. Returns the table's border, which is half of
+/// the widest outer edge on each side.
+fn collapse_borders(
+    table: &LayoutBox,
+    rows: &[RowRef],
+    cells: &mut [GridCell],
+    columns: usize,
+) -> EdgeSizes {
+    // Owner of each grid slot
+    let mut owners: Vec<Vec<Option<usize>>> = vec![vec![None; columns]; rows.len()];
+    for (index, cell) in cells.iter().enumerate() {
+        for slots in &mut owners[cell.row..cell.row + cell.row_span] {
+            slots[cell.col..cell.col + cell.col_span].fill(Some(index));
+        }
+    }
+
+    let styles: Vec<EdgeSizes> = cells
+        .iter()
+        .map(|cell| cell_box(table, rows, cell).style.border())
+        .collect();
+    let outer = table.style.border();
+    let neighbour = |row: usize, col: usize| owners[row][col].map(|i| styles[i]);
+    let mut table_border = EdgeSizes::zero();
+
+    for (index, cell) in cells.iter_mut().enumerate() {
+        let own = styles[index];
+        let last_row = cell.row + cell.row_span - 1;
+        let last_col = cell.col + cell.col_span - 1;
+        let rows_spanned = cell.row..=last_row;
+        let cols_spanned = cell.col..=last_col;
+
+        let left = if cell.col == 0 {
+            own.left.max(outer.left)
+        } else {
+            rows_spanned
+                .clone()
+                .filter_map(|r| neighbour(r, cell.col - 1))
+                .fold(own.left, |w, n| w.max(n.right))
+        };
+        let right = if last_col + 1 == columns {
+            own.right.max(outer.right)
+        } else {
+            rows_spanned
+                .filter_map(|r| neighbour(r, last_col + 1))
+                .fold(own.right, |w, n| w.max(n.left))
+        };
+        let top = if cell.row == 0 {
+            own.top.max(outer.top)
+        } else {
+            cols_spanned
+                .clone()
+                .filter_map(|c| neighbour(cell.row - 1, c))
+                .fold(own.top, |w, n| w.max(n.bottom))
+        };
+        let bottom = if last_row + 1 == rows.len() {
+            own.bottom.max(outer.bottom)
+        } else {
+            cols_spanned
+                .filter_map(|c| neighbour(last_row + 1, c))
+                .fold(own.bottom, |w, n| w.max(n.top))
+        };
+
+        if cell.col == 0 {
+            table_border.left = table_border.left.max(left / 2.0);
+        }
+        if last_col + 1 == columns {
+            table_border.right = table_border.right.max(right / 2.0);
+        }
+        if cell.row == 0 {
+            table_border.top = table_border.top.max(top / 2.0);
+        }
+        if last_row + 1 == rows.len() {
+            table_border.bottom = table_border.bottom.max(bottom / 2.0);
+        }
+        cell.border = EdgeSizes::new(top / 2.0, right / 2.0, bottom / 2.0, left / 2.0);
+    }
+
+    if cells.is_empty() {
+        outer
+    } else {
+        table_border
+    }
+}
+
+/// Minimum and maximum border-box widths of a cell
+///
+/// A specified cell width raises the minimum, but content that cannot be
+/// made narrower still wins.
+fn cell_widths(cell: &LayoutBox, border: EdgeSizes, metrics: &FontMetrics) -> (f32, f32) {
+    let metrics = FontMetrics::from_style(&cell.style).unwrap_or(*metrics);
+    let (mut min, mut max) = content_widths(&cell.children, &metrics);
+    if let ResolvedLength::Px(width) = cell.style.width {
+        min = min.max(width);
+        max = min;
+    }
+    let extra = cell.style.padding().horizontal() + border.horizontal();
+    (min + extra, max.max(min) + extra)
+}
+
+/// Minimum and maximum widths of a box's margin box
+///
+/// The minimum is the widest word or fixed-width box in it; the maximum
+/// is its width with no line breaks at all. Text is measured with the
+/// average character width of `metrics`.
+pub fn intrinsic_widths(layout_box: &LayoutBox, metrics: &FontMetrics) -> (f32, f32) {
+    if layout_box.box_type == BoxType::None {
+        return (0.0, 0.0);
+    }
+    if let Some(ref text) = layout_box.text {
+        let longest_word = text
+            .split_whitespace()
+            .map(|word| word.chars().count())
+            .max()
+            .unwrap_or(0);
+        return (
+            longest_word as f32 * metrics.avg_char_width,
+            text.chars().count() as f32 * metrics.avg_char_width,
+        );
+    }
+
+    let style = &layout_box.style;
+    let metrics = FontMetrics::from_style(style).unwrap_or(*metrics);
+    let (min, max) = match style.width {
+        ResolvedLength::Px(width) => (width, width),
+        ResolvedLength::Auto => content_widths(&layout_box.children, &metrics),
+    };
+    let extra = style.padding().horizontal()
+        + style.border().horizontal()
+        + style.margin_left.to_px()
+        + style.margin_right.to_px();
+    (min + extra, max + extra)
+}
+
+/// Minimum and maximum widths of a sequence of boxes, with consecutive
+/// inline boxes sharing a line
+fn content_widths(children: &[LayoutBox], metrics: &FontMetrics) -> (f32, f32) {
+    let mut min = 0.0f32;
+    let mut max = 0.0f32;
+    let mut line = 0.0f32;
+
+    for child in children {
+        let (child_min, child_max) = intrinsic_widths(child, metrics);
+        min = min.max(child_min);
+        if child.box_type.is_inline() {
+            line += child_max;
+        } else {
+            max = max.max(line).max(child_max);
+            line = 0.0;
+        }
+    }
+
+    (min, max.max(line))
+}
+
+/// Minimum and maximum width of each column
+///
+/// Single-column cells are applied first. A cell spanning several columns
+/// then widens them, in proportion to their maximum widths, until they
+/// and the spacing between them fit the cell.
+fn column_bounds(cells: &[GridCell], columns: usize, spacing: f32) -> (Vec<f32>, Vec<f32>) {
+    let mut min = vec![0.0f32; columns];
+    let mut max = vec![0.0f32; columns];
+
+    let mut ordered: Vec<&GridCell> = cells.iter().collect();
+    ordered.sort_by_key(|cell| cell.col_span);
+
+    for cell in ordered {
+        let span = cell.col..cell.col + cell.col_span;
+        let gaps = spacing * (cell.col_span - 1) as f32;
+        let weights: Vec<f32> = max[span.clone()].to_vec();
+        grow_to(&mut min[span.clone()], &weights, cell.min_width - gaps);
+        grow_to(&mut max[span.clone()], &weights, cell.max_width - gaps);
+        for col in span {
+            max[col] = max[col].max(min[col]);
+        }
+    }
+
+    (min, max)
+}
+
+/// Grow `widths` until they add up to at least `target`
+fn grow_to(widths: &mut [f32], weights: &[f32], target: f32) {
+    let current: f32 = widths.iter().sum();
+    if target > current {
+        share(widths, weights, target - current);
+    }
+}
+
+/// Add `extra` to `widths` in proportion to `weights`, or equally if the
+/// weights are all zero
+fn share(widths: &mut [f32], weights: &[f32], extra: f32) {
+    let total: f32 = weights.iter().sum();
+    let count = widths.len() as f32;
+    for (width, weight) in widths.iter_mut().zip(weights) {
+        *width += if total > 0.0 {
+            extra * weight / total
+        } else {
+            extra / count
+        };
+    }
+}
+
+/// Column widths for the space available to the columns
+///
+/// Columns get their maximum widths plus a share of any extra space. If
+/// there is less room than that, each column gets its minimum plus a
+/// share of the remaining space proportional to how much wider it would
+/// like to be.
+fn distribute_width(min: &[f32], max: &[f32], available: f32) -> Vec<f32> {
+    let min_total: f32 = min.iter().sum();
+    let max_total: f32 = max.iter().sum();
+
+    if available >= max_total {
+        let mut widths = max.to_vec();
+        share(&mut widths, max, available - max_total);
+        widths
+    } else if max_total > min_total {
+        let ratio = (available - min_total).max(0.0) / (max_total - min_total);
+        min.iter()
+            .zip(max)
+            .map(|(min, max)| min + (max - min) * ratio)
+            .collect()
+    } else {
+        min.to_vec()
+    }
+}
+
+/// Lay out a cell's content at `x`, with a border-box width of `width`
+///
+/// The cell is laid out at the top of the table grid; it is moved into
+/// its row once the row heights are known.
+fn layout_cell(
+    cell: &mut LayoutBox,
+    border: EdgeSizes,
+    x: f32,
+    width: f32,
+    context: &LayoutContext,
+) {
+    let padding = cell.style.padding();
+    let d = &mut cell.dimensions;
+    d.padding = padding;
+    d.border = border;
+    d.margin = EdgeSizes::zero();
+    d.content = Rect::new(
+        x + border.left + padding.left,
+        border.top + padding.top,
+        (width - border.horizontal() - padding.horizontal()).max(0.0),
+        0.0,
+    );
+
+    let content = cell.dimensions.content;
+    if cell.has_inline_children() && !cell.has_block_children() {
+        layout_inline_children(cell, ContainingBlock::from_rect(content), context);
+    } else {
+        layout_block_children(cell, context);
+    }
+
+    let bottom = cell
+        .children
+        .iter()
+        .filter(|child| child.box_type != BoxType::None)
+        .map(|child| child.dimensions.margin_box().bottom())
+        .fold(content.y + cell.dimensions.content.height, f32::max);
+    let mut height = bottom - content.y;
+    if let ResolvedLength::Px(h) = cell.style.height {
+        height = height.max(h);
+    }
+    cell.dimensions.content.height = height;
+}
+
+/// Height of each row
+///
+/// A row is as tall as its tallest single-row cell. Cells spanning several
+/// rows then grow those rows equally until they fit.
+fn row_heights(table: &LayoutBox, rows: &[RowRef], cells: &[GridCell], spacing: f32) -> Vec<f32> {
+    let mut heights: Vec<f32> = rows
+        .iter()
+        .map(|row| row_box(table, *row).style.height.to_px())
+        .collect();
+
+    for cell in cells.iter().filter(|cell| cell.row_span == 1) {
+        heights[cell.row] = heights[cell.row].max(cell.height);
+    }
+
+    let mut spanning: Vec<&GridCell> = cells.iter().filter(|cell| cell.row_span > 1).collect();
+    spanning.sort_by_key(|cell| cell.row_span);
+    for cell in spanning {
+        let span = &mut heights[cell.row..cell.row + cell.row_span];
+        let available = span.iter().sum::<f32>() + spacing * (cell.row_span - 1) as f32;
+        if cell.height > available {
+            let extra = (cell.height - available) / cell.row_span as f32;
+            for height in span {
+                *height += extra;
+            }
+        }
+    }
+
+    heights
+}
+
+/// Move a box and all its descendants
+fn translate(layout_box: &mut LayoutBox, dx: f32, dy: f32) {
+    layout_box.dimensions.content.x += dx;
+    layout_box.dimensions.content.y += dy;
+    for child in &mut layout_box.children {
+        translate(child, dx, dy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    fn table_box(display: Display, children: Vec<LayoutBox>) -> LayoutBox {
+        let mut layout_box = LayoutBox::block();
+        layout_box.style.display = display;
+        layout_box.style.margin_left = ResolvedLength::Px(0.0);
+        layout_box.style.margin_right = ResolvedLength::Px(0.0);
+        layout_box.children = children;
+        layout_box
+    }
+
+    fn text_cell(text: &str) -> LayoutBox {
+        table_box(
+            Display::TableCell,
+            vec![LayoutBox::anonymous_inline(String::from(text))],
+        )
+    }
+
+    fn spanning_cell(text: &str, col_span: u32, row_span: u32) -> LayoutBox {
+        let mut cell = text_cell(text);
+        cell.style.col_span = col_span;
+        cell.style.row_span = row_span;
+        cell
+    }
+
+    fn layout(table: &mut LayoutBox, width: f32) {
+        layout_table(
+            table,
+            ContainingBlock::new(width, 600.0),
+            &LayoutContext::default(),
+        );
+    }
+
+    fn cell_rect(table: &LayoutBox, row: usize, cell: usize) -> Rect {
+        table.children[row].children[cell].dimensions.border_box()
+    }
+
+    #[test]
+    fn test_column_widths_from_content() {
+        // Default metrics: 8px per character, 20px lines
+        let mut table = table_box(
+            Display::Table,
+            vec![
+                table_box(
+                    Display::TableRow,
+                    vec![text_cell("12345"), text_cell("123456789012345")],
+                ),
+                table_box(Display::TableRow, vec![text_cell("1"), text_cell("12")]),
+            ],
+        );
+        layout(&mut table, 800.0);
+
+        assert_eq!(table.dimensions.content.width, 160.0);
+        assert_eq!(cell_rect(&table, 1, 0), Rect::new(0.0, 20.0, 40.0, 20.0));
+        assert_eq!(cell_rect(&table, 1, 1), Rect::new(40.0, 20.0, 120.0, 20.0));
+        assert_eq!(
+            table.children[1].dimensions.content,
+            Rect::new(0.0, 20.0, 160.0, 20.0)
+        );
+
+        // Extra width is shared in proportion to the maximum widths
+        table.style.width = ResolvedLength::Px(320.0);
+        layout(&mut table, 800.0);
+        assert_eq!(cell_rect(&table, 0, 0).width, 80.0);
+        assert_eq!(cell_rect(&table, 0, 1).width, 240.0);
+
+        // Without room for the maximums, columns shrink towards their
+        // minimums and text wraps
+        let mut table = table_box(
+            Display::Table,
+            vec![table_box(
+                Display::TableRow,
+                vec![text_cell("aaaa aaaa"), text_cell("bb bb bb bb")],
+            )],
+        );
+        layout(&mut table, 104.0);
+        assert_eq!(cell_rect(&table, 0, 0).width, 52.0);
+        assert_eq!(cell_rect(&table, 0, 1).width, 52.0);
+        assert!(cell_rect(&table, 0, 1).height > 20.0);
+    }
+
+    #[test]
+    fn test_spans_and_spacing() {
+        // +---+---+---+
+        // | A     | B |
+        // +---+---+   |
+        // | C | D |   |
+        // +---+---+---+
+        let mut table = table_box(
+            Display::Table,
+            vec![
+                table_box(
+                    Display::TableRow,
+                    vec![spanning_cell("AAAAAAAAAA", 2, 1), spanning_cell("B", 1, 2)],
+                ),
+                table_box(Display::TableRow, vec![text_cell("C"), text_cell("D")]),
+            ],
+        );
+        table.style.border_spacing_horizontal = 2.0;
+        table.style.border_spacing_vertical = 4.0;
+        layout(&mut table, 800.0);
+
+        // The 80px spanning cell widens C and D equally past the 2px gap
+        assert_eq!(cell_rect(&table, 1, 0), Rect::new(2.0, 28.0, 39.0, 20.0));
+        assert_eq!(cell_rect(&table, 1, 1), Rect::new(43.0, 28.0, 39.0, 20.0));
+        assert_eq!(cell_rect(&table, 0, 0), Rect::new(2.0, 4.0, 80.0, 20.0));
+        // B covers the third column and both rows
+        assert_eq!(cell_rect(&table, 0, 1), Rect::new(84.0, 4.0, 8.0, 44.0));
+        assert_eq!(table.dimensions.content.width, 94.0);
+        assert_eq!(table.dimensions.content.height, 52.0);
+    }
+
+    #[test]
+    fn test_row_groups_and_collapsed_borders() {
+        let bordered = |text: &str, width: f32| {
+            let mut cell = text_cell(text);
+            cell.style.border_top_width = width;
+            cell.style.border_right_width = width;
+            cell.style.border_bottom_width = width;
+            cell.style.border_left_width = width;
+            cell
+        };
+        let mut table = table_box(
+            Display::Table,
+            vec![
+                table_box(
+                    Display::TableFooterGroup,
+                    vec![table_box(Display::TableRow, vec![bordered("F", 2.0)])],
+                ),
+                table_box(Display::TableRow, vec![bordered("B", 2.0)]),
+                table_box(
+                    Display::TableHeaderGroup,
+                    vec![table_box(Display::TableRow, vec![bordered("H", 6.0)])],
+                ),
+            ],
+        );
+        table.style.border_collapse = BorderCollapse::Collapse;
+        table.style.border_spacing_horizontal = 10.0;
+        table.style.border_top_width = 4.0;
+        layout(&mut table, 800.0);
+
+        // Header first, footer last; the header's 6px border wins over the
+        // table's 4px one and over the body row's 2px one
+        assert_eq!(table.dimensions.border, EdgeSizes::new(3.0, 3.0, 1.0, 3.0));
+        let header = &table.children[2].children[0].children[0];
+        assert_eq!(header.dimensions.border, EdgeSizes::new(3.0, 3.0, 3.0, 3.0));
+        let body = &table.children[1].children[0];
+        assert_eq!(body.dimensions.border, EdgeSizes::new(3.0, 1.0, 1.0, 1.0));
+        let footer = &table.children[0].children[0].children[0];
+        assert_eq!(footer.dimensions.border, EdgeSizes::new(1.0, 1.0, 1.0, 1.0));
+
+        // Borders are shared, so rows meet with no spacing
+        let header_row = table.children[2].children[0].dimensions.content;
+        let body_row = table.children[1].dimensions.content;
+        assert_eq!(body_row.y, header_row.bottom());
+        assert_eq!(
+            table.children[0].dimensions.content.bottom(),
+            table.dimensions.content.bottom()
+        );
+    }
+}
@@ -0,0 +1,720 @@
+//! DEFLATE compression (RFC 1951).
+//!
+//! Raw DEFLATE streams without zlib or gzip framing, as carried by the
+//! WebSocket permessage-deflate extension. The [`Deflater`] and the
+//! [`Inflater`] keep their LZ77 window between calls, so one stream can
+//! span several messages; `reset` starts a fresh context.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Smallest LZ77 window, in bits.
+pub const MIN_WINDOW_BITS: u8 = 8;
+
+/// Largest LZ77 window, in bits.
+pub const MAX_WINDOW_BITS: u8 = 15;
+
+/// LEN and NLEN of the empty stored block that ends a sync flush.
+pub const SYNC_FLUSH_TAIL: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+
+/// DEFLATE decoding errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeflateError {
+    /// Input ended in the middle of a block.
+    UnexpectedEnd,
+    /// Block type 3 is reserved.
+    InvalidBlockType,
+    /// Stored block length does not match its one's complement.
+    InvalidStoredLength,
+    /// Malformed Huffman code lengths or an undecodable symbol.
+    InvalidCode,
+    /// Back-reference reaches before the start of the window.
+    InvalidDistance,
+    /// Output would exceed the caller's limit.
+    OutputTooLarge,
+}
+
+/// Base match length for length symbols 257..=285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+
+/// Extra bits following length symbols 257..=285.
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distance for distance symbols 0..=29.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// Extra bits following distance symbols 0..=29.
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order in which code length code lengths are sent in a dynamic block.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// End-of-block symbol.
+const END_OF_BLOCK: u16 = 256;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Candidates examined per position before settling for the best so far.
+const MAX_CHAIN: usize = 64;
+
+const HASH_BITS: u32 = 15;
+
+/// Streaming compressor.
+///
+/// Each call to [`compress`](Self::compress) emits one fixed-Huffman
+/// block followed by a sync flush, minus the trailing
+/// [`SYNC_FLUSH_TAIL`].
+pub struct Deflater {
+    /// Largest back-reference distance.
+    window: usize,
+    /// Previously compressed input still inside the window.
+    history: Vec<u8>,
+}
+
+impl Deflater {
+    /// Create a compressor with a `window_bits` LZ77 window.
+    pub fn new(window_bits: u8) -> Self {
+        Self {
+            window: 1 << window_bits.clamp(MIN_WINDOW_BITS, MAX_WINDOW_BITS),
+            history: Vec::new(),
+        }
+    }
+
+    /// Forget earlier input, so the next output decodes on its own.
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    /// Compress `data`, which may refer back into earlier calls' input.
+    pub fn compress(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = BitWriter::default();
+
+        if !data.is_empty() {
+            let start = self.history.len();
+            let mut buf = core::mem::take(&mut self.history);
+            buf.extend_from_slice(data);
+
+            // BFINAL = 0, BTYPE = 01 (fixed Huffman codes)
+            out.bits(0b010, 3);
+
+            let mut chains = HashChains::new(buf.len());
+            for pos in 0..start {
+                chains.insert(&buf, pos);
+            }
+
+            let mut pos = start;
+            while pos < buf.len() {
+                let (length, distance) = chains.longest_match(&buf, pos, self.window);
+                if length >= MIN_MATCH {
+                    write_length(&mut out, length);
+                    write_distance(&mut out, distance);
+                    for p in pos..pos + length {
+                        chains.insert(&buf, p);
+                    }
+                    pos += length;
+                } else {
+                    write_literal(&mut out, buf[pos] as u16);
+                    chains.insert(&buf, pos);
+                    pos += 1;
+                }
+            }
+            write_literal(&mut out, END_OF_BLOCK);
+
+            let keep_from = buf.len().saturating_sub(self.window);
+            buf.drain(..keep_from);
+            self.history = buf;
+        }
+
+        // Sync flush: an empty stored block, whose LEN and NLEN are left off
+        out.bits(0b000, 3);
+        out.finish()
+    }
+}
+
+/// Streaming decompressor.
+pub struct Inflater {
+    /// Largest back-reference distance accepted.
+    window: usize,
+    /// Decompressed output still inside the window.
+    history: Vec<u8>,
+}
+
+impl Inflater {
+    /// Create a decompressor accepting a `window_bits` LZ77 window.
+    pub fn new(window_bits: u8) -> Self {
+        Self {
+            window: 1 << window_bits.clamp(MIN_WINDOW_BITS, MAX_WINDOW_BITS),
+            history: Vec::new(),
+        }
+    }
+
+    /// Forget earlier output; later back-references into it are errors.
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    /// Decompress a run of complete blocks, such as a sync-flushed
+    /// message. Decoding stops at the end of the input or after the
+    /// final block, and fails once the output exceeds `limit` bytes.
+    pub fn inflate(&mut self, data: &[u8], limit: usize) -> Result<Vec<u8>, DeflateError> {
+        let start = self.history.len();
+        if let Err(e) = self.inflate_blocks(data, start, limit) {
+            self.history.truncate(start);
+            return Err(e);
+        }
+
+        let output = self.history[start..].to_vec();
+        let keep_from = self.history.len().saturating_sub(self.window);
+        self.history.drain(..keep_from);
+        Ok(output)
+    }
+
+    fn inflate_blocks(
+        &mut self,
+        data: &[u8],
+        start: usize,
+        limit: usize,
+    ) -> Result<(), DeflateError> {
+        let mut input = BitReader::new(data);
+
+        while !input.at_end() {
+            let last = input.bits(1)? == 1;
+            match input.bits(2)? {
+                0 => self.stored_block(&mut input, start, limit)?,
+                1 => {
+                    let (literals, distances) = fixed_tables()?;
+                    self.huffman_block(&mut input, &literals, &distances, start, limit)?;
+                }
+                2 => {
+                    let (literals, distances) = dynamic_tables(&mut input)?;
+                    self.huffman_block(&mut input, &literals, &distances, start, limit)?;
+                }
+                _ => return Err(DeflateError::InvalidBlockType),
+            }
+            if last {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stored_block(
+        &mut self,
+        input: &mut BitReader<'_>,
+        start: usize,
+        limit: usize,
+    ) -> Result<(), DeflateError> {
+        input.align();
+        let header = input.bytes(4)?;
+        let len = u16::from_le_bytes([header[0], header[1]]);
+        let nlen = u16::from_le_bytes([header[2], header[3]]);
+        if len != !nlen {
+            return Err(DeflateError::InvalidStoredLength);
+        }
+
+        self.reserve(start, len as usize, limit)?;
+        let bytes = input.bytes(len as usize)?;
+        self.history.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn huffman_block(
+        &mut self,
+        input: &mut BitReader<'_>,
+        literals: &Huffman,
+        distances: &Huffman,
+        start: usize,
+        limit: usize,
+    ) -> Result<(), DeflateError> {
+        loop {
+            let symbol = literals.decode(input)?;
+            match symbol {
+                0..=255 => {
+                    self.reserve(start, 1, limit)?;
+                    self.history.push(symbol as u8);
+                }
+                END_OF_BLOCK => return Ok(()),
+                257..=285 => {
+                    let index = (symbol - 257) as usize;
+                    let length =
+                        LENGTH_BASE[index] as usize + input.bits(LENGTH_EXTRA[index])? as usize;
+
+                    let index = distances.decode(input)? as usize;
+                    if index >= DISTANCE_BASE.len() {
+                        return Err(DeflateError::InvalidCode);
+                    }
+                    let distance =
+                        DISTANCE_BASE[index] as usize + input.bits(DISTANCE_EXTRA[index])? as usize;
+                    if distance > self.history.len() || distance > self.window {
+                        return Err(DeflateError::InvalidDistance);
+                    }
+
+                    self.reserve(start, length, limit)?;
+                    for _ in 0..length {
+                        let byte = self.history[self.history.len() - distance];
+                        self.history.push(byte);
+                    }
+                }
+                _ => return Err(DeflateError::InvalidCode),
+            }
+        }
+    }
+
+    /// Check that `additional` more output bytes stay within `limit`.
+    fn reserve(&self, start: usize, additional: usize, limit: usize) -> Result<(), DeflateError> {
+        if self.history.len() - start + additional > limit {
+            return Err(DeflateError::OutputTooLarge);
+        }
+        Ok(())
+    }
+}
+
+/// Canonical Huffman decoding table.
+struct Huffman {
+    /// Number of codes of each length.
+    counts: [u16; 16],
+    /// Symbols ordered by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Build the canonical code for per-symbol code `lengths`.
+    fn new(lengths: &[u8]) -> Result<Self, DeflateError> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+
+        // Reject over-subscribed codes; incomplete ones are allowed
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(DeflateError::InvalidCode);
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    /// Decode one symbol, reading its code bit by bit.
+    fn decode(&self, input: &mut BitReader<'_>) -> Result<u16, DeflateError> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for &count in &self.counts[1..] {
+            code |= input.bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(DeflateError::InvalidCode)
+    }
+}
+
+/// Tables for blocks compressed with the fixed Huffman codes.
+fn fixed_tables() -> Result<(Huffman, Huffman), DeflateError> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+/// Read the code length header of a dynamic Huffman block.
+fn dynamic_tables(input: &mut BitReader<'_>) -> Result<(Huffman, Huffman), DeflateError> {
+    let literal_count = input.bits(5)? as usize + 257;
+    let distance_count = input.bits(5)? as usize + 1;
+    let code_count = input.bits(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30 {
+        return Err(DeflateError::InvalidCode);
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[symbol] = input.bits(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let (value, repeat) = match code.decode(input)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..i].last().ok_or(DeflateError::InvalidCode)?;
+                (previous, 3 + input.bits(2)? as usize)
+            }
+            17 => (0, 3 + input.bits(3)? as usize),
+            18 => (0, 11 + input.bits(7)? as usize),
+            _ => return Err(DeflateError::InvalidCode),
+        };
+        if i + repeat > lengths.len() {
+            return Err(DeflateError::InvalidCode);
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+
+    if lengths[END_OF_BLOCK as usize] == 0 {
+        return Err(DeflateError::InvalidCode);
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+/// Write a literal byte or the end-of-block symbol with the fixed code.
+fn write_literal(out: &mut BitWriter, symbol: u16) {
+    let (code, length) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xC0 + symbol - 280, 8),
+    };
+    out.huffman(code as u32, length);
+}
+
+fn write_length(out: &mut BitWriter, length: usize) {
+    let index = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base as usize <= length)
+        .unwrap_or(0);
+    write_literal(out, 257 + index as u16);
+    out.bits(
+        (length - LENGTH_BASE[index] as usize) as u32,
+        LENGTH_EXTRA[index],
+    );
+}
+
+fn write_distance(out: &mut BitWriter, distance: usize) {
+    let index = DISTANCE_BASE
+        .iter()
+        .rposition(|&base| base as usize <= distance)
+        .unwrap_or(0);
+    out.huffman(index as u32, 5);
+    out.bits(
+        (distance - DISTANCE_BASE[index] as usize) as u32,
+        DISTANCE_EXTRA[index],
+    );
+}
+
+/// LZ77 match finder over three-byte prefixes.
+struct HashChains {
+    /// Most recent position for each hash.
+    head: Vec<u32>,
+    /// Previous position with the same hash, per position.
+    prev: Vec<u32>,
+}
+
+impl HashChains {
+    const NONE: u32 = u32::MAX;
+
+    fn new(len: usize) -> Self {
+        Self {
+            head: vec![Self::NONE; 1 << HASH_BITS],
+            prev: vec![Self::NONE; len],
+        }
+    }
+
+    fn hash(buf: &[u8], pos: usize) -> Option<usize> {
+        let bytes = buf.get(pos..pos + MIN_MATCH)?;
+        let hash = ((bytes[0] as usize) << 10) ^ ((bytes[1] as usize) << 5) ^ bytes[2] as usize;
+        Some(hash & ((1 << HASH_BITS) - 1))
+    }
+
+    fn insert(&mut self, buf: &[u8], pos: usize) {
+        if let Some(hash) = Self::hash(buf, pos) {
+            self.prev[pos] = self.head[hash];
+            self.head[hash] = pos as u32;
+        }
+    }
+
+    /// Longest earlier match for `buf[pos..]` as (length, distance).
+    fn longest_match(&self, buf: &[u8], pos: usize, window: usize) -> (usize, usize) {
+        let Some(hash) = Self::hash(buf, pos) else {
+            return (0, 0);
+        };
+        let max_length = MAX_MATCH.min(buf.len() - pos);
+
+        let mut best = (0, 0);
+        let mut candidate = self.head[hash];
+        for _ in 0..MAX_CHAIN {
+            if candidate == Self::NONE {
+                break;
+            }
+            let candidate_pos = candidate as usize;
+            let distance = pos - candidate_pos;
+            if distance > window {
+                break;
+            }
+
+            let length = buf[candidate_pos..]
+                .iter()
+                .zip(&buf[pos..pos + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best.0 {
+                best = (length, distance);
+                if length == max_length {
+                    break;
+                }
+            }
+            candidate = self.prev[candidate_pos];
+        }
+
+        best
+    }
+}
+
+/// Least-significant-bit-first bit packer.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, count: u8) {
+        self.acc |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Write a Huffman code, which is packed most significant bit first.
+    fn huffman(&mut self, code: u32, length: u8) {
+        self.bits(code.reverse_bits() >> (32 - length as u32), length);
+    }
+
+    /// Pad to a byte boundary and return the bytes.
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Least-significant-bit-first bit reader.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Byte holding the next bit.
+    pos: usize,
+    /// Bits of `data[pos]` already consumed.
+    bit: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit: 0,
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bits(&mut self, count: u8) -> Result<u32, DeflateError> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.pos).ok_or(DeflateError::UnexpectedEnd)?;
+            value |= (((byte >> self.bit) & 1) as u32) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Skip to the next byte boundary.
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+
+    /// Take `len` whole bytes; the reader must be aligned.
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DeflateError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(DeflateError::UnexpectedEnd)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_tail(data: &[u8]) -> Vec<u8> {
+        [data, &SYNC_FLUSH_TAIL].concat()
+    }
+
+    #[test]
+    fn test_rfc7692_examples() {
+        // Section 7.2.3.1: "Hello" with a fixed Huffman block
+        let hello = [0xF2, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00];
+        let mut inflater = Inflater::new(MAX_WINDOW_BITS);
+        assert_eq!(
+            inflater.inflate(&with_tail(&hello), 1024).unwrap(),
+            b"Hello"
+        );
+
+        // Section 7.2.3.2: the second "Hello" refers back into the first
+        let again = [0xF2, 0x00, 0x11, 0x00, 0x00];
+        assert_eq!(
+            inflater.inflate(&with_tail(&again), 1024).unwrap(),
+            b"Hello"
+        );
+        inflater.reset();
+        assert_eq!(
+            inflater.inflate(&with_tail(&again), 1024),
+            Err(DeflateError::InvalidDistance)
+        );
+
+        // Section 7.2.3.3: a stored block
+        let stored = [
+            0x00, 0x05, 0x00, 0xFA, 0xFF, 0x48, 0x65, 0x6C, 0x6C, 0x6F, 0x00,
+        ];
+        assert_eq!(
+            inflater.inflate(&with_tail(&stored), 1024).unwrap(),
+            b"Hello"
+        );
+
+        // Section 7.2.3.4: a final block, after which the tail is ignored
+        let last = [0xF3, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00];
+        assert_eq!(inflater.inflate(&with_tail(&last), 1024).unwrap(), b"Hello");
+
+        let mut deflater = Deflater::new(MAX_WINDOW_BITS);
+        assert_eq!(deflater.compress(b"Hello"), hello);
+        // One back-reference rather than zlib's literal plus back-reference
+        assert_eq!(deflater.compress(b"Hello"), [0x02, 0x13, 0x00, 0x00]);
+        assert_eq!(deflater.compress(b""), [0x00]);
+    }
+
+    #[test]
+    fn test_dynamic_block() {
+        // Compressed by zlib with a sync flush
+        let compressed = [
+            0x74, 0xCB, 0xDB, 0x09, 0x80, 0x30, 0x0C, 0x46, 0xE1, 0x55, 0x32, 0x80, 0xB8, 0x87,
+            0x63, 0xF4, 0xF2, 0xD7, 0x06, 0x6A, 0x23, 0x4D, 0xA4, 0xB8, 0xBD, 0xF4, 0x49, 0x84,
+            0xFA, 0xFC, 0x9D, 0xB3, 0x19, 0x75, 0xA7, 0x64, 0x19, 0xE4, 0xA1, 0x46, 0x92, 0xC8,
+            0xF8, 0x80, 0x2E, 0xC4, 0xAF, 0x74, 0x69, 0x7F, 0xE4, 0x76, 0x0C, 0xE8, 0xAC, 0x51,
+            0x8E, 0x99, 0x24, 0x91, 0xC2, 0x9A, 0x2B, 0xF4, 0x3B, 0xE2, 0x94, 0x90, 0x47, 0xE0,
+            0x51, 0x18, 0x69, 0x6E, 0x5C, 0x43, 0x43, 0xBC, 0x0A, 0xDB, 0xBD, 0x3E, 0x00, 0x00,
+            0x00, 0xFF, 0xFF,
+        ];
+        let mut inflater = Inflater::new(MAX_WINDOW_BITS);
+        let text = inflater.inflate(&compressed, 1024).unwrap();
+        assert_eq!(
+            text,
+            b"It was the best of times, it was the worst of times, it was the age of \
+              wisdom, it was the age of foolishness, it was the epoch of belief, it was \
+              the epoch of incredulity."
+        );
+        assert_eq!(
+            Inflater::new(MAX_WINDOW_BITS).inflate(&compressed, 16),
+            Err(DeflateError::OutputTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_round_trip_across_messages() {
+        let mut deflater = Deflater::new(10);
+        let mut inflater = Inflater::new(10);
+        let messages: [Vec<u8>; 3] = [
+            b"abcabcabcabc".repeat(100),
+            (0..=255u8).cycle().take(3000).collect(),
+            b"abcabcabcabc".repeat(100),
+        ];
+
+        for message in &messages {
+            let compressed = deflater.compress(message);
+            assert!(compressed.len() < message.len() / 4);
+            assert_eq!(
+                inflater
+                    .inflate(&with_tail(&compressed), usize::MAX)
+                    .unwrap(),
+                *message
+            );
+        }
+
+        // A window too small for the sender's back-references is rejected
+        let block: Vec<u8> = (0..1000u32).map(|i| (i * i % 1009) as u8).collect();
+        let mut deflater = Deflater::new(12);
+        let mut inflater = Inflater::new(8);
+        let compressed = deflater.compress(&block.repeat(2));
+        assert_eq!(
+            inflater.inflate(&with_tail(&compressed), usize::MAX),
+            Err(DeflateError::InvalidDistance)
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let mut inflater = Inflater::new(MAX_WINDOW_BITS);
+        assert_eq!(
+            inflater.inflate(&[0x07], 16),
+            Err(DeflateError::InvalidBlockType)
+        );
+        assert_eq!(
+            inflater.inflate(&[0x00, 0x05, 0x00, 0x00, 0x00], 16),
+            Err(DeflateError::InvalidStoredLength)
+        );
+        assert_eq!(
+            inflater.inflate(&[0xF2, 0x48, 0xCD], 16),
+            Err(DeflateError::UnexpectedEnd)
+        );
+    }
+}
@@ -1,8 +1,21 @@
 //! DNS resolver implementation.
 //!
 //! This module provides DNS resolution capabilities for the network stack.
+//!
+//! [`resolve_cached`] answers from a process-wide cache keyed by name and
+//! record type, and only queries the server on a miss. Answers are kept
+//! for their TTL (a CNAME chain expires as a unit, with the shortest TTL
+//! along it), and NXDOMAIN and empty answers are cached for a bounded
+//! time (RFC 2308).
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use spin::Mutex;
 
-use crate::{IpAddress, NetworkError};
+use crate::{IpAddress, Ipv4Addr, Ipv6Addr, NetworkError};
 
 /// DNS record types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +41,24 @@ pub enum RecordType {
     TXT = 16,
 }
 
+impl RecordType {
+    /// Record type for a wire type code.
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(RecordType::A),
+            28 => Some(RecordType::AAAA),
+            5 => Some(RecordType::CNAME),
+            15 => Some(RecordType::MX),
+            2 => Some(RecordType::NS),
+            12 => Some(RecordType::PTR),
+            6 => Some(RecordType::SOA),
+            33 => Some(RecordType::SRV),
+            16 => Some(RecordType::TXT),
+            _ => None,
+        }
+    }
+}
+
 /// DNS query class.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u16)]
@@ -280,34 +311,7 @@ impl DnsResolver {
 
     /// Build a DNS query packet.
     pub fn build_query(&mut self, hostname: &str, record_type: RecordType) -> ([u8; 512], usize) {
-        let mut buffer = [0u8; 512];
-        let id = self.next_transaction_id();
-
-        // Build header
-        let header = DnsHeader::new_query(id);
-        buffer[0..2].copy_from_slice(&header.id.to_be_bytes());
-        buffer[2..4].copy_from_slice(&header.flags.to_be_bytes());
-        buffer[4..6].copy_from_slice(&header.qdcount.to_be_bytes());
-        buffer[6..8].copy_from_slice(&header.ancount.to_be_bytes());
-        buffer[8..10].copy_from_slice(&header.nscount.to_be_bytes());
-        buffer[10..12].copy_from_slice(&header.arcount.to_be_bytes());
-
-        // Build question
-        let question = DnsQuestion::new(hostname, record_type);
-        let mut offset = DnsHeader::SIZE;
-
-        // Copy name
-        buffer[offset..offset + question.name_len]
-            .copy_from_slice(&question.name[..question.name_len]);
-        offset += question.name_len;
-
-        // Copy type and class
-        buffer[offset..offset + 2].copy_from_slice(&(question.qtype as u16).to_be_bytes());
-        offset += 2;
-        buffer[offset..offset + 2].copy_from_slice(&(question.qclass as u16).to_be_bytes());
-        offset += 2;
-
-        (buffer, offset)
+        encode_query(self.next_transaction_id(), hostname, record_type)
     }
 
     /// Parse a DNS response.
@@ -365,6 +369,36 @@ impl DnsResolver {
     }
 }
 
+/// Encode a query for `hostname` with transaction ID `id`.
+fn encode_query(id: u16, hostname: &str, record_type: RecordType) -> ([u8; 512], usize) {
+    let mut buffer = [0u8; 512];
+
+    // Build header
+    let header = DnsHeader::new_query(id);
+    buffer[0..2].copy_from_slice(&header.id.to_be_bytes());
+    buffer[2..4].copy_from_slice(&header.flags.to_be_bytes());
+    buffer[4..6].copy_from_slice(&header.qdcount.to_be_bytes());
+    buffer[6..8].copy_from_slice(&header.ancount.to_be_bytes());
+    buffer[8..10].copy_from_slice(&header.nscount.to_be_bytes());
+    buffer[10..12].copy_from_slice(&header.arcount.to_be_bytes());
+
+    // Build question
+    let question = DnsQuestion::new(hostname, record_type);
+    let mut offset = DnsHeader::SIZE;
+
+    // Copy name
+    buffer[offset..offset + question.name_len].copy_from_slice(&question.name[..question.name_len]);
+    offset += question.name_len;
+
+    // Copy type and class
+    buffer[offset..offset + 2].copy_from_slice(&(question.qtype as u16).to_be_bytes());
+    offset += 2;
+    buffer[offset..offset + 2].copy_from_slice(&(question.qclass as u16).to_be_bytes());
+    offset += 2;
+
+    (buffer, offset)
+}
+
 /// DNS response.
 #[derive(Debug)]
 pub struct DnsResponse {
@@ -462,3 +496,736 @@ impl Default for DnsCache {
         Self::new()
     }
 }
+
+/// Record data the resolver uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordData {
+    /// A or AAAA address.
+    Address(IpAddress),
+    /// CNAME target.
+    Name(String),
+    /// SOA record, reduced to its negative caching TTL (`minimum`).
+    Soa { minimum: u32 },
+    /// Anything else.
+    Other,
+}
+
+/// A resource record from a DNS message.
+#[derive(Debug, Clone)]
+pub struct ResourceRecord {
+    /// Owner name, without the trailing dot.
+    pub name: String,
+    /// Record type code.
+    pub rtype: u16,
+    /// Time to live in seconds.
+    pub ttl: u32,
+    /// Parsed record data.
+    pub data: RecordData,
+}
+
+/// A parsed DNS response.
+#[derive(Debug, Clone)]
+pub struct DnsMessage {
+    /// Transaction ID.
+    pub id: u16,
+    /// Response code.
+    pub rcode: ResponseCode,
+    /// Name of the first question, if any.
+    pub question: Option<String>,
+    /// Answer section.
+    pub answers: Vec<ResourceRecord>,
+    /// Authority section (carries the SOA of negative answers).
+    pub authority: Vec<ResourceRecord>,
+}
+
+/// Compression pointers followed while reading one name.
+const MAX_NAME_POINTERS: usize = 16;
+
+/// Read a possibly compressed name at `*pos`, advancing past it.
+fn read_name(data: &[u8], pos: &mut usize) -> Option<String> {
+    let mut name = String::new();
+    let mut cursor = *pos;
+    let mut jumped = false;
+    let mut pointers = 0;
+
+    loop {
+        let len = *data.get(cursor)? as usize;
+        match len & 0xC0 {
+            0x00 if len == 0 => {
+                if !jumped {
+                    *pos = cursor + 1;
+                }
+                return Some(name);
+            }
+            0x00 => {
+                let label = data.get(cursor + 1..cursor + 1 + len)?;
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(core::str::from_utf8(label).ok()?);
+                cursor += 1 + len;
+            }
+            0xC0 => {
+                pointers += 1;
+                if pointers > MAX_NAME_POINTERS {
+                    return None;
+                }
+                let target = ((len & 0x3F) << 8) | *data.get(cursor + 1)? as usize;
+                if !jumped {
+                    *pos = cursor + 2;
+                    jumped = true;
+                }
+                cursor = target;
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Read the resource record at `*pos`, advancing past it.
+fn read_record(data: &[u8], pos: &mut usize) -> Option<ResourceRecord> {
+    let name = read_name(data, pos)?;
+    let rtype = read_u16(data, *pos)?;
+    let ttl = read_u32(data, *pos + 4)?;
+    let rdlen = read_u16(data, *pos + 8)? as usize;
+    let start = *pos + 10;
+    let rdata = data.get(start..start + rdlen)?;
+    *pos = start + rdlen;
+
+    let record = match RecordType::from_u16(rtype) {
+        Some(RecordType::A) if rdlen == 4 => {
+            RecordData::Address(IpAddress::V4(Ipv4Addr(rdata.try_into().ok()?)))
+        }
+        Some(RecordType::AAAA) if rdlen == 16 => {
+            RecordData::Address(IpAddress::V6(Ipv6Addr(rdata.try_into().ok()?)))
+        }
+        Some(RecordType::CNAME) => {
+            let mut at = start;
+            RecordData::Name(read_name(data, &mut at)?)
+        }
+        Some(RecordType::SOA) => {
+            let mut at = start;
+            read_name(data, &mut at)?;
+            read_name(data, &mut at)?;
+            // serial, refresh, retry, expire, then minimum
+            RecordData::Soa {
+                minimum: read_u32(data, at + 16)?,
+            }
+        }
+        _ => RecordData::Other,
+    };
+
+    Some(ResourceRecord {
+        name,
+        rtype,
+        ttl,
+        data: record,
+    })
+}
+
+/// Parse a DNS response message.
+pub fn parse_message(data: &[u8]) -> Result<DnsMessage, NetworkError> {
+    if data.len() < DnsHeader::SIZE {
+        return Err(NetworkError::InvalidPacket);
+    }
+    let header = DnsHeader {
+        id: u16::from_be_bytes([data[0], data[1]]),
+        flags: u16::from_be_bytes([data[2], data[3]]),
+        qdcount: u16::from_be_bytes([data[4], data[5]]),
+        ancount: u16::from_be_bytes([data[6], data[7]]),
+        nscount: u16::from_be_bytes([data[8], data[9]]),
+        arcount: u16::from_be_bytes([data[10], data[11]]),
+    };
+    if !header.is_response() {
+        return Err(NetworkError::InvalidPacket);
+    }
+
+    let mut pos = DnsHeader::SIZE;
+    let mut question = None;
+    for _ in 0..header.qdcount {
+        let name = read_name(data, &mut pos).ok_or(NetworkError::InvalidPacket)?;
+        question.get_or_insert(name);
+        pos += 4;
+    }
+    let mut records = |count: u16| {
+        (0..count)
+            .map(|_| read_record(data, &mut pos))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(NetworkError::InvalidPacket)
+    };
+    let answers = records(header.ancount)?;
+    let authority = records(header.nscount)?;
+
+    Ok(DnsMessage {
+        id: header.id,
+        rcode: header.response_code(),
+        question,
+        answers,
+        authority,
+    })
+}
+
+/// Entries kept by the resolver cache.
+pub const CACHE_CAPACITY: usize = 256;
+
+/// Longest time a positive answer is cached, in seconds.
+pub const MAX_TTL: u32 = 86_400;
+
+/// Longest time a negative answer is cached, in seconds.
+pub const MAX_NEGATIVE_TTL: u32 = 300;
+
+/// Negative caching time when the server sends no SOA, in seconds.
+const DEFAULT_NEGATIVE_TTL: u32 = 60;
+
+/// CNAME records followed from the queried name.
+const MAX_CNAME_HOPS: usize = 8;
+
+/// A name resolved through its CNAME chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    /// Names the CNAME chain led to, in order; the last is the canonical
+    /// name. Empty when the queried name had no CNAME.
+    pub aliases: Vec<String>,
+    /// Addresses of the canonical name.
+    pub addresses: Vec<IpAddress>,
+}
+
+/// A cached answer.
+#[derive(Debug, Clone)]
+enum CachedAnswer {
+    Found(Resolution),
+    /// NXDOMAIN.
+    NoSuchName,
+    /// The name exists but has no records of the type.
+    NoData,
+}
+
+impl CachedAnswer {
+    fn to_result(&self) -> Result<Resolution, NetworkError> {
+        match self {
+            CachedAnswer::Found(resolution) => Ok(resolution.clone()),
+            CachedAnswer::NoSuchName => Err(NetworkError::DnsError(String::from("NXDOMAIN"))),
+            CachedAnswer::NoData => Err(NetworkError::DnsError(String::from("No records"))),
+        }
+    }
+}
+
+struct CacheSlot {
+    answer: CachedAnswer,
+    /// Time the answer expires, in seconds.
+    expires_at: u64,
+    /// Cache use counter value at the last hit, for LRU eviction.
+    last_used: u64,
+}
+
+/// Resolver cache keyed by lowercase name and record type, evicting the
+/// least recently used entry when full.
+pub struct ResolverCache {
+    entries: BTreeMap<(String, u16), CacheSlot>,
+    capacity: usize,
+    uses: u64,
+}
+
+impl ResolverCache {
+    /// Create a cache holding up to `capacity` answers.
+    pub const fn new(capacity: usize) -> Self {
+        ResolverCache {
+            entries: BTreeMap::new(),
+            capacity,
+            uses: 0,
+        }
+    }
+
+    /// Number of cached answers, including expired ones not yet evicted.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop every cached answer.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Look up the cached answer for `name`, dropping it if expired.
+    /// `None` means the server has to be asked.
+    pub fn lookup(
+        &mut self,
+        name: &str,
+        record_type: RecordType,
+        current_time: u64,
+    ) -> Option<Result<Resolution, NetworkError>> {
+        let key = (name.to_ascii_lowercase(), record_type as u16);
+        let slot = self.entries.get_mut(&key)?;
+        if current_time >= slot.expires_at {
+            self.entries.remove(&key);
+            return None;
+        }
+        self.uses += 1;
+        slot.last_used = self.uses;
+        Some(slot.answer.to_result())
+    }
+
+    /// Cache the answer `message` gives for `name` and return it.
+    pub fn insert(
+        &mut self,
+        name: &str,
+        record_type: RecordType,
+        message: &DnsMessage,
+        current_time: u64,
+    ) -> Result<Resolution, NetworkError> {
+        let (answer, ttl) = Self::answer_from(name, record_type, message);
+        let result = answer.to_result();
+        if ttl == 0 {
+            return result;
+        }
+
+        let key = (name.to_ascii_lowercase(), record_type as u16);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, slot)| slot.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.uses += 1;
+        self.entries.insert(
+            key,
+            CacheSlot {
+                answer,
+                expires_at: current_time + ttl as u64,
+                last_used: self.uses,
+            },
+        );
+        result
+    }
+
+    /// The answer for `name` and how long it may be cached: the shortest
+    /// TTL along the CNAME chain and the addresses, or the negative TTL.
+    fn answer_from(
+        name: &str,
+        record_type: RecordType,
+        message: &DnsMessage,
+    ) -> (CachedAnswer, u32) {
+        let mut current = name;
+        let mut aliases: Vec<String> = Vec::new();
+        let mut ttl = MAX_TTL;
+
+        for _ in 0..MAX_CNAME_HOPS {
+            let cname = message
+                .answers
+                .iter()
+                .find_map(|record| match &record.data {
+                    RecordData::Name(target)
+                        if record.rtype == RecordType::CNAME as u16
+                            && record.name.eq_ignore_ascii_case(current) =>
+                    {
+                        Some((target, record.ttl))
+                    }
+                    _ => None,
+                });
+            let Some((target, cname_ttl)) = cname else {
+                break;
+            };
+            ttl = ttl.min(cname_ttl);
+            aliases.push(target.to_ascii_lowercase());
+            current = target;
+        }
+
+        let mut addresses = Vec::new();
+        for record in &message.answers {
+            if record.rtype == record_type as u16 && record.name.eq_ignore_ascii_case(current) {
+                if let RecordData::Address(address) = record.data {
+                    ttl = ttl.min(record.ttl);
+                    addresses.push(address);
+                }
+            }
+        }
+        if !addresses.is_empty() {
+            return (CachedAnswer::Found(Resolution { aliases, addresses }), ttl);
+        }
+
+        // Negative answer: the SOA's TTL, capped by its minimum field
+        let negative_ttl = message
+            .authority
+            .iter()
+            .find_map(|record| match record.data {
+                RecordData::Soa { minimum } => Some(record.ttl.min(minimum)),
+                _ => None,
+            })
+            .unwrap_or(DEFAULT_NEGATIVE_TTL)
+            .min(MAX_NEGATIVE_TTL);
+        let answer = if message.rcode == ResponseCode::NXDomain {
+            CachedAnswer::NoSuchName
+        } else {
+            CachedAnswer::NoData
+        };
+        (answer, negative_ttl)
+    }
+}
+
+/// Process-wide resolver cache.
+static CACHE: Mutex<ResolverCache> = Mutex::new(ResolverCache::new(CACHE_CAPACITY));
+
+/// Transaction ID of the next cached-resolver query.
+static NEXT_QUERY_ID: AtomicU16 = AtomicU16::new(1);
+
+/// Resolve `name` to its IPv4 addresses through the cache.
+///
+/// See [`lookup_cached`].
+pub fn resolve_cached<F>(
+    name: &str,
+    current_time: u64,
+    exchange: F,
+) -> Result<Vec<IpAddress>, NetworkError>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>, NetworkError>,
+{
+    lookup_cached(name, RecordType::A, current_time, exchange).map(|r| r.addresses)
+}
+
+/// Resolve `name` to its IPv6 addresses through the cache.
+///
+/// See [`lookup_cached`].
+pub fn resolve_cached_v6<F>(
+    name: &str,
+    current_time: u64,
+    exchange: F,
+) -> Result<Vec<IpAddress>, NetworkError>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>, NetworkError>,
+{
+    lookup_cached(name, RecordType::AAAA, current_time, exchange).map(|r| r.addresses)
+}
+
+/// Resolve `name` to both its IPv6 and IPv4 addresses through the cache,
+/// in the order a dual-stack client should try them: alternating between
+/// the families, IPv6 first (RFC 8305 section 4).
+///
+/// Succeeds if either lookup does; otherwise fails with the A lookup's
+/// error. See [`lookup_cached`].
+pub fn resolve_dual_stack<F>(
+    name: &str,
+    current_time: u64,
+    mut exchange: F,
+) -> Result<Vec<IpAddress>, NetworkError>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>, NetworkError>,
+{
+    let v6 = resolve_cached_v6(name, current_time, &mut exchange);
+    let v4 = resolve_cached(name, current_time, &mut exchange);
+    let (v6, v4) = match (v6, v4) {
+        (Err(_), Err(e)) => return Err(e),
+        (v6, v4) => (v6.unwrap_or_default(), v4.unwrap_or_default()),
+    };
+
+    let mut addresses = Vec::with_capacity(v6.len() + v4.len());
+    let (mut v6, mut v4) = (v6.into_iter(), v4.into_iter());
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => addresses.extend(a.into_iter().chain(b)),
+        }
+    }
+    Ok(addresses)
+}
+
+/// Look up `record_type` records of `name` through the cache.
+///
+/// On a miss, `exchange` sends the given query to a DNS server and
+/// returns its reply, and the answer is cached. `current_time` is in
+/// seconds. Names match case-insensitively; NXDOMAIN and empty answers are
+/// cached as errors too.
+pub fn lookup_cached<F>(
+    name: &str,
+    record_type: RecordType,
+    current_time: u64,
+    mut exchange: F,
+) -> Result<Resolution, NetworkError>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>, NetworkError>,
+{
+    let name = name.trim_end_matches('.');
+    if name.is_empty() || name.len() > 253 {
+        return Err(NetworkError::DnsError(String::from("Invalid name")));
+    }
+    if let Some(result) = CACHE.lock().lookup(name, record_type, current_time) {
+        return result;
+    }
+
+    // The lock is not held while waiting for the server
+    let id = NEXT_QUERY_ID.fetch_add(1, Ordering::Relaxed);
+    let (query, len) = encode_query(id, name, record_type);
+    let message = parse_message(&exchange(&query[..len])?)?;
+    if message.id != id
+        || !message
+            .question
+            .as_deref()
+            .is_some_and(|question| question.eq_ignore_ascii_case(name))
+    {
+        return Err(NetworkError::InvalidPacket);
+    }
+    if !matches!(
+        message.rcode,
+        ResponseCode::NoError | ResponseCode::NXDomain
+    ) {
+        return Err(NetworkError::DnsError(String::from("Bad response code")));
+    }
+
+    CACHE
+        .lock()
+        .insert(name, record_type, &message, current_time)
+}
+
+/// Drop every answer in the resolver cache.
+pub fn clear_cache() {
+    CACHE.lock().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// A name in wire format.
+    fn wire(name: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        for label in name.split('.') {
+            out.push(label.len() as u8);
+            out.extend_from_slice(label.as_bytes());
+        }
+        out.push(0);
+        out
+    }
+
+    /// Owner name pointing at the question name.
+    const QUESTION_NAME: [u8; 2] = [0xC0, 12];
+
+    /// Answer `query` with `rcode` and the given answer and authority
+    /// records of (owner, type, ttl, data).
+    fn reply(
+        query: &[u8],
+        rcode: u8,
+        answers: &[(Vec<u8>, RecordType, u32, Vec<u8>)],
+        authority: &[(Vec<u8>, RecordType, u32, Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut out = query.to_vec();
+        out[2] = 0x81;
+        out[3] = 0x80 | rcode;
+        out[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        out[8..10].copy_from_slice(&(authority.len() as u16).to_be_bytes());
+        for (owner, rtype, ttl, data) in answers.iter().chain(authority) {
+            out.extend_from_slice(owner);
+            out.extend_from_slice(&(*rtype as u16).to_be_bytes());
+            out.extend_from_slice(&1u16.to_be_bytes());
+            out.extend_from_slice(&ttl.to_be_bytes());
+            out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            out.extend_from_slice(data);
+        }
+        out
+    }
+
+    #[test]
+    fn test_resolve_cached_cname_chain() {
+        let mut queries = 0;
+        let mut responder = |query: &[u8]| {
+            queries += 1;
+            Ok(reply(
+                query,
+                0,
+                &[
+                    (
+                        QUESTION_NAME.to_vec(),
+                        RecordType::CNAME,
+                        300,
+                        wire("cdn.example.net"),
+                    ),
+                    (
+                        wire("CDN.example.net"),
+                        RecordType::A,
+                        60,
+                        vec![93, 184, 216, 34],
+                    ),
+                    (
+                        wire("cdn.example.net"),
+                        RecordType::A,
+                        120,
+                        vec![93, 184, 216, 35],
+                    ),
+                ],
+                &[],
+            ))
+        };
+
+        let first = lookup_cached("www.Example.com", RecordType::A, 1000, &mut responder).unwrap();
+        assert_eq!(first.aliases, ["cdn.example.net"]);
+        assert_eq!(
+            first.addresses,
+            [
+                IpAddress::V4(Ipv4Addr([93, 184, 216, 34])),
+                IpAddress::V4(Ipv4Addr([93, 184, 216, 35]))
+            ]
+        );
+
+        // Cached under any spelling of the name until the shortest TTL in
+        // the chain runs out
+        let second = lookup_cached("WWW.example.COM.", RecordType::A, 1059, &mut responder);
+        assert_eq!(second.unwrap(), first);
+        assert_eq!(
+            resolve_cached("www.example.com", 1059, &mut responder).unwrap(),
+            first.addresses
+        );
+        lookup_cached("www.example.com", RecordType::A, 1060, &mut responder).unwrap();
+        assert_eq!(queries, 2);
+    }
+
+    #[test]
+    fn test_negative_caching() {
+        let mut queries = 0;
+        let mut responder = |query: &[u8]| {
+            queries += 1;
+            let mut soa = wire("ns.example.org");
+            soa.extend(wire("admin.example.org"));
+            for field in [1u32, 7200, 3600, 1209600, 900] {
+                soa.extend_from_slice(&field.to_be_bytes());
+            }
+            Ok(reply(
+                query,
+                3,
+                &[],
+                &[(wire("example.org"), RecordType::SOA, 3600, soa)],
+            ))
+        };
+
+        let name = "missing.example.org";
+        assert!(matches!(
+            lookup_cached(name, RecordType::A, 0, &mut responder),
+            Err(NetworkError::DnsError(e)) if e == "NXDOMAIN"
+        ));
+        // The SOA allows 900s, but negative answers are capped
+        let capped = MAX_NEGATIVE_TTL as u64;
+        assert!(lookup_cached(name, RecordType::A, capped - 1, &mut responder).is_err());
+        assert!(lookup_cached(name, RecordType::A, capped, &mut responder).is_err());
+        assert_eq!(queries, 2);
+    }
+
+    #[test]
+    fn test_resolve_dual_stack() {
+        let v6 = |last: u8| {
+            let mut octets = [0u8; 16];
+            octets[..4].copy_from_slice(&[0x20, 0x01, 0x0D, 0xB8]);
+            octets[15] = last;
+            octets
+        };
+        let mut responder = |query: &[u8]| {
+            let qtype = read_u16(query, query.len() - 4).unwrap();
+            let name = read_name(query, &mut 12).unwrap();
+            let answers = match qtype {
+                _ if name.starts_with("missing") => Vec::new(),
+                28 if name.starts_with("v4only") => Vec::new(),
+                28 => vec![
+                    (QUESTION_NAME.to_vec(), RecordType::AAAA, 60, v6(1).to_vec()),
+                    (QUESTION_NAME.to_vec(), RecordType::AAAA, 60, v6(2).to_vec()),
+                ],
+                _ => vec![(
+                    QUESTION_NAME.to_vec(),
+                    RecordType::A,
+                    60,
+                    vec![192, 0, 2, 1],
+                )],
+            };
+            let rcode = if name.starts_with("missing") { 3 } else { 0 };
+            Ok(reply(query, rcode, &answers, &[]))
+        };
+
+        // Alternating families, IPv6 first
+        assert_eq!(
+            resolve_dual_stack("dual.example.com", 0, &mut responder).unwrap(),
+            [
+                IpAddress::V6(Ipv6Addr(v6(1))),
+                IpAddress::V4(Ipv4Addr([192, 0, 2, 1])),
+                IpAddress::V6(Ipv6Addr(v6(2))),
+            ]
+        );
+        assert_eq!(
+            resolve_cached_v6("dual.example.com", 0, &mut responder).unwrap(),
+            [
+                IpAddress::V6(Ipv6Addr(v6(1))),
+                IpAddress::V6(Ipv6Addr(v6(2)))
+            ]
+        );
+        assert_eq!(
+            resolve_dual_stack("v4only.example.com", 0, &mut responder).unwrap(),
+            [IpAddress::V4(Ipv4Addr([192, 0, 2, 1]))]
+        );
+        assert!(matches!(
+            resolve_dual_stack("missing.example.com", 0, &mut responder),
+            Err(NetworkError::DnsError(e)) if e == "NXDOMAIN"
+        ));
+    }
+
+    #[test]
+    fn test_cache_lru_eviction() {
+        let message = |name: &str, last: u8| DnsMessage {
+            id: 1,
+            rcode: ResponseCode::NoError,
+            question: Some(String::from(name)),
+            answers: vec![ResourceRecord {
+                name: String::from(name),
+                rtype: RecordType::A as u16,
+                ttl: 600,
+                data: RecordData::Address(IpAddress::V4(Ipv4Addr([10, 0, 0, last]))),
+            }],
+            authority: Vec::new(),
+        };
+
+        let mut cache = ResolverCache::new(2);
+        cache
+            .insert("a.test", RecordType::A, &message("a.test", 1), 0)
+            .unwrap();
+        cache
+            .insert("b.test", RecordType::A, &message("b.test", 2), 0)
+            .unwrap();
+        assert!(cache.lookup("A.TEST", RecordType::A, 1).is_some());
+        cache
+            .insert("c.test", RecordType::A, &message("c.test", 3), 1)
+            .unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.lookup("b.test", RecordType::A, 2).is_none());
+        assert!(cache.lookup("a.test", RecordType::A, 2).is_some());
+        assert!(cache.lookup("c.test", RecordType::A, 2).is_some());
+        // Keyed by type as well as name
+        assert!(cache.lookup("a.test", RecordType::AAAA, 2).is_none());
+    }
+
+    #[test]
+    fn test_parse_message_rejects_pointer_loop() {
+        let (query, len) = encode_query(7, "loop.test", RecordType::A);
+        let mut looped = reply(
+            &query[..len],
+            0,
+            &[(vec![0xC0, 12], RecordType::A, 1, vec![1, 2, 3, 4])],
+            &[],
+        );
+        // Point the question name at itself
+        looped[12] = 0xC0;
+        looped[13] = 12;
+        assert!(parse_message(&looped).is_err());
+    }
+}
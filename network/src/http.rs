@@ -2,13 +2,29 @@
 //!
 //! This module provides HTTP/1.1 client functionality for the network stack.
 //! It supports basic GET, POST, HEAD requests and handles chunked transfer encoding.
+//! Basic and Bearer authentication are supported, including answering a
+//! `401` Basic challenge with configured credentials. The client offers
+//! `h2` via ALPN; when a server negotiates it,
+//! [`HttpClient::execute_negotiated`] sends requests concurrently over one
+//! HTTP/2 connection (see [`crate::http2`]).
+//! [`HttpClient::execute_pooled`] reuses idle HTTP/1.1 keep-alive
+//! connections to the same origin from a [`ConnectionPool`].
+//! Hosts with both IPv6 and IPv4 addresses are reached with
+//! [`HttpClient::connect_dual_stack`], which races the addresses and keeps
+//! whichever connects first.
 
 use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use crate::NetworkError;
+use crate::http2::Http2Connection;
+use crate::tls::TlsConnector;
+use crate::websocket::base64_encode;
+use crate::{IpAddress, Ipv6Addr, NetworkError, SocketAddr};
+
+/// Header names and values.
+pub type HeaderMap = BTreeMap<String, String>;
 
 /// HTTP method types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -42,6 +58,7 @@ impl HttpMethod {
 pub enum HttpVersion {
     Http10,
     Http11,
+    Http2,
 }
 
 impl HttpVersion {
@@ -49,6 +66,7 @@ impl HttpVersion {
         match self {
             HttpVersion::Http10 => "HTTP/1.0",
             HttpVersion::Http11 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
         }
     }
 }
@@ -63,7 +81,7 @@ pub struct HttpRequest {
     /// HTTP version.
     pub version: HttpVersion,
     /// Request headers.
-    pub headers: BTreeMap<String, String>,
+    pub headers: HeaderMap,
     /// Request body.
     pub body: Vec<u8>,
 }
@@ -131,6 +149,40 @@ impl HttpRequest {
         self.header("Content-Type", content_type)
     }
 
+    /// Set HTTP Basic credentials (`Authorization: Basic base64(user:pass)`).
+    pub fn basic_auth(self, user: &str, pass: &str) -> Self {
+        let encoded = base64_encode(format!("{}:{}", user, pass).as_bytes());
+        self.header("Authorization", &format!("Basic {}", encoded))
+    }
+
+    /// Set a Bearer token (`Authorization: Bearer <token>`).
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Check if a header is set, ignoring the case of its name.
+    pub fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case(name))
+    }
+
+    /// Check if the connection may be kept open after this request, that
+    /// is unless it sends `Connection: close`.
+    pub fn keep_alive(&self) -> bool {
+        let connection = self
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Connection"))
+            .map(|(_, value)| value.as_str());
+        !has_token(connection, "close")
+    }
+
+    /// Get the Authorization header, if set.
+    pub fn authorization(&self) -> Option<&str> {
+        self.headers.get("Authorization").map(|v| v.as_str())
+    }
+
     /// Serialize the request to bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut request = format!(
@@ -241,6 +293,17 @@ impl HttpResponse {
         self.header("Content-Type")
     }
 
+    /// Check if the server keeps the connection open after this response:
+    /// HTTP/1.1 unless it sends `Connection: close`, HTTP/1.0 only with
+    /// `Connection: keep-alive`.
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.header("Connection").map(|v| v.as_str());
+        match self.version {
+            HttpVersion::Http10 => has_token(connection, "keep-alive"),
+            _ => !has_token(connection, "close"),
+        }
+    }
+
     /// Check if response uses chunked transfer encoding.
     pub fn is_chunked(&self) -> bool {
         self.header("Transfer-Encoding")
@@ -257,6 +320,20 @@ impl HttpResponse {
     pub fn location(&self) -> Option<&String> {
         self.header("Location")
     }
+
+    /// Parse the `WWW-Authenticate` header into its challenges.
+    pub fn auth_challenges(&self) -> Vec<AuthChallenge> {
+        self.header("WWW-Authenticate")
+            .map(|v| AuthChallenge::parse_all(v))
+            .unwrap_or_default()
+    }
+
+    /// Get the first `Basic` challenge, if the server offered one.
+    pub fn basic_challenge(&self) -> Option<AuthChallenge> {
+        self.auth_challenges()
+            .into_iter()
+            .find(|c| c.scheme.eq_ignore_ascii_case("basic"))
+    }
 }
 
 impl Default for HttpResponse {
@@ -265,6 +342,133 @@ impl Default for HttpResponse {
     }
 }
 
+/// A single authentication challenge from a `WWW-Authenticate` header
+/// (RFC 7235), e.g. `Basic realm="Admin", charset="UTF-8"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthChallenge {
+    /// Authentication scheme (e.g. "Basic", "Bearer").
+    pub scheme: String,
+    /// Auth parameters with lower-cased names.
+    pub params: BTreeMap<String, String>,
+}
+
+impl AuthChallenge {
+    /// Get the `realm` parameter.
+    pub fn realm(&self) -> Option<&str> {
+        self.params.get("realm").map(|v| v.as_str())
+    }
+
+    /// Parse every challenge in a `WWW-Authenticate` header value.
+    ///
+    /// Challenges and their parameters are both comma-separated, so a
+    /// token not followed by `=` starts a new challenge.
+    pub fn parse_all(value: &str) -> Vec<AuthChallenge> {
+        let bytes = value.as_bytes();
+        let mut pos = 0;
+        let mut challenges = Vec::new();
+
+        loop {
+            skip_separators(bytes, &mut pos);
+            let scheme = read_token(bytes, &mut pos);
+            if scheme.is_empty() {
+                break;
+            }
+            let mut challenge = AuthChallenge {
+                scheme: scheme.to_string(),
+                params: BTreeMap::new(),
+            };
+
+            loop {
+                skip_separators(bytes, &mut pos);
+                let start = pos;
+                let name = read_token(bytes, &mut pos);
+                skip_whitespace(bytes, &mut pos);
+                if name.is_empty() || bytes.get(pos) != Some(&b'=') {
+                    // Next challenge (or end of input).
+                    pos = start;
+                    break;
+                }
+                pos += 1;
+                skip_whitespace(bytes, &mut pos);
+                let param_value = if bytes.get(pos) == Some(&b'"') {
+                    read_quoted(bytes, &mut pos)
+                } else {
+                    read_token(bytes, &mut pos).to_string()
+                };
+                challenge
+                    .params
+                    .insert(name.to_ascii_lowercase(), param_value);
+            }
+
+            challenges.push(challenge);
+        }
+
+        challenges
+    }
+}
+
+/// Check if a comma-separated header value such as `Connection` lists
+/// `token`.
+fn has_token(value: Option<&str>, token: &str) -> bool {
+    value.is_some_and(|value| {
+        value
+            .split(',')
+            .any(|item| item.trim().eq_ignore_ascii_case(token))
+    })
+}
+
+fn skip_whitespace(bytes: &[u8], pos: &mut usize) {
+    while *pos < bytes.len() && (bytes[*pos] == b' ' || bytes[*pos] == b'\t') {
+        *pos += 1;
+    }
+}
+
+fn skip_separators(bytes: &[u8], pos: &mut usize) {
+    while *pos < bytes.len() && matches!(bytes[*pos], b' ' | b'\t' | b',') {
+        *pos += 1;
+    }
+}
+
+fn read_token<'a>(bytes: &'a [u8], pos: &mut usize) -> &'a str {
+    let start = *pos;
+    while *pos < bytes.len() && !matches!(bytes[*pos], b' ' | b'\t' | b',' | b'=' | b'"') {
+        *pos += 1;
+    }
+    core::str::from_utf8(&bytes[start..*pos]).unwrap_or("")
+}
+
+fn read_quoted(bytes: &[u8], pos: &mut usize) -> String {
+    // Skip opening quote.
+    *pos += 1;
+    let mut out = Vec::new();
+    while *pos < bytes.len() {
+        match bytes[*pos] {
+            b'"' => {
+                *pos += 1;
+                break;
+            }
+            b'\\' if *pos + 1 < bytes.len() => {
+                out.push(bytes[*pos + 1]);
+                *pos += 2;
+            }
+            b => {
+                out.push(b);
+                *pos += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Basic credentials configured on an [`HttpClient`].
+#[derive(Debug, Clone)]
+struct Credentials {
+    user: String,
+    pass: String,
+    /// Only answer challenges for this realm, if set.
+    realm: Option<String>,
+}
+
 /// HTTP client error.
 #[derive(Debug, Clone)]
 pub enum HttpError {
@@ -290,6 +494,12 @@ impl From<NetworkError> for HttpError {
     }
 }
 
+/// Default limit on a decoded chunked body.
+pub const DEFAULT_MAX_CHUNKED_BODY: usize = 64 * 1024 * 1024;
+
+/// Longest chunk size line (with extensions) or trailer line accepted.
+const MAX_CHUNK_LINE: usize = 4096;
+
 /// HTTP response parser.
 pub struct HttpParser {
     state: ParserState,
@@ -298,6 +508,9 @@ pub struct HttpParser {
     content_length: Option<usize>,
     chunked: bool,
     body_received: usize,
+    max_chunked_body: usize,
+    /// The response has no body whatever its headers say (a `HEAD` reply).
+    no_body: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -307,6 +520,10 @@ enum ParserState {
     Body,
     ChunkedSize,
     ChunkedData,
+    /// The CRLF after a chunk's data.
+    ChunkedDataEnd,
+    /// Trailer fields after the last chunk.
+    Trailers,
     Complete,
     Error,
 }
@@ -321,10 +538,31 @@ impl HttpParser {
             content_length: None,
             chunked: false,
             body_received: 0,
+            max_chunked_body: DEFAULT_MAX_CHUNKED_BODY,
+            no_body: false,
+        }
+    }
+
+    /// Create a parser for the response to a `method` request; the
+    /// response to `HEAD` has no body.
+    pub fn for_method(method: HttpMethod) -> Self {
+        Self {
+            no_body: method == HttpMethod::Head,
+            ..Self::new()
         }
     }
 
+    /// Limit the decoded size of a chunked body; a larger one is an error.
+    pub fn max_chunked_body(mut self, limit: usize) -> Self {
+        self.max_chunked_body = limit;
+        self
+    }
+
     /// Feed data to the parser.
+    ///
+    /// Data may arrive split anywhere, including inside a chunk size line;
+    /// `Ok(false)` means more data is needed and `Ok(true)` that the
+    /// response is complete.
     pub fn feed(&mut self, data: &[u8]) -> Result<bool, HttpError> {
         self.buffer.extend_from_slice(data);
 
@@ -355,6 +593,16 @@ impl HttpParser {
                         return Ok(false);
                     }
                 }
+                ParserState::ChunkedDataEnd => {
+                    if !self.parse_chunk_data_end()? {
+                        return Ok(false);
+                    }
+                }
+                ParserState::Trailers => {
+                    if !self.parse_trailers()? {
+                        return Ok(false);
+                    }
+                }
                 ParserState::Complete => {
                     return Ok(true);
                 }
@@ -375,6 +623,12 @@ impl HttpParser {
         self.state == ParserState::Complete
     }
 
+    /// Check if the body ends only when the server closes the connection
+    /// (no `Content-Length` and not chunked).
+    pub fn reads_until_close(&self) -> bool {
+        self.state == ParserState::Body && self.content_length.is_none()
+    }
+
     fn parse_status_line(&mut self) -> Result<bool, HttpError> {
         if let Some(pos) = self.find_crlf() {
             let line: Vec<u8> = self.buffer.drain(..pos + 2).collect();
@@ -423,7 +677,13 @@ impl HttpParser {
                     self.content_length = self.response.content_length();
                     self.chunked = self.response.is_chunked();
 
-                    if self.chunked {
+                    let status = self.response.status;
+                    if self.no_body
+                        || status == StatusCode::NO_CONTENT
+                        || status == StatusCode::NOT_MODIFIED
+                    {
+                        self.state = ParserState::Complete;
+                    } else if self.chunked {
                         self.state = ParserState::ChunkedSize;
                     } else if let Some(len) = self.content_length {
                         if len == 0 {
@@ -438,20 +698,23 @@ impl HttpParser {
                     return Ok(true);
                 }
 
-                let line = String::from_utf8_lossy(&line[..line.len() - 2]);
-
-                // Parse header
-                if let Some(colon) = line.find(':') {
-                    let name = line[..colon].trim().to_string();
-                    let value = line[colon + 1..].trim().to_string();
-                    self.response.headers.insert(name, value);
-                }
+                self.insert_header(&line[..line.len() - 2]);
             } else {
                 return Ok(false);
             }
         }
     }
 
+    /// Add a `name: value` header or trailer line to the response.
+    fn insert_header(&mut self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        if let Some(colon) = line.find(':') {
+            let name = line[..colon].trim().to_string();
+            let value = line[colon + 1..].trim().to_string();
+            self.response.headers.insert(name, value);
+        }
+    }
+
     fn parse_body(&mut self) -> Result<bool, HttpError> {
         if let Some(content_length) = self.content_length {
             let remaining = content_length - self.body_received;
@@ -475,28 +738,45 @@ impl HttpParser {
     }
 
     fn parse_chunk_size(&mut self) -> Result<bool, HttpError> {
-        if let Some(pos) = self.find_crlf() {
-            let line: Vec<u8> = self.buffer.drain(..pos + 2).collect();
-            let line = String::from_utf8_lossy(&line[..line.len() - 2]);
-
-            // Parse hex size (ignore extensions after semicolon)
-            let size_str = line.split(';').next().unwrap_or("");
-            let size = usize::from_str_radix(size_str.trim(), 16)
-                .map_err(|_| HttpError::InvalidResponse("Invalid chunk size".to_string()))?;
-
-            if size == 0 {
-                // Last chunk
-                self.state = ParserState::Complete;
-                return Ok(true);
-            }
+        let Some(pos) = self.find_line()? else {
+            return Ok(false);
+        };
+        let line: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+
+        // Hex size, then optional whitespace and `;extensions`, which are
+        // ignored
+        let digits = line.iter().take_while(|b| b.is_ascii_hexdigit()).count();
+        let rest = &line[digits..pos];
+        let rest_start = rest
+            .iter()
+            .position(|&b| b != b' ' && b != b'\t')
+            .unwrap_or(rest.len());
+        if digits == 0 || (rest_start < rest.len() && rest[rest_start] != b';') {
+            return Err(HttpError::InvalidResponse("Invalid chunk size".to_string()));
+        }
+        let size = line[..digits].iter().try_fold(0usize, |size, &digit| {
+            let value = (digit as char).to_digit(16).unwrap_or(0) as usize;
+            size.checked_mul(16)?.checked_add(value)
+        });
+        let size =
+            size.ok_or_else(|| HttpError::InvalidResponse("Chunk size overflow".to_string()))?;
+
+        if size == 0 {
+            // Last chunk; trailers may follow
+            self.state = ParserState::Trailers;
+            return Ok(true);
+        }
 
-            self.content_length = Some(size);
-            self.body_received = 0;
-            self.state = ParserState::ChunkedData;
-            Ok(true)
-        } else {
-            Ok(false)
+        if size > self.max_chunked_body - self.response.body.len() {
+            return Err(HttpError::InvalidResponse(
+                "Chunked body too large".to_string(),
+            ));
         }
+
+        self.content_length = Some(size);
+        self.body_received = 0;
+        self.state = ParserState::ChunkedData;
+        Ok(true)
     }
 
     fn parse_chunk_data(&mut self) -> Result<bool, HttpError> {
@@ -504,21 +784,54 @@ impl HttpParser {
         let remaining = chunk_size - self.body_received;
         let available = self.buffer.len().min(remaining);
 
-        let data: Vec<u8> = self.buffer.drain(..available).collect();
-        self.response.body.extend_from_slice(&data);
+        self.response.body.extend(self.buffer.drain(..available));
         self.body_received += available;
 
         if self.body_received >= chunk_size {
-            // Consume trailing CRLF
-            if self.buffer.len() >= 2 {
-                self.buffer.drain(..2);
-            }
-            self.state = ParserState::ChunkedSize;
+            self.state = ParserState::ChunkedDataEnd;
             return Ok(true);
         }
         Ok(false)
     }
 
+    fn parse_chunk_data_end(&mut self) -> Result<bool, HttpError> {
+        if self.buffer.len() < 2 {
+            return Ok(false);
+        }
+        if &self.buffer[..2] != b"\r\n" {
+            return Err(HttpError::InvalidResponse(
+                "Missing CRLF after chunk".to_string(),
+            ));
+        }
+        self.buffer.drain(..2);
+        self.state = ParserState::ChunkedSize;
+        Ok(true)
+    }
+
+    fn parse_trailers(&mut self) -> Result<bool, HttpError> {
+        while let Some(pos) = self.find_line()? {
+            let line: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+            if pos == 0 {
+                self.state = ParserState::Complete;
+                return Ok(true);
+            }
+            self.insert_header(&line[..pos]);
+        }
+        Ok(false)
+    }
+
+    /// Find the CRLF ending a chunk size or trailer line, failing if the
+    /// buffered line is already too long to be one.
+    fn find_line(&self) -> Result<Option<usize>, HttpError> {
+        match self.find_crlf() {
+            Some(pos) if pos <= MAX_CHUNK_LINE => Ok(Some(pos)),
+            None if self.buffer.len() <= MAX_CHUNK_LINE => Ok(None),
+            _ => Err(HttpError::InvalidResponse(
+                "Chunk line too long".to_string(),
+            )),
+        }
+    }
+
     fn find_crlf(&self) -> Option<usize> {
         for i in 0..self.buffer.len().saturating_sub(1) {
             if self.buffer[i] == b'\r' && self.buffer[i + 1] == b'\n' {
@@ -578,8 +891,23 @@ impl Url {
             (rest, "/")
         };
 
-        // Parse host and port
-        let (host, port) = if let Some(pos) = host_port.rfind(':') {
+        // Parse host and port; an IPv6 literal is bracketed
+        let (host, port) = if let Some(literal) = host_port.strip_prefix('[') {
+            let (host, rest) = literal
+                .split_once(']')
+                .ok_or_else(|| HttpError::InvalidUrl("Unclosed IPv6 literal".to_string()))?;
+            if Ipv6Addr::parse(host).is_none() {
+                return Err(HttpError::InvalidUrl("Invalid IPv6 literal".to_string()));
+            }
+            let port = match rest.strip_prefix(':') {
+                Some(port_str) => port_str
+                    .parse()
+                    .map_err(|_| HttpError::InvalidUrl("Invalid port".to_string()))?,
+                None if rest.is_empty() => default_port,
+                None => return Err(HttpError::InvalidUrl("Invalid port".to_string())),
+            };
+            (host, port)
+        } else if let Some(pos) = host_port.rfind(':') {
             let port_str = &host_port[pos + 1..];
             let port: u16 = port_str
                 .parse()
@@ -624,7 +952,7 @@ impl Url {
         }
     }
 
-    /// Get host with port.
+    /// Get host with port, bracketing an IPv6 literal.
     pub fn host_port(&self) -> String {
         let default_port = match self.scheme.as_str() {
             "http" => 80,
@@ -632,10 +960,15 @@ impl Url {
             _ => 0,
         };
 
-        if self.port == default_port {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
             self.host.clone()
+        };
+        if self.port == default_port {
+            host
         } else {
-            format!("{}:{}", self.host, self.port)
+            format!("{}:{}", host, self.port)
         }
     }
 
@@ -656,15 +989,27 @@ pub struct HttpClient {
     max_redirects: u32,
     /// Request timeout in milliseconds.
     timeout_ms: u64,
+    /// Credentials used to answer `401` Basic challenges.
+    credentials: Option<Credentials>,
+    /// Headers added to every request that does not set them itself.
+    default_headers: HeaderMap,
 }
 
 impl HttpClient {
+    /// ALPN protocols to offer during the TLS handshake, preferring HTTP/2.
+    pub const ALPN_PROTOCOLS: [&'static str; 2] = ["h2", "http/1.1"];
+
     /// Create a new HTTP client.
     pub fn new() -> Self {
         Self {
             user_agent: "KPIO-Browser/0.1".to_string(),
             max_redirects: 10,
             timeout_ms: 30000,
+            credentials: None,
+            default_headers: [("Accept", "*/*"), ("Accept-Encoding", "identity")]
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
         }
     }
 
@@ -674,6 +1019,38 @@ impl HttpClient {
         self
     }
 
+    /// Add headers sent with every request.
+    ///
+    /// These replace earlier defaults of the same name. A header set on the
+    /// request itself always wins over a default.
+    pub fn with_defaults(mut self, headers: HeaderMap) -> Self {
+        for (name, value) in headers {
+            self.default_headers
+                .retain(|key, _| !key.eq_ignore_ascii_case(&name));
+            self.default_headers.insert(name, value);
+        }
+        self
+    }
+
+    /// Get the headers sent with every request.
+    pub fn default_headers(&self) -> &HeaderMap {
+        &self.default_headers
+    }
+
+    /// Add the `User-Agent` and default headers that `request` does not
+    /// already set.
+    pub fn apply_defaults(&self, mut request: HttpRequest) -> HttpRequest {
+        if !request.has_header("User-Agent") {
+            request = request.user_agent(&self.user_agent);
+        }
+        for (name, value) in &self.default_headers {
+            if !request.has_header(name) {
+                request.headers.insert(name.clone(), value.clone());
+            }
+        }
+        request
+    }
+
     /// Set maximum redirects.
     pub fn max_redirects(mut self, max: u32) -> Self {
         self.max_redirects = max;
@@ -686,16 +1063,353 @@ impl HttpClient {
         self
     }
 
+    /// Set Basic credentials used to answer `401` challenges for any realm.
+    pub fn credentials(mut self, user: &str, pass: &str) -> Self {
+        self.credentials = Some(Credentials {
+            user: user.to_string(),
+            pass: pass.to_string(),
+            realm: None,
+        });
+        self
+    }
+
+    /// Set Basic credentials used to answer `401` challenges for `realm` only.
+    pub fn credentials_for_realm(mut self, realm: &str, user: &str, pass: &str) -> Self {
+        self.credentials = Some(Credentials {
+            user: user.to_string(),
+            pass: pass.to_string(),
+            realm: Some(realm.to_string()),
+        });
+        self
+    }
+
+    /// Build the retry for a `401` response carrying a Basic challenge.
+    ///
+    /// Returns `None` if the response is not a Basic challenge, no matching
+    /// credentials are configured, or the request already carried exactly
+    /// these credentials (so a rejected login is not retried forever).
+    pub fn reauthenticate(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> Option<HttpRequest> {
+        if response.status != StatusCode::UNAUTHORIZED {
+            return None;
+        }
+        let credentials = self.credentials.as_ref()?;
+        let challenge = response.basic_challenge()?;
+        if let Some(realm) = &credentials.realm {
+            if challenge.realm() != Some(realm.as_str()) {
+                return None;
+            }
+        }
+
+        let retry = request
+            .clone()
+            .basic_auth(&credentials.user, &credentials.pass);
+        if request.authorization() == retry.authorization() {
+            return None;
+        }
+        Some(retry)
+    }
+
+    /// Send a request through `transport`, retrying once with the configured
+    /// credentials if the server answers with a `401` Basic challenge.
+    pub fn execute<F>(
+        &self,
+        request: HttpRequest,
+        mut transport: F,
+    ) -> Result<HttpResponse, HttpError>
+    where
+        F: FnMut(&HttpRequest) -> Result<HttpResponse, HttpError>,
+    {
+        let request = self.apply_defaults(request);
+        let response = transport(&request)?;
+        match self.reauthenticate(&request, &response) {
+            Some(retry) => transport(&retry),
+            None => Ok(response),
+        }
+    }
+
+    /// Send a request to `url`'s origin over an idle keep-alive connection
+    /// from `pool`, opening one with `connect` if there is none. Like
+    /// [`execute`](Self::execute), a `401` Basic challenge is answered once.
+    ///
+    /// `current_time` is in milliseconds, on the clock the pool's idle
+    /// timeout is measured with.
+    pub fn execute_pooled<C, F>(
+        &self,
+        pool: &mut ConnectionPool<C>,
+        url: &Url,
+        request: HttpRequest,
+        current_time: u64,
+        mut connect: F,
+    ) -> Result<HttpResponse, HttpError>
+    where
+        C: HttpConnection,
+        F: FnMut(&PoolKey) -> Result<C, HttpError>,
+    {
+        let key = PoolKey::from_url(url);
+        self.execute(request, |request| {
+            pool.send(&key, request, current_time, &mut connect)
+        })
+    }
+
+    /// Resolve the host of `url` to an address through the DNS cache (see
+    /// [`dns::resolve_cached`](crate::dns::resolve_cached)); an IP literal
+    /// needs no lookup. `exchange` sends a DNS query and returns the reply;
+    /// `current_time` is in seconds.
+    pub fn resolve_host<F>(
+        &self,
+        url: &Url,
+        current_time: u64,
+        exchange: F,
+    ) -> Result<IpAddress, HttpError>
+    where
+        F: FnMut(&[u8]) -> Result<Vec<u8>, NetworkError>,
+    {
+        if let Some(ip) = IpAddress::parse(&url.host) {
+            return Ok(ip);
+        }
+
+        crate::dns::resolve_cached(&url.host, current_time, exchange)
+            .map_err(|e| HttpError::DnsError(format!("{}: {:?}", url.host, e)))?
+            .first()
+            .copied()
+            .ok_or_else(|| HttpError::DnsError(url.host.clone()))
+    }
+
+    /// Resolve the host of `url` to every address worth trying, IPv6 and
+    /// IPv4 interleaved (see
+    /// [`dns::resolve_dual_stack`](crate::dns::resolve_dual_stack)), for
+    /// [`connect_dual_stack`](Self::connect_dual_stack). An IP literal
+    /// needs no lookup.
+    pub fn resolve_addresses<F>(
+        &self,
+        url: &Url,
+        current_time: u64,
+        exchange: F,
+    ) -> Result<Vec<SocketAddr>, HttpError>
+    where
+        F: FnMut(&[u8]) -> Result<Vec<u8>, NetworkError>,
+    {
+        let addresses = match IpAddress::parse(&url.host) {
+            Some(ip) => Vec::from([ip]),
+            None => crate::dns::resolve_dual_stack(&url.host, current_time, exchange)
+                .map_err(|e| HttpError::DnsError(format!("{}: {:?}", url.host, e)))?,
+        };
+        Ok(addresses
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, url.port))
+            .collect())
+    }
+
+    /// Connect to the first of `addresses` that answers.
+    ///
+    /// Attempts start in order, each [`CONNECTION_ATTEMPT_DELAY_MS`] after
+    /// the previous one, or at once if that one failed (RFC 8305
+    /// section 5); earlier attempts keep running, so a slow IPv6 path
+    /// loses to a working IPv4 one instead of stalling the request.
+    /// `start` begins a connection attempt, and `now` reads the clock in
+    /// milliseconds. Fails with [`HttpError::Timeout`] after the client's
+    /// timeout, or with the last attempt's error once every one failed.
+    pub fn connect_dual_stack<A, S, N>(
+        &self,
+        addresses: &[SocketAddr],
+        mut start: S,
+        mut now: N,
+    ) -> Result<(SocketAddr, A::Connection), HttpError>
+    where
+        A: ConnectAttempt,
+        S: FnMut(&SocketAddr) -> Result<A, HttpError>,
+        N: FnMut() -> u64,
+    {
+        let began = now();
+        let mut next = 0;
+        let mut next_start = began;
+        let mut pending: Vec<(SocketAddr, A)> = Vec::new();
+        let mut last_error = HttpError::Network("no addresses to connect to".to_string());
+
+        loop {
+            let time = now();
+            if next < addresses.len() && (time >= next_start || pending.is_empty()) {
+                let address = addresses[next];
+                next += 1;
+                match start(&address) {
+                    Ok(attempt) => {
+                        pending.push((address, attempt));
+                        next_start = time + CONNECTION_ATTEMPT_DELAY_MS;
+                    }
+                    Err(e) => {
+                        last_error = e;
+                        next_start = time;
+                    }
+                }
+            }
+
+            let mut index = 0;
+            while index < pending.len() {
+                match pending[index].1.poll() {
+                    Ok(Some(connection)) => return Ok((pending[index].0, connection)),
+                    Ok(None) => index += 1,
+                    Err(e) => {
+                        pending.remove(index);
+                        last_error = e;
+                        // Don't wait out the delay for a failed attempt
+                        next_start = time;
+                    }
+                }
+            }
+
+            if pending.is_empty() && next == addresses.len() {
+                return Err(last_error);
+            }
+            if time.saturating_sub(began) >= self.timeout_ms {
+                return Err(HttpError::Timeout);
+            }
+        }
+    }
+
+    /// A TLS connector for `host` that offers [`ALPN_PROTOCOLS`](Self::ALPN_PROTOCOLS),
+    /// so the server can pick HTTP/2. Pass the protocol it selected to
+    /// [`execute_negotiated`](Self::execute_negotiated).
+    pub fn tls_connector(&self, host: &str) -> TlsConnector {
+        Self::ALPN_PROTOCOLS.iter().fold(
+            TlsConnector::new().server_name(host),
+            |connector, protocol| connector.alpn_protocol(protocol),
+        )
+    }
+
+    /// Send `requests` over `conn` using the protocol selected by ALPN.
+    ///
+    /// When `alpn` is `h2` the requests are multiplexed over a new HTTP/2
+    /// connection with [`execute_multiplexed`](Self::execute_multiplexed);
+    /// otherwise they are sent one after another as HTTP/1.1, each through
+    /// [`execute`](Self::execute). Once the server closes an HTTP/1.1
+    /// connection the remaining requests fail. Responses are returned in
+    /// request order.
+    pub fn execute_negotiated<C: HttpConnection>(
+        &self,
+        conn: &mut C,
+        alpn: Option<&str>,
+        requests: &[HttpRequest],
+    ) -> Vec<Result<HttpResponse, HttpError>> {
+        if alpn == Some("h2") {
+            let mut h2 = Http2Connection::new();
+            return self.execute_multiplexed(&mut h2, requests, |bytes| {
+                conn.send(bytes)?;
+                conn.recv()
+            });
+        }
+
+        let mut open = true;
+        requests
+            .iter()
+            .map(|request| {
+                if !open {
+                    return Err(HttpError::ConnectionClosed);
+                }
+                self.execute(request.clone(), |request| match exchange(conn, request) {
+                    Ok((response, reusable)) => {
+                        open = reusable && request.keep_alive() && response.keep_alive();
+                        Ok(response)
+                    }
+                    Err(ExchangeError::Stale(e) | ExchangeError::Failed(e)) => {
+                        open = false;
+                        Err(e)
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Send `requests` concurrently over an HTTP/2 connection.
+    ///
+    /// `transport` writes the given bytes to the connection and returns
+    /// the bytes read back; an empty read means the connection closed.
+    /// Requests are sent once the server's SETTINGS arrive; those beyond
+    /// its concurrency limit wait for earlier streams to finish. Responses
+    /// are returned in request order.
+    pub fn execute_multiplexed<F>(
+        &self,
+        conn: &mut Http2Connection,
+        requests: &[HttpRequest],
+        mut transport: F,
+    ) -> Vec<Result<HttpResponse, HttpError>>
+    where
+        F: FnMut(&[u8]) -> Result<Vec<u8>, HttpError>,
+    {
+        let mut results: Vec<Option<Result<HttpResponse, HttpError>>> =
+            requests.iter().map(|_| None).collect();
+        let mut next = 0;
+        let mut in_flight: Vec<(usize, u32)> = Vec::new();
+
+        // Learn the server's stream limit before opening streams
+        while conn.is_open() && !conn.remote_settings_received() {
+            match transport(&conn.take_outbound()) {
+                Ok(data) if !data.is_empty() => {
+                    let _ = conn.recv(&data);
+                }
+                _ => break,
+            }
+        }
+
+        loop {
+            while next < requests.len() && conn.can_send_request() {
+                match conn.send_request(&self.apply_defaults(requests[next].clone())) {
+                    Ok(stream_id) => in_flight.push((next, stream_id)),
+                    Err(e) => results[next] = Some(Err(e.into())),
+                }
+                next += 1;
+            }
+
+            in_flight.retain(|&(index, stream_id)| match conn.poll_response(stream_id) {
+                Some(result) => {
+                    results[index] = Some(result.map_err(HttpError::from));
+                    false
+                }
+                None => true,
+            });
+
+            if in_flight.is_empty() {
+                // Done, or the connection can take no more streams
+                break;
+            }
+
+            let received = match transport(&conn.take_outbound()) {
+                Ok(data) if !data.is_empty() => data,
+                Ok(_) => {
+                    for &(index, _) in &in_flight {
+                        results[index] = Some(Err(HttpError::ConnectionClosed));
+                    }
+                    break;
+                }
+                Err(e) => {
+                    for &(index, _) in &in_flight {
+                        results[index] = Some(Err(e.clone()));
+                    }
+                    break;
+                }
+            };
+            // Streams failed by a connection error are collected above
+            let _ = conn.recv(&received);
+        }
+
+        results
+            .into_iter()
+            .map(|r| r.unwrap_or(Err(HttpError::ConnectionClosed)))
+            .collect()
+    }
+
     /// Build a GET request for a URL.
     pub fn get(&self, url: &str) -> Result<HttpRequest, HttpError> {
         let parsed = Url::parse(url)?;
 
-        Ok(HttpRequest::get(&parsed.path_and_query())
-            .host(&parsed.host_port())
-            .user_agent(&self.user_agent)
-            .header("Accept", "*/*")
-            .header("Accept-Encoding", "identity")
-            .header("Connection", "close"))
+        Ok(self.apply_defaults(
+            HttpRequest::get(&parsed.path_and_query())
+                .host(&parsed.host_port())
+                .header("Connection", "close"),
+        ))
     }
 
     /// Build a POST request for a URL.
@@ -707,12 +1421,12 @@ impl HttpClient {
     ) -> Result<HttpRequest, HttpError> {
         let parsed = Url::parse(url)?;
 
-        Ok(HttpRequest::post(&parsed.path_and_query(), body)
-            .host(&parsed.host_port())
-            .user_agent(&self.user_agent)
-            .content_type(content_type)
-            .header("Accept", "*/*")
-            .header("Connection", "close"))
+        Ok(self.apply_defaults(
+            HttpRequest::post(&parsed.path_and_query(), body)
+                .host(&parsed.host_port())
+                .content_type(content_type)
+                .header("Connection", "close"),
+        ))
     }
 
     /// Parse a response from raw bytes.
@@ -729,6 +1443,270 @@ impl Default for HttpClient {
     }
 }
 
+/// How long [`HttpClient::connect_dual_stack`] waits on one connection
+/// attempt before starting the next, in milliseconds (RFC 8305
+/// section 8).
+pub const CONNECTION_ATTEMPT_DELAY_MS: u64 = 250;
+
+/// A connection being established, such as a TCP handshake in progress.
+pub trait ConnectAttempt {
+    /// The established connection.
+    type Connection;
+
+    /// Check, without waiting, whether the attempt finished: `Some` once
+    /// connected, `None` while still in progress.
+    fn poll(&mut self) -> Result<Option<Self::Connection>, HttpError>;
+}
+
+/// A connection HTTP/1.1 requests are sent over, such as a TCP or TLS
+/// stream.
+pub trait HttpConnection {
+    /// Write all of `data`.
+    fn send(&mut self, data: &[u8]) -> Result<(), HttpError>;
+
+    /// Read the next bytes, waiting for some; an empty read means the
+    /// server closed the connection.
+    fn recv(&mut self) -> Result<Vec<u8>, HttpError>;
+
+    /// Check, without waiting, whether the server has closed the
+    /// connection.
+    fn is_closed(&self) -> bool;
+}
+
+/// The origin a pooled connection leads to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PoolKey {
+    /// URL scheme (http or https).
+    pub scheme: String,
+    /// Host name.
+    pub host: String,
+    /// Port number.
+    pub port: u16,
+}
+
+impl PoolKey {
+    /// The origin of `url`.
+    pub fn from_url(url: &Url) -> Self {
+        Self {
+            scheme: url.scheme.to_ascii_lowercase(),
+            host: url.host.to_ascii_lowercase(),
+            port: url.port,
+        }
+    }
+}
+
+/// Connection pool limits.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolConfig {
+    /// Idle connections older than this (in milliseconds) are closed.
+    pub idle_timeout_ms: u64,
+    /// Idle connections kept per origin.
+    pub max_per_host: usize,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_ms: 90_000,
+            max_per_host: 6,
+        }
+    }
+}
+
+/// Connection pool statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Connections opened.
+    pub opened: u64,
+    /// Requests sent over an idle connection.
+    pub reused: u64,
+    /// Idle connections found closed by the server when reused, and
+    /// replaced by a new one.
+    pub reconnected: u64,
+    /// Idle connections closed for exceeding the idle timeout.
+    pub expired: u64,
+    /// Connections closed after a response instead of returning to the
+    /// pool (`Connection: close`, a close-delimited body, or a full pool).
+    pub closed: u64,
+    /// Connections idle in the pool now.
+    pub idle: usize,
+}
+
+/// An idle connection and when it became idle.
+struct IdleConnection<C> {
+    conn: C,
+    idle_since: u64,
+}
+
+/// Keep-alive connections for reuse by later requests to the same
+/// origin.
+///
+/// A connection only returns to the pool once its response has been
+/// read completely, so a connection is never shared mid-response.
+pub struct ConnectionPool<C> {
+    config: PoolConfig,
+    idle: BTreeMap<PoolKey, Vec<IdleConnection<C>>>,
+    stats: PoolStats,
+}
+
+/// Why a request over a connection failed.
+enum ExchangeError {
+    /// The connection was closed before any of the response arrived.
+    Stale(HttpError),
+    /// The request failed after the response started.
+    Failed(HttpError),
+}
+
+impl<C: HttpConnection> ConnectionPool<C> {
+    /// Create an empty pool.
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            idle: BTreeMap::new(),
+            stats: PoolStats::default(),
+        }
+    }
+
+    /// Get the pool statistics.
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    /// Take the most recently used idle connection to `key`, closing any
+    /// that expired or that the server closed.
+    pub fn checkout(&mut self, key: &PoolKey, current_time: u64) -> Option<C> {
+        let idle = self.idle.get_mut(key)?;
+        let mut found = None;
+        while let Some(entry) = idle.pop() {
+            self.stats.idle -= 1;
+            if current_time.saturating_sub(entry.idle_since) >= self.config.idle_timeout_ms {
+                self.stats.expired += 1;
+            } else if !entry.conn.is_closed() {
+                found = Some(entry.conn);
+                break;
+            }
+        }
+        if idle.is_empty() {
+            self.idle.remove(key);
+        }
+        found
+    }
+
+    /// Return a connection whose response has been read completely. It is
+    /// dropped instead if `key` already has `max_per_host` idle ones.
+    pub fn checkin(&mut self, key: PoolKey, conn: C, current_time: u64) {
+        let idle = self.idle.entry(key).or_default();
+        if idle.len() >= self.config.max_per_host {
+            self.stats.closed += 1;
+            return;
+        }
+        idle.push(IdleConnection {
+            conn,
+            idle_since: current_time,
+        });
+        self.stats.idle += 1;
+    }
+
+    /// Close idle connections that exceeded the idle timeout.
+    pub fn purge_expired(&mut self, current_time: u64) {
+        let timeout = self.config.idle_timeout_ms;
+        let mut expired = 0;
+        self.idle.retain(|_, idle| {
+            let before = idle.len();
+            idle.retain(|entry| current_time.saturating_sub(entry.idle_since) < timeout);
+            expired += before - idle.len();
+            !idle.is_empty()
+        });
+        self.stats.expired += expired as u64;
+        self.stats.idle -= expired;
+    }
+
+    /// Send `request` over a pooled connection to `key`, or a new one from
+    /// `connect`. An idle connection the server turns out to have closed
+    /// is replaced by a new one once.
+    pub fn send<F>(
+        &mut self,
+        key: &PoolKey,
+        request: &HttpRequest,
+        current_time: u64,
+        mut connect: F,
+    ) -> Result<HttpResponse, HttpError>
+    where
+        F: FnMut(&PoolKey) -> Result<C, HttpError>,
+    {
+        let (mut conn, reused) = match self.checkout(key, current_time) {
+            Some(conn) => {
+                self.stats.reused += 1;
+                (conn, true)
+            }
+            None => (self.open(key, &mut connect)?, false),
+        };
+
+        let mut result = exchange(&mut conn, request);
+        if reused && matches!(result, Err(ExchangeError::Stale(_))) {
+            self.stats.reconnected += 1;
+            conn = self.open(key, &mut connect)?;
+            result = exchange(&mut conn, request);
+        }
+
+        match result {
+            Ok((response, reusable)) => {
+                if reusable && request.keep_alive() && response.keep_alive() {
+                    self.checkin(key.clone(), conn, current_time);
+                } else {
+                    self.stats.closed += 1;
+                }
+                Ok(response)
+            }
+            Err(ExchangeError::Stale(e) | ExchangeError::Failed(e)) => Err(e),
+        }
+    }
+
+    fn open<F>(&mut self, key: &PoolKey, connect: &mut F) -> Result<C, HttpError>
+    where
+        F: FnMut(&PoolKey) -> Result<C, HttpError>,
+    {
+        let conn = connect(key)?;
+        self.stats.opened += 1;
+        Ok(conn)
+    }
+}
+
+/// Send `request` and read its response. Also returns whether the
+/// connection ended cleanly after the response, so it can carry another.
+fn exchange<C: HttpConnection>(
+    conn: &mut C,
+    request: &HttpRequest,
+) -> Result<(HttpResponse, bool), ExchangeError> {
+    conn.send(&request.to_bytes())
+        .map_err(ExchangeError::Stale)?;
+
+    let mut parser = HttpParser::for_method(request.method);
+    let mut started = false;
+    loop {
+        let data = match conn.recv() {
+            Ok(data) => data,
+            Err(e) if !started => return Err(ExchangeError::Stale(e)),
+            Err(e) => return Err(ExchangeError::Failed(e)),
+        };
+        if data.is_empty() {
+            if !started {
+                return Err(ExchangeError::Stale(HttpError::ConnectionClosed));
+            }
+            if parser.reads_until_close() {
+                return Ok((parser.response(), false));
+            }
+            return Err(ExchangeError::Failed(HttpError::ConnectionClosed));
+        }
+        started = true;
+        if parser.feed(&data).map_err(ExchangeError::Failed)? {
+            // Bytes past the response mean the framing is off
+            let reusable = parser.buffer.is_empty();
+            return Ok((parser.response(), reusable));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -750,6 +1728,99 @@ mod tests {
         assert_eq!(url.port, 8080);
     }
 
+    #[test]
+    fn test_url_ipv6_literal() {
+        let url = Url::parse("http://[::1]:8080/status").unwrap();
+        assert_eq!(url.host, "::1");
+        assert_eq!(url.port, 8080);
+        assert_eq!(url.path, "/status");
+        assert_eq!(url.host_port(), "[::1]:8080");
+
+        let url = Url::parse("https://[2001:db8::1]").unwrap();
+        assert_eq!(url.host, "2001:db8::1");
+        assert_eq!(url.port, 443);
+        assert_eq!(url.host_port(), "[2001:db8::1]");
+
+        assert!(Url::parse("http://[::1/").is_err());
+        assert!(Url::parse("http://[not-an-address]/").is_err());
+        assert!(Url::parse("http://[::1]8080/").is_err());
+
+        // Literals need no DNS lookup
+        let client = HttpClient::new();
+        let url = Url::parse("http://[2001:db8::1]:8080/").unwrap();
+        let no_dns = |_: &[u8]| -> Result<Vec<u8>, NetworkError> { unreachable!() };
+        assert_eq!(
+            client.resolve_addresses(&url, 0, no_dns).unwrap(),
+            [SocketAddr::v6(
+                Ipv6Addr::parse("2001:db8::1").unwrap(),
+                8080
+            )]
+        );
+    }
+
+    /// A connection attempt that connects, or fails, once the shared
+    /// clock reaches a given time.
+    struct ScriptedAttempt<'a> {
+        clock: &'a core::cell::Cell<u64>,
+        done_at: u64,
+        connects: bool,
+    }
+
+    impl ConnectAttempt for ScriptedAttempt<'_> {
+        type Connection = u64;
+
+        fn poll(&mut self) -> Result<Option<u64>, HttpError> {
+            match self.clock.get() >= self.done_at {
+                false => Ok(None),
+                true if self.connects => Ok(Some(self.clock.get())),
+                true => Err(HttpError::ConnectionClosed),
+            }
+        }
+    }
+
+    #[test]
+    fn test_connect_dual_stack() {
+        let v6 = SocketAddr::v6(Ipv6Addr::parse("2001:db8::1").unwrap(), 80);
+        let v4 = SocketAddr::v4(192, 0, 2, 1, 80);
+        let client = HttpClient::new().timeout(5000);
+
+        // Each address takes the given time to connect, or to fail
+        let race = |outcomes: [(u64, bool); 2]| {
+            let clock = core::cell::Cell::new(0);
+            client.connect_dual_stack(
+                &[v6, v4],
+                |address| {
+                    let (took, connects) = outcomes[usize::from(*address == v4)];
+                    Ok(ScriptedAttempt {
+                        clock: &clock,
+                        done_at: clock.get() + took,
+                        connects,
+                    })
+                },
+                || clock.replace(clock.get() + 10),
+            )
+        };
+
+        // A fast IPv6 path is used without trying IPv4
+        assert_eq!(race([(50, true), (10, true)]).unwrap().0, v6);
+        // A slow IPv6 path loses to IPv4 started after the delay
+        let (address, connected_at) = race([(2000, true), (20, true)]).unwrap();
+        assert_eq!(address, v4);
+        assert!((CONNECTION_ATTEMPT_DELAY_MS..2000).contains(&connected_at));
+        // A failing IPv6 path falls back to IPv4 at once
+        let (address, connected_at) = race([(30, false), (20, true)]).unwrap();
+        assert_eq!(address, v4);
+        assert!(connected_at < CONNECTION_ATTEMPT_DELAY_MS);
+        assert!(matches!(
+            race([(30, false), (20, false)]),
+            Err(HttpError::ConnectionClosed)
+        ));
+        assert!(matches!(
+            race([(9000, true), (9000, true)]),
+            Err(HttpError::Timeout)
+        ));
+    }
+
     #[test]
     fn test_request_serialization() {
         let request = HttpRequest::get("/index.html")
@@ -774,4 +1845,442 @@ mod tests {
         assert_eq!(response.status.0, 200);
         assert_eq!(response.text(), Some("Hello, World!".to_string()));
     }
+
+    #[test]
+    fn test_chunked_response() {
+        let head = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+
+        // Fed one byte at a time, every split is just "need more data"
+        let mut parser = HttpParser::new();
+        let data = [&head[..], b"5\r\nhello\r\n0\r\n\r\n"].concat();
+        let (last, rest) = data.split_last().unwrap();
+        for byte in rest {
+            assert!(!parser.feed(core::slice::from_ref(byte)).unwrap());
+        }
+        assert!(parser.feed(&[*last]).unwrap());
+        assert_eq!(parser.response().text(), Some("hello".to_string()));
+
+        // Extensions are skipped and trailers become headers
+        let mut parser = HttpParser::new();
+        parser.feed(head).unwrap();
+        let complete = parser
+            .feed(b"6;name=\"v\"\r\nhello \r\nA ; ext\r\nchunked!!!\r\n0\r\nExpires: never\r\n\r\n")
+            .unwrap();
+        assert!(complete);
+        let response = parser.response();
+        assert_eq!(response.text(), Some("hello chunked!!!".to_string()));
+        assert_eq!(response.header("expires").unwrap(), "never");
+
+        let error = |body: &[u8], parser: HttpParser| {
+            let mut parser = parser;
+            parser.feed(head).unwrap();
+            parser.feed(body).is_err()
+        };
+        assert!(error(b"1ffffffffffffffff\r\n", HttpParser::new()));
+        assert!(error(b"zz\r\n", HttpParser::new()));
+        assert!(error(b"3\r\nabcX\r\n", HttpParser::new()));
+        assert!(error(
+            b"8\r\n12345678\r\n8\r\n",
+            HttpParser::new().max_chunked_body(12)
+        ));
+        assert!(!error(
+            b"8\r\n12345678\r\n4\r\n",
+            HttpParser::new().max_chunked_body(12)
+        ));
+        assert!(error(&[b'1'; MAX_CHUNK_LINE + 1], HttpParser::new()));
+    }
+
+    /// A connection replaying one scripted reply (as read chunks) per
+    /// request; with no reply left the server has closed it
+    struct ScriptedConnection {
+        replies: alloc::collections::VecDeque<Vec<&'static [u8]>>,
+        pending: alloc::collections::VecDeque<&'static [u8]>,
+        closed: bool,
+    }
+
+    impl HttpConnection for ScriptedConnection {
+        fn send(&mut self, _data: &[u8]) -> Result<(), HttpError> {
+            self.pending = self.replies.pop_front().unwrap_or_default().into();
+            Ok(())
+        }
+
+        fn recv(&mut self) -> Result<Vec<u8>, HttpError> {
+            Ok(self.pending.pop_front().unwrap_or_default().to_vec())
+        }
+
+        fn is_closed(&self) -> bool {
+            self.closed
+        }
+    }
+
+    #[test]
+    fn test_connection_pool() {
+        const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        const CLOSE: &[u8] = b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok";
+
+        let client = HttpClient::new();
+        let url = Url::parse("http://example.com/").unwrap();
+        let request = || HttpRequest::get("/").host("example.com");
+        let mut pool = ConnectionPool::new(PoolConfig {
+            idle_timeout_ms: 1000,
+            max_per_host: 1,
+        });
+        let mut scripts: alloc::collections::VecDeque<Vec<Vec<&'static [u8]>>> = alloc::vec![
+            // Reused, with the second response split mid-header; then the
+            // server downgrades to `Connection: close`
+            alloc::vec![
+                alloc::vec![OK],
+                alloc::vec![&OK[..10], &OK[10..]],
+                alloc::vec![CLOSE]
+            ],
+            // Closed by the server while idle without notice: the pooled
+            // connection reads nothing, so a new one is opened
+            alloc::vec![alloc::vec![OK]],
+            alloc::vec![alloc::vec![OK], alloc::vec![OK]],
+            // Replaces the connection that expired while idle
+            alloc::vec![alloc::vec![OK]],
+        ]
+        .into();
+        let mut connect = |key: &PoolKey| {
+            assert_eq!(key.host, "example.com");
+            assert_eq!(key.port, 80);
+            Ok(ScriptedConnection {
+                replies: scripts.pop_front().unwrap().into(),
+                pending: Default::default(),
+                closed: false,
+            })
+        };
+        let mut get = |pool: &mut ConnectionPool<ScriptedConnection>, time| {
+            client
+                .execute_pooled(pool, &url, request(), time, &mut connect)
+                .unwrap()
+        };
+
+        for _ in 0..3 {
+            assert_eq!(get(&mut pool, 0).text().unwrap(), "ok");
+        }
+        let stats = pool.stats();
+        assert_eq!((stats.opened, stats.reused, stats.closed), (1, 2, 1));
+        assert_eq!(stats.idle, 0);
+
+        get(&mut pool, 0);
+        get(&mut pool, 0);
+        let stats = pool.stats();
+        assert_eq!((stats.opened, stats.reused, stats.reconnected), (3, 3, 1));
+        assert_eq!(stats.idle, 1);
+
+        get(&mut pool, 5000);
+        let stats = pool.stats();
+        assert_eq!((stats.opened, stats.expired, stats.idle), (4, 1, 1));
+
+        // Full pool and a request asking to close
+        pool.checkin(
+            PoolKey::from_url(&url),
+            ScriptedConnection {
+                replies: Default::default(),
+                pending: Default::default(),
+                closed: false,
+            },
+            5000,
+        );
+        assert_eq!(pool.stats().closed, 2);
+        let mut reply = Some(ScriptedConnection {
+            replies: alloc::vec![alloc::vec![OK]].into(),
+            pending: Default::default(),
+            closed: false,
+        });
+        let mut fresh = ConnectionPool::new(PoolConfig::default());
+        client
+            .execute_pooled(
+                &mut fresh,
+                &url,
+                request().header("Connection", "close"),
+                0,
+                |_: &PoolKey| reply.take().ok_or(HttpError::ConnectionClosed),
+            )
+            .unwrap();
+        assert_eq!(fresh.stats().idle, 0);
+        assert_eq!(fresh.stats().closed, 1);
+    }
+
+    #[test]
+    fn test_pool_never_reuses_unfinished_response() {
+        const HEAD: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n";
+        let url = Url::parse("http://example.com/").unwrap();
+        let mut pool = ConnectionPool::new(PoolConfig::default());
+        let key = PoolKey::from_url(&url);
+        let conn = |reply: &'static [u8]| ScriptedConnection {
+            replies: alloc::vec![alloc::vec![reply, &b"partial"[..]]].into(),
+            pending: Default::default(),
+            closed: false,
+        };
+
+        // The body stops short: an error, and the connection is dropped
+        let mut first = Some(conn(HEAD));
+        let result = pool.send(&key, &HttpRequest::get("/"), 0, |_: &PoolKey| {
+            first.take().ok_or(HttpError::ConnectionClosed)
+        });
+        assert!(matches!(result, Err(HttpError::ConnectionClosed)));
+        assert_eq!(pool.stats().idle, 0);
+
+        // The same headers answering HEAD have no body, so the connection
+        // is reusable once they are read
+        let mut second = Some(ScriptedConnection {
+            replies: alloc::vec![alloc::vec![HEAD]].into(),
+            pending: Default::default(),
+            closed: false,
+        });
+        let response = pool
+            .send(&key, &HttpRequest::head("/"), 0, |_: &PoolKey| {
+                second.take().ok_or(HttpError::ConnectionClosed)
+            })
+            .unwrap();
+        assert_eq!(response.content_length(), Some(100));
+        assert_eq!(pool.stats().idle, 1);
+    }
+
+    #[test]
+    fn test_auth_headers() {
+        let request = HttpRequest::get("/").basic_auth("Aladdin", "open sesame");
+        assert_eq!(
+            request.authorization(),
+            Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+        );
+
+        let request = HttpRequest::get("/").bearer_auth("abc.def");
+        assert_eq!(request.authorization(), Some("Bearer abc.def"));
+    }
+
+    #[test]
+    fn test_default_headers() {
+        let mut defaults = HeaderMap::new();
+        defaults.insert("accept".to_string(), "text/html".to_string());
+        defaults.insert("Accept-Language".to_string(), "ko-KR,ko;q=0.9".to_string());
+        defaults.insert("DNT".to_string(), "1".to_string());
+        let client = HttpClient::new()
+            .user_agent("Test/1.0")
+            .with_defaults(defaults);
+
+        let request = client.get("http://example.com/").unwrap();
+        assert_eq!(request.headers.get("User-Agent").unwrap(), "Test/1.0");
+        assert_eq!(
+            request.headers.get("Accept-Language").unwrap(),
+            "ko-KR,ko;q=0.9"
+        );
+        assert_eq!(request.headers.get("DNT").unwrap(), "1");
+        // The new default replaces the built-in one despite the case difference
+        assert_eq!(request.headers.get("accept").unwrap(), "text/html");
+        assert!(!request.headers.contains_key("Accept"));
+        assert_eq!(request.headers.get("Accept-Encoding").unwrap(), "identity");
+
+        // Headers set on the request win, whatever their case
+        let request = client.apply_defaults(
+            HttpRequest::get("/")
+                .header("ACCEPT-LANGUAGE", "fr")
+                .user_agent("Other/2.0"),
+        );
+        assert_eq!(request.headers.get("ACCEPT-LANGUAGE").unwrap(), "fr");
+        assert!(!request.headers.contains_key("Accept-Language"));
+        assert_eq!(request.headers.get("User-Agent").unwrap(), "Other/2.0");
+
+        let mut sent = Vec::new();
+        client
+            .execute(HttpRequest::get("/").header("DNT", "0"), |request| {
+                sent.push(request.clone());
+                HttpClient::parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            })
+            .unwrap();
+        assert_eq!(sent[0].headers.get("DNT").unwrap(), "0");
+        assert_eq!(sent[0].headers.get("User-Agent").unwrap(), "Test/1.0");
+    }
+
+    #[test]
+    fn test_challenge_parsing() {
+        let challenges = AuthChallenge::parse_all(
+            r#"Bearer realm="api", error="invalid_token", Basic realm="Admin \"Area\"", charset=UTF-8"#,
+        );
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].scheme, "Bearer");
+        assert_eq!(challenges[0].realm(), Some("api"));
+        assert_eq!(
+            challenges[0].params.get("error").map(|v| v.as_str()),
+            Some("invalid_token")
+        );
+        assert_eq!(challenges[1].scheme, "Basic");
+        assert_eq!(challenges[1].realm(), Some("Admin \"Area\""));
+        assert_eq!(
+            challenges[1].params.get("charset").map(|v| v.as_str()),
+            Some("UTF-8")
+        );
+    }
+
+    #[test]
+    fn test_reauthenticate_on_basic_challenge() {
+        let client = HttpClient::new().credentials_for_realm("private", "user", "pass");
+        let unauthorized = HttpClient::parse_response(
+            b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"private\"\r\nContent-Length: 0\r\n\r\n",
+        )
+        .unwrap();
+
+        let mut sent = Vec::new();
+        let response = client
+            .execute(HttpRequest::get("/secret"), |req| {
+                sent.push(req.authorization().map(|a| a.to_string()));
+                if req.authorization().is_some() {
+                    HttpClient::parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                } else {
+                    Ok(unauthorized.clone())
+                }
+            })
+            .unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            sent,
+            alloc::vec![None, Some("Basic dXNlcjpwYXNz".to_string())]
+        );
+
+        // Credentials already rejected: no retry loop.
+        let authed = HttpRequest::get("/secret").basic_auth("user", "pass");
+        assert!(client.reauthenticate(&authed, &unauthorized).is_none());
+
+        // Other realm: not answered.
+        let other = HttpClient::new().credentials_for_realm("elsewhere", "user", "pass");
+        assert!(other
+            .reauthenticate(&HttpRequest::get("/"), &unauthorized)
+            .is_none());
+    }
+
+    /// An HTTP/2 server allowing one stream at a time, answering each
+    /// request with its path as the body
+    struct Http2EchoServer {
+        decoder: crate::http2::hpack::Decoder,
+        encoder: crate::http2::hpack::Encoder,
+        reply: Vec<u8>,
+        max_in_flight: usize,
+    }
+
+    impl Http2EchoServer {
+        fn new() -> Self {
+            Self {
+                decoder: crate::http2::hpack::Decoder::new(),
+                encoder: crate::http2::hpack::Encoder::new(),
+                reply: Vec::new(),
+                max_in_flight: 0,
+            }
+        }
+    }
+
+    impl HttpConnection for Http2EchoServer {
+        fn send(&mut self, mut bytes: &[u8]) -> Result<(), HttpError> {
+            use crate::http2::frame::{setting, Frame, FrameHeader, FRAME_HEADER_LEN};
+            use crate::http2::PREFACE;
+
+            if bytes.starts_with(PREFACE) {
+                bytes = &bytes[PREFACE.len()..];
+                Frame::Settings {
+                    ack: false,
+                    params: alloc::vec![(setting::MAX_CONCURRENT_STREAMS, 1)],
+                }
+                .encode(&mut self.reply);
+            }
+            let mut in_flight = 0;
+            while let Some(header) = FrameHeader::parse(bytes) {
+                let len = FRAME_HEADER_LEN + header.length as usize;
+                let frame = Frame::decode(&header, &bytes[FRAME_HEADER_LEN..len]).unwrap();
+                bytes = &bytes[len..];
+                if let Frame::Headers {
+                    stream_id, block, ..
+                } = frame
+                {
+                    in_flight += 1;
+                    let request = self.decoder.decode(&block).unwrap();
+                    let path = request.iter().find(|(n, _)| n == ":path").unwrap();
+                    Frame::Headers {
+                        stream_id,
+                        block: self
+                            .encoder
+                            .encode(&[(":status".to_string(), "200".to_string())]),
+                        end_stream: false,
+                        end_headers: true,
+                    }
+                    .encode(&mut self.reply);
+                    Frame::Data {
+                        stream_id,
+                        data: path.1.as_bytes().to_vec(),
+                        end_stream: true,
+                    }
+                    .encode(&mut self.reply);
+                }
+            }
+            self.max_in_flight = self.max_in_flight.max(in_flight);
+            Ok(())
+        }
+
+        fn recv(&mut self) -> Result<Vec<u8>, HttpError> {
+            Ok(core::mem::take(&mut self.reply))
+        }
+
+        fn is_closed(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_execute_multiplexed_over_http2() {
+        let client = HttpClient::new();
+        let requests: Vec<HttpRequest> = ["/a", "/b", "/c"]
+            .iter()
+            .map(|path| client.get(&format!("https://example.com{}", path)).unwrap())
+            .collect();
+
+        let mut server = Http2EchoServer::new();
+        let mut conn = Http2Connection::new();
+        let results = client.execute_multiplexed(&mut conn, &requests, |bytes| {
+            server.send(bytes)?;
+            server.recv()
+        });
+
+        let bodies: Vec<String> = results
+            .into_iter()
+            .map(|r| r.unwrap().text().unwrap())
+            .collect();
+        assert_eq!(bodies, ["/a", "/b", "/c"]);
+        assert_eq!(server.max_in_flight, 1);
+    }
+
+    #[test]
+    fn test_execute_negotiated() {
+        const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        const CLOSE: &[u8] = b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok";
+
+        let client = HttpClient::new();
+        let requests: Vec<HttpRequest> = ["/a", "/b", "/c"]
+            .iter()
+            .map(|path| HttpRequest::get(path).host("example.com"))
+            .collect();
+
+        // `h2` multiplexes over HTTP/2
+        let mut server = Http2EchoServer::new();
+        let bodies: Vec<String> = client
+            .execute_negotiated(&mut server, Some("h2"), &requests)
+            .into_iter()
+            .map(|r| r.unwrap().text().unwrap())
+            .collect();
+        assert_eq!(bodies, ["/a", "/b", "/c"]);
+
+        // Anything else is HTTP/1.1, one request after another until the
+        // server closes the connection
+        for alpn in [Some("http/1.1"), None] {
+            let mut conn = ScriptedConnection {
+                replies: alloc::vec![alloc::vec![OK], alloc::vec![CLOSE], alloc::vec![OK]].into(),
+                pending: Default::default(),
+                closed: false,
+            };
+            let results = client.execute_negotiated(&mut conn, alpn, &requests);
+            assert_eq!(results[0].as_ref().unwrap().text().unwrap(), "ok");
+            assert_eq!(results[1].as_ref().unwrap().text().unwrap(), "ok");
+            assert!(matches!(results[2], Err(HttpError::ConnectionClosed)));
+            assert_eq!(conn.replies.len(), 1);
+        }
+    }
 }
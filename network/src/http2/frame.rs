@@ -0,0 +1,621 @@
+//! HTTP/2 framing (RFC 9113 §4 and §6).
+//!
+//! Every frame starts with a 9-byte header: 24-bit payload length, type,
+//! flags, and a 31-bit stream identifier. [`FrameHeader::parse`] reads
+//! the header so the connection can check the length against
+//! `SETTINGS_MAX_FRAME_SIZE` before buffering the payload, and
+//! [`Frame::decode`] validates and decodes the payload.
+
+use alloc::vec::Vec;
+
+/// Length of the frame header.
+pub const FRAME_HEADER_LEN: usize = 9;
+
+/// Initial `SETTINGS_MAX_FRAME_SIZE`.
+pub const DEFAULT_MAX_FRAME_SIZE: u32 = 16_384;
+
+/// Largest value `SETTINGS_MAX_FRAME_SIZE` may take.
+pub const MAX_FRAME_SIZE_LIMIT: u32 = 16_777_215;
+
+/// Frame flags.
+pub mod flags {
+    /// DATA, HEADERS: last frame the endpoint sends on the stream.
+    pub const END_STREAM: u8 = 0x1;
+    /// SETTINGS, PING: acknowledgement.
+    pub const ACK: u8 = 0x1;
+    /// HEADERS, PUSH_PROMISE, CONTINUATION: header block is complete.
+    pub const END_HEADERS: u8 = 0x4;
+    /// DATA, HEADERS, PUSH_PROMISE: payload is padded.
+    pub const PADDED: u8 = 0x8;
+    /// HEADERS: priority fields are present.
+    pub const PRIORITY: u8 = 0x20;
+}
+
+/// SETTINGS parameter identifiers.
+pub mod setting {
+    pub const HEADER_TABLE_SIZE: u16 = 0x1;
+    pub const ENABLE_PUSH: u16 = 0x2;
+    pub const MAX_CONCURRENT_STREAMS: u16 = 0x3;
+    pub const INITIAL_WINDOW_SIZE: u16 = 0x4;
+    pub const MAX_FRAME_SIZE: u16 = 0x5;
+    pub const MAX_HEADER_LIST_SIZE: u16 = 0x6;
+}
+
+/// Frame types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FrameType {
+    Data = 0x0,
+    Headers = 0x1,
+    Priority = 0x2,
+    RstStream = 0x3,
+    Settings = 0x4,
+    PushPromise = 0x5,
+    Ping = 0x6,
+    GoAway = 0x7,
+    WindowUpdate = 0x8,
+    Continuation = 0x9,
+}
+
+impl FrameType {
+    /// Parse from byte. Unknown types must be ignored by the receiver.
+    pub fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            0x0 => Some(FrameType::Data),
+            0x1 => Some(FrameType::Headers),
+            0x2 => Some(FrameType::Priority),
+            0x3 => Some(FrameType::RstStream),
+            0x4 => Some(FrameType::Settings),
+            0x5 => Some(FrameType::PushPromise),
+            0x6 => Some(FrameType::Ping),
+            0x7 => Some(FrameType::GoAway),
+            0x8 => Some(FrameType::WindowUpdate),
+            0x9 => Some(FrameType::Continuation),
+            _ => None,
+        }
+    }
+}
+
+/// Error codes carried by RST_STREAM and GOAWAY.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    NoError,
+    ProtocolError,
+    InternalError,
+    FlowControlError,
+    SettingsTimeout,
+    StreamClosed,
+    FrameSizeError,
+    RefusedStream,
+    Cancel,
+    CompressionError,
+    ConnectError,
+    EnhanceYourCalm,
+    InadequateSecurity,
+    Http11Required,
+}
+
+impl ErrorCode {
+    /// Parse from the wire value. Unknown codes are treated as
+    /// `INTERNAL_ERROR`.
+    pub fn from_u32(code: u32) -> Self {
+        match code {
+            0x0 => ErrorCode::NoError,
+            0x1 => ErrorCode::ProtocolError,
+            0x3 => ErrorCode::FlowControlError,
+            0x4 => ErrorCode::SettingsTimeout,
+            0x5 => ErrorCode::StreamClosed,
+            0x6 => ErrorCode::FrameSizeError,
+            0x7 => ErrorCode::RefusedStream,
+            0x8 => ErrorCode::Cancel,
+            0x9 => ErrorCode::CompressionError,
+            0xa => ErrorCode::ConnectError,
+            0xb => ErrorCode::EnhanceYourCalm,
+            0xc => ErrorCode::InadequateSecurity,
+            0xd => ErrorCode::Http11Required,
+            _ => ErrorCode::InternalError,
+        }
+    }
+
+    /// Wire value.
+    pub fn to_u32(self) -> u32 {
+        match self {
+            ErrorCode::NoError => 0x0,
+            ErrorCode::ProtocolError => 0x1,
+            ErrorCode::InternalError => 0x2,
+            ErrorCode::FlowControlError => 0x3,
+            ErrorCode::SettingsTimeout => 0x4,
+            ErrorCode::StreamClosed => 0x5,
+            ErrorCode::FrameSizeError => 0x6,
+            ErrorCode::RefusedStream => 0x7,
+            ErrorCode::Cancel => 0x8,
+            ErrorCode::CompressionError => 0x9,
+            ErrorCode::ConnectError => 0xa,
+            ErrorCode::EnhanceYourCalm => 0xb,
+            ErrorCode::InadequateSecurity => 0xc,
+            ErrorCode::Http11Required => 0xd,
+        }
+    }
+}
+
+/// Frame header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Payload length.
+    pub length: u32,
+    /// Raw frame type.
+    pub frame_type: u8,
+    /// Flags.
+    pub flags: u8,
+    /// Stream identifier (reserved bit cleared).
+    pub stream_id: u32,
+}
+
+impl FrameHeader {
+    /// Parse a frame header from the start of `data`.
+    ///
+    /// Returns `None` if fewer than [`FRAME_HEADER_LEN`] bytes are available.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < FRAME_HEADER_LEN {
+            return None;
+        }
+        Some(Self {
+            length: u32::from_be_bytes([0, data[0], data[1], data[2]]),
+            frame_type: data[3],
+            flags: data[4],
+            stream_id: read_u31(&data[5..9]),
+        })
+    }
+
+    /// Serialize the header.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.length.to_be_bytes()[1..]);
+        out.push(self.frame_type);
+        out.push(self.flags);
+        out.extend_from_slice(&(self.stream_id & 0x7FFF_FFFF).to_be_bytes());
+    }
+
+    fn has(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+/// A decoded frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Data {
+        stream_id: u32,
+        data: Vec<u8>,
+        end_stream: bool,
+    },
+    Headers {
+        stream_id: u32,
+        block: Vec<u8>,
+        end_stream: bool,
+        end_headers: bool,
+    },
+    /// Stream prioritization is deprecated; the fields are not kept.
+    Priority {
+        stream_id: u32,
+    },
+    RstStream {
+        stream_id: u32,
+        error: ErrorCode,
+    },
+    Settings {
+        ack: bool,
+        params: Vec<(u16, u32)>,
+    },
+    PushPromise {
+        stream_id: u32,
+        promised_stream_id: u32,
+        block: Vec<u8>,
+        end_headers: bool,
+    },
+    Ping {
+        ack: bool,
+        data: [u8; 8],
+    },
+    GoAway {
+        last_stream_id: u32,
+        error: ErrorCode,
+        debug_data: Vec<u8>,
+    },
+    WindowUpdate {
+        stream_id: u32,
+        increment: u32,
+    },
+    Continuation {
+        stream_id: u32,
+        block: Vec<u8>,
+        end_headers: bool,
+    },
+    /// Frame of an unknown type, to be ignored.
+    Unknown {
+        frame_type: u8,
+        stream_id: u32,
+    },
+}
+
+impl Frame {
+    /// Decode a frame payload.
+    ///
+    /// Malformed frames produce the error code of the connection error
+    /// the receiver must raise.
+    pub fn decode(header: &FrameHeader, payload: &[u8]) -> Result<Frame, ErrorCode> {
+        let stream_id = header.stream_id;
+        let Some(frame_type) = FrameType::from_byte(header.frame_type) else {
+            return Ok(Frame::Unknown {
+                frame_type: header.frame_type,
+                stream_id,
+            });
+        };
+
+        // Frames that belong to a stream, and those that belong to the
+        // connection as a whole.
+        let on_stream = !matches!(
+            frame_type,
+            FrameType::Settings | FrameType::Ping | FrameType::GoAway | FrameType::WindowUpdate
+        );
+        if on_stream && stream_id == 0 {
+            return Err(ErrorCode::ProtocolError);
+        }
+        if matches!(
+            frame_type,
+            FrameType::Settings | FrameType::Ping | FrameType::GoAway
+        ) && stream_id != 0
+        {
+            return Err(ErrorCode::ProtocolError);
+        }
+
+        let frame = match frame_type {
+            FrameType::Data => Frame::Data {
+                stream_id,
+                data: strip_padding(header, payload)?.to_vec(),
+                end_stream: header.has(flags::END_STREAM),
+            },
+            FrameType::Headers => {
+                let mut block = strip_padding(header, payload)?;
+                if header.has(flags::PRIORITY) {
+                    block = block.get(5..).ok_or(ErrorCode::FrameSizeError)?;
+                }
+                Frame::Headers {
+                    stream_id,
+                    block: block.to_vec(),
+                    end_stream: header.has(flags::END_STREAM),
+                    end_headers: header.has(flags::END_HEADERS),
+                }
+            }
+            FrameType::Priority => {
+                expect_len(payload, 5)?;
+                Frame::Priority { stream_id }
+            }
+            FrameType::RstStream => {
+                expect_len(payload, 4)?;
+                Frame::RstStream {
+                    stream_id,
+                    error: ErrorCode::from_u32(read_u32(payload)),
+                }
+            }
+            FrameType::Settings => {
+                let ack = header.has(flags::ACK);
+                if (ack && !payload.is_empty()) || !payload.len().is_multiple_of(6) {
+                    return Err(ErrorCode::FrameSizeError);
+                }
+                let params = payload
+                    .chunks_exact(6)
+                    .map(|p| (u16::from_be_bytes([p[0], p[1]]), read_u32(&p[2..])))
+                    .collect();
+                Frame::Settings { ack, params }
+            }
+            FrameType::PushPromise => {
+                let rest = strip_padding(header, payload)?;
+                if rest.len() < 4 {
+                    return Err(ErrorCode::FrameSizeError);
+                }
+                Frame::PushPromise {
+                    stream_id,
+                    promised_stream_id: read_u31(rest),
+                    block: rest[4..].to_vec(),
+                    end_headers: header.has(flags::END_HEADERS),
+                }
+            }
+            FrameType::Ping => {
+                expect_len(payload, 8)?;
+                let mut data = [0u8; 8];
+                data.copy_from_slice(payload);
+                Frame::Ping {
+                    ack: header.has(flags::ACK),
+                    data,
+                }
+            }
+            FrameType::GoAway => {
+                if payload.len() < 8 {
+                    return Err(ErrorCode::FrameSizeError);
+                }
+                Frame::GoAway {
+                    last_stream_id: read_u31(payload),
+                    error: ErrorCode::from_u32(read_u32(&payload[4..])),
+                    debug_data: payload[8..].to_vec(),
+                }
+            }
+            FrameType::WindowUpdate => {
+                expect_len(payload, 4)?;
+                Frame::WindowUpdate {
+                    stream_id,
+                    increment: read_u31(payload),
+                }
+            }
+            FrameType::Continuation => Frame::Continuation {
+                stream_id,
+                block: payload.to_vec(),
+                end_headers: header.has(flags::END_HEADERS),
+            },
+        };
+        Ok(frame)
+    }
+
+    /// Serialize the frame (without padding or priority fields).
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let mut payload = Vec::new();
+        let (frame_type, flag_bits, stream_id) = match self {
+            Frame::Data {
+                stream_id,
+                data,
+                end_stream,
+            } => {
+                payload.extend_from_slice(data);
+                (
+                    FrameType::Data,
+                    flag(*end_stream, flags::END_STREAM),
+                    *stream_id,
+                )
+            }
+            Frame::Headers {
+                stream_id,
+                block,
+                end_stream,
+                end_headers,
+            } => {
+                payload.extend_from_slice(block);
+                let bits =
+                    flag(*end_stream, flags::END_STREAM) | flag(*end_headers, flags::END_HEADERS);
+                (FrameType::Headers, bits, *stream_id)
+            }
+            Frame::Priority { stream_id } => {
+                // Default priority: no dependency, weight 16
+                payload.extend_from_slice(&[0, 0, 0, 0, 15]);
+                (FrameType::Priority, 0, *stream_id)
+            }
+            Frame::RstStream { stream_id, error } => {
+                payload.extend_from_slice(&error.to_u32().to_be_bytes());
+                (FrameType::RstStream, 0, *stream_id)
+            }
+            Frame::Settings { ack, params } => {
+                for (id, value) in params {
+                    payload.extend_from_slice(&id.to_be_bytes());
+                    payload.extend_from_slice(&value.to_be_bytes());
+                }
+                (FrameType::Settings, flag(*ack, flags::ACK), 0)
+            }
+            Frame::PushPromise {
+                stream_id,
+                promised_stream_id,
+                block,
+                end_headers,
+            } => {
+                payload.extend_from_slice(&promised_stream_id.to_be_bytes());
+                payload.extend_from_slice(block);
+                let bits = flag(*end_headers, flags::END_HEADERS);
+                (FrameType::PushPromise, bits, *stream_id)
+            }
+            Frame::Ping { ack, data } => {
+                payload.extend_from_slice(data);
+                (FrameType::Ping, flag(*ack, flags::ACK), 0)
+            }
+            Frame::GoAway {
+                last_stream_id,
+                error,
+                debug_data,
+            } => {
+                payload.extend_from_slice(&last_stream_id.to_be_bytes());
+                payload.extend_from_slice(&error.to_u32().to_be_bytes());
+                payload.extend_from_slice(debug_data);
+                (FrameType::GoAway, 0, 0)
+            }
+            Frame::WindowUpdate {
+                stream_id,
+                increment,
+            } => {
+                payload.extend_from_slice(&increment.to_be_bytes());
+                (FrameType::WindowUpdate, 0, *stream_id)
+            }
+            Frame::Continuation {
+                stream_id,
+                block,
+                end_headers,
+            } => {
+                payload.extend_from_slice(block);
+                let bits = flag(*end_headers, flags::END_HEADERS);
+                (FrameType::Continuation, bits, *stream_id)
+            }
+            Frame::Unknown { .. } => return,
+        };
+
+        FrameHeader {
+            length: payload.len() as u32,
+            frame_type: frame_type as u8,
+            flags: flag_bits,
+            stream_id,
+        }
+        .encode(out);
+        out.extend_from_slice(&payload);
+    }
+}
+
+fn flag(set: bool, bit: u8) -> u8 {
+    if set {
+        bit
+    } else {
+        0
+    }
+}
+
+fn expect_len(payload: &[u8], len: usize) -> Result<(), ErrorCode> {
+    if payload.len() == len {
+        Ok(())
+    } else {
+        Err(ErrorCode::FrameSizeError)
+    }
+}
+
+/// Remove the pad length octet and trailing padding from a PADDED frame.
+fn strip_padding<'a>(header: &FrameHeader, payload: &'a [u8]) -> Result<&'a [u8], ErrorCode> {
+    if !header.has(flags::PADDED) {
+        return Ok(payload);
+    }
+    let (&pad_len, rest) = payload.split_first().ok_or(ErrorCode::FrameSizeError)?;
+    let pad_len = pad_len as usize;
+    if pad_len > rest.len() {
+        return Err(ErrorCode::ProtocolError);
+    }
+    Ok(&rest[..rest.len() - pad_len])
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn read_u31(data: &[u8]) -> u32 {
+    read_u32(data) & 0x7FFF_FFFF
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn roundtrip(frame: Frame) -> Frame {
+        let mut bytes = Vec::new();
+        frame.encode(&mut bytes);
+        let header = FrameHeader::parse(&bytes).unwrap();
+        assert_eq!(header.length as usize, bytes.len() - FRAME_HEADER_LEN);
+        Frame::decode(&header, &bytes[FRAME_HEADER_LEN..]).unwrap()
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let frames = [
+            Frame::Data {
+                stream_id: 1,
+                data: vec![1, 2, 3],
+                end_stream: true,
+            },
+            Frame::Headers {
+                stream_id: 3,
+                block: vec![0x82],
+                end_stream: false,
+                end_headers: true,
+            },
+            Frame::Settings {
+                ack: false,
+                params: vec![
+                    (setting::ENABLE_PUSH, 0),
+                    (setting::INITIAL_WINDOW_SIZE, 1 << 20),
+                ],
+            },
+            Frame::Ping {
+                ack: true,
+                data: [1, 2, 3, 4, 5, 6, 7, 8],
+            },
+            Frame::GoAway {
+                last_stream_id: 7,
+                error: ErrorCode::EnhanceYourCalm,
+                debug_data: b"slow down".to_vec(),
+            },
+            Frame::WindowUpdate {
+                stream_id: 0,
+                increment: 65_535,
+            },
+            Frame::RstStream {
+                stream_id: 5,
+                error: ErrorCode::Cancel,
+            },
+        ];
+        for frame in frames {
+            assert_eq!(roundtrip(frame.clone()), frame);
+        }
+    }
+
+    #[test]
+    fn test_padded_headers_with_priority() {
+        let header = FrameHeader {
+            length: 10,
+            frame_type: FrameType::Headers as u8,
+            flags: flags::PADDED | flags::PRIORITY | flags::END_HEADERS,
+            stream_id: 1,
+        };
+        // Pad length 2, priority (5 bytes), block [0x82, 0x84], padding
+        let payload = [2, 0, 0, 0, 0, 15, 0x82, 0x84, 0, 0];
+        assert_eq!(
+            Frame::decode(&header, &payload),
+            Ok(Frame::Headers {
+                stream_id: 1,
+                block: vec![0x82, 0x84],
+                end_stream: false,
+                end_headers: true,
+            })
+        );
+
+        // Padding longer than the payload
+        let payload = [9, 0x82];
+        let header = FrameHeader {
+            length: 2,
+            flags: flags::PADDED,
+            ..header
+        };
+        assert_eq!(
+            Frame::decode(&header, &payload),
+            Err(ErrorCode::ProtocolError)
+        );
+    }
+
+    #[test]
+    fn test_invalid_frames() {
+        let header = |frame_type: FrameType, stream_id: u32, length: u32| FrameHeader {
+            length,
+            frame_type: frame_type as u8,
+            flags: 0,
+            stream_id,
+        };
+        assert_eq!(
+            Frame::decode(&header(FrameType::Data, 0, 0), &[]),
+            Err(ErrorCode::ProtocolError)
+        );
+        assert_eq!(
+            Frame::decode(&header(FrameType::Settings, 1, 0), &[]),
+            Err(ErrorCode::ProtocolError)
+        );
+        assert_eq!(
+            Frame::decode(&header(FrameType::Settings, 0, 5), &[0; 5]),
+            Err(ErrorCode::FrameSizeError)
+        );
+        assert_eq!(
+            Frame::decode(&header(FrameType::Ping, 0, 4), &[0; 4]),
+            Err(ErrorCode::FrameSizeError)
+        );
+        assert_eq!(
+            Frame::decode(
+                &FrameHeader {
+                    frame_type: 0xFA,
+                    ..header(FrameType::Data, 3, 0)
+                },
+                &[]
+            ),
+            Ok(Frame::Unknown {
+                frame_type: 0xFA,
+                stream_id: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,957 @@
+//! HPACK header compression (RFC 7541).
+//!
+//! Each direction of an HTTP/2 connection has its own compression
+//! context: the [`Encoder`] on the sending side and the [`Decoder`] on
+//! the receiving side keep matching dynamic tables, so the tables must
+//! see every header block in order.
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// HPACK decoding errors.
+///
+/// All of them are connection errors of type `COMPRESSION_ERROR`: once a
+/// header block fails to decode the two dynamic tables are out of sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HpackError {
+    /// Header block ended in the middle of a representation.
+    Truncated,
+    /// Integer does not fit in 32 bits.
+    IntegerOverflow,
+    /// Index refers to no static or dynamic table entry.
+    InvalidIndex(usize),
+    /// Huffman-coded string is malformed.
+    InvalidHuffman,
+    /// Dynamic table size update above the negotiated limit.
+    TableSizeExceeded(usize),
+    /// Dynamic table size update after the first header field.
+    UnexpectedSizeUpdate,
+}
+
+/// Per-entry overhead counted towards the dynamic table size.
+const ENTRY_OVERHEAD: usize = 32;
+
+/// Default dynamic table size (`SETTINGS_HEADER_TABLE_SIZE`).
+pub const DEFAULT_TABLE_SIZE: usize = 4096;
+
+/// Static table (RFC 7541 Appendix A); index 1 is the first entry.
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Headers that are never added to the dynamic table, so their values
+/// cannot be probed through compression side channels.
+const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
+/// Dynamic table shared by the encoder and decoder implementations.
+#[derive(Debug, Clone)]
+struct DynamicTable {
+    /// Newest entry first.
+    entries: VecDeque<(String, String)>,
+    /// Sum of entry sizes.
+    size: usize,
+    /// Current maximum size.
+    max_size: usize,
+}
+
+impl DynamicTable {
+    fn new(max_size: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            size: 0,
+            max_size,
+        }
+    }
+
+    fn entry_size(name: &str, value: &str) -> usize {
+        name.len() + value.len() + ENTRY_OVERHEAD
+    }
+
+    /// Look up an entry by its HPACK index (static entries first).
+    fn get(&self, index: usize) -> Option<(&str, &str)> {
+        if index == 0 {
+            return None;
+        }
+        if index <= STATIC_TABLE.len() {
+            return Some(STATIC_TABLE[index - 1]);
+        }
+        self.entries
+            .get(index - STATIC_TABLE.len() - 1)
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Insert an entry, evicting the oldest ones to make room.
+    ///
+    /// An entry larger than the table empties it and is not stored.
+    fn insert(&mut self, name: String, value: String) {
+        let size = Self::entry_size(&name, &value);
+        while self.size + size > self.max_size {
+            if !self.evict() {
+                break;
+            }
+        }
+        if size <= self.max_size {
+            self.size += size;
+            self.entries.push_front((name, value));
+        }
+    }
+
+    fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        while self.size > self.max_size {
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) -> bool {
+        match self.entries.pop_back() {
+            Some((name, value)) => {
+                self.size -= Self::entry_size(&name, &value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Find `(index, value_matches)` for the best entry for a header.
+    fn find(&self, name: &str, value: &str) -> Option<(usize, bool)> {
+        let mut name_match = None;
+        let statics = STATIC_TABLE.iter().copied();
+        let dynamics = self.entries.iter().map(|(n, v)| (n.as_str(), v.as_str()));
+        for (i, (n, v)) in statics.chain(dynamics).enumerate() {
+            if n == name {
+                if v == value {
+                    return Some((i + 1, true));
+                }
+                name_match.get_or_insert(i + 1);
+            }
+        }
+        name_match.map(|i| (i, false))
+    }
+}
+
+/// Header block decoder.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    table: DynamicTable,
+    /// Largest table size the peer may select (our SETTINGS_HEADER_TABLE_SIZE).
+    max_table_size: usize,
+}
+
+impl Decoder {
+    /// Create a decoder with the default table size.
+    pub fn new() -> Self {
+        Self {
+            table: DynamicTable::new(DEFAULT_TABLE_SIZE),
+            max_table_size: DEFAULT_TABLE_SIZE,
+        }
+    }
+
+    /// Current dynamic table size in bytes.
+    pub fn table_size(&self) -> usize {
+        self.table.size
+    }
+
+    /// Decode a complete header block into `(name, value)` pairs.
+    pub fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>, HpackError> {
+        let mut headers = Vec::new();
+        let mut pos = 0;
+
+        while pos < block.len() {
+            let b = block[pos];
+            if b & 0x80 != 0 {
+                // Indexed header field
+                let index = decode_integer(block, &mut pos, 7)?;
+                let (name, value) = self
+                    .table
+                    .get(index)
+                    .ok_or(HpackError::InvalidIndex(index))?;
+                headers.push((name.to_string(), value.to_string()));
+            } else if b & 0xC0 == 0x40 {
+                // Literal with incremental indexing
+                let (name, value) = self.decode_literal(block, &mut pos, 6)?;
+                self.table.insert(name.clone(), value.clone());
+                headers.push((name, value));
+            } else if b & 0xE0 == 0x20 {
+                // Dynamic table size update, only allowed before any field
+                if !headers.is_empty() {
+                    return Err(HpackError::UnexpectedSizeUpdate);
+                }
+                let size = decode_integer(block, &mut pos, 5)?;
+                if size > self.max_table_size {
+                    return Err(HpackError::TableSizeExceeded(size));
+                }
+                self.table.set_max_size(size);
+            } else {
+                // Literal without indexing (0000) or never indexed (0001)
+                headers.push(self.decode_literal(block, &mut pos, 4)?);
+            }
+        }
+
+        Ok(headers)
+    }
+
+    fn decode_literal(
+        &self,
+        block: &[u8],
+        pos: &mut usize,
+        prefix: u8,
+    ) -> Result<(String, String), HpackError> {
+        let index = decode_integer(block, pos, prefix)?;
+        let name = if index == 0 {
+            decode_string(block, pos)?
+        } else {
+            let (name, _) = self
+                .table
+                .get(index)
+                .ok_or(HpackError::InvalidIndex(index))?;
+            name.to_string()
+        };
+        let value = decode_string(block, pos)?;
+        Ok((name, value))
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Header block encoder.
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    table: DynamicTable,
+    /// Size update to announce at the start of the next header block.
+    pending_size_update: Option<usize>,
+}
+
+impl Encoder {
+    /// Create an encoder with the default table size.
+    pub fn new() -> Self {
+        Self {
+            table: DynamicTable::new(DEFAULT_TABLE_SIZE),
+            pending_size_update: None,
+        }
+    }
+
+    /// Current dynamic table size in bytes.
+    pub fn table_size(&self) -> usize {
+        self.table.size
+    }
+
+    /// Apply the peer's `SETTINGS_HEADER_TABLE_SIZE`.
+    ///
+    /// The new size is announced at the start of the next header block.
+    pub fn set_max_table_size(&mut self, size: usize) {
+        let size = size.min(DEFAULT_TABLE_SIZE);
+        if size != self.table.max_size {
+            self.table.set_max_size(size);
+            self.pending_size_update = Some(size);
+        }
+    }
+
+    /// Encode a header list into a header block.
+    ///
+    /// Names must already be lower-case, as HTTP/2 requires.
+    pub fn encode(&mut self, headers: &[(String, String)]) -> Vec<u8> {
+        let mut block = Vec::new();
+
+        if let Some(size) = self.pending_size_update.take() {
+            encode_integer(&mut block, 0x20, 5, size);
+        }
+
+        for (name, value) in headers {
+            let sensitive = SENSITIVE_HEADERS.contains(&name.as_str());
+            match self.table.find(name, value) {
+                Some((index, true)) if !sensitive => {
+                    encode_integer(&mut block, 0x80, 7, index);
+                }
+                found => {
+                    let name_index = found.map(|(i, _)| i).unwrap_or(0);
+                    if sensitive {
+                        // Never indexed
+                        encode_integer(&mut block, 0x10, 4, name_index);
+                    } else {
+                        // Incremental indexing
+                        encode_integer(&mut block, 0x40, 6, name_index);
+                    }
+                    if name_index == 0 {
+                        encode_string(&mut block, name);
+                    }
+                    encode_string(&mut block, value);
+                    if !sensitive {
+                        self.table.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        block
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encode an integer with an `prefix`-bit prefix; `flags` holds the
+/// representation's high bits.
+fn encode_integer(out: &mut Vec<u8>, flags: u8, prefix: u8, value: usize) {
+    let max = (1usize << prefix) - 1;
+    if value < max {
+        out.push(flags | value as u8);
+        return;
+    }
+    out.push(flags | max as u8);
+    let mut rest = value - max;
+    while rest >= 0x80 {
+        out.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    out.push(rest as u8);
+}
+
+/// Decode an integer with an `prefix`-bit prefix.
+fn decode_integer(data: &[u8], pos: &mut usize, prefix: u8) -> Result<usize, HpackError> {
+    let max = (1usize << prefix) - 1;
+    let first = *data.get(*pos).ok_or(HpackError::Truncated)?;
+    *pos += 1;
+
+    let mut value = first as usize & max;
+    if value < max {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+    loop {
+        let b = *data.get(*pos).ok_or(HpackError::Truncated)?;
+        *pos += 1;
+        if shift > 28 {
+            return Err(HpackError::IntegerOverflow);
+        }
+        value += ((b & 0x7F) as usize) << shift;
+        if value > u32::MAX as usize {
+            return Err(HpackError::IntegerOverflow);
+        }
+        shift += 7;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Encode a string literal, Huffman-coded when that is shorter.
+fn encode_string(out: &mut Vec<u8>, s: &str) {
+    let huffman_len = huffman_encoded_len(s.as_bytes());
+    if huffman_len < s.len() {
+        encode_integer(out, 0x80, 7, huffman_len);
+        huffman_encode(out, s.as_bytes());
+    } else {
+        encode_integer(out, 0x00, 7, s.len());
+        out.extend_from_slice(s.as_bytes());
+    }
+}
+
+/// Decode a string literal.
+fn decode_string(data: &[u8], pos: &mut usize) -> Result<String, HpackError> {
+    let huffman = *data.get(*pos).ok_or(HpackError::Truncated)? & 0x80 != 0;
+    let len = decode_integer(data, pos, 7)?;
+    let end = pos.checked_add(len).ok_or(HpackError::Truncated)?;
+    let raw = data.get(*pos..end).ok_or(HpackError::Truncated)?;
+    *pos = end;
+
+    let bytes = if huffman {
+        huffman_decode(raw)?
+    } else {
+        raw.to_vec()
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn huffman_encoded_len(data: &[u8]) -> usize {
+    let bits: usize = data
+        .iter()
+        .map(|&b| HUFFMAN_CODES[b as usize].1 as usize)
+        .sum();
+    bits.div_ceil(8)
+}
+
+fn huffman_encode(out: &mut Vec<u8>, data: &[u8]) {
+    let mut acc: u64 = 0;
+    let mut bits = 0u32;
+    for &b in data {
+        let (code, len) = HUFFMAN_CODES[b as usize];
+        acc = (acc << len) | code as u64;
+        bits += len as u32;
+        while bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits > 0 {
+        // Pad with the most significant bits of EOS (all ones)
+        out.push(((acc << (8 - bits)) as u8) | (0xFF >> bits));
+    }
+}
+
+fn huffman_decode(data: &[u8]) -> Result<Vec<u8>, HpackError> {
+    let mut out = Vec::with_capacity(data.len() * 8 / 5);
+    // Canonical decoding: `code` holds the bits read for the current
+    // symbol, `first` the first code of length `len` and `offset` the
+    // index of that code in HUFFMAN_SYMBOLS.
+    let mut code = 0u32;
+    let mut len = 0usize;
+    let mut first = 0u32;
+    let mut offset = 0usize;
+    // Padding must be all ones: track whether the pending bits are.
+    let mut all_ones = true;
+
+    for &byte in data {
+        for shift in (0..8).rev() {
+            let bit = (byte >> shift) as u32 & 1;
+            code = (code << 1) | bit;
+            first <<= 1;
+            len += 1;
+            all_ones &= bit == 1;
+
+            let count = HUFFMAN_LENGTH_COUNTS.get(len).copied().unwrap_or(0) as u32;
+            if code.wrapping_sub(first) < count {
+                let symbol = HUFFMAN_SYMBOLS[offset + (code - first) as usize];
+                if symbol == 256 {
+                    // EOS in the string is an error
+                    return Err(HpackError::InvalidHuffman);
+                }
+                out.push(symbol as u8);
+                code = 0;
+                len = 0;
+                first = 0;
+                offset = 0;
+                all_ones = true;
+            } else {
+                if len >= HUFFMAN_LENGTH_COUNTS.len() - 1 {
+                    return Err(HpackError::InvalidHuffman);
+                }
+                first += count;
+                offset += count as usize;
+            }
+        }
+    }
+
+    // At most 7 bits of EOS prefix may pad the final octet
+    if len > 7 || !all_ones {
+        return Err(HpackError::InvalidHuffman);
+    }
+    Ok(out)
+}
+
+/// Huffman code for each symbol (RFC 7541 Appendix B): `(code, bit length)`.
+/// Symbol 256 is EOS.
+const HUFFMAN_CODES: [(u32, u8); 257] = [
+    (0x1ff8, 13),
+    (0x7fffd8, 23),
+    (0xfffffe2, 28),
+    (0xfffffe3, 28),
+    (0xfffffe4, 28),
+    (0xfffffe5, 28),
+    (0xfffffe6, 28),
+    (0xfffffe7, 28),
+    (0xfffffe8, 28),
+    (0xffffea, 24),
+    (0x3ffffffc, 30),
+    (0xfffffe9, 28),
+    (0xfffffea, 28),
+    (0x3ffffffd, 30),
+    (0xfffffeb, 28),
+    (0xfffffec, 28),
+    (0xfffffed, 28),
+    (0xfffffee, 28),
+    (0xfffffef, 28),
+    (0xffffff0, 28),
+    (0xffffff1, 28),
+    (0xffffff2, 28),
+    (0x3ffffffe, 30),
+    (0xffffff3, 28),
+    (0xffffff4, 28),
+    (0xffffff5, 28),
+    (0xffffff6, 28),
+    (0xffffff7, 28),
+    (0xffffff8, 28),
+    (0xffffff9, 28),
+    (0xffffffa, 28),
+    (0xffffffb, 28),
+    (0x14, 6),
+    (0x3f8, 10),
+    (0x3f9, 10),
+    (0xffa, 12),
+    (0x1ff9, 13),
+    (0x15, 6),
+    (0xf8, 8),
+    (0x7fa, 11),
+    (0x3fa, 10),
+    (0x3fb, 10),
+    (0xf9, 8),
+    (0x7fb, 11),
+    (0xfa, 8),
+    (0x16, 6),
+    (0x17, 6),
+    (0x18, 6),
+    (0x0, 5),
+    (0x1, 5),
+    (0x2, 5),
+    (0x19, 6),
+    (0x1a, 6),
+    (0x1b, 6),
+    (0x1c, 6),
+    (0x1d, 6),
+    (0x1e, 6),
+    (0x1f, 6),
+    (0x5c, 7),
+    (0xfb, 8),
+    (0x7ffc, 15),
+    (0x20, 6),
+    (0xffb, 12),
+    (0x3fc, 10),
+    (0x1ffa, 13),
+    (0x21, 6),
+    (0x5d, 7),
+    (0x5e, 7),
+    (0x5f, 7),
+    (0x60, 7),
+    (0x61, 7),
+    (0x62, 7),
+    (0x63, 7),
+    (0x64, 7),
+    (0x65, 7),
+    (0x66, 7),
+    (0x67, 7),
+    (0x68, 7),
+    (0x69, 7),
+    (0x6a, 7),
+    (0x6b, 7),
+    (0x6c, 7),
+    (0x6d, 7),
+    (0x6e, 7),
+    (0x6f, 7),
+    (0x70, 7),
+    (0x71, 7),
+    (0x72, 7),
+    (0xfc, 8),
+    (0x73, 7),
+    (0xfd, 8),
+    (0x1ffb, 13),
+    (0x7fff0, 19),
+    (0x1ffc, 13),
+    (0x3ffc, 14),
+    (0x22, 6),
+    (0x7ffd, 15),
+    (0x3, 5),
+    (0x23, 6),
+    (0x4, 5),
+    (0x24, 6),
+    (0x5, 5),
+    (0x25, 6),
+    (0x26, 6),
+    (0x27, 6),
+    (0x6, 5),
+    (0x74, 7),
+    (0x75, 7),
+    (0x28, 6),
+    (0x29, 6),
+    (0x2a, 6),
+    (0x7, 5),
+    (0x2b, 6),
+    (0x76, 7),
+    (0x2c, 6),
+    (0x8, 5),
+    (0x9, 5),
+    (0x2d, 6),
+    (0x77, 7),
+    (0x78, 7),
+    (0x79, 7),
+    (0x7a, 7),
+    (0x7b, 7),
+    (0x7ffe, 15),
+    (0x7fc, 11),
+    (0x3ffd, 14),
+    (0x1ffd, 13),
+    (0xffffffc, 28),
+    (0xfffe6, 20),
+    (0x3fffd2, 22),
+    (0xfffe7, 20),
+    (0xfffe8, 20),
+    (0x3fffd3, 22),
+    (0x3fffd4, 22),
+    (0x3fffd5, 22),
+    (0x7fffd9, 23),
+    (0x3fffd6, 22),
+    (0x7fffda, 23),
+    (0x7fffdb, 23),
+    (0x7fffdc, 23),
+    (0x7fffdd, 23),
+    (0x7fffde, 23),
+    (0xffffeb, 24),
+    (0x7fffdf, 23),
+    (0xffffec, 24),
+    (0xffffed, 24),
+    (0x3fffd7, 22),
+    (0x7fffe0, 23),
+    (0xffffee, 24),
+    (0x7fffe1, 23),
+    (0x7fffe2, 23),
+    (0x7fffe3, 23),
+    (0x7fffe4, 23),
+    (0x1fffdc, 21),
+    (0x3fffd8, 22),
+    (0x7fffe5, 23),
+    (0x3fffd9, 22),
+    (0x7fffe6, 23),
+    (0x7fffe7, 23),
+    (0xffffef, 24),
+    (0x3fffda, 22),
+    (0x1fffdd, 21),
+    (0xfffe9, 20),
+    (0x3fffdb, 22),
+    (0x3fffdc, 22),
+    (0x7fffe8, 23),
+    (0x7fffe9, 23),
+    (0x1fffde, 21),
+    (0x7fffea, 23),
+    (0x3fffdd, 22),
+    (0x3fffde, 22),
+    (0xfffff0, 24),
+    (0x1fffdf, 21),
+    (0x3fffdf, 22),
+    (0x7fffeb, 23),
+    (0x7fffec, 23),
+    (0x1fffe0, 21),
+    (0x1fffe1, 21),
+    (0x3fffe0, 22),
+    (0x1fffe2, 21),
+    (0x7fffed, 23),
+    (0x3fffe1, 22),
+    (0x7fffee, 23),
+    (0x7fffef, 23),
+    (0xfffea, 20),
+    (0x3fffe2, 22),
+    (0x3fffe3, 22),
+    (0x3fffe4, 22),
+    (0x7ffff0, 23),
+    (0x3fffe5, 22),
+    (0x3fffe6, 22),
+    (0x7ffff1, 23),
+    (0x3ffffe0, 26),
+    (0x3ffffe1, 26),
+    (0xfffeb, 20),
+    (0x7fff1, 19),
+    (0x3fffe7, 22),
+    (0x7ffff2, 23),
+    (0x3fffe8, 22),
+    (0x1ffffec, 25),
+    (0x3ffffe2, 26),
+    (0x3ffffe3, 26),
+    (0x3ffffe4, 26),
+    (0x7ffffde, 27),
+    (0x7ffffdf, 27),
+    (0x3ffffe5, 26),
+    (0xfffff1, 24),
+    (0x1ffffed, 25),
+    (0x7fff2, 19),
+    (0x1fffe3, 21),
+    (0x3ffffe6, 26),
+    (0x7ffffe0, 27),
+    (0x7ffffe1, 27),
+    (0x3ffffe7, 26),
+    (0x7ffffe2, 27),
+    (0xfffff2, 24),
+    (0x1fffe4, 21),
+    (0x1fffe5, 21),
+    (0x3ffffe8, 26),
+    (0x3ffffe9, 26),
+    (0xffffffd, 28),
+    (0x7ffffe3, 27),
+    (0x7ffffe4, 27),
+    (0x7ffffe5, 27),
+    (0xfffec, 20),
+    (0xfffff3, 24),
+    (0xfffed, 20),
+    (0x1fffe6, 21),
+    (0x3fffe9, 22),
+    (0x1fffe7, 21),
+    (0x1fffe8, 21),
+    (0x7ffff3, 23),
+    (0x3fffea, 22),
+    (0x3fffeb, 22),
+    (0x1ffffee, 25),
+    (0x1ffffef, 25),
+    (0xfffff4, 24),
+    (0xfffff5, 24),
+    (0x3ffffea, 26),
+    (0x7ffff4, 23),
+    (0x3ffffeb, 26),
+    (0x7ffffe6, 27),
+    (0x3ffffec, 26),
+    (0x3ffffed, 26),
+    (0x7ffffe7, 27),
+    (0x7ffffe8, 27),
+    (0x7ffffe9, 27),
+    (0x7ffffea, 27),
+    (0x7ffffeb, 27),
+    (0xffffffe, 28),
+    (0x7ffffec, 27),
+    (0x7ffffed, 27),
+    (0x7ffffee, 27),
+    (0x7ffffef, 27),
+    (0x7fffff0, 27),
+    (0x3ffffee, 26),
+    (0x3fffffff, 30),
+];
+
+/// Symbols ordered by `(bit length, code)`; the code is canonical, so
+/// decoding only needs this order and the number of codes of each length.
+const HUFFMAN_SYMBOLS: [u16; 257] = [
+    48, 49, 50, 97, 99, 101, 105, 111, 115, 116, 32, 37, 45, 46, 47, 51, 52, 53, 54, 55, 56, 57,
+    61, 65, 95, 98, 100, 102, 103, 104, 108, 109, 110, 112, 114, 117, 58, 66, 67, 68, 69, 70, 71,
+    72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 89, 106, 107, 113, 118, 119,
+    120, 121, 122, 38, 42, 44, 59, 88, 90, 33, 34, 40, 41, 63, 39, 43, 124, 35, 62, 0, 36, 64, 91,
+    93, 126, 94, 125, 60, 96, 123, 92, 195, 208, 128, 130, 131, 162, 184, 194, 224, 226, 153, 161,
+    167, 172, 176, 177, 179, 209, 216, 217, 227, 229, 230, 129, 132, 133, 134, 136, 146, 154, 156,
+    160, 163, 164, 169, 170, 173, 178, 181, 185, 186, 187, 189, 190, 196, 198, 228, 232, 233, 1,
+    135, 137, 138, 139, 140, 141, 143, 147, 149, 150, 151, 152, 155, 157, 158, 165, 166, 168, 174,
+    175, 180, 182, 183, 188, 191, 197, 231, 239, 9, 142, 144, 145, 148, 159, 171, 206, 215, 225,
+    236, 237, 199, 207, 234, 235, 192, 193, 200, 201, 202, 205, 210, 213, 218, 219, 238, 240, 242,
+    243, 255, 203, 204, 211, 212, 214, 221, 222, 223, 241, 244, 245, 246, 247, 248, 250, 251, 252,
+    253, 254, 2, 3, 4, 5, 6, 7, 8, 11, 12, 14, 15, 16, 17, 18, 19, 20, 21, 23, 24, 25, 26, 27, 28,
+    29, 30, 31, 127, 220, 249, 10, 13, 22, 256,
+];
+
+/// Number of codes of each bit length (index = length).
+const HUFFMAN_LENGTH_COUNTS: [u16; 31] = [
+    0, 0, 0, 0, 0, 10, 26, 32, 6, 0, 5, 3, 2, 6, 2, 3, 0, 0, 0, 3, 8, 13, 26, 29, 12, 4, 15, 19,
+    29, 0, 4,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(s: &str) -> Vec<u8> {
+        let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        digits
+            .chunks(2)
+            .map(|p| u8::from_str_radix(core::str::from_utf8(p).unwrap(), 16).unwrap())
+            .collect()
+    }
+
+    fn headers(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_integer_coding() {
+        // RFC 7541 C.1: 1337 with a 5-bit prefix
+        let mut out = Vec::new();
+        encode_integer(&mut out, 0, 5, 1337);
+        assert_eq!(out, [0x1F, 0x9A, 0x0A]);
+        let mut pos = 0;
+        assert_eq!(decode_integer(&out, &mut pos, 5), Ok(1337));
+        assert_eq!(pos, 3);
+
+        let mut pos = 0;
+        assert_eq!(
+            decode_integer(&[0x1F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], &mut pos, 5),
+            Err(HpackError::IntegerOverflow)
+        );
+    }
+
+    #[test]
+    fn test_huffman_roundtrip() {
+        let mut out = Vec::new();
+        huffman_encode(&mut out, b"www.example.com");
+        assert_eq!(out, unhex("f1e3 c2e5 f23a 6ba0 ab90 f4ff"));
+        assert_eq!(huffman_decode(&out).unwrap(), b"www.example.com");
+
+        let all: Vec<u8> = (0..=255).collect();
+        let mut out = Vec::new();
+        huffman_encode(&mut out, &all);
+        assert_eq!(huffman_decode(&out).unwrap(), all);
+
+        // Padding longer than 7 bits, or not all ones, is rejected
+        assert_eq!(
+            huffman_decode(&[0xFF, 0xFF]),
+            Err(HpackError::InvalidHuffman)
+        );
+        assert_eq!(huffman_decode(&[0x00]), Err(HpackError::InvalidHuffman));
+    }
+
+    #[test]
+    fn test_encode_requests_rfc_c4() {
+        let mut encoder = Encoder::new();
+        let mut decoder = Decoder::new();
+
+        let requests = [
+            (
+                headers(&[
+                    (":method", "GET"),
+                    (":scheme", "http"),
+                    (":path", "/"),
+                    (":authority", "www.example.com"),
+                ]),
+                "8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff",
+                57,
+            ),
+            (
+                headers(&[
+                    (":method", "GET"),
+                    (":scheme", "http"),
+                    (":path", "/"),
+                    (":authority", "www.example.com"),
+                    ("cache-control", "no-cache"),
+                ]),
+                "8286 84be 5886 a8eb 1064 9cbf",
+                110,
+            ),
+            (
+                headers(&[
+                    (":method", "GET"),
+                    (":scheme", "https"),
+                    (":path", "/index.html"),
+                    (":authority", "www.example.com"),
+                    ("custom-key", "custom-value"),
+                ]),
+                "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+                164,
+            ),
+        ];
+
+        for (list, expected, size) in requests {
+            let block = encoder.encode(&list);
+            assert_eq!(block, unhex(expected));
+            assert_eq!(encoder.table_size(), size);
+            assert_eq!(decoder.decode(&block).unwrap(), list);
+            assert_eq!(decoder.table_size(), size);
+        }
+    }
+
+    #[test]
+    fn test_decode_responses_with_eviction_rfc_c6() {
+        let mut decoder = Decoder::new();
+        decoder.table.set_max_size(256);
+
+        let block = unhex(
+            "4882 6402 5885 aec3 771a 4b61 96d0 7abe 9410 54d4 44a8 2005 9504 0b81 66e0 82a6
+             2d1b ff6e 919d 29ad 1718 63c7 8f0b 97c8 e9ae 82ae 43d3",
+        );
+        assert_eq!(
+            decoder.decode(&block).unwrap(),
+            headers(&[
+                (":status", "302"),
+                ("cache-control", "private"),
+                ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+                ("location", "https://www.example.com"),
+            ])
+        );
+        assert_eq!(decoder.table_size(), 222);
+
+        // ":status: 307" evicts ":status: 302"
+        let block = unhex("4883 640e ffc1 c0bf");
+        let decoded = decoder.decode(&block).unwrap();
+        assert_eq!(decoded[0], (":status".to_string(), "307".to_string()));
+        assert_eq!(decoded[3].1, "https://www.example.com");
+        assert_eq!(decoder.table_size(), 222);
+    }
+
+    #[test]
+    fn test_size_update_and_sensitive_headers() {
+        let mut encoder = Encoder::new();
+        let mut decoder = Decoder::new();
+
+        encoder.set_max_table_size(0);
+        let list = headers(&[("authorization", "Basic abc"), ("x-trace", "1")]);
+        let block = encoder.encode(&list);
+        // Size update to 0, then a never-indexed literal with a static name
+        assert_eq!(&block[..2], &[0x20, 0x1F]);
+        assert_eq!(decoder.decode(&block).unwrap(), list);
+        assert_eq!(encoder.table_size(), 0);
+        assert_eq!(decoder.table_size(), 0);
+
+        // Size updates above the advertised limit are rejected
+        assert_eq!(
+            Decoder::new().decode(&[0x3F, 0xE2, 0x1F]),
+            Err(HpackError::TableSizeExceeded(4097))
+        );
+        assert_eq!(
+            Decoder::new().decode(&[0x82, 0x20]),
+            Err(HpackError::UnexpectedSizeUpdate)
+        );
+        assert_eq!(
+            Decoder::new().decode(&[0xFE]),
+            Err(HpackError::InvalidIndex(126))
+        );
+    }
+}
@@ -0,0 +1,1273 @@
+//! HTTP/2 client (RFC 9113).
+//!
+//! Used when the TLS handshake negotiates `h2` via ALPN (see
+//! [`TlsSession::is_http2`](crate::tls::TlsSession::is_http2)). Like the
+//! HTTP/1.1 code, the connection is transport-agnostic: bytes read from
+//! the socket are fed to [`Http2Connection::recv`], and bytes to write
+//! are collected with [`Http2Connection::take_outbound`].
+//!
+//! Each request is a stream, so many requests can be in flight on one
+//! connection at once, up to the server's `SETTINGS_MAX_CONCURRENT_STREAMS`.
+//! Request bodies are sent as the server's flow-control windows allow,
+//! and our receive windows are replenished as response data arrives.
+//! Server push is disabled.
+
+pub mod frame;
+pub mod hpack;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::http::{HttpError, HttpRequest, HttpResponse, HttpVersion, StatusCode};
+
+pub use frame::{ErrorCode, Frame, FrameHeader, FrameType};
+pub use hpack::HpackError;
+
+use frame::{setting, DEFAULT_MAX_FRAME_SIZE, FRAME_HEADER_LEN, MAX_FRAME_SIZE_LIMIT};
+
+/// Client connection preface, sent before the first SETTINGS frame.
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Initial flow-control window for streams and the connection.
+pub const DEFAULT_WINDOW_SIZE: u32 = 65_535;
+
+/// Largest flow-control window.
+const MAX_WINDOW_SIZE: i64 = (1 << 31) - 1;
+
+/// Receive window we advertise for the connection and each stream.
+const LOCAL_WINDOW_SIZE: u32 = 1 << 20;
+
+/// Headers that are specific to an HTTP/1.1 connection and must not be
+/// sent over HTTP/2.
+const CONNECTION_HEADERS: [&str; 6] = [
+    "connection",
+    "host",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// HTTP/2 errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Http2Error {
+    /// The connection failed; a GOAWAY has been queued.
+    Connection(ErrorCode),
+    /// The stream was reset.
+    StreamReset(ErrorCode),
+    /// The server did not process the request (GOAWAY or REFUSED_STREAM);
+    /// it is safe to retry on a new connection.
+    Refused,
+    /// No more streams can be opened on this connection.
+    StreamLimit,
+    /// The request cannot be sent over HTTP/2.
+    InvalidRequest(String),
+    /// The response violates HTTP/2 message rules.
+    InvalidResponse(String),
+}
+
+impl From<Http2Error> for HttpError {
+    fn from(err: Http2Error) -> Self {
+        match err {
+            Http2Error::Refused | Http2Error::Connection(ErrorCode::NoError) => {
+                HttpError::ConnectionClosed
+            }
+            Http2Error::InvalidResponse(msg) => HttpError::InvalidResponse(msg),
+            other => HttpError::Network(format!("HTTP/2: {:?}", other)),
+        }
+    }
+}
+
+/// SETTINGS parameters of one endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    /// HPACK dynamic table size the endpoint's decoder allows.
+    pub header_table_size: u32,
+    /// Whether the endpoint accepts server push.
+    pub enable_push: bool,
+    /// Streams the endpoint allows its peer to open (`None` = unlimited).
+    pub max_concurrent_streams: Option<u32>,
+    /// Initial stream flow-control window.
+    pub initial_window_size: u32,
+    /// Largest frame payload the endpoint accepts.
+    pub max_frame_size: u32,
+    /// Advisory limit on the size of a header list.
+    pub max_header_list_size: Option<u32>,
+}
+
+impl Settings {
+    /// Apply one SETTINGS parameter received from the peer.
+    fn apply(&mut self, id: u16, value: u32) -> Result<(), ErrorCode> {
+        match id {
+            setting::HEADER_TABLE_SIZE => self.header_table_size = value,
+            setting::ENABLE_PUSH => {
+                if value > 1 {
+                    return Err(ErrorCode::ProtocolError);
+                }
+                self.enable_push = value == 1;
+            }
+            setting::MAX_CONCURRENT_STREAMS => self.max_concurrent_streams = Some(value),
+            setting::INITIAL_WINDOW_SIZE => {
+                if value as i64 > MAX_WINDOW_SIZE {
+                    return Err(ErrorCode::FlowControlError);
+                }
+                self.initial_window_size = value;
+            }
+            setting::MAX_FRAME_SIZE => {
+                if !(DEFAULT_MAX_FRAME_SIZE..=MAX_FRAME_SIZE_LIMIT).contains(&value) {
+                    return Err(ErrorCode::ProtocolError);
+                }
+                self.max_frame_size = value;
+            }
+            setting::MAX_HEADER_LIST_SIZE => self.max_header_list_size = Some(value),
+            // Unknown settings are ignored
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            header_table_size: hpack::DEFAULT_TABLE_SIZE as u32,
+            enable_push: true,
+            max_concurrent_streams: None,
+            initial_window_size: DEFAULT_WINDOW_SIZE,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            max_header_list_size: None,
+        }
+    }
+}
+
+/// Stream states (RFC 9113 §5.1) for streams we opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamState {
+    /// Both sides may send.
+    Open,
+    /// We finished sending the request.
+    HalfClosedLocal,
+    /// The server finished sending the response.
+    HalfClosedRemote,
+    /// Both sides finished, or the stream was reset.
+    Closed,
+}
+
+/// A request stream.
+#[derive(Debug)]
+struct Stream {
+    state: StreamState,
+    /// Bytes we may still send.
+    send_window: i64,
+    /// Bytes the server may still send.
+    recv_window: i64,
+    /// Received bytes not yet returned with WINDOW_UPDATE.
+    recv_unacked: u32,
+    /// Request body not yet sent.
+    pending: Vec<u8>,
+    /// Final (non-1xx) response headers received.
+    headers_done: bool,
+    response: HttpResponse,
+    /// Outcome, once the stream is finished.
+    result: Option<Result<HttpResponse, Http2Error>>,
+}
+
+impl Stream {
+    fn close_local(&mut self) {
+        self.state = match self.state {
+            StreamState::Open => StreamState::HalfClosedLocal,
+            _ => StreamState::Closed,
+        };
+    }
+
+    fn close_remote(&mut self) {
+        self.state = match self.state {
+            StreamState::Open => StreamState::HalfClosedRemote,
+            _ => StreamState::Closed,
+        };
+    }
+
+    fn fail(&mut self, error: Http2Error) {
+        self.state = StreamState::Closed;
+        self.pending.clear();
+        if self.result.is_none() {
+            self.result = Some(Err(error));
+        }
+    }
+}
+
+/// Header block split across HEADERS and CONTINUATION frames.
+#[derive(Debug)]
+struct PartialHeaders {
+    stream_id: u32,
+    end_stream: bool,
+    block: Vec<u8>,
+}
+
+/// Client side of an HTTP/2 connection.
+pub struct Http2Connection {
+    /// Our settings, as advertised.
+    local: Settings,
+    /// The server's settings.
+    remote: Settings,
+    /// Whether the server acknowledged our SETTINGS.
+    settings_acked: bool,
+    /// Whether the server's connection preface (SETTINGS) arrived.
+    preface_received: bool,
+    encoder: hpack::Encoder,
+    decoder: hpack::Decoder,
+    streams: BTreeMap<u32, Stream>,
+    next_stream_id: u32,
+    /// Connection-level send window.
+    send_window: i64,
+    /// Connection-level receive window.
+    recv_window: i64,
+    /// Received bytes not yet returned with WINDOW_UPDATE.
+    recv_unacked: u32,
+    continuation: Option<PartialHeaders>,
+    /// Last stream ID the server will process, after GOAWAY.
+    goaway: Option<u32>,
+    /// Connection error, once the connection has failed.
+    error: Option<ErrorCode>,
+    inbound: Vec<u8>,
+    outbound: Vec<u8>,
+}
+
+impl Http2Connection {
+    /// Create a connection and queue the client preface and SETTINGS.
+    pub fn new() -> Self {
+        let local = Settings {
+            enable_push: false,
+            initial_window_size: LOCAL_WINDOW_SIZE,
+            ..Settings::default()
+        };
+
+        let mut conn = Self {
+            local,
+            remote: Settings::default(),
+            settings_acked: false,
+            preface_received: false,
+            encoder: hpack::Encoder::new(),
+            decoder: hpack::Decoder::new(),
+            streams: BTreeMap::new(),
+            next_stream_id: 1,
+            send_window: DEFAULT_WINDOW_SIZE as i64,
+            recv_window: LOCAL_WINDOW_SIZE as i64,
+            recv_unacked: 0,
+            continuation: None,
+            goaway: None,
+            error: None,
+            inbound: Vec::new(),
+            outbound: Vec::new(),
+        };
+
+        conn.outbound.extend_from_slice(PREFACE);
+        conn.send_frame(Frame::Settings {
+            ack: false,
+            params: alloc::vec![
+                (setting::ENABLE_PUSH, 0),
+                (setting::INITIAL_WINDOW_SIZE, LOCAL_WINDOW_SIZE),
+            ],
+        });
+        // The connection window is not covered by SETTINGS
+        conn.send_frame(Frame::WindowUpdate {
+            stream_id: 0,
+            increment: LOCAL_WINDOW_SIZE - DEFAULT_WINDOW_SIZE,
+        });
+        conn
+    }
+
+    /// The server's settings.
+    pub fn remote_settings(&self) -> &Settings {
+        &self.remote
+    }
+
+    /// Whether the server's SETTINGS (its connection preface) arrived.
+    ///
+    /// Until then its stream limit is unknown.
+    pub fn remote_settings_received(&self) -> bool {
+        self.preface_received
+    }
+
+    /// Whether the server acknowledged our SETTINGS.
+    pub fn settings_acked(&self) -> bool {
+        self.settings_acked
+    }
+
+    /// Whether the connection is still usable for existing streams.
+    pub fn is_open(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Number of streams awaiting a response.
+    pub fn active_streams(&self) -> usize {
+        self.streams.values().filter(|s| s.result.is_none()).count()
+    }
+
+    /// Whether a new request can be sent now.
+    pub fn can_send_request(&self) -> bool {
+        self.error.is_none()
+            && self.goaway.is_none()
+            && self.next_stream_id <= MAX_WINDOW_SIZE as u32
+            && self
+                .remote
+                .max_concurrent_streams
+                .is_none_or(|max| (self.active_streams() as u32) < max)
+    }
+
+    /// Take the bytes to write to the transport.
+    pub fn take_outbound(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.outbound)
+    }
+
+    /// Open a stream for `request` and queue its HEADERS and body.
+    ///
+    /// The `Host` header becomes the `:authority` pseudo-header. Returns
+    /// the stream ID to pass to [`poll_response`](Self::poll_response).
+    pub fn send_request(&mut self, request: &HttpRequest) -> Result<u32, Http2Error> {
+        if let Some(code) = self.error {
+            return Err(Http2Error::Connection(code));
+        }
+        if self.goaway.is_some() {
+            return Err(Http2Error::Refused);
+        }
+        if !self.can_send_request() {
+            return Err(Http2Error::StreamLimit);
+        }
+
+        let authority = request
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| Http2Error::InvalidRequest("missing Host header".to_string()))?;
+
+        let mut headers = alloc::vec![
+            (":method".to_string(), request.method.as_str().to_string()),
+            (":scheme".to_string(), "https".to_string()),
+            (":authority".to_string(), authority),
+            (":path".to_string(), request.path.clone()),
+        ];
+        for (name, value) in &request.headers {
+            let name = name.to_ascii_lowercase();
+            if CONNECTION_HEADERS.contains(&name.as_str()) {
+                continue;
+            }
+            if name == "te" && !value.eq_ignore_ascii_case("trailers") {
+                continue;
+            }
+            headers.push((name, value.clone()));
+        }
+
+        let stream_id = self.next_stream_id;
+        self.next_stream_id += 2;
+
+        let end_stream = request.body.is_empty();
+        let block = self.encoder.encode(&headers);
+        self.send_header_block(stream_id, block, end_stream);
+
+        let mut stream = Stream {
+            state: StreamState::Open,
+            send_window: self.remote.initial_window_size as i64,
+            recv_window: self.local.initial_window_size as i64,
+            recv_unacked: 0,
+            pending: request.body.clone(),
+            headers_done: false,
+            response: HttpResponse::new(),
+            result: None,
+        };
+        stream.response.version = HttpVersion::Http2;
+        if end_stream {
+            stream.close_local();
+        }
+        self.streams.insert(stream_id, stream);
+        self.flush_data();
+
+        Ok(stream_id)
+    }
+
+    /// Take the response for a stream once it has completed or failed.
+    pub fn poll_response(&mut self, stream_id: u32) -> Option<Result<HttpResponse, Http2Error>> {
+        let stream = self.streams.get_mut(&stream_id)?;
+        let result = stream.result.take()?;
+        // Forget the stream once both sides are done with it; otherwise
+        // keep it so late frames are still accounted for.
+        if stream.state == StreamState::Closed {
+            self.streams.remove(&stream_id);
+        }
+        Some(result)
+    }
+
+    /// Abandon a request, resetting its stream with `CANCEL`.
+    pub fn cancel(&mut self, stream_id: u32) {
+        if let Some(stream) = self.streams.get_mut(&stream_id) {
+            if stream.state != StreamState::Closed {
+                stream.fail(Http2Error::StreamReset(ErrorCode::Cancel));
+                self.send_frame(Frame::RstStream {
+                    stream_id,
+                    error: ErrorCode::Cancel,
+                });
+            }
+        }
+    }
+
+    /// Start a graceful shutdown: queue GOAWAY and stop opening streams.
+    pub fn close(&mut self) {
+        if self.goaway.is_none() {
+            self.goaway = Some(0);
+        }
+        self.send_frame(Frame::GoAway {
+            last_stream_id: 0,
+            error: ErrorCode::NoError,
+            debug_data: Vec::new(),
+        });
+    }
+
+    /// Process bytes read from the transport.
+    ///
+    /// On a connection error, a GOAWAY is queued, every pending stream
+    /// fails, and the error is returned.
+    pub fn recv(&mut self, data: &[u8]) -> Result<(), Http2Error> {
+        if let Some(code) = self.error {
+            return Err(Http2Error::Connection(code));
+        }
+        self.inbound.extend_from_slice(data);
+
+        loop {
+            let Some(header) = FrameHeader::parse(&self.inbound) else {
+                return Ok(());
+            };
+            if header.length > self.local.max_frame_size {
+                return Err(self.fail_connection(ErrorCode::FrameSizeError));
+            }
+            let frame_len = FRAME_HEADER_LEN + header.length as usize;
+            if self.inbound.len() < frame_len {
+                return Ok(());
+            }
+
+            let frame: Vec<u8> = self.inbound.drain(..frame_len).collect();
+            let result = Frame::decode(&header, &frame[FRAME_HEADER_LEN..])
+                .and_then(|decoded| self.handle_frame(&header, decoded));
+            if let Err(code) = result {
+                return Err(self.fail_connection(code));
+            }
+        }
+    }
+
+    fn handle_frame(&mut self, header: &FrameHeader, frame: Frame) -> Result<(), ErrorCode> {
+        // The server preface is a SETTINGS frame
+        if !self.preface_received {
+            if !matches!(frame, Frame::Settings { ack: false, .. }) {
+                return Err(ErrorCode::ProtocolError);
+            }
+            self.preface_received = true;
+        }
+
+        // A header block must not be interleaved with other frames
+        if let Some(partial) = &self.continuation {
+            match frame {
+                Frame::Continuation { stream_id, .. } if stream_id == partial.stream_id => {}
+                _ => return Err(ErrorCode::ProtocolError),
+            }
+        }
+
+        match frame {
+            Frame::Data {
+                stream_id,
+                data,
+                end_stream,
+            } => self.on_data(stream_id, header.length, data, end_stream),
+            Frame::Headers {
+                stream_id,
+                block,
+                end_stream,
+                end_headers,
+            } => {
+                if self.is_idle(stream_id) {
+                    return Err(ErrorCode::ProtocolError);
+                }
+                if end_headers {
+                    self.on_header_block(stream_id, &block, end_stream)
+                } else {
+                    self.continuation = Some(PartialHeaders {
+                        stream_id,
+                        end_stream,
+                        block,
+                    });
+                    Ok(())
+                }
+            }
+            Frame::Continuation {
+                stream_id,
+                block,
+                end_headers,
+            } => {
+                let Some(mut partial) = self.continuation.take() else {
+                    return Err(ErrorCode::ProtocolError);
+                };
+                partial.block.extend_from_slice(&block);
+                if end_headers {
+                    self.on_header_block(stream_id, &partial.block, partial.end_stream)
+                } else {
+                    self.continuation = Some(partial);
+                    Ok(())
+                }
+            }
+            Frame::Priority { .. } => Ok(()),
+            Frame::RstStream { stream_id, error } => {
+                if self.is_idle(stream_id) {
+                    return Err(ErrorCode::ProtocolError);
+                }
+                if let Some(stream) = self.streams.get_mut(&stream_id) {
+                    stream.fail(match error {
+                        ErrorCode::RefusedStream => Http2Error::Refused,
+                        code => Http2Error::StreamReset(code),
+                    });
+                }
+                Ok(())
+            }
+            Frame::Settings { ack: true, .. } => {
+                self.settings_acked = true;
+                Ok(())
+            }
+            Frame::Settings { ack: false, params } => self.on_settings(&params),
+            // We advertised SETTINGS_ENABLE_PUSH = 0
+            Frame::PushPromise { .. } => Err(ErrorCode::ProtocolError),
+            Frame::Ping { ack, data } => {
+                if !ack {
+                    self.send_frame(Frame::Ping { ack: true, data });
+                }
+                Ok(())
+            }
+            Frame::GoAway { last_stream_id, .. } => {
+                self.goaway = Some(last_stream_id);
+                for (_, stream) in self.streams.range_mut(last_stream_id + 1..) {
+                    stream.fail(Http2Error::Refused);
+                }
+                Ok(())
+            }
+            Frame::WindowUpdate {
+                stream_id,
+                increment,
+            } => self.on_window_update(stream_id, increment),
+            Frame::Unknown { .. } => Ok(()),
+        }
+    }
+
+    fn on_data(
+        &mut self,
+        stream_id: u32,
+        flow_len: u32,
+        data: Vec<u8>,
+        end_stream: bool,
+    ) -> Result<(), ErrorCode> {
+        if self.is_idle(stream_id) {
+            return Err(ErrorCode::ProtocolError);
+        }
+
+        // The whole payload, padding included, counts against the windows
+        if flow_len as i64 > self.recv_window {
+            return Err(ErrorCode::FlowControlError);
+        }
+        self.recv_window -= flow_len as i64;
+        self.recv_unacked += flow_len;
+        if self.recv_unacked >= LOCAL_WINDOW_SIZE / 2 {
+            let increment = core::mem::take(&mut self.recv_unacked);
+            self.recv_window += increment as i64;
+            self.send_frame(Frame::WindowUpdate {
+                stream_id: 0,
+                increment,
+            });
+        }
+
+        // Frames for streams we already forgot or reset are dropped
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            return Ok(());
+        };
+        if stream.result.is_some() {
+            return Ok(());
+        }
+        if !stream.headers_done || flow_len as i64 > stream.recv_window {
+            let error = if stream.headers_done {
+                ErrorCode::FlowControlError
+            } else {
+                ErrorCode::ProtocolError
+            };
+            self.reset_stream(
+                stream_id,
+                error,
+                Http2Error::InvalidResponse("invalid DATA frame".to_string()),
+            );
+            return Ok(());
+        }
+
+        stream.recv_window -= flow_len as i64;
+        stream.response.body.extend_from_slice(&data);
+        if end_stream {
+            self.finish_stream(stream_id);
+        } else {
+            stream.recv_unacked += flow_len;
+            if stream.recv_unacked >= LOCAL_WINDOW_SIZE / 2 {
+                let increment = core::mem::take(&mut stream.recv_unacked);
+                stream.recv_window += increment as i64;
+                self.send_frame(Frame::WindowUpdate {
+                    stream_id,
+                    increment,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn on_header_block(
+        &mut self,
+        stream_id: u32,
+        block: &[u8],
+        end_stream: bool,
+    ) -> Result<(), ErrorCode> {
+        // Always decode, so the HPACK context stays in sync
+        let headers = self
+            .decoder
+            .decode(block)
+            .map_err(|_| ErrorCode::CompressionError)?;
+
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            return Ok(());
+        };
+        if stream.result.is_some() {
+            return Ok(());
+        }
+
+        if stream.headers_done {
+            // Trailers
+            if !end_stream {
+                self.reset_stream(
+                    stream_id,
+                    ErrorCode::ProtocolError,
+                    Http2Error::InvalidResponse("trailers without END_STREAM".to_string()),
+                );
+                return Ok(());
+            }
+            for (name, value) in headers {
+                if !name.starts_with(':') {
+                    insert_header(&mut stream.response, name, value);
+                }
+            }
+            self.finish_stream(stream_id);
+            return Ok(());
+        }
+
+        let status = headers
+            .iter()
+            .find(|(name, _)| name == ":status")
+            .and_then(|(_, value)| value.parse::<u16>().ok());
+        let Some(status) = status else {
+            self.reset_stream(
+                stream_id,
+                ErrorCode::ProtocolError,
+                Http2Error::InvalidResponse("missing :status".to_string()),
+            );
+            return Ok(());
+        };
+
+        // Interim (1xx) responses are skipped
+        if (100..200).contains(&status) {
+            if end_stream {
+                self.reset_stream(
+                    stream_id,
+                    ErrorCode::ProtocolError,
+                    Http2Error::InvalidResponse("1xx response ended the stream".to_string()),
+                );
+            }
+            return Ok(());
+        }
+
+        stream.headers_done = true;
+        stream.response.status = StatusCode(status);
+        for (name, value) in headers {
+            if !name.starts_with(':') {
+                insert_header(&mut stream.response, name, value);
+            }
+        }
+        if end_stream {
+            self.finish_stream(stream_id);
+        }
+        Ok(())
+    }
+
+    fn on_settings(&mut self, params: &[(u16, u32)]) -> Result<(), ErrorCode> {
+        let old_window = self.remote.initial_window_size;
+        for &(id, value) in params {
+            self.remote.apply(id, value)?;
+        }
+
+        // A new initial window adjusts every open stream's send window
+        let delta = self.remote.initial_window_size as i64 - old_window as i64;
+        if delta != 0 {
+            for stream in self.streams.values_mut() {
+                stream.send_window += delta;
+                if stream.send_window > MAX_WINDOW_SIZE {
+                    return Err(ErrorCode::FlowControlError);
+                }
+            }
+        }
+        self.encoder
+            .set_max_table_size(self.remote.header_table_size as usize);
+
+        self.send_frame(Frame::Settings {
+            ack: true,
+            params: Vec::new(),
+        });
+        self.flush_data();
+        Ok(())
+    }
+
+    fn on_window_update(&mut self, stream_id: u32, increment: u32) -> Result<(), ErrorCode> {
+        if stream_id == 0 {
+            if increment == 0 {
+                return Err(ErrorCode::ProtocolError);
+            }
+            self.send_window += increment as i64;
+            if self.send_window > MAX_WINDOW_SIZE {
+                return Err(ErrorCode::FlowControlError);
+            }
+        } else {
+            if self.is_idle(stream_id) {
+                return Err(ErrorCode::ProtocolError);
+            }
+            let Some(stream) = self.streams.get_mut(&stream_id) else {
+                return Ok(());
+            };
+            if increment == 0 || stream.send_window + increment as i64 > MAX_WINDOW_SIZE {
+                let error = if increment == 0 {
+                    ErrorCode::ProtocolError
+                } else {
+                    ErrorCode::FlowControlError
+                };
+                self.reset_stream(stream_id, error, Http2Error::StreamReset(error));
+                return Ok(());
+            }
+            stream.send_window += increment as i64;
+        }
+        self.flush_data();
+        Ok(())
+    }
+
+    /// Send as much pending request body data as the windows allow.
+    fn flush_data(&mut self) {
+        let max_frame = self.remote.max_frame_size as i64;
+        for (&stream_id, stream) in self.streams.iter_mut() {
+            while !stream.pending.is_empty() {
+                let window = self.send_window.min(stream.send_window).min(max_frame);
+                if window <= 0 {
+                    break;
+                }
+                let len = stream.pending.len().min(window as usize);
+                let data: Vec<u8> = stream.pending.drain(..len).collect();
+                let end_stream = stream.pending.is_empty();
+                self.send_window -= len as i64;
+                stream.send_window -= len as i64;
+
+                Frame::Data {
+                    stream_id,
+                    data,
+                    end_stream,
+                }
+                .encode(&mut self.outbound);
+                if end_stream {
+                    stream.close_local();
+                }
+            }
+        }
+    }
+
+    /// Queue a header block as HEADERS plus any CONTINUATION frames.
+    fn send_header_block(&mut self, stream_id: u32, block: Vec<u8>, end_stream: bool) {
+        let max_frame = self.remote.max_frame_size as usize;
+        let mut chunks = block.chunks(max_frame).peekable();
+        let first = chunks.next().unwrap_or(&[]).to_vec();
+        self.send_frame(Frame::Headers {
+            stream_id,
+            block: first,
+            end_stream,
+            end_headers: chunks.peek().is_none(),
+        });
+        while let Some(chunk) = chunks.next() {
+            self.send_frame(Frame::Continuation {
+                stream_id,
+                block: chunk.to_vec(),
+                end_headers: chunks.peek().is_none(),
+            });
+        }
+    }
+
+    fn send_frame(&mut self, frame: Frame) {
+        frame.encode(&mut self.outbound);
+    }
+
+    /// Whether `stream_id` names a stream that was never opened.
+    ///
+    /// Server-initiated (even) streams only exist through push, which is
+    /// disabled.
+    fn is_idle(&self, stream_id: u32) -> bool {
+        stream_id.is_multiple_of(2) || stream_id >= self.next_stream_id
+    }
+
+    fn finish_stream(&mut self, stream_id: u32) {
+        if let Some(stream) = self.streams.get_mut(&stream_id) {
+            stream.close_remote();
+            let response = core::mem::take(&mut stream.response);
+            stream.result = Some(Ok(response));
+            // The response is complete even if the request body is not
+            if stream.state != StreamState::Closed {
+                stream.pending.clear();
+                stream.state = StreamState::Closed;
+                self.send_frame(Frame::RstStream {
+                    stream_id,
+                    error: ErrorCode::NoError,
+                });
+            }
+        }
+    }
+
+    fn reset_stream(&mut self, stream_id: u32, code: ErrorCode, error: Http2Error) {
+        if let Some(stream) = self.streams.get_mut(&stream_id) {
+            stream.fail(error);
+        }
+        self.send_frame(Frame::RstStream {
+            stream_id,
+            error: code,
+        });
+    }
+
+    /// Fail the connection: queue GOAWAY and fail every pending stream.
+    fn fail_connection(&mut self, code: ErrorCode) -> Http2Error {
+        self.error = Some(code);
+        let last_stream_id = self.next_stream_id.saturating_sub(2);
+        self.send_frame(Frame::GoAway {
+            last_stream_id,
+            error: code,
+            debug_data: Vec::new(),
+        });
+        for stream in self.streams.values_mut() {
+            stream.fail(Http2Error::Connection(code));
+        }
+        Http2Error::Connection(code)
+    }
+}
+
+impl Default for Http2Connection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Add a response header, joining repeated fields with a comma.
+fn insert_header(response: &mut HttpResponse, name: String, value: String) {
+    match response.headers.get_mut(&name) {
+        Some(existing) => {
+            existing.push_str(", ");
+            existing.push_str(&value);
+        }
+        None => {
+            response.headers.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Minimal server side: decodes client frames and builds responses.
+    struct TestServer {
+        encoder: hpack::Encoder,
+        decoder: hpack::Decoder,
+        buffer: Vec<u8>,
+    }
+
+    impl TestServer {
+        fn new() -> Self {
+            Self {
+                encoder: hpack::Encoder::new(),
+                decoder: hpack::Decoder::new(),
+                buffer: Vec::new(),
+            }
+        }
+
+        /// Parse client output into frames, skipping the preface.
+        fn read(&mut self, bytes: &[u8]) -> Vec<Frame> {
+            self.buffer.extend_from_slice(bytes);
+            if self.buffer.starts_with(PREFACE) {
+                self.buffer.drain(..PREFACE.len());
+            }
+            let mut frames = Vec::new();
+            while let Some(header) = FrameHeader::parse(&self.buffer) {
+                let len = FRAME_HEADER_LEN + header.length as usize;
+                if self.buffer.len() < len {
+                    break;
+                }
+                let raw: Vec<u8> = self.buffer.drain(..len).collect();
+                frames.push(Frame::decode(&header, &raw[FRAME_HEADER_LEN..]).unwrap());
+            }
+            frames
+        }
+
+        fn headers(&mut self, stream_id: u32, status: &str, end_stream: bool) -> Vec<u8> {
+            let block = self.encoder.encode(&[
+                (":status".to_string(), status.to_string()),
+                ("content-type".to_string(), "text/plain".to_string()),
+            ]);
+            encode(Frame::Headers {
+                stream_id,
+                block,
+                end_stream,
+                end_headers: true,
+            })
+        }
+    }
+
+    fn encode(frame: Frame) -> Vec<u8> {
+        let mut out = Vec::new();
+        frame.encode(&mut out);
+        out
+    }
+
+    fn settings(params: Vec<(u16, u32)>) -> Vec<u8> {
+        encode(Frame::Settings { ack: false, params })
+    }
+
+    fn data(stream_id: u32, body: &[u8], end_stream: bool) -> Vec<u8> {
+        encode(Frame::Data {
+            stream_id,
+            data: body.to_vec(),
+            end_stream,
+        })
+    }
+
+    fn get(path: &str) -> HttpRequest {
+        HttpRequest::get(path)
+            .host("example.com")
+            .header("Connection", "close")
+    }
+
+    #[test]
+    fn test_preface_and_settings_exchange() {
+        let mut conn = Http2Connection::new();
+        let mut server = TestServer::new();
+
+        let out = conn.take_outbound();
+        assert!(out.starts_with(PREFACE));
+        let frames = server.read(&out);
+        assert_eq!(
+            frames[0],
+            Frame::Settings {
+                ack: false,
+                params: vec![
+                    (setting::ENABLE_PUSH, 0),
+                    (setting::INITIAL_WINDOW_SIZE, LOCAL_WINDOW_SIZE)
+                ],
+            }
+        );
+
+        conn.recv(&settings(vec![(setting::MAX_CONCURRENT_STREAMS, 2)]))
+            .unwrap();
+        conn.recv(&encode(Frame::Settings {
+            ack: true,
+            params: Vec::new(),
+        }))
+        .unwrap();
+        assert!(conn.settings_acked());
+        assert_eq!(conn.remote_settings().max_concurrent_streams, Some(2));
+        assert_eq!(
+            server.read(&conn.take_outbound()),
+            vec![Frame::Settings {
+                ack: true,
+                params: Vec::new()
+            }]
+        );
+
+        // PING is answered
+        conn.recv(&encode(Frame::Ping {
+            ack: false,
+            data: [7; 8],
+        }))
+        .unwrap();
+        assert_eq!(
+            server.read(&conn.take_outbound()),
+            vec![Frame::Ping {
+                ack: true,
+                data: [7; 8]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_multiplexed_requests() {
+        let mut conn = Http2Connection::new();
+        let mut server = TestServer::new();
+        server.read(&conn.take_outbound());
+        conn.recv(&settings(vec![(setting::MAX_CONCURRENT_STREAMS, 2)]))
+            .unwrap();
+
+        let a = conn.send_request(&get("/a")).unwrap();
+        let b = conn.send_request(&get("/b")).unwrap();
+        assert_eq!((a, b), (1, 3));
+        assert!(!conn.can_send_request());
+        assert_eq!(conn.send_request(&get("/c")), Err(Http2Error::StreamLimit));
+
+        let frames = server.read(&conn.take_outbound());
+        let mut blocks: Vec<_> = frames
+            .into_iter()
+            .filter_map(|f| match f {
+                Frame::Headers {
+                    block, end_stream, ..
+                } => Some((block, end_stream)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(blocks.len(), 2);
+        server.decoder.decode(&blocks[0].0).unwrap();
+        let request = server.decoder.decode(&blocks.pop().unwrap().0).unwrap();
+        assert!(blocks[0].1);
+        assert_eq!(request[0], (":method".to_string(), "GET".to_string()));
+        assert_eq!(
+            request[2],
+            (":authority".to_string(), "example.com".to_string())
+        );
+        assert_eq!(request[3], (":path".to_string(), "/b".to_string()));
+        // Connection-specific headers are dropped
+        assert!(request
+            .iter()
+            .all(|(n, _)| n != "connection" && n != "host"));
+
+        // Responses arrive interleaved, second stream first
+        let mut input = server.headers(b, "404", false);
+        input.extend(server.headers(a, "200", false));
+        input.extend(data(a, b"hello ", false));
+        input.extend(data(b, b"missing", true));
+        input.extend(data(a, b"world", true));
+        conn.recv(&input).unwrap();
+
+        let resp_b = conn.poll_response(b).unwrap().unwrap();
+        assert_eq!(resp_b.status, StatusCode::NOT_FOUND);
+        assert_eq!(resp_b.text().unwrap(), "missing");
+        let resp_a = conn.poll_response(a).unwrap().unwrap();
+        assert_eq!(resp_a.status, StatusCode::OK);
+        assert_eq!(resp_a.version, HttpVersion::Http2);
+        assert_eq!(resp_a.content_type().unwrap(), "text/plain");
+        assert_eq!(resp_a.text().unwrap(), "hello world");
+        assert!(conn.poll_response(a).is_none());
+
+        assert_eq!(conn.active_streams(), 0);
+        assert!(conn.can_send_request());
+        assert_eq!(conn.send_request(&get("/c")), Ok(5));
+    }
+
+    #[test]
+    fn test_request_body_respects_flow_control() {
+        let mut conn = Http2Connection::new();
+        let mut server = TestServer::new();
+        server.read(&conn.take_outbound());
+        conn.recv(&settings(vec![(setting::INITIAL_WINDOW_SIZE, 10)]))
+            .unwrap();
+        server.read(&conn.take_outbound());
+
+        let body = vec![0xAB; 25];
+        let request = HttpRequest::post("/upload", body).host("example.com");
+        let id = conn.send_request(&request).unwrap();
+
+        let sent = |frames: Vec<Frame>| -> (usize, bool) {
+            frames.iter().fold((0, false), |(n, end), f| match f {
+                Frame::Data {
+                    data, end_stream, ..
+                } => (n + data.len(), end || *end_stream),
+                _ => (n, end),
+            })
+        };
+        assert_eq!(sent(server.read(&conn.take_outbound())), (10, false));
+
+        conn.recv(&encode(Frame::WindowUpdate {
+            stream_id: id,
+            increment: 10,
+        }))
+        .unwrap();
+        assert_eq!(sent(server.read(&conn.take_outbound())), (10, false));
+
+        // Raising the initial window applies to open streams too
+        conn.recv(&settings(vec![(setting::INITIAL_WINDOW_SIZE, 100)]))
+            .unwrap();
+        assert_eq!(sent(server.read(&conn.take_outbound())), (5, true));
+    }
+
+    #[test]
+    fn test_receive_window_replenished() {
+        let mut conn = Http2Connection::new();
+        let mut server = TestServer::new();
+        server.read(&conn.take_outbound());
+        conn.recv(&settings(Vec::new())).unwrap();
+        let id = conn.send_request(&get("/big")).unwrap();
+        server.read(&conn.take_outbound());
+
+        conn.recv(&server.headers(id, "200", false)).unwrap();
+        let chunk = vec![0u8; DEFAULT_MAX_FRAME_SIZE as usize];
+        for _ in 0..(LOCAL_WINDOW_SIZE / 2 / DEFAULT_MAX_FRAME_SIZE) {
+            conn.recv(&data(id, &chunk, false)).unwrap();
+        }
+        let updates: Vec<_> = server
+            .read(&conn.take_outbound())
+            .into_iter()
+            .filter(|f| matches!(f, Frame::WindowUpdate { .. }))
+            .collect();
+        assert_eq!(
+            updates,
+            vec![
+                Frame::WindowUpdate {
+                    stream_id: 0,
+                    increment: LOCAL_WINDOW_SIZE / 2
+                },
+                Frame::WindowUpdate {
+                    stream_id: id,
+                    increment: LOCAL_WINDOW_SIZE / 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_reset_and_goaway() {
+        let mut conn = Http2Connection::new();
+        let mut server = TestServer::new();
+        server.read(&conn.take_outbound());
+        conn.recv(&settings(Vec::new())).unwrap();
+
+        let a = conn.send_request(&get("/a")).unwrap();
+        let b = conn.send_request(&get("/b")).unwrap();
+        let c = conn.send_request(&get("/c")).unwrap();
+
+        conn.recv(&encode(Frame::RstStream {
+            stream_id: a,
+            error: ErrorCode::Cancel,
+        }))
+        .unwrap();
+        conn.recv(&encode(Frame::GoAway {
+            last_stream_id: b,
+            error: ErrorCode::NoError,
+            debug_data: Vec::new(),
+        }))
+        .unwrap();
+
+        assert_eq!(
+            conn.poll_response(a).unwrap().unwrap_err(),
+            Http2Error::StreamReset(ErrorCode::Cancel)
+        );
+        assert_eq!(
+            conn.poll_response(c).unwrap().unwrap_err(),
+            Http2Error::Refused
+        );
+        // Stream b is still processed
+        assert!(conn.poll_response(b).is_none());
+        assert_eq!(conn.send_request(&get("/d")), Err(Http2Error::Refused));
+
+        let mut input = server.headers(b, "204", true);
+        input.extend(data(a, b"late", true));
+        conn.recv(&input).unwrap();
+        assert_eq!(
+            conn.poll_response(b).unwrap().unwrap().status,
+            StatusCode::NO_CONTENT
+        );
+    }
+
+    #[test]
+    fn test_connection_errors() {
+        // First frame from the server must be SETTINGS
+        let mut conn = Http2Connection::new();
+        conn.take_outbound();
+        assert_eq!(
+            conn.recv(&encode(Frame::Ping {
+                ack: false,
+                data: [0; 8]
+            })),
+            Err(Http2Error::Connection(ErrorCode::ProtocolError))
+        );
+        let out = TestServer::new().read(&conn.take_outbound());
+        assert!(matches!(
+            out.last(),
+            Some(Frame::GoAway {
+                error: ErrorCode::ProtocolError,
+                ..
+            })
+        ));
+        assert!(!conn.is_open());
+
+        // Push is disabled, and pending streams fail with the connection
+        let mut conn = Http2Connection::new();
+        conn.recv(&settings(Vec::new())).unwrap();
+        let id = conn.send_request(&get("/")).unwrap();
+        let push = encode(Frame::PushPromise {
+            stream_id: id,
+            promised_stream_id: 2,
+            block: Vec::new(),
+            end_headers: true,
+        });
+        assert_eq!(
+            conn.recv(&push),
+            Err(Http2Error::Connection(ErrorCode::ProtocolError))
+        );
+        assert_eq!(
+            conn.poll_response(id).unwrap().unwrap_err(),
+            Http2Error::Connection(ErrorCode::ProtocolError)
+        );
+
+        // Oversized frames
+        let mut conn = Http2Connection::new();
+        conn.recv(&settings(Vec::new())).unwrap();
+        let mut big = Vec::new();
+        FrameHeader {
+            length: DEFAULT_MAX_FRAME_SIZE + 1,
+            frame_type: FrameType::Data as u8,
+            flags: 0,
+            stream_id: 1,
+        }
+        .encode(&mut big);
+        assert_eq!(
+            conn.recv(&big),
+            Err(Http2Error::Connection(ErrorCode::FrameSizeError))
+        );
+    }
+
+    #[test]
+    fn test_continuation_and_interim_response() {
+        let mut conn = Http2Connection::new();
+        let mut server = TestServer::new();
+        conn.recv(&settings(Vec::new())).unwrap();
+        let id = conn.send_request(&get("/")).unwrap();
+
+        let mut input = server.headers(id, "103", false);
+        let block = server.encoder.encode(&[
+            (":status".to_string(), "200".to_string()),
+            ("set-cookie".to_string(), "a=1".to_string()),
+            ("set-cookie".to_string(), "b=2".to_string()),
+        ]);
+        let (head, tail) = block.split_at(3);
+        input.extend(encode(Frame::Headers {
+            stream_id: id,
+            block: head.to_vec(),
+            end_stream: true,
+            end_headers: false,
+        }));
+        input.extend(encode(Frame::Continuation {
+            stream_id: id,
+            block: tail.to_vec(),
+            end_headers: true,
+        }));
+        conn.recv(&input).unwrap();
+
+        let response = conn.poll_response(id).unwrap().unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.header("Set-Cookie").unwrap(), "a=1, b=2");
+    }
+}
@@ -1,16 +1,100 @@
 //! Network interface management.
+//!
+//! Besides the table of configured interfaces, this module owns the
+//! in-stack loopback path: packets addressed to `127.0.0.0/8` (or `::1`)
+//! never reach a NIC driver.  They are pushed onto a loopback receive
+//! queue instead and drained by the socket layer, so services bound to
+//! `127.0.0.1` are reachable from the same machine without SLIRP routing.
 
+use alloc::collections::VecDeque;
 use alloc::string::String;
 use alloc::vec::Vec;
-use spin::RwLock;
+use spin::{Mutex, RwLock};
 
-use crate::{InterfaceConfig, Ipv4Addr, MacAddr, NetworkError};
+use crate::{InterfaceConfig, IpAddr, Ipv4Addr, Ipv6Addr, MacAddr, NetworkError, SocketAddr};
+
+/// Name of the loopback interface.
+pub const LOOPBACK_NAME: &str = "lo";
+
+/// MTU of the loopback interface.
+pub const LOOPBACK_MTU: u16 = 65535;
+
+/// Maximum number of packets waiting in the loopback receive queue.
+const LOOPBACK_QUEUE_CAP: usize = 256;
 
 /// Global interface list.
 static INTERFACES: RwLock<Vec<InterfaceConfig>> = RwLock::new(Vec::new());
 
+/// Loopback receive queue: packets "transmitted" on `lo` land here.
+static LOOPBACK_RX: Mutex<VecDeque<LoopbackPacket>> = Mutex::new(VecDeque::new());
+
+/// Loopback interface counters.
+static LOOPBACK_STATS: Mutex<LoopbackStats> = Mutex::new(LoopbackStats {
+    rx_packets: 0,
+    tx_packets: 0,
+    rx_bytes: 0,
+    tx_bytes: 0,
+    dropped: 0,
+});
+
+/// Transport-level meaning of a loopback packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopbackKind {
+    /// TCP connection request (SYN) from `src` to `dst`.
+    TcpSyn,
+    /// TCP segment on an established connection.
+    TcpData,
+    /// UDP datagram.
+    Udp,
+}
+
+/// A packet travelling over the loopback interface.
+#[derive(Debug, Clone)]
+pub struct LoopbackPacket {
+    /// What the packet carries.
+    pub kind: LoopbackKind,
+    /// Sender address.
+    pub src: SocketAddr,
+    /// Destination address (always a loopback address).
+    pub dst: SocketAddr,
+    /// Payload bytes.
+    pub payload: Vec<u8>,
+}
+
+/// Loopback interface statistics.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoopbackStats {
+    /// Packets received on `lo`.
+    pub rx_packets: u64,
+    /// Packets transmitted on `lo`.
+    pub tx_packets: u64,
+    /// Payload bytes received on `lo`.
+    pub rx_bytes: u64,
+    /// Payload bytes transmitted on `lo`.
+    pub tx_bytes: u64,
+    /// Packets dropped because the receive queue was full.
+    pub dropped: u64,
+}
+
 /// Initialize interfaces.
+///
+/// Registers the loopback interface if it is not already present.
 pub fn init() -> Result<(), NetworkError> {
+    let mut interfaces = INTERFACES.write();
+    if !interfaces.iter().any(|i| i.name == LOOPBACK_NAME) {
+        interfaces.push(InterfaceConfig {
+            name: String::from(LOOPBACK_NAME),
+            mac: MacAddr([0; 6]),
+            ipv4: Some(Ipv4Addr::LOCALHOST),
+            netmask: Some(Ipv4Addr::new(255, 0, 0, 0)),
+            gateway: None,
+            ipv6: Some(Ipv6Addr::LOCALHOST),
+            ipv6_prefix_len: Some(128),
+            ipv6_gateway: None,
+            dns_servers: Vec::new(),
+            mtu: LOOPBACK_MTU,
+        });
+    }
     Ok(())
 }
 
@@ -46,3 +130,64 @@ pub fn configure_interface(
         Err(NetworkError::InterfaceNotFound(name.into()))
     }
 }
+
+/// Configure an interface's IPv6 address.
+pub fn configure_interface_v6(
+    name: &str,
+    ip: Ipv6Addr,
+    prefix_len: u8,
+    gateway: Option<Ipv6Addr>,
+) -> Result<(), NetworkError> {
+    if prefix_len > 128 {
+        return Err(NetworkError::InvalidAddress);
+    }
+    let mut interfaces = INTERFACES.write();
+    if let Some(iface) = interfaces.iter_mut().find(|i| i.name == name) {
+        iface.ipv6 = Some(ip);
+        iface.ipv6_prefix_len = Some(prefix_len);
+        iface.ipv6_gateway = gateway;
+        Ok(())
+    } else {
+        Err(NetworkError::InterfaceNotFound(name.into()))
+    }
+}
+
+/// Check whether packets to `ip` are routed over the loopback interface.
+pub fn is_loopback_route(ip: &IpAddr) -> bool {
+    ip.is_loopback()
+}
+
+/// Transmit a packet on the loopback interface.
+///
+/// The packet is short-circuited straight into the loopback receive
+/// queue; it is delivered once the socket layer drains the queue.
+pub fn loopback_transmit(packet: LoopbackPacket) -> Result<(), NetworkError> {
+    if !is_loopback_route(&packet.dst.ip) {
+        return Err(NetworkError::NetworkUnreachable);
+    }
+
+    let mut queue = LOOPBACK_RX.lock();
+    let mut stats = LOOPBACK_STATS.lock();
+    if queue.len() >= LOOPBACK_QUEUE_CAP {
+        stats.dropped += 1;
+        return Err(NetworkError::WouldBlock);
+    }
+    stats.tx_packets += 1;
+    stats.tx_bytes += packet.payload.len() as u64;
+    queue.push_back(packet);
+    Ok(())
+}
+
+/// Take the next packet from the loopback receive queue.
+pub fn loopback_receive() -> Option<LoopbackPacket> {
+    let packet = LOOPBACK_RX.lock().pop_front()?;
+    let mut stats = LOOPBACK_STATS.lock();
+    stats.rx_packets += 1;
+    stats.rx_bytes += packet.payload.len() as u64;
+    Some(packet)
+}
+
+/// Snapshot of the loopback interface counters.
+pub fn loopback_stats() -> LoopbackStats {
+    *LOOPBACK_STATS.lock()
+}
@@ -8,7 +8,7 @@
 //! The network stack is organized into:
 //!
 //! - `driver`: Network device drivers (VirtIO-Net, E1000)
-//! - `interface`: Network interface management
+//! - `interface`: Network interface management and the loopback path
 //! - `socket`: Socket API implementation
 //! - `tcp`: TCP protocol handling
 //! - `udp`: UDP protocol handling
@@ -20,10 +20,12 @@
 
 extern crate alloc;
 
+pub mod deflate;
 pub mod dhcp;
 pub mod dns;
 pub mod driver;
 pub mod http;
+pub mod http2;
 pub mod interface;
 pub mod socket;
 pub mod tcp;
@@ -117,6 +119,23 @@ impl Ipv4Addr {
             _ => false,
         }
     }
+
+    /// Parse dotted-quad notation (`192.0.2.1`).
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut octets = [0u8; 4];
+        let mut parts = text.split('.');
+        for octet in &mut octets {
+            let part = parts.next()?;
+            if part.is_empty() || part.len() > 3 || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            *octet = part.parse().ok()?;
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Ipv4Addr(octets))
+    }
 }
 
 /// IPv6 address.
@@ -130,10 +149,91 @@ impl Ipv6Addr {
     /// Loopback address (::1).
     pub const LOCALHOST: Ipv6Addr = Ipv6Addr([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
 
+    /// Create an IPv6 address from its eight 16-bit segments.
+    pub const fn from_segments(segments: [u16; 8]) -> Self {
+        let mut octets = [0u8; 16];
+        let mut i = 0;
+        while i < 8 {
+            let [hi, lo] = segments[i].to_be_bytes();
+            octets[i * 2] = hi;
+            octets[i * 2 + 1] = lo;
+            i += 1;
+        }
+        Ipv6Addr(octets)
+    }
+
     /// Get the octets.
     pub fn octets(&self) -> [u8; 16] {
         self.0
     }
+
+    /// Get the eight 16-bit segments.
+    pub fn segments(&self) -> [u16; 8] {
+        let mut segments = [0u16; 8];
+        for (segment, pair) in segments.iter_mut().zip(self.0.chunks_exact(2)) {
+            *segment = u16::from_be_bytes([pair[0], pair[1]]);
+        }
+        segments
+    }
+
+    /// Check if this is a link-local unicast address (`fe80::/10`).
+    pub fn is_link_local(&self) -> bool {
+        self.0[0] == 0xFE && (self.0[1] & 0xC0) == 0x80
+    }
+
+    /// Parse the text form of RFC 4291 section 2.2: colon-separated hex
+    /// segments, at most one `::` run of zeros, and optionally a trailing
+    /// dotted quad (`::ffff:192.0.2.1`). Zone IDs are not supported.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut segments = [0u16; 8];
+        match text.split_once("::") {
+            Some((head, tail)) => {
+                let mut head_segments = [0u16; 8];
+                let head_len = parse_segments(head, false, &mut head_segments)?;
+                let mut tail_segments = [0u16; 8];
+                let tail_len = parse_segments(tail, true, &mut tail_segments)?;
+                // `::` stands for at least one zero segment
+                if head_len + tail_len > 7 {
+                    return None;
+                }
+                segments[..head_len].copy_from_slice(&head_segments[..head_len]);
+                segments[8 - tail_len..].copy_from_slice(&tail_segments[..tail_len]);
+            }
+            None => {
+                if parse_segments(text, true, &mut segments)? != 8 {
+                    return None;
+                }
+            }
+        }
+        Some(Self::from_segments(segments))
+    }
+}
+
+/// Parse colon-separated IPv6 segments into `out`, returning how many
+/// were written. An empty `text` has none; a trailing dotted quad counts
+/// as two when `allow_ipv4` is set.
+fn parse_segments(text: &str, allow_ipv4: bool, out: &mut [u16; 8]) -> Option<usize> {
+    if text.is_empty() {
+        return Some(0);
+    }
+
+    let mut count = 0;
+    let mut parts = text.split(':').peekable();
+    while let Some(part) = parts.next() {
+        if allow_ipv4 && parts.peek().is_none() && part.contains('.') {
+            let [a, b, c, d] = Ipv4Addr::parse(part)?.octets();
+            *out.get_mut(count)? = u16::from_be_bytes([a, b]);
+            *out.get_mut(count + 1)? = u16::from_be_bytes([c, d]);
+            count += 2;
+        } else {
+            if part.is_empty() || part.len() > 4 || !part.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            *out.get_mut(count)? = u16::from_str_radix(part, 16).ok()?;
+            count += 1;
+        }
+    }
+    Some(count)
 }
 
 /// IP address (v4 or v6).
@@ -148,6 +248,35 @@ impl IpAddr {
     pub const fn from_v4_bytes(bytes: [u8; 4]) -> Self {
         IpAddr::V4(Ipv4Addr(bytes))
     }
+
+    /// Parse an IPv4 or IPv6 address literal.
+    pub fn parse(text: &str) -> Option<Self> {
+        match Ipv4Addr::parse(text) {
+            Some(v4) => Some(IpAddr::V4(v4)),
+            None => Ipv6Addr::parse(text).map(IpAddr::V6),
+        }
+    }
+
+    /// Check if this is an IPv6 address.
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self, IpAddr::V6(_))
+    }
+
+    /// Check if this is a loopback address (`127.0.0.0/8` or `::1`).
+    pub fn is_loopback(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => v4.is_loopback(),
+            IpAddr::V6(v6) => *v6 == Ipv6Addr::LOCALHOST,
+        }
+    }
+
+    /// Check if this is the unspecified (wildcard) address.
+    pub fn is_unspecified(&self) -> bool {
+        match self {
+            IpAddr::V4(v4) => *v4 == Ipv4Addr::UNSPECIFIED,
+            IpAddr::V6(v6) => *v6 == Ipv6Addr::UNSPECIFIED,
+        }
+    }
 }
 
 /// Socket address.
@@ -172,6 +301,14 @@ impl SocketAddr {
             port,
         }
     }
+
+    /// Create an IPv6 socket address.
+    pub fn v6(ip: Ipv6Addr, port: u16) -> Self {
+        SocketAddr {
+            ip: IpAddr::V6(ip),
+            port,
+        }
+    }
 }
 
 /// MAC address.
@@ -212,6 +349,12 @@ pub struct InterfaceConfig {
     pub netmask: Option<Ipv4Addr>,
     /// IPv4 gateway.
     pub gateway: Option<Ipv4Addr>,
+    /// IPv6 address.
+    pub ipv6: Option<Ipv6Addr>,
+    /// IPv6 prefix length.
+    pub ipv6_prefix_len: Option<u8>,
+    /// IPv6 gateway.
+    pub ipv6_gateway: Option<Ipv6Addr>,
     /// DNS servers.
     pub dns_servers: Vec<Ipv4Addr>,
     /// MTU.
@@ -232,5 +375,6 @@ pub fn interfaces() -> Vec<InterfaceConfig> {
 
 // Re-export HTTP types for convenience
 pub use http::{
-    HttpClient, HttpError, HttpMethod, HttpParser, HttpRequest, HttpResponse, StatusCode, Url,
+    AuthChallenge, ConnectionPool, HeaderMap, HttpClient, HttpConnection, HttpError, HttpMethod,
+    HttpParser, HttpRequest, HttpResponse, PoolConfig, PoolKey, PoolStats, StatusCode, Url,
 };
@@ -5,13 +5,36 @@
 //! one end of a connected pair is available via `recv()` on the other
 //! end.  This enables in-guest TCP echo testing without requiring
 //! real loopback routing (QEMU SLIRP does not route 127.0.0.1).
+//!
+//! Traffic addressed to a loopback address goes through the `lo`
+//! interface instead: connection requests, stream payload and datagrams
+//! are transmitted as [`LoopbackPacket`]s and delivered by
+//! [`poll_loopback`], which matches them against bound sockets the same
+//! way a real stack would (a missing listener refuses the connection).
+//!
+//! Stream sockets carry their payload through a [`TcpConnection`]: data
+//! passed to [`send`] is segmented by the connection and its segments are
+//! handed to the peer's connection, or sent over `lo` for loopback
+//! addresses. [`poll_tcp`] runs the connection timers, so lost segments
+//! are retransmitted and a connection whose user timeout expires fails
+//! with `TimedOut`. [`set_link`] routes a socket's segments through a
+//! [`NetworkDevice`] instead, such as one that drops packets. Statistics
+//! are read with [`tcp_stats`] and options are set with [`set_option`].
+//!
+//! Readiness of many sockets at once is waited for with a [`Poller`],
+//! the in-kernel counterpart of `epoll`.
 
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::vec;
 use alloc::vec::Vec;
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use spin::Mutex;
 
-use crate::{IpAddr, Ipv4Addr, NetworkError, SocketAddr};
+use crate::driver::NetworkDevice;
+use crate::interface::{self, LoopbackKind, LoopbackPacket};
+use crate::tcp::{self, ConnectionStats, TcpConnection, TcpOptions, TcpSegment};
+use crate::{IpAddr, Ipv4Addr, Ipv6Addr, NetworkError, SocketAddr};
 
 /// Socket handle.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -37,6 +60,7 @@ pub enum SocketState {
 }
 
 /// Poll readiness flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PollFlags(u32);
 
 impl PollFlags {
@@ -64,6 +88,16 @@ impl PollFlags {
     pub fn union(self, other: PollFlags) -> PollFlags {
         PollFlags(self.0 | other.0)
     }
+
+    /// Flags set in both `self` and `other`.
+    pub fn intersection(self, other: PollFlags) -> PollFlags {
+        PollFlags(self.0 & other.0)
+    }
+
+    /// Whether no flag is set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
 }
 
 /// Per-socket receive buffer capacity.
@@ -76,6 +110,10 @@ static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
 /// Accept queue: maps listening socket handle → vec of ready connected handles.
 static ACCEPT_QUEUE: Mutex<BTreeMap<u32, Vec<SocketHandle>>> = Mutex::new(BTreeMap::new());
 
+/// Latest time passed to [`poll_tcp`], in milliseconds: the clock of the
+/// TCP connections.
+static TCP_NOW: AtomicU64 = AtomicU64::new(0);
+
 /// A socket.
 pub struct Socket {
     handle: SocketHandle,
@@ -89,10 +127,16 @@ pub struct Socket {
     recv_buf: Vec<u8>,
     /// Socket options.
     opts: SocketOptions,
+    /// TCP state machine of a stream socket.
+    tcp: Option<TcpConnection>,
+    /// Device carrying the TCP segments to the peer, set by [`set_link`].
+    link: Option<Box<dyn NetworkDevice>>,
     /// Whether the read side is shut down.
     shut_rd: bool,
     /// Whether the write side is shut down.
     shut_wr: bool,
+    /// Whether the connected peer has closed its end.
+    peer_closed: bool,
 }
 
 /// Minimal socket option storage.
@@ -100,6 +144,11 @@ pub struct Socket {
 struct SocketOptions {
     reuse_addr: bool,
     keep_alive: bool,
+    nodelay: bool,
+    /// Seconds between keepalive probes.
+    keepalive_interval: u32,
+    /// Milliseconds; 0 means the default.
+    user_timeout: u32,
 }
 
 impl Default for SocketOptions {
@@ -107,26 +156,208 @@ impl Default for SocketOptions {
         Self {
             reuse_addr: false,
             keep_alive: false,
+            nodelay: false,
+            keepalive_interval: (tcp::DEFAULT_KEEPALIVE_INTERVAL_MS / 1000) as u32,
+            user_timeout: 0,
+        }
+    }
+}
+
+impl SocketOptions {
+    /// The options of the socket's TCP connection.
+    fn tcp(&self) -> TcpOptions {
+        TcpOptions {
+            nodelay: self.nodelay,
+            keepalive_interval: self
+                .keep_alive
+                .then_some(u64::from(self.keepalive_interval) * 1000),
+            user_timeout: match self.user_timeout {
+                0 => tcp::DEFAULT_USER_TIMEOUT_MS,
+                ms => u64::from(ms),
+            },
+        }
+    }
+}
+
+/// A socket option, as set with [`set_option`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketOption {
+    /// `SO_REUSEADDR`: allow binding an address that is still in use.
+    ReuseAddr(bool),
+    /// `SO_KEEPALIVE`: probe idle connections.
+    KeepAlive(bool),
+    /// `TCP_NODELAY`: send small segments at once instead of coalescing
+    /// them.
+    NoDelay(bool),
+    /// `TCP_KEEPINTVL`: seconds between keepalive probes.
+    KeepAliveInterval(u32),
+    /// `TCP_USER_TIMEOUT`: milliseconds sent data may go unacknowledged
+    /// before the connection fails with `TimedOut`; 0 restores the
+    /// default.
+    UserTimeout(u32),
+}
+
+impl Socket {
+    /// Create a closed, unbound socket.
+    fn new(handle: SocketHandle, socket_type: SocketType) -> Self {
+        Socket {
+            handle,
+            socket_type,
+            state: SocketState::Closed,
+            local_addr: None,
+            remote_addr: None,
+            peer_handle: None,
+            recv_buf: Vec::new(),
+            opts: SocketOptions::default(),
+            tcp: (socket_type == SocketType::Stream).then(TcpConnection::new),
+            link: None,
+            shut_rd: false,
+            shut_wr: false,
+            peer_closed: false,
+        }
+    }
+
+    /// Whether this socket's local address accepts traffic sent to `dst`.
+    fn is_bound_to(&self, dst: &SocketAddr) -> bool {
+        match &self.local_addr {
+            Some(la) => la.port == dst.port && (la.ip.is_unspecified() || la.ip == dst.ip),
+            None => false,
+        }
+    }
+}
+
+/// Ephemeral port assigned to an unbound socket on connect/sendto.
+fn ephemeral_port(handle: SocketHandle) -> u16 {
+    49152 + (handle.0 as u16 % 16384)
+}
+
+/// Initial TCP sequence number of a socket's connection.
+fn initial_seq(handle: SocketHandle) -> u32 {
+    handle.0.wrapping_mul(64_000)
+}
+
+/// Run the TCP handshake between a connecting socket and the socket
+/// accepting it. Both ends are in this stack, so their segments are
+/// handed over directly.
+fn handshake(client: &mut Socket, server: &mut Socket) {
+    let (Some(client_addr), Some(server_addr)) = (client.local_addr, server.local_addr) else {
+        return;
+    };
+    let (Some(client_tcp), Some(server_tcp)) = (&mut client.tcp, &mut server.tcp) else {
+        return;
+    };
+    *client_tcp = TcpConnection::new();
+    client_tcp.set_options(client.opts.tcp());
+    server_tcp.set_options(server.opts.tcp());
+
+    let now = TCP_NOW.load(Ordering::Relaxed);
+    let _ = server_tcp.listen(server_addr.port, initial_seq(server.handle));
+    let _ = client_tcp.connect(
+        client_addr.port,
+        server_addr.port,
+        initial_seq(client.handle),
+        now,
+    );
+    loop {
+        let to_server = client_tcp.take_outbound();
+        let to_client = server_tcp.take_outbound();
+        if to_server.is_empty() && to_client.is_empty() {
+            break;
+        }
+        for seg in &to_server {
+            server_tcp.on_segment(seg, now);
+        }
+        for seg in &to_client {
+            client_tcp.on_segment(seg, now);
+        }
+    }
+}
+
+/// Carry the TCP segments queued by `handle`, and the replies they cause,
+/// to their destination.
+///
+/// Segments pass through the socket's link device if it has one. Loopback
+/// connections send them over `lo`, to be delivered by [`poll_loopback`];
+/// the others hand them straight to the peer socket.
+fn transmit_segments(sockets: &mut BTreeMap<SocketHandle, Socket>, handle: SocketHandle) {
+    let mut pending = vec![handle];
+    while let Some(handle) = pending.pop() {
+        let Some(socket) = sockets.get_mut(&handle) else {
+            continue;
+        };
+        let Some(tcp) = &mut socket.tcp else {
+            continue;
+        };
+        let mut segments = tcp.take_outbound();
+        if segments.is_empty() {
+            continue;
+        }
+
+        if let Some(link) = &mut socket.link {
+            for seg in &segments {
+                let _ = link.transmit(&seg.to_bytes());
+            }
+            segments.clear();
+            let mut buffer = [0u8; TcpSegment::HEADER_LEN + tcp::MSS];
+            while link.can_receive() {
+                match link.receive(&mut buffer) {
+                    Ok(len) => segments.extend(TcpSegment::parse(&buffer[..len])),
+                    Err(_) => break,
+                }
+            }
+        }
+
+        let loopback = socket
+            .local_addr
+            .zip(socket.remote_addr)
+            .filter(|(_, remote)| remote.ip.is_loopback());
+        if let Some((src, dst)) = loopback {
+            for seg in segments {
+                let _ = interface::loopback_transmit(LoopbackPacket {
+                    kind: LoopbackKind::TcpData,
+                    src,
+                    dst,
+                    payload: seg.to_bytes(),
+                });
+            }
+        } else if let Some(peer) = socket.peer_handle {
+            if deliver_segments(sockets, peer, &segments) {
+                pending.push(peer);
+            }
         }
     }
 }
 
+/// Feed received segments to the TCP connection of `handle`, moving the
+/// data it accepts into the socket's receive buffer.
+///
+/// Returns `false` if there is no such stream socket.
+fn deliver_segments(
+    sockets: &mut BTreeMap<SocketHandle, Socket>,
+    handle: SocketHandle,
+    segments: &[TcpSegment],
+) -> bool {
+    let Some(socket) = sockets.get_mut(&handle) else {
+        return false;
+    };
+    let Some(tcp) = &mut socket.tcp else {
+        return false;
+    };
+    let now = TCP_NOW.load(Ordering::Relaxed);
+    for seg in segments {
+        tcp.on_segment(seg, now);
+    }
+    let mut buffer = [0u8; 1024];
+    while let Ok(len @ 1..) = tcp.recv(&mut buffer) {
+        socket.recv_buf.extend_from_slice(&buffer[..len]);
+    }
+    true
+}
+
 /// Create a new socket.
 pub fn create(socket_type: SocketType) -> Result<SocketHandle, NetworkError> {
     let handle = SocketHandle(NEXT_HANDLE.fetch_add(1, Ordering::Relaxed));
-    let socket = Socket {
-        handle,
-        socket_type,
-        state: SocketState::Closed,
-        local_addr: None,
-        remote_addr: None,
-        peer_handle: None,
-        recv_buf: Vec::new(),
-        opts: SocketOptions::default(),
-        shut_rd: false,
-        shut_wr: false,
-    };
-    SOCKETS.lock().insert(handle, socket);
+    SOCKETS.lock().insert(handle, Socket::new(handle, socket_type));
     Ok(handle)
 }
 
@@ -144,6 +375,11 @@ pub fn listen(handle: SocketHandle, _backlog: u32) -> Result<(), NetworkError> {
     let mut sockets = SOCKETS.lock();
     let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
     socket.state = SocketState::Listening;
+    if let (Some(tcp), Some(local)) = (&mut socket.tcp, socket.local_addr) {
+        *tcp = TcpConnection::new();
+        tcp.set_options(socket.opts.tcp());
+        let _ = tcp.listen(local.port, initial_seq(handle));
+    }
     drop(sockets);
     // Initialize an empty accept queue for this listener.
     ACCEPT_QUEUE.lock().entry(handle.0).or_insert_with(Vec::new);
@@ -155,18 +391,27 @@ pub fn listen(handle: SocketHandle, _backlog: u32) -> Result<(), NetworkError> {
 /// For kernel-internal testing, if the target address matches a
 /// listening socket's local address, we create a connected pair
 /// (the "accepted" socket is pushed onto the listener's accept queue).
+///
+/// Loopback destinations are routed over the `lo` interface and fail
+/// with `ConnectionRefused` when nothing listens on the target address.
 pub fn connect(handle: SocketHandle, addr: SocketAddr) -> Result<(), NetworkError> {
+    if interface::is_loopback_route(&addr.ip) {
+        return connect_loopback(handle, addr);
+    }
+
     let mut sockets = SOCKETS.lock();
     let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
     socket.remote_addr = Some(addr);
     socket.state = SocketState::Connected;
 
-    // Assign a local ephemeral address if not already bound.
+    // Assign a local ephemeral address of the peer's family if not
+    // already bound (QEMU user-mode networking's guest addresses).
     if socket.local_addr.is_none() {
-        socket.local_addr = Some(SocketAddr::new(
-            IpAddr::V4(Ipv4Addr::new(10, 0, 2, 15)),
-            49152 + (handle.0 as u16 % 16384),
-        ));
+        let local_ip = match addr.ip {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::new(10, 0, 2, 15)),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from_segments([0xFEC0, 0, 0, 0, 0, 0, 0, 0x15])),
+        };
+        socket.local_addr = Some(SocketAddr::new(local_ip, ephemeral_port(handle)));
     }
 
     let client_local = socket.local_addr;
@@ -188,25 +433,22 @@ pub fn connect(handle: SocketHandle, addr: SocketAddr) -> Result<(), NetworkErro
     if let Some(lh) = listener_handle {
         // Create a new socket for the accepted side of the connection.
         let peer = SocketHandle(NEXT_HANDLE.fetch_add(1, Ordering::Relaxed));
-        let listener_local = sockets.get(&lh).and_then(|s| s.local_addr);
-        let peer_sock = Socket {
-            handle: peer,
-            socket_type: client_type,
-            state: SocketState::Connected,
-            local_addr: listener_local,
-            remote_addr: client_local,
-            peer_handle: Some(handle),
-            recv_buf: Vec::new(),
-            opts: SocketOptions::default(),
-            shut_rd: false,
-            shut_wr: false,
-        };
-        sockets.insert(peer, peer_sock);
+        let listener = sockets.get(&lh);
+        let listener_local = listener.and_then(|s| s.local_addr);
+        let listener_opts = listener.map(|s| s.opts).unwrap_or_default();
+        let mut peer_sock = Socket::new(peer, client_type);
+        peer_sock.state = SocketState::Connected;
+        peer_sock.local_addr = listener_local;
+        peer_sock.remote_addr = client_local;
+        peer_sock.peer_handle = Some(handle);
+        peer_sock.opts = listener_opts;
 
         // Link the client to the peer.
         if let Some(cli) = sockets.get_mut(&handle) {
             cli.peer_handle = Some(peer);
+            handshake(cli, &mut peer_sock);
         }
+        sockets.insert(peer, peer_sock);
 
         drop(sockets);
 
@@ -219,6 +461,142 @@ pub fn connect(handle: SocketHandle, addr: SocketAddr) -> Result<(), NetworkErro
     Ok(())
 }
 
+/// Local address to use as the source of loopback traffic to `dst`.
+///
+/// Unbound sockets get an ephemeral port; a wildcard bind is narrowed to
+/// the destination's loopback address so replies can be matched.
+fn loopback_source(socket: &mut Socket, dst: &SocketAddr) -> SocketAddr {
+    let local = match socket.local_addr {
+        Some(la) if la.ip.is_unspecified() => SocketAddr::new(dst.ip, la.port),
+        Some(la) => la,
+        None => SocketAddr::new(dst.ip, ephemeral_port(socket.handle)),
+    };
+    socket.local_addr = Some(local);
+    local
+}
+
+/// Connect to a loopback address by sending a SYN over `lo`.
+fn connect_loopback(handle: SocketHandle, addr: SocketAddr) -> Result<(), NetworkError> {
+    let mut sockets = SOCKETS.lock();
+    let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
+    if socket.state == SocketState::Connected {
+        return Err(NetworkError::AlreadyConnected);
+    }
+    let src = loopback_source(socket, &addr);
+    socket.remote_addr = Some(addr);
+
+    // UDP "connect" only fixes the default destination.
+    if socket.socket_type == SocketType::Datagram {
+        socket.state = SocketState::Connected;
+        return Ok(());
+    }
+
+    socket.state = SocketState::Connecting;
+    drop(sockets);
+
+    interface::loopback_transmit(LoopbackPacket {
+        kind: LoopbackKind::TcpSyn,
+        src,
+        dst: addr,
+        payload: Vec::new(),
+    })?;
+    poll_loopback();
+
+    let mut sockets = SOCKETS.lock();
+    let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
+    if socket.state == SocketState::Connected {
+        Ok(())
+    } else {
+        socket.state = SocketState::Closed;
+        socket.remote_addr = None;
+        Err(NetworkError::ConnectionRefused)
+    }
+}
+
+/// Drain the loopback receive queue, delivering each packet to the
+/// socket it is addressed to.
+pub fn poll_loopback() {
+    while let Some(packet) = interface::loopback_receive() {
+        let mut sockets = SOCKETS.lock();
+        match packet.kind {
+            LoopbackKind::TcpSyn => {
+                if let Some((lh, peer)) = deliver_syn(&mut sockets, &packet) {
+                    drop(sockets);
+                    ACCEPT_QUEUE.lock().entry(lh.0).or_insert_with(Vec::new).push(peer);
+                }
+            }
+            LoopbackKind::TcpData => {
+                let target = sockets.iter().find_map(|(&h, s)| {
+                    (s.socket_type == SocketType::Stream
+                        && s.state == SocketState::Connected
+                        && s.local_addr == Some(packet.dst)
+                        && s.remote_addr == Some(packet.src))
+                    .then_some(h)
+                });
+                if let (Some(target), Ok(seg)) = (target, TcpSegment::parse(&packet.payload)) {
+                    deliver_segments(&mut sockets, target, &[seg]);
+                    transmit_segments(&mut sockets, target);
+                }
+            }
+            LoopbackKind::Udp => {
+                let target = sockets
+                    .values_mut()
+                    .find(|s| s.socket_type == SocketType::Datagram && s.is_bound_to(&packet.dst));
+                if let Some(target) = target {
+                    let space = SOCKET_BUF_CAP.saturating_sub(target.recv_buf.len());
+                    let len = packet.payload.len().min(space);
+                    target.recv_buf.extend_from_slice(&packet.payload[..len]);
+                }
+            }
+        }
+    }
+}
+
+/// Complete a loopback connection request.
+///
+/// Returns the listener and the newly accepted socket, or `None` after
+/// marking the connecting socket as refused.
+fn deliver_syn(
+    sockets: &mut BTreeMap<SocketHandle, Socket>,
+    packet: &LoopbackPacket,
+) -> Option<(SocketHandle, SocketHandle)> {
+    let client = sockets.iter().find_map(|(&h, s)| {
+        (s.socket_type == SocketType::Stream
+            && s.state == SocketState::Connecting
+            && s.local_addr == Some(packet.src)
+            && s.remote_addr == Some(packet.dst))
+        .then_some(h)
+    })?;
+    let listener = sockets.iter().find_map(|(&h, s)| {
+        (s.socket_type == SocketType::Stream
+            && s.state == SocketState::Listening
+            && s.is_bound_to(&packet.dst))
+        .then_some(h)
+    });
+
+    let Some(listener) = listener else {
+        // RST: leave the client in `Connecting`; `connect_loopback` turns
+        // that into `ConnectionRefused`.
+        return None;
+    };
+
+    let peer = SocketHandle(NEXT_HANDLE.fetch_add(1, Ordering::Relaxed));
+    let mut peer_sock = Socket::new(peer, SocketType::Stream);
+    peer_sock.state = SocketState::Connected;
+    peer_sock.local_addr = Some(packet.dst);
+    peer_sock.remote_addr = Some(packet.src);
+    peer_sock.peer_handle = Some(client);
+    peer_sock.opts = sockets.get(&listener).map(|s| s.opts).unwrap_or_default();
+
+    if let Some(cli) = sockets.get_mut(&client) {
+        cli.peer_handle = Some(peer);
+        cli.state = SocketState::Connected;
+        handshake(cli, &mut peer_sock);
+    }
+    sockets.insert(peer, peer_sock);
+    Some((listener, peer))
+}
+
 /// Accept a connection on a listening socket.
 ///
 /// Returns a new `SocketHandle` for the accepted connection, or
@@ -235,40 +613,60 @@ pub fn accept(handle: SocketHandle) -> Result<SocketHandle, NetworkError> {
 
 /// Send data on a connected socket.
 ///
-/// Writes data into the peer socket's receive buffer so that the
-/// peer can `recv()` it.
+/// Stream data goes through the socket's TCP connection and is limited
+/// by the space left in the peer's receive buffer. Loopback connections
+/// carry the segments over `lo`. Fails with the connection's error, such
+/// as `TimedOut`, once it has failed.
 pub fn send(handle: SocketHandle, data: &[u8]) -> Result<usize, NetworkError> {
-    let sockets = SOCKETS.lock();
+    let mut sockets = SOCKETS.lock();
     let socket = sockets.get(&handle).ok_or(NetworkError::NotConnected)?;
+    if socket.socket_type == SocketType::Datagram {
+        if let Some(remote) = socket.remote_addr.filter(|r| r.ip.is_loopback()) {
+            drop(sockets);
+            return sendto_dgram(handle, data, remote);
+        }
+    }
     if socket.state != SocketState::Connected {
         return Err(NetworkError::NotConnected);
     }
-    if socket.shut_wr {
+    if socket.shut_wr || socket.peer_closed {
         return Err(NetworkError::ConnectionReset);
     }
     let peer = socket.peer_handle.ok_or(NetworkError::NotConnected)?;
-    drop(sockets);
 
-    // Write into the peer's recv buffer.
-    let mut sockets = SOCKETS.lock();
     let peer_sock = sockets.get_mut(&peer).ok_or(NetworkError::NotConnected)?;
     let space = SOCKET_BUF_CAP.saturating_sub(peer_sock.recv_buf.len());
     let to_write = data.len().min(space);
     if to_write == 0 {
         return Err(NetworkError::WouldBlock);
     }
-    peer_sock.recv_buf.extend_from_slice(&data[..to_write]);
-    Ok(to_write)
+
+    let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
+    let Some(tcp) = &mut socket.tcp else {
+        // Write into the peer's recv buffer.
+        let peer_sock = sockets.get_mut(&peer).ok_or(NetworkError::NotConnected)?;
+        peer_sock.recv_buf.extend_from_slice(&data[..to_write]);
+        return Ok(to_write);
+    };
+    let sent = tcp.send(&data[..to_write], TCP_NOW.load(Ordering::Relaxed))?;
+    transmit_segments(&mut sockets, handle);
+    drop(sockets);
+    poll_loopback();
+    Ok(sent)
 }
 
 /// Receive data from a socket.
 ///
-/// Drains data from the socket's own receive buffer.
+/// Drains data from the socket's own receive buffer. Once that is empty,
+/// a failed TCP connection reports its error.
 pub fn recv(handle: SocketHandle, buffer: &mut [u8]) -> Result<usize, NetworkError> {
     let mut sockets = SOCKETS.lock();
     let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
     if socket.recv_buf.is_empty() {
-        if socket.shut_rd || socket.state == SocketState::Closed {
+        if let Some(error) = socket.tcp.as_ref().and_then(TcpConnection::error) {
+            return Err(error);
+        }
+        if socket.shut_rd || socket.peer_closed || socket.state == SocketState::Closed {
             return Ok(0); // EOF
         }
         return Err(NetworkError::WouldBlock);
@@ -317,44 +715,121 @@ pub fn getpeername(handle: SocketHandle) -> Result<SocketAddr, NetworkError> {
 
 /// Set a socket option.
 ///
+/// TCP options on a datagram socket fail with `NotImplemented`
+/// (ENOPROTOOPT), and a zero keepalive interval with `InvalidAddress`.
+pub fn set_option(handle: SocketHandle, option: SocketOption) -> Result<(), NetworkError> {
+    let mut sockets = SOCKETS.lock();
+    let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
+    let opts = &mut socket.opts;
+    match option {
+        SocketOption::ReuseAddr(on) => opts.reuse_addr = on,
+        SocketOption::KeepAlive(on) => opts.keep_alive = on,
+        _ if socket.tcp.is_none() => return Err(NetworkError::NotImplemented),
+        SocketOption::NoDelay(on) => opts.nodelay = on,
+        SocketOption::KeepAliveInterval(0) => return Err(NetworkError::InvalidAddress),
+        SocketOption::KeepAliveInterval(secs) => opts.keepalive_interval = secs,
+        SocketOption::UserTimeout(ms) => opts.user_timeout = ms,
+    }
+    let options = socket.opts.tcp();
+    if let Some(tcp) = &mut socket.tcp {
+        tcp.set_options(options);
+    }
+    Ok(())
+}
+
+/// Set a socket option by its `setsockopt` level and name.
+///
 /// Supported: `SO_REUSEADDR` (level=1, optname=2),
-/// `SO_KEEPALIVE` (level=1, optname=9).
+/// `SO_KEEPALIVE` (level=1, optname=9), `TCP_NODELAY` (level=6,
+/// optname=1), `TCP_KEEPINTVL` (level=6, optname=5) and
+/// `TCP_USER_TIMEOUT` (level=6, optname=18). See [`set_option`].
 pub fn setsockopt(
     handle: SocketHandle,
     level: u32,
     optname: u32,
     value: u32,
 ) -> Result<(), NetworkError> {
-    let mut sockets = SOCKETS.lock();
-    let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
-    if level == 1 {
+    let option = match (level, optname) {
         // SOL_SOCKET
-        match optname {
-            2 => socket.opts.reuse_addr = value != 0, // SO_REUSEADDR
-            9 => socket.opts.keep_alive = value != 0, // SO_KEEPALIVE
-            20 | 21 => {}                              // SO_RCVTIMEO / SO_SNDTIMEO (no-op for now)
-            _ => return Err(NetworkError::NotImplemented), // ENOPROTOOPT
-        }
-        Ok(())
-    } else {
-        Err(NetworkError::NotImplemented)
-    }
+        (1, 2) => SocketOption::ReuseAddr(value != 0), // SO_REUSEADDR
+        (1, 9) => SocketOption::KeepAlive(value != 0), // SO_KEEPALIVE
+        (1, 20 | 21) => return get_state(handle).map(drop), // SO_RCVTIMEO / SO_SNDTIMEO (no-op for now)
+        // IPPROTO_TCP
+        (6, 1) => SocketOption::NoDelay(value != 0), // TCP_NODELAY
+        (6, 5) => SocketOption::KeepAliveInterval(value), // TCP_KEEPINTVL
+        (6, 18) => SocketOption::UserTimeout(value), // TCP_USER_TIMEOUT
+        _ => return Err(NetworkError::NotImplemented), // ENOPROTOOPT
+    };
+    set_option(handle, option)
 }
 
 /// Get a socket option.
 pub fn getsockopt(handle: SocketHandle, level: u32, optname: u32) -> Result<u32, NetworkError> {
     let sockets = SOCKETS.lock();
     let socket = sockets.get(&handle).ok_or(NetworkError::NotConnected)?;
-    if level == 1 {
-        match optname {
-            2 => Ok(socket.opts.reuse_addr as u32),
-            9 => Ok(socket.opts.keep_alive as u32),
-            20 | 21 => Ok(0),
-            _ => Err(NetworkError::NotImplemented),
-        }
-    } else {
-        Err(NetworkError::NotImplemented)
+    let opts = &socket.opts;
+    match (level, optname) {
+        (1, 2) => Ok(opts.reuse_addr as u32),
+        (1, 9) => Ok(opts.keep_alive as u32),
+        (1, 20 | 21) => Ok(0),
+        (6, _) if socket.tcp.is_none() => Err(NetworkError::NotImplemented),
+        (6, 1) => Ok(opts.nodelay as u32),
+        (6, 5) => Ok(opts.keepalive_interval),
+        (6, 18) => Ok(opts.user_timeout),
+        _ => Err(NetworkError::NotImplemented),
+    }
+}
+
+/// Get the TCP statistics of a stream socket.
+pub fn tcp_stats(handle: SocketHandle) -> Result<ConnectionStats, NetworkError> {
+    let sockets = SOCKETS.lock();
+    let socket = sockets.get(&handle).ok_or(NetworkError::NotConnected)?;
+    socket
+        .tcp
+        .as_ref()
+        .map(TcpConnection::stats)
+        .ok_or(NetworkError::NotImplemented)
+}
+
+/// Carry the TCP segments a stream socket sends to its peer through
+/// `device` instead of handing them over directly.
+///
+/// Each segment is transmitted on the device, and what the device then
+/// has to receive is delivered to the peer. A device that drops packets
+/// thus makes a lossy link, which the connection recovers from by
+/// retransmitting as [`poll_tcp`] runs its timers.
+pub fn set_link(handle: SocketHandle, device: Box<dyn NetworkDevice>) -> Result<(), NetworkError> {
+    let mut sockets = SOCKETS.lock();
+    let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
+    if socket.tcp.is_none() {
+        return Err(NetworkError::NotImplemented);
+    }
+    socket.link = Some(device);
+    Ok(())
+}
+
+/// Run the timers of every TCP connection at `now` milliseconds and send
+/// what they produce: retransmissions and keepalive probes.
+///
+/// The clock must be monotonic; it also times the segments sent until
+/// the next call. A connection whose sent data goes unacknowledged for
+/// its user timeout fails, and its socket then reports `TimedOut` from
+/// [`send`] and [`recv`] and [`PollFlags::ERROR`] from [`poll`].
+pub fn poll_tcp(now: u64) {
+    let now = TCP_NOW.fetch_max(now, Ordering::Relaxed).max(now);
+    let mut sockets = SOCKETS.lock();
+    let handles: Vec<SocketHandle> = sockets
+        .iter_mut()
+        .filter_map(|(&handle, socket)| {
+            let _ = socket.tcp.as_mut()?.poll(now);
+            Some(handle)
+        })
+        .collect();
+    for handle in handles {
+        transmit_segments(&mut sockets, handle);
     }
+    drop(sockets);
+    poll_loopback();
 }
 
 /// Query readiness flags for a socket.
@@ -366,12 +841,20 @@ pub fn poll(handle: SocketHandle) -> PollFlags {
     };
     let mut flags = PollFlags::empty();
 
+    // A failed connection reports its error.
+    if socket.tcp.as_ref().is_some_and(|tcp| tcp.error().is_some()) {
+        flags = flags.union(PollFlags::ERROR);
+    }
     // Readable if recv buffer is non-empty or shut_rd (EOF ready).
     if !socket.recv_buf.is_empty() || socket.shut_rd {
         flags = flags.union(PollFlags::READABLE);
     }
+    // A closed peer leaves EOF to read and nothing to write to.
+    if socket.peer_closed {
+        flags = flags.union(PollFlags::READABLE).union(PollFlags::HANGUP);
+    }
     // Writable if connected and peer buffer has space.
-    if socket.state == SocketState::Connected && !socket.shut_wr {
+    if socket.state == SocketState::Connected && !socket.shut_wr && !socket.peer_closed {
         if let Some(peer) = socket.peer_handle {
             if let Some(ps) = sockets.get(&peer) {
                 if ps.recv_buf.len() < SOCKET_BUF_CAP {
@@ -396,10 +879,131 @@ pub fn poll(handle: SocketHandle) -> PollFlags {
     flags
 }
 
+/// A readiness event reported by [`Poller::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollEvent {
+    /// The socket that is ready.
+    pub handle: SocketHandle,
+    /// Its readiness, limited to the registered interest plus
+    /// [`PollFlags::ERROR`] and [`PollFlags::HANGUP`], which are always
+    /// reported.
+    pub flags: PollFlags,
+}
+
+/// A set of sockets whose readiness is waited for together, like an
+/// `epoll` instance.
+///
+/// Readiness is level-triggered: a socket is reported by every
+/// [`poll`](Self::poll) for as long as it stays ready.
+#[derive(Debug, Default)]
+pub struct Poller {
+    interests: BTreeMap<SocketHandle, PollFlags>,
+}
+
+impl Poller {
+    /// Create an empty poller.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start watching `handle` for `interest`.
+    ///
+    /// Fails with `InvalidAddress` if the socket is already registered.
+    pub fn register(
+        &mut self,
+        handle: SocketHandle,
+        interest: PollFlags,
+    ) -> Result<(), NetworkError> {
+        if !SOCKETS.lock().contains_key(&handle) {
+            return Err(NetworkError::NotConnected);
+        }
+        if self.interests.contains_key(&handle) {
+            return Err(NetworkError::InvalidAddress);
+        }
+        self.interests.insert(handle, interest);
+        Ok(())
+    }
+
+    /// Change the interest of a registered socket.
+    pub fn modify(
+        &mut self,
+        handle: SocketHandle,
+        interest: PollFlags,
+    ) -> Result<(), NetworkError> {
+        let entry = self
+            .interests
+            .get_mut(&handle)
+            .ok_or(NetworkError::InvalidAddress)?;
+        *entry = interest;
+        Ok(())
+    }
+
+    /// Stop watching a socket.
+    pub fn deregister(&mut self, handle: SocketHandle) -> Result<(), NetworkError> {
+        self.interests
+            .remove(&handle)
+            .map(|_| ())
+            .ok_or(NetworkError::InvalidAddress)
+    }
+
+    /// Number of registered sockets.
+    pub fn len(&self) -> usize {
+        self.interests.len()
+    }
+
+    /// Whether no socket is registered.
+    pub fn is_empty(&self) -> bool {
+        self.interests.is_empty()
+    }
+
+    /// Wait until at least one registered socket is ready.
+    ///
+    /// Each round calls `poll_rx` to pull frames off the NICs (the
+    /// kernel passes `net::poll_rx`), runs the TCP timers with
+    /// [`poll_tcp`] and then drains loopback, so data that arrived since
+    /// the last call makes its socket readable.
+    /// `timeout` is in milliseconds of `now`; `None` waits forever and
+    /// `Some(0)` checks once without waiting.  Returns an empty list on
+    /// timeout.
+    pub fn poll<R, N>(&self, timeout: Option<u64>, mut poll_rx: R, mut now: N) -> Vec<PollEvent>
+    where
+        R: FnMut(),
+        N: FnMut() -> u64,
+    {
+        let deadline = timeout.map(|ms| now().saturating_add(ms));
+        loop {
+            poll_rx();
+            poll_tcp(now());
+            let events = self.ready();
+            if !events.is_empty() || deadline.is_some_and(|d| now() >= d) {
+                return events;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// The registered sockets that are ready right now.
+    fn ready(&self) -> Vec<PollEvent> {
+        let always = PollFlags::ERROR.union(PollFlags::HANGUP);
+        self.interests
+            .iter()
+            .filter_map(|(&handle, &interest)| {
+                let flags = poll(handle).intersection(interest.union(always));
+                (!flags.is_empty()).then_some(PollEvent { handle, flags })
+            })
+            .collect()
+    }
+}
+
 /// Close a socket.
+///
+/// The connected peer, if any, sees end-of-file and a hangup.
 pub fn close(handle: SocketHandle) -> Result<(), NetworkError> {
     let mut sockets = SOCKETS.lock();
     if let Some(socket) = sockets.remove(&handle) {
+        if let Some(peer) = socket.peer_handle.and_then(|p| sockets.get_mut(&p)) {
+            peer.peer_closed = true;
+        }
         // Clean up accept queue if this was a listener.
         if socket.state == SocketState::Listening {
             drop(sockets);
@@ -426,14 +1030,32 @@ pub fn get_state(handle: SocketHandle) -> Result<SocketState, NetworkError> {
 /// Send a datagram to a specific address (UDP sendto).
 ///
 /// Looks up a Datagram socket bound to `dest.port` and deposits `data`
-/// directly into its receive buffer.  Returns bytes sent.
+/// directly into its receive buffer.  Loopback destinations are sent
+/// over `lo` instead.  Returns bytes sent.
 pub fn sendto_dgram(
-    _handle: SocketHandle,
+    handle: SocketHandle,
     data: &[u8],
     dest: SocketAddr,
 ) -> Result<usize, NetworkError> {
     let mut sockets = SOCKETS.lock();
 
+    if interface::is_loopback_route(&dest.ip) {
+        let socket = sockets.get_mut(&handle).ok_or(NetworkError::NotConnected)?;
+        let src = loopback_source(socket, &dest);
+        if socket.state == SocketState::Closed {
+            socket.state = SocketState::Bound;
+        }
+        drop(sockets);
+        interface::loopback_transmit(LoopbackPacket {
+            kind: LoopbackKind::Udp,
+            src,
+            dst: dest,
+            payload: data.to_vec(),
+        })?;
+        poll_loopback();
+        return Ok(data.len());
+    }
+
     // Find a bound datagram socket listening on the target port.
     let target = sockets.iter_mut().find_map(|(h, s)| {
         if s.socket_type == SocketType::Datagram {
@@ -461,3 +1083,340 @@ pub fn sendto_dgram(
     // No matching socket found — data is silently dropped (UDP semantics).
     Ok(data.len())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MacAddr;
+    use alloc::collections::VecDeque;
+
+    fn localhost(port: u16) -> SocketAddr {
+        SocketAddr::v4(127, 0, 0, 1, port)
+    }
+
+    /// A stub driver that drops every n-th packet it transmits.
+    struct LossyLink {
+        queue: VecDeque<Vec<u8>>,
+        drop_every: usize,
+        sent: usize,
+    }
+
+    impl LossyLink {
+        fn dropping_every(drop_every: usize) -> Box<Self> {
+            Box::new(LossyLink {
+                queue: VecDeque::new(),
+                drop_every,
+                sent: 0,
+            })
+        }
+    }
+
+    impl NetworkDevice for LossyLink {
+        fn mac_address(&self) -> MacAddr {
+            MacAddr([0x52, 0x54, 0, 0x12, 0x34, 0x56])
+        }
+
+        fn mtu(&self) -> u16 {
+            1500
+        }
+
+        fn link_up(&self) -> bool {
+            self.drop_every != 1
+        }
+
+        fn transmit(&mut self, packet: &[u8]) -> Result<(), NetworkError> {
+            self.sent += 1;
+            if !self.sent.is_multiple_of(self.drop_every) {
+                self.queue.push_back(packet.to_vec());
+            }
+            Ok(())
+        }
+
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, NetworkError> {
+            let packet = self.queue.pop_front().ok_or(NetworkError::WouldBlock)?;
+            buffer[..packet.len()].copy_from_slice(&packet);
+            Ok(packet.len())
+        }
+
+        fn can_receive(&self) -> bool {
+            !self.queue.is_empty()
+        }
+
+        fn link_speed(&self) -> u32 {
+            1000
+        }
+    }
+
+    /// Connect a client to a new listener on `port`, returning the
+    /// listener, the client and the accepted socket.
+    fn connected(port: u16) -> (SocketHandle, SocketHandle, SocketHandle) {
+        let server = create(SocketType::Stream).unwrap();
+        bind(server, localhost(port)).unwrap();
+        listen(server, 5).unwrap();
+        let client = create(SocketType::Stream).unwrap();
+        connect(client, localhost(port)).unwrap();
+        let accepted = accept(server).unwrap();
+        (server, client, accepted)
+    }
+
+    #[test]
+    fn test_loopback_accepts_client_from_same_process() {
+        let server = create(SocketType::Stream).unwrap();
+        bind(server, localhost(8080)).unwrap();
+        listen(server, 5).unwrap();
+
+        let client = create(SocketType::Stream).unwrap();
+        connect(client, localhost(8080)).unwrap();
+        assert_eq!(get_state(client).unwrap(), SocketState::Connected);
+
+        let accepted = accept(server).unwrap();
+        assert_eq!(getpeername(accepted).unwrap(), getsockname(client).unwrap());
+        assert_eq!(getpeername(client).unwrap(), localhost(8080));
+
+        assert_eq!(send(client, b"ping").unwrap(), 4);
+        let mut buf = [0u8; 16];
+        let n = recv(accepted, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"ping");
+
+        assert_eq!(send(accepted, b"pong").unwrap(), 4);
+        let n = recv(client, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"pong");
+
+        close(accepted).unwrap();
+        close(client).unwrap();
+        close(server).unwrap();
+    }
+
+    #[test]
+    fn test_ipv6_loopback_and_source_address() {
+        let server_addr = SocketAddr::v6(Ipv6Addr::LOCALHOST, 8083);
+        let server = create(SocketType::Stream).unwrap();
+        bind(server, server_addr).unwrap();
+        listen(server, 5).unwrap();
+
+        let client = create(SocketType::Stream).unwrap();
+        connect(client, server_addr).unwrap();
+        let accepted = accept(server).unwrap();
+        assert_eq!(getpeername(accepted).unwrap(), getsockname(client).unwrap());
+        assert_eq!(
+            getsockname(client).unwrap().ip,
+            IpAddr::V6(Ipv6Addr::LOCALHOST)
+        );
+
+        // Connections through the NIC get a source address of the peer's family
+        let remote = create(SocketType::Stream).unwrap();
+        let remote_addr = Ipv6Addr::parse("2001:db8::80").unwrap();
+        connect(remote, SocketAddr::v6(remote_addr, 9443)).unwrap();
+        assert!(getsockname(remote).unwrap().ip.is_ipv6());
+
+        close(remote).unwrap();
+        close(accepted).unwrap();
+        close(client).unwrap();
+        close(server).unwrap();
+    }
+
+    #[test]
+    fn test_tcp_options_and_stats() {
+        let server = create(SocketType::Stream).unwrap();
+        bind(server, localhost(8084)).unwrap();
+        setsockopt(server, 6, 1, 1).unwrap(); // TCP_NODELAY
+        listen(server, 5).unwrap();
+        assert_eq!(tcp_stats(server).unwrap().state, tcp::TcpState::Listen);
+
+        let client = create(SocketType::Stream).unwrap();
+        set_option(client, SocketOption::UserTimeout(5000)).unwrap();
+        set_option(client, SocketOption::KeepAlive(true)).unwrap();
+        assert!(set_option(client, SocketOption::KeepAliveInterval(0)).is_err());
+        connect(client, localhost(8084)).unwrap();
+        let accepted = accept(server).unwrap();
+
+        assert_eq!(getsockopt(client, 6, 18).unwrap(), 5000); // TCP_USER_TIMEOUT
+        assert_eq!(getsockopt(client, 1, 9).unwrap(), 1);
+        assert_eq!(getsockopt(client, 6, 1).unwrap(), 0);
+        // Accepted sockets inherit the listener's options
+        assert_eq!(getsockopt(accepted, 6, 1).unwrap(), 1);
+
+        for handle in [client, accepted] {
+            let stats = tcp_stats(handle).unwrap();
+            assert_eq!(stats.state, tcp::TcpState::Established);
+            assert_eq!(stats.retransmits, 0);
+        }
+
+        let udp = create(SocketType::Datagram).unwrap();
+        assert!(matches!(tcp_stats(udp), Err(NetworkError::NotImplemented)));
+        assert!(setsockopt(udp, 6, 1, 1).is_err());
+        setsockopt(udp, 1, 2, 1).unwrap(); // SO_REUSEADDR
+
+        close(udp).unwrap();
+        close(accepted).unwrap();
+        close(client).unwrap();
+        close(server).unwrap();
+    }
+
+    #[test]
+    fn test_lossy_link_retransmits() {
+        let (server, client, accepted) = connected(8086);
+        set_link(client, LossyLink::dropping_every(3)).unwrap();
+
+        let data: Vec<u8> = (0..6 * tcp::MSS).map(|i| (i % 251) as u8).collect();
+        let mut sent = 0;
+        let mut received = Vec::new();
+        let mut now = TCP_NOW.load(Ordering::Relaxed);
+        let start = now;
+        while received.len() < data.len() {
+            assert!(now - start < 60_000, "transfer stalled");
+            if sent < data.len() {
+                match send(client, &data[sent..]) {
+                    Ok(n) => sent += n,
+                    Err(NetworkError::WouldBlock) => {}
+                    Err(e) => panic!("send failed: {:?}", e),
+                }
+            }
+            now += 10;
+            poll_tcp(now);
+            let mut buf = [0u8; 2048];
+            while let Ok(n @ 1..) = recv(accepted, &mut buf) {
+                received.extend_from_slice(&buf[..n]);
+            }
+        }
+
+        assert_eq!(received, data);
+        let stats = tcp_stats(client).unwrap();
+        assert!(stats.retransmits > 0);
+        assert_eq!(stats.state, tcp::TcpState::Established);
+        assert_eq!(tcp_stats(accepted).unwrap().retransmits, 0);
+
+        close(accepted).unwrap();
+        close(client).unwrap();
+        close(server).unwrap();
+    }
+
+    #[test]
+    fn test_user_timeout_fails_the_socket() {
+        let (server, client, accepted) = connected(8087);
+        set_option(client, SocketOption::UserTimeout(500)).unwrap();
+        set_link(client, LossyLink::dropping_every(1)).unwrap();
+
+        assert_eq!(send(client, b"hello").unwrap(), 5);
+        let mut buf = [0u8; 8];
+        let mut now = TCP_NOW.load(Ordering::Relaxed);
+        let start = now;
+        let error = loop {
+            assert!(now - start < 60_000, "user timeout never expired");
+            now += 10;
+            poll_tcp(now);
+            match recv(client, &mut buf) {
+                Err(NetworkError::WouldBlock) => {}
+                Err(e) => break e,
+                Ok(n) => panic!("received {} bytes over a dead link", n),
+            }
+        };
+
+        assert!(matches!(error, NetworkError::TimedOut));
+        assert!(matches!(send(client, b"x"), Err(NetworkError::TimedOut)));
+        assert!(poll(client).contains(&PollFlags::ERROR));
+        let stats = tcp_stats(client).unwrap();
+        assert_eq!(stats.state, tcp::TcpState::Closed);
+        assert!(stats.retransmits > 0);
+        assert!(matches!(
+            recv(accepted, &mut buf),
+            Err(NetworkError::WouldBlock)
+        ));
+
+        close(accepted).unwrap();
+        close(client).unwrap();
+        close(server).unwrap();
+    }
+
+    #[test]
+    fn test_poller_readiness_and_hangup() {
+        let server = create(SocketType::Stream).unwrap();
+        bind(server, localhost(8085)).unwrap();
+        listen(server, 5).unwrap();
+
+        let event = |handle, flags| PollEvent { handle, flags };
+        let mut poller = Poller::new();
+        poller.register(server, PollFlags::READABLE).unwrap();
+        assert!(poller.register(server, PollFlags::READABLE).is_err());
+        let unknown = SocketHandle(u32::MAX);
+        assert!(poller.register(unknown, PollFlags::READABLE).is_err());
+
+        let client = create(SocketType::Stream).unwrap();
+        connect(client, localhost(8085)).unwrap();
+        let events = poller.poll(Some(0), || {}, || 0);
+        assert_eq!(events, [event(server, PollFlags::READABLE)]);
+        let accepted = accept(server).unwrap();
+        poller.deregister(server).unwrap();
+        assert!(poller.deregister(server).is_err());
+
+        let both = PollFlags::READABLE.union(PollFlags::WRITABLE);
+        poller.register(client, both).unwrap();
+        let events = poller.poll(Some(0), || {}, || 0);
+        assert_eq!(events, [event(client, PollFlags::WRITABLE)]);
+
+        // Nothing to read: the wait runs out.
+        poller.modify(client, PollFlags::READABLE).unwrap();
+        let clock = core::cell::Cell::new(0u64);
+        let tick = || {
+            clock.set(clock.get() + 1);
+            clock.get()
+        };
+        assert!(poller.poll(Some(20), || {}, tick).is_empty());
+        assert!(clock.get() >= 20);
+
+        // Data arriving during the RX pump wakes the poller.
+        let pumps = core::cell::Cell::new(0);
+        let rx = || {
+            pumps.set(pumps.get() + 1);
+            if pumps.get() == 5 {
+                send(accepted, b"ping").unwrap();
+            }
+        };
+        let events = poller.poll(None, rx, || 0);
+        assert_eq!(events, [event(client, PollFlags::READABLE)]);
+        assert_eq!(pumps.get(), 5);
+        let mut buf = [0u8; 8];
+        assert_eq!(recv(client, &mut buf).unwrap(), 4);
+
+        // A closed peer is reported as readable (EOF) plus hangup.
+        close(accepted).unwrap();
+        let events = poller.poll(Some(0), || {}, || 0);
+        let hup = PollFlags::READABLE.union(PollFlags::HANGUP);
+        assert_eq!(events, [event(client, hup)]);
+        assert_eq!(recv(client, &mut buf).unwrap(), 0);
+        let result = send(client, b"x");
+        assert!(matches!(result, Err(NetworkError::ConnectionReset)));
+
+        close(client).unwrap();
+        close(server).unwrap();
+    }
+
+    #[test]
+    fn test_loopback_connect_without_listener_is_refused() {
+        let client = create(SocketType::Stream).unwrap();
+        let result = connect(client, localhost(8081));
+        assert!(matches!(result, Err(NetworkError::ConnectionRefused)));
+        assert_eq!(get_state(client).unwrap(), SocketState::Closed);
+        close(client).unwrap();
+    }
+
+    #[test]
+    fn test_loopback_udp_send_recv() {
+        let server = create(SocketType::Datagram).unwrap();
+        bind(server, localhost(8082)).unwrap();
+
+        let client = create(SocketType::Datagram).unwrap();
+        assert_eq!(sendto_dgram(client, b"datagram", localhost(8082)).unwrap(), 8);
+        let mut buf = [0u8; 16];
+        let n = recv(server, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"datagram");
+
+        connect(client, localhost(8082)).unwrap();
+        assert_eq!(send(client, b"again").unwrap(), 5);
+        let n = recv(server, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"again");
+
+        close(client).unwrap();
+        close(server).unwrap();
+    }
+}
@@ -1,7 +1,54 @@
 //! TCP protocol handling.
+//!
+//! [`TcpConnection`] is the per-connection state machine. It performs no
+//! I/O itself: segments it wants sent are collected with
+//! [`TcpConnection::take_outbound`], received ones are fed to
+//! [`TcpConnection::on_segment`], and [`TcpConnection::poll`] runs the
+//! timers. Times are milliseconds on a caller-supplied clock.
+//!
+//! Unacknowledged segments are sent again when the retransmission timeout
+//! expires, and the timeout doubles on each consecutive expiry (RFC 6298).
+//! The congestion window follows slow start and congestion avoidance
+//! (RFC 5681). A connection whose data goes unacknowledged for its user
+//! timeout (RFC 5482) fails with [`NetworkError::TimedOut`].
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
 
 use crate::NetworkError;
 
+/// FIN flag: no more data from the sender.
+pub const FIN: u8 = 0x01;
+/// SYN flag: synchronize sequence numbers.
+pub const SYN: u8 = 0x02;
+/// RST flag: reset the connection.
+pub const RST: u8 = 0x04;
+/// PSH flag: push buffered data to the application.
+pub const PSH: u8 = 0x08;
+/// ACK flag: the acknowledgment number is significant.
+pub const ACK: u8 = 0x10;
+
+/// Maximum segment size (payload only).
+pub const MSS: usize = 1460;
+/// Retransmission timeout before the first RTT measurement, in
+/// milliseconds.
+pub const INITIAL_RTO_MS: u64 = 1000;
+/// Lower bound of the retransmission timeout, in milliseconds.
+pub const MIN_RTO_MS: u64 = 200;
+/// Upper bound of the retransmission timeout, in milliseconds.
+pub const MAX_RTO_MS: u64 = 60_000;
+/// Default user timeout, in milliseconds.
+pub const DEFAULT_USER_TIMEOUT_MS: u64 = 120_000;
+/// Default interval between keepalive probes, in milliseconds.
+pub const DEFAULT_KEEPALIVE_INTERVAL_MS: u64 = 75_000;
+
+/// Receive window we advertise.
+const WINDOW_SIZE: u16 = 65535;
+/// Bytes `send` buffers before it returns `WouldBlock`.
+const SEND_BUF_CAP: usize = 64 * 1024;
+/// Initial congestion window (RFC 5681 section 3.1, for this MSS).
+const INITIAL_CWND: usize = 3 * MSS;
+
 /// TCP connection state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TcpState {
@@ -18,12 +65,216 @@ pub enum TcpState {
     TimeWait,
 }
 
+impl TcpState {
+    /// The state's number in Linux's `tcp_info`.
+    pub fn linux_code(&self) -> u8 {
+        match self {
+            TcpState::Established => 1,
+            TcpState::SynSent => 2,
+            TcpState::SynReceived => 3,
+            TcpState::FinWait1 => 4,
+            TcpState::FinWait2 => 5,
+            TcpState::TimeWait => 6,
+            TcpState::Closed => 7,
+            TcpState::CloseWait => 8,
+            TcpState::LastAck => 9,
+            TcpState::Listen => 10,
+            TcpState::Closing => 11,
+        }
+    }
+}
+
+/// A TCP segment.
+///
+/// The checksum covers an IP pseudo-header, so it is left to the IP layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpSegment {
+    /// Source port.
+    pub src_port: u16,
+    /// Destination port.
+    pub dst_port: u16,
+    /// Sequence number.
+    pub seq: u32,
+    /// Acknowledgment number.
+    pub ack: u32,
+    /// Flags (`FIN`, `SYN`, ...).
+    pub flags: u8,
+    /// Receive window.
+    pub window: u16,
+    /// Payload.
+    pub payload: Vec<u8>,
+}
+
+impl TcpSegment {
+    /// Header length without options.
+    pub const HEADER_LEN: usize = 20;
+
+    /// Parse a segment, skipping any options.
+    pub fn parse(data: &[u8]) -> Result<Self, NetworkError> {
+        if data.len() < Self::HEADER_LEN {
+            return Err(NetworkError::InvalidPacket);
+        }
+        let data_offset = usize::from(data[12] >> 4) * 4;
+        if data_offset < Self::HEADER_LEN || data_offset > data.len() {
+            return Err(NetworkError::InvalidPacket);
+        }
+        Ok(TcpSegment {
+            src_port: u16::from_be_bytes([data[0], data[1]]),
+            dst_port: u16::from_be_bytes([data[2], data[3]]),
+            seq: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            ack: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+            flags: data[13],
+            window: u16::from_be_bytes([data[14], data[15]]),
+            payload: data[data_offset..].to_vec(),
+        })
+    }
+
+    /// Serialize the segment with a zero checksum.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::HEADER_LEN + self.payload.len());
+        out.extend_from_slice(&self.src_port.to_be_bytes());
+        out.extend_from_slice(&self.dst_port.to_be_bytes());
+        out.extend_from_slice(&self.seq.to_be_bytes());
+        out.extend_from_slice(&self.ack.to_be_bytes());
+        out.push(((Self::HEADER_LEN / 4) as u8) << 4);
+        out.push(self.flags);
+        out.extend_from_slice(&self.window.to_be_bytes());
+        out.extend_from_slice(&[0; 4]); // checksum, urgent pointer
+        out.extend_from_slice(&self.payload);
+        out
+    }
+
+    /// Sequence space the segment occupies: its payload, plus one each
+    /// for SYN and FIN.
+    pub fn seq_len(&self) -> u32 {
+        self.payload.len() as u32
+            + u32::from(self.flags & SYN != 0)
+            + u32::from(self.flags & FIN != 0)
+    }
+}
+
+/// Per-connection options, set through the socket layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpOptions {
+    /// Send small segments at once rather than holding them back while
+    /// earlier data is unacknowledged (Nagle's algorithm).
+    pub nodelay: bool,
+    /// Probe an idle connection this often, in milliseconds; `None`
+    /// disables keepalive.
+    pub keepalive_interval: Option<u64>,
+    /// Fail the connection once sent data or keepalive probes have gone
+    /// unanswered this long, in milliseconds.
+    pub user_timeout: u64,
+}
+
+impl Default for TcpOptions {
+    fn default() -> Self {
+        Self {
+            nodelay: false,
+            keepalive_interval: None,
+            user_timeout: DEFAULT_USER_TIMEOUT_MS,
+        }
+    }
+}
+
+/// A snapshot of a connection's state, for debugging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Connection state.
+    pub state: TcpState,
+    /// Segments sent again after the retransmission timeout expired.
+    pub retransmits: u32,
+    /// Smoothed round-trip time in milliseconds, once measured.
+    pub rtt_estimate: Option<u64>,
+    /// Current retransmission timeout in milliseconds, including backoff.
+    pub rto: u64,
+    /// Congestion window in bytes.
+    pub cwnd: u32,
+}
+
+impl ConnectionStats {
+    /// Length of [`to_bytes`](Self::to_bytes).
+    pub const ENCODED_LEN: usize = 20;
+
+    /// Encode as the `TCP_INFO` socket option: five native-endian `u32`s
+    /// holding the [Linux state number](TcpState::linux_code), the
+    /// retransmit count, the RTT estimate (0 until measured), the
+    /// retransmission timeout and the congestion window.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let fields = [
+            u32::from(self.state.linux_code()),
+            self.retransmits,
+            self.rtt_estimate
+                .map_or(0, |rtt| rtt.min(u64::from(u32::MAX)) as u32),
+            self.rto as u32,
+            self.cwnd,
+        ];
+        let mut out = [0u8; Self::ENCODED_LEN];
+        for (chunk, field) in out.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_ne_bytes());
+        }
+        out
+    }
+}
+
 /// TCP connection.
 pub struct TcpConnection {
     state: TcpState,
-    local_seq: u32,
-    remote_seq: u32,
+    local_port: u16,
+    remote_port: u16,
+    options: TcpOptions,
+    /// Initial send sequence number.
+    iss: u32,
+    /// Oldest unacknowledged sequence number.
+    snd_una: u32,
+    /// Next sequence number to send.
+    snd_nxt: u32,
+    /// Window the peer advertised.
+    snd_wnd: u32,
+    /// Next sequence number expected from the peer.
+    rcv_nxt: u32,
     window_size: u16,
+    /// Data accepted by `send` but not yet segmented.
+    send_buf: VecDeque<u8>,
+    recv_buf: VecDeque<u8>,
+    /// Segments sent but not yet acknowledged, oldest first.
+    unacked: VecDeque<TcpSegment>,
+    /// Segments waiting for `take_outbound`.
+    outbound: Vec<TcpSegment>,
+    /// `close` was called; FIN follows the buffered data.
+    fin_queued: bool,
+    /// The peer's FIN arrived.
+    fin_received: bool,
+    /// Smoothed round-trip time.
+    srtt: Option<u64>,
+    /// Round-trip time variation.
+    rttvar: u64,
+    /// Retransmission timeout.
+    rto: u64,
+    /// End of the segment being timed and when it was sent. Retransmitted
+    /// segments are never timed (Karn's algorithm).
+    rtt_probe: Option<(u32, u64)>,
+    /// When the retransmission timer expires.
+    retransmit_at: Option<u64>,
+    /// Consecutive retransmission timeouts without progress.
+    backoff: u32,
+    /// `snd_nxt` when the retransmission timeout last expired. The peer
+    /// drops out-of-order data, so everything sent before it is resent
+    /// as acknowledgments arrive.
+    recover: Option<u32>,
+    /// End of the data resent since the timeout.
+    resent_to: u32,
+    /// Since when sent data or keepalive probes have gone unanswered.
+    stalled_since: Option<u64>,
+    /// When the peer was last heard from.
+    last_heard: u64,
+    /// When the last keepalive probe was sent, while unanswered.
+    last_probe: Option<u64>,
+    cwnd: usize,
+    ssthresh: usize,
+    retransmits: u32,
+    /// Why the connection failed.
+    error: Option<NetworkError>,
 }
 
 impl TcpConnection {
@@ -31,9 +282,36 @@ impl TcpConnection {
     pub fn new() -> Self {
         TcpConnection {
             state: TcpState::Closed,
-            local_seq: 0,
-            remote_seq: 0,
-            window_size: 65535,
+            local_port: 0,
+            remote_port: 0,
+            options: TcpOptions::default(),
+            iss: 0,
+            snd_una: 0,
+            snd_nxt: 0,
+            snd_wnd: u32::from(WINDOW_SIZE),
+            rcv_nxt: 0,
+            window_size: WINDOW_SIZE,
+            send_buf: VecDeque::new(),
+            recv_buf: VecDeque::new(),
+            unacked: VecDeque::new(),
+            outbound: Vec::new(),
+            fin_queued: false,
+            fin_received: false,
+            srtt: None,
+            rttvar: 0,
+            rto: INITIAL_RTO_MS,
+            rtt_probe: None,
+            retransmit_at: None,
+            backoff: 0,
+            recover: None,
+            resent_to: 0,
+            stalled_since: None,
+            last_heard: 0,
+            last_probe: None,
+            cwnd: INITIAL_CWND,
+            ssthresh: usize::MAX,
+            retransmits: 0,
+            error: None,
         }
     }
 
@@ -41,6 +319,460 @@ impl TcpConnection {
     pub fn state(&self) -> TcpState {
         self.state
     }
+
+    /// Get the connection's options.
+    pub fn options(&self) -> TcpOptions {
+        self.options
+    }
+
+    /// Set the connection's options. They apply from the next call on.
+    pub fn set_options(&mut self, options: TcpOptions) {
+        self.options = options;
+    }
+
+    /// Why the connection failed, if it did.
+    pub fn error(&self) -> Option<NetworkError> {
+        self.error.clone()
+    }
+
+    /// Get a snapshot of the connection's state.
+    pub fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            state: self.state,
+            retransmits: self.retransmits,
+            rtt_estimate: self.srtt,
+            rto: self.rto,
+            cwnd: self.cwnd.min(u32::MAX as usize) as u32,
+        }
+    }
+
+    /// Open the connection actively by sending a SYN with initial
+    /// sequence number `iss`.
+    pub fn connect(
+        &mut self,
+        local_port: u16,
+        remote_port: u16,
+        iss: u32,
+        now: u64,
+    ) -> Result<(), NetworkError> {
+        if self.state != TcpState::Closed {
+            return Err(NetworkError::AlreadyConnected);
+        }
+        self.local_port = local_port;
+        self.remote_port = remote_port;
+        self.start(iss, now);
+        self.state = TcpState::SynSent;
+        self.send_segment(SYN, Vec::new(), now);
+        Ok(())
+    }
+
+    /// Wait for a SYN on `local_port`, answering it with initial sequence
+    /// number `iss`.
+    pub fn listen(&mut self, local_port: u16, iss: u32) -> Result<(), NetworkError> {
+        if self.state != TcpState::Closed {
+            return Err(NetworkError::AlreadyConnected);
+        }
+        self.local_port = local_port;
+        self.iss = iss;
+        self.state = TcpState::Listen;
+        Ok(())
+    }
+
+    /// Queue data to send. Returns how much was accepted.
+    pub fn send(&mut self, data: &[u8], now: u64) -> Result<usize, NetworkError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        if !matches!(self.state, TcpState::Established | TcpState::CloseWait) || self.fin_queued {
+            return Err(NetworkError::NotConnected);
+        }
+        let len = data.len().min(SEND_BUF_CAP - self.send_buf.len());
+        if len == 0 && !data.is_empty() {
+            return Err(NetworkError::WouldBlock);
+        }
+        self.send_buf.extend(&data[..len]);
+        self.flush(now);
+        Ok(len)
+    }
+
+    /// Read received data. Returns 0 once the peer has closed its side.
+    pub fn recv(&mut self, buffer: &mut [u8]) -> Result<usize, NetworkError> {
+        if self.recv_buf.is_empty() {
+            return match &self.error {
+                Some(error) => Err(error.clone()),
+                None if self.fin_received => Ok(0),
+                None => Err(NetworkError::WouldBlock),
+            };
+        }
+        let len = buffer.len().min(self.recv_buf.len());
+        for (dst, src) in buffer.iter_mut().zip(self.recv_buf.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+
+    /// Close our side of the connection. A FIN follows any buffered data.
+    pub fn close(&mut self, now: u64) {
+        match self.state {
+            TcpState::Closed | TcpState::Listen | TcpState::SynSent => {
+                self.state = TcpState::Closed;
+                self.unacked.clear();
+                self.retransmit_at = None;
+                self.stalled_since = None;
+            }
+            TcpState::SynReceived | TcpState::Established | TcpState::CloseWait => {
+                self.fin_queued = true;
+                self.flush(now);
+            }
+            _ => {}
+        }
+    }
+
+    /// Take the segments waiting to be transmitted.
+    pub fn take_outbound(&mut self) -> Vec<TcpSegment> {
+        core::mem::take(&mut self.outbound)
+    }
+
+    /// Process a segment received from the peer.
+    pub fn on_segment(&mut self, seg: &TcpSegment, now: u64) {
+        if self.state == TcpState::Closed || seg.dst_port != self.local_port {
+            return;
+        }
+        if self.state != TcpState::Listen && seg.src_port != self.remote_port {
+            return;
+        }
+        self.last_heard = now;
+        self.last_probe = None;
+        if self.unacked.is_empty() {
+            self.stalled_since = None;
+        }
+
+        if seg.flags & RST != 0 {
+            match self.state {
+                TcpState::Listen => {}
+                TcpState::SynSent => self.fail(NetworkError::ConnectionRefused),
+                _ => self.fail(NetworkError::ConnectionReset),
+            }
+            return;
+        }
+
+        match self.state {
+            TcpState::Listen => {
+                if seg.flags & SYN != 0 {
+                    self.remote_port = seg.src_port;
+                    let iss = self.iss;
+                    self.start(iss, now);
+                    self.rcv_nxt = seg.seq.wrapping_add(1);
+                    self.snd_wnd = u32::from(seg.window);
+                    self.state = TcpState::SynReceived;
+                    self.send_segment(SYN | ACK, Vec::new(), now);
+                }
+                return;
+            }
+            TcpState::SynSent => {
+                if seg.flags & (SYN | ACK) == SYN | ACK && seg.ack == self.snd_nxt {
+                    self.rcv_nxt = seg.seq.wrapping_add(1);
+                    self.process_ack(seg, now);
+                    self.state = TcpState::Established;
+                    self.send_segment(ACK, Vec::new(), now);
+                    self.flush(now);
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        if seg.flags & ACK != 0 {
+            self.process_ack(seg, now);
+        }
+
+        if seg.seq != self.rcv_nxt {
+            // Out of order, a duplicate, or a keepalive probe: tell the
+            // peer what we expect next
+            if seg.seq_len() > 0 || seq_lt(seg.seq, self.rcv_nxt) {
+                self.send_segment(ACK, Vec::new(), now);
+            }
+            return;
+        }
+
+        if !seg.payload.is_empty() && !self.fin_received {
+            self.recv_buf.extend(&seg.payload);
+            self.rcv_nxt = self.rcv_nxt.wrapping_add(seg.payload.len() as u32);
+        }
+        if seg.flags & FIN != 0 && !self.fin_received {
+            self.rcv_nxt = self.rcv_nxt.wrapping_add(1);
+            self.fin_received = true;
+            self.state = match self.state {
+                TcpState::SynReceived | TcpState::Established => TcpState::CloseWait,
+                TcpState::FinWait1 => TcpState::Closing,
+                TcpState::FinWait2 => TcpState::TimeWait,
+                state => state,
+            };
+        }
+        if seg.seq_len() > 0 {
+            self.send_segment(ACK, Vec::new(), now);
+        }
+        self.flush(now);
+    }
+
+    /// Run the connection's timers.
+    ///
+    /// Fails with [`NetworkError::TimedOut`] once sent data or keepalive
+    /// probes have gone unanswered for the user timeout, and with the
+    /// same error on every later call.
+    pub fn poll(&mut self, now: u64) -> Result<(), NetworkError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        if let Some(since) = self.stalled_since {
+            if now.saturating_sub(since) >= self.options.user_timeout {
+                self.fail(NetworkError::TimedOut);
+                return Err(NetworkError::TimedOut);
+            }
+        }
+        if self.retransmit_at.is_some_and(|at| now >= at) {
+            self.retransmit(now);
+        }
+
+        let idle = self.unacked.is_empty() && self.send_buf.is_empty();
+        if let (Some(interval), true) = (self.options.keepalive_interval, idle) {
+            let last = self.last_probe.unwrap_or(self.last_heard);
+            if matches!(self.state, TcpState::Established | TcpState::CloseWait)
+                && now.saturating_sub(last) >= interval
+            {
+                // An already acknowledged sequence number makes the peer
+                // answer with an ACK
+                self.outbound.push(TcpSegment {
+                    src_port: self.local_port,
+                    dst_port: self.remote_port,
+                    seq: self.snd_nxt.wrapping_sub(1),
+                    ack: self.rcv_nxt,
+                    flags: ACK,
+                    window: self.window_size,
+                    payload: Vec::new(),
+                });
+                self.last_probe = Some(now);
+                self.stalled_since.get_or_insert(now);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reset the send sequence and timers for a new connection.
+    fn start(&mut self, iss: u32, now: u64) {
+        self.iss = iss;
+        self.snd_una = iss;
+        self.snd_nxt = iss;
+        self.last_heard = now;
+    }
+
+    /// Queue a segment at `snd_nxt`, tracking it for retransmission if it
+    /// occupies sequence space.
+    fn send_segment(&mut self, flags: u8, payload: Vec<u8>, now: u64) {
+        let seg = TcpSegment {
+            src_port: self.local_port,
+            dst_port: self.remote_port,
+            seq: self.snd_nxt,
+            ack: self.rcv_nxt,
+            flags: if flags & SYN == 0 { flags | ACK } else { flags },
+            window: self.window_size,
+            payload,
+        };
+        let len = seg.seq_len();
+        if len > 0 {
+            self.snd_nxt = self.snd_nxt.wrapping_add(len);
+            if self.rtt_probe.is_none() {
+                self.rtt_probe = Some((self.snd_nxt, now));
+            }
+            self.retransmit_at.get_or_insert(now + self.rto);
+            self.stalled_since.get_or_insert(now);
+            self.unacked.push_back(seg.clone());
+        }
+        self.outbound.push(seg);
+    }
+
+    /// Segment buffered data as far as the windows allow, then send a
+    /// queued FIN once the buffer is empty.
+    fn flush(&mut self, now: u64) {
+        if !matches!(self.state, TcpState::Established | TcpState::CloseWait) {
+            return;
+        }
+        while !self.send_buf.is_empty() {
+            let in_flight = self.snd_nxt.wrapping_sub(self.snd_una) as usize;
+            let window = self.cwnd.min(self.snd_wnd as usize);
+            let len = self
+                .send_buf
+                .len()
+                .min(MSS)
+                .min(window.saturating_sub(in_flight));
+            // Nagle: hold a partial segment back while data is in flight
+            if len == 0 || (len < MSS && in_flight > 0 && !self.options.nodelay) {
+                break;
+            }
+            let payload = self.send_buf.drain(..len).collect();
+            self.send_segment(PSH | ACK, payload, now);
+        }
+
+        if self.fin_queued && self.send_buf.is_empty() {
+            self.fin_queued = false;
+            self.state = match self.state {
+                TcpState::CloseWait => TcpState::LastAck,
+                _ => TcpState::FinWait1,
+            };
+            self.send_segment(FIN | ACK, Vec::new(), now);
+        }
+    }
+
+    /// Process the acknowledgment number of `seg`.
+    fn process_ack(&mut self, seg: &TcpSegment, now: u64) {
+        let ack = seg.ack;
+        if !seq_lt(self.snd_una, ack) || seq_lt(self.snd_nxt, ack) {
+            if ack == self.snd_una {
+                self.snd_wnd = u32::from(seg.window);
+            }
+            return;
+        }
+        let acked = ack.wrapping_sub(self.snd_una) as usize;
+        self.snd_una = ack;
+        self.snd_wnd = u32::from(seg.window);
+
+        while let Some(front) = self.unacked.front_mut() {
+            let end = front.seq.wrapping_add(front.seq_len());
+            if !seq_lt(ack, end) {
+                self.unacked.pop_front();
+            } else {
+                // Partially acknowledged: keep only the rest
+                if seq_lt(front.seq, ack) {
+                    front.payload.drain(..ack.wrapping_sub(front.seq) as usize);
+                    front.seq = ack;
+                }
+                break;
+            }
+        }
+
+        if let Some((end, sent_at)) = self.rtt_probe {
+            if !seq_lt(ack, end) {
+                self.rtt_probe = None;
+                self.update_rtt(now.saturating_sub(sent_at));
+            }
+        }
+
+        // The handshake does not open the congestion window
+        if !matches!(self.state, TcpState::SynSent | TcpState::SynReceived) {
+            if self.cwnd < self.ssthresh {
+                self.cwnd += acked.min(MSS);
+            } else {
+                self.cwnd += (MSS * MSS / self.cwnd).max(1);
+            }
+        }
+        match self.recover {
+            Some(recover) if seq_lt(ack, recover) => self.resend_lost(recover),
+            _ => self.recover = None,
+        }
+
+        // Progress ends the backoff, as Linux does, rather than waiting for
+        // a new RTT sample
+        if self.backoff > 0 {
+            self.backoff = 0;
+            self.rto = self.base_rto();
+        }
+        if self.unacked.is_empty() {
+            self.retransmit_at = None;
+            self.stalled_since = None;
+        } else {
+            self.retransmit_at = Some(now + self.rto);
+            self.stalled_since = Some(now);
+        }
+
+        let all_acked = self.snd_una == self.snd_nxt;
+        self.state = match self.state {
+            TcpState::SynReceived => TcpState::Established,
+            TcpState::FinWait1 if all_acked => TcpState::FinWait2,
+            TcpState::Closing if all_acked => TcpState::TimeWait,
+            TcpState::LastAck if all_acked => TcpState::Closed,
+            state => state,
+        };
+    }
+
+    /// Fold a round-trip time sample into the estimate (RFC 6298
+    /// section 2).
+    fn update_rtt(&mut self, sample: u64) {
+        match self.srtt {
+            None => {
+                self.srtt = Some(sample);
+                self.rttvar = sample / 2;
+            }
+            Some(srtt) => {
+                self.rttvar = (3 * self.rttvar + srtt.abs_diff(sample)) / 4;
+                self.srtt = Some((7 * srtt + sample) / 8);
+            }
+        }
+        self.rto = self.base_rto();
+    }
+
+    /// The retransmission timeout without backoff.
+    fn base_rto(&self) -> u64 {
+        match self.srtt {
+            Some(srtt) => (srtt + (4 * self.rttvar).max(1)).clamp(MIN_RTO_MS, MAX_RTO_MS),
+            None => INITIAL_RTO_MS,
+        }
+    }
+
+    /// Send the oldest unacknowledged segment again and back off.
+    fn retransmit(&mut self, now: u64) {
+        let Some(mut seg) = self.unacked.front().cloned() else {
+            self.retransmit_at = None;
+            return;
+        };
+        seg.ack = self.rcv_nxt;
+        self.resent_to = seg.seq.wrapping_add(seg.seq_len());
+        self.recover = Some(self.snd_nxt);
+        self.outbound.push(seg);
+        self.retransmits += 1;
+        self.rtt_probe = None;
+
+        // RFC 5681 section 3.1: halve on the first timeout only
+        if self.backoff == 0 {
+            let in_flight = self.snd_nxt.wrapping_sub(self.snd_una) as usize;
+            self.ssthresh = (in_flight / 2).max(2 * MSS);
+        }
+        self.backoff += 1;
+        self.cwnd = MSS;
+        self.rto = (self.rto * 2).min(MAX_RTO_MS);
+        self.retransmit_at = Some(now + self.rto);
+    }
+
+    /// Resend segments sent before the timeout that the congestion window
+    /// now has room for.
+    fn resend_lost(&mut self, recover: u32) {
+        let mut resend = Vec::new();
+        for seg in &self.unacked {
+            let end = seg.seq.wrapping_add(seg.seq_len());
+            if seq_lt(seg.seq, self.resent_to) {
+                continue;
+            }
+            if !seq_lt(seg.seq, recover) || end.wrapping_sub(self.snd_una) as usize > self.cwnd {
+                break;
+            }
+            let mut seg = seg.clone();
+            seg.ack = self.rcv_nxt;
+            self.resent_to = end;
+            resend.push(seg);
+        }
+        self.retransmits += resend.len() as u32;
+        self.outbound.extend(resend);
+    }
+
+    /// Abort the connection with `error`.
+    fn fail(&mut self, error: NetworkError) {
+        self.state = TcpState::Closed;
+        self.error = Some(error);
+        self.send_buf.clear();
+        self.unacked.clear();
+        self.retransmit_at = None;
+        self.recover = None;
+        self.stalled_since = None;
+    }
 }
 
 impl Default for TcpConnection {
@@ -48,3 +780,295 @@ impl Default for TcpConnection {
         Self::new()
     }
 }
+
+/// Sequence number comparison `a < b`, allowing for wrap-around.
+fn seq_lt(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::NetworkDevice;
+    use crate::MacAddr;
+    use alloc::vec;
+
+    /// A stub driver carrying segments one way, dropping some of them.
+    #[derive(Default)]
+    struct LossyLink {
+        queue: VecDeque<Vec<u8>>,
+        /// Drop every n-th packet; 0 drops none.
+        drop_every: usize,
+        /// Drop every packet.
+        down: bool,
+        sent: usize,
+        dropped: usize,
+    }
+
+    impl NetworkDevice for LossyLink {
+        fn mac_address(&self) -> MacAddr {
+            MacAddr([0x52, 0x54, 0, 0x12, 0x34, 0x56])
+        }
+
+        fn mtu(&self) -> u16 {
+            1500
+        }
+
+        fn link_up(&self) -> bool {
+            !self.down
+        }
+
+        fn transmit(&mut self, packet: &[u8]) -> Result<(), NetworkError> {
+            self.sent += 1;
+            if self.down || (self.drop_every != 0 && self.sent.is_multiple_of(self.drop_every)) {
+                self.dropped += 1;
+            } else {
+                self.queue.push_back(packet.to_vec());
+            }
+            Ok(())
+        }
+
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, NetworkError> {
+            let packet = self.queue.pop_front().ok_or(NetworkError::WouldBlock)?;
+            buffer[..packet.len()].copy_from_slice(&packet);
+            Ok(packet.len())
+        }
+
+        fn can_receive(&self) -> bool {
+            !self.queue.is_empty()
+        }
+
+        fn link_speed(&self) -> u32 {
+            1000
+        }
+    }
+
+    /// A client and a server connection joined by a link each way.
+    struct Pair {
+        client: TcpConnection,
+        server: TcpConnection,
+        to_server: LossyLink,
+        to_client: LossyLink,
+        now: u64,
+    }
+
+    impl Pair {
+        /// Connect a client to a server over reliable links.
+        fn connected() -> Self {
+            let mut pair = Pair {
+                client: TcpConnection::new(),
+                server: TcpConnection::new(),
+                to_server: LossyLink::default(),
+                to_client: LossyLink::default(),
+                now: 0,
+            };
+            pair.server.listen(80, 5000).unwrap();
+            pair.client.connect(40000, 80, 1000, 0).unwrap();
+            while pair.client.state() != TcpState::Established
+                || pair.server.state() != TcpState::Established
+            {
+                pair.step().unwrap();
+            }
+            pair
+        }
+
+        /// Advance the clock by 10ms: run the timers, then carry what each
+        /// side sent across its link.
+        fn step(&mut self) -> Result<(), NetworkError> {
+            self.now += 10;
+            self.server.poll(self.now)?;
+            self.client.poll(self.now)?;
+            for seg in self.client.take_outbound() {
+                self.to_server.transmit(&seg.to_bytes())?;
+            }
+            for seg in self.server.take_outbound() {
+                self.to_client.transmit(&seg.to_bytes())?;
+            }
+            let mut buffer = [0u8; 2048];
+            while self.to_server.can_receive() {
+                let len = self.to_server.receive(&mut buffer)?;
+                let seg = TcpSegment::parse(&buffer[..len])?;
+                self.server.on_segment(&seg, self.now);
+            }
+            while self.to_client.can_receive() {
+                let len = self.to_client.receive(&mut buffer)?;
+                let seg = TcpSegment::parse(&buffer[..len])?;
+                self.client.on_segment(&seg, self.now);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_segment_round_trip() {
+        let seg = TcpSegment {
+            src_port: 40000,
+            dst_port: 80,
+            seq: 0xDEADBEEF,
+            ack: 7,
+            flags: PSH | ACK,
+            window: 1024,
+            payload: b"hello".to_vec(),
+        };
+        let bytes = seg.to_bytes();
+        assert_eq!(bytes.len(), TcpSegment::HEADER_LEN + 5);
+        assert_eq!(TcpSegment::parse(&bytes).unwrap(), seg);
+        assert_eq!(seg.seq_len(), 5);
+        assert!(TcpSegment::parse(&bytes[..19]).is_err());
+    }
+
+    #[test]
+    fn test_retransmits_lost_segments() {
+        let mut pair = Pair::connected();
+        assert_eq!(pair.client.stats().retransmits, 0);
+        pair.to_server.drop_every = 3;
+        pair.to_client.drop_every = 4;
+
+        let data: Vec<u8> = (0..20 * MSS).map(|i| (i % 251) as u8).collect();
+        let mut sent = 0;
+        let mut received = Vec::new();
+        while received.len() < data.len() {
+            assert!(pair.now < 60_000, "transfer stalled");
+            if sent < data.len() {
+                sent += pair.client.send(&data[sent..], pair.now).unwrap();
+            }
+            pair.step().unwrap();
+            let mut buffer = [0u8; 4096];
+            while let Ok(len) = pair.server.recv(&mut buffer) {
+                received.extend_from_slice(&buffer[..len]);
+            }
+        }
+
+        assert_eq!(received, data);
+        assert!(pair.to_server.dropped > 0);
+        let stats = pair.client.stats();
+        assert!(stats.retransmits > 0);
+        assert_eq!(stats.state, TcpState::Established);
+        assert!(stats.rtt_estimate.is_some());
+    }
+
+    #[test]
+    fn test_user_timeout() {
+        let mut pair = Pair::connected();
+        pair.client.set_options(TcpOptions {
+            user_timeout: 3000,
+            ..TcpOptions::default()
+        });
+        pair.to_server.down = true;
+
+        let sent_at = pair.now;
+        pair.client.send(b"hello", sent_at).unwrap();
+        let mut rtos = Vec::new();
+        let error = loop {
+            if let Err(e) = pair.step() {
+                break e;
+            }
+            let rto = pair.client.stats().rto;
+            if rtos.last() != Some(&rto) {
+                rtos.push(rto);
+            }
+        };
+
+        assert!(matches!(error, NetworkError::TimedOut));
+        assert_eq!(pair.now - sent_at, 3000);
+        // Each timeout doubles the RTO
+        assert!(rtos.len() >= 3);
+        assert!(rtos.windows(2).all(|w| w[1] == 2 * w[0]));
+        let stats = pair.client.stats();
+        assert_eq!(stats.state, TcpState::Closed);
+        assert_eq!(stats.retransmits as usize, rtos.len() - 1);
+        assert_eq!(stats.cwnd as usize, MSS);
+        assert!(matches!(
+            pair.client.send(b"more", pair.now),
+            Err(NetworkError::TimedOut)
+        ));
+    }
+
+    #[test]
+    fn test_nagle_and_nodelay() {
+        let mut pair = Pair::connected();
+        let now = pair.now;
+
+        pair.client.send(b"a", now).unwrap();
+        assert_eq!(pair.client.outbound.len(), 1);
+        // Held back while "a" is unacknowledged
+        pair.client.send(b"b", now).unwrap();
+        assert_eq!(pair.client.outbound.len(), 1);
+        for _ in 0..3 {
+            pair.step().unwrap();
+        }
+        let mut buffer = [0u8; 8];
+        assert_eq!(pair.server.recv(&mut buffer).unwrap(), 2);
+        assert_eq!(&buffer[..2], b"ab");
+
+        pair.client.set_options(TcpOptions {
+            nodelay: true,
+            ..TcpOptions::default()
+        });
+        let now = pair.now;
+        pair.client.send(b"c", now).unwrap();
+        pair.client.send(b"d", now).unwrap();
+        assert_eq!(pair.client.take_outbound().len(), 2);
+    }
+
+    #[test]
+    fn test_keepalive() {
+        let mut pair = Pair::connected();
+        pair.client.set_options(TcpOptions {
+            keepalive_interval: Some(1000),
+            user_timeout: 5000,
+            ..TcpOptions::default()
+        });
+
+        // Answered probes keep the connection alive
+        for _ in 0..300 {
+            pair.step().unwrap();
+        }
+        assert_eq!(pair.client.state(), TcpState::Established);
+        assert!(pair.to_server.sent >= 2);
+
+        // Unanswered ones time it out
+        pair.to_server.down = true;
+        let down_at = pair.now;
+        while pair.step().is_ok() {
+            assert!(pair.now - down_at < 10_000, "keepalive never timed out");
+        }
+        assert_eq!(pair.client.state(), TcpState::Closed);
+        assert_eq!(pair.client.stats().retransmits, 0);
+    }
+
+    #[test]
+    fn test_close_handshake() {
+        let mut pair = Pair::connected();
+        pair.client.close(pair.now);
+        pair.step().unwrap();
+        pair.step().unwrap();
+        assert_eq!(pair.client.state(), TcpState::FinWait2);
+        assert_eq!(pair.server.state(), TcpState::CloseWait);
+        let mut buffer = [0u8; 8];
+        assert_eq!(pair.server.recv(&mut buffer).unwrap(), 0);
+
+        pair.server.close(pair.now);
+        pair.step().unwrap();
+        pair.step().unwrap();
+        assert_eq!(pair.client.state(), TcpState::TimeWait);
+        assert_eq!(pair.server.state(), TcpState::Closed);
+    }
+
+    #[test]
+    fn test_stats_encoding() {
+        let stats = ConnectionStats {
+            state: TcpState::Established,
+            retransmits: 3,
+            rtt_estimate: Some(25),
+            rto: 400,
+            cwnd: 2920,
+        };
+        let bytes = stats.to_bytes();
+        let field = |i: usize| u32::from_ne_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+        assert_eq!(
+            vec![field(0), field(1), field(2), field(3), field(4)],
+            [1, 3, 25, 400, 2920]
+        );
+    }
+}
@@ -8,6 +8,7 @@
 extern crate alloc;
 
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt;
 
@@ -40,6 +41,8 @@ pub enum CertificateError {
     PathLengthExceeded,
     /// Certificate revoked.
     Revoked,
+    /// No trusted root anchors the chain.
+    UnknownIssuer,
 }
 
 impl fmt::Display for CertificateError {
@@ -60,6 +63,7 @@ impl fmt::Display for CertificateError {
             CertificateError::NameConstraintViolation => write!(f, "Name constraint violation"),
             CertificateError::PathLengthExceeded => write!(f, "Path length constraint exceeded"),
             CertificateError::Revoked => write!(f, "Certificate revoked"),
+            CertificateError::UnknownIssuer => write!(f, "Unknown issuer"),
         }
     }
 }
@@ -162,29 +166,43 @@ pub struct DistinguishedName {
 }
 
 impl DistinguishedName {
-    /// Parse from DER-encoded bytes.
+    /// Parse from a DER-encoded Name.
     pub fn from_der(data: &[u8]) -> Result<Self, CertificateError> {
         let mut dn = DistinguishedName::default();
+        let (rdns, _) = der_element(data, 0x30)?;
 
-        // Simplified parsing - would need full ASN.1 parser
+        // SEQUENCE OF SET OF AttributeTypeAndValue
         let mut offset = 0;
-        while offset < data.len() {
-            if offset + 2 > data.len() {
-                break;
-            }
-
-            // Skip to next SET/SEQUENCE
-            if data[offset] == 0x31 || data[offset] == 0x30 {
-                let len = parse_der_length(&data[offset + 1..])?;
-                offset += 2 + length_bytes(len);
+        while offset < rdns.len() {
+            let (set, set_len) = der_element(&rdns[offset..], 0x31)?;
+            offset += set_len;
+
+            let mut set_offset = 0;
+            while set_offset < set.len() {
+                let (attribute, attribute_len) = der_element(&set[set_offset..], 0x30)?;
+                set_offset += attribute_len;
+
+                let (oid, oid_len) = der_element(attribute, 0x06)?;
+                let value = &attribute[oid_len..];
+                let tag = *value.first().ok_or(CertificateError::InvalidAsn1)?;
+                let (value, _) = der_element(value, tag)?;
+                let Ok(value) = core::str::from_utf8(value) else {
+                    continue;
+                };
 
-                // Parse AttributeTypeAndValue
-                if offset + 2 < data.len() && data[offset] == 0x30 {
-                    // Parse OID and value
-                    // This is simplified - real implementation needs full ASN.1
-                }
+                let field = match oid {
+                    [0x55, 0x04, 0x03] => &mut dn.common_name,
+                    [0x55, 0x04, 0x06] => &mut dn.country,
+                    [0x55, 0x04, 0x07] => &mut dn.locality,
+                    [0x55, 0x04, 0x08] => &mut dn.state,
+                    [0x55, 0x04, 0x0A] => &mut dn.organization,
+                    [0x55, 0x04, 0x0B] => &mut dn.organizational_unit,
+                    // emailAddress
+                    [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x01] => &mut dn.email,
+                    _ => continue,
+                };
+                *field = Some(String::from(value));
             }
-            offset += 1;
         }
 
         Ok(dn)
@@ -245,6 +263,10 @@ pub struct Certificate {
     issuer: DistinguishedName,
     /// Subject.
     subject: DistinguishedName,
+    /// DER-encoded issuer name, for matching against issuer subjects.
+    issuer_der: Vec<u8>,
+    /// DER-encoded subject name.
+    subject_der: Vec<u8>,
     /// Not valid before (Unix timestamp).
     not_before: i64,
     /// Not valid after (Unix timestamp).
@@ -284,6 +306,8 @@ impl Certificate {
             signature_algorithm: None,
             issuer: DistinguishedName::default(),
             subject: DistinguishedName::default(),
+            issuer_der: Vec::new(),
+            subject_der: Vec::new(),
             not_before: 0,
             not_after: i64::MAX,
             public_key_algorithm: None,
@@ -388,11 +412,12 @@ impl Certificate {
             }
         }
 
-        // Issuer (SEQUENCE) - skip for now
+        // Issuer (SEQUENCE)
         if offset < data.len() && data[offset] == 0x30 {
-            offset += 1;
-            let len = parse_der_length(&data[offset..])?;
-            offset += length_bytes(len) + len;
+            let (_, len) = der_element(&data[offset..], 0x30)?;
+            self.issuer_der = data[offset..offset + len].to_vec();
+            self.issuer = DistinguishedName::from_der(&self.issuer_der)?;
+            offset += len;
         }
 
         // Validity (SEQUENCE)
@@ -403,22 +428,25 @@ impl Certificate {
 
             // Parse notBefore
             if offset < data.len() {
-                let time_len = self.parse_time(&data[offset..])?;
+                let (time, time_len) = Self::parse_time(&data[offset..])?;
+                self.not_before = time;
                 offset += time_len;
             }
 
             // Parse notAfter
             if offset < data.len() {
-                let time_len = self.parse_time_after(&data[offset..])?;
+                let (time, time_len) = Self::parse_time(&data[offset..])?;
+                self.not_after = time;
                 offset += time_len;
             }
         }
 
-        // Subject (SEQUENCE) - skip for now
+        // Subject (SEQUENCE)
         if offset < data.len() && data[offset] == 0x30 {
-            offset += 1;
-            let len = parse_der_length(&data[offset..])?;
-            offset += length_bytes(len) + len;
+            let (_, len) = der_element(&data[offset..], 0x30)?;
+            self.subject_der = data[offset..offset + len].to_vec();
+            self.subject = DistinguishedName::from_der(&self.subject_der)?;
+            offset += len;
         }
 
         // SubjectPublicKeyInfo (SEQUENCE)
@@ -459,45 +487,48 @@ impl Certificate {
         Ok(())
     }
 
-    /// Parse time field.
-    fn parse_time(&mut self, data: &[u8]) -> Result<usize, CertificateError> {
-        if data.is_empty() {
-            return Err(CertificateError::InvalidAsn1);
-        }
+    /// Parse a UTCTime or GeneralizedTime into a Unix timestamp,
+    /// returning it with the encoded length.
+    fn parse_time(data: &[u8]) -> Result<(i64, usize), CertificateError> {
+        let tag = *data.first().ok_or(CertificateError::InvalidAsn1)?;
+        let (value, len) = der_element(data, tag)?;
+
+        // DER requires seconds and a Z suffix
+        let digits = value
+            .strip_suffix(b"Z")
+            .filter(|digits| digits.iter().all(u8::is_ascii_digit))
+            .ok_or(CertificateError::InvalidAsn1)?;
+        let number = |digits: &[u8]| {
+            digits
+                .iter()
+                .fold(0i64, |n, digit| n * 10 + i64::from(digit - b'0'))
+        };
 
-        let (tag, offset) = match data[0] {
-            0x17 => (0x17, 1), // UTCTime
-            0x18 => (0x18, 1), // GeneralizedTime
+        let (year, rest) = match (tag, digits.len()) {
+            // UTCTime: two-digit years 50-99 are 19xx (RFC 5280)
+            (0x17, 12) => {
+                let year = number(&digits[..2]);
+                (
+                    if year >= 50 { 1900 + year } else { 2000 + year },
+                    &digits[2..],
+                )
+            }
+            // GeneralizedTime
+            (0x18, 14) => (number(&digits[..4]), &digits[4..]),
             _ => return Err(CertificateError::InvalidAsn1),
         };
-
-        let len = parse_der_length(&data[offset..])?;
-        let header = offset + length_bytes(len);
-
-        // Simplified time parsing - would parse actual time string
-        self.not_before = 0;
-
-        Ok(header + len)
-    }
-
-    /// Parse notAfter time field.
-    fn parse_time_after(&mut self, data: &[u8]) -> Result<usize, CertificateError> {
-        if data.is_empty() {
+        let [month, day, hour, minute, second] = [0, 2, 4, 6, 8].map(|i| number(&rest[i..i + 2]));
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
             return Err(CertificateError::InvalidAsn1);
         }
 
-        let offset = match data[0] {
-            0x17 | 0x18 => 1,
-            _ => return Err(CertificateError::InvalidAsn1),
-        };
-
-        let len = parse_der_length(&data[offset..])?;
-        let header = offset + length_bytes(len);
-
-        // Simplified - would parse actual time
-        self.not_after = i64::MAX;
-
-        Ok(header + len)
+        let time = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+        Ok((time, len))
     }
 
     /// Parse public key info.
@@ -602,8 +633,7 @@ impl Certificate {
         match oid {
             // Basic Constraints
             [0x55, 0x1D, 0x13] => {
-                // Parse basic constraints
-                self.is_ca = true; // Simplified
+                self.parse_basic_constraints(&data[oid_header + oid_len..])?;
             }
             // Key Usage
             [0x55, 0x1D, 0x0F] => {
@@ -627,6 +657,41 @@ impl Certificate {
         Ok(())
     }
 
+    /// Parse the Basic Constraints extension value, after the OID.
+    fn parse_basic_constraints(&mut self, data: &[u8]) -> Result<(), CertificateError> {
+        let mut value = data;
+        // Skip critical flag if present
+        if value.first() == Some(&0x01) {
+            let (_, len) = der_element(value, 0x01)?;
+            value = &value[len..];
+        }
+
+        let (octets, _) = der_element(value, 0x04)?;
+        let (mut constraints, _) = der_element(octets, 0x30)?;
+
+        // cA BOOLEAN DEFAULT FALSE
+        if constraints.first() == Some(&0x01) {
+            let (ca, len) = der_element(constraints, 0x01)?;
+            self.is_ca = ca.first().is_some_and(|&b| b != 0);
+            constraints = &constraints[len..];
+        }
+
+        // pathLenConstraint INTEGER OPTIONAL
+        if constraints.first() == Some(&0x02) {
+            let (path_length, _) = der_element(constraints, 0x02)?;
+            if path_length.is_empty() || path_length.len() > 4 || path_length[0] & 0x80 != 0 {
+                return Err(CertificateError::InvalidExtension);
+            }
+            self.path_length = Some(
+                path_length
+                    .iter()
+                    .fold(0u32, |n, &b| (n << 8) | u32::from(b)),
+            );
+        }
+
+        Ok(())
+    }
+
     /// Parse Subject Alternative Name extension.
     fn parse_san(&mut self, data: &[u8]) -> Result<(), CertificateError> {
         // Skip critical flag if present
@@ -773,6 +838,27 @@ impl Certificate {
         self.is_ca
     }
 
+    /// Get the path length constraint of a CA certificate.
+    pub fn path_length(&self) -> Option<u32> {
+        self.path_length
+    }
+
+    /// Check the validity window against a Unix timestamp.
+    pub fn check_validity(&self, now: i64) -> Result<(), CertificateError> {
+        if now < self.not_before {
+            Err(CertificateError::NotYetValid)
+        } else if now > self.not_after {
+            Err(CertificateError::Expired)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check whether `issuer`'s subject is this certificate's issuer.
+    pub fn is_issued_by(&self, issuer: &Certificate) -> bool {
+        !self.issuer_der.is_empty() && self.issuer_der == issuer.subject_der
+    }
+
     /// Check if certificate is expired.
     pub fn is_expired(&self) -> bool {
         // Would need current time
@@ -817,10 +903,9 @@ impl Certificate {
         false
     }
 
-    /// Get fingerprint (SHA-256).
+    /// Get fingerprint (SHA-256 of the DER encoding).
     pub fn fingerprint_sha256(&self) -> [u8; 32] {
-        // Would compute SHA-256 of the DER certificate
-        [0u8; 32]
+        super::crypto::sha256(&self.raw)
     }
 }
 
@@ -861,33 +946,14 @@ impl CertificateChain {
         &self.certificates
     }
 
-    /// Verify the chain against a root store.
-    pub fn verify(&self, root_store: &RootCertStore) -> Result<(), CertificateError> {
-        if self.certificates.is_empty() {
-            return Err(CertificateError::InvalidChain);
-        }
-
-        // Verify each certificate is signed by the next
-        for i in 0..self.certificates.len() - 1 {
-            let cert = &self.certificates[i];
-            let issuer = &self.certificates[i + 1];
-
-            if !cert.verify_signature(issuer) {
-                return Err(CertificateError::SignatureVerificationFailed);
-            }
-
-            if cert.is_expired() {
-                return Err(CertificateError::Expired);
-            }
-        }
-
-        // Verify the root against the store
-        let root = self.certificates.last().unwrap();
-        if !root_store.contains(root) {
-            return Err(CertificateError::InvalidChain);
-        }
-
-        Ok(())
+    /// Verify the chain against a root store, checking validity windows
+    /// at `now` (Unix time) when given.
+    pub fn verify(
+        &self,
+        root_store: &RootCertStore,
+        now: Option<i64>,
+    ) -> Result<(), CertificateError> {
+        root_store.verify(&self.certificates, now)
     }
 }
 
@@ -919,6 +985,74 @@ impl RootCertStore {
         Ok(())
     }
 
+    /// Verify a presented chain (leaf first) by building a path from the
+    /// leaf to one of the roots.
+    ///
+    /// The certificates after the leaf may come in any order, and unused
+    /// ones are ignored. Each issuer must name the certificate below it,
+    /// have signed it, be a CA, and allow the number of intermediates
+    /// under it. Validity windows are checked at `now` (Unix time) when
+    /// given.
+    pub fn verify(&self, chain: &[Certificate], now: Option<i64>) -> Result<(), CertificateError> {
+        let (leaf, intermediates) = chain.split_first().ok_or(CertificateError::InvalidChain)?;
+        if let Some(now) = now {
+            leaf.check_validity(now)?;
+        }
+
+        let mut used = vec![false; intermediates.len()];
+        self.build_path(leaf, 0, intermediates, &mut used, now)
+    }
+
+    /// Depth-first search for a path from `cert` to a root, where `depth`
+    /// intermediates already sit between `cert` and the leaf.
+    fn build_path(
+        &self,
+        cert: &Certificate,
+        depth: usize,
+        intermediates: &[Certificate],
+        used: &mut [bool],
+        now: Option<i64>,
+    ) -> Result<(), CertificateError> {
+        // A trusted certificate anchors the path by itself
+        if self
+            .roots
+            .iter()
+            .any(|root| root.subject_der == cert.subject_der && root.public_key == cert.public_key)
+        {
+            return Ok(());
+        }
+
+        let mut error = CertificateError::UnknownIssuer;
+        for root in self.roots.iter().filter(|root| cert.is_issued_by(root)) {
+            match check_issuer(cert, root, depth, now) {
+                Ok(()) => return Ok(()),
+                Err(e) => error = e,
+            }
+        }
+
+        if depth >= MAX_CHAIN_DEPTH {
+            return Err(error);
+        }
+        for (i, candidate) in intermediates.iter().enumerate() {
+            if used[i] || !cert.is_issued_by(candidate) {
+                continue;
+            }
+            if let Err(e) = check_issuer(cert, candidate, depth, now) {
+                error = e;
+                continue;
+            }
+
+            used[i] = true;
+            match self.build_path(candidate, depth + 1, intermediates, used, now) {
+                Ok(()) => return Ok(()),
+                Err(e) => error = e,
+            }
+            used[i] = false;
+        }
+
+        Err(error)
+    }
+
     /// Check if the store contains a certificate.
     pub fn contains(&self, cert: &Certificate) -> bool {
         for root in &self.roots {
@@ -987,6 +1121,59 @@ impl OcspResponder {
 
 // Helper functions
 
+/// Longest path of intermediates tried between a leaf and a root.
+const MAX_CHAIN_DEPTH: usize = 8;
+
+/// Check that `issuer` may have issued `cert` with `depth` intermediates
+/// below it.
+fn check_issuer(
+    cert: &Certificate,
+    issuer: &Certificate,
+    depth: usize,
+    now: Option<i64>,
+) -> Result<(), CertificateError> {
+    if !issuer.is_ca() {
+        return Err(CertificateError::InvalidChain);
+    }
+    if issuer
+        .path_length()
+        .is_some_and(|limit| depth > limit as usize)
+    {
+        return Err(CertificateError::PathLengthExceeded);
+    }
+    if let Some(now) = now {
+        issuer.check_validity(now)?;
+    }
+    if !cert.verify_signature(issuer) {
+        return Err(CertificateError::SignatureVerificationFailed);
+    }
+    Ok(())
+}
+
+/// Split a DER element with the given tag into its contents and its
+/// total encoded length.
+fn der_element(data: &[u8], tag: u8) -> Result<(&[u8], usize), CertificateError> {
+    if data.first() != Some(&tag) {
+        return Err(CertificateError::InvalidAsn1);
+    }
+    let len = parse_der_length(&data[1..])?;
+    let header = 1 + length_bytes(len);
+    if data.len() < header + len {
+        return Err(CertificateError::InvalidDer);
+    }
+    Ok((&data[header..header + len], header + len))
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
 /// Parse DER length.
 fn parse_der_length(data: &[u8]) -> Result<usize, CertificateError> {
     if data.is_empty() {
@@ -1025,7 +1212,7 @@ fn length_bytes(len: usize) -> usize {
 }
 
 /// Match hostname against pattern (supports wildcards).
-fn matches_hostname(pattern: &str, hostname: &str) -> bool {
+pub(crate) fn matches_hostname(pattern: &str, hostname: &str) -> bool {
     if pattern == hostname {
         return true;
     }
@@ -1073,9 +1260,251 @@ fn base64_decode(input: &str) -> Result<Vec<u8>, CertificateError> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    /// 2024-01-01T00:00:00Z
+    pub(crate) const NOW: i64 = 1_704_067_200;
+    pub(crate) const VALID: (&str, &str) = ("230101000000Z", "300101000000Z");
+    /// End of `VALID`, 2030-01-01T00:00:00Z
+    const VALID_UNTIL: i64 = 1_893_456_000;
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        match content.len() {
+            len @ 0..=0x7F => out.push(len as u8),
+            len @ 0x80..=0xFF => out.extend_from_slice(&[0x81, len as u8]),
+            len => {
+                out.push(0x82);
+                out.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn name(common_name: &str) -> Vec<u8> {
+        let attribute = [
+            tlv(0x06, &[0x55, 0x04, 0x03]),
+            tlv(0x0C, common_name.as_bytes()),
+        ]
+        .concat();
+        tlv(0x30, &tlv(0x31, &tlv(0x30, &attribute)))
+    }
+
+    fn time(value: &str) -> Vec<u8> {
+        let tag = if value.len() == 13 { 0x17 } else { 0x18 };
+        tlv(tag, value.as_bytes())
+    }
+
+    /// Build a certificate with a distinct public key per `key`, and the
+    /// Basic Constraints extension when `constraints` (CA flag and path
+    /// length) is given.
+    pub(crate) fn issue(
+        subject: &str,
+        issuer: &str,
+        key: u8,
+        constraints: Option<(bool, Option<u8>)>,
+        validity: (&str, &str),
+    ) -> Certificate {
+        let algorithm = tlv(
+            0x30,
+            &[
+                tlv(
+                    0x06,
+                    &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B],
+                ),
+                tlv(0x05, &[]),
+            ]
+            .concat(),
+        );
+        let public_key = tlv(
+            0x30,
+            &[
+                tlv(
+                    0x30,
+                    &tlv(0x06, &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01]),
+                ),
+                tlv(0x03, &[0, 4, key, key]),
+            ]
+            .concat(),
+        );
+
+        let mut tbs = [
+            tlv(0xA0, &tlv(0x02, &[2])),
+            tlv(0x02, &[key]),
+            algorithm.clone(),
+            name(issuer),
+            tlv(0x30, &[time(validity.0), time(validity.1)].concat()),
+            name(subject),
+            public_key,
+        ]
+        .concat();
+        if let Some((ca, path_length)) = constraints {
+            let mut value = Vec::new();
+            if ca {
+                value.extend(tlv(0x01, &[0xFF]));
+            }
+            if let Some(path_length) = path_length {
+                value.extend(tlv(0x02, &[path_length]));
+            }
+            let extension = [
+                tlv(0x06, &[0x55, 0x1D, 0x13]),
+                tlv(0x01, &[0xFF]),
+                tlv(0x04, &tlv(0x30, &value)),
+            ]
+            .concat();
+            tbs.extend(tlv(0xA3, &tlv(0x30, &tlv(0x30, &extension))));
+        }
+
+        let der = tlv(
+            0x30,
+            &[tlv(0x30, &tbs), algorithm, tlv(0x03, &[0, 1, 2, 3])].concat(),
+        );
+        Certificate::from_der(&der).unwrap()
+    }
+
+    fn store(roots: &[&Certificate]) -> RootCertStore {
+        let mut store = RootCertStore::empty();
+        for root in roots {
+            store.add((*root).clone()).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_parse_names_validity_and_constraints() {
+        let cert = issue(
+            "Intermediate",
+            "Root",
+            2,
+            Some((true, Some(1))),
+            ("240101000000Z", "20500101000000Z"),
+        );
+        assert_eq!(cert.subject().common_name.as_deref(), Some("Intermediate"));
+        assert_eq!(cert.issuer().to_string(), "CN=Root");
+        assert_eq!(cert.not_before(), NOW);
+        assert_eq!(cert.not_after(), 2_524_608_000);
+        assert!(cert.is_ca());
+        assert_eq!(cert.path_length(), Some(1));
+        assert!(matches!(
+            cert.check_validity(NOW - 1),
+            Err(CertificateError::NotYetValid)
+        ));
+        assert!(cert.check_validity(NOW).is_ok());
+
+        // Two-digit years from 50 are in the 1900s
+        let old = issue(
+            "Old",
+            "Old",
+            3,
+            Some((false, None)),
+            ("500101000000Z", "491231235959Z"),
+        );
+        assert_eq!(old.not_before(), -631_152_000);
+        assert_eq!(old.not_after(), 2_524_607_999);
+        assert!(!old.is_ca());
+    }
+
+    #[test]
+    fn test_fingerprint_is_sha256_of_der() {
+        let cert = issue("example.com", "Root", 2, None, VALID);
+        let fingerprint = cert.fingerprint_sha256();
+        assert_eq!(fingerprint, crate::tls::crypto::sha256(cert.to_der()));
+        assert_ne!(fingerprint, [0; 32]);
+        let other = issue("example.com", "Root", 3, None, VALID);
+        assert_ne!(other.fingerprint_sha256(), fingerprint);
+    }
+
+    #[test]
+    fn test_chain_with_out_of_order_intermediates() {
+        let root = issue("Root", "Root", 1, Some((true, None)), VALID);
+        let upper = issue("Upper CA", "Root", 2, Some((true, Some(1))), VALID);
+        let lower = issue("Lower CA", "Upper CA", 3, Some((true, Some(0))), VALID);
+        let leaf = issue("example.com", "Lower CA", 4, None, VALID);
+        let unrelated = issue("Other CA", "Root", 5, Some((true, None)), VALID);
+        let roots = store(&[&root]);
+
+        let presented = [leaf.clone(), upper.clone(), lower.clone()];
+        assert!(roots.verify(&presented, Some(NOW)).is_ok());
+        let presented = [leaf.clone(), unrelated, root.clone(), lower.clone(), upper];
+        assert!(roots.verify(&presented, Some(NOW)).is_ok());
+
+        // A missing intermediate breaks the path
+        assert!(matches!(
+            roots.verify(&[leaf.clone(), lower.clone()], Some(NOW)),
+            Err(CertificateError::UnknownIssuer)
+        ));
+        assert!(matches!(
+            CertificateChain::from_certificates(vec![leaf, lower]).verify(&store(&[]), None),
+            Err(CertificateError::UnknownIssuer)
+        ));
+    }
+
+    #[test]
+    fn test_chain_rejects_unanchored_issuers() {
+        let root = issue("Root", "Root", 1, Some((true, None)), VALID);
+        let ca = issue("CA", "Root", 2, Some((true, None)), VALID);
+        let roots = store(&[&root]);
+
+        // The leaf's issuer is neither presented nor trusted
+        let stray = issue("example.com", "Elsewhere CA", 3, None, VALID);
+        assert!(matches!(
+            roots.verify(&[stray, ca], Some(NOW)),
+            Err(CertificateError::UnknownIssuer)
+        ));
+
+        // A self-consistent chain up to an untrusted root
+        let other_root = issue("Other Root", "Other Root", 9, Some((true, None)), VALID);
+        let other_ca = issue("Other CA", "Other Root", 8, Some((true, None)), VALID);
+        let leaf = issue("example.com", "Other CA", 7, None, VALID);
+        let presented = [leaf, other_ca, other_root.clone()];
+        assert!(matches!(
+            roots.verify(&presented, Some(NOW)),
+            Err(CertificateError::UnknownIssuer)
+        ));
+        assert!(store(&[&other_root]).verify(&presented, Some(NOW)).is_ok());
+    }
+
+    #[test]
+    fn test_chain_constraints_and_validity() {
+        let root = issue("Root", "Root", 1, Some((true, Some(0))), VALID);
+        let roots = store(&[&root]);
+
+        let not_ca = issue("Server", "Root", 2, Some((false, None)), VALID);
+        let leaf = issue("example.com", "Server", 3, None, VALID);
+        assert!(matches!(
+            roots.verify(&[leaf, not_ca], Some(NOW)),
+            Err(CertificateError::InvalidChain)
+        ));
+
+        // The root allows no intermediates below it
+        let ca = issue("CA", "Root", 4, Some((true, None)), VALID);
+        let leaf = issue("example.com", "CA", 5, None, VALID);
+        assert!(matches!(
+            roots.verify(&[leaf.clone(), ca], Some(NOW)),
+            Err(CertificateError::PathLengthExceeded)
+        ));
+
+        let expired = issue(
+            "CA",
+            "Root",
+            6,
+            Some((true, None)),
+            ("200101000000Z", "210101000000Z"),
+        );
+        let roots = store(&[&issue("Root", "Root", 1, Some((true, None)), VALID)]);
+        assert!(matches!(
+            roots.verify(&[leaf.clone(), expired.clone()], Some(NOW)),
+            Err(CertificateError::Expired)
+        ));
+        assert!(roots.verify(&[leaf.clone(), expired], None).is_ok());
+        assert!(matches!(
+            roots.verify(&[leaf], Some(VALID_UNTIL + 1)),
+            Err(CertificateError::Expired)
+        ));
+    }
+
     #[test]
     fn test_hostname_matching() {
         assert!(matches_hostname("example.com", "example.com"));
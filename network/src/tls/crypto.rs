@@ -0,0 +1,868 @@
+//! TLS 1.3 cryptographic primitives.
+//!
+//! The AEAD ciphers of the TLS 1.3 cipher suites (AES-128-GCM,
+//! AES-256-GCM and ChaCha20-Poly1305), and the SHA-2, HMAC and HKDF
+//! functions behind the key schedule and the record key and IV
+//! derivation (RFC 8446 §7).
+
+use alloc::vec::Vec;
+
+use super::{CipherSuite, TlsError};
+
+/// Length of the authentication tag appended by every supported AEAD.
+pub const TAG_LEN: usize = 16;
+
+/// Length of the per-record nonce.
+pub const NONCE_LEN: usize = 12;
+
+/// Key and IV protecting one direction of a TLS 1.3 connection.
+pub struct TrafficKey {
+    cipher: Cipher,
+    iv: [u8; NONCE_LEN],
+}
+
+enum Cipher {
+    AesGcm(Aes),
+    ChaCha20Poly1305([u8; 32]),
+}
+
+impl TrafficKey {
+    /// Derive the record key and IV from a traffic secret.
+    pub fn derive(suite: CipherSuite, secret: &[u8]) -> Result<Self, TlsError> {
+        if !suite.is_tls13() {
+            return Err(TlsError::UnsupportedCipherSuite);
+        }
+        let hash = HashAlgorithm::for_suite(suite);
+        if secret.len() != hash.output_len() {
+            return Err(TlsError::HandshakeFailure);
+        }
+
+        let key = hkdf_expand_label(hash, secret, b"key", &[], suite.key_length());
+        let mut iv = [0u8; NONCE_LEN];
+        iv.copy_from_slice(&hkdf_expand_label(hash, secret, b"iv", &[], NONCE_LEN));
+
+        let cipher = match suite {
+            CipherSuite::Tls13Chacha20Poly1305Sha256 => {
+                let mut chacha_key = [0u8; 32];
+                chacha_key.copy_from_slice(&key);
+                Cipher::ChaCha20Poly1305(chacha_key)
+            }
+            _ => Cipher::AesGcm(Aes::new(&key)),
+        };
+
+        Ok(Self { cipher, iv })
+    }
+
+    /// Encrypt a record, appending the authentication tag.
+    pub fn seal(&self, seq: u64, aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let nonce = self.nonce(seq);
+        let mut out = plaintext.to_vec();
+        let tag = match &self.cipher {
+            Cipher::AesGcm(aes) => {
+                gcm_ctr(aes, &nonce, &mut out);
+                gcm_tag(aes, &nonce, aad, &out)
+            }
+            Cipher::ChaCha20Poly1305(key) => {
+                chacha20_xor(key, &nonce, &mut out);
+                poly1305(&poly1305_key(key, &nonce), &mac_data(aad, &out))
+            }
+        };
+        out.extend_from_slice(&tag);
+        out
+    }
+
+    /// Verify a record's authentication tag and decrypt it.
+    pub fn open(&self, seq: u64, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, TlsError> {
+        if ciphertext.len() < TAG_LEN {
+            return Err(TlsError::DecryptionError);
+        }
+        let (body, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+        let nonce = self.nonce(seq);
+
+        let expected = match &self.cipher {
+            Cipher::AesGcm(aes) => gcm_tag(aes, &nonce, aad, body),
+            Cipher::ChaCha20Poly1305(key) => {
+                poly1305(&poly1305_key(key, &nonce), &mac_data(aad, body))
+            }
+        };
+        // Compare without an early exit so timing does not leak the tag
+        let diff = expected
+            .iter()
+            .zip(tag)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b));
+        if diff != 0 {
+            return Err(TlsError::AuthenticationError);
+        }
+
+        let mut plaintext = body.to_vec();
+        match &self.cipher {
+            Cipher::AesGcm(aes) => gcm_ctr(aes, &nonce, &mut plaintext),
+            Cipher::ChaCha20Poly1305(key) => chacha20_xor(key, &nonce, &mut plaintext),
+        }
+        Ok(plaintext)
+    }
+
+    /// Per-record nonce: the IV XORed with the padded sequence number
+    /// (RFC 8446 §5.3).
+    fn nonce(&self, seq: u64) -> [u8; NONCE_LEN] {
+        let mut nonce = self.iv;
+        for (byte, seq_byte) in nonce[NONCE_LEN - 8..].iter_mut().zip(seq.to_be_bytes()) {
+            *byte ^= seq_byte;
+        }
+        nonce
+    }
+}
+
+/// Hash of a cipher suite's key schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+impl HashAlgorithm {
+    /// The hash named by a cipher suite.
+    pub fn for_suite(suite: CipherSuite) -> Self {
+        match suite {
+            CipherSuite::Tls13Aes256GcmSha384
+            | CipherSuite::EcdheRsaAes256GcmSha384
+            | CipherSuite::EcdheEcdsaAes256GcmSha384 => HashAlgorithm::Sha384,
+            _ => HashAlgorithm::Sha256,
+        }
+    }
+
+    pub fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+        }
+    }
+
+    fn block_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 64,
+            HashAlgorithm::Sha384 => 128,
+        }
+    }
+
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => sha256(data).to_vec(),
+            HashAlgorithm::Sha384 => sha384(data).to_vec(),
+        }
+    }
+}
+
+/// HKDF-Extract (RFC 5869); an empty salt stands for a string of zeros.
+pub fn hkdf_extract(hash: HashAlgorithm, salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hmac(hash, salt, ikm)
+}
+
+/// HKDF-Expand-Label (RFC 8446 §7.1).
+pub fn hkdf_expand_label(
+    hash: HashAlgorithm,
+    secret: &[u8],
+    label: &[u8],
+    context: &[u8],
+    len: usize,
+) -> Vec<u8> {
+    let mut info = Vec::with_capacity(10 + label.len() + context.len());
+    info.extend_from_slice(&(len as u16).to_be_bytes());
+    info.push((6 + label.len()) as u8);
+    info.extend_from_slice(b"tls13 ");
+    info.extend_from_slice(label);
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+    hkdf_expand(hash, secret, &info, len)
+}
+
+/// HKDF-Expand (RFC 5869).
+fn hkdf_expand(hash: HashAlgorithm, prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    let mut okm = Vec::with_capacity(len);
+    let mut block = Vec::new();
+    let mut counter = 1u8;
+    while okm.len() < len {
+        let mut input = block;
+        input.extend_from_slice(info);
+        input.push(counter);
+        block = hmac(hash, prk, &input);
+        okm.extend_from_slice(&block);
+        counter += 1;
+    }
+    okm.truncate(len);
+    okm
+}
+
+/// HMAC (RFC 2104).
+fn hmac(hash: HashAlgorithm, key: &[u8], data: &[u8]) -> Vec<u8> {
+    let block_len = hash.block_len();
+    let mut block_key = if key.len() > block_len {
+        hash.digest(key)
+    } else {
+        key.to_vec()
+    };
+    block_key.resize(block_len, 0);
+
+    let mut inner: Vec<u8> = block_key.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(data);
+    let mut outer: Vec<u8> = block_key.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(&hash.digest(&inner));
+    hash.digest(&outer)
+}
+
+// SHA-2 (FIPS 180-4)
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const SHA512_K: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+const SHA384_IV: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state = SHA256_IV;
+    for block in md_pad(data, 64, 8).chunks_exact(64) {
+        sha256_compress(&mut state, block);
+    }
+    let mut out = [0u8; 32];
+    for (bytes, word) in out.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+fn sha384(data: &[u8]) -> [u8; 48] {
+    let mut state = SHA384_IV;
+    for block in md_pad(data, 128, 16).chunks_exact(128) {
+        sha512_compress(&mut state, block);
+    }
+    let mut out = [0u8; 48];
+    for (bytes, word) in out.chunks_exact_mut(8).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+/// Append the 1 bit, zero fill and the big-endian message bit length in
+/// a `len_bytes` field, up to a whole number of blocks.
+fn md_pad(data: &[u8], block_len: usize, len_bytes: usize) -> Vec<u8> {
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % block_len != block_len - len_bytes {
+        padded.push(0);
+    }
+    let bits = (data.len() as u128 * 8).to_be_bytes();
+    padded.extend_from_slice(&bits[16 - len_bytes..]);
+    padded
+}
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in SHA256_K.iter().zip(w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+fn sha512_compress(state: &mut [u64; 8], block: &[u8]) {
+    let mut w = [0u64; 80];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(8)) {
+        let mut be = [0u8; 8];
+        be.copy_from_slice(bytes);
+        *word = u64::from_be_bytes(be);
+    }
+    for i in 16..80 {
+        let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+        let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in SHA512_K.iter().zip(w) {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(w);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+// AES (FIPS 197), encryption only: GCM never runs the cipher backwards
+
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// Expanded AES-128 or AES-256 key.
+struct Aes {
+    round_keys: Vec<[u8; 16]>,
+}
+
+impl Aes {
+    fn new(key: &[u8]) -> Self {
+        let nk = key.len() / 4;
+        let rounds = nk + 6;
+        let mut words: Vec<[u8; 4]> = key
+            .chunks_exact(4)
+            .map(|w| [w[0], w[1], w[2], w[3]])
+            .collect();
+        for i in nk..4 * (rounds + 1) {
+            let mut temp = words[i - 1];
+            if i % nk == 0 {
+                temp = [
+                    SBOX[temp[1] as usize] ^ RCON[i / nk - 1],
+                    SBOX[temp[2] as usize],
+                    SBOX[temp[3] as usize],
+                    SBOX[temp[0] as usize],
+                ];
+            } else if nk > 6 && i % nk == 4 {
+                temp = temp.map(|b| SBOX[b as usize]);
+            }
+            let prev = words[i - nk];
+            words.push(core::array::from_fn(|j| prev[j] ^ temp[j]));
+        }
+
+        let round_keys = words
+            .chunks_exact(4)
+            .map(|w| core::array::from_fn(|j| w[j / 4][j % 4]))
+            .collect();
+        Self { round_keys }
+    }
+
+    fn encrypt_block(&self, block: &mut [u8; 16]) {
+        let last = self.round_keys.len() - 1;
+        for (round, key) in self.round_keys.iter().enumerate() {
+            if round > 0 {
+                for byte in block.iter_mut() {
+                    *byte = SBOX[*byte as usize];
+                }
+                shift_rows(block);
+                if round != last {
+                    mix_columns(block);
+                }
+            }
+            for (byte, k) in block.iter_mut().zip(key) {
+                *byte ^= k;
+            }
+        }
+    }
+}
+
+/// Rotate row `r` of the column-major state left by `r`.
+fn shift_rows(block: &mut [u8; 16]) {
+    let state = *block;
+    for (i, byte) in block.iter_mut().enumerate() {
+        let (row, col) = (i % 4, i / 4);
+        *byte = state[row + 4 * ((col + row) % 4)];
+    }
+}
+
+fn mix_columns(block: &mut [u8; 16]) {
+    for column in block.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [column[0], column[1], column[2], column[3]];
+        column[0] = xtime(a0) ^ xtime(a1) ^ a1 ^ a2 ^ a3;
+        column[1] = a0 ^ xtime(a1) ^ xtime(a2) ^ a2 ^ a3;
+        column[2] = a0 ^ a1 ^ xtime(a2) ^ xtime(a3) ^ a3;
+        column[3] = xtime(a0) ^ a0 ^ a1 ^ a2 ^ xtime(a3);
+    }
+}
+
+/// Multiply by x in GF(2^8).
+fn xtime(a: u8) -> u8 {
+    (a << 1) ^ ((a >> 7) * 0x1b)
+}
+
+// GCM (NIST SP 800-38D) with a 96-bit nonce
+
+/// CTR-mode keystream starting at counter block 2 (block 1 masks the tag).
+fn gcm_ctr(aes: &Aes, nonce: &[u8; NONCE_LEN], data: &mut [u8]) {
+    let mut counter = [0u8; 16];
+    counter[..NONCE_LEN].copy_from_slice(nonce);
+    for (i, chunk) in data.chunks_mut(16).enumerate() {
+        counter[12..].copy_from_slice(&(i as u32).wrapping_add(2).to_be_bytes());
+        let mut keystream = counter;
+        aes.encrypt_block(&mut keystream);
+        for (byte, k) in chunk.iter_mut().zip(keystream) {
+            *byte ^= k;
+        }
+    }
+}
+
+fn gcm_tag(aes: &Aes, nonce: &[u8; NONCE_LEN], aad: &[u8], ciphertext: &[u8]) -> [u8; TAG_LEN] {
+    let mut h = [0u8; 16];
+    aes.encrypt_block(&mut h);
+    let h = u128::from_be_bytes(h);
+
+    let mut y = 0u128;
+    for data in [aad, ciphertext] {
+        for chunk in data.chunks(16) {
+            let mut block = [0u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            y = gf128_mul(y ^ u128::from_be_bytes(block), h);
+        }
+    }
+    let lengths = ((aad.len() as u128 * 8) << 64) | (ciphertext.len() as u128 * 8);
+    y = gf128_mul(y ^ lengths, h);
+
+    let mut mask = [0u8; 16];
+    mask[..NONCE_LEN].copy_from_slice(nonce);
+    mask[15] = 1;
+    aes.encrypt_block(&mut mask);
+    (y ^ u128::from_be_bytes(mask)).to_be_bytes()
+}
+
+/// Multiply in GHASH's bit-reflected GF(2^128), without data-dependent
+/// branches.
+fn gf128_mul(x: u128, y: u128) -> u128 {
+    let mut z = 0u128;
+    let mut v = y;
+    for i in (0..128).rev() {
+        z ^= v & 0u128.wrapping_sub((x >> i) & 1);
+        v = (v >> 1) ^ ((0xE1 << 120) & 0u128.wrapping_sub(v & 1));
+    }
+    z
+}
+
+// ChaCha20 and Poly1305 (RFC 8439)
+
+fn chacha20_block(key: &[u8; 32], counter: u32, nonce: &[u8; NONCE_LEN]) -> [u8; 64] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    for (word, bytes) in state[4..12].iter_mut().zip(key.chunks_exact(4)) {
+        *word = le32(bytes);
+    }
+    state[12] = counter;
+    for (word, bytes) in state[13..].iter_mut().zip(nonce.chunks_exact(4)) {
+        *word = le32(bytes);
+    }
+
+    let mut working = state;
+    for _ in 0..10 {
+        quarter_round(&mut working, 0, 4, 8, 12);
+        quarter_round(&mut working, 1, 5, 9, 13);
+        quarter_round(&mut working, 2, 6, 10, 14);
+        quarter_round(&mut working, 3, 7, 11, 15);
+        quarter_round(&mut working, 0, 5, 10, 15);
+        quarter_round(&mut working, 1, 6, 11, 12);
+        quarter_round(&mut working, 2, 7, 8, 13);
+        quarter_round(&mut working, 3, 4, 9, 14);
+    }
+
+    let mut out = [0u8; 64];
+    for ((bytes, word), initial) in out.chunks_exact_mut(4).zip(working).zip(state) {
+        bytes.copy_from_slice(&word.wrapping_add(initial).to_le_bytes());
+    }
+    out
+}
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// Encrypt or decrypt with the keystream from block counter 1 onwards.
+fn chacha20_xor(key: &[u8; 32], nonce: &[u8; NONCE_LEN], data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(64).enumerate() {
+        let keystream = chacha20_block(key, (i as u32).wrapping_add(1), nonce);
+        for (byte, k) in chunk.iter_mut().zip(keystream) {
+            *byte ^= k;
+        }
+    }
+}
+
+/// One-time Poly1305 key from block counter 0.
+fn poly1305_key(key: &[u8; 32], nonce: &[u8; NONCE_LEN]) -> [u8; 32] {
+    let block = chacha20_block(key, 0, nonce);
+    let mut one_time_key = [0u8; 32];
+    one_time_key.copy_from_slice(&block[..32]);
+    one_time_key
+}
+
+/// The AEAD construction's MAC input: padded AAD, padded ciphertext and
+/// both lengths.
+fn mac_data(aad: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(aad.len() + ciphertext.len() + 48);
+    for part in [aad, ciphertext] {
+        data.extend_from_slice(part);
+        data.resize(data.len().next_multiple_of(16), 0);
+    }
+    data.extend_from_slice(&(aad.len() as u64).to_le_bytes());
+    data.extend_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    data
+}
+
+/// Poly1305 over 26-bit limbs.
+fn poly1305(key: &[u8; 32], message: &[u8]) -> [u8; TAG_LEN] {
+    const MASK: u32 = 0x3ff_ffff;
+
+    let r = [
+        le32(&key[0..]) & 0x3ff_ffff,
+        (le32(&key[3..]) >> 2) & 0x3ff_ff03,
+        (le32(&key[6..]) >> 4) & 0x3ff_c0ff,
+        (le32(&key[9..]) >> 6) & 0x3f0_3fff,
+        (le32(&key[12..]) >> 8) & 0x00f_ffff,
+    ]
+    .map(u64::from);
+    let s = [r[1] * 5, r[2] * 5, r[3] * 5, r[4] * 5];
+    let mut h = [0u32; 5];
+
+    for chunk in message.chunks(16) {
+        // Full blocks get the 2^128 bit from the 17th byte; a short final
+        // block has its 1 byte placed straight after the message
+        let mut block = [0u8; 17];
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()] = 1;
+        h[0] += le32(&block[0..]) & MASK;
+        h[1] += (le32(&block[3..]) >> 2) & MASK;
+        h[2] += (le32(&block[6..]) >> 4) & MASK;
+        h[3] += (le32(&block[9..]) >> 6) & MASK;
+        h[4] += (le32(&block[12..]) >> 8) | (u32::from(block[16]) << 24);
+
+        let [h0, h1, h2, h3, h4] = h.map(u64::from);
+        let d = [
+            h0 * r[0] + h1 * s[3] + h2 * s[2] + h3 * s[1] + h4 * s[0],
+            h0 * r[1] + h1 * r[0] + h2 * s[3] + h3 * s[2] + h4 * s[1],
+            h0 * r[2] + h1 * r[1] + h2 * r[0] + h3 * s[3] + h4 * s[2],
+            h0 * r[3] + h1 * r[2] + h2 * r[1] + h3 * r[0] + h4 * s[3],
+            h0 * r[4] + h1 * r[3] + h2 * r[2] + h3 * r[1] + h4 * r[0],
+        ];
+        let mut carry = 0u64;
+        for (limb, d) in h.iter_mut().zip(d) {
+            let d = d + carry;
+            *limb = d as u32 & MASK;
+            carry = d >> 26;
+        }
+        h[0] += carry as u32 * 5;
+        h[1] += h[0] >> 26;
+        h[0] &= MASK;
+    }
+
+    // Fully carry h, then subtract p = 2^130 - 5 if h >= p
+    let mut carry = 0;
+    for limb in h[1..].iter_mut() {
+        *limb += carry;
+        carry = *limb >> 26;
+        *limb &= MASK;
+    }
+    h[0] += carry * 5;
+    h[1] += h[0] >> 26;
+    h[0] &= MASK;
+
+    let mut g = [0u32; 5];
+    let mut carry = 5;
+    for (g, h) in g.iter_mut().zip(h) {
+        *g = h + carry;
+        carry = *g >> 26;
+        *g &= MASK;
+    }
+    g[4] = g[4].wrapping_add(carry << 26).wrapping_sub(1 << 26);
+    let keep_g = (g[4] >> 31).wrapping_sub(1);
+    for (h, g) in h.iter_mut().zip(g) {
+        *h = (*h & !keep_g) | (g & keep_g);
+    }
+
+    // h mod 2^128, plus s
+    let words = [
+        h[0] | (h[1] << 26),
+        (h[1] >> 6) | (h[2] << 20),
+        (h[2] >> 12) | (h[3] << 14),
+        (h[3] >> 18) | (h[4] << 8),
+    ];
+    let mut tag = [0u8; TAG_LEN];
+    let mut carry = 0u64;
+    for ((bytes, word), pad) in tag
+        .chunks_exact_mut(4)
+        .zip(words)
+        .zip(key[16..].chunks_exact(4))
+    {
+        let sum = u64::from(word) + u64::from(le32(pad)) + carry;
+        bytes.copy_from_slice(&(sum as u32).to_le_bytes());
+        carry = sum >> 32;
+    }
+    tag
+}
+
+fn le32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_key_schedule() {
+        // Server handshake traffic secret from RFC 8448 §3
+        let secret = hex("b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38");
+        let hash = HashAlgorithm::Sha256;
+        assert_eq!(
+            hkdf_expand_label(hash, &secret, b"key", &[], 16),
+            hex("3fce516009c21727d0f2e4e86ee403bc")
+        );
+        let key = TrafficKey::derive(CipherSuite::Tls13Aes128GcmSha256, &secret).unwrap();
+        assert_eq!(key.iv.to_vec(), hex("5d313eb2671276ee13000b30"));
+
+        let secret: Vec<u8> = (0..48).collect();
+        let key = TrafficKey::derive(CipherSuite::Tls13Aes256GcmSha384, &secret).unwrap();
+        assert_eq!(key.iv.to_vec(), hex("42822531a0fe88648fc09e9f"));
+        assert!(matches!(
+            TrafficKey::derive(CipherSuite::Tls13Aes128GcmSha256, &secret),
+            Err(TlsError::HandshakeFailure)
+        ));
+        assert!(matches!(
+            TrafficKey::derive(CipherSuite::EcdheRsaAes128GcmSha256, &secret[..32]),
+            Err(TlsError::UnsupportedCipherSuite)
+        ));
+    }
+
+    #[test]
+    fn test_aead_known_answers() {
+        let key32: Vec<u8> = (0..32).collect();
+        let mut chacha_key = [0u8; 32];
+        chacha_key.copy_from_slice(&key32);
+        let mut iv = [0u8; NONCE_LEN];
+        iv.copy_from_slice(&hex("cafebabefacedbaddecaf888"));
+        let aad = hex("1703030035");
+        let plaintext = b"The quick brown fox jumps over the lazy dog\x17";
+
+        let cases = [
+            (
+                Cipher::AesGcm(Aes::new(&key32[..16])),
+                "dd11a296f482e862c130abfa328dcec7e0644004319eddd542c9790d1355cda5\
+                 f24cb96c936e645ca920d72bb21c1effda5c2c790250a96da27eedc9",
+            ),
+            (
+                Cipher::AesGcm(Aes::new(&key32)),
+                "decbc506db0f26782d2b3faf146ae71f6b4fb871b56c07043dff6b07dbfb458b\
+                 c535e12aa0ae304242344f7f9b699dce93f484cc077bfd5543fa7394",
+            ),
+            (
+                Cipher::ChaCha20Poly1305(chacha_key),
+                "d8e90ca6245a8b4766ad61efec09b9e754bd19b57ea62d1d1c214ffe142816b3\
+                 1baf91b26793a874534a74eaad671722c03832de4c54f6e2b45fc41f",
+            ),
+        ];
+        for (cipher, expected) in cases {
+            let key = TrafficKey { cipher, iv };
+            let sealed = key.seal(0, &aad, plaintext);
+            assert_eq!(sealed, hex(expected));
+            assert_eq!(key.open(0, &aad, &sealed).unwrap(), plaintext);
+            // The sequence number is part of the nonce
+            assert!(matches!(
+                key.open(1, &aad, &sealed),
+                Err(TlsError::AuthenticationError)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_chacha20_poly1305_rfc8439() {
+        // RFC 8439 §2.8.2: the IV is the constant plus zeroed sequence
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = 0x80 + i as u8;
+        }
+        let mut iv = [0u8; NONCE_LEN];
+        iv.copy_from_slice(&hex("070000004041424344454647"));
+        let key = TrafficKey {
+            cipher: Cipher::ChaCha20Poly1305(key),
+            iv,
+        };
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one \
+                          tip for the future, sunscreen would be it.";
+
+        let sealed = key.seal(0, &hex("50515253c0c1c2c3c4c5c6c7"), plaintext);
+        assert_eq!(sealed.len(), plaintext.len() + TAG_LEN);
+        assert_eq!(
+            sealed[plaintext.len()..],
+            hex("1ae10b594f09e26a7e902ecbd0600691")
+        );
+    }
+}
@@ -11,6 +11,7 @@ use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
+use super::crypto::{hkdf_expand_label, hkdf_extract, HashAlgorithm};
 use super::{CipherSuite, TlsError, TlsVersion};
 
 /// Handshake message type.
@@ -294,6 +295,26 @@ impl ClientHello {
         data
     }
 
+    /// Find an extension by type.
+    pub fn find_extension(&self, ext_type: ExtensionType) -> Option<&Extension> {
+        self.extensions
+            .iter()
+            .find(|ext| ext.extension_type == ext_type as u16)
+    }
+
+    /// Get the host name requested via SNI, if any.
+    pub fn server_name(&self) -> Option<String> {
+        self.find_extension(ExtensionType::ServerName)
+            .and_then(Extension::parse_server_name)
+    }
+
+    /// Get the ALPN protocols offered by the client, in preference order.
+    pub fn alpn_protocols(&self) -> Vec<String> {
+        self.find_extension(ExtensionType::ApplicationLayerProtocolNegotiation)
+            .map(Extension::parse_alpn)
+            .unwrap_or_default()
+    }
+
     /// Serialize extensions.
     fn serialize_extensions(&self) -> Vec<u8> {
         let mut data = Vec::new();
@@ -399,6 +420,13 @@ impl ServerHello {
         self.extensions.push(ext);
     }
 
+    /// Find an extension by type.
+    pub fn find_extension(&self, ext_type: ExtensionType) -> Option<&Extension> {
+        self.extensions
+            .iter()
+            .find(|ext| ext.extension_type == ext_type as u16)
+    }
+
     /// Check if this is a HelloRetryRequest.
     pub fn is_hello_retry_request(&self) -> bool {
         // HelloRetryRequest has a special random value
@@ -623,6 +651,74 @@ impl Extension {
         )
     }
 
+    /// Parse the host name from a Server Name Indication extension.
+    ///
+    /// Returns `None` if this is not an SNI extension, the list is
+    /// malformed, or it carries no `host_name` entry.
+    pub fn parse_server_name(&self) -> Option<String> {
+        if self.extension_type != ExtensionType::ServerName as u16 || self.data.len() < 2 {
+            return None;
+        }
+
+        let list_len = u16::from_be_bytes([self.data[0], self.data[1]]) as usize;
+        let list = self.data.get(2..2 + list_len)?;
+
+        let mut offset = 0;
+        while offset + 3 <= list.len() {
+            let name_type = list[offset];
+            let name_len = u16::from_be_bytes([list[offset + 1], list[offset + 2]]) as usize;
+            offset += 3;
+
+            let name = list.get(offset..offset + name_len)?;
+            offset += name_len;
+
+            // host_name = 0; other name types are reserved
+            if name_type == 0 {
+                let name = core::str::from_utf8(name).ok()?;
+                return Some(name.to_ascii_lowercase());
+            }
+        }
+
+        None
+    }
+
+    /// Parse the protocol name list from an ALPN extension.
+    ///
+    /// Returns an empty list if this is not an ALPN extension or the
+    /// list is malformed.
+    pub fn parse_alpn(&self) -> Vec<String> {
+        let mut protocols = Vec::new();
+
+        if self.extension_type != ExtensionType::ApplicationLayerProtocolNegotiation as u16
+            || self.data.len() < 2
+        {
+            return protocols;
+        }
+
+        let list_len = u16::from_be_bytes([self.data[0], self.data[1]]) as usize;
+        let list = match self.data.get(2..2 + list_len) {
+            Some(list) => list,
+            None => return protocols,
+        };
+
+        let mut offset = 0;
+        while offset < list.len() {
+            let proto_len = list[offset] as usize;
+            offset += 1;
+
+            match list.get(offset..offset + proto_len) {
+                Some(proto) if proto_len > 0 => match core::str::from_utf8(proto) {
+                    Ok(proto) => protocols.push(String::from(proto)),
+                    Err(_) => return Vec::new(),
+                },
+                _ => return Vec::new(),
+            }
+            offset += proto_len;
+        }
+
+        protocols
+    }
+
     /// Serialize to bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut data = Vec::new();
@@ -766,14 +862,21 @@ impl HandshakeHash {
 
     /// Get the current transcript hash (SHA-256).
     pub fn transcript_hash(&self) -> [u8; 32] {
-        // Would compute SHA-256 of messages
-        [0u8; 32]
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&HashAlgorithm::Sha256.digest(&self.messages));
+        hash
     }
 
     /// Get the current transcript hash (SHA-384).
     pub fn transcript_hash_384(&self) -> [u8; 48] {
-        // Would compute SHA-384 of messages
-        [0u8; 48]
+        let mut hash = [0u8; 48];
+        hash.copy_from_slice(&HashAlgorithm::Sha384.digest(&self.messages));
+        hash
+    }
+
+    /// Get the current transcript hash with the hash of a cipher suite.
+    pub fn transcript_hash_for(&self, suite: CipherSuite) -> Vec<u8> {
+        HashAlgorithm::for_suite(suite).digest(&self.messages)
     }
 }
 
@@ -783,6 +886,132 @@ impl Default for HandshakeHash {
     }
 }
 
+/// TLS 1.3 key schedule (RFC 8446 §7.1), without PSKs.
+///
+/// Secrets are derived as the handshake progresses: the early secret on
+/// creation, the handshake traffic secrets once the ECDHE shared secret
+/// is known, and the master and application traffic secrets after the
+/// server's Finished.
+#[derive(Clone)]
+pub struct KeySchedule {
+    /// Hash of the negotiated cipher suite.
+    hash: HashAlgorithm,
+    /// Early secret.
+    early_secret: Vec<u8>,
+    /// Handshake secret.
+    handshake_secret: Vec<u8>,
+    /// Master secret.
+    master_secret: Vec<u8>,
+    /// Client handshake traffic secret.
+    client_handshake_traffic_secret: Vec<u8>,
+    /// Server handshake traffic secret.
+    server_handshake_traffic_secret: Vec<u8>,
+    /// Client application traffic secret.
+    client_application_traffic_secret: Vec<u8>,
+    /// Server application traffic secret.
+    server_application_traffic_secret: Vec<u8>,
+}
+
+impl KeySchedule {
+    /// Start the key schedule for a TLS 1.3 cipher suite.
+    pub fn new(suite: CipherSuite) -> Result<Self, TlsError> {
+        if !suite.is_tls13() {
+            return Err(TlsError::UnsupportedCipherSuite);
+        }
+        let hash = HashAlgorithm::for_suite(suite);
+        let zeros = vec![0u8; hash.output_len()];
+
+        Ok(Self {
+            hash,
+            early_secret: hkdf_extract(hash, &[], &zeros),
+            handshake_secret: Vec::new(),
+            master_secret: Vec::new(),
+            client_handshake_traffic_secret: Vec::new(),
+            server_handshake_traffic_secret: Vec::new(),
+            client_application_traffic_secret: Vec::new(),
+            server_application_traffic_secret: Vec::new(),
+        })
+    }
+
+    /// Length of every secret, the suite's hash length.
+    pub fn hash_len(&self) -> usize {
+        self.hash.output_len()
+    }
+
+    /// Derive the handshake secret and handshake traffic secrets from the
+    /// ECDHE shared secret and the transcript hash through ServerHello.
+    pub fn derive_handshake_secrets(&mut self, shared_secret: &[u8], transcript_hash: &[u8]) {
+        let salt = self.derive_secret(&self.early_secret, b"derived", &self.empty_hash());
+        self.handshake_secret = hkdf_extract(self.hash, &salt, shared_secret);
+        self.client_handshake_traffic_secret =
+            self.derive_secret(&self.handshake_secret, b"c hs traffic", transcript_hash);
+        self.server_handshake_traffic_secret =
+            self.derive_secret(&self.handshake_secret, b"s hs traffic", transcript_hash);
+    }
+
+    /// Derive the master secret and application traffic secrets from the
+    /// transcript hash through the server Finished.
+    pub fn derive_application_secrets(&mut self, transcript_hash: &[u8]) -> Result<(), TlsError> {
+        if self.handshake_secret.is_empty() {
+            return Err(TlsError::HandshakeFailure);
+        }
+
+        let salt = self.derive_secret(&self.handshake_secret, b"derived", &self.empty_hash());
+        self.master_secret = hkdf_extract(self.hash, &salt, &vec![0u8; self.hash_len()]);
+        self.client_application_traffic_secret =
+            self.derive_secret(&self.master_secret, b"c ap traffic", transcript_hash);
+        self.server_application_traffic_secret =
+            self.derive_secret(&self.master_secret, b"s ap traffic", transcript_hash);
+
+        Ok(())
+    }
+
+    /// Get the early secret.
+    pub fn early_secret(&self) -> &[u8] {
+        &self.early_secret
+    }
+
+    /// Get the handshake secret, empty until derived.
+    pub fn handshake_secret(&self) -> &[u8] {
+        &self.handshake_secret
+    }
+
+    /// Get the master secret, empty until derived.
+    pub fn master_secret(&self) -> &[u8] {
+        &self.master_secret
+    }
+
+    /// Get the client handshake traffic secret.
+    pub fn client_handshake_traffic_secret(&self) -> &[u8] {
+        &self.client_handshake_traffic_secret
+    }
+
+    /// Get the server handshake traffic secret.
+    pub fn server_handshake_traffic_secret(&self) -> &[u8] {
+        &self.server_handshake_traffic_secret
+    }
+
+    /// Get the client application traffic secret.
+    pub fn client_application_traffic_secret(&self) -> &[u8] {
+        &self.client_application_traffic_secret
+    }
+
+    /// Get the server application traffic secret.
+    pub fn server_application_traffic_secret(&self) -> &[u8] {
+        &self.server_application_traffic_secret
+    }
+
+    /// Derive-Secret(Secret, Label, Messages), given the messages' hash.
+    fn derive_secret(&self, secret: &[u8], label: &[u8], transcript_hash: &[u8]) -> Vec<u8> {
+        hkdf_expand_label(self.hash, secret, label, transcript_hash, self.hash_len())
+    }
+
+    /// Hash of an empty transcript.
+    fn empty_hash(&self) -> Vec<u8> {
+        self.hash.digest(&[])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -812,6 +1041,29 @@ mod tests {
         assert_eq!(bytes[1], 0); // ServerName = 0
     }
 
+    #[test]
+    fn test_parse_server_name() {
+        let ext = Extension::server_name("Example.COM");
+        assert_eq!(ext.parse_server_name().as_deref(), Some("example.com"));
+
+        let truncated = Extension::new(ExtensionType::ServerName as u16, vec![0x00, 0x10, 0x00]);
+        assert_eq!(truncated.parse_server_name(), None);
+    }
+
+    #[test]
+    fn test_parse_alpn() {
+        let ext = Extension::alpn(&["h2", "http/1.1"]);
+        assert_eq!(ext.parse_alpn(), vec!["h2", "http/1.1"]);
+
+        let mut hello = ClientHello::new([0u8; 32], vec![CipherSuite::Tls13Aes128GcmSha256]);
+        hello.add_extension(Extension::server_name("example.com"));
+        hello.add_extension(ext);
+
+        let parsed = ClientHello::from_bytes(&hello.to_bytes()).unwrap();
+        assert_eq!(parsed.server_name().as_deref(), Some("example.com"));
+        assert_eq!(parsed.alpn_protocols(), vec!["h2", "http/1.1"]);
+    }
+
     #[test]
     fn test_supported_versions() {
         let ext = Extension::supported_versions(&[TlsVersion::Tls13, TlsVersion::Tls12]);
@@ -822,4 +1074,91 @@ mod tests {
             ExtensionType::SupportedVersions as u16
         );
     }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_key_schedule_rfc8448() {
+        // RFC 8448 §3, simple 1-RTT handshake
+        let mut schedule = KeySchedule::new(CipherSuite::Tls13Aes128GcmSha256).unwrap();
+        assert_eq!(
+            schedule.early_secret(),
+            hex("33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a")
+        );
+        assert!(schedule.derive_application_secrets(&[0; 32]).is_err());
+
+        schedule.derive_handshake_secrets(
+            &hex("8bd4054fb55b9d63fdfbacf9f04b9f0d35e6d63f537563efd46272900f89492d"),
+            &hex("860c06edc07858ee8e78f0e7428c58edd6b43f2ca3e6e95f02ed063cf0e1cad8"),
+        );
+        assert_eq!(
+            schedule.handshake_secret(),
+            hex("1dc826e93606aa6fdc0aadc12f741b01046aa6b99f691ed221a9f0ca043fbeac")
+        );
+        assert_eq!(
+            schedule.client_handshake_traffic_secret(),
+            hex("b3eddb126e067f35a780b3abf45e2d8f3b1a950738f52e9600746a0e27a55a21")
+        );
+        assert_eq!(
+            schedule.server_handshake_traffic_secret(),
+            hex("b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38")
+        );
+
+        // ClientHello..server Finished
+        let transcript_hash =
+            hex("9608102a0f1ccc6db6250b7b7e417b1a000eaada3daae4777a7686c9ff83df13");
+        schedule
+            .derive_application_secrets(&transcript_hash)
+            .unwrap();
+        assert_eq!(
+            schedule.master_secret(),
+            hex("18df06843d13a08bf2a449844c5f8a478001bc4d4c627984d5a41da8d0402919")
+        );
+        assert_eq!(
+            schedule.client_application_traffic_secret(),
+            hex("9e40646ce79a7f9dc05af8889bce6552875afa0b06df0087f792ebb7c17504a5")
+        );
+        assert_eq!(
+            schedule.server_application_traffic_secret(),
+            hex("a11af9f05531f856ad47116b45a950328204b4f44bfb6b3a4b4f1f3fcb631643")
+        );
+    }
+
+    #[test]
+    fn test_key_schedule_sha384() {
+        let mut schedule = KeySchedule::new(CipherSuite::Tls13Aes256GcmSha384).unwrap();
+        assert_eq!(schedule.hash_len(), 48);
+        schedule.derive_handshake_secrets(&[0x42; 32], &[0x24; 48]);
+        schedule.derive_application_secrets(&[0x24; 48]).unwrap();
+        assert_eq!(schedule.master_secret().len(), 48);
+        assert_eq!(schedule.server_application_traffic_secret().len(), 48);
+
+        assert!(matches!(
+            KeySchedule::new(CipherSuite::EcdheRsaAes256GcmSha384),
+            Err(TlsError::UnsupportedCipherSuite)
+        ));
+    }
+
+    #[test]
+    fn test_transcript_hash() {
+        let mut transcript = HandshakeHash::new();
+        transcript.add(b"ab");
+        transcript.add(b"c");
+        assert_eq!(
+            transcript.transcript_hash().to_vec(),
+            hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            transcript.transcript_hash_for(CipherSuite::Tls13Aes256GcmSha384),
+            hex(
+                "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+                 8086072ba1e7cc2358baeca134c825a7"
+            )
+        );
+    }
 }
@@ -13,6 +13,7 @@ use alloc::vec::Vec;
 use core::fmt;
 
 pub mod certificate;
+mod crypto;
 pub mod handshake;
 pub mod record;
 
@@ -20,6 +21,8 @@ pub use certificate::*;
 pub use handshake::*;
 pub use record::*;
 
+use crypto::TrafficKey;
+
 /// TLS version.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TlsVersion {
@@ -188,6 +191,10 @@ pub enum TlsError {
     UnknownCa,
     /// Hostname mismatch.
     HostnameMismatch,
+    /// No certificate is configured for the requested SNI host name.
+    UnrecognizedName,
+    /// No ALPN protocol in common with the peer.
+    NoApplicationProtocol,
 }
 
 impl fmt::Display for TlsError {
@@ -208,6 +215,8 @@ impl fmt::Display for TlsError {
             TlsError::CertificateRevoked => write!(f, "Certificate revoked"),
             TlsError::UnknownCa => write!(f, "Unknown CA"),
             TlsError::HostnameMismatch => write!(f, "Hostname mismatch"),
+            TlsError::UnrecognizedName => write!(f, "Unrecognized server name"),
+            TlsError::NoApplicationProtocol => write!(f, "No application protocol"),
         }
     }
 }
@@ -294,6 +303,11 @@ pub struct TlsConfig {
     pub server_name: Option<String>,
     /// Whether to verify certificates.
     pub verify_certificates: bool,
+    /// Trusted root certificates that peer chains must lead to.
+    pub root_store: RootCertStore,
+    /// Current Unix time for checking certificate validity windows;
+    /// `None` skips those checks on systems without a clock.
+    pub current_time: Option<i64>,
     /// Whether to require client certificates.
     pub require_client_cert: bool,
     /// ALPN protocols.
@@ -320,6 +334,8 @@ impl Default for TlsConfig {
             ],
             server_name: None,
             verify_certificates: true,
+            root_store: RootCertStore::empty(),
+            current_time: None,
             require_client_cert: false,
             alpn_protocols: Vec::new(),
             session_resumption: true,
@@ -328,6 +344,84 @@ impl Default for TlsConfig {
     }
 }
 
+/// Server certificate paired with its private key.
+#[derive(Debug, Clone)]
+pub struct CertifiedKey {
+    /// Certificate presented to the client.
+    pub certificate: Certificate,
+    /// Private key for the certificate.
+    pub private_key: Vec<u8>,
+}
+
+/// Server-side certificate selection by SNI host name.
+///
+/// Host names may be exact (`www.example.com`) or a single-label
+/// wildcard (`*.example.com`). Exact entries win over wildcards, and
+/// the default certificate is used when nothing matches or the client
+/// did not send SNI.
+#[derive(Debug, Clone, Default)]
+pub struct CertificateResolver {
+    /// Fallback certificate.
+    default: Option<CertifiedKey>,
+    /// Certificates registered per host name (lowercased).
+    by_name: Vec<(String, CertifiedKey)>,
+}
+
+impl CertificateResolver {
+    /// Create an empty resolver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the fallback certificate.
+    pub fn set_default(&mut self, certificate: Certificate, private_key: Vec<u8>) {
+        self.default = Some(CertifiedKey {
+            certificate,
+            private_key,
+        });
+    }
+
+    /// Register a certificate for a host name, replacing any previous entry.
+    pub fn add(&mut self, host: &str, certificate: Certificate, private_key: Vec<u8>) {
+        let host = host.to_ascii_lowercase();
+        let key = CertifiedKey {
+            certificate,
+            private_key,
+        };
+
+        if let Some(entry) = self.by_name.iter_mut().find(|(name, _)| *name == host) {
+            entry.1 = key;
+        } else {
+            self.by_name.push((host, key));
+        }
+    }
+
+    /// Check whether any certificate is configured.
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.by_name.is_empty()
+    }
+
+    /// Select the certificate for a requested server name. Host names
+    /// compare case-insensitively.
+    pub fn resolve(&self, server_name: Option<&str>) -> Option<&CertifiedKey> {
+        let name = match server_name {
+            Some(name) => name.to_ascii_lowercase(),
+            None => return self.default.as_ref(),
+        };
+
+        self.by_name
+            .iter()
+            .find(|(host, _)| *host == name)
+            .or_else(|| {
+                self.by_name.iter().find(|(host, _)| {
+                    host.starts_with("*.") && certificate::matches_hostname(host, &name)
+                })
+            })
+            .map(|(_, key)| key)
+            .or(self.default.as_ref())
+    }
+}
+
 /// TLS session state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TlsState {
@@ -375,6 +469,12 @@ pub struct TlsSession {
     peer_certificates: Vec<Certificate>,
     /// Selected ALPN protocol.
     alpn_protocol: Option<String>,
+    /// Host name requested via SNI (server) or sent via SNI (client).
+    server_name: Option<String>,
+    /// Server certificates available for SNI dispatch.
+    cert_resolver: CertificateResolver,
+    /// Certificate selected for this connection (server only).
+    selected_certificate: Option<CertifiedKey>,
     /// Sequence number for sending.
     send_seq: u64,
     /// Sequence number for receiving.
@@ -383,6 +483,14 @@ pub struct TlsSession {
     client_traffic_secret: Vec<u8>,
     /// Traffic secrets for TLS 1.3.
     server_traffic_secret: Vec<u8>,
+    /// Handshake messages sent and received so far.
+    transcript: HandshakeHash,
+    /// TLS 1.3 key schedule, once the shared secret is known.
+    key_schedule: Option<KeySchedule>,
+    /// Key protecting records we send.
+    write_key: Option<TrafficKey>,
+    /// Key protecting records we receive.
+    read_key: Option<TrafficKey>,
 }
 
 impl TlsSession {
@@ -393,6 +501,7 @@ impl TlsSession {
         for (i, byte) in client_random.iter_mut().enumerate() {
             *byte = (i as u8).wrapping_mul(17).wrapping_add(42);
         }
+        let server_name = config.server_name.clone();
 
         Self {
             config,
@@ -406,10 +515,17 @@ impl TlsSession {
             session_id: Vec::new(),
             peer_certificates: Vec::new(),
             alpn_protocol: None,
+            server_name,
+            cert_resolver: CertificateResolver::new(),
+            selected_certificate: None,
             send_seq: 0,
             recv_seq: 0,
             client_traffic_secret: Vec::new(),
             server_traffic_secret: Vec::new(),
+            transcript: HandshakeHash::new(),
+            key_schedule: None,
+            write_key: None,
+            read_key: None,
         }
     }
 
@@ -432,10 +548,17 @@ impl TlsSession {
             session_id: Vec::new(),
             peer_certificates: Vec::new(),
             alpn_protocol: None,
+            server_name: None,
+            cert_resolver: CertificateResolver::new(),
+            selected_certificate: None,
             send_seq: 0,
             recv_seq: 0,
             client_traffic_secret: Vec::new(),
             server_traffic_secret: Vec::new(),
+            transcript: HandshakeHash::new(),
+            key_schedule: None,
+            write_key: None,
+            read_key: None,
         }
     }
 
@@ -464,6 +587,23 @@ impl TlsSession {
         self.alpn_protocol.as_deref()
     }
 
+    /// Check whether `h2` was negotiated via ALPN.
+    pub fn is_http2(&self) -> bool {
+        self.alpn_protocol.as_deref() == Some("h2")
+    }
+
+    /// Get the SNI host name for this session.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    /// Get the certificate selected for this connection (server only).
+    pub fn selected_certificate(&self) -> Option<&Certificate> {
+        self.selected_certificate
+            .as_ref()
+            .map(|key| &key.certificate)
+    }
+
     /// Get peer certificates.
     pub fn peer_certificates(&self) -> &[Certificate] {
         &self.peer_certificates
@@ -513,18 +653,21 @@ impl TlsSession {
             // Client waiting for ServerHello
             (true, TlsState::ClientHelloSent, 2) => {
                 self.process_server_hello(data)?;
+                self.transcript.add(data);
                 self.state = TlsState::ServerHelloReceived;
                 Ok(Vec::new())
             }
             // Client waiting for Certificate
             (true, TlsState::ServerHelloReceived, 11) => {
                 self.process_certificate(data)?;
+                self.transcript.add(data);
                 self.state = TlsState::CertificateReceived;
                 Ok(Vec::new())
             }
             // Server waiting for ClientHello
             (false, TlsState::Initial, 1) => {
                 self.process_client_hello(data)?;
+                self.transcript.add(data);
                 self.build_server_response()
             }
             _ => {
@@ -605,6 +748,7 @@ impl TlsSession {
 
         // Wrap in record
         let record = self.wrap_record(22, &hello);
+        self.transcript.add(&hello);
 
         self.state = TlsState::ClientHelloSent;
 
@@ -692,39 +836,29 @@ impl TlsSession {
             return Err(TlsError::InvalidRecord);
         }
 
-        // Skip handshake header (4 bytes)
-        let payload = &data[4..];
-
-        // Legacy version
-        let _version = [payload[0], payload[1]];
-
-        // Server random
-        self.server_random.copy_from_slice(&payload[2..34]);
-
-        // Session ID
-        let session_id_len = payload[34] as usize;
-        if payload.len() < 35 + session_id_len + 3 {
-            return Err(TlsError::InvalidRecord);
-        }
-
-        self.session_id = payload[35..35 + session_id_len].to_vec();
-
-        // Cipher suite
-        let offset = 35 + session_id_len;
-        let cipher_id = u16::from_be_bytes([payload[offset], payload[offset + 1]]);
-        self.cipher_suite = CipherSuite::from_id(cipher_id);
+        let hello = ServerHello::from_bytes(data)?;
 
-        if self.cipher_suite.is_none() {
-            return Err(TlsError::UnsupportedCipherSuite);
-        }
+        self.server_random = hello.random;
+        self.session_id = hello.session_id.clone();
+        self.cipher_suite = Some(hello.cipher_suite);
 
         // Determine version from extensions or cipher suite
-        if self.cipher_suite.unwrap().is_tls13() {
+        if hello.cipher_suite.is_tls13() {
             self.version = Some(TlsVersion::Tls13);
         } else {
             self.version = Some(TlsVersion::Tls12);
         }
 
+        // The server must select exactly one of the protocols we offered
+        if let Some(ext) = hello.find_extension(ExtensionType::ApplicationLayerProtocolNegotiation)
+        {
+            let selected = ext.parse_alpn();
+            if selected.len() != 1 || !self.config.alpn_protocols.contains(&selected[0]) {
+                return Err(TlsError::HandshakeFailure);
+            }
+            self.alpn_protocol = selected.into_iter().next();
+        }
+
         Ok(())
     }
 
@@ -780,31 +914,17 @@ impl TlsSession {
             return Err(TlsError::InvalidRecord);
         }
 
-        // Skip handshake header
-        let payload = &data[4..];
-
-        // Client random
-        self.client_random.copy_from_slice(&payload[2..34]);
+        let hello = ClientHello::from_bytes(data)?;
 
-        // Session ID
-        let session_id_len = payload[34] as usize;
-        self.session_id = payload[35..35 + session_id_len].to_vec();
+        self.client_random = hello.random;
+        self.session_id = hello.session_id.clone();
 
         // Find cipher suite
-        let offset = 35 + session_id_len;
-        let cipher_len = u16::from_be_bytes([payload[offset], payload[offset + 1]]) as usize;
-
-        for i in (0..cipher_len).step_by(2) {
-            let cipher_id =
-                u16::from_be_bytes([payload[offset + 2 + i], payload[offset + 2 + i + 1]]);
-
-            if let Some(suite) = CipherSuite::from_id(cipher_id) {
-                if self.config.cipher_suites.contains(&suite) {
-                    self.cipher_suite = Some(suite);
-                    break;
-                }
-            }
-        }
+        self.cipher_suite = hello
+            .cipher_suites
+            .iter()
+            .copied()
+            .find(|suite| self.config.cipher_suites.contains(suite));
 
         if self.cipher_suite.is_none() {
             return Err(TlsError::UnsupportedCipherSuite);
@@ -817,9 +937,55 @@ impl TlsSession {
             self.version = Some(TlsVersion::Tls12);
         }
 
+        self.server_name = hello.server_name();
+        self.select_certificate()?;
+        self.alpn_protocol = self.select_alpn(&hello.alpn_protocols())?;
+
         Ok(())
     }
 
+    /// Pick the server certificate for the requested SNI host name.
+    fn select_certificate(&mut self) -> Result<(), TlsError> {
+        if self.cert_resolver.is_empty() {
+            return Ok(());
+        }
+
+        match self.cert_resolver.resolve(self.server_name.as_deref()) {
+            Some(key) => {
+                self.selected_certificate = Some(key.clone());
+                Ok(())
+            }
+            None => {
+                self.state = TlsState::Error;
+                Err(TlsError::UnrecognizedName)
+            }
+        }
+    }
+
+    /// Pick an ALPN protocol from the client's offer.
+    ///
+    /// The server's configured order takes precedence. If both sides
+    /// advertise protocols but none overlap, the handshake fails
+    /// (RFC 7301, Section 3.2).
+    fn select_alpn(&mut self, offered: &[String]) -> Result<Option<String>, TlsError> {
+        if offered.is_empty() || self.config.alpn_protocols.is_empty() {
+            return Ok(None);
+        }
+
+        match self
+            .config
+            .alpn_protocols
+            .iter()
+            .find(|proto| offered.contains(proto))
+        {
+            Some(proto) => Ok(Some(proto.clone())),
+            None => {
+                self.state = TlsState::Error;
+                Err(TlsError::NoApplicationProtocol)
+            }
+        }
+    }
+
     /// Build server response.
     fn build_server_response(&mut self) -> Result<Vec<u8>, TlsError> {
         let mut response = Vec::new();
@@ -827,9 +993,14 @@ impl TlsSession {
         // ServerHello
         let server_hello = self.build_server_hello()?;
         response.extend_from_slice(&self.wrap_record(22, &server_hello));
+        self.transcript.add(&server_hello);
 
         // Certificate (if we have one)
-        // For now, skip certificate
+        if let Some(ref key) = self.selected_certificate {
+            let certificate = self.build_certificate(&key.certificate);
+            response.extend_from_slice(&self.wrap_record(22, &certificate));
+            self.transcript.add(&certificate);
+        }
 
         self.state = TlsState::ServerHelloReceived;
 
@@ -867,8 +1038,19 @@ impl TlsSession {
         // Compression method
         hello.push(0);
 
-        // Extensions (minimal)
-        hello.extend_from_slice(&[0, 0]); // No extensions
+        // Extensions
+        let mut extensions = Vec::new();
+        if self.server_name.is_some() && self.selected_certificate.is_some() {
+            // Empty server_name acknowledges that SNI was used (RFC 6066)
+            extensions.extend_from_slice(
+                &Extension::new(ExtensionType::ServerName as u16, Vec::new()).to_bytes(),
+            );
+        }
+        if let Some(ref proto) = self.alpn_protocol {
+            extensions.extend_from_slice(&Extension::alpn(&[proto.as_str()]).to_bytes());
+        }
+        hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        hello.extend_from_slice(&extensions);
 
         // Update length
         let length = hello.len() - 4;
@@ -879,6 +1061,21 @@ impl TlsSession {
         Ok(hello)
     }
 
+    /// Build a Certificate handshake message carrying a single certificate.
+    fn build_certificate(&self, certificate: &Certificate) -> Vec<u8> {
+        let der = certificate.to_der();
+        let list_len = der.len() + 3;
+        let length = list_len + 3;
+
+        let mut message = Vec::with_capacity(4 + length);
+        message.push(11);
+        message.extend_from_slice(&(length as u32).to_be_bytes()[1..]);
+        message.extend_from_slice(&(list_len as u32).to_be_bytes()[1..]);
+        message.extend_from_slice(&(der.len() as u32).to_be_bytes()[1..]);
+        message.extend_from_slice(der);
+        message
+    }
+
     /// Verify certificate chain.
     fn verify_certificate_chain(&self) -> Result<(), TlsError> {
         if self.peer_certificates.is_empty() {
@@ -893,75 +1090,187 @@ impl TlsSession {
             }
         }
 
-        // Verify expiration
-        for cert in &self.peer_certificates {
-            if cert.is_expired() {
-                return Err(TlsError::CertificateExpired);
-            }
-        }
+        self.config
+            .root_store
+            .verify(&self.peer_certificates, self.config.current_time)
+            .map_err(|e| match e {
+                CertificateError::UnknownIssuer => TlsError::UnknownCa,
+                CertificateError::Expired => TlsError::CertificateExpired,
+                CertificateError::Revoked => TlsError::CertificateRevoked,
+                e => TlsError::CertificateError(e),
+            })
+    }
 
-        // Verify chain (simplified - would need root store)
-        // For now, just check basic validity
-        for i in 0..self.peer_certificates.len() - 1 {
-            let cert = &self.peer_certificates[i];
-            let issuer = &self.peer_certificates[i + 1];
+    /// Get the TLS 1.3 key schedule, once handshake secrets are derived.
+    pub fn key_schedule(&self) -> Option<&KeySchedule> {
+        self.key_schedule.as_ref()
+    }
 
-            if !cert.verify_signature(issuer) {
-                return Err(TlsError::BadCertificate);
-            }
-        }
+    /// Run the TLS 1.3 key schedule up to the handshake traffic secrets,
+    /// given the ECDHE shared secret. Call this right after ServerHello,
+    /// while the transcript ends there.
+    pub fn derive_handshake_secrets(&mut self, shared_secret: &[u8]) -> Result<(), TlsError> {
+        let suite = self.cipher_suite.ok_or(TlsError::HandshakeFailure)?;
+        let transcript_hash = self.transcript.transcript_hash_for(suite);
+        let mut schedule = KeySchedule::new(suite)?;
+        schedule.derive_handshake_secrets(shared_secret, &transcript_hash);
+        self.key_schedule = Some(schedule);
+
+        Ok(())
+    }
+
+    /// Finish the TLS 1.3 key schedule once the transcript runs through
+    /// the server Finished, and switch to the application traffic keys.
+    pub fn derive_application_secrets(&mut self) -> Result<(), TlsError> {
+        let suite = self.cipher_suite.ok_or(TlsError::HandshakeFailure)?;
+        let transcript_hash = self.transcript.transcript_hash_for(suite);
+        let schedule = self
+            .key_schedule
+            .as_mut()
+            .ok_or(TlsError::HandshakeFailure)?;
+        schedule.derive_application_secrets(&transcript_hash)?;
+
+        let client_secret = schedule.client_application_traffic_secret().to_vec();
+        let server_secret = schedule.server_application_traffic_secret().to_vec();
+        self.set_traffic_secrets(&client_secret, &server_secret)
+    }
+
+    /// Install the TLS 1.3 application traffic secrets from the key
+    /// schedule (RFC 8446 §7.1) and start protecting application data
+    /// with the negotiated cipher suite.
+    pub fn set_traffic_secrets(
+        &mut self,
+        client_secret: &[u8],
+        server_secret: &[u8],
+    ) -> Result<(), TlsError> {
+        let suite = self.cipher_suite.ok_or(TlsError::HandshakeFailure)?;
+        let client_key = TrafficKey::derive(suite, client_secret)?;
+        let server_key = TrafficKey::derive(suite, server_secret)?;
+
+        let (write_key, read_key) = if self.is_client {
+            (client_key, server_key)
+        } else {
+            (server_key, client_key)
+        };
+        self.write_key = Some(write_key);
+        self.read_key = Some(read_key);
+        self.client_traffic_secret = client_secret.to_vec();
+        self.server_traffic_secret = server_secret.to_vec();
+        self.send_seq = 0;
+        self.recv_seq = 0;
+        self.state = TlsState::Connected;
 
         Ok(())
     }
 
-    /// Encrypt application data.
+    /// Encrypt application data into one or more records.
     pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, TlsError> {
         if self.state != TlsState::Connected {
             return Err(TlsError::HandshakeFailure);
         }
 
-        // In a real implementation, this would encrypt using the negotiated cipher
-        // For now, just wrap in a record
-        let record = self.wrap_record(23, plaintext);
-        self.send_seq += 1;
+        if plaintext.is_empty() {
+            return self.seal_record(23, &[]);
+        }
 
-        Ok(record)
+        let mut records = Vec::new();
+        for fragment in plaintext.chunks(Record::MAX_FRAGMENT_SIZE) {
+            records.extend_from_slice(&self.seal_record(23, fragment)?);
+        }
+
+        Ok(records)
     }
 
-    /// Decrypt application data.
+    /// Decrypt one application data record.
     pub fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, TlsError> {
         if self.state != TlsState::Connected {
             return Err(TlsError::HandshakeFailure);
         }
+        let key = self.read_key.as_ref().ok_or(TlsError::HandshakeFailure)?;
 
         if ciphertext.len() < 5 {
             return Err(TlsError::InvalidRecord);
         }
 
+        // Protected records always carry the application data outer type
         let content_type = ciphertext[0];
         if content_type != 23 {
             return Err(TlsError::InvalidRecord);
         }
 
         let length = u16::from_be_bytes([ciphertext[3], ciphertext[4]]) as usize;
-        if ciphertext.len() < 5 + length {
+        if length > Record::MAX_FRAGMENT_SIZE + 256 || ciphertext.len() < 5 + length {
             return Err(TlsError::InvalidRecord);
         }
 
-        // In a real implementation, this would decrypt using the negotiated cipher
-        let plaintext = ciphertext[5..5 + length].to_vec();
-        self.recv_seq += 1;
+        let next_seq = self
+            .recv_seq
+            .checked_add(1)
+            .ok_or(TlsError::ConnectionClosed)?;
+        let mut inner = match key.open(self.recv_seq, &ciphertext[..5], &ciphertext[5..5 + length])
+        {
+            Ok(inner) => inner,
+            Err(e) => {
+                // A record that fails to authenticate ends the connection
+                self.state = TlsState::Error;
+                return Err(e);
+            }
+        };
+        self.recv_seq = next_seq;
+
+        // The real content type is the last non-zero byte of the inner
+        // plaintext, after any padding
+        let type_pos = inner
+            .iter()
+            .rposition(|&b| b != 0)
+            .ok_or(TlsError::DecryptionError)?;
+        let inner_type = inner[type_pos];
+        inner.truncate(type_pos);
+        if inner.len() > Record::MAX_FRAGMENT_SIZE {
+            return Err(TlsError::InvalidRecord);
+        }
 
-        Ok(plaintext)
+        match inner_type {
+            23 => Ok(inner),
+            21 => self.process_alert(&inner),
+            // Post-handshake messages such as NewSessionTicket
+            22 => Ok(Vec::new()),
+            _ => Err(TlsError::InvalidRecord),
+        }
     }
 
     /// Close the session.
     pub fn close(&mut self) -> Result<Vec<u8>, TlsError> {
+        // Build close_notify alert
+        let alert = [1, 0]; // Warning level, close_notify
+        let record = if self.state == TlsState::Connected {
+            self.seal_record(21, &alert)?
+        } else {
+            self.wrap_record(21, &alert)
+        };
         self.state = TlsState::Closing;
 
-        // Build close_notify alert
-        let alert = vec![1, 0]; // Warning level, close_notify
-        let record = self.wrap_record(21, &alert);
+        Ok(record)
+    }
+
+    /// Protect one record with the write key (RFC 8446 §5.2).
+    fn seal_record(&mut self, content_type: u8, data: &[u8]) -> Result<Vec<u8>, TlsError> {
+        let key = self.write_key.as_ref().ok_or(TlsError::HandshakeFailure)?;
+        let next_seq = self
+            .send_seq
+            .checked_add(1)
+            .ok_or(TlsError::ConnectionClosed)?;
+
+        let mut inner = Vec::with_capacity(data.len() + 1);
+        inner.extend_from_slice(data);
+        inner.push(content_type);
+
+        let length = (inner.len() + crypto::TAG_LEN) as u16;
+        let mut record = vec![23, 0x03, 0x03];
+        record.extend_from_slice(&length.to_be_bytes());
+        let protected = key.seal(self.send_seq, &record, &inner);
+        record.extend_from_slice(&protected);
+        self.send_seq = next_seq;
 
         Ok(record)
     }
@@ -997,6 +1306,18 @@ impl TlsConnector {
         self
     }
 
+    /// Set the trusted root certificates.
+    pub fn root_store(mut self, store: RootCertStore) -> Self {
+        self.config.root_store = store;
+        self
+    }
+
+    /// Set the current Unix time for certificate validity checks.
+    pub fn current_time(mut self, now: i64) -> Self {
+        self.config.current_time = Some(now);
+        self
+    }
+
     /// Set minimum TLS version.
     pub fn min_version(mut self, version: TlsVersion) -> Self {
         self.config.min_version = version;
@@ -1024,20 +1345,37 @@ impl Default for TlsConnector {
 /// TLS acceptor for servers.
 pub struct TlsAcceptor {
     config: TlsConfig,
-    certificate: Option<Certificate>,
-    private_key: Option<Vec<u8>>,
+    certificates: CertificateResolver,
 }
 
 impl TlsAcceptor {
     /// Create a new TLS acceptor.
+    ///
+    /// The certificate is used for clients that send no SNI or request
+    /// a host name without a dedicated certificate.
     pub fn new(certificate: Certificate, private_key: Vec<u8>) -> Self {
+        let mut certificates = CertificateResolver::new();
+        certificates.set_default(certificate, private_key);
+
         Self {
             config: TlsConfig::default(),
-            certificate: Some(certificate),
-            private_key: Some(private_key),
+            certificates,
         }
     }
 
+    /// Serve a dedicated certificate for an SNI host name.
+    ///
+    /// `host` may be a wildcard such as `*.example.com`.
+    pub fn sni_certificate(
+        mut self,
+        host: &str,
+        certificate: Certificate,
+        private_key: Vec<u8>,
+    ) -> Self {
+        self.certificates.add(host, certificate, private_key);
+        self
+    }
+
     /// Set whether to require client certificates.
     pub fn require_client_cert(mut self, require: bool) -> Self {
         self.config.require_client_cert = require;
@@ -1052,7 +1390,9 @@ impl TlsAcceptor {
 
     /// Accept a client connection.
     pub fn accept(self) -> TlsSession {
-        TlsSession::new_server(self.config)
+        let mut session = TlsSession::new_server(self.config);
+        session.cert_resolver = self.certificates;
+        session
     }
 }
 
@@ -1096,4 +1436,321 @@ mod tests {
         assert!(session.is_client);
         assert_eq!(session.state(), TlsState::Initial);
     }
+
+    fn server_config(alpn: &[&str]) -> TlsConfig {
+        let mut config = TlsConfig::default();
+        config.alpn_protocols = alpn.iter().map(|p| p.to_string()).collect();
+        config
+    }
+
+    #[test]
+    fn test_alpn_negotiation() {
+        let mut client = TlsConnector::new()
+            .server_name("example.com")
+            .alpn_protocol("http/1.1")
+            .alpn_protocol("h2")
+            .connect();
+        let mut server = TlsSession::new_server(server_config(&["h2", "http/1.1"]));
+
+        let client_hello = client.build_client_hello().unwrap();
+        let server_hello = server.process_handshake(&client_hello).unwrap();
+
+        // Server preference wins
+        assert_eq!(server.alpn_protocol(), Some("h2"));
+        assert_eq!(server.server_name(), Some("example.com"));
+        assert!(server.is_http2());
+
+        client.process_handshake(&server_hello).unwrap();
+        assert_eq!(client.state(), TlsState::ServerHelloReceived);
+        assert_eq!(client.alpn_protocol(), Some("h2"));
+    }
+
+    #[test]
+    fn test_alpn_no_overlap() {
+        let mut client = TlsConnector::new().alpn_protocol("spdy/3").connect();
+        let mut server = TlsSession::new_server(server_config(&["h2", "http/1.1"]));
+
+        let client_hello = client.build_client_hello().unwrap();
+        assert!(matches!(
+            server.process_handshake(&client_hello),
+            Err(TlsError::NoApplicationProtocol)
+        ));
+        assert_eq!(server.state(), TlsState::Error);
+    }
+
+    #[test]
+    fn test_alpn_not_offered() {
+        let mut client = TlsConnector::new().connect();
+        let mut server = TlsSession::new_server(server_config(&["h2"]));
+
+        let client_hello = client.build_client_hello().unwrap();
+        let server_hello = server.process_handshake(&client_hello).unwrap();
+        assert_eq!(server.alpn_protocol(), None);
+
+        client.process_handshake(&server_hello).unwrap();
+        assert_eq!(client.alpn_protocol(), None);
+        assert!(!client.is_http2());
+    }
+
+    /// A client and server that negotiated `suite` and share traffic secrets.
+    fn connected_pair(suite: CipherSuite) -> (TlsSession, TlsSession) {
+        let secret_len = if suite == CipherSuite::Tls13Aes256GcmSha384 {
+            48
+        } else {
+            32
+        };
+        let client_secret = vec![0x11; secret_len];
+        let server_secret = vec![0x22; secret_len];
+
+        let mut client = TlsConnector::new().connect();
+        let mut server = TlsSession::new_server(TlsConfig::default());
+        for session in [&mut client, &mut server] {
+            session.version = Some(TlsVersion::Tls13);
+            session.cipher_suite = Some(suite);
+            session
+                .set_traffic_secrets(&client_secret, &server_secret)
+                .unwrap();
+        }
+        (client, server)
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        for suite in [
+            CipherSuite::Tls13Aes128GcmSha256,
+            CipherSuite::Tls13Aes256GcmSha384,
+            CipherSuite::Tls13Chacha20Poly1305Sha256,
+        ] {
+            let (mut client, mut server) = connected_pair(suite);
+            let request = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+
+            let record = client.encrypt(request).unwrap();
+            assert_eq!(record[..3], [23, 0x03, 0x03]);
+            assert_eq!(record.len(), 5 + request.len() + 1 + 16);
+            assert!(!record.windows(request.len()).any(|w| w == request));
+            assert_eq!(server.decrypt(&record).unwrap(), request);
+
+            // Each direction keeps its own key and sequence number
+            for message in [&b"first"[..], b"second"] {
+                let record = server.encrypt(message).unwrap();
+                assert_eq!(client.decrypt(&record).unwrap(), message);
+            }
+            let record = client.encrypt(b"again").unwrap();
+            assert_eq!(server.decrypt(&record).unwrap(), b"again");
+
+            // Oversized writes are split across records
+            let body = vec![0x5a; Record::MAX_FRAGMENT_SIZE + 10];
+            let records = client.encrypt(&body).unwrap();
+            let first_len = 5 + Record::MAX_FRAGMENT_SIZE + 1 + 16;
+            let mut received = server.decrypt(&records[..first_len]).unwrap();
+            received.extend(server.decrypt(&records[first_len..]).unwrap());
+            assert_eq!(received, body);
+
+            let close = client.close().unwrap();
+            assert_eq!(close[0], 23);
+            assert!(server.decrypt(&close).unwrap().is_empty());
+            assert_eq!(server.state(), TlsState::Closing);
+        }
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampering() {
+        let (mut client, mut server) = connected_pair(CipherSuite::Tls13Aes128GcmSha256);
+        let record = client.encrypt(b"secret").unwrap();
+
+        let mut tampered = record.clone();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        assert!(matches!(
+            server.decrypt(&tampered),
+            Err(TlsError::AuthenticationError)
+        ));
+        assert_eq!(server.state(), TlsState::Error);
+
+        // The record header is authenticated too
+        let (mut client, mut server) = connected_pair(CipherSuite::Tls13Chacha20Poly1305Sha256);
+        let mut record = client.encrypt(b"secret").unwrap();
+        record[2] = 0x01;
+        assert!(matches!(
+            server.decrypt(&record),
+            Err(TlsError::AuthenticationError)
+        ));
+
+        // Replaying a record fails once the sequence number has moved on
+        let (mut client, mut server) = connected_pair(CipherSuite::Tls13Aes256GcmSha384);
+        let record = client.encrypt(b"secret").unwrap();
+        server.decrypt(&record).unwrap();
+        assert!(matches!(
+            server.decrypt(&record),
+            Err(TlsError::AuthenticationError)
+        ));
+    }
+
+    #[test]
+    fn test_key_schedule_agrees() {
+        let mut client = TlsConnector::new().server_name("example.com").connect();
+        let mut server = TlsSession::new_server(TlsConfig::default());
+
+        let client_hello = client.build_client_hello().unwrap();
+        let server_hello = server.process_handshake(&client_hello).unwrap();
+        client.process_handshake(&server_hello).unwrap();
+        assert_eq!(client.cipher_suite(), server.cipher_suite());
+        assert!(client.derive_application_secrets().is_err());
+
+        let shared_secret = [0x5a; 32];
+        for session in [&mut client, &mut server] {
+            session.derive_handshake_secrets(&shared_secret).unwrap();
+        }
+        let (client_schedule, server_schedule) = (
+            client.key_schedule().unwrap(),
+            server.key_schedule().unwrap(),
+        );
+        assert_eq!(
+            client_schedule.client_handshake_traffic_secret(),
+            server_schedule.client_handshake_traffic_secret()
+        );
+        assert_ne!(
+            client_schedule.client_handshake_traffic_secret(),
+            client_schedule.server_handshake_traffic_secret()
+        );
+
+        for session in [&mut client, &mut server] {
+            session.derive_application_secrets().unwrap();
+            assert!(session.is_connected());
+        }
+        assert_eq!(client.client_traffic_secret, server.client_traffic_secret);
+        let record = client.encrypt(b"hello").unwrap();
+        assert_eq!(server.decrypt(&record).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_encrypt_requires_traffic_keys() {
+        let mut session = TlsConnector::new().connect();
+        assert!(session.encrypt(b"data").is_err());
+        assert!(session.set_traffic_secrets(&[0; 32], &[0; 32]).is_err());
+
+        session.cipher_suite = Some(CipherSuite::EcdheRsaAes128GcmSha256);
+        assert!(matches!(
+            session.set_traffic_secrets(&[0; 32], &[0; 32]),
+            Err(TlsError::UnsupportedCipherSuite)
+        ));
+        assert!(!session.is_connected());
+    }
+
+    #[test]
+    fn test_server_chain_needs_trusted_root() {
+        use certificate::tests::{issue, NOW, VALID};
+
+        let root = issue("Test Root", "Test Root", 1, Some((true, None)), VALID);
+        let leaf = issue("example.com", "Test Root", 2, None, VALID);
+        let mut trusted = RootCertStore::empty();
+        trusted.add(root).unwrap();
+
+        for (store, anchored) in [(trusted, true), (RootCertStore::empty(), false)] {
+            let mut client = TlsConnector::new()
+                .server_name("example.com")
+                .root_store(store)
+                .current_time(NOW)
+                .connect();
+            let mut server = TlsAcceptor::new(leaf.clone(), Vec::new()).accept();
+
+            let client_hello = client.build_client_hello().unwrap();
+            let response = server.process_handshake(&client_hello).unwrap();
+            let hello_len = 5 + u16::from_be_bytes([response[3], response[4]]) as usize;
+            client.process_handshake(&response[..hello_len]).unwrap();
+
+            let result = client.process_handshake(&response[hello_len..]);
+            if anchored {
+                result.unwrap();
+                assert_eq!(client.state(), TlsState::CertificateReceived);
+            } else {
+                assert!(matches!(result, Err(TlsError::UnknownCa)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_certificate_resolver_empty() {
+        let resolver = CertificateResolver::new();
+        assert!(resolver.is_empty());
+        assert!(resolver.resolve(Some("example.com")).is_none());
+        assert!(resolver.resolve(None).is_none());
+    }
+
+    /// Private key bytes of the certificate chosen for `name`.
+    fn resolved(resolver: &CertificateResolver, name: Option<&str>) -> Option<u8> {
+        resolver.resolve(name).map(|key| key.private_key[0])
+    }
+
+    #[test]
+    fn test_certificate_resolver_sni() {
+        use certificate::tests::{issue, VALID};
+
+        let cert = |host: &str| issue(host, "Test Root", 2, None, VALID);
+        let mut resolver = CertificateResolver::new();
+        resolver.add("*.example.com", cert("*.example.com"), vec![2]);
+        resolver.add("www.example.com", cert("www.example.com"), vec![1]);
+        resolver.add("API.Example.com", cert("api.example.com"), vec![3]);
+        assert!(!resolver.is_empty());
+
+        // No default: misses find nothing
+        assert_eq!(resolved(&resolver, Some("www.example.com")), Some(1));
+        assert_eq!(resolved(&resolver, Some("other.org")), None);
+        assert_eq!(resolved(&resolver, None), None);
+
+        resolver.set_default(cert("default.test"), vec![0]);
+        for (name, key) in [
+            // Exact entries win over the wildcard
+            (Some("www.example.com"), 1),
+            (Some("mail.example.com"), 2),
+            (Some("api.example.com"), 3),
+            // A wildcard covers exactly one label
+            (Some("a.b.example.com"), 0),
+            (Some("example.com"), 0),
+            (Some("other.org"), 0),
+            (None, 0),
+            // Case does not matter on either side
+            (Some("WWW.Example.COM"), 1),
+            (Some("Mail.EXAMPLE.com"), 2),
+            (Some("api.EXAMPLE.com"), 3),
+        ] {
+            assert_eq!(resolved(&resolver, name), Some(key), "{:?}", name);
+        }
+
+        // Adding a host again replaces its certificate
+        resolver.add("WWW.example.com", cert("www.example.com"), vec![4]);
+        assert_eq!(resolved(&resolver, Some("www.example.com")), Some(4));
+    }
+
+    #[test]
+    fn test_acceptor_sni_certificate() {
+        use certificate::tests::{issue, VALID};
+
+        let cert = |host: &str| issue(host, "Test Root", 2, None, VALID);
+        for (server_name, expected) in [
+            (Some("www.example.com"), "www.example.com"),
+            (Some("Mail.Example.COM"), "*.example.com"),
+            (Some("example.org"), "default.test"),
+            (None, "default.test"),
+        ] {
+            let mut client = TlsConnector::new().verify_certificates(false);
+            if let Some(name) = server_name {
+                client = client.server_name(name);
+            }
+            let mut client = client.connect();
+            let mut server = TlsAcceptor::new(cert("default.test"), Vec::new())
+                .sni_certificate("*.example.com", cert("*.example.com"), Vec::new())
+                .sni_certificate("www.example.com", cert("www.example.com"), Vec::new())
+                .accept();
+
+            let client_hello = client.build_client_hello().unwrap();
+            server.process_handshake(&client_hello).unwrap();
+            let selected = server.selected_certificate().unwrap();
+            assert_eq!(
+                selected.subject().common_name.as_deref(),
+                Some(expected),
+                "{:?}",
+                server_name
+            );
+        }
+    }
 }
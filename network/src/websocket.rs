@@ -1,20 +1,33 @@
 //! WebSocket Protocol Implementation (RFC 6455)
 //!
 //! This module provides a WebSocket client implementation for real-time
-//! bidirectional communication over TCP connections.
+//! bidirectional communication over TCP connections, with optional
+//! permessage-deflate compression (RFC 7692).
 
 use alloc::collections::VecDeque;
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt;
 
+use crate::deflate::{
+    DeflateError, Deflater, Inflater, MAX_WINDOW_BITS, MIN_WINDOW_BITS, SYNC_FLUSH_TAIL,
+};
+
 /// WebSocket protocol version.
 pub const WEBSOCKET_VERSION: &str = "13";
 
 /// WebSocket GUID for handshake (RFC 6455).
 const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
 
+/// permessage-deflate extension token (RFC 7692).
+pub const PERMESSAGE_DEFLATE: &str = "permessage-deflate";
+
+/// RSV1 bit, which permessage-deflate sets on the first frame of a
+/// compressed message.
+const RSV1: u8 = 0x4;
+
 /// WebSocket error types.
 #[derive(Debug, Clone)]
 pub enum WebSocketError {
@@ -238,6 +251,32 @@ impl Frame {
         bytes
     }
 
+    /// Header and payload lengths of the frame at the start of `data`,
+    /// or `None` until the whole header has arrived.
+    pub fn peek_len(data: &[u8]) -> Option<(usize, u64)> {
+        let second_byte = *data.get(1)?;
+        let length_bytes = match second_byte & 0x7F {
+            126 => 2,
+            127 => 8,
+            _ => 0,
+        };
+        let mask_bytes = if (second_byte & 0x80) != 0 { 4 } else { 0 };
+
+        let header_len = 2 + length_bytes + mask_bytes;
+        if data.len() < header_len {
+            return None;
+        }
+
+        let payload_len = if length_bytes == 0 {
+            (second_byte & 0x7F) as u64
+        } else {
+            data[2..2 + length_bytes]
+                .iter()
+                .fold(0, |len, &byte| (len << 8) | byte as u64)
+        };
+        Some((header_len, payload_len))
+    }
+
     /// Decode frame from bytes (server response, unmasked).
     pub fn decode(data: &[u8]) -> Result<(Frame, usize), WebSocketError> {
         if data.len() < 2 {
@@ -391,6 +430,130 @@ impl Message {
     }
 }
 
+/// Negotiated permessage-deflate parameters (RFC 7692).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeflateParams {
+    /// Server resets its compression context after each message.
+    pub server_no_context_takeover: bool,
+    /// Client resets its compression context after each message.
+    pub client_no_context_takeover: bool,
+    /// LZ77 window the server compresses with, in bits.
+    pub server_max_window_bits: u8,
+    /// LZ77 window the client compresses with, in bits.
+    pub client_max_window_bits: u8,
+}
+
+impl Default for DeflateParams {
+    fn default() -> Self {
+        Self {
+            server_no_context_takeover: false,
+            client_no_context_takeover: false,
+            server_max_window_bits: MAX_WINDOW_BITS,
+            client_max_window_bits: MAX_WINDOW_BITS,
+        }
+    }
+}
+
+impl DeflateParams {
+    /// Extension offer sent in the handshake request.
+    pub const OFFER: &'static str = "permessage-deflate; client_max_window_bits";
+
+    /// Parse the parameters of the server's `permessage-deflate`
+    /// response, i.e. everything after the extension token.
+    pub fn parse(params: &str) -> Result<Self, WebSocketError> {
+        let mut result = Self::default();
+        let mut seen: Vec<&str> = Vec::new();
+
+        for param in params.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, value) = match param.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                None => (param, None),
+            };
+            if seen.contains(&name) {
+                return Err(WebSocketError::HandshakeFailed(format!(
+                    "Duplicate permessage-deflate parameter: {}",
+                    name
+                )));
+            }
+            seen.push(name);
+
+            match (name, value) {
+                ("server_no_context_takeover", None) => result.server_no_context_takeover = true,
+                ("client_no_context_takeover", None) => result.client_no_context_takeover = true,
+                ("server_max_window_bits", Some(bits)) => {
+                    result.server_max_window_bits = parse_window_bits(bits)?;
+                }
+                ("client_max_window_bits", Some(bits)) => {
+                    result.client_max_window_bits = parse_window_bits(bits)?;
+                }
+                _ => {
+                    return Err(WebSocketError::HandshakeFailed(format!(
+                        "Invalid permessage-deflate parameter: {}",
+                        param
+                    )));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+fn parse_window_bits(value: &str) -> Result<u8, WebSocketError> {
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|bits| (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(bits))
+        .ok_or_else(|| WebSocketError::HandshakeFailed(format!("Invalid window bits: {}", value)))
+}
+
+/// permessage-deflate compression state for one connection.
+struct DeflateContext {
+    params: DeflateParams,
+    /// Compresses outgoing messages.
+    deflater: Deflater,
+    /// Decompresses incoming messages.
+    inflater: Inflater,
+}
+
+impl DeflateContext {
+    fn new(params: DeflateParams) -> Self {
+        Self {
+            params,
+            deflater: Deflater::new(params.client_max_window_bits),
+            inflater: Inflater::new(params.server_max_window_bits),
+        }
+    }
+
+    /// Compress a data frame's payload and mark it with RSV1.
+    fn compress(&mut self, frame: &mut Frame) {
+        frame.payload = self.deflater.compress(&frame.payload);
+        frame.rsv |= RSV1;
+        if self.params.client_no_context_takeover {
+            self.deflater.reset();
+        }
+    }
+
+    /// Decompress a complete message's payload.
+    fn decompress(
+        &mut self,
+        mut payload: Vec<u8>,
+        limit: usize,
+    ) -> Result<Vec<u8>, WebSocketError> {
+        // The sender strips the tail of its sync flush
+        payload.extend_from_slice(&SYNC_FLUSH_TAIL);
+        let result = self.inflater.inflate(&payload, limit);
+        if self.params.server_no_context_takeover {
+            self.inflater.reset();
+        }
+
+        result.map_err(|e| match e {
+            DeflateError::OutputTooLarge => WebSocketError::MessageTooLarge(limit),
+            e => WebSocketError::InvalidFrame(format!("Invalid compressed payload: {:?}", e)),
+        })
+    }
+}
+
 /// WebSocket URL parser.
 #[derive(Debug, Clone)]
 pub struct WebSocketUrl {
@@ -754,6 +917,12 @@ pub struct WebSocketClient {
     protocol: Option<String>,
     /// Negotiated extensions.
     extensions: Vec<String>,
+    /// Offer permessage-deflate in the handshake.
+    offer_deflate: bool,
+    /// permessage-deflate state, if the server accepted it.
+    deflate: Option<DeflateContext>,
+    /// Whether the fragmented message being received is compressed.
+    fragment_compressed: bool,
 }
 
 impl WebSocketClient {
@@ -769,6 +938,9 @@ impl WebSocketClient {
             max_message_size: 16 * 1024 * 1024, // 16MB default
             protocol: None,
             extensions: Vec::new(),
+            offer_deflate: true,
+            deflate: None,
+            fragment_compressed: false,
         }
     }
 
@@ -778,6 +950,12 @@ impl WebSocketClient {
         self
     }
 
+    /// Offer permessage-deflate compression (on by default).
+    pub fn permessage_deflate(mut self, enabled: bool) -> Self {
+        self.offer_deflate = enabled;
+        self
+    }
+
     /// Get connection state.
     pub fn state(&self) -> ConnectionState {
         self.state
@@ -793,11 +971,21 @@ impl WebSocketClient {
         self.protocol.as_deref()
     }
 
+    /// Get negotiated permessage-deflate parameters.
+    pub fn deflate_params(&self) -> Option<DeflateParams> {
+        self.deflate.as_ref().map(|deflate| deflate.params)
+    }
+
     /// Create handshake for URL.
     pub fn create_handshake(&mut self, url: &str) -> Result<HandshakeBuilder, WebSocketError> {
         let ws_url = WebSocketUrl::parse(url)?;
         self.state = ConnectionState::Connecting;
-        Ok(HandshakeBuilder::new(ws_url, &mut self.rng))
+        let handshake = HandshakeBuilder::new(ws_url, &mut self.rng);
+        if self.offer_deflate {
+            Ok(handshake.extension(DeflateParams::OFFER))
+        } else {
+            Ok(handshake)
+        }
     }
 
     /// Process handshake response.
@@ -874,6 +1062,7 @@ impl WebSocketClient {
             ));
         }
 
+        self.deflate = self.negotiate_deflate()?.map(DeflateContext::new);
         self.state = ConnectionState::Open;
 
         // Store remaining data in recv buffer
@@ -885,6 +1074,26 @@ impl WebSocketClient {
         Ok(true)
     }
 
+    /// Find the server's permessage-deflate response, if any.
+    fn negotiate_deflate(&self) -> Result<Option<DeflateParams>, WebSocketError> {
+        let mut negotiated = None;
+
+        for extension in &self.extensions {
+            let (name, params) = extension.split_once(';').unwrap_or((extension, ""));
+            if !name.trim().eq_ignore_ascii_case(PERMESSAGE_DEFLATE) {
+                continue;
+            }
+            if !self.offer_deflate || negotiated.is_some() {
+                return Err(WebSocketError::HandshakeFailed(
+                    "Unexpected permessage-deflate response".to_string(),
+                ));
+            }
+            negotiated = Some(DeflateParams::parse(params)?);
+        }
+
+        Ok(negotiated)
+    }
+
     /// Send a message.
     pub fn send(&mut self, message: Message) -> Result<(), WebSocketError> {
         if self.state != ConnectionState::Open {
@@ -893,7 +1102,13 @@ impl WebSocketClient {
             ));
         }
 
-        let frame = message.to_frame();
+        let mut frame = message.to_frame();
+        if let Some(deflate) = self.deflate.as_mut() {
+            // Control frames are never compressed
+            if frame.opcode.is_data() {
+                deflate.compress(&mut frame);
+            }
+        }
         let mask_key = self.rng.mask_key();
         let encoded = frame.encode(mask_key);
 
@@ -939,22 +1154,36 @@ impl WebSocketClient {
     /// Try to receive a message.
     pub fn recv(&mut self) -> Result<Option<Message>, WebSocketError> {
         loop {
-            if self.recv_buffer.is_empty() {
+            // Wait until a whole frame has arrived, refusing oversized
+            // frames before buffering their payload
+            let Some((header_len, payload_len)) = Frame::peek_len(&self.recv_buffer) else {
+                return Ok(None);
+            };
+            if payload_len > self.max_message_size as u64 {
+                return Err(WebSocketError::MessageTooLarge(payload_len as usize));
+            }
+            if self.recv_buffer.len() < header_len + payload_len as usize {
                 return Ok(None);
             }
 
-            // Try to decode a frame
-            let (frame, consumed) = match Frame::decode(&self.recv_buffer) {
-                Ok(result) => result,
-                Err(WebSocketError::InvalidFrame(_)) if self.recv_buffer.len() < 2 => {
-                    return Ok(None); // Need more data
-                }
-                Err(e) => return Err(e),
-            };
+            let (frame, consumed) = Frame::decode(&self.recv_buffer)?;
 
             // Remove consumed bytes
             self.recv_buffer.drain(..consumed);
 
+            // RSV1 is only meaningful on the first frame of a compressed
+            // data message; no other extension bits are negotiated
+            let compressed = frame.rsv & RSV1 != 0;
+            if frame.rsv & !RSV1 != 0
+                || (compressed
+                    && (self.deflate.is_none()
+                        || !matches!(frame.opcode, Opcode::Text | Opcode::Binary)))
+            {
+                return Err(WebSocketError::ProtocolError(
+                    "Unexpected reserved bits".to_string(),
+                ));
+            }
+
             // Handle control frames immediately
             if frame.opcode.is_control() {
                 match frame.opcode {
@@ -1006,11 +1235,13 @@ impl WebSocketClient {
 
                     if frame.fin {
                         // Complete message
-                        return self.complete_message(frame.opcode, frame.payload);
+                        let payload = self.decompress(compressed, frame.payload)?;
+                        return self.complete_message(frame.opcode, payload);
                     } else {
                         // Start fragmented message
                         self.fragment_opcode = Some(frame.opcode);
                         self.fragment_buffer = frame.payload;
+                        self.fragment_compressed = compressed;
                     }
                 }
                 Opcode::Continuation => {
@@ -1031,6 +1262,7 @@ impl WebSocketClient {
                         // Complete fragmented message
                         let payload = core::mem::take(&mut self.fragment_buffer);
                         self.fragment_opcode = None;
+                        let payload = self.decompress(self.fragment_compressed, payload)?;
                         return self.complete_message(opcode, payload);
                     }
                 }
@@ -1039,6 +1271,18 @@ impl WebSocketClient {
         }
     }
 
+    /// Decompress a message's payload if it arrived compressed.
+    fn decompress(
+        &mut self,
+        compressed: bool,
+        payload: Vec<u8>,
+    ) -> Result<Vec<u8>, WebSocketError> {
+        match self.deflate.as_mut() {
+            Some(deflate) if compressed => deflate.decompress(payload, self.max_message_size),
+            _ => Ok(payload),
+        }
+    }
+
     /// Complete a message from payload.
     fn complete_message(
         &self,
@@ -1075,3 +1319,327 @@ fn find_header_end(data: &[u8]) -> Option<usize> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the client's handshake against a server accepting `extensions`.
+    fn handshake(
+        client: &mut WebSocketClient,
+        extensions: Option<&str>,
+    ) -> Result<bool, WebSocketError> {
+        let handshake = client.create_handshake("ws://example.com/chat").unwrap();
+        let accept = handshake.expected_accept_key();
+
+        let mut response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n",
+            accept
+        );
+        if let Some(extensions) = extensions {
+            response.push_str(&format!("Sec-WebSocket-Extensions: {}\r\n", extensions));
+        }
+        response.push_str("\r\n");
+
+        client.process_handshake_response(response.as_bytes(), &accept)
+    }
+
+    fn open(extensions: Option<&str>) -> WebSocketClient {
+        let mut client = WebSocketClient::default();
+        assert!(handshake(&mut client, extensions).unwrap());
+        client
+    }
+
+    /// Send `message` from `sender` and receive it on `receiver`,
+    /// returning the bytes that crossed the wire.
+    fn transfer(
+        sender: &mut WebSocketClient,
+        receiver: &mut WebSocketClient,
+        message: Message,
+    ) -> (Vec<u8>, Result<Option<Message>, WebSocketError>) {
+        sender.send(message).unwrap();
+        let wire = sender.next_outgoing().unwrap();
+        receiver.feed(&wire);
+        (wire, receiver.recv())
+    }
+
+    /// Unmasked frame as a server sends it.
+    fn server_frame(fin: bool, opcode: Opcode, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Frame::new(opcode, payload.to_vec());
+        frame.fin = fin;
+        let mut bytes = frame.encode([0; 4]);
+        bytes[1] &= 0x7F;
+        let header_len = bytes.len() - payload.len() - 4;
+        bytes.drain(header_len..header_len + 4);
+        bytes
+    }
+
+    #[test]
+    fn test_fragmented_message_with_interleaved_ping() {
+        let mut client = open(None);
+        client.feed(&server_frame(false, Opcode::Text, b"Hello, "));
+        client.feed(&server_frame(true, Opcode::Ping, b"heartbeat"));
+        client.feed(&server_frame(true, Opcode::Continuation, b"world"));
+
+        // The ping is delivered, and answered, before the message completes
+        assert!(matches!(client.recv(), Ok(Some(Message::Ping(ref p))) if p == b"heartbeat"));
+        let (pong, _) = Frame::decode(&client.next_outgoing().unwrap()).unwrap();
+        assert_eq!(pong.opcode, Opcode::Pong);
+        assert_eq!(pong.payload, b"heartbeat");
+
+        assert!(matches!(client.recv(), Ok(Some(Message::Text(ref t))) if t == "Hello, world"));
+        assert!(matches!(client.recv(), Ok(None)));
+    }
+
+    #[test]
+    fn test_fragment_sequencing() {
+        let mut client = open(None);
+        client.feed(&server_frame(false, Opcode::Binary, b"one"));
+        client.feed(&server_frame(true, Opcode::Text, b"two"));
+        assert!(matches!(
+            client.recv(),
+            Err(WebSocketError::ProtocolError(_))
+        ));
+
+        let mut client = open(None);
+        client.feed(&server_frame(true, Opcode::Continuation, b"orphan"));
+        assert!(matches!(
+            client.recv(),
+            Err(WebSocketError::ProtocolError(_))
+        ));
+
+        // Control frames cannot themselves be fragmented
+        let mut client = open(None);
+        client.feed(&server_frame(false, Opcode::Ping, b""));
+        assert!(matches!(
+            client.recv(),
+            Err(WebSocketError::ProtocolError(_))
+        ));
+    }
+
+    #[test]
+    fn test_frame_split_across_reads() {
+        let text = "x".repeat(300);
+        let bytes = server_frame(true, Opcode::Text, text.as_bytes());
+        assert_eq!(Frame::peek_len(&bytes), Some((4, 300)));
+
+        let mut client = open(None);
+        for byte in &bytes[..bytes.len() - 1] {
+            client.feed(core::slice::from_ref(byte));
+            assert!(matches!(client.recv(), Ok(None)));
+        }
+        client.feed(&bytes[bytes.len() - 1..]);
+        assert!(matches!(client.recv(), Ok(Some(Message::Text(ref t))) if *t == text));
+    }
+
+    #[test]
+    fn test_message_size_limit() {
+        let mut client = open(None).max_message_size(16);
+        client.feed(&server_frame(true, Opcode::Binary, &[0; 16]));
+        assert!(matches!(client.recv(), Ok(Some(Message::Binary(_)))));
+
+        // Refused as soon as the header arrives
+        client.feed(&server_frame(true, Opcode::Binary, &[0; 17])[..2]);
+        assert!(matches!(
+            client.recv(),
+            Err(WebSocketError::MessageTooLarge(17))
+        ));
+
+        let mut client = open(None).max_message_size(16);
+        client.feed(&server_frame(false, Opcode::Text, &[b'a'; 10]));
+        client.feed(&server_frame(true, Opcode::Continuation, &[b'a'; 10]));
+        assert!(matches!(
+            client.recv(),
+            Err(WebSocketError::MessageTooLarge(20))
+        ));
+    }
+
+    #[test]
+    fn test_text_message_utf8() {
+        // A code point may straddle two fragments
+        let text = "caf\u{e9} cr\u{e8}me";
+        let (head, tail) = text.as_bytes().split_at(4);
+        let mut client = open(None);
+        client.feed(&server_frame(false, Opcode::Text, head));
+        client.feed(&server_frame(true, Opcode::Continuation, tail));
+        assert!(matches!(client.recv(), Ok(Some(Message::Text(ref t))) if t == text));
+
+        client.feed(&server_frame(false, Opcode::Text, b"ok"));
+        client.feed(&server_frame(true, Opcode::Continuation, &[0xC3, 0x28]));
+        assert!(matches!(
+            client.recv(),
+            Err(WebSocketError::InvalidFrame(_))
+        ));
+    }
+
+    #[test]
+    fn test_permessage_deflate_offer() {
+        let mut client = WebSocketClient::default();
+        let request = client
+            .create_handshake("ws://example.com/chat")
+            .unwrap()
+            .build_request();
+        let request = core::str::from_utf8(&request).unwrap();
+        assert!(request
+            .contains("Sec-WebSocket-Extensions: permessage-deflate; client_max_window_bits\r\n"));
+
+        let mut client = WebSocketClient::default().permessage_deflate(false);
+        let request = client
+            .create_handshake("ws://example.com/chat")
+            .unwrap()
+            .build_request();
+        assert!(!core::str::from_utf8(&request)
+            .unwrap()
+            .contains("Sec-WebSocket-Extensions"));
+    }
+
+    #[test]
+    fn test_permessage_deflate_parameters() {
+        let client = open(Some(
+            "permessage-deflate; server_no_context_takeover; client_max_window_bits=10",
+        ));
+        assert_eq!(
+            client.deflate_params(),
+            Some(DeflateParams {
+                server_no_context_takeover: true,
+                client_no_context_takeover: false,
+                server_max_window_bits: 15,
+                client_max_window_bits: 10,
+            })
+        );
+        assert_eq!(
+            open(Some(
+                "x-custom, permessage-deflate; server_max_window_bits=\"9\""
+            ))
+            .deflate_params()
+            .map(|params| params.server_max_window_bits),
+            Some(9)
+        );
+
+        for response in [
+            "permessage-deflate; server_max_window_bits=16",
+            "permessage-deflate; server_max_window_bits",
+            "permessage-deflate; client_no_context_takeover=1",
+            "permessage-deflate; server_no_context_takeover; server_no_context_takeover",
+            "permessage-deflate; mystery",
+            "permessage-deflate, permessage-deflate",
+        ] {
+            let mut client = WebSocketClient::default();
+            assert!(
+                matches!(
+                    handshake(&mut client, Some(response)),
+                    Err(WebSocketError::HandshakeFailed(_))
+                ),
+                "{}",
+                response
+            );
+        }
+
+        // Accepting an extension the client did not offer
+        let mut client = WebSocketClient::default().permessage_deflate(false);
+        assert!(matches!(
+            handshake(&mut client, Some("permessage-deflate")),
+            Err(WebSocketError::HandshakeFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_permessage_deflate_loopback() {
+        let mut sender = open(Some("permessage-deflate"));
+        let mut receiver = open(Some("permessage-deflate"));
+        let text = "All work and no play makes Jack a dull boy. ".repeat(500);
+
+        let (first, received) = transfer(&mut sender, &mut receiver, Message::Text(text.clone()));
+        assert_ne!(first[0] & 0x40, 0);
+        assert!(first.len() < text.len() / 20);
+        assert!(matches!(received, Ok(Some(Message::Text(ref t))) if *t == text));
+
+        // The second copy is one back-reference into the shared window
+        let (second, received) = transfer(&mut sender, &mut receiver, Message::Text(text.clone()));
+        assert!(second.len() < first.len());
+        assert!(matches!(received, Ok(Some(Message::Text(ref t))) if *t == text));
+
+        // Control frames go out uncompressed
+        let (ping, received) =
+            transfer(&mut sender, &mut receiver, Message::Ping(b"ping".to_vec()));
+        assert_eq!(ping[0] & 0x70, 0);
+        assert!(matches!(received, Ok(Some(Message::Ping(ref p))) if p == b"ping"));
+
+        let binary: Vec<u8> = (0..4096u32).map(|i| (i % 16) as u8).collect();
+        let (_, received) = transfer(&mut sender, &mut receiver, Message::Binary(binary.clone()));
+        assert!(matches!(received, Ok(Some(Message::Binary(ref b))) if *b == binary));
+    }
+
+    #[test]
+    fn test_permessage_deflate_fallback() {
+        let mut sender = open(None);
+        let mut receiver = open(None);
+        assert_eq!(sender.deflate_params(), None);
+
+        let text = "All work and no play makes Jack a dull boy. ".repeat(50);
+        let (wire, received) = transfer(&mut sender, &mut receiver, Message::Text(text.clone()));
+        assert_eq!(wire[0] & 0x70, 0);
+        assert!(wire.len() > text.len());
+        assert!(matches!(received, Ok(Some(Message::Text(ref t))) if *t == text));
+
+        // A compressed frame without negotiation is a protocol error
+        let mut compressing = open(Some("permessage-deflate"));
+        let (_, received) = transfer(&mut compressing, &mut receiver, Message::Text(text));
+        assert!(matches!(received, Err(WebSocketError::ProtocolError(_))));
+    }
+
+    #[test]
+    fn test_permessage_deflate_context_takeover() {
+        let text = "the same message, over and over";
+
+        // The sender keeps its context, but the receiver was told it would not
+        let mut sender = open(Some("permessage-deflate"));
+        let mut receiver = open(Some("permessage-deflate; server_no_context_takeover"));
+        let (_, received) = transfer(&mut sender, &mut receiver, Message::Text(text.to_string()));
+        assert!(matches!(received, Ok(Some(Message::Text(ref t))) if t == text));
+        let (_, received) = transfer(&mut sender, &mut receiver, Message::Text(text.to_string()));
+        assert!(matches!(received, Err(WebSocketError::InvalidFrame(_))));
+
+        // With no context takeover every message stands alone
+        let mut sender = open(Some("permessage-deflate; client_no_context_takeover"));
+        let mut receiver = open(Some("permessage-deflate; server_no_context_takeover"));
+        let (first, _) = transfer(&mut sender, &mut receiver, Message::Text(text.to_string()));
+        let (second, received) =
+            transfer(&mut sender, &mut receiver, Message::Text(text.to_string()));
+        assert_eq!(first.len(), second.len());
+        assert!(matches!(received, Ok(Some(Message::Text(ref t))) if t == text));
+    }
+
+    #[test]
+    fn test_permessage_deflate_fragments() {
+        let text = "fragmented and compressed ".repeat(20);
+        let payload = Deflater::new(MAX_WINDOW_BITS).compress(text.as_bytes());
+        let (head, tail) = payload.split_at(payload.len() / 2);
+
+        // RSV1 goes on the first frame only
+        let first = Frame {
+            fin: false,
+            rsv: RSV1,
+            opcode: Opcode::Text,
+            payload: head.to_vec(),
+        };
+        let mut last = Frame::new(Opcode::Continuation, tail.to_vec());
+
+        let mut receiver = open(Some("permessage-deflate"));
+        receiver.feed(&first.encode([1, 2, 3, 4]));
+        receiver.feed(&last.encode([5, 6, 7, 8]));
+        assert!(matches!(receiver.recv(), Ok(Some(Message::Text(ref t))) if *t == text));
+
+        let mut receiver = open(Some("permessage-deflate"));
+        last.rsv = RSV1;
+        receiver.feed(&first.encode([1, 2, 3, 4]));
+        receiver.feed(&last.encode([5, 6, 7, 8]));
+        assert!(matches!(
+            receiver.recv(),
+            Err(WebSocketError::ProtocolError(_))
+        ));
+    }
+}
@@ -12,7 +12,7 @@
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use crate::executor::{ExecutorContext, HostFunction};
+use crate::executor::{ExecutorContext, HostFunction, ResourceUsage};
 use crate::host;
 use crate::instance::Imports;
 use crate::module::Module;
@@ -71,6 +71,8 @@ pub struct LaunchResult {
     pub trapped: bool,
     /// Trap message, if any.
     pub trap_message: Option<String>,
+    /// Resources the app used while running.
+    pub usage: ResourceUsage,
 }
 
 // ---------------------------------------------------------------------------
@@ -124,6 +126,7 @@ pub fn launch_wasm(wasm_bytes: &[u8], config: &LaunchConfig) -> Result<LaunchRes
                 stderr: ctx.stderr.clone(),
                 trapped: false,
                 trap_message: None,
+                usage: ctx.resource_usage(),
             })
         }
         Err(trap) => {
@@ -137,9 +140,16 @@ pub fn launch_wasm(wasm_bytes: &[u8], config: &LaunchConfig) -> Result<LaunchRes
                 trapped: !is_exit,
                 trap_message: if is_exit {
                     None
-                } else {
+                } else if ctx.backtrace.is_empty() {
                     Some(alloc::format!("{}", trap))
+                } else {
+                    Some(alloc::format!(
+                        "{}\nwasm backtrace:\n{}",
+                        trap,
+                        ctx.format_backtrace()
+                    ))
                 },
+                usage: ctx.resource_usage(),
             })
         }
     }
@@ -0,0 +1,177 @@
+//! Host Clocks
+//!
+//! Time for guest clocks comes from the TSC. The kernel calibrates the
+//! TSC against the PIT and anchors it to the RTC at boot; the embedder
+//! hands both to [`calibrate`] so that guests see the same time as the
+//! kernel. With the `kernel` feature this happens on the first reading
+//! after the kernel's clock is up. Until then the TSC is assumed to run
+//! at 2 GHz and the wall clock starts at the Unix epoch, matching the
+//! kernel's own fallback.
+//!
+//! Readings of the monotonic clock never go backwards, even when the
+//! calibration changes or the TSCs of different CPUs disagree, and a new
+//! calibration only changes the rate at which they advance.
+
+#[cfg(feature = "kernel")]
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Nanoseconds per second.
+pub const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// TSC frequency assumed before calibration.
+const FALLBACK_TSC_HZ: u64 = 2_000_000_000;
+
+static CLOCK: TscClock = TscClock::new();
+
+/// Set the TSC frequency and anchor the wall clock: `unix_ns` is the
+/// current wall-clock time in nanoseconds since the Unix epoch.
+pub fn calibrate(tsc_hz: u64, unix_ns: u64) {
+    sync_with_kernel();
+    CLOCK.calibrate(tsc_hz, unix_ns, read_tsc());
+}
+
+/// Nanoseconds since the TSC was reset. Never decreases.
+pub fn monotonic_ns() -> u64 {
+    sync_with_kernel();
+    CLOCK.monotonic_ns(read_tsc())
+}
+
+/// Nanoseconds since the Unix epoch.
+///
+/// Until the wall clock is anchored, by [`calibrate`] or, with the
+/// `kernel` feature, once the kernel has read the RTC, this counts from
+/// the epoch instead: it starts near zero rather than at the real date.
+pub fn realtime_ns() -> u64 {
+    sync_with_kernel();
+    CLOCK.realtime_ns(read_tsc())
+}
+
+/// Resolution of both clocks in nanoseconds: one TSC tick, rounded up.
+pub fn resolution_ns() -> u64 {
+    sync_with_kernel();
+    CLOCK.resolution_ns()
+}
+
+/// Clock state derived from TSC readings passed in by the caller.
+struct TscClock {
+    hz: AtomicU64,
+    /// Wall-clock time at monotonic time zero, in nanoseconds since the
+    /// epoch.
+    wall_offset_ns: AtomicU64,
+    /// TSC value and monotonic time at the last calibration; readings
+    /// continue from there at the new frequency.
+    base_tsc: AtomicU64,
+    base_ns: AtomicU64,
+    /// Latest monotonic reading handed out.
+    last_ns: AtomicU64,
+}
+
+impl TscClock {
+    const fn new() -> Self {
+        Self {
+            hz: AtomicU64::new(FALLBACK_TSC_HZ),
+            wall_offset_ns: AtomicU64::new(0),
+            base_tsc: AtomicU64::new(0),
+            base_ns: AtomicU64::new(0),
+            last_ns: AtomicU64::new(0),
+        }
+    }
+
+    fn calibrate(&self, tsc_hz: u64, unix_ns: u64, tsc: u64) {
+        let now = self.monotonic_ns(tsc);
+        self.base_tsc.store(tsc, Ordering::Relaxed);
+        self.base_ns.store(now, Ordering::Relaxed);
+        self.hz.store(tsc_hz.max(1), Ordering::Relaxed);
+        self.wall_offset_ns
+            .store(unix_ns.saturating_sub(now), Ordering::Relaxed);
+    }
+
+    fn monotonic_ns(&self, tsc: u64) -> u64 {
+        let hz = self.hz.load(Ordering::Relaxed);
+        let ticks = tsc.saturating_sub(self.base_tsc.load(Ordering::Relaxed));
+        let ns = self.base_ns.load(Ordering::Relaxed)
+            + (ticks as u128 * NANOS_PER_SEC as u128 / hz as u128) as u64;
+        let last = self.last_ns.fetch_max(ns, Ordering::AcqRel);
+        ns.max(last)
+    }
+
+    fn realtime_ns(&self, tsc: u64) -> u64 {
+        let monotonic = self.monotonic_ns(tsc);
+        self.wall_offset_ns.load(Ordering::Relaxed) + monotonic
+    }
+
+    fn resolution_ns(&self) -> u64 {
+        NANOS_PER_SEC
+            .div_ceil(self.hz.load(Ordering::Relaxed))
+            .max(1)
+    }
+}
+
+/// Take over the kernel's calibration once its clock is running.
+#[cfg(feature = "kernel")]
+fn sync_with_kernel() {
+    static SYNCED: AtomicBool = AtomicBool::new(false);
+    if SYNCED.load(Ordering::Acquire) || !kpio_kernel::time::is_initialized() {
+        return;
+    }
+    if !SYNCED.swap(true, Ordering::AcqRel) {
+        let now = kpio_kernel::time::now();
+        let unix_ns = now.secs * NANOS_PER_SEC + u64::from(now.nanos);
+        calibrate(kpio_kernel::time::tsc_frequency(), unix_ns);
+    }
+}
+
+#[cfg(not(feature = "kernel"))]
+fn sync_with_kernel() {}
+
+#[cfg(target_arch = "x86_64")]
+fn read_tsc() -> u64 {
+    // SAFETY: RDTSC has no side effects and is available on every x86-64 CPU.
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+/// Without a TSC, advance by one tick per reading.
+#[cfg(not(target_arch = "x86_64"))]
+fn read_tsc() -> u64 {
+    static TICKS: AtomicU64 = AtomicU64::new(0);
+    TICKS.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monotonic_across_calibration() {
+        let clock = TscClock::new();
+        assert_eq!(clock.monotonic_ns(2_000), 1_000);
+        assert_eq!(clock.resolution_ns(), 1);
+
+        // A higher frequency would shrink raw readings; they continue
+        // from the calibration point at the new rate instead
+        clock.calibrate(4 * NANOS_PER_SEC, 0, 4_000);
+        assert_eq!(clock.monotonic_ns(4_000), 2_000);
+        assert_eq!(clock.monotonic_ns(8_000), 3_000);
+
+        // TSCs of another CPU lagging behind never move the clock back
+        assert_eq!(clock.monotonic_ns(6_000), 3_000);
+        assert_eq!(clock.monotonic_ns(0), 3_000);
+
+        clock.calibrate(NANOS_PER_SEC / 2, 0, 8_000);
+        assert_eq!(clock.monotonic_ns(8_001), 3_002);
+        assert_eq!(clock.resolution_ns(), 2);
+    }
+
+    #[test]
+    fn test_realtime_anchor() {
+        let clock = TscClock::new();
+        // Unanchored, the wall clock starts at the epoch
+        assert_eq!(clock.realtime_ns(2_000), 1_000);
+
+        let unix_ns = 1_700_000_000 * NANOS_PER_SEC;
+        clock.calibrate(FALLBACK_TSC_HZ, unix_ns, 4_000);
+        assert_eq!(clock.realtime_ns(4_000), unix_ns);
+        assert_eq!(clock.realtime_ns(6_000), unix_ns + 1_000);
+    }
+}
@@ -253,7 +253,7 @@ pub fn lift(
             let v = expect_i32(values, 0)?;
             Ok(ComponentValue::U16(v as u16))
         }
-        ComponentType::U32 => {
+        ComponentType::U32 | ComponentType::Own(_) | ComponentType::Borrow(_) => {
             let v = expect_i32(values, 0)?;
             Ok(ComponentValue::U32(v as u32))
         }
@@ -414,7 +414,9 @@ fn read_core_value_from_memory(
         | ComponentType::S32
         | ComponentType::Char
         | ComponentType::Enum(_)
-        | ComponentType::Flags(_) => {
+        | ComponentType::Flags(_)
+        | ComponentType::Own(_)
+        | ComponentType::Borrow(_) => {
             let bytes = mem.read(offset, 4)?;
             let v = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
             Ok(CoreValue::I32(v))
@@ -460,7 +462,9 @@ pub fn core_value_count(ty: &ComponentType) -> usize {
         | ComponentType::F32
         | ComponentType::Char
         | ComponentType::Enum(_)
-        | ComponentType::Flags(_) => 1,
+        | ComponentType::Flags(_)
+        | ComponentType::Own(_)
+        | ComponentType::Borrow(_) => 1,
         ComponentType::U64 | ComponentType::S64 | ComponentType::F64 => 1,
         ComponentType::String | ComponentType::List(_) => 2, // (ptr, len)
         ComponentType::Record(fields) => fields.iter().map(|(_, t)| core_value_count(t)).sum(),
@@ -3,20 +3,359 @@
 //! A `ComponentInstance` wraps resolved imports and component exports,
 //! providing `call()` that automatically lowers arguments and lifts results
 //! through the canonical ABI.
+//!
+//! Imports are called through the instance's [`ComponentCtx`], which owns
+//! the handle table for resources and, when WASI interfaces are linked,
+//! the WASI P2 host state.
 
-use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use super::canonical::{self, CoreValue};
-use super::linker::{ComponentExport, HostExport};
+use super::canonical::{self, CoreValue, LinearMemory};
+use super::linker::{ComponentExport, HostExport, HostResource, ResourceDropFn};
 use super::{ComponentError, ComponentType, ComponentValue};
+use crate::wasi::Vfs;
+use crate::wasi2::Wasi2Ctx;
+
+/// A resolved import.
+#[derive(Clone)]
+pub(crate) enum LinkedImport {
+    /// A host function.
+    Host(HostExport),
+    /// `[resource-drop]<resource>`, provided by the linker.
+    ResourceDrop {
+        resource: String,
+        drop: Option<ResourceDropFn>,
+    },
+}
+
+impl LinkedImport {
+    pub(crate) fn resource_drop(resource: &HostResource) -> Self {
+        LinkedImport::ResourceDrop {
+            resource: resource.name.clone(),
+            drop: resource.drop,
+        }
+    }
+
+    /// Parameter and result types.
+    pub(crate) fn signature(&self) -> (Vec<ComponentType>, Vec<ComponentType>) {
+        match self {
+            LinkedImport::Host(export) => (export.params.clone(), export.results.clone()),
+            LinkedImport::ResourceDrop { resource, .. } => (
+                alloc::vec![ComponentType::Own(resource.clone())],
+                alloc::vec![],
+            ),
+        }
+    }
+}
+
+/// Guest handles to host resources.
+///
+/// Handle 0 is never issued, as in the canonical ABI.
+#[derive(Default)]
+struct HandleTable {
+    /// Slot `handle - 1`: resource name and representation.
+    slots: Vec<Option<(String, u32)>>,
+    free: Vec<usize>,
+}
+
+impl HandleTable {
+    fn insert(&mut self, resource: &str, rep: u32) -> u32 {
+        let entry = Some((String::from(resource), rep));
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = entry;
+                index
+            }
+            None => {
+                self.slots.push(entry);
+                self.slots.len() - 1
+            }
+        };
+        index as u32 + 1
+    }
+
+    fn get(&self, handle: u32, resource: &str) -> Result<u32, ComponentError> {
+        let slot = (handle as usize)
+            .checked_sub(1)
+            .and_then(|index| self.slots.get(index))
+            .and_then(|slot| slot.as_ref());
+        match slot {
+            Some((name, rep)) if name == resource => Ok(*rep),
+            Some((name, _)) => Err(ComponentError::Trap(alloc::format!(
+                "handle {} is a {}, not a {}",
+                handle,
+                name,
+                resource
+            ))),
+            None => Err(ComponentError::Trap(alloc::format!(
+                "unknown handle {}",
+                handle
+            ))),
+        }
+    }
+
+    fn take(&mut self, handle: u32, resource: &str) -> Result<u32, ComponentError> {
+        let rep = self.get(handle, resource)?;
+        let index = handle as usize - 1;
+        self.slots[index] = None;
+        self.free.push(index);
+        Ok(rep)
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+}
+
+/// Per-instance state passed to host functions.
+pub struct ComponentCtx {
+    /// Resolved imports: "interface#func" → import.
+    imports: BTreeMap<String, LinkedImport>,
+    /// Guest handles to host resources.
+    handles: HandleTable,
+    /// WASI P2 state, present when a `wasi:` interface is imported.
+    wasi: Option<Wasi2Ctx>,
+    /// Files visible through `wasi:filesystem`.
+    vfs: Vfs,
+    /// Status passed to `wasi:cli/exit`.
+    exit_code: Option<u32>,
+}
+
+impl ComponentCtx {
+    fn new(imports: BTreeMap<String, LinkedImport>) -> Self {
+        let wasi = imports
+            .keys()
+            .any(|key| key.starts_with("wasi:"))
+            .then(Wasi2Ctx::new);
+        Self {
+            imports,
+            handles: HandleTable::default(),
+            wasi,
+            vfs: Vfs::new(),
+            exit_code: None,
+        }
+    }
+
+    /// Call a resolved import function (for use by component internals).
+    ///
+    /// `own` and `borrow` arguments are guest handles; owned ones are
+    /// consumed by the call. `own` results come back as new handles.
+    pub fn call_import(
+        &mut self,
+        interface: &str,
+        func_name: &str,
+        args: &[ComponentValue],
+    ) -> Result<Vec<ComponentValue>, ComponentError> {
+        let key = alloc::format!("{}#{}", interface, func_name);
+        let import = self
+            .imports
+            .get(&key)
+            .cloned()
+            .ok_or_else(|| ComponentError::ImportNotFound(key.clone()))?;
+        let (params, results) = import.signature();
+
+        // Validate args
+        if args.len() != params.len() {
+            return Err(ComponentError::TypeMismatch(alloc::format!(
+                "import {} expected {} args, got {}",
+                key,
+                params.len(),
+                args.len()
+            )));
+        }
+        for (i, (arg, ty)) in args.iter().zip(&params).enumerate() {
+            if !type_matches(arg, ty) {
+                return Err(ComponentError::TypeMismatch(alloc::format!(
+                    "import {} argument {} type mismatch",
+                    key,
+                    i
+                )));
+            }
+        }
+
+        let mut reps = Vec::with_capacity(args.len());
+        for (arg, ty) in args.iter().zip(&params) {
+            let handles = &mut self.handles;
+            reps.push(map_handles(
+                arg.clone(),
+                ty,
+                &mut |resource, own, handle| {
+                    if own {
+                        handles.take(handle, resource)
+                    } else {
+                        handles.get(handle, resource)
+                    }
+                },
+            )?);
+        }
+
+        let values = match import {
+            LinkedImport::Host(export) => (export.func)(self, &reps)?,
+            LinkedImport::ResourceDrop { drop, .. } => {
+                if let (Some(drop), ComponentValue::U32(rep)) = (drop, &reps[0]) {
+                    drop(self, *rep);
+                }
+                Vec::new()
+            }
+        };
+
+        let handles = &mut self.handles;
+        values
+            .into_iter()
+            .zip(&results)
+            .map(|(value, ty)| {
+                map_handles(value, ty, &mut |resource, _, rep| {
+                    Ok(handles.insert(resource, rep))
+                })
+            })
+            .collect()
+    }
+
+    /// Call a resolved import with core WASM arguments, as a core module
+    /// would through `canon lower`.
+    ///
+    /// Arguments are lifted from their flattened form, with strings and
+    /// lists read from `memory`; results are lowered back into it.
+    pub fn call_import_lowered(
+        &mut self,
+        interface: &str,
+        func_name: &str,
+        args: &[CoreValue],
+        memory: &mut dyn LinearMemory,
+    ) -> Result<Vec<CoreValue>, ComponentError> {
+        let key = alloc::format!("{}#{}", interface, func_name);
+        let (params, _) = self
+            .imports
+            .get(&key)
+            .ok_or(ComponentError::ImportNotFound(key))?
+            .signature();
+
+        let mut lifted = Vec::with_capacity(params.len());
+        let mut next = 0;
+        for ty in &params {
+            let count = canonical::core_value_count(ty);
+            let values = args.get(next..next + count).ok_or_else(|| {
+                ComponentError::TypeMismatch(String::from("too few core arguments"))
+            })?;
+            lifted.push(canonical::lift(values, ty, Some(&*memory))?);
+            next += count;
+        }
+        if next != args.len() {
+            return Err(ComponentError::TypeMismatch(String::from(
+                "too many core arguments",
+            )));
+        }
+
+        let mut lowered = Vec::new();
+        for value in self.call_import(interface, func_name, &lifted)? {
+            lowered.extend(canonical::lower(&value, Some(&mut *memory))?);
+        }
+        Ok(lowered)
+    }
+
+    /// WASI P2 state, or a trap if no WASI interface was linked.
+    pub fn wasi(&mut self) -> Result<&mut Wasi2Ctx, ComponentError> {
+        self.wasi
+            .as_mut()
+            .ok_or_else(|| ComponentError::Trap(String::from("WASI is not linked")))
+    }
+
+    /// Files visible through `wasi:filesystem`.
+    pub fn vfs(&self) -> &Vfs {
+        &self.vfs
+    }
+
+    /// Mutable access to the files visible through `wasi:filesystem`.
+    pub fn vfs_mut(&mut self) -> &mut Vfs {
+        &mut self.vfs
+    }
+
+    /// Status passed to `wasi:cli/exit`, if the component called it.
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    pub(crate) fn set_exit_code(&mut self, code: u32) {
+        self.exit_code = Some(code);
+    }
+
+    /// Number of live resource handles.
+    pub fn handle_count(&self) -> usize {
+        self.handles.len()
+    }
+}
+
+/// Rewrite every resource handle inside `value` with `f(resource, own, handle)`.
+fn map_handles(
+    value: ComponentValue,
+    ty: &ComponentType,
+    f: &mut dyn FnMut(&str, bool, u32) -> Result<u32, ComponentError>,
+) -> Result<ComponentValue, ComponentError> {
+    Ok(match (value, ty) {
+        (ComponentValue::U32(handle), ComponentType::Own(resource)) => {
+            ComponentValue::U32(f(resource, true, handle)?)
+        }
+        (ComponentValue::U32(handle), ComponentType::Borrow(resource)) => {
+            ComponentValue::U32(f(resource, false, handle)?)
+        }
+        (ComponentValue::List(items), ComponentType::List(elem_ty)) => ComponentValue::List(
+            items
+                .into_iter()
+                .map(|item| map_handles(item, elem_ty, f))
+                .collect::<Result<_, _>>()?,
+        ),
+        (ComponentValue::Record(fields), ComponentType::Record(field_tys)) => {
+            ComponentValue::Record(
+                fields
+                    .into_iter()
+                    .zip(field_tys)
+                    .map(|((name, value), (_, ty))| Ok((name, map_handles(value, ty, f)?)))
+                    .collect::<Result<_, ComponentError>>()?,
+            )
+        }
+        (
+            ComponentValue::Variant {
+                discriminant,
+                name,
+                value: Some(payload),
+            },
+            ComponentType::Variant(cases),
+        ) => {
+            let payload = match cases.get(discriminant as usize) {
+                Some((_, Some(payload_ty))) => map_handles(*payload, payload_ty, f)?,
+                _ => *payload,
+            };
+            ComponentValue::Variant {
+                discriminant,
+                name,
+                value: Some(alloc::boxed::Box::new(payload)),
+            }
+        }
+        (ComponentValue::Option(Some(inner)), ComponentType::Option(inner_ty)) => {
+            ComponentValue::Option(Some(alloc::boxed::Box::new(map_handles(
+                *inner, inner_ty, f,
+            )?)))
+        }
+        (ComponentValue::Result(Ok(Some(inner))), ComponentType::Result { ok: Some(ty), .. }) => {
+            ComponentValue::Result(Ok(Some(alloc::boxed::Box::new(map_handles(
+                *inner, ty, f,
+            )?))))
+        }
+        (ComponentValue::Result(Err(Some(inner))), ComponentType::Result { err: Some(ty), .. }) => {
+            ComponentValue::Result(Err(Some(alloc::boxed::Box::new(map_handles(
+                *inner, ty, f,
+            )?))))
+        }
+        (value, _) => value,
+    })
+}
 
 /// An instantiated component with typed call semantics.
 pub struct ComponentInstance {
-    /// Resolved host imports: "interface#func" → HostExport
-    imports: BTreeMap<String, HostExport>,
+    /// Imports and host state.
+    ctx: ComponentCtx,
     /// Component's own exports.
     exports: Vec<ComponentExport>,
 }
@@ -24,10 +363,13 @@ pub struct ComponentInstance {
 impl ComponentInstance {
     /// Create a new component instance with resolved imports and exports.
     pub(crate) fn new(
-        imports: BTreeMap<String, HostExport>,
+        imports: BTreeMap<String, LinkedImport>,
         exports: Vec<ComponentExport>,
     ) -> Self {
-        Self { imports, exports }
+        Self {
+            ctx: ComponentCtx::new(imports),
+            exports,
+        }
     }
 
     /// Call a component export by name with high-level `ComponentValue` args.
@@ -38,7 +380,7 @@ impl ComponentInstance {
     /// 3. Calls the export function directly (MVP: host-defined components)
     /// 4. Returns typed results
     pub fn call(
-        &self,
+        &mut self,
         name: &str,
         args: &[ComponentValue],
     ) -> Result<Vec<ComponentValue>, ComponentError> {
@@ -69,7 +411,7 @@ impl ComponentInstance {
 
         // Execute
         match export.func {
-            Some(func) => func(args),
+            Some(func) => func(&mut self.ctx, args),
             None => Err(ComponentError::Trap(String::from(
                 "export has no implementation",
             ))),
@@ -78,28 +420,12 @@ impl ComponentInstance {
 
     /// Call a resolved import function (for use by component internals).
     pub fn call_import(
-        &self,
+        &mut self,
         interface: &str,
         func_name: &str,
         args: &[ComponentValue],
     ) -> Result<Vec<ComponentValue>, ComponentError> {
-        let key = alloc::format!("{}#{}", interface, func_name);
-        let host_export = self
-            .imports
-            .get(&key)
-            .ok_or_else(|| ComponentError::ImportNotFound(key.clone()))?;
-
-        // Validate args
-        if args.len() != host_export.params.len() {
-            return Err(ComponentError::TypeMismatch(alloc::format!(
-                "import {} expected {} args, got {}",
-                key,
-                host_export.params.len(),
-                args.len()
-            )));
-        }
-
-        (host_export.func)(args)
+        self.ctx.call_import(interface, func_name, args)
     }
 
     /// Get the list of export names.
@@ -109,13 +435,23 @@ impl ComponentInstance {
 
     /// Get the list of import keys ("interface#func").
     pub fn import_keys(&self) -> Vec<&str> {
-        self.imports.keys().map(|k| k.as_str()).collect()
+        self.ctx.imports.keys().map(|k| k.as_str()).collect()
     }
 
     /// Check if an export exists.
     pub fn has_export(&self, name: &str) -> bool {
         self.exports.iter().any(|e| e.name == name)
     }
+
+    /// Get the instance context.
+    pub fn context(&self) -> &ComponentCtx {
+        &self.ctx
+    }
+
+    /// Get the instance context mutably.
+    pub fn context_mut(&mut self) -> &mut ComponentCtx {
+        &mut self.ctx
+    }
 }
 
 /// Check if a `ComponentValue` is compatible with a `ComponentType`.
@@ -125,6 +461,7 @@ fn type_matches(value: &ComponentValue, ty: &ComponentType) -> bool {
         (ComponentValue::U8(_), ComponentType::U8) => true,
         (ComponentValue::U16(_), ComponentType::U16) => true,
         (ComponentValue::U32(_), ComponentType::U32) => true,
+        (ComponentValue::U32(_), ComponentType::Own(_) | ComponentType::Borrow(_)) => true,
         (ComponentValue::U64(_), ComponentType::U64) => true,
         (ComponentValue::S8(_), ComponentType::S8) => true,
         (ComponentValue::S16(_), ComponentType::S16) => true,
@@ -152,7 +489,10 @@ mod tests {
     use super::*;
     use alloc::string::String;
 
-    fn add_fn(args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+    fn add_fn(
+        _ctx: &mut ComponentCtx,
+        args: &[ComponentValue],
+    ) -> Result<Vec<ComponentValue>, ComponentError> {
         let a = match &args[0] {
             ComponentValue::S32(v) => *v,
             _ => return Err(ComponentError::TypeMismatch(String::from("expected s32"))),
@@ -164,7 +504,10 @@ mod tests {
         Ok(alloc::vec![ComponentValue::S32(a + b)])
     }
 
-    fn greet_fn(args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+    fn greet_fn(
+        _ctx: &mut ComponentCtx,
+        args: &[ComponentValue],
+    ) -> Result<Vec<ComponentValue>, ComponentError> {
         let name = match &args[0] {
             ComponentValue::String(s) => s.clone(),
             _ => return Err(ComponentError::TypeMismatch(String::from("expected string"))),
@@ -179,12 +522,12 @@ mod tests {
         let mut imports = BTreeMap::new();
         imports.insert(
             String::from("test:math/ops#add"),
-            HostExport {
+            LinkedImport::Host(HostExport {
                 name: String::from("add"),
                 params: alloc::vec![ComponentType::S32, ComponentType::S32],
                 results: alloc::vec![ComponentType::S32],
                 func: add_fn,
-            },
+            }),
         );
 
         let exports = alloc::vec![
@@ -207,7 +550,7 @@ mod tests {
 
     #[test]
     fn test_instance_call_export() {
-        let inst = make_instance();
+        let mut inst = make_instance();
         let result = inst
             .call(
                 "compute",
@@ -219,7 +562,7 @@ mod tests {
 
     #[test]
     fn test_instance_call_greet() {
-        let inst = make_instance();
+        let mut inst = make_instance();
         let result = inst
             .call("greet", &[ComponentValue::String(String::from("World"))])
             .unwrap();
@@ -231,7 +574,7 @@ mod tests {
 
     #[test]
     fn test_instance_call_import() {
-        let inst = make_instance();
+        let mut inst = make_instance();
         let result = inst
             .call_import(
                 "test:math/ops",
@@ -244,21 +587,21 @@ mod tests {
 
     #[test]
     fn test_instance_export_not_found() {
-        let inst = make_instance();
+        let mut inst = make_instance();
         let result = inst.call("nonexistent", &[]);
         assert!(matches!(result, Err(ComponentError::ExportNotFound(_))));
     }
 
     #[test]
     fn test_instance_arg_count_mismatch() {
-        let inst = make_instance();
+        let mut inst = make_instance();
         let result = inst.call("compute", &[ComponentValue::S32(10)]);
         assert!(matches!(result, Err(ComponentError::TypeMismatch(_))));
     }
 
     #[test]
     fn test_instance_arg_type_mismatch() {
-        let inst = make_instance();
+        let mut inst = make_instance();
         let result = inst.call(
             "compute",
             &[ComponentValue::Bool(true), ComponentValue::S32(10)],
@@ -285,7 +628,7 @@ mod tests {
 
     #[test]
     fn test_instance_import_not_found() {
-        let inst = make_instance();
+        let mut inst = make_instance();
         let result = inst.call_import("missing:iface/here", "func", &[]);
         assert!(matches!(result, Err(ComponentError::ImportNotFound(_))));
     }
@@ -324,7 +667,7 @@ mod tests {
             results: alloc::vec![],
             func: None,
         }];
-        let inst = ComponentInstance::new(BTreeMap::new(), exports);
+        let mut inst = ComponentInstance::new(BTreeMap::new(), exports);
         let result = inst.call("stub", &[]);
         assert!(matches!(result, Err(ComponentError::Trap(_))));
     }
@@ -345,6 +688,7 @@ mod tests {
                     results: alloc::vec![ComponentType::S32],
                     func: add_fn,
                 }],
+                resources: alloc::vec![],
             })
             .unwrap();
 
@@ -355,7 +699,7 @@ mod tests {
             func: Some(add_fn),
         }];
 
-        let instance = linker.instantiate(&["test:math/ops"], exports).unwrap();
+        let mut instance = linker.instantiate(&["test:math/ops"], exports).unwrap();
 
         // Call export
         let result = instance
@@ -373,4 +717,127 @@ mod tests {
             .unwrap();
         assert_eq!(result, alloc::vec![ComponentValue::S32(300)]);
     }
+
+    // ── Resources and the canonical ABI adapter ──────────────────────
+
+    fn counter_new(
+        _ctx: &mut ComponentCtx,
+        args: &[ComponentValue],
+    ) -> Result<Vec<ComponentValue>, ComponentError> {
+        // The representation is the initial value itself.
+        Ok(alloc::vec![args[0].clone()])
+    }
+
+    fn counter_get(
+        _ctx: &mut ComponentCtx,
+        args: &[ComponentValue],
+    ) -> Result<Vec<ComponentValue>, ComponentError> {
+        Ok(alloc::vec![args[0].clone()])
+    }
+
+    fn counter_drop(ctx: &mut ComponentCtx, rep: u32) {
+        ctx.set_exit_code(rep);
+    }
+
+    fn make_counter_instance() -> ComponentInstance {
+        use super::super::linker::{ComponentLinker, InterfaceInstance};
+
+        let mut linker = ComponentLinker::new();
+        linker
+            .define_instance(InterfaceInstance {
+                name: String::from("test:counter/api"),
+                exports: alloc::vec![
+                    HostExport {
+                        name: String::from("[constructor]counter"),
+                        params: alloc::vec![ComponentType::U32],
+                        results: alloc::vec![ComponentType::Own(String::from("counter"))],
+                        func: counter_new,
+                    },
+                    HostExport {
+                        name: String::from("[method]counter.get"),
+                        params: alloc::vec![ComponentType::Borrow(String::from("counter"))],
+                        results: alloc::vec![ComponentType::U32],
+                        func: counter_get,
+                    },
+                ],
+                resources: alloc::vec![HostResource {
+                    name: String::from("counter"),
+                    drop: Some(counter_drop),
+                }],
+            })
+            .unwrap();
+        linker.instantiate(&["test:counter/api"], alloc::vec![]).unwrap()
+    }
+
+    #[test]
+    fn test_instance_resource_handles() {
+        let mut inst = make_counter_instance();
+        let iface = "test:counter/api";
+
+        let a = inst
+            .call_import(iface, "[constructor]counter", &[ComponentValue::U32(70)])
+            .unwrap();
+        let b = inst
+            .call_import(iface, "[constructor]counter", &[ComponentValue::U32(80)])
+            .unwrap();
+        // Guests see handles, not representations
+        assert_eq!(a, alloc::vec![ComponentValue::U32(1)]);
+        assert_eq!(b, alloc::vec![ComponentValue::U32(2)]);
+        assert_eq!(inst.context().handle_count(), 2);
+
+        let got = inst.call_import(iface, "[method]counter.get", &b).unwrap();
+        assert_eq!(got, alloc::vec![ComponentValue::U32(80)]);
+
+        // Dropping runs the destructor with the representation
+        inst.call_import(iface, "[resource-drop]counter", &a).unwrap();
+        assert_eq!(inst.context().exit_code(), Some(70));
+        assert_eq!(inst.context().handle_count(), 1);
+
+        let stale = inst.call_import(iface, "[method]counter.get", &a);
+        assert!(matches!(stale, Err(ComponentError::Trap(_))));
+    }
+
+    #[test]
+    fn test_instance_call_import_lowered() {
+        use super::super::canonical::VecMemory;
+
+        let mut inst = make_instance();
+        let mut memory = VecMemory::new(64);
+        let result = inst
+            .context_mut()
+            .call_import_lowered(
+                "test:math/ops",
+                "add",
+                &[CoreValue::I32(2), CoreValue::I32(40)],
+                &mut memory,
+            )
+            .unwrap();
+        assert_eq!(result, alloc::vec![CoreValue::I32(42)]);
+
+        let result = inst.context_mut().call_import_lowered(
+            "test:math/ops",
+            "add",
+            &[CoreValue::I32(2)],
+            &mut memory,
+        );
+        assert!(matches!(result, Err(ComponentError::TypeMismatch(_))));
+
+        // Resource handles lower to i32
+        let mut inst = make_counter_instance();
+        let handle = inst
+            .context_mut()
+            .call_import_lowered(
+                "test:counter/api",
+                "[constructor]counter",
+                &[CoreValue::I32(5)],
+                &mut memory,
+            )
+            .unwrap();
+        assert_eq!(handle, alloc::vec![CoreValue::I32(1)]);
+        let value = inst
+            .context_mut()
+            .call_import_lowered("test:counter/api", "[method]counter.get", &handle, &mut memory)
+            .unwrap();
+        assert_eq!(value, alloc::vec![CoreValue::I32(5)]);
+    }
 }
@@ -1,20 +1,34 @@
 //! Component Linker — resolves imports and instantiates components.
 //!
 //! The `ComponentLinker` lets callers register named interface instances
-//! (collections of typed host functions) and then instantiate a component
-//! with those imports resolved.
+//! (collections of typed host functions and resources) and then instantiate
+//! a component with those imports resolved.
+//!
+//! Resources need no glue from the host: the instance keeps a handle table,
+//! turns the representations returned for `own<T>` results into guest
+//! handles and back, and provides `[resource-drop]T` for every resource an
+//! interface declares. [`ComponentLinker::add_wasi_p2`] registers the
+//! standard WASI Preview 2 interfaces in one call.
 
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use super::canonical::CoreValue;
-use super::instance::ComponentInstance;
+use super::instance::{ComponentCtx, ComponentInstance, LinkedImport};
 use super::{ComponentError, ComponentType, ComponentValue};
 
 /// Signature of a host function callable through the component model.
-pub type ComponentHostFn = fn(&[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError>;
+///
+/// Resource handles in the arguments have already been replaced by the
+/// resource representation, and `own<T>` results are representations the
+/// instance will wrap in fresh handles.
+pub type ComponentHostFn =
+    fn(&mut ComponentCtx, &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError>;
+
+/// Destructor run with the representation of a resource whose owned
+/// handle the guest dropped.
+pub type ResourceDropFn = fn(&mut ComponentCtx, u32);
 
 /// Definition of a single host function export.
 #[derive(Clone)]
@@ -29,6 +43,15 @@ pub struct HostExport {
     pub func: ComponentHostFn,
 }
 
+/// A resource type defined by a host interface.
+#[derive(Clone)]
+pub struct HostResource {
+    /// Resource name, as used in `ComponentType::Own` and `Borrow`.
+    pub name: String,
+    /// Destructor, if the host keeps state for each resource.
+    pub drop: Option<ResourceDropFn>,
+}
+
 /// An interface instance holding named exports.
 #[derive(Clone)]
 pub struct InterfaceInstance {
@@ -36,6 +59,28 @@ pub struct InterfaceInstance {
     pub name: String,
     /// Exported functions.
     pub exports: Vec<HostExport>,
+    /// Resource types; each gets a `[resource-drop]<name>` export.
+    pub resources: Vec<HostResource>,
+}
+
+/// A function a component imports, with the signature it expects.
+#[derive(Debug, Clone)]
+pub struct ImportedFunction {
+    /// Function name.
+    pub name: String,
+    /// Parameter types.
+    pub params: Vec<ComponentType>,
+    /// Result types.
+    pub results: Vec<ComponentType>,
+}
+
+/// An interface a component imports.
+#[derive(Debug, Clone)]
+pub struct ComponentImport {
+    /// Interface name (e.g., "wasi:cli/stdout@0.2.0").
+    pub interface: String,
+    /// Functions used from the interface.
+    pub functions: Vec<ImportedFunction>,
 }
 
 /// Component linker — collects interface definitions and instantiates
@@ -67,6 +112,12 @@ impl ComponentLinker {
         Ok(())
     }
 
+    /// Register the WASI Preview 2 interfaces: filesystem, clocks, random,
+    /// CLI, I/O streams, sockets and HTTP.
+    pub fn add_wasi_p2(&mut self) -> Result<(), ComponentError> {
+        super::wasi_bridge::register_wasi_p2(self)
+    }
+
     /// Check whether a named interface is already defined.
     pub fn has_instance(&self, name: &str) -> bool {
         self.instances.contains_key(name)
@@ -120,7 +171,61 @@ impl ComponentLinker {
             let inst = &self.instances[import];
             for export in &inst.exports {
                 let key = alloc::format!("{}#{}", import, export.name);
-                resolved.insert(key, export.clone());
+                resolved.insert(key, LinkedImport::Host(export.clone()));
+            }
+            for resource in &inst.resources {
+                let key = alloc::format!("{}#[resource-drop]{}", import, resource.name);
+                resolved.insert(key, LinkedImport::resource_drop(resource));
+            }
+        }
+
+        Ok(ComponentInstance::new(resolved, exports))
+    }
+
+    /// Instantiate a component from the functions it imports.
+    ///
+    /// Each imported function must be defined with exactly the signature
+    /// the component expects. `[resource-drop]<name>` imports resolve to
+    /// the linker-provided destructor of a resource the interface declares.
+    pub fn link(
+        &self,
+        imports: &[ComponentImport],
+        exports: Vec<ComponentExport>,
+    ) -> Result<ComponentInstance, ComponentError> {
+        let mut resolved = BTreeMap::new();
+        for import in imports {
+            let inst = self
+                .instances
+                .get(&import.interface)
+                .ok_or_else(|| ComponentError::ImportNotFound(import.interface.clone()))?;
+            for func in &import.functions {
+                let key = alloc::format!("{}#{}", import.interface, func.name);
+                let linked = match func.name.strip_prefix("[resource-drop]") {
+                    Some(name) => inst
+                        .resources
+                        .iter()
+                        .find(|r| r.name == name)
+                        .map(LinkedImport::resource_drop),
+                    None => inst
+                        .exports
+                        .iter()
+                        .find(|e| e.name == func.name)
+                        .map(|e| LinkedImport::Host(e.clone())),
+                }
+                .ok_or_else(|| ComponentError::ImportNotFound(key.clone()))?;
+
+                let (params, results) = linked.signature();
+                if func.params != params || func.results != results {
+                    return Err(ComponentError::TypeMismatch(alloc::format!(
+                        "import {} expects {:?} -> {:?}, host defines {:?} -> {:?}",
+                        key,
+                        func.params,
+                        func.results,
+                        params,
+                        results
+                    )));
+                }
+                resolved.insert(key, linked);
             }
         }
 
@@ -144,7 +249,8 @@ pub struct ComponentExport {
     /// Result types.
     pub results: Vec<ComponentType>,
     /// Implementation: given lowered args, returns lowered results.
-    /// For host-defined components (testing), this is a direct fn pointer.
+    /// For host-defined components (testing), this is a direct fn pointer
+    /// that reaches the component's imports through the context.
     pub func: Option<ComponentHostFn>,
 }
 
@@ -155,11 +261,17 @@ mod tests {
     use super::*;
     use alloc::string::String;
 
-    fn dummy_fn(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+    fn dummy_fn(
+        _ctx: &mut ComponentCtx,
+        _args: &[ComponentValue],
+    ) -> Result<Vec<ComponentValue>, ComponentError> {
         Ok(alloc::vec![ComponentValue::U32(42)])
     }
 
-    fn add_fn(args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+    fn add_fn(
+        _ctx: &mut ComponentCtx,
+        args: &[ComponentValue],
+    ) -> Result<Vec<ComponentValue>, ComponentError> {
         let a = match &args[0] {
             ComponentValue::S32(v) => *v,
             _ => return Err(ComponentError::TypeMismatch(String::from("expected s32"))),
@@ -188,6 +300,7 @@ mod tests {
                     func: add_fn,
                 },
             ],
+            resources: alloc::vec![],
         }
     }
 
@@ -260,4 +373,74 @@ mod tests {
         let result = linker.instantiate(&["missing:iface/here"], alloc::vec![]);
         assert!(matches!(result, Err(ComponentError::ImportNotFound(_))));
     }
+
+    fn signature(
+        name: &str,
+        params: Vec<ComponentType>,
+        results: Vec<ComponentType>,
+    ) -> ImportedFunction {
+        ImportedFunction {
+            name: String::from(name),
+            params,
+            results,
+        }
+    }
+
+    #[test]
+    fn test_linker_link_checks_signatures() {
+        let mut linker = ComponentLinker::new();
+        let mut iface = make_test_interface();
+        iface.resources.push(HostResource {
+            name: String::from("accumulator"),
+            drop: None,
+        });
+        linker.define_instance(iface).unwrap();
+
+        let imports = [ComponentImport {
+            interface: String::from("test:math/ops"),
+            functions: alloc::vec![
+                signature(
+                    "add",
+                    alloc::vec![ComponentType::S32, ComponentType::S32],
+                    alloc::vec![ComponentType::S32],
+                ),
+                signature(
+                    "[resource-drop]accumulator",
+                    alloc::vec![ComponentType::Own(String::from("accumulator"))],
+                    alloc::vec![],
+                ),
+            ],
+        }];
+        let instance = linker.link(&imports, alloc::vec![]).unwrap();
+        // Only what the component imports is linked
+        assert_eq!(
+            instance.import_keys(),
+            alloc::vec![
+                "test:math/ops#[resource-drop]accumulator",
+                "test:math/ops#add"
+            ]
+        );
+
+        let wrong = [ComponentImport {
+            interface: String::from("test:math/ops"),
+            functions: alloc::vec![signature(
+                "add",
+                alloc::vec![ComponentType::S64, ComponentType::S64],
+                alloc::vec![ComponentType::S64],
+            )],
+        }];
+        let result = linker.link(&wrong, alloc::vec![]);
+        assert!(matches!(result, Err(ComponentError::TypeMismatch(_))));
+
+        let missing = [ComponentImport {
+            interface: String::from("test:math/ops"),
+            functions: alloc::vec![signature(
+                "[resource-drop]missing",
+                alloc::vec![],
+                alloc::vec![]
+            )],
+        }];
+        let result = linker.link(&missing, alloc::vec![]);
+        assert!(matches!(result, Err(ComponentError::ImportNotFound(_))));
+    }
 }
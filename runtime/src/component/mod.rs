@@ -9,6 +9,7 @@
 //! - `canonical.rs` — Canonical ABI lowering (host→WASM) and lifting (WASM→host)
 //! - `linker.rs` — Component linker for import resolution and instantiation
 //! - `instance.rs` — Component instance with typed call interface
+//! - `wasi_bridge.rs` — WASI P2 interfaces for `ComponentLinker::add_wasi_p2`
 
 pub mod canonical;
 pub mod instance;
@@ -97,6 +98,10 @@ pub enum ComponentType {
         ok: Option<Box<ComponentType>>,
         err: Option<Box<ComponentType>>,
     },
+    /// Owned handle to the named resource (`own<T>`), passed as a `U32`.
+    Own(String),
+    /// Borrowed handle to the named resource (`borrow<T>`), passed as a `U32`.
+    Borrow(String),
 }
 
 /// Error type for component operations.
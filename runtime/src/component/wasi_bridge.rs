@@ -3,21 +3,45 @@
 //! Registers WASI Preview 2 interfaces (`wasi:clocks`, `wasi:random`,
 //! `wasi:cli`, etc.) as component model imports so that components
 //! can use standard WASI interfaces through the ComponentLinker.
+//!
+//! Resource representations are handles into the instance's
+//! [`Wasi2Ctx`] resource table; the linker maps them to guest handles.
+//! Signatures follow the 0.2.0 WIT definitions, except that the `error`
+//! resource of `stream-error` is passed as a string, `network` parameters
+//! are omitted, TCP sockets do not expose streams, and HTTP requests and
+//! responses are passed by value instead of as resources.
 
 use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use super::linker::{ComponentHostFn, ComponentLinker, HostExport, InterfaceInstance};
+use super::instance::ComponentCtx;
+use super::linker::{
+    ComponentHostFn, ComponentLinker, HostExport, HostResource, InterfaceInstance,
+};
 use super::{ComponentError, ComponentType, ComponentValue};
+use crate::wasi::WasiError;
+use crate::wasi2::filesystem::{self, Descriptor, DescriptorKind, DescriptorType};
+use crate::wasi2::sockets::{self, IpAddress, IpAddressFamily, IpSocketAddress, SocketError};
+use crate::wasi2::streams::InputStreamData;
+use crate::wasi2::{http, ResourceData, ResourceError, ResourceHandle, ResourceType, StreamError};
 
 /// Register all WASI P2 interfaces on the given linker.
 pub fn register_wasi_p2(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
     register_clocks_monotonic(linker)?;
     register_clocks_wall(linker)?;
     register_random(linker)?;
+    register_io_streams(linker)?;
     register_cli_environment(linker)?;
+    register_cli_stdin(linker)?;
     register_cli_stdout(linker)?;
+    register_cli_stderr(linker)?;
+    register_cli_exit(linker)?;
+    register_filesystem_types(linker)?;
+    register_filesystem_preopens(linker)?;
+    register_sockets_tcp_create_socket(linker)?;
+    register_sockets_tcp(linker)?;
+    register_http_outgoing_handler(linker)?;
     Ok(())
 }
 
@@ -39,17 +63,24 @@ fn register_clocks_monotonic(linker: &mut ComponentLinker) -> Result<(), Compone
                 func: wasi_clock_resolution,
             },
         ],
+        resources: alloc::vec![],
     };
     linker.define_instance(iface)
 }
 
-fn wasi_clock_now(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+fn wasi_clock_now(
+    _ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
     // MVP: return a simulated monotonic timestamp.
     // In a real kernel, this would use rdtsc or HPET.
     Ok(alloc::vec![ComponentValue::U64(1_000_000)])
 }
 
-fn wasi_clock_resolution(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+fn wasi_clock_resolution(
+    _ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
     Ok(alloc::vec![ComponentValue::U64(1_000)]) // 1μs resolution
 }
 
@@ -66,11 +97,15 @@ fn register_clocks_wall(linker: &mut ComponentLinker) -> Result<(), ComponentErr
             ])],
             func: wasi_wall_clock_now,
         }],
+        resources: alloc::vec![],
     };
     linker.define_instance(iface)
 }
 
-fn wasi_wall_clock_now(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+fn wasi_wall_clock_now(
+    _ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
     Ok(alloc::vec![ComponentValue::Record(alloc::vec![
         (String::from("seconds"), ComponentValue::U64(1700000000)),
         (String::from("nanoseconds"), ComponentValue::U32(0)),
@@ -95,11 +130,15 @@ fn register_random(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
                 func: wasi_random_u64,
             },
         ],
+        resources: alloc::vec![],
     };
     linker.define_instance(iface)
 }
 
-fn wasi_random_bytes(args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+fn wasi_random_bytes(
+    _ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
     let len = match &args[0] {
         ComponentValue::U64(v) => *v as usize,
         _ => return Err(ComponentError::TypeMismatch(String::from("expected u64"))),
@@ -111,11 +150,145 @@ fn wasi_random_bytes(args: &[ComponentValue]) -> Result<Vec<ComponentValue>, Com
     Ok(alloc::vec![ComponentValue::List(list)])
 }
 
-fn wasi_random_u64(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+fn wasi_random_u64(
+    _ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
     let mut rng = crate::wasi2::random::RandomGenerator::new();
     Ok(alloc::vec![ComponentValue::U64(rng.get_random_u64())])
 }
 
+/// Register `wasi:io/streams@0.2.0`.
+fn register_io_streams(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    let input = || ComponentType::Borrow(String::from("input-stream"));
+    let output = || ComponentType::Borrow(String::from("output-stream"));
+    let iface = InterfaceInstance {
+        name: String::from("wasi:io/streams@0.2.0"),
+        exports: alloc::vec![
+            HostExport {
+                name: String::from("[method]input-stream.read"),
+                params: alloc::vec![input(), ComponentType::U64],
+                results: alloc::vec![result_type(Some(bytes_type()), Some(stream_error_type()))],
+                func: wasi_input_stream_read,
+            },
+            HostExport {
+                name: String::from("[method]input-stream.blocking-read"),
+                params: alloc::vec![input(), ComponentType::U64],
+                results: alloc::vec![result_type(Some(bytes_type()), Some(stream_error_type()))],
+                func: wasi_input_stream_read,
+            },
+            HostExport {
+                name: String::from("[method]output-stream.check-write"),
+                params: alloc::vec![output()],
+                results: alloc::vec![result_type(
+                    Some(ComponentType::U64),
+                    Some(stream_error_type())
+                )],
+                func: wasi_output_stream_check_write,
+            },
+            HostExport {
+                name: String::from("[method]output-stream.write"),
+                params: alloc::vec![output(), bytes_type()],
+                results: alloc::vec![result_type(None, Some(stream_error_type()))],
+                func: wasi_output_stream_write,
+            },
+            HostExport {
+                name: String::from("[method]output-stream.blocking-write-and-flush"),
+                params: alloc::vec![output(), bytes_type()],
+                results: alloc::vec![result_type(None, Some(stream_error_type()))],
+                func: wasi_output_stream_write,
+            },
+        ],
+        resources: alloc::vec![
+            HostResource {
+                name: String::from("input-stream"),
+                drop: Some(drop_wasi_resource),
+            },
+            HostResource {
+                name: String::from("output-stream"),
+                drop: Some(drop_wasi_resource),
+            },
+        ],
+    };
+    linker.define_instance(iface)
+}
+
+fn wasi_input_stream_read(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let handle = arg_handle(args, 0)?;
+    let len = arg_u64(args, 1)? as usize;
+    let read = match ctx
+        .wasi()?
+        .resources
+        .get_mut(handle, ResourceType::InputStream)
+    {
+        Ok(ResourceData::InputStream(stream)) => stream.read(len),
+        Ok(_) => unreachable!("resource table returned the wrong type"),
+        Err(e) => return Err(resource_trap(e)),
+    };
+    Ok(alloc::vec![match read {
+        Ok(data) => ok_value(Some(bytes_value(&data))),
+        Err(e) => err_value(stream_error_value(e)),
+    }])
+}
+
+fn wasi_output_stream_check_write(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let handle = arg_handle(args, 0)?;
+    let permitted = match ctx
+        .wasi()?
+        .resources
+        .get(handle, ResourceType::OutputStream)
+    {
+        Ok(ResourceData::OutputStream(stream)) => stream.check_write(),
+        Ok(_) => unreachable!("resource table returned the wrong type"),
+        Err(e) => return Err(resource_trap(e)),
+    };
+    Ok(alloc::vec![match permitted {
+        Ok(n) => ok_value(Some(ComponentValue::U64(n))),
+        Err(e) => err_value(stream_error_value(e)),
+    }])
+}
+
+fn wasi_output_stream_write(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let handle = arg_handle(args, 0)?;
+    let data = arg_bytes(args, 1)?;
+    let written = match ctx
+        .wasi()?
+        .resources
+        .get_mut(handle, ResourceType::OutputStream)
+    {
+        Ok(ResourceData::OutputStream(stream)) => stream.blocking_write_and_flush(&data),
+        Ok(_) => unreachable!("resource table returned the wrong type"),
+        Err(e) => return Err(resource_trap(e)),
+    };
+    Ok(alloc::vec![match written {
+        Ok(()) => ok_value(None),
+        Err(e) => err_value(stream_error_value(e)),
+    }])
+}
+
+/// Destructor for resources kept in the WASI resource table. The stdio
+/// streams are shared by every `get-stdin`/`get-stdout`/`get-stderr`
+/// handle and stay alive.
+fn drop_wasi_resource(ctx: &mut ComponentCtx, rep: u32) {
+    let Ok(wasi) = ctx.wasi() else {
+        return;
+    };
+    let handle = ResourceHandle::from_u32(rep);
+    let stdio = [wasi.stdin_handle, wasi.stdout_handle, wasi.stderr_handle];
+    if !stdio.contains(&Some(handle)) {
+        let _ = wasi.resources.delete(handle);
+    }
+}
+
 /// Register `wasi:cli/environment@0.2.0`.
 fn register_cli_environment(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
     let iface = InterfaceInstance {
@@ -138,38 +311,1046 @@ fn register_cli_environment(linker: &mut ComponentLinker) -> Result<(), Componen
                 results: alloc::vec![ComponentType::List(Box::new(ComponentType::String))],
                 func: wasi_get_arguments,
             },
+            HostExport {
+                name: String::from("initial-cwd"),
+                params: alloc::vec![],
+                results: alloc::vec![ComponentType::Option(Box::new(ComponentType::String))],
+                func: wasi_initial_cwd,
+            },
         ],
+        resources: alloc::vec![],
     };
     linker.define_instance(iface)
 }
 
-fn wasi_get_environment(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
-    // Return empty environment in sandboxed context
-    Ok(alloc::vec![ComponentValue::List(alloc::vec![])])
+fn wasi_get_environment(
+    ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let vars = ctx
+        .wasi()?
+        .cli_env
+        .get_environment()
+        .iter()
+        .map(|(key, value)| {
+            ComponentValue::Record(alloc::vec![
+                (String::from("key"), ComponentValue::String(key.clone())),
+                (String::from("value"), ComponentValue::String(value.clone())),
+            ])
+        })
+        .collect();
+    Ok(alloc::vec![ComponentValue::List(vars)])
+}
+
+fn wasi_get_arguments(
+    ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let args = ctx
+        .wasi()?
+        .cli_env
+        .get_arguments()
+        .iter()
+        .map(|arg| ComponentValue::String(arg.clone()))
+        .collect();
+    Ok(alloc::vec![ComponentValue::List(args)])
+}
+
+fn wasi_initial_cwd(
+    ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let cwd = ctx
+        .wasi()?
+        .cli_env
+        .initial_cwd()
+        .map(|cwd| Box::new(ComponentValue::String(String::from(cwd))));
+    Ok(alloc::vec![ComponentValue::Option(cwd)])
 }
 
-fn wasi_get_arguments(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
-    // Return empty arguments in sandboxed context
-    Ok(alloc::vec![ComponentValue::List(alloc::vec![])])
+/// Register `wasi:cli/stdin@0.2.0`.
+fn register_cli_stdin(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    register_stdio(linker, "stdin", "input-stream", wasi_get_stdin)
 }
 
 /// Register `wasi:cli/stdout@0.2.0`.
 fn register_cli_stdout(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    register_stdio(linker, "stdout", "output-stream", wasi_get_stdout)
+}
+
+/// Register `wasi:cli/stderr@0.2.0`.
+fn register_cli_stderr(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    register_stdio(linker, "stderr", "output-stream", wasi_get_stderr)
+}
+
+fn register_stdio(
+    linker: &mut ComponentLinker,
+    stream: &str,
+    resource: &str,
+    func: ComponentHostFn,
+) -> Result<(), ComponentError> {
     let iface = InterfaceInstance {
-        name: String::from("wasi:cli/stdout@0.2.0"),
+        name: alloc::format!("wasi:cli/{}@0.2.0", stream),
         exports: alloc::vec![HostExport {
-            name: String::from("get-stdout"),
+            name: alloc::format!("get-{}", stream),
             params: alloc::vec![],
-            results: alloc::vec![ComponentType::U32], // resource handle
-            func: wasi_get_stdout,
+            results: alloc::vec![ComponentType::Own(String::from(resource))],
+            func,
+        }],
+        resources: alloc::vec![],
+    };
+    linker.define_instance(iface)
+}
+
+fn wasi_get_stdin(
+    ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    stdio_handle(ctx.wasi()?.stdin_handle)
+}
+
+fn wasi_get_stdout(
+    ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    stdio_handle(ctx.wasi()?.stdout_handle)
+}
+
+fn wasi_get_stderr(
+    ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    stdio_handle(ctx.wasi()?.stderr_handle)
+}
+
+fn stdio_handle(handle: Option<ResourceHandle>) -> Result<Vec<ComponentValue>, ComponentError> {
+    let handle =
+        handle.ok_or_else(|| ComponentError::Trap(String::from("stdio stream not available")))?;
+    Ok(alloc::vec![ComponentValue::U32(handle.as_u32())])
+}
+
+/// Register `wasi:cli/exit@0.2.0`.
+fn register_cli_exit(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    let iface = InterfaceInstance {
+        name: String::from("wasi:cli/exit@0.2.0"),
+        exports: alloc::vec![HostExport {
+            name: String::from("exit"),
+            params: alloc::vec![result_type(None, None)],
+            results: alloc::vec![],
+            func: wasi_exit,
         }],
+        resources: alloc::vec![],
     };
     linker.define_instance(iface)
 }
 
-fn wasi_get_stdout(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
-    // Return a handle representing stdout (handle 1)
-    Ok(alloc::vec![ComponentValue::U32(1)])
+/// Record the exit status and trap to unwind the component.
+fn wasi_exit(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let code = match &args[0] {
+        ComponentValue::Result(Ok(_)) => 0,
+        _ => 1,
+    };
+    ctx.set_exit_code(code);
+    Err(ComponentError::Trap(alloc::format!("exit({})", code)))
+}
+
+/// `error-code` cases of `wasi:filesystem/types`, in WIT order.
+const FS_ERROR_CODES: &[&str] = &[
+    "access",
+    "would-block",
+    "already",
+    "bad-descriptor",
+    "busy",
+    "deadlock",
+    "quota",
+    "exist",
+    "file-too-large",
+    "illegal-byte-sequence",
+    "in-progress",
+    "interrupted",
+    "invalid",
+    "io",
+    "is-directory",
+    "loop",
+    "too-many-links",
+    "message-size",
+    "name-too-long",
+    "no-device",
+    "no-entry",
+    "no-lock",
+    "insufficient-memory",
+    "insufficient-space",
+    "not-directory",
+    "not-empty",
+    "not-recoverable",
+    "unsupported",
+    "no-tty",
+    "no-such-device",
+    "overflow",
+    "not-permitted",
+    "pipe",
+    "read-only",
+    "invalid-seek",
+    "text-file-busy",
+    "cross-device",
+];
+
+/// `descriptor-type` cases of `wasi:filesystem/types`, in WIT order.
+const DESCRIPTOR_TYPES: &[&str] = &[
+    "unknown",
+    "block-device",
+    "character-device",
+    "directory",
+    "fifo",
+    "symbolic-link",
+    "regular-file",
+    "socket",
+];
+
+/// `open-flags` bits.
+const OPEN_CREATE: u32 = 1 << 0;
+const OPEN_DIRECTORY: u32 = 1 << 1;
+const OPEN_EXCLUSIVE: u32 = 1 << 2;
+const OPEN_TRUNCATE: u32 = 1 << 3;
+
+/// `descriptor-flags` bits.
+const DESCRIPTOR_READ: u32 = 1 << 0;
+const DESCRIPTOR_WRITE: u32 = 1 << 1;
+
+/// Register `wasi:filesystem/types@0.2.0`.
+fn register_filesystem_types(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    let descriptor = || ComponentType::Borrow(String::from("descriptor"));
+    let error = || Some(enum_type(FS_ERROR_CODES));
+    let iface = InterfaceInstance {
+        name: String::from("wasi:filesystem/types@0.2.0"),
+        exports: alloc::vec![
+            HostExport {
+                name: String::from("[method]descriptor.open-at"),
+                params: alloc::vec![
+                    descriptor(),
+                    flags_type(&["symlink-follow"]),
+                    ComponentType::String,
+                    flags_type(&["create", "directory", "exclusive", "truncate"]),
+                    flags_type(&[
+                        "read",
+                        "write",
+                        "file-integrity-sync",
+                        "data-integrity-sync",
+                        "requested-write-sync",
+                        "mutate-directory",
+                    ]),
+                ],
+                results: alloc::vec![result_type(
+                    Some(ComponentType::Own(String::from("descriptor"))),
+                    error()
+                )],
+                func: wasi_descriptor_open_at,
+            },
+            HostExport {
+                name: String::from("[method]descriptor.stat"),
+                params: alloc::vec![descriptor()],
+                results: alloc::vec![result_type(
+                    Some(ComponentType::Record(alloc::vec![
+                        (String::from("type"), enum_type(DESCRIPTOR_TYPES)),
+                        (String::from("link-count"), ComponentType::U64),
+                        (String::from("size"), ComponentType::U64),
+                    ])),
+                    error()
+                )],
+                func: wasi_descriptor_stat,
+            },
+            HostExport {
+                name: String::from("[method]descriptor.read"),
+                params: alloc::vec![descriptor(), ComponentType::U64, ComponentType::U64],
+                results: alloc::vec![result_type(
+                    Some(tuple_type(alloc::vec![bytes_type(), ComponentType::Bool])),
+                    error()
+                )],
+                func: wasi_descriptor_read,
+            },
+            HostExport {
+                name: String::from("[method]descriptor.write"),
+                params: alloc::vec![descriptor(), bytes_type(), ComponentType::U64],
+                results: alloc::vec![result_type(Some(ComponentType::U64), error())],
+                func: wasi_descriptor_write,
+            },
+            HostExport {
+                name: String::from("[method]descriptor.read-via-stream"),
+                params: alloc::vec![descriptor(), ComponentType::U64],
+                results: alloc::vec![result_type(
+                    Some(ComponentType::Own(String::from("input-stream"))),
+                    error()
+                )],
+                func: wasi_descriptor_read_via_stream,
+            },
+        ],
+        resources: alloc::vec![HostResource {
+            name: String::from("descriptor"),
+            drop: Some(drop_wasi_resource),
+        }],
+    };
+    linker.define_instance(iface)
+}
+
+fn wasi_descriptor_open_at(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let dir = descriptor(ctx, arg_handle(args, 0)?)?;
+    let path = arg_string(args, 2)?;
+    let open_flags = arg_flags(args, 3)?;
+    let descriptor_flags = arg_flags(args, 4)?;
+
+    let create = open_flags & OPEN_CREATE != 0;
+    let opened = dir
+        .open_at(
+            ctx.vfs(),
+            &path,
+            create,
+            open_flags & OPEN_EXCLUSIVE != 0,
+            open_flags & OPEN_TRUNCATE != 0,
+            descriptor_flags & DESCRIPTOR_WRITE != 0,
+        )
+        .and_then(|mut opened| {
+            if opened.kind == DescriptorKind::File {
+                if open_flags & OPEN_DIRECTORY != 0 {
+                    return Err(WasiError::NotDir);
+                }
+                if !ctx.vfs().exists(&opened.path) {
+                    ctx.vfs_mut().create_file(&opened.path, Vec::new())?;
+                } else if open_flags & OPEN_TRUNCATE != 0 {
+                    ctx.vfs_mut().truncate(&opened.path)?;
+                }
+                opened.readable = descriptor_flags & DESCRIPTOR_READ != 0;
+            }
+            Ok(opened)
+        });
+    let opened = match opened {
+        Ok(opened) => opened,
+        Err(e) => return Ok(alloc::vec![err_value(fs_error_value(e))]),
+    };
+
+    let handle = ctx
+        .wasi()?
+        .resources
+        .push(ResourceType::Descriptor, ResourceData::Descriptor(opened))
+        .map_err(resource_trap)?;
+    Ok(alloc::vec![ok_value(Some(ComponentValue::U32(
+        handle.as_u32()
+    )))])
+}
+
+fn wasi_descriptor_stat(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let desc = descriptor(ctx, arg_handle(args, 0)?)?;
+    Ok(alloc::vec![match desc.stat(ctx.vfs()) {
+        Ok(stat) => ok_value(Some(ComponentValue::Record(alloc::vec![
+            (
+                String::from("type"),
+                enum_value(DESCRIPTOR_TYPES, descriptor_type_name(stat.descriptor_type)),
+            ),
+            (
+                String::from("link-count"),
+                ComponentValue::U64(stat.link_count)
+            ),
+            (String::from("size"), ComponentValue::U64(stat.size)),
+        ]))),
+        Err(e) => err_value(fs_error_value(e)),
+    }])
+}
+
+fn wasi_descriptor_read(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let desc = descriptor(ctx, arg_handle(args, 0)?)?;
+    let len = arg_u64(args, 1)?;
+    let offset = arg_u64(args, 2)?;
+    let read = match desc.kind {
+        DescriptorKind::Directory => Err(WasiError::IsDir),
+        DescriptorKind::File if !desc.readable => Err(WasiError::BadF),
+        DescriptorKind::File => ctx.vfs().read_file(&desc.path).map(|data| {
+            let start = (offset as usize).min(data.len());
+            let end = start.saturating_add(len as usize).min(data.len());
+            (data[start..end].to_vec(), end == data.len())
+        }),
+    };
+    Ok(alloc::vec![match read {
+        Ok((data, eof)) => ok_value(Some(tuple_value(alloc::vec![
+            bytes_value(&data),
+            ComponentValue::Bool(eof),
+        ]))),
+        Err(e) => err_value(fs_error_value(e)),
+    }])
+}
+
+fn wasi_descriptor_write(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let desc = descriptor(ctx, arg_handle(args, 0)?)?;
+    let data = arg_bytes(args, 1)?;
+    let offset = arg_u64(args, 2)?;
+    let written = match desc.kind {
+        DescriptorKind::Directory => Err(WasiError::IsDir),
+        DescriptorKind::File if !desc.writable => Err(WasiError::BadF),
+        DescriptorKind::File => ctx.vfs_mut().write_file(&desc.path, offset as usize, &data),
+    };
+    Ok(alloc::vec![match written {
+        Ok(()) => ok_value(Some(ComponentValue::U64(data.len() as u64))),
+        Err(e) => err_value(fs_error_value(e)),
+    }])
+}
+
+fn wasi_descriptor_read_via_stream(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let desc = descriptor(ctx, arg_handle(args, 0)?)?;
+    let offset = arg_u64(args, 1)?;
+    let stream: InputStreamData = match desc.read_via_stream(ctx.vfs(), offset) {
+        Ok(stream) => stream,
+        Err(e) => return Ok(alloc::vec![err_value(fs_error_value(e))]),
+    };
+    let handle = ctx
+        .wasi()?
+        .resources
+        .push(ResourceType::InputStream, ResourceData::InputStream(stream))
+        .map_err(resource_trap)?;
+    Ok(alloc::vec![ok_value(Some(ComponentValue::U32(
+        handle.as_u32()
+    )))])
+}
+
+/// Register `wasi:filesystem/preopens@0.2.0`.
+fn register_filesystem_preopens(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    let iface = InterfaceInstance {
+        name: String::from("wasi:filesystem/preopens@0.2.0"),
+        exports: alloc::vec![HostExport {
+            name: String::from("get-directories"),
+            params: alloc::vec![],
+            results: alloc::vec![ComponentType::List(Box::new(tuple_type(alloc::vec![
+                ComponentType::Own(String::from("descriptor")),
+                ComponentType::String,
+            ])))],
+            func: wasi_get_directories,
+        }],
+        resources: alloc::vec![],
+    };
+    linker.define_instance(iface)
+}
+
+fn wasi_get_directories(
+    ctx: &mut ComponentCtx,
+    _args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let wasi = ctx.wasi()?;
+    let mut preopens = wasi.preopens.clone();
+    if preopens.is_empty() {
+        preopens = filesystem::default_preopens();
+    }
+    let mut dirs = Vec::new();
+    for preopen in preopens {
+        let handle = wasi
+            .resources
+            .push(
+                ResourceType::Descriptor,
+                ResourceData::Descriptor(preopen.descriptor),
+            )
+            .map_err(resource_trap)?;
+        dirs.push(tuple_value(alloc::vec![
+            ComponentValue::U32(handle.as_u32()),
+            ComponentValue::String(preopen.guest_path),
+        ]));
+    }
+    Ok(alloc::vec![ComponentValue::List(dirs)])
+}
+
+/// `error-code` cases of `wasi:sockets/network`, in WIT order.
+const SOCKET_ERROR_CODES: &[&str] = &[
+    "unknown",
+    "access-denied",
+    "not-supported",
+    "invalid-argument",
+    "out-of-memory",
+    "timeout",
+    "concurrency-conflict",
+    "not-in-progress",
+    "would-block",
+    "invalid-state",
+    "new-socket-limit",
+    "address-not-bindable",
+    "address-in-use",
+    "remote-unreachable",
+    "connection-refused",
+    "connection-reset",
+    "connection-aborted",
+    "datagram-too-large",
+    "name-unresolvable",
+    "temporary-resolver-failure",
+    "permanent-resolver-failure",
+];
+
+/// Register `wasi:sockets/tcp-create-socket@0.2.0`.
+fn register_sockets_tcp_create_socket(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    let iface = InterfaceInstance {
+        name: String::from("wasi:sockets/tcp-create-socket@0.2.0"),
+        exports: alloc::vec![HostExport {
+            name: String::from("create-tcp-socket"),
+            params: alloc::vec![enum_type(&["ipv4", "ipv6"])],
+            results: alloc::vec![result_type(
+                Some(ComponentType::Own(String::from("tcp-socket"))),
+                Some(enum_type(SOCKET_ERROR_CODES))
+            )],
+            func: wasi_create_tcp_socket,
+        }],
+        resources: alloc::vec![],
+    };
+    linker.define_instance(iface)
+}
+
+fn wasi_create_tcp_socket(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let family = match &args[0] {
+        ComponentValue::Enum {
+            discriminant: 1, ..
+        } => IpAddressFamily::Ipv6,
+        _ => IpAddressFamily::Ipv4,
+    };
+    let socket_id = sockets::create_tcp_socket(family);
+    let handle = ctx
+        .wasi()?
+        .resources
+        .push(ResourceType::TcpSocket, ResourceData::TcpSocket(socket_id))
+        .map_err(resource_trap)?;
+    Ok(alloc::vec![ok_value(Some(ComponentValue::U32(
+        handle.as_u32()
+    )))])
+}
+
+/// Register `wasi:sockets/tcp@0.2.0`.
+fn register_sockets_tcp(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    let socket = || ComponentType::Borrow(String::from("tcp-socket"));
+    let unit_result = || result_type(None, Some(enum_type(SOCKET_ERROR_CODES)));
+    let iface = InterfaceInstance {
+        name: String::from("wasi:sockets/tcp@0.2.0"),
+        exports: alloc::vec![
+            HostExport {
+                name: String::from("[method]tcp-socket.start-bind"),
+                params: alloc::vec![socket(), socket_address_type()],
+                results: alloc::vec![unit_result()],
+                func: wasi_tcp_start_bind,
+            },
+            HostExport {
+                name: String::from("[method]tcp-socket.finish-bind"),
+                params: alloc::vec![socket()],
+                results: alloc::vec![unit_result()],
+                func: wasi_tcp_finish_bind,
+            },
+            HostExport {
+                name: String::from("[method]tcp-socket.start-connect"),
+                params: alloc::vec![socket(), socket_address_type()],
+                results: alloc::vec![unit_result()],
+                func: wasi_tcp_start_connect,
+            },
+            HostExport {
+                name: String::from("[method]tcp-socket.finish-connect"),
+                params: alloc::vec![socket()],
+                results: alloc::vec![unit_result()],
+                func: wasi_tcp_finish_connect,
+            },
+        ],
+        resources: alloc::vec![HostResource {
+            name: String::from("tcp-socket"),
+            drop: Some(drop_tcp_socket),
+        }],
+    };
+    linker.define_instance(iface)
+}
+
+fn wasi_tcp_start_bind(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let address = arg_socket_address(args, 1)?;
+    with_tcp_socket(ctx, args, |socket| socket.start_bind(address))
+}
+
+fn wasi_tcp_finish_bind(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    with_tcp_socket(ctx, args, |socket| socket.finish_bind())
+}
+
+fn wasi_tcp_start_connect(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let address = arg_socket_address(args, 1)?;
+    with_tcp_socket(ctx, args, |socket| socket.start_connect(address))
+}
+
+fn wasi_tcp_finish_connect(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    with_tcp_socket(ctx, args, |socket| socket.finish_connect())
+}
+
+/// Run `f` on the socket behind the `self` argument and convert its
+/// outcome to `result<_, error-code>`.
+fn with_tcp_socket(
+    ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+    f: impl FnOnce(&mut sockets::TcpSocket) -> Result<(), SocketError>,
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let id = tcp_socket_id(ctx, arg_handle(args, 0)?)?;
+    Ok(alloc::vec![match sockets::with_tcp_socket(id, f) {
+        Ok(Ok(())) => ok_value(None),
+        Ok(Err(e)) | Err(e) => err_value(socket_error_value(e)),
+    }])
+}
+
+fn drop_tcp_socket(ctx: &mut ComponentCtx, rep: u32) {
+    let handle = ResourceHandle::from_u32(rep);
+    if let Ok(id) = tcp_socket_id(ctx, handle) {
+        let _ = sockets::with_tcp_socket(id, |socket| socket.shutdown(sockets::ShutdownType::Both));
+    }
+    drop_wasi_resource(ctx, rep);
+}
+
+fn tcp_socket_id(ctx: &mut ComponentCtx, handle: ResourceHandle) -> Result<u32, ComponentError> {
+    match ctx.wasi()?.resources.get(handle, ResourceType::TcpSocket) {
+        Ok(ResourceData::TcpSocket(id)) => Ok(*id),
+        Ok(_) => unreachable!("resource table returned the wrong type"),
+        Err(e) => Err(resource_trap(e)),
+    }
+}
+
+/// `ip-socket-address` of `wasi:sockets/network`.
+fn socket_address_type() -> ComponentType {
+    ComponentType::Variant(alloc::vec![
+        (
+            String::from("ipv4"),
+            Some(ComponentType::Record(alloc::vec![
+                (String::from("port"), ComponentType::U16),
+                (
+                    String::from("address"),
+                    tuple_type(alloc::vec![ComponentType::U8; 4])
+                ),
+            ])),
+        ),
+        (
+            String::from("ipv6"),
+            Some(ComponentType::Record(alloc::vec![
+                (String::from("port"), ComponentType::U16),
+                (String::from("flow-info"), ComponentType::U32),
+                (
+                    String::from("address"),
+                    tuple_type(alloc::vec![ComponentType::U16; 8])
+                ),
+                (String::from("scope-id"), ComponentType::U32),
+            ])),
+        ),
+    ])
+}
+
+fn arg_socket_address(
+    args: &[ComponentValue],
+    index: usize,
+) -> Result<IpSocketAddress, ComponentError> {
+    let invalid = || ComponentError::TypeMismatch(String::from("expected ip-socket-address"));
+    let ComponentValue::Variant {
+        discriminant,
+        value: Some(payload),
+        ..
+    } = &args[index]
+    else {
+        return Err(invalid());
+    };
+    let ComponentValue::Record(fields) = &**payload else {
+        return Err(invalid());
+    };
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+            .ok_or_else(invalid)
+    };
+    let port = match field("port")? {
+        ComponentValue::U16(port) => *port,
+        _ => return Err(invalid()),
+    };
+    let ComponentValue::Record(parts) = field("address")? else {
+        return Err(invalid());
+    };
+    let address = match discriminant {
+        0 => {
+            let mut octets = [0u8; 4];
+            for (octet, (_, part)) in octets.iter_mut().zip(parts) {
+                let ComponentValue::U8(v) = part else {
+                    return Err(invalid());
+                };
+                *octet = *v;
+            }
+            IpAddress::Ipv4(octets[0], octets[1], octets[2], octets[3])
+        }
+        _ => {
+            let mut segments = [0u16; 8];
+            for (segment, (_, part)) in segments.iter_mut().zip(parts) {
+                let ComponentValue::U16(v) = part else {
+                    return Err(invalid());
+                };
+                *segment = *v;
+            }
+            IpAddress::Ipv6(segments)
+        }
+    };
+    Ok(IpSocketAddress { address, port })
+}
+
+fn socket_error_value(e: SocketError) -> ComponentValue {
+    let name = match e {
+        SocketError::AddressInUse => "address-in-use",
+        SocketError::AddressNotBindable => "address-not-bindable",
+        SocketError::ConnectionRefused => "connection-refused",
+        SocketError::ConnectionReset => "connection-reset",
+        SocketError::ConnectionAborted => "connection-aborted",
+        SocketError::InvalidArgument => "invalid-argument",
+        SocketError::WouldBlock => "would-block",
+        SocketError::NotConnected
+        | SocketError::AlreadyBound
+        | SocketError::AlreadyConnected
+        | SocketError::AlreadyListening
+        | SocketError::NotBound
+        | SocketError::NotListening => "invalid-state",
+        SocketError::Unknown(_) => "unknown",
+    };
+    enum_value(SOCKET_ERROR_CODES, name)
+}
+
+/// Register `wasi:http/outgoing-handler@0.2.0`.
+fn register_http_outgoing_handler(linker: &mut ComponentLinker) -> Result<(), ComponentError> {
+    let optional_string = || ComponentType::Option(Box::new(ComponentType::String));
+    let headers = || {
+        ComponentType::List(Box::new(tuple_type(alloc::vec![
+            ComponentType::String,
+            bytes_type(),
+        ])))
+    };
+    let iface = InterfaceInstance {
+        name: String::from("wasi:http/outgoing-handler@0.2.0"),
+        exports: alloc::vec![HostExport {
+            name: String::from("handle"),
+            params: alloc::vec![ComponentType::Record(alloc::vec![
+                (String::from("method"), ComponentType::String),
+                (String::from("scheme"), optional_string()),
+                (String::from("authority"), optional_string()),
+                (String::from("path-with-query"), optional_string()),
+                (String::from("headers"), headers()),
+                (String::from("body"), bytes_type()),
+            ])],
+            results: alloc::vec![result_type(
+                Some(ComponentType::Record(alloc::vec![
+                    (String::from("status"), ComponentType::U16),
+                    (String::from("headers"), headers()),
+                    (String::from("body"), bytes_type()),
+                ])),
+                Some(ComponentType::String)
+            )],
+            func: wasi_http_handle,
+        }],
+        resources: alloc::vec![],
+    };
+    linker.define_instance(iface)
+}
+
+fn wasi_http_handle(
+    _ctx: &mut ComponentCtx,
+    args: &[ComponentValue],
+) -> Result<Vec<ComponentValue>, ComponentError> {
+    let invalid = || ComponentError::TypeMismatch(String::from("expected http request"));
+    let ComponentValue::Record(fields) = &args[0] else {
+        return Err(invalid());
+    };
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+            .ok_or_else(invalid)
+    };
+    let optional_string = |name: &str| match field(name)? {
+        ComponentValue::Option(Some(value)) => match &**value {
+            ComponentValue::String(s) => Ok(Some(s.clone())),
+            _ => Err(invalid()),
+        },
+        ComponentValue::Option(None) => Ok(None),
+        _ => Err(invalid()),
+    };
+
+    let method = match field("method")? {
+        ComponentValue::String(method) => match method.as_str() {
+            "GET" => http::Method::Get,
+            "HEAD" => http::Method::Head,
+            "POST" => http::Method::Post,
+            "PUT" => http::Method::Put,
+            "DELETE" => http::Method::Delete,
+            "CONNECT" => http::Method::Connect,
+            "OPTIONS" => http::Method::Options,
+            "TRACE" => http::Method::Trace,
+            "PATCH" => http::Method::Patch,
+            _ => http::Method::Other(method.clone()),
+        },
+        _ => return Err(invalid()),
+    };
+    let mut request = http::OutgoingRequest::new(method);
+    request.scheme = optional_string("scheme")?.map(|scheme| match scheme.as_str() {
+        "http" => http::Scheme::Http,
+        "https" => http::Scheme::Https,
+        _ => http::Scheme::Other(scheme),
+    });
+    request.authority = optional_string("authority")?;
+    request.path_with_query = optional_string("path-with-query")?;
+    let ComponentValue::List(headers) = field("headers")? else {
+        return Err(invalid());
+    };
+    for header in headers {
+        match header {
+            ComponentValue::Record(parts) => match parts.as_slice() {
+                [(_, ComponentValue::String(name)), (_, value)] => {
+                    request.headers.append(name, list_bytes(value)?)
+                }
+                _ => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        }
+    }
+    request.body = list_bytes(field("body")?)?;
+
+    Ok(alloc::vec![match http::handle(&request, None) {
+        Ok(response) => {
+            let mut headers = Vec::new();
+            for name in response.headers.names() {
+                for value in response.headers.get(&name) {
+                    headers.push(tuple_value(alloc::vec![
+                        ComponentValue::String(name.clone()),
+                        bytes_value(&value),
+                    ]));
+                }
+            }
+            ok_value(Some(ComponentValue::Record(alloc::vec![
+                (
+                    String::from("status"),
+                    ComponentValue::U16(response.status.0)
+                ),
+                (String::from("headers"), ComponentValue::List(headers)),
+                (String::from("body"), bytes_value(&response.body)),
+            ])))
+        }
+        Err(e) => err_value(ComponentValue::String(alloc::format!("{:?}", e))),
+    }])
+}
+
+// ── Type and value helpers ───────────────────────────────────────────
+
+fn bytes_type() -> ComponentType {
+    ComponentType::List(Box::new(ComponentType::U8))
+}
+
+fn result_type(ok: Option<ComponentType>, err: Option<ComponentType>) -> ComponentType {
+    ComponentType::Result {
+        ok: ok.map(Box::new),
+        err: err.map(Box::new),
+    }
+}
+
+/// A tuple is a record with positional field names.
+fn tuple_type(types: Vec<ComponentType>) -> ComponentType {
+    ComponentType::Record(
+        types
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| (alloc::format!("{}", i), ty))
+            .collect(),
+    )
+}
+
+fn enum_type(names: &[&str]) -> ComponentType {
+    ComponentType::Enum(names.iter().map(|&name| String::from(name)).collect())
+}
+
+fn flags_type(names: &[&str]) -> ComponentType {
+    ComponentType::Flags(names.iter().map(|&name| String::from(name)).collect())
+}
+
+/// `stream-error` of `wasi:io/streams`.
+fn stream_error_type() -> ComponentType {
+    ComponentType::Variant(alloc::vec![
+        (
+            String::from("last-operation-failed"),
+            Some(ComponentType::String)
+        ),
+        (String::from("closed"), None),
+    ])
+}
+
+fn bytes_value(bytes: &[u8]) -> ComponentValue {
+    ComponentValue::List(bytes.iter().copied().map(ComponentValue::U8).collect())
+}
+
+fn tuple_value(values: Vec<ComponentValue>) -> ComponentValue {
+    ComponentValue::Record(
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| (alloc::format!("{}", i), value))
+            .collect(),
+    )
+}
+
+fn ok_value(value: Option<ComponentValue>) -> ComponentValue {
+    ComponentValue::Result(Ok(value.map(Box::new)))
+}
+
+fn err_value(value: ComponentValue) -> ComponentValue {
+    ComponentValue::Result(Err(Some(Box::new(value))))
+}
+
+fn enum_value(names: &[&str], name: &str) -> ComponentValue {
+    let discriminant = names
+        .iter()
+        .position(|&n| n == name)
+        .expect("enum case is listed") as u32;
+    ComponentValue::Enum {
+        discriminant,
+        name: String::from(name),
+    }
+}
+
+fn stream_error_value(e: StreamError) -> ComponentValue {
+    match e {
+        StreamError::LastOperationFailed(msg) => ComponentValue::Variant {
+            discriminant: 0,
+            name: String::from("last-operation-failed"),
+            value: Some(Box::new(ComponentValue::String(msg))),
+        },
+        StreamError::Closed => ComponentValue::Variant {
+            discriminant: 1,
+            name: String::from("closed"),
+            value: None,
+        },
+    }
+}
+
+fn fs_error_value(e: WasiError) -> ComponentValue {
+    let name = match e {
+        WasiError::Access | WasiError::NotCapable => "access",
+        WasiError::Again => "would-block",
+        WasiError::BadF => "bad-descriptor",
+        WasiError::Busy => "busy",
+        WasiError::Exist => "exist",
+        WasiError::FBig => "file-too-large",
+        WasiError::Inval => "invalid",
+        WasiError::IsDir => "is-directory",
+        WasiError::NameTooLong => "name-too-long",
+        WasiError::NoEnt => "no-entry",
+        WasiError::NoSpc => "insufficient-space",
+        WasiError::NotDir => "not-directory",
+        WasiError::NotEmpty => "not-empty",
+        WasiError::NotSup | WasiError::NoSys => "unsupported",
+        WasiError::Perm => "not-permitted",
+        WasiError::RoFs => "read-only",
+        _ => "io",
+    };
+    enum_value(FS_ERROR_CODES, name)
+}
+
+fn descriptor_type_name(ty: DescriptorType) -> &'static str {
+    match ty {
+        DescriptorType::Unknown => "unknown",
+        DescriptorType::BlockDevice => "block-device",
+        DescriptorType::CharacterDevice => "character-device",
+        DescriptorType::Directory => "directory",
+        DescriptorType::Fifo => "fifo",
+        DescriptorType::SymbolicLink => "symbolic-link",
+        DescriptorType::RegularFile => "regular-file",
+        DescriptorType::Socket => "socket",
+    }
+}
+
+fn descriptor(
+    ctx: &mut ComponentCtx,
+    handle: ResourceHandle,
+) -> Result<Descriptor, ComponentError> {
+    match ctx.wasi()?.resources.get(handle, ResourceType::Descriptor) {
+        Ok(ResourceData::Descriptor(desc)) => Ok(desc.clone()),
+        Ok(_) => unreachable!("resource table returned the wrong type"),
+        Err(e) => Err(resource_trap(e)),
+    }
+}
+
+fn resource_trap(e: ResourceError) -> ComponentError {
+    ComponentError::Trap(alloc::format!("WASI resource error: {:?}", e))
+}
+
+fn arg_handle(args: &[ComponentValue], index: usize) -> Result<ResourceHandle, ComponentError> {
+    match &args[index] {
+        ComponentValue::U32(rep) => Ok(ResourceHandle::from_u32(*rep)),
+        _ => Err(ComponentError::TypeMismatch(String::from(
+            "expected handle",
+        ))),
+    }
+}
+
+fn arg_u64(args: &[ComponentValue], index: usize) -> Result<u64, ComponentError> {
+    match &args[index] {
+        ComponentValue::U64(v) => Ok(*v),
+        _ => Err(ComponentError::TypeMismatch(String::from("expected u64"))),
+    }
+}
+
+fn arg_flags(args: &[ComponentValue], index: usize) -> Result<u32, ComponentError> {
+    match &args[index] {
+        ComponentValue::Flags(bits) => Ok(*bits),
+        _ => Err(ComponentError::TypeMismatch(String::from("expected flags"))),
+    }
+}
+
+fn arg_string(args: &[ComponentValue], index: usize) -> Result<String, ComponentError> {
+    match &args[index] {
+        ComponentValue::String(s) => Ok(s.clone()),
+        _ => Err(ComponentError::TypeMismatch(String::from(
+            "expected string",
+        ))),
+    }
+}
+
+fn arg_bytes(args: &[ComponentValue], index: usize) -> Result<Vec<u8>, ComponentError> {
+    list_bytes(&args[index])
+}
+
+fn list_bytes(value: &ComponentValue) -> Result<Vec<u8>, ComponentError> {
+    let invalid = || ComponentError::TypeMismatch(String::from("expected list<u8>"));
+    match value {
+        ComponentValue::List(items) => items
+            .iter()
+            .map(|item| match item {
+                ComponentValue::U8(b) => Ok(*b),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
 }
 
 // ── Tests ────────────────────────────────────────────────────────────
@@ -177,6 +1358,27 @@ fn wasi_get_stdout(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, Comp
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::component::instance::ComponentInstance;
+
+    /// An instance with every WASI interface linked.
+    fn wasi_instance() -> ComponentInstance {
+        let mut linker = ComponentLinker::new();
+        linker.add_wasi_p2().unwrap();
+        let names: Vec<String> = linker
+            .interface_names()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        linker.instantiate(&names, alloc::vec![]).unwrap()
+    }
+
+    fn ok_payload(value: &ComponentValue) -> &ComponentValue {
+        match value {
+            ComponentValue::Result(Ok(Some(payload))) => payload,
+            other => panic!("expected ok, got {:?}", other),
+        }
+    }
 
     #[test]
     fn test_register_wasi_p2_all() {
@@ -188,11 +1390,20 @@ mod tests {
         assert!(linker.has_instance("wasi:random/random@0.2.0"));
         assert!(linker.has_instance("wasi:cli/environment@0.2.0"));
         assert!(linker.has_instance("wasi:cli/stdout@0.2.0"));
+        assert!(linker.has_instance("wasi:io/streams@0.2.0"));
+        assert!(linker.has_instance("wasi:filesystem/types@0.2.0"));
+        assert!(linker.has_instance("wasi:filesystem/preopens@0.2.0"));
+        assert!(linker.has_instance("wasi:sockets/tcp@0.2.0"));
+        assert!(linker.has_instance("wasi:http/outgoing-handler@0.2.0"));
+
+        // Registering twice is a conflict
+        assert!(linker.add_wasi_p2().is_err());
     }
 
     #[test]
     fn test_wasi_clock_now() {
-        let result = wasi_clock_now(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let result = wasi_clock_now(inst.context_mut(), &[]).unwrap();
         assert_eq!(result.len(), 1);
         if let ComponentValue::U64(v) = &result[0] {
             assert!(*v > 0);
@@ -203,7 +1414,8 @@ mod tests {
 
     #[test]
     fn test_wasi_clock_resolution() {
-        let result = wasi_clock_resolution(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let result = wasi_clock_resolution(inst.context_mut(), &[]).unwrap();
         assert_eq!(result.len(), 1);
         if let ComponentValue::U64(v) = &result[0] {
             assert_eq!(*v, 1_000);
@@ -214,7 +1426,8 @@ mod tests {
 
     #[test]
     fn test_wasi_wall_clock_now() {
-        let result = wasi_wall_clock_now(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let result = wasi_wall_clock_now(inst.context_mut(), &[]).unwrap();
         assert_eq!(result.len(), 1);
         if let ComponentValue::Record(fields) = &result[0] {
             assert_eq!(fields.len(), 2);
@@ -227,7 +1440,8 @@ mod tests {
 
     #[test]
     fn test_wasi_random_bytes() {
-        let result = wasi_random_bytes(&[ComponentValue::U64(16)]).unwrap();
+        let mut inst = wasi_instance();
+        let result = wasi_random_bytes(inst.context_mut(), &[ComponentValue::U64(16)]).unwrap();
         assert_eq!(result.len(), 1);
         if let ComponentValue::List(bytes) = &result[0] {
             assert_eq!(bytes.len(), 16);
@@ -238,30 +1452,52 @@ mod tests {
 
     #[test]
     fn test_wasi_random_u64() {
-        let result = wasi_random_u64(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let result = wasi_random_u64(inst.context_mut(), &[]).unwrap();
         assert_eq!(result.len(), 1);
         assert!(matches!(result[0], ComponentValue::U64(_)));
     }
 
     #[test]
     fn test_wasi_get_environment() {
-        let result = wasi_get_environment(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let ctx = inst.context_mut();
+        ctx.wasi()
+            .unwrap()
+            .cli_env
+            .set_vars(alloc::vec![(String::from("HOME"), String::from("/"))]);
+        let result = wasi_get_environment(ctx, &[]).unwrap();
         assert_eq!(result.len(), 1);
-        assert!(matches!(result[0], ComponentValue::List(_)));
+        assert_eq!(
+            result[0],
+            ComponentValue::List(alloc::vec![ComponentValue::Record(alloc::vec![
+                (
+                    String::from("key"),
+                    ComponentValue::String(String::from("HOME"))
+                ),
+                (
+                    String::from("value"),
+                    ComponentValue::String(String::from("/"))
+                ),
+            ])])
+        );
     }
 
     #[test]
     fn test_wasi_get_arguments() {
-        let result = wasi_get_arguments(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let result = wasi_get_arguments(inst.context_mut(), &[]).unwrap();
         assert_eq!(result.len(), 1);
         assert!(matches!(result[0], ComponentValue::List(_)));
     }
 
     #[test]
     fn test_wasi_get_stdout() {
-        let result = wasi_get_stdout(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let result = wasi_get_stdout(inst.context_mut(), &[]).unwrap();
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0], ComponentValue::U32(1));
+        let stdout = inst.context_mut().wasi().unwrap().stdout_handle.unwrap();
+        assert_eq!(result[0], ComponentValue::U32(stdout.as_u32()));
     }
 
     #[test]
@@ -273,7 +1509,8 @@ mod tests {
             .resolve("wasi:clocks/monotonic-clock@0.2.0", "now")
             .unwrap();
         assert_eq!(export.name, "now");
-        let result = (export.func)(&[]).unwrap();
+        let mut inst = wasi_instance();
+        let result = (export.func)(inst.context_mut(), &[]).unwrap();
         assert!(matches!(result[0], ComponentValue::U64(_)));
     }
 
@@ -284,7 +1521,10 @@ mod tests {
         let mut linker = ComponentLinker::new();
         register_wasi_p2(&mut linker).unwrap();
 
-        fn my_app(_args: &[ComponentValue]) -> Result<Vec<ComponentValue>, ComponentError> {
+        fn my_app(
+            _ctx: &mut ComponentCtx,
+            _args: &[ComponentValue],
+        ) -> Result<Vec<ComponentValue>, ComponentError> {
             Ok(alloc::vec![ComponentValue::U32(0)]) // exit code 0
         }
 
@@ -295,7 +1535,7 @@ mod tests {
             func: Some(my_app),
         }];
 
-        let instance = linker
+        let mut instance = linker
             .instantiate(
                 &[
                     "wasi:clocks/monotonic-clock@0.2.0",
@@ -315,4 +1555,290 @@ mod tests {
             .unwrap();
         assert!(matches!(clock[0], ComponentValue::U64(_)));
     }
+
+    #[test]
+    fn test_wasi_hello_world_component() {
+        use super::super::linker::{ComponentExport, ComponentImport, ImportedFunction};
+        use crate::wasi2::streams::OutputStreamData;
+
+        // A component that prints through its stdout import, then drops
+        // the stream handle.
+        fn run(
+            ctx: &mut ComponentCtx,
+            _args: &[ComponentValue],
+        ) -> Result<Vec<ComponentValue>, ComponentError> {
+            let stdout = ctx.call_import("wasi:cli/stdout@0.2.0", "get-stdout", &[])?;
+            let message = bytes_value(b"hello\n");
+            ctx.call_import(
+                "wasi:io/streams@0.2.0",
+                "[method]output-stream.blocking-write-and-flush",
+                &[stdout[0].clone(), message],
+            )?;
+            ctx.call_import(
+                "wasi:io/streams@0.2.0",
+                "[resource-drop]output-stream",
+                &stdout,
+            )?;
+            Ok(alloc::vec![])
+        }
+
+        let output_stream = || String::from("output-stream");
+        let imports = [
+            ComponentImport {
+                interface: String::from("wasi:cli/stdout@0.2.0"),
+                functions: alloc::vec![ImportedFunction {
+                    name: String::from("get-stdout"),
+                    params: alloc::vec![],
+                    results: alloc::vec![ComponentType::Own(output_stream())],
+                }],
+            },
+            ComponentImport {
+                interface: String::from("wasi:io/streams@0.2.0"),
+                functions: alloc::vec![
+                    ImportedFunction {
+                        name: String::from("[method]output-stream.blocking-write-and-flush"),
+                        params: alloc::vec![ComponentType::Borrow(output_stream()), bytes_type()],
+                        results: alloc::vec![result_type(None, Some(stream_error_type()))],
+                    },
+                    ImportedFunction {
+                        name: String::from("[resource-drop]output-stream"),
+                        params: alloc::vec![ComponentType::Own(output_stream())],
+                        results: alloc::vec![],
+                    },
+                ],
+            },
+        ];
+        let exports = alloc::vec![ComponentExport {
+            name: String::from("run"),
+            params: alloc::vec![],
+            results: alloc::vec![],
+            func: Some(run),
+        }];
+
+        let mut linker = ComponentLinker::new();
+        linker.add_wasi_p2().unwrap();
+        let mut instance = linker.link(&imports, exports).unwrap();
+        instance.call("run", &[]).unwrap();
+        instance.call("run", &[]).unwrap();
+
+        let ctx = instance.context_mut();
+        assert_eq!(ctx.handle_count(), 0);
+        let wasi = ctx.wasi().unwrap();
+        let stdout = wasi.stdout_handle.unwrap();
+        match wasi.resources.get(stdout, ResourceType::OutputStream) {
+            Ok(ResourceData::OutputStream(OutputStreamData::Stdout(stream))) => {
+                assert_eq!(stream.data(), b"hello\nhello\n");
+            }
+            _ => panic!("stdout stream dropped"),
+        }
+    }
+
+    #[test]
+    fn test_wasi_filesystem_through_preopen() {
+        let mut inst = wasi_instance();
+        let types = "wasi:filesystem/types@0.2.0";
+
+        let dirs = inst
+            .call_import("wasi:filesystem/preopens@0.2.0", "get-directories", &[])
+            .unwrap();
+        let ComponentValue::List(dirs) = &dirs[0] else {
+            panic!("expected list");
+        };
+        let ComponentValue::Record(root) = &dirs[0] else {
+            panic!("expected tuple");
+        };
+        assert_eq!(root[1].1, ComponentValue::String(String::from("/")));
+        let root = root[0].1.clone();
+
+        // Create, write and read back a file
+        let opened = inst
+            .call_import(
+                types,
+                "[method]descriptor.open-at",
+                &[
+                    root.clone(),
+                    ComponentValue::Flags(0),
+                    ComponentValue::String(String::from("notes.txt")),
+                    ComponentValue::Flags(OPEN_CREATE),
+                    ComponentValue::Flags(DESCRIPTOR_READ | DESCRIPTOR_WRITE),
+                ],
+            )
+            .unwrap();
+        let file = ok_payload(&opened[0]).clone();
+        let written = inst
+            .call_import(
+                types,
+                "[method]descriptor.write",
+                &[file.clone(), bytes_value(b"kpio"), ComponentValue::U64(0)],
+            )
+            .unwrap();
+        assert_eq!(ok_payload(&written[0]), &ComponentValue::U64(4));
+        assert_eq!(
+            inst.context().vfs().read_file("/notes.txt").unwrap(),
+            b"kpio"
+        );
+
+        let read = inst
+            .call_import(
+                types,
+                "[method]descriptor.read",
+                &[
+                    file.clone(),
+                    ComponentValue::U64(16),
+                    ComponentValue::U64(1),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            ok_payload(&read[0]),
+            &tuple_value(alloc::vec![bytes_value(b"pio"), ComponentValue::Bool(true)])
+        );
+
+        let stat = inst
+            .call_import(
+                types,
+                "[method]descriptor.stat",
+                core::slice::from_ref(&file),
+            )
+            .unwrap();
+        let ComponentValue::Record(stat) = ok_payload(&stat[0]) else {
+            panic!("expected record");
+        };
+        assert_eq!(stat[0].1, enum_value(DESCRIPTOR_TYPES, "regular-file"));
+        assert_eq!(stat[2].1, ComponentValue::U64(4));
+
+        // Errors come back as error codes, not traps
+        let missing = inst
+            .call_import(
+                types,
+                "[method]descriptor.open-at",
+                &[
+                    root,
+                    ComponentValue::Flags(0),
+                    ComponentValue::String(String::from("missing.txt")),
+                    ComponentValue::Flags(0),
+                    ComponentValue::Flags(DESCRIPTOR_READ),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            missing[0],
+            err_value(enum_value(FS_ERROR_CODES, "no-entry"))
+        );
+
+        // Dropping a descriptor releases the WASI resource too
+        let before = inst.context_mut().wasi().unwrap().resources.len();
+        inst.call_import(
+            types,
+            "[resource-drop]descriptor",
+            core::slice::from_ref(&file),
+        )
+        .unwrap();
+        assert_eq!(
+            inst.context_mut().wasi().unwrap().resources.len(),
+            before - 1
+        );
+        let stale = inst.call_import(types, "[method]descriptor.stat", &[file]);
+        assert!(matches!(stale, Err(ComponentError::Trap(_))));
+    }
+
+    #[test]
+    fn test_wasi_tcp_socket_bind() {
+        let mut inst = wasi_instance();
+        let created = inst
+            .call_import(
+                "wasi:sockets/tcp-create-socket@0.2.0",
+                "create-tcp-socket",
+                &[enum_value(&["ipv4", "ipv6"], "ipv4")],
+            )
+            .unwrap();
+        let socket = ok_payload(&created[0]).clone();
+
+        let address = ComponentValue::Variant {
+            discriminant: 0,
+            name: String::from("ipv4"),
+            value: Some(Box::new(ComponentValue::Record(alloc::vec![
+                (String::from("port"), ComponentValue::U16(8080)),
+                (
+                    String::from("address"),
+                    tuple_value(alloc::vec![
+                        ComponentValue::U8(127),
+                        ComponentValue::U8(0),
+                        ComponentValue::U8(0),
+                        ComponentValue::U8(1)
+                    ])
+                ),
+            ]))),
+        };
+        let tcp = "wasi:sockets/tcp@0.2.0";
+        let bound = inst
+            .call_import(
+                tcp,
+                "[method]tcp-socket.start-bind",
+                &[socket.clone(), address],
+            )
+            .unwrap();
+        assert_eq!(bound[0], ok_value(None));
+        let finished = inst
+            .call_import(
+                tcp,
+                "[method]tcp-socket.finish-bind",
+                core::slice::from_ref(&socket),
+            )
+            .unwrap();
+        assert_eq!(finished[0], ok_value(None));
+        let again = inst
+            .call_import(
+                tcp,
+                "[method]tcp-socket.finish-bind",
+                core::slice::from_ref(&socket),
+            )
+            .unwrap();
+        assert!(matches!(again[0], ComponentValue::Result(Err(_))));
+
+        inst.call_import(tcp, "[resource-drop]tcp-socket", &[socket])
+            .unwrap();
+        assert_eq!(inst.context().handle_count(), 0);
+    }
+
+    #[test]
+    fn test_wasi_http_handle() {
+        let mut inst = wasi_instance();
+        let request = ComponentValue::Record(alloc::vec![
+            (
+                String::from("method"),
+                ComponentValue::String(String::from("GET"))
+            ),
+            (String::from("scheme"), ComponentValue::Option(None)),
+            (String::from("authority"), ComponentValue::Option(None)),
+            (
+                String::from("path-with-query"),
+                ComponentValue::Option(Some(Box::new(ComponentValue::String(String::from(
+                    "/index.html"
+                ))))),
+            ),
+            (String::from("headers"), ComponentValue::List(alloc::vec![])),
+            (String::from("body"), bytes_value(b"")),
+        ]);
+        let response = inst
+            .call_import("wasi:http/outgoing-handler@0.2.0", "handle", &[request])
+            .unwrap();
+        let ComponentValue::Record(fields) = ok_payload(&response[0]) else {
+            panic!("expected record");
+        };
+        assert_eq!(fields[0].1, ComponentValue::U16(200));
+        assert!(matches!(&fields[1].1, ComponentValue::List(h) if !h.is_empty()));
+    }
+
+    #[test]
+    fn test_wasi_exit_records_status() {
+        let mut inst = wasi_instance();
+        let result = inst.call_import(
+            "wasi:cli/exit@0.2.0",
+            "exit",
+            &[ComponentValue::Result(Err(None))],
+        );
+        assert!(matches!(result, Err(ComponentError::Trap(_))));
+        assert_eq!(inst.context().exit_code(), Some(1));
+    }
 }
@@ -9,7 +9,7 @@ use alloc::vec::Vec;
 use spin::Mutex;
 
 use crate::executor::{self, ExecutorContext, HostFunction};
-use crate::instance::{Imports, Instance};
+use crate::instance::{Imports, Instance, Linker};
 use crate::interpreter::{TrapError, WasmValue};
 use crate::module::Module;
 use crate::parser::WasmParser;
@@ -74,7 +74,14 @@ impl Engine {
     }
 
     /// Parse and validate a WASM module from bytes.
+    ///
+    /// When caching is enabled the parsed module is served from
+    /// [`crate::module_cache`] on repeat loads of the same bytes.
     pub fn load_module(&self, wasm_bytes: &[u8]) -> Result<Module, RuntimeError> {
+        if self.cache_enabled {
+            let module = crate::module_cache::load(wasm_bytes)?;
+            return Ok((*module).clone());
+        }
         let module = Module::from_bytes(wasm_bytes)?;
         Ok(module)
     }
@@ -90,12 +97,30 @@ impl Engine {
         module: &Module,
         imports: Imports,
     ) -> Result<Instance, RuntimeError> {
-        let mut instance = Instance::new_with_imports(module, imports)?;
+        let instance = Instance::new_with_imports(module, imports)?;
+        self.apply_config(instance)
+    }
+
+    /// Instantiate a module with imports resolved by a [`Linker`].
+    pub fn instantiate_with_linker(
+        &self,
+        module: &Module,
+        linker: &Linker,
+    ) -> Result<Instance, RuntimeError> {
+        let instance = linker.instantiate(module)?;
+        self.apply_config(instance)
+    }
+
+    /// Apply engine-wide settings (fuel, call depth, memory64 size) to a
+    /// fresh instance.
+    fn apply_config(&self, mut instance: Instance) -> Result<Instance, RuntimeError> {
         if self.config.enable_fuel {
             instance.set_fuel(Some(self.config.initial_fuel));
         } else {
             instance.set_fuel(None);
         }
+        instance.set_max_call_depth(self.config.max_call_depth);
+        instance.set_max_memory_pages(self.config.max_memory_pages)?;
         Ok(instance)
     }
 
@@ -4,16 +4,19 @@
 //! `interpreter.rs`. This is the core execution loop that processes all
 //! ~200 WASM MVP instructions.
 
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
+use crate::externref::ExternRefTable;
 use crate::interpreter::{
     BlockFrame, BlockKind, CallFrame, GlobalValue, Table, TrapError, ValueStack, WasmValue,
-    MAX_CALL_STACK_DEPTH,
+    DEFAULT_MAX_CALL_DEPTH,
 };
-use crate::memory::LinearMemory;
-use crate::module::{ExportKind, FunctionType, ImportKind, Module, ValueType};
+use crate::jit::ProfileData;
+use crate::memory::{LinearMemory, MAX_MEMORY64_SIZE, PAGE_SIZE};
+use crate::module::{ExportKind, FunctionType, ImportKind, MemoryType, Module, ValueType};
 use crate::opcodes::Instruction;
 use crate::parser::BlockType;
 use crate::wasi::WasiCtx;
@@ -44,6 +47,12 @@ pub struct ExecutorContext {
     pub host_functions: Vec<Option<HostFunction>>,
     /// Fuel remaining (None = unlimited).
     pub fuel: Option<u64>,
+    /// Instructions executed so far, counted even when fuel is unlimited.
+    pub fuel_consumed: u64,
+    /// Largest total linear-memory size seen, in pages.
+    pub peak_memory_pages: u32,
+    /// Number of host function calls made.
+    pub host_calls: u64,
     /// stdout capture buffer.
     pub stdout: Vec<u8>,
     /// stderr capture buffer.
@@ -54,6 +63,19 @@ pub struct ExecutorContext {
     pub wasi_ctx: Option<WasiCtx>,
     /// WASI Preview 2 context for resource-based WASI P2 interfaces.
     pub wasi2_ctx: Option<crate::wasi2::Wasi2Ctx>,
+    /// Host objects referenced by `externref` values.
+    pub extern_refs: ExternRefTable,
+    /// Maximum number of nested WASM call frames.
+    pub max_call_depth: usize,
+    /// Frames below the innermost running [`execute_function`], counted
+    /// across host functions that call back into WASM.
+    pub call_depth: usize,
+    /// Function indices of the frames active at the last trap, innermost
+    /// first. Cleared when a new outermost call starts.
+    pub backtrace: Vec<u32>,
+    /// Calls and loop back-edges per local function, keyed by function
+    /// index, while profiling is enabled.
+    pub profiles: Option<BTreeMap<u32, ProfileData>>,
 }
 
 impl ExecutorContext {
@@ -95,29 +117,23 @@ impl ExecutorContext {
         // Check imports for memory
         for import in &module.imports {
             if let ImportKind::Memory(ref mem_type) = import.kind {
-                memories.push(
-                    LinearMemory::new(mem_type.min, mem_type.max)
-                        .map_err(|e| TrapError::ExecutionError(alloc::format!("{:?}", e)))?,
-                );
+                memories.push(create_memory(mem_type)?);
             }
         }
         // Module-defined memories
         for mem_type in &module.memories {
-            memories.push(
-                LinearMemory::new(mem_type.min, mem_type.max)
-                    .map_err(|e| TrapError::ExecutionError(alloc::format!("{:?}", e)))?,
-            );
+            memories.push(create_memory(mem_type)?);
         }
 
         // Initialize tables
         let mut tables = Vec::new();
         for import in &module.imports {
             if let ImportKind::Table(ref tt) = import.kind {
-                tables.push(Table::new(tt.min, tt.max));
+                tables.push(Table::with_element_type(tt.element_type, tt.min, tt.max));
             }
         }
         for tt in &module.tables {
-            tables.push(Table::new(tt.min, tt.max));
+            tables.push(Table::with_element_type(tt.element_type, tt.min, tt.max));
         }
 
         // Initialize globals
@@ -138,6 +154,7 @@ impl ExecutorContext {
             });
         }
 
+        let peak_memory_pages = memories.iter().map(|m| m.pages()).sum();
         let mut ctx = ExecutorContext {
             module,
             memories,
@@ -145,11 +162,19 @@ impl ExecutorContext {
             globals,
             host_functions,
             fuel: Some(10_000_000),
+            fuel_consumed: 0,
+            peak_memory_pages,
+            host_calls: 0,
             stdout: Vec::new(),
             stderr: Vec::new(),
             exit_code: None,
             wasi_ctx: None,
             wasi2_ctx: None,
+            extern_refs: ExternRefTable::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            call_depth: 0,
+            backtrace: Vec::new(),
+            profiles: None,
         };
 
         // Initialize data segments
@@ -213,7 +238,11 @@ impl ExecutorContext {
         Ok(())
     }
 
-    /// Initialize element segments into tables.
+    /// Initialize active element segments into tables.
+    ///
+    /// Each segment is bounds-checked before any slot is written. Active
+    /// segments are dropped afterwards, so only passive segments remain
+    /// available to `table.init`.
     fn init_element_segments(&mut self) -> Result<(), TrapError> {
         let elem_segs: Vec<_> = self.module.elements.clone();
         for seg in &elem_segs {
@@ -228,8 +257,38 @@ impl ExecutorContext {
                     .ok_or(TrapError::UndefinedElement {
                         index: seg.table_idx,
                     })?;
+            check_table_range(table, offset, seg.func_indices.len() as u32)?;
             for (i, &func_idx) in seg.func_indices.iter().enumerate() {
-                table.set(offset + i as u32, Some(func_idx))?;
+                table.set(offset + i as u32, func_idx)?;
+            }
+        }
+        for seg in self.module.elements.iter_mut().filter(|seg| !seg.passive) {
+            seg.func_indices.clear();
+        }
+        Ok(())
+    }
+
+    /// Store a reference in a table slot, keeping `externref` counts balanced.
+    pub fn table_set_ref(
+        &mut self,
+        table_idx: u32,
+        index: u32,
+        value: Option<u32>,
+    ) -> Result<(), TrapError> {
+        let table = self
+            .tables
+            .get_mut(table_idx as usize)
+            .ok_or(TrapError::UndefinedElement { index: table_idx })?;
+        let old = table.get(index)?;
+        table.set(index, value)?;
+
+        if table.is_extern() {
+            // Retain before release so re-storing the same handle is safe
+            if let Some(handle) = value {
+                self.extern_refs.retain(handle);
+            }
+            if let Some(handle) = old {
+                self.extern_refs.release(handle);
             }
         }
         Ok(())
@@ -244,6 +303,100 @@ impl ExecutorContext {
     pub fn is_host_function(&self, func_idx: u32) -> bool {
         (func_idx as usize) < self.module.import_function_count()
     }
+
+    /// Total size of all linear memories, in pages.
+    pub fn memory_pages(&self) -> u32 {
+        self.memories.iter().map(|m| m.pages()).sum()
+    }
+
+    /// Snapshot of the resources this context has used so far.
+    pub fn resource_usage(&self) -> ResourceUsage {
+        let memory_pages = self.memory_pages();
+        ResourceUsage {
+            memory_pages,
+            // Hosts can grow memory directly, so the current size may
+            // exceed the last recorded peak.
+            peak_memory_pages: self.peak_memory_pages.max(memory_pages),
+            fuel_consumed: self.fuel_consumed,
+            table_elements: self.tables.iter().map(|t| t.size() as usize).sum(),
+            host_calls: self.host_calls,
+        }
+    }
+
+    /// Start recording calls and loop iterations for the JIT's tiering
+    /// decisions.
+    pub fn enable_profiling(&mut self) {
+        self.profiles.get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Take the profiles recorded so far, leaving profiling enabled.
+    ///
+    /// Feed them to [`JitEngine::merge_profiles`](crate::jit::JitEngine::merge_profiles).
+    pub fn take_profiles(&mut self) -> BTreeMap<u32, ProfileData> {
+        self.profiles
+            .as_mut()
+            .map(core::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn record_call(&mut self, func_idx: u32) {
+        if let Some(profiles) = &mut self.profiles {
+            profiles.entry(func_idx).or_default().record_call();
+        }
+    }
+
+    fn record_back_edge(&mut self, func_idx: u32, loop_pc: usize) {
+        if let Some(profiles) = &mut self.profiles {
+            profiles
+                .entry(func_idx)
+                .or_default()
+                .record_back_edge(loop_pc as u32);
+        }
+    }
+
+    /// Describe the last trap's frames, one `  #N name` line each,
+    /// using debug names from the module where available.
+    pub fn format_backtrace(&self) -> String {
+        let mut out = String::new();
+        for (depth, &func_idx) in self.backtrace.iter().enumerate() {
+            out.push_str(&alloc::format!(
+                "  #{} {}\n",
+                depth,
+                self.module.function_label(func_idx)
+            ));
+        }
+        out
+    }
+}
+
+/// Resources consumed by an execution context.
+///
+/// Reported by [`ExecutorContext::resource_usage`] so callers can compare
+/// what a module actually used against its sandbox limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Current total linear-memory size, in pages.
+    pub memory_pages: u32,
+    /// Largest total linear-memory size reached, in pages.
+    pub peak_memory_pages: u32,
+    /// Instructions executed (one unit of fuel each).
+    pub fuel_consumed: u64,
+    /// Total number of elements across all tables.
+    pub table_elements: usize,
+    /// Number of host function calls made.
+    pub host_calls: u64,
+}
+
+impl ResourceUsage {
+    /// Current linear-memory size in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.memory_pages as usize * crate::memory::PAGE_SIZE
+    }
+
+    /// Peak linear-memory size in bytes.
+    pub fn peak_memory_bytes(&self) -> usize {
+        self.peak_memory_pages as usize * crate::memory::PAGE_SIZE
+    }
 }
 
 // ============================================================================
@@ -270,7 +423,21 @@ pub fn execute_export(
     execute_function(ctx, func_idx, args)
 }
 
+/// Check that `[start, start + len)` lies within `table`.
+fn check_table_range(table: &Table, start: u32, len: u32) -> Result<(), TrapError> {
+    match start.checked_add(len) {
+        Some(end) if end <= table.size() => Ok(()),
+        _ => Err(TrapError::UndefinedElement {
+            index: start.max(table.size()),
+        }),
+    }
+}
+
 /// Execute a WASM function by function index.
+///
+/// Calls are run on an explicit frame stack rather than by recursing, so
+/// guest recursion cannot overflow the host stack. Recursion deeper than
+/// `ctx.max_call_depth` traps with [`TrapError::CallStackExhausted`].
 pub fn execute_function(
     ctx: &mut ExecutorContext,
     func_idx: u32,
@@ -281,6 +448,28 @@ pub fn execute_function(
         return call_host_function(ctx, func_idx, args);
     }
 
+    // Host functions may call back in, so restore the depth on every exit
+    let base_depth = ctx.call_depth;
+    if base_depth == 0 {
+        ctx.backtrace.clear();
+    }
+    let result = run_function(ctx, func_idx, args, base_depth);
+    ctx.call_depth = base_depth;
+    result
+}
+
+/// Run a local function with `base_depth` frames already active.
+fn run_function(
+    ctx: &mut ExecutorContext,
+    func_idx: u32,
+    args: &[WasmValue],
+    base_depth: usize,
+) -> Result<Vec<WasmValue>, TrapError> {
+    if base_depth >= ctx.max_call_depth {
+        return Err(TrapError::CallStackExhausted);
+    }
+    ctx.record_call(func_idx);
+
     let func_type = ctx
         .func_type(func_idx)
         .ok_or(TrapError::FunctionNotFound(func_idx))?
@@ -345,7 +534,30 @@ pub fn execute_function(
     };
     call_stack.push(frame);
 
-    // Main execution loop
+    // Main execution loop, recording the active frames if it traps
+    if let Err(trap) = run_frames(ctx, &mut stack, &mut call_stack, base_depth) {
+        ctx.backtrace
+            .extend(call_stack.iter().rev().map(|frame| frame.func_idx));
+        return Err(trap);
+    }
+
+    // Collect final results
+    let mut results = Vec::new();
+    let n = return_arity.min(stack.len());
+    for _ in 0..n {
+        results.push(stack.pop()?);
+    }
+    results.reverse();
+    Ok(results)
+}
+
+/// Run frames until `call_stack` is empty, leaving results on `stack`.
+fn run_frames(
+    ctx: &mut ExecutorContext,
+    stack: &mut ValueStack,
+    call_stack: &mut Vec<CallFrame>,
+    base_depth: usize,
+) -> Result<(), TrapError> {
     loop {
         let frame = match call_stack.last_mut() {
             Some(f) => f,
@@ -365,7 +577,7 @@ pub fn execute_function(
             call_stack.pop();
 
             // Collect return values
-            let results = collect_results(&mut stack, arity, base);
+            let results = collect_results(stack, arity, base);
             stack.truncate(base);
             for r in results {
                 stack.push(r)?;
@@ -387,16 +599,17 @@ pub fn execute_function(
             }
             *fuel -= 1;
         }
+        ctx.fuel_consumed += 1;
 
         // Execute instruction
-        match execute_instruction(ctx, &mut stack, &mut call_stack, &instr)? {
+        match execute_instruction(ctx, stack, call_stack, &instr)? {
             ControlFlow::Continue => {}
             ControlFlow::Return => {
                 // Return from current function
                 let frame = call_stack.last().unwrap();
                 let arity = frame.return_arity;
                 let base = frame.stack_base;
-                let results = collect_results(&mut stack, arity, base);
+                let results = collect_results(stack, arity, base);
                 call_stack.pop();
                 if let Some(parent) = call_stack.last() {
                     stack.truncate(parent.stack_base + stack.len().saturating_sub(base));
@@ -410,16 +623,18 @@ pub fn execute_function(
                 }
             }
             ControlFlow::CallFunction(target_idx, target_args) => {
-                if call_stack.len() >= MAX_CALL_STACK_DEPTH {
-                    return Err(TrapError::CallStackOverflow);
+                if base_depth + call_stack.len() >= ctx.max_call_depth {
+                    return Err(TrapError::CallStackExhausted);
                 }
 
                 if ctx.is_host_function(target_idx) {
+                    ctx.call_depth = base_depth + call_stack.len();
                     let results = call_host_function(ctx, target_idx, &target_args)?;
                     for r in results {
                         stack.push(r)?;
                     }
                 } else {
+                    ctx.record_call(target_idx);
                     let ft = ctx
                         .func_type(target_idx)
                         .ok_or(TrapError::FunctionNotFound(target_idx))?
@@ -480,15 +695,55 @@ pub fn execute_function(
             }
         }
     }
+    Ok(())
+}
 
-    // Collect final results
-    let mut results = Vec::new();
-    let n = return_arity.min(stack.len());
-    for _ in 0..n {
-        results.push(stack.pop()?);
+/// Allocate a linear memory of the declared type.
+///
+/// A 64-bit memory's declared maximum is clamped to
+/// [`MAX_MEMORY64_SIZE`]; only its initial size has to fit.
+fn create_memory(mem_type: &MemoryType) -> Result<LinearMemory, TrapError> {
+    let pages = |n: u64| u32::try_from(n).unwrap_or(u32::MAX);
+    let memory = if mem_type.memory64 {
+        let limit = (MAX_MEMORY64_SIZE / PAGE_SIZE) as u64;
+        LinearMemory::new_64(
+            pages(mem_type.min),
+            mem_type.max.map(|max| pages(max.min(limit))),
+        )
+    } else {
+        LinearMemory::new(pages(mem_type.min), mem_type.max.map(pages))
+    };
+    memory.map_err(|e| TrapError::ExecutionError(alloc::format!("{:?}", e)))
+}
+
+/// Whether the default memory uses 64-bit addresses.
+fn memory_is_64(ctx: &ExecutorContext) -> bool {
+    ctx.memories.first().is_some_and(LinearMemory::is_64)
+}
+
+/// Pop a memory address operand: an i64 for 64-bit memories, an i32
+/// otherwise.
+fn pop_address(ctx: &ExecutorContext, stack: &mut ValueStack) -> Result<u64, TrapError> {
+    if memory_is_64(ctx) {
+        Ok(stack.pop_i64()? as u64)
+    } else {
+        Ok(stack.pop_i32()? as u32 as u64)
     }
-    results.reverse();
-    Ok(results)
+}
+
+/// Pop a load/store address and add the memarg `offset`.
+///
+/// An address past `usize::MAX` saturates, so the bounds check traps on it.
+fn effective_address(
+    ctx: &ExecutorContext,
+    stack: &mut ValueStack,
+    offset: u64,
+) -> Result<usize, TrapError> {
+    let base = pop_address(ctx, stack)?;
+    Ok(base
+        .checked_add(offset)
+        .and_then(|addr| usize::try_from(addr).ok())
+        .unwrap_or(usize::MAX))
 }
 
 /// Call a host function.
@@ -502,6 +757,7 @@ fn call_host_function(
         .get(func_idx as usize)
         .and_then(|h| h.clone())
         .ok_or(TrapError::FunctionNotFound(func_idx))?;
+    ctx.host_calls += 1;
     (hf.func)(ctx, args)
 }
 
@@ -646,13 +902,17 @@ fn execute_instruction(
         }
 
         Instruction::Br(label_idx) => {
-            branch(stack, frame, *label_idx)?;
+            if let Some(loop_pc) = branch(stack, frame, *label_idx)? {
+                ctx.record_back_edge(frame.func_idx, loop_pc);
+            }
         }
 
         Instruction::BrIf(label_idx) => {
             let cond = stack.pop_i32()?;
             if cond != 0 {
-                branch(stack, frame, *label_idx)?;
+                if let Some(loop_pc) = branch(stack, frame, *label_idx)? {
+                    ctx.record_back_edge(frame.func_idx, loop_pc);
+                }
             }
         }
 
@@ -663,7 +923,9 @@ fn execute_instruction(
             } else {
                 *default
             };
-            branch(stack, frame, label)?;
+            if let Some(loop_pc) = branch(stack, frame, label)? {
+                ctx.record_back_edge(frame.func_idx, loop_pc);
+            }
         }
 
         Instruction::Return => {
@@ -783,7 +1045,9 @@ fn execute_instruction(
         Instruction::GlobalSet(idx) => {
             let val = stack.pop()?;
             if let Some(g) = ctx.globals.get_mut(*idx as usize) {
-                g.value = val;
+                let old = core::mem::replace(&mut g.value, val);
+                ctx.extern_refs.retain_value(val);
+                ctx.extern_refs.release_value(old);
             }
         }
 
@@ -796,21 +1060,12 @@ fn execute_instruction(
                 .tables
                 .get(*table_idx as usize)
                 .ok_or(TrapError::UndefinedElement { index: *table_idx })?;
-            let val = table.get(idx)?;
-            stack.push(WasmValue::FuncRef(val))?;
+            stack.push(table.get_value(idx)?)?;
         }
         Instruction::TableSet(table_idx) => {
             let val = stack.pop()?;
             let idx = stack.pop_i32()? as u32;
-            let func_ref = match val {
-                WasmValue::FuncRef(v) => v,
-                _ => None,
-            };
-            let table = ctx
-                .tables
-                .get_mut(*table_idx as usize)
-                .ok_or(TrapError::UndefinedElement { index: *table_idx })?;
-            table.set(idx, func_ref)?;
+            ctx.table_set_ref(*table_idx, idx, ref_handle(val))?;
         }
         Instruction::TableSize(table_idx) => {
             let table = ctx
@@ -822,21 +1077,28 @@ fn execute_instruction(
         Instruction::TableGrow(table_idx) => {
             let n = stack.pop_i32()? as u32;
             let init = stack.pop()?;
-            let init_ref = match init {
-                WasmValue::FuncRef(v) => v,
-                _ => None,
-            };
+            let init_ref = ref_handle(init);
             let table = ctx
                 .tables
                 .get_mut(*table_idx as usize)
                 .ok_or(TrapError::UndefinedElement { index: *table_idx })?;
+            let is_extern = table.is_extern();
             match table.grow(n, init_ref) {
-                Ok(old) => stack.push(WasmValue::I32(old as i32))?,
+                Ok(old) => {
+                    // Each new slot holds its own reference
+                    if let (true, Some(handle)) = (is_extern, init_ref) {
+                        for _ in 0..n {
+                            ctx.extern_refs.retain(handle);
+                        }
+                    }
+                    stack.push(WasmValue::I32(old as i32))?
+                }
                 Err(_) => stack.push(WasmValue::I32(-1))?,
             }
         }
         Instruction::TableInit(elem_idx, table_idx) => {
-            // table.init: copy elements from passive element segment to table
+            // table.init: copy elements from passive element segment to table.
+            // Both ranges are checked before any slot is written.
             let n = stack.pop_i32()? as u32;   // count
             let s = stack.pop_i32()? as u32;   // source offset in element segment
             let d = stack.pop_i32()? as u32;   // destination offset in table
@@ -853,9 +1115,9 @@ fn execute_instruction(
                 .tables
                 .get_mut(*table_idx as usize)
                 .ok_or(TrapError::UndefinedElement { index: *table_idx })?;
+            check_table_range(table, d, n)?;
             for i in 0..n {
-                let func_idx = elem.func_indices[(s + i) as usize];
-                table.set(d + i, Some(func_idx))?;
+                table.set(d + i, elem.func_indices[(s + i) as usize])?;
             }
         }
         Instruction::ElemDrop(elem_idx) => {
@@ -868,25 +1130,38 @@ fn execute_instruction(
             }
         }
         Instruction::TableCopy(dst_table, src_table) => {
-            // table.copy: copy entries between tables (or within one table)
+            // table.copy: copy entries between tables (or within one table).
+            // Both ranges are checked before any slot is written.
             let n = stack.pop_i32()? as u32;
             let s = stack.pop_i32()? as u32;
             let d = stack.pop_i32()? as u32;
+            check_table_range(
+                ctx.tables
+                    .get(*src_table as usize)
+                    .ok_or(TrapError::UndefinedElement { index: *src_table })?,
+                s,
+                n,
+            )?;
+            check_table_range(
+                ctx.tables
+                    .get(*dst_table as usize)
+                    .ok_or(TrapError::UndefinedElement { index: *dst_table })?,
+                d,
+                n,
+            )?;
             if *dst_table == *src_table {
-                // Overlapping copy within the same table
-                let table = ctx
-                    .tables
-                    .get_mut(*dst_table as usize)
-                    .ok_or(TrapError::UndefinedElement { index: *dst_table })?;
+                // Overlapping copy within the same table (memmove order)
+                let copy_one = |ctx: &mut ExecutorContext, i: u32| -> Result<(), TrapError> {
+                    let val = ctx.tables[*dst_table as usize].get(s + i)?;
+                    ctx.table_set_ref(*dst_table, d + i, val)
+                };
                 if d <= s {
                     for i in 0..n {
-                        let val = table.get(s + i)?;
-                        table.set(d + i, val)?;
+                        copy_one(ctx, i)?;
                     }
                 } else {
                     for i in (0..n).rev() {
-                        let val = table.get(s + i)?;
-                        table.set(d + i, val)?;
+                        copy_one(ctx, i)?;
                     }
                 }
             } else {
@@ -899,12 +1174,8 @@ fn execute_instruction(
                 for i in 0..n {
                     entries.push(src.get(s + i)?);
                 }
-                let dst = ctx
-                    .tables
-                    .get_mut(*dst_table as usize)
-                    .ok_or(TrapError::UndefinedElement { index: *dst_table })?;
                 for (i, val) in entries.into_iter().enumerate() {
-                    dst.set(d + i as u32, val)?;
+                    ctx.table_set_ref(*dst_table, d + i as u32, val)?;
                 }
             }
         }
@@ -913,16 +1184,9 @@ fn execute_instruction(
             let n = stack.pop_i32()? as u32;
             let val = stack.pop()?;
             let d = stack.pop_i32()? as u32;
-            let func_ref = match val {
-                WasmValue::FuncRef(v) => v,
-                _ => None,
-            };
-            let table = ctx
-                .tables
-                .get_mut(*table_idx as usize)
-                .ok_or(TrapError::UndefinedElement { index: *table_idx })?;
+            let r = ref_handle(val);
             for i in 0..n {
-                table.set(d + i, func_ref)?;
+                ctx.table_set_ref(*table_idx, d + i, r)?;
             }
         }
 
@@ -930,8 +1194,7 @@ fn execute_instruction(
         // Memory Load
         // ====================================================================
         Instruction::I32Load(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 4,
@@ -947,8 +1210,7 @@ fn execute_instruction(
             stack.push(WasmValue::I32(val as i32))?;
         }
         Instruction::I64Load(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 8,
@@ -964,8 +1226,7 @@ fn execute_instruction(
             stack.push(WasmValue::I64(val as i64))?;
         }
         Instruction::F32Load(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 4,
@@ -981,8 +1242,7 @@ fn execute_instruction(
             stack.push(WasmValue::F32(f32::from_bits(bits)))?;
         }
         Instruction::F64Load(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 8,
@@ -1000,8 +1260,7 @@ fn execute_instruction(
 
         // i32 partial loads
         Instruction::I32Load8S(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 1,
@@ -1017,8 +1276,7 @@ fn execute_instruction(
             stack.push(WasmValue::I32(val as i8 as i32))?;
         }
         Instruction::I32Load8U(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 1,
@@ -1034,8 +1292,7 @@ fn execute_instruction(
             stack.push(WasmValue::I32(val as i32))?;
         }
         Instruction::I32Load16S(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 2,
@@ -1051,8 +1308,7 @@ fn execute_instruction(
             stack.push(WasmValue::I32(val as i16 as i32))?;
         }
         Instruction::I32Load16U(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 2,
@@ -1070,8 +1326,7 @@ fn execute_instruction(
 
         // i64 partial loads
         Instruction::I64Load8S(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 1,
@@ -1087,8 +1342,7 @@ fn execute_instruction(
             stack.push(WasmValue::I64(val as i8 as i64))?;
         }
         Instruction::I64Load8U(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 1,
@@ -1104,8 +1358,7 @@ fn execute_instruction(
             stack.push(WasmValue::I64(val as i64))?;
         }
         Instruction::I64Load16S(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 2,
@@ -1121,8 +1374,7 @@ fn execute_instruction(
             stack.push(WasmValue::I64(val as i16 as i64))?;
         }
         Instruction::I64Load16U(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 2,
@@ -1138,8 +1390,7 @@ fn execute_instruction(
             stack.push(WasmValue::I64(val as i64))?;
         }
         Instruction::I64Load32S(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 4,
@@ -1155,8 +1406,7 @@ fn execute_instruction(
             stack.push(WasmValue::I64(val as i32 as i64))?;
         }
         Instruction::I64Load32U(_, offset) => {
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx.memories.first().ok_or(TrapError::MemoryOutOfBounds {
                 offset: addr,
                 size: 4,
@@ -1177,8 +1427,7 @@ fn execute_instruction(
         // ====================================================================
         Instruction::I32Store(_, offset) => {
             let val = stack.pop_i32()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1196,8 +1445,7 @@ fn execute_instruction(
         }
         Instruction::I64Store(_, offset) => {
             let val = stack.pop_i64()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1215,8 +1463,7 @@ fn execute_instruction(
         }
         Instruction::F32Store(_, offset) => {
             let val = stack.pop_f32()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1234,8 +1481,7 @@ fn execute_instruction(
         }
         Instruction::F64Store(_, offset) => {
             let val = stack.pop_f64()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1255,8 +1501,7 @@ fn execute_instruction(
         // Partial stores
         Instruction::I32Store8(_, offset) => {
             let val = stack.pop_i32()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1274,8 +1519,7 @@ fn execute_instruction(
         }
         Instruction::I32Store16(_, offset) => {
             let val = stack.pop_i32()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1293,8 +1537,7 @@ fn execute_instruction(
         }
         Instruction::I64Store8(_, offset) => {
             let val = stack.pop_i64()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1312,8 +1555,7 @@ fn execute_instruction(
         }
         Instruction::I64Store16(_, offset) => {
             let val = stack.pop_i64()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1331,8 +1573,7 @@ fn execute_instruction(
         }
         Instruction::I64Store32(_, offset) => {
             let val = stack.pop_i64()?;
-            let base = stack.pop_i32()? as u32;
-            let addr = (base as u64 + *offset as u64) as usize;
+            let addr = effective_address(ctx, stack, *offset)?;
             let mem = ctx
                 .memories
                 .first_mut()
@@ -1354,24 +1595,35 @@ fn execute_instruction(
         // ====================================================================
         Instruction::MemorySize => {
             let pages = ctx.memories.first().map(|m| m.pages()).unwrap_or(0);
-            stack.push(WasmValue::I32(pages as i32))?;
+            if memory_is_64(ctx) {
+                stack.push(WasmValue::I64(pages as i64))?;
+            } else {
+                stack.push(WasmValue::I32(pages as i32))?;
+            }
         }
         Instruction::MemoryGrow => {
-            let delta = stack.pop_i32()? as u32;
-            if let Some(mem) = ctx.memories.first_mut() {
-                match mem.grow(delta) {
-                    Ok(old_pages) => stack.push(WasmValue::I32(old_pages as i32))?,
-                    Err(_) => stack.push(WasmValue::I32(-1))?,
-                }
+            let memory64 = memory_is_64(ctx);
+            let delta = pop_address(ctx, stack)?;
+            let grown = match (ctx.memories.first_mut(), u32::try_from(delta)) {
+                (Some(mem), Ok(delta)) => mem.grow(delta).ok(),
+                _ => None,
+            };
+            if grown.is_some() {
+                ctx.peak_memory_pages = ctx.peak_memory_pages.max(ctx.memory_pages());
+            }
+            // Previous size in pages, or -1 if the memory could not grow.
+            let result = grown.map_or(-1, i64::from);
+            if memory64 {
+                stack.push(WasmValue::I64(result))?;
             } else {
-                stack.push(WasmValue::I32(-1))?;
+                stack.push(WasmValue::I32(result as i32))?;
             }
         }
         Instruction::MemoryInit(data_idx) => {
             // memory.init: copy data from passive data segment into memory
             let n = stack.pop_i32()? as u32;     // byte count
             let s = stack.pop_i32()? as u32;     // source offset in data segment
-            let d = stack.pop_i32()? as u32;     // destination offset in memory
+            let d = pop_address(ctx, stack)?;   // destination offset in memory
             let seg = ctx.module.data.get(*data_idx as usize).ok_or(
                 TrapError::ExecutionError(String::from("data segment index OOB")),
             )?;
@@ -1411,9 +1663,9 @@ fn execute_instruction(
         }
         Instruction::MemoryCopy => {
             // memory.copy: copy bytes within the same memory (overlapping safe)
-            let n = stack.pop_i32()? as usize;   // byte count
-            let s = stack.pop_i32()? as usize;   // source offset
-            let d = stack.pop_i32()? as usize;   // destination offset
+            let n = pop_address(ctx, stack)? as usize; // byte count
+            let s = pop_address(ctx, stack)? as usize; // source offset
+            let d = pop_address(ctx, stack)? as usize; // destination offset
             let mem = ctx.memories.first_mut().ok_or(TrapError::MemoryOutOfBounds {
                 offset: d,
                 size: n,
@@ -1438,9 +1690,9 @@ fn execute_instruction(
         }
         Instruction::MemoryFill => {
             // memory.fill: fill a memory region with a byte value
-            let n = stack.pop_i32()? as usize;   // byte count
-            let val = stack.pop_i32()? as u8;     // fill value
-            let d = stack.pop_i32()? as usize;    // destination offset
+            let n = pop_address(ctx, stack)? as usize; // byte count
+            let val = stack.pop_i32()? as u8;          // fill value
+            let d = pop_address(ctx, stack)? as usize; // destination offset
             let mem = ctx.memories.first_mut().ok_or(TrapError::MemoryOutOfBounds {
                 offset: d,
                 size: n,
@@ -2239,8 +2491,22 @@ fn get_current_instructions<'a>(ctx: &'a ExecutorContext, frame: &CallFrame) ->
     &ctx.module.code[local_idx].instructions
 }
 
-/// Branch to a label by depth index.
-fn branch(stack: &mut ValueStack, frame: &mut CallFrame, label_idx: u32) -> Result<(), TrapError> {
+/// Extract the handle of a reference value (`None` for null or non-references).
+fn ref_handle(value: WasmValue) -> Option<u32> {
+    match value {
+        WasmValue::FuncRef(r) | WasmValue::ExternRef(r) => r,
+        _ => None,
+    }
+}
+
+/// Branch to the label `label_idx` blocks out.
+///
+/// Returns the loop header's instruction index if this was a back-edge.
+fn branch(
+    stack: &mut ValueStack,
+    frame: &mut CallFrame,
+    label_idx: u32,
+) -> Result<Option<usize>, TrapError> {
     let block_idx = frame.block_stack.len() - 1 - label_idx as usize;
     let target_block = &frame.block_stack[block_idx];
 
@@ -2255,6 +2521,7 @@ fn branch(stack: &mut ValueStack, frame: &mut CallFrame, label_idx: u32) -> Resu
             stack.push(r)?;
         }
         frame.pc = target_block.start_pc + 1; // right after the Loop instruction
+        Ok(Some(target_block.start_pc))
     } else {
         // Branch to block end
         let results = collect_results(stack, arity, base);
@@ -2266,9 +2533,8 @@ fn branch(stack: &mut ValueStack, frame: &mut CallFrame, label_idx: u32) -> Resu
         // Pop all blocks up to and including the target
         frame.block_stack.truncate(block_idx);
         frame.pc = end_pc + 1;
+        Ok(None)
     }
-
-    Ok(())
 }
 
 /// Find the matching `end` for a block/loop/if at the given pc.
@@ -2546,10 +2812,10 @@ fn f64_sqrt(a: f64) -> f64 {
 }
 
 // ============================================================================
-// Saturating Truncation Helpers
+// Saturating Truncation Helpers (shared with the JIT IR interpreter)
 // ============================================================================
 
-fn trunc_sat_f32_i32(a: f32) -> i32 {
+pub(crate) fn trunc_sat_f32_i32(a: f32) -> i32 {
     if a.is_nan() {
         return 0;
     }
@@ -2562,7 +2828,7 @@ fn trunc_sat_f32_i32(a: f32) -> i32 {
     a as i32
 }
 
-fn trunc_sat_f32_u32(a: f32) -> u32 {
+pub(crate) fn trunc_sat_f32_u32(a: f32) -> u32 {
     if a.is_nan() {
         return 0;
     }
@@ -2575,7 +2841,7 @@ fn trunc_sat_f32_u32(a: f32) -> u32 {
     a as u32
 }
 
-fn trunc_sat_f64_i32(a: f64) -> i32 {
+pub(crate) fn trunc_sat_f64_i32(a: f64) -> i32 {
     if a.is_nan() {
         return 0;
     }
@@ -2588,7 +2854,7 @@ fn trunc_sat_f64_i32(a: f64) -> i32 {
     a as i32
 }
 
-fn trunc_sat_f64_u32(a: f64) -> u32 {
+pub(crate) fn trunc_sat_f64_u32(a: f64) -> u32 {
     if a.is_nan() {
         return 0;
     }
@@ -2601,7 +2867,7 @@ fn trunc_sat_f64_u32(a: f64) -> u32 {
     a as u32
 }
 
-fn trunc_sat_f32_i64(a: f32) -> i64 {
+pub(crate) fn trunc_sat_f32_i64(a: f32) -> i64 {
     if a.is_nan() {
         return 0;
     }
@@ -2614,7 +2880,7 @@ fn trunc_sat_f32_i64(a: f32) -> i64 {
     a as i64
 }
 
-fn trunc_sat_f32_u64(a: f32) -> u64 {
+pub(crate) fn trunc_sat_f32_u64(a: f32) -> u64 {
     if a.is_nan() {
         return 0;
     }
@@ -2627,7 +2893,7 @@ fn trunc_sat_f32_u64(a: f32) -> u64 {
     a as u64
 }
 
-fn trunc_sat_f64_i64(a: f64) -> i64 {
+pub(crate) fn trunc_sat_f64_i64(a: f64) -> i64 {
     if a.is_nan() {
         return 0;
     }
@@ -2640,7 +2906,7 @@ fn trunc_sat_f64_i64(a: f64) -> i64 {
     a as i64
 }
 
-fn trunc_sat_f64_u64(a: f64) -> u64 {
+pub(crate) fn trunc_sat_f64_u64(a: f64) -> u64 {
     if a.is_nan() {
         return 0;
     }
@@ -2698,6 +2964,7 @@ mod tests {
             }],
             data: vec![],
             name: None,
+            names: Default::default(),
             data_count: None,
         }
     }
@@ -2714,9 +2981,10 @@ mod tests {
     ) -> Module {
         let mut m = make_module(params, results, locals, instructions, export_name);
         m.memories.push(crate::module::MemoryType {
-            min: mem_min,
-            max: mem_max,
+            min: mem_min.into(),
+            max: mem_max.map(Into::into),
             shared: false,
+            memory64: false,
         });
         m
     }
@@ -3028,6 +3296,90 @@ mod tests {
         assert_eq!(result[0].as_i32(), Some(6765));
     }
 
+    #[test]
+    fn test_call_depth_limit() {
+        // depth(n) = if n == 0 then 0 else depth(n - 1)
+        let module = make_module(
+            vec![ValueType::I32],
+            vec![ValueType::I32],
+            vec![],
+            vec![
+                LocalGet(0),
+                I32Eqz,
+                If(BlockType::Value(ValueType::I32)),
+                I32Const(0),
+                Else,
+                LocalGet(0),
+                I32Const(1),
+                I32Sub,
+                Call(0),
+                End,
+                End,
+            ],
+            "depth",
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        ctx.max_call_depth = 10;
+
+        // n + 1 frames
+        let result = execute_export(&mut ctx, "depth", &[WasmValue::I32(9)]).unwrap();
+        assert_eq!(result[0].as_i32(), Some(0));
+        assert!(matches!(
+            execute_export(&mut ctx, "depth", &[WasmValue::I32(10)]),
+            Err(TrapError::CallStackExhausted)
+        ));
+        assert_eq!(ctx.call_depth, 0);
+
+        // Unbounded recursion traps at the default limit
+        ctx.max_call_depth = DEFAULT_MAX_CALL_DEPTH;
+        assert!(matches!(
+            execute_export(&mut ctx, "depth", &[WasmValue::I32(-1)]),
+            Err(TrapError::CallStackExhausted)
+        ));
+        let result = execute_export(&mut ctx, "depth", &[WasmValue::I32(500)]).unwrap();
+        assert_eq!(result[0].as_i32(), Some(0));
+    }
+
+    #[test]
+    fn test_trap_backtrace_uses_debug_names() {
+        // countdown(n) = if n == 0 then unreachable else countdown(n - 1)
+        let mut module = make_module(
+            vec![ValueType::I32],
+            vec![ValueType::I32],
+            vec![],
+            vec![
+                LocalGet(0),
+                I32Eqz,
+                If(BlockType::Value(ValueType::I32)),
+                Unreachable,
+                Else,
+                LocalGet(0),
+                I32Const(1),
+                I32Sub,
+                Call(0),
+                End,
+                End,
+            ],
+            "run",
+        );
+        module.names.functions.insert(0, String::from("countdown"));
+        let mut ctx = ExecutorContext::new(module).unwrap();
+
+        assert!(matches!(
+            execute_export(&mut ctx, "run", &[WasmValue::I32(2)]),
+            Err(TrapError::Unreachable)
+        ));
+        assert_eq!(ctx.backtrace, vec![0, 0, 0]);
+        assert_eq!(
+            ctx.format_backtrace(),
+            "  #0 countdown\n  #1 countdown\n  #2 countdown\n"
+        );
+
+        // Each outermost call starts a fresh backtrace
+        let _ = execute_export(&mut ctx, "run", &[WasmValue::I32(0)]);
+        assert_eq!(ctx.backtrace, vec![0]);
+    }
+
     // Factorial (iterative with loop)
     #[test]
     fn test_factorial_iterative() {
@@ -3083,6 +3435,46 @@ mod tests {
         assert_eq!(result[0].as_i32(), Some(3628800));
     }
 
+    #[test]
+    fn test_profiling_counts_back_edges() {
+        // spin(n): while n != 0 { n -= 1 }
+        let module = make_module(
+            vec![ValueType::I32],
+            vec![],
+            vec![],
+            vec![
+                Block(BlockType::Empty),
+                Loop(BlockType::Empty),
+                LocalGet(0),
+                I32Eqz,
+                BrIf(1),
+                LocalGet(0),
+                I32Const(1),
+                I32Sub,
+                LocalSet(0),
+                Br(0),
+                End,
+                End,
+                End,
+            ],
+            "spin",
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+
+        execute_export(&mut ctx, "spin", &[WasmValue::I32(3)]).unwrap();
+        assert!(ctx.take_profiles().is_empty());
+
+        ctx.enable_profiling();
+        execute_export(&mut ctx, "spin", &[WasmValue::I32(500)]).unwrap();
+        execute_export(&mut ctx, "spin", &[WasmValue::I32(0)]).unwrap();
+        let profiles = ctx.take_profiles();
+        let profile = &profiles[&0];
+        assert_eq!(profile.call_count, 2);
+        assert_eq!(profile.loop_iterations, 500);
+        assert_eq!(profile.hottest_loop(), Some((1, 500)));
+        assert!(ctx.take_profiles().is_empty());
+    }
+
     // B-QG5: memory.grow + load/store
     #[test]
     fn test_memory_store_load() {
@@ -3142,6 +3534,72 @@ mod tests {
         assert_eq!(result[0].as_i32(), Some(3));
     }
 
+    fn make_module_with_memory64(
+        instructions: Vec<crate::opcodes::Instruction>,
+        mem_min: u64,
+    ) -> Module {
+        let mut m = make_module(vec![], vec![ValueType::I64], vec![], instructions, "run");
+        m.memories.push(crate::module::MemoryType {
+            min: mem_min,
+            max: None,
+            shared: false,
+            memory64: true,
+        });
+        m
+    }
+
+    #[test]
+    fn test_memory64_addresses_beyond_4gb() {
+        const ADDR: i64 = 0x1_0000_0008;
+        let module = make_module_with_memory64(
+            vec![
+                I64Const(ADDR),
+                I64Const(0x0123_4567_89AB_CDEF),
+                I64Store(0, 0),
+                // Same address, reached through the memarg offset
+                I64Const(8),
+                I64Load(0, 0x1_0000_0000),
+                End,
+            ],
+            65537, // 4GB + 1 page
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        let result = execute_export(&mut ctx, "run", &[]).unwrap();
+        assert_eq!(result[0].as_i64(), Some(0x0123_4567_89AB_CDEF));
+        assert_eq!(ctx.memories[0].size(), 65537 * crate::memory::PAGE_SIZE);
+
+        let module = make_module_with_memory64(vec![I64Const(-8), I64Load(0, 0), End], 65537);
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        let result = execute_export(&mut ctx, "run", &[]);
+        assert!(matches!(result, Err(TrapError::MemoryOutOfBounds { .. })));
+    }
+
+    #[test]
+    fn test_memory64_size_and_grow() {
+        let module = make_module_with_memory64(
+            vec![
+                I64Const(1),
+                MemoryGrow, // 1
+                Drop,
+                I64Const(1 << 32),
+                MemoryGrow, // -1: more pages than fit in a u32
+                Drop,
+                I64Const(4),
+                MemoryGrow, // -1: past the page limit
+                MemorySize,
+                I64Add,
+                End,
+            ],
+            1,
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        assert!(ctx.memories[0].is_64());
+        ctx.memories[0].limit_pages(4).unwrap();
+        let result = execute_export(&mut ctx, "run", &[]).unwrap();
+        // -1 from the last grow plus the final size of 2 pages
+        assert_eq!(result[0].as_i64(), Some(1));
+    }
+
     // B-QG6: Traps
     #[test]
     fn test_trap_division_by_zero() {
@@ -3227,7 +3685,7 @@ mod tests {
             elements: vec![crate::module::Element {
                 table_idx: 0,
                 offset_expr: vec![I32Const(0)],
-                func_indices: vec![0, 1], // table[0]=add, table[1]=mul
+                func_indices: vec![Some(0), Some(1)], // table[0]=add, table[1]=mul
                 passive: false,
             }],
             code: vec![
@@ -3258,6 +3716,7 @@ mod tests {
             ],
             data: vec![],
             name: None,
+            names: Default::default(),
             data_count: None,
         };
 
@@ -3282,6 +3741,123 @@ mod tests {
         assert_eq!(result[0].as_i32(), Some(21));
     }
 
+    /// Module with a 4-slot funcref table, the given element segments and
+    /// an exported `run` function executing `instructions`.
+    fn make_table_module(
+        elements: Vec<crate::module::Element>,
+        instructions: Vec<crate::opcodes::Instruction>,
+    ) -> Module {
+        let mut module = make_module(vec![], vec![], vec![], instructions, "run");
+        module.tables.push(crate::module::TableType {
+            element_type: ValueType::FuncRef,
+            min: 4,
+            max: None,
+        });
+        module.elements = elements;
+        module
+    }
+
+    fn active_segment(offset: i32, func_indices: Vec<Option<u32>>) -> crate::module::Element {
+        crate::module::Element {
+            table_idx: 0,
+            offset_expr: vec![I32Const(offset)],
+            func_indices,
+            passive: false,
+        }
+    }
+
+    fn passive_segment(func_indices: Vec<Option<u32>>) -> crate::module::Element {
+        crate::module::Element {
+            table_idx: 0,
+            offset_expr: vec![],
+            func_indices,
+            passive: true,
+        }
+    }
+
+    #[test]
+    fn test_table_init_from_passive_segment() {
+        let module = make_table_module(
+            vec![
+                active_segment(0, vec![Some(7)]),
+                passive_segment(vec![Some(1), None, Some(2)]),
+            ],
+            vec![I32Const(1), I32Const(0), I32Const(3), TableInit(1, 0), End],
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        // Passive segments are not applied at instantiation
+        assert_eq!(ctx.tables[0].elements, vec![Some(7), None, None, None]);
+        // Active segments are dropped once applied
+        assert!(ctx.module.elements[0].func_indices.is_empty());
+
+        execute_export(&mut ctx, "run", &[]).unwrap();
+        assert_eq!(ctx.tables[0].elements, vec![Some(7), Some(1), None, Some(2)]);
+    }
+
+    #[test]
+    fn test_table_init_bounds_checked_before_write() {
+        let module = make_table_module(
+            vec![passive_segment(vec![Some(1), Some(2), Some(3)])],
+            vec![I32Const(2), I32Const(0), I32Const(3), TableInit(0, 0), End],
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        assert!(execute_export(&mut ctx, "run", &[]).is_err());
+        assert_eq!(ctx.tables[0].elements, vec![None; 4]);
+    }
+
+    #[test]
+    fn test_table_init_after_elem_drop() {
+        let mut module = make_table_module(
+            vec![passive_segment(vec![Some(1)])],
+            vec![
+                ElemDrop(0),
+                I32Const(0),
+                I32Const(0),
+                LocalGet(0),
+                TableInit(0, 0),
+                End,
+            ],
+        );
+        module.types[0].params = vec![ValueType::I32];
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        // A dropped segment behaves as empty: zero-length init succeeds
+        execute_export(&mut ctx, "run", &[WasmValue::I32(0)]).unwrap();
+        assert!(execute_export(&mut ctx, "run", &[WasmValue::I32(1)]).is_err());
+        assert_eq!(ctx.tables[0].elements, vec![None; 4]);
+    }
+
+    #[test]
+    fn test_table_copy_overlapping() {
+        let init = || active_segment(0, vec![Some(0), Some(1), Some(2), None]);
+
+        // Forward overlap: dst > src must copy back-to-front
+        let module = make_table_module(
+            vec![init()],
+            vec![I32Const(1), I32Const(0), I32Const(3), TableCopy(0, 0), End],
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        execute_export(&mut ctx, "run", &[]).unwrap();
+        assert_eq!(ctx.tables[0].elements, vec![Some(0), Some(0), Some(1), Some(2)]);
+
+        // Backward overlap: dst < src
+        let module = make_table_module(
+            vec![init()],
+            vec![I32Const(0), I32Const(1), I32Const(3), TableCopy(0, 0), End],
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        execute_export(&mut ctx, "run", &[]).unwrap();
+        assert_eq!(ctx.tables[0].elements, vec![Some(1), Some(2), None, None]);
+
+        // Out-of-bounds copy traps without touching the table
+        let module = make_table_module(
+            vec![init()],
+            vec![I32Const(2), I32Const(0), I32Const(3), TableCopy(0, 0), End],
+        );
+        let mut ctx = ExecutorContext::new(module).unwrap();
+        assert!(execute_export(&mut ctx, "run", &[]).is_err());
+        assert_eq!(ctx.tables[0].elements, vec![Some(0), Some(1), Some(2), None]);
+    }
+
     // Additional comparison tests
     #[test]
     fn test_i32_comparisons() {
@@ -3326,6 +3902,59 @@ mod tests {
         assert_eq!(result[0].as_i64(), Some(-1i64));
     }
 
+    #[test]
+    fn test_trunc_sat_reference_values() {
+        let f32 = WasmValue::F32;
+        let f64 = WasmValue::F64;
+        // Reference values from the spec's conversions.wast
+        let cases = [
+            (I32TruncSatF32S, f32(f32::NAN), 0),
+            (I32TruncSatF32S, f32(-1.5), -1),
+            (I32TruncSatF32S, f32(2147483648.0), i32::MAX as i64),
+            (I32TruncSatF32S, f32(-2147483904.0), i32::MIN as i64),
+            (I32TruncSatF32U, f32(4294967040.0), -256),
+            (I32TruncSatF32U, f32(f32::NEG_INFINITY), 0),
+            (I32TruncSatF64S, f64(f64::INFINITY), i32::MAX as i64),
+            (I32TruncSatF64S, f64(-2147483649.0), i32::MIN as i64),
+            (I32TruncSatF64U, f64(1e16), -1),
+            (I32TruncSatF64U, f64(-0.9), 0),
+            (I64TruncSatF32S, f32(-f32::NAN), 0),
+            (I64TruncSatF32S, f32(9223372036854775808.0), i64::MAX),
+            (I64TruncSatF32U, f32(18446742974197923840.0), -1099511627776),
+            (I64TruncSatF32U, f32(-1.0), 0),
+            (I64TruncSatF64S, f64(f64::NEG_INFINITY), i64::MIN),
+            (I64TruncSatF64S, f64(9223372036854774784.0), 9223372036854774784),
+            (I64TruncSatF64U, f64(18446744073709551616.0), -1),
+            (I64TruncSatF64U, f64(18446744073709549568.0), -2048),
+        ];
+        for (op, arg, expected) in cases {
+            let param = match arg {
+                WasmValue::F32(_) => ValueType::F32,
+                _ => ValueType::F64,
+            };
+            let wide = matches!(
+                op,
+                I64TruncSatF32S | I64TruncSatF32U | I64TruncSatF64S | I64TruncSatF64U
+            );
+            let result = if wide { ValueType::I64 } else { ValueType::I32 };
+            let module = make_module(
+                vec![param],
+                vec![result],
+                vec![],
+                vec![LocalGet(0), op.clone(), End],
+                "trunc",
+            );
+            let mut ctx = ExecutorContext::new(module).unwrap();
+            let value = execute_export(&mut ctx, "trunc", &[arg]).unwrap()[0];
+            let actual = if wide {
+                value.as_i64()
+            } else {
+                value.as_i32().map(i64::from)
+            };
+            assert_eq!(actual, Some(expected), "{:?}({:?})", op, arg);
+        }
+    }
+
     // Select test
     #[test]
     fn test_select() {
@@ -3402,4 +4031,71 @@ mod tests {
             Err(TrapError::IntegerOverflow)
         ));
     }
+
+    /// Module exporting `store(idx: i32, v: externref)` into a 2-slot externref table.
+    fn make_externref_module() -> Module {
+        let mut m = make_module(
+            vec![ValueType::I32, ValueType::ExternRef],
+            vec![],
+            vec![],
+            vec![LocalGet(0), LocalGet(1), TableSet(0), End],
+            "store",
+        );
+        m.tables.push(crate::module::TableType {
+            element_type: ValueType::ExternRef,
+            min: 2,
+            max: None,
+        });
+        m
+    }
+
+    /// Host object that counts its destructor runs.
+    struct Tracked(alloc::rc::Rc<core::cell::Cell<u32>>);
+
+    impl core::ops::Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_externref_table_set_releases_host_object() {
+        let drops = alloc::rc::Rc::new(core::cell::Cell::new(0));
+        let mut ctx = ExecutorContext::new(make_externref_module()).unwrap();
+
+        let v = ctx.extern_refs.lift(Tracked(drops.clone()));
+        let WasmValue::ExternRef(Some(handle)) = v else {
+            panic!("expected externref");
+        };
+        execute_export(&mut ctx, "store", &[WasmValue::I32(0), v]).unwrap();
+        execute_export(&mut ctx, "store", &[WasmValue::I32(1), v]).unwrap();
+        ctx.extern_refs.release(handle);
+        assert_eq!(ctx.extern_refs.refcount(handle), 2);
+
+        let null = WasmValue::ExternRef(None);
+        execute_export(&mut ctx, "store", &[WasmValue::I32(0), null]).unwrap();
+        assert_eq!(drops.get(), 0);
+        execute_export(&mut ctx, "store", &[WasmValue::I32(1), null]).unwrap();
+        assert_eq!(drops.get(), 1);
+
+        // Teardown must not free it a second time
+        drop(ctx);
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn test_externref_freed_on_teardown() {
+        let drops = alloc::rc::Rc::new(core::cell::Cell::new(0));
+        let mut ctx = ExecutorContext::new(make_externref_module()).unwrap();
+
+        let v = ctx.extern_refs.lift(Tracked(drops.clone()));
+        execute_export(&mut ctx, "store", &[WasmValue::I32(0), v]).unwrap();
+        // Overwriting a slot with the handle it already holds keeps it alive
+        execute_export(&mut ctx, "store", &[WasmValue::I32(0), v]).unwrap();
+        ctx.extern_refs.release_value(v);
+        assert_eq!(drops.get(), 0);
+
+        drop(ctx);
+        assert_eq!(drops.get(), 1);
+    }
 }
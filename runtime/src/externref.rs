@@ -0,0 +1,212 @@
+//! Host reference table for `externref` values.
+//!
+//! Host objects passed into WASM live in an [`ExternRefTable`] and are
+//! referred to by the `u32` handle carried in `WasmValue::ExternRef`.
+//! Entries are reference-counted: the reference returned by
+//! [`ExternRefTable::lift`] and every table slot or global holding the
+//! handle each count once. The host object is dropped when the count
+//! reaches zero, or when the owning instance is torn down.
+//!
+//! Values on the operand stack and in locals are not counted, so the host
+//! must keep its lifted reference until the call that receives it returns.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::interpreter::WasmValue;
+
+/// A live host object and its reference count.
+struct ExternEntry {
+    object: Box<dyn Any>,
+    refcount: u32,
+}
+
+/// Reference-counted table of host objects exposed as `externref`.
+pub struct ExternRefTable {
+    entries: Vec<Option<ExternEntry>>,
+    free_list: Vec<u32>,
+}
+
+impl ExternRefTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            free_list: Vec::new(),
+        }
+    }
+
+    /// Lift a host object into WASM, returning an `externref` value.
+    ///
+    /// The returned value carries one reference owned by the caller, which
+    /// must eventually be dropped with [`release`](Self::release).
+    pub fn lift<T: Any>(&mut self, object: T) -> WasmValue {
+        WasmValue::ExternRef(Some(self.lift_boxed(Box::new(object))))
+    }
+
+    /// Lift an already boxed host object, returning its handle.
+    pub fn lift_boxed(&mut self, object: Box<dyn Any>) -> u32 {
+        let entry = ExternEntry {
+            object,
+            refcount: 1,
+        };
+        if let Some(handle) = self.free_list.pop() {
+            self.entries[handle as usize] = Some(entry);
+            handle
+        } else {
+            self.entries.push(Some(entry));
+            (self.entries.len() - 1) as u32
+        }
+    }
+
+    /// Add a reference to a live handle. Returns `false` if it is not live.
+    pub fn retain(&mut self, handle: u32) -> bool {
+        match self.entry_mut(handle) {
+            Some(entry) => {
+                entry.refcount = entry.refcount.saturating_add(1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop a reference to a handle, freeing the host object at zero.
+    ///
+    /// Returns `true` if the object was freed.
+    pub fn release(&mut self, handle: u32) -> bool {
+        let Some(entry) = self.entry_mut(handle) else {
+            return false;
+        };
+        entry.refcount -= 1;
+        if entry.refcount > 0 {
+            return false;
+        }
+
+        // Take the entry out before dropping so a re-entrant destructor
+        // never observes a half-freed slot.
+        let entry = self.entries[handle as usize].take();
+        self.free_list.push(handle);
+        drop(entry);
+        true
+    }
+
+    /// Add a reference for an `externref` value (no-op for other values).
+    pub fn retain_value(&mut self, value: WasmValue) {
+        if let WasmValue::ExternRef(Some(handle)) = value {
+            self.retain(handle);
+        }
+    }
+
+    /// Drop a reference for an `externref` value (no-op for other values).
+    pub fn release_value(&mut self, value: WasmValue) {
+        if let WasmValue::ExternRef(Some(handle)) = value {
+            self.release(handle);
+        }
+    }
+
+    /// Borrow the host object behind a handle.
+    pub fn get(&self, handle: u32) -> Option<&dyn Any> {
+        self.entries
+            .get(handle as usize)?
+            .as_ref()
+            .map(|e| e.object.as_ref())
+    }
+
+    /// Borrow the host object behind a handle as a concrete type.
+    pub fn downcast<T: Any>(&self, handle: u32) -> Option<&T> {
+        self.get(handle)?.downcast_ref::<T>()
+    }
+
+    /// Current reference count of a handle (0 if not live).
+    pub fn refcount(&self, handle: u32) -> u32 {
+        self.entries
+            .get(handle as usize)
+            .and_then(|e| e.as_ref())
+            .map_or(0, |e| e.refcount)
+    }
+
+    /// Number of live host objects.
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.free_list.len()
+    }
+
+    /// Check if no host objects are live.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn entry_mut(&mut self, handle: u32) -> Option<&mut ExternEntry> {
+        self.entries.get_mut(handle as usize)?.as_mut()
+    }
+}
+
+impl Default for ExternRefTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    /// Host object that counts how many times it was dropped.
+    struct Tracked(Rc<Cell<u32>>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_release_frees_at_zero() {
+        let drops = Rc::new(Cell::new(0));
+        let mut table = ExternRefTable::new();
+
+        let WasmValue::ExternRef(Some(h)) = table.lift(Tracked(drops.clone())) else {
+            panic!("expected externref");
+        };
+        assert!(table.retain(h));
+        assert_eq!(table.refcount(h), 2);
+
+        assert!(!table.release(h));
+        assert_eq!(drops.get(), 0);
+        assert!(table.release(h));
+        assert_eq!(drops.get(), 1);
+
+        // Further releases on a dead handle are ignored
+        assert!(!table.release(h));
+        assert_eq!(drops.get(), 1);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_handle_reuse() {
+        let mut table = ExternRefTable::new();
+        let WasmValue::ExternRef(Some(a)) = table.lift(1u32) else {
+            panic!("expected externref");
+        };
+        table.release(a);
+        let WasmValue::ExternRef(Some(b)) = table.lift(2u32) else {
+            panic!("expected externref");
+        };
+        assert_eq!(a, b);
+        assert_eq!(table.downcast::<u32>(b), Some(&2));
+        assert_eq!(table.downcast::<u64>(b), None);
+    }
+
+    #[test]
+    fn test_drop_table_frees_objects() {
+        let drops = Rc::new(Cell::new(0));
+        let mut table = ExternRefTable::new();
+        let v = table.lift(Tracked(drops.clone()));
+        table.retain_value(v);
+
+        drop(table);
+        assert_eq!(drops.get(), 1);
+    }
+}
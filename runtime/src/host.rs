@@ -15,7 +15,10 @@ use spin::Mutex;
 use crate::executor::ExecutorContext;
 use crate::instance::Imports;
 use crate::interpreter::{TrapError, WasmValue};
-use crate::wasi::{ClockId, FdFlags, FdRights, LookupFlags, OFlags, Whence};
+use crate::wasi::{
+    ClockId, Event, FdFlags, FdRights, LookupFlags, OFlags, RiFlags, SdFlags, Subscription,
+    WasiCtx, WasiError, Whence,
+};
 
 // ─── KPIO IPC / Process / Capability / GPU Global State ────────────
 
@@ -200,11 +203,17 @@ fn register_wasi_functions(imports: &mut Imports) {
         "environ_sizes_get",
         host_environ_sizes_get,
     );
+    imports.add_function(
+        "wasi_snapshot_preview1",
+        "clock_res_get",
+        host_clock_res_get,
+    );
     imports.add_function(
         "wasi_snapshot_preview1",
         "clock_time_get",
         host_clock_time_get,
     );
+    imports.add_function("wasi_snapshot_preview1", "poll_oneoff", host_poll_oneoff);
     imports.add_function("wasi_snapshot_preview1", "fd_close", host_fd_close);
     imports.add_function("wasi_snapshot_preview1", "fd_read", host_fd_read);
     imports.add_function("wasi_snapshot_preview1", "fd_write", host_fd_write);
@@ -215,6 +224,11 @@ fn register_wasi_functions(imports: &mut Imports) {
         "fd_fdstat_get",
         host_fd_fdstat_get,
     );
+    imports.add_function(
+        "wasi_snapshot_preview1",
+        "fd_fdstat_set_flags",
+        host_fd_fdstat_set_flags,
+    );
     imports.add_function(
         "wasi_snapshot_preview1",
         "fd_prestat_get",
@@ -250,6 +264,14 @@ fn register_wasi_functions(imports: &mut Imports) {
     );
     imports.add_function("wasi_snapshot_preview1", "proc_exit", host_proc_exit);
     imports.add_function("wasi_snapshot_preview1", "random_get", host_random_get);
+    imports.add_function("wasi_snapshot_preview1", "sock_accept", host_sock_accept);
+    imports.add_function("wasi_snapshot_preview1", "sock_recv", host_sock_recv);
+    imports.add_function("wasi_snapshot_preview1", "sock_send", host_sock_send);
+    imports.add_function(
+        "wasi_snapshot_preview1",
+        "sock_shutdown",
+        host_sock_shutdown,
+    );
 }
 
 /// Register KPIO-specific functions.
@@ -453,6 +475,26 @@ fn host_fd_fdstat_get(
     }
 }
 
+/// fd_fdstat_set_flags(fd, flags) -> errno
+fn host_fd_fdstat_set_flags(
+    ctx: &mut ExecutorContext,
+    args: &[WasmValue],
+) -> Result<Vec<WasmValue>, TrapError> {
+    let fd = arg_i32(args, 0) as u32;
+    let flags = arg_i32(args, 1) as u16;
+
+    let result = if let Some(ref mut wasi) = ctx.wasi_ctx {
+        wasi.fd_fdstat_set_flags(fd, FdFlags::from_bits_truncate(flags))
+    } else {
+        return Ok(vec![WasmValue::I32(8)]); // EBADF
+    };
+
+    match result {
+        Ok(()) => Ok(vec![WasmValue::I32(0)]),
+        Err(e) => Ok(vec![WasmValue::I32(e.to_errno())]),
+    }
+}
+
 /// fd_prestat_get(fd, prestat_ptr) -> errno
 /// Prestat layout: tag(1) + pad(3) + dir_name_len(4) = 8 bytes
 fn host_fd_prestat_get(
@@ -808,6 +850,33 @@ fn host_environ_get(
     Ok(vec![WasmValue::I32(0)])
 }
 
+/// clock_res_get(id, resolution_ptr) -> errno
+fn host_clock_res_get(
+    ctx: &mut ExecutorContext,
+    args: &[WasmValue],
+) -> Result<Vec<WasmValue>, TrapError> {
+    let clock_id_val = arg_i32(args, 0) as u32;
+    let resolution_ptr = arg_i32(args, 1) as u32;
+
+    let Some(clock_id) = ClockId::from_u32(clock_id_val) else {
+        return Ok(vec![WasmValue::I32(WasiError::Inval.to_errno())]);
+    };
+
+    let result = if let Some(ref wasi) = ctx.wasi_ctx {
+        wasi.clock_res_get(clock_id)
+    } else {
+        Ok(crate::clock::resolution_ns())
+    };
+
+    match result {
+        Ok(resolution) => {
+            mem_write_u64(ctx, resolution_ptr, resolution)?;
+            Ok(vec![WasmValue::I32(0)])
+        }
+        Err(e) => Ok(vec![WasmValue::I32(e.to_errno())]),
+    }
+}
+
 /// clock_time_get(id, precision, time_ptr) -> errno
 fn host_clock_time_get(
     ctx: &mut ExecutorContext,
@@ -817,12 +886,14 @@ fn host_clock_time_get(
     let precision = arg_i64(args, 1) as u64;
     let time_ptr = arg_i32(args, 2) as u32;
 
-    let clock_id = ClockId::from_u32(clock_id_val).unwrap_or(ClockId::Monotonic);
+    let Some(clock_id) = ClockId::from_u32(clock_id_val) else {
+        return Ok(vec![WasmValue::I32(WasiError::Inval.to_errno())]);
+    };
 
     let result = if let Some(ref mut wasi) = ctx.wasi_ctx {
         wasi.clock_time_get(clock_id, precision)
     } else {
-        Ok(0)
+        Ok(clock_id.now_ns())
     };
 
     match result {
@@ -834,6 +905,44 @@ fn host_clock_time_get(
     }
 }
 
+/// poll_oneoff(in_ptr, out_ptr, nsubscriptions, nevents_ptr) -> errno
+fn host_poll_oneoff(
+    ctx: &mut ExecutorContext,
+    args: &[WasmValue],
+) -> Result<Vec<WasmValue>, TrapError> {
+    let in_ptr = arg_i32(args, 0) as u32;
+    let out_ptr = arg_i32(args, 1) as u32;
+    let nsubscriptions = arg_i32(args, 2) as u32;
+    let nevents_ptr = arg_i32(args, 3) as u32;
+
+    let size = nsubscriptions.saturating_mul(Subscription::SIZE as u32);
+    let bytes = mem_read_bytes(ctx, in_ptr, size)?;
+    let subscriptions: Result<Vec<Subscription>, WasiError> = bytes
+        .chunks_exact(Subscription::SIZE)
+        .map(Subscription::from_bytes)
+        .collect();
+    let subscriptions = match subscriptions {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => return Ok(vec![WasmValue::I32(e.to_errno())]),
+    };
+
+    // Without a WASI context, poll a fresh one that only has stdio.
+    let result = match ctx.wasi_ctx {
+        Some(ref wasi) => wasi.poll_oneoff(&subscriptions),
+        None => WasiCtx::new().poll_oneoff(&subscriptions),
+    };
+
+    match result {
+        Ok(events) => {
+            let out: Vec<u8> = events.iter().flat_map(Event::to_bytes).collect();
+            mem_write_bytes(ctx, out_ptr, &out)?;
+            mem_write_u32(ctx, nevents_ptr, events.len() as u32)?;
+            Ok(vec![WasmValue::I32(0)])
+        }
+        Err(e) => Ok(vec![WasmValue::I32(e.to_errno())]),
+    }
+}
+
 /// random_get(buf_ptr, buf_len) -> errno
 fn host_random_get(
     ctx: &mut ExecutorContext,
@@ -876,6 +985,119 @@ fn host_proc_exit(
     Err(TrapError::ProcessExit(code))
 }
 
+/// sock_accept(fd, flags, fd_ptr) -> errno
+fn host_sock_accept(
+    ctx: &mut ExecutorContext,
+    args: &[WasmValue],
+) -> Result<Vec<WasmValue>, TrapError> {
+    let fd = arg_i32(args, 0) as u32;
+    let flags = arg_i32(args, 1) as u16;
+    let fd_ptr = arg_i32(args, 2) as u32;
+
+    let result = if let Some(ref mut wasi) = ctx.wasi_ctx {
+        match FdFlags::from_bits(flags) {
+            Some(flags) => wasi.sock_accept(fd, flags),
+            None => Err(WasiError::Inval),
+        }
+    } else {
+        return Ok(vec![WasmValue::I32(8)]); // EBADF
+    };
+
+    match result {
+        Ok(new_fd) => {
+            mem_write_u32(ctx, fd_ptr, new_fd)?;
+            Ok(vec![WasmValue::I32(0)])
+        }
+        Err(e) => Ok(vec![WasmValue::I32(e.to_errno())]),
+    }
+}
+
+/// sock_recv(fd, ri_data_ptr, ri_data_len, ri_flags, ro_datalen_ptr,
+///           ro_flags_ptr) -> errno
+fn host_sock_recv(
+    ctx: &mut ExecutorContext,
+    args: &[WasmValue],
+) -> Result<Vec<WasmValue>, TrapError> {
+    let fd = arg_i32(args, 0) as u32;
+    let iovs_ptr = arg_i32(args, 1) as u32;
+    let iovs_cnt = arg_i32(args, 2) as u32;
+    let ri_flags = arg_i32(args, 3) as u16;
+    let ro_datalen_ptr = arg_i32(args, 4) as u32;
+    let ro_flags_ptr = arg_i32(args, 5) as u32;
+
+    let mut total_buf_size = 0u32;
+    for i in 0..iovs_cnt {
+        let buf_len = mem_read_u32(ctx, iovs_ptr + i * 8 + 4)?;
+        total_buf_size += buf_len;
+    }
+
+    let wasi = match ctx.wasi_ctx {
+        Some(ref mut w) => w,
+        None => return Ok(vec![WasmValue::I32(8)]), // EBADF
+    };
+
+    let mut recv_buf = vec![0u8; total_buf_size as usize];
+    let result = wasi.sock_recv(fd, &mut recv_buf, RiFlags::from_bits_truncate(ri_flags));
+
+    match result {
+        Ok((n, ro_flags)) => {
+            let written = write_to_iovs(ctx, iovs_ptr, iovs_cnt, &recv_buf[..n])?;
+            mem_write_u32(ctx, ro_datalen_ptr, written)?;
+            mem_write_bytes(ctx, ro_flags_ptr, &ro_flags.bits().to_le_bytes())?;
+            Ok(vec![WasmValue::I32(0)])
+        }
+        Err(e) => Ok(vec![WasmValue::I32(e.to_errno())]),
+    }
+}
+
+/// sock_send(fd, si_data_ptr, si_data_len, si_flags, so_datalen_ptr) -> errno
+fn host_sock_send(
+    ctx: &mut ExecutorContext,
+    args: &[WasmValue],
+) -> Result<Vec<WasmValue>, TrapError> {
+    let fd = arg_i32(args, 0) as u32;
+    let iovs_ptr = arg_i32(args, 1) as u32;
+    let iovs_cnt = arg_i32(args, 2) as u32;
+    // si_flags (args[3]) has no flags defined
+    let so_datalen_ptr = arg_i32(args, 4) as u32;
+
+    let data = read_iovs(ctx, iovs_ptr, iovs_cnt)?;
+
+    let result = if let Some(ref mut wasi) = ctx.wasi_ctx {
+        wasi.sock_send(fd, &data)
+    } else {
+        return Ok(vec![WasmValue::I32(8)]); // EBADF
+    };
+
+    match result {
+        Ok(n) => {
+            mem_write_u32(ctx, so_datalen_ptr, n as u32)?;
+            Ok(vec![WasmValue::I32(0)])
+        }
+        Err(e) => Ok(vec![WasmValue::I32(e.to_errno())]),
+    }
+}
+
+/// sock_shutdown(fd, how) -> errno
+fn host_sock_shutdown(
+    ctx: &mut ExecutorContext,
+    args: &[WasmValue],
+) -> Result<Vec<WasmValue>, TrapError> {
+    let fd = arg_i32(args, 0) as u32;
+    let how = arg_i32(args, 1) as u8;
+
+    let result = if let Some(ref mut wasi) = ctx.wasi_ctx {
+        wasi.sock_shutdown(fd, SdFlags::from_bits_truncate(how))
+    } else {
+        return Ok(vec![WasmValue::I32(8)]); // EBADF
+    };
+
+    match result {
+        Ok(()) => Ok(vec![WasmValue::I32(0)]),
+        Err(e) => Ok(vec![WasmValue::I32(e.to_errno())]),
+    }
+}
+
 // ─── KPIO IPC Functions ────────────────────────────────────────────
 
 /// ipc_send(channel_id_lo, channel_id_hi, buf_ptr, buf_len) -> errno
@@ -1125,7 +1347,6 @@ fn host_gpu_present(
 mod tests {
     use super::*;
     use crate::module::{MemoryType, Module};
-    use crate::wasi::WasiCtx;
     use alloc::string::String;
     use alloc::vec;
 
@@ -1140,6 +1361,7 @@ mod tests {
                 min: 1,
                 max: Some(10),
                 shared: false,
+                memory64: false,
             }],
             globals: vec![],
             exports: vec![],
@@ -1148,6 +1370,7 @@ mod tests {
             code: vec![],
             data: vec![],
             name: None,
+            names: Default::default(),
             data_count: None,
         }
     }
@@ -1367,6 +1590,131 @@ mod tests {
         assert!(time > 0, "Clock time should be non-zero");
     }
 
+    /// Module exporting `elapsed() -> i64`, which reads the monotonic
+    /// clock twice through `clock_time_get` and returns the difference.
+    fn elapsed_module() -> Module {
+        use crate::module::{
+            Export, ExportKind, FunctionBody, FunctionType, Import, ImportKind, ValueType,
+        };
+        use crate::opcodes::Instruction::*;
+
+        let mut module = test_module();
+        module.types.push(FunctionType {
+            params: vec![ValueType::I32, ValueType::I64, ValueType::I32],
+            results: vec![ValueType::I32],
+        });
+        module.types.push(FunctionType {
+            params: vec![],
+            results: vec![ValueType::I64],
+        });
+        module.imports.push(Import {
+            module: String::from("wasi_snapshot_preview1"),
+            name: String::from("clock_time_get"),
+            kind: ImportKind::Function(0),
+        });
+        module.functions.push(1);
+        module.code.push(FunctionBody {
+            locals: vec![],
+            instructions: vec![
+                I32Const(1),
+                I64Const(0),
+                I32Const(0),
+                Call(0),
+                Drop,
+                I32Const(1),
+                I64Const(0),
+                I32Const(8),
+                Call(0),
+                Drop,
+                I32Const(8),
+                I64Load(3, 0),
+                I32Const(0),
+                I64Load(3, 0),
+                I64Sub,
+                End,
+            ],
+            raw_bytes: vec![],
+        });
+        module.exports.push(Export {
+            name: String::from("elapsed"),
+            kind: ExportKind::Function,
+            index: 1,
+        });
+        module
+    }
+
+    #[test]
+    fn test_wasm_clock_duration() {
+        let mut imports = crate::instance::Imports::new();
+        register_all(&mut imports);
+        // Instances without a WASI context still read the host clocks
+        let mut instance =
+            crate::instance::Instance::new_with_imports(&elapsed_module(), imports).unwrap();
+
+        for _ in 0..100 {
+            let result = instance.call_typed("elapsed", &[]).unwrap();
+            let WasmValue::I64(elapsed) = result[0] else {
+                panic!("expected i64, got {:?}", result);
+            };
+            assert!(elapsed >= 0, "monotonic clock went back by {} ns", -elapsed);
+        }
+        let bytes = instance.context().memories[0].read_bytes(0, 8).unwrap();
+        assert!(u64::from_le_bytes(bytes.try_into().unwrap()) > 0);
+    }
+
+    #[test]
+    fn test_host_poll_oneoff() {
+        let mut ctx = test_ctx_with_wasi();
+
+        // Two subscriptions at 0: a 1 ms relative timeout (userdata 7)
+        // and stdout becoming writable (userdata 9).
+        let mut subs = [0u8; 2 * Subscription::SIZE];
+        subs[0..8].copy_from_slice(&7u64.to_le_bytes());
+        subs[16..20].copy_from_slice(&1u32.to_le_bytes()); // MONOTONIC
+        subs[24..32].copy_from_slice(&1_000_000u64.to_le_bytes());
+        subs[48..56].copy_from_slice(&9u64.to_le_bytes());
+        subs[56] = 2; // FD_WRITE
+        subs[64..68].copy_from_slice(&1u32.to_le_bytes());
+        ctx.memories[0].write_bytes(0, &subs).unwrap();
+
+        let args = [
+            WasmValue::I32(0),
+            WasmValue::I32(256),
+            WasmValue::I32(2),
+            WasmValue::I32(512),
+        ];
+        let result = host_poll_oneoff(&mut ctx, &args).unwrap();
+        assert_eq!(result[0], WasmValue::I32(0));
+        assert_eq!(mem_read_u32(&ctx, 512).unwrap(), 1);
+        let event = ctx.memories[0].read_bytes(256, Event::SIZE).unwrap();
+        assert_eq!(&event[0..8], &9u64.to_le_bytes());
+        assert_eq!(&event[8..10], &[0, 0]); // no error
+        assert_eq!(event[10], 2); // FD_WRITE
+
+        // Unknown clock
+        ctx.memories[0]
+            .write_bytes(16, &9u32.to_le_bytes())
+            .unwrap();
+        let result = host_poll_oneoff(&mut ctx, &args).unwrap();
+        assert_eq!(result[0], WasmValue::I32(WasiError::Inval.to_errno()));
+    }
+
+    #[test]
+    fn test_host_clock_res_get() {
+        let mut ctx = test_ctx_with_wasi();
+
+        // clock_res_get(MONOTONIC=1, resolution_ptr=8)
+        let result = host_clock_res_get(&mut ctx, &[WasmValue::I32(1), WasmValue::I32(8)]).unwrap();
+        assert_eq!(result[0], WasmValue::I32(0));
+        let bytes = ctx.memories[0].read_bytes(8, 8).unwrap();
+        let resolution = u64::from_le_bytes(bytes.try_into().unwrap());
+        assert_eq!(resolution, crate::clock::resolution_ns());
+
+        // Unknown clock
+        let result = host_clock_res_get(&mut ctx, &[WasmValue::I32(9), WasmValue::I32(8)]).unwrap();
+        assert_eq!(result[0], WasmValue::I32(WasiError::Inval.to_errno()));
+    }
+
     // C-QG6: Random via host function
     #[test]
     fn test_host_cqg6_random() {
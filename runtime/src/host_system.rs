@@ -214,6 +214,7 @@ mod tests {
             min: 1,
             max: None,
             shared: false,
+            memory64: false,
         });
         ExecutorContext::new(module).unwrap()
     }
@@ -9,10 +9,12 @@ use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
-use crate::executor::{self, ExecutorContext, HostFn, HostFunction as ExecHostFunction};
+use crate::executor::{
+    self, ExecutorContext, HostFn, HostFunction as ExecHostFunction, ResourceUsage,
+};
 use crate::interpreter::WasmValue;
 use crate::memory::LinearMemory;
-use crate::module::Module;
+use crate::module::{FunctionType, ImportKind, Module};
 use crate::RuntimeError;
 
 /// An instantiated WASM module with execution context.
@@ -33,8 +35,14 @@ impl Instance {
     /// Create a new instance with imports.
     pub fn new_with_imports(module: &Module, imports: Imports) -> Result<Self, RuntimeError> {
         // Convert Imports to executor HostFunction list
-        let host_fns = imports.to_exec_host_functions();
+        Self::from_host_functions(module, imports.to_exec_host_functions())
+    }
 
+    /// Create an instance from an already-resolved host function list.
+    fn from_host_functions(
+        module: &Module,
+        host_fns: Vec<ExecHostFunction>,
+    ) -> Result<Self, RuntimeError> {
         let ctx = ExecutorContext::new_with_host_functions(module.clone(), host_fns)
             .map_err(|e| RuntimeError::InstantiationError(alloc::format!("{}", e)))?;
 
@@ -54,6 +62,14 @@ impl Instance {
             .map_err(|e| RuntimeError::ExecutionError(alloc::format!("{}", e)))
     }
 
+    /// Describe the frames active when the last call trapped.
+    ///
+    /// Frames are listed innermost first and named from the module's
+    /// "name" section where available. Empty if the last call succeeded.
+    pub fn trap_backtrace(&self) -> String {
+        self.ctx.format_backtrace()
+    }
+
     /// Call an exported function (legacy API, returns empty bytes).
     pub fn call(&mut self, name: &str, args: &[u8]) -> Result<Vec<u8>, RuntimeError> {
         let wasm_args: Vec<WasmValue> = args
@@ -110,6 +126,26 @@ impl Instance {
         self.ctx.fuel = fuel;
     }
 
+    /// Set the maximum nested call depth.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.ctx.max_call_depth = depth;
+    }
+
+    /// Cap 64-bit memories at `pages` pages.
+    ///
+    /// 32-bit memories are already bounded by their 4 GB index space.
+    pub fn set_max_memory_pages(&mut self, pages: u32) -> Result<(), RuntimeError> {
+        for mem in self.ctx.memories.iter_mut().filter(|m| m.is_64()) {
+            mem.limit_pages(pages)?;
+        }
+        Ok(())
+    }
+
+    /// Get the memory, fuel, table, and host-call usage so far.
+    pub fn resource_usage(&self) -> ResourceUsage {
+        self.ctx.resource_usage()
+    }
+
     /// Get a reference to the store.
     pub fn store(&self) -> &Store {
         &self.store
@@ -186,6 +222,188 @@ impl Imports {
     }
 }
 
+/// A host function registered with a [`Linker`].
+#[derive(Clone)]
+struct LinkedFunction {
+    /// Implementation.
+    func: HostFn,
+    /// Expected signature, if the definer declared one.
+    ty: Option<FunctionType>,
+}
+
+/// Registry of named host modules used to instantiate WASM modules.
+///
+/// Host functions are defined under a `(module, name)` pair before
+/// instantiation; [`Linker::instantiate`] then checks every function
+/// import of the module against those definitions and fails with a
+/// descriptive error instead of leaving unresolved imports to trap at
+/// call time.
+#[derive(Clone, Default)]
+pub struct Linker {
+    /// Defined host functions keyed by (module, name).
+    functions: BTreeMap<(String, String), LinkedFunction>,
+}
+
+impl Linker {
+    /// Create a new empty linker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a linker with all built-in host APIs defined
+    /// (WASI, `kpio_gui`, `kpio_system`, `kpio_net`, ...).
+    pub fn with_host_apis() -> Self {
+        let mut imports = Imports::new();
+        crate::host::register_all(&mut imports);
+        let mut linker = Self::new();
+        for ((module, name), func) in imports.functions {
+            linker
+                .functions
+                .insert((module, name), LinkedFunction { func, ty: None });
+        }
+        linker
+    }
+
+    /// Define a host function under `module::name`.
+    ///
+    /// Returns an error if the name is already defined.
+    pub fn define(&mut self, module: &str, name: &str, func: HostFn) -> Result<(), RuntimeError> {
+        self.insert(module, name, LinkedFunction { func, ty: None })
+    }
+
+    /// Define a host function with a declared signature.
+    ///
+    /// Modules importing `module::name` with any other signature are
+    /// rejected by [`Linker::instantiate`].
+    pub fn define_typed(
+        &mut self,
+        module: &str,
+        name: &str,
+        ty: FunctionType,
+        func: HostFn,
+    ) -> Result<(), RuntimeError> {
+        self.insert(module, name, LinkedFunction { func, ty: Some(ty) })
+    }
+
+    /// Define every function from an existing import set.
+    ///
+    /// Returns an error on the first name that is already defined.
+    pub fn define_imports(&mut self, imports: &Imports) -> Result<(), RuntimeError> {
+        for ((module, name), func) in &imports.functions {
+            self.define(module, name, *func)?;
+        }
+        Ok(())
+    }
+
+    /// Check whether `module::name` is defined.
+    pub fn contains(&self, module: &str, name: &str) -> bool {
+        self.functions
+            .contains_key(&(String::from(module), String::from(name)))
+    }
+
+    /// Get the names of all host modules with at least one definition.
+    pub fn module_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.functions.keys().map(|(m, _)| m.as_str()).collect();
+        names.dedup();
+        names
+    }
+
+    /// Resolve the imports of `module` and instantiate it.
+    ///
+    /// Every function import must be defined, and must match the declared
+    /// signature when one was given. Memory, table and global imports are
+    /// created from their import types, as with [`Instance::new`].
+    pub fn instantiate(&self, module: &Module) -> Result<Instance, RuntimeError> {
+        let mut host_fns = Vec::new();
+        for import in &module.imports {
+            let key = (import.module.clone(), import.name.clone());
+            let type_idx = match import.kind {
+                ImportKind::Function(type_idx) => type_idx,
+                ref kind => {
+                    if self.functions.contains_key(&key) {
+                        return Err(RuntimeError::InstantiationError(alloc::format!(
+                            "incompatible import type for {}::{}: module imports a {}, \
+                             but a function is defined",
+                            import.module,
+                            import.name,
+                            import_kind_name(kind)
+                        )));
+                    }
+                    continue;
+                }
+            };
+
+            let linked = self.functions.get(&key).ok_or_else(|| {
+                RuntimeError::InstantiationError(alloc::format!(
+                    "unknown import: {}::{} has not been defined",
+                    import.module,
+                    import.name
+                ))
+            })?;
+
+            let import_ty = module.types.get(type_idx as usize).ok_or_else(|| {
+                RuntimeError::InstantiationError(alloc::format!(
+                    "import {}::{} references invalid type index {}",
+                    import.module,
+                    import.name,
+                    type_idx
+                ))
+            })?;
+            if let Some(ref expected) = linked.ty {
+                if expected != import_ty {
+                    return Err(RuntimeError::InstantiationError(alloc::format!(
+                        "incompatible import type for {}::{}: expected {:?} -> {:?}, \
+                         module imports {:?} -> {:?}",
+                        import.module,
+                        import.name,
+                        expected.params,
+                        expected.results,
+                        import_ty.params,
+                        import_ty.results
+                    )));
+                }
+            }
+
+            host_fns.push(ExecHostFunction {
+                module: import.module.clone(),
+                name: import.name.clone(),
+                func: linked.func,
+                type_idx: Some(type_idx),
+            });
+        }
+
+        Instance::from_host_functions(module, host_fns)
+    }
+
+    fn insert(
+        &mut self,
+        module: &str,
+        name: &str,
+        linked: LinkedFunction,
+    ) -> Result<(), RuntimeError> {
+        let key = (String::from(module), String::from(name));
+        if self.functions.contains_key(&key) {
+            return Err(RuntimeError::InstantiationError(alloc::format!(
+                "import {}::{} already defined",
+                module,
+                name
+            )));
+        }
+        self.functions.insert(key, linked);
+        Ok(())
+    }
+}
+
+/// Human-readable name of an import kind for diagnostics.
+fn import_kind_name(kind: &ImportKind) -> &'static str {
+    match kind {
+        ImportKind::Function(_) => "function",
+        ImportKind::Table(_) => "table",
+        ImportKind::Memory(_) => "memory",
+        ImportKind::Global(_) => "global",
+    }
+}
+
 /// Instance store for host-side data.
 pub struct Store {
     /// Host data indexed by key.
@@ -228,3 +446,215 @@ impl Default for Store {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::TrapError;
+    use crate::module::{Export, ExportKind, FunctionBody, Import, MemoryType, ValueType};
+    use crate::opcodes::Instruction;
+    use alloc::vec;
+
+    fn host_double(
+        _ctx: &mut ExecutorContext,
+        args: &[WasmValue],
+    ) -> Result<Vec<WasmValue>, TrapError> {
+        let v = args.first().and_then(|v| v.as_i32()).unwrap_or(0);
+        Ok(vec![WasmValue::I32(v * 2)])
+    }
+
+    fn i32_to_i32() -> FunctionType {
+        FunctionType {
+            params: vec![ValueType::I32],
+            results: vec![ValueType::I32],
+        }
+    }
+
+    /// Module importing `env::double : (i32) -> i32` and re-exporting it.
+    fn importing_module() -> Module {
+        let mut module = Module::empty();
+        module.types.push(i32_to_i32());
+        module.imports.push(Import {
+            module: String::from("env"),
+            name: String::from("double"),
+            kind: ImportKind::Function(0),
+        });
+        module.exports.push(Export {
+            name: String::from("double"),
+            kind: ExportKind::Function,
+            index: 0,
+        });
+        module
+    }
+
+    #[test]
+    fn test_linker_define_then_instantiate() {
+        let mut linker = Linker::new();
+        linker.define("env", "double", host_double).unwrap();
+        assert!(linker.contains("env", "double"));
+        assert_eq!(linker.module_names(), vec!["env"]);
+
+        let mut instance = linker.instantiate(&importing_module()).unwrap();
+        let result = instance
+            .call_typed("double", &[WasmValue::I32(21)])
+            .unwrap();
+        assert_eq!(result, vec![WasmValue::I32(42)]);
+    }
+
+    #[test]
+    fn test_linker_duplicate_definition() {
+        let mut linker = Linker::new();
+        linker.define("env", "double", host_double).unwrap();
+        assert!(linker.define("env", "double", host_double).is_err());
+    }
+
+    #[test]
+    fn test_linker_missing_import() {
+        let mut linker = Linker::new();
+        linker.define("env", "triple", host_double).unwrap();
+        match linker.instantiate(&importing_module()) {
+            Err(RuntimeError::InstantiationError(msg)) => {
+                assert!(msg.contains("unknown import"));
+                assert!(msg.contains("env::double"));
+            }
+            _ => panic!("expected missing import error"),
+        }
+    }
+
+    #[test]
+    fn test_linker_signature_mismatch() {
+        let mut linker = Linker::new();
+        let ty = FunctionType {
+            params: vec![ValueType::I64],
+            results: vec![ValueType::I64],
+        };
+        linker
+            .define_typed("env", "double", ty, host_double)
+            .unwrap();
+        match linker.instantiate(&importing_module()) {
+            Err(RuntimeError::InstantiationError(msg)) => {
+                assert!(msg.contains("incompatible import type"));
+            }
+            _ => panic!("expected signature mismatch error"),
+        }
+
+        let mut linker = Linker::new();
+        linker
+            .define_typed("env", "double", i32_to_i32(), host_double)
+            .unwrap();
+        assert!(linker.instantiate(&importing_module()).is_ok());
+    }
+
+    #[test]
+    fn test_linker_kind_mismatch() {
+        let mut module = Module::empty();
+        module.imports.push(Import {
+            module: String::from("env"),
+            name: String::from("memory"),
+            kind: ImportKind::Memory(MemoryType {
+                min: 1,
+                max: None,
+                shared: false,
+                memory64: false,
+            }),
+        });
+
+        // Undefined non-function imports are created from their types.
+        assert!(Linker::new().instantiate(&module).is_ok());
+
+        let mut linker = Linker::new();
+        linker.define("env", "memory", host_double).unwrap();
+        match linker.instantiate(&module) {
+            Err(RuntimeError::InstantiationError(msg)) => {
+                assert!(msg.contains("module imports a memory"));
+            }
+            _ => panic!("expected import kind mismatch error"),
+        }
+    }
+
+    #[test]
+    fn test_linker_with_host_apis() {
+        let linker = Linker::with_host_apis();
+        assert!(linker.contains("wasi_snapshot_preview1", "fd_write"));
+        assert!(linker.contains("kpio_gui", "create_window"));
+
+        let mut imports = Imports::new();
+        imports.add_function("env", "double", host_double);
+        let mut linker = Linker::new();
+        linker.define_imports(&imports).unwrap();
+        assert!(linker.define_imports(&imports).is_err());
+    }
+
+    #[test]
+    fn test_resource_usage_after_memory_grow() {
+        // grow() -> i32: memory.grow 2, then env::double on the old size
+        let mut module = importing_module();
+        module.types.push(FunctionType {
+            params: vec![],
+            results: vec![ValueType::I32],
+        });
+        module.functions.push(1);
+        module.code.push(FunctionBody {
+            locals: vec![],
+            instructions: vec![
+                Instruction::I32Const(2),
+                Instruction::MemoryGrow,
+                Instruction::Call(0),
+                Instruction::End,
+            ],
+            raw_bytes: vec![],
+        });
+        module.memories.push(MemoryType {
+            min: 1,
+            max: Some(4),
+            shared: false,
+            memory64: false,
+        });
+        module.exports.push(Export {
+            name: String::from("grow"),
+            kind: ExportKind::Function,
+            index: 1,
+        });
+
+        let mut imports = Imports::new();
+        imports.add_function("env", "double", host_double);
+        let mut instance = Instance::new_with_imports(&module, imports).unwrap();
+
+        let before = instance.resource_usage();
+        assert_eq!(before.memory_pages, 1);
+        assert_eq!(before.peak_memory_pages, 1);
+        assert_eq!(before.fuel_consumed, 0);
+        assert_eq!(before.host_calls, 0);
+
+        let result = instance.call_typed("grow", &[]).unwrap();
+        assert_eq!(result, vec![WasmValue::I32(2)]);
+
+        let after = instance.resource_usage();
+        assert_eq!(after.memory_pages, 3);
+        assert_eq!(after.peak_memory_pages, 3);
+        assert_eq!(after.memory_bytes(), 3 * crate::memory::PAGE_SIZE);
+        assert!(after.fuel_consumed >= 3);
+        assert_eq!(after.host_calls, 1);
+        assert_eq!(after.table_elements, 0);
+    }
+
+    #[test]
+    fn test_max_memory_pages_caps_memory64() {
+        let mut module = importing_module();
+        module.memories.push(MemoryType {
+            min: 65537,
+            max: None,
+            shared: false,
+            memory64: true,
+        });
+        let mut imports = Imports::new();
+        imports.add_function("env", "double", host_double);
+        let mut instance = Instance::new_with_imports(&module, imports).unwrap();
+
+        assert!(instance.set_max_memory_pages(256).is_err());
+        instance.set_max_memory_pages(65540).unwrap();
+        let memory = instance.memory_mut().unwrap();
+        assert_eq!(memory.max_pages(), Some(65540));
+        assert!(memory.grow(4).is_err());
+    }
+}
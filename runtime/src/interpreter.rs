@@ -13,8 +13,8 @@ use crate::parser::BlockType;
 /// Maximum value stack depth (in values).
 pub const MAX_VALUE_STACK_DEPTH: usize = 16384;
 
-/// Maximum call stack depth (frames).
-pub const MAX_CALL_STACK_DEPTH: usize = 1024;
+/// Default maximum call depth (frames), see [`crate::RuntimeConfig::max_call_depth`].
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
 
 // ============================================================================
 // WASM Values
@@ -294,8 +294,8 @@ pub enum TrapError {
     StackOverflow,
     /// Value stack underflow.
     StackUnderflow,
-    /// Call stack overflow (too deep recursion).
-    CallStackOverflow,
+    /// Call depth limit exceeded (too deep recursion).
+    CallStackExhausted,
     /// Unreachable instruction executed.
     Unreachable,
     /// Type mismatch.
@@ -343,9 +343,9 @@ impl core::fmt::Display for TrapError {
                     offset, size, memory_size
                 )
             }
-            TrapError::StackOverflow => write!(f, "call stack exhausted"),
+            TrapError::StackOverflow => write!(f, "value stack overflow"),
             TrapError::StackUnderflow => write!(f, "stack underflow"),
-            TrapError::CallStackOverflow => write!(f, "call stack overflow"),
+            TrapError::CallStackExhausted => write!(f, "call stack exhausted"),
             TrapError::Unreachable => write!(f, "unreachable"),
             TrapError::TypeMismatch { expected, got } => {
                 write!(f, "type mismatch: expected {}, got {:?}", expected, got)
@@ -379,18 +379,34 @@ impl core::fmt::Display for TrapError {
 /// Table for indirect function calls.
 #[derive(Debug, Clone)]
 pub struct Table {
-    /// Table elements (function indices, None = uninitialized).
+    /// Table elements (function indices or externref handles, None = null).
     pub elements: Vec<Option<u32>>,
     /// Maximum size.
     pub max: Option<u32>,
+    /// Reference type held by the table (`FuncRef` or `ExternRef`).
+    pub element_type: ValueType,
 }
 
 impl Table {
-    /// Create a new table.
+    /// Create a new funcref table.
     pub fn new(min: u32, max: Option<u32>) -> Self {
+        Self::with_element_type(ValueType::FuncRef, min, max)
+    }
+
+    /// Create a new table holding the given reference type.
+    pub fn with_element_type(element_type: ValueType, min: u32, max: Option<u32>) -> Self {
         let mut elements = Vec::with_capacity(min as usize);
         elements.resize(min as usize, None);
-        Table { elements, max }
+        Table {
+            elements,
+            max,
+            element_type,
+        }
+    }
+
+    /// Check if this table holds `externref` values.
+    pub fn is_extern(&self) -> bool {
+        self.element_type == ValueType::ExternRef
     }
 
     /// Get a table element.
@@ -401,6 +417,16 @@ impl Table {
             .ok_or(TrapError::UndefinedElement { index })
     }
 
+    /// Get a table element as a typed reference value.
+    pub fn get_value(&self, index: u32) -> Result<WasmValue, TrapError> {
+        let r = self.get(index)?;
+        Ok(if self.is_extern() {
+            WasmValue::ExternRef(r)
+        } else {
+            WasmValue::FuncRef(r)
+        })
+    }
+
     /// Set a table element.
     pub fn set(&mut self, index: u32, value: Option<u32>) -> Result<(), TrapError> {
         if index as usize >= self.elements.len() {
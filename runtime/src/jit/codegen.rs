@@ -3,12 +3,14 @@
 //! This module generates native x86-64 machine code from the IR representation.
 
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::ptr::NonNull;
 
 use super::compiler::CompilationError;
-use super::ir::{IrFunction, IrInstruction, IrOpcode, IrType};
+use super::cpu::{self, CpuFeatures};
+use super::ir::{BlockId, BlockKind, IrFunction, IrInstruction, IrOpcode, IrType};
 
 /// Generated native code.
 #[derive(Debug)]
@@ -142,7 +144,23 @@ pub enum XmmReg {
     Xmm15 = 15,
 }
 
+/// An open block, loop or if during code generation.
+#[derive(Debug, Clone, Copy)]
+struct Control {
+    kind: BlockKind,
+    /// Branch target: the loop header for loops, the end otherwise.
+    label: usize,
+    /// Start of the else arm of an `if` that has not reached it yet.
+    else_label: Option<usize>,
+    /// Values a branch to this block carries.
+    arity: usize,
+}
+
 /// Code generator for x86-64.
+///
+/// Values live on the machine stack, one 8-byte slot each. Below the
+/// locals the frame has one slot per nesting level holding the stack
+/// pointer at block entry, which branches restore before jumping.
 pub struct CodeGenerator {
     /// Generated code buffer.
     code: Vec<u8>,
@@ -152,66 +170,173 @@ pub struct CodeGenerator {
     labels: Vec<Option<usize>>,
     /// Pending label references.
     pending_labels: Vec<(usize, usize, i32)>, // (code_offset, label_idx, addend)
+    /// Open blocks, innermost last.
+    control: Vec<Control>,
+    /// Loop headers: (instruction index, label, nesting level).
+    loops: Vec<(usize, usize, usize)>,
+    /// Label of the shared epilogue.
+    epilogue: usize,
+    /// Params plus declared locals of the function being compiled.
+    total_locals: usize,
+    /// Optional CPU features the generated code may use.
+    features: CpuFeatures,
 }
 
 impl CodeGenerator {
-    /// Create a new code generator.
+    /// Create a new code generator for the host CPU.
     pub fn new() -> Self {
+        Self::with_features(cpu::features())
+    }
+
+    /// Create a code generator that only uses the given CPU features.
+    pub fn with_features(features: CpuFeatures) -> Self {
         Self {
             code: Vec::new(),
             stack_offset: 0,
             labels: Vec::new(),
             pending_labels: Vec::new(),
+            control: Vec::new(),
+            loops: Vec::new(),
+            epilogue: 0,
+            total_locals: 0,
+            features,
         }
     }
 
+    /// CPU features the generated code may use.
+    pub fn features(&self) -> CpuFeatures {
+        self.features
+    }
+
     /// Reset the generator for a new function.
     fn reset(&mut self) {
         self.code.clear();
         self.stack_offset = 0;
         self.labels.clear();
         self.pending_labels.clear();
+        self.control.clear();
+        self.loops.clear();
     }
 
     /// Generate baseline code (minimal optimization).
     pub fn generate_baseline(&self, ir: &IrFunction) -> Result<NativeCode, CompilationError> {
-        let mut gen = Self::new();
+        let mut gen = Self::with_features(self.features);
         gen.compile_function(ir, false)
     }
 
     /// Generate optimized code.
     pub fn generate_optimized(&self, ir: &IrFunction) -> Result<NativeCode, CompilationError> {
-        let mut gen = Self::new();
+        let mut gen = Self::with_features(self.features);
         gen.compile_function(ir, true)
     }
 
+    /// Generate code that enters `ir` at the header of the loop starting
+    /// at instruction `loop_pc`, for on-stack replacement.
+    ///
+    /// The entry takes a pointer to the values of all locals (params
+    /// first) at the loop header and runs the rest of the function. The
+    /// value stack must be empty there. Normal entries take a pointer to
+    /// the params.
+    pub fn generate_osr(
+        &self,
+        ir: &IrFunction,
+        loop_pc: usize,
+    ) -> Result<NativeCode, CompilationError> {
+        let mut gen = Self::with_features(self.features);
+        let frame_size = gen.emit_function(ir)?;
+        let (label, level) = gen
+            .loops
+            .iter()
+            .find(|&&(pc, _, _)| pc == loop_pc)
+            .map(|&(_, label, level)| (label, level))
+            .ok_or_else(|| {
+                CompilationError::CodeGen(format!("no loop at instruction {}", loop_pc))
+            })?;
+
+        let entry = gen.code.len();
+        gen.emit_prologue(frame_size as i32);
+        gen.emit_copy_locals(ir.total_locals());
+        // The enclosing blocks were entered with the same empty stack
+        for level in 0..level {
+            gen.emit_save_height(level);
+        }
+        gen.emit_jump(label);
+        gen.resolve_labels();
+
+        Ok(NativeCode::new(gen.code.clone(), entry, frame_size))
+    }
+
     /// Compile a function.
     fn compile_function(
         &mut self,
         ir: &IrFunction,
         _optimize: bool,
     ) -> Result<NativeCode, CompilationError> {
+        let frame_size = self.emit_function(ir)?;
+
+        // Resolve pending labels
+        self.resolve_labels();
+
+        Ok(NativeCode::new(self.code.clone(), 0, frame_size))
+    }
+
+    /// Emit the function's normal entry, body and epilogue, returning the
+    /// frame size.
+    fn emit_function(&mut self, ir: &IrFunction) -> Result<usize, CompilationError> {
         self.reset();
+        self.total_locals = ir.total_locals();
+        self.epilogue = self.new_label();
 
-        // Calculate frame size
-        let locals_size = ir.total_locals() * 8;
-        let frame_size = ((locals_size + 15) / 16) * 16; // 16-byte aligned
+        // Calculate frame size: locals, then one saved stack pointer per
+        // nesting level
+        let mut depth = 0usize;
+        let mut max_depth = 0;
+        for inst in &ir.body {
+            match inst.opcode {
+                IrOpcode::Block(_) | IrOpcode::Loop(_) | IrOpcode::If(_) => {
+                    depth += 1;
+                    max_depth = max_depth.max(depth);
+                }
+                IrOpcode::End => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        let slots_size = (self.total_locals + max_depth) * 8;
+        let frame_size = slots_size.div_ceil(16) * 16; // 16-byte aligned
 
-        // Function prologue
+        // Function prologue: params come in through rdi, the other locals
+        // start zeroed
         self.emit_prologue(frame_size as i32);
+        self.emit_copy_locals(ir.params.len());
+        if self.total_locals > ir.params.len() {
+            self.emit_bytes(&[0x31, 0xC0]); // xor eax, eax
+            for idx in ir.params.len()..self.total_locals {
+                let offset = self.local_offset(idx as u32, ir);
+                self.emit_store_local(offset);
+            }
+        }
 
         // Compile each IR instruction
-        for inst in &ir.body {
+        for (pc, inst) in ir.body.iter().enumerate() {
+            if let IrOpcode::Loop(_) = inst.opcode {
+                self.loops.push((pc, self.labels.len(), self.control.len()));
+            }
             self.compile_instruction(inst, ir)?;
         }
 
+        // Close blocks left open by a missing `end`
+        while let Some(block) = self.control.pop() {
+            self.close_block(block);
+        }
+
         // Function epilogue (implicit return)
+        if !ir.results.is_empty() {
+            self.emit_byte(0x58); // pop rax (return value)
+        }
+        self.bind_label(self.epilogue);
         self.emit_epilogue(frame_size as i32);
 
-        // Resolve pending labels
-        self.resolve_labels();
-
-        Ok(NativeCode::new(self.code.clone(), 0, frame_size))
+        Ok(frame_size)
     }
 
     /// Emit function prologue.
@@ -236,13 +361,8 @@ impl CodeGenerator {
     /// Emit function epilogue.
     fn emit_epilogue(&mut self, frame_size: i32) {
         if frame_size > 0 {
-            // add rsp, frame_size
-            if frame_size <= 127 {
-                self.emit_bytes(&[0x48, 0x83, 0xC4, frame_size as u8]);
-            } else {
-                self.emit_bytes(&[0x48, 0x81, 0xC4]);
-                self.emit_i32(frame_size);
-            }
+            // mov rsp, rbp (also drops values a return left behind)
+            self.emit_bytes(&[0x48, 0x89, 0xEC]);
         }
 
         // pop rbp
@@ -488,28 +608,43 @@ impl CodeGenerator {
                 self.emit_bytes(&[0x48, 0xD3, 0xC8]); // ror rax, cl
                 self.emit_byte(0x50); // push rax
             }
+            IrOpcode::I64Clz if self.features.lzcnt => {
+                self.emit_byte(0x58); // pop rax
+                self.emit_bytes(&[0xF3, 0x48, 0x0F, 0xBD, 0xC0]); // lzcnt rax, rax
+                self.emit_byte(0x50); // push rax
+            }
             IrOpcode::I64Clz => {
                 self.emit_byte(0x58); // pop rax
                 // Use bsr + xor trick for clz (no LZCNT dependency)
                 // test rax, rax; jz zero_case; bsr rcx, rax; xor rcx, 63; jmp done; zero_case: mov ecx, 64; done:
                 self.emit_bytes(&[0x48, 0x85, 0xC0]); // test rax, rax
-                self.emit_bytes(&[0x74, 0x09]); // jz +9
+                self.emit_bytes(&[0x74, 0x0A]); // jz +10
                 self.emit_bytes(&[0x48, 0x0F, 0xBD, 0xC8]); // bsr rcx, rax
                 self.emit_bytes(&[0x48, 0x83, 0xF1, 0x3F]); // xor rcx, 63
                 self.emit_bytes(&[0xEB, 0x05]); // jmp +5
                 self.emit_bytes(&[0xB9, 0x40, 0x00, 0x00, 0x00]); // mov ecx, 64
                 self.emit_byte(0x51); // push rcx
             }
+            IrOpcode::I64Ctz if self.features.bmi1 => {
+                self.emit_byte(0x58); // pop rax
+                self.emit_bytes(&[0xF3, 0x48, 0x0F, 0xBC, 0xC0]); // tzcnt rax, rax
+                self.emit_byte(0x50); // push rax
+            }
             IrOpcode::I64Ctz => {
                 self.emit_byte(0x58); // pop rax
                 // test rax, rax; jz zero_case; bsf rcx, rax; jmp done; zero_case: mov ecx, 64; done:
                 self.emit_bytes(&[0x48, 0x85, 0xC0]); // test rax, rax
-                self.emit_bytes(&[0x74, 0x07]); // jz +7
+                self.emit_bytes(&[0x74, 0x06]); // jz +6
                 self.emit_bytes(&[0x48, 0x0F, 0xBC, 0xC8]); // bsf rcx, rax
                 self.emit_bytes(&[0xEB, 0x05]); // jmp +5
                 self.emit_bytes(&[0xB9, 0x40, 0x00, 0x00, 0x00]); // mov ecx, 64
                 self.emit_byte(0x51); // push rcx
             }
+            IrOpcode::I64Popcnt if self.features.popcnt => {
+                self.emit_byte(0x58); // pop rax
+                self.emit_bytes(&[0xF3, 0x48, 0x0F, 0xB8, 0xC0]); // popcnt rax, rax
+                self.emit_byte(0x50); // push rax
+            }
             IrOpcode::I64Popcnt => {
                 self.emit_byte(0x58); // pop rax
                 // Software popcnt: parallel bit counting
@@ -592,6 +727,11 @@ impl CodeGenerator {
                 self.emit_bytes(&[0xD3, 0xC8]); // ror eax, cl
                 self.emit_byte(0x50); // push rax
             }
+            IrOpcode::I32Clz if self.features.lzcnt => {
+                self.emit_byte(0x58); // pop rax
+                self.emit_bytes(&[0xF3, 0x0F, 0xBD, 0xC0]); // lzcnt eax, eax
+                self.emit_byte(0x50); // push rax
+            }
             IrOpcode::I32Clz => {
                 self.emit_byte(0x58); // pop rax
                 self.emit_bytes(&[0x85, 0xC0]); // test eax, eax
@@ -602,15 +742,25 @@ impl CodeGenerator {
                 self.emit_bytes(&[0xB9, 0x20, 0x00, 0x00, 0x00]); // mov ecx, 32
                 self.emit_byte(0x51); // push rcx
             }
+            IrOpcode::I32Ctz if self.features.bmi1 => {
+                self.emit_byte(0x58); // pop rax
+                self.emit_bytes(&[0xF3, 0x0F, 0xBC, 0xC0]); // tzcnt eax, eax
+                self.emit_byte(0x50); // push rax
+            }
             IrOpcode::I32Ctz => {
                 self.emit_byte(0x58); // pop rax
                 self.emit_bytes(&[0x85, 0xC0]); // test eax, eax
-                self.emit_bytes(&[0x74, 0x06]); // jz +6
+                self.emit_bytes(&[0x74, 0x05]); // jz +5
                 self.emit_bytes(&[0x0F, 0xBC, 0xC8]); // bsf ecx, eax
                 self.emit_bytes(&[0xEB, 0x05]); // jmp +5
                 self.emit_bytes(&[0xB9, 0x20, 0x00, 0x00, 0x00]); // mov ecx, 32
                 self.emit_byte(0x51); // push rcx
             }
+            IrOpcode::I32Popcnt if self.features.popcnt => {
+                self.emit_byte(0x58); // pop rax
+                self.emit_bytes(&[0xF3, 0x0F, 0xB8, 0xC0]); // popcnt eax, eax
+                self.emit_byte(0x50); // push rax
+            }
             IrOpcode::I32Popcnt => {
                 self.emit_byte(0x58); // pop rax
                 // Software popcnt (32-bit Hamming weight)
@@ -1030,6 +1180,16 @@ impl CodeGenerator {
                 self.emit_byte(0x50); // push rax
             }
 
+            // Conversions - saturating float to int
+            IrOpcode::I32TruncSatF32S => self.emit_trunc_sat(false, true, false),
+            IrOpcode::I32TruncSatF32U => self.emit_trunc_sat(false, false, false),
+            IrOpcode::I32TruncSatF64S => self.emit_trunc_sat(true, true, false),
+            IrOpcode::I32TruncSatF64U => self.emit_trunc_sat(true, false, false),
+            IrOpcode::I64TruncSatF32S => self.emit_trunc_sat(false, true, true),
+            IrOpcode::I64TruncSatF32U => self.emit_trunc_sat(false, false, true),
+            IrOpcode::I64TruncSatF64S => self.emit_trunc_sat(true, true, true),
+            IrOpcode::I64TruncSatF64U => self.emit_trunc_sat(true, false, true),
+
             // Conversions - float to float
             IrOpcode::F32DemoteF64 => {
                 self.emit_byte(0x58); // pop rax
@@ -1086,25 +1246,46 @@ impl CodeGenerator {
             }
 
             // Control flow - BrIf
-            IrOpcode::BrIf(_depth) => {
+            IrOpcode::BrIf(depth) => {
                 // Pop condition, conditionally branch
                 self.emit_byte(0x58); // pop rax (condition)
                 self.emit_bytes(&[0x85, 0xC0]); // test eax, eax
-                // jnz rel32 (placeholder)
-                self.emit_bytes(&[0x0F, 0x85]);
-                self.emit_i32(0); // placeholder
-            }
-            IrOpcode::BrTable(table_idx) => {
-                // Pop index, branch to target
-                self.emit_byte(0x58); // pop rax (index)
-                // For now, emit a simple bounds check + indirect jump placeholder
-                // The actual table dispatch will be patched by the runtime
-                self.emit_bytes(&[0x0F, 0x0B]); // ud2 (placeholder - table dispatch)
-            }
+                let skip = self.emit_jump8(0x74); // jz skip
+                self.emit_branch(depth, ir);
+                self.patch_jump8(skip);
+            }
+            IrOpcode::BrTable(table_idx) => match ir.branch_tables.get(table_idx as usize) {
+                Some(targets) if !targets.is_empty() => {
+                    self.emit_byte(0x59); // pop rcx (index)
+                    let (default, targets) = targets.split_last().unwrap();
+                    for (i, &depth) in targets.iter().enumerate() {
+                        // cmp ecx, i
+                        if i <= 127 {
+                            self.emit_bytes(&[0x83, 0xF9, i as u8]);
+                        } else {
+                            self.emit_bytes(&[0x81, 0xF9]);
+                            self.emit_i32(i as i32);
+                        }
+                        let skip = self.emit_jump8(0x75); // jne skip
+                        self.emit_branch(depth, ir);
+                        self.patch_jump8(skip);
+                    }
+                    self.emit_branch(*default, ir);
+                }
+                _ => {
+                    self.emit_byte(0x58); // pop rax (index)
+                    self.emit_bytes(&[0x0F, 0x0B]); // ud2 (no such table)
+                }
+            },
             IrOpcode::Else => {
-                // Jump to end of if-else, mark else label position
-                self.emit_byte(0xE9); // jmp rel32
-                self.emit_i32(0); // placeholder
+                // Jump past the else arm, which starts here
+                if let Some(block) = self.control.last_mut() {
+                    if let Some(else_label) = block.else_label.take() {
+                        let end = block.label;
+                        self.emit_jump(end);
+                        self.bind_label(else_label);
+                    }
+                }
             }
             IrOpcode::CallIndirect(_type_idx) => {
                 // Pop function index, validate type, call
@@ -1263,13 +1444,12 @@ impl CodeGenerator {
             }
 
             // Control flow
-            IrOpcode::Return => {
-                if !ir.results.is_empty() {
-                    self.emit_byte(0x58); // pop rax (return value)
-                }
-            }
+            IrOpcode::Return => self.emit_return(ir),
             IrOpcode::End => {
-                // Block/loop end - resolve labels
+                // The function's own `end` has no block to close
+                if let Some(block) = self.control.pop() {
+                    self.close_block(block);
+                }
             }
             IrOpcode::Drop => {
                 self.emit_bytes(&[0x48, 0x83, 0xC4, 0x08]); // add rsp, 8
@@ -1282,28 +1462,28 @@ impl CodeGenerator {
                 self.emit_bytes(&[0x48, 0x0F, 0x44, 0xCA]); // cmovz rcx, rdx
                 self.emit_byte(0x51); // push rcx
             }
-            IrOpcode::Block(_) => {
-                let _label = self.labels.len();
-                self.labels.push(None);
+            IrOpcode::Block(block_id) => {
+                let arity = self.block_arity(ir, block_id)?;
+                let label = self.new_label();
+                self.open_block(BlockKind::Block, label, None, arity);
             }
             IrOpcode::Loop(_) => {
-                let _label = self.labels.len();
-                self.labels.push(Some(self.code.len()));
+                // Branches to a loop carry no values
+                let label = self.new_label();
+                self.bind_label(label);
+                self.open_block(BlockKind::Loop, label, None, 0);
             }
-            IrOpcode::If(_) => {
+            IrOpcode::If(block_id) => {
+                let arity = self.block_arity(ir, block_id)?;
                 self.emit_byte(0x58); // pop rax
+                let label = self.new_label();
+                let else_label = self.new_label();
+                self.open_block(BlockKind::If, label, Some(else_label), arity);
                 self.emit_bytes(&[0x85, 0xC0]); // test eax, eax
                 self.emit_bytes(&[0x0F, 0x84]); // jz rel32
-                let label = self.labels.len();
-                self.labels.push(None);
-                let patch_offset = self.code.len();
-                self.emit_i32(0); // placeholder
-                self.pending_labels.push((patch_offset, label, 0));
-            }
-            IrOpcode::Br(_depth) => {
-                self.emit_byte(0xE9); // jmp rel32
-                self.emit_i32(0); // placeholder
+                self.emit_label_ref(else_label);
             }
+            IrOpcode::Br(depth) => self.emit_branch(depth, ir),
             IrOpcode::Call(_func_idx) => {
                 self.emit_byte(0xE8); // call rel32
                 self.emit_i32(0); // placeholder
@@ -1333,6 +1513,116 @@ impl CodeGenerator {
         Ok(())
     }
 
+    /// Values a branch to `block_id` carries, from its block type.
+    fn block_arity(&self, ir: &IrFunction, block_id: BlockId) -> Result<usize, CompilationError> {
+        let arity = ir
+            .blocks
+            .get(&block_id)
+            .map_or(0, |info| info.results.len());
+        if arity > 1 {
+            return Err(CompilationError::CodeGen(String::from(
+                "multi-value blocks are not supported",
+            )));
+        }
+        Ok(arity)
+    }
+
+    /// Enter a block, saving the stack pointer for branches out of it.
+    fn open_block(
+        &mut self,
+        kind: BlockKind,
+        label: usize,
+        else_label: Option<usize>,
+        arity: usize,
+    ) {
+        self.emit_save_height(self.control.len());
+        self.control.push(Control {
+            kind,
+            label,
+            else_label,
+            arity,
+        });
+    }
+
+    /// Bind the labels of a block that ends here.
+    fn close_block(&mut self, block: Control) {
+        if let Some(else_label) = block.else_label {
+            self.bind_label(else_label);
+        }
+        if block.kind != BlockKind::Loop {
+            self.bind_label(block.label);
+        }
+    }
+
+    /// Branch to the block `depth` levels out, or return if that is the
+    /// function itself.
+    fn emit_branch(&mut self, depth: u32, ir: &IrFunction) {
+        let Some(level) = self.control.len().checked_sub(depth as usize + 1) else {
+            self.emit_return(ir);
+            return;
+        };
+        let target = self.control[level];
+        if target.arity > 0 {
+            self.emit_byte(0x58); // pop rax (branch value)
+        }
+        self.emit_restore_height(level);
+        if target.arity > 0 {
+            self.emit_byte(0x50); // push rax
+        }
+        self.emit_jump(target.label);
+    }
+
+    /// Return from the function.
+    fn emit_return(&mut self, ir: &IrFunction) {
+        if !ir.results.is_empty() {
+            self.emit_byte(0x58); // pop rax (return value)
+        }
+        self.emit_jump(self.epilogue);
+    }
+
+    /// Copy the first `count` locals from the array rdi points to.
+    fn emit_copy_locals(&mut self, count: usize) {
+        for idx in 0..count {
+            // mov rax, [rdi + idx * 8]
+            let disp = idx as i32 * 8;
+            if disp <= 127 {
+                self.emit_bytes(&[0x48, 0x8B, 0x47, disp as u8]);
+            } else {
+                self.emit_bytes(&[0x48, 0x8B, 0x87]);
+                self.emit_i32(disp);
+            }
+            self.emit_store_local(-(idx as i32 + 1) * 8);
+        }
+    }
+
+    /// Offset from rbp of the stack pointer saved at entry to the block
+    /// at nesting `level`.
+    fn height_offset(&self, level: usize) -> i32 {
+        -((self.total_locals + level + 1) as i32) * 8
+    }
+
+    /// Emit `mov [rbp + slot], rsp` for nesting `level`.
+    fn emit_save_height(&mut self, level: usize) {
+        let offset = self.height_offset(level);
+        self.emit_rbp_operand(0x89, offset);
+    }
+
+    /// Emit `mov rsp, [rbp + slot]` for nesting `level`.
+    fn emit_restore_height(&mut self, level: usize) {
+        let offset = self.height_offset(level);
+        self.emit_rbp_operand(0x8B, offset);
+    }
+
+    /// Emit a 64-bit `mov` between rsp and `[rbp + offset]`.
+    fn emit_rbp_operand(&mut self, opcode: u8, offset: i32) {
+        if offset >= -128 {
+            self.emit_bytes(&[0x48, opcode, 0x65, offset as u8]);
+        } else {
+            self.emit_bytes(&[0x48, opcode, 0xA5]);
+            self.emit_i32(offset);
+        }
+    }
+
     /// Calculate local variable offset from rbp.
     fn local_offset(&self, idx: u32, ir: &IrFunction) -> i32 {
         // Locals are stored at [rbp - 8], [rbp - 16], etc.
@@ -1503,6 +1793,121 @@ impl CodeGenerator {
         self.emit_byte(0x50); // push rax
     }
 
+    /// Emit a saturating float-to-int truncation (`trunc_sat`).
+    ///
+    /// `cvtt*2si` yields the "integer indefinite" value for NaN and out of
+    /// range inputs, so those are filtered out first: NaN gives 0 and
+    /// values at or beyond the target range clamp to its bounds. An i32
+    /// result is zero-extended like the other i32 conversions.
+    fn emit_trunc_sat(&mut self, double: bool, signed: bool, wide: bool) {
+        // Scalar single/double prefixes for cvtt*2si/sub*, and the operand
+        // size prefix that turns ucomiss into ucomisd.
+        let scalar = if double { 0xF2 } else { 0xF3 };
+        let ucomis: &[u8] = if double {
+            &[0x66, 0x0F, 0x2E]
+        } else {
+            &[0x0F, 0x2E]
+        };
+        let float_bits = |v: f64| {
+            if double {
+                v.to_bits()
+            } else {
+                (v as f32).to_bits() as u64
+            }
+        };
+        const TWO_31: f64 = 2147483648.0;
+        const TWO_63: f64 = 9223372036854775808.0;
+        let (min, max, lower, upper): (i64, i64, f64, f64) = match (signed, wide) {
+            (true, false) => (0x8000_0000, 0x7FFF_FFFF, -TWO_31, TWO_31),
+            (false, false) => (0, 0xFFFF_FFFF, 0.0, 2.0 * TWO_31),
+            (true, true) => (i64::MIN, i64::MAX, -TWO_63, TWO_63),
+            (false, true) => (0, -1, 0.0, 2.0 * TWO_63),
+        };
+
+        self.emit_byte(0x58); // pop rax
+        self.emit_bytes(&[0x66, 0x48, 0x0F, 0x6E, 0xC0]); // movq xmm0, rax
+        self.emit_bytes(&[0x31, 0xC9]); // xor ecx, ecx
+        self.emit_bytes(ucomis);
+        self.emit_byte(0xC0); // ucomis xmm0, xmm0
+        let mut to_done = Vec::new();
+        to_done.push(self.emit_jump8(0x7A)); // jp done (NaN)
+
+        for (result, bound, jcc) in [(max, upper, 0x73), (min, lower, 0x76)] {
+            self.emit_bytes(&[0x48, 0xB9]); // mov rcx, result
+            self.emit_i64(result);
+            self.emit_bytes(&[0x48, 0xBA]); // mov rdx, bound
+            self.emit_i64(float_bits(bound) as i64);
+            self.emit_bytes(&[0x66, 0x48, 0x0F, 0x6E, 0xCA]); // movq xmm1, rdx
+            self.emit_bytes(ucomis);
+            self.emit_byte(0xC1); // ucomis xmm0, xmm1
+            to_done.push(self.emit_jump8(jcc)); // jae/jbe done
+        }
+
+        if !signed && wide {
+            // [2^63, 2^64) is out of reach of the signed conversion:
+            // convert x - 2^63 and set the top bit
+            self.emit_bytes(&[0x48, 0xBA]); // mov rdx, 2^63
+            self.emit_i64(float_bits(TWO_63) as i64);
+            self.emit_bytes(&[0x66, 0x48, 0x0F, 0x6E, 0xCA]); // movq xmm1, rdx
+            self.emit_bytes(ucomis);
+            self.emit_byte(0xC1); // ucomis xmm0, xmm1
+            let to_small = self.emit_jump8(0x72); // jb small
+            self.emit_bytes(&[scalar, 0x0F, 0x5C, 0xC1]); // subs xmm0, xmm1
+            self.emit_bytes(&[scalar, 0x48, 0x0F, 0x2C, 0xC8]); // cvtts2si rcx, xmm0
+            self.emit_bytes(&[0x48, 0x0F, 0xBA, 0xF9, 0x3F]); // btc rcx, 63
+            to_done.push(self.emit_jump8(0xEB)); // jmp done
+            self.patch_jump8(to_small);
+        }
+
+        if signed && !wide {
+            self.emit_bytes(&[scalar, 0x0F, 0x2C, 0xC8]); // cvtts2si ecx, xmm0
+        } else {
+            self.emit_bytes(&[scalar, 0x48, 0x0F, 0x2C, 0xC8]); // cvtts2si rcx, xmm0
+        }
+        for jump in to_done {
+            self.patch_jump8(jump);
+        }
+        self.emit_byte(0x51); // push rcx
+    }
+
+    /// Emit a short jump with a placeholder displacement, returning the
+    /// displacement's offset for [`Self::patch_jump8`].
+    fn emit_jump8(&mut self, opcode: u8) -> usize {
+        self.emit_bytes(&[opcode, 0x00]);
+        self.code.len() - 1
+    }
+
+    /// Point a short jump emitted by [`Self::emit_jump8`] at the current
+    /// position.
+    fn patch_jump8(&mut self, at: usize) {
+        let rel = self.code.len() - (at + 1);
+        debug_assert!(rel <= i8::MAX as usize);
+        self.code[at] = rel as u8;
+    }
+
+    /// Allocate an unbound label.
+    fn new_label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    /// Bind a label to the current position.
+    fn bind_label(&mut self, label: usize) {
+        self.labels[label] = Some(self.code.len());
+    }
+
+    /// Emit a rel32 displacement to a label, resolved at the end.
+    fn emit_label_ref(&mut self, label: usize) {
+        self.pending_labels.push((self.code.len(), label, 0));
+        self.emit_i32(0); // placeholder
+    }
+
+    /// Emit `jmp label`.
+    fn emit_jump(&mut self, label: usize) {
+        self.emit_byte(0xE9); // jmp rel32
+        self.emit_label_ref(label);
+    }
+
     /// Resolve pending label references.
     fn resolve_labels(&mut self) {
         for (offset, label_idx, addend) in &self.pending_labels {
@@ -1536,6 +1941,45 @@ impl Default for CodeGenerator {
     }
 }
 
+/// Run `code` on the host CPU, passing `args` as the params (or, for an
+/// OSR entry, all locals).
+#[cfg(all(test, target_arch = "x86_64", target_os = "linux"))]
+pub(crate) fn run_native(code: &NativeCode, args: &[i64]) -> i64 {
+    extern "C" {
+        fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, off: i64) -> *mut u8;
+        fn mprotect(addr: *mut u8, len: usize, prot: i32) -> i32;
+        fn munmap(addr: *mut u8, len: usize) -> i32;
+    }
+    const PROT_READ: i32 = 1;
+    const PROT_WRITE: i32 = 2;
+    const PROT_EXEC: i32 = 4;
+    const MAP_PRIVATE_ANONYMOUS: i32 = 0x22;
+
+    // SAFETY: the mapping is fresh and private, sized to the code, and
+    // only made executable once the copy is done. The code follows the
+    // System V ABI with a pointer to `args`, which holds every value it
+    // reads and outlives the call; the page is unmapped after it returns.
+    unsafe {
+        let len = code.size();
+        let page = mmap(
+            core::ptr::null_mut(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE_ANONYMOUS,
+            -1,
+            0,
+        );
+        assert!(!page.is_null() && page as isize != -1, "mmap failed");
+        core::ptr::copy_nonoverlapping(code.code().as_ptr(), page, len);
+        assert_eq!(mprotect(page, len, PROT_READ | PROT_EXEC), 0);
+        let entry: extern "sysv64" fn(*const i64) -> i64 =
+            core::mem::transmute(page.add(code.entry_offset()));
+        let result = entry(args.as_ptr());
+        munmap(page, len);
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2538,4 +2982,271 @@ mod tests {
         ]);
         assert!(code.size() > 0);
     }
+
+    // ====== CPU feature dispatch ======
+
+    const I32_SAMPLES: [u32; 6] = [0, 1, 0x8000_0000, 0x00FF_0000, 0xFFFF_FFFF, 0x1234_5678];
+    const I64_SAMPLES: [u64; 6] = [
+        0,
+        1,
+        0x8000_0000_0000_0000,
+        0x00FF_0000_0000_0000,
+        u64::MAX,
+        0x1234_5678_9ABC_DEF0,
+    ];
+
+    /// Compile `arg; op; return` and run it on the host CPU.
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn run_unary(features: CpuFeatures, arg: IrOpcode, op: IrOpcode) -> u64 {
+        let mut func = IrFunction::new(0, vec![], vec![IrType::I64]);
+        for op in [arg, op, IrOpcode::Return] {
+            func.add_instruction(IrInstruction::new(op, 0));
+        }
+        let code = CodeGenerator::with_features(features)
+            .generate_baseline(&func)
+            .expect("compilation failed");
+        run_native(&code, &[]) as u64
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn check_bit_ops(features: CpuFeatures) {
+        for v in I32_SAMPLES {
+            for op in [IrOpcode::I32Clz, IrOpcode::I32Ctz, IrOpcode::I32Popcnt] {
+                let expected = match op {
+                    IrOpcode::I32Clz => v.leading_zeros(),
+                    IrOpcode::I32Ctz => v.trailing_zeros(),
+                    _ => v.count_ones(),
+                };
+                let result = run_unary(features, IrOpcode::Const32(v as i32), op);
+                assert_eq!(result, expected as u64, "{:?}({:#x})", op, v);
+            }
+        }
+        for v in I64_SAMPLES {
+            for op in [IrOpcode::I64Clz, IrOpcode::I64Ctz, IrOpcode::I64Popcnt] {
+                let expected = match op {
+                    IrOpcode::I64Clz => v.leading_zeros(),
+                    IrOpcode::I64Ctz => v.trailing_zeros(),
+                    _ => v.count_ones(),
+                };
+                let result = run_unary(features, IrOpcode::Const64(v as i64), op);
+                assert_eq!(result, expected as u64, "{:?}({:#x})", op, v);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_bit_ops_fallback_results() {
+        check_bit_ops(CpuFeatures::baseline());
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_bit_ops_host_features_results() {
+        check_bit_ops(cpu::features());
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_trunc_sat_results() {
+        use IrOpcode::*;
+        let f32 = |v: f32| ConstF32(v.to_bits());
+        let f64 = |v: f64| ConstF64(v.to_bits());
+        // Reference values from the spec's conversions.wast; i32 results
+        // are zero-extended
+        let cases = [
+            (I32TruncSatF32S, f32(-1.5), 0xFFFF_FFFF),
+            (I32TruncSatF32S, f32(2147483520.0), 2147483520),
+            (I32TruncSatF32S, f32(2147483648.0), 0x7FFF_FFFF),
+            (I32TruncSatF32S, f32(-2147483904.0), 0x8000_0000),
+            (I32TruncSatF32S, f32(f32::NAN), 0),
+            (I32TruncSatF32U, f32(4294967040.0), 0xFFFF_FF00),
+            (I32TruncSatF32U, f32(-0.9), 0),
+            (I32TruncSatF32U, f32(f32::INFINITY), 0xFFFF_FFFF),
+            (I32TruncSatF64S, f64(-2147483648.9), 0x8000_0000),
+            (I32TruncSatF64S, f64(2147483647.9), 0x7FFF_FFFF),
+            (I32TruncSatF64S, f64(f64::NEG_INFINITY), 0x8000_0000),
+            (I32TruncSatF64U, f64(1e16), 0xFFFF_FFFF),
+            (I32TruncSatF64U, f64(-f64::NAN), 0),
+            (
+                I64TruncSatF32S,
+                f32(-9223373136366403584.0),
+                i64::MIN as u64,
+            ),
+            (
+                I64TruncSatF32S,
+                f32(9223371487098961920.0),
+                9223371487098961920,
+            ),
+            (
+                I64TruncSatF32U,
+                f32(18446742974197923840.0),
+                0xFFFF_FF00_0000_0000,
+            ),
+            (I64TruncSatF32U, f32(f32::INFINITY), u64::MAX),
+            (I64TruncSatF64S, f64(9223372036854775808.0), i64::MAX as u64),
+            (I64TruncSatF64S, f64(-1.9), -1i64 as u64),
+            (
+                I64TruncSatF64U,
+                f64(18446744073709549568.0),
+                18446744073709549568,
+            ),
+            (I64TruncSatF64U, f64(9223372036854775808.0), 1 << 63),
+            (I64TruncSatF64U, f64(1e8), 100_000_000),
+            (I64TruncSatF64U, f64(-0.0), 0),
+            (I64TruncSatF64U, f64(f64::NAN), 0),
+        ];
+        for (op, arg, expected) in cases {
+            let result = run_unary(CpuFeatures::baseline(), arg, op);
+            assert_eq!(result, expected, "{:?}({:?})", op, arg);
+        }
+    }
+
+    #[test]
+    fn test_feature_dispatch_encoding() {
+        let body = || {
+            let mut func = IrFunction::new(0, vec![], vec![]);
+            for op in [
+                IrOpcode::Const32(1),
+                IrOpcode::I32Clz,
+                IrOpcode::I32Ctz,
+                IrOpcode::I32Popcnt,
+                IrOpcode::Drop,
+            ] {
+                func.add_instruction(IrInstruction::new(op, 0));
+            }
+            func
+        };
+        let listing = |features| {
+            let code = CodeGenerator::with_features(features)
+                .generate_baseline(&body())
+                .expect("compilation failed");
+            crate::jit::disasm::disasm(&code)
+        };
+
+        let native = listing(CpuFeatures::all());
+        assert!(native.contains("lzcnt eax, eax"), "{}", native);
+        assert!(native.contains("tzcnt eax, eax"), "{}", native);
+        assert!(native.contains("popcnt eax, eax"), "{}", native);
+
+        let fallback = listing(CpuFeatures::baseline());
+        assert!(fallback.contains("bsr ecx, eax"), "{}", fallback);
+        assert!(fallback.contains("bsf ecx, eax"), "{}", fallback);
+        assert!(!fallback.contains("cnt"), "{}", fallback);
+    }
+
+    // ====== Structured control flow ======
+
+    /// `sum = 0 + 1 + ... + (n - 1)` with the loop at instruction 1.
+    fn sum_loop_ir() -> IrFunction {
+        use IrOpcode::*;
+        let mut func = IrFunction::new(0, vec![IrType::I32], vec![IrType::I32]);
+        func.add_local(IrType::I32); // sum
+        func.add_local(IrType::I32); // i
+        for op in [
+            Block(BlockId(0)),
+            Loop(BlockId(1)),
+            LocalGet(2),
+            LocalGet(0),
+            I32GeS,
+            BrIf(1),
+            LocalGet(1),
+            LocalGet(2),
+            I32Add,
+            LocalSet(1),
+            LocalGet(2),
+            Const32(1),
+            I32Add,
+            LocalSet(2),
+            Br(0),
+            End,
+            End,
+            LocalGet(1),
+        ] {
+            func.add_instruction(IrInstruction::new(op, 0));
+        }
+        func
+    }
+
+    #[test]
+    fn test_control_flow_listing_decodes() {
+        let code = CodeGenerator::new()
+            .generate_baseline(&sum_loop_ir())
+            .expect("compilation failed");
+        let listing = crate::jit::disasm::disasm(&code);
+        assert!(!listing.contains(".byte"), "{}", listing);
+        assert!(!listing.contains("ud2"), "{}", listing);
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_loop_results() {
+        let code = CodeGenerator::new()
+            .generate_baseline(&sum_loop_ir())
+            .expect("compilation failed");
+        for n in [0, 1, 10, 1000] {
+            assert_eq!(run_native(&code, &[n]) as i32, (n * (n - 1) / 2) as i32);
+        }
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_translated_branch_results() {
+        use crate::jit::ir::WasmToIr;
+        let run = |wasm: &[u8], arg: i64| {
+            let func = WasmToIr::new()
+                .translate_function(0, vec![IrType::I32], vec![IrType::I32], &[], wasm)
+                .expect("translation failed");
+            let code = CodeGenerator::new()
+                .generate_baseline(&func)
+                .expect("compilation failed");
+            run_native(&code, &[arg]) as i32
+        };
+
+        // block block block (br_table 0 1 2 (local.get 0)) end
+        // return 10 end return 11 end 12
+        let br_table = [
+            0x02, 0x40, 0x02, 0x40, 0x02, 0x40, 0x20, 0x00, 0x0E, 0x02, 0x00, 0x01, 0x02, 0x0B,
+            0x41, 0x0A, 0x0F, 0x0B, 0x41, 0x0B, 0x0F, 0x0B, 0x41, 0x0C, 0x0B,
+        ];
+        assert_eq!(run(&br_table, 0), 10);
+        assert_eq!(run(&br_table, 1), 11);
+        assert_eq!(run(&br_table, 2), 12);
+        assert_eq!(run(&br_table, 99), 12);
+
+        // block (result i32) 7 (br_if 0 (local.get 0)) drop 8 end
+        let br_if_value = [
+            0x02, 0x7F, 0x41, 0x07, 0x20, 0x00, 0x0D, 0x00, 0x1A, 0x41, 0x08, 0x0B, 0x0B,
+        ];
+        assert_eq!(run(&br_if_value, 1), 7);
+        assert_eq!(run(&br_if_value, 0), 8);
+
+        // if (result i32) (local.get 0) 1 else 2 end
+        let if_else = [
+            0x20, 0x00, 0x04, 0x7F, 0x41, 0x01, 0x05, 0x41, 0x02, 0x0B, 0x0B,
+        ];
+        assert_eq!(run(&if_else, 5), 1);
+        assert_eq!(run(&if_else, 0), 2);
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_osr_entry_resumes_loop() {
+        let func = sum_loop_ir();
+        let code = CodeGenerator::new()
+            .generate_osr(&func, 1)
+            .expect("compilation failed");
+        assert_ne!(code.entry_offset(), 0);
+
+        // Halfway through sum(100): sum(50) so far, i = 50
+        let locals = [100, 50 * 49 / 2, 50];
+        assert_eq!(run_native(&code, &locals), 100 * 99 / 2);
+        // Entering with the loop already finished just returns
+        assert_eq!(run_native(&code, &[3, 42, 3]), 42);
+
+        assert!(matches!(
+            CodeGenerator::new().generate_osr(&func, 0),
+            Err(CompilationError::CodeGen(_))
+        ));
+    }
 }
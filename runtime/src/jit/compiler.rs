@@ -19,6 +19,8 @@ pub enum CompilationError {
     BelowThreshold,
     /// AOT compilation is disabled.
     AotDisabled,
+    /// On-stack replacement is disabled.
+    OsrDisabled,
     /// Translation error.
     Translation(String),
     /// Code generation error.
@@ -101,6 +103,16 @@ impl JitCompiler {
         Ok(code)
     }
 
+    /// Compile already translated IR with an on-stack replacement entry
+    /// at the loop starting at instruction `loop_pc`.
+    pub fn compile_osr(
+        &self,
+        ir_func: &IrFunction,
+        loop_pc: usize,
+    ) -> Result<NativeCode, CompilationError> {
+        self.codegen.generate_osr(ir_func, loop_pc)
+    }
+
     /// Compile a function at optimized tier.
     pub fn compile_optimized(
         &self,
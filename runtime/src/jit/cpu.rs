@@ -0,0 +1,73 @@
+//! Host CPU Feature Detection
+//!
+//! The code generator only assumes the x86-64 baseline. Optional
+//! instructions are used when CPUID reports them; otherwise a baseline
+//! sequence with the same result is emitted, so generated code stays
+//! correct on older (virtual) CPUs.
+
+use spin::Once;
+
+/// Optional x86-64 features the code generator can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CpuFeatures {
+    /// `popcnt` (CPUID.01H:ECX bit 23).
+    pub popcnt: bool,
+    /// `lzcnt` (CPUID.80000001H:ECX bit 5, ABM).
+    pub lzcnt: bool,
+    /// BMI1, which provides `tzcnt` (CPUID.07H:EBX bit 3).
+    pub bmi1: bool,
+}
+
+impl CpuFeatures {
+    /// No optional features; every instruction uses the fallback sequence.
+    pub const fn baseline() -> Self {
+        Self {
+            popcnt: false,
+            lzcnt: false,
+            bmi1: false,
+        }
+    }
+
+    /// Every feature the code generator knows about.
+    pub const fn all() -> Self {
+        Self {
+            popcnt: true,
+            lzcnt: true,
+            bmi1: true,
+        }
+    }
+}
+
+/// Features of the host CPU, probed once with CPUID.
+pub fn features() -> CpuFeatures {
+    static FEATURES: Once<CpuFeatures> = Once::new();
+    *FEATURES.call_once(detect)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[allow(unused_unsafe)] // `__cpuid` is only a safe fn on newer toolchains
+fn detect() -> CpuFeatures {
+    use core::arch::x86_64::__cpuid;
+
+    let mut features = CpuFeatures::baseline();
+
+    let max_leaf = unsafe { __cpuid(0) }.eax;
+    if max_leaf >= 1 {
+        features.popcnt = unsafe { __cpuid(1) }.ecx & (1 << 23) != 0;
+    }
+    if max_leaf >= 7 {
+        features.bmi1 = unsafe { __cpuid(7) }.ebx & (1 << 3) != 0;
+    }
+
+    let max_ext_leaf = unsafe { __cpuid(0x8000_0000) }.eax;
+    if max_ext_leaf >= 0x8000_0001 {
+        features.lzcnt = unsafe { __cpuid(0x8000_0001) }.ecx & (1 << 5) != 0;
+    }
+
+    features
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn detect() -> CpuFeatures {
+    CpuFeatures::baseline()
+}
@@ -0,0 +1,992 @@
+//! Native Code Disassembler
+//!
+//! This module renders generated x86-64 machine code as Intel-syntax
+//! assembly for debugging the JIT. The decoder covers the instruction
+//! subset emitted by the code generator (integer ALU, shifts, moves,
+//! branches, setcc/cmovcc, and scalar SSE); anything else is rendered as
+//! a `.byte` directive and decoding resumes at the next byte.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use super::codegen::NativeCode;
+
+/// A single decoded instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Offset of the first byte within the code buffer.
+    pub offset: usize,
+    /// Encoded length in bytes.
+    pub len: usize,
+    /// Intel-syntax text (e.g. `mov rax, qword ptr [rbp-0x8]`).
+    pub text: String,
+}
+
+/// Disassemble generated native code.
+///
+/// Produces one line per instruction in the form
+/// `offset:  encoded bytes  mnemonic operands`.
+pub fn disasm(code: &NativeCode) -> String {
+    disasm_bytes(code.code())
+}
+
+/// Disassemble a raw x86-64 byte buffer.
+pub fn disasm_bytes(code: &[u8]) -> String {
+    let mut out = String::new();
+
+    for insn in decode(code) {
+        let mut hex = String::new();
+        for (i, byte) in code[insn.offset..insn.offset + insn.len].iter().enumerate() {
+            if i > 0 {
+                hex.push(' ');
+            }
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        let _ = writeln!(out, "{:04x}:  {:<30} {}", insn.offset, hex, insn.text);
+    }
+
+    out
+}
+
+/// Decode a byte buffer into instructions.
+pub fn decode(code: &[u8]) -> Vec<Instruction> {
+    let mut insns = Vec::new();
+    let mut offset = 0;
+
+    while offset < code.len() {
+        let mut decoder = Decoder::new(code, offset);
+        let (len, text) = match decoder.decode() {
+            Some(text) => (decoder.pos - offset, text),
+            None => (1, format!(".byte 0x{:02x}", code[offset])),
+        };
+
+        insns.push(Instruction { offset, len, text });
+        offset += len;
+    }
+
+    insns
+}
+
+const REG64: [&str; 16] = [
+    "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15",
+];
+
+const REG32: [&str; 16] = [
+    "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi", "r8d", "r9d", "r10d", "r11d", "r12d",
+    "r13d", "r14d", "r15d",
+];
+
+const REG16: [&str; 16] = [
+    "ax", "cx", "dx", "bx", "sp", "bp", "si", "di", "r8w", "r9w", "r10w", "r11w", "r12w", "r13w",
+    "r14w", "r15w",
+];
+
+const REG8_REX: [&str; 16] = [
+    "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil", "r8b", "r9b", "r10b", "r11b", "r12b",
+    "r13b", "r14b", "r15b",
+];
+
+const REG8_LEGACY: [&str; 8] = ["al", "cl", "dl", "bl", "ah", "ch", "dh", "bh"];
+
+const CONDITIONS: [&str; 16] = [
+    "o", "no", "b", "ae", "e", "ne", "be", "a", "s", "ns", "p", "np", "l", "ge", "le", "g",
+];
+
+const ALU_OPS: [&str; 8] = ["add", "or", "adc", "sbb", "and", "sub", "xor", "cmp"];
+
+const SHIFT_OPS: [&str; 8] = ["rol", "ror", "rcl", "rcr", "shl", "shr", "shl", "sar"];
+
+/// `cmpss`/`cmpsd` predicate names for immediates 0-7.
+const SSE_PREDICATES: [&str; 8] = ["eq", "lt", "le", "unord", "neq", "nlt", "nle", "ord"];
+
+/// Operand width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Width {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+    Xmm,
+}
+
+impl Width {
+    fn ptr(self) -> &'static str {
+        match self {
+            Width::Byte => "byte ptr ",
+            Width::Word => "word ptr ",
+            Width::Dword => "dword ptr ",
+            Width::Qword => "qword ptr ",
+            Width::Xmm => "xmmword ptr ",
+        }
+    }
+}
+
+/// Decoded r/m operand.
+enum RmOperand {
+    /// Register number (REX.B applied).
+    Reg(u8),
+    /// Memory address, already formatted as `[...]`.
+    Mem(String),
+}
+
+/// Decoded ModRM byte.
+struct ModRm {
+    /// Register field (REX.R applied).
+    reg: u8,
+    /// Register field without REX.R, used for opcode extensions.
+    ext: u8,
+    /// R/M operand.
+    rm: RmOperand,
+}
+
+/// Mandatory/legacy prefixes seen before the opcode.
+#[derive(Default)]
+struct Prefixes {
+    opsize: bool,
+    rep: bool,
+    repne: bool,
+    rex: Option<u8>,
+}
+
+impl Prefixes {
+    fn rex_w(&self) -> bool {
+        self.rex.is_some_and(|r| r & 0x08 != 0)
+    }
+
+    fn rex_r(&self) -> u8 {
+        self.rex.map_or(0, |r| (r & 0x04) << 1)
+    }
+
+    fn rex_x(&self) -> u8 {
+        self.rex.map_or(0, |r| (r & 0x02) << 2)
+    }
+
+    fn rex_b(&self) -> u8 {
+        self.rex.map_or(0, |r| (r & 0x01) << 3)
+    }
+
+    /// General-purpose operand width for non-byte instructions.
+    fn width(&self) -> Width {
+        if self.rex_w() {
+            Width::Qword
+        } else if self.opsize {
+            Width::Word
+        } else {
+            Width::Dword
+        }
+    }
+}
+
+/// Single-instruction decoder.
+struct Decoder<'a> {
+    code: &'a [u8],
+    pos: usize,
+    prefixes: Prefixes,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(code: &'a [u8], pos: usize) -> Self {
+        Self {
+            code,
+            pos,
+            prefixes: Prefixes::default(),
+        }
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        let b = *self.code.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn i8(&mut self) -> Option<i64> {
+        self.u8().map(|b| b as i8 as i64)
+    }
+
+    fn i32(&mut self) -> Option<i64> {
+        let bytes = self.code.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        Some(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let bytes = self.code.get(self.pos..self.pos + 8)?;
+        self.pos += 8;
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        Some(u64::from_le_bytes(buf))
+    }
+
+    /// Read an immediate sized for the current operand width
+    /// (imm16 with 0x66, otherwise imm32 sign-extended).
+    fn imm(&mut self) -> Option<i64> {
+        if self.prefixes.width() == Width::Word {
+            let bytes = self.code.get(self.pos..self.pos + 2)?;
+            self.pos += 2;
+            Some(i16::from_le_bytes([bytes[0], bytes[1]]) as i64)
+        } else {
+            self.i32()
+        }
+    }
+
+    fn decode(&mut self) -> Option<String> {
+        loop {
+            match *self.code.get(self.pos)? {
+                0x66 => self.prefixes.opsize = true,
+                0xF2 => self.prefixes.repne = true,
+                0xF3 => self.prefixes.rep = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        let b = self.u8()?;
+        let op = if (0x40..=0x4F).contains(&b) {
+            self.prefixes.rex = Some(b);
+            self.u8()?
+        } else {
+            b
+        };
+
+        if op == 0x0F {
+            let op2 = self.u8()?;
+            self.decode_0f(op2)
+        } else {
+            self.decode_primary(op)
+        }
+    }
+
+    fn modrm(&mut self) -> Option<ModRm> {
+        let byte = self.u8()?;
+        let md = byte >> 6;
+        let ext = (byte >> 3) & 7;
+        let reg = ext | self.prefixes.rex_r();
+        let rm = byte & 7;
+
+        if md == 3 {
+            return Some(ModRm {
+                reg,
+                ext,
+                rm: RmOperand::Reg(rm | self.prefixes.rex_b()),
+            });
+        }
+
+        let mut addr = String::from("[");
+        let mut has_base = true;
+
+        if rm == 4 {
+            let sib = self.u8()?;
+            let scale = 1u8 << (sib >> 6);
+            let index = ((sib >> 3) & 7) | self.prefixes.rex_x();
+            let base = sib & 7;
+
+            if base == 5 && md == 0 {
+                has_base = false;
+            } else {
+                addr.push_str(REG64[(base | self.prefixes.rex_b()) as usize]);
+            }
+
+            // index 0b100 without REX.X means "no index"
+            if index != 4 {
+                if has_base {
+                    addr.push('+');
+                }
+                addr.push_str(REG64[index as usize]);
+                if scale > 1 {
+                    let _ = write!(addr, "*{}", scale);
+                }
+                has_base = true;
+            }
+
+            if base == 5 && md == 0 {
+                let disp = self.i32()?;
+                push_disp(&mut addr, disp, has_base);
+            }
+        } else if rm == 5 && md == 0 {
+            addr.push_str("rip");
+            let disp = self.i32()?;
+            push_disp(&mut addr, disp, true);
+        } else {
+            addr.push_str(REG64[(rm | self.prefixes.rex_b()) as usize]);
+        }
+
+        match md {
+            1 => {
+                let disp = self.i8()?;
+                push_disp(&mut addr, disp, has_base);
+            }
+            2 => {
+                let disp = self.i32()?;
+                push_disp(&mut addr, disp, has_base);
+            }
+            _ => {}
+        }
+
+        addr.push(']');
+        Some(ModRm {
+            reg,
+            ext,
+            rm: RmOperand::Mem(addr),
+        })
+    }
+
+    fn reg_name(&self, reg: u8, width: Width) -> String {
+        let reg = reg as usize;
+        String::from(match width {
+            Width::Qword => REG64[reg],
+            Width::Dword => REG32[reg],
+            Width::Word => REG16[reg],
+            Width::Byte if self.prefixes.rex.is_some() => REG8_REX[reg],
+            Width::Byte => REG8_LEGACY[reg & 7],
+            Width::Xmm => return format!("xmm{}", reg),
+        })
+    }
+
+    fn rm_name(&self, rm: &RmOperand, width: Width) -> String {
+        match rm {
+            RmOperand::Reg(r) => self.reg_name(*r, width),
+            RmOperand::Mem(addr) => format!("{}{}", width.ptr(), addr),
+        }
+    }
+
+    /// Branch target for a relative displacement ending at the current position.
+    fn target(&self, rel: i64) -> String {
+        format!("0x{:x}", (self.pos as i64).wrapping_add(rel))
+    }
+
+    fn decode_primary(&mut self, op: u8) -> Option<String> {
+        let width = self.prefixes.width();
+
+        match op {
+            // ALU: add/or/adc/sbb/and/sub/xor/cmp
+            0x00..=0x3F if op & 7 < 6 => {
+                let name = ALU_OPS[(op >> 3) as usize];
+                match op & 7 {
+                    0..=3 => {
+                        let w = if op & 1 == 0 { Width::Byte } else { width };
+                        let m = self.modrm()?;
+                        let reg = self.reg_name(m.reg, w);
+                        let rm = self.rm_name(&m.rm, w);
+                        if op & 2 == 0 {
+                            Some(format!("{} {}, {}", name, rm, reg))
+                        } else {
+                            Some(format!("{} {}, {}", name, reg, rm))
+                        }
+                    }
+                    4 => {
+                        let imm = self.i8()?;
+                        Some(format!("{} al, {}", name, hex_imm(imm)))
+                    }
+                    _ => {
+                        let imm = self.imm()?;
+                        let acc = self.reg_name(0, width);
+                        Some(format!("{} {}, {}", name, acc, hex_imm(imm)))
+                    }
+                }
+            }
+            0x50..=0x57 => Some(format!(
+                "push {}",
+                REG64[((op - 0x50) | self.prefixes.rex_b()) as usize]
+            )),
+            0x58..=0x5F => Some(format!(
+                "pop {}",
+                REG64[((op - 0x58) | self.prefixes.rex_b()) as usize]
+            )),
+            0x63 => {
+                let m = self.modrm()?;
+                Some(format!(
+                    "movsxd {}, {}",
+                    self.reg_name(m.reg, width),
+                    self.rm_name(&m.rm, Width::Dword)
+                ))
+            }
+            0x69 | 0x6B => {
+                let m = self.modrm()?;
+                let imm = if op == 0x69 { self.imm()? } else { self.i8()? };
+                Some(format!(
+                    "imul {}, {}, {}",
+                    self.reg_name(m.reg, width),
+                    self.rm_name(&m.rm, width),
+                    hex_imm(imm)
+                ))
+            }
+            0x70..=0x7F => {
+                let rel = self.i8()?;
+                Some(format!(
+                    "j{} {}",
+                    CONDITIONS[(op & 0xF) as usize],
+                    self.target(rel)
+                ))
+            }
+            0x80 | 0x81 | 0x83 => {
+                let w = if op == 0x80 { Width::Byte } else { width };
+                let m = self.modrm()?;
+                let imm = if op == 0x81 { self.imm()? } else { self.i8()? };
+                Some(format!(
+                    "{} {}, {}",
+                    ALU_OPS[m.ext as usize],
+                    self.rm_name(&m.rm, w),
+                    hex_imm(imm)
+                ))
+            }
+            0x84..=0x8B => {
+                let name = match op {
+                    0x84 | 0x85 => "test",
+                    0x86 | 0x87 => "xchg",
+                    _ => "mov",
+                };
+                let w = if op & 1 == 0 { Width::Byte } else { width };
+                let m = self.modrm()?;
+                let reg = self.reg_name(m.reg, w);
+                let rm = self.rm_name(&m.rm, w);
+                if op >= 0x8A {
+                    Some(format!("{} {}, {}", name, reg, rm))
+                } else {
+                    Some(format!("{} {}, {}", name, rm, reg))
+                }
+            }
+            0x8D => {
+                let m = self.modrm()?;
+                match m.rm {
+                    RmOperand::Mem(ref addr) => {
+                        Some(format!("lea {}, {}", self.reg_name(m.reg, width), addr))
+                    }
+                    RmOperand::Reg(_) => None,
+                }
+            }
+            0x90 if self.prefixes.rex_b() == 0 => Some(String::from("nop")),
+            0x98 => Some(String::from(match width {
+                Width::Qword => "cdqe",
+                Width::Word => "cbw",
+                _ => "cwde",
+            })),
+            0x99 => Some(String::from(match width {
+                Width::Qword => "cqo",
+                Width::Word => "cwd",
+                _ => "cdq",
+            })),
+            0xB0..=0xB7 => {
+                let imm = self.u8()?;
+                let reg = self.reg_name((op - 0xB0) | self.prefixes.rex_b(), Width::Byte);
+                Some(format!("mov {}, 0x{:x}", reg, imm))
+            }
+            0xB8..=0xBF => {
+                let reg = (op - 0xB8) | self.prefixes.rex_b();
+                if width == Width::Qword {
+                    let imm = self.u64()?;
+                    Some(format!("movabs {}, 0x{:x}", REG64[reg as usize], imm))
+                } else {
+                    let imm = self.imm()?;
+                    Some(format!(
+                        "mov {}, {}",
+                        self.reg_name(reg, width),
+                        hex_imm(imm)
+                    ))
+                }
+            }
+            0xC0 | 0xC1 | 0xD0 | 0xD1 | 0xD2 | 0xD3 => {
+                let w = if op & 1 == 0 { Width::Byte } else { width };
+                let m = self.modrm()?;
+                let count = match op {
+                    0xC0 | 0xC1 => format!("0x{:x}", self.u8()?),
+                    0xD0 | 0xD1 => String::from("1"),
+                    _ => String::from("cl"),
+                };
+                Some(format!(
+                    "{} {}, {}",
+                    SHIFT_OPS[m.ext as usize],
+                    self.rm_name(&m.rm, w),
+                    count
+                ))
+            }
+            0xC3 => Some(String::from("ret")),
+            0xC6 | 0xC7 => {
+                let w = if op == 0xC6 { Width::Byte } else { width };
+                let m = self.modrm()?;
+                if m.ext != 0 {
+                    return None;
+                }
+                let imm = if op == 0xC6 { self.i8()? } else { self.imm()? };
+                Some(format!("mov {}, {}", self.rm_name(&m.rm, w), hex_imm(imm)))
+            }
+            0xCC => Some(String::from("int3")),
+            0xE8 => {
+                let rel = self.i32()?;
+                Some(format!("call {}", self.target(rel)))
+            }
+            0xE9 => {
+                let rel = self.i32()?;
+                Some(format!("jmp {}", self.target(rel)))
+            }
+            0xEB => {
+                let rel = self.i8()?;
+                Some(format!("jmp {}", self.target(rel)))
+            }
+            0xF6 | 0xF7 => {
+                let w = if op == 0xF6 { Width::Byte } else { width };
+                let m = self.modrm()?;
+                let rm = self.rm_name(&m.rm, w);
+                match m.ext {
+                    0 | 1 => {
+                        let imm = if op == 0xF6 { self.i8()? } else { self.imm()? };
+                        Some(format!("test {}, {}", rm, hex_imm(imm)))
+                    }
+                    ext => {
+                        let name =
+                            ["", "", "not", "neg", "mul", "imul", "div", "idiv"][ext as usize];
+                        Some(format!("{} {}", name, rm))
+                    }
+                }
+            }
+            0xFE | 0xFF => {
+                let m = self.modrm()?;
+                match (op, m.ext) {
+                    (_, 0) | (_, 1) => {
+                        let w = if op == 0xFE { Width::Byte } else { width };
+                        let name = if m.ext == 0 { "inc" } else { "dec" };
+                        Some(format!("{} {}", name, self.rm_name(&m.rm, w)))
+                    }
+                    (0xFF, 2) => Some(format!("call {}", self.rm_name(&m.rm, Width::Qword))),
+                    (0xFF, 4) => Some(format!("jmp {}", self.rm_name(&m.rm, Width::Qword))),
+                    (0xFF, 6) => Some(format!("push {}", self.rm_name(&m.rm, Width::Qword))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn decode_0f(&mut self, op: u8) -> Option<String> {
+        let width = self.prefixes.width();
+
+        // Scalar/packed SSE suffix selected by the mandatory prefix.
+        let suffix = if self.prefixes.rep {
+            "ss"
+        } else if self.prefixes.repne {
+            "sd"
+        } else if self.prefixes.opsize {
+            "pd"
+        } else {
+            "ps"
+        };
+
+        match op {
+            0x05 => Some(String::from("syscall")),
+            0x0B => Some(String::from("ud2")),
+            0x10 | 0x11 => {
+                let m = self.modrm()?;
+                let name = match (self.prefixes.rep, self.prefixes.repne, self.prefixes.opsize) {
+                    (true, _, _) => "movss",
+                    (_, true, _) => "movsd",
+                    (_, _, true) => "movupd",
+                    _ => "movups",
+                };
+                let reg = self.reg_name(m.reg, Width::Xmm);
+                let rm = self.xmm_rm(&m.rm);
+                if op == 0x10 {
+                    Some(format!("{} {}, {}", name, reg, rm))
+                } else {
+                    Some(format!("{} {}, {}", name, rm, reg))
+                }
+            }
+            0x1F => {
+                let m = self.modrm()?;
+                Some(format!("nop {}", self.rm_name(&m.rm, width)))
+            }
+            0x2A if self.prefixes.rep || self.prefixes.repne => {
+                let m = self.modrm()?;
+                let src_width = if self.prefixes.rex_w() {
+                    Width::Qword
+                } else {
+                    Width::Dword
+                };
+                Some(format!(
+                    "cvtsi2{} {}, {}",
+                    suffix,
+                    self.reg_name(m.reg, Width::Xmm),
+                    self.rm_name(&m.rm, src_width)
+                ))
+            }
+            0x2C | 0x2D if self.prefixes.rep || self.prefixes.repne => {
+                let m = self.modrm()?;
+                let name = if op == 0x2C { "cvtt" } else { "cvt" };
+                let dst_width = if self.prefixes.rex_w() {
+                    Width::Qword
+                } else {
+                    Width::Dword
+                };
+                Some(format!(
+                    "{}{}2si {}, {}",
+                    name,
+                    suffix,
+                    self.reg_name(m.reg, dst_width),
+                    self.xmm_rm(&m.rm)
+                ))
+            }
+            0x2E | 0x2F => {
+                let m = self.modrm()?;
+                let name = if op == 0x2E { "ucomis" } else { "comis" };
+                let suffix = if self.prefixes.opsize { "d" } else { "s" };
+                Some(format!(
+                    "{}{} {}, {}",
+                    name,
+                    suffix,
+                    self.reg_name(m.reg, Width::Xmm),
+                    self.xmm_rm(&m.rm)
+                ))
+            }
+            0x40..=0x4F => {
+                let m = self.modrm()?;
+                Some(format!(
+                    "cmov{} {}, {}",
+                    CONDITIONS[(op & 0xF) as usize],
+                    self.reg_name(m.reg, width),
+                    self.rm_name(&m.rm, width)
+                ))
+            }
+            0x51 | 0x54 | 0x55 | 0x56 | 0x57 | 0x58 | 0x59 | 0x5C | 0x5D | 0x5E | 0x5F => {
+                let m = self.modrm()?;
+                let name = match op {
+                    0x51 => "sqrt",
+                    0x54 => "and",
+                    0x55 => "andn",
+                    0x56 => "or",
+                    0x57 => "xor",
+                    0x58 => "add",
+                    0x59 => "mul",
+                    0x5C => "sub",
+                    0x5D => "min",
+                    0x5E => "div",
+                    _ => "max",
+                };
+                // Bitwise ops only exist in packed form
+                let suffix = match (op, self.prefixes.opsize) {
+                    (0x54..=0x57, true) => "pd",
+                    (0x54..=0x57, false) => "ps",
+                    _ => suffix,
+                };
+                Some(format!(
+                    "{}{} {}, {}",
+                    name,
+                    suffix,
+                    self.reg_name(m.reg, Width::Xmm),
+                    self.xmm_rm(&m.rm)
+                ))
+            }
+            0x5A if self.prefixes.rep || self.prefixes.repne => {
+                let m = self.modrm()?;
+                let name = if self.prefixes.rep {
+                    "cvtss2sd"
+                } else {
+                    "cvtsd2ss"
+                };
+                Some(format!(
+                    "{} {}, {}",
+                    name,
+                    self.reg_name(m.reg, Width::Xmm),
+                    self.xmm_rm(&m.rm)
+                ))
+            }
+            0x6E if self.prefixes.opsize => {
+                let m = self.modrm()?;
+                let (name, w) = if self.prefixes.rex_w() {
+                    ("movq", Width::Qword)
+                } else {
+                    ("movd", Width::Dword)
+                };
+                Some(format!(
+                    "{} {}, {}",
+                    name,
+                    self.reg_name(m.reg, Width::Xmm),
+                    self.rm_name(&m.rm, w)
+                ))
+            }
+            0x7E if self.prefixes.opsize => {
+                let m = self.modrm()?;
+                let (name, w) = if self.prefixes.rex_w() {
+                    ("movq", Width::Qword)
+                } else {
+                    ("movd", Width::Dword)
+                };
+                Some(format!(
+                    "{} {}, {}",
+                    name,
+                    self.rm_name(&m.rm, w),
+                    self.reg_name(m.reg, Width::Xmm)
+                ))
+            }
+            0x7E if self.prefixes.rep => {
+                let m = self.modrm()?;
+                Some(format!(
+                    "movq {}, {}",
+                    self.reg_name(m.reg, Width::Xmm),
+                    self.rm_name(&m.rm, Width::Qword)
+                ))
+            }
+            0x80..=0x8F => {
+                let rel = self.i32()?;
+                Some(format!(
+                    "j{} {}",
+                    CONDITIONS[(op & 0xF) as usize],
+                    self.target(rel)
+                ))
+            }
+            0x90..=0x9F => {
+                let m = self.modrm()?;
+                Some(format!(
+                    "set{} {}",
+                    CONDITIONS[(op & 0xF) as usize],
+                    self.rm_name(&m.rm, Width::Byte)
+                ))
+            }
+            0xA2 => Some(String::from("cpuid")),
+            0xAF => {
+                let m = self.modrm()?;
+                Some(format!(
+                    "imul {}, {}",
+                    self.reg_name(m.reg, width),
+                    self.rm_name(&m.rm, width)
+                ))
+            }
+            0xB6 | 0xB7 | 0xBE | 0xBF => {
+                let m = self.modrm()?;
+                let name = if op < 0xBE { "movzx" } else { "movsx" };
+                let src = if op & 1 == 0 {
+                    Width::Byte
+                } else {
+                    Width::Word
+                };
+                Some(format!(
+                    "{} {}, {}",
+                    name,
+                    self.reg_name(m.reg, width),
+                    self.rm_name(&m.rm, src)
+                ))
+            }
+            0xB8 | 0xBC | 0xBD => {
+                let name = match (op, self.prefixes.rep) {
+                    (0xB8, true) => "popcnt",
+                    (0xBC, true) => "tzcnt",
+                    (0xBD, true) => "lzcnt",
+                    (0xBC, false) => "bsf",
+                    (0xBD, false) => "bsr",
+                    _ => return None,
+                };
+                let m = self.modrm()?;
+                Some(format!(
+                    "{} {}, {}",
+                    name,
+                    self.reg_name(m.reg, width),
+                    self.rm_name(&m.rm, width)
+                ))
+            }
+            0xC2 => {
+                let m = self.modrm()?;
+                let imm = self.u8()?;
+                let reg = self.reg_name(m.reg, Width::Xmm);
+                let rm = self.xmm_rm(&m.rm);
+                match SSE_PREDICATES.get(imm as usize) {
+                    Some(pred) => Some(format!("cmp{}{} {}, {}", pred, suffix, reg, rm)),
+                    None => Some(format!("cmp{} {}, {}, 0x{:x}", suffix, reg, rm, imm)),
+                }
+            }
+            0xD6 if self.prefixes.opsize => {
+                let m = self.modrm()?;
+                Some(format!(
+                    "movq {}, {}",
+                    self.rm_name(&m.rm, Width::Qword),
+                    self.reg_name(m.reg, Width::Xmm)
+                ))
+            }
+            0x3A if self.prefixes.opsize => {
+                let op3 = self.u8()?;
+                let name = match op3 {
+                    0x08 => "roundps",
+                    0x09 => "roundpd",
+                    0x0A => "roundss",
+                    0x0B => "roundsd",
+                    _ => return None,
+                };
+                let m = self.modrm()?;
+                let imm = self.u8()?;
+                Some(format!(
+                    "{} {}, {}, 0x{:x}",
+                    name,
+                    self.reg_name(m.reg, Width::Xmm),
+                    self.xmm_rm(&m.rm),
+                    imm
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Render an SSE r/m operand: an XMM register or a sized memory operand.
+    fn xmm_rm(&self, rm: &RmOperand) -> String {
+        match rm {
+            RmOperand::Reg(r) => self.reg_name(*r, Width::Xmm),
+            RmOperand::Mem(addr) => format!("{}{}", self.sse_width().ptr(), addr),
+        }
+    }
+
+    /// Memory operand width for the current SSE instruction.
+    fn sse_width(&self) -> Width {
+        if self.prefixes.rep {
+            Width::Dword
+        } else if self.prefixes.repne {
+            Width::Qword
+        } else {
+            Width::Xmm
+        }
+    }
+}
+
+/// Append a signed displacement to an address expression.
+fn push_disp(addr: &mut String, disp: i64, has_base: bool) {
+    if disp < 0 {
+        let _ = write!(addr, "-0x{:x}", disp.unsigned_abs());
+    } else if !has_base {
+        let _ = write!(addr, "0x{:x}", disp);
+    } else if disp > 0 {
+        let _ = write!(addr, "+0x{:x}", disp);
+    }
+}
+
+/// Format a signed immediate.
+fn hex_imm(imm: i64) -> String {
+    if imm < 0 {
+        format!("-0x{:x}", imm.unsigned_abs())
+    } else {
+        format!("0x{:x}", imm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jit::codegen::CodeGenerator;
+    use crate::jit::ir::{IrFunction, IrInstruction, IrOpcode, IrType};
+    use alloc::vec;
+
+    fn texts(code: &[u8]) -> Vec<String> {
+        decode(code).into_iter().map(|insn| insn.text).collect()
+    }
+
+    fn compile_body(body: Vec<IrOpcode>) -> NativeCode {
+        let mut func = IrFunction::new(0, vec![], vec![IrType::I32]);
+        for op in body {
+            func.add_instruction(IrInstruction::new(op, 0));
+        }
+        CodeGenerator::new()
+            .generate_baseline(&func)
+            .expect("compilation failed")
+    }
+
+    #[test]
+    fn test_prologue_epilogue() {
+        let code = [
+            0x55, // push rbp
+            0x48, 0x89, 0xE5, // mov rbp, rsp
+            0x48, 0x83, 0xEC, 0x10, // sub rsp, 16
+            0x48, 0x83, 0xC4, 0x10, // add rsp, 16
+            0x5D, // pop rbp
+            0xC3, // ret
+        ];
+        assert_eq!(
+            texts(&code),
+            vec![
+                "push rbp",
+                "mov rbp, rsp",
+                "sub rsp, 0x10",
+                "add rsp, 0x10",
+                "pop rbp",
+                "ret"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memory_operands() {
+        assert_eq!(
+            texts(&[0x48, 0x8B, 0x45, 0xF8]),
+            vec!["mov rax, qword ptr [rbp-0x8]"]
+        );
+        assert_eq!(
+            texts(&[0x48, 0x89, 0x85, 0x00, 0xFF, 0xFF, 0xFF]),
+            vec!["mov qword ptr [rbp-0x100], rax"]
+        );
+        assert_eq!(
+            texts(&[0x48, 0x8B, 0x04, 0x24]),
+            vec!["mov rax, qword ptr [rsp]"]
+        );
+        assert_eq!(texts(&[0x66, 0x89, 0x01]), vec!["mov word ptr [rcx], ax"]);
+    }
+
+    #[test]
+    fn test_branches() {
+        // jz +2; jmp rel32 back to 0; call rel32 to 0
+        let code = [
+            0x74, 0x02, 0xEB, 0xFC, 0xE9, 0xF7, 0xFF, 0xFF, 0xFF, 0xE8, 0xF2, 0xFF, 0xFF, 0xFF,
+        ];
+        assert_eq!(
+            texts(&code),
+            vec!["je 0x4", "jmp 0x0", "jmp 0x0", "call 0x0"]
+        );
+    }
+
+    #[test]
+    fn test_sse() {
+        assert_eq!(
+            texts(&[0x66, 0x48, 0x0F, 0x6E, 0xC1]),
+            vec!["movq xmm0, rcx"]
+        );
+        assert_eq!(
+            texts(&[0x66, 0x48, 0x0F, 0x7E, 0xC0]),
+            vec!["movq rax, xmm0"]
+        );
+        assert_eq!(texts(&[0xF3, 0x0F, 0x58, 0xC1]), vec!["addss xmm0, xmm1"]);
+        assert_eq!(
+            texts(&[0xF2, 0x0F, 0xC2, 0xC1, 0x01]),
+            vec!["cmpltsd xmm0, xmm1"]
+        );
+        assert_eq!(
+            texts(&[0x66, 0x0F, 0x3A, 0x0A, 0xC0, 0x02]),
+            vec!["roundss xmm0, xmm0, 0x2"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_bytes_fallback() {
+        // 0x06 (push es) is invalid in 64-bit mode
+        assert_eq!(texts(&[0x06, 0xC3]), vec![".byte 0x06", "ret"]);
+        // Truncated rel32 and a missing ModRM byte
+        assert_eq!(texts(&[0xE8, 0x01]), vec![".byte 0xe8", ".byte 0x01"]);
+    }
+
+    #[test]
+    fn test_generated_code_fully_decodes() {
+        let code = compile_body(vec![
+            IrOpcode::Const32(10),
+            IrOpcode::Const32(3),
+            IrOpcode::I32DivS,
+            IrOpcode::Const64(-1),
+            IrOpcode::I64Popcnt,
+            IrOpcode::I64Eqz,
+            IrOpcode::I32Add,
+            IrOpcode::ConstF64(2.0f64.to_bits()),
+            IrOpcode::F64Sqrt,
+            IrOpcode::Drop,
+            IrOpcode::Return,
+        ]);
+
+        let listing = disasm(&code);
+        assert!(!listing.contains(".byte"), "{}", listing);
+        assert!(listing.contains("idiv ecx"));
+        assert!(listing.contains("sqrtsd xmm0, xmm0"));
+        assert!(listing.lines().next().unwrap().ends_with("push rbp"));
+        assert!(listing.lines().last().unwrap().ends_with("ret"));
+    }
+}
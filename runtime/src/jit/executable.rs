@@ -5,6 +5,8 @@
 
 use alloc::vec::Vec;
 
+use super::trap;
+
 /// Permission state for an executable region.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RegionState {
@@ -146,6 +148,9 @@ impl ExecutableRegion {
 }
 
 /// Manager for multiple executable regions.
+///
+/// Executable regions are registered with the trap handler so that CPU
+/// faults raised by their code become WASM traps.
 pub struct ExecutableMemoryManager {
     /// Active regions.
     regions: Vec<ExecutableRegion>,
@@ -177,6 +182,7 @@ impl ExecutableMemoryManager {
 
         let id = self.regions.len();
         self.total_size += code.len();
+        trap::register_code(region.code()?);
         self.regions.push(region);
         Ok(id)
     }
@@ -189,6 +195,9 @@ impl ExecutableMemoryManager {
     /// Free a region by ID.
     pub fn free(&mut self, id: usize) {
         if let Some(region) = self.regions.get_mut(id) {
+            if let Ok(code) = region.code() {
+                trap::unregister_code(code);
+            }
             self.total_size -= region.size();
             region.free();
         }
@@ -208,6 +217,16 @@ impl ExecutableMemoryManager {
     }
 }
 
+impl Drop for ExecutableMemoryManager {
+    fn drop(&mut self) {
+        for region in &self.regions {
+            if let Ok(code) = region.code() {
+                trap::unregister_code(code);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
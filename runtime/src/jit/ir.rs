@@ -8,6 +8,13 @@ use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
+use crate::executor::{
+    trunc_sat_f32_i32, trunc_sat_f32_i64, trunc_sat_f32_u32, trunc_sat_f32_u64, trunc_sat_f64_i32,
+    trunc_sat_f64_i64, trunc_sat_f64_u32, trunc_sat_f64_u64,
+};
+
+use super::osr::{OsrFrame, OsrHook};
+
 /// IR Opcode for the JIT compiler.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IrOpcode {
@@ -184,6 +191,16 @@ pub enum IrOpcode {
     I64Extend16S,
     I64Extend32S,
 
+    // Saturating truncation (0xFC prefix)
+    I32TruncSatF32S,
+    I32TruncSatF32U,
+    I32TruncSatF64S,
+    I32TruncSatF64U,
+    I64TruncSatF32S,
+    I64TruncSatF32U,
+    I64TruncSatF64S,
+    I64TruncSatF64U,
+
     // Control flow
     Block(BlockId),
     Loop(BlockId),
@@ -411,23 +428,31 @@ impl WasmToIr {
             0x00 => IrOpcode::Unreachable,
             0x01 => return Ok(IrInstruction::new(IrOpcode::End, offset)), // nop
             0x02 => {
-                let block_id = self.new_block(BlockKind::Block);
-                let _block_type = reader.read_signed_leb128()?;
+                let block_type = reader.read_signed_leb128()?;
+                let block_id = self.new_block(BlockKind::Block, block_type, offset);
                 IrOpcode::Block(block_id)
             }
             0x03 => {
-                let block_id = self.new_block(BlockKind::Loop);
-                let _block_type = reader.read_signed_leb128()?;
+                let block_type = reader.read_signed_leb128()?;
+                let block_id = self.new_block(BlockKind::Loop, block_type, offset);
                 IrOpcode::Loop(block_id)
             }
             0x04 => {
-                let block_id = self.new_block(BlockKind::If);
-                let _block_type = reader.read_signed_leb128()?;
+                let block_type = reader.read_signed_leb128()?;
+                let block_id = self.new_block(BlockKind::If, block_type, offset);
                 IrOpcode::If(block_id)
             }
             0x05 => IrOpcode::Else,
             0x0B => {
-                self.block_stack.pop();
+                if let Some(block_id) = self.block_stack.pop() {
+                    if let Some(info) = self
+                        .current_func
+                        .as_mut()
+                        .and_then(|func| func.blocks.get_mut(&block_id))
+                    {
+                        info.end_offset = Some(offset);
+                    }
+                }
                 IrOpcode::End
             }
             0x0C => {
@@ -720,16 +745,55 @@ impl WasmToIr {
             0xC3 => IrOpcode::I64Extend16S,
             0xC4 => IrOpcode::I64Extend32S,
 
+            // Multi-byte opcodes; only the saturating truncations are
+            // supported
+            0xFC => match reader.read_unsigned_leb128()? {
+                0 => IrOpcode::I32TruncSatF32S,
+                1 => IrOpcode::I32TruncSatF32U,
+                2 => IrOpcode::I32TruncSatF64S,
+                3 => IrOpcode::I32TruncSatF64U,
+                4 => IrOpcode::I64TruncSatF32S,
+                5 => IrOpcode::I64TruncSatF32U,
+                6 => IrOpcode::I64TruncSatF64S,
+                7 => IrOpcode::I64TruncSatF64U,
+                _ => return Err(TranslationError::UnsupportedOpcode(opcode)),
+            },
+
             _ => return Err(TranslationError::UnsupportedOpcode(opcode)),
         };
 
         Ok(IrInstruction::new(ir_opcode, offset))
     }
 
-    fn new_block(&mut self, kind: BlockKind) -> BlockId {
+    fn new_block(&mut self, kind: BlockKind, block_type: i64, offset: u32) -> BlockId {
         let id = BlockId(self.next_block_id);
         self.next_block_id += 1;
         self.block_stack.push(id);
+
+        // Single-result block types; type indices (multi-value) are not
+        // supported and leave the results empty
+        let result = match block_type {
+            -0x01 => Some(IrType::I32),
+            -0x02 => Some(IrType::I64),
+            -0x03 => Some(IrType::F32),
+            -0x04 => Some(IrType::F64),
+            -0x05 => Some(IrType::V128),
+            -0x10 => Some(IrType::FuncRef),
+            -0x11 => Some(IrType::ExternRef),
+            _ => None,
+        };
+        if let Some(ref mut func) = self.current_func {
+            func.blocks.insert(
+                id,
+                BlockInfo {
+                    kind,
+                    params: Vec::new(),
+                    results: result.into_iter().collect(),
+                    start_offset: offset,
+                    end_offset: None,
+                },
+            );
+        }
         id
     }
 }
@@ -889,6 +953,7 @@ pub enum IrTrap {
     InvalidBranch,
     IntegerOverflow,
     MemoryBoundsViolation,
+    NativeFault(String),
 }
 
 impl IrInterpreter {
@@ -913,6 +978,26 @@ impl IrInterpreter {
 
     /// Execute an IR function with the given arguments (as i64).
     pub fn execute(&mut self, func: &IrFunction, args: &[i64]) -> IrExecResult {
+        self.run(func, args, None)
+    }
+
+    /// Execute an IR function, offering each loop that gets hot to `osr`
+    /// to finish the call in compiled code.
+    pub fn execute_with_osr(
+        &mut self,
+        func: &IrFunction,
+        args: &[i64],
+        osr: &mut dyn OsrHook,
+    ) -> IrExecResult {
+        self.run(func, args, Some(osr))
+    }
+
+    fn run(
+        &mut self,
+        func: &IrFunction,
+        args: &[i64],
+        mut osr: Option<&mut dyn OsrHook>,
+    ) -> IrExecResult {
         self.stack.clear();
         self.block_stack.clear();
 
@@ -927,6 +1012,8 @@ impl IrInterpreter {
 
         let mut pc = 0usize;
         let body_len = func.body.len();
+        // Back-edges taken per loop, for OSR
+        let mut back_edges: BTreeMap<usize, u64> = BTreeMap::new();
 
         while pc < body_len {
             let inst = &func.body[pc];
@@ -1238,6 +1325,32 @@ impl IrInterpreter {
                     self.unop_i64(|a| (a as i32) as i64);
                 }
 
+                // Floats are carried as bit patterns
+                IrOpcode::I32TruncSatF32S => {
+                    self.unop_i64(|a| trunc_sat_f32_i32(f32::from_bits(a as u32)) as i64);
+                }
+                IrOpcode::I32TruncSatF32U => {
+                    self.unop_i64(|a| trunc_sat_f32_u32(f32::from_bits(a as u32)) as i32 as i64);
+                }
+                IrOpcode::I32TruncSatF64S => {
+                    self.unop_i64(|a| trunc_sat_f64_i32(f64::from_bits(a as u64)) as i64);
+                }
+                IrOpcode::I32TruncSatF64U => {
+                    self.unop_i64(|a| trunc_sat_f64_u32(f64::from_bits(a as u64)) as i32 as i64);
+                }
+                IrOpcode::I64TruncSatF32S => {
+                    self.unop_i64(|a| trunc_sat_f32_i64(f32::from_bits(a as u32)));
+                }
+                IrOpcode::I64TruncSatF32U => {
+                    self.unop_i64(|a| trunc_sat_f32_u64(f32::from_bits(a as u32)) as i64);
+                }
+                IrOpcode::I64TruncSatF64S => {
+                    self.unop_i64(|a| trunc_sat_f64_i64(f64::from_bits(a as u64)));
+                }
+                IrOpcode::I64TruncSatF64U => {
+                    self.unop_i64(|a| trunc_sat_f64_u64(f64::from_bits(a as u64)) as i64);
+                }
+
                 // ── Control Flow ──
                 IrOpcode::Block(block_id) => {
                     self.block_stack.push(IrBlock {
@@ -1306,7 +1419,14 @@ impl IrInterpreter {
                     self.block_stack.pop();
                 }
                 IrOpcode::Br(depth) => match self.do_branch(depth, &func.body, &mut pc) {
-                    Ok(()) => {}
+                    Ok(true) => {
+                        if let Some(result) =
+                            self.back_edge(func, pc, &mut back_edges, osr.as_deref_mut())
+                        {
+                            return result;
+                        }
+                    }
+                    Ok(false) => {}
                     Err(t) => return IrExecResult::Trap(t),
                 },
                 IrOpcode::BrIf(depth) => {
@@ -1316,7 +1436,14 @@ impl IrInterpreter {
                     };
                     if cond != 0 {
                         match self.do_branch(depth, &func.body, &mut pc) {
-                            Ok(()) => {}
+                            Ok(true) => {
+                                if let Some(result) =
+                                    self.back_edge(func, pc, &mut back_edges, osr.as_deref_mut())
+                                {
+                                    return result;
+                                }
+                            }
+                            Ok(false) => {}
                             Err(t) => return IrExecResult::Trap(t),
                         }
                     }
@@ -1517,13 +1644,38 @@ impl IrInterpreter {
         }
     }
 
-    /// Branch by depth: pop blocks and jump.
+    /// Count a back-edge to the loop whose body starts at `pc`, and offer
+    /// the loop to `osr` once it reaches the threshold.
+    fn back_edge(
+        &self,
+        func: &IrFunction,
+        pc: usize,
+        back_edges: &mut BTreeMap<usize, u64>,
+        osr: Option<&mut (dyn OsrHook + '_)>,
+    ) -> Option<IrExecResult> {
+        let osr = osr?;
+        let loop_pc = pc - 1;
+        let count = back_edges.entry(loop_pc).or_insert(0);
+        *count += 1;
+        if *count != osr.threshold() {
+            return None;
+        }
+        let frame = OsrFrame {
+            loop_pc,
+            locals: &self.locals,
+            stack: &self.stack,
+        };
+        osr.enter(func, &frame)
+    }
+
+    /// Branch by depth: pop blocks and jump. Returns whether this was a
+    /// back-edge to a loop.
     fn do_branch(
         &mut self,
         depth: u32,
         body: &[IrInstruction],
         pc: &mut usize,
-    ) -> Result<(), IrTrap> {
+    ) -> Result<bool, IrTrap> {
         if depth as usize >= self.block_stack.len() {
             return Err(IrTrap::InvalidBranch);
         }
@@ -1539,6 +1691,7 @@ impl IrInterpreter {
         if target_block.kind == BlockKind::Loop {
             // Loop: branch to start_pc (re-enter loop)
             *pc = target_block.start_pc;
+            return Ok(true);
         } else {
             // Block/If: branch to end (skip to matching End)
             self.block_stack.pop(); // pop the target block too
@@ -1558,7 +1711,7 @@ impl IrInterpreter {
             } // skip the End
         }
 
-        Ok(())
+        Ok(false)
     }
 }
 
@@ -1629,4 +1782,41 @@ mod tests {
         let result = interp.execute(&func, &[]);
         assert_eq!(result, IrExecResult::Ok(vec![0]));
     }
+
+    #[test]
+    fn test_ir_trunc_sat() {
+        // f64.const nan; i32.trunc_sat_f64_s; f32.const -inf; i64.trunc_sat_f32_u
+        let mut code = vec![0x44];
+        code.extend_from_slice(&f64::NAN.to_bits().to_le_bytes());
+        code.extend_from_slice(&[0xFC, 0x02, 0x43]);
+        code.extend_from_slice(&f32::NEG_INFINITY.to_bits().to_le_bytes());
+        code.extend_from_slice(&[0xFC, 0x05, 0x0B]);
+        let mut translator = WasmToIr::new();
+        let func = translator
+            .translate_function(0, vec![], vec![IrType::I32, IrType::I64], &[], &code)
+            .unwrap();
+        assert_eq!(func.body[1].opcode, IrOpcode::I32TruncSatF64S);
+        assert_eq!(func.body[3].opcode, IrOpcode::I64TruncSatF32U);
+
+        let mut interp = IrInterpreter::new();
+        assert_eq!(interp.execute(&func, &[]), IrExecResult::Ok(vec![0, 0]));
+
+        let mut func = IrFunction::new(0, vec![], vec![IrType::I32, IrType::I64]);
+        func.body = vec![
+            IrInstruction::new(IrOpcode::ConstF32(3e9f32.to_bits()), 0),
+            IrInstruction::new(IrOpcode::I32TruncSatF32S, 0),
+            IrInstruction::new(IrOpcode::ConstF64(1e20f64.to_bits()), 0),
+            IrInstruction::new(IrOpcode::I64TruncSatF64U, 0),
+        ];
+        let mut interp = IrInterpreter::new();
+        assert_eq!(
+            interp.execute(&func, &[]),
+            IrExecResult::Ok(vec![i32::MAX as i64, -1])
+        );
+
+        let mut translator = WasmToIr::new();
+        assert!(translator
+            .translate_function(0, vec![], vec![], &[], &[0xFC, 0x08, 0x0B])
+            .is_err());
+    }
 }
@@ -22,9 +22,13 @@ pub mod bench;
 pub mod cache;
 pub mod codegen;
 pub mod compiler;
+pub mod cpu;
+pub mod disasm;
 pub mod executable;
 pub mod ir;
+pub mod osr;
 pub mod profile;
+pub mod trap;
 
 use alloc::collections::BTreeMap;
 use alloc::string::String;
@@ -33,10 +37,17 @@ use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use spin::RwLock;
 
+use crate::module::Module;
+use ir::IrFunction;
+
 pub use cache::{CacheEntry, CodeCache};
 pub use codegen::{CodeGenerator, NativeCode};
 pub use compiler::{CompilationError, CompilationResult, JitCompiler};
+pub use cpu::CpuFeatures;
+pub use disasm::disasm;
+pub use osr::{EngineOsr, OsrFrame, OsrHook};
 pub use profile::{HotnessCounter, ProfileData};
+pub use trap::{catch_traps, handle_fault, CpuFault, GuardRegion, TrapKind};
 
 /// JIT compilation tier.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -56,10 +67,12 @@ pub struct JitOptions {
     pub baseline_enabled: bool,
     /// Enable optimizing JIT compilation.
     pub optimized_enabled: bool,
-    /// Threshold for baseline compilation (call count).
+    /// Threshold for baseline compilation (hotness, see `loop_weight`).
     pub baseline_threshold: u32,
-    /// Threshold for optimizing compilation (call count).
+    /// Threshold for optimizing compilation (hotness, see `loop_weight`).
     pub optimized_threshold: u32,
+    /// Loop iterations that count as one call towards the thresholds.
+    pub loop_weight: u32,
     /// Maximum code cache size in bytes.
     pub max_cache_size: usize,
     /// Enable AOT compilation.
@@ -75,9 +88,10 @@ impl Default for JitOptions {
             optimized_enabled: true,
             baseline_threshold: 100,          // Compile after 100 calls
             optimized_threshold: 10_000,      // Optimize after 10k calls
+            loop_weight: 100,                 // 100 loop iterations ~ 1 call
             max_cache_size: 64 * 1024 * 1024, // 64 MB code cache
             aot_enabled: true,
-            osr_enabled: false, // OSR is complex, disabled by default
+            osr_enabled: false, // Needs an embedder that can run native code
         }
     }
 }
@@ -99,6 +113,10 @@ pub struct JitStats {
     pub cache_hits: AtomicU64,
     /// Cache misses.
     pub cache_misses: AtomicU64,
+    /// Functions compiled with an on-stack replacement entry.
+    pub osr_compilations: AtomicU64,
+    /// Interpreter calls that moved to native code mid-execution.
+    pub osr_entries: AtomicU64,
 }
 
 impl JitStats {
@@ -121,6 +139,16 @@ impl JitStats {
         self.generated_code_bytes
             .fetch_add(code_size as u64, Ordering::Relaxed);
     }
+
+    pub fn record_osr_compilation(&self, code_size: usize) {
+        self.osr_compilations.fetch_add(1, Ordering::Relaxed);
+        self.generated_code_bytes
+            .fetch_add(code_size as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_osr_entry(&self) {
+        self.osr_entries.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 /// JIT engine managing compilation and execution.
@@ -131,6 +159,8 @@ pub struct JitEngine {
     code_cache: Arc<RwLock<CodeCache>>,
     /// Profile data per function.
     profiles: RwLock<BTreeMap<FunctionId, ProfileData>>,
+    /// Code with an OSR entry, by function and loop instruction index.
+    osr_code: RwLock<BTreeMap<(FunctionId, usize), Arc<NativeCode>>>,
     /// JIT statistics.
     stats: JitStats,
     /// Compiler instance.
@@ -166,6 +196,7 @@ impl JitEngine {
         Self {
             code_cache: Arc::new(RwLock::new(CodeCache::new(options.max_cache_size))),
             profiles: RwLock::new(BTreeMap::new()),
+            osr_code: RwLock::new(BTreeMap::new()),
             stats: JitStats::new(),
             compiler: JitCompiler::new(),
             options,
@@ -247,16 +278,93 @@ impl JitEngine {
         let profile = profiles.entry(func_id).or_insert_with(ProfileData::new);
 
         profile.call_count += 1;
+        self.tier_for(profile)
+    }
 
-        if profile.call_count >= self.options.optimized_threshold {
+    /// Tier a profile has earned, counting loop iterations as well as
+    /// calls so that functions with hot loops are promoted early.
+    fn tier_for(&self, profile: &ProfileData) -> CompilationTier {
+        let hotness = profile.hotness(self.options.loop_weight);
+        if hotness >= self.options.optimized_threshold as u64 {
             CompilationTier::Optimized
-        } else if profile.call_count >= self.options.baseline_threshold {
+        } else if hotness >= self.options.baseline_threshold as u64 {
             CompilationTier::Baseline
         } else {
             CompilationTier::Interpreter
         }
     }
 
+    /// Merge profiles recorded by the interpreter for one module, keyed by
+    /// function index (see [`ExecutorContext::take_profiles`]).
+    ///
+    /// [`ExecutorContext::take_profiles`]: crate::executor::ExecutorContext::take_profiles
+    pub fn merge_profiles(&self, module_id: u64, profiles: &BTreeMap<u32, ProfileData>) {
+        let mut ours = self.profiles.write();
+        for (&func_index, profile) in profiles {
+            ours.entry(FunctionId::new(module_id, func_index))
+                .or_default()
+                .merge(profile);
+        }
+    }
+
+    /// Tier a function has earned from its profile so far.
+    pub fn tier(&self, func_id: FunctionId) -> CompilationTier {
+        self.profiles
+            .read()
+            .get(&func_id)
+            .map_or(CompilationTier::Interpreter, |profile| {
+                self.tier_for(profile)
+            })
+    }
+
+    /// Loop header to enter compiled code at while the function is still
+    /// running in the interpreter (on-stack replacement).
+    ///
+    /// Returns the instruction index of the function's hottest loop once
+    /// that loop alone has run enough iterations to justify baseline
+    /// compilation. Always `None` unless OSR is enabled.
+    pub fn osr_candidate(&self, func_id: FunctionId) -> Option<u32> {
+        let threshold = self.osr_threshold()?;
+        let (loop_pc, count) = self.profiles.read().get(&func_id)?.hottest_loop()?;
+        (count >= threshold).then_some(loop_pc)
+    }
+
+    /// Back-edges a single loop takes before it is worth compiling for
+    /// on-stack replacement, or `None` if OSR is disabled.
+    pub fn osr_threshold(&self) -> Option<u64> {
+        self.options
+            .osr_enabled
+            .then(|| self.options.baseline_threshold as u64 * self.options.loop_weight as u64)
+    }
+
+    /// Get or compile code for `ir` with an on-stack replacement entry at
+    /// the loop starting at instruction `loop_pc`.
+    ///
+    /// The code's entry point takes a pointer to all locals at the loop
+    /// header, see [`CodeGenerator::generate_osr`].
+    pub fn compile_osr(
+        &self,
+        func_id: FunctionId,
+        ir: &IrFunction,
+        loop_pc: usize,
+    ) -> Result<Arc<NativeCode>, CompilationError> {
+        if !self.options.osr_enabled {
+            return Err(CompilationError::OsrDisabled);
+        }
+        if let Some(code) = self.osr_code.read().get(&(func_id, loop_pc)) {
+            self.stats.cache_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(code.clone());
+        }
+        self.stats.cache_misses.fetch_add(1, Ordering::Relaxed);
+
+        let code = Arc::new(self.compiler.compile_osr(ir, loop_pc)?);
+        self.stats.record_osr_compilation(code.size());
+        self.osr_code
+            .write()
+            .insert((func_id, loop_pc), code.clone());
+        Ok(code)
+    }
+
     /// AOT compile an entire module.
     pub fn aot_compile(
         &self,
@@ -274,6 +382,39 @@ impl JitEngine {
     pub fn invalidate(&self, func_id: FunctionId) {
         let mut cache = self.code_cache.write();
         cache.remove(&func_id);
+        self.osr_code.write().retain(|&(id, _), _| id != func_id);
+    }
+
+    /// Disassemble the cached native code for a function.
+    ///
+    /// Returns `None` if the function has not been compiled yet.
+    pub fn dump_function(&self, func_id: FunctionId) -> Option<String> {
+        let cache = self.code_cache.read();
+        cache.get(&func_id).map(|entry| disasm(&entry.code))
+    }
+
+    /// Summarize the profiles of one module's functions, hottest first.
+    ///
+    /// Functions are labelled with their debug names from `module`.
+    pub fn profile_report(&self, module_id: u64, module: &Module) -> Vec<FunctionProfile> {
+        let cache = self.code_cache.read();
+        let mut report: Vec<FunctionProfile> = self
+            .profiles
+            .read()
+            .iter()
+            .filter(|(id, _)| id.module_id == module_id)
+            .map(|(id, profile)| FunctionProfile {
+                func_index: id.func_index,
+                name: module.function_label(id.func_index),
+                call_count: profile.call_count,
+                loop_iterations: profile.loop_iterations,
+                tier: cache
+                    .get(id)
+                    .map_or(CompilationTier::Interpreter, |entry| entry.tier),
+            })
+            .collect();
+        report.sort_by(|a, b| b.call_count.cmp(&a.call_count));
+        report
     }
 
     /// Get JIT statistics.
@@ -300,6 +441,19 @@ pub struct CacheStats {
     pub max_size: usize,
 }
 
+/// Profile summary for one function, see [`JitEngine::profile_report`].
+#[derive(Debug, Clone)]
+pub struct FunctionProfile {
+    pub func_index: u32,
+    /// Debug name, or a fallback label if the module has none.
+    pub name: String,
+    pub call_count: u32,
+    /// Back-edges taken in the function's loops.
+    pub loop_iterations: u64,
+    /// Tier of the cached code, `Interpreter` if not compiled.
+    pub tier: CompilationTier,
+}
+
 impl Default for JitEngine {
     fn default() -> Self {
         Self::new()
@@ -1133,6 +1287,63 @@ mod tests {
         assert_eq!(engine.cache_stats().entries, 0);
     }
 
+    #[test]
+    fn test_profile_report_uses_debug_names() {
+        let engine = JitEngine::new();
+        let wasm = simple_wasm_bytecode();
+        let mut module = Module::empty();
+        module.names.functions.insert(0, String::from("hot_loop"));
+
+        for _ in 0..100 {
+            let _ = engine.get_or_compile(FunctionId::new(1, 0), &wasm);
+        }
+        let _ = engine.get_or_compile(FunctionId::new(1, 1), &wasm);
+        let _ = engine.get_or_compile(FunctionId::new(2, 0), &wasm);
+
+        let report = engine.profile_report(1, &module);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].name, "hot_loop");
+        assert_eq!(report[0].tier, CompilationTier::Baseline);
+        assert_eq!(report[1].name, "func[1]");
+        assert_eq!(report[1].tier, CompilationTier::Interpreter);
+    }
+
+    #[test]
+    fn test_hot_loop_promotes_rarely_called_function() {
+        let engine = JitEngine::with_options(JitOptions {
+            osr_enabled: true,
+            ..JitOptions::default()
+        });
+        let func_id = FunctionId::new(1, 0);
+        assert_eq!(
+            engine.update_profile_and_get_tier(func_id),
+            CompilationTier::Interpreter
+        );
+
+        // One call whose loop at instruction 3 ran 20k times
+        let mut profile = ProfileData::new();
+        profile.record_call();
+        for _ in 0..20_000 {
+            profile.record_back_edge(3);
+        }
+        profile.record_back_edge(9);
+        let mut profiles = BTreeMap::new();
+        profiles.insert(0, profile);
+        engine.merge_profiles(1, &profiles);
+
+        // 2 calls + 20001 / 100 iterations
+        assert_eq!(engine.tier(func_id), CompilationTier::Baseline);
+        assert_eq!(engine.osr_candidate(func_id), Some(3));
+        assert_eq!(
+            engine.tier(FunctionId::new(1, 1)),
+            CompilationTier::Interpreter
+        );
+
+        let report = engine.profile_report(1, &Module::empty());
+        assert_eq!(report[0].call_count, 2);
+        assert_eq!(report[0].loop_iterations, 20_001);
+    }
+
     // ────────────────────────────────────────────────────────────────────
     // Additional structural tests
     // ────────────────────────────────────────────────────────────────────
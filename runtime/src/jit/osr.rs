@@ -0,0 +1,277 @@
+//! On-Stack Replacement
+//!
+//! Tiering happens at call boundaries, so a function that is called once
+//! and spends its time in a loop would never leave the interpreter. OSR
+//! lets the interpreter hand such a loop over to compiled code while the
+//! call is still running: once a loop's back-edges reach the threshold,
+//! the function is compiled with an extra entry at the loop header (see
+//! [`CodeGenerator::generate_osr`]), the interpreter's locals are copied
+//! into the native frame, and the rest of the call runs natively.
+//!
+//! [`CodeGenerator::generate_osr`]: super::codegen::CodeGenerator::generate_osr
+
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::codegen::NativeCode;
+use super::ir::{IrExecResult, IrFunction, IrOpcode, IrTrap, IrType};
+use super::trap::catch_traps;
+use super::{FunctionId, JitEngine};
+use crate::interpreter::TrapError;
+
+/// Interpreter state at a loop header, handed to an [`OsrHook`].
+#[derive(Debug, Clone, Copy)]
+pub struct OsrFrame<'a> {
+    /// Instruction index of the loop.
+    pub loop_pc: usize,
+    /// All locals, params first.
+    pub locals: &'a [i64],
+    /// The value stack.
+    pub stack: &'a [i64],
+}
+
+/// Decides when and how a running interpreter call moves to native code.
+pub trait OsrHook {
+    /// Back-edges a loop takes before [`OsrHook::enter`] is offered it.
+    fn threshold(&self) -> u64;
+
+    /// Finish the call from `frame` in compiled code and return its
+    /// result, or `None` to keep interpreting.
+    fn enter(&mut self, func: &IrFunction, frame: &OsrFrame<'_>) -> Option<IrExecResult>;
+}
+
+/// OSR through a [`JitEngine`].
+///
+/// The engine compiles and caches the OSR entry; `run` executes it,
+/// passing the locals array as the only argument. `run` is called inside
+/// [`catch_traps`], so a fault in the native code ends the call with a
+/// trap; it must not own resources across the call. Functions that need
+/// module context (calls, memory, globals) stay in the interpreter.
+pub struct EngineOsr<'a, R> {
+    engine: &'a JitEngine,
+    func_id: FunctionId,
+    run: R,
+}
+
+impl<'a, R> EngineOsr<'a, R>
+where
+    R: FnMut(&NativeCode, &[i64]) -> i64,
+{
+    pub fn new(engine: &'a JitEngine, func_id: FunctionId, run: R) -> Self {
+        Self {
+            engine,
+            func_id,
+            run,
+        }
+    }
+}
+
+impl<R> OsrHook for EngineOsr<'_, R>
+where
+    R: FnMut(&NativeCode, &[i64]) -> i64,
+{
+    fn threshold(&self) -> u64 {
+        self.engine.osr_threshold().unwrap_or(u64::MAX)
+    }
+
+    fn enter(&mut self, func: &IrFunction, frame: &OsrFrame<'_>) -> Option<IrExecResult> {
+        if !frame.stack.is_empty() || func.results.len() > 1 || !is_self_contained(func) {
+            return None;
+        }
+        let code = self
+            .engine
+            .compile_osr(self.func_id, func, frame.loop_pc)
+            .ok()?;
+        self.engine.stats().record_osr_entry();
+
+        let run = &mut self.run;
+        let value = match catch_traps(None, || run(&code, frame.locals)) {
+            Ok(value) => value,
+            Err(trap) => return Some(IrExecResult::Trap(ir_trap(trap))),
+        };
+        // The interpreter keeps i32 values sign-extended
+        let results = match func.results.first() {
+            None => Vec::new(),
+            Some(IrType::I32) => vec![value as i32 as i64],
+            Some(_) => vec![value],
+        };
+        Some(IrExecResult::Ok(results))
+    }
+}
+
+/// Map a trap raised by native code to the interpreter's trap.
+fn ir_trap(trap: TrapError) -> IrTrap {
+    match trap {
+        TrapError::DivisionByZero => IrTrap::DivisionByZero,
+        TrapError::IntegerOverflow => IrTrap::IntegerOverflow,
+        TrapError::Unreachable => IrTrap::Unreachable,
+        TrapError::MemoryOutOfBounds { .. } => IrTrap::MemoryBoundsViolation,
+        TrapError::StackOverflow | TrapError::CallStackExhausted => IrTrap::StackOverflow,
+        other => IrTrap::NativeFault(other.to_string()),
+    }
+}
+
+/// Whether compiled code for `func` can run without a module instance.
+fn is_self_contained(func: &IrFunction) -> bool {
+    func.body.iter().all(|inst| {
+        !matches!(
+            inst.opcode,
+            IrOpcode::Call(_)
+                | IrOpcode::CallIndirect(_)
+                | IrOpcode::GlobalGet(_)
+                | IrOpcode::GlobalSet(_)
+                | IrOpcode::Load32(_)
+                | IrOpcode::Load64(_)
+                | IrOpcode::Load8S(_)
+                | IrOpcode::Load8U(_)
+                | IrOpcode::Load16S(_)
+                | IrOpcode::Load16U(_)
+                | IrOpcode::Store32(_)
+                | IrOpcode::Store64(_)
+                | IrOpcode::Store8(_)
+                | IrOpcode::Store16(_)
+                | IrOpcode::MemorySize
+                | IrOpcode::MemoryGrow
+                | IrOpcode::RefFunc(_)
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jit::compiler::CompilationError;
+    use crate::jit::ir::{BlockId, IrInstruction, IrInterpreter};
+    use crate::jit::JitOptions;
+    use core::sync::atomic::Ordering;
+
+    /// `sum = 0 + 1 + ... + (n - 1)` in a single call.
+    fn sum_loop() -> IrFunction {
+        use IrOpcode::*;
+        let mut func = IrFunction::new(0, vec![IrType::I32], vec![IrType::I32]);
+        func.add_local(IrType::I32); // sum
+        func.add_local(IrType::I32); // i
+        for op in [
+            Block(BlockId(0)),
+            Loop(BlockId(1)),
+            LocalGet(2),
+            LocalGet(0),
+            I32GeS,
+            BrIf(1),
+            LocalGet(1),
+            LocalGet(2),
+            I32Add,
+            LocalSet(1),
+            LocalGet(2),
+            Const32(1),
+            I32Add,
+            LocalSet(2),
+            Br(0),
+            End,
+            End,
+            LocalGet(1),
+        ] {
+            func.add_instruction(IrInstruction::new(op, 0));
+        }
+        func
+    }
+
+    fn osr_engine() -> JitEngine {
+        JitEngine::with_options(JitOptions {
+            osr_enabled: true,
+            baseline_threshold: 10,
+            loop_weight: 100,
+            ..JitOptions::default()
+        })
+    }
+
+    #[test]
+    fn test_osr_disabled_keeps_interpreting() {
+        let engine = JitEngine::new();
+        let func = sum_loop();
+        let func_id = FunctionId::new(1, 0);
+        let mut osr = EngineOsr::new(&engine, func_id, |_: &NativeCode, _: &[i64]| {
+            panic!("OSR is disabled")
+        });
+
+        let result = IrInterpreter::new().execute_with_osr(&func, &[5000], &mut osr);
+        assert_eq!(result, IrExecResult::Ok(vec![5000 * 4999 / 2]));
+        assert_eq!(engine.osr_threshold(), None);
+        assert!(matches!(
+            engine.compile_osr(func_id, &func, 1),
+            Err(CompilationError::OsrDisabled)
+        ));
+    }
+
+    #[test]
+    fn test_osr_declines_functions_needing_module() {
+        let engine = osr_engine();
+        let mut func = sum_loop();
+        // Touch memory after the loop
+        func.body
+            .insert(17, IrInstruction::new(IrOpcode::MemorySize, 0));
+        func.body.insert(18, IrInstruction::new(IrOpcode::Drop, 0));
+        let mut osr = EngineOsr::new(
+            &engine,
+            FunctionId::new(1, 0),
+            |_: &NativeCode, _: &[i64]| panic!("function needs a module instance"),
+        );
+
+        let result = IrInterpreter::new().execute_with_osr(&func, &[5000], &mut osr);
+        assert_eq!(result, IrExecResult::Ok(vec![5000 * 4999 / 2]));
+        assert_eq!(engine.stats().osr_entries.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn test_osr_enters_one_call_hot_loop() {
+        use crate::jit::codegen::run_native;
+
+        let _serial = crate::jit::trap::tests::SERIAL.lock();
+        let func = sum_loop();
+        let n = 300_000i64;
+        let expected = IrExecResult::Ok(vec![(n * (n - 1) / 2) as i32 as i64]);
+        assert_eq!(IrInterpreter::new().execute(&func, &[n]), expected);
+
+        // The function is called once, so only OSR can tier it up
+        let engine = osr_engine();
+        let threshold = engine.osr_threshold().unwrap() as i64;
+        let mut entries = Vec::new();
+        let mut osr = EngineOsr::new(&engine, FunctionId::new(1, 0), |code, locals| {
+            entries.push(locals.to_vec());
+            run_native(code, locals)
+        });
+        let replaced = IrInterpreter::new().execute_with_osr(&func, &[n], &mut osr);
+        assert_eq!(replaced, expected);
+
+        // Native code took over once, after `threshold` iterations, and
+        // ran the remaining ones
+        assert_eq!(
+            entries,
+            [vec![n, threshold * (threshold - 1) / 2, threshold]]
+        );
+        let stats = engine.stats();
+        assert_eq!(stats.osr_compilations.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.osr_entries.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_osr_trap_ends_the_call() {
+        use crate::jit::trap::raise_trap;
+
+        let _serial = crate::jit::trap::tests::SERIAL.lock();
+        let engine = osr_engine();
+        let mut osr = EngineOsr::new(
+            &engine,
+            FunctionId::new(1, 0),
+            |_: &NativeCode, _: &[i64]| raise_trap(TrapError::Unreachable),
+        );
+        let result = IrInterpreter::new().execute_with_osr(&sum_loop(), &[5000], &mut osr);
+        assert_eq!(result, IrExecResult::Trap(IrTrap::Unreachable));
+        assert_eq!(engine.stats().osr_entries.load(Ordering::Relaxed), 1);
+
+        let trap = TrapError::ExecutionError("PageFault in JIT code".into());
+        assert!(matches!(ir_trap(trap), IrTrap::NativeFault(_)));
+    }
+}
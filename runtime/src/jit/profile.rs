@@ -12,8 +12,11 @@ use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 pub struct ProfileData {
     /// Number of times the function was called.
     pub call_count: u32,
-    /// Number of times loops executed.
+    /// Number of times loops executed (back-edges taken).
     pub loop_iterations: u64,
+    /// Back-edges taken per loop, keyed by the instruction index of the
+    /// loop header.
+    pub loop_counts: BTreeMap<u32, u64>,
     /// Branch taken/not-taken statistics.
     pub branch_stats: Vec<BranchStats>,
     /// Type feedback for polymorphic calls.
@@ -30,6 +33,7 @@ impl ProfileData {
         Self {
             call_count: 0,
             loop_iterations: 0,
+            loop_counts: BTreeMap::new(),
             branch_stats: Vec::new(),
             type_feedback: Vec::new(),
             allocation_sites: Vec::new(),
@@ -47,6 +51,21 @@ impl ProfileData {
         self.loop_iterations = self.loop_iterations.saturating_add(iterations);
     }
 
+    /// Record a branch back to the header of the loop at `loop_pc`.
+    pub fn record_back_edge(&mut self, loop_pc: u32) {
+        self.loop_iterations = self.loop_iterations.saturating_add(1);
+        let count = self.loop_counts.entry(loop_pc).or_insert(0);
+        *count = count.saturating_add(1);
+    }
+
+    /// The loop with the most back-edges, as `(loop_pc, count)`.
+    pub fn hottest_loop(&self) -> Option<(u32, u64)> {
+        self.loop_counts
+            .iter()
+            .max_by_key(|(_, &count)| count)
+            .map(|(&pc, &count)| (pc, count))
+    }
+
     /// Record branch taken.
     pub fn record_branch_taken(&mut self, branch_idx: usize) {
         self.ensure_branch(branch_idx);
@@ -79,10 +98,23 @@ impl ProfileData {
         self.call_count >= threshold
     }
 
+    /// Hotness in call equivalents: calls plus loop iterations, with
+    /// `loop_weight` iterations counting as one call.
+    ///
+    /// A function called once but spinning in a long loop scores as hot
+    /// as one called many times.
+    pub fn hotness(&self, loop_weight: u32) -> u64 {
+        self.call_count as u64 + self.loop_iterations / loop_weight.max(1) as u64
+    }
+
     /// Merge with another profile.
     pub fn merge(&mut self, other: &ProfileData) {
         self.call_count = self.call_count.saturating_add(other.call_count);
         self.loop_iterations = self.loop_iterations.saturating_add(other.loop_iterations);
+        for (&pc, &count) in &other.loop_counts {
+            let total = self.loop_counts.entry(pc).or_insert(0);
+            *total = total.saturating_add(count);
+        }
 
         for (i, other_stats) in other.branch_stats.iter().enumerate() {
             self.ensure_branch(i);
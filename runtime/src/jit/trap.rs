@@ -0,0 +1,449 @@
+//! Trap Handling for JIT Code
+//!
+//! JIT-compiled code does not check for every trap condition in software.
+//! `unreachable` compiles to `ud2`, integer division relies on the CPU's
+//! divide error, and linear memory accesses past the end hit unmapped
+//! guard pages. Each of these raises a CPU fault that must become a WASM
+//! trap for the one instance instead of taking down the kernel.
+//!
+//! Host code enters JIT code through [`catch_traps`], which records a
+//! call boundary (callee-saved registers, stack pointer and return
+//! address). The kernel's exception handlers pass kernel-mode faults to
+//! [`handle_fault`]: if the faulting instruction lies in a code region
+//! registered by [`ExecutableMemoryManager`](super::executable::ExecutableMemoryManager),
+//! the fault is classified and the handler resumes at a landing pad that
+//! restores the boundary, so [`catch_traps`] returns the trap as an
+//! ordinary [`TrapError`]. Callers such as `Instance::call` then report
+//! it exactly like an interpreter trap.
+//!
+//! Unwinding skips the frames between the boundary and the fault without
+//! running destructors, so code running inside [`catch_traps`] must not
+//! own resources that need dropping.
+//!
+//! The active boundary is global: JIT code runs on one CPU at a time.
+
+use alloc::format;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use spin::RwLock;
+
+use crate::interpreter::TrapError;
+
+/// CPU fault raised while executing JIT code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuFault {
+    /// Divide error (#DE).
+    DivideError,
+    /// Invalid opcode (#UD).
+    InvalidOpcode,
+    /// General protection fault (#GP).
+    GeneralProtection,
+    /// Page fault (#PF) at the given address.
+    PageFault { address: u64 },
+}
+
+/// Linear memory reservation of the instance being executed.
+///
+/// JIT code addresses linear memory directly; everything between the end
+/// of the accessible memory and the end of the reservation is unmapped,
+/// so out-of-bounds accesses fault inside this region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardRegion {
+    /// Address of linear memory offset 0.
+    pub base: u64,
+    /// Accessible memory size in bytes.
+    pub memory_size: usize,
+    /// Total reserved size in bytes, including guard pages.
+    pub reserved: usize,
+}
+
+impl GuardRegion {
+    /// Get the linear memory offset of an address inside the reservation.
+    pub fn offset_of(&self, address: u64) -> Option<usize> {
+        let offset = address.checked_sub(self.base)?;
+        (offset < self.reserved as u64).then_some(offset as usize)
+    }
+}
+
+/// Trap kind derived from a CPU fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapKind {
+    /// `ud2` executed (`unreachable`).
+    Unreachable,
+    /// Integer division faulted.
+    IntegerDivision,
+    /// Linear memory access hit the guard region.
+    MemoryOutOfBounds { offset: usize, memory_size: usize },
+    /// Any other fault in JIT code.
+    Fault { fault: CpuFault, rip: u64 },
+}
+
+impl TrapKind {
+    /// Classify a fault given the two bytes at the faulting instruction.
+    pub fn from_fault(
+        fault: CpuFault,
+        rip: u64,
+        opcode: [u8; 2],
+        guard: Option<&GuardRegion>,
+    ) -> Self {
+        match fault {
+            CpuFault::InvalidOpcode if opcode == UD2 => TrapKind::Unreachable,
+            CpuFault::DivideError => TrapKind::IntegerDivision,
+            CpuFault::PageFault { address } => {
+                match guard.and_then(|g| Some((g.offset_of(address)?, g.memory_size))) {
+                    Some((offset, memory_size)) => TrapKind::MemoryOutOfBounds {
+                        offset,
+                        memory_size,
+                    },
+                    None => TrapKind::Fault { fault, rip },
+                }
+            }
+            _ => TrapKind::Fault { fault, rip },
+        }
+    }
+
+    /// Convert into the trap reported to the host.
+    ///
+    /// A divide error is reported as division by zero; `INT_MIN / -1`
+    /// raises the same fault and cannot be told apart here.
+    pub fn into_trap(self) -> TrapError {
+        match self {
+            TrapKind::Unreachable => TrapError::Unreachable,
+            TrapKind::IntegerDivision => TrapError::DivisionByZero,
+            TrapKind::MemoryOutOfBounds {
+                offset,
+                memory_size,
+            } => TrapError::MemoryOutOfBounds {
+                offset,
+                size: 1,
+                memory_size,
+            },
+            TrapKind::Fault { fault, rip } => {
+                TrapError::ExecutionError(format!("{:?} in JIT code at {:#x}", fault, rip))
+            }
+        }
+    }
+}
+
+/// Encoding of `ud2`.
+const UD2: [u8; 2] = [0x0F, 0x0B];
+
+/// Address ranges of executable JIT code.
+static CODE_RANGES: RwLock<Vec<Range<usize>>> = RwLock::new(Vec::new());
+
+/// Register a range of executable JIT code.
+pub(crate) fn register_code(code: &[u8]) {
+    let range = code.as_ptr_range();
+    CODE_RANGES
+        .write()
+        .push(range.start as usize..range.end as usize);
+}
+
+/// Unregister a range previously passed to [`register_code`].
+pub(crate) fn unregister_code(code: &[u8]) {
+    let start = code.as_ptr() as usize;
+    CODE_RANGES.write().retain(|r| r.start != start);
+}
+
+/// Check if an address lies in registered JIT code.
+///
+/// Never blocks: if the registry is being updated, the address is
+/// treated as foreign.
+pub fn is_jit_code(address: u64) -> bool {
+    let address = address as usize;
+    CODE_RANGES
+        .try_read()
+        .is_some_and(|ranges| ranges.iter().any(|r| r.contains(&address)))
+}
+
+/// Registers restored when unwinding to a call boundary.
+#[repr(C)]
+#[derive(Default)]
+struct JmpBuf {
+    rbx: u64,
+    rbp: u64,
+    r12: u64,
+    r13: u64,
+    r14: u64,
+    r15: u64,
+    rsp: u64,
+    rip: u64,
+}
+
+/// A call boundary established by [`catch_traps`].
+#[repr(C)]
+struct TrapBoundary {
+    /// Must stay first: the landing pad treats the boundary as a `JmpBuf`.
+    jmp: JmpBuf,
+    guard: Option<GuardRegion>,
+    /// Set by the fault handler.
+    fault: Option<TrapKind>,
+    /// Set by [`raise_trap`].
+    trap: Option<TrapError>,
+    prev: *mut TrapBoundary,
+}
+
+/// Innermost active call boundary.
+static ACTIVE: AtomicPtr<TrapBoundary> = AtomicPtr::new(ptr::null_mut());
+
+extern "sysv64" {
+    /// Save the boundary registers, call `entry(arg)` and return 0, or
+    /// return non-zero when unwound by [`kpio_jit_resume`].
+    fn kpio_jit_enter(buf: *mut JmpBuf, entry: extern "sysv64" fn(*mut u8), arg: *mut u8) -> u64;
+    /// Unwind to the boundary saved in `buf`.
+    fn kpio_jit_resume(buf: *mut JmpBuf) -> !;
+    /// Fault resume address: unwinds to the active boundary.
+    fn kpio_jit_trap_landing() -> !;
+}
+
+core::arch::global_asm!(
+    ".text",
+    ".p2align 4",
+    ".globl kpio_jit_enter",
+    "kpio_jit_enter:",
+    "    mov [rdi], rbx",
+    "    mov [rdi + 8], rbp",
+    "    mov [rdi + 16], r12",
+    "    mov [rdi + 24], r13",
+    "    mov [rdi + 32], r14",
+    "    mov [rdi + 40], r15",
+    "    lea rax, [rsp + 8]",
+    "    mov [rdi + 48], rax",
+    "    mov rax, [rsp]",
+    "    mov [rdi + 56], rax",
+    // Keep the stack 16-byte aligned across the call
+    "    sub rsp, 8",
+    "    mov rdi, rdx",
+    "    call rsi",
+    "    add rsp, 8",
+    "    xor eax, eax",
+    "    ret",
+    ".p2align 4",
+    ".globl kpio_jit_trap_landing",
+    "kpio_jit_trap_landing:",
+    "    mov rdi, [rip + {active}]",
+    ".globl kpio_jit_resume",
+    "kpio_jit_resume:",
+    "    mov rbx, [rdi]",
+    "    mov rbp, [rdi + 8]",
+    "    mov r12, [rdi + 16]",
+    "    mov r13, [rdi + 24]",
+    "    mov r14, [rdi + 32]",
+    "    mov r15, [rdi + 40]",
+    "    mov rsp, [rdi + 48]",
+    "    mov eax, 1",
+    "    jmp qword ptr [rdi + 56]",
+    active = sym ACTIVE,
+);
+
+extern "sysv64" fn call_closure<F: FnOnce()>(arg: *mut u8) {
+    // SAFETY: `arg` points to the `Option<F>` owned by `enter`.
+    let slot = unsafe { &mut *(arg as *mut Option<F>) };
+    if let Some(f) = slot.take() {
+        f();
+    }
+}
+
+/// Run `f` at a new call boundary. Returns non-zero if it was unwound.
+fn enter<F: FnOnce()>(boundary: *mut TrapBoundary, f: F) -> u64 {
+    let mut slot = Some(f);
+    // SAFETY: the boundary outlives the call; `call_closure::<F>` matches
+    // the type behind the argument pointer.
+    unsafe {
+        kpio_jit_enter(
+            boundary as *mut JmpBuf,
+            call_closure::<F>,
+            &mut slot as *mut Option<F> as *mut u8,
+        )
+    }
+}
+
+/// Run `f`, turning faults in JIT code it calls into traps.
+///
+/// `guard` describes the linear memory the JIT code accesses, so that
+/// guard page hits are reported as out-of-bounds accesses. Boundaries
+/// nest; a trap unwinds to the innermost one.
+pub fn catch_traps<R, F: FnOnce() -> R>(guard: Option<GuardRegion>, f: F) -> Result<R, TrapError> {
+    let mut boundary = TrapBoundary {
+        jmp: JmpBuf::default(),
+        guard,
+        fault: None,
+        trap: None,
+        prev: ACTIVE.load(Ordering::Acquire),
+    };
+    let boundary_ptr: *mut TrapBoundary = &mut boundary;
+    ACTIVE.store(boundary_ptr, Ordering::Release);
+
+    let mut result = None;
+    let unwound = enter(boundary_ptr, || result = Some(f()));
+
+    // SAFETY: the boundary is still live; nothing else refers to it now.
+    let boundary = unsafe { &mut *boundary_ptr };
+    ACTIVE.store(boundary.prev, Ordering::Release);
+
+    match (unwound, result) {
+        (0, Some(value)) => Ok(value),
+        _ => Err(boundary
+            .trap
+            .take()
+            .or_else(|| boundary.fault.map(TrapKind::into_trap))
+            .unwrap_or_else(|| TrapError::ExecutionError("JIT code unwound".into()))),
+    }
+}
+
+/// Raise a trap from host or runtime code called by JIT code, unwinding
+/// to the innermost [`catch_traps`].
+///
+/// # Panics
+///
+/// Panics if no call boundary is active.
+pub fn raise_trap(trap: TrapError) -> ! {
+    let boundary = ACTIVE.load(Ordering::Acquire);
+    assert!(!boundary.is_null(), "raise_trap outside of catch_traps");
+    // SAFETY: the active boundary lives in a `catch_traps` frame below us.
+    unsafe {
+        (*boundary).trap = Some(trap);
+        kpio_jit_resume(boundary as *mut JmpBuf)
+    }
+}
+
+/// Handle a kernel-mode CPU fault.
+///
+/// Called by the exception handlers with the faulting instruction
+/// pointer. If the fault came from registered JIT code inside a call
+/// boundary, records the trap and returns the address the handler must
+/// resume at. Returns `None` for faults the runtime does not own.
+pub fn handle_fault(fault: CpuFault, rip: u64) -> Option<u64> {
+    let boundary = ACTIVE.load(Ordering::Acquire);
+    if boundary.is_null() || !is_jit_code(rip) {
+        return None;
+    }
+
+    let opcode = if fault == CpuFault::InvalidOpcode && is_jit_code(rip + 1) {
+        // SAFETY: both bytes lie in registered, live JIT code.
+        unsafe { ptr::read_volatile(rip as *const [u8; 2]) }
+    } else {
+        [0; 2]
+    };
+
+    // SAFETY: the active boundary lives in a `catch_traps` frame on the
+    // faulting stack.
+    unsafe {
+        let kind = TrapKind::from_fault(fault, rip, opcode, (*boundary).guard.as_ref());
+        (*boundary).fault = Some(kind);
+    }
+    Some(kpio_jit_trap_landing as *const () as u64)
+}
+
+/// Route kernel exception fixups to [`handle_fault`].
+#[cfg(feature = "kernel")]
+pub fn install() {
+    use kpio_kernel::interrupts::{register_fault_fixup, FaultKind};
+
+    fn fixup(kind: FaultKind, rip: u64) -> Option<u64> {
+        let fault = match kind {
+            FaultKind::DivideError => CpuFault::DivideError,
+            FaultKind::InvalidOpcode => CpuFault::InvalidOpcode,
+            FaultKind::GeneralProtection => CpuFault::GeneralProtection,
+            FaultKind::PageFault(address) => CpuFault::PageFault { address },
+        };
+        handle_fault(fault, rip)
+    }
+
+    register_fault_fixup(fixup);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloc::vec;
+    use spin::Mutex;
+
+    /// The active boundary is global; keep tests from interleaving.
+    pub(crate) static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_classify_faults() {
+        let guard = GuardRegion {
+            base: 0x10_0000,
+            memory_size: 0x1_0000,
+            reserved: 0x2_0000,
+        };
+
+        let kind = TrapKind::from_fault(CpuFault::InvalidOpcode, 0x1000, UD2, None);
+        assert_eq!(kind, TrapKind::Unreachable);
+        let kind = TrapKind::from_fault(CpuFault::DivideError, 0x1000, [0; 2], None);
+        assert!(matches!(kind.into_trap(), TrapError::DivisionByZero));
+
+        let fault = CpuFault::PageFault { address: 0x11_0004 };
+        let kind = TrapKind::from_fault(fault, 0x1000, [0; 2], Some(&guard));
+        assert_eq!(
+            kind,
+            TrapKind::MemoryOutOfBounds {
+                offset: 0x1_0004,
+                memory_size: 0x1_0000
+            }
+        );
+
+        // Outside the reservation: a stray access, not a bounds violation
+        let fault = CpuFault::PageFault { address: 0x13_0000 };
+        let kind = TrapKind::from_fault(fault, 0x1000, [0; 2], Some(&guard));
+        assert_eq!(kind, TrapKind::Fault { fault, rip: 0x1000 });
+        assert!(matches!(kind.into_trap(), TrapError::ExecutionError(_)));
+    }
+
+    #[test]
+    fn test_catch_traps_returns_value() {
+        let _serial = SERIAL.lock();
+        assert_eq!(catch_traps(None, || 42).unwrap(), 42);
+        assert!(ACTIVE.load(Ordering::Acquire).is_null());
+    }
+
+    #[test]
+    fn test_raise_trap_unwinds_nested_boundaries() {
+        let _serial = SERIAL.lock();
+        let mut reached = false;
+        let outer = catch_traps(None, || {
+            let inner = catch_traps(None, || {
+                raise_trap(TrapError::Unreachable);
+            });
+            assert!(matches!(inner, Err(TrapError::Unreachable)));
+            reached = true;
+            raise_trap(TrapError::FuelExhausted)
+        });
+        assert!(reached);
+        assert!(matches!(outer, Err(TrapError::FuelExhausted)));
+        assert!(ACTIVE.load(Ordering::Acquire).is_null());
+    }
+
+    #[test]
+    fn test_fault_in_jit_code_resumes_at_boundary() {
+        let _serial = SERIAL.lock();
+        let code = vec![0x90, 0x0F, 0x0B, 0xC3]; // nop; ud2; ret
+        register_code(&code);
+        let rip = code.as_ptr() as u64 + 1;
+
+        // Faults outside a boundary or outside JIT code are not ours
+        assert_eq!(handle_fault(CpuFault::InvalidOpcode, rip), None);
+
+        let result: Result<(), TrapError> = catch_traps(None, || {
+            let local = 0u8;
+            assert_eq!(
+                handle_fault(CpuFault::InvalidOpcode, &local as *const u8 as u64),
+                None
+            );
+
+            let landing = handle_fault(CpuFault::InvalidOpcode, rip).unwrap();
+            // Resume where the exception handler would
+            // SAFETY: the landing pad unwinds to this boundary.
+            let landing: extern "sysv64" fn() -> ! = unsafe { core::mem::transmute(landing) };
+            landing()
+        });
+        assert!(matches!(result, Err(TrapError::Unreachable)));
+
+        unregister_code(&code);
+        assert!(!is_jit_code(rip));
+    }
+}
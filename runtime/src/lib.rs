@@ -11,14 +11,16 @@
 //!
 //! - `parser`: WASM binary parser (sections + instruction decoding)
 //! - `module`: Parsed module representation + structural validation
-//! - `instance`: Instantiation + import resolution
+//! - `module_cache`: Parsed-module cache keyed by SHA-256 of the binary
+//! - `instance`: Instantiation + import resolution (`Linker` for named host modules)
 //! - `executor` / `interpreter`: Stack-machine execution and traps
+//! - `externref`: Reference-counted host objects behind `externref` handles
 //! - `wasi`: WASI Preview 1 context + in-memory VFS
 //! - `wasi2`: WASI Preview 2 (streams, filesystem, clocks, random, CLI, sockets, HTTP)
 //! - `host`: Host function bindings (WASI + KPIO/GPU/NET)
 //! - `host_gui` / `host_system` / `host_net`: KPIO-specific host API bindings
 //! - `memory` / `sandbox`: Linear memory + resource limiting
-//! - `jit`: Tiered JIT compiler (IR + x86_64 codegen + cache + profiling + benchmarks)
+//! - `jit`: Tiered JIT compiler (IR + x86_64 codegen + cache + profiling + benchmarks + fault traps)
 //! - `wit`: WebAssembly Interface Types (WIT) parser and type system
 //! - `component`: WASM Component Model (canonical ABI, linker, instances, WASI bridge)
 //! - `package`: `.kpioapp` ZIP-based application package format
@@ -33,9 +35,11 @@
 extern crate alloc;
 
 pub mod app_launcher;
+pub mod clock;
 pub mod component;
 pub mod engine;
 pub mod executor;
+pub mod externref;
 pub mod host;
 pub mod host_gui;
 pub mod host_net;
@@ -45,6 +49,7 @@ pub mod interpreter;
 pub mod jit;
 pub mod memory;
 pub mod module;
+pub mod module_cache;
 pub mod opcodes;
 pub mod package;
 pub mod parser;
@@ -84,6 +89,9 @@ pub enum RuntimeError {
 #[derive(Debug, Clone)]
 pub struct RuntimeConfig {
     /// Maximum memory per instance (in pages, 64KB each).
+    ///
+    /// Enforced on 64-bit memories, which would otherwise be able to grow
+    /// to [`memory::MAX_MEMORY64_SIZE`].
     pub max_memory_pages: u32,
     /// Maximum table size.
     pub max_table_size: u32,
@@ -101,6 +109,8 @@ pub struct RuntimeConfig {
     pub enable_fuel: bool,
     /// Initial fuel amount.
     pub initial_fuel: u64,
+    /// Maximum nested call depth before execution traps.
+    pub max_call_depth: usize,
 }
 
 impl Default for RuntimeConfig {
@@ -115,6 +125,7 @@ impl Default for RuntimeConfig {
             stack_size: 1024 * 1024, // 1 MB
             enable_fuel: true,
             initial_fuel: 1_000_000,
+            max_call_depth: interpreter::DEFAULT_MAX_CALL_DEPTH,
         }
     }
 }
@@ -122,17 +133,23 @@ impl Default for RuntimeConfig {
 /// Initialize the WASM runtime.
 pub fn init() -> Result<(), RuntimeError> {
     engine::init()?;
+    // Turn faults in JIT code into traps instead of kernel panics
+    #[cfg(feature = "kernel")]
+    jit::trap::install();
     Ok(())
 }
 
 /// Execute a WASM module.
+///
+/// The parsed module is cached, so repeated calls with identical bytes
+/// skip parsing and validation.
 pub fn execute(wasm_bytes: &[u8], entry_point: &str, args: &[u8]) -> Result<Vec<u8>, RuntimeError> {
-    let module = module::Module::from_bytes(wasm_bytes)?;
+    let module = module_cache::load(wasm_bytes)?;
     let mut inst = instance::Instance::new(&module)?;
     inst.call(entry_point, args)
 }
 
 /// Load and validate a WASM module without executing.
 pub fn validate(wasm_bytes: &[u8]) -> Result<(), RuntimeError> {
-    module::Module::validate(wasm_bytes)
+    module_cache::load(wasm_bytes).map(|_| ())
 }
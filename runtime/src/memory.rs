@@ -14,6 +14,11 @@ pub const PAGE_SIZE: usize = 65536;
 /// Maximum memory size (4 GB).
 pub const MAX_MEMORY_SIZE: usize = 4 * 1024 * 1024 * 1024;
 
+/// Maximum size of a 64-bit memory (16 GB).
+///
+/// Embedders can lower it per instance with [`LinearMemory::limit_pages`].
+pub const MAX_MEMORY64_SIZE: usize = 16 * 1024 * 1024 * 1024;
+
 /// Linear memory for a WASM instance.
 pub struct LinearMemory {
     /// Memory data.
@@ -24,21 +29,38 @@ pub struct LinearMemory {
 
     /// Maximum size in pages (if specified).
     max_pages: Option<u32>,
+
+    /// Addressed with 64-bit indices (`memory64`).
+    memory64: bool,
 }
 
 impl LinearMemory {
     /// Create a new linear memory.
     pub fn new(initial_pages: u32, max_pages: Option<u32>) -> Result<Self, RuntimeError> {
+        Self::with_index_type(initial_pages, max_pages, false)
+    }
+
+    /// Create a new 64-bit linear memory, which may grow past 4 GB.
+    pub fn new_64(initial_pages: u32, max_pages: Option<u32>) -> Result<Self, RuntimeError> {
+        Self::with_index_type(initial_pages, max_pages, true)
+    }
+
+    fn with_index_type(
+        initial_pages: u32,
+        max_pages: Option<u32>,
+        memory64: bool,
+    ) -> Result<Self, RuntimeError> {
+        let size_limit = Self::size_limit(memory64);
         let initial_size = initial_pages as usize * PAGE_SIZE;
 
-        if initial_size > MAX_MEMORY_SIZE {
+        if initial_size > size_limit {
             return Err(RuntimeError::MemoryError(
                 "Initial memory size exceeds maximum".into(),
             ));
         }
 
         if let Some(max) = max_pages {
-            if (max as usize * PAGE_SIZE) > MAX_MEMORY_SIZE {
+            if (max as usize * PAGE_SIZE) > size_limit {
                 return Err(RuntimeError::MemoryError(
                     "Maximum memory size exceeds limit".into(),
                 ));
@@ -50,16 +72,23 @@ impl LinearMemory {
             }
         }
 
-        let mut data = Vec::new();
-        data.resize(initial_size, 0);
-
         Ok(LinearMemory {
-            data,
+            data: vec![0; initial_size],
             current_pages: initial_pages,
             max_pages,
+            memory64,
         })
     }
 
+    /// Absolute size limit for a memory of the given index type.
+    fn size_limit(memory64: bool) -> usize {
+        if memory64 {
+            MAX_MEMORY64_SIZE
+        } else {
+            MAX_MEMORY_SIZE
+        }
+    }
+
     /// Get the current size in bytes.
     pub fn size(&self) -> usize {
         self.data.len()
@@ -75,6 +104,26 @@ impl LinearMemory {
         self.max_pages
     }
 
+    /// Whether this is a 64-bit memory.
+    pub fn is_64(&self) -> bool {
+        self.memory64
+    }
+
+    /// Cap the memory at `limit` pages, on top of its declared maximum.
+    ///
+    /// Fails if the memory is already larger than `limit`.
+    pub fn limit_pages(&mut self, limit: u32) -> Result<(), RuntimeError> {
+        if self.current_pages > limit {
+            return Err(RuntimeError::ResourceLimit(alloc::format!(
+                "Memory of {} pages exceeds limit of {} pages",
+                self.current_pages,
+                limit
+            )));
+        }
+        self.max_pages = Some(self.max_pages.map_or(limit, |max| max.min(limit)));
+        Ok(())
+    }
+
     /// Grow memory by the specified number of pages.
     /// Returns the previous size in pages, or an error if growth fails.
     pub fn grow(&mut self, delta_pages: u32) -> Result<u32, RuntimeError> {
@@ -92,7 +141,7 @@ impl LinearMemory {
         }
 
         let new_size = new_pages as usize * PAGE_SIZE;
-        if new_size > MAX_MEMORY_SIZE {
+        if new_size > Self::size_limit(self.memory64) {
             return Err(RuntimeError::MemoryError(
                 "Would exceed absolute maximum memory size".into(),
             ));
@@ -251,6 +300,7 @@ impl Clone for LinearMemory {
             data: self.data.clone(),
             current_pages: self.current_pages,
             max_pages: self.max_pages,
+            memory64: self.memory64,
         }
     }
 }
@@ -4,6 +4,7 @@
 //! All WASM sections are parsed into structured data that can be
 //! consumed by the interpreter engine and JIT compiler.
 
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 
@@ -38,6 +39,8 @@ pub struct Module {
     pub data: Vec<DataSegment>,
     /// Module name (from custom "name" section).
     pub name: Option<String>,
+    /// Function and local names (from custom "name" section).
+    pub names: NameSection,
     /// Data count section (for validation).
     pub data_count: Option<u32>,
 }
@@ -58,6 +61,7 @@ impl Module {
             code: Vec::new(),
             data: Vec::new(),
             name: None,
+            names: NameSection::default(),
             data_count: None,
         }
     }
@@ -113,6 +117,46 @@ impl Module {
         self.name.as_deref()
     }
 
+    /// Get a function's debug name from the "name" section.
+    pub fn function_name(&self, func_idx: u32) -> Option<&str> {
+        self.names.functions.get(&func_idx).map(String::as_str)
+    }
+
+    /// Get a local's debug name from the "name" section.
+    pub fn local_name(&self, func_idx: u32, local_idx: u32) -> Option<&str> {
+        self.names
+            .locals
+            .get(&func_idx)?
+            .get(&local_idx)
+            .map(String::as_str)
+    }
+
+    /// Describe a function for diagnostics.
+    ///
+    /// Prefers the debug name, then an import's `module.name` or an
+    /// export name, and falls back to `func[N]`.
+    pub fn function_label(&self, func_idx: u32) -> String {
+        if let Some(name) = self.function_name(func_idx) {
+            return String::from(name);
+        }
+        let import = self
+            .imports
+            .iter()
+            .filter(|i| matches!(i.kind, ImportKind::Function(_)))
+            .nth(func_idx as usize);
+        if let Some(import) = import {
+            return alloc::format!("{}.{}", import.module, import.name);
+        }
+        let export = self
+            .exports
+            .iter()
+            .find(|e| e.kind == ExportKind::Function && e.index == func_idx);
+        match export {
+            Some(export) => export.name.clone(),
+            None => alloc::format!("func[{}]", func_idx),
+        }
+    }
+
     /// Get exported functions.
     pub fn exports(&self) -> &[Export] {
         &self.exports
@@ -176,6 +220,18 @@ impl Module {
     }
 }
 
+/// Debug names from the custom "name" section.
+///
+/// Only the module, function and local subsections are kept; the section
+/// is optional, so every map may be empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameSection {
+    /// Function index → name.
+    pub functions: BTreeMap<u32, String>,
+    /// Function index → local index → name.
+    pub locals: BTreeMap<u32, BTreeMap<u32, String>>,
+}
+
 /// An exported function or value.
 #[derive(Debug, Clone)]
 pub struct Export {
@@ -260,11 +316,13 @@ pub struct TableType {
 #[derive(Debug, Clone)]
 pub struct MemoryType {
     /// Minimum pages (64KB each).
-    pub min: u32,
+    pub min: u64,
     /// Maximum pages (if specified).
-    pub max: Option<u32>,
+    pub max: Option<u64>,
     /// Is shared memory (for threads).
     pub shared: bool,
+    /// Uses 64-bit addresses (`memory64` proposal).
+    pub memory64: bool,
 }
 
 /// Global variable definition (type + init expression).
@@ -292,8 +350,8 @@ pub struct Element {
     pub table_idx: u32,
     /// Offset expression (empty if passive).
     pub offset_expr: Vec<Instruction>,
-    /// Function indices.
-    pub func_indices: Vec<u32>,
+    /// Function references (`None` = `ref.null`).
+    pub func_indices: Vec<Option<u32>>,
     /// Whether this is a passive segment.
     pub passive: bool,
 }
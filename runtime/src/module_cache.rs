@@ -0,0 +1,438 @@
+//! Parsed module cache.
+//!
+//! Parsing and validating a WASM binary is the dominant fixed cost of
+//! `execute`/`validate`. System-service modules are run repeatedly with
+//! identical bytes, so the result of `Module::from_bytes` is cached here,
+//! keyed by the SHA-256 digest of the binary. Any change to the bytes —
+//! even a single bit — yields a different key, so stale entries are never
+//! returned. Entries are evicted least-recently-used once either the entry
+//! count or the total binary size budget is exceeded.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::module::Module;
+use crate::RuntimeError;
+
+/// Default maximum number of cached modules.
+pub const DEFAULT_MAX_ENTRIES: usize = 64;
+
+/// Default budget for the summed size of cached binaries (16 MiB).
+pub const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// Global module cache used by [`crate::execute`] and [`crate::validate`].
+static MODULE_CACHE: Mutex<ModuleCache> =
+    Mutex::new(ModuleCache::new(DEFAULT_MAX_ENTRIES, DEFAULT_MAX_BYTES));
+
+/// SHA-256 digest of a WASM binary.
+pub type ModuleHash = [u8; 32];
+
+/// Load a module through the global cache.
+pub fn load(bytes: &[u8]) -> Result<Arc<Module>, RuntimeError> {
+    let hash = sha256(bytes);
+    if let Some(module) = MODULE_CACHE.lock().lookup(&hash, bytes.len()) {
+        return Ok(module);
+    }
+
+    // Parse without holding the lock; another caller may race us to the
+    // same insert, which is harmless.
+    let module = Arc::new(Module::from_bytes(bytes)?);
+    MODULE_CACHE
+        .lock()
+        .insert(hash, bytes.len(), module.clone());
+    Ok(module)
+}
+
+/// Get statistics for the global cache.
+pub fn stats() -> ModuleCacheStats {
+    MODULE_CACHE.lock().stats()
+}
+
+/// Drop every entry from the global cache.
+pub fn clear() {
+    MODULE_CACHE.lock().clear();
+}
+
+/// Change the limits of the global cache, evicting as needed.
+pub fn set_limits(max_entries: usize, max_bytes: usize) {
+    MODULE_CACHE.lock().set_limits(max_entries, max_bytes);
+}
+
+/// A cached, already-validated module.
+#[derive(Debug, Clone)]
+struct CachedModule {
+    /// The parsed module.
+    module: Arc<Module>,
+    /// Length of the source binary.
+    binary_size: usize,
+}
+
+/// Cache of parsed modules keyed by binary hash, with LRU eviction.
+pub struct ModuleCache {
+    /// Cached entries by binary hash.
+    entries: BTreeMap<ModuleHash, CachedModule>,
+    /// Access order for LRU (most recent last).
+    access_order: Vec<ModuleHash>,
+    /// Summed size of cached binaries.
+    total_bytes: usize,
+    /// Maximum number of entries.
+    max_entries: usize,
+    /// Maximum summed size of cached binaries.
+    max_bytes: usize,
+    /// Lookups served from the cache.
+    hits: u64,
+    /// Lookups that required a parse.
+    misses: u64,
+    /// Entries dropped to stay within limits.
+    evictions: u64,
+}
+
+impl ModuleCache {
+    /// Create an empty cache with the given limits.
+    pub const fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            access_order: Vec::new(),
+            total_bytes: 0,
+            max_entries,
+            max_bytes,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Return the cached module for `bytes`, parsing and caching it on a miss.
+    pub fn get_or_load(&mut self, bytes: &[u8]) -> Result<Arc<Module>, RuntimeError> {
+        let hash = sha256(bytes);
+        if let Some(module) = self.lookup(&hash, bytes.len()) {
+            return Ok(module);
+        }
+        let module = Arc::new(Module::from_bytes(bytes)?);
+        self.insert(hash, bytes.len(), module.clone());
+        Ok(module)
+    }
+
+    /// Look up a module by hash, updating hit/miss counters and LRU order.
+    pub fn lookup(&mut self, hash: &ModuleHash, binary_size: usize) -> Option<Arc<Module>> {
+        match self.entries.get(hash) {
+            Some(entry) if entry.binary_size == binary_size => {
+                let module = entry.module.clone();
+                self.hits += 1;
+                self.touch(hash);
+                Some(module)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Insert a validated module. Binaries larger than the byte budget are
+    /// not cached.
+    pub fn insert(&mut self, hash: ModuleHash, binary_size: usize, module: Arc<Module>) {
+        if binary_size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+
+        self.remove(&hash);
+        while !self.entries.is_empty()
+            && (self.entries.len() >= self.max_entries
+                || self.total_bytes + binary_size > self.max_bytes)
+        {
+            self.evict_lru();
+        }
+
+        self.total_bytes += binary_size;
+        self.access_order.push(hash);
+        self.entries.insert(
+            hash,
+            CachedModule {
+                module,
+                binary_size,
+            },
+        );
+    }
+
+    /// Remove a module by hash.
+    pub fn remove(&mut self, hash: &ModuleHash) -> Option<Arc<Module>> {
+        let entry = self.entries.remove(hash)?;
+        self.total_bytes -= entry.binary_size;
+        self.access_order.retain(|h| h != hash);
+        Some(entry.module)
+    }
+
+    /// Check whether a module with this hash is cached.
+    pub fn contains(&self, hash: &ModuleHash) -> bool {
+        self.entries.contains_key(hash)
+    }
+
+    /// Change the cache limits, evicting entries that no longer fit.
+    pub fn set_limits(&mut self, max_entries: usize, max_bytes: usize) {
+        self.max_entries = max_entries;
+        self.max_bytes = max_bytes;
+        while !self.entries.is_empty()
+            && (self.entries.len() > self.max_entries || self.total_bytes > self.max_bytes)
+        {
+            self.evict_lru();
+        }
+    }
+
+    /// Evict the least recently used entry.
+    fn evict_lru(&mut self) {
+        if let Some(hash) = self.access_order.first().copied() {
+            self.remove(&hash);
+            self.evictions += 1;
+        }
+    }
+
+    /// Mark an entry as recently used.
+    fn touch(&mut self, hash: &ModuleHash) {
+        self.access_order.retain(|h| h != hash);
+        self.access_order.push(*hash);
+    }
+
+    /// Get number of cached modules.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop all entries. Counters are preserved.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.access_order.clear();
+        self.total_bytes = 0;
+    }
+
+    /// Get statistics about the cache.
+    pub fn stats(&self) -> ModuleCacheStats {
+        ModuleCacheStats {
+            entries: self.entries.len(),
+            total_bytes: self.total_bytes,
+            max_entries: self.max_entries,
+            max_bytes: self.max_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+}
+
+/// Module cache statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    /// Number of cached modules.
+    pub entries: usize,
+    /// Summed size of cached binaries.
+    pub total_bytes: usize,
+    /// Maximum number of entries.
+    pub max_entries: usize,
+    /// Maximum summed size of cached binaries.
+    pub max_bytes: usize,
+    /// Lookups served from the cache.
+    pub hits: u64,
+    /// Lookups that required a parse.
+    pub misses: u64,
+    /// Entries dropped to stay within limits.
+    pub evictions: u64,
+}
+
+impl ModuleCacheStats {
+    /// Fraction of lookups served from the cache.
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f32 / total as f32
+        }
+    }
+}
+
+// ── SHA-256 (FIPS 180-4) ────────────────────────────────────
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// SHA-256 digest of `data`.
+pub fn sha256(data: &[u8]) -> ModuleHash {
+    let mut h = SHA256_IV;
+
+    let mut blocks = data.chunks_exact(64);
+    for block in &mut blocks {
+        sha256_compress(&mut h, block);
+    }
+
+    // Final one or two blocks: remainder, 0x80, zero padding, bit length.
+    let rem = blocks.remainder();
+    let mut tail = [0u8; 128];
+    tail[..rem.len()].copy_from_slice(rem);
+    tail[rem.len()] = 0x80;
+    let tail_len = if rem.len() < 56 { 64 } else { 128 };
+    let bit_len = (data.len() as u64).wrapping_mul(8);
+    tail[tail_len - 8..tail_len].copy_from_slice(&bit_len.to_be_bytes());
+    for block in tail[..tail_len].chunks_exact(64) {
+        sha256_compress(&mut h, block);
+    }
+
+    let mut out = [0u8; 32];
+    for (i, word) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+fn sha256_compress(h: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for i in 0..16 {
+        w[i] = u32::from_be_bytes([
+            block[i * 4],
+            block[i * 4 + 1],
+            block[i * 4 + 2],
+            block[i * 4 + 3],
+        ]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = *h;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ ((!e) & g);
+        let t1 = hh
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(SHA256_K[i])
+            .wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        hh = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (state, v) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+        *state = state.wrapping_add(v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty module followed by a custom section named `name`.
+    fn wasm_with_custom(name: u8) -> Vec<u8> {
+        let mut bytes = alloc::vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        bytes.extend_from_slice(&[0x00, 0x02, 0x01, name]);
+        bytes
+    }
+
+    #[test]
+    fn test_sha256_known_vectors() {
+        assert_eq!(
+            sha256(b""),
+            [
+                0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f,
+                0xb9, 0x24, 0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b,
+                0x78, 0x52, 0xb8, 0x55
+            ]
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            [
+                0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+                0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+                0xf2, 0x00, 0x15, 0xad
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeat_load_hits_cache() {
+        let mut cache = ModuleCache::new(4, 1024);
+        let wasm = wasm_with_custom(b'a');
+
+        let first = cache.get_or_load(&wasm).unwrap();
+        let second = cache.get_or_load(&wasm).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.total_bytes, wasm.len());
+    }
+
+    #[test]
+    fn test_changed_bytes_miss() {
+        let mut cache = ModuleCache::new(4, 1024);
+        let a = cache.get_or_load(&wasm_with_custom(b'a')).unwrap();
+        let b = cache.get_or_load(&wasm_with_custom(b'b')).unwrap();
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(cache.stats().misses, 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_binary_not_cached() {
+        let mut cache = ModuleCache::new(4, 1024);
+        assert!(cache.get_or_load(b"not wasm").is_err());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = ModuleCache::new(2, 1024);
+        let a = wasm_with_custom(b'a');
+        let b = wasm_with_custom(b'b');
+        let c = wasm_with_custom(b'c');
+
+        cache.get_or_load(&a).unwrap();
+        cache.get_or_load(&b).unwrap();
+        // Touch `a` so `b` becomes least recently used.
+        cache.get_or_load(&a).unwrap();
+        cache.get_or_load(&c).unwrap();
+
+        assert!(cache.contains(&sha256(&a)));
+        assert!(!cache.contains(&sha256(&b)));
+        assert!(cache.contains(&sha256(&c)));
+        assert_eq!(cache.stats().evictions, 1);
+
+        cache.set_limits(1, 1024);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&sha256(&c)));
+    }
+}
@@ -99,55 +99,55 @@ pub enum Instruction {
     // Memory Instructions — Load
     // ========================================================================
     /// Load i32 from memory. Params: (align, offset).
-    I32Load(u32, u32),
+    I32Load(u32, u64),
     /// Load i64 from memory. Params: (align, offset).
-    I64Load(u32, u32),
+    I64Load(u32, u64),
     /// Load f32 from memory. Params: (align, offset).
-    F32Load(u32, u32),
+    F32Load(u32, u64),
     /// Load f64 from memory. Params: (align, offset).
-    F64Load(u32, u32),
+    F64Load(u32, u64),
     /// Load i32 from i8 (sign-extend). Params: (align, offset).
-    I32Load8S(u32, u32),
+    I32Load8S(u32, u64),
     /// Load i32 from u8 (zero-extend). Params: (align, offset).
-    I32Load8U(u32, u32),
+    I32Load8U(u32, u64),
     /// Load i32 from i16 (sign-extend). Params: (align, offset).
-    I32Load16S(u32, u32),
+    I32Load16S(u32, u64),
     /// Load i32 from u16 (zero-extend). Params: (align, offset).
-    I32Load16U(u32, u32),
+    I32Load16U(u32, u64),
     /// Load i64 from i8 (sign-extend). Params: (align, offset).
-    I64Load8S(u32, u32),
+    I64Load8S(u32, u64),
     /// Load i64 from u8 (zero-extend). Params: (align, offset).
-    I64Load8U(u32, u32),
+    I64Load8U(u32, u64),
     /// Load i64 from i16 (sign-extend). Params: (align, offset).
-    I64Load16S(u32, u32),
+    I64Load16S(u32, u64),
     /// Load i64 from u16 (zero-extend). Params: (align, offset).
-    I64Load16U(u32, u32),
+    I64Load16U(u32, u64),
     /// Load i64 from i32 (sign-extend). Params: (align, offset).
-    I64Load32S(u32, u32),
+    I64Load32S(u32, u64),
     /// Load i64 from u32 (zero-extend). Params: (align, offset).
-    I64Load32U(u32, u32),
+    I64Load32U(u32, u64),
 
     // ========================================================================
     // Memory Instructions — Store
     // ========================================================================
     /// Store i32 to memory. Params: (align, offset).
-    I32Store(u32, u32),
+    I32Store(u32, u64),
     /// Store i64 to memory. Params: (align, offset).
-    I64Store(u32, u32),
+    I64Store(u32, u64),
     /// Store f32 to memory. Params: (align, offset).
-    F32Store(u32, u32),
+    F32Store(u32, u64),
     /// Store f64 to memory. Params: (align, offset).
-    F64Store(u32, u32),
+    F64Store(u32, u64),
     /// Store low 8 bits of i32. Params: (align, offset).
-    I32Store8(u32, u32),
+    I32Store8(u32, u64),
     /// Store low 16 bits of i32. Params: (align, offset).
-    I32Store16(u32, u32),
+    I32Store16(u32, u64),
     /// Store low 8 bits of i64. Params: (align, offset).
-    I64Store8(u32, u32),
+    I64Store8(u32, u64),
     /// Store low 16 bits of i64. Params: (align, offset).
-    I64Store16(u32, u32),
+    I64Store16(u32, u64),
     /// Store low 32 bits of i64. Params: (align, offset).
-    I64Store32(u32, u32),
+    I64Store32(u32, u64),
 
     // ========================================================================
     // Memory Instructions — Size/Grow
@@ -5,12 +5,13 @@
 //!
 //! Reference: <https://webassembly.github.io/spec/core/binary/index.html>
 
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 
 use crate::module::{
     DataSegment, Element, Export, ExportKind, FunctionBody, FunctionType, Global, GlobalType,
-    Import, ImportKind, MemoryType, Module, TableType, ValueType,
+    Import, ImportKind, MemoryType, Module, NameSection, TableType, ValueType,
 };
 use crate::opcodes::Instruction;
 
@@ -302,6 +303,7 @@ impl WasmParser {
         let mut code = Vec::new();
         let mut data = Vec::new();
         let mut name = None;
+        let mut names = NameSection::default();
         let mut data_count = None;
 
         while !reader.is_empty() {
@@ -313,11 +315,11 @@ impl WasmParser {
 
             match section_id {
                 Some(SectionId::Custom) => {
-                    // Try to parse name section
-                    let custom_name =
-                        Self::try_parse_custom_name(bytes, section_start, section_size);
-                    if let Some(n) = custom_name {
-                        name = Some(n);
+                    // Debug names are best-effort: a malformed "name"
+                    // section is ignored rather than rejecting the module
+                    let mut sr = reader.sub_reader(section_start, section_size)?;
+                    if sr.read_name().is_ok_and(|n| n == "name") {
+                        Self::parse_name_section(&mut sr, &mut name, &mut names);
                     }
                     reader.skip(section_size)?;
                 }
@@ -401,6 +403,7 @@ impl WasmParser {
             code,
             data,
             name,
+            names,
             data_count,
         })
     }
@@ -589,69 +592,72 @@ impl WasmParser {
         let mut elements = Vec::with_capacity(count);
 
         for _ in 0..count {
+            // Bit 0: passive/declarative, bit 1: explicit table index (active)
+            // or declarative (non-active), bit 2: element expressions
+            // instead of function indices.
             let flags = reader.read_leb128_u32()?;
+            if flags > 7 {
+                return Err(ParseError::new(
+                    "Unsupported element segment kind",
+                    reader.position(),
+                ));
+            }
+            let active = flags & 0b001 == 0;
+            let uses_exprs = flags & 0b100 != 0;
 
-            match flags {
-                // Active element: table 0, expr offset, vec of funcidx
-                0 => {
-                    let offset_expr = Self::parse_init_expr(reader)?;
-                    let func_count = reader.read_leb128_u32()? as usize;
-                    let mut func_indices = Vec::with_capacity(func_count);
-                    for _ in 0..func_count {
-                        func_indices.push(reader.read_leb128_u32()?);
-                    }
-                    elements.push(Element {
-                        table_idx: 0,
-                        offset_expr,
-                        func_indices,
-                        passive: false,
-                    });
-                }
-                // Passive element with elemkind
-                1 => {
-                    let _elemkind = reader.read_byte()?; // 0x00 = funcref
-                    let func_count = reader.read_leb128_u32()? as usize;
-                    let mut func_indices = Vec::with_capacity(func_count);
-                    for _ in 0..func_count {
-                        func_indices.push(reader.read_leb128_u32()?);
-                    }
-                    elements.push(Element {
-                        table_idx: 0,
-                        offset_expr: Vec::new(),
-                        func_indices,
-                        passive: true,
-                    });
-                }
-                // Active element with explicit table index
-                2 => {
-                    let table_idx = reader.read_leb128_u32()?;
-                    let offset_expr = Self::parse_init_expr(reader)?;
-                    let _elemkind = reader.read_byte()?;
-                    let func_count = reader.read_leb128_u32()? as usize;
-                    let mut func_indices = Vec::with_capacity(func_count);
-                    for _ in 0..func_count {
-                        func_indices.push(reader.read_leb128_u32()?);
-                    }
-                    elements.push(Element {
-                        table_idx,
-                        offset_expr,
-                        func_indices,
-                        passive: false,
-                    });
-                }
-                _ => {
-                    // Other element segment kinds — skip for MVP
-                    return Err(ParseError::new(
-                        "Unsupported element segment kind",
-                        reader.position(),
-                    ));
+            let (table_idx, offset_expr) = if active {
+                let table_idx = if flags & 0b010 != 0 {
+                    reader.read_leb128_u32()?
+                } else {
+                    0
+                };
+                (table_idx, Self::parse_init_expr(reader)?)
+            } else {
+                (0, Vec::new())
+            };
+
+            // elemkind (0x00 = funcref) or reftype, absent for flags 0 and 4
+            if flags & 0b011 != 0 {
+                let _kind = reader.read_byte()?;
+            }
+
+            let item_count = reader.read_leb128_u32()? as usize;
+            let mut func_indices = Vec::with_capacity(item_count);
+            for _ in 0..item_count {
+                if uses_exprs {
+                    func_indices.push(Self::parse_elem_expr(reader)?);
+                } else {
+                    func_indices.push(Some(reader.read_leb128_u32()?));
                 }
             }
+
+            // Declarative segments (flags 3 and 7) only forward-declare
+            // references for `ref.func`; they behave as already dropped.
+            if !active && flags & 0b010 != 0 {
+                func_indices.clear();
+            }
+
+            elements.push(Element {
+                table_idx,
+                offset_expr,
+                func_indices,
+                passive: !active,
+            });
         }
 
         Ok(elements)
     }
 
+    /// Parse an element expression (`ref.func idx` or `ref.null t`).
+    fn parse_elem_expr(reader: &mut BinaryReader) -> Result<Option<u32>, ParseError> {
+        let position = reader.position();
+        match Self::parse_init_expr(reader)?.as_slice() {
+            [Instruction::RefFunc(idx)] => Ok(Some(*idx)),
+            [Instruction::RefNull] => Ok(None),
+            _ => Err(ParseError::new("Unsupported element expression", position)),
+        }
+    }
+
     /// Parse Code Section (10): function bodies.
     fn parse_code_section(reader: &mut BinaryReader) -> Result<Vec<FunctionBody>, ParseError> {
         let count = reader.read_leb128_u32()? as usize;
@@ -776,16 +782,32 @@ impl WasmParser {
     }
 
     /// Parse a memory type.
+    ///
+    /// Flag bit 0x04 marks a 64-bit memory (`memory64`), whose limits are
+    /// encoded as u64.
     fn parse_memory_type(reader: &mut BinaryReader) -> Result<MemoryType, ParseError> {
         let flags = reader.read_byte()?;
         let shared = flags & 0x02 != 0;
-        let min = reader.read_leb128_u32()?;
+        let memory64 = flags & 0x04 != 0;
+        let read_limit = |reader: &mut BinaryReader| {
+            if memory64 {
+                reader.read_leb128_u64()
+            } else {
+                reader.read_leb128_u32().map(u64::from)
+            }
+        };
+        let min = read_limit(reader)?;
         let max = if flags & 0x01 != 0 {
-            Some(reader.read_leb128_u32()?)
+            Some(read_limit(reader)?)
         } else {
             None
         };
-        Ok(MemoryType { min, max, shared })
+        Ok(MemoryType {
+            min,
+            max,
+            shared,
+            memory64,
+        })
     }
 
     /// Parse a global type.
@@ -964,121 +986,122 @@ impl WasmParser {
             0x26 => TableSet(reader.read_leb128_u32()?),
 
             // ====== Memory Load ======
+            // memarg offsets are u64 so 64-bit memories can use them in full.
             0x28 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Load(align, offset)
             }
             0x29 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Load(align, offset)
             }
             0x2A => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 F32Load(align, offset)
             }
             0x2B => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 F64Load(align, offset)
             }
             0x2C => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Load8S(align, offset)
             }
             0x2D => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Load8U(align, offset)
             }
             0x2E => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Load16S(align, offset)
             }
             0x2F => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Load16U(align, offset)
             }
             0x30 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Load8S(align, offset)
             }
             0x31 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Load8U(align, offset)
             }
             0x32 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Load16S(align, offset)
             }
             0x33 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Load16U(align, offset)
             }
             0x34 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Load32S(align, offset)
             }
             0x35 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Load32U(align, offset)
             }
 
             // ====== Memory Store ======
             0x36 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Store(align, offset)
             }
             0x37 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Store(align, offset)
             }
             0x38 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 F32Store(align, offset)
             }
             0x39 => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 F64Store(align, offset)
             }
             0x3A => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Store8(align, offset)
             }
             0x3B => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I32Store16(align, offset)
             }
             0x3C => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Store8(align, offset)
             }
             0x3D => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Store16(align, offset)
             }
             0x3E => {
                 let align = reader.read_leb128_u32()?;
-                let offset = reader.read_leb128_u32()?;
+                let offset = reader.read_leb128_u64()?;
                 I64Store32(align, offset)
             }
 
@@ -1357,36 +1380,65 @@ impl WasmParser {
         }
     }
 
-    /// Try to parse a "name" custom section.
-    fn try_parse_custom_name(
-        data: &[u8],
-        section_start: usize,
-        section_size: usize,
-    ) -> Option<String> {
-        if section_start + section_size > data.len() {
-            return None;
-        }
-
-        let mut reader = BinaryReader::new(&data[section_start..section_start + section_size]);
-        let name = reader.read_name().ok()?;
-
-        if name == "name" {
-            // Parse module name subsection
-            while !reader.is_empty() {
-                let subsection_id = reader.read_byte().ok()?;
-                let subsection_size = reader.read_leb128_u32().ok()? as usize;
+    /// Parse the payload of a "name" custom section.
+    ///
+    /// Reads the module (0), function (1) and local (2) subsections and
+    /// skips the rest. Parsing stops at the first malformed subsection,
+    /// keeping whatever names were read before it.
+    fn parse_name_section(
+        reader: &mut BinaryReader,
+        module_name: &mut Option<String>,
+        names: &mut NameSection,
+    ) {
+        while !reader.is_empty() {
+            let Ok(subsection_id) = reader.read_byte() else {
+                return;
+            };
+            let Ok(size) = reader.read_leb128_u32() else {
+                return;
+            };
+            let Ok(mut sr) = reader.sub_reader(reader.position(), size as usize) else {
+                return;
+            };
+            if reader.skip(size as usize).is_err() {
+                return;
+            }
 
-                if subsection_id == 0 {
-                    // Module name subsection
-                    let module_name = reader.read_name().ok()?;
-                    return Some(module_name);
-                } else {
-                    reader.skip(subsection_size).ok()?;
-                }
+            let parsed = match subsection_id {
+                0 => sr.read_name().map(|n| *module_name = Some(n)),
+                1 => Self::parse_name_map(&mut sr).map(|map| names.functions = map),
+                2 => Self::parse_indirect_name_map(&mut sr).map(|map| names.locals = map),
+                _ => Ok(()),
+            };
+            if parsed.is_err() {
+                return;
             }
         }
+    }
 
-        None
+    /// Parse a name map: `vec(index name)`.
+    fn parse_name_map(reader: &mut BinaryReader) -> Result<BTreeMap<u32, String>, ParseError> {
+        let count = reader.read_leb128_u32()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..count {
+            let idx = reader.read_leb128_u32()?;
+            let name = reader.read_name()?;
+            map.insert(idx, name);
+        }
+        Ok(map)
+    }
+
+    /// Parse an indirect name map: `vec(index namemap)`.
+    fn parse_indirect_name_map(
+        reader: &mut BinaryReader,
+    ) -> Result<BTreeMap<u32, BTreeMap<u32, String>>, ParseError> {
+        let count = reader.read_leb128_u32()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..count {
+            let idx = reader.read_leb128_u32()?;
+            map.insert(idx, Self::parse_name_map(reader)?);
+        }
+        Ok(map)
     }
 }
 
@@ -1471,10 +1523,14 @@ impl ModuleValidator {
                     return Err(ParseError::new("Memory min exceeds max", 0));
                 }
             }
-            // 65536 pages = 4GB limit
-            if mem.min > 65536 {
+            // 65536 pages = 4GB limit; 2^48 pages for 64-bit memories
+            let max_pages = if mem.memory64 { 1 << 48 } else { 65536 };
+            if mem.min > max_pages {
                 return Err(ParseError::new("Memory min too large", 0));
             }
+            if mem.max.is_some_and(|max| max > max_pages) {
+                return Err(ParseError::new("Memory max too large", 0));
+            }
         }
 
         Ok(())
@@ -1581,7 +1637,7 @@ impl ModuleValidator {
                     0,
                 ));
             }
-            for &func_idx in &elem.func_indices {
+            for &func_idx in elem.func_indices.iter().flatten() {
                 if func_idx as usize >= num_funcs {
                     return Err(ParseError::new(
                         &alloc::format!(
@@ -1730,6 +1786,31 @@ mod tests {
         assert_eq!(module.types[0].results[0], ValueType::I32);
     }
 
+    #[test]
+    fn test_parse_passive_and_declarative_elements() {
+        #[rustfmt::skip]
+        let wasm = [
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+            0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section: () -> ()
+            0x03, 0x02, 0x01, 0x00, // function section: 1 func of type 0
+            0x04, 0x04, 0x01, 0x70, 0x00, 0x02, // table section: funcref, min 2
+            0x09, 0x0E, // element section
+            0x02, // 2 segments
+            0x05, 0x70, 0x02, // passive, funcref, 2 exprs
+            0xD2, 0x00, 0x0B, // ref.func 0
+            0xD0, 0x70, 0x0B, // ref.null func
+            0x03, 0x00, 0x01, 0x00, // declarative, elemkind, [func 0]
+            0x0A, 0x04, 0x01, 0x02, 0x00, 0x0B, // code section: empty body
+        ];
+        let module = WasmParser::parse(&wasm).unwrap();
+        assert_eq!(module.elements.len(), 2);
+        assert!(module.elements[0].passive);
+        assert_eq!(module.elements[0].func_indices, alloc::vec![Some(0), None]);
+        // Declarative segments are dropped from the start
+        assert!(module.elements[1].passive);
+        assert!(module.elements[1].func_indices.is_empty());
+    }
+
     #[test]
     fn test_parse_export_section() {
         // Export section with one function export "_start" => func 0
@@ -1773,6 +1854,44 @@ mod tests {
         // Full import parsing is tested with real WASM binaries
     }
 
+    #[test]
+    fn test_parse_name_section() {
+        #[rustfmt::skip]
+        let wasm = [
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+            0x00, // custom section
+            0x1E, // section size
+            0x04, b'n', b'a', b'm', b'e', // "name"
+            0x00, 0x04, 0x03, b'a', b'p', b'p', // module name: "app"
+            0x01, 0x07, 0x01, 0x02, 0x04, b'm', b'a', b'i', b'n', // func 2: "main"
+            0x02, 0x06, 0x01, 0x02, 0x01, 0x00, 0x01, b'n', // func 2, local 0: "n"
+            0x07, 0x00, // unknown subsection
+        ];
+        let module = WasmParser::parse(&wasm).unwrap();
+        assert_eq!(module.name(), Some("app"));
+        assert_eq!(module.function_name(2), Some("main"));
+        assert_eq!(module.function_name(0), None);
+        assert_eq!(module.local_name(2, 0), Some("n"));
+        assert_eq!(module.function_label(2), "main");
+        assert_eq!(module.function_label(3), "func[3]");
+    }
+
+    #[test]
+    fn test_parse_malformed_name_section() {
+        // The function subsection claims two entries but holds one
+        #[rustfmt::skip]
+        let wasm = [
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+            0x00, 0x0F, // custom section
+            0x04, b'n', b'a', b'm', b'e', // "name"
+            0x00, 0x02, 0x01, b'm', // module name: "m"
+            0x01, 0x04, 0x02, 0x00, 0x01, b'f', // truncated function names
+        ];
+        let module = WasmParser::parse(&wasm).unwrap();
+        assert_eq!(module.name(), Some("m"));
+        assert!(module.names.functions.is_empty());
+    }
+
     #[test]
     fn test_parse_memory_section() {
         // Memory section: 1 memory with min=1, max=16
@@ -1790,5 +1909,32 @@ mod tests {
         assert_eq!(module.memories.len(), 1);
         assert_eq!(module.memories[0].min, 1);
         assert_eq!(module.memories[0].max, Some(16));
+        assert!(!module.memories[0].memory64);
+    }
+
+    #[test]
+    fn test_parse_memory64_section() {
+        // 64-bit memory with min=65537 (just over 4GB), max=2^32 pages
+        #[rustfmt::skip]
+        let wasm = [
+            0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, // header
+            0x05, // memory section
+            0x0A, // section size
+            0x01, // 1 memory
+            0x05, // has max, memory64
+            0x81, 0x80, 0x04, // min = 65537
+            0x80, 0x80, 0x80, 0x80, 0x10, // max = 2^32
+        ];
+        let module = WasmParser::parse(&wasm).unwrap();
+        assert!(module.memories[0].memory64);
+        assert_eq!(module.memories[0].min, 65537);
+        assert_eq!(module.memories[0].max, Some(1 << 32));
+        assert!(module.validate_structure().is_ok());
+
+        // More than 65536 pages is out of range for a 32-bit memory
+        let mut wasm32 = wasm;
+        wasm32[11] = 0x01;
+        let module = WasmParser::parse(&wasm32).unwrap();
+        assert!(module.validate_structure().is_err());
     }
 }
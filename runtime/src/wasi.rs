@@ -3,11 +3,18 @@
 //! This module provides a complete WASI (WebAssembly System Interface) Preview 1
 //! implementation with an integrated in-memory virtual filesystem (VFS).
 //! All file I/O operations are sandboxed to preopened directories.
+//!
+//! Guests can also serve TCP connections on preopened listening sockets
+//! (`sock_accept`, `sock_recv`, `sock_send`, `sock_shutdown`), backed by
+//! the `wasi:sockets` implementation in [`crate::wasi2::sockets`], and
+//! wait on clocks and descriptors with `poll_oneoff`.
 
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 
+use crate::clock;
+use crate::wasi2::sockets::{self, IpSocketAddress, ShutdownType, SocketError, TcpState};
 use crate::RuntimeError;
 
 // ─── Virtual File System ───────────────────────────────────────────
@@ -395,8 +402,6 @@ pub struct WasiCtx {
     stderr_buf: Vec<u8>,
     /// Exit code (if proc_exit was called).
     exit_code: Option<u32>,
-    /// Monotonic counter (nanoseconds, increments per call).
-    monotonic_counter: u64,
     /// PRNG state for random_get.
     random_state: u64,
 }
@@ -419,7 +424,6 @@ impl WasiCtx {
             stdout_buf: Vec::new(),
             stderr_buf: Vec::new(),
             exit_code: None,
-            monotonic_counter: 1_000_000_000, // Start at 1 second in nanoseconds
             random_state: 0xDEAD_BEEF_CAFE_BABE,
         }
     }
@@ -468,6 +472,7 @@ impl WasiCtx {
             offset: 0,
             path: Some(vfs_path.clone()),
             preopen_guest_path: Some(String::from(guest_path)),
+            socket: None,
         };
 
         let fd_num = self.alloc_fd(fd);
@@ -475,6 +480,32 @@ impl WasiCtx {
         fd_num
     }
 
+    /// Preopen a TCP socket listening on `address`, for the guest to
+    /// accept connections from with `sock_accept`. Returns the assigned
+    /// FD number.
+    pub fn preopen_tcp_listener(&mut self, address: IpSocketAddress) -> Result<u32, WasiError> {
+        let port = address.port;
+        let socket = sockets::create_tcp_socket(address.address.family());
+        let listening = sockets::with_tcp_socket(socket, |s| {
+            s.start_bind(address)?;
+            s.finish_bind()?;
+            s.start_listen()?;
+            s.finish_listen()
+        });
+        if let Err(err) = listening.and_then(|result| result) {
+            sockets::close_tcp_socket(socket);
+            return Err(err.into());
+        }
+        sockets::tcp_register_listener(socket, port);
+
+        let fd = FileDescriptor::tcp_socket(
+            socket,
+            FdRights::SOCK_ACCEPT | FdRights::POLL_FD,
+            FdFlags::empty(),
+        );
+        Ok(self.alloc_fd(fd))
+    }
+
     /// Allocate a new file descriptor.
     fn alloc_fd(&mut self, fd: FileDescriptor) -> u32 {
         let num = self.next_fd;
@@ -487,6 +518,12 @@ impl WasiCtx {
 
     /// fd_read - Read from a file descriptor.
     pub fn fd_read(&mut self, fd: u32, buf: &mut [u8]) -> Result<usize, WasiError> {
+        if self.is_socket(fd) {
+            return self
+                .sock_recv(fd, buf, RiFlags::empty())
+                .map(|(len, _)| len);
+        }
+
         // Validate FD and permissions
         {
             let file = self.fds.get(&fd).ok_or(WasiError::BadF)?;
@@ -531,6 +568,10 @@ impl WasiCtx {
 
     /// fd_write - Write to a file descriptor.
     pub fn fd_write(&mut self, fd: u32, data: &[u8]) -> Result<usize, WasiError> {
+        if self.is_socket(fd) {
+            return self.sock_send(fd, data);
+        }
+
         // Validate FD and permissions
         {
             let file = self.fds.get(&fd).ok_or(WasiError::BadF)?;
@@ -633,7 +674,10 @@ impl WasiCtx {
         if fd < 3 {
             return Err(WasiError::Access);
         }
-        self.fds.remove(&fd).ok_or(WasiError::BadF)?;
+        let file = self.fds.remove(&fd).ok_or(WasiError::BadF)?;
+        if let Some(socket) = file.socket {
+            sockets::close_tcp_socket(socket);
+        }
         Ok(())
     }
 
@@ -648,6 +692,13 @@ impl WasiCtx {
         })
     }
 
+    /// fd_fdstat_set_flags - Set file descriptor flags.
+    pub fn fd_fdstat_set_flags(&mut self, fd: u32, flags: FdFlags) -> Result<(), WasiError> {
+        let file = self.fds.get_mut(&fd).ok_or(WasiError::BadF)?;
+        file.flags = flags;
+        Ok(())
+    }
+
     /// fd_prestat_get - Get preopened directory info.
     pub fn fd_prestat_get(&self, fd: u32) -> Result<Prestat, WasiError> {
         let file = self.fds.get(&fd).ok_or(WasiError::BadF)?;
@@ -702,6 +753,7 @@ impl WasiCtx {
                 offset: 0,
                 path: Some(full_path),
                 preopen_guest_path: None,
+                socket: None,
             };
             return Ok(self.alloc_fd(new_fd));
         }
@@ -729,6 +781,7 @@ impl WasiCtx {
             offset: 0,
             path: Some(full_path),
             preopen_guest_path: None,
+            socket: None,
         };
         Ok(self.alloc_fd(new_fd))
     }
@@ -879,21 +932,65 @@ impl WasiCtx {
         (count, total_size)
     }
 
-    /// clock_time_get - Get current time in nanoseconds.
+    /// clock_res_get - Get the resolution of a clock in nanoseconds.
+    pub fn clock_res_get(&self, _clock_id: ClockId) -> Result<u64, WasiError> {
+        // Every clock is derived from the TSC
+        Ok(clock::resolution_ns())
+    }
+
+    /// clock_time_get - Get current time in nanoseconds. See
+    /// [`ClockId::now_ns`].
     pub fn clock_time_get(&mut self, clock_id: ClockId, _precision: u64) -> Result<u64, WasiError> {
-        match clock_id {
-            ClockId::Realtime => {
-                self.monotonic_counter += 1_000_000; // +1ms per call
-                Ok(1_700_000_000_000_000_000 + self.monotonic_counter)
-            }
-            ClockId::Monotonic => {
-                self.monotonic_counter += 1_000_000; // +1ms per call
-                Ok(self.monotonic_counter)
+        Ok(clock_id.now_ns())
+    }
+
+    /// poll_oneoff - Wait until at least one subscription triggers and
+    /// return an event for each one that did.
+    ///
+    /// Clock subscriptions fire once their clock reaches the timeout.
+    /// Reads and writes on stdio and VFS files never block, so those
+    /// FDs are always ready; a read reports the bytes left before end of
+    /// file. Sockets are readable once data or a connection is waiting.
+    /// A bad FD is reported in its event rather than failing the call.
+    pub fn poll_oneoff(&self, subscriptions: &[Subscription]) -> Result<Vec<Event>, WasiError> {
+        if subscriptions.is_empty() {
+            return Err(WasiError::Inval);
+        }
+        // Relative timeouts count from the start of the call.
+        let deadlines: Vec<Option<u64>> = subscriptions
+            .iter()
+            .map(|sub| match sub.kind {
+                SubscriptionKind::Clock {
+                    id, timeout, flags, ..
+                } => Some(if flags.contains(SubClockFlags::ABSTIME) {
+                    timeout
+                } else {
+                    id.now_ns().saturating_add(timeout)
+                }),
+                _ => None,
+            })
+            .collect();
+
+        loop {
+            let mut events = Vec::new();
+            for (sub, deadline) in subscriptions.iter().zip(&deadlines) {
+                let event = match sub.kind {
+                    SubscriptionKind::Clock { id, .. } => deadline
+                        .filter(|&deadline| id.now_ns() >= deadline)
+                        .map(|_| Event::new(sub.userdata, EventType::Clock)),
+                    SubscriptionKind::FdRead(fd) => {
+                        self.fd_poll(sub.userdata, fd, EventType::FdRead)
+                    }
+                    SubscriptionKind::FdWrite(fd) => {
+                        self.fd_poll(sub.userdata, fd, EventType::FdWrite)
+                    }
+                };
+                events.extend(event);
             }
-            ClockId::ProcessCputime | ClockId::ThreadCputime => {
-                self.monotonic_counter += 100_000;
-                Ok(self.monotonic_counter)
+            if !events.is_empty() {
+                return Ok(events);
             }
+            core::hint::spin_loop();
         }
     }
 
@@ -913,8 +1010,151 @@ impl WasiCtx {
         self.exit_code = Some(code);
     }
 
+    /// sock_accept - Accept a connection on a listening socket as a new
+    /// FD with `flags` (only `NONBLOCK` is allowed). A non-blocking
+    /// listener fails with `Again` when no connection is pending.
+    pub fn sock_accept(&mut self, fd: u32, flags: FdFlags) -> Result<u32, WasiError> {
+        if !(flags - FdFlags::NONBLOCK).is_empty() {
+            return Err(WasiError::Inval);
+        }
+        let (listener, listener_flags) = self.socket_fd(fd, FdRights::SOCK_ACCEPT)?;
+        let socket = sockets::tcp_accept(listener, !listener_flags.contains(FdFlags::NONBLOCK))?;
+
+        let rights = FdRights::READ
+            | FdRights::WRITE
+            | FdRights::POLL_FD
+            | FdRights::SOCK_RECV
+            | FdRights::SOCK_SEND
+            | FdRights::SOCK_SHUTDOWN;
+        Ok(self.alloc_fd(FileDescriptor::tcp_socket(socket, rights, flags)))
+    }
+
+    /// sock_recv - Receive from a connected socket. Returns the number of
+    /// bytes read (0 at end of stream) and the output flags.
+    ///
+    /// A non-blocking socket fails with `Again` when no data is ready.
+    /// Loopback sockets never wait, as nothing can arrive on them while
+    /// the guest is blocked. `RECV_WAITALL` may still return short reads;
+    /// `RECV_PEEK` is not supported.
+    pub fn sock_recv(
+        &mut self,
+        fd: u32,
+        buf: &mut [u8],
+        ri_flags: RiFlags,
+    ) -> Result<(usize, RoFlags), WasiError> {
+        if ri_flags.contains(RiFlags::RECV_PEEK) {
+            return Err(WasiError::NotSup);
+        }
+        let (socket, flags) = self.socket_fd(fd, FdRights::SOCK_RECV)?;
+        let nonblocking = flags.contains(FdFlags::NONBLOCK);
+
+        let data = sockets::with_tcp_socket(socket, |s| match s.state {
+            // Shut down: end of stream
+            TcpState::Closed => Ok(Vec::new()),
+            _ if nonblocking => s.try_recv(buf.len()),
+            _ => s.recv(buf.len()),
+        })??;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok((data.len(), RoFlags::empty()))
+    }
+
+    /// sock_send - Send on a connected socket. Returns the number of
+    /// bytes sent.
+    pub fn sock_send(&mut self, fd: u32, data: &[u8]) -> Result<usize, WasiError> {
+        let (socket, _) = self.socket_fd(fd, FdRights::SOCK_SEND)?;
+        sockets::with_tcp_socket(socket, |s| {
+            if s.state == TcpState::Closed {
+                return Err(WasiError::Pipe);
+            }
+            s.send(data).map_err(WasiError::from)
+        })?
+    }
+
+    /// sock_shutdown - Shut down a connected socket. Either direction
+    /// closes the whole connection.
+    pub fn sock_shutdown(&mut self, fd: u32, how: SdFlags) -> Result<(), WasiError> {
+        let (socket, _) = self.socket_fd(fd, FdRights::SOCK_SHUTDOWN)?;
+        let how = if how == SdFlags::RD | SdFlags::WR {
+            ShutdownType::Both
+        } else if how == SdFlags::RD {
+            ShutdownType::Receive
+        } else if how == SdFlags::WR {
+            ShutdownType::Send
+        } else {
+            return Err(WasiError::Inval);
+        };
+        sockets::with_tcp_socket(socket, |s| s.shutdown(how))??;
+        Ok(())
+    }
+
     // ─── Internal Helpers ──────────────────────────────────────────
 
+    /// The `poll_oneoff` event for reading from or writing to `fd`, or
+    /// `None` while the FD is not ready.
+    fn fd_poll(&self, userdata: u64, fd: u32, event_type: EventType) -> Option<Event> {
+        let event = Event::new(userdata, event_type);
+        let Some(file) = self.fds.get(&fd) else {
+            return Some(event.with_error(WasiError::BadF));
+        };
+        let right = match event_type {
+            EventType::FdWrite => FdRights::WRITE,
+            _ => FdRights::READ,
+        };
+
+        if let Some(socket) = file.socket {
+            if event_type == EventType::FdWrite {
+                return Some(event);
+            }
+            // A kernel-backed connection cannot be peeked at; let the
+            // read itself report `Again`.
+            let readable = sockets::with_tcp_socket(socket, |s| {
+                s.kernel_conn.is_some() || !s.recv_buffer.is_empty() || !s.accept_queue.is_empty()
+            });
+            return match readable {
+                Ok(true) => Some(event),
+                Ok(false) => None,
+                Err(err) => Some(event.with_error(err.into())),
+            };
+        }
+        if !file.rights.contains(right) {
+            return Some(event.with_error(WasiError::Access));
+        }
+        if file.fd_type == FdType::Directory {
+            return Some(event.with_error(WasiError::IsDir));
+        }
+        if event_type == EventType::FdWrite {
+            return Some(event);
+        }
+        let remaining = match &file.path {
+            Some(path) => self
+                .vfs
+                .file_size(path)
+                .map(|size| size.saturating_sub(file.offset)),
+            // stdin is always at end of file
+            None => Ok(0),
+        };
+        Some(match remaining {
+            Ok(nbytes) => event.with_nbytes(nbytes),
+            Err(err) => event.with_error(err),
+        })
+    }
+
+    /// Whether `fd` is an open socket.
+    fn is_socket(&self, fd: u32) -> bool {
+        self.fds.get(&fd).is_some_and(|file| file.socket.is_some())
+    }
+
+    /// Look up the socket behind `fd` and the FD's flags, checking the FD
+    /// has `rights`.
+    fn socket_fd(&self, fd: u32, rights: FdRights) -> Result<(u32, FdFlags), WasiError> {
+        let file = self.fds.get(&fd).ok_or(WasiError::BadF)?;
+        let socket = file.socket.ok_or(WasiError::NotSock)?;
+        if !file.rights.contains(rights) {
+            return Err(WasiError::Access);
+        }
+        Ok((socket, file.flags))
+    }
+
     /// Resolve a path relative to a directory FD and verify it stays
     /// within the sandbox (within the directory FD's scope).
     fn resolve_sandboxed_path(&self, dir_fd: u32, path: &str) -> Result<String, WasiError> {
@@ -965,6 +1205,8 @@ pub struct FileDescriptor {
     pub path: Option<String>,
     /// Guest-visible preopened directory path (only for preopened dirs).
     pub preopen_guest_path: Option<String>,
+    /// Socket ID in [`crate::wasi2::sockets`] (only for sockets).
+    pub socket: Option<u32>,
 }
 
 impl FileDescriptor {
@@ -977,6 +1219,7 @@ impl FileDescriptor {
             offset: 0,
             path: None,
             preopen_guest_path: None,
+            socket: None,
         }
     }
 
@@ -989,6 +1232,7 @@ impl FileDescriptor {
             offset: 0,
             path: None,
             preopen_guest_path: None,
+            socket: None,
         }
     }
 
@@ -1001,6 +1245,7 @@ impl FileDescriptor {
             offset: 0,
             path: None,
             preopen_guest_path: None,
+            socket: None,
         }
     }
 
@@ -1013,6 +1258,7 @@ impl FileDescriptor {
             offset: 0,
             path: Some(path),
             preopen_guest_path: None,
+            socket: None,
         }
     }
 
@@ -1025,6 +1271,20 @@ impl FileDescriptor {
             offset: 0,
             path: Some(path),
             preopen_guest_path: None,
+            socket: None,
+        }
+    }
+
+    /// Create a TCP socket descriptor.
+    pub fn tcp_socket(socket: u32, rights: FdRights, flags: FdFlags) -> Self {
+        FileDescriptor {
+            fd_type: FdType::SocketStream,
+            rights,
+            flags,
+            offset: 0,
+            path: None,
+            preopen_guest_path: None,
+            socket: Some(socket),
         }
     }
 }
@@ -1072,6 +1332,8 @@ bitflags::bitflags! {
         const POLL_FD = 1 << 16;
         const SOCK_RECV = 1 << 17;
         const SOCK_SEND = 1 << 18;
+        const SOCK_SHUTDOWN = 1 << 19;
+        const SOCK_ACCEPT = 1 << 20;
     }
 }
 
@@ -1234,6 +1496,27 @@ impl WasiError {
     }
 }
 
+impl From<SocketError> for WasiError {
+    fn from(err: SocketError) -> Self {
+        match err {
+            SocketError::AddressInUse => WasiError::AddrInUse,
+            SocketError::AddressNotBindable => WasiError::AddrNotAvail,
+            SocketError::ConnectionRefused => WasiError::ConnRefused,
+            SocketError::ConnectionReset => WasiError::ConnReset,
+            SocketError::ConnectionAborted => WasiError::ConnAborted,
+            SocketError::NotConnected => WasiError::NotConn,
+            SocketError::WouldBlock => WasiError::Again,
+            SocketError::AlreadyConnected => WasiError::IsConn,
+            SocketError::InvalidArgument
+            | SocketError::AlreadyBound
+            | SocketError::AlreadyListening
+            | SocketError::NotBound
+            | SocketError::NotListening => WasiError::Inval,
+            SocketError::Unknown(_) => WasiError::Io,
+        }
+    }
+}
+
 impl From<WasiError> for RuntimeError {
     fn from(err: WasiError) -> Self {
         RuntimeError::WasiError(alloc::format!("WASI error: {:?}", err))
@@ -1274,6 +1557,32 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// Input flags for sock_recv.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RiFlags: u16 {
+        const RECV_PEEK = 1 << 0;
+        const RECV_WAITALL = 1 << 1;
+    }
+}
+
+bitflags::bitflags! {
+    /// Output flags of sock_recv.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RoFlags: u16 {
+        const RECV_DATA_TRUNCATED = 1 << 0;
+    }
+}
+
+bitflags::bitflags! {
+    /// Directions to shut down with sock_shutdown.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SdFlags: u8 {
+        const RD = 1 << 0;
+        const WR = 1 << 1;
+    }
+}
+
 bitflags::bitflags! {
     /// Lookup flags for path operations.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1349,6 +1658,157 @@ impl ClockId {
             _ => None,
         }
     }
+
+    /// Current reading of the clock in nanoseconds.
+    ///
+    /// The realtime clock is the kernel's RTC-anchored wall clock; until
+    /// it is anchored (see [`clock::realtime_ns`]) it counts from the Unix
+    /// epoch. There is no per-process CPU accounting, so the CPU-time clocks report
+    /// monotonic time like the monotonic clock.
+    pub fn now_ns(self) -> u64 {
+        match self {
+            ClockId::Realtime => clock::realtime_ns(),
+            ClockId::Monotonic | ClockId::ProcessCputime | ClockId::ThreadCputime => {
+                clock::monotonic_ns()
+            }
+        }
+    }
+}
+
+/// Type of a `poll_oneoff` subscription and of the event it produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EventType {
+    /// A clock reached the subscription's timeout.
+    Clock = 0,
+    /// An FD is ready for reading.
+    FdRead = 1,
+    /// An FD is ready for writing.
+    FdWrite = 2,
+}
+
+bitflags::bitflags! {
+    /// Flags of a clock subscription.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SubClockFlags: u16 {
+        /// The timeout is an absolute time rather than relative to now.
+        const ABSTIME = 1 << 0;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags of an FD readiness event.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct EventRwFlags: u16 {
+        /// The peer closed the connection.
+        const HANGUP = 1 << 0;
+    }
+}
+
+/// What a `poll_oneoff` subscription waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// `id` reaching `timeout` nanoseconds.
+    Clock {
+        id: ClockId,
+        timeout: u64,
+        precision: u64,
+        flags: SubClockFlags,
+    },
+    /// The FD becoming readable.
+    FdRead(u32),
+    /// The FD becoming writable.
+    FdWrite(u32),
+}
+
+/// A `poll_oneoff` subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscription {
+    /// Value copied into the resulting event.
+    pub userdata: u64,
+    /// What to wait for.
+    pub kind: SubscriptionKind,
+}
+
+impl Subscription {
+    /// Size of a subscription in guest memory.
+    pub const SIZE: usize = 48;
+
+    /// Decode a subscription from its guest-memory layout. Unknown
+    /// types and clock IDs are `Inval`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WasiError> {
+        if bytes.len() < Self::SIZE {
+            return Err(WasiError::Inval);
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+
+        let kind = match bytes[8] {
+            0 => SubscriptionKind::Clock {
+                id: ClockId::from_u32(u32_at(16)).ok_or(WasiError::Inval)?,
+                timeout: u64_at(24),
+                precision: u64_at(32),
+                flags: SubClockFlags::from_bits(u16_at(40)).ok_or(WasiError::Inval)?,
+            },
+            1 => SubscriptionKind::FdRead(u32_at(16)),
+            2 => SubscriptionKind::FdWrite(u32_at(16)),
+            _ => return Err(WasiError::Inval),
+        };
+        Ok(Subscription {
+            userdata: u64_at(0),
+            kind,
+        })
+    }
+}
+
+/// An event reported by `poll_oneoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    /// The triggering subscription's userdata.
+    pub userdata: u64,
+    /// Error polling the FD, `Success` otherwise.
+    pub error: WasiError,
+    /// Type of the triggering subscription.
+    pub event_type: EventType,
+    /// Bytes available to read (FD events only).
+    pub nbytes: u64,
+    /// FD state (FD events only).
+    pub flags: EventRwFlags,
+}
+
+impl Event {
+    /// Size of an event in guest memory.
+    pub const SIZE: usize = 32;
+
+    fn new(userdata: u64, event_type: EventType) -> Self {
+        Event {
+            userdata,
+            error: WasiError::Success,
+            event_type,
+            nbytes: 0,
+            flags: EventRwFlags::empty(),
+        }
+    }
+
+    fn with_error(self, error: WasiError) -> Self {
+        Event { error, ..self }
+    }
+
+    fn with_nbytes(self, nbytes: u64) -> Self {
+        Event { nbytes, ..self }
+    }
+
+    /// Encode the event in its guest-memory layout.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.userdata.to_le_bytes());
+        bytes[8..10].copy_from_slice(&(self.error as u16).to_le_bytes());
+        bytes[10] = self.event_type as u8;
+        bytes[16..24].copy_from_slice(&self.nbytes.to_le_bytes());
+        bytes[24..26].copy_from_slice(&self.flags.bits().to_le_bytes());
+        bytes
+    }
 }
 
 /// Directory entry.
@@ -1551,7 +2011,112 @@ mod tests {
         let t1 = ctx.clock_time_get(ClockId::Monotonic, 0).unwrap();
         let t2 = ctx.clock_time_get(ClockId::Monotonic, 0).unwrap();
         assert!(t1 > 0, "Monotonic time should be non-zero");
-        assert!(t2 > t1, "Monotonic time should increase");
+        assert!(t2 >= t1, "Monotonic time should not go backwards");
+    }
+
+    #[test]
+    fn test_clock_monotonic_non_decreasing() {
+        let mut ctx = WasiCtx::new();
+        let mut last = ctx.clock_time_get(ClockId::Monotonic, 0).unwrap();
+        for _ in 0..10_000 {
+            let now = ctx.clock_time_get(ClockId::Monotonic, 0).unwrap();
+            assert!(now >= last, "monotonic clock went from {} to {}", last, now);
+            last = now;
+        }
+
+        let res = ctx.clock_res_get(ClockId::Monotonic).unwrap();
+        assert!((1..=1_000).contains(&res), "resolution {} ns", res);
+    }
+
+    #[test]
+    fn test_poll_oneoff_clock() {
+        let ctx = WasiCtx::new();
+        assert_eq!(ctx.poll_oneoff(&[]), Err(WasiError::Inval));
+
+        let sleep = |userdata, timeout| Subscription {
+            userdata,
+            kind: SubscriptionKind::Clock {
+                id: ClockId::Monotonic,
+                timeout,
+                precision: 0,
+                flags: SubClockFlags::empty(),
+            },
+        };
+        let start = clock::monotonic_ns();
+        let events = ctx.poll_oneoff(&[sleep(1, 2_000_000), sleep(2, 60 * clock::NANOS_PER_SEC)]);
+        let elapsed = clock::monotonic_ns() - start;
+        let events = events.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].userdata, 1);
+        assert_eq!(events[0].event_type, EventType::Clock);
+        assert_eq!(events[0].error, WasiError::Success);
+        assert!(elapsed >= 2_000_000, "woke after {} ns", elapsed);
+
+        // An absolute deadline in the past fires at once
+        let past = Subscription {
+            userdata: 3,
+            kind: SubscriptionKind::Clock {
+                id: ClockId::Realtime,
+                timeout: 0,
+                precision: 0,
+                flags: SubClockFlags::ABSTIME,
+            },
+        };
+        assert_eq!(ctx.poll_oneoff(&[past]).unwrap()[0].userdata, 3);
+    }
+
+    #[test]
+    fn test_poll_oneoff_fds() {
+        let mut ctx = WasiCtx::new();
+        let dir_fd = ctx.preopen_dir("/data");
+        ctx.vfs
+            .create_file("/data/in.txt", b"0123456789".to_vec())
+            .unwrap();
+        let fd = ctx
+            .path_open(
+                dir_fd,
+                LookupFlags::empty(),
+                "in.txt",
+                OFlags::empty(),
+                FdRights::READ,
+                FdRights::empty(),
+                FdFlags::empty(),
+            )
+            .unwrap();
+        let mut buf = [0u8; 4];
+        ctx.fd_read(fd, &mut buf).unwrap();
+
+        let subs = [
+            Subscription {
+                userdata: 1,
+                kind: SubscriptionKind::FdRead(fd),
+            },
+            Subscription {
+                userdata: 2,
+                kind: SubscriptionKind::FdWrite(fd),
+            },
+            Subscription {
+                userdata: 3,
+                kind: SubscriptionKind::FdRead(99),
+            },
+            Subscription {
+                userdata: 4,
+                kind: SubscriptionKind::FdWrite(1),
+            },
+        ];
+        let events = ctx.poll_oneoff(&subs).unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].nbytes, 6);
+        assert_eq!(events[0].error, WasiError::Success);
+        assert_eq!(events[1].error, WasiError::Access);
+        assert_eq!(events[2].error, WasiError::BadF);
+        assert_eq!(events[3].error, WasiError::Success);
+        assert_eq!(events[3].event_type, EventType::FdWrite);
+
+        let bytes = events[0].to_bytes();
+        assert_eq!(&bytes[0..8], &1u64.to_le_bytes());
+        assert_eq!(bytes[10], EventType::FdRead as u8);
+        assert_eq!(&bytes[16..24], &6u64.to_le_bytes());
     }
 
     // C-QG6: Random non-zero bytes
@@ -1797,4 +2362,105 @@ mod tests {
         assert_eq!(stat.fs_filetype, FdType::CharDevice);
         assert!(stat.fs_rights_base.contains(FdRights::WRITE));
     }
+
+    #[test]
+    fn test_sock_serve_loopback_connection() {
+        use crate::wasi2::sockets::{IpAddress, IpAddressFamily};
+
+        let mut ctx = WasiCtx::new();
+        let listener = ctx
+            .preopen_tcp_listener(IpSocketAddress {
+                address: IpAddress::localhost_v4(),
+                port: 18080,
+            })
+            .unwrap();
+        let stat = ctx.fd_fdstat_get(listener).unwrap();
+        assert_eq!(stat.fs_filetype, FdType::SocketStream);
+        assert_eq!(
+            ctx.sock_accept(listener, FdFlags::empty()),
+            Err(WasiError::Again)
+        );
+
+        // A local client connects and sends a request
+        let client = sockets::create_tcp_socket(IpAddressFamily::Ipv4);
+        let server_side = sockets::tcp_loopback_connect(client, 18080).unwrap();
+        sockets::with_tcp_socket(client, |s| s.send(b"GET / HTTP/1.0\r\n\r\n"))
+            .unwrap()
+            .unwrap();
+        sockets::tcp_loopback_deliver(client, server_side);
+
+        let conn = ctx.sock_accept(listener, FdFlags::NONBLOCK).unwrap();
+        let mut buf = [0u8; 64];
+        let (n, ro_flags) = ctx.sock_recv(conn, &mut buf, RiFlags::empty()).unwrap();
+        assert_eq!(&buf[..n], b"GET / HTTP/1.0\r\n\r\n");
+        assert!(ro_flags.is_empty());
+        assert_eq!(
+            ctx.sock_recv(conn, &mut buf, RiFlags::empty()),
+            Err(WasiError::Again)
+        );
+
+        // The response can go through fd_write as well
+        assert_eq!(ctx.fd_write(conn, b"HTTP/1.0 204\r\n\r\n"), Ok(16));
+        sockets::tcp_loopback_deliver(server_side, client);
+        let response = sockets::with_tcp_socket(client, |s| s.recv(64))
+            .unwrap()
+            .unwrap();
+        assert_eq!(response, b"HTTP/1.0 204\r\n\r\n");
+
+        ctx.sock_shutdown(conn, SdFlags::RD | SdFlags::WR).unwrap();
+        assert_eq!(ctx.fd_read(conn, &mut buf), Ok(0));
+        assert_eq!(ctx.sock_send(conn, b"late"), Err(WasiError::Pipe));
+        ctx.fd_close(conn).unwrap();
+
+        // A closed listener takes no more connections
+        ctx.fd_close(listener).unwrap();
+        assert_eq!(
+            sockets::tcp_loopback_connect(client, 18080),
+            Err(SocketError::ConnectionRefused)
+        );
+    }
+
+    #[test]
+    fn test_sock_calls_check_fd() {
+        use crate::wasi2::sockets::IpAddress;
+
+        let mut ctx = WasiCtx::new();
+        let listener = ctx
+            .preopen_tcp_listener(IpSocketAddress {
+                address: IpAddress::localhost_v4(),
+                port: 18081,
+            })
+            .unwrap();
+        let mut buf = [0u8; 8];
+
+        assert_eq!(
+            ctx.sock_accept(1, FdFlags::empty()),
+            Err(WasiError::NotSock)
+        );
+        assert_eq!(
+            ctx.sock_accept(listener, FdFlags::APPEND),
+            Err(WasiError::Inval)
+        );
+        // A listener cannot be read from or shut down
+        assert_eq!(
+            ctx.sock_recv(listener, &mut buf, RiFlags::empty()),
+            Err(WasiError::Access)
+        );
+        assert_eq!(
+            ctx.sock_shutdown(listener, SdFlags::WR),
+            Err(WasiError::Access)
+        );
+        assert_eq!(
+            ctx.sock_recv(listener, &mut buf, RiFlags::RECV_PEEK),
+            Err(WasiError::NotSup)
+        );
+
+        ctx.fd_fdstat_set_flags(listener, FdFlags::NONBLOCK)
+            .unwrap();
+        assert_eq!(
+            ctx.fd_fdstat_get(listener).unwrap().fs_flags,
+            FdFlags::NONBLOCK
+        );
+        ctx.fd_close(listener).unwrap();
+    }
 }
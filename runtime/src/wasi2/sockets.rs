@@ -250,6 +250,28 @@ impl TcpSocket {
         if self.state != TcpState::ListenStarted {
             return Err(SocketError::NotListening);
         }
+
+        // When the kernel feature is enabled and the socket is not bound
+        // to loopback, listen on the kernel stack for real connections.
+        #[cfg(feature = "kernel")]
+        {
+            if let Some(IpSocketAddress {
+                address: IpAddress::Ipv4(a, ..),
+                port,
+            }) = &self.local_address
+            {
+                if *a != 127 {
+                    match kpio_kernel::net::wasi_bridge::tcp_listen(*port) {
+                        Ok(handle) => {
+                            self.kernel_conn = Some(handle.conn_id.0);
+                            core::mem::forget(handle);
+                        }
+                        Err(_) => return Err(SocketError::AddressInUse),
+                    }
+                }
+            }
+        }
+
         self.state = TcpState::Listening;
         Ok(())
     }
@@ -309,6 +331,29 @@ impl TcpSocket {
         Ok(data)
     }
 
+    /// Like [`recv`](Self::recv), but never waits on the kernel stack for
+    /// data. An empty result from a kernel connection is end of stream.
+    pub fn try_recv(&mut self, max_len: usize) -> Result<Vec<u8>, SocketError> {
+        if self.state != TcpState::Connected {
+            return Err(SocketError::NotConnected);
+        }
+        #[cfg(feature = "kernel")]
+        if let Some(conn_id) = self.kernel_conn {
+            let handle = kpio_kernel::net::wasi_bridge::TcpHandle {
+                conn_id: kpio_kernel::net::tcp::ConnId(conn_id),
+            };
+            let mut buf = alloc::vec![0u8; max_len];
+            let result = kpio_kernel::net::wasi_bridge::tcp_recv_nonblocking(&handle, &mut buf);
+            core::mem::forget(handle);
+            return match result {
+                Ok(n) => Ok(buf[..n].to_vec()),
+                Err(kpio_kernel::net::NetError::WouldBlock) => Err(SocketError::WouldBlock),
+                Err(_) => Err(SocketError::ConnectionReset),
+            };
+        }
+        self.recv(max_len)
+    }
+
     pub fn shutdown(&mut self, _how: ShutdownType) -> Result<(), SocketError> {
         if self.state != TcpState::Connected {
             return Err(SocketError::NotConnected);
@@ -627,6 +672,80 @@ pub fn tcp_loopback_connect(
     })
 }
 
+/// Accept the next connection on a listening TCP socket, returning the
+/// ID of the new connected socket.
+///
+/// Kernel-backed listeners wait for a connection when `blocking`.
+/// Loopback connections only arrive through [`tcp_loopback_connect`],
+/// so there is nothing to wait for: an empty loopback backlog is
+/// `WouldBlock` either way.
+#[cfg_attr(not(feature = "kernel"), allow(unused_variables))]
+pub fn tcp_accept(listener_id: u32, blocking: bool) -> Result<u32, SocketError> {
+    with_mgr(|mgr| {
+        let listener = mgr
+            .tcp_sockets
+            .get_mut(&listener_id)
+            .ok_or(SocketError::InvalidArgument)?;
+        if listener.state != TcpState::Listening {
+            return Err(SocketError::NotListening);
+        }
+        if !listener.accept_queue.is_empty() || listener.kernel_conn.is_none() {
+            return listener.accept();
+        }
+
+        #[cfg(feature = "kernel")]
+        if let Some(conn_id) = listener.kernel_conn {
+            let family = listener.family;
+            let local_address = listener.local_address.clone();
+
+            let handle = kpio_kernel::net::wasi_bridge::TcpHandle {
+                conn_id: kpio_kernel::net::tcp::ConnId(conn_id),
+            };
+            let result = if blocking {
+                kpio_kernel::net::wasi_bridge::tcp_accept(&handle)
+            } else {
+                kpio_kernel::net::wasi_bridge::tcp_accept_nonblocking(&handle)
+            };
+            core::mem::forget(handle);
+            let accepted = result.map_err(|e| match e {
+                kpio_kernel::net::NetError::WouldBlock | kpio_kernel::net::NetError::TimedOut => {
+                    SocketError::WouldBlock
+                }
+                _ => SocketError::ConnectionAborted,
+            })?;
+
+            let id = mgr.next_id;
+            mgr.next_id += 1;
+            let mut socket = TcpSocket::new(id, family);
+            socket.state = TcpState::Connected;
+            socket.local_address = local_address;
+            socket.kernel_conn = Some(accepted.conn_id.0);
+            core::mem::forget(accepted);
+            mgr.tcp_sockets.insert(id, socket);
+            return Ok(id);
+        }
+
+        Err(SocketError::WouldBlock)
+    })
+}
+
+/// Close a TCP socket and drop it: a kernel connection is closed, and a
+/// listener stops taking loopback connections.
+pub fn close_tcp_socket(id: u32) {
+    with_mgr(|mgr| {
+        #[cfg(feature = "kernel")]
+        if let Some(conn_id) = mgr.tcp_sockets.get(&id).and_then(|s| s.kernel_conn) {
+            let handle = kpio_kernel::net::wasi_bridge::TcpHandle {
+                conn_id: kpio_kernel::net::tcp::ConnId(conn_id),
+            };
+            let _ = kpio_kernel::net::wasi_bridge::tcp_close(&handle);
+            kpio_kernel::net::wasi_bridge::tcp_destroy(handle);
+        }
+        mgr.tcp_sockets.remove(&id);
+        mgr.tcp_listeners.retain(|_, listener| *listener != id);
+    });
+}
+
 /// Helper: register a bound+listening socket's port in the listener map.
 pub fn tcp_register_listener(socket_id: u32, port: u16) {
     with_mgr(|mgr| {
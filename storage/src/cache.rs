@@ -1,101 +1,53 @@
 //! Block cache implementation.
 //!
 //! This module provides a block caching layer to improve I/O performance
-//! by reducing disk accesses.
+//! by reducing disk accesses. Blocks are keyed by `(device, block)` and
+//! sized by each device's `BlockDeviceInfo::block_size`; the least
+//! recently used block is evicted once the cache is full.
+//!
+//! Writes are write-back: a written block is only marked dirty, so
+//! repeated writes to it reach the device once, when the device is
+//! flushed (or the block is evicted). Flushing writes runs of adjacent
+//! dirty blocks with a single device request.
+//!
+//! Filesystems mounted through the VFS access their device through a
+//! [`CachedDevice`]. Unmounting flushes the device and drops its blocks.
 
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec;
 use alloc::vec::Vec;
 use spin::Mutex;
 
-use crate::StorageError;
-
-/// Maximum number of cached blocks.
-const CACHE_SIZE: usize = 1024;
-
-/// Block size for cache (must match filesystem block size).
-const BLOCK_SIZE: usize = 4096;
-
-/// Cache entry state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CacheState {
-    /// Entry is free.
-    Free,
-    /// Entry contains valid data.
-    Clean,
-    /// Entry contains modified data.
-    Dirty,
-    /// Entry is locked for I/O.
-    Locked,
-}
-
-/// Cache entry.
-pub struct CacheEntry {
-    /// Device ID.
-    device_id: u32,
-    /// Block number.
-    block_num: u64,
-    /// Entry state.
-    state: CacheState,
-    /// Reference count.
-    ref_count: u32,
-    /// Access count for LRU.
-    access_count: u64,
-    /// Block data.
-    data: [u8; BLOCK_SIZE],
-}
+use crate::driver::BlockDevice;
+use crate::{BlockDeviceInfo, StorageError};
 
-impl CacheEntry {
-    /// Create a new free cache entry.
-    pub const fn new() -> Self {
-        CacheEntry {
-            device_id: 0,
-            block_num: 0,
-            state: CacheState::Free,
-            ref_count: 0,
-            access_count: 0,
-            data: [0; BLOCK_SIZE],
-        }
-    }
+/// Default number of cached blocks.
+pub const DEFAULT_CAPACITY: usize = 1024;
 
-    /// Check if entry is free.
-    pub fn is_free(&self) -> bool {
-        self.state == CacheState::Free
-    }
+/// Cache key: device index and block number.
+type BlockKey = (u32, u64);
 
-    /// Check if entry matches device and block.
-    pub fn matches(&self, device_id: u32, block_num: u64) -> bool {
-        self.device_id == device_id && self.block_num == block_num && self.state != CacheState::Free
-    }
-
-    /// Get data reference.
-    pub fn data(&self) -> &[u8; BLOCK_SIZE] {
-        &self.data
-    }
-
-    /// Get mutable data reference.
-    pub fn data_mut(&mut self) -> &mut [u8; BLOCK_SIZE] {
-        &mut self.data
-    }
-
-    /// Mark as dirty.
-    pub fn mark_dirty(&mut self) {
-        if self.state == CacheState::Clean {
-            self.state = CacheState::Dirty;
-        }
-    }
-
-    /// Mark as clean.
-    pub fn mark_clean(&mut self) {
-        if self.state == CacheState::Dirty {
-            self.state = CacheState::Clean;
-        }
-    }
+/// Cache entry.
+struct CacheEntry {
+    /// Device the block belongs to, for write-back.
+    device: &'static dyn BlockDevice,
+    /// Block data.
+    data: Vec<u8>,
+    /// Modified since it was last written to the device.
+    dirty: bool,
+    /// Access stamp, the entry's key in the LRU list.
+    last_access: u64,
 }
 
 /// Block cache.
 pub struct BlockCache {
+    /// Maximum number of cached blocks.
+    capacity: usize,
     /// Cache entries.
-    entries: [CacheEntry; CACHE_SIZE],
+    entries: BTreeMap<BlockKey, CacheEntry>,
+    /// Entries by access stamp, least recently used first.
+    lru: BTreeMap<u64, BlockKey>,
     /// Global access counter.
     access_counter: u64,
     /// Number of cache hits.
@@ -107,11 +59,12 @@ pub struct BlockCache {
 }
 
 impl BlockCache {
-    /// Create a new block cache.
-    pub const fn new() -> Self {
-        const ENTRY: CacheEntry = CacheEntry::new();
+    /// Create a block cache holding up to `capacity` blocks.
+    pub const fn new(capacity: usize) -> Self {
         BlockCache {
-            entries: [ENTRY; CACHE_SIZE],
+            capacity,
+            entries: BTreeMap::new(),
+            lru: BTreeMap::new(),
             access_counter: 0,
             hits: 0,
             misses: 0,
@@ -119,186 +72,306 @@ impl BlockCache {
         }
     }
 
-    /// Find a cache entry.
-    pub fn find(&mut self, device_id: u32, block_num: u64) -> Option<usize> {
-        for (i, entry) in self.entries.iter().enumerate() {
-            if entry.matches(device_id, block_num) {
-                return Some(i);
-            }
-        }
-        None
-    }
-
-    /// Get a block from cache.
-    pub fn get(&mut self, device_id: u32, block_num: u64) -> Option<&[u8; BLOCK_SIZE]> {
-        if let Some(idx) = self.find(device_id, block_num) {
-            self.access_counter += 1;
-            self.entries[idx].access_count = self.access_counter;
-            self.entries[idx].ref_count += 1;
-            self.hits += 1;
-            Some(&self.entries[idx].data)
-        } else {
-            self.misses += 1;
-            None
+    /// Change the capacity, evicting blocks if it shrinks.
+    pub fn set_capacity(&mut self, capacity: usize) -> Result<(), StorageError> {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.evict()?;
         }
+        Ok(())
     }
 
-    /// Get a mutable block from cache.
-    pub fn get_mut(&mut self, device_id: u32, block_num: u64) -> Option<&mut [u8; BLOCK_SIZE]> {
-        if let Some(idx) = self.find(device_id, block_num) {
-            self.access_counter += 1;
-            self.entries[idx].access_count = self.access_counter;
-            self.entries[idx].ref_count += 1;
-            self.entries[idx].mark_dirty();
-            self.hits += 1;
-            Some(&mut self.entries[idx].data)
-        } else {
-            self.misses += 1;
-            None
+    /// Read blocks, fetching runs of uncached blocks with one device
+    /// request each.
+    pub fn read_blocks(
+        &mut self,
+        device_id: u32,
+        device: &'static dyn BlockDevice,
+        start_block: u64,
+        buffer: &mut [u8],
+    ) -> Result<(), StorageError> {
+        let block_size = block_size(device)?;
+        if !buffer.len().is_multiple_of(block_size) {
+            return Err(StorageError::InvalidArgument);
         }
-    }
 
-    /// Find a free or evictable slot.
-    fn find_slot(&self) -> Option<usize> {
-        // First, look for a free entry
-        for (i, entry) in self.entries.iter().enumerate() {
-            if entry.is_free() {
-                return Some(i);
+        let count = buffer.len() / block_size;
+        let mut i = 0;
+        while i < count {
+            let block = start_block + i as u64;
+            if let Some(data) = self.lookup((device_id, block)) {
+                buffer[i * block_size..(i + 1) * block_size].copy_from_slice(data);
+                self.hits += 1;
+                i += 1;
+                continue;
             }
-        }
 
-        // Find LRU clean entry
-        let mut min_access = u64::MAX;
-        let mut min_idx = None;
-
-        for (i, entry) in self.entries.iter().enumerate() {
-            if entry.state == CacheState::Clean && entry.ref_count == 0 {
-                if entry.access_count < min_access {
-                    min_access = entry.access_count;
-                    min_idx = Some(i);
-                }
+            let mut end = i + 1;
+            while end < count
+                && !self
+                    .entries
+                    .contains_key(&(device_id, start_block + end as u64))
+            {
+                end += 1;
+            }
+            let run = &mut buffer[i * block_size..end * block_size];
+            if device.read_blocks(block, run)? < run.len() {
+                return Err(StorageError::IoError);
             }
+            self.misses += (end - i) as u64;
+            for (j, data) in run.chunks_exact(block_size).enumerate() {
+                self.insert((device_id, block + j as u64), device, data.to_vec(), false)?;
+            }
+            i = end;
         }
-
-        min_idx
+        Ok(())
     }
 
-    /// Insert a block into cache.
-    pub fn insert(
+    /// Write blocks into the cache. With `sync` they are also written to
+    /// the device before returning; otherwise they stay dirty until the
+    /// next flush.
+    pub fn write_blocks(
         &mut self,
         device_id: u32,
-        block_num: u64,
+        device: &'static dyn BlockDevice,
+        start_block: u64,
         data: &[u8],
-    ) -> Result<usize, StorageError> {
-        // Check if already cached
-        if let Some(idx) = self.find(device_id, block_num) {
-            // Update existing entry
-            self.entries[idx].data[..data.len()].copy_from_slice(data);
-            self.access_counter += 1;
-            self.entries[idx].access_count = self.access_counter;
-            return Ok(idx);
-        }
-
-        // Find a slot
-        let idx = self.find_slot().ok_or(StorageError::NoSpace)?;
-
-        // Initialize entry
-        self.entries[idx].device_id = device_id;
-        self.entries[idx].block_num = block_num;
-        self.entries[idx].state = CacheState::Clean;
-        self.entries[idx].ref_count = 0;
-        self.access_counter += 1;
-        self.entries[idx].access_count = self.access_counter;
-        self.entries[idx].data[..data.len()].copy_from_slice(data);
+        sync: bool,
+    ) -> Result<(), StorageError> {
+        let block_size = block_size(device)?;
+        if !data.len().is_multiple_of(block_size) {
+            return Err(StorageError::InvalidArgument);
+        }
 
-        Ok(idx)
+        if sync && device.write_blocks(start_block, data)? < data.len() {
+            return Err(StorageError::IoError);
+        }
+        for (i, block) in data.chunks_exact(block_size).enumerate() {
+            self.insert(
+                (device_id, start_block + i as u64),
+                device,
+                block.to_vec(),
+                !sync,
+            )?;
+        }
+        Ok(())
     }
 
-    /// Release a reference to a cache entry.
-    pub fn release(&mut self, device_id: u32, block_num: u64) {
-        if let Some(idx) = self.find(device_id, block_num) {
-            if self.entries[idx].ref_count > 0 {
-                self.entries[idx].ref_count -= 1;
-            }
+    /// Read one block.
+    pub fn read_block(
+        &mut self,
+        device_id: u32,
+        device: &'static dyn BlockDevice,
+        block_num: u64,
+        buffer: &mut [u8],
+    ) -> Result<(), StorageError> {
+        if buffer.len() != block_size(device)? {
+            return Err(StorageError::InvalidArgument);
         }
+        self.read_blocks(device_id, device, block_num, buffer)
     }
 
-    /// Invalidate a cache entry.
-    pub fn invalidate(&mut self, device_id: u32, block_num: u64) {
-        if let Some(idx) = self.find(device_id, block_num) {
-            if self.entries[idx].state == CacheState::Dirty {
-                self.dirty_count -= 1;
-            }
-            self.entries[idx].state = CacheState::Free;
-            self.entries[idx].ref_count = 0;
+    /// Write one block.
+    pub fn write_block(
+        &mut self,
+        device_id: u32,
+        device: &'static dyn BlockDevice,
+        block_num: u64,
+        data: &[u8],
+        sync: bool,
+    ) -> Result<(), StorageError> {
+        if data.len() != block_size(device)? {
+            return Err(StorageError::InvalidArgument);
         }
+        self.write_blocks(device_id, device, block_num, data, sync)
     }
 
-    /// Invalidate all entries for a device.
-    pub fn invalidate_device(&mut self, device_id: u32) {
-        for entry in &mut self.entries {
-            if entry.device_id == device_id && entry.state != CacheState::Free {
-                if entry.state == CacheState::Dirty {
-                    self.dirty_count -= 1;
+    /// Write all dirty blocks of a device.
+    pub fn flush_device(&mut self, device_id: u32) -> Result<(), StorageError> {
+        let dirty: Vec<(u64, &'static dyn BlockDevice, Vec<u8>)> = self
+            .entries
+            .range((device_id, 0)..=(device_id, u64::MAX))
+            .filter(|(_, e)| e.dirty)
+            .map(|(&(_, block), e)| (block, e.device, e.data.clone()))
+            .collect();
+
+        let mut run: Option<(u64, &'static dyn BlockDevice, Vec<u8>)> = None;
+        for (block, device, data) in dirty {
+            if let Some((start, _, blocks)) = &mut run {
+                if *start + (blocks.len() / data.len()) as u64 == block {
+                    blocks.extend_from_slice(&data);
+                    continue;
                 }
-                entry.state = CacheState::Free;
-                entry.ref_count = 0;
             }
+            if let Some((start, device, blocks)) = run.replace((block, device, data)) {
+                self.write_back(device_id, device, start, &blocks)?;
+            }
+        }
+        if let Some((start, device, blocks)) = run {
+            self.write_back(device_id, device, start, &blocks)?;
         }
+        Ok(())
     }
 
-    /// Get all dirty entries for a device.
-    pub fn get_dirty_blocks(&self, device_id: u32) -> Vec<(u64, &[u8; BLOCK_SIZE])> {
-        let mut result = Vec::new();
-
-        for entry in &self.entries {
-            if entry.device_id == device_id && entry.state == CacheState::Dirty {
-                result.push((entry.block_num, &entry.data));
-            }
+    /// Write all dirty blocks of every device.
+    pub fn flush_all(&mut self) -> Result<(), StorageError> {
+        let mut devices: Vec<u32> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.dirty)
+            .map(|(&(device_id, _), _)| device_id)
+            .collect();
+        devices.dedup();
+        for device_id in devices {
+            self.flush_device(device_id)?;
         }
+        Ok(())
+    }
 
-        result
+    /// Invalidate a cache entry, discarding unwritten changes.
+    pub fn invalidate(&mut self, device_id: u32, block_num: u64) {
+        self.remove((device_id, block_num));
     }
 
-    /// Mark a block as clean.
-    pub fn mark_clean(&mut self, device_id: u32, block_num: u64) {
-        if let Some(idx) = self.find(device_id, block_num) {
-            if self.entries[idx].state == CacheState::Dirty {
-                self.entries[idx].state = CacheState::Clean;
-                self.dirty_count -= 1;
-            }
+    /// Invalidate all entries for a device, discarding unwritten changes.
+    pub fn invalidate_device(&mut self, device_id: u32) {
+        let keys: Vec<BlockKey> = self
+            .entries
+            .range((device_id, 0)..=(device_id, u64::MAX))
+            .map(|(&key, _)| key)
+            .collect();
+        for key in keys {
+            self.remove(key);
         }
     }
 
     /// Get cache statistics.
     pub fn stats(&self) -> CacheStats {
         CacheStats {
-            size: CACHE_SIZE,
-            used: self.entries.iter().filter(|e| !e.is_free()).count(),
+            size: self.capacity,
+            used: self.entries.len(),
             dirty: self.dirty_count,
             hits: self.hits,
             misses: self.misses,
         }
     }
 
-    /// Flush all dirty entries.
-    pub fn flush_all(&mut self) {
-        // Note: Actual write-back would require device access
-        for entry in &mut self.entries {
-            if entry.state == CacheState::Dirty {
-                entry.state = CacheState::Clean;
+    /// Clear the entire cache, discarding unwritten changes.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+        self.dirty_count = 0;
+    }
+
+    /// Look up a block and mark it most recently used.
+    fn lookup(&mut self, key: BlockKey) -> Option<&[u8]> {
+        let entry = self.entries.get_mut(&key)?;
+        self.lru.remove(&entry.last_access);
+        self.access_counter += 1;
+        entry.last_access = self.access_counter;
+        self.lru.insert(entry.last_access, key);
+        Some(&entry.data)
+    }
+
+    /// Store a block, replacing any cached copy.
+    fn insert(
+        &mut self,
+        key: BlockKey,
+        device: &'static dyn BlockDevice,
+        data: Vec<u8>,
+        dirty: bool,
+    ) -> Result<(), StorageError> {
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.data = data;
+            match (entry.dirty, dirty) {
+                (false, true) => self.dirty_count += 1,
+                (true, false) => self.dirty_count -= 1,
+                _ => {}
             }
+            entry.dirty = dirty;
+            self.lookup(key);
+            return Ok(());
         }
-        self.dirty_count = 0;
+
+        if self.capacity == 0 {
+            // Nothing can be cached, so writes go straight through
+            if dirty && device.write_blocks(key.1, &data)? < data.len() {
+                return Err(StorageError::IoError);
+            }
+            return Ok(());
+        }
+        while self.entries.len() >= self.capacity {
+            self.evict()?;
+        }
+
+        self.access_counter += 1;
+        self.lru.insert(self.access_counter, key);
+        self.entries.insert(
+            key,
+            CacheEntry {
+                device,
+                data,
+                dirty,
+                last_access: self.access_counter,
+            },
+        );
+        if dirty {
+            self.dirty_count += 1;
+        }
+        Ok(())
     }
 
-    /// Clear the entire cache.
-    pub fn clear(&mut self) {
-        for entry in &mut self.entries {
-            entry.state = CacheState::Free;
-            entry.ref_count = 0;
+    /// Evict the least recently used block, writing it back if dirty.
+    fn evict(&mut self) -> Result<(), StorageError> {
+        let Some((_, &key)) = self.lru.iter().next() else {
+            return Ok(());
+        };
+        let entry = &self.entries[&key];
+        if entry.dirty {
+            let (device, data) = (entry.device, entry.data.clone());
+            self.write_back(key.0, device, key.1, &data)?;
         }
-        self.dirty_count = 0;
+        self.remove(key);
+        Ok(())
+    }
+
+    /// Write dirty blocks to the device and mark them clean.
+    fn write_back(
+        &mut self,
+        device_id: u32,
+        device: &'static dyn BlockDevice,
+        start_block: u64,
+        data: &[u8],
+    ) -> Result<(), StorageError> {
+        if device.write_blocks(start_block, data)? < data.len() {
+            return Err(StorageError::IoError);
+        }
+        let block_size = block_size(device)?;
+        for i in 0..(data.len() / block_size) as u64 {
+            if let Some(entry) = self.entries.get_mut(&(device_id, start_block + i)) {
+                if entry.dirty {
+                    entry.dirty = false;
+                    self.dirty_count -= 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, key: BlockKey) {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.lru.remove(&entry.last_access);
+            if entry.dirty {
+                self.dirty_count -= 1;
+            }
+        }
+    }
+}
+
+fn block_size(device: &dyn BlockDevice) -> Result<usize, StorageError> {
+    match device.info().block_size as usize {
+        0 => Err(StorageError::InvalidArgument),
+        size => Ok(size),
     }
 }
 
@@ -329,8 +402,91 @@ impl CacheStats {
     }
 }
 
+/// A block device whose I/O goes through the global cache.
+pub struct CachedDevice {
+    /// Index of the device in the driver registry.
+    id: u32,
+    /// Underlying device.
+    device: &'static dyn BlockDevice,
+}
+
+impl CachedDevice {
+    /// Wrap registered device `index`.
+    pub fn new(index: usize) -> Result<Self, StorageError> {
+        let device = crate::driver::get_device(index).ok_or(StorageError::DeviceNotFound)?;
+        Ok(CachedDevice {
+            id: index as u32,
+            device,
+        })
+    }
+
+    /// Wrap registered device `index`, leaking the wrapper so it can back
+    /// a mounted filesystem.
+    pub fn leak(index: usize) -> Result<&'static dyn BlockDevice, StorageError> {
+        Ok(Box::leak(Box::new(Self::new(index)?)))
+    }
+}
+
+impl BlockDevice for CachedDevice {
+    fn info(&self) -> BlockDeviceInfo {
+        self.device.info()
+    }
+
+    fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+        let block_size = block_size(self.device)?;
+        let whole = buffer.len() / block_size * block_size;
+        let (blocks, tail) = buffer.split_at_mut(whole);
+
+        let mut cache = CACHE.lock();
+        cache.read_blocks(self.id, self.device, start_block, blocks)?;
+        if !tail.is_empty() {
+            let mut block = vec![0u8; block_size];
+            let last = start_block + (whole / block_size) as u64;
+            cache.read_blocks(self.id, self.device, last, &mut block)?;
+            tail.copy_from_slice(&block[..tail.len()]);
+        }
+        Ok(buffer.len())
+    }
+
+    fn write_blocks(&self, start_block: u64, data: &[u8]) -> Result<usize, StorageError> {
+        let block_size = block_size(self.device)?;
+        let whole = data.len() / block_size * block_size;
+        let (blocks, tail) = data.split_at(whole);
+
+        let mut cache = CACHE.lock();
+        cache.write_blocks(self.id, self.device, start_block, blocks, false)?;
+        if !tail.is_empty() {
+            // A partial block keeps the rest of its old contents
+            let mut block = vec![0u8; block_size];
+            let last = start_block + (whole / block_size) as u64;
+            cache.read_blocks(self.id, self.device, last, &mut block)?;
+            block[..tail.len()].copy_from_slice(tail);
+            cache.write_blocks(self.id, self.device, last, &block, false)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        CACHE.lock().flush_device(self.id)?;
+        self.device.flush()
+    }
+
+    fn discard(&self, start_block: u64, num_blocks: u64) -> Result<(), StorageError> {
+        let mut cache = CACHE.lock();
+        for block in start_block..start_block.saturating_add(num_blocks) {
+            cache.invalidate(self.id, block);
+        }
+        drop(cache);
+        self.device.discard(start_block, num_blocks)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.device.is_ready()
+    }
+}
+
 /// Global block cache.
-static CACHE: Mutex<BlockCache> = Mutex::new(BlockCache::new());
+static CACHE: Mutex<BlockCache> = Mutex::new(BlockCache::new(DEFAULT_CAPACITY));
 
 /// Initialize the block cache.
 pub fn init() -> Result<(), StorageError> {
@@ -339,17 +495,32 @@ pub fn init() -> Result<(), StorageError> {
     Ok(())
 }
 
-/// Get a block from cache.
-pub fn get_block(device_id: u32, block_num: u64) -> Option<[u8; BLOCK_SIZE]> {
-    let mut cache = CACHE.lock();
-    cache.get(device_id, block_num).copied()
+/// Set the cache capacity in blocks.
+pub fn set_capacity(blocks: usize) -> Result<(), StorageError> {
+    CACHE.lock().set_capacity(blocks)
 }
 
-/// Insert a block into cache.
-pub fn cache_block(device_id: u32, block_num: u64, data: &[u8]) -> Result<(), StorageError> {
-    let mut cache = CACHE.lock();
-    cache.insert(device_id, block_num, data)?;
-    Ok(())
+/// Read one block of registered device `device_id` through the cache.
+pub fn read_block(device_id: u32, block_num: u64, buffer: &mut [u8]) -> Result<(), StorageError> {
+    let device =
+        crate::driver::get_device(device_id as usize).ok_or(StorageError::DeviceNotFound)?;
+    CACHE
+        .lock()
+        .read_block(device_id, device, block_num, buffer)
+}
+
+/// Write one block of registered device `device_id` through the cache.
+pub fn write_block(
+    device_id: u32,
+    block_num: u64,
+    data: &[u8],
+    sync: bool,
+) -> Result<(), StorageError> {
+    let device =
+        crate::driver::get_device(device_id as usize).ok_or(StorageError::DeviceNotFound)?;
+    CACHE
+        .lock()
+        .write_block(device_id, device, block_num, data, sync)
 }
 
 /// Invalidate a cached block.
@@ -358,14 +529,224 @@ pub fn invalidate_block(device_id: u32, block_num: u64) {
     cache.invalidate(device_id, block_num);
 }
 
+/// Write back and drop every cached block of a device.
+pub fn release_device(device_id: u32) -> Result<(), StorageError> {
+    let mut cache = CACHE.lock();
+    cache.flush_device(device_id)?;
+    cache.invalidate_device(device_id);
+    Ok(())
+}
+
 /// Get cache statistics.
 pub fn get_stats() -> CacheStats {
     let cache = CACHE.lock();
     cache.stats()
 }
 
-/// Flush all dirty blocks.
-pub fn flush() {
+/// Write back all dirty blocks.
+pub fn flush() -> Result<(), StorageError> {
     let mut cache = CACHE.lock();
-    cache.flush_all();
+    cache.flush_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const BLOCK: usize = 512;
+
+    /// RAM disk that logs every request as `(start_block, blocks)`.
+    struct CountingDisk {
+        data: Mutex<Vec<u8>>,
+        reads: Mutex<Vec<(u64, usize)>>,
+        writes: Mutex<Vec<(u64, usize)>>,
+    }
+
+    impl CountingDisk {
+        fn new(blocks: usize) -> &'static Self {
+            let data = (0..blocks * BLOCK).map(|i| (i / BLOCK) as u8).collect();
+            Box::leak(Box::new(CountingDisk {
+                data: Mutex::new(data),
+                reads: Mutex::new(Vec::new()),
+                writes: Mutex::new(Vec::new()),
+            }))
+        }
+
+        fn reads(&self) -> Vec<(u64, usize)> {
+            self.reads.lock().clone()
+        }
+
+        fn writes(&self) -> Vec<(u64, usize)> {
+            self.writes.lock().clone()
+        }
+
+        fn block(&self, block: u64) -> Vec<u8> {
+            let start = block as usize * BLOCK;
+            self.data.lock()[start..start + BLOCK].to_vec()
+        }
+    }
+
+    impl BlockDevice for CountingDisk {
+        fn info(&self) -> BlockDeviceInfo {
+            BlockDeviceInfo {
+                name: [0; 32],
+                name_len: 0,
+                block_size: BLOCK as u32,
+                total_blocks: (self.data.lock().len() / BLOCK) as u64,
+                read_only: false,
+                supports_trim: false,
+                optimal_io_size: 1,
+                physical_block_size: BLOCK as u32,
+            }
+        }
+
+        fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+            self.reads.lock().push((start_block, buffer.len() / BLOCK));
+            let start = start_block as usize * BLOCK;
+            buffer.copy_from_slice(&self.data.lock()[start..start + buffer.len()]);
+            Ok(buffer.len())
+        }
+
+        fn write_blocks(&self, start_block: u64, data: &[u8]) -> Result<usize, StorageError> {
+            self.writes.lock().push((start_block, data.len() / BLOCK));
+            let start = start_block as usize * BLOCK;
+            self.data.lock()[start..start + data.len()].copy_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&self) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn discard(&self, _start_block: u64, _num_blocks: u64) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_read_run_in_one_request() {
+        let disk = CountingDisk::new(16);
+        let mut cache = BlockCache::new(16);
+
+        // Block 3 is cached; 0..3 and 4..6 are fetched as two runs
+        let mut block = vec![0u8; BLOCK];
+        cache.read_block(0, disk, 3, &mut block).unwrap();
+        let mut buffer = vec![0u8; 6 * BLOCK];
+        cache.read_blocks(0, disk, 0, &mut buffer).unwrap();
+        assert_eq!(disk.reads(), [(3, 1), (0, 3), (4, 2)]);
+        for (i, chunk) in buffer.chunks(BLOCK).enumerate() {
+            assert!(chunk.iter().all(|&b| b == i as u8));
+        }
+
+        // Everything is cached now
+        cache.read_blocks(0, disk, 0, &mut buffer).unwrap();
+        assert_eq!(disk.reads().len(), 3);
+        assert_eq!(cache.stats().hits, 7);
+        assert_eq!(cache.stats().misses, 6);
+    }
+
+    #[test]
+    fn test_repeated_writes_reach_device_at_flush() {
+        let disk = CountingDisk::new(16);
+        let mut cache = BlockCache::new(16);
+
+        for value in 1..=5u8 {
+            cache
+                .write_block(0, disk, 7, &[value; BLOCK], false)
+                .unwrap();
+        }
+        assert!(disk.writes().is_empty());
+        assert_eq!(cache.stats().dirty, 1);
+
+        let mut block = vec![0u8; BLOCK];
+        cache.read_block(0, disk, 7, &mut block).unwrap();
+        assert_eq!(block, [5; BLOCK]);
+        assert!(disk.reads().is_empty());
+
+        cache.flush_device(0).unwrap();
+        assert_eq!(disk.writes(), [(7, 1)]);
+        assert_eq!(disk.block(7), [5; BLOCK]);
+        assert_eq!(cache.stats().dirty, 0);
+
+        // Clean blocks are not written again
+        cache.flush_device(0).unwrap();
+        assert_eq!(disk.writes().len(), 1);
+    }
+
+    #[test]
+    fn test_flush_coalesces_adjacent_blocks() {
+        let disk = CountingDisk::new(16);
+        let mut cache = BlockCache::new(16);
+
+        for block in [2, 3, 4, 9, 10] {
+            cache
+                .write_block(0, disk, block, &[0xAA; BLOCK], false)
+                .unwrap();
+        }
+        cache.flush_all().unwrap();
+        assert_eq!(disk.writes(), [(2, 3), (9, 2)]);
+        assert_eq!(disk.block(4), [0xAA; BLOCK]);
+        assert_eq!(disk.block(5), [5; BLOCK]);
+    }
+
+    #[test]
+    fn test_evicting_dirty_block_writes_it_back() {
+        let disk = CountingDisk::new(16);
+        let mut cache = BlockCache::new(2);
+
+        cache
+            .write_block(0, disk, 1, &[0x11; BLOCK], false)
+            .unwrap();
+        let mut block = vec![0u8; BLOCK];
+        cache.read_block(0, disk, 2, &mut block).unwrap();
+        assert!(disk.writes().is_empty());
+
+        // Block 1 is least recently used and goes first
+        cache.read_block(0, disk, 3, &mut block).unwrap();
+        assert_eq!(disk.writes(), [(1, 1)]);
+        assert_eq!(disk.block(1), [0x11; BLOCK]);
+        assert_eq!(cache.stats().used, 2);
+        assert_eq!(cache.stats().dirty, 0);
+
+        // Shrinking evicts too
+        cache
+            .write_block(0, disk, 3, &[0x33; BLOCK], false)
+            .unwrap();
+        cache.set_capacity(0).unwrap();
+        assert_eq!(disk.block(3), [0x33; BLOCK]);
+        assert_eq!(cache.stats().used, 0);
+    }
+
+    #[test]
+    fn test_invalidate_device() {
+        let disk = CountingDisk::new(16);
+        let other = CountingDisk::new(16);
+        let mut cache = BlockCache::new(16);
+
+        cache
+            .write_block(0, disk, 1, &[0xFF; BLOCK], false)
+            .unwrap();
+        let mut block = vec![0u8; BLOCK];
+        cache.read_block(0, disk, 2, &mut block).unwrap();
+        cache
+            .write_block(1, other, 1, &[0xEE; BLOCK], false)
+            .unwrap();
+
+        cache.invalidate_device(0);
+        assert_eq!(cache.stats().used, 1);
+        assert_eq!(cache.stats().dirty, 1);
+
+        // The unwritten change is gone and the block is read again
+        cache.flush_all().unwrap();
+        assert!(disk.writes().is_empty());
+        cache.read_block(0, disk, 1, &mut block).unwrap();
+        assert_eq!(block, [1; BLOCK]);
+        assert_eq!(disk.reads(), [(2, 1), (1, 1)]);
+        assert_eq!(other.writes(), [(1, 1)]);
+    }
 }
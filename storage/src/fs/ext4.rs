@@ -4,8 +4,11 @@
 //! Linux filesystem. It supports extents, journaling, and large files.
 
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 
+use spin::RwLock;
+
 use crate::driver::BlockDevice;
 use crate::vfs::{Filesystem, FsStats};
 use crate::{DirEntry, FileMetadata, FilePermissions, FileType, OpenFlags, StorageError};
@@ -13,6 +16,15 @@ use crate::{DirEntry, FileMetadata, FilePermissions, FileType, OpenFlags, Storag
 /// ext4 superblock magic number.
 pub const EXT4_SUPER_MAGIC: u16 = 0xEF53;
 
+/// Extent tree node magic number.
+pub const EXT4_EXT_MAGIC: u16 = 0xF30A;
+
+/// Inode flag: data is mapped by an extent tree.
+pub const EXT4_EXTENTS_FL: u32 = 0x0008_0000;
+
+/// Inode flag: data is stored inline in the inode.
+pub const EXT4_INLINE_DATA_FL: u32 = 0x1000_0000;
+
 /// ext4 superblock.
 #[derive(Debug, Clone)]
 #[repr(C)]
@@ -226,6 +238,11 @@ impl Ext4Superblock {
         self.s_free_blocks_count_lo as u64 | ((self.s_free_blocks_count_hi as u64) << 32)
     }
 
+    /// Get number of blocks reserved for the superuser.
+    pub fn r_blocks_count(&self) -> u64 {
+        self.s_r_blocks_count_lo as u64 | ((self.s_r_blocks_count_hi as u64) << 32)
+    }
+
     /// Check if extent feature is enabled.
     pub fn has_extents(&self) -> bool {
         self.s_feature_incompat & 0x0040 != 0
@@ -321,6 +338,16 @@ impl Ext4GroupDesc {
     pub fn inode_table(&self, _has_64bit: bool) -> u64 {
         self.bg_inode_table_lo as u64 | ((self.bg_inode_table_hi as u64) << 32)
     }
+
+    /// Get free blocks count.
+    pub fn free_blocks_count(&self) -> u32 {
+        self.bg_free_blocks_count_lo as u32 | ((self.bg_free_blocks_count_hi as u32) << 16)
+    }
+
+    /// Get free inodes count.
+    pub fn free_inodes_count(&self) -> u32 {
+        self.bg_free_inodes_count_lo as u32 | ((self.bg_free_inodes_count_hi as u32) << 16)
+    }
 }
 
 /// ext4 inode.
@@ -409,7 +436,7 @@ impl Ext4Inode {
 
     /// Check if extents are used.
     pub fn uses_extents(&self) -> bool {
-        self.i_flags & 0x80000 != 0
+        self.i_flags & EXT4_EXTENTS_FL != 0
     }
 
     /// Get file type.
@@ -486,7 +513,12 @@ impl Ext4Extent {
 
     /// Get extent length.
     pub fn len(&self) -> u16 {
-        self.ee_len & 0x7FFF
+        // Unwritten extents store their length plus 32768
+        if self.is_unwritten() {
+            self.ee_len - 0x8000
+        } else {
+            self.ee_len
+        }
     }
 
     /// Check if this is an unwritten extent.
@@ -495,6 +527,27 @@ impl Ext4Extent {
     }
 }
 
+/// ext4 extent tree index, the entry type of interior nodes.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Ext4ExtentIdx {
+    /// First file block covered by the child node.
+    pub ei_block: u32,
+    /// Low 32 bits of the child node's block.
+    pub ei_leaf_lo: u32,
+    /// High 16 bits of the child node's block.
+    pub ei_leaf_hi: u16,
+    /// Unused.
+    pub ei_unused: u16,
+}
+
+impl Ext4ExtentIdx {
+    /// Get the child node's block number.
+    pub fn leaf(&self) -> u64 {
+        self.ei_leaf_lo as u64 | ((self.ei_leaf_hi as u64) << 32)
+    }
+}
+
 /// ext4 directory entry.
 #[derive(Debug, Clone)]
 #[repr(C)]
@@ -541,30 +594,400 @@ impl Ext4DirEntry {
     }
 }
 
-/// ext4 filesystem.
+/// Supported incompatible features: filetype, recover, extents, 64bit,
+/// mmp, flex_bg, ea_inode, csum_seed, largedir and inline_data. Anything
+/// else (meta_bg, compression, encryption, ...) changes the on-disk layout
+/// in ways this reader does not handle.
+const EXT4_INCOMPAT_SUPPORTED: u32 = 0xE7C6;
+
+/// Root directory inode.
+const EXT4_ROOT_INO: u32 = 2;
+
+/// Maximum extent tree depth.
+const EXT4_MAX_EXTENT_DEPTH: u16 = 5;
+
+/// Size of an extent header, index or leaf entry.
+const EXT4_EXTENT_ENTRY_SIZE: usize = 12;
+
+/// Direct block pointers in a block-mapped inode.
+const EXT4_NDIR_BLOCKS: usize = 12;
+
+/// Number of open file handles.
+const MAX_OPEN_FILES: usize = 128;
+
+/// A run of logical blocks that are contiguous on disk.
+#[derive(Debug, Clone, Copy)]
+struct BlockRun {
+    /// First physical block, `None` for holes and unwritten extents.
+    start: Option<u64>,
+    /// Number of logical blocks in the run.
+    len: u64,
+}
+
+#[derive(Debug, Clone)]
+struct OpenFile {
+    ino: u32,
+    inode: Ext4Inode,
+}
+
+/// ext4 filesystem (read-only).
 pub struct Ext4Filesystem {
+    /// Backing device.
+    device: &'static dyn BlockDevice,
+    /// Device sector size.
+    sector_size: u64,
     /// Superblock.
     superblock: Ext4Superblock,
+    /// Block group descriptors.
+    groups: Vec<Ext4GroupDesc>,
     /// Block size.
     block_size: u32,
+    /// On-disk inode size.
+    inode_size: u32,
     /// Is read-only.
     read_only: bool,
+    /// Open file handles.
+    open_files: RwLock<Vec<Option<OpenFile>>>,
 }
 
 impl Ext4Filesystem {
-    /// Create a new ext4 filesystem (placeholder).
-    pub fn new() -> Self {
-        Ext4Filesystem {
+    /// Mount the ext4 filesystem on `device`.
+    pub fn mount(device: &'static dyn BlockDevice) -> Result<Self, StorageError> {
+        let sector_size = device.info().block_size as u64;
+        if sector_size == 0 {
+            return Err(StorageError::InvalidFilesystem);
+        }
+
+        let mut fs = Ext4Filesystem {
+            device,
+            sector_size,
             superblock: unsafe { core::mem::zeroed() },
-            block_size: 4096,
-            read_only: false,
+            groups: Vec::new(),
+            block_size: 1024,
+            inode_size: Ext4Inode::BASE_SIZE as u32,
+            read_only: true,
+            open_files: RwLock::new(vec![None; MAX_OPEN_FILES]),
+        };
+
+        let mut raw = [0u8; Ext4Superblock::SIZE];
+        fs.read_bytes(Ext4Superblock::OFFSET as u64, &mut raw)?;
+        let sb: Ext4Superblock = unsafe { core::ptr::read_unaligned(raw.as_ptr() as *const _) };
+        if sb.s_magic != EXT4_SUPER_MAGIC
+            || sb.s_log_block_size > 6
+            || sb.s_blocks_per_group == 0
+            || sb.s_inodes_per_group == 0
+        {
+            return Err(StorageError::InvalidFilesystem);
         }
+        if sb.s_feature_incompat & !EXT4_INCOMPAT_SUPPORTED != 0 {
+            return Err(StorageError::Unsupported);
+        }
+
+        fs.block_size = sb.block_size();
+        if sb.s_rev_level > 0 {
+            fs.inode_size = sb.s_inode_size as u32;
+            if fs.inode_size < Ext4Inode::BASE_SIZE as u32 || fs.inode_size > fs.block_size {
+                return Err(StorageError::InvalidFilesystem);
+            }
+        }
+
+        // The descriptor table follows the block holding the superblock
+        let desc_size = sb.desc_size() as usize;
+        let mut table = vec![0u8; sb.group_count() as usize * desc_size];
+        let table_block = sb.s_first_data_block as u64 + 1;
+        fs.read_bytes(table_block * fs.block_size as u64, &mut table)?;
+        fs.groups = table
+            .chunks_exact(desc_size)
+            .map(|raw| {
+                let mut desc = [0u8; core::mem::size_of::<Ext4GroupDesc>()];
+                let len = raw.len().min(desc.len());
+                desc[..len].copy_from_slice(&raw[..len]);
+                unsafe { core::ptr::read_unaligned(desc.as_ptr() as *const Ext4GroupDesc) }
+            })
+            .collect();
+        fs.superblock = sb;
+
+        if !fs.read_inode(EXT4_ROOT_INO)?.is_dir() {
+            return Err(StorageError::Corrupted);
+        }
+        Ok(fs)
     }
-}
 
-impl Default for Ext4Filesystem {
-    fn default() -> Self {
-        Self::new()
+    /// Read `out.len()` bytes starting at byte `offset` of the device.
+    fn read_bytes(&self, offset: u64, out: &mut [u8]) -> Result<(), StorageError> {
+        let first = offset / self.sector_size;
+        let skip = (offset % self.sector_size) as usize;
+        let sectors = (skip + out.len()).div_ceil(self.sector_size as usize);
+        let mut buf = vec![0u8; sectors * self.sector_size as usize];
+        if self.device.read_blocks(first, &mut buf)? < buf.len() {
+            return Err(StorageError::IoError);
+        }
+        out.copy_from_slice(&buf[skip..skip + out.len()]);
+        Ok(())
+    }
+
+    /// Read one filesystem block.
+    fn read_block(&self, block: u64) -> Result<Vec<u8>, StorageError> {
+        let mut buf = vec![0u8; self.block_size as usize];
+        self.read_bytes(block * self.block_size as u64, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_inode(&self, ino: u32) -> Result<Ext4Inode, StorageError> {
+        if ino == 0 || ino > self.superblock.s_inodes_count {
+            return Err(StorageError::Corrupted);
+        }
+        let index = ino - 1;
+        let group = self
+            .groups
+            .get((index / self.superblock.s_inodes_per_group) as usize)
+            .ok_or(StorageError::Corrupted)?;
+        let slot = (index % self.superblock.s_inodes_per_group) as u64;
+        let offset = group.inode_table(self.superblock.has_64bit()) * self.block_size as u64
+            + slot * self.inode_size as u64;
+
+        // Fields past the on-disk inode size read as zero
+        let mut raw = [0u8; core::mem::size_of::<Ext4Inode>()];
+        let len = (self.inode_size as usize).min(raw.len());
+        self.read_bytes(offset, &mut raw[..len])?;
+        Ok(unsafe { core::ptr::read_unaligned(raw.as_ptr() as *const Ext4Inode) })
+    }
+
+    /// Map a logical block of `inode` to the run of blocks containing it.
+    fn map_block(&self, inode: &Ext4Inode, logical: u32) -> Result<BlockRun, StorageError> {
+        if inode.uses_extents() {
+            self.map_extent(inode, logical)
+        } else {
+            self.map_indirect(inode, logical)
+        }
+    }
+
+    /// Walk the extent tree rooted in `i_block`.
+    fn map_extent(&self, inode: &Ext4Inode, logical: u32) -> Result<BlockRun, StorageError> {
+        let mut node: Vec<u8> = inode.i_block.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let mut parent_depth = None;
+        // First logical block past the current subtree
+        let mut end = u64::MAX;
+        let logical = logical as u64;
+
+        loop {
+            let header: Ext4ExtentHeader =
+                unsafe { core::ptr::read_unaligned(node.as_ptr() as *const _) };
+            let entries = header.eh_entries as usize;
+            if header.eh_magic != EXT4_EXT_MAGIC
+                || header.eh_depth > EXT4_MAX_EXTENT_DEPTH
+                || parent_depth.is_some_and(|depth| header.eh_depth + 1 != depth)
+                || (entries + 1) * EXT4_EXTENT_ENTRY_SIZE > node.len()
+            {
+                return Err(StorageError::Corrupted);
+            }
+            parent_depth = Some(header.eh_depth);
+            let entry = |i: usize| node[(i + 1) * EXT4_EXTENT_ENTRY_SIZE..].as_ptr();
+
+            if header.eh_depth == 0 {
+                for i in 0..entries {
+                    let extent: Ext4Extent =
+                        unsafe { core::ptr::read_unaligned(entry(i) as *const _) };
+                    let first = extent.ee_block as u64;
+                    if logical < first {
+                        end = end.min(first);
+                        break;
+                    }
+                    let len = extent.len() as u64;
+                    if logical < first + len {
+                        let skip = logical - first;
+                        return Ok(BlockRun {
+                            start: (!extent.is_unwritten()).then(|| extent.start() + skip),
+                            len: len - skip,
+                        });
+                    }
+                }
+                return Ok(BlockRun {
+                    start: None,
+                    len: end - logical,
+                });
+            }
+
+            // Descend into the last index starting at or before `logical`
+            let mut child = None;
+            for i in 0..entries {
+                let index: Ext4ExtentIdx =
+                    unsafe { core::ptr::read_unaligned(entry(i) as *const _) };
+                if logical < index.ei_block as u64 {
+                    end = end.min(index.ei_block as u64);
+                    break;
+                }
+                child = Some(index.leaf());
+            }
+            let Some(child) = child else {
+                return Ok(BlockRun {
+                    start: None,
+                    len: end - logical,
+                });
+            };
+            node = self.read_block(child)?;
+        }
+    }
+
+    /// Resolve a block through the direct and indirect pointers of an
+    /// ext2/ext3 style inode.
+    fn map_indirect(&self, inode: &Ext4Inode, logical: u32) -> Result<BlockRun, StorageError> {
+        let per_block = (self.block_size / 4) as u64;
+        let mut logical = logical as u64;
+        let hole = BlockRun {
+            start: None,
+            len: 1,
+        };
+
+        let (mut block, levels) = if logical < EXT4_NDIR_BLOCKS as u64 {
+            (inode.i_block[logical as usize], 0)
+        } else {
+            logical -= EXT4_NDIR_BLOCKS as u64;
+            let mut levels = 1;
+            let mut span = per_block;
+            while logical >= span {
+                logical -= span;
+                levels += 1;
+                span *= per_block;
+                if levels > 3 {
+                    return Ok(hole);
+                }
+            }
+            (inode.i_block[EXT4_NDIR_BLOCKS - 1 + levels], levels)
+        };
+
+        let mut span = per_block.pow(levels as u32);
+        for _ in 0..levels {
+            if block == 0 {
+                return Ok(hole);
+            }
+            let data = self.read_block(block as u64)?;
+            span /= per_block;
+            let pos = (logical / span) as usize * 4;
+            logical %= span;
+            block = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        }
+
+        Ok(BlockRun {
+            start: (block != 0).then_some(block as u64),
+            len: 1,
+        })
+    }
+
+    /// Read file data; holes and unwritten extents read as zeros.
+    fn read_data(
+        &self,
+        inode: &Ext4Inode,
+        offset: u64,
+        out: &mut [u8],
+    ) -> Result<usize, StorageError> {
+        let size = inode.size();
+        if offset >= size {
+            return Ok(0);
+        }
+        if inode.i_flags & EXT4_INLINE_DATA_FL != 0 {
+            return Err(StorageError::Unsupported);
+        }
+
+        let to_read = core::cmp::min(out.len() as u64, size - offset) as usize;
+        let block_size = self.block_size as u64;
+        let mut done = 0;
+        while done < to_read {
+            let pos = offset + done as u64;
+            let logical = u32::try_from(pos / block_size).map_err(|_| StorageError::Corrupted)?;
+            let within = pos % block_size;
+            let run = self.map_block(inode, logical)?;
+
+            let available = run.len.saturating_mul(block_size) - within;
+            let chunk = core::cmp::min(available, (to_read - done) as u64) as usize;
+            let dst = &mut out[done..done + chunk];
+            match run.start {
+                Some(start) => self.read_bytes(start * block_size + within, dst)?,
+                None => dst.fill(0),
+            }
+            done += chunk;
+        }
+        Ok(done)
+    }
+
+    /// Read all entries of a directory, including `.` and `..`.
+    fn read_dir_entries(&self, dir: &Ext4Inode) -> Result<Vec<Ext4DirEntry>, StorageError> {
+        if !dir.is_dir() {
+            return Err(StorageError::NotADirectory);
+        }
+        let mut data = vec![0u8; dir.size() as usize];
+        let len = self.read_data(dir, 0, &mut data)?;
+        data.truncate(len);
+
+        // Entries never cross a block boundary; htree index blocks look
+        // like a single unused entry, so a linear scan sees every name
+        let mut entries = Vec::new();
+        for block in data.chunks(self.block_size as usize) {
+            let mut pos = 0;
+            while pos + 8 <= block.len() {
+                let ino = u32::from_le_bytes([
+                    block[pos],
+                    block[pos + 1],
+                    block[pos + 2],
+                    block[pos + 3],
+                ]);
+                let rec_len = u16::from_le_bytes([block[pos + 4], block[pos + 5]]) as usize;
+                let name_len = block[pos + 6] as usize;
+                if rec_len < 8 || pos + rec_len > block.len() {
+                    return Err(StorageError::Corrupted);
+                }
+                if ino != 0 && name_len > 0 && 8 + name_len <= rec_len {
+                    let mut entry = Ext4DirEntry {
+                        inode: ino,
+                        rec_len: rec_len as u16,
+                        name_len: name_len as u8,
+                        file_type: block[pos + 7],
+                        name: [0; 255],
+                    };
+                    entry.name[..name_len].copy_from_slice(&block[pos + 8..pos + 8 + name_len]);
+                    entries.push(entry);
+                }
+                pos += rec_len;
+            }
+        }
+        Ok(entries)
+    }
+
+    fn resolve_path(&self, path: &str) -> Result<(u32, Ext4Inode), StorageError> {
+        let mut ino = EXT4_ROOT_INO;
+        let mut inode = self.read_inode(ino)?;
+        for name in path.split('/').filter(|c| !c.is_empty() && *c != ".") {
+            if !inode.is_dir() {
+                return Err(StorageError::NotADirectory);
+            }
+            ino = self
+                .read_dir_entries(&inode)?
+                .iter()
+                .find(|e| &e.name[..e.name_len as usize] == name.as_bytes())
+                .map(|e| e.inode)
+                .ok_or(StorageError::FileNotFound)?;
+            inode = self.read_inode(ino)?;
+        }
+        Ok((ino, inode))
+    }
+
+    fn alloc_handle(&self, of: OpenFile) -> Result<u64, StorageError> {
+        let mut files = self.open_files.write();
+        for (idx, slot) in files.iter_mut().enumerate() {
+            if slot.is_none() {
+                *slot = Some(of);
+                return Ok(idx as u64);
+            }
+        }
+        Err(StorageError::TooManyOpenFiles)
+    }
+
+    fn get_handle(&self, handle: u64) -> Result<OpenFile, StorageError> {
+        let files = self.open_files.read();
+        files
+            .get(handle as usize)
+            .and_then(|slot| slot.clone())
+            .ok_or(StorageError::InvalidFd)
     }
 }
 
@@ -574,79 +997,151 @@ impl Filesystem for Ext4Filesystem {
     }
 
     fn statfs(&self) -> Result<FsStats, StorageError> {
+        // The superblock totals are only brought up to date at unmount, the
+        // group descriptors are current
+        let free_blocks = self
+            .groups
+            .iter()
+            .map(|group| group.free_blocks_count() as u64)
+            .sum::<u64>();
+        let free_inodes = self
+            .groups
+            .iter()
+            .map(|group| group.free_inodes_count() as u64)
+            .sum::<u64>();
+
         Ok(FsStats {
             fs_type: EXT4_SUPER_MAGIC as u32,
             block_size: self.block_size,
             total_blocks: self.superblock.blocks_count(),
-            free_blocks: self.superblock.free_blocks_count(),
-            available_blocks: self.superblock.free_blocks_count(),
+            free_blocks,
+            available_blocks: free_blocks.saturating_sub(self.superblock.r_blocks_count()),
             total_inodes: self.superblock.s_inodes_count as u64,
-            free_inodes: self.superblock.s_free_inodes_count as u64,
+            free_inodes,
             fs_id: 0,
             max_name_len: 255,
             fragment_size: self.block_size,
-            flags: crate::MountFlags::empty(),
+            flags: if self.read_only {
+                crate::MountFlags::READ_ONLY
+            } else {
+                crate::MountFlags::empty()
+            },
         })
     }
 
-    fn lookup(&self, _path: &str) -> Result<FileMetadata, StorageError> {
-        Err(StorageError::NotImplemented)
+    fn lookup(&self, path: &str) -> Result<FileMetadata, StorageError> {
+        let (ino, inode) = self.resolve_path(path)?;
+        let mut meta = inode.to_metadata(ino as u64);
+        meta.block_size = self.block_size;
+        Ok(meta)
     }
 
-    fn readdir(&self, _path: &str, _offset: u64) -> Result<Vec<DirEntry>, StorageError> {
-        Err(StorageError::NotImplemented)
+    fn readdir(&self, path: &str, offset: u64) -> Result<Vec<DirEntry>, StorageError> {
+        let (_, dir) = self.resolve_path(path)?;
+        Ok(self
+            .read_dir_entries(&dir)?
+            .iter()
+            .filter(|e| !matches!(&e.name[..e.name_len as usize], b"." | b".."))
+            .skip(offset as usize)
+            .map(Ext4DirEntry::to_dir_entry)
+            .collect())
     }
 
     fn create(&self, _path: &str, _mode: u16) -> Result<u64, StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn mkdir(&self, _path: &str, _mode: u16) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn unlink(&self, _path: &str) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn rmdir(&self, _path: &str) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn rename(&self, _old: &str, _new: &str) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn symlink(&self, _target: &str, _link: &str) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
-    fn readlink(&self, _path: &str) -> Result<String, StorageError> {
-        Err(StorageError::NotImplemented)
+    fn readlink(&self, path: &str) -> Result<String, StorageError> {
+        let (_, inode) = self.resolve_path(path)?;
+        if !inode.is_symlink() {
+            return Err(StorageError::InvalidArgument);
+        }
+
+        // Short targets are stored in place of the block map
+        let size = inode.size() as usize;
+        let target = if size < 60 && !inode.uses_extents() && inode.i_blocks_lo == 0 {
+            inode
+                .i_block
+                .iter()
+                .flat_map(|w| w.to_le_bytes())
+                .take(size)
+                .collect()
+        } else {
+            let mut data = vec![0u8; size];
+            let len = self.read_data(&inode, 0, &mut data)?;
+            data.truncate(len);
+            data
+        };
+        String::from_utf8(target).map_err(|_| StorageError::Corrupted)
     }
 
     fn link(&self, _old: &str, _new: &str) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn setattr(&self, _path: &str, _attr: &FileMetadata) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
-    fn open(&self, _path: &str, _flags: OpenFlags) -> Result<u64, StorageError> {
-        Err(StorageError::NotImplemented)
+    fn open(&self, path: &str, flags: OpenFlags) -> Result<u64, StorageError> {
+        if flags.intersects(OpenFlags::WRITE | OpenFlags::TRUNCATE | OpenFlags::APPEND) {
+            return Err(StorageError::ReadOnly);
+        }
+        let (ino, inode) = match self.resolve_path(path) {
+            Err(StorageError::FileNotFound) if flags.contains(OpenFlags::CREATE) => {
+                return Err(StorageError::ReadOnly);
+            }
+            result => result?,
+        };
+
+        if inode.is_dir() && !flags.contains(OpenFlags::DIRECTORY) {
+            return Err(StorageError::NotAFile);
+        }
+        if !inode.is_dir() && flags.contains(OpenFlags::DIRECTORY) {
+            return Err(StorageError::NotADirectory);
+        }
+
+        self.alloc_handle(OpenFile { ino, inode })
     }
 
-    fn close(&self, _handle: u64) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+    fn close(&self, handle: u64) -> Result<(), StorageError> {
+        let mut files = self.open_files.write();
+        let slot = files
+            .get_mut(handle as usize)
+            .ok_or(StorageError::InvalidFd)?;
+        slot.take().map(|_| ()).ok_or(StorageError::InvalidFd)
     }
 
-    fn read(&self, _handle: u64, _offset: u64, _buffer: &mut [u8]) -> Result<usize, StorageError> {
-        Err(StorageError::NotImplemented)
+    fn read(&self, handle: u64, offset: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+        let file = self.get_handle(handle)?;
+        if file.inode.is_dir() {
+            return Err(StorageError::NotAFile);
+        }
+        self.read_data(&file.inode, offset, buffer)
     }
 
     fn write(&self, _handle: u64, _offset: u64, _data: &[u8]) -> Result<usize, StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn flush(&self, _handle: u64) -> Result<(), StorageError> {
@@ -658,14 +1153,212 @@ impl Filesystem for Ext4Filesystem {
     }
 
     fn truncate(&self, _path: &str, _size: u64) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn fallocate(&self, _handle: u64, _offset: u64, _len: u64) -> Result<(), StorageError> {
-        Err(StorageError::NotImplemented)
+        Err(StorageError::ReadOnly)
     }
 
     fn sync(&self) -> Result<(), StorageError> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlockDeviceInfo;
+    use alloc::boxed::Box;
+
+    const SECTOR: usize = 512;
+    const BLOCK: usize = 4096;
+    const INODE_SIZE: usize = 256;
+    const INODE_TABLE: usize = 4;
+    const ROOT_DIR: usize = 5;
+    const EXTENT_LEAF: usize = 6;
+    const FILE_INO: u32 = 12;
+    const FILE_BLOCKS: usize = 256;
+    /// The file's extents as (logical, physical, length); blocks 128 and
+    /// 129 are a hole.
+    const EXTENTS: [(usize, usize, usize); 3] = [(0, 16, 128), (130, 200, 100), (230, 400, 26)];
+
+    struct MemDisk(Vec<u8>);
+
+    impl BlockDevice for MemDisk {
+        fn info(&self) -> BlockDeviceInfo {
+            BlockDeviceInfo {
+                name: [0; 32],
+                name_len: 0,
+                block_size: SECTOR as u32,
+                total_blocks: (self.0.len() / SECTOR) as u64,
+                read_only: true,
+                supports_trim: false,
+                optimal_io_size: 1,
+                physical_block_size: SECTOR as u32,
+            }
+        }
+
+        fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+            let start = start_block as usize * SECTOR;
+            buffer.copy_from_slice(&self.0[start..start + buffer.len()]);
+            Ok(buffer.len())
+        }
+
+        fn write_blocks(&self, _start_block: u64, _data: &[u8]) -> Result<usize, StorageError> {
+            Err(StorageError::ReadOnly)
+        }
+
+        fn flush(&self) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn discard(&self, _start_block: u64, _num_blocks: u64) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+    }
+
+    fn put16(image: &mut [u8], at: usize, v: u16) {
+        image[at..at + 2].copy_from_slice(&v.to_le_bytes());
+    }
+
+    fn put32(image: &mut [u8], at: usize, v: u32) {
+        image[at..at + 4].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// Write an extent node header at `at`.
+    fn put_header(image: &mut [u8], at: usize, entries: u16, max: u16, depth: u16) {
+        put16(image, at, EXT4_EXT_MAGIC);
+        put16(image, at + 2, entries);
+        put16(image, at + 4, max);
+        put16(image, at + 6, depth);
+    }
+
+    /// Write a leaf extent at `at`.
+    fn put_extent(image: &mut [u8], at: usize, logical: usize, physical: usize, len: usize) {
+        put32(image, at, logical as u32);
+        put16(image, at + 4, len as u16);
+        put32(image, at + 8, physical as u32);
+    }
+
+    /// Byte `pos` of the test file.
+    fn file_byte(pos: usize) -> u8 {
+        let logical = pos / BLOCK;
+        if EXTENTS
+            .iter()
+            .any(|&(first, _, len)| (first..first + len).contains(&logical))
+        {
+            (pos % 251) as u8 ^ logical as u8
+        } else {
+            0
+        }
+    }
+
+    /// Build a single-group ext4 image with a 1 MiB `/big.bin` whose
+    /// extent tree has an index node in the inode and one leaf block.
+    fn image() -> &'static MemDisk {
+        let mut image = vec![0u8; 512 * BLOCK];
+
+        let sb = Ext4Superblock::OFFSET;
+        put32(&mut image, sb, 16);
+        put32(&mut image, sb + 4, 512);
+        put32(&mut image, sb + 24, 2);
+        put32(&mut image, sb + 32, 32768);
+        put32(&mut image, sb + 40, 16);
+        put16(&mut image, sb + 56, EXT4_SUPER_MAGIC);
+        put32(&mut image, sb + 76, 1);
+        put16(&mut image, sb + 88, INODE_SIZE as u16);
+        // filetype | extents
+        put32(&mut image, sb + 96, 0x0002 | 0x0040);
+
+        put32(&mut image, BLOCK + 8, INODE_TABLE as u32);
+
+        let inode = |ino: u32| INODE_TABLE * BLOCK + (ino as usize - 1) * INODE_SIZE;
+        let root = inode(EXT4_ROOT_INO);
+        put16(&mut image, root, 0x41ED);
+        put32(&mut image, root + 4, BLOCK as u32);
+        put16(&mut image, root + 26, 2);
+        put32(&mut image, root + 32, EXT4_EXTENTS_FL);
+        put_header(&mut image, root + 40, 1, 4, 0);
+        put_extent(&mut image, root + 52, 0, ROOT_DIR, 1);
+
+        let file = inode(FILE_INO);
+        put16(&mut image, file, 0x81A4);
+        put32(&mut image, file + 4, (FILE_BLOCKS * BLOCK) as u32);
+        put16(&mut image, file + 26, 1);
+        put32(&mut image, file + 32, EXT4_EXTENTS_FL);
+        put_header(&mut image, file + 40, 1, 4, 1);
+        put32(&mut image, file + 52, 0);
+        put32(&mut image, file + 56, EXTENT_LEAF as u32);
+
+        let leaf = EXTENT_LEAF * BLOCK;
+        put_header(&mut image, leaf, EXTENTS.len() as u16, 340, 0);
+        for (i, &(logical, physical, len)) in EXTENTS.iter().enumerate() {
+            put_extent(&mut image, leaf + 12 * (i + 1), logical, physical, len);
+            for block in 0..len {
+                let dst = (physical + block) * BLOCK;
+                let src = (logical + block) * BLOCK;
+                for j in 0..BLOCK {
+                    image[dst + j] = file_byte(src + j);
+                }
+            }
+        }
+
+        let dir = ROOT_DIR * BLOCK;
+        for (at, ino, rec_len, name, file_type) in [
+            (0, EXT4_ROOT_INO, 12, &b"."[..], 2),
+            (12, EXT4_ROOT_INO, 12, &b".."[..], 2),
+            (24, FILE_INO, BLOCK - 24, &b"big.bin"[..], 1),
+        ] {
+            put32(&mut image, dir + at, ino);
+            put16(&mut image, dir + at + 4, rec_len as u16);
+            image[dir + at + 6] = name.len() as u8;
+            image[dir + at + 7] = file_type;
+            image[dir + at + 8..dir + at + 8 + name.len()].copy_from_slice(name);
+        }
+
+        Box::leak(Box::new(MemDisk(image)))
+    }
+
+    #[test]
+    fn test_read_through_depth_one_extent_tree() {
+        let fs = Ext4Filesystem::mount(image()).unwrap();
+        let names: Vec<_> = fs
+            .readdir("/", 0)
+            .unwrap()
+            .iter()
+            .map(|entry| String::from(entry.name_str()))
+            .collect();
+        assert!(names.iter().any(|name| name == "big.bin"));
+
+        let meta = fs.lookup("/big.bin").unwrap();
+        assert_eq!(meta.size, (FILE_BLOCKS * BLOCK) as u64);
+        assert_eq!(meta.file_type, FileType::Regular);
+
+        let handle = fs.open("/big.bin", OpenFlags::READ).unwrap();
+        let mut data = vec![0xEE; FILE_BLOCKS * BLOCK + 100];
+        assert_eq!(fs.read(handle, 0, &mut data).unwrap(), FILE_BLOCKS * BLOCK);
+        for (pos, &byte) in data[..FILE_BLOCKS * BLOCK].iter().enumerate() {
+            assert_eq!(byte, file_byte(pos), "byte {}", pos);
+        }
+
+        // A read straddling the hole and the jump to the next extent
+        let offset = 128 * BLOCK - 10;
+        let mut part = vec![0xEE; 2 * BLOCK + 20];
+        assert_eq!(
+            fs.read(handle, offset as u64, &mut part).unwrap(),
+            part.len()
+        );
+        assert_eq!(part[..], data[offset..offset + part.len()]);
+
+        let mut tail = [0u8; 16];
+        let end = (FILE_BLOCKS * BLOCK) as u64;
+        assert_eq!(fs.read(handle, end - 4, &mut tail).unwrap(), 4);
+        assert_eq!(fs.read(handle, end, &mut tail).unwrap(), 0);
+        fs.close(handle).unwrap();
+    }
+}
@@ -82,6 +82,30 @@ pub mod fat_entry {
     pub const MASK: u32 = 0x0FFFFFFF;
 }
 
+/// FSInfo sector layout.
+pub mod fsinfo {
+    pub const LEAD_SIG: u32 = 0x41615252;
+    pub const STRUC_SIG: u32 = 0x61417272;
+    pub const TRAIL_SIG: u32 = 0xAA550000;
+    pub const LEAD_SIG_OFFSET: usize = 0;
+    pub const STRUC_SIG_OFFSET: usize = 484;
+    pub const FREE_COUNT_OFFSET: usize = 488;
+    pub const TRAIL_SIG_OFFSET: usize = 508;
+    pub const SIZE: usize = 512;
+    /// Free count value meaning "unknown".
+    pub const UNKNOWN: u32 = 0xFFFFFFFF;
+
+    /// Read the little-endian field at `offset`.
+    pub fn field(sector: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            sector[offset],
+            sector[offset + 1],
+            sector[offset + 2],
+            sector[offset + 3],
+        ])
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C, packed)]
 pub struct Fat32DirEntry {
@@ -107,6 +131,8 @@ impl Fat32DirEntry {
     pub const ATTR_LONG_NAME: u8 = 0x0F;
     pub const DELETED: u8 = 0xE5;
     pub const LAST: u8 = 0x00;
+    /// Set in the sequence number of the final (first stored) long-name slot.
+    pub const LAST_LONG_ENTRY: u8 = 0x40;
 
     pub fn is_free(&self) -> bool {
         self.name[0] == Self::DELETED || self.name[0] == Self::LAST
@@ -132,6 +158,14 @@ impl Fat32DirEntry {
         ((self.fst_clus_hi as u32) << 16) | self.fst_clus_lo as u32
     }
 
+    /// Checksum of the 8.3 name, stored in every long-name slot that
+    /// belongs to this entry.
+    pub fn checksum(&self) -> u8 {
+        self.name
+            .iter()
+            .fold(0u8, |sum, &b| sum.rotate_right(1).wrapping_add(b))
+    }
+
     pub fn short_name(&self) -> [u8; 13] {
         let mut result = [0u8; 13];
         let mut pos = 0usize;
@@ -214,6 +248,138 @@ impl Fat32DirEntry {
     }
 }
 
+/// Slots needed for the longest VFAT name (255 UCS-2 code units).
+const LFN_MAX_SLOTS: usize = 20;
+
+/// Name characters held by one long-name slot.
+const LFN_UNITS_PER_SLOT: usize = 13;
+
+/// Byte offsets of the name characters within a long-name slot.
+const LFN_UNIT_OFFSETS: [usize; LFN_UNITS_PER_SLOT] =
+    [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+
+/// Collects the VFAT long-name slots that precede a short entry.
+///
+/// Slots are stored in reverse: the first one in the directory carries
+/// `LAST_LONG_ENTRY` and the highest sequence number, and the sequence
+/// counts down to 1 in the slot just before the short entry.
+#[derive(Default)]
+struct LongNameBuilder {
+    units: Vec<u16>,
+    checksum: u8,
+    /// Sequence number the next slot must carry, 0 once all are in.
+    next: u8,
+    /// Chain offset of the first slot.
+    first_slot: u32,
+    active: bool,
+}
+
+impl LongNameBuilder {
+    fn push(&mut self, slot: &[u8], offset: u32) {
+        let seq = slot[0] & 0x1F;
+        let checksum = slot[13];
+        let count = seq as usize;
+
+        if slot[0] & Fat32DirEntry::LAST_LONG_ENTRY != 0 {
+            if count == 0 || count > LFN_MAX_SLOTS {
+                self.reset();
+                return;
+            }
+            self.units = vec![0xFFFF; count * LFN_UNITS_PER_SLOT];
+            self.checksum = checksum;
+            self.first_slot = offset;
+            self.active = true;
+        } else if !self.active || seq == 0 || seq != self.next || checksum != self.checksum {
+            // Orphaned or out-of-order slot
+            self.reset();
+            return;
+        }
+
+        let start = (count - 1) * LFN_UNITS_PER_SLOT;
+        for (i, &pos) in LFN_UNIT_OFFSETS.iter().enumerate() {
+            self.units[start + i] = u16::from_le_bytes([slot[pos], slot[pos + 1]]);
+        }
+        self.next = seq - 1;
+    }
+
+    /// Take the long name belonging to `short` along with the offset of
+    /// its first slot. Returns `None` if the slots are incomplete or were
+    /// left behind by an entry that no longer exists.
+    fn finish(&mut self, short: &Fat32DirEntry) -> Option<(String, u32)> {
+        let complete = self.active && self.next == 0 && self.checksum == short.checksum();
+        let units = core::mem::take(&mut self.units);
+        let first_slot = self.first_slot;
+        self.reset();
+        if !complete {
+            return None;
+        }
+
+        // The name ends with a NUL unless it fills its last slot exactly;
+        // the remainder is padded with 0xFFFF
+        let len = units
+            .iter()
+            .position(|&u| u == 0x0000 || u == 0xFFFF)
+            .unwrap_or(units.len());
+        if len == 0 {
+            return None;
+        }
+        let name = char::decode_utf16(units[..len].iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        Some((name, first_slot))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A short directory entry and where it lives, with its long name if it
+/// has one.
+#[derive(Debug, Clone)]
+struct DirRecord {
+    entry: Fat32DirEntry,
+    long_name: Option<String>,
+    /// Chain offset of the short entry.
+    offset: u32,
+    /// Chain offset of the first long-name slot, `offset` without one.
+    first_slot: u32,
+}
+
+impl DirRecord {
+    /// Whether `name` is this entry's long or short name, ignoring case.
+    fn matches(&self, name: &str) -> bool {
+        fn upper(s: &str) -> impl Iterator<Item = char> + '_ {
+            s.chars().flat_map(char::to_uppercase)
+        }
+        if self
+            .long_name
+            .as_deref()
+            .is_some_and(|long| upper(long).eq(upper(name)))
+        {
+            return true;
+        }
+        let short = self.entry.short_name();
+        let len = short.iter().position(|&b| b == 0).unwrap_or(short.len());
+        core::str::from_utf8(&short[..len]).is_ok_and(|s| s.eq_ignore_ascii_case(name))
+    }
+
+    fn to_dir_entry(&self) -> DirEntry {
+        let mut dir_entry = self.entry.to_dir_entry();
+        if let Some(long) = &self.long_name {
+            // Up to 255 UCS-2 units can need more UTF-8 than the buffer holds
+            let mut len = long.len().min(dir_entry.name.len());
+            while !long.is_char_boundary(len) {
+                len -= 1;
+            }
+            dir_entry.name[..len].copy_from_slice(&long.as_bytes()[..len]);
+            dir_entry.name[len..].fill(0);
+            dir_entry.name_len = len;
+        }
+        dir_entry
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct OpenFile {
     first_cluster: u32,
@@ -247,13 +413,19 @@ impl Fat32Filesystem {
             return Err(StorageError::InvalidFilesystem);
         }
 
-        Ok(Self {
+        let fs = Self {
             device,
             bpb,
             read_only: false,
             free_clusters: AtomicU32::new(0),
             open_files: RwLock::new([None; 128]),
-        })
+        };
+        // A data region that doesn't fit is left for `check` to report
+        if bpb.total_sectors() > bpb.first_data_sector() {
+            let free = fs.count_free_clusters()?;
+            fs.free_clusters.store(free, Ordering::Relaxed);
+        }
+        Ok(fs)
     }
 
     fn bps(&self) -> usize {
@@ -311,26 +483,117 @@ impl Fat32Filesystem {
         ]) & fat_entry::MASK)
     }
 
-    fn read_dir_entries(&self, start_cluster: u32) -> Result<Vec<Fat32DirEntry>, StorageError> {
-        let mut entries = Vec::new();
+    /// Read the whole FAT (one entry per cluster, including the two
+    /// reserved entries).
+    fn read_fat(&self) -> Result<Vec<u32>, StorageError> {
+        let entries = self.bpb.total_clusters() as usize + 2;
+        let bps = self.bps();
+        if entries * 4 > self.bpb.fat_size() as usize * bps {
+            return Err(StorageError::Corrupted);
+        }
+
+        let mut fat = Vec::with_capacity(entries);
+        let mut sector = vec![0u8; bps];
+        let mut lba = self.bpb.reserved_sectors as u32;
+        while fat.len() < entries {
+            self.read_sector(lba, &mut sector)?;
+            for chunk in sector.chunks_exact(4) {
+                if fat.len() == entries {
+                    break;
+                }
+                fat.push(
+                    u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) & fat_entry::MASK,
+                );
+            }
+            lba += 1;
+        }
+        Ok(fat)
+    }
+
+    /// Sector holding the FSInfo structure, if the volume has one.
+    fn fsinfo_sector(&self) -> Option<u32> {
+        let sector = self.bpb.fs_info;
+        if sector == 0 || sector >= self.bpb.reserved_sectors || self.bps() < fsinfo::SIZE {
+            return None;
+        }
+        Some(sector as u32)
+    }
+
+    /// Read the FSInfo sector, `None` if its signatures don't check out.
+    fn read_fsinfo(&self) -> Result<Option<(u32, Vec<u8>)>, StorageError> {
+        let Some(sector) = self.fsinfo_sector() else {
+            return Ok(None);
+        };
+        let mut buf = vec![0u8; self.bps()];
+        self.read_sector(sector, &mut buf)?;
+
+        if fsinfo::field(&buf, fsinfo::LEAD_SIG_OFFSET) != fsinfo::LEAD_SIG
+            || fsinfo::field(&buf, fsinfo::STRUC_SIG_OFFSET) != fsinfo::STRUC_SIG
+            || fsinfo::field(&buf, fsinfo::TRAIL_SIG_OFFSET) != fsinfo::TRAIL_SIG
+        {
+            return Ok(None);
+        }
+        Ok(Some((sector, buf)))
+    }
+
+    /// Count free clusters. The FSInfo count is used when it is known and
+    /// in range, otherwise the FAT is scanned.
+    fn count_free_clusters(&self) -> Result<u32, StorageError> {
+        if let Some((_, buf)) = self.read_fsinfo()? {
+            let free = fsinfo::field(&buf, fsinfo::FREE_COUNT_OFFSET);
+            if free != fsinfo::UNKNOWN && free <= self.bpb.total_clusters() {
+                return Ok(free);
+            }
+        }
+
+        let fat = self.read_fat()?;
+        Ok(fat[2..].iter().filter(|&&entry| entry == 0).count() as u32)
+    }
+
+    /// Read a directory's live entries, pairing each with its long name.
+    fn read_dir_entries(&self, start_cluster: u32) -> Result<Vec<DirRecord>, StorageError> {
+        let mut records = Vec::new();
+        let mut long_name = LongNameBuilder::default();
         let mut cluster = start_cluster;
-        let mut cluster_buf = vec![0u8; self.cluster_size()];
+        let cluster_size = self.cluster_size();
+        let mut cluster_buf = vec![0u8; cluster_size];
+        let mut chain_offset: u32 = 0;
 
         for _ in 0..4096 {
             self.read_cluster(cluster, &mut cluster_buf)?;
-            for chunk in cluster_buf.chunks_exact(Fat32DirEntry::SIZE) {
-                let entry: Fat32DirEntry = unsafe {
-                    core::ptr::read_unaligned(chunk.as_ptr() as *const Fat32DirEntry)
-                };
+            for (slot, chunk) in cluster_buf.chunks_exact(Fat32DirEntry::SIZE).enumerate() {
+                let entry: Fat32DirEntry =
+                    unsafe { core::ptr::read_unaligned(chunk.as_ptr() as *const Fat32DirEntry) };
+                let offset = chain_offset + (slot * Fat32DirEntry::SIZE) as u32;
                 if entry.is_last() {
-                    return Ok(entries);
+                    return Ok(records);
                 }
-                if entry.is_free() || entry.is_long_name() || entry.is_volume_label() {
+                if entry.is_free() {
+                    long_name.reset();
                     continue;
                 }
-                entries.push(entry);
+                // Long-name slots have the volume label bit set too
+                if entry.is_long_name() {
+                    long_name.push(chunk, offset);
+                    continue;
+                }
+                if entry.is_volume_label() {
+                    long_name.reset();
+                    continue;
+                }
+                let (long_name, first_slot) = match long_name.finish(&entry) {
+                    Some((name, first_slot)) => (Some(name), first_slot),
+                    None => (None, offset),
+                };
+                records.push(DirRecord {
+                    entry,
+                    long_name,
+                    offset,
+                    first_slot,
+                });
             }
 
+            chain_offset += cluster_size as u32;
             let next = self.read_fat_entry(cluster)?;
             if next >= fat_entry::EOC_MIN || next == fat_entry::BAD {
                 break;
@@ -341,7 +604,7 @@ impl Fat32Filesystem {
             cluster = next;
         }
 
-        Ok(entries)
+        Ok(records)
     }
 
     fn resolve_path(&self, path: &str) -> Result<Fat32DirEntry, StorageError> {
@@ -372,21 +635,7 @@ impl Fat32Filesystem {
             .collect::<Vec<_>>();
 
         for (idx, component) in components.iter().enumerate() {
-            let mut found = None;
-            for entry in self.read_dir_entries(current_cluster)? {
-                let short = entry.short_name();
-                let mut len = 0usize;
-                while len < short.len() && short[len] != 0 {
-                    len += 1;
-                }
-                let entry_name = core::str::from_utf8(&short[..len]).unwrap_or("");
-                if entry_name.eq_ignore_ascii_case(component) {
-                    found = Some(entry);
-                    break;
-                }
-            }
-
-            let entry = found.ok_or(StorageError::FileNotFound)?;
+            let entry = self.find_entry_in_dir(current_cluster, component)?.entry;
             current_entry = Some(entry);
 
             if entry.is_dir() {
@@ -518,6 +767,17 @@ impl Fat32Filesystem {
          Ok(())
      }
 
+     /// Store the current free cluster count in the FSInfo sector.
+     fn write_fsinfo(&self) -> Result<(), StorageError> {
+         let Some((sector, mut buf)) = self.read_fsinfo()? else {
+             return Ok(());
+         };
+         let free = self.free_clusters.load(Ordering::Relaxed);
+         let at = fsinfo::FREE_COUNT_OFFSET;
+         buf[at..at + 4].copy_from_slice(&free.to_le_bytes());
+         self.write_sector(sector, &buf)
+     }
+
      fn alloc_cluster(&self) -> Result<u32, StorageError> {
          let total = self.bpb.total_clusters();
          for cluster in 2..total + 2 {
@@ -614,61 +874,13 @@ impl Fat32Filesystem {
          }
      }
 
-     /// Compare two 11-byte FAT short names (case-insensitive).
-     fn names_match(a: &[u8; 11], b: &[u8; 11]) -> bool {
-         for i in 0..11 {
-             if a[i].to_ascii_uppercase() != b[i].to_ascii_uppercase() {
-                 return false;
-             }
-         }
-         true
-     }
-
-     /// Find a directory entry by short name within a directory cluster chain.
-     /// Returns (entry, byte_offset_within_chain).
-     fn find_entry_in_dir(
-         &self,
-         dir_cluster: u32,
-         target: &[u8; 11],
-     ) -> Result<(Fat32DirEntry, u32), StorageError> {
-         let mut cluster = dir_cluster;
-         let cluster_size = self.cluster_size();
-         let entries_per_cluster = cluster_size / Fat32DirEntry::SIZE;
-         let mut chain_offset: u32 = 0;
-
-         for _ in 0..4096 {
-             let mut cluster_buf = vec![0u8; cluster_size];
-             self.read_cluster(cluster, &mut cluster_buf)?;
-
-             for i in 0..entries_per_cluster {
-                 let offset = i * Fat32DirEntry::SIZE;
-                 // SAFETY: Reading a packed 32-byte struct from an aligned buffer slice.
-                 let entry: Fat32DirEntry = unsafe {
-                     core::ptr::read_unaligned(cluster_buf[offset..].as_ptr() as *const _)
-                 };
-                 if entry.is_last() {
-                     return Err(StorageError::FileNotFound);
-                 }
-                 if entry.is_free() || entry.is_long_name() || entry.is_volume_label() {
-                     continue;
-                 }
-                 if Self::names_match(&entry.name, target) {
-                     return Ok((entry, chain_offset + offset as u32));
-                 }
-             }
-
-             chain_offset += cluster_size as u32;
-             let next = self.read_fat_entry(cluster)?;
-             if next >= fat_entry::EOC_MIN || next == fat_entry::BAD {
-                 break;
-             }
-             if next < 2 {
-                 return Err(StorageError::Corrupted);
-             }
-             cluster = next;
-         }
-
-         Err(StorageError::FileNotFound)
+     /// Find a directory entry by long or short name (case-insensitive)
+     /// within a directory cluster chain.
+     fn find_entry_in_dir(&self, dir_cluster: u32, name: &str) -> Result<DirRecord, StorageError> {
+         self.read_dir_entries(dir_cluster)?
+             .into_iter()
+             .find(|record| record.matches(name))
+             .ok_or(StorageError::FileNotFound)
      }
 
      /// Find a free 32-byte slot in a directory's cluster chain.
@@ -823,6 +1035,18 @@ impl Fat32Filesystem {
          self.write_sector(sector, &sector_buf)
      }
 
+     /// Mark a short entry and the long-name slots in front of it deleted.
+     fn mark_record_deleted(
+         &self,
+         dir_cluster: u32,
+         record: &DirRecord,
+     ) -> Result<(), StorageError> {
+         for offset in (record.first_slot..=record.offset).step_by(Fat32DirEntry::SIZE) {
+             self.mark_dir_entry_deleted(dir_cluster, offset)?;
+         }
+         Ok(())
+     }
+
      /// Count existing clusters in a chain and return (count, last_cluster).
      fn count_chain(&self, first_cluster: u32) -> Result<(u32, u32), StorageError> {
          if first_cluster < 2 {
@@ -967,8 +1191,8 @@ impl Fat32Filesystem {
 
          let mut entries = self
              .read_dir_entries(cluster)?
-             .into_iter()
-             .map(|e| e.to_dir_entry())
+             .iter()
+             .map(DirRecord::to_dir_entry)
              .collect::<Vec<_>>();
 
          if offset as usize >= entries.len() {
@@ -1100,10 +1324,10 @@ impl Fat32Filesystem {
          }
 
          let (parent_path, name) = Self::split_parent_name(path)?;
-         let short_name = Self::make_short_name(name)?;
          let parent_cluster = self.parent_cluster(parent_path)?;
 
-         let (entry, offset) = self.find_entry_in_dir(parent_cluster, &short_name)?;
+         let record = self.find_entry_in_dir(parent_cluster, name)?;
+         let entry = record.entry;
          if entry.is_dir() {
              return Err(StorageError::NotAFile);
          }
@@ -1112,7 +1336,7 @@ impl Fat32Filesystem {
          self.free_chain(entry.first_cluster())?;
 
          // Mark directory entry as deleted.
-         self.mark_dir_entry_deleted(parent_cluster, offset)?;
+         self.mark_record_deleted(parent_cluster, &record)?;
 
          Ok(())
      }
@@ -1123,10 +1347,10 @@ impl Fat32Filesystem {
          }
 
          let (parent_path, name) = Self::split_parent_name(path)?;
-         let short_name = Self::make_short_name(name)?;
          let parent_cluster = self.parent_cluster(parent_path)?;
 
-         let (entry, offset) = self.find_entry_in_dir(parent_cluster, &short_name)?;
+         let record = self.find_entry_in_dir(parent_cluster, name)?;
+         let entry = record.entry;
          if !entry.is_dir() {
              return Err(StorageError::NotADirectory);
          }
@@ -1141,7 +1365,7 @@ impl Fat32Filesystem {
          self.free_chain(entry.first_cluster())?;
 
          // Mark the parent's dir entry as deleted.
-         self.mark_dir_entry_deleted(parent_cluster, offset)?;
+         self.mark_record_deleted(parent_cluster, &record)?;
 
          Ok(())
      }
@@ -1174,8 +1398,7 @@ impl Fat32Filesystem {
                  let (parent_path, name) =
                      Self::split_parent_name(path).unwrap_or(("/", ""));
                  let pclus = self.parent_cluster(parent_path).unwrap_or(self.bpb.root_cluster);
-                 let short = Self::make_short_name(name).unwrap_or([b' '; 11]);
-                 let (_found, off) = self.find_entry_in_dir(pclus, &short).unwrap_or((e, 0));
+                 let off = self.find_entry_in_dir(pclus, name).map_or(0, |r| r.offset);
                  (e, pclus, off)
              }
              Err(StorageError::FileNotFound) if flags.contains(OpenFlags::CREATE) => {
@@ -1187,8 +1410,7 @@ impl Fat32Filesystem {
                  let (parent_path, name) =
                      Self::split_parent_name(path).unwrap_or(("/", ""));
                  let pclus = self.parent_cluster(parent_path).unwrap_or(self.bpb.root_cluster);
-                 let short = Self::make_short_name(name).unwrap_or([b' '; 11]);
-                 let (_found, off) = self.find_entry_in_dir(pclus, &short).unwrap_or((e, 0));
+                 let off = self.find_entry_in_dir(pclus, name).map_or(0, |r| r.offset);
                  (e, pclus, off)
              }
              Err(e) => return Err(e),
@@ -1354,10 +1576,9 @@ impl Fat32Filesystem {
          }
 
          let (parent_path, name) = Self::split_parent_name(path)?;
-         let short_name = Self::make_short_name(name)?;
          let parent_cluster = self.parent_cluster(parent_path)?;
 
-         let (entry, offset) = self.find_entry_in_dir(parent_cluster, &short_name)?;
+         let DirRecord { entry, offset, .. } = self.find_entry_in_dir(parent_cluster, name)?;
          if entry.is_dir() {
              return Err(StorageError::NotAFile);
          }
@@ -1401,6 +1622,857 @@ impl Fat32Filesystem {
      }
 
      fn sync(&self) -> Result<(), StorageError> {
+         if !self.read_only {
+             self.write_fsinfo()?;
+         }
          self.device.flush()
      }
  }
+
+// ── Consistency check ───────────────────────────────────────────────
+
+/// A problem found by [`check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsckIssue {
+    /// `cluster` is already owned by another chain (or earlier in this one).
+    CrossLinked { path: String, cluster: u32 },
+    /// The chain links from `cluster` to a free, reserved or out-of-range `next`.
+    BadChainLink {
+        path: String,
+        cluster: u32,
+        next: u32,
+    },
+    /// An allocated chain that no directory entry references.
+    LostChain { first_cluster: u32, length: u32 },
+    /// A directory entry that cannot be used as-is.
+    InvalidEntry { path: String, reason: &'static str },
+}
+
+impl core::fmt::Display for FsckIssue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FsckIssue::CrossLinked { path, cluster } => {
+                write!(f, "{}: cross-linked at cluster {}", path, cluster)
+            }
+            FsckIssue::BadChainLink {
+                path,
+                cluster,
+                next,
+            } => write!(
+                f,
+                "{}: cluster {} links to invalid cluster {:#x}",
+                path, cluster, next
+            ),
+            FsckIssue::LostChain {
+                first_cluster,
+                length,
+            } => write!(
+                f,
+                "lost chain of {} cluster(s) starting at {}",
+                length, first_cluster
+            ),
+            FsckIssue::InvalidEntry { path, reason } => write!(f, "{}: {}", path, reason),
+        }
+    }
+}
+
+/// Result of a [`check`] run.
+#[derive(Debug, Clone, Default)]
+pub struct FsckReport {
+    /// Problems found, in discovery order.
+    pub issues: Vec<FsckIssue>,
+    /// Data clusters on the volume.
+    pub total_clusters: u32,
+    /// Clusters reachable from the directory tree.
+    pub used_clusters: u32,
+    /// Lost clusters returned to the free pool (repair only).
+    pub reclaimed_clusters: u32,
+    /// Whether fixes were written to the device.
+    pub repaired: bool,
+}
+
+impl FsckReport {
+    /// True if no problems were found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check the FAT32 volume on `device` for consistency.
+///
+/// Walks every cluster chain reachable from the root directory looking for
+/// cross-linked clusters, broken links and invalid directory entries, then
+/// reports allocated chains that nothing references.  With `repair` set,
+/// lost clusters are freed, cross-linked and broken chains are truncated
+/// and invalid entries are cleared or deleted.  The volume must not be
+/// mounted while repairing.
+pub fn check(device: &'static dyn BlockDevice, repair: bool) -> Result<FsckReport, StorageError> {
+    let fs = Fat32Filesystem::mount(device)?;
+    if fs.bpb.total_sectors() <= fs.bpb.first_data_sector() {
+        return Err(StorageError::Corrupted);
+    }
+
+    let fat = fs.read_fat()?;
+    let mut checker = Checker {
+        referenced: vec![false; fat.len()],
+        fat,
+        fs: &fs,
+        repair,
+        report: FsckReport {
+            total_clusters: fs.bpb.total_clusters(),
+            ..FsckReport::default()
+        },
+    };
+
+    let root = fs.bpb.root_cluster;
+    if !checker.in_range(root) {
+        return Err(StorageError::Corrupted);
+    }
+    let root_chain = checker.walk_chain(root, "/")?;
+
+    let mut pending = vec![(root_chain, root, String::from("/"))];
+    while let Some((chain, first, path)) = pending.pop() {
+        checker.check_dir(&chain, first, &path, &mut pending)?;
+    }
+
+    checker.collect_lost()?;
+    if checker.report.repaired {
+        // Repairs free clusters behind the running count's back
+        let free = checker.fat[2..].iter().filter(|&&entry| entry == 0).count();
+        fs.free_clusters.store(free as u32, Ordering::Relaxed);
+        fs.write_fsinfo()?;
+        device.flush()?;
+    }
+
+    Ok(checker.report)
+}
+
+/// State of a single [`check`] run.
+struct Checker<'a> {
+    fs: &'a Fat32Filesystem,
+    /// In-memory copy of the FAT, kept in sync with repairs.
+    fat: Vec<u32>,
+    /// Clusters claimed by a chain reachable from the root.
+    referenced: Vec<bool>,
+    repair: bool,
+    report: FsckReport,
+}
+
+impl Checker<'_> {
+    fn in_range(&self, cluster: u32) -> bool {
+        cluster >= 2 && (cluster as usize) < self.fat.len()
+    }
+
+    fn set_fat(&mut self, cluster: u32, value: u32) -> Result<(), StorageError> {
+        self.report.repaired = true;
+        self.fs.write_fat_entry(cluster, value)?;
+        self.fat[cluster as usize] = value;
+        Ok(())
+    }
+
+    fn delete_entry(&mut self, dir_cluster: u32, offset: u32) -> Result<(), StorageError> {
+        self.report.repaired = true;
+        self.fs.mark_dir_entry_deleted(dir_cluster, offset)
+    }
+
+    fn set_entry_size(
+        &mut self,
+        dir_cluster: u32,
+        offset: u32,
+        size: u32,
+    ) -> Result<(), StorageError> {
+        self.report.repaired = true;
+        self.fs.update_dir_entry_size(dir_cluster, offset, size)
+    }
+
+    /// Walk the chain starting at `first`, claiming its clusters.
+    ///
+    /// The chain is cut short at the first cross-link or broken link; the
+    /// returned clusters are the part that is safe to use.
+    fn walk_chain(&mut self, first: u32, path: &str) -> Result<Vec<u32>, StorageError> {
+        let mut chain: Vec<u32> = Vec::new();
+        let mut cluster = first;
+
+        loop {
+            if self.referenced[cluster as usize] {
+                self.report.issues.push(FsckIssue::CrossLinked {
+                    path: String::from(path),
+                    cluster,
+                });
+                if let (true, Some(&last)) = (self.repair, chain.last()) {
+                    self.set_fat(last, 0x0FFF_FFFF)?;
+                }
+                break;
+            }
+            self.referenced[cluster as usize] = true;
+            self.report.used_clusters += 1;
+            chain.push(cluster);
+
+            let next = self.fat[cluster as usize];
+            if next >= fat_entry::EOC_MIN {
+                break;
+            }
+            if !self.in_range(next) || self.fat[next as usize] == 0 {
+                self.report.issues.push(FsckIssue::BadChainLink {
+                    path: String::from(path),
+                    cluster,
+                    next,
+                });
+                if self.repair {
+                    self.set_fat(cluster, 0x0FFF_FFFF)?;
+                }
+                break;
+            }
+            cluster = next;
+        }
+
+        Ok(chain)
+    }
+
+    fn invalid_entry(&mut self, path: &str, reason: &'static str) {
+        self.report.issues.push(FsckIssue::InvalidEntry {
+            path: String::from(path),
+            reason,
+        });
+    }
+
+    /// Check the entries of one directory, queueing its subdirectories.
+    fn check_dir(
+        &mut self,
+        chain: &[u32],
+        dir_cluster: u32,
+        path: &str,
+        pending: &mut Vec<(Vec<u32>, u32, String)>,
+    ) -> Result<(), StorageError> {
+        let cluster_size = self.fs.cluster_size();
+        let mut cluster_buf = vec![0u8; cluster_size];
+
+        for (index, &cluster) in chain.iter().enumerate() {
+            self.fs.read_cluster(cluster, &mut cluster_buf)?;
+
+            for (slot, chunk) in cluster_buf.chunks_exact(Fat32DirEntry::SIZE).enumerate() {
+                // SAFETY: Reading a packed 32-byte struct from a byte buffer.
+                let entry: Fat32DirEntry =
+                    unsafe { core::ptr::read_unaligned(chunk.as_ptr() as *const Fat32DirEntry) };
+                if entry.is_last() {
+                    return Ok(());
+                }
+                if entry.is_free() || entry.is_long_name() || entry.is_volume_label() {
+                    continue;
+                }
+                // "." and ".." point back at this directory and its parent.
+                if entry.name[0] == b'.' {
+                    continue;
+                }
+
+                let offset = (index * cluster_size + slot * Fat32DirEntry::SIZE) as u32;
+                self.check_entry(&entry, dir_cluster, offset, path, pending)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_entry(
+        &mut self,
+        entry: &Fat32DirEntry,
+        dir_cluster: u32,
+        offset: u32,
+        dir_path: &str,
+        pending: &mut Vec<(Vec<u32>, u32, String)>,
+    ) -> Result<(), StorageError> {
+        let short = entry.short_name();
+        let len = short.iter().position(|&b| b == 0).unwrap_or(short.len());
+        let name = String::from_utf8_lossy(&short[..len]);
+        let path = if dir_path == "/" {
+            alloc::format!("/{}", name)
+        } else {
+            alloc::format!("{}/{}", dir_path, name)
+        };
+
+        if !valid_short_name(&entry.name) {
+            self.invalid_entry(&path, "invalid short name");
+            if self.repair {
+                self.delete_entry(dir_cluster, offset)?;
+            }
+            return Ok(());
+        }
+
+        let first = entry.first_cluster();
+        let is_dir = entry.is_dir();
+        let size = entry.file_size;
+
+        if first == 0 {
+            if is_dir {
+                self.invalid_entry(&path, "directory has no clusters");
+                if self.repair {
+                    self.delete_entry(dir_cluster, offset)?;
+                }
+            } else if size > 0 {
+                self.invalid_entry(&path, "non-empty file has no clusters");
+                if self.repair {
+                    self.set_entry_size(dir_cluster, offset, 0)?;
+                }
+            }
+            return Ok(());
+        }
+
+        if !self.in_range(first) || self.fat[first as usize] == 0 {
+            self.invalid_entry(&path, "first cluster is free or out of range");
+            if self.repair {
+                self.clear_entry(is_dir, dir_cluster, offset)?;
+            }
+            return Ok(());
+        }
+
+        let chain = self.walk_chain(first, &path)?;
+        if chain.is_empty() {
+            // Cross-linked at its first cluster: nothing is safe to keep.
+            if self.repair {
+                self.clear_entry(is_dir, dir_cluster, offset)?;
+            }
+            return Ok(());
+        }
+
+        if is_dir {
+            pending.push((chain, first, path));
+            return Ok(());
+        }
+
+        let allocated = chain.len() as u64 * self.fs.cluster_size() as u64;
+        if size as u64 > allocated {
+            self.invalid_entry(&path, "file size exceeds its cluster chain");
+            if self.repair {
+                self.set_entry_size(dir_cluster, offset, allocated as u32)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Detach an entry from its clusters: files become empty, directories
+    /// are deleted.
+    fn clear_entry(
+        &mut self,
+        is_dir: bool,
+        dir_cluster: u32,
+        offset: u32,
+    ) -> Result<(), StorageError> {
+        if is_dir {
+            return self.delete_entry(dir_cluster, offset);
+        }
+        self.report.repaired = true;
+        self.fs.update_dir_entry_cluster(dir_cluster, offset, 0)?;
+        self.fs.update_dir_entry_size(dir_cluster, offset, 0)
+    }
+
+    /// Report (and with `repair`, free) allocated clusters that are not
+    /// reachable from the directory tree.
+    fn collect_lost(&mut self) -> Result<(), StorageError> {
+        let lost = |c: &Self, cluster: usize| {
+            let entry = c.fat[cluster];
+            entry != 0 && entry != fat_entry::BAD && !c.referenced[cluster]
+        };
+
+        // Lost clusters that another lost cluster links to are not chain heads.
+        let mut is_target = vec![false; self.fat.len()];
+        for cluster in 2..self.fat.len() {
+            if lost(self, cluster) {
+                let next = self.fat[cluster];
+                if self.in_range(next) {
+                    is_target[next as usize] = true;
+                }
+            }
+        }
+
+        // Heads first, then whatever is left over (chains that loop).
+        let mut visited = vec![false; self.fat.len()];
+        for heads_only in [true, false] {
+            for head in 2..self.fat.len() {
+                if visited[head] || !lost(self, head) || (heads_only && is_target[head]) {
+                    continue;
+                }
+
+                let mut chain = Vec::new();
+                let mut cluster = head;
+                while !visited[cluster] && lost(self, cluster) {
+                    visited[cluster] = true;
+                    chain.push(cluster as u32);
+                    let next = self.fat[cluster];
+                    if !self.in_range(next) {
+                        break;
+                    }
+                    cluster = next as usize;
+                }
+
+                self.report.issues.push(FsckIssue::LostChain {
+                    first_cluster: head as u32,
+                    length: chain.len() as u32,
+                });
+                if self.repair {
+                    for &c in &chain {
+                        self.set_fat(c, 0)?;
+                    }
+                    self.report.reclaimed_clusters += chain.len() as u32;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check an 11-byte short name for characters FAT does not allow.
+fn valid_short_name(name: &[u8; 11]) -> bool {
+    const ILLEGAL: &[u8] = b"\"*+,./:;<=>?[\\]|";
+    if name[0] == b' ' {
+        return false;
+    }
+    name.iter().enumerate().all(|(i, &b)| {
+        // 0x05 stands in for a leading 0xE5 byte.
+        (i == 0 && b == 0x05) || (b >= 0x20 && !ILLEGAL.contains(&b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlockDeviceInfo;
+    use alloc::boxed::Box;
+    use spin::Mutex;
+
+    const SECTOR: usize = 512;
+    const RESERVED: u32 = 32;
+    const FAT_SIZE: u32 = 8;
+    const SECTORS_PER_CLUSTER: u8 = 8;
+
+    struct MemDisk(Mutex<Vec<u8>>);
+
+    impl BlockDevice for MemDisk {
+        fn info(&self) -> BlockDeviceInfo {
+            BlockDeviceInfo {
+                name: [0; 32],
+                name_len: 0,
+                block_size: SECTOR as u32,
+                total_blocks: (self.0.lock().len() / SECTOR) as u64,
+                read_only: false,
+                supports_trim: false,
+                optimal_io_size: 1,
+                physical_block_size: SECTOR as u32,
+            }
+        }
+
+        fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+            let disk = self.0.lock();
+            let start = start_block as usize * SECTOR;
+            buffer.copy_from_slice(&disk[start..start + buffer.len()]);
+            Ok(buffer.len())
+        }
+
+        fn write_blocks(&self, start_block: u64, data: &[u8]) -> Result<usize, StorageError> {
+            let mut disk = self.0.lock();
+            let start = start_block as usize * SECTOR;
+            disk[start..start + data.len()].copy_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&self) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn discard(&self, _start_block: u64, _num_blocks: u64) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+    }
+
+    /// Format a FAT32 volume with an empty root directory in cluster 2
+    /// and `free` as the FSInfo free count.
+    fn format(sectors: u32, free: u32) -> &'static MemDisk {
+        let mut disk = vec![0u8; sectors as usize * SECTOR];
+        let put16 = |disk: &mut Vec<u8>, at: usize, v: u16| {
+            disk[at..at + 2].copy_from_slice(&v.to_le_bytes())
+        };
+        let put32 = |disk: &mut Vec<u8>, at: usize, v: u32| {
+            disk[at..at + 4].copy_from_slice(&v.to_le_bytes())
+        };
+
+        put16(&mut disk, 11, SECTOR as u16);
+        disk[13] = SECTORS_PER_CLUSTER;
+        put16(&mut disk, 14, RESERVED as u16);
+        disk[16] = 2;
+        disk[21] = 0xF8;
+        put32(&mut disk, 32, sectors);
+        put32(&mut disk, 36, FAT_SIZE);
+        put32(&mut disk, 44, 2);
+        put16(&mut disk, 48, 1);
+        disk[510] = 0x55;
+        disk[511] = 0xAA;
+
+        for (offset, value) in [
+            (fsinfo::LEAD_SIG_OFFSET, fsinfo::LEAD_SIG),
+            (fsinfo::STRUC_SIG_OFFSET, fsinfo::STRUC_SIG),
+            (fsinfo::FREE_COUNT_OFFSET, free),
+            (fsinfo::TRAIL_SIG_OFFSET, fsinfo::TRAIL_SIG),
+        ] {
+            put32(&mut disk, SECTOR + offset, value);
+        }
+
+        for fat in 0..2 {
+            let start = (RESERVED + fat * FAT_SIZE) as usize * SECTOR;
+            put32(&mut disk, start, 0x0FFFFFF8);
+            put32(&mut disk, start + 4, 0x0FFFFFFF);
+            put32(&mut disk, start + 8, 0x0FFFFFFF);
+        }
+
+        Box::leak(Box::new(MemDisk(Mutex::new(disk))))
+    }
+
+    #[test]
+    fn test_statfs_counts() {
+        let disk = format(8192, fsinfo::UNKNOWN);
+        let info = disk.info();
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        let stats = fs.statfs().unwrap();
+
+        let data_sectors = info.total_blocks - (RESERVED + 2 * FAT_SIZE) as u64;
+        assert_eq!(stats.block_size, SECTOR as u32 * SECTORS_PER_CLUSTER as u32);
+        assert_eq!(
+            stats.total_blocks * stats.block_size as u64,
+            data_sectors * info.block_size as u64
+        );
+        // Unknown FSInfo count: the FAT is scanned, the root holds one cluster
+        assert_eq!(stats.free_blocks, stats.total_blocks - 1);
+
+        let handle = fs
+            .open("/data.bin", OpenFlags::CREATE | OpenFlags::WRITE)
+            .unwrap();
+        fs.write(handle, 0, &[0xA5; 10_000]).unwrap();
+        fs.close(handle).unwrap();
+        let written = fs.statfs().unwrap();
+        assert_eq!(written.free_blocks, stats.free_blocks - 3);
+        assert_eq!(written.available_blocks, written.free_blocks);
+
+        // The count survives a remount through FSInfo
+        fs.sync().unwrap();
+        let remounted = Fat32Filesystem::mount(disk).unwrap();
+        assert_eq!(remounted.statfs().unwrap().free_blocks, written.free_blocks);
+
+        remounted.unlink("/data.bin").unwrap();
+        assert_eq!(remounted.statfs().unwrap().free_blocks, stats.free_blocks);
+    }
+
+    #[test]
+    fn test_statfs_fsinfo_hint() {
+        let fs = Fat32Filesystem::mount(format(8192, 100)).unwrap();
+        assert_eq!(fs.statfs().unwrap().free_blocks, 100);
+
+        // Out of range: fall back to scanning the FAT
+        let fs = Fat32Filesystem::mount(format(8192, 5000)).unwrap();
+        let stats = fs.statfs().unwrap();
+        assert_eq!(stats.free_blocks, stats.total_blocks - 1);
+    }
+
+    /// Create `path` holding `len` bytes and return its first cluster.
+    fn put_file(fs: &Fat32Filesystem, path: &str, len: usize) -> u32 {
+        let handle = fs.open(path, OpenFlags::CREATE | OpenFlags::WRITE).unwrap();
+        fs.write(handle, 0, &vec![0x5A; len]).unwrap();
+        fs.close(handle).unwrap();
+        fs.find_entry_in_dir(fs.bpb.root_cluster, &path[1..])
+            .unwrap()
+            .entry
+            .first_cluster()
+    }
+
+    fn snapshot(disk: &MemDisk) -> Vec<u8> {
+        disk.0.lock().clone()
+    }
+
+    #[test]
+    fn test_fsck_clean_volume_is_not_written() {
+        let disk = format(8192, fsinfo::UNKNOWN);
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        put_file(&fs, "/A.BIN", 10_000);
+        fs.mkdir("/DIR", 0o755).unwrap();
+        fs.sync().unwrap();
+
+        let before = snapshot(disk);
+        let report = check(disk, true).unwrap();
+        assert!(report.is_clean(), "{:?}", report.issues);
+        assert!(!report.repaired);
+        assert_eq!(report.used_clusters, 1 + 3 + 1);
+        assert_eq!(snapshot(disk), before);
+    }
+
+    #[test]
+    fn test_fsck_cross_linked_chains() {
+        let disk = format(8192, fsinfo::UNKNOWN);
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        let a = put_file(&fs, "/A.BIN", 10_000);
+        let b = put_file(&fs, "/B.BIN", 10_000);
+        let b_tail = fs.read_fat_entry(b).unwrap();
+        // B now runs into the middle of A's chain
+        let a_second = fs.read_fat_entry(a).unwrap();
+        fs.write_fat_entry(b, a_second).unwrap();
+        fs.sync().unwrap();
+
+        // A read-only check leaves the volume alone
+        let before = snapshot(disk);
+        let report = check(disk, false).unwrap();
+        assert!(!report.repaired);
+        assert_eq!(snapshot(disk), before);
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            FsckIssue::CrossLinked { path, cluster } if path == "/B.BIN" && *cluster == a_second
+        )));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            FsckIssue::InvalidEntry { path, .. } if path == "/B.BIN"
+        )));
+        // B's original tail is no longer reachable
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            FsckIssue::LostChain { first_cluster, length: 2 } if *first_cluster == b_tail
+        )));
+
+        let report = check(disk, true).unwrap();
+        assert!(report.repaired);
+        assert_eq!(report.reclaimed_clusters, 2);
+        let report = check(disk, false).unwrap();
+        assert!(report.is_clean(), "{:?}", report.issues);
+
+        // B keeps its first cluster; A is untouched
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        assert_eq!(fs.read_fat_entry(b).unwrap(), 0x0FFF_FFFF);
+        assert_eq!(fs.lookup("/B.BIN").unwrap().size, fs.cluster_size() as u64);
+        assert_eq!(fs.lookup("/A.BIN").unwrap().size, 10_000);
+    }
+
+    #[test]
+    fn test_fsck_lost_chain() {
+        let disk = format(8192, fsinfo::UNKNOWN);
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        let free = fs.statfs().unwrap().free_blocks;
+        fs.write_fat_entry(100, 101).unwrap();
+        fs.write_fat_entry(101, 102).unwrap();
+        fs.write_fat_entry(102, 0x0FFF_FFFF).unwrap();
+        fs.write_fat_entry(200, 0x0FFF_FFFF).unwrap();
+        // Bad clusters are not lost
+        fs.write_fat_entry(300, fat_entry::BAD).unwrap();
+        fs.sync().unwrap();
+
+        let report = check(disk, false).unwrap();
+        assert_eq!(report.issues.len(), 2, "{:?}", report.issues);
+        assert!(matches!(
+            report.issues[0],
+            FsckIssue::LostChain {
+                first_cluster: 100,
+                length: 3
+            }
+        ));
+        assert!(matches!(
+            report.issues[1],
+            FsckIssue::LostChain {
+                first_cluster: 200,
+                length: 1
+            }
+        ));
+
+        let report = check(disk, true).unwrap();
+        assert!(report.repaired);
+        assert_eq!(report.reclaimed_clusters, 4);
+        assert!(check(disk, false).unwrap().is_clean());
+
+        // The freed clusters are counted again after a remount
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        assert_eq!(fs.read_fat_entry(100).unwrap(), 0);
+        assert_eq!(fs.read_fat_entry(300).unwrap(), fat_entry::BAD);
+        assert_eq!(fs.statfs().unwrap().free_blocks, free - 1);
+    }
+
+    #[test]
+    fn test_fsck_bad_sizes() {
+        let disk = format(8192, fsinfo::UNKNOWN);
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        let root = fs.bpb.root_cluster;
+        put_file(&fs, "/EMPTY.TXT", 0);
+        put_file(&fs, "/SHORT.TXT", 100);
+        let empty = fs.find_entry_in_dir(root, "EMPTY.TXT").unwrap().offset;
+        let short = fs.find_entry_in_dir(root, "SHORT.TXT").unwrap().offset;
+        fs.update_dir_entry_size(root, empty, 500).unwrap();
+        fs.update_dir_entry_size(root, short, 9000).unwrap();
+        fs.sync().unwrap();
+
+        let report = check(disk, false).unwrap();
+        let reasons: Vec<_> = report
+            .issues
+            .iter()
+            .map(|issue| match issue {
+                FsckIssue::InvalidEntry { path, reason } => (path.as_str(), *reason),
+                other => panic!("unexpected issue {}", other),
+            })
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                ("/EMPTY.TXT", "non-empty file has no clusters"),
+                ("/SHORT.TXT", "file size exceeds its cluster chain"),
+            ]
+        );
+
+        let report = check(disk, true).unwrap();
+        assert!(report.repaired);
+        assert_eq!(report.reclaimed_clusters, 0);
+        assert!(check(disk, false).unwrap().is_clean());
+
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+        assert_eq!(fs.lookup("/EMPTY.TXT").unwrap().size, 0);
+        assert_eq!(
+            fs.lookup("/SHORT.TXT").unwrap().size,
+            fs.cluster_size() as u64
+        );
+    }
+
+    /// A short directory entry for an empty file.
+    fn short_slot(name: &[u8; 11]) -> [u8; 32] {
+        let mut slot = [0u8; 32];
+        slot[..11].copy_from_slice(name);
+        slot
+    }
+
+    /// The long-name slots for `long`, in directory order, checksummed
+    /// against the 8.3 name `short`.
+    fn long_slots(long: &str, short: &[u8; 11]) -> Vec<[u8; 32]> {
+        let checksum = short
+            .iter()
+            .fold(0u8, |sum, &b| sum.rotate_right(1).wrapping_add(b));
+        let mut units: Vec<u16> = long.encode_utf16().collect();
+        let count = units.len().div_ceil(LFN_UNITS_PER_SLOT);
+        if units.len() < count * LFN_UNITS_PER_SLOT {
+            units.push(0x0000);
+        }
+        units.resize(count * LFN_UNITS_PER_SLOT, 0xFFFF);
+
+        (1..=count)
+            .rev()
+            .map(|seq| {
+                let mut slot = [0u8; 32];
+                slot[0] = seq as u8;
+                if seq == count {
+                    slot[0] |= Fat32DirEntry::LAST_LONG_ENTRY;
+                }
+                slot[11] = Fat32DirEntry::ATTR_LONG_NAME;
+                slot[13] = checksum;
+                let chunk = &units[(seq - 1) * LFN_UNITS_PER_SLOT..][..LFN_UNITS_PER_SLOT];
+                for (&pos, unit) in LFN_UNIT_OFFSETS.iter().zip(chunk) {
+                    slot[pos..pos + 2].copy_from_slice(&unit.to_le_bytes());
+                }
+                slot
+            })
+            .collect()
+    }
+
+    /// Format a volume whose root directory holds `slots`.
+    fn format_with_root(slots: &[[u8; 32]]) -> &'static MemDisk {
+        let disk = format(8192, fsinfo::UNKNOWN);
+        let root = (RESERVED + 2 * FAT_SIZE) as usize * SECTOR;
+        let mut data = disk.0.lock();
+        for (i, slot) in slots.iter().enumerate() {
+            data[root + i * 32..][..32].copy_from_slice(slot);
+        }
+        drop(data);
+        disk
+    }
+
+    fn root_names(fs: &Fat32Filesystem) -> Vec<String> {
+        fs.readdir("/", 0)
+            .unwrap()
+            .iter()
+            .map(|entry| String::from(entry.name_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lfn_names() {
+        let long = "A rather long file name.text";
+        let short = *b"ARATHE~1TEX";
+        let mut slots = long_slots(long, &short);
+        assert_eq!(slots.len(), 3);
+        slots.push(short_slot(&short));
+        // Exactly one slot: no terminator, no padding
+        let exact = "Thirteen_char";
+        slots.extend(long_slots(exact, b"THIRTE~1   "));
+        slots.push(short_slot(b"THIRTE~1   "));
+        slots.push(short_slot(b"PLAIN   TXT"));
+        let fs = Fat32Filesystem::mount(format_with_root(&slots)).unwrap();
+
+        assert_eq!(root_names(&fs), [long, exact, "PLAIN.TXT"]);
+
+        // Long and short names both open, in any case
+        for path in [
+            "/A rather long file name.text",
+            "/a RATHER long FILE name.TEXT",
+            "/ARATHE~1.TEX",
+            "/arathe~1.tex",
+            "/thirteen_CHAR",
+            "/thirte~1",
+            "/plain.txt",
+        ] {
+            let handle = fs.open(path, OpenFlags::READ).unwrap();
+            fs.close(handle).unwrap();
+        }
+        assert!(matches!(
+            fs.open("/A rather long file name", OpenFlags::READ),
+            Err(StorageError::FileNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_lfn_checksum_mismatch() {
+        let short = *b"ARATHE~1TEX";
+        let mut slots = long_slots("A rather long file name.text", b"ARATHE~2TEX");
+        slots.push(short_slot(&short));
+        // Slots whose sequence breaks off are dropped as well
+        let mut broken = long_slots("Another long name.text", b"ANOTHE~1TEX");
+        broken.remove(1);
+        slots.extend(broken);
+        slots.push(short_slot(b"ANOTHE~1TEX"));
+        let fs = Fat32Filesystem::mount(format_with_root(&slots)).unwrap();
+
+        assert_eq!(root_names(&fs), ["ARATHE~1.TEX", "ANOTHE~1.TEX"]);
+        assert!(matches!(
+            fs.open("/A rather long file name.text", OpenFlags::READ),
+            Err(StorageError::FileNotFound)
+        ));
+        let handle = fs.open("/arathe~1.tex", OpenFlags::READ).unwrap();
+        fs.close(handle).unwrap();
+    }
+
+    #[test]
+    fn test_lfn_deleted_slots() {
+        let short = *b"ARATHE~1TEX";
+        let mut slots = long_slots("A rather long file name.text", &short);
+        for slot in &mut slots {
+            slot[0] = Fat32DirEntry::DELETED;
+        }
+        slots.push(short_slot(&short));
+        slots.extend(long_slots("Second long name.text", b"SECOND~1TEX"));
+        slots.push(short_slot(b"SECOND~1TEX"));
+        let disk = format_with_root(&slots);
+        let fs = Fat32Filesystem::mount(disk).unwrap();
+
+        assert_eq!(root_names(&fs), ["ARATHE~1.TEX", "Second long name.text"]);
+
+        // Unlinking by long name deletes the slots along with the entry
+        fs.unlink("/second LONG name.text").unwrap();
+        assert_eq!(root_names(&fs), ["ARATHE~1.TEX"]);
+        let root = (RESERVED + 2 * FAT_SIZE) as usize * SECTOR;
+        let data = disk.0.lock();
+        for i in slots.len() - 3..slots.len() {
+            assert_eq!(data[root + i * 32], Fat32DirEntry::DELETED);
+        }
+    }
+}
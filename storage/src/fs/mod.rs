@@ -4,10 +4,14 @@
 //! - ext4 (Linux extended filesystem)
 //! - FAT32 (File Allocation Table)
 //! - NTFS (Windows NT filesystem, read-only)
+//! - tmpfs (in-memory)
+//! - overlayfs (writable layer over a read-only one)
 
 pub mod ext4;
 pub mod fat32;
 pub mod ntfs;
+pub mod overlayfs;
+pub mod tmpfs;
 
 use crate::StorageError;
 
@@ -77,6 +81,10 @@ pub enum FilesystemType {
     Fat32,
     /// NTFS filesystem (read-only).
     Ntfs,
+    /// In-memory filesystem.
+    Tmpfs,
+    /// Union of a writable layer over a read-only one.
+    Overlay,
     /// Unknown filesystem.
     Unknown,
 }
@@ -88,6 +96,8 @@ impl FilesystemType {
             "ext4" | "ext3" | "ext2" => FilesystemType::Ext4,
             "fat32" | "fat" | "vfat" => FilesystemType::Fat32,
             "ntfs" => FilesystemType::Ntfs,
+            "tmpfs" => FilesystemType::Tmpfs,
+            "overlayfs" | "overlay" => FilesystemType::Overlay,
             _ => FilesystemType::Unknown,
         }
     }
@@ -98,6 +108,8 @@ impl FilesystemType {
             FilesystemType::Ext4 => "ext4",
             FilesystemType::Fat32 => "fat32",
             FilesystemType::Ntfs => "ntfs",
+            FilesystemType::Tmpfs => "tmpfs",
+            FilesystemType::Overlay => "overlayfs",
             FilesystemType::Unknown => "unknown",
         }
     }
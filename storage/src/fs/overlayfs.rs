@@ -0,0 +1,652 @@
+//! overlayfs - union of a writable upper layer over a read-only lower one.
+//!
+//! Lookups see the upper layer first and fall through to the lower one.
+//! The lower layer is never modified:
+//! - Writing to a lower file first copies it up (with its parent
+//!   directories) into the upper layer.
+//! - Deleting a lower entry leaves a whiteout, an empty `.wh.<name>` file
+//!   next to where the entry would be in the upper layer.
+//! - A directory created over a whiteout gets a `.wh..wh..opq` marker so
+//!   the old lower contents stay hidden.
+//!
+//! `readdir` merges both layers and hides whited-out names. Whiteout
+//! files are never visible through the overlay. Renaming a directory that
+//! exists in the lower layer fails with [`StorageError::CrossDeviceLink`],
+//! as on Linux, so callers fall back to copy and delete.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+
+use crate::vfs::{Filesystem, FsStats};
+use crate::{DirEntry, FileMetadata, FileType, OpenFlags, StorageError};
+
+/// overlayfs magic number (as reported by statfs).
+pub const OVERLAYFS_SUPER_MAGIC: u32 = 0x794C_7630;
+
+/// Prefix of whiteout file names.
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// Marker file making an upper directory hide the lower one.
+const OPAQUE_MARKER: &str = ".wh..wh..opq";
+
+/// Chunk size for copy-up.
+const COPY_CHUNK: usize = 4096;
+
+/// Which layer an open file lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Upper,
+    Lower,
+}
+
+/// A union filesystem stacking `upper` over `lower`.
+pub struct OverlayFs {
+    lower: &'static dyn Filesystem,
+    upper: &'static dyn Filesystem,
+    handles: Mutex<BTreeMap<u64, (Layer, u64)>>,
+    next_handle: AtomicU64,
+}
+
+impl OverlayFs {
+    /// Stack a writable `upper` filesystem over a read-only `lower` one.
+    pub fn new(lower: &'static dyn Filesystem, upper: &'static dyn Filesystem) -> Self {
+        OverlayFs {
+            lower,
+            upper,
+            handles: Mutex::new(BTreeMap::new()),
+            next_handle: AtomicU64::new(1),
+        }
+    }
+
+    /// Check if the lower layer's entry at `path` shows through.
+    ///
+    /// It is hidden by a whiteout of the path or any ancestor, or by an
+    /// opaque or non-directory upper ancestor.
+    fn lower_visible(&self, path: &str) -> bool {
+        let mut prefix = String::new();
+        for name in components(path) {
+            let parent = if prefix.is_empty() { "/" } else { &prefix };
+            if let Ok(meta) = self.upper.lookup(parent) {
+                if meta.file_type != FileType::Directory
+                    || self.upper.lookup(&join(parent, OPAQUE_MARKER)).is_ok()
+                {
+                    return false;
+                }
+            }
+            if self.upper.lookup(&whiteout_path(parent, name)).is_ok() {
+                return false;
+            }
+            prefix.push('/');
+            prefix.push_str(name);
+        }
+        true
+    }
+
+    /// Find the layer that provides `path`.
+    fn locate(&self, path: &str) -> Result<(Layer, FileMetadata), StorageError> {
+        if components(path).any(|name| name.starts_with(WHITEOUT_PREFIX)) {
+            return Err(StorageError::FileNotFound);
+        }
+        match self.upper.lookup(path) {
+            Ok(meta) => return Ok((Layer::Upper, meta)),
+            Err(StorageError::FileNotFound) => {}
+            Err(e) => return Err(e),
+        }
+        if self.lower_visible(path) {
+            self.lower.lookup(path).map(|meta| (Layer::Lower, meta))
+        } else {
+            Err(StorageError::FileNotFound)
+        }
+    }
+
+    fn layer(&self, layer: Layer) -> &'static dyn Filesystem {
+        match layer {
+            Layer::Upper => self.upper,
+            Layer::Lower => self.lower,
+        }
+    }
+
+    /// Make sure `path` exists in the upper layer, copying it and its
+    /// parent directories up from the lower layer if needed.
+    fn copy_up(&self, path: &str) -> Result<(), StorageError> {
+        let (layer, meta) = self.locate(path)?;
+        if layer == Layer::Upper {
+            return Ok(());
+        }
+        self.copy_up_parent(path)?;
+
+        match meta.file_type {
+            FileType::Directory => self.upper.mkdir(path, meta.permissions.0)?,
+            FileType::Symlink => self.upper.symlink(&self.lower.readlink(path)?, path)?,
+            _ => {
+                self.upper.create(path, meta.permissions.0)?;
+                self.copy_data(path)?;
+            }
+        }
+        self.upper.setattr(path, &meta)
+    }
+
+    /// Copy up the parent directory of `path`.
+    fn copy_up_parent(&self, path: &str) -> Result<(), StorageError> {
+        match parent(path) {
+            "/" => Ok(()),
+            dir => self.copy_up(dir),
+        }
+    }
+
+    /// Copy a regular file's contents from the lower to the upper layer.
+    fn copy_data(&self, path: &str) -> Result<(), StorageError> {
+        let src = self.lower.open(path, OpenFlags::READ)?;
+        let dst = match self.upper.open(path, OpenFlags::WRITE) {
+            Ok(dst) => dst,
+            Err(e) => {
+                let _ = self.lower.close(src);
+                return Err(e);
+            }
+        };
+
+        let mut buffer = [0u8; COPY_CHUNK];
+        let mut offset = 0u64;
+        let result = loop {
+            let n = match self.lower.read(src, offset, &mut buffer) {
+                Ok(0) | Err(StorageError::EndOfFile) => break Ok(()),
+                Ok(n) => n,
+                Err(e) => break Err(e),
+            };
+            if let Err(e) = self.upper.write(dst, offset, &buffer[..n]) {
+                break Err(e);
+            }
+            offset += n as u64;
+        };
+
+        let _ = self.lower.close(src);
+        self.upper.close(dst)?;
+        result
+    }
+
+    /// Prepare the upper layer for a new entry at `path`: copy up its
+    /// parent and remove a whiteout left by an earlier delete.
+    ///
+    /// Returns `true` if a whiteout was removed.
+    fn prepare_create(&self, path: &str) -> Result<bool, StorageError> {
+        if self.locate(path).is_ok() {
+            return Err(StorageError::AlreadyExists);
+        }
+        let (dir, name) = split(path)?;
+        if name.starts_with(WHITEOUT_PREFIX) {
+            return Err(StorageError::InvalidName);
+        }
+        match self.locate(dir) {
+            Ok((_, meta)) if meta.file_type != FileType::Directory => {
+                return Err(StorageError::NotADirectory)
+            }
+            Ok(_) => {}
+            Err(_) => return Err(StorageError::DirectoryNotFound),
+        }
+        self.copy_up_parent(path)?;
+
+        let whiteout = whiteout_path(dir, name);
+        if self.upper.lookup(&whiteout).is_ok() {
+            self.upper.unlink(&whiteout)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Hide the lower entry at `path`, if there is one.
+    fn whiteout(&self, path: &str) -> Result<(), StorageError> {
+        if !self.lower_visible(path) || self.lower.lookup(path).is_err() {
+            return Ok(());
+        }
+        self.copy_up_parent(path)?;
+        let (dir, name) = split(path)?;
+        self.upper.create(&whiteout_path(dir, name), 0)?;
+        Ok(())
+    }
+
+    /// Merged directory listing, without dot entries.
+    fn merged_entries(&self, path: &str) -> Result<Vec<DirEntry>, StorageError> {
+        let (layer, meta) = self.locate(path)?;
+        if meta.file_type != FileType::Directory {
+            return Err(StorageError::NotADirectory);
+        }
+
+        let mut entries: Vec<DirEntry> = Vec::new();
+        let mut whiteouts: Vec<String> = Vec::new();
+        let mut opaque = false;
+
+        if layer == Layer::Upper {
+            for entry in self.upper.readdir(path, 0)? {
+                let name = entry.name_str();
+                if name == OPAQUE_MARKER {
+                    opaque = true;
+                } else if let Some(hidden) = name.strip_prefix(WHITEOUT_PREFIX) {
+                    whiteouts.push(String::from(hidden));
+                } else if !entry.is_dot_entry() {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        if !opaque && self.lower_visible(path) {
+            if let Ok(lower_entries) = self.lower.readdir(path, 0) {
+                for entry in lower_entries {
+                    let name = entry.name_str();
+                    if !entry.is_dot_entry()
+                        && !whiteouts.iter().any(|w| w == name)
+                        && !entries.iter().any(|e| e.name_str() == name)
+                    {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Remove whiteouts and the opaque marker from an upper directory
+    /// that is otherwise empty.
+    fn clear_upper_dir(&self, path: &str) -> Result<(), StorageError> {
+        for entry in self.upper.readdir(path, 0)? {
+            let name = entry.name_str();
+            if name.starts_with(WHITEOUT_PREFIX) {
+                self.upper.unlink(&join(path, name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_handle(&self, layer: Layer, fs_handle: u64) -> u64 {
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        self.handles.lock().insert(handle, (layer, fs_handle));
+        handle
+    }
+
+    fn handle(&self, handle: u64) -> Result<(&'static dyn Filesystem, u64), StorageError> {
+        let (layer, fs_handle) = *self
+            .handles
+            .lock()
+            .get(&handle)
+            .ok_or(StorageError::InvalidFd)?;
+        Ok((self.layer(layer), fs_handle))
+    }
+}
+
+impl Filesystem for OverlayFs {
+    fn fs_type(&self) -> &str {
+        "overlayfs"
+    }
+
+    fn statfs(&self) -> Result<FsStats, StorageError> {
+        let mut stats = self.upper.statfs()?;
+        stats.fs_type = OVERLAYFS_SUPER_MAGIC;
+        Ok(stats)
+    }
+
+    fn lookup(&self, path: &str) -> Result<FileMetadata, StorageError> {
+        self.locate(path).map(|(_, meta)| meta)
+    }
+
+    fn readdir(&self, path: &str, offset: u64) -> Result<Vec<DirEntry>, StorageError> {
+        let mut entries = self.merged_entries(path)?;
+        if offset as usize >= entries.len() {
+            return Ok(Vec::new());
+        }
+        Ok(entries.split_off(offset as usize))
+    }
+
+    fn create(&self, path: &str, mode: u16) -> Result<u64, StorageError> {
+        self.prepare_create(path)?;
+        self.upper.create(path, mode)
+    }
+
+    fn mkdir(&self, path: &str, mode: u16) -> Result<(), StorageError> {
+        let replaced = self.prepare_create(path)?;
+        self.upper.mkdir(path, mode)?;
+        if replaced {
+            self.upper.create(&join(path, OPAQUE_MARKER), 0)?;
+        }
+        Ok(())
+    }
+
+    fn unlink(&self, path: &str) -> Result<(), StorageError> {
+        let (layer, meta) = self.locate(path)?;
+        if meta.file_type == FileType::Directory {
+            return Err(StorageError::NotAFile);
+        }
+        if layer == Layer::Upper {
+            self.upper.unlink(path)?;
+        }
+        self.whiteout(path)
+    }
+
+    fn rmdir(&self, path: &str) -> Result<(), StorageError> {
+        if components(path).next().is_none() {
+            return Err(StorageError::PermissionDenied);
+        }
+        let (layer, meta) = self.locate(path)?;
+        if meta.file_type != FileType::Directory {
+            return Err(StorageError::NotADirectory);
+        }
+        if !self.merged_entries(path)?.is_empty() {
+            return Err(StorageError::DirectoryNotEmpty);
+        }
+        if layer == Layer::Upper {
+            self.clear_upper_dir(path)?;
+            self.upper.rmdir(path)?;
+        }
+        self.whiteout(path)
+    }
+
+    fn rename(&self, old_path: &str, new_path: &str) -> Result<(), StorageError> {
+        let (_, meta) = self.locate(old_path)?;
+        if old_path == new_path {
+            return Ok(());
+        }
+        let is_dir = meta.file_type == FileType::Directory;
+        if is_dir && self.lower_visible(old_path) && self.lower.lookup(old_path).is_ok() {
+            return Err(StorageError::CrossDeviceLink);
+        }
+        if let Ok((_, target)) = self.locate(new_path) {
+            if target.file_type == FileType::Directory {
+                return Err(StorageError::AlreadyExists);
+            }
+            self.unlink(new_path)?;
+        }
+
+        self.copy_up(old_path)?;
+        let replaced = self.prepare_create(new_path)?;
+        self.upper.rename(old_path, new_path)?;
+        if replaced && is_dir {
+            match self.upper.create(&join(new_path, OPAQUE_MARKER), 0) {
+                Ok(_) | Err(StorageError::AlreadyExists) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.whiteout(old_path)
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), StorageError> {
+        self.prepare_create(link_path)?;
+        self.upper.symlink(target, link_path)
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, StorageError> {
+        let (layer, _) = self.locate(path)?;
+        self.layer(layer).readlink(path)
+    }
+
+    fn link(&self, old_path: &str, new_path: &str) -> Result<(), StorageError> {
+        self.copy_up(old_path)?;
+        self.prepare_create(new_path)?;
+        self.upper.link(old_path, new_path)
+    }
+
+    fn setattr(&self, path: &str, attr: &FileMetadata) -> Result<(), StorageError> {
+        self.copy_up(path)?;
+        self.upper.setattr(path, attr)
+    }
+
+    fn open(&self, path: &str, flags: OpenFlags) -> Result<u64, StorageError> {
+        let writes = OpenFlags::WRITE | OpenFlags::TRUNCATE | OpenFlags::APPEND;
+        let layer = match self.locate(path) {
+            Ok(_) if flags.contains(OpenFlags::CREATE | OpenFlags::EXCLUSIVE) => {
+                return Err(StorageError::AlreadyExists)
+            }
+            Ok((Layer::Lower, _)) if flags.intersects(writes) => {
+                self.copy_up(path)?;
+                Layer::Upper
+            }
+            Ok((layer, _)) => layer,
+            Err(StorageError::FileNotFound) if flags.contains(OpenFlags::CREATE) => {
+                self.prepare_create(path)?;
+                Layer::Upper
+            }
+            Err(e) => return Err(e),
+        };
+
+        let fs_handle = self.layer(layer).open(path, flags)?;
+        Ok(self.insert_handle(layer, fs_handle))
+    }
+
+    fn close(&self, handle: u64) -> Result<(), StorageError> {
+        let (layer, fs_handle) = self
+            .handles
+            .lock()
+            .remove(&handle)
+            .ok_or(StorageError::InvalidFd)?;
+        self.layer(layer).close(fs_handle)
+    }
+
+    fn read(&self, handle: u64, offset: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+        let (fs, fs_handle) = self.handle(handle)?;
+        fs.read(fs_handle, offset, buffer)
+    }
+
+    fn write(&self, handle: u64, offset: u64, data: &[u8]) -> Result<usize, StorageError> {
+        let (fs, fs_handle) = self.handle(handle)?;
+        fs.write(fs_handle, offset, data)
+    }
+
+    fn flush(&self, handle: u64) -> Result<(), StorageError> {
+        let (fs, fs_handle) = self.handle(handle)?;
+        fs.flush(fs_handle)
+    }
+
+    fn fsync(&self, handle: u64, data_only: bool) -> Result<(), StorageError> {
+        let (fs, fs_handle) = self.handle(handle)?;
+        fs.fsync(fs_handle, data_only)
+    }
+
+    fn truncate(&self, path: &str, size: u64) -> Result<(), StorageError> {
+        self.copy_up(path)?;
+        self.upper.truncate(path, size)
+    }
+
+    fn fallocate(&self, handle: u64, offset: u64, len: u64) -> Result<(), StorageError> {
+        let (fs, fs_handle) = self.handle(handle)?;
+        fs.fallocate(fs_handle, offset, len)
+    }
+
+    fn sync(&self) -> Result<(), StorageError> {
+        self.upper.sync()
+    }
+}
+
+/// Non-empty components of a path.
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|c| !c.is_empty() && *c != ".")
+}
+
+/// Split a path into its parent directory and final component.
+fn split(path: &str) -> Result<(&str, &str), StorageError> {
+    let path = path.trim_end_matches('/');
+    match path.rsplit_once('/') {
+        Some((_, "")) | None => Err(StorageError::InvalidPath),
+        Some(("", name)) => Ok(("/", name)),
+        Some((dir, name)) => Ok((dir, name)),
+    }
+}
+
+/// Parent directory of a path.
+fn parent(path: &str) -> &str {
+    split(path).map_or("/", |(dir, _)| dir)
+}
+
+fn join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+fn whiteout_path(dir: &str, name: &str) -> String {
+    join(dir, &format!("{}{}", WHITEOUT_PREFIX, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::tmpfs::TmpFs;
+    use alloc::boxed::Box;
+
+    fn write_file(fs: &dyn Filesystem, path: &str, data: &[u8]) {
+        let handle = fs.open(path, OpenFlags::CREATE | OpenFlags::WRITE).unwrap();
+        fs.write(handle, 0, data).unwrap();
+        fs.close(handle).unwrap();
+    }
+
+    fn read_file(fs: &dyn Filesystem, path: &str) -> Vec<u8> {
+        let handle = fs.open(path, OpenFlags::READ).unwrap();
+        let mut buffer = [0u8; 64];
+        let n = fs.read(handle, 0, &mut buffer).unwrap();
+        fs.close(handle).unwrap();
+        buffer[..n].to_vec()
+    }
+
+    fn names(fs: &dyn Filesystem, path: &str) -> Vec<String> {
+        let mut names: Vec<String> = fs
+            .readdir(path, 0)
+            .unwrap()
+            .iter()
+            .filter(|entry| !entry.is_dot_entry())
+            .map(|entry| String::from(entry.name_str()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// An overlay over a lower layer holding `/dir/file` ("lower") and
+    /// `/dir/other`.
+    fn overlay() -> (&'static TmpFs, &'static TmpFs, OverlayFs) {
+        let lower: &'static TmpFs = Box::leak(Box::new(TmpFs::new()));
+        let upper: &'static TmpFs = Box::leak(Box::new(TmpFs::new()));
+        lower.mkdir("/dir", 0o755).unwrap();
+        write_file(lower, "/dir/file", b"lower");
+        write_file(lower, "/dir/other", b"other");
+        (lower, upper, OverlayFs::new(lower, upper))
+    }
+
+    #[test]
+    fn test_write_copies_up() {
+        let (lower, upper, fs) = overlay();
+        assert_eq!(read_file(&fs, "/dir/file"), b"lower");
+        assert!(upper.lookup("/dir/file").is_err());
+
+        let handle = fs.open("/dir/file", OpenFlags::WRITE).unwrap();
+        fs.write(handle, 0, b"UP").unwrap();
+        fs.close(handle).unwrap();
+
+        assert_eq!(read_file(&fs, "/dir/file"), b"UPwer");
+        assert_eq!(read_file(upper, "/dir/file"), b"UPwer");
+        assert_eq!(read_file(lower, "/dir/file"), b"lower");
+        assert_eq!(upper.lookup("/dir").unwrap().file_type, FileType::Directory);
+    }
+
+    #[test]
+    fn test_unlink_leaves_whiteout() {
+        let (lower, upper, fs) = overlay();
+        fs.unlink("/dir/file").unwrap();
+
+        assert!(upper.lookup("/dir/.wh.file").is_ok());
+        assert_eq!(
+            fs.lookup("/dir/file").unwrap_err(),
+            StorageError::FileNotFound
+        );
+        assert_eq!(names(&fs, "/dir"), ["other"]);
+        assert_eq!(read_file(lower, "/dir/file"), b"lower");
+
+        // A new file replaces the whiteout
+        write_file(&fs, "/dir/file", b"new");
+        assert!(upper.lookup("/dir/.wh.file").is_err());
+        assert_eq!(read_file(&fs, "/dir/file"), b"new");
+    }
+
+    #[test]
+    fn test_mkdir_over_whiteout_is_opaque() {
+        let (_, upper, fs) = overlay();
+        fs.unlink("/dir/file").unwrap();
+        fs.unlink("/dir/other").unwrap();
+        fs.rmdir("/dir").unwrap();
+        assert!(upper.lookup("/.wh.dir").is_ok());
+        assert_eq!(fs.lookup("/dir").unwrap_err(), StorageError::FileNotFound);
+
+        fs.mkdir("/dir", 0o755).unwrap();
+        assert!(upper.lookup("/.wh.dir").is_err());
+        assert!(upper.lookup("/dir/.wh..wh..opq").is_ok());
+        assert!(names(&fs, "/dir").is_empty());
+        assert_eq!(
+            fs.lookup("/dir/file").unwrap_err(),
+            StorageError::FileNotFound
+        );
+    }
+
+    #[test]
+    fn test_readdir_merges_layers() {
+        let (_, _, fs) = overlay();
+        write_file(&fs, "/dir/file", b"changed");
+        write_file(&fs, "/dir/new", b"new");
+        assert_eq!(names(&fs, "/dir"), ["file", "new", "other"]);
+        assert_eq!(names(&fs, "/"), ["dir"]);
+    }
+
+    #[test]
+    fn test_rmdir_checks_merged_entries() {
+        let (_, upper, fs) = overlay();
+        // The upper copy is empty, but the lower files still show
+        fs.mkdir("/dir/sub", 0o755).unwrap();
+        fs.rmdir("/dir/sub").unwrap();
+        assert!(upper.lookup("/dir").is_ok());
+        assert_eq!(fs.rmdir("/dir"), Err(StorageError::DirectoryNotEmpty));
+
+        // Once both lower files are whited out, it only looks empty
+        fs.unlink("/dir/file").unwrap();
+        fs.unlink("/dir/other").unwrap();
+        assert_eq!(names(upper, "/dir"), [".wh.file", ".wh.other"]);
+        fs.rmdir("/dir").unwrap();
+        assert_eq!(fs.lookup("/dir").unwrap_err(), StorageError::FileNotFound);
+        assert!(names(&fs, "/").is_empty());
+    }
+
+    #[test]
+    fn test_rename() {
+        let (lower, _, fs) = overlay();
+        assert_eq!(
+            fs.rename("/dir", "/moved"),
+            Err(StorageError::CrossDeviceLink)
+        );
+
+        fs.rename("/dir/file", "/dir/renamed").unwrap();
+        assert_eq!(read_file(&fs, "/dir/renamed"), b"lower");
+        assert_eq!(
+            fs.lookup("/dir/file").unwrap_err(),
+            StorageError::FileNotFound
+        );
+        assert!(lower.lookup("/dir/file").is_ok());
+
+        // Directories only in the upper layer move normally
+        fs.mkdir("/fresh", 0o755).unwrap();
+        fs.rename("/fresh", "/moved").unwrap();
+        assert_eq!(names(&fs, "/"), ["dir", "moved"]);
+    }
+
+    #[test]
+    fn test_whiteout_names_rejected() {
+        let (_, upper, fs) = overlay();
+        assert_eq!(fs.create("/.wh.x", 0o644), Err(StorageError::InvalidName));
+        assert_eq!(
+            fs.mkdir("/dir/.wh.y", 0o755),
+            Err(StorageError::InvalidName)
+        );
+
+        fs.unlink("/dir/file").unwrap();
+        assert!(upper.lookup("/dir/.wh.file").is_ok());
+        assert_eq!(
+            fs.lookup("/dir/.wh.file").unwrap_err(),
+            StorageError::FileNotFound
+        );
+    }
+}
@@ -0,0 +1,701 @@
+//! tmpfs - in-memory filesystem.
+//!
+//! Files live in kernel heap memory and disappear on unmount. tmpfs is
+//! used for scratch space and as the writable upper layer of overlayfs.
+//! File data grows on demand, optionally up to a size limit (see
+//! [`TmpFs::with_size_limit`]). Timestamps come from the clock installed
+//! with [`set_clock`].
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use spin::{Mutex, RwLock};
+
+use crate::vfs::{Filesystem, FsStats, MAX_NAME_LEN};
+use crate::{DirEntry, FileMetadata, FilePermissions, FileType, OpenFlags, StorageError};
+
+/// tmpfs magic number (as reported by statfs).
+pub const TMPFS_MAGIC: u32 = 0x0102_1994;
+
+/// Inode number of the root directory.
+const ROOT_INODE: u64 = 1;
+
+/// Unit of `FileMetadata::blocks` and of the size limit.
+const BLOCK_SIZE: u64 = 512;
+
+/// Source of timestamps, in nanoseconds since the Unix epoch.
+pub type Clock = fn() -> u64;
+
+fn no_clock() -> u64 {
+    0
+}
+
+static CLOCK: RwLock<Clock> = RwLock::new(no_clock as Clock);
+
+/// Set the clock used for tmpfs timestamps. Until one is set, all
+/// timestamps are zero.
+pub fn set_clock(clock: Clock) {
+    *CLOCK.write() = clock;
+}
+
+fn now() -> u64 {
+    (CLOCK.read())()
+}
+
+/// Contents of a tmpfs node.
+enum NodeData {
+    File(Vec<u8>),
+    Directory(BTreeMap<String, u64>),
+    Symlink(String),
+}
+
+/// A tmpfs inode.
+struct Node {
+    meta: FileMetadata,
+    data: NodeData,
+}
+
+impl Node {
+    fn new(inode: u64, file_type: FileType, mode: u16, data: NodeData) -> Self {
+        let now = now();
+        Node {
+            meta: FileMetadata {
+                file_type,
+                permissions: FilePermissions(mode),
+                atime: now,
+                mtime: now,
+                ctime: now,
+                crtime: now,
+                inode,
+                ..FileMetadata::default()
+            },
+            data,
+        }
+    }
+}
+
+struct TmpFsInner {
+    nodes: BTreeMap<u64, Node>,
+    next_inode: u64,
+    /// Open handles mapped to inode numbers.
+    handles: BTreeMap<u64, u64>,
+    next_handle: u64,
+    /// Blocks of file data currently allocated.
+    used_blocks: u64,
+    /// Allocation limit in blocks, if any.
+    max_blocks: Option<u64>,
+}
+
+impl TmpFsInner {
+    /// Resolve a path to an inode number without following symlinks.
+    fn resolve(&self, path: &str) -> Result<u64, StorageError> {
+        let mut inode = ROOT_INODE;
+        for name in path.split('/').filter(|c| !c.is_empty()) {
+            let node = self.nodes.get(&inode).ok_or(StorageError::FileNotFound)?;
+            inode = match &node.data {
+                NodeData::Directory(entries) => {
+                    *entries.get(name).ok_or(StorageError::FileNotFound)?
+                }
+                _ => return Err(StorageError::NotADirectory),
+            };
+        }
+        Ok(inode)
+    }
+
+    /// Resolve the parent directory of a path and return it with the
+    /// final component.
+    fn resolve_parent<'a>(&self, path: &'a str) -> Result<(u64, &'a str), StorageError> {
+        let path = path.trim_end_matches('/');
+        let (parent, name) = path.rsplit_once('/').ok_or(StorageError::InvalidPath)?;
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(StorageError::InvalidName);
+        }
+        if name.len() > MAX_NAME_LEN {
+            return Err(StorageError::NameTooLong);
+        }
+        let parent = self.resolve(parent)?;
+        match self.nodes.get(&parent).map(|n| &n.data) {
+            Some(NodeData::Directory(_)) => Ok((parent, name)),
+            Some(_) => Err(StorageError::NotADirectory),
+            None => Err(StorageError::FileNotFound),
+        }
+    }
+
+    fn entries_mut(&mut self, dir: u64) -> &mut BTreeMap<String, u64> {
+        match self.nodes.get_mut(&dir).map(|n| &mut n.data) {
+            Some(NodeData::Directory(entries)) => entries,
+            _ => unreachable!("parent checked by resolve_parent"),
+        }
+    }
+
+    fn file_mut(&mut self, inode: u64) -> Result<&mut Vec<u8>, StorageError> {
+        match self.nodes.get_mut(&inode).map(|n| &mut n.data) {
+            Some(NodeData::File(data)) => Ok(data),
+            Some(_) => Err(StorageError::NotAFile),
+            None => Err(StorageError::InvalidFd),
+        }
+    }
+
+    /// Add a new node under `path`.
+    fn insert(
+        &mut self,
+        path: &str,
+        file_type: FileType,
+        mode: u16,
+        data: NodeData,
+    ) -> Result<u64, StorageError> {
+        let (parent, name) = self.resolve_parent(path)?;
+        if self.entries_mut(parent).contains_key(name) {
+            return Err(StorageError::AlreadyExists);
+        }
+
+        let inode = self.next_inode;
+        self.next_inode += 1;
+        self.nodes
+            .insert(inode, Node::new(inode, file_type, mode, data));
+        self.entries_mut(parent).insert(name.to_string(), inode);
+        if file_type == FileType::Directory {
+            self.nodes.get_mut(&parent).unwrap().meta.nlink += 1;
+        }
+        self.touch(parent);
+        Ok(inode)
+    }
+
+    /// Record a modification of `inode`'s contents.
+    fn touch(&mut self, inode: u64) {
+        if let Some(node) = self.nodes.get_mut(&inode) {
+            node.meta.mtime = now();
+            node.meta.ctime = node.meta.mtime;
+        }
+    }
+
+    /// Drop a link to `inode`, freeing it once unreferenced.
+    fn release(&mut self, inode: u64) {
+        let Some(node) = self.nodes.get_mut(&inode) else {
+            return;
+        };
+        node.meta.nlink = node.meta.nlink.saturating_sub(1);
+        node.meta.ctime = now();
+        if node.meta.nlink == 0 && !self.handles.values().any(|&i| i == inode) {
+            self.remove(inode);
+        }
+    }
+
+    /// Free a node and the blocks it holds.
+    fn remove(&mut self, inode: u64) {
+        if let Some(node) = self.nodes.remove(&inode) {
+            self.used_blocks -= node.meta.blocks;
+        }
+    }
+
+    fn set_size(&mut self, inode: u64, size: u64) -> Result<(), StorageError> {
+        let len = usize::try_from(size).map_err(|_| StorageError::FilesystemFull)?;
+        let blocks = size.div_ceil(BLOCK_SIZE);
+        let old_blocks = self.nodes.get(&inode).map_or(0, |n| n.meta.blocks);
+        let used_blocks = self.used_blocks - old_blocks + blocks;
+        if blocks > old_blocks && self.max_blocks.is_some_and(|max| used_blocks > max) {
+            return Err(StorageError::NoSpace);
+        }
+
+        let data = self.file_mut(inode)?;
+        data.resize(len, 0);
+        self.used_blocks = used_blocks;
+        let node = self.nodes.get_mut(&inode).unwrap();
+        node.meta.size = size;
+        node.meta.blocks = blocks;
+        self.touch(inode);
+        Ok(())
+    }
+}
+
+/// An in-memory filesystem.
+pub struct TmpFs {
+    inner: Mutex<TmpFsInner>,
+}
+
+impl TmpFs {
+    /// Create an empty tmpfs.
+    pub fn new() -> Self {
+        let mut nodes = BTreeMap::new();
+        let mut root = Node::new(
+            ROOT_INODE,
+            FileType::Directory,
+            FilePermissions::DEFAULT_DIR.0,
+            NodeData::Directory(BTreeMap::new()),
+        );
+        root.meta.nlink = 2;
+        nodes.insert(ROOT_INODE, root);
+
+        TmpFs {
+            inner: Mutex::new(TmpFsInner {
+                nodes,
+                next_inode: ROOT_INODE + 1,
+                handles: BTreeMap::new(),
+                next_handle: 1,
+                used_blocks: 0,
+                max_blocks: None,
+            }),
+        }
+    }
+
+    /// Create an empty tmpfs holding at most `max_bytes` of file data.
+    /// Growing a file past the limit fails with `NoSpace`.
+    pub fn with_size_limit(max_bytes: u64) -> Self {
+        let mut fs = Self::new();
+        fs.inner.get_mut().max_blocks = Some(max_bytes / BLOCK_SIZE);
+        fs
+    }
+}
+
+impl Default for TmpFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Filesystem for TmpFs {
+    fn fs_type(&self) -> &str {
+        "tmpfs"
+    }
+
+    fn statfs(&self) -> Result<FsStats, StorageError> {
+        let inner = self.inner.lock();
+        let total = inner.max_blocks.unwrap_or(inner.used_blocks);
+        let free = total.saturating_sub(inner.used_blocks);
+        Ok(FsStats {
+            fs_type: TMPFS_MAGIC,
+            block_size: BLOCK_SIZE as u32,
+            total_blocks: total,
+            free_blocks: free,
+            available_blocks: free,
+            total_inodes: inner.nodes.len() as u64,
+            ..FsStats::default()
+        })
+    }
+
+    fn lookup(&self, path: &str) -> Result<FileMetadata, StorageError> {
+        let inner = self.inner.lock();
+        let inode = inner.resolve(path)?;
+        Ok(inner.nodes[&inode].meta.clone())
+    }
+
+    fn readdir(&self, path: &str, offset: u64) -> Result<Vec<DirEntry>, StorageError> {
+        let inner = self.inner.lock();
+        let inode = inner.resolve(path)?;
+        let NodeData::Directory(entries) = &inner.nodes[&inode].data else {
+            return Err(StorageError::NotADirectory);
+        };
+
+        Ok(entries
+            .iter()
+            .skip(offset as usize)
+            .map(|(name, &inode)| {
+                let mut entry = DirEntry {
+                    name: [0; 256],
+                    name_len: name.len(),
+                    inode,
+                    file_type: inner.nodes[&inode].meta.file_type,
+                };
+                entry.name[..name.len()].copy_from_slice(name.as_bytes());
+                entry
+            })
+            .collect())
+    }
+
+    fn create(&self, path: &str, mode: u16) -> Result<u64, StorageError> {
+        self.inner
+            .lock()
+            .insert(path, FileType::Regular, mode, NodeData::File(Vec::new()))
+    }
+
+    fn mkdir(&self, path: &str, mode: u16) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = inner.insert(
+            path,
+            FileType::Directory,
+            mode,
+            NodeData::Directory(BTreeMap::new()),
+        )?;
+        inner.nodes.get_mut(&inode).unwrap().meta.nlink = 2;
+        Ok(())
+    }
+
+    fn unlink(&self, path: &str) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let (parent, name) = inner.resolve_parent(path)?;
+        let inode = *inner
+            .entries_mut(parent)
+            .get(name)
+            .ok_or(StorageError::FileNotFound)?;
+        if let NodeData::Directory(_) = inner.nodes[&inode].data {
+            return Err(StorageError::NotAFile);
+        }
+        inner.entries_mut(parent).remove(name);
+        inner.touch(parent);
+        inner.release(inode);
+        Ok(())
+    }
+
+    fn rmdir(&self, path: &str) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let (parent, name) = inner.resolve_parent(path)?;
+        let inode = *inner
+            .entries_mut(parent)
+            .get(name)
+            .ok_or(StorageError::FileNotFound)?;
+        match &inner.nodes[&inode].data {
+            NodeData::Directory(entries) if !entries.is_empty() => {
+                return Err(StorageError::DirectoryNotEmpty)
+            }
+            NodeData::Directory(_) => {}
+            _ => return Err(StorageError::NotADirectory),
+        }
+        inner.entries_mut(parent).remove(name);
+        inner.remove(inode);
+        inner.nodes.get_mut(&parent).unwrap().meta.nlink -= 1;
+        inner.touch(parent);
+        Ok(())
+    }
+
+    fn rename(&self, old_path: &str, new_path: &str) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let (old_parent, old_name) = inner.resolve_parent(old_path)?;
+        let (new_parent, new_name) = inner.resolve_parent(new_path)?;
+        let inode = *inner
+            .entries_mut(old_parent)
+            .get(old_name)
+            .ok_or(StorageError::FileNotFound)?;
+        let is_dir = inner.nodes[&inode].meta.file_type == FileType::Directory;
+
+        // A directory cannot be moved inside itself
+        if is_dir {
+            let mut ancestor = new_parent;
+            while ancestor != ROOT_INODE {
+                if ancestor == inode {
+                    return Err(StorageError::InvalidArgument);
+                }
+                ancestor = inner
+                    .nodes
+                    .iter()
+                    .find(|(_, n)| {
+                        matches!(&n.data, NodeData::Directory(e) if e.values().any(|&i| i == ancestor))
+                    })
+                    .map_or(ROOT_INODE, |(&i, _)| i);
+            }
+        }
+
+        // Replace an existing target of a compatible type
+        if let Some(&target) = inner.entries_mut(new_parent).get(new_name) {
+            if target == inode {
+                return Ok(());
+            }
+            match (&inner.nodes[&target].data, is_dir) {
+                (NodeData::Directory(entries), true) if !entries.is_empty() => {
+                    return Err(StorageError::DirectoryNotEmpty)
+                }
+                (NodeData::Directory(_), true) => {
+                    inner.remove(target);
+                    inner.nodes.get_mut(&new_parent).unwrap().meta.nlink -= 1;
+                }
+                (NodeData::Directory(_), false) => return Err(StorageError::NotAFile),
+                (_, true) => return Err(StorageError::NotADirectory),
+                (_, false) => inner.release(target),
+            }
+        }
+
+        inner.entries_mut(old_parent).remove(old_name);
+        inner
+            .entries_mut(new_parent)
+            .insert(new_name.to_string(), inode);
+        if is_dir && old_parent != new_parent {
+            inner.nodes.get_mut(&old_parent).unwrap().meta.nlink -= 1;
+            inner.nodes.get_mut(&new_parent).unwrap().meta.nlink += 1;
+        }
+        inner.touch(old_parent);
+        inner.touch(new_parent);
+        inner.nodes.get_mut(&inode).unwrap().meta.ctime = now();
+        Ok(())
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = inner.insert(
+            link_path,
+            FileType::Symlink,
+            0o777,
+            NodeData::Symlink(target.to_string()),
+        )?;
+        inner.nodes.get_mut(&inode).unwrap().meta.size = target.len() as u64;
+        Ok(())
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, StorageError> {
+        let inner = self.inner.lock();
+        let inode = inner.resolve(path)?;
+        match &inner.nodes[&inode].data {
+            NodeData::Symlink(target) => Ok(target.clone()),
+            _ => Err(StorageError::InvalidArgument),
+        }
+    }
+
+    fn link(&self, old_path: &str, new_path: &str) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = inner.resolve(old_path)?;
+        if inner.nodes[&inode].meta.file_type == FileType::Directory {
+            return Err(StorageError::PermissionDenied);
+        }
+        let (parent, name) = inner.resolve_parent(new_path)?;
+        if inner.entries_mut(parent).contains_key(name) {
+            return Err(StorageError::AlreadyExists);
+        }
+        inner.entries_mut(parent).insert(name.to_string(), inode);
+        inner.touch(parent);
+        let meta = &mut inner.nodes.get_mut(&inode).unwrap().meta;
+        meta.nlink += 1;
+        meta.ctime = now();
+        Ok(())
+    }
+
+    fn setattr(&self, path: &str, attr: &FileMetadata) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = inner.resolve(path)?;
+        let meta = &mut inner.nodes.get_mut(&inode).unwrap().meta;
+        meta.permissions = attr.permissions;
+        meta.uid = attr.uid;
+        meta.gid = attr.gid;
+        meta.atime = attr.atime;
+        meta.mtime = attr.mtime;
+        meta.ctime = attr.ctime;
+        meta.crtime = attr.crtime;
+        Ok(())
+    }
+
+    fn open(&self, path: &str, flags: OpenFlags) -> Result<u64, StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = match inner.resolve(path) {
+            Ok(_) if flags.contains(OpenFlags::CREATE | OpenFlags::EXCLUSIVE) => {
+                return Err(StorageError::AlreadyExists)
+            }
+            Ok(inode) => inode,
+            Err(StorageError::FileNotFound) if flags.contains(OpenFlags::CREATE) => inner.insert(
+                path,
+                FileType::Regular,
+                FilePermissions::DEFAULT_FILE.0,
+                NodeData::File(Vec::new()),
+            )?,
+            Err(e) => return Err(e),
+        };
+
+        match inner.nodes[&inode].data {
+            NodeData::Directory(_) if flags.contains(OpenFlags::WRITE) => {
+                return Err(StorageError::NotAFile)
+            }
+            NodeData::File(_) if flags.contains(OpenFlags::DIRECTORY) => {
+                return Err(StorageError::NotADirectory)
+            }
+            NodeData::File(_) if flags.contains(OpenFlags::TRUNCATE) => inner.set_size(inode, 0)?,
+            _ => {}
+        }
+
+        let handle = inner.next_handle;
+        inner.next_handle += 1;
+        inner.handles.insert(handle, inode);
+        Ok(handle)
+    }
+
+    fn close(&self, handle: u64) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = inner
+            .handles
+            .remove(&handle)
+            .ok_or(StorageError::InvalidFd)?;
+        // Free files unlinked while open
+        if inner.nodes.get(&inode).is_some_and(|n| n.meta.nlink == 0)
+            && !inner.handles.values().any(|&i| i == inode)
+        {
+            inner.remove(inode);
+        }
+        Ok(())
+    }
+
+    fn read(&self, handle: u64, offset: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = *inner.handles.get(&handle).ok_or(StorageError::InvalidFd)?;
+        let data = inner.file_mut(inode)?;
+        let start = (offset as usize).min(data.len());
+        let len = buffer.len().min(data.len() - start);
+        buffer[..len].copy_from_slice(&data[start..start + len]);
+        Ok(len)
+    }
+
+    fn write(&self, handle: u64, offset: u64, data: &[u8]) -> Result<usize, StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = *inner.handles.get(&handle).ok_or(StorageError::InvalidFd)?;
+        let end = offset
+            .checked_add(data.len() as u64)
+            .ok_or(StorageError::InvalidArgument)?;
+        if end > inner.nodes[&inode].meta.size {
+            inner.set_size(inode, end)?;
+        }
+        let file = inner.file_mut(inode)?;
+        file[offset as usize..end as usize].copy_from_slice(data);
+        inner.touch(inode);
+        Ok(data.len())
+    }
+
+    fn flush(&self, handle: u64) -> Result<(), StorageError> {
+        self.fsync(handle, false)
+    }
+
+    fn fsync(&self, handle: u64, _data_only: bool) -> Result<(), StorageError> {
+        // Nothing to write back
+        let inner = self.inner.lock();
+        inner
+            .handles
+            .contains_key(&handle)
+            .then_some(())
+            .ok_or(StorageError::InvalidFd)
+    }
+
+    fn truncate(&self, path: &str, size: u64) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = inner.resolve(path)?;
+        inner.set_size(inode, size)
+    }
+
+    fn fallocate(&self, handle: u64, offset: u64, len: u64) -> Result<(), StorageError> {
+        let mut inner = self.inner.lock();
+        let inode = *inner.handles.get(&handle).ok_or(StorageError::InvalidFd)?;
+        let end = offset
+            .checked_add(len)
+            .ok_or(StorageError::InvalidArgument)?;
+        if end > inner.nodes[&inode].meta.size {
+            inner.set_size(inode, end)?;
+        }
+        Ok(())
+    }
+
+    fn sync(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    /// Clock that advances on every reading, so each change gets a
+    /// later timestamp than the last.
+    fn ticking_clock() -> u64 {
+        static TICKS: AtomicU64 = AtomicU64::new(1);
+        TICKS.fetch_add(1, Ordering::Relaxed)
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let fs = TmpFs::with_size_limit(4 * BLOCK_SIZE);
+        let handle = fs.open("/a", OpenFlags::CREATE | OpenFlags::WRITE).unwrap();
+        fs.write(handle, 0, &[1; 3 * BLOCK_SIZE as usize]).unwrap();
+        assert_eq!(fs.statfs().unwrap().free_blocks, 1);
+
+        // One more block fits, a second does not
+        let other = fs.open("/b", OpenFlags::CREATE | OpenFlags::WRITE).unwrap();
+        fs.write(other, 0, &[2; BLOCK_SIZE as usize]).unwrap();
+        assert!(matches!(
+            fs.write(other, BLOCK_SIZE, &[2]),
+            Err(StorageError::NoSpace)
+        ));
+        assert!(matches!(
+            fs.fallocate(handle, 0, 4 * BLOCK_SIZE),
+            Err(StorageError::NoSpace)
+        ));
+        assert_eq!(fs.lookup("/b").unwrap().size, BLOCK_SIZE);
+
+        // Shrinking and unlinking give the space back
+        fs.truncate("/a", 0).unwrap();
+        fs.write(other, BLOCK_SIZE, &[2]).unwrap();
+        fs.close(other).unwrap();
+        fs.unlink("/b").unwrap();
+        assert_eq!(fs.statfs().unwrap().free_blocks, 4);
+        fs.close(handle).unwrap();
+    }
+
+    #[test]
+    fn test_nlink_counts() {
+        let fs = TmpFs::new();
+        fs.mkdir("/dir", 0o755).unwrap();
+        fs.mkdir("/dir/sub", 0o755).unwrap();
+        assert_eq!(fs.lookup("/").unwrap().nlink, 3);
+        assert_eq!(fs.lookup("/dir").unwrap().nlink, 3);
+        assert_eq!(fs.lookup("/dir/sub").unwrap().nlink, 2);
+
+        fs.create("/dir/file", 0o644).unwrap();
+        assert_eq!(fs.lookup("/dir/file").unwrap().nlink, 1);
+        fs.link("/dir/file", "/hard").unwrap();
+        assert_eq!(fs.lookup("/hard").unwrap().nlink, 2);
+        fs.unlink("/dir/file").unwrap();
+        assert_eq!(fs.lookup("/hard").unwrap().nlink, 1);
+
+        // Moving a directory moves its ".." link
+        fs.rename("/dir/sub", "/sub").unwrap();
+        assert_eq!(fs.lookup("/dir").unwrap().nlink, 2);
+        assert_eq!(fs.lookup("/").unwrap().nlink, 4);
+        fs.rmdir("/sub").unwrap();
+        assert_eq!(fs.lookup("/").unwrap().nlink, 3);
+    }
+
+    #[test]
+    fn test_mtime_updates() {
+        set_clock(ticking_clock);
+        let fs = TmpFs::new();
+        fs.mkdir("/dir", 0o755).unwrap();
+        let dir = fs.lookup("/dir").unwrap().mtime;
+
+        let handle = fs
+            .open("/dir/file", OpenFlags::CREATE | OpenFlags::WRITE)
+            .unwrap();
+        let created = fs.lookup("/dir/file").unwrap().mtime;
+        assert!(fs.lookup("/dir").unwrap().mtime > dir);
+
+        fs.write(handle, 0, b"data").unwrap();
+        let written = fs.lookup("/dir/file").unwrap();
+        assert!(written.mtime > created);
+        assert_eq!(written.ctime, written.mtime);
+
+        // Reading changes nothing
+        let mut buf = [0u8; 4];
+        fs.read(handle, 0, &mut buf).unwrap();
+        assert_eq!(fs.lookup("/dir/file").unwrap().mtime, written.mtime);
+        fs.close(handle).unwrap();
+
+        let dir = fs.lookup("/dir").unwrap().mtime;
+        fs.unlink("/dir/file").unwrap();
+        assert!(fs.lookup("/dir").unwrap().mtime > dir);
+    }
+
+    #[test]
+    fn test_rmdir_not_empty() {
+        let fs = TmpFs::new();
+        fs.mkdir("/dir", 0o755).unwrap();
+        fs.create("/dir/file", 0o644).unwrap();
+        assert!(matches!(
+            fs.rmdir("/dir"),
+            Err(StorageError::DirectoryNotEmpty)
+        ));
+        fs.mkdir("/other", 0o755).unwrap();
+        fs.create("/other/file", 0o644).unwrap();
+        assert!(matches!(
+            fs.rename("/dir", "/other"),
+            Err(StorageError::DirectoryNotEmpty)
+        ));
+        assert!(matches!(
+            fs.rmdir("/dir/file"),
+            Err(StorageError::NotADirectory)
+        ));
+
+        fs.unlink("/dir/file").unwrap();
+        fs.rmdir("/dir").unwrap();
+        assert!(matches!(fs.lookup("/dir"), Err(StorageError::FileNotFound)));
+    }
+}
@@ -359,6 +359,13 @@ pub fn mount(
     vfs::mount(device, mount_point, fs_type, flags)
 }
 
+/// List the partitions of a registered block device.
+pub fn list_partitions(device: &str) -> Result<Vec<partition::PartitionInfo>, StorageError> {
+    let index = driver::find_device(device).ok_or(StorageError::DeviceNotFound)?;
+    let device = driver::get_device(index).ok_or(StorageError::DeviceNotFound)?;
+    partition::read_partitions(device)
+}
+
 /// Unmount a filesystem.
 pub fn unmount(mount_point: &str) -> Result<(), StorageError> {
     vfs::unmount(mount_point)
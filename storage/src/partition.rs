@@ -3,8 +3,17 @@
 //! This module provides support for parsing partition tables:
 //! - MBR (Master Boot Record)
 //! - GPT (GUID Partition Table)
+//!
+//! [`read_partitions`] lists the partitions of either kind of table, and
+//! [`PartitionDevice`] exposes one of them as a block device so it can be
+//! mounted.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
-use crate::StorageError;
+use crate::driver::BlockDevice;
+use crate::{BlockDeviceInfo, StorageError};
 
 /// Partition type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,7 +48,9 @@ pub enum PartitionType {
     EfiSystem,
     /// GPT protective MBR.
     GptProtective,
-    /// Unknown type.
+    /// Microsoft Basic Data (GPT; FAT or NTFS).
+    BasicData,
+    /// Unknown type (the MBR type byte, 0 for an unlisted GPT type GUID).
     Unknown(u8),
 }
 
@@ -76,6 +87,18 @@ impl PartitionType {
     pub fn is_gpt(&self) -> bool {
         matches!(self, PartitionType::GptProtective)
     }
+
+    /// Partition type of a GPT type GUID.
+    pub fn from_gpt_guid(guid: &[u8; 16]) -> Self {
+        match *guid {
+            GptPartitionEntry::TYPE_EMPTY => PartitionType::Empty,
+            GptPartitionEntry::TYPE_EFI_SYSTEM => PartitionType::EfiSystem,
+            GptPartitionEntry::TYPE_MICROSOFT_BASIC => PartitionType::BasicData,
+            GptPartitionEntry::TYPE_LINUX_FS => PartitionType::Linux,
+            GptPartitionEntry::TYPE_LINUX_SWAP => PartitionType::LinuxSwap,
+            _ => PartitionType::Unknown(0),
+        }
+    }
 }
 
 /// MBR partition entry.
@@ -213,9 +236,12 @@ impl Mbr {
         })
     }
 
-    /// Check if this is a GPT disk.
+    /// Check if this is a GPT disk: a protective MBR has a GPT
+    /// protective entry starting at LBA 1.
     pub fn is_gpt(&self) -> bool {
-        self.partitions[0].get_type().is_gpt()
+        self.partitions
+            .iter()
+            .any(|entry| entry.get_type().is_gpt() && entry.start_lba == 1)
     }
 }
 
@@ -258,6 +284,10 @@ impl GptHeader {
     pub const SIZE: usize = 92;
     /// GPT signature.
     pub const SIGNATURE: &'static [u8; 8] = b"EFI PART";
+    /// Offset of the header CRC32 field.
+    const CRC32_OFFSET: usize = 16;
+    /// Largest partition entry array accepted.
+    const MAX_ENTRIES_LEN: usize = 1024 * 1024;
 
     /// Parse GPT header from bytes.
     pub fn from_bytes(data: &[u8]) -> Result<Self, StorageError> {
@@ -302,6 +332,29 @@ impl GptHeader {
             partition_entries_crc32: u32::from_le_bytes([data[88], data[89], data[90], data[91]]),
         })
     }
+
+    /// Check the header CRC32 against `data`, the sector the header was
+    /// parsed from.
+    pub fn verify_crc(&self, data: &[u8]) -> bool {
+        let size = self.header_size as usize;
+        if size < Self::SIZE || size > data.len() {
+            return false;
+        }
+        let mut header = data[..size].to_vec();
+        header[Self::CRC32_OFFSET..Self::CRC32_OFFSET + 4].fill(0);
+        crc32(&header) == self.header_crc32
+    }
+
+    /// Size of the partition entry array in bytes, `None` if the header
+    /// describes an unreasonable one.
+    pub fn entries_len(&self) -> Option<usize> {
+        let size = self.partition_entry_size as usize;
+        if size < GptPartitionEntry::SIZE || !size.is_power_of_two() {
+            return None;
+        }
+        let len = size.checked_mul(self.num_partition_entries as usize)?;
+        (len <= Self::MAX_ENTRIES_LEN).then_some(len)
+    }
 }
 
 /// GPT partition entry.
@@ -440,7 +493,7 @@ impl GptPartitionEntry {
 /// Partition information.
 #[derive(Debug, Clone)]
 pub struct PartitionInfo {
-    /// Partition index.
+    /// Partition number, counting table slots from 1.
     pub index: u8,
     /// Partition type.
     pub part_type: PartitionType,
@@ -456,6 +509,24 @@ pub struct PartitionInfo {
     pub name: Option<[u16; 36]>,
 }
 
+impl PartitionInfo {
+    /// Last LBA of the partition.
+    pub fn end_lba(&self) -> u64 {
+        (self.start_lba + self.sectors).saturating_sub(1)
+    }
+
+    /// Partition name (GPT), decoded from UTF-16 up to the first NUL.
+    pub fn name_string(&self) -> Option<String> {
+        let name = self.name?;
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        Some(
+            char::decode_utf16(name[..len].iter().copied())
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect(),
+        )
+    }
+}
+
 /// Partition table type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PartitionTableType {
@@ -482,3 +553,470 @@ pub fn detect_table_type(sector0: &[u8], sector1: &[u8]) -> PartitionTableType {
 
     PartitionTableType::None
 }
+
+/// Read the partition table of `device`.
+///
+/// An MBR disk lists its primary partitions. A GPT disk lists the used
+/// entries of the primary GPT, or of the backup GPT in the last LBA when
+/// the primary header or entry array fails its CRC. A disk without a
+/// partition table has no partitions.
+pub fn read_partitions(device: &dyn BlockDevice) -> Result<Vec<PartitionInfo>, StorageError> {
+    let info = device.info();
+    let block_size = info.block_size as usize;
+    if block_size < Mbr::SIZE {
+        return Err(StorageError::InvalidArgument);
+    }
+
+    let mut sector = vec![0u8; block_size];
+    device.read_blocks(0, &mut sector)?;
+    let Ok(mbr) = Mbr::from_bytes(&sector) else {
+        return Ok(Vec::new());
+    };
+    if !mbr.is_gpt() {
+        return Ok(mbr_partitions(&mbr));
+    }
+
+    let last_lba = info
+        .total_blocks
+        .checked_sub(1)
+        .ok_or(StorageError::InvalidFilesystem)?;
+    let (header, entries) = match read_gpt(device, 1, last_lba) {
+        Ok(gpt) => gpt,
+        Err(_) => read_gpt(device, last_lba, last_lba)?,
+    };
+
+    let entry_size = header.partition_entry_size as usize;
+    let mut partitions = Vec::new();
+    for (i, data) in entries.chunks_exact(entry_size).enumerate() {
+        let entry = GptPartitionEntry::from_bytes(data)?;
+        if !entry.is_valid() || entry.size() == 0 {
+            continue;
+        }
+        let Ok(index) = u8::try_from(i + 1) else {
+            break;
+        };
+        partitions.push(PartitionInfo {
+            index,
+            part_type: PartitionType::from_gpt_guid(&entry.type_guid),
+            start_lba: entry.start_lba,
+            sectors: entry.size(),
+            bootable: entry.legacy_bootable(),
+            type_guid: Some(entry.type_guid),
+            name: Some(entry.name),
+        });
+    }
+    Ok(partitions)
+}
+
+/// Primary partitions of an MBR; extended partitions are not followed.
+fn mbr_partitions(mbr: &Mbr) -> Vec<PartitionInfo> {
+    let entries = mbr.partitions;
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.is_valid() && !entry.get_type().is_extended())
+        .map(|(i, entry)| PartitionInfo {
+            index: i as u8 + 1,
+            part_type: entry.get_type(),
+            start_lba: entry.start_lba as u64,
+            sectors: entry.num_sectors as u64,
+            bootable: entry.is_bootable(),
+            type_guid: None,
+            name: None,
+        })
+        .collect()
+}
+
+/// Read and check the GPT header at `lba` and its partition entry array.
+fn read_gpt(
+    device: &dyn BlockDevice,
+    lba: u64,
+    last_lba: u64,
+) -> Result<(GptHeader, Vec<u8>), StorageError> {
+    let block_size = device.info().block_size as usize;
+    let mut sector = vec![0u8; block_size];
+    device.read_blocks(lba, &mut sector)?;
+
+    let header = GptHeader::from_bytes(&sector)?;
+    if !header.verify_crc(&sector) || header.current_lba != lba {
+        return Err(StorageError::InvalidFilesystem);
+    }
+
+    let len = header
+        .entries_len()
+        .ok_or(StorageError::InvalidFilesystem)?;
+    let blocks = len.div_ceil(block_size) as u64;
+    if header.partition_entry_lba == 0
+        || header
+            .partition_entry_lba
+            .checked_add(blocks)
+            .is_none_or(|end| end > last_lba + 1)
+    {
+        return Err(StorageError::InvalidFilesystem);
+    }
+
+    let mut entries = vec![0u8; blocks as usize * block_size];
+    device.read_blocks(header.partition_entry_lba, &mut entries)?;
+    entries.truncate(len);
+    if crc32(&entries) != header.partition_entries_crc32 {
+        return Err(StorageError::InvalidFilesystem);
+    }
+    Ok((header, entries))
+}
+
+/// CRC32 lookup table (IEEE polynomial, reflected).
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC32 as used by GPT headers and entry arrays.
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Split a partition device name into the disk name and partition
+/// number, following the Linux convention: `sda1` is partition 1 of
+/// `sda`, and a disk whose name ends in a digit puts a `p` before the
+/// number (`nvme0n1p2`).
+pub fn split_partition_name(name: &str) -> Option<(&str, u8)> {
+    let disk = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number: u8 = name[disk.len()..].parse().ok()?;
+    let disk = match disk.strip_suffix('p') {
+        Some(base) if base.ends_with(|c: char| c.is_ascii_digit()) => base,
+        _ => disk,
+    };
+    (number > 0 && !disk.is_empty()).then_some((disk, number))
+}
+
+/// One partition of a block device, exposed as a block device of its own.
+pub struct PartitionDevice {
+    /// The whole disk.
+    device: &'static dyn BlockDevice,
+    /// First LBA of the partition on the disk.
+    start_lba: u64,
+    /// Partition size in blocks.
+    sectors: u64,
+    /// Device name.
+    name: [u8; 32],
+    /// Name length.
+    name_len: usize,
+}
+
+impl PartitionDevice {
+    /// Expose `partition` of `device` under `name`.
+    pub fn new(device: &'static dyn BlockDevice, partition: &PartitionInfo, name: &str) -> Self {
+        let mut name_buf = [0u8; 32];
+        let name_len = name.len().min(name_buf.len());
+        name_buf[..name_len].copy_from_slice(&name.as_bytes()[..name_len]);
+        PartitionDevice {
+            device,
+            start_lba: partition.start_lba,
+            sectors: partition.sectors,
+            name: name_buf,
+            name_len,
+        }
+    }
+
+    /// Map `blocks` blocks starting at partition block `start` to a disk
+    /// LBA, failing if they run past the end of the partition.
+    fn disk_lba(&self, start: u64, blocks: u64) -> Result<u64, StorageError> {
+        match start.checked_add(blocks) {
+            Some(end) if end <= self.sectors => Ok(self.start_lba + start),
+            _ => Err(StorageError::InvalidBlock),
+        }
+    }
+
+    /// Blocks spanned by a buffer of `len` bytes.
+    fn blocks(&self, len: usize) -> u64 {
+        let block_size = self.device.info().block_size.max(1) as usize;
+        len.div_ceil(block_size) as u64
+    }
+}
+
+impl BlockDevice for PartitionDevice {
+    fn info(&self) -> BlockDeviceInfo {
+        BlockDeviceInfo {
+            name: self.name,
+            name_len: self.name_len,
+            total_blocks: self.sectors,
+            ..self.device.info()
+        }
+    }
+
+    fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+        let lba = self.disk_lba(start_block, self.blocks(buffer.len()))?;
+        self.device.read_blocks(lba, buffer)
+    }
+
+    fn write_blocks(&self, start_block: u64, data: &[u8]) -> Result<usize, StorageError> {
+        let lba = self.disk_lba(start_block, self.blocks(data.len()))?;
+        self.device.write_blocks(lba, data)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        self.device.flush()
+    }
+
+    fn discard(&self, start_block: u64, num_blocks: u64) -> Result<(), StorageError> {
+        let lba = self.disk_lba(start_block, num_blocks)?;
+        self.device.discard(lba, num_blocks)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.device.is_ready()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use spin::Mutex;
+
+    const SECTOR: usize = 512;
+    const DISK_SECTORS: u64 = 256;
+    const ENTRIES: u32 = 128;
+    /// Sectors taken by the partition entry array.
+    const ENTRY_SECTORS: u64 = (ENTRIES as u64 * GptPartitionEntry::SIZE as u64) / SECTOR as u64;
+
+    struct MemDisk(Mutex<Vec<u8>>);
+
+    impl BlockDevice for MemDisk {
+        fn info(&self) -> BlockDeviceInfo {
+            BlockDeviceInfo {
+                name: [0; 32],
+                name_len: 0,
+                block_size: SECTOR as u32,
+                total_blocks: (self.0.lock().len() / SECTOR) as u64,
+                read_only: false,
+                supports_trim: false,
+                optimal_io_size: 1,
+                physical_block_size: SECTOR as u32,
+            }
+        }
+
+        fn read_blocks(&self, start_block: u64, buffer: &mut [u8]) -> Result<usize, StorageError> {
+            let disk = self.0.lock();
+            let start = start_block as usize * SECTOR;
+            buffer.copy_from_slice(&disk[start..start + buffer.len()]);
+            Ok(buffer.len())
+        }
+
+        fn write_blocks(&self, start_block: u64, data: &[u8]) -> Result<usize, StorageError> {
+            let mut disk = self.0.lock();
+            let start = start_block as usize * SECTOR;
+            disk[start..start + data.len()].copy_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&self) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn discard(&self, _start_block: u64, _num_blocks: u64) -> Result<(), StorageError> {
+            Ok(())
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+    }
+
+    fn mbr_entry(disk: &mut [u8], slot: usize, part_type: u8, start: u32, sectors: u32) {
+        let at = 446 + slot * MbrPartitionEntry::SIZE;
+        disk[at + 4] = part_type;
+        disk[at + 8..at + 12].copy_from_slice(&start.to_le_bytes());
+        disk[at + 12..at + 16].copy_from_slice(&sectors.to_le_bytes());
+        disk[510] = 0x55;
+        disk[511] = 0xAA;
+    }
+
+    /// Write a GPT header at `lba` whose entry array starts at `entries_lba`.
+    fn gpt_header(disk: &mut [u8], lba: u64, backup_lba: u64, entries_lba: u64, entries_crc: u32) {
+        let at = lba as usize * SECTOR;
+        let header = &mut disk[at..at + GptHeader::SIZE];
+        header[0..8].copy_from_slice(GptHeader::SIGNATURE);
+        header[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        header[12..16].copy_from_slice(&(GptHeader::SIZE as u32).to_le_bytes());
+        header[24..32].copy_from_slice(&lba.to_le_bytes());
+        header[32..40].copy_from_slice(&backup_lba.to_le_bytes());
+        header[40..48].copy_from_slice(&(2 + ENTRY_SECTORS).to_le_bytes());
+        header[48..56].copy_from_slice(&(DISK_SECTORS - 2 - ENTRY_SECTORS).to_le_bytes());
+        header[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+        header[80..84].copy_from_slice(&ENTRIES.to_le_bytes());
+        header[84..88].copy_from_slice(&(GptPartitionEntry::SIZE as u32).to_le_bytes());
+        header[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+        let crc = crc32(header);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// A GPT disk with an EFI system partition in slot 0, an empty slot 1
+    /// and a Linux partition in slot 2, with both primary and backup GPT.
+    fn gpt_disk() -> &'static MemDisk {
+        let mut disk = vec![0u8; DISK_SECTORS as usize * SECTOR];
+        mbr_entry(&mut disk, 0, 0xEE, 1, DISK_SECTORS as u32 - 1);
+
+        let mut entries = vec![0u8; ENTRY_SECTORS as usize * SECTOR];
+        for (slot, type_guid, start, end, name) in [
+            (
+                0,
+                GptPartitionEntry::TYPE_EFI_SYSTEM,
+                40u64,
+                79u64,
+                "EFI system",
+            ),
+            (
+                2,
+                GptPartitionEntry::TYPE_LINUX_FS,
+                80,
+                199,
+                "root \u{00E9}",
+            ),
+        ] {
+            let entry = &mut entries[slot * GptPartitionEntry::SIZE..][..GptPartitionEntry::SIZE];
+            entry[0..16].copy_from_slice(&type_guid);
+            entry[16] = slot as u8 + 1;
+            entry[32..40].copy_from_slice(&start.to_le_bytes());
+            entry[40..48].copy_from_slice(&end.to_le_bytes());
+            for (i, unit) in name.encode_utf16().enumerate() {
+                entry[56 + i * 2..58 + i * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+        let entries_crc = crc32(&entries);
+
+        let backup_entries = DISK_SECTORS - 1 - ENTRY_SECTORS;
+        for entries_lba in [2, backup_entries] {
+            let at = entries_lba as usize * SECTOR;
+            disk[at..at + entries.len()].copy_from_slice(&entries);
+        }
+        gpt_header(&mut disk, 1, DISK_SECTORS - 1, 2, entries_crc);
+        gpt_header(&mut disk, DISK_SECTORS - 1, 1, backup_entries, entries_crc);
+
+        Box::leak(Box::new(MemDisk(Mutex::new(disk))))
+    }
+
+    fn check_gpt_partitions(partitions: &[PartitionInfo]) {
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].index, 1);
+        assert_eq!(partitions[0].part_type, PartitionType::EfiSystem);
+        assert_eq!(partitions[0].start_lba, 40);
+        assert_eq!(partitions[0].end_lba(), 79);
+        assert_eq!(partitions[0].name_string().as_deref(), Some("EFI system"));
+        assert_eq!(partitions[1].index, 3);
+        assert_eq!(partitions[1].part_type, PartitionType::Linux);
+        assert_eq!(partitions[1].sectors, 120);
+        assert_eq!(
+            partitions[1].name_string().as_deref(),
+            Some("root \u{00E9}")
+        );
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_gpt_partitions() {
+        let disk = gpt_disk();
+        check_gpt_partitions(&read_partitions(disk).unwrap());
+
+        // A damaged primary header falls back to the backup GPT
+        disk.0.lock()[SECTOR + 40] ^= 0xFF;
+        check_gpt_partitions(&read_partitions(disk).unwrap());
+
+        // So does a damaged primary entry array
+        let disk = gpt_disk();
+        disk.0.lock()[2 * SECTOR + 60] ^= 0xFF;
+        check_gpt_partitions(&read_partitions(disk).unwrap());
+
+        // With both copies damaged there is nothing to trust
+        disk.0.lock()[(DISK_SECTORS as usize - 1) * SECTOR + 40] ^= 0xFF;
+        assert_eq!(
+            read_partitions(disk).unwrap_err(),
+            StorageError::InvalidFilesystem
+        );
+    }
+
+    #[test]
+    fn test_mbr_partitions() {
+        let mut disk = vec![0u8; DISK_SECTORS as usize * SECTOR];
+        mbr_entry(&mut disk, 0, 0x0C, 2048, 100);
+        mbr_entry(&mut disk, 1, 0x05, 4096, 100);
+        mbr_entry(&mut disk, 3, 0x83, 8192, 200);
+        // A 0xEE entry not at LBA 1 is not a protective MBR
+        mbr_entry(&mut disk, 2, 0xEE, 5000, 10);
+        let disk: &'static MemDisk = Box::leak(Box::new(MemDisk(Mutex::new(disk))));
+
+        let partitions = read_partitions(disk).unwrap();
+        let found: Vec<(u8, PartitionType, u64)> = partitions
+            .iter()
+            .map(|p| (p.index, p.part_type, p.start_lba))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, PartitionType::Fat32Lba, 2048),
+                (3, PartitionType::GptProtective, 5000),
+                (4, PartitionType::Linux, 8192),
+            ]
+        );
+
+        let blank: &'static MemDisk = Box::leak(Box::new(MemDisk(Mutex::new(vec![0u8; SECTOR]))));
+        assert!(read_partitions(blank).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_partition_device() {
+        let disk = gpt_disk();
+        disk.0.lock()[80 * SECTOR] = 0x42;
+        let partitions = read_partitions(disk).unwrap();
+        let device = PartitionDevice::new(disk, &partitions[1], "vda3");
+        assert_eq!(device.info().name_str(), "vda3");
+        assert_eq!(device.info().total_blocks, 120);
+
+        let mut block = [0u8; SECTOR];
+        device.read_blocks(0, &mut block).unwrap();
+        assert_eq!(block[0], 0x42);
+        device.read_blocks(119, &mut block).unwrap();
+        assert_eq!(
+            device.read_blocks(120, &mut block),
+            Err(StorageError::InvalidBlock)
+        );
+        let mut two = [0u8; 2 * SECTOR];
+        assert_eq!(
+            device.read_blocks(119, &mut two),
+            Err(StorageError::InvalidBlock)
+        );
+    }
+
+    #[test]
+    fn test_split_partition_name() {
+        assert_eq!(split_partition_name("sda1"), Some(("sda", 1)));
+        assert_eq!(split_partition_name("vda12"), Some(("vda", 12)));
+        assert_eq!(split_partition_name("nvme0n1p2"), Some(("nvme0n1", 2)));
+        assert_eq!(split_partition_name("sdp1"), Some(("sdp", 1)));
+        assert_eq!(split_partition_name("sda"), None);
+        assert_eq!(split_partition_name("sda0"), None);
+        assert_eq!(split_partition_name("42"), None);
+    }
+}
@@ -8,6 +8,7 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use spin::RwLock;
 
+use crate::driver::BlockDevice;
 use crate::{DirEntry, FileMetadata, FileType, MountFlags, OpenFlags, SeekFrom, StorageError};
 
 /// Maximum number of mount points.
@@ -19,6 +20,9 @@ pub const MAX_PATH_LEN: usize = 4096;
 /// Maximum filename length.
 pub const MAX_NAME_LEN: usize = 255;
 
+/// Maximum number of symlinks followed while resolving one path.
+pub const MAX_SYMLINK_HOPS: usize = 40;
+
 /// Global mount table.
 static MOUNT_TABLE: RwLock<MountTable> = RwLock::new(MountTable::new());
 static FILESYSTEM_TABLE: RwLock<[Option<&'static dyn Filesystem>; MAX_MOUNTS]> =
@@ -272,6 +276,87 @@ fn get_filesystem(mount_idx: usize) -> Result<&'static dyn Filesystem, StorageEr
     table[mount_idx].ok_or(StorageError::InvalidFilesystem)
 }
 
+/// Resolve the symlinks in an absolute path, returning a path without
+/// links, `.` or `..` components. The last component is only followed
+/// with `follow_last`, and may be missing so it can be created.
+///
+/// Relative link targets resolve against the link's directory and
+/// absolute ones against the root of the mount holding the link. More
+/// than [`MAX_SYMLINK_HOPS`] links fail with `TooManySymlinks`.
+pub fn resolve_path(path: &str, follow_last: bool) -> Result<String, StorageError> {
+    if path.is_empty() || !path.starts_with('/') {
+        return Err(StorageError::InvalidPath);
+    }
+
+    let components = |path: &str| -> Vec<String> {
+        path.split('/')
+            .filter(|c| !c.is_empty())
+            .rev()
+            .map(String::from)
+            .collect()
+    };
+    let join = |resolved: &[String]| -> String {
+        let mut path = String::new();
+        for name in resolved {
+            path.push('/');
+            path.push_str(name);
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    };
+
+    let mut resolved: Vec<String> = Vec::new();
+    // Components still to walk, the next one last
+    let mut pending = components(path);
+    let mut hops = 0;
+    while let Some(name) = pending.pop() {
+        match name.as_str() {
+            "." => continue,
+            ".." => {
+                resolved.pop();
+                continue;
+            }
+            _ => resolved.push(name),
+        }
+        if pending.is_empty() && !follow_last {
+            break;
+        }
+
+        let current = join(&resolved);
+        let table = MOUNT_TABLE.read();
+        let mount_idx = table
+            .find_mount(&current)
+            .ok_or(StorageError::FileNotFound)?;
+        let mount = table.mounts[mount_idx];
+        drop(table);
+
+        let fs = get_filesystem(mount_idx)?;
+        let rel = relative_path(&mount, &current);
+        match fs.lookup(rel) {
+            Ok(meta) if meta.file_type == FileType::Symlink => {}
+            Ok(_) => continue,
+            Err(StorageError::FileNotFound) if pending.is_empty() => break,
+            Err(e) => return Err(e),
+        }
+
+        hops += 1;
+        if hops > MAX_SYMLINK_HOPS {
+            return Err(StorageError::TooManySymlinks);
+        }
+        let target = fs.readlink(rel)?;
+        resolved.pop();
+        if target.starts_with('/') {
+            resolved = components(mount.mount_point_str());
+            resolved.reverse();
+        }
+        pending.extend(components(&target));
+    }
+
+    Ok(join(&resolved))
+}
+
 /// File handle table.
 static FILE_HANDLES: RwLock<FileHandleTable> = RwLock::new(FileHandleTable::new());
 
@@ -356,22 +441,121 @@ pub fn is_mounted(mount_point: &str) -> bool {
 }
 
 /// Mount a filesystem.
+///
+/// `tmpfs` needs no device and has no size limit; pass a
+/// [`TmpFs::with_size_limit`](crate::fs::tmpfs::TmpFs::with_size_limit)
+/// to [`mount_filesystem`] for a capped one.
+///
+/// `overlayfs` stacks a fresh tmpfs over the filesystem already mounted
+/// at `mount_point`, which becomes its read-only lower layer, so mounting
+/// a disk image at `/` and then `mount("overlay", "/", "overlayfs", flags)`
+/// gives a writable root that never modifies the disk.
 pub fn mount(
     device: &str,
     mount_point: &str,
     fs_type: &str,
     flags: MountFlags,
 ) -> Result<(), StorageError> {
-    let mut table = MOUNT_TABLE.write();
+    let kind = crate::fs::FilesystemType::from_str(fs_type);
+    let overlay = kind == crate::fs::FilesystemType::Overlay;
+    let current = mounted_filesystem(mount_point);
+    if current.is_some() && !overlay {
+        return Err(StorageError::AlreadyExists);
+    }
 
-    // Check if mount point already exists
-    for mount in table.mounts.iter() {
-        if mount.active && mount.mount_point_str() == mount_point {
-            return Err(StorageError::AlreadyExists);
+    let fs: &'static dyn Filesystem = match kind {
+        crate::fs::FilesystemType::Fat32 => {
+            let fat = crate::fs::fat32::Fat32Filesystem::mount(open_block_device(device)?)?;
+            Box::leak(Box::new(fat))
         }
+        crate::fs::FilesystemType::Ext4 => {
+            let ext4 = crate::fs::ext4::Ext4Filesystem::mount(open_block_device(device)?)?;
+            Box::leak(Box::new(ext4))
+        }
+        crate::fs::FilesystemType::Tmpfs => Box::leak(Box::new(crate::fs::tmpfs::TmpFs::new())),
+        crate::fs::FilesystemType::Overlay => {
+            let lower = current.ok_or(StorageError::InvalidArgument)?;
+            let upper: &'static dyn Filesystem =
+                Box::leak(Box::new(crate::fs::tmpfs::TmpFs::new()));
+            Box::leak(Box::new(crate::fs::overlayfs::OverlayFs::new(lower, upper)))
+        }
+        _ => return Err(StorageError::Unsupported),
+    };
+
+    install_mount(device, mount_point, fs_type, fs, flags, overlay)
+}
+
+/// Open a registered block device, or a partition of one named like
+/// `sda1` or `nvme0n1p2` (see [`split_partition_name`]), for mounting.
+///
+/// [`split_partition_name`]: crate::partition::split_partition_name
+fn open_block_device(device: &str) -> Result<&'static dyn BlockDevice, StorageError> {
+    if let Some(index) = crate::driver::find_device(device) {
+        return crate::cache::CachedDevice::leak(index);
     }
 
-    let slot = table.find_free_slot().ok_or(StorageError::NoSpace)?;
+    let (disk, number) =
+        crate::partition::split_partition_name(device).ok_or(StorageError::DeviceNotFound)?;
+    let index = crate::driver::find_device(disk).ok_or(StorageError::DeviceNotFound)?;
+    let disk = crate::cache::CachedDevice::new(index)?;
+    let partition = crate::partition::read_partitions(&disk)?
+        .into_iter()
+        .find(|partition| partition.index == number)
+        .ok_or(StorageError::DeviceNotFound)?;
+    let disk: &'static dyn BlockDevice = Box::leak(Box::new(disk));
+    Ok(Box::leak(Box::new(crate::partition::PartitionDevice::new(
+        disk, &partition, device,
+    ))))
+}
+
+/// Mount an already constructed filesystem, such as an
+/// [`OverlayFs`](crate::fs::overlayfs::OverlayFs) with custom layers.
+///
+/// With `replace`, the mount at `mount_point` (typically the overlay's
+/// lower layer) is replaced instead of failing.
+pub fn mount_filesystem(
+    device: &str,
+    mount_point: &str,
+    fs: &'static dyn Filesystem,
+    flags: MountFlags,
+    replace: bool,
+) -> Result<(), StorageError> {
+    install_mount(device, mount_point, fs.fs_type(), fs, flags, replace)
+}
+
+/// Get the filesystem mounted exactly at `mount_point`.
+fn mounted_filesystem(mount_point: &str) -> Option<&'static dyn Filesystem> {
+    let table = MOUNT_TABLE.read();
+    let idx = table
+        .mounts
+        .iter()
+        .position(|m| m.active && m.mount_point_str() == mount_point)?;
+    FILESYSTEM_TABLE.read()[idx]
+}
+
+/// Record a mount in the mount and filesystem tables.
+fn install_mount(
+    device: &str,
+    mount_point: &str,
+    fs_type: &str,
+    fs: &'static dyn Filesystem,
+    flags: MountFlags,
+    replace: bool,
+) -> Result<(), StorageError> {
+    let mut table = MOUNT_TABLE.write();
+    let mut filesystems = FILESYSTEM_TABLE.write();
+
+    // An existing mount point can only be reused if it is the empty root
+    // placeholder or is being replaced
+    let existing = table
+        .mounts
+        .iter()
+        .position(|m| m.active && m.mount_point_str() == mount_point);
+    let slot = match existing {
+        Some(idx) if replace || filesystems[idx].is_none() => idx,
+        Some(_) => return Err(StorageError::AlreadyExists),
+        None => table.find_free_slot().ok_or(StorageError::NoSpace)?,
+    };
 
     let mut info = MountInfo::empty();
 
@@ -396,19 +580,8 @@ pub fn mount(
     info.flags = flags;
     info.active = true;
 
-    let device_idx = crate::driver::find_device(device).ok_or(StorageError::DeviceNotFound)?;
-    let block_device = crate::driver::get_device(device_idx).ok_or(StorageError::DeviceNotFound)?;
-
-    let fs: &'static dyn Filesystem = match crate::fs::FilesystemType::from_str(fs_type) {
-        crate::fs::FilesystemType::Fat32 => {
-            let fat = crate::fs::fat32::Fat32Filesystem::mount(block_device)?;
-            Box::leak(Box::new(fat))
-        }
-        _ => return Err(StorageError::Unsupported),
-    };
-
     table.mounts[slot] = info;
-    FILESYSTEM_TABLE.write()[slot] = Some(fs);
+    filesystems[slot] = Some(fs);
 
     Ok(())
 }
@@ -424,6 +597,11 @@ pub fn unmount(mount_point: &str) -> Result<(), StorageError> {
                 return Err(StorageError::PermissionDenied);
             }
 
+            // Cached blocks must not outlive the mount
+            if let Some(device_idx) = crate::driver::find_device(mount.device_str()) {
+                crate::cache::release_device(device_idx as u32)?;
+            }
+
             mount.active = false;
             FILESYSTEM_TABLE.write()[idx] = None;
             return Ok(());
@@ -463,6 +641,8 @@ pub fn open(path: &str, flags: OpenFlags) -> Result<u32, StorageError> {
         return Err(StorageError::NameTooLong);
     }
 
+    let path = resolve_path(path, !flags.contains(OpenFlags::NOFOLLOW))?;
+    let path = path.as_str();
     let table = MOUNT_TABLE.read();
     let mount_idx = table.find_mount(path).ok_or(StorageError::FileNotFound)?;
     let mount = table.mounts[mount_idx];
@@ -524,6 +704,50 @@ pub fn write(fd: u32, data: &[u8]) -> Result<usize, StorageError> {
     let fs = get_filesystem(handle.mount_idx)?;
     let bytes_written = fs.write(handle.fs_handle, handle.offset, data)?;
     handle.offset += bytes_written as u64;
+    if handle.flags.intersects(OpenFlags::SYNC | OpenFlags::DSYNC) {
+        fs.fsync(handle.fs_handle, !handle.flags.contains(OpenFlags::SYNC))?;
+    }
+
+    Ok(bytes_written)
+}
+
+/// Read from a file at `offset` without moving the file position.
+///
+/// Reading at or past end of file returns a short count or 0.
+pub fn pread(fd: u32, buffer: &mut [u8], offset: u64) -> Result<usize, StorageError> {
+    offset
+        .checked_add(buffer.len() as u64)
+        .ok_or(StorageError::InvalidArgument)?;
+
+    let handles = FILE_HANDLES.read();
+    let handle = handles.get(fd).ok_or(StorageError::InvalidFd)?;
+
+    if !handle.flags.contains(OpenFlags::READ) {
+        return Err(StorageError::PermissionDenied);
+    }
+
+    let fs = get_filesystem(handle.mount_idx)?;
+    fs.read(handle.fs_handle, offset, buffer)
+}
+
+/// Write to a file at `offset` without moving the file position.
+pub fn pwrite(fd: u32, data: &[u8], offset: u64) -> Result<usize, StorageError> {
+    offset
+        .checked_add(data.len() as u64)
+        .ok_or(StorageError::InvalidArgument)?;
+
+    let handles = FILE_HANDLES.read();
+    let handle = handles.get(fd).ok_or(StorageError::InvalidFd)?;
+
+    if !handle.flags.contains(OpenFlags::WRITE) {
+        return Err(StorageError::PermissionDenied);
+    }
+
+    let fs = get_filesystem(handle.mount_idx)?;
+    let bytes_written = fs.write(handle.fs_handle, offset, data)?;
+    if handle.flags.intersects(OpenFlags::SYNC | OpenFlags::DSYNC) {
+        fs.fsync(handle.fs_handle, !handle.flags.contains(OpenFlags::SYNC))?;
+    }
 
     Ok(bytes_written)
 }
@@ -563,12 +787,17 @@ pub fn seek(fd: u32, pos: SeekFrom) -> Result<u64, StorageError> {
     Ok(new_offset)
 }
 
-/// Get file metadata.
+/// Get file metadata, following symlinks.
 pub fn stat(path: &str) -> Result<FileMetadata, StorageError> {
-    if path.is_empty() || !path.starts_with('/') {
-        return Err(StorageError::InvalidPath);
-    }
+    lookup(&resolve_path(path, true)?)
+}
 
+/// Get file metadata without following a symlink in the last component.
+pub fn lstat(path: &str) -> Result<FileMetadata, StorageError> {
+    lookup(&resolve_path(path, false)?)
+}
+
+fn lookup(path: &str) -> Result<FileMetadata, StorageError> {
     let table = MOUNT_TABLE.read();
     let mount_idx = table.find_mount(path).ok_or(StorageError::FileNotFound)?;
     let mount = table.mounts[mount_idx];
@@ -578,12 +807,39 @@ pub fn stat(path: &str) -> Result<FileMetadata, StorageError> {
     fs.lookup(relative_path(&mount, path))
 }
 
-/// Read a directory.
-pub fn readdir(path: &str) -> Result<Vec<DirEntry>, StorageError> {
-    if path.is_empty() || !path.starts_with('/') {
-        return Err(StorageError::InvalidPath);
+/// Read the target of a symlink.
+pub fn readlink(path: &str) -> Result<String, StorageError> {
+    let path = resolve_path(path, false)?;
+    let table = MOUNT_TABLE.read();
+    let mount_idx = table.find_mount(&path).ok_or(StorageError::FileNotFound)?;
+    let mount = table.mounts[mount_idx];
+    drop(table);
+
+    let fs = get_filesystem(mount_idx)?;
+    fs.readlink(relative_path(&mount, &path))
+}
+
+/// Create a symlink at `link_path` pointing to `target`.
+pub fn symlink(target: &str, link_path: &str) -> Result<(), StorageError> {
+    let link_path = resolve_path(link_path, false)?;
+    let table = MOUNT_TABLE.read();
+    let mount_idx = table
+        .find_mount(&link_path)
+        .ok_or(StorageError::FileNotFound)?;
+    let mount = &table.mounts[mount_idx];
+
+    if mount.flags.contains(MountFlags::READ_ONLY) {
+        return Err(StorageError::ReadOnly);
     }
 
+    let fs = get_filesystem(mount_idx)?;
+    fs.symlink(target, relative_path(mount, &link_path))
+}
+
+/// Read a directory.
+pub fn readdir(path: &str) -> Result<Vec<DirEntry>, StorageError> {
+    let path = resolve_path(path, true)?;
+    let path = path.as_str();
     let table = MOUNT_TABLE.read();
     let mount_idx = table.find_mount(path).ok_or(StorageError::FileNotFound)?;
     let mount = table.mounts[mount_idx];
@@ -595,10 +851,8 @@ pub fn readdir(path: &str) -> Result<Vec<DirEntry>, StorageError> {
 
 /// Create a directory.
 pub fn mkdir(path: &str, mode: u16) -> Result<(), StorageError> {
-    if path.is_empty() || !path.starts_with('/') {
-        return Err(StorageError::InvalidPath);
-    }
-
+    let path = resolve_path(path, false)?;
+    let path = path.as_str();
     let table = MOUNT_TABLE.read();
     let mount_idx = table.find_mount(path).ok_or(StorageError::FileNotFound)?;
     let mount = &table.mounts[mount_idx];
@@ -613,10 +867,8 @@ pub fn mkdir(path: &str, mode: u16) -> Result<(), StorageError> {
 
 /// Remove a file.
 pub fn unlink(path: &str) -> Result<(), StorageError> {
-    if path.is_empty() || !path.starts_with('/') {
-        return Err(StorageError::InvalidPath);
-    }
-
+    let path = resolve_path(path, false)?;
+    let path = path.as_str();
     let table = MOUNT_TABLE.read();
     let mount_idx = table.find_mount(path).ok_or(StorageError::FileNotFound)?;
     let mount = &table.mounts[mount_idx];
@@ -631,10 +883,8 @@ pub fn unlink(path: &str) -> Result<(), StorageError> {
 
 /// Remove a directory.
 pub fn rmdir(path: &str) -> Result<(), StorageError> {
-    if path.is_empty() || !path.starts_with('/') {
-        return Err(StorageError::InvalidPath);
-    }
-
+    let path = resolve_path(path, false)?;
+    let path = path.as_str();
     let table = MOUNT_TABLE.read();
     let mount_idx = table.find_mount(path).ok_or(StorageError::FileNotFound)?;
     let mount = &table.mounts[mount_idx];
@@ -649,13 +899,9 @@ pub fn rmdir(path: &str) -> Result<(), StorageError> {
 
 /// Rename a file or directory.
 pub fn rename(old_path: &str, new_path: &str) -> Result<(), StorageError> {
-    if old_path.is_empty() || !old_path.starts_with('/') {
-        return Err(StorageError::InvalidPath);
-    }
-    if new_path.is_empty() || !new_path.starts_with('/') {
-        return Err(StorageError::InvalidPath);
-    }
-
+    let old_path = resolve_path(old_path, false)?;
+    let new_path = resolve_path(new_path, false)?;
+    let (old_path, new_path) = (old_path.as_str(), new_path.as_str());
     let table = MOUNT_TABLE.read();
     let old_mount = table
         .find_mount(old_path)
@@ -690,5 +936,119 @@ pub fn sync_all() -> Result<(), StorageError> {
             fs.sync()?;
         }
     }
-    Ok(())
+    crate::cache::flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::tmpfs::TmpFs;
+
+    #[test]
+    fn test_symlink_resolution() {
+        let fs: &'static TmpFs = Box::leak(Box::new(TmpFs::new()));
+        mount_filesystem("none", "/symlinks", fs, MountFlags::empty(), false).unwrap();
+        mkdir("/symlinks/dir", 0o755).unwrap();
+        let fd = open("/symlinks/dir/file", OpenFlags::CREATE | OpenFlags::WRITE).unwrap();
+        write(fd, b"data").unwrap();
+        close(fd).unwrap();
+
+        // Relative targets resolve against the link's directory, absolute
+        // ones against the mount root
+        symlink("file", "/symlinks/dir/relative").unwrap();
+        symlink("/dir/file", "/symlinks/absolute").unwrap();
+        symlink("dir", "/symlinks/dirlink").unwrap();
+        assert_eq!(stat("/symlinks/dir/relative").unwrap().size, 4);
+        assert_eq!(stat("/symlinks/absolute").unwrap().size, 4);
+        assert_eq!(stat("/symlinks/dirlink/relative").unwrap().size, 4);
+        assert_eq!(stat("/symlinks/dirlink/../dir/file").unwrap().size, 4);
+
+        let link = lstat("/symlinks/absolute").unwrap();
+        assert_eq!(link.file_type, FileType::Symlink);
+        assert_eq!(link.size, "/dir/file".len() as u64);
+
+        symlink("b", "/symlinks/a").unwrap();
+        symlink("a", "/symlinks/b").unwrap();
+        assert_eq!(
+            stat("/symlinks/a").unwrap_err(),
+            StorageError::TooManySymlinks
+        );
+        assert_eq!(
+            open("/symlinks/a/x", OpenFlags::READ).unwrap_err(),
+            StorageError::TooManySymlinks
+        );
+        assert_eq!(lstat("/symlinks/a").unwrap().file_type, FileType::Symlink);
+        let fd = open("/symlinks/a", OpenFlags::READ | OpenFlags::NOFOLLOW).unwrap();
+        close(fd).unwrap();
+    }
+
+    #[test]
+    fn test_overlay_mount_replaces_lower() {
+        let lower: &'static TmpFs = Box::leak(Box::new(TmpFs::new()));
+        mount_filesystem("none", "/overlaid", lower, MountFlags::empty(), false).unwrap();
+        let fd = open("/overlaid/file", OpenFlags::CREATE | OpenFlags::WRITE).unwrap();
+        write(fd, b"lower").unwrap();
+        close(fd).unwrap();
+
+        assert_eq!(
+            mount("none", "/overlaid", "tmpfs", MountFlags::empty()).unwrap_err(),
+            StorageError::AlreadyExists
+        );
+        mount("overlay", "/overlaid", "overlayfs", MountFlags::empty()).unwrap();
+        let mounts: Vec<MountInfo> = list_mounts()
+            .into_iter()
+            .filter(|m| m.mount_point_str() == "/overlaid")
+            .collect();
+        assert_eq!(mounts.len(), 1);
+        assert_eq!(mounts[0].fs_type_str(), "overlayfs");
+
+        // Writes land in the new upper layer
+        let fd = open("/overlaid/file", OpenFlags::WRITE | OpenFlags::TRUNCATE).unwrap();
+        write(fd, b"upper").unwrap();
+        close(fd).unwrap();
+        let fd = open("/overlaid/file", OpenFlags::READ).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(read(fd, &mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"upper");
+        close(fd).unwrap();
+
+        let handle = lower.open("/file", OpenFlags::READ).unwrap();
+        assert_eq!(lower.read(handle, 0, &mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"lower");
+        lower.close(handle).unwrap();
+    }
+
+    #[test]
+    fn test_positional_io() {
+        let fs: &'static TmpFs = Box::leak(Box::new(TmpFs::new()));
+        mount_filesystem("none", "/positional", fs, MountFlags::empty(), false).unwrap();
+        let fd = open(
+            "/positional/file",
+            OpenFlags::CREATE | OpenFlags::READ | OpenFlags::WRITE,
+        )
+        .unwrap();
+        write(fd, b"hello").unwrap();
+
+        assert_eq!(pwrite(fd, b"J", 0).unwrap(), 1);
+        let mut buf = [0u8; 8];
+        assert_eq!(pread(fd, &mut buf, 1).unwrap(), 4);
+        assert_eq!(&buf[..4], b"ello");
+        assert_eq!(pread(fd, &mut buf, 5).unwrap(), 0);
+        assert_eq!(pread(fd, &mut buf, 100).unwrap(), 0);
+        assert_eq!(
+            pread(fd, &mut buf, u64::MAX - 2).unwrap_err(),
+            StorageError::InvalidArgument
+        );
+        assert_eq!(
+            pwrite(fd, b"xy", u64::MAX).unwrap_err(),
+            StorageError::InvalidArgument
+        );
+
+        // The file position is still where the first write left it
+        assert_eq!(seek(fd, SeekFrom::Current(0)).unwrap(), 5);
+        write(fd, b"!").unwrap();
+        assert_eq!(pread(fd, &mut buf, 0).unwrap(), 6);
+        assert_eq!(&buf[..6], b"Jello!");
+        close(fd).unwrap();
+    }
 }
@@ -4,8 +4,10 @@
 
 use crate::browser::{BrowserHandle, ElementHandle, JsValue};
 use crate::screenshot::{ComparisonResult, Screenshot};
+use crate::TestContext;
 use alloc::format;
 use alloc::string::String;
+use alloc::vec::Vec;
 
 /// Assertion result
 pub type AssertResult = Result<(), String>;
@@ -410,6 +412,41 @@ pub fn assert_js_eq(value: &JsValue, expected: &JsValue) -> AssertResult {
     }
 }
 
+// Console assertions
+
+/// Assert the page logged no errors and threw no uncaught exceptions
+pub fn assert_no_console_errors(ctx: &mut TestContext) -> AssertResult {
+    let errors: Vec<String> = ctx
+        .capture_console()
+        .iter()
+        .filter(|m| m.is_error())
+        .map(|m| format!("{}", m))
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected no console errors, got {}:\n{}",
+            errors.len(),
+            errors.join("\n")
+        ))
+    }
+}
+
+/// Assert some console message contains a substring
+pub fn assert_console_contains(ctx: &mut TestContext, substring: &str) -> AssertResult {
+    let console = ctx.capture_console();
+    if console.iter().any(|m| m.text.contains(substring)) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected a console message containing '{}', got {} messages",
+            substring,
+            console.len()
+        ))
+    }
+}
+
 // Performance assertions
 
 /// Assert duration is under threshold
@@ -20,6 +20,8 @@ pub struct BrowserHandle {
     active_tab: usize,
     /// Browser state
     state: BrowserState,
+    /// Console messages forwarded from page scripts, not yet taken
+    console: Vec<ConsoleMessage>,
 }
 
 /// Browser state
@@ -159,6 +161,7 @@ impl BrowserHandle {
             }],
             active_tab: 0,
             state: BrowserState::Ready,
+            console: Vec::new(),
         })
     }
 
@@ -257,6 +260,17 @@ impl BrowserHandle {
         self.state
     }
 
+    /// Record a console message or uncaught exception from a page script.
+    /// The browser forwards its tabs' JS engine output through this.
+    pub fn record_console_message(&mut self, message: ConsoleMessage) {
+        self.console.push(message);
+    }
+
+    /// Take console messages recorded since the last call
+    pub fn take_console_messages(&mut self) -> Vec<ConsoleMessage> {
+        core::mem::take(&mut self.console)
+    }
+
     /// Execute JavaScript
     pub fn evaluate(&self, script: &str) -> Result<JsValue, String> {
         let _ = script;
@@ -507,6 +521,50 @@ impl ElementHandle {
     }
 }
 
+/// Console message severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConsoleLevel {
+    Debug,
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+/// A console message or uncaught exception from a page script
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsoleMessage {
+    /// Severity (uncaught exceptions are errors)
+    pub level: ConsoleLevel,
+    /// Message text
+    pub text: String,
+    /// Script URL
+    pub source: String,
+    /// Line number (1-based, 0 if unknown)
+    pub line: usize,
+    /// Column number (1-based, 0 if unknown)
+    pub column: usize,
+    /// Whether this reports an exception no script caught
+    pub uncaught: bool,
+}
+
+impl ConsoleMessage {
+    /// Check if this is an error or an uncaught exception
+    pub fn is_error(&self) -> bool {
+        self.level == ConsoleLevel::Error || self.uncaught
+    }
+}
+
+impl core::fmt::Display for ConsoleMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "[{:?}] {} ({}:{}:{})",
+            self.level, self.text, self.source, self.line, self.column
+        )
+    }
+}
+
 /// Bounding box of an element
 #[derive(Debug, Clone, Copy)]
 pub struct BoundingBox {
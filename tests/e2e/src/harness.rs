@@ -3,12 +3,14 @@
 //! Provides the test runner and reporting infrastructure.
 
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 use spin::Mutex;
 
 use crate::{
-    browser, performance, SuiteReport, TestCase, TestContext, TestResult, TestStatus, TestSuite,
+    browser, performance, FlakyTest, SuiteReport, TestCase, TestContext, TestResult, TestStatus,
+    TestSuite,
 };
 
 /// Test runner for executing test suites
@@ -19,6 +21,8 @@ pub struct TestRunner {
     config: TestConfig,
     /// Event listeners
     listeners: Vec<Box<dyn TestListener + Send + Sync>>,
+    /// Pass/fail history used for flaky-test quarantine
+    history: Option<TestHistory>,
 }
 
 /// Test runner configuration
@@ -40,6 +44,11 @@ pub struct TestConfig {
     pub screenshot_on_failure: bool,
     /// Generate performance report
     pub performance_report: bool,
+    /// Pass rate (percentage) below which a test with mixed results is
+    /// quarantined; only applies when the runner has a `TestHistory`
+    pub flaky_threshold: f32,
+    /// Minimum recorded runs before a test can be quarantined
+    pub flaky_min_runs: usize,
 }
 
 impl Default for TestConfig {
@@ -53,6 +62,8 @@ impl Default for TestConfig {
             fail_fast: false,
             screenshot_on_failure: true,
             performance_report: true,
+            flaky_threshold: 95.0,
+            flaky_min_runs: 5,
         }
     }
 }
@@ -198,6 +209,7 @@ impl TestRunner {
             suites: Vec::new(),
             config: TestConfig::default(),
             listeners: Vec::new(),
+            history: None,
         }
     }
 
@@ -207,6 +219,7 @@ impl TestRunner {
             suites: Vec::new(),
             config,
             listeners: Vec::new(),
+            history: None,
         }
     }
 
@@ -215,6 +228,21 @@ impl TestRunner {
         self.suites.push(suite);
     }
 
+    /// Track pass/fail history across runs and quarantine flaky tests
+    pub fn set_history(&mut self, history: TestHistory) {
+        self.history = Some(history);
+    }
+
+    /// Get the pass/fail history (including this run's results)
+    pub fn history(&self) -> Option<&TestHistory> {
+        self.history.as_ref()
+    }
+
+    /// Take the pass/fail history, e.g. to persist it with `to_json`
+    pub fn take_history(&mut self) -> Option<TestHistory> {
+        self.history.take()
+    }
+
     /// Add a listener
     pub fn add_listener<L: TestListener + Send + Sync + 'static>(&mut self, listener: L) {
         self.listeners.push(Box::new(listener));
@@ -281,7 +309,11 @@ impl TestRunner {
                 listener.on_test_end(&test_name, &result);
             }
 
+            let flaky = self.record_history(&report.name, &test_name, result.status);
             report.add_result(test_name, result);
+            if let Some(flaky) = flaky {
+                report.quarantine(flaky);
+            }
 
             if self.config.fail_fast && !report.all_passed() {
                 break;
@@ -291,6 +323,34 @@ impl TestRunner {
         report
     }
 
+    /// Record a result in the history and return it as flaky if the
+    /// test should be quarantined
+    fn record_history(
+        &mut self,
+        suite_name: &str,
+        test_name: &str,
+        status: TestStatus,
+    ) -> Option<FlakyTest> {
+        let history = self.history.as_mut()?;
+        history.record(suite_name, test_name, status);
+
+        if history.is_flaky(
+            suite_name,
+            test_name,
+            self.config.flaky_threshold,
+            self.config.flaky_min_runs,
+        ) {
+            Some(FlakyTest {
+                name: String::from(test_name),
+                pass_rate: history.pass_rate(suite_name, test_name)?,
+                runs: history.runs(suite_name, test_name),
+                status,
+            })
+        } else {
+            None
+        }
+    }
+
     /// Check if test should run based on filters (using extracted info)
     fn should_run_test_info(&self, name: &str, tags: &[String]) -> bool {
         // Check tag filter
@@ -369,6 +429,391 @@ impl TestRunner {
     }
 }
 
+/// Per-test pass/fail history across runs, keyed by `suite::test`
+///
+/// Only the most recent `window` outcomes are kept, so the pass rate is a
+/// rolling one. The history round-trips through a small JSON document
+/// (`{"window":20,"tests":{"suite::test":[true,false]}}`) that callers
+/// persist between runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestHistory {
+    /// Rolling window size
+    window: usize,
+    /// Outcomes (true = passed), oldest first
+    tests: BTreeMap<String, Vec<bool>>,
+}
+
+impl Default for TestHistory {
+    fn default() -> Self {
+        Self::new(20)
+    }
+}
+
+impl TestHistory {
+    /// Create an empty history keeping the last `window` runs per test
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            tests: BTreeMap::new(),
+        }
+    }
+
+    fn key(suite_name: &str, test_name: &str) -> String {
+        alloc::format!("{}::{}", suite_name, test_name)
+    }
+
+    /// Record a test outcome; skipped tests are ignored
+    pub fn record(&mut self, suite_name: &str, test_name: &str, status: TestStatus) {
+        let passed = match status {
+            TestStatus::Passed => true,
+            TestStatus::Failed | TestStatus::Timeout | TestStatus::Error => false,
+            TestStatus::Skipped => return,
+        };
+        let runs = self
+            .tests
+            .entry(Self::key(suite_name, test_name))
+            .or_default();
+        runs.push(passed);
+        if runs.len() > self.window {
+            let excess = runs.len() - self.window;
+            runs.drain(..excess);
+        }
+    }
+
+    /// Record every result of a suite report
+    pub fn record_report(&mut self, report: &SuiteReport) {
+        for (test_name, result) in &report.results {
+            self.record(&report.name, test_name, result.status);
+        }
+    }
+
+    /// Number of recorded runs in the window
+    pub fn runs(&self, suite_name: &str, test_name: &str) -> usize {
+        self.tests
+            .get(&Self::key(suite_name, test_name))
+            .map_or(0, |runs| runs.len())
+    }
+
+    /// Rolling pass rate as percentage, if the test has any history
+    pub fn pass_rate(&self, suite_name: &str, test_name: &str) -> Option<f32> {
+        let runs = self.tests.get(&Self::key(suite_name, test_name))?;
+        if runs.is_empty() {
+            return None;
+        }
+        let passed = runs.iter().filter(|&&p| p).count();
+        Some((passed as f32 / runs.len() as f32) * 100.0)
+    }
+
+    /// Check if a test is flaky: enough runs, both passes and failures in
+    /// the window, and a pass rate below `threshold`
+    ///
+    /// Tests that fail every run are broken rather than flaky and are never
+    /// quarantined.
+    pub fn is_flaky(
+        &self,
+        suite_name: &str,
+        test_name: &str,
+        threshold: f32,
+        min_runs: usize,
+    ) -> bool {
+        let Some(runs) = self.tests.get(&Self::key(suite_name, test_name)) else {
+            return false;
+        };
+        if runs.len() < min_runs.max(1) || !runs.contains(&true) || !runs.contains(&false) {
+            return false;
+        }
+        self.pass_rate(suite_name, test_name)
+            .is_some_and(|rate| rate < threshold)
+    }
+
+    /// Serialize to the JSON store format
+    pub fn to_json(&self) -> String {
+        let mut json = alloc::format!("{{\"window\":{},\"tests\":{{", self.window);
+        for (i, (name, runs)) in self.tests.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json_escape_into(&mut json, name);
+            json.push_str(":[");
+            for (j, passed) in runs.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                json.push_str(if *passed { "true" } else { "false" });
+            }
+            json.push(']');
+        }
+        json.push_str("}}");
+        json
+    }
+
+    /// Parse the JSON store format; unknown keys are ignored
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut parser = JsonParser::new(json);
+        let mut history = Self::default();
+
+        parser.expect(b'{')?;
+        if !parser.eat(b'}') {
+            loop {
+                let key = parser.string()?;
+                parser.expect(b':')?;
+                match key.as_str() {
+                    "window" => history.window = parser.number()?.max(1),
+                    "tests" => history.tests = parse_tests(&mut parser)?,
+                    _ => parser.skip_value()?,
+                }
+                if !parser.eat(b',') {
+                    break;
+                }
+            }
+            parser.expect(b'}')?;
+        }
+        parser.end()?;
+
+        // Re-apply the window in case the store was written with a larger one
+        let window = history.window;
+        for runs in history.tests.values_mut() {
+            if runs.len() > window {
+                let excess = runs.len() - window;
+                runs.drain(..excess);
+            }
+        }
+        Ok(history)
+    }
+}
+
+fn parse_tests(parser: &mut JsonParser<'_>) -> Result<BTreeMap<String, Vec<bool>>, String> {
+    let mut tests = BTreeMap::new();
+    parser.expect(b'{')?;
+    if parser.eat(b'}') {
+        return Ok(tests);
+    }
+    loop {
+        let name = parser.string()?;
+        parser.expect(b':')?;
+        let mut runs = Vec::new();
+        parser.expect(b'[')?;
+        if !parser.eat(b']') {
+            loop {
+                runs.push(parser.boolean()?);
+                if !parser.eat(b',') {
+                    break;
+                }
+            }
+            parser.expect(b']')?;
+        }
+        tests.insert(name, runs);
+        if !parser.eat(b',') {
+            break;
+        }
+    }
+    parser.expect(b'}')?;
+    Ok(tests)
+}
+
+fn json_escape_into(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&alloc::format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Minimal JSON reader for the history store
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            bytes: input.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn error(&self, what: &str) -> String {
+        alloc::format!("history JSON: expected {} at byte {}", what, self.pos)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.error(&alloc::format!("'{}'", byte as char)))
+        }
+    }
+
+    fn end(&mut self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("end of input")),
+        }
+    }
+
+    fn literal(&mut self, word: &str) -> bool {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn boolean(&mut self) -> Result<bool, String> {
+        self.skip_ws();
+        if self.literal("true") {
+            Ok(true)
+        } else if self.literal("false") {
+            Ok(false)
+        } else {
+            Err(self.error("boolean"))
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        self.skip_ws();
+        let start = self.pos;
+        while self.bytes.get(self.pos).is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        core::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| self.error("non-negative integer"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| self.error("closing '\"'"))?;
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self
+                        .bytes
+                        .get(self.pos)
+                        .ok_or_else(|| self.error("escape"))?;
+                    self.pos += 1;
+                    let c = match escaped {
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let hex = self
+                                .bytes
+                                .get(self.pos..self.pos + 4)
+                                .and_then(|h| core::str::from_utf8(h).ok())
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .ok_or_else(|| self.error("\\u escape"))?;
+                            self.pos += 4;
+                            char::from_u32(hex).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        other => other as char,
+                    };
+                    let mut buf = [0u8; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                other => out.push(other),
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("UTF-8 string"))
+    }
+
+    fn skip_value(&mut self) -> Result<(), String> {
+        match self.peek() {
+            Some(b'"') => self.string().map(|_| ()),
+            Some(b'{') => {
+                self.pos += 1;
+                if self.eat(b'}') {
+                    return Ok(());
+                }
+                loop {
+                    self.string()?;
+                    self.expect(b':')?;
+                    self.skip_value()?;
+                    if !self.eat(b',') {
+                        break;
+                    }
+                }
+                self.expect(b'}')
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                if self.eat(b']') {
+                    return Ok(());
+                }
+                loop {
+                    self.skip_value()?;
+                    if !self.eat(b',') {
+                        break;
+                    }
+                }
+                self.expect(b']')
+            }
+            Some(_) => {
+                if self.literal("true") || self.literal("false") || self.literal("null") {
+                    return Ok(());
+                }
+                let start = self.pos;
+                while self
+                    .bytes
+                    .get(self.pos)
+                    .is_some_and(|b| b.is_ascii_digit() || b"+-.eE".contains(b))
+                {
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    Err(self.error("value"))
+                } else {
+                    Ok(())
+                }
+            }
+            None => Err(self.error("value")),
+        }
+    }
+}
+
 /// Test builder for fluent test creation
 pub struct TestBuilder {
     name: String,
@@ -487,3 +932,85 @@ pub fn run_with_config(config: TestConfig) -> Vec<SuiteReport> {
 
     runner.run()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_json_round_trip() {
+        let mut history = TestHistory::new(3);
+        for status in [
+            TestStatus::Passed,
+            TestStatus::Failed,
+            TestStatus::Skipped,
+            TestStatus::Timeout,
+            TestStatus::Passed,
+        ] {
+            history.record("net", "fetch", status);
+        }
+        history.record("css", "quote \" back\\slash\ttab\u{1}", TestStatus::Passed);
+        history.record("ui", "ünïcode", TestStatus::Error);
+
+        let json = history.to_json();
+        assert!(json.starts_with("{\"window\":3,\"tests\":{"));
+        assert!(json.contains("\"net::fetch\":[false,false,true]"));
+        assert_eq!(TestHistory::from_json(&json), Ok(history.clone()));
+
+        // Whitespace and unknown keys are accepted; a larger stored window
+        // is trimmed to the one in the document
+        let json = r#" { "version": [1, {"x": null}], "tests" : {
+            "a::b" : [ true , false , true ] , "c::d": [] } ,
+            "window": 2, "extra": -1.5e3 } "#;
+        let parsed = TestHistory::from_json(json).unwrap();
+        assert_eq!(parsed.runs("a", "b"), 2);
+        assert_eq!(parsed.pass_rate("a", "b"), Some(50.0));
+        assert_eq!(parsed.runs("c", "d"), 0);
+        assert_eq!(
+            TestHistory::from_json(r#"{"tests":{"e::\u0041\n":[true]}}"#)
+                .unwrap()
+                .runs("e", "A\n"),
+            1
+        );
+        assert_eq!(TestHistory::from_json("{}"), Ok(TestHistory::default()));
+    }
+
+    #[test]
+    fn test_history_json_malformed() {
+        for json in [
+            "",
+            "[]",
+            "{\"window\":\"20\"}",
+            "{\"window\":-1}",
+            "{\"tests\":[]}",
+            "{\"tests\":{\"a::b\":[1]}}",
+            "{\"tests\":{\"a::b\":[tru]}}",
+            "{\"tests\":{\"a::b\":true}}",
+            "{\"tests\":{a::b:[true]}}",
+            "{\"window\":20,}",
+            "{\"window\" 20}",
+            "{\"window\":20}{}",
+            "{\"tests\":{\"\\u00zz\":[]}}",
+        ] {
+            assert!(TestHistory::from_json(json).is_err(), "{:?}", json);
+        }
+    }
+
+    #[test]
+    fn test_history_json_truncated() {
+        let mut history = TestHistory::new(5);
+        history.record("suite", "escaped \"name\"", TestStatus::Passed);
+        history.record("suite", "other", TestStatus::Failed);
+        let json = history.to_json();
+
+        // Every strict prefix is an error rather than a partial history
+        for len in 0..json.len() {
+            assert!(
+                TestHistory::from_json(&json[..len]).is_err(),
+                "{:?}",
+                &json[..len]
+            );
+        }
+        assert!(TestHistory::from_json(&json).is_ok());
+    }
+}
@@ -63,6 +63,8 @@ pub struct TestContext {
     pub measurements: Vec<performance::Measurement>,
     /// Log messages
     pub logs: Vec<LogEntry>,
+    /// Console messages and uncaught exceptions captured from the page
+    pub console: Vec<browser::ConsoleMessage>,
 }
 
 /// Log entry
@@ -165,6 +167,7 @@ impl TestContext {
             screenshots: Vec::new(),
             measurements: Vec::new(),
             logs: Vec::new(),
+            console: Vec::new(),
         }
     }
 
@@ -212,6 +215,15 @@ impl TestContext {
         }
     }
 
+    /// Capture console messages forwarded by the browser since the last
+    /// capture, and return everything captured during this test
+    pub fn capture_console(&mut self) -> &[browser::ConsoleMessage] {
+        if let Some(ref mut browser) = self.browser {
+            self.console.extend(browser.take_console_messages());
+        }
+        &self.console
+    }
+
     /// Start a performance measurement
     pub fn start_measurement(&mut self, name: &str) -> performance::MeasurementHandle {
         performance::MeasurementHandle::new(name)
@@ -290,6 +302,23 @@ pub struct SuiteReport {
     pub skipped: usize,
     /// Error count
     pub errors: usize,
+    /// Tests whose historical pass rate is below the stability threshold
+    pub flaky: Vec<FlakyTest>,
+    /// Failures and errors from quarantined tests (reported, not fatal)
+    pub quarantined_failures: usize,
+}
+
+/// A test flagged as unstable by its pass/fail history
+#[derive(Debug, Clone)]
+pub struct FlakyTest {
+    /// Test name
+    pub name: String,
+    /// Rolling pass rate as percentage
+    pub pass_rate: f32,
+    /// Number of runs in the rolling window
+    pub runs: usize,
+    /// Status in the current run
+    pub status: TestStatus,
 }
 
 impl SuiteReport {
@@ -303,6 +332,8 @@ impl SuiteReport {
             failed: 0,
             skipped: 0,
             errors: 0,
+            flaky: Vec::new(),
+            quarantined_failures: 0,
         }
     }
 
@@ -319,9 +350,31 @@ impl SuiteReport {
         self.results.push((test_name, result));
     }
 
-    /// Check if all tests passed
+    /// Check if all tests passed (failures of quarantined tests don't count)
     pub fn all_passed(&self) -> bool {
-        self.failed == 0 && self.errors == 0
+        self.failed + self.errors == self.quarantined_failures
+    }
+
+    /// Quarantine a flaky test: it stays in the report, but its failure in
+    /// this run no longer fails the suite
+    pub fn quarantine(&mut self, test: FlakyTest) {
+        if matches!(
+            test.status,
+            TestStatus::Failed | TestStatus::Timeout | TestStatus::Error
+        ) {
+            self.quarantined_failures += 1;
+        }
+        self.flaky.push(test);
+    }
+
+    /// Get tests flagged as flaky in this run
+    pub fn flaky_tests(&self) -> &[FlakyTest] {
+        &self.flaky
+    }
+
+    /// Check if a test is quarantined in this run
+    pub fn is_quarantined(&self, test_name: &str) -> bool {
+        self.flaky.iter().any(|t| t.name == test_name)
     }
 
     /// Get pass rate as percentage
@@ -343,6 +396,14 @@ impl fmt::Display for SuiteReport {
         writeln!(f, "Failed:  {} ✗", self.failed)?;
         writeln!(f, "Skipped: {} ○", self.skipped)?;
         writeln!(f, "Errors:  {} !", self.errors)?;
+        if !self.flaky.is_empty() {
+            writeln!(
+                f,
+                "Flaky:   {} ~ ({} quarantined failures)",
+                self.flaky.len(),
+                self.quarantined_failures
+            )?;
+        }
         writeln!(f, "Duration: {}ms", self.total_duration_ms)?;
         writeln!(f, "Pass Rate: {:.1}%", self.pass_rate())
     }
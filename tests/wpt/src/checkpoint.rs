@@ -0,0 +1,427 @@
+//! Run checkpoints
+//!
+//! The runner periodically appends finished results to a checkpoint file
+//! so an interrupted run can be resumed (see `WptConfig::resume_from`).
+//! The file holds one JSON record per line; a crash mid-write leaves at
+//! most a truncated last line, which is ignored on load.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{SubtestResult, SubtestStatus, TestResult, TestStatus, json};
+
+/// Checkpoint file name inside `WptConfig::output_dir`
+pub const CHECKPOINT_FILE: &str = "checkpoint.jsonl";
+
+/// Storage for checkpoint files
+pub trait CheckpointStore {
+    /// Read a whole file, `None` if it does not exist
+    fn read(&self, path: &str) -> Option<String>;
+
+    /// Replace a file's contents
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), String>;
+
+    /// Append to a file, creating it if needed
+    fn append(&mut self, path: &str, contents: &str) -> Result<(), String>;
+}
+
+/// In-memory checkpoint storage
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    files: BTreeMap<String, String>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CheckpointStore for MemoryStore {
+    fn read(&self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        self.files
+            .insert(String::from(path), String::from(contents));
+        Ok(())
+    }
+
+    fn append(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        self.files
+            .entry(String::from(path))
+            .or_default()
+            .push_str(contents);
+        Ok(())
+    }
+}
+
+/// Checkpoint storage on the host filesystem
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsStore;
+
+#[cfg(feature = "std")]
+impl CheckpointStore for FsStore {
+    fn read(&self, path: &str) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        std::fs::write(path, contents).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn append(&mut self, path: &str, contents: &str) -> Result<(), String> {
+        use std::io::Write;
+
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+/// Encode a result as one checkpoint line, including the newline
+pub fn encode_record(result: &TestResult) -> String {
+    let mut line = format!(
+        "{{\"path\":{},\"status\":\"{}\",\"duration_ms\":{},\"attempts\":{},\"message\":{},\"stack\":{},\"subtests\":[",
+        json::string(&result.path),
+        result.status.as_str(),
+        result.duration_ms,
+        result.attempts,
+        json::opt_string(&result.message),
+        json::opt_string(&result.stack),
+    );
+    for (i, subtest) in result.subtests.iter().enumerate() {
+        if i > 0 {
+            line.push(',');
+        }
+        line.push_str(&format!(
+            "{{\"name\":{},\"status\":\"{}\",\"message\":{},\"expected\":{}}}",
+            json::string(&subtest.name),
+            subtest.status.as_str(),
+            json::opt_string(&subtest.message),
+            match subtest.expected {
+                Some(status) => format!("\"{}\"", status.as_str()),
+                None => String::from("null"),
+            },
+        ));
+    }
+    line.push_str("]}\n");
+    line
+}
+
+/// Decode checkpoint contents, skipping records that do not parse (such
+/// as a line cut short by a crash). Later records for a path replace
+/// earlier ones.
+pub fn decode(contents: &str) -> BTreeMap<String, TestResult> {
+    let mut results = BTreeMap::new();
+    for line in contents.lines() {
+        if let Some(result) = decode_record(line) {
+            results.insert(result.path.clone(), result);
+        }
+    }
+    results
+}
+
+/// Decode one checkpoint line
+pub fn decode_record(line: &str) -> Option<TestResult> {
+    let mut parser = Parser {
+        bytes: line.as_bytes(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.bytes.len() {
+        return None;
+    }
+
+    let subtests = value
+        .get("subtests")?
+        .as_array()?
+        .iter()
+        .map(|subtest| {
+            Some(SubtestResult {
+                name: String::from(subtest.get("name")?.as_str()?),
+                status: SubtestStatus::parse(subtest.get("status")?.as_str()?)?,
+                message: subtest.get("message")?.as_opt_string()?,
+                expected: match subtest.get("expected")? {
+                    Value::Null => None,
+                    expected => Some(SubtestStatus::parse(expected.as_str()?)?),
+                },
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(TestResult {
+        path: String::from(value.get("path")?.as_str()?),
+        status: TestStatus::parse(value.get("status")?.as_str()?)?,
+        subtests,
+        duration_ms: value.get("duration_ms")?.as_u64()?,
+        // Records written before flaky retries have no attempt count
+        attempts: match value.get("attempts") {
+            Some(attempts) => u32::try_from(attempts.as_u64()?).ok()?,
+            None => 1,
+        },
+        message: value.get("message")?.as_opt_string()?,
+        stack: value.get("stack")?.as_opt_string()?,
+    })
+}
+
+/// The JSON values checkpoint records use
+enum Value {
+    Null,
+    Number(u64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_opt_string(&self) -> Option<Option<String>> {
+        match self {
+            Value::Null => Some(None),
+            Value::String(s) => Some(Some(s.clone())),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parser for a single record; numbers are unsigned integers only
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while matches!(self.bytes.get(self.pos), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match *self.bytes.get(self.pos)? {
+            b'n' => {
+                if !self.bytes[self.pos..].starts_with(b"null") {
+                    return None;
+                }
+                self.pos += 4;
+                Some(Value::Null)
+            }
+            b'"' => self.string().map(Value::String),
+            b'0'..=b'9' => {
+                let start = self.pos;
+                while self.bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                    self.pos += 1;
+                }
+                core::str::from_utf8(&self.bytes[start..self.pos])
+                    .ok()?
+                    .parse()
+                    .ok()
+                    .map(Value::Number)
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        items.push(self.value()?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return None;
+                        }
+                    }
+                }
+                Some(Value::Array(items))
+            }
+            b'{' => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        if !self.eat(b':') {
+                            return None;
+                        }
+                        fields.push((key, self.value()?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return None;
+                        }
+                    }
+                }
+                Some(Value::Object(fields))
+            }
+            _ => None,
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return None;
+        }
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match *self.bytes.get(self.pos)? {
+                b'"' => {
+                    self.pos += 1;
+                    return String::from_utf8(out).ok();
+                }
+                b'\\' => {
+                    let escape = *self.bytes.get(self.pos + 1)?;
+                    self.pos += 2;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let hex = self.bytes.get(self.pos..self.pos + 4)?;
+                            self.pos += 4;
+                            let code =
+                                u32::from_str_radix(core::str::from_utf8(hex).ok()?, 16).ok()?;
+                            char::from_u32(code)?
+                        }
+                        _ => return None,
+                    };
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                byte => {
+                    out.push(byte);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn sample() -> TestResult {
+        TestResult {
+            path: String::from("/dom/\"quoted\"\\path.html"),
+            status: TestStatus::Error,
+            subtests: vec![
+                SubtestResult {
+                    name: String::from("line\nbreak\ttab \u{1}é"),
+                    status: SubtestStatus::Fail,
+                    message: Some(String::from("expected 1")),
+                    expected: Some(SubtestStatus::Pass),
+                },
+                SubtestResult {
+                    name: String::from("second"),
+                    status: SubtestStatus::PreconditionFailed,
+                    message: None,
+                    expected: None,
+                },
+            ],
+            duration_ms: 1234,
+            attempts: 2,
+            message: None,
+            stack: Some(String::from("at test()")),
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let line = encode_record(&sample());
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.matches('\n').count(), 1);
+
+        let decoded = decode_record(line.trim_end()).unwrap();
+        let original = sample();
+        assert_eq!(decoded.path, original.path);
+        assert_eq!(decoded.status, original.status);
+        assert_eq!(decoded.duration_ms, 1234);
+        assert_eq!(decoded.attempts, 2);
+        assert_eq!(decoded.message, None);
+        assert_eq!(decoded.stack, original.stack);
+        assert_eq!(decoded.subtests.len(), 2);
+        assert_eq!(decoded.subtests[0].name, original.subtests[0].name);
+        assert_eq!(decoded.subtests[0].expected, Some(SubtestStatus::Pass));
+        assert_eq!(
+            decoded.subtests[1].status,
+            SubtestStatus::PreconditionFailed
+        );
+    }
+
+    #[test]
+    fn test_truncated_and_duplicate_records() {
+        let mut first = sample();
+        first.path = String::from("/a.html");
+        let mut retried = first.clone();
+        retried.status = TestStatus::Ok;
+        let mut last = sample();
+        last.path = String::from("/b.html");
+
+        let mut contents = encode_record(&first);
+        contents.push_str(&encode_record(&retried));
+        let last_line = encode_record(&last);
+        contents.push_str(&last_line[..last_line.len() / 2]);
+
+        let results = decode(&contents);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results["/a.html"].status, TestStatus::Ok);
+        assert!(decode("").is_empty());
+    }
+}
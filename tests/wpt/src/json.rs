@@ -0,0 +1,31 @@
+//! JSON string encoding shared by the result writers
+
+use alloc::format;
+use alloc::string::String;
+
+/// Encode a string as a JSON string literal
+pub(crate) fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Encode an optional string, `null` if absent
+pub(crate) fn opt_string(s: &Option<String>) -> String {
+    match s {
+        Some(s) => string(s),
+        None => String::from("null"),
+    }
+}
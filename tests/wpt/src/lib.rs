@@ -4,8 +4,12 @@
 
 #![no_std]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+pub mod checkpoint;
 pub mod harness;
+mod json;
 pub mod manifest;
 pub mod results;
 pub mod runner;
@@ -64,6 +68,23 @@ pub enum ExpectedResult {
     Flaky,
 }
 
+impl ExpectedResult {
+    /// Whether a test with this expectation ending in `status` is as
+    /// expected. Skips are decided by the runner, not the test, so they
+    /// always are.
+    pub fn matches(&self, status: TestStatus) -> bool {
+        matches!(
+            (self, status),
+            (ExpectedResult::Flaky, _)
+                | (_, TestStatus::Skip)
+                | (ExpectedResult::Pass, TestStatus::Ok)
+                | (ExpectedResult::Fail, TestStatus::Error)
+                | (ExpectedResult::Timeout, TestStatus::Timeout)
+                | (ExpectedResult::Crash, TestStatus::Crash)
+        )
+    }
+}
+
 /// WPT test result
 #[derive(Debug, Clone)]
 pub struct TestResult {
@@ -73,8 +94,10 @@ pub struct TestResult {
     pub status: TestStatus,
     /// Subtest results (for testharness.js tests)
     pub subtests: Vec<SubtestResult>,
-    /// Duration in milliseconds
+    /// Duration in milliseconds, summed over all attempts
     pub duration_ms: u64,
+    /// Times the test was run; above 1 when a flaky test was retried
+    pub attempts: u32,
     /// Error message (if any)
     pub message: Option<String>,
     /// Stack trace (if any)
@@ -96,6 +119,31 @@ pub enum TestStatus {
     Skip,
 }
 
+impl TestStatus {
+    /// Status name as used in wptreport.json
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestStatus::Ok => "OK",
+            TestStatus::Error => "ERROR",
+            TestStatus::Timeout => "TIMEOUT",
+            TestStatus::Crash => "CRASH",
+            TestStatus::Skip => "SKIP",
+        }
+    }
+
+    /// Parse a status name from [`TestStatus::as_str`]
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "OK" => Some(TestStatus::Ok),
+            "ERROR" => Some(TestStatus::Error),
+            "TIMEOUT" => Some(TestStatus::Timeout),
+            "CRASH" => Some(TestStatus::Crash),
+            "SKIP" => Some(TestStatus::Skip),
+            _ => None,
+        }
+    }
+}
+
 /// Subtest result (for testharness.js)
 #[derive(Debug, Clone)]
 pub struct SubtestResult {
@@ -124,6 +172,31 @@ pub enum SubtestStatus {
     PreconditionFailed,
 }
 
+impl SubtestStatus {
+    /// Status name as used in wptreport.json
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubtestStatus::Pass => "PASS",
+            SubtestStatus::Fail => "FAIL",
+            SubtestStatus::Timeout => "TIMEOUT",
+            SubtestStatus::NotRun => "NOTRUN",
+            SubtestStatus::PreconditionFailed => "PRECONDITION_FAILED",
+        }
+    }
+
+    /// Parse a status name from [`SubtestStatus::as_str`]
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "PASS" => Some(SubtestStatus::Pass),
+            "FAIL" => Some(SubtestStatus::Fail),
+            "TIMEOUT" => Some(SubtestStatus::Timeout),
+            "NOTRUN" => Some(SubtestStatus::NotRun),
+            "PRECONDITION_FAILED" => Some(SubtestStatus::PreconditionFailed),
+            _ => None,
+        }
+    }
+}
+
 /// WPT run configuration
 #[derive(Debug, Clone)]
 pub struct WptConfig {
@@ -147,6 +220,28 @@ pub struct WptConfig {
     pub only_failing: bool,
     /// Run unstable tests
     pub run_unstable: bool,
+    /// Checkpoint to resume an interrupted run from: tests recorded in
+    /// it are not run again and their results are merged in
+    pub resume_from: Option<String>,
+    /// Tests between checkpoint writes (0 disables checkpoints)
+    pub checkpoint_interval: usize,
+    /// Extra runs a `Flaky` test gets while it does not pass
+    pub flaky_retries: u32,
+    /// Time a single testharness.js subtest may run before it is recorded
+    /// as timed out and the rest of the file carries on (`None` leaves
+    /// only the whole-test timeout)
+    pub subtest_timeout_ms: Option<u64>,
+}
+
+impl WptConfig {
+    /// Path of the checkpoint file this run writes
+    pub fn checkpoint_path(&self) -> String {
+        alloc::format!(
+            "{}/{}",
+            self.output_dir.trim_end_matches('/'),
+            checkpoint::CHECKPOINT_FILE
+        )
+    }
 }
 
 impl Default for WptConfig {
@@ -162,6 +257,10 @@ impl Default for WptConfig {
             output_dir: String::from("./wpt-results"),
             only_failing: false,
             run_unstable: false,
+            resume_from: None,
+            checkpoint_interval: 50,
+            flaky_retries: 3,
+            subtest_timeout_ms: None,
         }
     }
 }
@@ -192,7 +291,9 @@ pub struct RunSummary {
     pub skipped: usize,
     /// Unexpected results
     pub unexpected: usize,
-    /// Total duration in seconds
+    /// Flaky tests that needed more than one attempt
+    pub retried: usize,
+    /// Total duration in seconds (sum of test durations)
     pub duration_s: f64,
     /// Individual results
     pub results: Vec<TestResult>,
@@ -209,14 +310,22 @@ impl RunSummary {
             crashed: 0,
             skipped: 0,
             unexpected: 0,
+            retried: 0,
             duration_s: 0.0,
             results: Vec::new(),
         }
     }
 
-    /// Add a result
-    pub fn add_result(&mut self, result: TestResult) {
+    /// Add the result of `test`
+    pub fn add_result(&mut self, result: TestResult, test: &TestMetadata) {
         self.total += 1;
+        if !test.expected.matches(result.status) {
+            self.unexpected += 1;
+        }
+        if result.attempts > 1 {
+            self.retried += 1;
+        }
+        self.duration_s += result.duration_ms as f64 / 1000.0;
         match result.status {
             TestStatus::Ok => self.passed += 1,
             TestStatus::Error => self.failed += 1,
@@ -312,3 +421,62 @@ impl WptCategories {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(expected: ExpectedResult) -> TestMetadata {
+        TestMetadata {
+            path: String::from("/dom/test.html"),
+            test_type: TestType::TestHarness,
+            title: String::new(),
+            expected,
+            timeout: 10,
+            disabled: None,
+            preconditions: Vec::new(),
+        }
+    }
+
+    fn result(status: TestStatus) -> TestResult {
+        TestResult {
+            path: String::from("/dom/test.html"),
+            status,
+            subtests: Vec::new(),
+            duration_ms: 0,
+            attempts: 1,
+            message: None,
+            stack: None,
+        }
+    }
+
+    #[test]
+    fn test_unexpected_for_each_expectation() {
+        let statuses = [
+            TestStatus::Ok,
+            TestStatus::Error,
+            TestStatus::Timeout,
+            TestStatus::Crash,
+            TestStatus::Skip,
+        ];
+        // Whether each of `statuses` is unexpected
+        let cases = [
+            (ExpectedResult::Pass, [false, true, true, true, false]),
+            (ExpectedResult::Fail, [true, false, true, true, false]),
+            (ExpectedResult::Timeout, [true, true, false, true, false]),
+            (ExpectedResult::Crash, [true, true, true, false, false]),
+            (ExpectedResult::Flaky, [false, false, false, false, false]),
+        ];
+        for (expected, unexpected) in cases {
+            for (status, unexpected) in statuses.into_iter().zip(unexpected) {
+                let mut summary = RunSummary::new();
+                summary.add_result(result(status), &metadata(expected.clone()));
+                assert_eq!(
+                    summary.unexpected, unexpected as usize,
+                    "expected {:?}, got {:?}",
+                    expected, status
+                );
+            }
+        }
+    }
+}
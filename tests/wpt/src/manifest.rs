@@ -337,6 +337,133 @@ impl TestPath {
     }
 }
 
+/// Check whether a test path matches a `WptConfig::filter` glob
+///
+/// See [`compile_filter`] for the syntax.
+pub fn matches_filter(path: &str, pattern: &str) -> bool {
+    compile_filter(pattern).matches(path)
+}
+
+/// Compile a `WptConfig::filter` glob
+///
+/// `*` matches within a path segment, `**` matches any number of whole
+/// segments (including none, so `css/**` matches `css` itself), `?`
+/// matches one character and `{a,b}` matches either alternative. Leading
+/// slashes are ignored, and a trailing slash selects everything under a
+/// directory.
+pub fn compile_filter(pattern: &str) -> CompiledFilter {
+    let mut pattern = String::from(pattern.trim_start_matches('/'));
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+    let alternatives = expand_braces(&pattern)
+        .iter()
+        .map(|alternative| {
+            alternative
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| match segment {
+                    "**" => GlobSegment::AnySegments,
+                    _ => GlobSegment::Pattern(segment.chars().collect()),
+                })
+                .collect()
+        })
+        .collect();
+    CompiledFilter { alternatives }
+}
+
+/// A compiled test path glob, see [`compile_filter`]
+#[derive(Debug, Clone)]
+pub struct CompiledFilter {
+    /// Brace-expanded alternatives, split into path segments
+    alternatives: Vec<Vec<GlobSegment>>,
+}
+
+#[derive(Debug, Clone)]
+enum GlobSegment {
+    /// `**`
+    AnySegments,
+    /// A segment with `*` and `?` wildcards
+    Pattern(Vec<char>),
+}
+
+impl CompiledFilter {
+    /// Check whether a test path matches
+    pub fn matches(&self, path: &str) -> bool {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        self.alternatives
+            .iter()
+            .any(|globs| match_segments(globs, &segments))
+    }
+}
+
+fn match_segments(globs: &[GlobSegment], segments: &[&str]) -> bool {
+    match globs.split_first() {
+        None => segments.is_empty(),
+        Some((GlobSegment::AnySegments, rest)) => {
+            (0..=segments.len()).any(|skip| match_segments(rest, &segments[skip..]))
+        }
+        Some((GlobSegment::Pattern(pattern), rest)) => match segments.split_first() {
+            Some((segment, remaining)) => {
+                let chars: Vec<char> = segment.chars().collect();
+                match_segment(pattern, &chars) && match_segments(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| match_segment(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && match_segment(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && match_segment(rest, &text[1..]),
+    }
+}
+
+/// Expand `{a,b}` alternations; unbalanced braces are literal
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return alloc::vec![String::from(pattern)];
+    };
+
+    // Find the matching close brace and the top-level commas
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut close = None;
+    for (i, c) in pattern[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => commas.push(open + i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return alloc::vec![String::from(pattern)];
+    };
+
+    let prefix = &pattern[..open];
+    let suffix = &pattern[close + 1..];
+    let mut bounds = alloc::vec![open];
+    bounds.extend(commas);
+    bounds.push(close);
+    bounds
+        .windows(2)
+        .flat_map(|w| {
+            let option = &pattern[w[0] + 1..w[1]];
+            expand_braces(&alloc::format!("{}{}{}", prefix, option, suffix))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,4 +476,41 @@ mod tests {
         assert!(TestPath::matches("/dom/test.html", "/dom/*"));
         assert!(TestPath::matches("/dom/sub/test.html", "/dom/**/*.html"));
     }
+
+    #[test]
+    fn test_filter_globs() {
+        assert!(matches_filter(
+            "/dom/nodes/Node-appendChild.html",
+            "dom/*/Node-*"
+        ));
+        assert!(!matches_filter(
+            "/dom/nodes/deep/Node-a.html",
+            "dom/*/Node-*"
+        ));
+        assert!(matches_filter(
+            "/dom/nodes/deep/Node-a.html",
+            "/dom/**/Node-*"
+        ));
+        assert!(matches_filter("/dom/Node-a.html", "dom/**/Node-?.html"));
+        assert!(!matches_filter("/dom/Node-ab.html", "dom/**/Node-?.html"));
+
+        // `**` spans zero segments
+        assert!(matches_filter("/css", "css/**"));
+        assert!(matches_filter("/css/a/b.html", "css/**"));
+        assert!(!matches_filter("/cssom/a.html", "css/**"));
+
+        // Leading and trailing slashes
+        assert!(matches_filter("css/a.html", "/css/"));
+        assert!(matches_filter("/css/a/b.html", "css/"));
+        assert!(!matches_filter("/css-text/a.html", "/css/"));
+
+        // Alternation, including nested braces
+        let filter = compile_filter("{dom,html/{dom,semantics}}/**/*.{html,any.js}");
+        assert!(filter.matches("/dom/a.html"));
+        assert!(filter.matches("/html/semantics/x/y.any.js"));
+        assert!(filter.matches("/html/dom/z.html"));
+        assert!(!filter.matches("/html/rendering/z.html"));
+        assert!(!filter.matches("/dom/a.js"));
+        assert!(matches_filter("/a{b.html", "a{b.html"));
+    }
 }
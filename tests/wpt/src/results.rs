@@ -6,7 +6,7 @@ use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use crate::{RunSummary, SubtestResult, SubtestStatus, TestResult, TestStatus};
+use crate::{RunSummary, SubtestResult, TestResult, TestStatus, json};
 
 /// Result reporter trait
 pub trait ResultReporter {
@@ -38,14 +38,15 @@ impl ResultReporter for JsonReporter {
         json.push_str(&format!("  \"failed\": {},\n", summary.failed));
         json.push_str(&format!("  \"timeout\": {},\n", summary.timeout));
         json.push_str(&format!("  \"skipped\": {},\n", summary.skipped));
+        json.push_str(&format!("  \"retried\": {},\n", summary.retried));
         json.push_str(&format!("  \"pass_rate\": {:.2},\n", summary.pass_rate()));
         json.push_str(&format!("  \"duration_s\": {:.2},\n", summary.duration_s));
 
         json.push_str("  \"results\": [\n");
         for (i, result) in summary.results.iter().enumerate() {
             json.push_str(&format!(
-                "    {{\"path\": \"{}\", \"status\": \"{:?}\", \"duration_ms\": {}}}",
-                result.path, result.status, result.duration_ms
+                "    {{\"path\": \"{}\", \"status\": \"{:?}\", \"duration_ms\": {}, \"attempts\": {}}}",
+                result.path, result.status, result.duration_ms, result.attempts
             ));
             if i < summary.results.len() - 1 {
                 json.push_str(",\n");
@@ -83,6 +84,7 @@ impl ResultReporter for MarkdownReporter {
         md.push_str(&format!("| Failed | {} |\n", summary.failed));
         md.push_str(&format!("| Timeout | {} |\n", summary.timeout));
         md.push_str(&format!("| Skipped | {} |\n", summary.skipped));
+        md.push_str(&format!("| Retried | {} |\n", summary.retried));
         md.push_str(&format!("| Pass Rate | {:.2}% |\n", summary.pass_rate()));
         md.push_str(&format!("| Duration | {:.2}s |\n\n", summary.duration_s));
 
@@ -275,13 +277,7 @@ impl WptReportFormat {
             .iter()
             .map(|r| WptResult {
                 test: r.path.clone(),
-                status: match r.status {
-                    TestStatus::Ok => String::from("OK"),
-                    TestStatus::Error => String::from("ERROR"),
-                    TestStatus::Timeout => String::from("TIMEOUT"),
-                    TestStatus::Crash => String::from("CRASH"),
-                    TestStatus::Skip => String::from("SKIP"),
-                },
+                status: String::from(r.status.as_str()),
                 message: r.message.clone(),
                 duration: r.duration_ms,
                 subtests: r
@@ -289,15 +285,7 @@ impl WptReportFormat {
                     .iter()
                     .map(|s| WptSubtest {
                         name: s.name.clone(),
-                        status: match s.status {
-                            SubtestStatus::Pass => String::from("PASS"),
-                            SubtestStatus::Fail => String::from("FAIL"),
-                            SubtestStatus::Timeout => String::from("TIMEOUT"),
-                            SubtestStatus::NotRun => String::from("NOTRUN"),
-                            SubtestStatus::PreconditionFailed => {
-                                String::from("PRECONDITION_FAILED")
-                            }
-                        },
+                        status: String::from(s.status.as_str()),
                         message: s.message.clone(),
                     })
                     .collect(),
@@ -314,21 +302,24 @@ impl WptReportFormat {
         // Run info
         json.push_str("  \"run_info\": {\n");
         json.push_str(&format!(
-            "    \"product\": \"{}\",\n",
-            self.run_info.product
+            "    \"product\": {},\n",
+            json::string(&self.run_info.product)
         ));
         json.push_str(&format!(
-            "    \"browser_version\": \"{}\",\n",
-            self.run_info.browser_version
+            "    \"browser_version\": {},\n",
+            json::string(&self.run_info.browser_version)
         ));
-        json.push_str(&format!("    \"os\": \"{}\",\n", self.run_info.os));
         json.push_str(&format!(
-            "    \"os_version\": \"{}\",\n",
-            self.run_info.os_version
+            "    \"os\": {},\n",
+            json::string(&self.run_info.os)
         ));
         json.push_str(&format!(
-            "    \"revision\": \"{}\"\n",
-            self.run_info.revision
+            "    \"os_version\": {},\n",
+            json::string(&self.run_info.os_version)
+        ));
+        json.push_str(&format!(
+            "    \"revision\": {}\n",
+            json::string(&self.run_info.revision)
         ));
         json.push_str("  },\n");
 
@@ -336,16 +327,25 @@ impl WptReportFormat {
         json.push_str("  \"results\": [\n");
         for (i, result) in self.results.iter().enumerate() {
             json.push_str("    {\n");
-            json.push_str(&format!("      \"test\": \"{}\",\n", result.test));
+            json.push_str(&format!(
+                "      \"test\": {},\n",
+                json::string(&result.test)
+            ));
             json.push_str(&format!("      \"status\": \"{}\",\n", result.status));
+            json.push_str(&format!(
+                "      \"message\": {},\n",
+                json::opt_string(&result.message)
+            ));
             json.push_str(&format!("      \"duration\": {},\n", result.duration));
 
             // Subtests
             json.push_str("      \"subtests\": [\n");
             for (j, subtest) in result.subtests.iter().enumerate() {
                 json.push_str(&format!(
-                    "        {{\"name\": \"{}\", \"status\": \"{}\"}}",
-                    subtest.name, subtest.status
+                    "        {{\"name\": {}, \"status\": \"{}\", \"message\": {}}}",
+                    json::string(&subtest.name),
+                    subtest.status,
+                    json::opt_string(&subtest.message)
                 ));
                 if j < result.subtests.len() - 1 {
                     json.push_str(",\n");
@@ -369,6 +369,12 @@ impl WptReportFormat {
     }
 }
 
+/// Serialize a run as a `wptreport.json` document, the format wpt.fyi
+/// and the upstream WPT tooling consume
+pub fn to_wptreport(summary: &RunSummary, run_info: &RunInfo) -> String {
+    WptReportFormat::from_summary(summary, run_info.clone()).to_json()
+}
+
 /// Result comparison for regression detection
 pub struct ResultComparator {
     baseline: RunSummary,
@@ -470,3 +476,73 @@ impl ComparisonResult {
         summary
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SubtestStatus;
+    use alloc::vec;
+
+    #[test]
+    fn test_wptreport_document() {
+        let summary = RunSummary {
+            results: vec![
+                TestResult {
+                    path: String::from("/dom/nodes/Node-\"quoted\".html"),
+                    status: TestStatus::Ok,
+                    subtests: vec![
+                        SubtestResult {
+                            name: String::from("appendChild\n"),
+                            status: SubtestStatus::Pass,
+                            message: None,
+                            expected: None,
+                        },
+                        SubtestResult {
+                            name: String::from("removeChild"),
+                            status: SubtestStatus::PreconditionFailed,
+                            message: Some(String::from("assert_equals: \\ got 1")),
+                            expected: None,
+                        },
+                    ],
+                    duration_ms: 12,
+                    attempts: 1,
+                    message: None,
+                    stack: None,
+                },
+                TestResult {
+                    path: String::from("/css/crash.html"),
+                    status: TestStatus::Crash,
+                    subtests: Vec::new(),
+                    duration_ms: 3,
+                    attempts: 1,
+                    message: Some(String::from("renderer exited")),
+                    stack: None,
+                },
+            ],
+            ..RunSummary::default()
+        };
+        let run_info = RunInfo {
+            revision: String::from("abc123"),
+            ..RunInfo::default()
+        };
+
+        let report = to_wptreport(&summary, &run_info);
+        assert!(report.contains("\"product\": \"kpio\""));
+        assert!(report.contains("\"os\": \"kpio-os\""));
+        assert!(report.contains("\"revision\": \"abc123\""));
+        assert!(report.contains("\"test\": \"/dom/nodes/Node-\\\"quoted\\\".html\""));
+        assert!(report.contains("\"status\": \"OK\""));
+        assert!(report.contains("\"status\": \"CRASH\""));
+        assert!(report.contains("\"message\": \"renderer exited\""));
+        assert!(
+            report.contains(
+                "{\"name\": \"appendChild\\n\", \"status\": \"PASS\", \"message\": null}"
+            )
+        );
+        assert!(report.contains(
+            "{\"name\": \"removeChild\", \"status\": \"PRECONDITION_FAILED\", \
+             \"message\": \"assert_equals: \\\\ got 1\"}"
+        ));
+        assert!(report.contains("\"subtests\": [\n      ]"));
+    }
+}
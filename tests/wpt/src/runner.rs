@@ -3,9 +3,12 @@
 //! Executes WPT tests against the KPIO browser.
 
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 
+use crate::checkpoint::{self, CheckpointStore};
+use crate::manifest::compile_filter;
 use crate::{
     ExpectedResult, RunSummary, SubtestResult, SubtestStatus, TestMetadata, TestResult, TestStatus,
     TestType, WptConfig,
@@ -19,6 +22,8 @@ pub struct WptRunner {
     tests: Vec<TestMetadata>,
     /// Event listeners
     listeners: Vec<Box<dyn RunnerListener + Send + Sync>>,
+    /// Where checkpoints are written and resumed from
+    checkpoint_store: Option<Box<dyn CheckpointStore + Send + Sync>>,
 }
 
 /// Runner event listener
@@ -76,16 +81,57 @@ impl RunnerListener for ConsoleListener {
     }
 }
 
+/// A report from testharness.js in the page under test
+#[derive(Debug, Clone)]
+pub enum HarnessMessage {
+    /// A subtest started running
+    SubtestStart { name: String },
+    /// A subtest finished
+    SubtestEnd(SubtestResult),
+    /// The harness finished; no more subtests follow
+    Complete {
+        status: TestStatus,
+        message: Option<String>,
+    },
+}
+
+/// Connection to the harness in a running test page
+pub trait HarnessChannel {
+    /// Wait up to `timeout_ms` for the next message, `None` if none
+    /// arrived in time
+    fn recv(&mut self, timeout_ms: u64) -> Option<HarnessMessage>;
+
+    /// Milliseconds since the test page was loaded
+    fn elapsed_ms(&self) -> u64;
+
+    /// Tell the harness to give up on a hung subtest (testharness.js
+    /// `force_timeout`) so the subtests after it can run
+    fn abandon_subtest(&mut self, name: &str);
+}
+
 impl WptRunner {
     /// Create a new WPT runner
     pub fn new(config: WptConfig) -> Self {
+        #[cfg(feature = "std")]
+        let checkpoint_store: Option<Box<dyn CheckpointStore + Send + Sync>> =
+            Some(Box::new(checkpoint::FsStore));
+        #[cfg(not(feature = "std"))]
+        let checkpoint_store = None;
+
         Self {
             config,
             tests: Vec::new(),
             listeners: Vec::new(),
+            checkpoint_store,
         }
     }
 
+    /// Set where checkpoints are stored. Without a store (the default
+    /// without the `std` feature) runs are not checkpointed or resumed.
+    pub fn set_checkpoint_store<S: CheckpointStore + Send + Sync + 'static>(&mut self, store: S) {
+        self.checkpoint_store = Some(Box::new(store));
+    }
+
     /// Add a listener
     pub fn add_listener<L: RunnerListener + Send + Sync + 'static>(&mut self, listener: L) {
         self.listeners.push(Box::new(listener));
@@ -114,28 +160,86 @@ impl WptRunner {
         self.tests.retain(|t| t.test_type == test_type);
     }
 
-    /// Run all loaded tests
+    /// Run all loaded tests matching `filter`
+    ///
+    /// Results are checkpointed every `checkpoint_interval` tests. With
+    /// `resume_from` set, tests already in that checkpoint are not run;
+    /// their recorded results go into the summary instead.
     pub fn run(&mut self) -> RunSummary {
-        let total_tests = self.tests.len();
+        let filter = self.config.filter.as_deref().map(compile_filter);
+        let tests: Vec<&TestMetadata> = self
+            .tests
+            .iter()
+            .filter(|test| filter.as_ref().is_none_or(|f| f.matches(&test.path)))
+            .collect();
+        let total_tests = tests.len();
 
         for listener in &mut self.listeners {
             listener.on_run_start(total_tests);
         }
 
         let mut summary = RunSummary::new();
+        let mut resumed = self.load_checkpoint();
+        let checkpoint_path = self.config.checkpoint_path();
+        let interval = self.config.checkpoint_interval;
+
+        // Start this run's checkpoint with the results it carries over,
+        // so it can be resumed from in turn
+        if interval > 0 {
+            let carried: String = tests
+                .iter()
+                .filter_map(|test| resumed.get(&test.path))
+                .map(checkpoint::encode_record)
+                .collect();
+            Self::write_checkpoint(
+                &mut self.checkpoint_store,
+                &checkpoint_path,
+                &carried,
+                false,
+            );
+        }
+
+        let mut pending = String::new();
+        let mut pending_count = 0;
+
+        for test in tests {
+            if let Some(result) = resumed.remove(&test.path) {
+                summary.add_result(result, test);
+                continue;
+            }
 
-        for test in &self.tests {
             for listener in &mut self.listeners {
                 listener.on_test_start(test);
             }
 
-            let result = self.run_single_test(test);
+            let result = run_with_retries(test, self.config.flaky_retries, |test| {
+                self.run_single_test(test)
+            });
 
             for listener in &mut self.listeners {
                 listener.on_test_end(test, &result);
             }
 
-            summary.add_result(result);
+            if interval > 0 {
+                pending.push_str(&checkpoint::encode_record(&result));
+                pending_count += 1;
+                if pending_count >= interval {
+                    Self::write_checkpoint(
+                        &mut self.checkpoint_store,
+                        &checkpoint_path,
+                        &pending,
+                        true,
+                    );
+                    pending.clear();
+                    pending_count = 0;
+                }
+            }
+
+            summary.add_result(result, test);
+        }
+
+        if pending_count > 0 {
+            Self::write_checkpoint(&mut self.checkpoint_store, &checkpoint_path, &pending, true);
         }
 
         for listener in &mut self.listeners {
@@ -145,6 +249,35 @@ impl WptRunner {
         summary
     }
 
+    /// Results recorded in the checkpoint named by `resume_from`, by path
+    fn load_checkpoint(&self) -> BTreeMap<String, TestResult> {
+        match (&self.config.resume_from, &self.checkpoint_store) {
+            (Some(path), Some(store)) => store
+                .read(path)
+                .map(|contents| checkpoint::decode(&contents))
+                .unwrap_or_default(),
+            _ => BTreeMap::new(),
+        }
+    }
+
+    /// Write or append checkpoint records
+    fn write_checkpoint(
+        store: &mut Option<Box<dyn CheckpointStore + Send + Sync>>,
+        path: &str,
+        records: &str,
+        append: bool,
+    ) {
+        let Some(store) = store else {
+            return;
+        };
+        // A failed write only costs the ability to resume
+        let _ = if append {
+            store.append(path, records)
+        } else {
+            store.write(path, records)
+        };
+    }
+
     /// Run a single test
     fn run_single_test(&self, test: &TestMetadata) -> TestResult {
         // Check if disabled
@@ -154,6 +287,7 @@ impl WptRunner {
                 status: TestStatus::Skip,
                 subtests: Vec::new(),
                 duration_ms: 0,
+                attempts: 1,
                 message: test.disabled.clone(),
                 stack: None,
             };
@@ -170,6 +304,7 @@ impl WptRunner {
                     status: TestStatus::Skip,
                     subtests: Vec::new(),
                     duration_ms: 0,
+                    attempts: 1,
                     message: Some(String::from("Manual test skipped in automated run")),
                     stack: None,
                 }
@@ -177,19 +312,29 @@ impl WptRunner {
         }
     }
 
+    /// Collect a testharness.js test's results from its page as they
+    /// arrive, applying `subtest_timeout_ms` to each subtest
+    pub fn collect_testharness_result(
+        &self,
+        test: &TestMetadata,
+        channel: &mut dyn HarnessChannel,
+    ) -> TestResult {
+        collect_subtests(test, channel, self.config.subtest_timeout_ms)
+    }
+
     /// Run a testharness.js test
     fn run_testharness_test(&self, test: &TestMetadata) -> TestResult {
         // In real implementation:
         // 1. Start browser
         // 2. Navigate to test page
-        // 3. Wait for testharness.js to complete
-        // 4. Collect results from page
+        // 3. Collect results with `collect_testharness_result`
 
         TestResult {
             path: test.path.clone(),
             status: TestStatus::Ok,
             subtests: Vec::new(),
             duration_ms: 0,
+            attempts: 1,
             message: None,
             stack: None,
         }
@@ -207,6 +352,7 @@ impl WptRunner {
             status: TestStatus::Ok,
             subtests: Vec::new(),
             duration_ms: 0,
+            attempts: 1,
             message: None,
             stack: None,
         }
@@ -223,6 +369,7 @@ impl WptRunner {
             status: TestStatus::Ok,
             subtests: Vec::new(),
             duration_ms: 0,
+            attempts: 1,
             message: None,
             stack: None,
         }
@@ -235,14 +382,124 @@ impl WptRunner {
 
     /// Check if result is expected
     pub fn is_expected(&self, test: &TestMetadata, result: &TestResult) -> bool {
-        match (&test.expected, &result.status) {
-            (ExpectedResult::Pass, TestStatus::Ok) => true,
-            (ExpectedResult::Fail, TestStatus::Error) => true,
-            (ExpectedResult::Timeout, TestStatus::Timeout) => true,
-            (ExpectedResult::Crash, TestStatus::Crash) => true,
-            (ExpectedResult::Flaky, _) => true,
-            _ => false,
+        test.expected.matches(result.status)
+    }
+}
+
+/// Run `test`, running a `Flaky` one again while it does not pass, up to
+/// `retries` more times. The last attempt is the result, with the
+/// durations of all attempts summed.
+fn run_with_retries<F>(test: &TestMetadata, retries: u32, mut run: F) -> TestResult
+where
+    F: FnMut(&TestMetadata) -> TestResult,
+{
+    let mut result = run(test);
+    if test.expected != ExpectedResult::Flaky {
+        return result;
+    }
+
+    let mut attempts = 1;
+    let mut duration_ms = result.duration_ms;
+    while attempts <= retries
+        && matches!(
+            result.status,
+            TestStatus::Error | TestStatus::Timeout | TestStatus::Crash
+        )
+    {
+        result = run(test);
+        attempts += 1;
+        duration_ms += result.duration_ms;
+    }
+    result.attempts = attempts;
+    result.duration_ms = duration_ms;
+    result
+}
+
+/// Read harness messages until the harness completes or the test's own
+/// timeout runs out. A subtest running longer than `subtest_timeout_ms`
+/// is recorded as `Timeout` and abandoned; the subtests reported before
+/// it are kept and the ones after it still run. When the whole test times
+/// out, the subtests that finished by then are kept.
+fn collect_subtests(
+    test: &TestMetadata,
+    channel: &mut dyn HarnessChannel,
+    subtest_timeout_ms: Option<u64>,
+) -> TestResult {
+    let test_timeout_ms = u64::from(test.timeout) * 1000;
+    let mut subtests: Vec<SubtestResult> = Vec::new();
+    // The subtest running now and when it started
+    let mut running: Option<(String, u64)> = None;
+    let mut abandoned: Vec<String> = Vec::new();
+
+    let (status, message) = loop {
+        let now = channel.elapsed_ms();
+        if now >= test_timeout_ms {
+            break (TestStatus::Timeout, None);
+        }
+        let mut wait = test_timeout_ms - now;
+        if let (Some((_, started)), Some(limit)) = (&running, subtest_timeout_ms) {
+            wait = wait.min((started + limit).saturating_sub(now));
+        }
+
+        match channel.recv(wait) {
+            Some(HarnessMessage::SubtestStart { name }) => {
+                running = Some((name, channel.elapsed_ms()));
+            }
+            Some(HarnessMessage::SubtestEnd(result)) => {
+                if running
+                    .as_ref()
+                    .is_some_and(|(name, _)| *name == result.name)
+                {
+                    running = None;
+                }
+                // An abandoned subtest's own late result is already recorded
+                if let Some(i) = abandoned.iter().position(|name| *name == result.name) {
+                    abandoned.remove(i);
+                    continue;
+                }
+                subtests.push(result);
+            }
+            Some(HarnessMessage::Complete { status, message }) => break (status, message),
+            None => {
+                let Some(limit) = subtest_timeout_ms else {
+                    continue;
+                };
+                match running.take() {
+                    Some((name, started)) if channel.elapsed_ms() >= started + limit => {
+                        channel.abandon_subtest(&name);
+                        subtests.push(SubtestResult {
+                            name: name.clone(),
+                            status: SubtestStatus::Timeout,
+                            message: Some(alloc::format!("Subtest timed out after {}ms", limit)),
+                            expected: None,
+                        });
+                        abandoned.push(name);
+                    }
+                    still_running => running = still_running,
+                }
+            }
         }
+    };
+
+    // Like `TestHarness`, failing or timed out subtests fail the test
+    let status = if status == TestStatus::Ok
+        && subtests
+            .iter()
+            .any(|s| matches!(s.status, SubtestStatus::Fail | SubtestStatus::Timeout))
+    {
+        TestStatus::Error
+    } else {
+        status
+    };
+
+    TestResult {
+        path: test.path.clone(),
+        status,
+        subtests,
+        duration_ms: channel.elapsed_ms(),
+        attempts: 1,
+        message,
+        stack: None,
     }
 }
 
@@ -441,3 +698,293 @@ impl PromiseTest {
         self.harness
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::MemoryStore;
+    use alloc::format;
+    use alloc::sync::Arc;
+    use spin::Mutex;
+
+    /// Shares one in-memory store between runs
+    #[derive(Clone, Default)]
+    struct SharedStore(Arc<Mutex<MemoryStore>>);
+
+    impl CheckpointStore for SharedStore {
+        fn read(&self, path: &str) -> Option<String> {
+            self.0.lock().read(path)
+        }
+
+        fn write(&mut self, path: &str, contents: &str) -> Result<(), String> {
+            self.0.lock().write(path, contents)
+        }
+
+        fn append(&mut self, path: &str, contents: &str) -> Result<(), String> {
+            self.0.lock().append(path, contents)
+        }
+    }
+
+    fn runner(store: &SharedStore, config: WptConfig, count: usize) -> WptRunner {
+        let mut runner = WptRunner::new(config);
+        runner.set_checkpoint_store(store.clone());
+        for i in 0..count {
+            runner.tests.push(TestMetadata {
+                path: format!("/dom/test-{}.html", i),
+                test_type: TestType::TestHarness,
+                title: String::new(),
+                expected: ExpectedResult::Pass,
+                timeout: 10,
+                disabled: None,
+                preconditions: Vec::new(),
+            });
+        }
+        runner
+    }
+
+    #[test]
+    fn test_resume_from_truncated_checkpoint() {
+        let store = SharedStore::default();
+        let config = WptConfig {
+            checkpoint_interval: 3,
+            ..WptConfig::default()
+        };
+        let path = config.checkpoint_path();
+        assert_eq!(path, "./wpt-results/checkpoint.jsonl");
+
+        // A first run checkpoints every 3 tests
+        runner(&store, config.clone(), 7).run();
+        assert_eq!(store.read(&path).unwrap().lines().count(), 7);
+
+        // Simulate a crash: two slow failing results recorded, then a
+        // record cut off mid-write
+        let slow_failure = |i: usize| TestResult {
+            path: format!("/dom/test-{}.html", i),
+            status: TestStatus::Error,
+            subtests: Vec::new(),
+            duration_ms: 1500,
+            attempts: 1,
+            message: Some(String::from("failed")),
+            stack: None,
+        };
+        let mut crashed = checkpoint::encode_record(&slow_failure(0));
+        crashed.push_str(&checkpoint::encode_record(&slow_failure(1)));
+        crashed.push_str(&checkpoint::encode_record(&slow_failure(1)));
+        crashed.push_str("{\"path\":\"/dom/test-2.html\",\"sta");
+        let resume_path = String::from("/old/checkpoint.jsonl");
+        store.clone().write(&resume_path, &crashed).unwrap();
+
+        let summary = runner(
+            &store,
+            WptConfig {
+                resume_from: Some(resume_path),
+                ..config
+            },
+            5,
+        )
+        .run();
+
+        // Tests 0 and 1 come from the checkpoint, 2..5 ran again
+        assert_eq!(summary.total, 5);
+        assert_eq!(summary.failed, 2);
+        assert_eq!(summary.passed, 3);
+        assert_eq!(summary.unexpected, 2);
+        assert_eq!(summary.duration_s, 3.0);
+        assert_eq!(summary.results[1].message.as_deref(), Some("failed"));
+
+        // The new checkpoint holds every result once
+        let results = checkpoint::decode(&store.read(&path).unwrap());
+        assert_eq!(results.len(), 5);
+        assert_eq!(results["/dom/test-0.html"].status, TestStatus::Error);
+        assert_eq!(results["/dom/test-4.html"].status, TestStatus::Ok);
+    }
+
+    #[test]
+    fn test_filter_selects_tests() {
+        let store = SharedStore::default();
+        let config = WptConfig {
+            filter: Some(String::from("/dom/test-{1,3}.html")),
+            ..WptConfig::default()
+        };
+        let summary = runner(&store, config, 5).run();
+        let paths: Vec<&str> = summary.results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["/dom/test-1.html", "/dom/test-3.html"]);
+    }
+
+    #[test]
+    fn test_flaky_retries() {
+        let test = |expected| TestMetadata {
+            path: String::from("/dom/flaky.html"),
+            test_type: TestType::TestHarness,
+            title: String::new(),
+            expected,
+            timeout: 10,
+            disabled: None,
+            preconditions: Vec::new(),
+        };
+        // Each run takes 10ms; the test passes on the given attempt
+        let passing_on = |pass_on: u32| {
+            let mut runs = 0;
+            move |test: &TestMetadata| {
+                runs += 1;
+                TestResult {
+                    path: test.path.clone(),
+                    status: if runs >= pass_on {
+                        TestStatus::Ok
+                    } else {
+                        TestStatus::Timeout
+                    },
+                    subtests: Vec::new(),
+                    duration_ms: 10,
+                    attempts: 1,
+                    message: None,
+                    stack: None,
+                }
+            }
+        };
+
+        let flaky = test(ExpectedResult::Flaky);
+        let recovered = run_with_retries(&flaky, 3, passing_on(3));
+        assert_eq!(recovered.status, TestStatus::Ok);
+        assert_eq!(recovered.attempts, 3);
+        assert_eq!(recovered.duration_ms, 30);
+
+        let stable = run_with_retries(&flaky, 3, passing_on(1));
+        assert_eq!(stable.attempts, 1);
+
+        let failing = run_with_retries(&flaky, 3, passing_on(10));
+        assert_eq!(failing.status, TestStatus::Timeout);
+        assert_eq!(failing.attempts, 4);
+        assert_eq!(failing.duration_ms, 40);
+
+        // Only tests expected to be flaky are retried
+        let pass = test(ExpectedResult::Pass);
+        let not_retried = run_with_retries(&pass, 3, passing_on(2));
+        assert_eq!(not_retried.status, TestStatus::Timeout);
+        assert_eq!(not_retried.attempts, 1);
+
+        let mut summary = RunSummary::new();
+        summary.add_result(stable, &flaky);
+        summary.add_result(recovered, &flaky);
+        summary.add_result(not_retried, &pass);
+        assert_eq!(summary.passed, 2);
+        assert_eq!(summary.retried, 1);
+        assert_eq!(summary.unexpected, 1);
+    }
+
+    /// Replays messages at fixed times on a simulated clock
+    struct ScriptedChannel {
+        now: u64,
+        script: Vec<(u64, HarnessMessage)>,
+        abandoned: Vec<String>,
+    }
+
+    impl HarnessChannel for ScriptedChannel {
+        fn recv(&mut self, timeout_ms: u64) -> Option<HarnessMessage> {
+            match self.script.first() {
+                Some((at, _)) if *at <= self.now + timeout_ms => {
+                    self.now = self.now.max(*at);
+                    Some(self.script.remove(0).1)
+                }
+                _ => {
+                    self.now += timeout_ms;
+                    None
+                }
+            }
+        }
+
+        fn elapsed_ms(&self) -> u64 {
+            self.now
+        }
+
+        fn abandon_subtest(&mut self, name: &str) {
+            self.abandoned.push(String::from(name));
+        }
+    }
+
+    #[test]
+    fn test_subtest_timeout() {
+        let test = TestMetadata {
+            path: String::from("/dom/many-subtests.html"),
+            test_type: TestType::TestHarness,
+            title: String::new(),
+            expected: ExpectedResult::Pass,
+            timeout: 1,
+            disabled: None,
+            preconditions: Vec::new(),
+        };
+        let start = |name: &str| HarnessMessage::SubtestStart {
+            name: String::from(name),
+        };
+        let end = |name: &str, status| {
+            HarnessMessage::SubtestEnd(SubtestResult {
+                name: String::from(name),
+                status,
+                message: None,
+                expected: None,
+            })
+        };
+        let channel = |script| ScriptedChannel {
+            now: 0,
+            script,
+            abandoned: Vec::new(),
+        };
+        let complete = HarnessMessage::Complete {
+            status: TestStatus::Ok,
+            message: None,
+        };
+
+        // With a watchdog, "hang" times out and the rest still run; its
+        // late result is dropped
+        let mut with_watchdog = channel(alloc::vec![
+            (0, start("first")),
+            (10, end("first", SubtestStatus::Pass)),
+            (10, start("hang")),
+            (150, start("after")),
+            (160, end("after", SubtestStatus::Pass)),
+            (170, end("hang", SubtestStatus::Fail)),
+            (200, complete),
+        ]);
+        let result = collect_subtests(&test, &mut with_watchdog, Some(100));
+        let subtests: Vec<(&str, SubtestStatus)> = result
+            .subtests
+            .iter()
+            .map(|s| (s.name.as_str(), s.status))
+            .collect();
+        assert_eq!(
+            subtests,
+            [
+                ("first", SubtestStatus::Pass),
+                ("hang", SubtestStatus::Timeout),
+                ("after", SubtestStatus::Pass),
+            ]
+        );
+        assert_eq!(with_watchdog.abandoned, ["hang"]);
+        assert_eq!(result.status, TestStatus::Error);
+        assert_eq!(result.duration_ms, 200);
+
+        // Without one the whole test times out, keeping what finished
+        let mut without = channel(alloc::vec![
+            (0, start("first")),
+            (10, end("first", SubtestStatus::Pass)),
+            (10, start("hang")),
+        ]);
+        let result = collect_subtests(&test, &mut without, None);
+        assert_eq!(result.status, TestStatus::Timeout);
+        assert_eq!(result.duration_ms, 1000);
+        assert_eq!(result.subtests.len(), 1);
+        assert_eq!(result.subtests[0].status, SubtestStatus::Pass);
+    }
+
+    #[test]
+    fn test_checkpoints_disabled() {
+        let store = SharedStore::default();
+        let config = WptConfig {
+            checkpoint_interval: 0,
+            ..WptConfig::default()
+        };
+        let summary = runner(&store, config.clone(), 3).run();
+        assert_eq!(summary.total, 3);
+        assert_eq!(store.read(&config.checkpoint_path()), None);
+    }
+}
@@ -63,6 +63,15 @@ pub enum Command {
     /// Move the mouse to specific coordinates.
     MouseMove(MouseMoveArgs),
 
+    /// Record input commands sent to an instance into a replay script.
+    Record(RecordArgs),
+
+    /// Replay a recorded input script against an instance.
+    Replay(ReplayArgs),
+
+    /// Stream mutated input to the serial console and save inputs that crash or hang it.
+    FuzzSerial(FuzzSerialArgs),
+
     /// Save, restore, list, or delete VM state snapshots.
     Snapshot(SnapshotArgs),
 
@@ -87,6 +96,12 @@ pub enum Command {
     /// Verify test results against a manifest.
     Verify(VerifyArgs),
 
+    /// Write an lcov report of guest code executed by an instance.
+    Coverage(CoverageArgs),
+
+    /// Print the gdbstub address of an instance and optionally launch gdb.
+    GdbAttach(GdbAttachArgs),
+
     /// Run pre-flight health checks without creating an instance.
     Health,
 
@@ -148,6 +163,22 @@ pub struct CreateArgs {
     /// Additional QEMU arguments passed through verbatim.
     #[arg(long)]
     pub extra_args: Vec<String>,
+
+    /// Record executed guest code for the `coverage` command (TCG only).
+    #[arg(long, default_value_t = false)]
+    pub coverage: bool,
+
+    /// Expose the QEMU gdbstub for the `gdb-attach` command.
+    #[arg(long, default_value_t = false)]
+    pub gdb: bool,
+
+    /// gdbstub TCP port (default: first free port from 1234). Implies --gdb.
+    #[arg(long)]
+    pub gdb_port: Option<u16>,
+
+    /// Hold the CPU at reset until a debugger continues it. Implies --gdb.
+    #[arg(long, default_value_t = false)]
+    pub gdb_wait: bool,
 }
 
 // ── serial ───────────────────────────────────────────────────────────
@@ -352,6 +383,74 @@ pub struct MouseMoveArgs {
     pub y: u32,
 }
 
+// ── record ───────────────────────────────────────────────────────────
+
+#[derive(clap::Args, Debug)]
+pub struct RecordArgs {
+    /// Instance name.
+    pub name: String,
+
+    /// Script file to record to (default: input.jsonl in the instance store).
+    #[arg(long, conflicts_with = "stop")]
+    pub output: Option<PathBuf>,
+
+    /// Stop the active recording.
+    #[arg(long, default_value_t = false)]
+    pub stop: bool,
+}
+
+// ── replay ───────────────────────────────────────────────────────────
+
+#[derive(clap::Args, Debug)]
+pub struct ReplayArgs {
+    /// Instance name.
+    pub name: String,
+
+    /// Recorded input script to replay.
+    #[arg(long)]
+    pub script: PathBuf,
+
+    /// Playback speed multiplier (2.0 replays twice as fast).
+    #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+    pub speed: f64,
+}
+
+/// Parse a positive, finite speed multiplier.
+pub fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("invalid speed: {s} (must be a positive number)")),
+    }
+}
+
+// ── fuzz-serial ──────────────────────────────────────────────────────
+
+#[derive(clap::Args, Debug)]
+pub struct FuzzSerialArgs {
+    /// Instance name.
+    pub name: String,
+
+    /// Number of inputs to send.
+    #[arg(long, default_value_t = 1000)]
+    pub iterations: usize,
+
+    /// Mutator seed (default: derived from the current time).
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// File of command lines to mutate, one per line (default: built-in corpus).
+    #[arg(long)]
+    pub corpus: Option<PathBuf>,
+
+    /// Directory to save reproducers in (default: fuzz/ in the instance store).
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Milliseconds to wait for the console to answer before reporting a hang.
+    #[arg(long, default_value_t = 5000)]
+    pub hang_timeout: u64,
+}
+
 // ── snapshot ─────────────────────────────────────────────────────────
 
 #[derive(clap::Args, Debug)]
@@ -473,6 +572,55 @@ pub struct VerifyArgs {
     pub mode: Option<String>,
 }
 
+// ── coverage ─────────────────────────────────────────────────────────
+
+#[derive(clap::Args, Debug)]
+pub struct CoverageArgs {
+    /// Instance name (must have been created with --coverage).
+    pub name: String,
+
+    /// Kernel binary with debug info (default: debug build output).
+    #[arg(long)]
+    pub kernel: Option<PathBuf>,
+
+    /// Path to write the lcov report (default: coverage.info in the instance store).
+    #[arg(long)]
+    pub lcov: Option<PathBuf>,
+
+    /// Offset the kernel was loaded at, subtracted from traced addresses.
+    #[arg(long, value_parser = parse_address)]
+    pub load_offset: Option<u64>,
+}
+
+/// Parse a hex (`0x...`) or decimal address.
+pub fn parse_address(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid address: {s}"))
+}
+
+// ── gdb-attach ───────────────────────────────────────────────────────
+
+#[derive(clap::Args, Debug)]
+pub struct GdbAttachArgs {
+    /// Instance name (must have been created with --gdb).
+    pub name: String,
+
+    /// Kernel binary with debug info (default: debug build output).
+    #[arg(long)]
+    pub kernel: Option<PathBuf>,
+
+    /// Launch gdb connected to the instance instead of only printing the address.
+    #[arg(long, default_value_t = false)]
+    pub launch: bool,
+
+    /// gdb binary to launch (default: first of rust-gdb, gdb, gdb-multiarch on PATH).
+    #[arg(long)]
+    pub gdb: Option<String>,
+}
+
 // ── help ─────────────────────────────────────────────────────────────
 
 #[derive(clap::Args, Debug)]
@@ -0,0 +1,489 @@
+//! Coverage subcommand — map QEMU execution traces to kernel source lines.
+//!
+//! Instances created with `--coverage` run under TCG with QEMU's `in_asm`
+//! log enabled, which records every guest instruction the first time it is
+//! translated. `coverage` reads that log, maps the addresses back to source
+//! lines and functions using the kernel's debug info (via `addr2line` and
+//! `nm`), and writes an lcov report.
+//!
+//! Coverage is coarse: a line counts as hit once any of its instructions
+//! was translated, and hit counts are not execution counts.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+use crate::cli::CoverageArgs;
+use crate::error::KpioTestError;
+use crate::store;
+use crate::watchdog;
+
+// ── Output types ─────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct CoverageOutput {
+    pub name: String,
+    pub lcov_path: PathBuf,
+    pub addresses: usize,
+    pub files: usize,
+    pub lines_hit: usize,
+    pub functions_total: usize,
+    pub functions_hit: usize,
+}
+
+// ── QEMU configuration ───────────────────────────────────────────────
+
+/// QEMU arguments that record translated guest code to `trace_path`.
+///
+/// Tracing requires the TCG accelerator; KVM executes guest code natively.
+pub fn qemu_args(trace_path: &Path) -> Vec<String> {
+    vec![
+        "-accel".to_string(),
+        "tcg".to_string(),
+        "-d".to_string(),
+        "in_asm".to_string(),
+        "-D".to_string(),
+        trace_path.display().to_string(),
+    ]
+}
+
+// ── Trace parsing ────────────────────────────────────────────────────
+
+/// Extract guest code addresses from a QEMU log.
+///
+/// Understands both `-d in_asm` instruction lines (`0xffff...:  insn`) and
+/// `-d exec` block lines (`Trace 0: 0x... [cs_base/pc/flags/cflags] sym`).
+pub fn parse_trace(content: &str) -> BTreeSet<u64> {
+    let mut addresses = BTreeSet::new();
+
+    for line in content.lines() {
+        let line = line.trim_start();
+        if let Some(rest) = line.strip_prefix("0x") {
+            if let Some((addr, _)) = rest.split_once(':') {
+                if let Ok(addr) = u64::from_str_radix(addr, 16) {
+                    addresses.insert(addr);
+                }
+            }
+        } else if line.starts_with("Trace ") {
+            if let Some(addr) = parse_exec_line(line) {
+                addresses.insert(addr);
+            }
+        }
+    }
+
+    addresses
+}
+
+/// Extract the guest PC from an `-d exec` trace line.
+fn parse_exec_line(line: &str) -> Option<u64> {
+    let start = line.find('[')? + 1;
+    let end = start + line[start..].find(']')?;
+    let fields: Vec<&str> = line[start..end].split('/').collect();
+    // Older QEMU logs only the PC; newer ones log cs_base/pc/flags[/cflags]
+    let pc = if fields.len() > 1 {
+        fields[1]
+    } else {
+        fields[0]
+    };
+    u64::from_str_radix(pc.trim(), 16).ok()
+}
+
+// ── Symbolization ────────────────────────────────────────────────────
+
+/// Source location of a code address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub function: String,
+    pub file: String,
+    pub line: u32,
+}
+
+/// A function symbol from the kernel binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSymbol {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+}
+
+/// Parse `addr2line -f -C` output (function and `file:line` per address).
+///
+/// Returns one entry per address; `None` where debug info has no location.
+pub fn parse_addr2line(output: &str) -> Vec<Option<SourceLocation>> {
+    let lines: Vec<&str> = output.lines().collect();
+    lines
+        .chunks(2)
+        .map(|pair| {
+            let function = pair.first()?.trim();
+            let location = pair.get(1)?.trim();
+            // Strip " (discriminator N)" suffixes
+            let location = location.split(" (").next().unwrap_or(location);
+            let (file, line) = location.rsplit_once(':')?;
+            let line: u32 = line.parse().ok()?;
+            if file == "??" || line == 0 {
+                return None;
+            }
+            Some(SourceLocation {
+                function: function.to_string(),
+                file: file.to_string(),
+                line,
+            })
+        })
+        .collect()
+}
+
+/// Parse `nm -C -S --defined-only` output into text (code) symbols.
+pub fn parse_nm(output: &str) -> Vec<FunctionSymbol> {
+    let mut symbols: Vec<FunctionSymbol> = output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, ' ');
+            let address = u64::from_str_radix(parts.next()?, 16).ok()?;
+            let size = u64::from_str_radix(parts.next()?, 16).ok()?;
+            let kind = parts.next()?;
+            let name = parts.next()?.trim();
+            if !matches!(kind, "T" | "t" | "W" | "w") || size == 0 {
+                return None;
+            }
+            Some(FunctionSymbol {
+                name: name.to_string(),
+                address,
+                size,
+            })
+        })
+        .collect();
+    symbols.sort_by_key(|s| s.address);
+    symbols
+}
+
+/// Find the first of `names` on PATH.
+pub fn find_tool(names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        Command::new(name)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .ok()
+            .map(|_| name.to_string())
+    })
+}
+
+/// Resolve addresses in `kernel` to source locations with `addr2line`.
+fn symbolize(
+    kernel: &Path,
+    addresses: &[u64],
+) -> Result<Vec<Option<SourceLocation>>, KpioTestError> {
+    let tool = find_tool(&["llvm-addr2line", "addr2line"]).ok_or_else(|| {
+        KpioTestError::SymbolizerNotFound {
+            hint: "install binutils or LLVM (addr2line) to map coverage to source".to_string(),
+        }
+    })?;
+
+    let mut child = Command::new(tool)
+        .args(["-f", "-C", "-e"])
+        .arg(kernel)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Feed addresses from a separate thread so a full stdout pipe can't
+    // deadlock against a full stdin pipe.
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| KpioTestError::Io(std::io::Error::other("addr2line stdin unavailable")))?;
+    let input: String = addresses.iter().map(|a| format!("{a:#x}\n")).collect();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+
+    Ok(parse_addr2line(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// List the function symbols in `kernel` with `nm`.
+fn list_functions(kernel: &Path) -> Result<Vec<FunctionSymbol>, KpioTestError> {
+    let tool = find_tool(&["llvm-nm", "nm"]).ok_or_else(|| KpioTestError::SymbolizerNotFound {
+        hint: "install binutils or LLVM (nm) to list kernel functions".to_string(),
+    })?;
+
+    let output = Command::new(tool)
+        .args(["-C", "-S", "--defined-only"])
+        .arg(kernel)
+        .output()?;
+
+    Ok(parse_nm(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// ── Report ───────────────────────────────────────────────────────────
+
+/// Coverage for a single source file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileCoverage {
+    /// Line number → hit count.
+    pub lines: BTreeMap<u32, u64>,
+    /// Function name → (declaration line, hit count).
+    pub functions: BTreeMap<String, (u32, u64)>,
+}
+
+/// Coverage across all source files.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CoverageReport {
+    pub files: BTreeMap<String, FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Build a report from symbolized addresses and function symbols.
+    ///
+    /// `hits` holds the location of each covered address, `functions` the
+    /// location of each function's entry point with whether it was hit.
+    pub fn build(
+        hits: &[Option<SourceLocation>],
+        functions: &[(Option<SourceLocation>, &FunctionSymbol, bool)],
+    ) -> Self {
+        let mut report = CoverageReport::default();
+
+        for loc in hits.iter().flatten() {
+            let file = report.files.entry(loc.file.clone()).or_default();
+            *file.lines.entry(loc.line).or_insert(0) += 1;
+        }
+
+        for (loc, symbol, hit) in functions {
+            let Some(loc) = loc else { continue };
+            let file = report.files.entry(loc.file.clone()).or_default();
+            file.functions
+                .insert(symbol.name.clone(), (loc.line, u64::from(*hit)));
+            // Record the entry line so unexecuted functions count as missed lines
+            file.lines.entry(loc.line).or_insert(0);
+        }
+
+        report
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.files
+            .values()
+            .map(|f| f.lines.values().filter(|&&h| h > 0).count())
+            .sum()
+    }
+
+    pub fn functions_total(&self) -> usize {
+        self.files.values().map(|f| f.functions.len()).sum()
+    }
+
+    pub fn functions_hit(&self) -> usize {
+        self.files
+            .values()
+            .map(|f| f.functions.values().filter(|(_, h)| *h > 0).count())
+            .sum()
+    }
+
+    /// Render the report in lcov tracefile format.
+    pub fn to_lcov(&self, test_name: &str) -> String {
+        let mut out = String::new();
+        for (path, file) in &self.files {
+            out.push_str(&format!("TN:{test_name}\nSF:{path}\n"));
+            for (name, (line, _)) in &file.functions {
+                out.push_str(&format!("FN:{line},{name}\n"));
+            }
+            for (name, (_, hits)) in &file.functions {
+                out.push_str(&format!("FNDA:{hits},{name}\n"));
+            }
+            let fn_hit = file.functions.values().filter(|(_, h)| *h > 0).count();
+            out.push_str(&format!("FNF:{}\nFNH:{fn_hit}\n", file.functions.len()));
+            for (line, hits) in &file.lines {
+                out.push_str(&format!("DA:{line},{hits}\n"));
+            }
+            let lines_hit = file.lines.values().filter(|&&h| h > 0).count();
+            out.push_str(&format!("LF:{}\nLH:{lines_hit}\n", file.lines.len()));
+            out.push_str("end_of_record\n");
+        }
+        out
+    }
+}
+
+/// Check whether any covered address falls inside `symbol`.
+fn function_hit(addresses: &BTreeSet<u64>, symbol: &FunctionSymbol) -> bool {
+    addresses
+        .range(symbol.address..symbol.address.saturating_add(symbol.size))
+        .next()
+        .is_some()
+}
+
+// ── Handler ──────────────────────────────────────────────────────────
+
+/// Collect coverage for an instance and write an lcov report.
+pub fn coverage(args: CoverageArgs) -> Result<serde_json::Value, KpioTestError> {
+    let mut st = store::read_state(&args.name)?;
+    watchdog::enforce(&mut st)?;
+
+    let trace_path = store::coverage_trace_path(&args.name);
+    if !trace_path.exists() {
+        return Err(KpioTestError::CoverageNotEnabled {
+            name: args.name.clone(),
+        });
+    }
+
+    let kernel = args.kernel.unwrap_or_else(default_kernel_path);
+    if !kernel.exists() {
+        return Err(KpioTestError::FileNotFound { path: kernel });
+    }
+
+    let load_offset = args.load_offset.unwrap_or(0);
+
+    // Translate guest addresses back to link-time addresses
+    let content = String::from_utf8_lossy(&std::fs::read(&trace_path)?).into_owned();
+    let addresses: BTreeSet<u64> = parse_trace(&content)
+        .into_iter()
+        .filter_map(|a| a.checked_sub(load_offset))
+        .collect();
+
+    let symbols = list_functions(&kernel)?;
+    let addr_list: Vec<u64> = addresses.iter().copied().collect();
+    let entry_list: Vec<u64> = symbols.iter().map(|s| s.address).collect();
+    let hits = symbolize(&kernel, &addr_list)?;
+    let entries = symbolize(&kernel, &entry_list)?;
+
+    let functions: Vec<_> = entries
+        .into_iter()
+        .zip(&symbols)
+        .map(|(loc, sym)| (loc, sym, function_hit(&addresses, sym)))
+        .collect();
+    let report = CoverageReport::build(&hits, &functions);
+
+    let lcov_path = args
+        .lcov
+        .unwrap_or_else(|| store::instance_dir(&args.name).join("coverage.info"));
+    std::fs::write(&lcov_path, report.to_lcov(&args.name))?;
+
+    let output = CoverageOutput {
+        name: args.name,
+        lcov_path,
+        addresses: addresses.len(),
+        files: report.files.len(),
+        lines_hit: report.lines_hit(),
+        functions_total: report.functions_total(),
+        functions_hit: report.functions_hit(),
+    };
+    Ok(serde_json::to_value(output)?)
+}
+
+/// Default kernel binary path (debug build output).
+pub fn default_kernel_path() -> PathBuf {
+    PathBuf::from("target/x86_64-unknown-none/debug/kpio-kernel")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qemu_args_enable_in_asm_log() {
+        let args = qemu_args(Path::new("cov.log"));
+        let idx = args.iter().position(|a| a == "-d").unwrap();
+        assert_eq!(args[idx + 1], "in_asm");
+        assert!(args.contains(&"tcg".to_string()));
+        assert!(args.contains(&"cov.log".to_string()));
+    }
+
+    #[test]
+    fn parse_trace_in_asm() {
+        let log = "----------------\n\
+                   IN: \n\
+                   0xffffffff80001000:  55                       pushq    %rbp\n\
+                   0xffffffff80001001:  48 89 e5                 movq     %rsp, %rbp\n\
+                   \n";
+        let addrs = parse_trace(log);
+        assert_eq!(
+            addrs.into_iter().collect::<Vec<_>>(),
+            vec![0xffffffff80001000, 0xffffffff80001001]
+        );
+    }
+
+    #[test]
+    fn parse_trace_exec() {
+        let log = "Trace 0: 0x7f0000001000 [00000000/ffffffff80002000/00000000/ff020000] \n\
+                   Trace 0x7f0000002000 [ffffffff80003000] kernel_main\n";
+        let addrs = parse_trace(log);
+        assert!(addrs.contains(&0xffffffff80002000));
+        assert!(addrs.contains(&0xffffffff80003000));
+    }
+
+    #[test]
+    fn parse_addr2line_output() {
+        let out = "kernel_main\n/src/kernel/src/main.rs:80\n??\n??:0\nfoo\n/src/a.rs:12 (discriminator 3)\n";
+        let locs = parse_addr2line(out);
+        assert_eq!(locs.len(), 3);
+        assert_eq!(locs[0].as_ref().unwrap().line, 80);
+        assert!(locs[1].is_none());
+        assert_eq!(locs[2].as_ref().unwrap().file, "/src/a.rs");
+        assert_eq!(locs[2].as_ref().unwrap().line, 12);
+    }
+
+    #[test]
+    fn parse_nm_keeps_sized_text_symbols() {
+        let out = "0000000000001000 0000000000000040 T kernel_main\n\
+                   0000000000002000 0000000000000010 D SOME_DATA\n\
+                   0000000000000800 0000000000000020 t serial::init\n\
+                   0000000000003000 U undefined\n";
+        let syms = parse_nm(out);
+        assert_eq!(syms.len(), 2);
+        assert_eq!(syms[0].name, "serial::init");
+        assert_eq!(syms[1].size, 0x40);
+    }
+
+    #[test]
+    fn function_hit_checks_range() {
+        let sym = FunctionSymbol {
+            name: "f".into(),
+            address: 0x1000,
+            size: 0x10,
+        };
+        let addrs: BTreeSet<u64> = [0x1010].into_iter().collect();
+        assert!(!function_hit(&addrs, &sym));
+        let addrs: BTreeSet<u64> = [0x100f].into_iter().collect();
+        assert!(function_hit(&addrs, &sym));
+    }
+
+    #[test]
+    fn lcov_report_format() {
+        let loc = |line| {
+            Some(SourceLocation {
+                function: "f".into(),
+                file: "a.rs".into(),
+                line,
+            })
+        };
+        let f = FunctionSymbol {
+            name: "f".into(),
+            address: 0,
+            size: 1,
+        };
+        let g = FunctionSymbol {
+            name: "g".into(),
+            address: 8,
+            size: 1,
+        };
+        let hits = vec![loc(10), loc(11), loc(11)];
+        let functions = vec![(loc(10), &f, true), (loc(20), &g, false)];
+        let report = CoverageReport::build(&hits, &functions);
+
+        assert_eq!(report.lines_hit(), 2);
+        assert_eq!(report.functions_total(), 2);
+        assert_eq!(report.functions_hit(), 1);
+
+        let lcov = report.to_lcov("boot");
+        assert!(lcov.starts_with("TN:boot\nSF:a.rs\n"));
+        assert!(lcov.contains("FN:20,g\n"));
+        assert!(lcov.contains("FNDA:0,g\n"));
+        assert!(lcov.contains("DA:11,2\n"));
+        assert!(lcov.contains("DA:20,0\n"));
+        assert!(lcov.contains("LF:3\nLH:2\n"));
+        assert!(lcov.ends_with("end_of_record\n"));
+    }
+}
@@ -34,6 +34,18 @@ pub enum KpioTestError {
     #[error("Network device required for port forwarding")]
     NetworkDeviceRequired,
 
+    #[error("Coverage not enabled for instance: {name} (create with --coverage)")]
+    CoverageNotEnabled { name: String },
+
+    #[error("Symbolizer not found: {hint}")]
+    SymbolizerNotFound { hint: String },
+
+    #[error("gdbstub not enabled for instance: {name} (create with --gdb)")]
+    GdbNotEnabled { name: String },
+
+    #[error("Debugger not found: {hint}")]
+    DebuggerNotFound { hint: String },
+
     #[error("Unknown subcommand: {name}")]
     UnknownSubcommand { name: String },
 
@@ -74,6 +86,12 @@ pub enum KpioTestError {
 
     #[error("File not found: {path}")]
     FileNotFound { path: PathBuf },
+
+    #[error("Instance not recording: {name}")]
+    NotRecording { name: String },
+
+    #[error("gdbstub port in use: {port}")]
+    GdbPortInUse { port: u16 },
 }
 
 impl KpioTestError {
@@ -92,6 +110,10 @@ impl KpioTestError {
             | Self::SnapshotRequiresQcow2
             | Self::SharedDirRequired
             | Self::NetworkDeviceRequired
+            | Self::CoverageNotEnabled { .. }
+            | Self::SymbolizerNotFound { .. }
+            | Self::GdbNotEnabled { .. }
+            | Self::DebuggerNotFound { .. }
             | Self::UnknownSubcommand { .. }
             | Self::Io(_)
             | Self::Json(_) => ExitCode::from(2),
@@ -106,7 +128,9 @@ impl KpioTestError {
             | Self::VerificationFailed { .. }
             | Self::BuildFailed { .. }
             | Self::SnapshotNotFound { .. }
-            | Self::FileNotFound { .. } => ExitCode::from(1),
+            | Self::FileNotFound { .. }
+            | Self::NotRecording { .. }
+            | Self::GdbPortInUse { .. } => ExitCode::from(1),
         }
     }
 }
@@ -0,0 +1,391 @@
+//! fuzz-serial subcommand — feed mutated input to the kernel console.
+//!
+//! Each iteration takes a command line from the corpus, mutates it with
+//! the `kpio-fuzz` [`Mutator`], and writes it to the instance's serial
+//! input followed by a newline (the same `ringbuf-write` path
+//! `send-command` uses). A probe command whose output differs from its
+//! echoed input is sent after it; the console is alive once the probe's
+//! output shows up in the serial log.
+//!
+//! A run stops at the first finding:
+//!
+//! - **panic** — the kernel panic banner (or a Rust panic message)
+//!   appeared in the serial log;
+//! - **hang** — the probe got no answer within `--hang-timeout`;
+//! - **timed-out** / **crashed** — the watchdog terminated the instance
+//!   or found its QEMU process dead.
+//!
+//! Every byte written so far is saved to a reproducer file, so the
+//! sequence can be replayed against a fresh instance with the same seed
+//! or by writing the file to its console.
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use kpio_fuzz::Mutator;
+use serde::Serialize;
+
+use crate::cli::FuzzSerialArgs;
+use crate::error::KpioTestError;
+use crate::qmp::QmpClient;
+use crate::serial;
+use crate::state::InstanceStatus;
+use crate::{store, watchdog};
+
+/// Command lines mutated when no `--corpus` is given.
+pub const DEFAULT_CORPUS: &[&str] = &[
+    "help",
+    "ls -la /",
+    "echo hello world",
+    "cat /etc/hostname | wc -c",
+    "seq 1 10 | sort -r | head -n 3",
+    "export A=1; echo $A ${A}",
+    "printf '%s=%d\\n' x 42",
+    "test -d / && echo yes || echo no",
+    "find / -name '*.txt'",
+    "grep -c root /etc/passwd",
+    "echo \"quoted \\\"string\\\"\" > /tmp/f; cat < /tmp/f",
+    "history",
+];
+
+/// Shell syntax spliced into inputs by the mutator.
+const DICTIONARY: &[&str] = &[
+    "|", "||", "&&", ";", ">", ">>", "<", "$", "${", "$(", "`", "\"", "'", "\\", "*", "~", "--",
+    "=", "%s", "%n", "\x1b[", "\t", "\x03",
+];
+
+/// Longest input sent in one iteration, in bytes.
+pub const MAX_INPUT_LEN: usize = 512;
+
+/// Serial output that means the kernel panicked.
+const PANIC_MARKERS: &[&str] = &["KERNEL PANIC", "panicked at"];
+
+/// How often the serial log is polled while waiting for the probe.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// ── Output types ─────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    Panic,
+    Hang,
+    TimedOut,
+    Crashed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    /// Iteration whose input triggered the finding (0-based).
+    pub iteration: usize,
+    /// Serial line that reported the panic.
+    pub line: Option<String>,
+    /// File holding every byte written to the console during the run.
+    pub reproducer: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FuzzSerialOutput {
+    pub name: String,
+    pub seed: u64,
+    pub iterations: usize,
+    pub bytes_sent: usize,
+    pub finding: Option<Finding>,
+}
+
+// ── Input generation ─────────────────────────────────────────────────
+
+/// Generates the input lines of a run.
+///
+/// The sequence depends only on the seed and the corpus.
+pub struct InputGenerator {
+    mutator: Mutator,
+    corpus: Vec<Vec<u8>>,
+    next: usize,
+}
+
+impl InputGenerator {
+    pub fn new(seed: u64, corpus: Vec<Vec<u8>>) -> Self {
+        let mut mutator = Mutator::new(seed);
+        for entry in DICTIONARY {
+            mutator.add_dictionary(entry.as_bytes().to_vec());
+        }
+        let corpus = if corpus.is_empty() {
+            DEFAULT_CORPUS
+                .iter()
+                .map(|s| s.as_bytes().to_vec())
+                .collect()
+        } else {
+            corpus
+        };
+        Self {
+            mutator,
+            corpus,
+            next: 0,
+        }
+    }
+
+    /// The next input, without its trailing newline.
+    pub fn next_input(&mut self) -> Vec<u8> {
+        let mut input = self.corpus[self.next % self.corpus.len()].clone();
+        // Stack a few mutations so inputs drift further from the corpus.
+        for _ in 0..=self.next % 4 {
+            self.mutator.mutate(&mut input);
+        }
+        self.next += 1;
+        input.truncate(MAX_INPUT_LEN);
+        input
+    }
+}
+
+/// Parse a corpus file: one command line per line, blank lines skipped.
+pub fn parse_corpus(content: &[u8]) -> Vec<Vec<u8>> {
+    content
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+// ── Liveness probe ───────────────────────────────────────────────────
+
+/// Command sent after input `iteration` to check the console still runs.
+///
+/// The escaped hyphen keeps the echoed command line from matching
+/// [`probe_marker`]; only the command's output does.
+pub fn probe_command(iteration: usize) -> String {
+    format!("echo kpio-fuzz\\-probe-{iteration}\n")
+}
+
+/// Output of [`probe_command`].
+pub fn probe_marker(iteration: usize) -> String {
+    format!("kpio-fuzz-probe-{iteration}")
+}
+
+/// First line of `output` reporting a kernel panic.
+pub fn find_panic(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find(|line| PANIC_MARKERS.iter().any(|m| line.contains(m)))
+        .map(|line| line.trim().to_string())
+}
+
+// ── Handler ──────────────────────────────────────────────────────────
+
+/// `fuzz-serial <name> [--iterations N] [--seed S] [--corpus FILE] [--output DIR] [--hang-timeout MS]`
+pub fn fuzz_serial(args: FuzzSerialArgs) -> Result<serde_json::Value, KpioTestError> {
+    let mut state = store::read_state(&args.name)?;
+    watchdog::enforce(&mut state)?;
+
+    if state.status != InstanceStatus::Running {
+        return Err(KpioTestError::InstanceNotRunning {
+            name: args.name.clone(),
+        });
+    }
+
+    let corpus = match &args.corpus {
+        Some(path) if !path.exists() => {
+            return Err(KpioTestError::FileNotFound { path: path.clone() })
+        }
+        Some(path) => parse_corpus(&fs::read(path)?),
+        None => Vec::new(),
+    };
+    let seed = args.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    let output_dir = args
+        .output
+        .clone()
+        .unwrap_or_else(|| store::fuzz_dir(&args.name));
+    let hang_timeout = Duration::from_millis(args.hang_timeout);
+
+    let log_path = store::serial_log_path(&args.name);
+    let mut log_offset = fs::metadata(&log_path).map_or(0, |m| m.len());
+    let mut qmp = QmpClient::connect(&state.qmp_socket)?;
+    let mut generator = InputGenerator::new(seed, corpus);
+    let mut sent = Vec::new();
+    let mut finding = None;
+    let mut iterations = 0;
+
+    for iteration in 0..args.iterations {
+        let mut input = generator.next_input();
+        input.push(b'\n');
+        let probe_cmd = probe_command(iteration);
+        sent.extend_from_slice(&input);
+        sent.extend_from_slice(probe_cmd.as_bytes());
+        iterations += 1;
+
+        // A write failing means QMP (and QEMU) went away; let the watchdog say why.
+        let written = serial::write_input(&mut qmp, &input)
+            .and_then(|()| serial::write_input(&mut qmp, probe_cmd.as_bytes()));
+
+        let probe = written.map(|()| {
+            let marker = probe_marker(iteration);
+            wait_for_probe(&log_path, &mut log_offset, &marker, hang_timeout)
+        });
+        let (kind, line) = match probe {
+            Ok(Probe::Answered) => continue,
+            Ok(Probe::Panicked(line)) => (FindingKind::Panic, Some(line)),
+            Ok(Probe::Silent) | Err(_) => {
+                watchdog::enforce(&mut state)?;
+                match state.status {
+                    InstanceStatus::TimedOut => (FindingKind::TimedOut, None),
+                    InstanceStatus::Crashed => (FindingKind::Crashed, None),
+                    _ => (FindingKind::Hang, None),
+                }
+            }
+        };
+
+        finding = Some(Finding {
+            kind,
+            iteration,
+            line,
+            reproducer: save_reproducer(&output_dir, kind, seed, iteration, &sent)?,
+        });
+        break;
+    }
+
+    let output = FuzzSerialOutput {
+        name: args.name,
+        seed,
+        iterations,
+        bytes_sent: sent.len(),
+        finding,
+    };
+    Ok(serde_json::to_value(output)?)
+}
+
+/// What the console did after an input.
+enum Probe {
+    /// The probe's output appeared.
+    Answered,
+    /// The kernel panicked; carries the panic line.
+    Panicked(String),
+    /// Nothing within the hang timeout.
+    Silent,
+}
+
+/// Poll the serial log past `offset` until `marker` or a panic shows up,
+/// or `timeout` elapses. `offset` is advanced past the output read.
+fn wait_for_probe(log_path: &Path, offset: &mut u64, marker: &str, timeout: Duration) -> Probe {
+    let start = Instant::now();
+    let mut output = String::new();
+    loop {
+        if let Ok(chunk) = read_from(log_path, *offset) {
+            *offset += chunk.len() as u64;
+            output.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        if let Some(line) = find_panic(&output) {
+            return Probe::Panicked(line);
+        }
+        if output.contains(marker) {
+            return Probe::Answered;
+        }
+        if start.elapsed() >= timeout {
+            return Probe::Silent;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Read the bytes of `path` from `offset` to the end.
+fn read_from(path: &Path, offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Write the bytes sent during a run to `<dir>/<kind>-<seed>-<iteration>.bin`.
+fn save_reproducer(
+    dir: &Path,
+    kind: FindingKind,
+    seed: u64,
+    iteration: usize,
+    sent: &[u8],
+) -> Result<PathBuf, KpioTestError> {
+    fs::create_dir_all(dir)?;
+    let kind = serde_json::to_value(kind)?;
+    let path = dir.join(format!(
+        "{}-{seed}-{iteration}.bin",
+        kind.as_str().unwrap_or("finding")
+    ));
+    fs::write(&path, sent)?;
+    Ok(path)
+}
+
+// ── Unit tests ───────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generator_is_deterministic_per_seed() {
+        let run = |seed| {
+            let mut generator = InputGenerator::new(seed, Vec::new());
+            (0..50).map(|_| generator.next_input()).collect::<Vec<_>>()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+        assert!(run(7).iter().all(|input| input.len() <= MAX_INPUT_LEN));
+    }
+
+    #[test]
+    fn parse_corpus_skips_blank_lines() {
+        let corpus = parse_corpus(b"ls\r\n\n   \necho hi\n");
+        assert_eq!(corpus, vec![b"ls".to_vec(), b"echo hi".to_vec()]);
+    }
+
+    #[test]
+    fn probe_echo_does_not_match_marker() {
+        let command = probe_command(12);
+        assert!(!command.contains(&probe_marker(12)));
+        assert_eq!(probe_marker(12), "kpio-fuzz-probe-12");
+    }
+
+    #[test]
+    fn find_panic_reports_banner_line() {
+        let log = "kpio> ls\nbin etc\n========\nKERNEL PANIC\n========\n";
+        assert_eq!(find_panic(log).as_deref(), Some("KERNEL PANIC"));
+        let log = "panicked at kernel/src/terminal/shell.rs:42:9:\n";
+        assert!(find_panic(log).unwrap().starts_with("panicked at"));
+        assert_eq!(find_panic("kpio> echo ok\nok\n"), None);
+    }
+
+    #[test]
+    fn reproducer_holds_sent_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path =
+            save_reproducer(dir.path(), FindingKind::TimedOut, 3, 9, b"ls\n\xff|\n").unwrap();
+        assert_eq!(path.file_name().unwrap(), "timed-out-3-9.bin");
+        assert_eq!(fs::read(&path).unwrap(), b"ls\n\xff|\n");
+    }
+
+    #[test]
+    fn wait_for_probe_reads_only_new_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("serial.log");
+        fs::write(&log, "kpio-fuzz-probe-0\n").unwrap();
+        let mut offset = fs::metadata(&log).unwrap().len();
+        let timeout = Duration::from_millis(100);
+
+        assert!(matches!(
+            wait_for_probe(&log, &mut offset, &probe_marker(0), timeout),
+            Probe::Silent
+        ));
+        fs::write(&log, "kpio-fuzz-probe-0\nkpio-fuzz-probe-1\n").unwrap();
+        assert!(matches!(
+            wait_for_probe(&log, &mut offset, &probe_marker(1), timeout),
+            Probe::Answered
+        ));
+    }
+}
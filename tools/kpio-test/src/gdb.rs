@@ -0,0 +1,245 @@
+//! gdb-attach subcommand — connect a debugger to an instance's gdbstub.
+//!
+//! Instances created with `--gdb` (or `--gdb-port` / `--gdb-wait`) open
+//! QEMU's gdbstub on a TCP port recorded in `state.json`. Ports are
+//! allocated so parallel instances never share one, which rules out QEMU's
+//! `-s` shorthand (always port 1234).
+//!
+//! `gdb-attach` prints the remote target and writes a `gdbinit` helper
+//! script into the instance store that selects the x86-64 architecture,
+//! loads the kernel symbols and connects. With `--launch` it also runs gdb
+//! with that script.
+
+use std::collections::BTreeSet;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::cli::GdbAttachArgs;
+use crate::coverage;
+use crate::error::KpioTestError;
+use crate::state::InstanceStatus;
+use crate::store;
+use crate::watchdog;
+
+/// First port tried when allocating a gdbstub port (the port `-s` uses).
+pub const DEFAULT_PORT: u16 = 1234;
+
+/// Number of ports scanned upward from `DEFAULT_PORT`.
+const PORT_RANGE: u16 = 256;
+
+// ── Output types ─────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct GdbAttachOutput {
+    pub name: String,
+    pub target: String,
+    pub port: u16,
+    pub script: PathBuf,
+    pub kernel: Option<PathBuf>,
+    pub launched: bool,
+    pub gdb_exit_code: Option<i32>,
+}
+
+// ── QEMU configuration ───────────────────────────────────────────────
+
+/// QEMU arguments that open a gdbstub on `port`.
+///
+/// With `wait`, the CPU is held at reset until the debugger continues it.
+pub fn qemu_args(port: u16, wait: bool) -> Vec<String> {
+    let mut args = vec!["-gdb".to_string(), format!("tcp:{}", target(port))];
+    if wait {
+        args.push("-S".to_string());
+    }
+    args
+}
+
+/// Remote target string for a gdbstub on `port`.
+pub fn target(port: u16) -> String {
+    format!("127.0.0.1:{port}")
+}
+
+// ── Port allocation ──────────────────────────────────────────────────
+
+/// Pick the gdbstub port for a new instance.
+///
+/// A `requested` port is used as-is if it is free. Otherwise ports are
+/// scanned upward from `DEFAULT_PORT`. Ports recorded by running instances
+/// are skipped even when nothing is listening on them yet, so instances
+/// created back to back don't race for the same port.
+pub fn allocate_port(requested: Option<u16>) -> Result<u16, KpioTestError> {
+    let reserved = reserved_ports();
+
+    if let Some(port) = requested {
+        if reserved.contains(&port) || !port_is_free(port) {
+            return Err(KpioTestError::GdbPortInUse { port });
+        }
+        return Ok(port);
+    }
+
+    pick_port(&reserved, port_is_free).ok_or_else(|| {
+        KpioTestError::Io(std::io::Error::other(format!(
+            "no free gdbstub port in {}..{}",
+            DEFAULT_PORT,
+            DEFAULT_PORT + PORT_RANGE
+        )))
+    })
+}
+
+/// First port from `DEFAULT_PORT` that is neither reserved nor bound.
+fn pick_port(reserved: &BTreeSet<u16>, is_free: impl Fn(u16) -> bool) -> Option<u16> {
+    (DEFAULT_PORT..DEFAULT_PORT + PORT_RANGE)
+        .find(|port| !reserved.contains(port) && is_free(*port))
+}
+
+/// gdbstub ports recorded by running instances.
+fn reserved_ports() -> BTreeSet<u16> {
+    store::list_instances()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| store::read_state(name).ok())
+        .filter(|state| state.status == InstanceStatus::Running)
+        .filter_map(|state| state.config.gdb_port)
+        .collect()
+}
+
+/// Check whether `port` can be bound on the loopback interface.
+fn port_is_free(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+}
+
+// ── Helper script ────────────────────────────────────────────────────
+
+/// Build the gdb helper script that connects to `target`.
+pub fn gdbinit(kernel: Option<&Path>, target: &str) -> String {
+    let mut script = String::from("set architecture i386:x86-64\n");
+    if let Some(kernel) = kernel {
+        script.push_str(&format!("symbol-file {}\n", kernel.display()));
+    }
+    script.push_str(&format!("target remote {target}\n"));
+    script
+}
+
+// ── Handler ──────────────────────────────────────────────────────────
+
+/// Print the gdbstub target of an instance and optionally launch gdb.
+pub fn gdb_attach(args: GdbAttachArgs) -> Result<serde_json::Value, KpioTestError> {
+    let mut st = store::read_state(&args.name)?;
+    watchdog::enforce(&mut st)?;
+
+    if st.status != InstanceStatus::Running {
+        return Err(KpioTestError::InstanceNotRunning {
+            name: args.name.clone(),
+        });
+    }
+
+    let port = st
+        .config
+        .gdb_port
+        .ok_or_else(|| KpioTestError::GdbNotEnabled {
+            name: args.name.clone(),
+        })?;
+    let target = target(port);
+
+    // An explicit kernel must exist; the default is skipped if not built.
+    let kernel = match args.kernel {
+        Some(kernel) if !kernel.exists() => {
+            return Err(KpioTestError::FileNotFound { path: kernel });
+        }
+        Some(kernel) => Some(kernel),
+        None => Some(coverage::default_kernel_path()).filter(|k| k.exists()),
+    };
+
+    let script = store::gdb_script_path(&args.name);
+    std::fs::write(&script, gdbinit(kernel.as_deref(), &target))?;
+
+    let mut gdb_exit_code = None;
+    if args.launch {
+        let gdb = match args.gdb {
+            Some(gdb) => gdb,
+            None => {
+                coverage::find_tool(&["rust-gdb", "gdb", "gdb-multiarch"]).ok_or_else(|| {
+                    KpioTestError::DebuggerNotFound {
+                        hint: "install gdb or pass --gdb <binary>".to_string(),
+                    }
+                })?
+            }
+        };
+
+        let status = Command::new(&gdb)
+            .arg("-x")
+            .arg(&script)
+            .status()
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    KpioTestError::DebuggerNotFound {
+                        hint: format!("{gdb} could not be executed"),
+                    }
+                } else {
+                    KpioTestError::Io(e)
+                }
+            })?;
+        gdb_exit_code = status.code();
+    }
+
+    let output = GdbAttachOutput {
+        name: args.name,
+        target,
+        port,
+        script,
+        kernel,
+        launched: args.launch,
+        gdb_exit_code,
+    };
+    Ok(serde_json::to_value(output)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qemu_args_open_stub_on_port() {
+        assert_eq!(qemu_args(1240, false), vec!["-gdb", "tcp:127.0.0.1:1240"]);
+    }
+
+    #[test]
+    fn qemu_args_wait_holds_cpu() {
+        let args = qemu_args(1234, true);
+        assert_eq!(args.last().map(String::as_str), Some("-S"));
+    }
+
+    #[test]
+    fn pick_port_skips_reserved_and_bound() {
+        let reserved: BTreeSet<u16> = [1234, 1235].into_iter().collect();
+        assert_eq!(pick_port(&reserved, |port| port != 1236), Some(1237));
+    }
+
+    #[test]
+    fn pick_port_exhausted() {
+        assert_eq!(pick_port(&BTreeSet::new(), |_| false), None);
+    }
+
+    #[test]
+    fn gdbinit_sets_arch_symbols_and_target() {
+        let script = gdbinit(Some(Path::new("kernel.elf")), "127.0.0.1:1234");
+        let lines: Vec<&str> = script.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "set architecture i386:x86-64",
+                "symbol-file kernel.elf",
+                "target remote 127.0.0.1:1234",
+            ]
+        );
+    }
+
+    #[test]
+    fn gdbinit_without_kernel() {
+        let script = gdbinit(None, "127.0.0.1:1234");
+        assert!(!script.contains("symbol-file"));
+        assert!(script.ends_with("target remote 127.0.0.1:1234\n"));
+    }
+}
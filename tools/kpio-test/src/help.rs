@@ -72,6 +72,9 @@ fn all_subcommands() -> Vec<SubcommandSummary> {
         SubcommandSummary { name: "type-text".into(), description: "Type a text string as sequential key events".into() },
         SubcommandSummary { name: "mouse-click".into(), description: "Send a mouse click at specific coordinates".into() },
         SubcommandSummary { name: "mouse-move".into(), description: "Move the mouse to specific coordinates".into() },
+        SubcommandSummary { name: "record".into(), description: "Record input commands sent to an instance into a replay script".into() },
+        SubcommandSummary { name: "replay".into(), description: "Replay a recorded input script against an instance".into() },
+        SubcommandSummary { name: "fuzz-serial".into(), description: "Stream mutated input to the serial console and save inputs that crash or hang it".into() },
         SubcommandSummary { name: "snapshot".into(), description: "Save, restore, list, or delete VM state snapshots".into() },
         SubcommandSummary { name: "guest-info".into(), description: "Query guest VM configuration and runtime information".into() },
         SubcommandSummary { name: "port-forward".into(), description: "Configure host-guest port forwarding".into() },
@@ -80,6 +83,8 @@ fn all_subcommands() -> Vec<SubcommandSummary> {
         SubcommandSummary { name: "logs".into(), description: "Retrieve QEMU process log output".into() },
         SubcommandSummary { name: "build".into(), description: "Build the kernel and create the UEFI disk image".into() },
         SubcommandSummary { name: "verify".into(), description: "Verify test results against a manifest".into() },
+        SubcommandSummary { name: "coverage".into(), description: "Write an lcov report of guest code executed by an instance".into() },
+        SubcommandSummary { name: "gdb-attach".into(), description: "Print the gdbstub address of an instance and optionally launch gdb".into() },
         SubcommandSummary { name: "health".into(), description: "Run pre-flight health checks without creating an instance".into() },
         SubcommandSummary { name: "destroy-all".into(), description: "Destroy all managed instances".into() },
         SubcommandSummary { name: "destroy".into(), description: "Destroy a specific instance and clean up resources".into() },
@@ -102,6 +107,10 @@ fn subcommand_detail(name: &str) -> Option<SubcommandHelp> {
                 ParameterInfo { name: "--virtio-net".into(), param_type: "bool".into(), required: false, default: Some("false".into()), description: "Attach VirtIO network device".into() },
                 ParameterInfo { name: "--virtio-blk".into(), param_type: "path".into(), required: false, default: None, description: "Attach VirtIO block device".into() },
                 ParameterInfo { name: "--shared-dir".into(), param_type: "path".into(), required: false, default: None, description: "VirtIO-9p shared directory".into() },
+                ParameterInfo { name: "--coverage".into(), param_type: "bool".into(), required: false, default: Some("false".into()), description: "Record executed code for coverage (TCG only)".into() },
+                ParameterInfo { name: "--gdb".into(), param_type: "bool".into(), required: false, default: Some("false".into()), description: "Expose the QEMU gdbstub".into() },
+                ParameterInfo { name: "--gdb-port".into(), param_type: "u16".into(), required: false, default: None, description: "gdbstub port (first free from 1234 if omitted)".into() },
+                ParameterInfo { name: "--gdb-wait".into(), param_type: "bool".into(), required: false, default: Some("false".into()), description: "Hold the CPU at reset until gdb continues".into() },
             ],
             exit_codes,
             examples: vec![
@@ -133,6 +142,81 @@ fn subcommand_detail(name: &str) -> Option<SubcommandHelp> {
                 "kpio-test verify boot-test --manifest tests/manifests/default.toml --mode smoke".into(),
             ],
         }),
+        "coverage" => Some(SubcommandHelp {
+            name: "coverage".into(),
+            description: "Write an lcov report of guest code executed by an instance".into(),
+            parameters: vec![
+                ParameterInfo { name: "name".into(), param_type: "string".into(), required: true, default: None, description: "Instance name (created with --coverage)".into() },
+                ParameterInfo { name: "--kernel".into(), param_type: "path".into(), required: false, default: Some("target/x86_64-unknown-none/debug/kpio-kernel".into()), description: "Kernel binary with debug info".into() },
+                ParameterInfo { name: "--lcov".into(), param_type: "path".into(), required: false, default: None, description: "lcov report path".into() },
+                ParameterInfo { name: "--load-offset".into(), param_type: "u64".into(), required: false, default: Some("0".into()), description: "Kernel load offset subtracted from traced addresses".into() },
+            ],
+            exit_codes,
+            examples: vec![
+                "kpio-test create boot-test --coverage".into(),
+                "kpio-test coverage boot-test --lcov coverage.info".into(),
+            ],
+        }),
+        "gdb-attach" => Some(SubcommandHelp {
+            name: "gdb-attach".into(),
+            description: "Print the gdbstub address of an instance and optionally launch gdb".into(),
+            parameters: vec![
+                ParameterInfo { name: "name".into(), param_type: "string".into(), required: true, default: None, description: "Instance name (created with --gdb)".into() },
+                ParameterInfo { name: "--kernel".into(), param_type: "path".into(), required: false, default: Some("target/x86_64-unknown-none/debug/kpio-kernel".into()), description: "Kernel binary with debug info".into() },
+                ParameterInfo { name: "--launch".into(), param_type: "bool".into(), required: false, default: Some("false".into()), description: "Launch gdb connected to the instance".into() },
+                ParameterInfo { name: "--gdb".into(), param_type: "string".into(), required: false, default: None, description: "gdb binary to launch".into() },
+            ],
+            exit_codes,
+            examples: vec![
+                "kpio-test create debug-test --gdb-wait".into(),
+                "kpio-test gdb-attach debug-test --launch".into(),
+            ],
+        }),
+        "record" => Some(SubcommandHelp {
+            name: "record".into(),
+            description: "Record input commands sent to an instance into a replay script".into(),
+            parameters: vec![
+                ParameterInfo { name: "name".into(), param_type: "string".into(), required: true, default: None, description: "Instance name".into() },
+                ParameterInfo { name: "--output".into(), param_type: "path".into(), required: false, default: Some("input.jsonl in the instance store".into()), description: "Script file to record to".into() },
+                ParameterInfo { name: "--stop".into(), param_type: "bool".into(), required: false, default: Some("false".into()), description: "Stop the active recording".into() },
+            ],
+            exit_codes,
+            examples: vec![
+                "kpio-test record gui-test --output repro.jsonl".into(),
+                "kpio-test record gui-test --stop".into(),
+            ],
+        }),
+        "replay" => Some(SubcommandHelp {
+            name: "replay".into(),
+            description: "Replay a recorded input script against an instance".into(),
+            parameters: vec![
+                ParameterInfo { name: "name".into(), param_type: "string".into(), required: true, default: None, description: "Instance name".into() },
+                ParameterInfo { name: "--script".into(), param_type: "path".into(), required: true, default: None, description: "Recorded input script".into() },
+                ParameterInfo { name: "--speed".into(), param_type: "f64".into(), required: false, default: Some("1.0".into()), description: "Playback speed multiplier".into() },
+            ],
+            exit_codes,
+            examples: vec![
+                "kpio-test replay fresh-test --script repro.jsonl".into(),
+                "kpio-test replay fresh-test --script repro.jsonl --speed 4".into(),
+            ],
+        }),
+        "fuzz-serial" => Some(SubcommandHelp {
+            name: "fuzz-serial".into(),
+            description: "Stream mutated input to the serial console and save inputs that crash or hang it".into(),
+            parameters: vec![
+                ParameterInfo { name: "name".into(), param_type: "string".into(), required: true, default: None, description: "Instance name".into() },
+                ParameterInfo { name: "--iterations".into(), param_type: "usize".into(), required: false, default: Some("1000".into()), description: "Number of inputs to send".into() },
+                ParameterInfo { name: "--seed".into(), param_type: "u64".into(), required: false, default: None, description: "Mutator seed (derived from the time if omitted)".into() },
+                ParameterInfo { name: "--corpus".into(), param_type: "path".into(), required: false, default: None, description: "Command lines to mutate, one per line".into() },
+                ParameterInfo { name: "--output".into(), param_type: "path".into(), required: false, default: Some("fuzz/ in the instance store".into()), description: "Reproducer directory".into() },
+                ParameterInfo { name: "--hang-timeout".into(), param_type: "u64".into(), required: false, default: Some("5000".into()), description: "Milliseconds before an unanswered console counts as hung".into() },
+            ],
+            exit_codes,
+            examples: vec![
+                "kpio-test fuzz-serial shell-test --iterations 500".into(),
+                "kpio-test fuzz-serial shell-test --seed 42 --corpus commands.txt".into(),
+            ],
+        }),
         "wait-for" => Some(SubcommandHelp {
             name: "wait-for".into(),
             description: "Block until a serial pattern appears or timeout elapses".into(),
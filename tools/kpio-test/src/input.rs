@@ -2,13 +2,15 @@
 //!
 //! Provides handlers for `send-key`, `type-text`, `mouse-click`, and
 //! `mouse-move` subcommands. All input is injected through QMP's
-//! `input-send-event` command.
+//! `input-send-event` command. Inputs are also appended to the instance's
+//! input script while a recording is active (see `record`).
 
 use serde::Serialize;
 
 use crate::cli::{MouseClickArgs, MouseMoveArgs, SendKeyArgs, TypeTextArgs};
 use crate::error::KpioTestError;
 use crate::qmp::{InputEvent, QmpClient};
+use crate::record::{self, InputAction};
 use crate::state::InstanceStatus;
 use crate::{store, watchdog};
 
@@ -277,6 +279,49 @@ pub fn mouse_button_event(button_index: u32, down: bool) -> InputEvent {
     }
 }
 
+/// Build the events for a key combination: press every key in order,
+/// then release them in reverse order.
+pub fn key_combo_events(keys: &[String]) -> Result<Vec<InputEvent>, KpioTestError> {
+    // Resolve all key names to qcodes first so we fail fast on bad names.
+    let qcodes: Vec<&str> = keys
+        .iter()
+        .map(|k| key_name_to_qcode(k))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut events: Vec<InputEvent> = Vec::new();
+    for &qcode in &qcodes {
+        events.push(key_event(qcode, true));
+    }
+    for &qcode in qcodes.iter().rev() {
+        events.push(key_event(qcode, false));
+    }
+    Ok(events)
+}
+
+/// Build the events that type one character, holding shift if needed.
+pub fn char_events(ch: char) -> Result<Vec<InputEvent>, KpioTestError> {
+    let (qcode, needs_shift) = char_to_qcode(ch)?;
+    let mut events = Vec::new();
+
+    if needs_shift {
+        events.push(key_event("shift", true));
+    }
+    events.push(key_event(qcode, true));
+    events.push(key_event(qcode, false));
+    if needs_shift {
+        events.push(key_event("shift", false));
+    }
+    Ok(events)
+}
+
+/// Build the press/release events for a mouse click.
+pub fn click_events(button_index: u32) -> Vec<InputEvent> {
+    vec![
+        mouse_button_event(button_index, true),
+        mouse_button_event(button_index, false),
+    ]
+}
+
 // ── Subcommand handlers ──────────────────────────────────────────────
 
 /// `send-key <name> <key> [<key2> ...]` — send key press/release events.
@@ -292,23 +337,16 @@ pub fn send_key(args: SendKeyArgs) -> Result<serde_json::Value, KpioTestError> {
 
     let mut qmp = QmpClient::connect(&state.qmp_socket)?;
 
-    // Resolve all key names to qcodes first so we fail fast on bad names.
-    let qcodes: Vec<&str> = args
-        .keys
-        .iter()
-        .map(|k| key_name_to_qcode(k))
-        .collect::<Result<Vec<_>, _>>()?;
-
-    // Send press events for all keys, then release in reverse order.
-    let mut events: Vec<InputEvent> = Vec::new();
-    for &qcode in &qcodes {
-        events.push(key_event(qcode, true));
-    }
-    for &qcode in qcodes.iter().rev() {
-        events.push(key_event(qcode, false));
-    }
+    let events = key_combo_events(&args.keys)?;
     qmp.input_send_event(&events)?;
 
+    record::capture(
+        &args.name,
+        InputAction::SendKey {
+            keys: args.keys.clone(),
+        },
+    )?;
+
     let output = SendKeyOutput {
         name: args.name,
         keys_sent: args.keys,
@@ -331,22 +369,17 @@ pub fn type_text(args: TypeTextArgs) -> Result<serde_json::Value, KpioTestError>
     let mut keys_sent: usize = 0;
 
     for ch in args.text.chars() {
-        let (qcode, needs_shift) = char_to_qcode(ch)?;
-        let mut events = Vec::new();
-
-        if needs_shift {
-            events.push(key_event("shift", true));
-        }
-        events.push(key_event(qcode, true));
-        events.push(key_event(qcode, false));
-        if needs_shift {
-            events.push(key_event("shift", false));
-        }
-
-        qmp.input_send_event(&events)?;
+        qmp.input_send_event(&char_events(ch)?)?;
         keys_sent += 1;
     }
 
+    record::capture(
+        &args.name,
+        InputAction::TypeText {
+            text: args.text.clone(),
+        },
+    )?;
+
     let output = TypeTextOutput {
         name: args.name,
         text: args.text,
@@ -374,11 +407,16 @@ pub fn mouse_click(args: MouseClickArgs) -> Result<serde_json::Value, KpioTestEr
     qmp.input_send_event(&move_events)?;
 
     // Press and release button
-    let click_events = vec![
-        mouse_button_event(btn_index, true),
-        mouse_button_event(btn_index, false),
-    ];
-    qmp.input_send_event(&click_events)?;
+    qmp.input_send_event(&click_events(btn_index))?;
+
+    record::capture(
+        &args.name,
+        InputAction::MouseClick {
+            x: args.x,
+            y: args.y,
+            button: args.button.clone(),
+        },
+    )?;
 
     let output = MouseClickOutput {
         name: args.name,
@@ -405,6 +443,14 @@ pub fn mouse_move(args: MouseMoveArgs) -> Result<serde_json::Value, KpioTestErro
     let events = mouse_move_events(args.x, args.y);
     qmp.input_send_event(&events)?;
 
+    record::capture(
+        &args.name,
+        InputAction::MouseMove {
+            x: args.x,
+            y: args.y,
+        },
+    )?;
+
     let output = MouseMoveOutput {
         name: args.name,
         x: args.x,
@@ -537,6 +583,35 @@ mod tests {
         assert_eq!(evt.data["down"], false);
     }
 
+    #[test]
+    fn key_combo_releases_in_reverse() {
+        let evts = key_combo_events(&["ctrl".to_string(), "c".to_string()]).unwrap();
+        let order: Vec<(&str, bool)> = evts
+            .iter()
+            .map(|e| {
+                (
+                    e.data["key"]["data"].as_str().unwrap(),
+                    e.data["down"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            order,
+            vec![("ctrl", true), ("c", true), ("c", false), ("ctrl", false)]
+        );
+        assert!(key_combo_events(&["nonexistent_key".to_string()]).is_err());
+    }
+
+    #[test]
+    fn char_events_wrap_shift() {
+        assert_eq!(char_events('a').unwrap().len(), 2);
+        let evts = char_events('A').unwrap();
+        assert_eq!(evts.len(), 4);
+        assert_eq!(evts[0].data["key"]["data"], "shift");
+        assert_eq!(evts[3].data["key"]["data"], "shift");
+        assert_eq!(evts[3].data["down"], false);
+    }
+
     #[test]
     fn button_name_mapping() {
         assert_eq!(button_to_index("left").unwrap(), 0);
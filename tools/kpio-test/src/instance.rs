@@ -10,7 +10,9 @@ use chrono::Utc;
 use serde::Serialize;
 
 use crate::cli::CreateArgs;
+use crate::coverage;
 use crate::error::KpioTestError;
+use crate::gdb;
 use crate::health;
 use crate::state::{InstanceConfig, InstanceState, InstanceStatus};
 use crate::store;
@@ -23,6 +25,7 @@ pub struct CreateOutput {
     pub name: String,
     pub pid: u32,
     pub qmp_socket: String,
+    pub gdb_port: Option<u16>,
     pub status: String,
 }
 
@@ -75,9 +78,22 @@ pub fn create(args: CreateArgs) -> Result<serde_json::Value, KpioTestError> {
     let report = health::check(Some(&image_path));
     health::validate(&report)?;
 
+    // Reserve a gdbstub port before anything is written to the store
+    let gdb_port = if args.gdb || args.gdb_wait || args.gdb_port.is_some() {
+        Some(gdb::allocate_port(args.gdb_port)?)
+    } else {
+        None
+    };
+
     // Create instance store (fails if name already exists)
     store::create_store(name)?;
 
+    // Coverage tracing is passed through as extra QEMU arguments
+    let mut extra_args = args.extra_args.clone();
+    if args.coverage {
+        extra_args.extend(coverage::qemu_args(&store::coverage_trace_path(name)));
+    }
+
     let config = InstanceConfig {
         image_path: image_path.clone(),
         memory: args.memory.clone(),
@@ -85,7 +101,9 @@ pub fn create(args: CreateArgs) -> Result<serde_json::Value, KpioTestError> {
         virtio_net: args.virtio_net,
         virtio_blk: args.virtio_blk.clone(),
         shared_dir: args.shared_dir.clone(),
-        extra_args: args.extra_args.clone(),
+        extra_args,
+        gdb_port,
+        gdb_wait: args.gdb_wait,
     };
 
     // Build QEMU command-line arguments
@@ -125,6 +143,7 @@ pub fn create(args: CreateArgs) -> Result<serde_json::Value, KpioTestError> {
         name: name.clone(),
         pid,
         qmp_socket: state.qmp_socket.to_string_lossy().to_string(),
+        gdb_port,
         status: "running".to_string(),
     };
     Ok(serde_json::to_value(output)?)
@@ -311,6 +330,11 @@ pub fn build_qemu_args(name: &str, config: &InstanceConfig) -> Vec<String> {
         ]);
     }
 
+    // gdbstub
+    if let Some(port) = config.gdb_port {
+        args.extend(gdb::qemu_args(port, config.gdb_wait));
+    }
+
     // Extra args
     args.extend(config.extra_args.clone());
 
@@ -428,6 +452,8 @@ mod tests {
             virtio_blk: None,
             shared_dir: None,
             extra_args: vec![],
+            gdb_port: None,
+            gdb_wait: false,
         }
     }
 
@@ -507,4 +533,22 @@ mod tests {
         assert!(args.contains(&"-cpu".to_string()));
         assert!(args.contains(&"host".to_string()));
     }
+
+    #[test]
+    fn build_qemu_args_no_gdb_by_default() {
+        let args = build_qemu_args("test", &sample_config());
+        assert!(!args.contains(&"-gdb".to_string()));
+        assert!(!args.contains(&"-S".to_string()));
+    }
+
+    #[test]
+    fn build_qemu_args_gdb() {
+        let mut config = sample_config();
+        config.gdb_port = Some(1235);
+        config.gdb_wait = true;
+        let args = build_qemu_args("test", &config);
+        let idx = args.iter().position(|a| a == "-gdb").unwrap();
+        assert_eq!(args[idx + 1], "tcp:127.0.0.1:1235");
+        assert!(args.contains(&"-S".to_string()));
+    }
 }
@@ -1,6 +1,9 @@
 pub mod build;
 pub mod cli;
+pub mod coverage;
 pub mod error;
+pub mod fuzz;
+pub mod gdb;
 pub mod health;
 pub mod help;
 pub mod input;
@@ -10,6 +13,7 @@ pub mod network;
 pub mod ocr;
 pub mod output;
 pub mod qmp;
+pub mod record;
 pub mod screenshot;
 pub mod serial;
 pub mod snapshot;
@@ -1,6 +1,9 @@
 pub mod build;
 pub mod cli;
+pub mod coverage;
 pub mod error;
+pub mod fuzz;
+pub mod gdb;
 pub mod health;
 pub mod help;
 pub mod input;
@@ -10,6 +13,7 @@ pub mod network;
 pub mod ocr;
 pub mod output;
 pub mod qmp;
+pub mod record;
 pub mod screenshot;
 pub mod serial;
 pub mod snapshot;
@@ -164,6 +168,9 @@ fn main() -> ExitCode {
         Command::TypeText(args) => input::type_text(args),
         Command::MouseClick(args) => input::mouse_click(args),
         Command::MouseMove(args) => input::mouse_move(args),
+        Command::Record(args) => record::record(args),
+        Command::Replay(args) => record::replay(args),
+        Command::FuzzSerial(args) => fuzz::fuzz_serial(args),
         Command::Snapshot(args) => snapshot::snapshot(args),
         Command::GuestInfo(args) => guest_info(&args.name),
         Command::PortForward(args) => network::port_forward(args),
@@ -172,6 +179,8 @@ fn main() -> ExitCode {
         Command::Logs(args) => logs(args),
         Command::Build(args) => build::build(args),
         Command::Verify(args) => manifest::verify(args),
+        Command::Coverage(args) => coverage::coverage(args),
+        Command::GdbAttach(args) => gdb::gdb_attach(args),
         Command::Health => {
             let report = health::check(None);
             serde_json::to_value(&report).map_err(|e| error::KpioTestError::Json(e))
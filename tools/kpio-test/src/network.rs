@@ -315,6 +315,8 @@ VLAN -1 (net0):
                 virtio_blk: None,
                 shared_dir: None,
                 extra_args: vec![],
+                gdb_port: None,
+                gdb_wait: false,
             },
         }
     }
@@ -0,0 +1,344 @@
+//! Input recording and deterministic replay.
+//!
+//! `record <name> --output <script>` starts capturing every `send-key`,
+//! `type-text`, `mouse-click`, and `mouse-move` issued against an instance.
+//! Because each CLI invocation is a separate process, the recording lives
+//! on disk: a marker file in the Instance_Store names the script and the
+//! time recording started, and each input handler appends one line to the
+//! script after its events are sent. `record <name> --stop` removes the
+//! marker.
+//!
+//! Scripts are JSON Lines, one input per line, timestamped relative to
+//! the start of the recording:
+//!
+//! ```text
+//! {"offset_ms":0,"command":"mouse-click","x":120,"y":48,"button":"left"}
+//! {"offset_ms":850,"command":"type-text","text":"ls"}
+//! {"offset_ms":1900,"command":"send-key","keys":["ret"]}
+//! ```
+//!
+//! `replay <name> --script <script>` re-issues the inputs against a
+//! (typically fresh) instance at the recorded cadence, optionally scaled
+//! by `--speed`.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{RecordArgs, ReplayArgs};
+use crate::error::KpioTestError;
+use crate::input;
+use crate::qmp::QmpClient;
+use crate::state::InstanceStatus;
+use crate::{store, watchdog};
+
+// ── Script format ────────────────────────────────────────────────────
+
+/// One input command, as issued on the command line.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum InputAction {
+    SendKey { keys: Vec<String> },
+    TypeText { text: String },
+    MouseClick { x: u32, y: u32, button: String },
+    MouseMove { x: u32, y: u32 },
+}
+
+/// An input command and when it was issued.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedInput {
+    /// Milliseconds since the recording started.
+    pub offset_ms: u64,
+    #[serde(flatten)]
+    pub action: InputAction,
+}
+
+/// Active recording marker (`recording.json` in the Instance_Store).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recording {
+    /// Script file the inputs are appended to.
+    pub script: PathBuf,
+    /// Recording start timestamp (ISO 8601).
+    pub started_at: String,
+}
+
+/// Parse a script, skipping blank lines.
+pub fn parse_script(content: &str) -> Result<Vec<RecordedInput>, KpioTestError> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(KpioTestError::Json))
+        .collect()
+}
+
+/// Time after the start of a replay at which an input recorded at
+/// `offset_ms` is issued.
+pub fn replay_offset(offset_ms: u64, speed: f64) -> Duration {
+    Duration::from_secs_f64(offset_ms as f64 / 1000.0 / speed)
+}
+
+// ── Capture ──────────────────────────────────────────────────────────
+
+/// Read the recording marker for an instance, if one is active.
+pub fn active_recording(name: &str) -> Result<Option<Recording>, KpioTestError> {
+    let path = store::recording_path(name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+/// Append `action` to the instance's script if a recording is active.
+///
+/// Called by the input handlers after their events have been sent.
+pub fn capture(name: &str, action: InputAction) -> Result<(), KpioTestError> {
+    let Some(recording) = active_recording(name)? else {
+        return Ok(());
+    };
+
+    let started = chrono::DateTime::parse_from_rfc3339(&recording.started_at)
+        .map_err(|e| KpioTestError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+    let offset_ms = (Utc::now() - started.with_timezone(&Utc))
+        .num_milliseconds()
+        .max(0) as u64;
+
+    let entry = RecordedInput { offset_ms, action };
+    let mut script = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&recording.script)?;
+    writeln!(script, "{}", serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+// ── Playback ─────────────────────────────────────────────────────────
+
+/// Send the QMP events for one recorded input.
+pub fn send_action(qmp: &mut QmpClient, action: &InputAction) -> Result<(), KpioTestError> {
+    match action {
+        InputAction::SendKey { keys } => qmp.input_send_event(&input::key_combo_events(keys)?),
+        InputAction::TypeText { text } => {
+            for ch in text.chars() {
+                qmp.input_send_event(&input::char_events(ch)?)?;
+            }
+            Ok(())
+        }
+        InputAction::MouseClick { x, y, button } => {
+            let btn_index = input::button_to_index(button)?;
+            qmp.input_send_event(&input::mouse_move_events(*x, *y))?;
+            qmp.input_send_event(&input::click_events(btn_index))
+        }
+        InputAction::MouseMove { x, y } => qmp.input_send_event(&input::mouse_move_events(*x, *y)),
+    }
+}
+
+// ── Output structs ───────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct RecordStartOutput {
+    name: String,
+    script: PathBuf,
+    action: &'static str,
+}
+
+#[derive(Serialize)]
+struct RecordStopOutput {
+    name: String,
+    script: PathBuf,
+    events: usize,
+    action: &'static str,
+}
+
+#[derive(Serialize)]
+struct ReplayOutput {
+    name: String,
+    script: PathBuf,
+    events_replayed: usize,
+    speed: f64,
+    duration_ms: u64,
+}
+
+// ── Subcommand handlers ──────────────────────────────────────────────
+
+/// `record <name> [--output <script>]` / `record <name> --stop`.
+pub fn record(args: RecordArgs) -> Result<serde_json::Value, KpioTestError> {
+    if args.stop {
+        return stop_recording(&args.name);
+    }
+
+    let mut state = store::read_state(&args.name)?;
+    watchdog::enforce(&mut state)?;
+
+    if state.status != InstanceStatus::Running {
+        return Err(KpioTestError::InstanceNotRunning {
+            name: args.name.clone(),
+        });
+    }
+
+    let script = args
+        .output
+        .unwrap_or_else(|| store::input_script_path(&args.name));
+    if let Some(parent) = script.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    // Starting a new recording discards any previous script at this path.
+    fs::write(&script, "")?;
+
+    let recording = Recording {
+        script: script.clone(),
+        started_at: Utc::now().to_rfc3339(),
+    };
+    fs::write(
+        store::recording_path(&args.name),
+        serde_json::to_string_pretty(&recording)?,
+    )?;
+
+    let output = RecordStartOutput {
+        name: args.name,
+        script,
+        action: "started",
+    };
+    Ok(serde_json::to_value(output)?)
+}
+
+/// Stop the active recording and report how many inputs it captured.
+fn stop_recording(name: &str) -> Result<serde_json::Value, KpioTestError> {
+    if !store::instance_exists(name) {
+        return Err(KpioTestError::InstanceNotFound {
+            name: name.to_string(),
+        });
+    }
+    let recording = active_recording(name)?.ok_or_else(|| KpioTestError::NotRecording {
+        name: name.to_string(),
+    })?;
+    fs::remove_file(store::recording_path(name))?;
+
+    let events = if recording.script.exists() {
+        parse_script(&fs::read_to_string(&recording.script)?)?.len()
+    } else {
+        0
+    };
+
+    let output = RecordStopOutput {
+        name: name.to_string(),
+        script: recording.script,
+        events,
+        action: "stopped",
+    };
+    Ok(serde_json::to_value(output)?)
+}
+
+/// `replay <name> --script <script> [--speed <factor>]`.
+pub fn replay(args: ReplayArgs) -> Result<serde_json::Value, KpioTestError> {
+    let mut state = store::read_state(&args.name)?;
+    watchdog::enforce(&mut state)?;
+
+    if state.status != InstanceStatus::Running {
+        return Err(KpioTestError::InstanceNotRunning {
+            name: args.name.clone(),
+        });
+    }
+
+    let inputs = read_script(&args.script)?;
+    let mut qmp = QmpClient::connect(&state.qmp_socket)?;
+
+    let start = Instant::now();
+    for recorded in &inputs {
+        let due = replay_offset(recorded.offset_ms, args.speed);
+        let elapsed = start.elapsed();
+        if due > elapsed {
+            std::thread::sleep(due - elapsed);
+        }
+        send_action(&mut qmp, &recorded.action)?;
+    }
+
+    let output = ReplayOutput {
+        name: args.name,
+        script: args.script,
+        events_replayed: inputs.len(),
+        speed: args.speed,
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+    Ok(serde_json::to_value(output)?)
+}
+
+/// Read and parse a script file.
+fn read_script(path: &Path) -> Result<Vec<RecordedInput>, KpioTestError> {
+    if !path.exists() {
+        return Err(KpioTestError::FileNotFound {
+            path: path.to_path_buf(),
+        });
+    }
+    parse_script(&fs::read_to_string(path)?)
+}
+
+// ── Unit tests ───────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_input_line_format() {
+        let entry = RecordedInput {
+            offset_ms: 1900,
+            action: InputAction::SendKey {
+                keys: vec!["ctrl".into(), "c".into()],
+            },
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        assert_eq!(
+            line,
+            r#"{"offset_ms":1900,"command":"send-key","keys":["ctrl","c"]}"#
+        );
+    }
+
+    #[test]
+    fn parse_script_round_trip() {
+        let inputs = vec![
+            RecordedInput {
+                offset_ms: 0,
+                action: InputAction::MouseClick {
+                    x: 120,
+                    y: 48,
+                    button: "left".into(),
+                },
+            },
+            RecordedInput {
+                offset_ms: 850,
+                action: InputAction::TypeText { text: "ls".into() },
+            },
+            RecordedInput {
+                offset_ms: 1200,
+                action: InputAction::MouseMove { x: 5, y: 6 },
+            },
+        ];
+        let script: String = inputs
+            .iter()
+            .map(|i| serde_json::to_string(i).unwrap() + "\n\n")
+            .collect();
+        assert_eq!(parse_script(&script).unwrap(), inputs);
+    }
+
+    #[test]
+    fn parse_script_rejects_unknown_command() {
+        let script = r#"{"offset_ms":0,"command":"screenshot"}"#;
+        assert!(matches!(
+            parse_script(script).unwrap_err(),
+            KpioTestError::Json(_)
+        ));
+    }
+
+    #[test]
+    fn replay_offset_scales_with_speed() {
+        assert_eq!(replay_offset(1500, 1.0), Duration::from_millis(1500));
+        assert_eq!(replay_offset(1500, 2.0), Duration::from_millis(750));
+        assert_eq!(replay_offset(1500, 0.5), Duration::from_millis(3000));
+        assert_eq!(replay_offset(0, 4.0), Duration::ZERO);
+    }
+}
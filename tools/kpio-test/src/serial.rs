@@ -163,26 +163,27 @@ pub fn send_command(args: SendCommandArgs) -> Result<serde_json::Value, KpioTest
     }
 
     let mut qmp = QmpClient::connect(&state.qmp_socket)?;
+    write_input(&mut qmp, format!("{}\n", args.text).as_bytes())?;
 
-    // Encode text + newline as base64 for the ringbuf-write QMP command.
-    let data = format!("{}\n", args.text);
-    let encoded = base64_encode(data.as_bytes());
+    let output = SendCommandOutput {
+        name: args.name,
+        text: args.text,
+        sent: true,
+    };
+    Ok(serde_json::to_value(output)?)
+}
 
+/// Write raw bytes to the serial console input via QMP.
+pub fn write_input(qmp: &mut QmpClient, data: &[u8]) -> Result<(), KpioTestError> {
+    // ringbuf-write takes base64 so arbitrary bytes survive the JSON transport.
     qmp.execute_void(
         "ringbuf-write",
         Some(serde_json::json!({
             "device": "serial0",
-            "data": encoded,
+            "data": base64_encode(data),
             "format": "base64"
         })),
-    )?;
-
-    let output = SendCommandOutput {
-        name: args.name,
-        text: args.text,
-        sent: true,
-    };
-    Ok(serde_json::to_value(output)?)
+    )
 }
 
 // ── Internal helpers ─────────────────────────────────────────────────
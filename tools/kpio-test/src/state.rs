@@ -65,6 +65,12 @@ pub struct InstanceConfig {
     pub shared_dir: Option<PathBuf>,
     /// Extra QEMU command-line arguments.
     pub extra_args: Vec<String>,
+    /// TCP port of the QEMU gdbstub, if enabled.
+    #[serde(default)]
+    pub gdb_port: Option<u16>,
+    /// Whether the CPU is held at reset until a debugger continues it.
+    #[serde(default)]
+    pub gdb_wait: bool,
 }
 
 /// Full persisted state for a single QEMU instance (`state.json`).
@@ -173,6 +179,8 @@ mod tests {
             virtio_blk: None,
             shared_dir: None,
             extra_args: vec![],
+            gdb_port: None,
+            gdb_wait: false,
         }
     }
 
@@ -260,9 +268,19 @@ mod tests {
             virtio_blk: Some(PathBuf::from("extra.img")),
             shared_dir: Some(PathBuf::from("/tmp/share")),
             extra_args: vec!["-cpu".to_string(), "host".to_string()],
+            gdb_port: Some(1234),
+            gdb_wait: true,
         };
         let json = serde_json::to_string(&config).unwrap();
         let back: InstanceConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(config, back);
     }
+
+    #[test]
+    fn instance_config_without_gdb_fields() {
+        let json = r#"{"image_path":"disk.img","memory":"512M","gui":false,"virtio_net":false,"virtio_blk":null,"shared_dir":null,"extra_args":[]}"#;
+        let config: InstanceConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.gdb_port, None);
+        assert!(!config.gdb_wait);
+    }
 }
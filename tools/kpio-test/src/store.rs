@@ -6,6 +6,10 @@
 //! - `qemu.log`     — QEMU process stderr/stdout
 //! - `qmp.sock`     — QMP Unix socket (or named pipe path on Windows)
 //! - `screenshots/` — captured screenshots
+//! - `coverage.log` — QEMU translated-code log (instances created with `--coverage`)
+//! - `gdbinit`      — gdb helper script written by `gdb-attach`
+//! - `recording.json` — active input recording marker (see `record`)
+//! - `input.jsonl`  — default input script written by `record`
 
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -46,6 +50,31 @@ pub fn qmp_socket_path(name: &str) -> PathBuf {
     instance_dir(name).join("qmp.sock")
 }
 
+/// Return the path to the coverage trace log for the given instance.
+pub fn coverage_trace_path(name: &str) -> PathBuf {
+    instance_dir(name).join("coverage.log")
+}
+
+/// Return the path to the gdb helper script for the given instance.
+pub fn gdb_script_path(name: &str) -> PathBuf {
+    instance_dir(name).join("gdbinit")
+}
+
+/// Return the path to the active input recording marker for the given instance.
+pub fn recording_path(name: &str) -> PathBuf {
+    instance_dir(name).join("recording.json")
+}
+
+/// Return the default input script path for the given instance.
+pub fn input_script_path(name: &str) -> PathBuf {
+    instance_dir(name).join("input.jsonl")
+}
+
+/// Return the default fuzz-serial reproducer directory for the given instance.
+pub fn fuzz_dir(name: &str) -> PathBuf {
+    instance_dir(name).join("fuzz")
+}
+
 /// Return the screenshot output directory for the given instance.
 pub fn screenshot_dir(name: &str) -> PathBuf {
     instance_dir(name).join("screenshots")
@@ -177,6 +206,8 @@ mod tests {
                 virtio_blk: None,
                 shared_dir: None,
                 extra_args: vec![],
+                gdb_port: None,
+                gdb_wait: false,
             },
         }
     }
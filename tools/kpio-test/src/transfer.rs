@@ -163,6 +163,8 @@ mod tests {
                 virtio_blk: None,
                 shared_dir,
                 extra_args: vec![],
+                gdb_port: None,
+                gdb_wait: false,
             },
         }
     }
@@ -33,6 +33,8 @@ fn sample_state(name: &str) -> InstanceState {
             virtio_blk: None,
             shared_dir: None,
             extra_args: vec![],
+            gdb_port: None,
+            gdb_wait: false,
         },
     }
 }
@@ -13,13 +13,13 @@ use std::process::ExitCode;
 
 /// Strategy that produces an arbitrary `KpioTestError` variant.
 ///
-/// We tag each variant with an index (0..=20) and generate random payloads.
+/// We tag each variant with an index (0..=24) and generate random payloads.
 fn arb_kpio_test_error() -> impl Strategy<Value = KpioTestError> {
     // Reusable leaf strategies
     let arb_string = "[a-zA-Z0-9_ /\\-\\.]{0,64}";
     let arb_path = arb_string.prop_map(PathBuf::from);
 
-    (0..=20u8, arb_string, arb_path, 1..3600u64, 0..1000usize).prop_map(
+    (0..=24u8, arb_string, arb_path, 1..3600u64, 0..1000usize).prop_map(
         |(tag, s, p, secs, count)| match tag {
             // Infrastructure errors (exit code 2)
             0 => KpioTestError::QemuNotFound {
@@ -65,8 +65,18 @@ fn arb_kpio_test_error() -> impl Strategy<Value = KpioTestError> {
                 tag: s.to_string(),
             },
             18 => KpioTestError::FileNotFound { path: p },
+            19 => KpioTestError::NotRecording {
+                name: s.to_string(),
+            },
+            20 => KpioTestError::GdbNotEnabled {
+                name: s.to_string(),
+            },
+            21 => KpioTestError::DebuggerNotFound {
+                hint: s.to_string(),
+            },
+            22 => KpioTestError::GdbPortInUse { port: secs as u16 },
             // Wrap around to cover more infrastructure variants
-            19 => KpioTestError::QemuNotFound {
+            23 => KpioTestError::QemuNotFound {
                 hint: s.to_string(),
             },
             _ => KpioTestError::OvmfNotFound {
@@ -88,6 +98,8 @@ fn is_infrastructure(err: &KpioTestError) -> bool {
             | KpioTestError::SnapshotRequiresQcow2
             | KpioTestError::SharedDirRequired
             | KpioTestError::NetworkDeviceRequired
+            | KpioTestError::GdbNotEnabled { .. }
+            | KpioTestError::DebuggerNotFound { .. }
             | KpioTestError::UnknownSubcommand { .. }
             | KpioTestError::Io(_)
             | KpioTestError::Json(_)
@@ -108,6 +120,8 @@ fn is_operational(err: &KpioTestError) -> bool {
             | KpioTestError::BuildFailed { .. }
             | KpioTestError::SnapshotNotFound { .. }
             | KpioTestError::FileNotFound { .. }
+            | KpioTestError::NotRecording { .. }
+            | KpioTestError::GdbPortInUse { .. }
     )
 }
 
@@ -38,6 +38,8 @@ fn make_state(shared_dir: Option<PathBuf>) -> InstanceState {
             virtio_blk: None,
             shared_dir,
             extra_args: vec![],
+            gdb_port: None,
+            gdb_wait: false,
         },
     }
 }
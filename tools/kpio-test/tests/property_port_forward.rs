@@ -39,6 +39,8 @@ fn make_state(virtio_net: bool) -> InstanceState {
             virtio_blk: None,
             shared_dir: None,
             extra_args: vec![],
+            gdb_port: None,
+            gdb_wait: false,
         },
     }
 }
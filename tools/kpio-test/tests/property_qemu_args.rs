@@ -40,6 +40,8 @@ fn arb_config() -> impl Strategy<Value = InstanceConfig> {
                     None
                 },
                 extra_args: vec![],
+                gdb_port: None,
+                gdb_wait: false,
             },
         )
 }
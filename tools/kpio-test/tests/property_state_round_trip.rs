@@ -30,8 +30,10 @@ fn arb_config() -> impl Strategy<Value = InstanceConfig> {
         proptest::option::of("[a-zA-Z0-9_/\\-\\.]{1,32}"), // virtio_blk
         proptest::option::of("[a-zA-Z0-9_/\\-\\.]{1,32}"), // shared_dir
         proptest::collection::vec("[a-zA-Z0-9\\-]{1,16}", 0..4), // extra_args
+        proptest::option::of(1024u16..),        // gdb_port
+        any::<bool>(),                           // gdb_wait
     )
-        .prop_map(|(img, mem, gui, vnet, vblk, sdir, args)| InstanceConfig {
+        .prop_map(|(img, mem, gui, vnet, vblk, sdir, args, gdb_port, gdb_wait)| InstanceConfig {
             image_path: PathBuf::from(img),
             memory: mem,
             gui,
@@ -39,6 +41,8 @@ fn arb_config() -> impl Strategy<Value = InstanceConfig> {
             virtio_blk: vblk.map(PathBuf::from),
             shared_dir: sdir.map(PathBuf::from),
             extra_args: args,
+            gdb_port,
+            gdb_wait,
         })
 }
 
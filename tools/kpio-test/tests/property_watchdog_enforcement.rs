@@ -36,6 +36,8 @@ fn make_expired_state(name: &str) -> InstanceState {
             virtio_blk: None,
             shared_dir: None,
             extra_args: vec![],
+            gdb_port: None,
+            gdb_wait: false,
         },
     }
 }
@@ -2,7 +2,9 @@
 //!
 //! This module provides standard I/O operations like print and read.
 
-use crate::syscall::{self, linux, raw_syscall1, raw_syscall2, raw_syscall3, SyscallResult};
+use crate::syscall::{
+    self, linux, raw_syscall1, raw_syscall2, raw_syscall3, raw_syscall4, SyscallResult,
+};
 
 /// File descriptor for stdin.
 pub const STDIN: u64 = 0;
@@ -35,6 +37,36 @@ pub fn read(fd: u64, buf: &mut [u8]) -> SyscallResult {
     }
 }
 
+/// Read bytes from a file descriptor at `offset`, leaving the file
+/// position unchanged.
+///
+/// Returns a short count (or 0) at end of file.
+pub fn pread(fd: u64, buf: &mut [u8], offset: u64) -> SyscallResult {
+    unsafe {
+        raw_syscall4(
+            linux::SYS_PREAD64,
+            fd,
+            buf.as_mut_ptr() as u64,
+            buf.len() as u64,
+            offset,
+        )
+    }
+}
+
+/// Write bytes to a file descriptor at `offset`, leaving the file
+/// position unchanged.
+pub fn pwrite(fd: u64, buf: &[u8], offset: u64) -> SyscallResult {
+    unsafe {
+        raw_syscall4(
+            linux::SYS_PWRITE64,
+            fd,
+            buf.as_ptr() as u64,
+            buf.len() as u64,
+            offset,
+        )
+    }
+}
+
 /// Print a string to stdout.
 pub fn print(s: &str) {
     let _ = write(STDOUT, s.as_bytes());
@@ -131,6 +163,16 @@ impl File {
         read(self.fd, buf)
     }
 
+    /// Read data at `offset` without moving the file position.
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> SyscallResult {
+        pread(self.fd, buf, offset)
+    }
+
+    /// Write data at `offset` without moving the file position.
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> SyscallResult {
+        pwrite(self.fd, buf, offset)
+    }
+
     /// Seek to a position in the file.
     pub fn seek(&self, offset: i64, whence: u32) -> Result<u64, syscall::SyscallError> {
         syscall::fs_seek(self.fd, offset, whence)
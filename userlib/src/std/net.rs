@@ -186,10 +186,91 @@ impl TcpStream {
     }
 
     /// Set nodelay
-    pub fn set_nodelay(&self, _nodelay: bool) -> Result<(), IoError> {
-        // TODO: Implement via syscall
-        Ok(())
+    pub fn set_nodelay(&self, nodelay: bool) -> Result<(), IoError> {
+        self.set_option(IPPROTO_TCP, TCP_NODELAY, nodelay as u32)
+    }
+
+    /// Get nodelay
+    pub fn nodelay(&self) -> Result<bool, IoError> {
+        self.option(IPPROTO_TCP, TCP_NODELAY)
+            .map(|value| value != 0)
+    }
+
+    /// Probe the idle connection at this interval; `None` disables
+    /// keepalive
+    pub fn set_keepalive(&self, interval: Option<core::time::Duration>) -> Result<(), IoError> {
+        if let Some(interval) = interval {
+            let secs = interval.as_secs().clamp(1, u32::MAX as u64) as u32;
+            self.set_option(IPPROTO_TCP, TCP_KEEPINTVL, secs)?;
+        }
+        self.set_option(SOL_SOCKET, SO_KEEPALIVE, interval.is_some() as u32)
+    }
+
+    /// Fail the connection once sent data goes unacknowledged this long;
+    /// `None` restores the default
+    pub fn set_user_timeout(&self, timeout: Option<core::time::Duration>) -> Result<(), IoError> {
+        let ms = timeout.map_or(0, |t| t.as_millis().clamp(1, u32::MAX as u128) as u32);
+        self.set_option(IPPROTO_TCP, TCP_USER_TIMEOUT, ms)
+    }
+
+    /// Get the connection's TCP statistics
+    pub fn tcp_stats(&self) -> Result<TcpStats, IoError> {
+        let mut info = [0u8; 20];
+        let len = syscall::net_getsockopt(self.fd, IPPROTO_TCP, TCP_INFO, &mut info)
+            .map_err(|_| IoError::Other)?;
+        if len < info.len() as u64 {
+            return Err(IoError::InvalidData);
+        }
+        let field = |i: usize| u32::from_ne_bytes([info[i], info[i + 1], info[i + 2], info[i + 3]]);
+        Ok(TcpStats {
+            state: field(0) as u8,
+            retransmits: field(4),
+            rtt_estimate: match field(8) {
+                0 => None,
+                ms => Some(core::time::Duration::from_millis(ms as u64)),
+            },
+            rto: core::time::Duration::from_millis(field(12) as u64),
+            cwnd: field(16),
+        })
     }
+
+    fn set_option(&self, level: u32, optname: u32, value: u32) -> Result<(), IoError> {
+        syscall::net_setsockopt(self.fd, level, optname, value)
+            .map(|_| ())
+            .map_err(|_| IoError::Other)
+    }
+
+    fn option(&self, level: u32, optname: u32) -> Result<u32, IoError> {
+        let mut value = [0u8; 4];
+        syscall::net_getsockopt(self.fd, level, optname, &mut value)
+            .map(|_| u32::from_ne_bytes(value))
+            .map_err(|_| IoError::Other)
+    }
+}
+
+// Socket option levels and names (Linux values)
+const SOL_SOCKET: u32 = 1;
+const SO_KEEPALIVE: u32 = 9;
+const IPPROTO_TCP: u32 = 6;
+const TCP_NODELAY: u32 = 1;
+const TCP_KEEPINTVL: u32 = 5;
+const TCP_INFO: u32 = 11;
+const TCP_USER_TIMEOUT: u32 = 18;
+
+/// TCP connection statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpStats {
+    /// Connection state, numbered like Linux's `TCP_ESTABLISHED` (1)
+    /// through `TCP_CLOSING` (11)
+    pub state: u8,
+    /// Segments sent again after the retransmission timeout expired
+    pub retransmits: u32,
+    /// Smoothed round-trip time, once measured
+    pub rtt_estimate: Option<core::time::Duration>,
+    /// Retransmission timeout, including backoff
+    pub rto: core::time::Duration,
+    /// Congestion window in bytes
+    pub cwnd: u32,
 }
 
 impl Drop for TcpStream {
@@ -24,10 +24,14 @@ pub mod linux {
     pub const SYS_MUNMAP: u64 = 11;
     pub const SYS_BRK: u64 = 12;
     pub const SYS_IOCTL: u64 = 16;
+    pub const SYS_PREAD64: u64 = 17;
+    pub const SYS_PWRITE64: u64 = 18;
     pub const SYS_PIPE: u64 = 22;
     pub const SYS_SCHED_YIELD: u64 = 24;
     pub const SYS_NANOSLEEP: u64 = 35;
     pub const SYS_GETPID: u64 = 39;
+    pub const SYS_SETSOCKOPT: u64 = 54;
+    pub const SYS_GETSOCKOPT: u64 = 55;
     pub const SYS_FORK: u64 = 57;
     pub const SYS_EXECVE: u64 = 59;
     pub const SYS_EXIT: u64 = 60;
@@ -42,6 +46,8 @@ pub mod linux {
     pub const SYS_UNLINK: u64 = 87;
     pub const SYS_GETTIMEOFDAY: u64 = 96;
     pub const SYS_GETPPID: u64 = 110;
+    pub const SYS_STATFS: u64 = 137;
+    pub const SYS_FSTATFS: u64 = 138;
     pub const SYS_ARCH_PRCTL: u64 = 158;
     pub const SYS_GETTID: u64 = 186;
     pub const SYS_FUTEX: u64 = 202;
@@ -397,6 +403,43 @@ pub unsafe fn raw_syscall3(nr: u64, a1: u64, a2: u64, a3: u64) -> SyscallResult
     convert_result(ret)
 }
 
+/// Raw syscall with 4 arguments.
+#[inline]
+pub unsafe fn raw_syscall4(nr: u64, a1: u64, a2: u64, a3: u64, a4: u64) -> SyscallResult {
+    let ret: i64;
+    asm!(
+        "syscall",
+        inout("rax") nr => ret,
+        in("rdi") a1,
+        in("rsi") a2,
+        in("rdx") a3,
+        in("r10") a4,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack, preserves_flags)
+    );
+    convert_result(ret)
+}
+
+/// Raw syscall with 5 arguments.
+#[inline]
+pub unsafe fn raw_syscall5(nr: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64) -> SyscallResult {
+    let ret: i64;
+    asm!(
+        "syscall",
+        inout("rax") nr => ret,
+        in("rdi") a1,
+        in("rsi") a2,
+        in("rdx") a3,
+        in("r10") a4,
+        in("r8") a5,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack, preserves_flags)
+    );
+    convert_result(ret)
+}
+
 // ============================================
 // Helper: null-terminated path buffer
 // ============================================
@@ -483,6 +526,36 @@ pub fn net_local_addr(fd: u64) -> Result<([u8; 4], u16), SyscallError> {
     Ok(([0, 0, 0, 0], 0))
 }
 
+/// Set an integer socket option.
+pub fn net_setsockopt(fd: u64, level: u32, optname: u32, value: u32) -> SyscallResult {
+    unsafe {
+        raw_syscall5(
+            linux::SYS_SETSOCKOPT,
+            fd,
+            level as u64,
+            optname as u64,
+            &value as *const u32 as u64,
+            4,
+        )
+    }
+}
+
+/// Read a socket option into `buf`. Returns the length the kernel wrote.
+pub fn net_getsockopt(fd: u64, level: u32, optname: u32, buf: &mut [u8]) -> SyscallResult {
+    let mut len = buf.len() as u32;
+    unsafe {
+        raw_syscall5(
+            linux::SYS_GETSOCKOPT,
+            fd,
+            level as u64,
+            optname as u64,
+            buf.as_mut_ptr() as u64,
+            &mut len as *mut u32 as u64,
+        )
+    }?;
+    Ok(len as u64)
+}
+
 // --- File System ---
 
 /// Open a file. `path` is passed as a null-terminated C string.
@@ -572,6 +645,51 @@ pub fn fs_stat_fd(fd: u64) -> Result<(u64, bool, bool), SyscallError> {
     Ok((st.st_size as u64, is_dir, is_file))
 }
 
+/// Linux statfs buffer layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LinuxStatfs {
+    /// Filesystem magic number.
+    pub f_type: i64,
+    /// Block size in bytes.
+    pub f_bsize: i64,
+    /// Total data blocks.
+    pub f_blocks: u64,
+    /// Free blocks.
+    pub f_bfree: u64,
+    /// Free blocks available to unprivileged users.
+    pub f_bavail: u64,
+    /// Total file nodes.
+    pub f_files: u64,
+    /// Free file nodes.
+    pub f_ffree: u64,
+    pub f_fsid: [i32; 2],
+    /// Maximum filename length.
+    pub f_namelen: i64,
+    pub f_frsize: i64,
+    /// Mount flags (`ST_RDONLY` = 1).
+    pub f_flags: i64,
+    pub _spare: [i64; 4],
+}
+
+/// Statistics for the filesystem containing `path`.
+pub fn fs_statfs(path: &str) -> Result<LinuxStatfs, SyscallError> {
+    let mut st = core::mem::MaybeUninit::<LinuxStatfs>::uninit();
+    with_cstr(path, |ptr| unsafe {
+        raw_syscall2(linux::SYS_STATFS, ptr as u64, st.as_mut_ptr() as u64)
+    })?;
+    Ok(unsafe { st.assume_init() })
+}
+
+/// Statistics for the filesystem containing an open file descriptor.
+pub fn fs_statfs_fd(fd: u64) -> Result<LinuxStatfs, SyscallError> {
+    let mut st = core::mem::MaybeUninit::<LinuxStatfs>::uninit();
+    unsafe {
+        raw_syscall2(linux::SYS_FSTATFS, fd, st.as_mut_ptr() as u64)?;
+    }
+    Ok(unsafe { st.assume_init() })
+}
+
 /// Read directory entries. Opens the directory, reads via
 /// SYS_GETDENTS64, parses entries, then closes the fd.
 pub fn fs_readdir(path: &str) -> Result<Vec<(String, bool)>, SyscallError> {